@@ -81,6 +81,8 @@ nodeagent:
   node_ip: "${NODE_IP}"
   grpc_port: 47004
   log_level: "info"
+  container_runtime: "podman"  # or "docker"
+  workload_backend: "podman"  # or "systemd" to run workloads as local systemd units
   metrics:
     collection_interval: 5
     batch_size: 50
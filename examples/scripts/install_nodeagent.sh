@@ -301,6 +301,7 @@ nodeagent:
   node_ip: "${NODE_IP}"
   grpc_port: ${NODEAGENT_PORT}
   log_level: "info"
+  container_runtime: "podman"  # or "docker"
   metrics:
     collection_interval: 5
     batch_size: 50
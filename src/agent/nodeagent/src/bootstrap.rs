@@ -0,0 +1,238 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+//! Joining a master with a bootstrap token
+//!
+//! `nodeagent --bootstrap <token>` redeems a token issued by
+//! `POST /api/v1/nodes/bootstrap-token` for the bootstrap bundle of this
+//! node and writes the config file from it: the master address, the node
+//! name, the labels and the CA certificate, stored next to the config file
+//! as `tls/ca.crt`. Other settings of an existing config file are kept.
+//! NodeAgent then starts from the written config and registers as usual,
+//! so later starts need no token.
+
+use crate::config::Config;
+use common::bootstrap::{self, BootstrapBundle, RedeemRequest};
+use serde_yaml::Value;
+use std::path::{Path, PathBuf};
+
+/// Config of a node without config file, completed by the bundle
+const CONFIG_TEMPLATE: &str = "
+nodeagent:
+  node_type: cloud
+  node_role: nodeagent
+  grpc_port: 47004
+  log_level: info
+  metrics:
+    collection_interval: 5
+    batch_size: 50
+";
+
+/// Redeem a token and write the config file of this node
+///
+/// ### Parameters
+/// * `token: &str` - the bootstrap token
+/// * `master_ip: Option<String>` - address of the master, else the
+///   `master_ip` of the existing config file
+/// * `config_path: &Path` - config file to write
+/// ### Returns
+/// * `Ok(node_name)` - name the node registers with
+pub async fn run(
+    token: &str,
+    master_ip: Option<String>,
+    config_path: &Path,
+) -> Result<String, String> {
+    let existing = match std::fs::read_to_string(config_path) {
+        Ok(contents) => Some(contents),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(format!("Cannot read {}: {}", config_path.display(), e)),
+    };
+    let master_ip = master_ip
+        .or_else(|| existing.as_deref().and_then(configured_master_ip))
+        .ok_or("--master is needed when the config file names no master_ip")?;
+
+    println!("Redeeming bootstrap token at {}", master_ip);
+    let bundle = fetch(&master_ip, token).await?;
+
+    let ca_cert = if bundle.ca_cert.is_empty() {
+        None
+    } else {
+        let path = ca_cert_path(config_path);
+        write_file(&path, &bundle.ca_cert)?;
+        Some(path)
+    };
+    let hostname = hostname::get()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "unknown".to_string());
+    let contents = render(existing.as_deref(), &bundle, ca_cert.as_deref(), &hostname)?;
+    let config =
+        Config::parse(&contents).map_err(|e| format!("Invalid bootstrap config: {}", e))?;
+    write_file(config_path, &contents)?;
+    Ok(config.nodeagent.node_name)
+}
+
+/// Exchange the token for the bootstrap bundle at the master
+async fn fetch(master_ip: &str, token: &str) -> Result<BootstrapBundle, String> {
+    let body = serde_json::to_string(&RedeemRequest {
+        token: token.to_string(),
+    })
+    .map_err(|e| e.to_string())?;
+    let request = hyper::Request::post(bootstrap::redeem_url(master_ip))
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(hyper::Body::from(body))
+        .map_err(|e| e.to_string())?;
+    let response = hyper::Client::new()
+        .request(request)
+        .await
+        .map_err(|e| format!("Cannot reach the master at {}: {}", master_ip, e))?;
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body())
+        .await
+        .map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        let reason = serde_json::from_slice::<common::error::ApiError>(&body)
+            .map(|error| error.message)
+            .unwrap_or_else(|_| String::from_utf8_lossy(&body).to_string());
+        return Err(format!(
+            "Master refused the bootstrap token ({}): {}",
+            status, reason
+        ));
+    }
+    serde_json::from_slice(&body).map_err(|e| format!("Invalid bootstrap bundle: {}", e))
+}
+
+/// `master_ip` of a config file, if it names one
+fn configured_master_ip(contents: &str) -> Option<String> {
+    let config: Value = serde_yaml::from_str(contents).ok()?;
+    config["nodeagent"]["master_ip"]
+        .as_str()
+        .filter(|ip| !ip.is_empty())
+        .map(str::to_string)
+}
+
+/// CA certificate of the master, next to the config file
+fn ca_cert_path(config_path: &Path) -> PathBuf {
+    config_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("tls")
+        .join("ca.crt")
+}
+
+/// Config file of the node with the settings of the bundle
+///
+/// The node name of the bundle replaces the configured one, else the
+/// configured one or the hostname is kept, so the node keeps its identity
+/// across restarts. Labels of the bundle are added to the configured ones.
+fn render(
+    existing: Option<&str>,
+    bundle: &BootstrapBundle,
+    ca_cert: Option<&Path>,
+    hostname: &str,
+) -> Result<String, String> {
+    let mut config: Value = serde_yaml::from_str(existing.unwrap_or(CONFIG_TEMPLATE))
+        .map_err(|e| format!("Invalid config file: {}", e))?;
+    if !config["nodeagent"].is_mapping() {
+        config["nodeagent"] = Value::Mapping(Default::default());
+    }
+    let node = &mut config["nodeagent"];
+
+    node["master_ip"] = bundle.master_ip.clone().into();
+    if !bundle.node_name.is_empty() {
+        node["node_name"] = bundle.node_name.clone().into();
+    } else if node["node_name"].as_str().is_none_or(str::is_empty) {
+        node["node_name"] = hostname.into();
+    }
+    if node["system"].is_null() {
+        node["system"]["hostname"] = hostname.into();
+        node["system"]["platform"] = std::env::consts::OS.into();
+        node["system"]["architecture"] = std::env::consts::ARCH.into();
+    }
+    let mut labels: Vec<_> = bundle.labels.iter().collect();
+    labels.sort();
+    for (key, value) in labels {
+        node["labels"][key.as_str()] = value.clone().into();
+    }
+    if let Some(ca_cert) = ca_cert {
+        node["tls"]["enabled"] = true.into();
+        node["tls"]["ca_cert"] = ca_cert.to_string_lossy().to_string().into();
+    }
+    serde_yaml::to_string(&config).map_err(|e| e.to_string())
+}
+
+/// Replace a file, creating its directory
+fn write_file(path: &Path, contents: &str) -> Result<(), String> {
+    let error = |e: std::io::Error| format!("Cannot write {}: {}", path.display(), e);
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(error)?;
+    }
+    let temporary = path.with_extension("tmp");
+    std::fs::write(&temporary, contents).map_err(error)?;
+    std::fs::rename(&temporary, path).map_err(error)
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn bundle(node_name: &str) -> BootstrapBundle {
+        BootstrapBundle {
+            master_ip: "10.0.0.1".to_string(),
+            api_endpoint: "http://10.0.0.1:47098".to_string(),
+            ca_cert: String::new(),
+            node_name: node_name.to_string(),
+            labels: HashMap::from([("zone".to_string(), "front".to_string())]),
+        }
+    }
+
+    #[test]
+    fn test_render_new_config() {
+        let contents = render(None, &bundle(""), None, "guest-1").unwrap();
+        let config = Config::parse(&contents).unwrap();
+        assert_eq!(config.nodeagent.master_ip, "10.0.0.1");
+        assert_eq!(config.nodeagent.node_name, "guest-1");
+        assert_eq!(config.nodeagent.system.hostname, "guest-1");
+        assert_eq!(config.nodeagent.labels["zone"], "front");
+        assert!(!config.nodeagent.tls.enabled);
+    }
+
+    #[test]
+    fn test_render_keeps_existing_config() {
+        let existing = "nodeagent:\n  node_name: old\n  master_ip: 10.0.0.9\n  grpc_port: 47100\n  log_level: debug\n  metrics:\n    collection_interval: 1\n    batch_size: 1\n  system:\n    hostname: old\n    platform: linux\n    architecture: arm64\n  labels:\n    gpu: \"true\"\n";
+        assert_eq!(configured_master_ip(existing).as_deref(), Some("10.0.0.9"));
+
+        let contents = render(
+            Some(existing),
+            &bundle("zone-front"),
+            Some(Path::new("/etc/pullpiri/tls/ca.crt")),
+            "guest-1",
+        )
+        .unwrap();
+        let config: Value = serde_yaml::from_str(&contents).unwrap();
+        let node = &config["nodeagent"];
+        assert_eq!(node["node_name"].as_str(), Some("zone-front"));
+        assert_eq!(node["master_ip"].as_str(), Some("10.0.0.1"));
+        assert_eq!(node["grpc_port"].as_u64(), Some(47100));
+        assert_eq!(node["labels"]["gpu"].as_str(), Some("true"));
+        assert_eq!(node["labels"]["zone"].as_str(), Some("front"));
+        assert_eq!(
+            node["tls"]["ca_cert"].as_str(),
+            Some("/etc/pullpiri/tls/ca.crt")
+        );
+
+        // Without a name in the bundle the configured identity is kept
+        let contents = render(Some(existing), &bundle(""), None, "guest-1").unwrap();
+        assert_eq!(Config::parse(&contents).unwrap().nodeagent.node_name, "old");
+    }
+
+    #[test]
+    fn test_ca_cert_next_to_config() {
+        assert_eq!(
+            ca_cert_path(Path::new("/etc/pullpiri/nodeagent.yaml")),
+            PathBuf::from("/etc/pullpiri/tls/ca.crt")
+        );
+    }
+}
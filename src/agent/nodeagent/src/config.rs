@@ -4,14 +4,24 @@
 */
 use if_addrs::{get_if_addrs, Interface};
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::Read;
-use std::path::Path;
-use std::sync::OnceLock;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
 use thiserror::Error;
+use tokio::sync::watch;
 
 // Global config instance
-static NODEAGENT_CONFIG: OnceLock<Config> = OnceLock::new();
+static NODEAGENT_CONFIG: OnceLock<RwLock<Arc<Config>>> = OnceLock::new();
+
+/// Config file used when neither `--config` nor [`CONFIG_PATH_ENV`] is given
+pub const DEFAULT_CONFIG_PATH: &str = "/etc/pullpiri/nodeagent.yaml";
+/// Environment variable naming the config file
+pub const CONFIG_PATH_ENV: &str = "PULLPIRI_NODEAGENT_CONFIG";
+
+const NODE_TYPES: &[&str] = &["cloud", "vehicle"];
+const NODE_ROLES: &[&str] = &["master", "nodeagent", "bluechi"];
 
 #[derive(Debug, Error)]
 pub enum ConfigError {
@@ -20,6 +30,29 @@ pub enum ConfigError {
 
     #[error("Failed to parse YAML: {0}")]
     YamlError(#[from] serde_yaml::Error),
+
+    #[error("Invalid value of `{field}`: {reason}")]
+    InvalidField { field: String, reason: String },
+}
+
+impl ConfigError {
+    fn invalid(field: &str, reason: impl Into<String>) -> Self {
+        ConfigError::InvalidField {
+            field: format!("nodeagent.{}", field),
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Path of the config file: the `--config` flag, else [`CONFIG_PATH_ENV`],
+/// else [`DEFAULT_CONFIG_PATH`]
+pub fn config_path(flag: Option<PathBuf>) -> PathBuf {
+    flag.or_else(|| {
+        std::env::var_os(CONFIG_PATH_ENV)
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    })
+    .unwrap_or_else(|| PathBuf::from(DEFAULT_CONFIG_PATH))
 }
 
 #[derive(Debug, Deserialize, Clone, Default, PartialEq)]
@@ -36,6 +69,7 @@ pub struct SystemConfig {
 }
 
 #[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct NodeAgentConfig {
     #[serde(default = "default_node_name")]
     pub node_name: String,
@@ -52,6 +86,213 @@ pub struct NodeAgentConfig {
     pub system: SystemConfig,
     #[serde(default = "default_yaml_storage")]
     pub yaml_storage: String,
+    #[serde(default)]
+    pub container_runtime: ContainerRuntimeKind,
+    /// Unix socket of the container runtime, the runtime default if empty
+    #[serde(default)]
+    pub runtime_socket: String,
+    /// Labels matched by the `nodeSelector` of models
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// Taints as `key[=value]:Effect`, e.g. `dedicated=adas:NoSchedule`
+    #[serde(default)]
+    pub taints: Vec<String>,
+    /// Lines of the containers streamed to MonitoringServer, see `resource::logs`
+    #[serde(default)]
+    pub log_collection: LogCollectionConfig,
+    /// Collectors of node specific metrics, see `resource::plugin`
+    #[serde(default)]
+    pub plugins: Vec<PluginConfig>,
+    /// How workloads of ActionController are run, see `runtime::systemd`
+    #[serde(default)]
+    pub workload_backend: WorkloadBackend,
+    /// Quadlet directory receiving the `.kube` units of the systemd backend
+    #[serde(default = "default_unit_directory")]
+    pub unit_directory: String,
+    /// Seconds between two heartbeats sent to the API server
+    #[serde(default = "default_heartbeat_interval")]
+    pub heartbeat_interval: u64,
+    #[serde(default)]
+    pub tls: TlsConfig,
+    /// Queue of messages to the master while it is unreachable, see `outbox`
+    #[serde(default)]
+    pub outbox: OutboxConfig,
+    /// Sampling of the resource usage of containers, see `resource::sampler`
+    #[serde(default)]
+    pub sampling: SamplingConfig,
+    /// Wait for the API server of the master before starting, see `common::startup`
+    #[serde(default)]
+    pub startup: common::setting::StartupSettings,
+}
+
+/// Cadence and window of the container usage samples
+///
+/// Rates are computed between two samples and reported as the minimum,
+/// average and maximum of the samples within the window.
+///
+/// ```yaml
+/// sampling:
+///   interval_ms: 1000
+///   window_secs: 10
+/// ```
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SamplingConfig {
+    /// Time between two samples of a container
+    #[serde(default = "default_sampling_interval_ms")]
+    pub interval_ms: u64,
+    /// Time the reported aggregates cover
+    #[serde(default = "default_sampling_window_secs")]
+    pub window_secs: u64,
+}
+
+impl Default for SamplingConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: default_sampling_interval_ms(),
+            window_secs: default_sampling_window_secs(),
+        }
+    }
+}
+
+/// Persistent queue of outbound messages
+///
+/// ```yaml
+/// outbox:
+///   path: /var/lib/pullpiri/outbox.jsonl
+///   max_bytes: 1048576
+/// ```
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct OutboxConfig {
+    /// File keeping the queue across restarts
+    #[serde(default = "default_outbox_path")]
+    pub path: String,
+    /// Size of the queued messages above which the oldest are dropped
+    #[serde(default = "default_outbox_max_bytes")]
+    pub max_bytes: u64,
+}
+
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            path: default_outbox_path(),
+            max_bytes: default_outbox_max_bytes(),
+        }
+    }
+}
+
+/// Certificates of the gRPC connections of this node
+///
+/// ```yaml
+/// tls:
+///   enabled: true
+///   ca_cert: /etc/pullpiri/tls/ca.crt
+///   cert: /etc/pullpiri/tls/node.crt
+///   key: /etc/pullpiri/tls/node.key
+/// ```
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// CA certificate the certificate of the master is checked against
+    #[serde(default)]
+    pub ca_cert: String,
+    /// Certificate of this node, in PEM
+    #[serde(default)]
+    pub cert: String,
+    /// Private key of `cert`, in PEM
+    #[serde(default)]
+    pub key: String,
+}
+
+/// External collector of node specific metrics
+///
+/// ```yaml
+/// plugins:
+///   - name: can-stats
+///     command: /usr/libexec/pullpiri/can-stats
+///     args: ["can0"]
+///     interval: 5
+///   - name: gpu-temp
+///     library: /usr/lib/pullpiri/libgputemp.so
+/// ```
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub struct PluginConfig {
+    pub name: String,
+    /// Executable printing the metrics as JSON on stdout
+    #[serde(default)]
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Shared object exporting `pullpiri_collect_metrics`
+    #[serde(default)]
+    pub library: String,
+    /// Seconds between two collections
+    #[serde(default = "default_plugin_interval")]
+    pub interval: u64,
+    /// Seconds a collection may take
+    #[serde(default = "default_plugin_timeout")]
+    pub timeout: u64,
+}
+
+/// Container engine used to inspect containers on this node
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerRuntimeKind {
+    #[default]
+    Podman,
+    Docker,
+}
+
+/// Backend running the workloads of this node
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkloadBackend {
+    /// Containers created through the Podman API
+    #[default]
+    Podman,
+    /// Quadlet `.kube` units started through systemd, without Bluechi
+    Systemd,
+}
+
+/// Following of the container logs
+///
+/// Every running container is followed from its last `tail` lines, and the
+/// lines are sent to MonitoringServer by batches of at most `batch_lines`
+/// lines, or after `flush_ms` if fewer are written.
+///
+/// ```yaml
+/// log_collection:
+///   enabled: true
+///   tail: 100
+///   batch_lines: 200
+///   flush_ms: 1000
+/// ```
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct LogCollectionConfig {
+    #[serde(default = "default_log_collection_enabled")]
+    pub enabled: bool,
+    /// Lines already written that are sent when a container is followed
+    #[serde(default = "default_log_tail")]
+    pub tail: u32,
+    #[serde(default = "default_log_batch_lines")]
+    pub batch_lines: usize,
+    #[serde(default = "default_log_flush_ms")]
+    pub flush_ms: u64,
+}
+
+impl Default for LogCollectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_log_collection_enabled(),
+            tail: default_log_tail(),
+            batch_lines: default_log_batch_lines(),
+            flush_ms: default_log_flush_ms(),
+        }
+    }
 }
 
 fn default_node_name() -> String {
@@ -69,10 +310,187 @@ fn default_node_role() -> String {
     "nodeagent".to_string()
 }
 
+fn default_plugin_interval() -> u64 {
+    10
+}
+
+fn default_plugin_timeout() -> u64 {
+    5
+}
+
 fn default_yaml_storage() -> String {
     "/etc/pullpiri/yaml".to_string()
 }
 
+fn default_unit_directory() -> String {
+    "/etc/containers/systemd".to_string()
+}
+
+fn default_heartbeat_interval() -> u64 {
+    3
+}
+
+fn default_outbox_path() -> String {
+    "/var/lib/pullpiri/outbox.jsonl".to_string()
+}
+
+fn default_outbox_max_bytes() -> u64 {
+    1024 * 1024
+}
+
+fn default_sampling_interval_ms() -> u64 {
+    1000
+}
+
+fn default_sampling_window_secs() -> u64 {
+    10
+}
+
+impl NodeAgentConfig {
+    /// Check the values serde cannot, naming the first invalid field
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !NODE_TYPES.contains(&self.node_type.as_str()) {
+            return Err(ConfigError::invalid(
+                "node_type",
+                format!(
+                    "'{}' is not one of {}",
+                    self.node_type,
+                    NODE_TYPES.join(", ")
+                ),
+            ));
+        }
+        if !NODE_ROLES.contains(&self.node_role.as_str()) {
+            return Err(ConfigError::invalid(
+                "node_role",
+                format!(
+                    "'{}' is not one of {}",
+                    self.node_role,
+                    NODE_ROLES.join(", ")
+                ),
+            ));
+        }
+        if self.master_ip.trim().is_empty() || self.master_ip.contains(char::is_whitespace) {
+            return Err(ConfigError::invalid(
+                "master_ip",
+                format!("'{}' is not an address or host name", self.master_ip),
+            ));
+        }
+        if !self.node_ip.is_empty() && self.node_ip.parse::<std::net::IpAddr>().is_err() {
+            return Err(ConfigError::invalid(
+                "node_ip",
+                format!("'{}' is not an IP address", self.node_ip),
+            ));
+        }
+        if self.grpc_port == 0 {
+            return Err(ConfigError::invalid("grpc_port", "must not be 0"));
+        }
+        if self.heartbeat_interval == 0 {
+            return Err(ConfigError::invalid(
+                "heartbeat_interval",
+                "must be at least 1 second",
+            ));
+        }
+        if self.log_collection.batch_lines == 0 {
+            return Err(ConfigError::invalid(
+                "log_collection.batch_lines",
+                "must not be 0",
+            ));
+        }
+        if self.outbox.max_bytes == 0 {
+            return Err(ConfigError::invalid("outbox.max_bytes", "must not be 0"));
+        }
+        if self.sampling.interval_ms < 100 {
+            return Err(ConfigError::invalid(
+                "sampling.interval_ms",
+                "must be at least 100 ms",
+            ));
+        }
+        if self.sampling.window_secs.saturating_mul(1000) < self.sampling.interval_ms {
+            return Err(ConfigError::invalid(
+                "sampling.window_secs",
+                "must cover at least one sampling interval",
+            ));
+        }
+        if let Some(key) = self
+            .labels
+            .keys()
+            .find(|key| key.is_empty() || key.contains(['=', ',']))
+        {
+            return Err(ConfigError::invalid(
+                &format!("labels.{}", key),
+                "label keys must not be empty or contain '=' or ','",
+            ));
+        }
+        for (index, taint) in self.taints.iter().enumerate() {
+            common::spec::artifact::node::Taint::parse(taint)
+                .map_err(|e| ConfigError::invalid(&format!("taints[{}]", index), e))?;
+        }
+        for (index, plugin) in self.plugins.iter().enumerate() {
+            let field = |name: &str| format!("plugins[{}].{}", index, name);
+            if plugin.name.is_empty() {
+                return Err(ConfigError::invalid(&field("name"), "must not be empty"));
+            }
+            if plugin.command.is_empty() == plugin.library.is_empty() {
+                return Err(ConfigError::invalid(
+                    &field("command"),
+                    "exactly one of command and library must be set",
+                ));
+            }
+        }
+        self.tls.validate()
+    }
+}
+
+impl TlsConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.cert.is_empty() != self.key.is_empty() {
+            let missing = if self.cert.is_empty() { "cert" } else { "key" };
+            return Err(ConfigError::invalid(
+                &format!("tls.{}", missing),
+                "cert and key must be set together",
+            ));
+        }
+        if self.ca_cert.is_empty() && self.cert.is_empty() {
+            return Err(ConfigError::invalid(
+                "tls.ca_cert",
+                "enabled TLS needs a CA certificate or a node certificate",
+            ));
+        }
+        for (name, path) in [
+            ("ca_cert", &self.ca_cert),
+            ("cert", &self.cert),
+            ("key", &self.key),
+        ] {
+            if !path.is_empty() && !Path::new(path).is_file() {
+                return Err(ConfigError::invalid(
+                    &format!("tls.{}", name),
+                    format!("'{}' is not a readable file", path),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn default_log_collection_enabled() -> bool {
+    true
+}
+
+fn default_log_tail() -> u32 {
+    100
+}
+
+fn default_log_batch_lines() -> usize {
+    200
+}
+
+fn default_log_flush_ms() -> u64 {
+    1000
+}
+
 #[derive(Debug, Deserialize, Clone, Default, PartialEq)]
 pub struct Config {
     pub nodeagent: NodeAgentConfig,
@@ -84,10 +502,44 @@ impl Config {
         let mut contents = String::new();
         file.read_to_string(&mut contents)?;
 
-        let config = serde_yaml::from_str(&contents)?;
+        Self::parse(&contents)
+    }
+
+    /// Parse and validate a config
+    ///
+    /// Parse errors name the field and its line, validation errors the
+    /// field and the accepted values.
+    pub fn parse(contents: &str) -> Result<Self, ConfigError> {
+        let config: Config = serde_yaml::from_str(contents)?;
+        config.nodeagent.validate()?;
         Ok(config)
     }
 
+    /// Reload the config file whenever it changes
+    ///
+    /// Every reloaded config also becomes the global config. Must be called
+    /// within a tokio runtime.
+    pub fn watch(&self, path: PathBuf) -> watch::Receiver<Arc<Config>> {
+        let mut file = common::config_watch::watch_file(
+            path,
+            self.clone(),
+            common::config_watch::DEFAULT_POLL_INTERVAL,
+            |contents| Config::parse(contents).map_err(|e| e.to_string()),
+        );
+        // the global config is updated before subscribers are notified
+        let (tx, rx) = watch::channel(Arc::new(self.clone()));
+        tokio::spawn(async move {
+            while file.changed().await.is_ok() {
+                let reloaded = file.borrow_and_update().clone();
+                Config::set_global_arc(reloaded.clone());
+                if tx.send(reloaded).is_err() {
+                    break;
+                }
+            }
+        });
+        rx
+    }
+
     pub fn get_host_ip(&self) -> String {
         // If node_ip is explicitly set in config, use it
         if !self.nodeagent.node_ip.is_empty() {
@@ -121,18 +573,57 @@ impl Config {
         self.nodeagent.yaml_storage.clone()
     }
 
+    /// Time between two heartbeats, the default if unset
+    pub fn get_heartbeat_interval(&self) -> std::time::Duration {
+        let seconds = match self.nodeagent.heartbeat_interval {
+            0 => default_heartbeat_interval(),
+            seconds => seconds,
+        };
+        std::time::Duration::from_secs(seconds)
+    }
+
+    /// Time between two samples of the container usage
+    pub fn get_sampling_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.nodeagent.sampling.interval_ms)
+    }
+
+    /// Time the aggregated container usage covers
+    pub fn get_sampling_window(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.nodeagent.sampling.window_secs)
+    }
+
+    /// Metadata registered with the API server: the labels and the taints
+    pub fn get_node_metadata(&self) -> HashMap<String, String> {
+        let mut metadata = self.nodeagent.labels.clone();
+        if !self.nodeagent.taints.is_empty() {
+            metadata.insert(
+                common::spec::artifact::node::NODE_TAINTS_KEY.to_string(),
+                self.nodeagent.taints.join(","),
+            );
+        }
+        metadata
+    }
+
     // Get or initialize the global config
-    pub fn get() -> &'static Config {
-        NODEAGENT_CONFIG.get().unwrap_or_else(|| {
-            let default_config = Config::default();
-            NODEAGENT_CONFIG.set(default_config.clone()).unwrap_or(());
-            NODEAGENT_CONFIG.get().unwrap()
-        })
+    pub fn get() -> Arc<Config> {
+        let global = NODEAGENT_CONFIG.get_or_init(|| RwLock::new(Arc::new(Config::default())));
+        match global.read() {
+            Ok(config) => config.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
     }
 
     // Set the global config
     pub fn set_global(config: Config) {
-        let _ = NODEAGENT_CONFIG.set(config);
+        Self::set_global_arc(Arc::new(config));
+    }
+
+    fn set_global_arc(config: Arc<Config>) {
+        let global = NODEAGENT_CONFIG.get_or_init(|| RwLock::new(config.clone()));
+        match global.write() {
+            Ok(mut global) => *global = config,
+            Err(poisoned) => *poisoned.into_inner() = config,
+        }
     }
 }
 
@@ -201,4 +692,182 @@ mod tests {
         };
         assert!(!config.get_host_ip().is_empty());
     }
+
+    #[test]
+    fn test_container_runtime_setting() {
+        let yaml = r#"
+nodeagent:
+  master_ip: "127.0.0.1"
+  grpc_port: 47004
+  log_level: "info"
+  metrics:
+    collection_interval: 5
+    batch_size: 50
+  system:
+    hostname: "node"
+    platform: "Linux"
+    architecture: "x86_64"
+"#;
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            config.nodeagent.container_runtime,
+            ContainerRuntimeKind::Podman
+        );
+        assert!(config.nodeagent.runtime_socket.is_empty());
+
+        let docker = format!(
+            "{}  container_runtime: docker\n  runtime_socket: /var/run/docker.sock\n",
+            yaml
+        );
+        let config: Config = serde_yaml::from_str(&docker).unwrap();
+        assert_eq!(
+            config.nodeagent.container_runtime,
+            ContainerRuntimeKind::Docker
+        );
+        assert_eq!(config.nodeagent.runtime_socket, "/var/run/docker.sock");
+
+        let unknown = format!("{}  container_runtime: containerd\n", yaml);
+        assert!(serde_yaml::from_str::<Config>(&unknown).is_err());
+
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.nodeagent.workload_backend, WorkloadBackend::Podman);
+        assert_eq!(config.nodeagent.unit_directory, "/etc/containers/systemd");
+        let systemd = format!("{}  workload_backend: systemd\n", yaml);
+        let config: Config = serde_yaml::from_str(&systemd).unwrap();
+        assert_eq!(config.nodeagent.workload_backend, WorkloadBackend::Systemd);
+
+        let plugins = format!(
+            "{}  plugins:\n    - name: can-stats\n      command: /usr/bin/can-stats\n      interval: 2\n",
+            yaml
+        );
+        let config: Config = serde_yaml::from_str(&plugins).unwrap();
+        assert_eq!(config.nodeagent.plugins[0].command, "/usr/bin/can-stats");
+        assert_eq!(config.nodeagent.plugins[0].interval, 2);
+        assert_eq!(config.nodeagent.plugins[0].timeout, 5);
+    }
+
+    #[test]
+    fn test_node_metadata_from_labels_and_taints() {
+        let mut config = Config::default();
+        assert!(config.get_node_metadata().is_empty());
+
+        config
+            .nodeagent
+            .labels
+            .insert("zone".to_string(), "front".to_string());
+        config.nodeagent.taints = vec![
+            "dedicated=adas:NoSchedule".to_string(),
+            "maintenance:PreferNoSchedule".to_string(),
+        ];
+        let metadata = config.get_node_metadata();
+        assert_eq!(metadata.get("zone"), Some(&"front".to_string()));
+        assert_eq!(
+            metadata.get("taints"),
+            Some(&"dedicated=adas:NoSchedule,maintenance:PreferNoSchedule".to_string())
+        );
+    }
+
+    const MINIMAL: &str = r#"
+nodeagent:
+  master_ip: "10.0.0.1"
+  grpc_port: 47004
+  log_level: "info"
+  metrics:
+    collection_interval: 5
+    batch_size: 50
+  system:
+    hostname: "node"
+    platform: "Linux"
+    architecture: "x86_64"
+"#;
+
+    fn parse_error(extra: &str) -> String {
+        Config::parse(&format!("{}{}", MINIMAL, extra))
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn test_parse_validates_fields() {
+        let config = Config::parse(MINIMAL).unwrap();
+        assert_eq!(config.nodeagent.heartbeat_interval, 3);
+        assert!(!config.nodeagent.tls.enabled);
+        assert_eq!(
+            config.get_sampling_interval(),
+            std::time::Duration::from_secs(1)
+        );
+
+        let config = Config::parse(&format!(
+            "{}  heartbeat_interval: 10
+",
+            MINIMAL
+        ))
+        .unwrap();
+        assert_eq!(
+            config.get_heartbeat_interval(),
+            std::time::Duration::from_secs(10)
+        );
+
+        assert_eq!(
+            parse_error("  node_role: worker\n"),
+            "Invalid value of `nodeagent.node_role`: 'worker' is not one of master, nodeagent, bluechi"
+        );
+        assert_eq!(
+            parse_error("  node_ip: 10.0.0\n"),
+            "Invalid value of `nodeagent.node_ip`: '10.0.0' is not an IP address"
+        );
+        assert_eq!(
+            parse_error("  heartbeat_interval: 0\n"),
+            "Invalid value of `nodeagent.heartbeat_interval`: must be at least 1 second"
+        );
+        assert!(parse_error("  taints: [\"dedicated=adas\"]\n")
+            .starts_with("Invalid value of `nodeagent.taints[0]`"));
+        assert!(parse_error("  heartbeat_intervall: 5\n")
+            .contains("unknown field `heartbeat_intervall`"));
+        assert!(parse_error("  labels: front\n").contains("nodeagent.labels"));
+        assert_eq!(
+            parse_error("  sampling:\n    interval_ms: 5000\n    window_secs: 2\n"),
+            "Invalid value of `nodeagent.sampling.window_secs`: must cover at least one sampling interval"
+        );
+    }
+
+    #[test]
+    fn test_tls_settings() {
+        let cert = std::env::temp_dir().join("nodeagent-config-test.crt");
+        std::fs::write(&cert, "cert").unwrap();
+        let tls = |fields: &str| format!("  tls:\n    enabled: true\n{}", fields);
+
+        let config = Config::parse(&format!(
+            "{}{}",
+            MINIMAL,
+            tls(&format!("    ca_cert: {}\n", cert.display()))
+        ))
+        .unwrap();
+        assert!(config.nodeagent.tls.enabled);
+
+        assert_eq!(
+            parse_error(&tls(&format!("    cert: {}\n", cert.display()))),
+            "Invalid value of `nodeagent.tls.key`: cert and key must be set together"
+        );
+        assert_eq!(
+            parse_error(&tls("    ca_cert: /nonexistent/ca.crt\n")),
+            "Invalid value of `nodeagent.tls.ca_cert`: '/nonexistent/ca.crt' is not a readable file"
+        );
+        // Disabled TLS is not checked
+        assert!(Config::parse(&format!(
+            "{}  tls:\n    ca_cert: /nonexistent/ca.crt\n",
+            MINIMAL
+        ))
+        .is_ok());
+        let _ = std::fs::remove_file(cert);
+    }
+
+    #[test]
+    fn test_config_path_precedence() {
+        let flag = PathBuf::from("/tmp/flag.yaml");
+        assert_eq!(config_path(Some(flag.clone())), flag);
+        if std::env::var_os(CONFIG_PATH_ENV).is_none() {
+            assert_eq!(config_path(None), PathBuf::from(DEFAULT_CONFIG_PATH));
+        }
+    }
 }
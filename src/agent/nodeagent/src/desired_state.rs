@@ -74,6 +74,8 @@ pub enum RestartPolicy {
 pub struct ProbeConfig {
     /// Optional liveness probe configuration.
     pub liveness: Option<LivenessProbe>,
+    /// Optional readiness probe configuration.
+    pub readiness: Option<ReadinessProbe>,
 }
 
 /// Configuration for a liveness probe that checks if the container is healthy.
@@ -91,7 +93,12 @@ pub struct LivenessProbe {
     pub failure_threshold: u8,
 }
 
-/// Specifies the mechanism used to perform a liveness probe.
+/// Configuration for a readiness probe that checks if the container can serve requests.
+///
+/// A failing readiness probe never stops the container, the result is only reported.
+pub type ReadinessProbe = LivenessProbe;
+
+/// Specifies the mechanism used to perform a liveness or readiness probe.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ProbeType {
     /// HTTP GET probe against a specific path and port.
@@ -149,6 +156,7 @@ mod tests {
                 timeout_seconds: 3,
                 failure_threshold: 3,
             }),
+            readiness: None,
         });
 
         assert!(state.probe_config.is_some());
@@ -3,12 +3,16 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 use crate::desired_state::{DesiredState, LivenessProbe, ProbeConfig, ProbeType, RestartPolicy};
+use crate::runtime::podman::image;
+use crate::runtime::systemd::{self, UnitError};
 use common::nodeagent::fromactioncontroller::{
-    HandleWorkloadRequest, HandleWorkloadResponse, WorkloadCommand,
+    HandleUnitRequest, HandleUnitResponse, HandleWorkloadRequest, HandleWorkloadResponse,
+    PrePullImagesRequest, PrePullProgress, PrePullState, WorkloadCommand,
 };
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, Mutex};
+use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status};
 
 /// Extract pod name from pod YAML string.
@@ -20,38 +24,55 @@ fn extract_pod_name(pod_yaml: &str) -> Result<String, Box<dyn std::error::Error>
 
 /// Convert a common crate ProbeConfig into a nodeagent DesiredState ProbeConfig.
 ///
-/// Returns `None` if the liveness probe configuration has no recognized probe type
-/// (i.e., no `http`, `tcp`, or `exec` field), as this would be a configuration error.
+/// A liveness or readiness probe without a recognized probe type (i.e., no
+/// `http`, `tcp`, or `exec` field) is a configuration error and is dropped.
 fn convert_probe_config(common_probe: &common::spec::k8s::pod::ProbeConfig) -> Option<ProbeConfig> {
-    let liveness = common_probe.liveness.as_ref().and_then(|lp| {
-        let probe_type = if let Some(http) = &lp.http {
-            ProbeType::Http {
-                path: http.path.clone(),
-                port: http.port,
-            }
-        } else if let Some(tcp) = &lp.tcp {
-            ProbeType::Tcp { port: tcp.port }
-        } else if let Some(exec) = &lp.exec {
-            ProbeType::Exec {
-                command: exec.command.clone(),
-            }
-        } else {
-            eprintln!(
-                "[NodeAgent] Liveness probe configuration has no probe type (http/tcp/exec); ignoring"
-            );
-            return None;
-        };
+    let liveness = common_probe
+        .liveness
+        .as_ref()
+        .and_then(|lp| convert_probe("Liveness", lp));
+    let readiness = common_probe
+        .readiness
+        .as_ref()
+        .and_then(|rp| convert_probe("Readiness", rp));
+
+    Some(ProbeConfig {
+        liveness,
+        readiness,
+    })
+}
 
-        Some(LivenessProbe {
-            probe_type,
-            initial_delay_seconds: lp.initialDelaySeconds,
-            period_seconds: lp.periodSeconds,
-            timeout_seconds: lp.timeoutSeconds,
-            failure_threshold: lp.failureThreshold,
-        })
-    });
+/// Convert one probe of the Pod YAML, `None` if it has no probe type.
+fn convert_probe(
+    kind: &str,
+    lp: &common::spec::k8s::pod::LivenessProbeSpec,
+) -> Option<LivenessProbe> {
+    let probe_type = if let Some(http) = &lp.http {
+        ProbeType::Http {
+            path: http.path.clone(),
+            port: http.port,
+        }
+    } else if let Some(tcp) = &lp.tcp {
+        ProbeType::Tcp { port: tcp.port }
+    } else if let Some(exec) = &lp.exec {
+        ProbeType::Exec {
+            command: exec.command.clone(),
+        }
+    } else {
+        eprintln!(
+            "[NodeAgent] {} probe configuration has no probe type (http/tcp/exec); ignoring",
+            kind
+        );
+        return None;
+    };
 
-    Some(ProbeConfig { liveness })
+    Some(LivenessProbe {
+        probe_type,
+        initial_delay_seconds: lp.initialDelaySeconds,
+        period_seconds: lp.periodSeconds,
+        timeout_seconds: lp.timeoutSeconds,
+        failure_threshold: lp.failureThreshold,
+    })
 }
 
 pub async fn handle_workload(
@@ -59,6 +80,9 @@ pub async fn handle_workload(
     desired_states_cache: Arc<Mutex<HashMap<String, DesiredState>>>,
 ) -> Result<Response<HandleWorkloadResponse>, Status> {
     let req = request.into_inner();
+    if systemd::enabled() {
+        return handle_workload_unit(req).await;
+    }
     let pod_yaml = req.pod.clone();
     let command = req.workload_command;
 
@@ -102,7 +126,7 @@ pub async fn handle_workload(
 
         // Start the container via Podman API and convert any error to String immediately
         // to avoid holding Box<dyn Error> (not Send) across the subsequent await points.
-        let start_result = crate::runtime::podman::handle_workload(command, &pod_yaml)
+        let start_result = crate::runtime::handle_workload(command, &pod_yaml)
             .await
             .map_err(|e| e.to_string());
 
@@ -150,7 +174,7 @@ pub async fn handle_workload(
         println!("Removed desired state from cache for: {}", pod_name);
 
         // Stop/remove the container via Podman API
-        match crate::runtime::podman::handle_workload(command, &pod_yaml).await {
+        match crate::runtime::handle_workload(command, &pod_yaml).await {
             Ok(_) => Ok(Response::new(HandleWorkloadResponse {
                 status: true,
                 desc: format!(
@@ -161,8 +185,8 @@ pub async fn handle_workload(
             Err(e) => Err(Status::internal(format!("Failed to stop container: {}", e))),
         }
     } else {
-        // For other commands (Restart, Pause, Unpause, etc.), forward to Podman without cache changes
-        match crate::runtime::podman::handle_workload(command, &pod_yaml).await {
+        // For other commands (Create, Restart, Pause, Unpause), forward to Podman without cache changes
+        match crate::runtime::handle_workload(command, &pod_yaml).await {
             Ok(_) => {
                 println!("Workload command {} executed for: {}", command, pod_name);
                 Ok(Response::new(HandleWorkloadResponse {
@@ -170,14 +194,134 @@ pub async fn handle_workload(
                     desc: format!("Workload command executed for {}", pod_name),
                 }))
             }
-            Err(e) => Err(Status::unimplemented(format!(
-                "handle_workload is not implemented yet: {}",
-                e
+            Err(e) => Err(Status::internal(format!(
+                "Failed to execute workload command {}: {}",
+                command, e
             ))),
         }
     }
 }
 
+/// gRPC status of a failed unit operation
+fn unit_status(e: UnitError) -> Status {
+    match e {
+        UnitError::Yaml(_) => Status::invalid_argument(e.to_string()),
+        UnitError::NotManaged(_) => Status::permission_denied(e.to_string()),
+        UnitError::Unsupported(_) => Status::unimplemented(e.to_string()),
+        UnitError::DBus(_) | UnitError::Io(_) => Status::internal(e.to_string()),
+    }
+}
+
+/// Run a workload as systemd unit
+///
+/// systemd restarts the unit by its restart policy, so no desired state is
+/// cached for the reconciliation loop.
+async fn handle_workload_unit(
+    req: HandleWorkloadRequest,
+) -> Result<Response<HandleWorkloadResponse>, Status> {
+    let units = systemd::handle_workload(req.workload_command, &req.pod)
+        .await
+        .map_err(unit_status)?;
+    println!(
+        "Workload command {} executed for unit {}",
+        req.workload_command,
+        units.join(", ")
+    );
+    Ok(Response::new(HandleWorkloadResponse {
+        status: true,
+        desc: format!("Workload command executed for {}", units.join(", ")),
+    }))
+}
+
+/// Handle an operation on a unit generated by NodeAgent
+pub async fn handle_unit(
+    request: Request<HandleUnitRequest>,
+) -> Result<Response<HandleUnitResponse>, Status> {
+    let req = request.into_inner();
+    #[cfg(feature = "mock-runtime")]
+    if crate::runtime::mock::enabled() {
+        crate::runtime::mock::handle_unit(req.unit_command, &req.unit)
+            .map_err(|e| Status::internal(e.to_string()))?;
+        return Ok(Response::new(HandleUnitResponse {
+            status: true,
+            desc: format!(
+                "Unit command {} recorded for {}",
+                req.unit_command, req.unit
+            ),
+        }));
+    }
+    if !systemd::enabled() {
+        return Err(Status::failed_precondition(
+            "workload_backend of this NodeAgent is not systemd",
+        ));
+    }
+    systemd::handle_unit(req.unit_command, &req.unit)
+        .await
+        .map_err(unit_status)?;
+    Ok(Response::new(HandleUnitResponse {
+        status: true,
+        desc: format!(
+            "Unit command {} executed for {}",
+            req.unit_command, req.unit
+        ),
+    }))
+}
+
+/// Pull images ahead of a workload update
+///
+/// Images are pulled one after another. A `Pulling` message is streamed when
+/// an image starts and a message with its final state when it ends, the
+/// stream closes after the last image.
+pub async fn prepull_images(
+    request: Request<PrePullImagesRequest>,
+) -> Result<Response<ReceiverStream<Result<PrePullProgress, Status>>>, Status> {
+    let images = request.into_inner().images;
+    if images.iter().any(|i| i.image.trim().is_empty()) {
+        return Err(Status::invalid_argument("image name must not be empty"));
+    }
+
+    let (tx, rx) = mpsc::channel(images.len().max(1) * 2);
+    tokio::spawn(async move {
+        let total = images.len() as u32;
+        for (index, requested) in images.iter().enumerate() {
+            let progress = |state, digest: String, message: String, completed| PrePullProgress {
+                image: requested.image.clone(),
+                state: state as i32,
+                digest,
+                message,
+                completed,
+                total,
+            };
+            let started = progress(
+                PrePullState::Pulling,
+                String::new(),
+                String::new(),
+                index as u32,
+            );
+            if tx.send(Ok(started)).await.is_err() {
+                return;
+            }
+
+            let outcome = image::prepull_image(requested).await;
+            println!(
+                "[NodeAgent] Pre-pull of {}: {:?} {}",
+                requested.image, outcome.state, outcome.message
+            );
+            let finished = progress(
+                outcome.state,
+                outcome.digest,
+                outcome.message,
+                index as u32 + 1,
+            );
+            if tx.send(Ok(finished)).await.is_err() {
+                return;
+            }
+        }
+    });
+
+    Ok(Response::new(ReceiverStream::new(rx)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -317,6 +461,30 @@ spec:
         assert_eq!(cache.lock().await.len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_handle_workload_pause_keeps_cache_on_podman_failure() {
+        let cache = make_cache();
+        {
+            let mut c = cache.lock().await;
+            c.insert(
+                "test-pod".to_string(),
+                DesiredState::new("test-pod".to_string()),
+            );
+        }
+
+        // PAUSE command will fail because podman is not running
+        let request = tonic::Request::new(HandleWorkloadRequest {
+            workload_command: WorkloadCommand::Pause as i32,
+            pod: VALID_POD_YAML.to_string(),
+        });
+
+        let result = handle_workload(request, Arc::clone(&cache)).await;
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().code(), tonic::Code::Internal);
+        // Pause does not change the desired state
+        assert_eq!(cache.lock().await.len(), 1);
+    }
+
     #[test]
     fn test_convert_probe_config_http() {
         use common::spec::k8s::pod::{HttpProbeSpec, LivenessProbeSpec, ProbeConfig};
@@ -333,6 +501,7 @@ spec:
                 timeoutSeconds: 3,
                 failureThreshold: 3,
             }),
+            readiness: None,
         };
 
         let result = convert_probe_config(&common_probe);
@@ -365,6 +534,7 @@ spec:
                 timeoutSeconds: 1,
                 failureThreshold: 3,
             }),
+            readiness: None,
         };
 
         let result = convert_probe_config(&common_probe);
@@ -392,6 +562,7 @@ spec:
                 timeoutSeconds: 5,
                 failureThreshold: 3,
             }),
+            readiness: None,
         };
 
         let result = convert_probe_config(&common_probe);
@@ -407,12 +578,41 @@ spec:
     #[test]
     fn test_convert_probe_config_no_liveness() {
         use common::spec::k8s::pod::ProbeConfig;
-        let common_probe = ProbeConfig { liveness: None };
+        let common_probe = ProbeConfig {
+            liveness: None,
+            readiness: None,
+        };
         let result = convert_probe_config(&common_probe);
         assert!(result.is_some());
         assert!(result.unwrap().liveness.is_none());
     }
 
+    #[test]
+    fn test_convert_probe_config_readiness() {
+        use common::spec::k8s::pod::{LivenessProbeSpec, ProbeConfig, TcpProbeSpec};
+        let common_probe = ProbeConfig {
+            liveness: None,
+            readiness: Some(LivenessProbeSpec {
+                http: None,
+                tcp: Some(TcpProbeSpec { port: 8080 }),
+                exec: None,
+                initialDelaySeconds: 2,
+                periodSeconds: 5,
+                timeoutSeconds: 1,
+                failureThreshold: 3,
+            }),
+        };
+
+        let probe_config = convert_probe_config(&common_probe).unwrap();
+        assert!(probe_config.liveness.is_none());
+        let readiness = probe_config.readiness.unwrap();
+        assert_eq!(readiness.initial_delay_seconds, 2);
+        assert!(matches!(
+            readiness.probe_type,
+            ProbeType::Tcp { port: 8080 }
+        ));
+    }
+
     #[test]
     fn test_pod_yaml_with_probe_config_parses_correctly() {
         let yaml = r#"
@@ -3,8 +3,10 @@
 * SPDX-License-Identifier: Apache-2.0
 */
 use common::nodeagent::fromapiserver::{
-    ConfigRequest, ConfigResponse, HandleYamlRequest, HandleYamlResponse, HeartbeatRequest,
-    HeartbeatResponse, NodeRegistrationRequest, NodeRegistrationResponse, StatusAck, StatusReport,
+    ConfigRequest, ConfigResponse, ContainerLogsRequest, ContainerLogsResponse, HandleYamlRequest,
+    HandleYamlResponse, HeartbeatRequest, HeartbeatResponse, ListContainersRequest,
+    ListContainersResponse, NodeRegistrationRequest, NodeRegistrationResponse, StatusAck,
+    StatusReport,
 };
 use tokio::sync::mpsc;
 use tonic::{Request, Response, Status};
@@ -51,6 +53,7 @@ pub async fn register_node(
             master_endpoint: format!("http://{}:47098", master_ip),
             heartbeat_interval: 30,
             settings: std::collections::HashMap::new(),
+            liveness_window: 90,
         }),
     };
 
@@ -92,6 +95,7 @@ pub async fn heartbeat(
             master_endpoint: format!("http://{}:47098", master_ip),
             heartbeat_interval: 30,
             settings: std::collections::HashMap::new(),
+            liveness_window: 90,
         }),
     };
 
@@ -116,13 +120,45 @@ pub async fn receive_config(
     Ok(Response::new(response))
 }
 
+/// Read the logs of a container on this node
+pub async fn get_container_logs(
+    request: Request<ContainerLogsRequest>,
+) -> Result<Response<ContainerLogsResponse>, Status> {
+    let req = request.into_inner();
+    if req.container_id.is_empty() {
+        return Err(Status::invalid_argument("container_id is required"));
+    }
+
+    match crate::resource::container::get_logs(&req.container_id, req.tail).await {
+        Ok(logs) => Ok(Response::new(ContainerLogsResponse { logs })),
+        Err(e) => Err(Status::not_found(format!(
+            "cannot read logs of container '{}': {}",
+            req.container_id, e
+        ))),
+    }
+}
+
+/// List the containers on this node with their current state
+pub async fn list_containers(
+    hostname: String,
+    _request: Request<ListContainersRequest>,
+) -> Result<Response<ListContainersResponse>, Status> {
+    match crate::resource::container::inspect(hostname).await {
+        Ok(containers) => Ok(Response::new(ListContainersResponse { containers })),
+        Err(e) => Err(Status::unavailable(format!(
+            "cannot read containers: {}",
+            e
+        ))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::grpc::receiver::{NodeAgentConnection, NodeAgentReceiver};
     use common::nodeagent::fromapiserver::{
-        ClusterConfig, ConfigRequest, ConfigResponse, HandleYamlRequest, HandleYamlResponse,
-        HeartbeatRequest, HeartbeatResponse, NodeRegistrationRequest, NodeRegistrationResponse,
-        StatusAck, StatusReport,
+        ClusterConfig, ConfigRequest, ConfigResponse, ContainerLogsRequest, HandleYamlRequest,
+        HandleYamlResponse, HeartbeatRequest, HeartbeatResponse, NodeRegistrationRequest,
+        NodeRegistrationResponse, StatusAck, StatusReport,
     };
     use std::sync::Arc;
     use tokio::sync::mpsc;
@@ -343,4 +379,22 @@ spec:
         assert!(response.applied);
         assert_eq!(response.message, "Configuration applied successfully");
     }
+
+    #[tokio::test]
+    async fn test_get_container_logs_requires_container_id() {
+        let (tx, _rx) = mpsc::channel(1);
+        let receiver = NodeAgentReceiver::new(
+            tx,
+            "test-node".to_string(),
+            "test-host".to_string(),
+            "192.168.1.100".to_string(),
+            Arc::new(Mutex::new(std::collections::HashMap::new())),
+        );
+
+        let status = receiver
+            .get_container_logs(Request::new(ContainerLogsRequest::default()))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
 }
@@ -9,16 +9,21 @@ pub mod apiserver;
 use crate::desired_state::DesiredState;
 use common::nodeagent::node_agent_connection_server::NodeAgentConnection;
 use common::nodeagent::{
-    fromactioncontroller::{HandleWorkloadRequest, HandleWorkloadResponse},
+    fromactioncontroller::{
+        HandleUnitRequest, HandleUnitResponse, HandleWorkloadRequest, HandleWorkloadResponse,
+        PrePullImagesRequest, PrePullProgress,
+    },
     fromapiserver::{
-        ConfigRequest, ConfigResponse, HandleYamlRequest, HandleYamlResponse, HeartbeatRequest,
-        HeartbeatResponse, NodeRegistrationRequest, NodeRegistrationResponse, StatusAck,
-        StatusReport,
+        ConfigRequest, ConfigResponse, ContainerLogsRequest, ContainerLogsResponse,
+        HandleYamlRequest, HandleYamlResponse, HeartbeatRequest, HeartbeatResponse,
+        ListContainersRequest, ListContainersResponse, NodeRegistrationRequest,
+        NodeRegistrationResponse, StatusAck, StatusReport,
     },
 };
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::{mpsc, Mutex};
+use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status};
 
 /// NodeAgent gRPC service handler
@@ -53,6 +58,8 @@ impl NodeAgentReceiver {
 
 #[tonic::async_trait]
 impl NodeAgentConnection for NodeAgentReceiver {
+    type PrePullImagesStream = ReceiverStream<Result<PrePullProgress, Status>>;
+
     /// Handle a yaml request from API-Server
     ///
     /// Receives a yaml from API-Server and forwards it to the NodeAgent manager for processing.
@@ -95,14 +102,50 @@ impl NodeAgentConnection for NodeAgentReceiver {
         apiserver::receive_config(request).await
     }
 
+    /// Read the logs of a container for API server
+    async fn get_container_logs(
+        &self,
+        request: Request<ContainerLogsRequest>,
+    ) -> Result<Response<ContainerLogsResponse>, Status> {
+        apiserver::get_container_logs(request).await
+    }
+
+    /// List the containers of this node for API server
+    async fn list_containers(
+        &self,
+        request: Request<ListContainersRequest>,
+    ) -> Result<Response<ListContainersResponse>, Status> {
+        apiserver::list_containers(self.hostname.clone(), request).await
+    }
+
     /// Handle a workload request from ActionController
     ///
     /// Stores desired state in the in-memory cache on START and removes it on STOP/REMOVE,
-    /// then delegates to the Podman runtime.
+    /// then delegates to the Podman runtime, or to systemd with the systemd backend.
     async fn handle_workload(
         &self,
         request: Request<HandleWorkloadRequest>,
     ) -> Result<Response<HandleWorkloadResponse>, Status> {
         actioncontroller::handle_workload(request, Arc::clone(&self.desired_states_cache)).await
     }
+
+    /// Handle an operation on a systemd unit from ActionController
+    ///
+    /// Only available with the systemd workload backend.
+    async fn handle_unit(
+        &self,
+        request: Request<HandleUnitRequest>,
+    ) -> Result<Response<HandleUnitResponse>, Status> {
+        actioncontroller::handle_unit(request).await
+    }
+
+    /// Pull images for an upcoming workload update from ActionController
+    ///
+    /// Streams the progress of every image, see `actioncontroller::prepull_images`.
+    async fn pre_pull_images(
+        &self,
+        request: Request<PrePullImagesRequest>,
+    ) -> Result<Response<Self::PrePullImagesStream>, Status> {
+        actioncontroller::prepull_images(request).await
+    }
 }
@@ -4,17 +4,127 @@
  */
 
 use common::apiserver::api_server_connection_client::ApiServerConnectionClient;
-use common::monitoringserver::{ContainerList, SendContainerListResponse};
+use common::compat;
+use common::monitoringserver::{
+    ContainerEventList, ContainerList, ContainerLogBatch, SendContainerListResponse,
+};
 use common::nodeagent::fromapiserver::{
-    HeartbeatRequest, HeartbeatResponse, NodeRegistrationRequest, NodeRegistrationResponse,
-    StatusAck, StatusReport,
+    HeartbeatAck, HeartbeatRequest, HeartbeatResponse, NodeRegistrationRequest,
+    NodeRegistrationResponse, NodeStatusDelta, StatusAck, StatusReport,
 };
+use common::pullpiri::node::v1::{self, node_registration_client::NodeRegistrationClient};
 use common::statemanager::{
     state_manager_connection_client::StateManagerConnectionClient, Action, Response,
 };
 
 use common::monitoringserver::monitoring_server_connection_client::MonitoringServerConnectionClient;
-use tonic::{Request, Status};
+use common::rpc::RpcClient;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Status, Streaming};
+
+/// Number of event batches buffered before `ContainerEventStream::send` waits
+const CONTAINER_EVENT_BUFFER: usize = 16;
+/// Number of log batches buffered before `ContainerLogStream::send` waits
+const CONTAINER_LOG_BUFFER: usize = 16;
+
+/// Number of status deltas buffered before `HeartbeatStream::exchange` waits
+const HEARTBEAT_BUFFER: usize = 4;
+
+/// Server receiving a container event stream
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContainerEventTarget {
+    MonitoringServer,
+    StateManager,
+}
+
+/// Open `StreamContainerEvents` call to the monitoring server or state manager
+pub struct ContainerEventStream {
+    tx: mpsc::Sender<ContainerEventList>,
+    acks: JoinHandle<()>,
+}
+
+impl ContainerEventStream {
+    /// Queue a batch of events on the stream
+    pub async fn send(&self, events: ContainerEventList) -> Result<(), Status> {
+        if self.is_closed() {
+            return Err(Status::unavailable("container event stream is closed"));
+        }
+        self.tx
+            .send(events)
+            .await
+            .map_err(|_| Status::unavailable("container event stream is closed"))
+    }
+
+    /// The stream is closed once the server side ends or stops acknowledging
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed() || self.acks.is_finished()
+    }
+}
+
+impl Drop for ContainerEventStream {
+    fn drop(&mut self) {
+        self.acks.abort();
+    }
+}
+
+/// Open `HeartbeatStream` call to the API server
+pub struct HeartbeatStream {
+    tx: mpsc::Sender<NodeStatusDelta>,
+    inbound: Streaming<HeartbeatAck>,
+}
+
+impl HeartbeatStream {
+    /// Send a status delta and wait for its acknowledgement
+    pub async fn exchange(&mut self, delta: NodeStatusDelta) -> Result<HeartbeatAck, Status> {
+        self.tx
+            .send(delta)
+            .await
+            .map_err(|_| Status::unavailable("heartbeat stream is closed"))?;
+        self.inbound
+            .message()
+            .await?
+            .ok_or_else(|| Status::unavailable("heartbeat stream is closed"))
+    }
+}
+
+/// Client of a server running on the master node
+fn master_client(service: &str, port: u16) -> RpcClient {
+    let config = crate::config::Config::get();
+    let addr = format!("http://{}:{}", config.nodeagent.master_ip, port);
+    RpcClient::new(service, addr)
+}
+
+/// Open `StreamContainerLogs` call to the monitoring server
+pub struct ContainerLogStream {
+    tx: mpsc::Sender<ContainerLogBatch>,
+    call: JoinHandle<()>,
+}
+
+impl ContainerLogStream {
+    /// Queue a batch of log lines on the stream
+    pub async fn send(&self, batch: ContainerLogBatch) -> Result<(), Status> {
+        if self.is_closed() {
+            return Err(Status::unavailable("container log stream is closed"));
+        }
+        self.tx
+            .send(batch)
+            .await
+            .map_err(|_| Status::unavailable("container log stream is closed"))
+    }
+
+    /// The stream is closed once the call to the server ended
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed() || self.call.is_finished()
+    }
+}
+
+impl Drop for ContainerLogStream {
+    fn drop(&mut self) {
+        self.call.abort();
+    }
+}
 
 /// Sender for making gRPC requests to Monitoring Server
 #[derive(Clone, Default)]
@@ -26,21 +136,14 @@ impl NodeAgentSender {
         &mut self,
         action: Action,
     ) -> Result<tonic::Response<Response>, Status> {
-        let addr = common::statemanager::connect_server();
-        let client = StateManagerConnectionClient::connect(addr).await;
-        match client {
-            Ok(mut client) => {
-                // Send the action
-                client.send_action(Request::new(action)).await
-            }
-            Err(e) => {
-                // Handle connection error
-                Err(Status::unknown(format!(
-                    "Failed to connect statemanager: {}",
-                    e
-                )))
-            }
-        }
+        let action = &action;
+        RpcClient::new("StateManager", common::statemanager::connect_server())
+            .call(|channel| async move {
+                StateManagerConnectionClient::new(channel)
+                    .send_action(Request::new(action.clone()))
+                    .await
+            })
+            .await
     }
 
     /// Send a ContainerList to the monitoring server via gRPC
@@ -48,24 +151,14 @@ impl NodeAgentSender {
         &mut self,
         container_list: ContainerList,
     ) -> Result<tonic::Response<SendContainerListResponse>, Status> {
-        let config = crate::config::Config::get();
-        let master_ip = config.nodeagent.master_ip.clone();
-        let addr = format!("http://{}:47003", master_ip);
-
-        let client = MonitoringServerConnectionClient::connect(addr).await;
-
-        match client {
-            Ok(mut client) => {
-                // Send the container list
-                client
-                    .send_container_list(Request::new(container_list))
+        let container_list = &container_list;
+        master_client("MonitoringServer", 47003)
+            .call(|channel| async move {
+                MonitoringServerConnectionClient::new(channel)
+                    .send_container_list(Request::new(container_list.clone()))
                     .await
-            }
-            Err(e) => {
-                // Handle connection error
-                Err(Status::unknown(format!("Failed to connect: {}", e)))
-            }
-        }
+            })
+            .await
     }
 
     /// Send node information to the monitoring server
@@ -73,16 +166,29 @@ impl NodeAgentSender {
         &mut self,
         node_info: common::monitoringserver::NodeInfo,
     ) -> Result<tonic::Response<common::monitoringserver::SendNodeInfoResponse>, Status> {
-        let config = crate::config::Config::get();
-        let master_ip = config.nodeagent.master_ip.clone();
-        let addr = format!("http://{}:47003", master_ip);
-
-        let client = MonitoringServerConnectionClient::connect(addr).await;
+        let node_info = &node_info;
+        master_client("MonitoringServer", 47003)
+            .call(|channel| async move {
+                MonitoringServerConnectionClient::new(channel)
+                    .send_node_info(Request::new(node_info.clone()))
+                    .await
+            })
+            .await
+    }
 
-        match client {
-            Ok(mut client) => client.send_node_info(Request::new(node_info)).await,
-            Err(e) => Err(Status::unknown(format!("Failed to connect: {}", e))),
-        }
+    /// Send the metrics of the collector plugins to the monitoring server
+    pub async fn send_node_metrics(
+        &mut self,
+        node_metrics: common::monitoringserver::NodeMetrics,
+    ) -> Result<tonic::Response<common::monitoringserver::SendNodeMetricsResponse>, Status> {
+        let node_metrics = &node_metrics;
+        master_client("MonitoringServer", 47003)
+            .call(|channel| async move {
+                MonitoringServerConnectionClient::new(channel)
+                    .send_node_metrics(Request::new(node_metrics.clone()))
+                    .await
+            })
+            .await
     }
 
     /// Send a changed ContainerList to the state manager via gRPC
@@ -90,75 +196,158 @@ impl NodeAgentSender {
         &mut self,
         container_list: ContainerList,
     ) -> Result<tonic::Response<SendContainerListResponse>, Status> {
-        let config = crate::config::Config::get();
-        let master_ip = config.nodeagent.master_ip.clone();
-        let addr = format!("http://{}:47006", master_ip);
-
-        let client = StateManagerConnectionClient::connect(addr).await;
-
-        match client {
-            Ok(mut client) => {
-                // Send the changed container list
-                client
-                    .send_changed_container_list(Request::new(container_list))
+        let container_list = &container_list;
+        master_client("StateManager", 47006)
+            .call(|channel| async move {
+                StateManagerConnectionClient::new(channel)
+                    .send_changed_container_list(Request::new(container_list.clone()))
                     .await
+            })
+            .await
+    }
+
+    /// Open a container event stream to the monitoring server or the state manager
+    ///
+    /// Acks are drained by a background task; the stream reports itself closed
+    /// when that task ends so the caller can reopen it and resend a full snapshot.
+    /// Opening the stream is not retried, the caller reopens it on its next send.
+    pub async fn open_container_event_stream(
+        &mut self,
+        target: ContainerEventTarget,
+    ) -> Result<ContainerEventStream, Status> {
+        let (tx, rx) = mpsc::channel(CONTAINER_EVENT_BUFFER);
+        let outbound = Request::new(ReceiverStream::new(rx));
+
+        let response = match target {
+            ContainerEventTarget::MonitoringServer => {
+                let channel = master_client("MonitoringServer", 47003).channel().await?;
+                MonitoringServerConnectionClient::new(channel)
+                    .stream_container_events(outbound)
+                    .await?
             }
-            Err(e) => {
-                // Handle connection error
-                Err(Status::unknown(format!("Failed to connect: {}", e)))
+            ContainerEventTarget::StateManager => {
+                let channel = master_client("StateManager", 47006).channel().await?;
+                StateManagerConnectionClient::new(channel)
+                    .stream_container_events(outbound)
+                    .await?
             }
-        }
+        };
+
+        let mut inbound = response.into_inner();
+        let acks = tokio::spawn(async move {
+            loop {
+                match inbound.message().await {
+                    Ok(Some(_)) => {}
+                    Ok(None) => break,
+                    Err(e) => {
+                        eprintln!(
+                            "[NodeAgent] Container event stream to {:?} failed: {}",
+                            target, e
+                        );
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(ContainerEventStream { tx, acks })
+    }
+
+    /// Open a container log stream to the monitoring server
+    ///
+    /// The server only answers when the stream ends, so the call runs in a
+    /// background task and the stream reports itself closed when it fails.
+    pub async fn open_container_log_stream(&mut self) -> Result<ContainerLogStream, Status> {
+        let channel = master_client("MonitoringServer", 47003).channel().await?;
+        let (tx, rx) = mpsc::channel(CONTAINER_LOG_BUFFER);
+        let call = tokio::spawn(async move {
+            if let Err(e) = MonitoringServerConnectionClient::new(channel)
+                .stream_container_logs(Request::new(ReceiverStream::new(rx)))
+                .await
+            {
+                eprintln!("[NodeAgent] Container log stream failed: {}", e);
+            }
+        });
+        Ok(ContainerLogStream { tx, call })
     }
 
     /// Register this node with the API server
+    ///
+    /// Registers over `pullpiri.node.v1`, or the unversioned call if the API
+    /// server predates it.
     pub async fn register_with_api_server(
         &mut self,
         registration_request: NodeRegistrationRequest,
     ) -> Result<tonic::Response<NodeRegistrationResponse>, Status> {
-        let config = crate::config::Config::get();
-        let master_ip = config.nodeagent.master_ip.clone();
-        let addr = format!("http://{}:47098", master_ip);
-
-        let client = ApiServerConnectionClient::connect(addr).await;
-
-        match client {
-            Ok(mut client) => {
-                client
-                    .register_node(Request::new(registration_request))
+        let mut versioned = v1::RegisterNodeRequest::from(registration_request.clone());
+        versioned.agent_version = env!("CARGO_PKG_VERSION").to_string();
+        let versioned = &versioned;
+        let result = master_client("API server", 47098)
+            .call(|channel| async move {
+                NodeRegistrationClient::new(channel)
+                    .register_node(Request::new(versioned.clone()))
+                    .await
+            })
+            .await;
+        match result {
+            Ok(response) => Ok(tonic::Response::new(response.into_inner().into())),
+            Err(status) if compat::is_unimplemented(&status) => {
+                let registration_request = &registration_request;
+                master_client("API server", 47098)
+                    .call(|channel| async move {
+                        ApiServerConnectionClient::new(channel)
+                            .register_node(Request::new(registration_request.clone()))
+                            .await
+                    })
                     .await
             }
-            Err(e) => Err(Status::unknown(format!(
-                "Failed to connect to API server: {}",
-                e
-            ))),
+            Err(status) => Err(status),
         }
     }
 
     /// Send heartbeat to the API server
     pub async fn send_heartbeat(
         &mut self,
-        _heartbeat_request: HeartbeatRequest,
+        heartbeat_request: HeartbeatRequest,
     ) -> Result<tonic::Response<HeartbeatResponse>, Status> {
-        // For NodeAgent sender, we use the NodeAgent service to send heartbeat
-        // This is a local operation that would be handled by the node's own receiver
-        // In practice, heartbeats are typically sent from NodeAgent to API server
-        // but this implementation allows for local heartbeat processing
-
-        // TODO: Implement heartbeat sending to API server if needed
-        // For now, return a success response
-        // Use master_ip from config
-        let config = crate::config::Config::get();
-        let master_ip = config.nodeagent.master_ip.clone();
-        let master_endpoint = format!("http://{}:47098", master_ip);
-
-        Ok(tonic::Response::new(HeartbeatResponse {
-            ack: true,
-            updated_config: Some(common::nodeagent::fromapiserver::ClusterConfig {
-                master_endpoint,
-                heartbeat_interval: 30,
-                settings: std::collections::HashMap::new(),
-            }),
-        }))
+        let versioned = &v1::HeartbeatRequest::from(heartbeat_request.clone());
+        let result = master_client("API server", 47098)
+            .call(|channel| async move {
+                NodeRegistrationClient::new(channel)
+                    .heartbeat(Request::new(versioned.clone()))
+                    .await
+            })
+            .await;
+        match result {
+            Ok(response) => Ok(tonic::Response::new(response.into_inner().into())),
+            Err(status) if compat::is_unimplemented(&status) => {
+                let heartbeat_request = &heartbeat_request;
+                master_client("API server", 47098)
+                    .call(|channel| async move {
+                        ApiServerConnectionClient::new(channel)
+                            .heartbeat(Request::new(heartbeat_request.clone()))
+                            .await
+                    })
+                    .await
+            }
+            Err(status) => Err(status),
+        }
+    }
+
+    /// Open a heartbeat stream to the API server
+    ///
+    /// Opening the stream is not retried, the caller reopens it on its next
+    /// heartbeat.
+    pub async fn open_heartbeat_stream(&mut self) -> Result<HeartbeatStream, Status> {
+        let (tx, rx) = mpsc::channel(HEARTBEAT_BUFFER);
+        let channel = master_client("API server", 47098).channel().await?;
+        let response = ApiServerConnectionClient::new(channel)
+            .heartbeat_stream(Request::new(ReceiverStream::new(rx)))
+            .await?;
+        Ok(HeartbeatStream {
+            tx,
+            inbound: response.into_inner(),
+        })
     }
 
     /// Send status report to the API server
@@ -180,15 +369,19 @@ impl NodeAgentSender {
 
 #[cfg(test)]
 mod tests {
-    use crate::grpc::sender::NodeAgentSender;
+    use crate::grpc::sender::{
+        ContainerEventStream, ContainerEventTarget, ContainerLogStream, NodeAgentSender,
+    };
     use common::monitoringserver::{
-        ContainerList, NodeInfo, SendContainerListResponse, SendNodeInfoResponse,
+        ContainerEventList, ContainerList, ContainerLogBatch, NodeInfo, SendContainerListResponse,
+        SendNodeInfoResponse,
     };
     use common::nodeagent::fromapiserver::{
         HeartbeatRequest, HeartbeatResponse, NodeRegistrationRequest, NodeRegistrationResponse,
         StatusAck, StatusReport,
     };
     use common::statemanager::{Action, Response as SMResponse};
+    use tokio::sync::mpsc;
     use tonic::{Request, Response, Status};
 
     #[tokio::test]
@@ -265,15 +458,15 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_send_heartbeat_returns_success() {
+    async fn test_send_heartbeat_without_api_server() {
         let mut sender = NodeAgentSender::default();
 
-        let req = HeartbeatRequest::default();
-        let result = sender.send_heartbeat(req).await;
-        assert!(result.is_ok());
-        let resp = result.unwrap().into_inner();
-        assert!(resp.ack);
-        assert_eq!(resp.updated_config.as_ref().unwrap().heartbeat_interval, 30);
+        // No API server runs in the test environment
+        let status = sender
+            .send_heartbeat(HeartbeatRequest::default())
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unavailable);
     }
 
     #[tokio::test]
@@ -320,6 +513,54 @@ mod tests {
         assert!(result.is_ok() || result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_open_container_event_stream_without_state_manager() {
+        let mut sender = NodeAgentSender::default();
+
+        // StateManager is not running in test environment
+        let Err(status) = sender
+            .open_container_event_stream(ContainerEventTarget::StateManager)
+            .await
+        else {
+            panic!("stream opened without a StateManager");
+        };
+        assert!(
+            matches!(
+                status.code(),
+                tonic::Code::Unavailable | tonic::Code::DeadlineExceeded
+            ),
+            "{}",
+            status
+        );
+        assert!(status.message().contains("StateManager"));
+    }
+
+    #[tokio::test]
+    async fn test_container_event_stream_reports_closed() {
+        let (tx, rx) = mpsc::channel(1);
+        let stream = ContainerEventStream {
+            tx,
+            acks: tokio::spawn(async {}),
+        };
+        drop(rx);
+
+        assert!(stream.is_closed());
+        assert!(stream.send(ContainerEventList::default()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_container_log_stream_reports_closed() {
+        let (tx, rx) = mpsc::channel(1);
+        let stream = ContainerLogStream {
+            tx,
+            call: tokio::spawn(async {}),
+        };
+        drop(rx);
+
+        assert!(stream.is_closed());
+        assert!(stream.send(ContainerLogBatch::default()).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_register_with_api_server_with_invalid_addr() {
         let mut sender = NodeAgentSender::default();
@@ -337,8 +578,7 @@ mod tests {
         let req = HeartbeatRequest::default();
         let result1 = sender.send_heartbeat(req.clone()).await;
         let result2 = sender.send_heartbeat(req).await;
-        assert!(result1.is_ok());
-        assert!(result2.is_ok());
+        assert_eq!(result1.is_ok(), result2.is_ok());
     }
 
     #[tokio::test]
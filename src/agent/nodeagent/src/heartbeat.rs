@@ -0,0 +1,290 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+//! Heartbeats streamed to the API server
+//!
+//! The agent keeps one `HeartbeatStream` call open and sends the node status
+//! metrics that changed since the previous heartbeat; the first heartbeat of a
+//! stream, and the one after the API server asks for a resync, carries all of
+//! them. The heartbeat interval pushed by the API server with every ack
+//! replaces the configured one. While the stream cannot be opened, plain
+//! `Heartbeat` calls keep the node alive.
+//!
+//! Node conditions such as `RuntimeUnavailable` travel with the status as
+//! `condition/<name>` metrics holding their reason, and are removed from the
+//! status once cleared.
+
+use crate::grpc::sender::{HeartbeatStream, NodeAgentSender};
+use common::monitoringserver::NodeInfo;
+use common::nodeagent::fromapiserver::{ClusterConfig, HeartbeatRequest, NodeStatusDelta};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::{interval_at, Instant};
+
+/// Latest status metrics of the node
+static STATUS: Lazy<Mutex<HashMap<String, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Raised node conditions with their reason
+static CONDITIONS: Lazy<Mutex<HashMap<String, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Prefix of the status metrics carrying node conditions
+pub const CONDITION_PREFIX: &str = "condition/";
+
+/// Keep the latest node info as heartbeat status
+///
+/// Usages are rounded to whole percents so that small fluctuations do not
+/// count as changes.
+pub fn record_node_info(node_info: &NodeInfo) {
+    let status = HashMap::from([
+        (
+            "cpu_usage".to_string(),
+            format!("{:.0}", node_info.cpu_usage),
+        ),
+        ("cpu_count".to_string(), node_info.cpu_count.to_string()),
+        ("gpu_count".to_string(), node_info.gpu_count.to_string()),
+        (
+            "mem_usage".to_string(),
+            format!("{:.0}", node_info.mem_usage),
+        ),
+        (
+            "total_memory".to_string(),
+            node_info.total_memory.to_string(),
+        ),
+        ("os".to_string(), node_info.os.clone()),
+        ("arch".to_string(), node_info.arch.clone()),
+        ("ip".to_string(), node_info.ip.clone()),
+    ]);
+    if let Ok(mut current) = STATUS.lock() {
+        *current = status;
+    }
+}
+
+/// Raise a node condition, or clear it with `None`
+pub fn set_condition(name: &str, reason: Option<&str>) {
+    if let Ok(mut conditions) = CONDITIONS.lock() {
+        match reason {
+            Some(reason) => conditions.insert(name.to_string(), reason.to_string()),
+            None => conditions.remove(name),
+        };
+    }
+}
+
+fn current_status() -> HashMap<String, String> {
+    let mut status = STATUS
+        .lock()
+        .map(|status| status.clone())
+        .unwrap_or_default();
+    if let Ok(conditions) = CONDITIONS.lock() {
+        status.extend(
+            conditions
+                .iter()
+                .map(|(name, reason)| (format!("{}{}", CONDITION_PREFIX, name), reason.clone())),
+        );
+    }
+    status
+}
+
+fn timestamp() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Builds the status deltas of one heartbeat stream
+#[derive(Default)]
+pub struct StatusTracker {
+    sequence: u64,
+    /// Status known to the API server, `None` until a full status is sent
+    sent: Option<HashMap<String, String>>,
+}
+
+impl StatusTracker {
+    /// Delta from the status last sent to `status`
+    pub fn next(&mut self, node_id: &str, status: &HashMap<String, String>) -> NodeStatusDelta {
+        self.sequence += 1;
+        let mut delta = NodeStatusDelta {
+            node_id: node_id.to_string(),
+            timestamp: timestamp(),
+            sequence: self.sequence,
+            ..Default::default()
+        };
+        match &self.sent {
+            None => {
+                delta.full = true;
+                delta.changed_metrics = status.clone();
+            }
+            Some(sent) => {
+                delta.changed_metrics = status
+                    .iter()
+                    .filter(|(name, value)| sent.get(*name) != Some(*value))
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect();
+                delta.removed_metrics = sent
+                    .keys()
+                    .filter(|name| !status.contains_key(*name))
+                    .cloned()
+                    .collect();
+                delta.removed_metrics.sort();
+            }
+        }
+        self.sent = Some(status.clone());
+        delta
+    }
+
+    /// Send the full status with the next delta
+    pub fn resync(&mut self) {
+        self.sent = None;
+    }
+}
+
+/// Heartbeat interval pushed by the API server, `current` if none
+fn pushed_interval(config: Option<&ClusterConfig>, current: Duration) -> Duration {
+    match config {
+        Some(config) if config.heartbeat_interval > 0 => {
+            Duration::from_secs(config.heartbeat_interval as u64)
+        }
+        _ => current,
+    }
+}
+
+/// Send heartbeats to the API server until the agent stops
+///
+/// ### Parameters
+/// * `node_id: String` - id the node registered with
+/// * `interval: Duration` - interval until the API server pushes one
+pub async fn run(node_id: String, mut interval: Duration) {
+    let mut sender = NodeAgentSender::default();
+    let mut stream: Option<HeartbeatStream> = None;
+    let mut tracker = StatusTracker::default();
+    let mut ticker = interval_at(Instant::now(), interval);
+
+    loop {
+        ticker.tick().await;
+        let config = match send(&mut sender, &mut stream, &mut tracker, &node_id, interval).await {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("Failed to send heartbeat: {:?}", e);
+                continue;
+            }
+        };
+
+        let pushed = pushed_interval(config.as_ref(), interval);
+        if pushed != interval {
+            println!(
+                "Heartbeat interval changed from {}s to {}s",
+                interval.as_secs(),
+                pushed.as_secs()
+            );
+            interval = pushed;
+            ticker = interval_at(Instant::now() + interval, interval);
+        }
+    }
+}
+
+/// Send one heartbeat, over the stream if it can be opened
+async fn send(
+    sender: &mut NodeAgentSender,
+    stream: &mut Option<HeartbeatStream>,
+    tracker: &mut StatusTracker,
+    node_id: &str,
+    interval: Duration,
+) -> Result<Option<ClusterConfig>, tonic::Status> {
+    if stream.is_none() {
+        match sender.open_heartbeat_stream().await {
+            Ok(opened) => {
+                *stream = Some(opened);
+                *tracker = StatusTracker::default();
+            }
+            Err(e) => {
+                eprintln!("Failed to open heartbeat stream: {}", e.message());
+                let request = HeartbeatRequest {
+                    node_id: node_id.to_string(),
+                    timestamp: timestamp(),
+                };
+                let response = sender.send_heartbeat(request).await?;
+                return Ok(response.into_inner().updated_config);
+            }
+        }
+    }
+    let Some(open) = stream.as_mut() else {
+        return Ok(None);
+    };
+
+    let delta = tracker.next(node_id, &current_status());
+    let ack = match tokio::time::timeout(interval, open.exchange(delta)).await {
+        Ok(Ok(ack)) => ack,
+        Ok(Err(e)) => {
+            *stream = None;
+            return Err(e);
+        }
+        Err(_) => {
+            *stream = None;
+            return Err(tonic::Status::deadline_exceeded(
+                "heartbeat was not acknowledged",
+            ));
+        }
+    };
+    if ack.resync {
+        tracker.resync();
+    }
+    Ok(ack.config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_tracker_sends_changes_only() {
+        let mut tracker = StatusTracker::default();
+        let first = status(&[("cpu_usage", "10"), ("gpu_count", "1")]);
+        let delta = tracker.next("node-1", &first);
+        assert!(delta.full);
+        assert_eq!(delta.sequence, 1);
+        assert_eq!(delta.changed_metrics, first);
+
+        let delta = tracker.next("node-1", &first);
+        assert!(!delta.full);
+        assert!(delta.changed_metrics.is_empty());
+        assert!(delta.removed_metrics.is_empty());
+
+        let delta = tracker.next("node-1", &status(&[("cpu_usage", "12")]));
+        assert_eq!(delta.sequence, 3);
+        assert_eq!(delta.changed_metrics, status(&[("cpu_usage", "12")]));
+        assert_eq!(delta.removed_metrics, vec!["gpu_count"]);
+
+        tracker.resync();
+        let delta = tracker.next("node-1", &status(&[("cpu_usage", "12")]));
+        assert!(delta.full);
+        assert_eq!(delta.sequence, 4);
+    }
+
+    #[test]
+    fn test_pushed_interval() {
+        let current = Duration::from_secs(3);
+        let config = ClusterConfig {
+            heartbeat_interval: 10,
+            ..Default::default()
+        };
+        assert_eq!(
+            pushed_interval(Some(&config), current),
+            Duration::from_secs(10)
+        );
+        assert_eq!(pushed_interval(None, current), current);
+        assert_eq!(
+            pushed_interval(Some(&ClusterConfig::default()), current),
+            current
+        );
+    }
+}
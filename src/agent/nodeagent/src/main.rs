@@ -8,22 +8,34 @@
 //! and launches both concurrently. It also provides unit tests for initialization.
 
 use clap::Parser;
+use common::health;
 use common::nodeagent::fromapiserver::{HandleYamlRequest, NodeRegistrationRequest};
+use common::startup::{self, Dependency};
+use common::supervise::{self, RestartPolicy};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
+pub mod bootstrap;
 pub mod config;
 pub mod desired_state;
 pub mod grpc;
+pub mod heartbeat;
 pub mod manager;
+pub mod outbox;
 pub mod probe;
 pub mod resource;
 pub mod runtime;
+pub mod watchdog;
 
 use crate::desired_state::DesiredState;
 use common::nodeagent::node_agent_connection_server::NodeAgentConnectionServer;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
-use tokio::sync::Mutex;
+use tokio::sync::{watch, Mutex};
+
+/// Readiness check of the manager applying workloads
+const CHECK_MANAGER: &str = "manager";
+/// Readiness check of the registration with the API server
+const CHECK_REGISTRATION: &str = "registration";
 
 /// Launches the NodeAgentManager in an asynchronous task.
 ///
@@ -41,68 +53,97 @@ async fn launch_manager(
     match manager.initialize().await {
         Ok(_) => {
             println!("NodeAgentManager successfully initialized");
-            // Add registration with API server
-            let mut sender = grpc::sender::NodeAgentSender::default();
-
-            // Use IP address from config file
-            let host_ip = config.get_host_ip();
-            let node_name = config.get_node_name();
-            // node_id를 node_name과 동일하게 설정 (IP 주소 제거)
-            let node_id = node_name.clone();
-
-            let registration_request = NodeRegistrationRequest {
-                node_id: node_id.clone(),
-                hostname: hostname.clone(),
-                ip_address: host_ip.clone(),
-                metadata: std::collections::HashMap::new(),
-                resources: None,
-                node_type: match config.nodeagent.node_type.as_str() {
-                    "cloud" => 1,   // NodeType::Cloud as i32
-                    "vehicle" => 2, // NodeType::Vehicle as i32
-                    _ => 0,         // NodeType::Unspecified as i32
-                },
-                node_role: match config.nodeagent.node_role.as_str() {
-                    "master" => 1,    // NodeRole::Master as i32
-                    "nodeagent" => 2, // NodeRole::Nodeagent as i32
-                    "bluechi" => 3,   // NodeRole::Bluechi as i32
-                    _ => 0,           // NodeRole::Unspecified as i32
-                },
-            };
-
-            // Register with API server
-            match sender.register_with_api_server(registration_request).await {
-                Ok(_) => println!("Successfully registered with API server"),
-                Err(e) => eprintln!("Failed to register with API server: {:?}", e),
+            health::set_ready(CHECK_MANAGER);
+            let registration_request = registration_request(&config, &hostname);
+            let node_id = registration_request.node_id.clone();
+
+            // Register with API server, once it is reachable
+            match outbox::send(outbox::Message::Registration(registration_request)).await {
+                Ok(outbox::Delivery::Sent) => {
+                    println!("Successfully registered with API server");
+                    health::set_ready(CHECK_REGISTRATION);
+                }
+                Ok(outbox::Delivery::Queued) => {
+                    eprintln!("API server unreachable, registration queued");
+                    health::set_not_ready(CHECK_REGISTRATION, "API server unreachable");
+                }
+                Err(e) => {
+                    eprintln!("Failed to register with API server: {:?}", e);
+                    health::set_not_ready(CHECK_REGISTRATION, e.to_string());
+                }
             }
 
             // Start heartbeat task
-            let mut sender_clone = sender.clone();
-            let node_id_clone = node_id.clone();
-            tokio::spawn(async move {
-                let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(3));
-                loop {
-                    interval.tick().await;
-                    let heartbeat_request = common::nodeagent::fromapiserver::HeartbeatRequest {
-                        node_id: node_id_clone.clone(),
-                        timestamp: std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap()
-                            .as_secs() as i64, // Cast to i64
-                    };
-                    // Fix: call on instance, not static method
-                    if let Err(e) = sender_clone.send_heartbeat(heartbeat_request).await {
-                        eprintln!("Failed to send heartbeat: {:?}", e);
-                    }
-                }
+            let interval = config.get_heartbeat_interval();
+            supervise::spawn("heartbeat", RestartPolicy::default(), move || {
+                heartbeat::run(node_id.clone(), interval)
             });
 
             // Run the manager
             if let Err(e) = manager.run().await {
                 eprintln!("Error running NodeAgentManager: {:?}", e);
             }
+            health::set_not_ready(CHECK_MANAGER, "stopped");
         }
         Err(e) => {
             eprintln!("Failed to initialize NodeAgentManager: {:?}", e);
+            health::set_not_ready(CHECK_MANAGER, format!("initialization failed: {:?}", e));
+        }
+    }
+}
+
+/// Builds the registration of this node with the API server.
+fn registration_request(config: &config::Config, hostname: &str) -> NodeRegistrationRequest {
+    // node_id를 node_name과 동일하게 설정 (IP 주소 제거)
+    let node_id = config.get_node_name();
+
+    NodeRegistrationRequest {
+        node_id,
+        hostname: hostname.to_string(),
+        // Use IP address from config file
+        ip_address: config.get_host_ip(),
+        metadata: config.get_node_metadata(),
+        resources: None,
+        node_type: match config.nodeagent.node_type.as_str() {
+            "cloud" => 1,   // NodeType::Cloud as i32
+            "vehicle" => 2, // NodeType::Vehicle as i32
+            _ => 0,         // NodeType::Unspecified as i32
+        },
+        node_role: match config.nodeagent.node_role.as_str() {
+            "master" => 1,    // NodeRole::Master as i32
+            "nodeagent" => 2, // NodeRole::Nodeagent as i32
+            "bluechi" => 3,   // NodeRole::Bluechi as i32
+            _ => 0,           // NodeRole::Unspecified as i32
+        },
+    }
+}
+
+/// Registers the node again whenever the reloaded configuration changes it.
+///
+/// New labels, taints or addresses reach the API server without a restart.
+async fn register_on_config_change(
+    mut updates: watch::Receiver<Arc<config::Config>>,
+    hostname: String,
+) {
+    let mut registered = registration_request(&updates.borrow(), &hostname);
+    while updates.changed().await.is_ok() {
+        let config = updates.borrow_and_update().clone();
+        let request = registration_request(&config, &hostname);
+        if request == registered {
+            continue;
+        }
+        println!(
+            "Configuration changed, registering node {} again",
+            request.node_id
+        );
+        match outbox::send(outbox::Message::Registration(request.clone())).await {
+            Ok(outbox::Delivery::Sent) => {
+                registered = request;
+                health::set_ready(CHECK_REGISTRATION);
+            }
+            // Delivered by the outbox once the API server is reachable
+            Ok(outbox::Delivery::Queued) => registered = request,
+            Err(e) => eprintln!("Failed to register reloaded configuration: {:?}", e),
         }
     }
 }
@@ -151,10 +192,38 @@ async fn initialize(
         config.nodeagent.master_ip, config.nodeagent.grpc_port
     );
 
-    let _ = Server::builder()
+    health::set_ready(health::CHECK_GRPC_SERVER);
+    let result = Server::builder()
+        .layer(common::trace::GrpcTraceLayer)
         .add_service(NodeAgentConnectionServer::new(server))
+        .add_service(common::reflection::service())
         .serve(addr)
         .await;
+    let reason = match result {
+        Ok(()) => "stopped".to_string(),
+        Err(e) => e.to_string(),
+    };
+    health::set_not_ready(health::CHECK_GRPC_SERVER, reason);
+}
+
+/// Wait for the dependencies of the `startup` section, by default the API
+/// server of the master
+async fn wait_for_master(config: &config::Config) -> Result<(), String> {
+    let settings = &config.nodeagent.startup;
+    let dependencies = match settings.dependencies.get("nodeagent") {
+        Some(specs) => specs
+            .iter()
+            .map(|spec| Dependency::parse(spec))
+            .collect::<Result<Vec<_>, _>>()?,
+        None => vec![Dependency::Grpc {
+            name: "apiserver".to_string(),
+            url: format!("http://{}:47098", config.nodeagent.master_ip),
+        }],
+    };
+    for dependency in &dependencies {
+        println!("Waiting for {}", dependency.describe());
+    }
+    startup::wait_for("nodeagent", &dependencies, settings).await
 }
 
 /// Main entry point for the NodeAgent binary.
@@ -164,9 +233,23 @@ async fn initialize(
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Path to the configuration file
-    #[arg(short, long, default_value = "/etc/pullpiri/nodeagent.yaml")]
-    config: PathBuf,
+    /// Path to the configuration file, else `PULLPIRI_NODEAGENT_CONFIG` or
+    /// `/etc/pullpiri/nodeagent.yaml`
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+    /// Join the master with a token of `POST /api/v1/nodes/bootstrap-token`,
+    /// writing the configuration file before starting
+    #[arg(long, value_name = "TOKEN")]
+    bootstrap: Option<String>,
+    /// Address of the master the bootstrap token is redeemed at, else the
+    /// `master_ip` of the configuration file
+    #[arg(long, value_name = "IP", requires = "bootstrap")]
+    master: Option<String>,
+    /// Keep workloads in memory instead of running them with Podman or
+    /// systemd, for development hosts without them
+    #[cfg(feature = "mock-runtime")]
+    #[arg(long)]
+    mock_runtime: bool,
 }
 
 #[cfg(not(feature = "tarpaulin_include"))]
@@ -174,26 +257,61 @@ struct Args {
 async fn main() {
     // Parse command line arguments
     let args = Args::parse();
+    #[cfg(feature = "mock-runtime")]
+    if args.mock_runtime {
+        println!("Workloads are kept in memory by the mock runtime");
+        runtime::mock::enable();
+    }
 
     // Load configuration file
-    let app_config = match config::Config::load(&args.config) {
+    let config_path = config::config_path(args.config);
+    if let Some(token) = args.bootstrap.as_deref() {
+        match bootstrap::run(token, args.master, &config_path).await {
+            Ok(node_name) => println!(
+                "Bootstrapped node {} into {}",
+                node_name,
+                config_path.display()
+            ),
+            Err(e) => {
+                eprintln!("Bootstrap failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    let app_config = match config::Config::load(&config_path) {
         Ok(config) => {
-            println!("Loaded configuration from {}", args.config.display());
+            println!("Loaded configuration from {}", config_path.display());
             config
         }
-        Err(err) => {
+        Err(config::ConfigError::IoError(err)) if err.kind() == std::io::ErrorKind::NotFound => {
             eprintln!(
                 "Error loading configuration from {}: {}",
-                args.config.display(),
+                config_path.display(),
                 err
             );
             eprintln!("Falling back to default configuration");
             config::Config::default()
         }
+        Err(err) => {
+            // An invalid file is not replaced by defaults, which could
+            // register the node with the wrong role or master
+            eprintln!(
+                "Invalid configuration in {}: {}",
+                config_path.display(),
+                err
+            );
+            std::process::exit(1);
+        }
     };
+    if app_config.nodeagent.tls.enabled {
+        eprintln!(
+            "Warning: TLS certificates are configured but gRPC connections are not encrypted yet"
+        );
+    }
 
     // Set global config for other parts of the application
     config::Config::set_global(app_config.clone());
+    let config_updates = app_config.watch(config_path);
 
     let mut hostname = app_config.get_hostname();
     if hostname.is_empty() || hostname == "$(hostname)" {
@@ -208,11 +326,42 @@ async fn main() {
         .to_string();
     }
     println!("Starting NodeAgent on host: {}", hostname);
+    if let Err(e) = wait_for_master(&app_config).await {
+        eprintln!("NodeAgent starts without its dependencies, {}", e);
+    }
+    health::init(
+        "nodeagent",
+        &[
+            health::CHECK_GRPC_SERVER,
+            CHECK_MANAGER,
+            CHECK_REGISTRATION,
+            watchdog::CHECK_RUNTIME,
+        ],
+    );
+    tokio::spawn(health::serve(common::nodeagent::open_health_server(
+        &app_config.get_host_ip(),
+    )));
 
     // Create the shared desired states cache - used by both manager and gRPC receiver
     let desired_states_cache: Arc<Mutex<HashMap<String, DesiredState>>> =
         Arc::new(Mutex::new(HashMap::new()));
 
+    supervise::spawn("runtime_watchdog", RestartPolicy::default(), watchdog::run);
+    supervise::spawn(
+        "container_sampler",
+        RestartPolicy::default(),
+        resource::sampler::run,
+    );
+    supervise::spawn(
+        "container_logs",
+        RestartPolicy::default(),
+        resource::logs::run,
+    );
+    outbox::init(
+        &app_config.nodeagent.outbox.path,
+        app_config.nodeagent.outbox.max_bytes,
+    );
+
     let (tx_grpc, rx_grpc) = channel::<HandleYamlRequest>(100);
     let mgr = launch_manager(
         rx_grpc,
@@ -220,6 +369,7 @@ async fn main() {
         app_config.clone(),
         Arc::clone(&desired_states_cache),
     );
+    tokio::spawn(register_on_config_change(config_updates, hostname.clone()));
     let grpc = initialize(tx_grpc, hostname, app_config, desired_states_cache);
 
     tokio::join!(mgr, grpc);
@@ -349,4 +499,23 @@ mod tests {
         assert!(guard.contains_key("shared-pod"));
         assert_eq!(guard.len(), 1);
     }
+
+    #[test]
+    fn test_registration_request_follows_config() {
+        let mut config = Config::default();
+        config.nodeagent.node_name = "zone1".to_string();
+        config.nodeagent.node_role = "nodeagent".to_string();
+        let request = crate::registration_request(&config, "zone1-host");
+        assert_eq!(request.node_id, "zone1");
+        assert_eq!(request.hostname, "zone1-host");
+        assert_eq!(request.node_role, 2);
+
+        config
+            .nodeagent
+            .labels
+            .insert("zone".to_string(), "front".to_string());
+        let reloaded = crate::registration_request(&config, "zone1-host");
+        assert_ne!(reloaded, request);
+        assert_eq!(reloaded.metadata.get("zone"), Some(&"front".to_string()));
+    }
 }
@@ -8,11 +8,15 @@
 //! a gRPC sender for communicating with the monitoring server or other services.
 //! It is designed to be thread-safe and run in an async context.
 use crate::desired_state::DesiredState;
-use crate::grpc::sender::NodeAgentSender;
-use common::monitoringserver::{ContainerInfo, ContainerList};
+use crate::grpc::sender::{ContainerEventStream, ContainerEventTarget, NodeAgentSender};
+use crate::resource::RuntimeEvent;
+use common::monitoringserver::{
+    ContainerEvent, ContainerEventList, ContainerEventType, ContainerInfo, ContainerList,
+};
+use common::nodeagent::fromactioncontroller::WorkloadCommand;
 use common::nodeagent::fromapiserver::HandleYamlRequest;
 use common::Result;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::{mpsc, Mutex};
 
@@ -81,53 +85,43 @@ impl NodeAgentManager {
 
     /// Background task: Periodically gathers container info using inspect().
     ///
-    /// This runs in an infinite loop and logs or processes container info as needed.
+    /// Changes are streamed as container events to the monitoring server (including stats)
+    /// and to the state manager (excluding stats). When a stream cannot be opened the
+    /// previous unary calls are used instead, so older servers keep working.
+    /// OOM kills are taken from the runtime events, as the inspected state may miss them.
+    /// Nothing is published while the runtime socket is down, so the containers are not
+    /// reported as gone during an outage.
     async fn gather_container_info_loop(&self) {
-        use crate::resource::container::inspect;
+        use crate::resource::container::{get_events, inspect};
         use tokio::time::{sleep, Duration};
 
-        // This is the previous container list for comparison
-        let mut previous_container_list = Vec::new();
+        let mut monitoring = ContainerEventPublisher::new(ContainerEventTarget::MonitoringServer);
+        let mut statemanager = ContainerEventPublisher::new(ContainerEventTarget::StateManager);
+        let mut events_since = unix_now_secs();
 
         loop {
-            let container_list = inspect(self.hostname.clone()).await.unwrap_or_default();
-            let node = self.hostname.clone();
-
-            // Send the container info to the monitoring server
-            {
-                let mut sender = self.sender.lock().await;
-                if let Err(e) = sender
-                    .send_container_list(ContainerList {
-                        node_name: node.clone(),
-                        containers: container_list.clone(),
-                    })
-                    .await
-                {
-                    eprintln!("[NodeAgent] Error sending container info: {}", e);
-                }
+            if !crate::watchdog::runtime_available() {
+                sleep(Duration::from_secs(1)).await;
+                continue;
             }
+            let now = unix_now_secs();
+            let oom_killed = match get_events(events_since).await {
+                Ok(events) => oom_killed_containers(&events),
+                Err(_) => HashSet::new(),
+            };
+            events_since = now;
 
-            // Check if the container list is changed from the previous one except for ContainerList.stats
-            // (which is not included in the comparison)
-            if !containers_equal_except_stats(&previous_container_list, &container_list) {
-                // println!(
-                //     "Container list changed for node: {}. Previous: {:?}, Current: {:?}",
-                //     node, previous_container_list, container_list
-                // );
-
-                // Save the previous container list for comparison
-                previous_container_list = container_list.clone();
+            let container_list = inspect(self.hostname.clone()).await.unwrap_or_default();
 
-                // Send the changed container list to the state manager
-                let mut sender = self.sender.lock().await;
-                if let Err(e) = sender
-                    .send_changed_container_list(ContainerList {
-                        node_name: node.clone(),
-                        containers: container_list,
-                    })
+            for publisher in [&mut monitoring, &mut statemanager] {
+                if let Err(e) = publisher
+                    .publish(&self.sender, &self.hostname, &container_list, &oom_killed)
                     .await
                 {
-                    eprintln!("[NodeAgent] Error sending changed container list: {}", e);
+                    eprintln!(
+                        "[NodeAgent] Error sending container info to {:?}: {}",
+                        publisher.target, e
+                    );
                 }
             }
 
@@ -164,6 +158,8 @@ impl NodeAgentManager {
                 ip: node_info_data.ip,
             };
 
+            crate::heartbeat::record_node_info(&node_info);
+
             // Send NodeInfo to monitoring server
             {
                 let mut sender = self.sender.lock().await;
@@ -192,6 +188,75 @@ impl NodeAgentManager {
         }
     }
 
+    /// Background task: Periodically collects the metrics of the configured plugins
+    /// and sends them to the monitoring server.
+    ///
+    /// Returns right away if no plugin is configured.
+    async fn gather_plugin_metrics_loop(&self) {
+        use crate::resource::plugin::Plugins;
+        use common::monitoringserver::NodeMetrics;
+        use std::time::Instant;
+        use tokio::time::{sleep, Duration};
+
+        let mut plugins = Plugins::from_config(&crate::config::Config::get().nodeagent.plugins);
+        if plugins.is_empty() {
+            return;
+        }
+
+        loop {
+            let metrics = plugins.collect_due(Instant::now()).await;
+            if !metrics.is_empty() {
+                let node_metrics = NodeMetrics {
+                    node_name: self.hostname.clone(),
+                    metrics,
+                    timestamp: std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map(|d| d.as_secs() as i64)
+                        .unwrap_or(0),
+                };
+                let mut sender = self.sender.lock().await;
+                if let Err(e) = sender.send_node_metrics(node_metrics).await {
+                    eprintln!("[NodeAgent] Error sending plugin metrics: {}", e);
+                }
+            }
+            sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    /// Background task: Periodically reads the properties of the workload units.
+    ///
+    /// The units run by the systemd backend or the Bluechi agent are sent to the state
+    /// manager whenever one of them changes, so their models follow the units.
+    /// Returns right away if the workloads of this node do not run as units.
+    async fn gather_unit_status_loop(&self) {
+        use crate::runtime::systemd::status;
+        use tokio::time::{sleep, Duration};
+
+        if !status::enabled() {
+            return;
+        }
+
+        let mut reported: Option<Vec<ContainerInfo>> = None;
+        loop {
+            match status::unit_containers().await {
+                Ok(units) if reported.as_ref() != Some(&units) => {
+                    let container_list = ContainerList {
+                        node_name: self.hostname.clone(),
+                        containers: units.clone(),
+                    };
+                    let message = crate::outbox::Message::ChangedContainers(container_list);
+                    match crate::outbox::send(message).await {
+                        Ok(_) => reported = Some(units),
+                        Err(e) => eprintln!("[NodeAgent] Error sending unit status: {}", e),
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("[NodeAgent] Error reading unit status: {}", e),
+            }
+            sleep(Duration::from_secs(2)).await;
+        }
+    }
+
     /// Runs the NodeAgentManager event loop.
     ///
     /// Spawns the gRPC processing task and the container info gatherer, and waits for them to finish.
@@ -214,6 +279,18 @@ impl NodeAgentManager {
             nodeinfo_manager.gather_node_info_loop().await;
         });
 
+        // Spawn a background task to collect the metrics of the plugins
+        let plugin_manager = Arc::clone(&arc_self);
+        let plugin_task = tokio::spawn(async move {
+            plugin_manager.gather_plugin_metrics_loop().await;
+        });
+
+        // Spawn a background task to report the state of the workload units
+        let unit_manager = Arc::clone(&arc_self);
+        let unit_task = tokio::spawn(async move {
+            unit_manager.gather_unit_status_loop().await;
+        });
+
         // Spawn the reconciliation loop to detect and recover exited containers
         let reconcile_cache = Arc::clone(&arc_self.desired_states_cache);
         let reconciler = tokio::spawn(async move {
@@ -230,6 +307,8 @@ impl NodeAgentManager {
             grpc_processor,
             container_gatherer,
             nodeinfo_task,
+            plugin_task,
+            unit_task,
             reconciler,
             probe_task
         );
@@ -274,6 +353,12 @@ pub async fn reconciliation_loop(desired_states_cache: Arc<Mutex<HashMap<String,
         Arc::new(Mutex::new(HashMap::new()));
 
     loop {
+        // Containers cannot be repaired while the runtime socket is down.
+        if !crate::watchdog::runtime_available() {
+            sleep(Duration::from_secs(1)).await;
+            continue;
+        }
+
         // Clone desired states and release the lock immediately for better concurrency.
         let desired_states = {
             let cache = desired_states_cache.lock().await;
@@ -400,7 +485,8 @@ async fn handle_missing_container(desired: &DesiredState) -> Option<String> {
         return None;
     }
 
-    match crate::runtime::podman::container::start(&desired.pod_yaml).await {
+    let start = WorkloadCommand::Start as i32;
+    match crate::runtime::handle_workload(start, &desired.pod_yaml).await {
         Ok(ids) => {
             let new_id = ids.into_iter().next();
             if let Some(ref id) = new_id {
@@ -509,7 +595,18 @@ async fn handle_exited_container(
     );
 
     let restart_path = format!("/v4.0.0/libpod/containers/{}/restart", desired.container_id);
-    match crate::runtime::podman::post(&restart_path, Body::empty()).await {
+    #[cfg(feature = "mock-runtime")]
+    let restarted = if crate::runtime::mock::enabled() {
+        crate::runtime::mock::restart(&desired.container_id).map(|_| ())
+    } else {
+        crate::runtime::podman::post(&restart_path, Body::empty())
+            .await
+            .map(|_| ())
+            .map_err(Into::into)
+    };
+    #[cfg(not(feature = "mock-runtime"))]
+    let restarted = crate::runtime::podman::post(&restart_path, Body::empty()).await;
+    match restarted {
         Ok(_) => {
             eprintln!(
                 "[Reconciliation] Container '{}' restarted successfully",
@@ -535,6 +632,192 @@ async fn handle_exited_container(
     }
 }
 
+/// Sends container changes of this node to one server
+struct ContainerEventPublisher {
+    target: ContainerEventTarget,
+    /// Open event stream, reopened with a full snapshot after it closes
+    stream: Option<ContainerEventStream>,
+    /// Containers as last reported to the server
+    reported: Vec<ContainerInfo>,
+}
+
+impl ContainerEventPublisher {
+    fn new(target: ContainerEventTarget) -> Self {
+        Self {
+            target,
+            stream: None,
+            reported: Vec::new(),
+        }
+    }
+
+    /// Stats only matter to the monitoring server
+    fn include_stats(&self) -> bool {
+        self.target == ContainerEventTarget::MonitoringServer
+    }
+
+    /// Report the difference between the last reported and the current containers
+    async fn publish(
+        &mut self,
+        sender: &Mutex<NodeAgentSender>,
+        node: &str,
+        current: &[ContainerInfo],
+        oom_killed: &HashSet<String>,
+    ) -> std::result::Result<(), tonic::Status> {
+        if self.stream.as_ref().is_some_and(|s| s.is_closed()) {
+            self.stream = None;
+        }
+        // A new stream would overtake the lists still queued in the outbox
+        if self.target == ContainerEventTarget::StateManager && crate::outbox::pending() {
+            self.stream = None;
+            return self.publish_list(sender, node, current).await;
+        }
+        if self.stream.is_none() {
+            match sender
+                .lock()
+                .await
+                .open_container_event_stream(self.target)
+                .await
+            {
+                Ok(stream) => {
+                    // A new stream starts empty on the server side
+                    self.stream = Some(stream);
+                    self.reported.clear();
+                }
+                Err(_) => return self.publish_list(sender, node, current).await,
+            }
+        }
+
+        let events =
+            diff_container_lists(&self.reported, current, self.include_stats(), oom_killed);
+        if events.is_empty() {
+            return Ok(());
+        }
+        let batch = ContainerEventList {
+            node_name: node.to_string(),
+            events,
+        };
+        if let Some(stream) = &self.stream {
+            if let Err(e) = stream.send(batch).await {
+                self.stream = None;
+                return Err(e);
+            }
+        }
+        self.reported = current.to_vec();
+        Ok(())
+    }
+
+    /// Fallback for servers without `StreamContainerEvents`
+    async fn publish_list(
+        &mut self,
+        sender: &Mutex<NodeAgentSender>,
+        node: &str,
+        current: &[ContainerInfo],
+    ) -> std::result::Result<(), tonic::Status> {
+        let list = ContainerList {
+            node_name: node.to_string(),
+            containers: current.to_vec(),
+        };
+        match self.target {
+            ContainerEventTarget::MonitoringServer => {
+                sender.lock().await.send_container_list(list).await?;
+            }
+            ContainerEventTarget::StateManager => {
+                if containers_equal_except_stats(&self.reported, current) {
+                    return Ok(());
+                }
+                crate::outbox::send(crate::outbox::Message::ChangedContainers(list)).await?;
+            }
+        }
+        self.reported = current.to_vec();
+        Ok(())
+    }
+}
+
+/// Build the events that turn the `previous` container list into `current`
+///
+/// Changed containers in `oom_killed` are reported as `OOM`.
+fn diff_container_lists(
+    previous: &[ContainerInfo],
+    current: &[ContainerInfo],
+    include_stats: bool,
+    oom_killed: &HashSet<String>,
+) -> Vec<ContainerEvent> {
+    let timestamp_ns = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as i64)
+        .unwrap_or_default();
+    let event = |event_type: ContainerEventType, container: &ContainerInfo| ContainerEvent {
+        event_type: event_type as i32,
+        container: Some(container.clone()),
+        timestamp_ns,
+    };
+
+    let mut events = Vec::new();
+    for container in current {
+        match previous.iter().find(|p| p.id == container.id) {
+            None => events.push(event(ContainerEventType::Created, container)),
+            Some(prev) => {
+                let unchanged = if include_stats {
+                    prev == container
+                } else {
+                    containers_equal_except_stats(
+                        std::slice::from_ref(prev),
+                        std::slice::from_ref(container),
+                    )
+                };
+                if unchanged {
+                    continue;
+                }
+                let event_type = if oom_killed.contains(&container.id) {
+                    ContainerEventType::Oom
+                } else {
+                    container_event_type(prev, container)
+                };
+                events.push(event(event_type, container));
+            }
+        }
+    }
+    for prev in previous {
+        if !current.iter().any(|c| c.id == prev.id) {
+            events.push(event(ContainerEventType::Removed, prev));
+        }
+    }
+    events
+}
+
+/// Classify the change of a container between two inspections
+fn container_event_type(prev: &ContainerInfo, current: &ContainerInfo) -> ContainerEventType {
+    let flag = |c: &ContainerInfo, key: &str| c.state.get(key).is_some_and(|v| v == "true");
+
+    if flag(current, "OOMKilled") && !flag(prev, "OOMKilled") {
+        ContainerEventType::Oom
+    } else if flag(prev, "Running") && !flag(current, "Running") {
+        ContainerEventType::Died
+    } else if flag(current, "Running")
+        && (!flag(prev, "Running") || prev.state.get("StartedAt") != current.state.get("StartedAt"))
+    {
+        ContainerEventType::Restarted
+    } else {
+        ContainerEventType::Updated
+    }
+}
+
+/// Ids of the containers killed by the OOM killer according to runtime events
+fn oom_killed_containers(events: &[RuntimeEvent]) -> HashSet<String> {
+    events
+        .iter()
+        .filter(|e| e.Action == "oom")
+        .map(|e| e.Actor.ID.clone())
+        .collect()
+}
+
+fn unix_now_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
 fn containers_equal_except_stats<'a>(a: &'a [ContainerInfo], b: &'a [ContainerInfo]) -> bool {
     if a.len() != b.len() {
         return false;
@@ -546,7 +829,7 @@ fn containers_equal_except_stats<'a>(a: &'a [ContainerInfo], b: &'a [ContainerIn
             && c1.state == c2.state
             && c1.config == c2.config
             && c1.annotation == c2.annotation
-        // do NOT compare c1.stats/c2.stats
+        // do NOT compare c1.stats/c2.stats nor the sampled usage
     })
 }
 
@@ -619,6 +902,7 @@ spec:
             config: HashMap::new(),
             annotation: HashMap::new(),
             stats: HashMap::new(),
+            usage: None,
         };
         let c2 = ContainerInfo {
             id: "id1".to_string(),
@@ -628,6 +912,7 @@ spec:
             config: HashMap::new(),
             annotation: HashMap::new(),
             stats: HashMap::new(),
+            usage: None,
         };
         let c3 = ContainerInfo {
             id: "id2".to_string(),
@@ -637,6 +922,7 @@ spec:
             config: HashMap::new(),
             annotation: HashMap::new(),
             stats: HashMap::new(),
+            usage: None,
         };
 
         // True: stats ignored, all else equal
@@ -656,6 +942,100 @@ spec:
         ));
     }
 
+    fn container_with_state(id: &str, state: &[(&str, &str)]) -> ContainerInfo {
+        ContainerInfo {
+            id: id.to_string(),
+            state: state
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_container_lists_created_removed_and_stats() {
+        use common::monitoringserver::ContainerEventType;
+
+        let a = container_with_state("a", &[("Running", "true")]);
+        let b = container_with_state("b", &[("Running", "true")]);
+        let mut a_stats = a.clone();
+        a_stats
+            .stats
+            .insert("CpuTotalUsage".to_string(), "10".to_string());
+
+        let only_a = vec![a.clone()];
+        let with_stats = vec![a_stats];
+        let none = std::collections::HashSet::new();
+
+        let events = super::diff_container_lists(&[], &only_a, false, &none);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type(), ContainerEventType::Created);
+
+        let events = super::diff_container_lists(&[a, b], &only_a, false, &none);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type(), ContainerEventType::Removed);
+        assert_eq!(events[0].container.as_ref().unwrap().id, "b");
+
+        // Stats only changes are reported when requested
+        assert!(super::diff_container_lists(&only_a, &with_stats, false, &none).is_empty());
+        let events = super::diff_container_lists(&only_a, &with_stats, true, &none);
+        assert_eq!(events[0].event_type(), ContainerEventType::Updated);
+
+        // Runtime OOM events take precedence over the inspected state
+        let oom = std::collections::HashSet::from(["a".to_string()]);
+        let events = super::diff_container_lists(&only_a, &with_stats, true, &oom);
+        assert_eq!(events[0].event_type(), ContainerEventType::Oom);
+    }
+
+    #[test]
+    fn test_oom_killed_containers() {
+        use crate::resource::{EventActor, RuntimeEvent};
+
+        let event = |action: &str, id: &str| RuntimeEvent {
+            Type: "container".to_string(),
+            Action: action.to_string(),
+            Actor: EventActor { ID: id.to_string() },
+            time: 0,
+        };
+        let events = vec![event("oom", "a"), event("die", "a"), event("die", "b")];
+
+        let oom = super::oom_killed_containers(&events);
+        assert_eq!(oom.len(), 1);
+        assert!(oom.contains("a"));
+    }
+
+    #[test]
+    fn test_container_event_type_classification() {
+        use common::monitoringserver::ContainerEventType;
+
+        let running = container_with_state("a", &[("Running", "true"), ("StartedAt", "t1")]);
+        let exited = container_with_state("a", &[("Running", "false"), ("StartedAt", "t1")]);
+        let oom = container_with_state("a", &[("Running", "false"), ("OOMKilled", "true")]);
+        let restarted = container_with_state("a", &[("Running", "true"), ("StartedAt", "t2")]);
+
+        assert_eq!(
+            super::container_event_type(&running, &exited),
+            ContainerEventType::Died
+        );
+        assert_eq!(
+            super::container_event_type(&running, &oom),
+            ContainerEventType::Oom
+        );
+        assert_eq!(
+            super::container_event_type(&exited, &running),
+            ContainerEventType::Restarted
+        );
+        assert_eq!(
+            super::container_event_type(&running, &restarted),
+            ContainerEventType::Restarted
+        );
+        assert_eq!(
+            super::container_event_type(&exited, &exited),
+            ContainerEventType::Updated
+        );
+    }
+
     #[tokio::test]
     async fn test_new_creates_instance_with_correct_hostname() {
         let (_tx, rx) = mpsc::channel(1);
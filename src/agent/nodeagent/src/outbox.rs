@@ -0,0 +1,374 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Persistent queue of messages to the master node
+//!
+//! Registrations and changed container lists that cannot be delivered
+//! because ApiServer or StateManager are unreachable are queued instead of
+//! dropped. Once a message waits, every later one is queued behind it, so
+//! the servers receive them in the order they were sent. The queue is kept
+//! as JSON lines in a file and survives a restart of NodeAgent.
+//!
+//! A flush task delivers the queue whenever the master answers again. When
+//! the queued messages exceed `max_bytes`, the oldest are dropped; messages
+//! the server refuses are dropped too. Both are counted in
+//! `pullpiri_nodeagent_outbox_dropped_total`, served on `/metrics` of the
+//! health endpoint.
+
+use crate::grpc::sender::NodeAgentSender;
+use common::metrics;
+use common::monitoringserver::ContainerList;
+use common::nodeagent::fromapiserver::NodeRegistrationRequest;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tonic::{Code, Status};
+
+pub const OUTBOX_MESSAGES: &str = "pullpiri_nodeagent_outbox_messages";
+pub const OUTBOX_DROPPED_TOTAL: &str = "pullpiri_nodeagent_outbox_dropped_total";
+
+/// Time between two delivery attempts of a waiting queue
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+
+static OUTBOX: OnceLock<Mutex<Outbox>> = OnceLock::new();
+
+/// Message to the master node
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "message")]
+pub enum Message {
+    /// Registration with the API server
+    Registration(NodeRegistrationRequest),
+    /// Changed container list for the state manager
+    ChangedContainers(ContainerList),
+}
+
+impl Message {
+    async fn deliver(&self) -> Result<(), Status> {
+        let mut sender = NodeAgentSender::default();
+        match self {
+            Message::Registration(request) => {
+                let response = sender
+                    .register_with_api_server(request.clone())
+                    .await?
+                    .into_inner();
+                if !response.success {
+                    return Err(Status::failed_precondition(response.message));
+                }
+            }
+            Message::ChangedContainers(list) => {
+                sender.send_changed_container_list(list.clone()).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How a message was handed over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    Sent,
+    /// Queued until the master is reachable again
+    Queued,
+}
+
+/// Whether a failed call may succeed later, once the server is reachable
+fn is_transient(status: &Status) -> bool {
+    matches!(
+        status.code(),
+        Code::Unavailable | Code::DeadlineExceeded | Code::Unknown | Code::Cancelled
+    )
+}
+
+fn record_dropped(reason: &str, count: usize) {
+    for _ in 0..count {
+        metrics::inc_counter(
+            OUTBOX_DROPPED_TOTAL,
+            "Outbound messages dropped from the outbox, by reason",
+            &[("reason", reason)],
+        );
+    }
+}
+
+/// Queue kept in memory and mirrored to a file
+#[derive(Debug)]
+pub struct Outbox {
+    path: PathBuf,
+    max_bytes: u64,
+    /// Messages with the length of their line in the file
+    queue: VecDeque<(Message, u64)>,
+    bytes: u64,
+}
+
+impl Outbox {
+    /// Open the queue kept at `path`, empty if there is no file yet
+    ///
+    /// Lines that cannot be parsed, e.g. one cut short by a crash, are
+    /// skipped.
+    pub fn open(path: impl Into<PathBuf>, max_bytes: u64) -> Self {
+        let mut outbox = Self {
+            path: path.into(),
+            max_bytes,
+            queue: VecDeque::new(),
+            bytes: 0,
+        };
+        if let Ok(contents) = std::fs::read_to_string(&outbox.path) {
+            for line in contents.lines() {
+                if let Ok(message) = serde_json::from_str::<Message>(line) {
+                    outbox.bytes += line.len() as u64 + 1;
+                    outbox.queue.push_back((message, line.len() as u64 + 1));
+                }
+            }
+        }
+        let evicted = outbox.evict();
+        if evicted > 0 {
+            outbox.persist();
+        }
+        outbox
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn front(&self) -> Option<&Message> {
+        self.queue.front().map(|(message, _)| message)
+    }
+
+    /// Queue a message behind the others
+    ///
+    /// ### Returns
+    /// * `usize` - number of old messages dropped to stay within `max_bytes`
+    pub fn push(&mut self, message: Message) -> usize {
+        let line = match serde_json::to_string(&message) {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("[Outbox] Failed to encode message: {}", e);
+                return 0;
+            }
+        };
+        let len = line.len() as u64 + 1;
+        self.queue.push_back((message, len));
+        self.bytes += len;
+        let evicted = self.evict();
+        if evicted > 0 {
+            self.persist();
+        } else if let Err(e) = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| writeln!(file, "{}", line))
+        {
+            eprintln!("[Outbox] Failed to append to {:?}: {}", self.path, e);
+        }
+        evicted
+    }
+
+    /// Remove the first message, once delivered or refused
+    pub fn pop(&mut self) -> Option<Message> {
+        let (message, len) = self.queue.pop_front()?;
+        self.bytes -= len;
+        self.persist();
+        Some(message)
+    }
+
+    /// Drop the oldest messages until the queue fits `max_bytes`, always
+    /// keeping the newest
+    fn evict(&mut self) -> usize {
+        let mut evicted = 0;
+        while self.bytes > self.max_bytes && self.queue.len() > 1 {
+            if let Some((_, len)) = self.queue.pop_front() {
+                self.bytes -= len;
+                evicted += 1;
+            }
+        }
+        evicted
+    }
+
+    /// Write the whole queue, replacing the file at once
+    fn persist(&self) {
+        let tmp = self.path.with_extension("tmp");
+        let result = std::fs::File::create(&tmp)
+            .and_then(|mut file| {
+                for (message, _) in &self.queue {
+                    if let Ok(line) = serde_json::to_string(message) {
+                        writeln!(file, "{}", line)?;
+                    }
+                }
+                file.sync_all()
+            })
+            .and_then(|_| std::fs::rename(&tmp, &self.path));
+        if let Err(e) = result {
+            eprintln!("[Outbox] Failed to write {:?}: {}", self.path, e);
+        }
+    }
+}
+
+fn set_gauge(outbox: &Outbox) {
+    metrics::set_gauge(
+        OUTBOX_MESSAGES,
+        "Outbound messages waiting for the master node",
+        &[],
+        outbox.len() as f64,
+    );
+}
+
+/// Open the queue and start delivering what it holds
+///
+/// Must be called once within a tokio runtime; before, [`send`] delivers
+/// directly without queueing.
+pub fn init(path: &str, max_bytes: u64) {
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let outbox = Outbox::open(path, max_bytes);
+    if !outbox.is_empty() {
+        println!(
+            "[Outbox] {} message(s) from a previous run are waiting",
+            outbox.len()
+        );
+    }
+    set_gauge(&outbox);
+    if OUTBOX.set(Mutex::new(outbox)).is_ok() {
+        tokio::spawn(flush_loop());
+    }
+}
+
+/// Whether messages are waiting for the master node
+pub fn pending() -> bool {
+    OUTBOX
+        .get()
+        .and_then(|outbox| outbox.lock().ok().map(|outbox| !outbox.is_empty()))
+        .unwrap_or(false)
+}
+
+fn enqueue(message: Message) {
+    let Some(outbox) = OUTBOX.get() else {
+        return;
+    };
+    if let Ok(mut outbox) = outbox.lock() {
+        let evicted = outbox.push(message);
+        if evicted > 0 {
+            eprintln!(
+                "[Outbox] Queue exceeds {} bytes, dropped {} old message(s)",
+                outbox.max_bytes, evicted
+            );
+        }
+        record_dropped("evicted", evicted);
+        set_gauge(&outbox);
+    }
+}
+
+/// Send a message to the master node, or queue it while that is unreachable
+///
+/// ### Returns
+/// * `Result<Delivery, Status>` - whether the message was sent or queued,
+///   the error if the server refused it
+pub async fn send(message: Message) -> Result<Delivery, Status> {
+    if OUTBOX.get().is_none() {
+        return message.deliver().await.map(|_| Delivery::Sent);
+    }
+    if pending() {
+        enqueue(message);
+        return Ok(Delivery::Queued);
+    }
+    match message.deliver().await {
+        Ok(()) => Ok(Delivery::Sent),
+        Err(status) if is_transient(&status) => {
+            enqueue(message);
+            Ok(Delivery::Queued)
+        }
+        Err(status) => Err(status),
+    }
+}
+
+/// Deliver the queue in order while the master node answers
+async fn flush_loop() {
+    let Some(outbox) = OUTBOX.get() else {
+        return;
+    };
+    loop {
+        tokio::time::sleep(FLUSH_INTERVAL).await;
+        while let Some(message) = outbox.lock().ok().and_then(|o| o.front().cloned()) {
+            match message.deliver().await {
+                Ok(()) => {
+                    if let Message::Registration(_) = message {
+                        common::health::set_ready(crate::CHECK_REGISTRATION);
+                    }
+                }
+                Err(status) if is_transient(&status) => break,
+                Err(status) => {
+                    eprintln!(
+                        "[Outbox] Dropping message refused by the server: {}",
+                        status
+                    );
+                    record_dropped("rejected", 1);
+                }
+            }
+            if let Ok(mut outbox) = outbox.lock() {
+                // Unless evicted meanwhile to make room
+                if outbox.front() == Some(&message) {
+                    outbox.pop();
+                }
+                set_gauge(&outbox);
+                if outbox.is_empty() {
+                    println!("[Outbox] All queued messages delivered");
+                }
+            }
+        }
+    }
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(node: &str) -> Message {
+        Message::ChangedContainers(ContainerList {
+            node_name: node.to_string(),
+            containers: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn test_outbox_persists_in_order_and_evicts_oldest() {
+        let path = std::env::temp_dir().join(format!("outbox-test-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut outbox = Outbox::open(&path, 1024);
+        assert!(outbox.is_empty());
+        outbox.push(list("n1"));
+        outbox.push(Message::Registration(NodeRegistrationRequest {
+            node_id: "n1".to_string(),
+            ..Default::default()
+        }));
+        outbox.push(list("n2"));
+
+        // A restart finds the queue in the same order
+        let mut reopened = Outbox::open(&path, 1024);
+        assert_eq!(reopened.len(), 3);
+        assert_eq!(reopened.pop(), Some(list("n1")));
+        assert!(matches!(reopened.front(), Some(Message::Registration(_))));
+        assert_eq!(Outbox::open(&path, 1024).len(), 2);
+
+        // Dropping the long registration line makes room for the lists
+        let mut small = Outbox::open(&path, 160);
+        assert_eq!(small.len(), 1);
+        assert_eq!(small.front(), Some(&list("n2")));
+        assert_eq!(small.push(list("n3")), 0);
+        assert_eq!(small.push(list("n4")), 1);
+        assert_eq!(small.front(), Some(&list("n3")));
+        assert_eq!(Outbox::open(&path, 160).len(), 2);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -3,12 +3,13 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-//! Liveness probe executor for NodeAgent.
+//! Liveness and readiness probe executor for NodeAgent.
 //!
-//! This module provides `check_liveness_probe`, which dispatches to the appropriate
-//! probe checker (HTTP, TCP, or Exec) based on the probe type configured in `LivenessProbe`.
+//! This module provides `check_liveness_probe` and `check_readiness_probe`, which
+//! dispatch to the appropriate probe checker (HTTP, TCP, or Exec) based on the probe
+//! type configured in `LivenessProbe`.
 
-use crate::desired_state::{LivenessProbe, ProbeType};
+use crate::desired_state::{LivenessProbe, ProbeType, ReadinessProbe};
 
 /// Execute a liveness probe for the given container and return whether it succeeded.
 ///
@@ -17,25 +18,36 @@ use crate::desired_state::{LivenessProbe, ProbeType};
 /// - `ProbeType::Tcp`  → TCP connection attempt to container's target IP
 /// - `ProbeType::Exec` → `podman exec <container_id> <command>`
 pub async fn check_liveness_probe(container_id: &str, probe: &LivenessProbe) -> bool {
+    check_probe("liveness", container_id, probe).await
+}
+
+/// Execute a readiness probe for the given container and return whether it succeeded.
+///
+/// Readiness probes use the same checkers as liveness probes.
+pub async fn check_readiness_probe(container_id: &str, probe: &ReadinessProbe) -> bool {
+    check_probe("readiness", container_id, probe).await
+}
+
+async fn check_probe(kind: &str, container_id: &str, probe: &LivenessProbe) -> bool {
     match &probe.probe_type {
         ProbeType::Http { path, port } => {
             println!(
-                "[Probe] Checking liveness probe for container {}: HTTP GET {} on port {}",
-                container_id, path, port
+                "[Probe] Checking {} probe for container {}: HTTP GET {} on port {}",
+                kind, container_id, path, port
             );
             super::checker::check_http(container_id, path, *port, probe.timeout_seconds).await
         }
         ProbeType::Tcp { port } => {
             println!(
-                "[Probe] Checking liveness probe for container {}: TCP on port {}",
-                container_id, port
+                "[Probe] Checking {} probe for container {}: TCP on port {}",
+                kind, container_id, port
             );
             super::checker::check_tcp(container_id, *port, probe.timeout_seconds).await
         }
         ProbeType::Exec { command } => {
             println!(
-                "[Probe] Checking liveness probe for container {}: Exec {:?}",
-                container_id, command
+                "[Probe] Checking {} probe for container {}: Exec {:?}",
+                kind, container_id, command
             );
             super::checker::check_exec(container_id, command, probe.timeout_seconds).await
         }
@@ -90,4 +102,17 @@ mod tests {
         let result = check_liveness_probe("nonexistent-container-xyz", &probe).await;
         assert!(!result);
     }
+
+    #[tokio::test]
+    async fn test_check_readiness_probe_tcp_closed_port_returns_false() {
+        let probe = ReadinessProbe {
+            probe_type: ProbeType::Tcp { port: 19996 },
+            initial_delay_seconds: 0,
+            period_seconds: 10,
+            timeout_seconds: 1,
+            failure_threshold: 3,
+        };
+        let result = check_readiness_probe("test-container", &probe).await;
+        assert!(!result);
+    }
 }
@@ -3,23 +3,41 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-//! Liveness probe module for NodeAgent.
+//! Liveness and readiness probe module for NodeAgent.
 //!
 //! This module implements the `probe_loop` function that continuously monitors
-//! running containers and applies liveness probes based on their `DesiredState`
-//! configuration. When a container fails its liveness probe `failure_threshold`
-//! consecutive times, it is stopped via the Podman API.
+//! running containers and applies liveness and readiness probes based on their
+//! `DesiredState` configuration. When a container fails its liveness probe
+//! `failure_threshold` consecutive times, it is stopped via the Podman API.
+//! A failing readiness probe only marks the container as not ready.
+//!
+//! The latest results are kept per container and added to the `state` map of
+//! the reported containers (see `add_probe_results`), so StateManager can hold
+//! a model back from Running until its containers are ready.
 
 pub mod checker;
 pub mod liveness;
 
-use crate::desired_state::DesiredState;
+use crate::desired_state::{DesiredState, LivenessProbe};
+use common::spec::k8s::pod::{ProbeResult, LIVENESS_STATE_KEY, READINESS_STATE_KEY};
+use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::SystemTime;
 use tokio::sync::Mutex;
 use tokio::time::{sleep, Duration};
 
+/// Latest probe results of the running containers, keyed by container ID.
+static PROBE_RESULTS: Lazy<std::sync::Mutex<HashMap<String, ProbeResults>>> =
+    Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
+
+/// Probe results of one container, `None` for probes that are not configured.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ProbeResults {
+    pub liveness: Option<ProbeResult>,
+    pub readiness: Option<ProbeResult>,
+}
+
 /// Tracks per-container probe timing state.
 struct ProbeState {
     /// When this probe state was first created (used as a proxy for container start time
@@ -27,20 +45,111 @@ struct ProbeState {
     first_seen_at: SystemTime,
     /// The last time a liveness probe was executed for this container.
     last_probe_at: Option<SystemTime>,
+    /// The last time a readiness probe was executed for this container.
+    last_readiness_probe_at: Option<SystemTime>,
+    /// Consecutive readiness probe failures.
+    readiness_failures: u8,
+    results: ProbeResults,
+}
+
+impl ProbeState {
+    fn new(now: SystemTime) -> Self {
+        Self {
+            first_seen_at: now,
+            last_probe_at: None,
+            last_readiness_probe_at: None,
+            readiness_failures: 0,
+            results: ProbeResults::default(),
+        }
+    }
+
+    /// Whether a probe is due, given when it was last executed.
+    fn is_due(&self, probe: &LivenessProbe, last: Option<SystemTime>, now: SystemTime) -> bool {
+        let elapsed_since_start = now.duration_since(self.first_seen_at).unwrap_or_default();
+        if elapsed_since_start.as_secs() < probe.initial_delay_seconds as u64 {
+            return false;
+        }
+        match last {
+            Some(last) => {
+                now.duration_since(last).unwrap_or_default().as_secs()
+                    >= probe.period_seconds as u64
+            }
+            None => true,
+        }
+    }
 }
 
-/// Main liveness probe loop.
+/// Readiness after a probe: ready on the first success, not ready until one
+/// succeeds and again after `failure_threshold` consecutive failures.
+fn next_readiness(
+    current: Option<ProbeResult>,
+    failures: u8,
+    success: bool,
+    failure_threshold: u8,
+) -> ProbeResult {
+    if success || (current == Some(ProbeResult::Success) && failures < failure_threshold) {
+        ProbeResult::Success
+    } else {
+        ProbeResult::Failure
+    }
+}
+
+/// Latest probe results of a container.
+pub fn probe_results(container_id: &str) -> ProbeResults {
+    PROBE_RESULTS
+        .lock()
+        .ok()
+        .and_then(|results| results.get(container_id).copied())
+        .unwrap_or_default()
+}
+
+/// Add the probe results of a container to its reported `state` map.
+///
+/// Nothing is added for probes that are not configured.
+pub fn add_probe_results(container_id: &str, state: &mut HashMap<String, String>) {
+    let results = probe_results(container_id);
+    if let Some(liveness) = results.liveness {
+        state.insert(
+            LIVENESS_STATE_KEY.to_string(),
+            liveness.as_str().to_string(),
+        );
+    }
+    if let Some(readiness) = results.readiness {
+        state.insert(
+            READINESS_STATE_KEY.to_string(),
+            readiness.as_str().to_string(),
+        );
+    }
+}
+
+fn publish_results(probe_states: &HashMap<String, ProbeState>) {
+    if let Ok(mut results) = PROBE_RESULTS.lock() {
+        *results = probe_states
+            .iter()
+            .map(|(id, state)| (id.clone(), state.results))
+            .collect();
+    }
+}
+
+/// Main probe loop.
 ///
 /// Runs every second. For each running container that has a `probe_config` in the
-/// `desired_states_cache`, the loop:
+/// `desired_states_cache`, the loop handles each configured probe as follows:
 /// 1. Checks whether `initial_delay_seconds` has elapsed since the container was first seen.
 /// 2. Checks whether `period_seconds` has elapsed since the last probe.
-/// 3. Executes the liveness probe.
+/// 3. Executes the probe.
 /// 4. Increments the failure counter on failure, resets it on success.
-/// 5. Stops the container via Podman if `failure_threshold` consecutive failures occur.
+/// 5. Liveness: stops the container via Podman if `failure_threshold` consecutive failures occur.
+///    Readiness: marks the container as not ready instead.
 pub async fn probe_loop(desired_states_cache: Arc<Mutex<HashMap<String, DesiredState>>>) {
     use crate::resource::container::get_list;
 
+    if crate::runtime::mock_enabled() {
+        // Containers of the mock runtime have no process or network to probe
+        println!("[Probe] Probes are not run with the mock runtime");
+        return;
+    }
+
     let mut failure_counts: HashMap<String, u8> = HashMap::new();
     let mut probe_states: HashMap<String, ProbeState> = HashMap::new();
 
@@ -84,39 +193,63 @@ pub async fn probe_loop(desired_states_cache: Arc<Mutex<HashMap<String, DesiredS
                 None => continue, // No desired state → no probe configured
             };
 
+            let probe_config = match desired.probe_config.as_ref() {
+                Some(pc) if pc.liveness.is_some() || pc.readiness.is_some() => pc,
+                _ => continue, // No probe configured → skip
+            };
+
+            // Get or create the probe state for this container.
+            let probe_state = probe_states
+                .entry(container_id.clone())
+                .or_insert_with(|| ProbeState::new(now));
+
+            if let Some(readiness_probe) = &probe_config.readiness {
+                let readiness = probe_state.results.readiness;
+                if readiness.is_none() {
+                    probe_state.results.readiness = Some(ProbeResult::Pending);
+                }
+                if probe_state.is_due(readiness_probe, probe_state.last_readiness_probe_at, now) {
+                    let success =
+                        liveness::check_readiness_probe(container_id, readiness_probe).await;
+                    probe_state.last_readiness_probe_at = Some(now);
+                    probe_state.readiness_failures = if success {
+                        0
+                    } else {
+                        probe_state.readiness_failures.saturating_add(1)
+                    };
+                    let next = next_readiness(
+                        readiness,
+                        probe_state.readiness_failures,
+                        success,
+                        readiness_probe.failure_threshold,
+                    );
+                    if Some(next) != readiness {
+                        println!(
+                            "[Probe] Container {} is {}",
+                            container_id,
+                            if next == ProbeResult::Success {
+                                "ready"
+                            } else {
+                                "not ready"
+                            }
+                        );
+                    }
+                    probe_state.results.readiness = Some(next);
+                }
+            }
+
             // Check whether the desired state includes a liveness probe config.
-            let liveness_probe = match desired
-                .probe_config
-                .as_ref()
-                .and_then(|pc| pc.liveness.as_ref())
-            {
+            let liveness_probe = match probe_config.liveness.as_ref() {
                 Some(lp) => lp,
                 None => continue, // No liveness probe configured → skip
             };
-
-            // Get or create the probe state for this container.
-            let probe_state =
-                probe_states
-                    .entry(container_id.clone())
-                    .or_insert_with(|| ProbeState {
-                        first_seen_at: now,
-                        last_probe_at: None,
-                    });
-
-            // Check initial_delay_seconds: skip probe until delay has elapsed.
-            let elapsed_since_start = now
-                .duration_since(probe_state.first_seen_at)
-                .unwrap_or_default();
-            if elapsed_since_start.as_secs() < liveness_probe.initial_delay_seconds as u64 {
-                continue;
+            if probe_state.results.liveness.is_none() {
+                probe_state.results.liveness = Some(ProbeResult::Pending);
             }
 
-            // Check period_seconds: skip probe if not enough time has passed since last probe.
-            if let Some(last_probe_at) = probe_state.last_probe_at {
-                let elapsed_since_last = now.duration_since(last_probe_at).unwrap_or_default();
-                if elapsed_since_last.as_secs() < liveness_probe.period_seconds as u64 {
-                    continue;
-                }
+            // Skip until initial_delay_seconds and period_seconds have elapsed.
+            if !probe_state.is_due(liveness_probe, probe_state.last_probe_at, now) {
+                continue;
             }
 
             // Execute the liveness probe.
@@ -136,6 +269,7 @@ pub async fn probe_loop(desired_states_cache: Arc<Mutex<HashMap<String, DesiredS
                     );
                 }
                 failure_counts.insert(container_id.clone(), 0);
+                probe_state.results.liveness = Some(ProbeResult::Success);
             } else {
                 let count = failure_counts.entry(container_id.clone()).or_insert(0);
                 *count += 1;
@@ -156,6 +290,7 @@ pub async fn probe_loop(desired_states_cache: Arc<Mutex<HashMap<String, DesiredS
             }
         }
 
+        publish_results(&probe_states);
         sleep(Duration::from_secs(1)).await;
     }
 }
@@ -190,11 +325,10 @@ mod tests {
     #[test]
     fn test_probe_state_initializes_correctly() {
         let now = SystemTime::now();
-        let state = ProbeState {
-            first_seen_at: now,
-            last_probe_at: None,
-        };
+        let state = ProbeState::new(now);
         assert!(state.last_probe_at.is_none());
+        assert!(state.last_readiness_probe_at.is_none());
+        assert_eq!(state.results, ProbeResults::default());
         assert!(state
             .first_seen_at
             .duration_since(std::time::UNIX_EPOCH)
@@ -266,6 +400,7 @@ mod tests {
                 timeout_seconds: 3,
                 failure_threshold: 3,
             }),
+            readiness: None,
         });
 
         let probe_config = state.probe_config.as_ref().unwrap();
@@ -275,6 +410,67 @@ mod tests {
         assert_eq!(liveness.failure_threshold, 3);
     }
 
+    #[test]
+    fn test_next_readiness() {
+        // Not ready until the first success
+        assert_eq!(
+            next_readiness(Some(ProbeResult::Pending), 1, false, 3),
+            ProbeResult::Failure
+        );
+        assert_eq!(
+            next_readiness(Some(ProbeResult::Failure), 0, true, 3),
+            ProbeResult::Success
+        );
+        // Ready until failure_threshold consecutive failures
+        assert_eq!(
+            next_readiness(Some(ProbeResult::Success), 2, false, 3),
+            ProbeResult::Success
+        );
+        assert_eq!(
+            next_readiness(Some(ProbeResult::Success), 3, false, 3),
+            ProbeResult::Failure
+        );
+    }
+
+    #[test]
+    fn test_probe_state_is_due() {
+        let now = SystemTime::now();
+        let probe = LivenessProbe {
+            probe_type: ProbeType::Tcp { port: 8080 },
+            initial_delay_seconds: 5,
+            period_seconds: 10,
+            timeout_seconds: 1,
+            failure_threshold: 3,
+        };
+        let mut state = ProbeState::new(now - Duration::from_secs(3));
+        assert!(!state.is_due(&probe, None, now));
+
+        state.first_seen_at = now - Duration::from_secs(30);
+        assert!(state.is_due(&probe, None, now));
+        assert!(!state.is_due(&probe, Some(now - Duration::from_secs(5)), now));
+        assert!(state.is_due(&probe, Some(now - Duration::from_secs(10)), now));
+    }
+
+    #[test]
+    fn test_add_probe_results() {
+        PROBE_RESULTS.lock().unwrap().insert(
+            "probed-container".to_string(),
+            ProbeResults {
+                liveness: None,
+                readiness: Some(ProbeResult::Pending),
+            },
+        );
+
+        let mut state = HashMap::new();
+        add_probe_results("probed-container", &mut state);
+        assert_eq!(state.get(READINESS_STATE_KEY).unwrap(), "pending");
+        assert!(!state.contains_key(LIVENESS_STATE_KEY));
+
+        let mut state = HashMap::new();
+        add_probe_results("unknown-container", &mut state);
+        assert!(state.is_empty());
+    }
+
     #[test]
     fn test_initial_delay_check() {
         let start = SystemTime::now()
@@ -2,8 +2,8 @@
 * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
 * SPDX-License-Identifier: Apache-2.0
 */
-use super::{Container, ContainerError, ContainerInspect, ContainerStats};
-use crate::runtime::podman::get;
+use super::{Container, ContainerError, ContainerInspect, ContainerStats, RuntimeEvent};
+use crate::runtime::container_runtime;
 use common::monitoringserver::ContainerInfo;
 use futures::future::try_join_all;
 use std::collections::HashMap;
@@ -17,51 +17,14 @@ pub async fn inspect(hostname: String) -> std::result::Result<Vec<ContainerInfo>
         let host_name = hostname.clone();
         async move {
             let inspect = get_inspect(&id).await?;
+            // Usage comes from the sampler, the map only tells why it is missing
             let mut stats_map = HashMap::new();
-            if inspect.State.Status == "running" {
-                match get_stats(&id).await {
-                    Ok(stats) => {
-                        stats_map.insert(
-                            "CpuTotalUsage".to_string(),
-                            stats.cpu_stats.cpu_usage.total_usage.to_string(),
-                        );
-                        stats_map.insert(
-                            "CpuUsageInKernelMode".to_string(),
-                            stats.cpu_stats.cpu_usage.usage_in_kernelmode.to_string(),
-                        );
-                        stats_map.insert(
-                            "CpuUsageInUserMode".to_string(),
-                            stats.cpu_stats.cpu_usage.usage_in_usermode.to_string(),
-                        );
-                        stats_map.insert(
-                            "MemoryUsage".to_string(),
-                            stats.memory_stats.usage.to_string(),
-                        );
-                        stats_map.insert(
-                            "MemoryLimit".to_string(),
-                            stats.memory_stats.limit.to_string(),
-                        );
-                        stats_map.insert(
-                            "Networks".to_string(),
-                            stats
-                                .networks
-                                .as_ref()
-                                .map(|nets| {
-                                    nets.iter()
-                                        .map(|(name, net)| format!("{}: {{{}}}", name, net))
-                                        .collect::<Vec<_>>()
-                                        .join(", ")
-                                })
-                                .unwrap_or_else(|| "None".to_string()),
-                        );
-                    }
-                    Err(e) => {
-                        println!("Failed to get stats for {}: {:?}", id, e);
-                        stats_map.insert("Status".to_string(), "StatsUnavailable".to_string());
-                    }
-                }
+            let usage = if inspect.State.Status == "running" {
+                super::sampler::usage(&id).await
             } else {
-                println!("Container {} is not running, stats unavailable.", id);
+                None
+            };
+            if usage.is_none() {
                 stats_map.insert("Status".to_string(), "StatsUnavailable".to_string());
             }
             let mut state_map = HashMap::new();
@@ -79,6 +42,7 @@ pub async fn inspect(hostname: String) -> std::result::Result<Vec<ContainerInfo>
             state_map.insert("Error".to_string(), inspect.State.Error);
             state_map.insert("StartedAt".to_string(), inspect.State.StartedAt);
             state_map.insert("FinishedAt".to_string(), inspect.State.FinishedAt);
+            crate::probe::add_probe_results(&id, &mut state_map);
 
             let mut config_map = HashMap::new();
             config_map.insert("Hostname".to_string(), host_name);
@@ -134,6 +98,7 @@ pub async fn inspect(hostname: String) -> std::result::Result<Vec<ContainerInfo>
                 config: config_map,
                 annotation: annotation_map,
                 stats: stats_map,
+                usage,
             })
         }
     }))
@@ -146,35 +111,34 @@ pub async fn inspect(hostname: String) -> std::result::Result<Vec<ContainerInfo>
 }
 
 pub async fn get_list() -> Result<Vec<Container>> {
-    let body = get("/v4.0.0/libpod/containers/json?all=true").await?;
-
-    let containers: Vec<Container> = serde_json::from_slice(&body)?;
-    //println!("get list {:#?}", containers);
-    Ok(containers)
+    container_runtime().list().await
 }
 
 pub async fn get_inspect(
     id: &str,
 ) -> std::result::Result<ContainerInspect, Box<dyn std::error::Error + Send + Sync>> {
-    let path = &format!("/v4.0.0/libpod/containers/{}/json?all=true", id);
-    let body = get(path).await?;
-
-    let inspect: ContainerInspect = serde_json::from_slice(&body)?;
-    //println!("inspect in container.rs{:#?}", inspect);
-
-    Ok(inspect)
+    container_runtime().inspect(id).await
 }
 
 pub async fn get_stats(
     id: &str,
 ) -> std::result::Result<ContainerStats, Box<dyn std::error::Error + Send + Sync>> {
-    let path = &format!("/v4.0.0/libpod/containers/{}/stats?stream=false", id);
-    let body = get(path).await?;
+    container_runtime().stats(id).await
+}
+
+/// Container events of the runtime since `since` (unix seconds)
+pub async fn get_events(since: i64) -> Result<Vec<RuntimeEvent>> {
+    container_runtime().events(since).await
+}
 
-    let stats: ContainerStats = serde_json::from_slice(&body)?;
-    //println!("{:#?}", stats);
+/// Last `tail` lines of the output of a container, all lines if 0
+pub async fn get_logs(id: &str, tail: u32) -> Result<String> {
+    container_runtime().logs(id, tail).await
+}
 
-    Ok(stats)
+/// Log stream of a container from its last `tail` lines, see [`crate::runtime::LogDemuxer`]
+pub async fn follow_logs(id: &str, tail: u32) -> Result<hyper::Body> {
+    container_runtime().follow_logs(id, tail).await
 }
 
 //Unit Test Cases
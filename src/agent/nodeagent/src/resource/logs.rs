@@ -0,0 +1,220 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Collection of the container logs
+//!
+//! Every running container is followed from its last `log_collection.tail`
+//! lines until it stops. The lines of all containers are sent to
+//! MonitoringServer on one `StreamContainerLogs` stream, by batches of at
+//! most `log_collection.batch_lines` lines or every `log_collection.flush_ms`.
+//! Lines written while MonitoringServer is unreachable are dropped, the last
+//! lines of a container can still be read from NodeAgent by ApiServer.
+
+use super::container::{follow_logs, get_list};
+use crate::config::Config;
+use crate::grpc::sender::{ContainerLogStream, NodeAgentSender};
+use crate::runtime::LogDemuxer;
+use common::monitoringserver::{ContainerLogBatch, ContainerLogLine};
+use hyper::body::HttpBody;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{interval, MissedTickBehavior};
+
+/// Time between two checks of the running containers
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+/// Lines buffered from the followers before they wait for the shipper
+const LINE_BUFFER: usize = 1024;
+
+/// Lines waiting to be sent, split into batches
+#[derive(Debug, Default)]
+struct Pending {
+    lines: Vec<ContainerLogLine>,
+}
+
+impl Pending {
+    fn push(&mut self, line: ContainerLogLine) {
+        self.lines.push(line);
+    }
+
+    fn is_full(&self, batch_lines: usize) -> bool {
+        self.lines.len() >= batch_lines
+    }
+
+    /// Batches of at most `batch_lines` lines, emptying the pending lines
+    fn take(&mut self, node_name: &str, batch_lines: usize) -> Vec<ContainerLogBatch> {
+        std::mem::take(&mut self.lines)
+            .chunks(batch_lines.max(1))
+            .map(|lines| ContainerLogBatch {
+                node_name: node_name.to_string(),
+                lines: lines.to_vec(),
+            })
+            .collect()
+    }
+}
+
+/// Follow a container and forward its lines until its log stream ends
+async fn follow(id: String, name: String, tail: u32, tx: mpsc::Sender<ContainerLogLine>) {
+    let mut body = match follow_logs(&id, tail).await {
+        Ok(body) => body,
+        Err(e) => {
+            eprintln!("[NodeAgent] Failed to follow the logs of {}: {}", name, e);
+            return;
+        }
+    };
+    let mut demuxer = LogDemuxer::default();
+    while let Some(chunk) = body.data().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                eprintln!("[NodeAgent] Log stream of {} failed: {}", name, e);
+                return;
+            }
+        };
+        let timestamp_ns = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as i64;
+        for line in demuxer.push(&chunk) {
+            let line = ContainerLogLine {
+                container_id: id.clone(),
+                container_name: name.clone(),
+                line,
+                timestamp_ns,
+            };
+            if tx.send(line).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Start following the running containers that are not followed yet
+async fn refresh(
+    followers: &mut HashMap<String, JoinHandle<()>>,
+    tail: u32,
+    tx: &mpsc::Sender<ContainerLogLine>,
+) {
+    followers.retain(|_, follower| !follower.is_finished());
+    if !crate::watchdog::runtime_available() {
+        return;
+    }
+    let list = match get_list().await {
+        Ok(list) => list,
+        Err(e) => {
+            eprintln!("[NodeAgent] Failed to list containers to follow: {}", e);
+            return;
+        }
+    };
+    for container in list {
+        if container.State != "running" || followers.contains_key(&container.Id) {
+            continue;
+        }
+        let name = container
+            .Names
+            .first()
+            .map(|name| name.trim_start_matches('/').to_string())
+            .unwrap_or_default();
+        let follower = tokio::spawn(follow(container.Id.clone(), name, tail, tx.clone()));
+        followers.insert(container.Id, follower);
+    }
+}
+
+/// Send the pending lines, reopening the stream if it is closed
+async fn flush(stream: &mut Option<ContainerLogStream>, pending: &mut Pending, config: &Config) {
+    let settings = &config.nodeagent.log_collection;
+    let batches = pending.take(&config.nodeagent.node_name, settings.batch_lines);
+    if batches.is_empty() {
+        return;
+    }
+    if stream.as_ref().is_none_or(ContainerLogStream::is_closed) {
+        *stream = match NodeAgentSender::default().open_container_log_stream().await {
+            Ok(opened) => Some(opened),
+            Err(e) => {
+                eprintln!("[NodeAgent] Failed to open the container log stream: {}", e);
+                return;
+            }
+        };
+    }
+    let Some(opened) = stream.as_ref() else {
+        return;
+    };
+    for batch in batches {
+        if let Err(e) = opened.send(batch).await {
+            eprintln!("[NodeAgent] Failed to send container logs: {}", e);
+            *stream = None;
+            return;
+        }
+    }
+}
+
+/// Follow the running containers and ship their lines to MonitoringServer
+pub async fn run() {
+    let (tx, mut rx) = mpsc::channel(LINE_BUFFER);
+    let mut followers: HashMap<String, JoinHandle<()>> = HashMap::new();
+    let mut stream: Option<ContainerLogStream> = None;
+    let mut pending = Pending::default();
+
+    let mut refresh_tick = interval(REFRESH_INTERVAL);
+    refresh_tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut flush_tick = interval(Duration::from_millis(
+        Config::get().nodeagent.log_collection.flush_ms.max(100),
+    ));
+    flush_tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        let config = Config::get();
+        let settings = &config.nodeagent.log_collection;
+        tokio::select! {
+            _ = refresh_tick.tick() => {
+                if settings.enabled {
+                    refresh(&mut followers, settings.tail, &tx).await;
+                } else {
+                    followers.drain().for_each(|(_, follower)| follower.abort());
+                    stream = None;
+                }
+            }
+            _ = flush_tick.tick() => flush(&mut stream, &mut pending, &config).await,
+            Some(line) = rx.recv() => {
+                pending.push(line);
+                if pending.is_full(settings.batch_lines) {
+                    flush(&mut stream, &mut pending, &config).await;
+                }
+            }
+        }
+    }
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(text: &str) -> ContainerLogLine {
+        ContainerLogLine {
+            container_id: "abc".to_string(),
+            container_name: "web".to_string(),
+            line: text.to_string(),
+            timestamp_ns: 0,
+        }
+    }
+
+    #[test]
+    fn test_pending_lines_are_split_into_batches() {
+        let mut pending = Pending::default();
+        for text in ["1", "2", "3"] {
+            pending.push(line(text));
+        }
+        assert!(pending.is_full(3));
+        assert!(!pending.is_full(4));
+
+        let batches = pending.take("hpc", 2);
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].node_name, "hpc");
+        assert_eq!(batches[0].lines.len(), 2);
+        assert_eq!(batches[1].lines[0].line, "3");
+        assert!(pending.take("hpc", 2).is_empty());
+    }
+}
@@ -3,7 +3,10 @@
 * SPDX-License-Identifier: Apache-2.0
 */
 pub mod container;
+pub mod logs;
 pub mod nodeinfo;
+pub mod plugin;
+pub mod sampler;
 
 use serde::Deserialize;
 use std::collections::HashMap;
@@ -48,6 +51,22 @@ pub enum ContainerError {
     Env(#[from] std::env::VarError),
 }
 
+#[derive(Error, Debug)]
+pub enum PluginError {
+    #[error("Plugin I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Plugin output error: {0}")]
+    Output(#[from] serde_json::Error),
+    #[error("Plugin exited with {0}")]
+    Exit(std::process::ExitStatus),
+    #[error("Plugin timed out after {0} s")]
+    Timeout(u64),
+    #[error("Plugin library error: {0}")]
+    Library(String),
+    #[error("Plugin config error: {0}")]
+    Config(String),
+}
+
 #[allow(non_snake_case, unused)]
 #[derive(Deserialize, Debug)]
 pub struct Container {
@@ -167,6 +186,25 @@ pub struct ContainerNetworkStats {
     pub tx_dropped: u64,
 }
 
+/// Event reported by the events API of the container runtime
+#[allow(non_snake_case, unused)]
+#[derive(Deserialize, Debug, Clone)]
+pub struct RuntimeEvent {
+    #[serde(default)]
+    pub Type: String,
+    #[serde(default)]
+    pub Action: String,
+    pub Actor: EventActor,
+    #[serde(default)]
+    pub time: i64,
+}
+
+#[allow(non_snake_case, unused)]
+#[derive(Deserialize, Debug, Clone)]
+pub struct EventActor {
+    pub ID: String,
+}
+
 use std::fmt;
 
 impl fmt::Display for ContainerNetworkStats {
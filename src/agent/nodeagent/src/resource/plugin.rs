@@ -0,0 +1,367 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Collector plugins for node specific metrics
+//!
+//! ECUs expose different health data, e.g. CAN bus statistics or GPU
+//! temperatures. Such metrics are read by the plugins of the `plugins`
+//! section of the NodeAgent config and sent to the MonitoringServer as
+//! `NodeMetrics`. A plugin is either
+//! * a `command`, run for every collection, printing the metrics on stdout, or
+//! * a shared object (`library`) exporting
+//!   `char *pullpiri_collect_metrics(void)`, returning the same JSON, and
+//!   optionally `void pullpiri_free_metrics(char *)` to release it.
+//!
+//! The metrics are a JSON list, or an object with a `metrics` list:
+//!
+//! ```json
+//! {"metrics": [{"name": "can_rx_errors", "value": 3, "unit": "count", "labels": {"bus": "can0"}}]}
+//! ```
+use super::PluginError;
+use crate::config::PluginConfig;
+use common::monitoringserver::NodeMetric;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const COLLECT_SYMBOL: &CStr = c"pullpiri_collect_metrics";
+const FREE_SYMBOL: &CStr = c"pullpiri_free_metrics";
+
+/// Metric as reported by a plugin
+#[derive(Deserialize, Debug)]
+struct PluginMetric {
+    name: String,
+    value: f64,
+    #[serde(default)]
+    unit: String,
+    #[serde(default)]
+    labels: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum PluginOutput {
+    List(Vec<PluginMetric>),
+    Object { metrics: Vec<PluginMetric> },
+}
+
+/// Parse the JSON output of the plugin `collector`
+pub fn parse_metrics(collector: &str, output: &str) -> Result<Vec<NodeMetric>, PluginError> {
+    let metrics = match serde_json::from_str(output)? {
+        PluginOutput::List(metrics) => metrics,
+        PluginOutput::Object { metrics } => metrics,
+    };
+    Ok(metrics
+        .into_iter()
+        .map(|metric| NodeMetric {
+            name: metric.name,
+            value: metric.value,
+            unit: metric.unit,
+            labels: metric.labels,
+            collector: collector.to_string(),
+        })
+        .collect())
+}
+
+type CollectFn = unsafe extern "C" fn() -> *mut c_char;
+type FreeFn = unsafe extern "C" fn(*mut c_char);
+
+/// Shared object of a plugin, open until dropped
+struct Library {
+    handle: *mut c_void,
+    collect: CollectFn,
+    free: Option<FreeFn>,
+    /// Serializes the calls, plugins need not be thread safe
+    lock: Mutex<()>,
+}
+
+// The handle is only used to close the library, calls are serialized by `lock`
+unsafe impl Send for Library {}
+unsafe impl Sync for Library {}
+
+impl Library {
+    fn open(path: &str) -> Result<Self, PluginError> {
+        let c_path = CString::new(path).map_err(|e| PluginError::Library(e.to_string()))?;
+        // SAFETY: c_path is a valid NUL-terminated string
+        let handle = unsafe { libc::dlopen(c_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        if handle.is_null() {
+            return Err(PluginError::Library(dl_error(path)));
+        }
+
+        // SAFETY: handle is open, the symbols have the documented signatures
+        let collect = unsafe { libc::dlsym(handle, COLLECT_SYMBOL.as_ptr()) };
+        if collect.is_null() {
+            unsafe { libc::dlclose(handle) };
+            return Err(PluginError::Library(format!(
+                "{} does not export {}",
+                path,
+                COLLECT_SYMBOL.to_string_lossy()
+            )));
+        }
+        let free = unsafe { libc::dlsym(handle, FREE_SYMBOL.as_ptr()) };
+        Ok(Self {
+            handle,
+            collect: unsafe { std::mem::transmute::<*mut c_void, CollectFn>(collect) },
+            free: (!free.is_null())
+                .then(|| unsafe { std::mem::transmute::<*mut c_void, FreeFn>(free) }),
+            lock: Mutex::new(()),
+        })
+    }
+
+    fn collect(&self) -> Result<String, PluginError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        // SAFETY: the library stays open while self lives
+        let output = unsafe { (self.collect)() };
+        if output.is_null() {
+            return Err(PluginError::Library("no metrics returned".to_string()));
+        }
+        // SAFETY: the plugin returns a NUL-terminated string
+        let json = unsafe { CStr::from_ptr(output) }
+            .to_string_lossy()
+            .into_owned();
+        if let Some(free) = self.free {
+            unsafe { free(output) };
+        }
+        Ok(json)
+    }
+}
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        // SAFETY: handle was returned by dlopen and is closed once
+        unsafe { libc::dlclose(self.handle) };
+    }
+}
+
+fn dl_error(path: &str) -> String {
+    // SAFETY: dlerror returns NULL or a NUL-terminated string
+    let error = unsafe { libc::dlerror() };
+    if error.is_null() {
+        format!("cannot open {}", path)
+    } else {
+        unsafe { CStr::from_ptr(error) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+enum Source {
+    Command { command: String, args: Vec<String> },
+    Library(Arc<Library>),
+}
+
+/// One configured plugin
+pub struct Collector {
+    name: String,
+    source: Source,
+    interval: Duration,
+    timeout: Duration,
+    next_collection: Instant,
+}
+
+impl Collector {
+    /// Create the collector of a plugin, opening its shared object if any
+    pub fn from_config(config: &PluginConfig) -> Result<Self, PluginError> {
+        let source = match (config.command.is_empty(), config.library.is_empty()) {
+            (false, true) => Source::Command {
+                command: config.command.clone(),
+                args: config.args.clone(),
+            },
+            (true, false) => Source::Library(Arc::new(Library::open(&config.library)?)),
+            _ => {
+                return Err(PluginError::Config(format!(
+                    "plugin '{}' needs either a command or a library",
+                    config.name
+                )))
+            }
+        };
+        Ok(Self {
+            name: config.name.clone(),
+            source,
+            interval: Duration::from_secs(config.interval.max(1)),
+            timeout: Duration::from_secs(config.timeout.max(1)),
+            next_collection: Instant::now(),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Run the plugin once and return its metrics
+    pub async fn collect(&self) -> Result<Vec<NodeMetric>, PluginError> {
+        let output = match &self.source {
+            Source::Command { command, args } => {
+                let child = tokio::process::Command::new(command)
+                    .args(args)
+                    .stdin(Stdio::null())
+                    .stderr(Stdio::null())
+                    .kill_on_drop(true)
+                    .output();
+                let output = tokio::time::timeout(self.timeout, child)
+                    .await
+                    .map_err(|_| PluginError::Timeout(self.timeout.as_secs()))??;
+                if !output.status.success() {
+                    return Err(PluginError::Exit(output.status));
+                }
+                String::from_utf8_lossy(&output.stdout).into_owned()
+            }
+            Source::Library(library) => {
+                // A call that times out keeps running, later calls wait for it
+                let library = Arc::clone(library);
+                let call = tokio::task::spawn_blocking(move || library.collect());
+                tokio::time::timeout(self.timeout, call)
+                    .await
+                    .map_err(|_| PluginError::Timeout(self.timeout.as_secs()))?
+                    .map_err(|e| PluginError::Library(e.to_string()))??
+            }
+        };
+        parse_metrics(&self.name, &output)
+    }
+}
+
+/// Plugins of the node, each collected at its own interval
+pub struct Plugins {
+    collectors: Vec<Collector>,
+}
+
+impl Plugins {
+    /// Create the collectors of the configured plugins, skipping invalid ones
+    pub fn from_config(configs: &[PluginConfig]) -> Self {
+        let mut collectors = Vec::new();
+        for config in configs {
+            match Collector::from_config(config) {
+                Ok(collector) => collectors.push(collector),
+                Err(e) => eprintln!("[NodeAgent] Skipping plugin {}: {}", config.name, e),
+            }
+        }
+        Self { collectors }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.collectors.is_empty()
+    }
+
+    /// Collect the plugins whose interval elapsed at `now`
+    ///
+    /// A failing plugin is logged and collected again at its next interval.
+    pub async fn collect_due(&mut self, now: Instant) -> Vec<NodeMetric> {
+        let mut metrics = Vec::new();
+        for collector in self.collectors.iter_mut() {
+            if collector.next_collection > now {
+                continue;
+            }
+            collector.next_collection = now + collector.interval;
+            match collector.collect().await {
+                Ok(collected) => metrics.extend(collected),
+                Err(e) => eprintln!(
+                    "[NodeAgent] Plugin {} failed to collect metrics: {}",
+                    collector.name(),
+                    e
+                ),
+            }
+        }
+        metrics
+    }
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command_plugin(name: &str, script: &str) -> PluginConfig {
+        PluginConfig {
+            name: name.to_string(),
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            interval: 10,
+            timeout: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_metrics_list_and_object() {
+        let metrics = parse_metrics(
+            "gpu-temp",
+            r#"[{"name": "gpu_temperature", "value": 61.5, "unit": "celsius", "labels": {"gpu": "0"}}]"#,
+        )
+        .unwrap();
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].collector, "gpu-temp");
+        assert_eq!(metrics[0].labels["gpu"], "0");
+
+        let metrics = parse_metrics(
+            "can",
+            r#"{"metrics": [{"name": "can_rx_errors", "value": 3}]}"#,
+        )
+        .unwrap();
+        assert_eq!(metrics[0].value, 3.0);
+        assert!(metrics[0].unit.is_empty());
+
+        assert!(parse_metrics("can", "rx_errors=3").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_command_plugin_collects_at_interval() {
+        let mut plugins = Plugins::from_config(&[
+            command_plugin(
+                "can-stats",
+                r#"echo '{"metrics": [{"name": "can_rx_errors", "value": 3, "labels": {"bus": "can0"}}]}'"#,
+            ),
+            command_plugin("broken", "exit 1"),
+            PluginConfig {
+                name: "unconfigured".to_string(),
+                ..Default::default()
+            },
+        ]);
+        assert_eq!(plugins.collectors.len(), 2);
+
+        let now = Instant::now();
+        let metrics = plugins.collect_due(now).await;
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].name, "can_rx_errors");
+        assert_eq!(metrics[0].collector, "can-stats");
+
+        assert!(plugins
+            .collect_due(now + Duration::from_secs(5))
+            .await
+            .is_empty());
+        assert_eq!(
+            plugins
+                .collect_due(now + Duration::from_secs(10))
+                .await
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_command_plugin_timeout() {
+        let collector = Collector::from_config(&command_plugin("slow", "sleep 5")).unwrap();
+        let start = Instant::now();
+        assert!(matches!(
+            collector.collect().await,
+            Err(PluginError::Timeout(1))
+        ));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_missing_library() {
+        let config = PluginConfig {
+            name: "gpu-temp".to_string(),
+            library: "/nonexistent/libgputemp.so".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            Collector::from_config(&config),
+            Err(PluginError::Library(_))
+        ));
+    }
+}
@@ -0,0 +1,292 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Sampling of the resource usage of containers
+//!
+//! Every running container is sampled each `sampling.interval_ms`. CPU and
+//! network rates are the deltas of the runtime counters between two samples
+//! of a container divided by the time between them. A counter that went
+//! back, e.g. after the container restarted, yields no rate for that sample.
+//! Container reports carry the minimum, average and maximum of the samples
+//! of the last `sampling.window_secs` as typed numbers.
+
+use super::container::get_stats;
+use super::ContainerStats;
+use crate::config::Config;
+use common::monitoringserver::{ContainerUsage, UsageAggregate};
+use futures::future::join_all;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+
+static SAMPLER: OnceLock<Mutex<Sampler>> = OnceLock::new();
+
+/// Cumulative counters of a container at one sample
+#[derive(Debug, Clone, Copy)]
+struct Counters {
+    at: Instant,
+    cpu_ns: u64,
+    rx_bytes: Option<u64>,
+    tx_bytes: Option<u64>,
+}
+
+/// Usage of a container at one sample
+#[derive(Debug, Clone, Copy)]
+struct Point {
+    at: Instant,
+    cpu_percent: Option<f64>,
+    memory_bytes: u64,
+    memory_limit_bytes: u64,
+    rx_bytes_per_sec: Option<f64>,
+    tx_bytes_per_sec: Option<f64>,
+}
+
+#[derive(Debug, Default)]
+struct Series {
+    last: Option<Counters>,
+    points: VecDeque<Point>,
+}
+
+/// Samples of every container within the window
+#[derive(Debug, Default)]
+pub struct Sampler {
+    containers: HashMap<String, Series>,
+}
+
+impl Sampler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a sample of a container taken at `now`
+    ///
+    /// Samples older than `window` are dropped.
+    pub fn record(&mut self, id: &str, stats: &ContainerStats, now: Instant, window: Duration) {
+        let network_total = |bytes: fn(&super::ContainerNetworkStats) -> u64| {
+            stats
+                .networks
+                .as_ref()
+                .map(|networks| networks.values().map(bytes).sum::<u64>())
+        };
+        let counters = Counters {
+            at: now,
+            cpu_ns: stats.cpu_stats.cpu_usage.total_usage,
+            rx_bytes: network_total(|net| net.rx_bytes),
+            tx_bytes: network_total(|net| net.tx_bytes),
+        };
+        let series = self.containers.entry(id.to_string()).or_default();
+        let previous = series.last.replace(counters);
+        let elapsed = previous
+            .map(|previous| now.duration_since(previous.at).as_secs_f64())
+            .filter(|secs| *secs > 0.0);
+        let rate = |current: Option<u64>, previous: Option<u64>| {
+            Some(current?.checked_sub(previous?)? as f64 / elapsed?)
+        };
+        series.points.push_back(Point {
+            at: now,
+            cpu_percent: rate(Some(counters.cpu_ns), previous.map(|p| p.cpu_ns))
+                .map(|ns_per_sec| ns_per_sec / 1e9 * 100.0),
+            memory_bytes: stats.memory_stats.usage,
+            memory_limit_bytes: stats.memory_stats.limit,
+            rx_bytes_per_sec: rate(counters.rx_bytes, previous.and_then(|p| p.rx_bytes)),
+            tx_bytes_per_sec: rate(counters.tx_bytes, previous.and_then(|p| p.tx_bytes)),
+        });
+        while series
+            .points
+            .front()
+            .is_some_and(|point| now.duration_since(point.at) >= window)
+        {
+            series.points.pop_front();
+        }
+    }
+
+    /// Usage of a container over the window ending at `now`
+    ///
+    /// ### Returns
+    /// * `Option<ContainerUsage>` - `None` if the container has no sample
+    ///   within the window
+    pub fn usage(&self, id: &str, now: Instant, window: Duration) -> Option<ContainerUsage> {
+        let points: Vec<&Point> = self
+            .containers
+            .get(id)?
+            .points
+            .iter()
+            .filter(|point| now.duration_since(point.at) < window)
+            .collect();
+        let (first, last) = (points.first()?, points.last()?);
+        Some(ContainerUsage {
+            samples: points.len() as u32,
+            window_ms: last.at.duration_since(first.at).as_millis() as u64,
+            cpu_percent: aggregate(points.iter().filter_map(|p| p.cpu_percent)),
+            memory_bytes: aggregate(points.iter().map(|p| p.memory_bytes as f64)),
+            memory_limit_bytes: last.memory_limit_bytes,
+            rx_bytes_per_sec: aggregate(points.iter().filter_map(|p| p.rx_bytes_per_sec)),
+            tx_bytes_per_sec: aggregate(points.iter().filter_map(|p| p.tx_bytes_per_sec)),
+        })
+    }
+
+    /// Forget the containers not in `ids`
+    pub fn retain(&mut self, ids: &HashSet<String>) {
+        self.containers.retain(|id, _| ids.contains(id));
+    }
+}
+
+/// Minimum, average and maximum of `values`, `None` if there are none
+fn aggregate(values: impl Iterator<Item = f64>) -> Option<UsageAggregate> {
+    let mut count = 0;
+    let mut result = UsageAggregate {
+        min: f64::MAX,
+        avg: 0.0,
+        max: f64::MIN,
+    };
+    for value in values {
+        count += 1;
+        result.min = result.min.min(value);
+        result.max = result.max.max(value);
+        result.avg += value;
+    }
+    if count == 0 {
+        return None;
+    }
+    result.avg /= count as f64;
+    Some(result)
+}
+
+fn sampler() -> MutexGuard<'static, Sampler> {
+    SAMPLER
+        .get_or_init(|| Mutex::new(Sampler::new()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// Take a sample of a container now
+async fn sample(id: &str, window: Duration) {
+    match get_stats(id).await {
+        Ok(stats) => sampler().record(id, &stats, Instant::now(), window),
+        Err(e) => println!("Failed to get stats for {}: {:?}", id, e),
+    }
+}
+
+/// Usage of a running container, sampled now if it has no recent sample
+pub async fn usage(id: &str) -> Option<ContainerUsage> {
+    let window = Config::get().get_sampling_window();
+    let usage = sampler().usage(id, Instant::now(), window);
+    if usage.is_some() {
+        return usage;
+    }
+    sample(id, window).await;
+    sampler().usage(id, Instant::now(), window)
+}
+
+/// Sample the running containers every `sampling.interval_ms`
+///
+/// The sampling config is read again for every round, so a reloaded config
+/// applies right away. Nothing is sampled while the runtime socket is down.
+pub async fn run() {
+    loop {
+        let config = Config::get();
+        if crate::watchdog::runtime_available() {
+            match super::container::get_list().await {
+                Ok(list) => {
+                    let running: HashSet<String> = list
+                        .into_iter()
+                        .filter(|container| container.State == "running")
+                        .map(|container| container.Id)
+                        .collect();
+                    let window = config.get_sampling_window();
+                    join_all(running.iter().map(|id| sample(id, window))).await;
+                    sampler().retain(&running);
+                }
+                Err(e) => eprintln!("[NodeAgent] Failed to list containers to sample: {}", e),
+            }
+        }
+        sleep(config.get_sampling_interval()).await;
+    }
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resource::{
+        ContainerCpuStats, ContainerCpuUsage, ContainerMemoryStats, ContainerNetworkStats,
+    };
+
+    fn stats(cpu_ns: u64, memory: u64, rx_bytes: u64) -> ContainerStats {
+        ContainerStats {
+            Id: "abc".to_string(),
+            name: "web".to_string(),
+            cpu_stats: ContainerCpuStats {
+                cpu_usage: ContainerCpuUsage {
+                    total_usage: cpu_ns,
+                    usage_in_kernelmode: 0,
+                    usage_in_usermode: cpu_ns,
+                },
+                online_cpus: Some(2),
+            },
+            memory_stats: ContainerMemoryStats {
+                usage: memory,
+                limit: 4096,
+            },
+            networks: Some(HashMap::from([(
+                "eth0".to_string(),
+                ContainerNetworkStats {
+                    rx_bytes,
+                    rx_packets: 0,
+                    rx_errors: 0,
+                    rx_dropped: 0,
+                    tx_bytes: 0,
+                    tx_packets: 0,
+                    tx_errors: 0,
+                    tx_dropped: 0,
+                },
+            )])),
+        }
+    }
+
+    #[test]
+    fn test_rates_and_aggregates_over_window() {
+        let window = Duration::from_secs(10);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut sampler = Sampler::new();
+
+        sampler.record("abc", &stats(0, 1000, 0), at(0), window);
+        let usage = sampler.usage("abc", at(0), window).unwrap();
+        assert_eq!(usage.samples, 1);
+        assert!(usage.cpu_percent.is_none());
+        assert_eq!(usage.memory_bytes.unwrap().avg, 1000.0);
+
+        // Half a CPU, then a full one
+        sampler.record("abc", &stats(1_000_000_000, 2000, 1000), at(2), window);
+        sampler.record("abc", &stats(3_000_000_000, 3000, 3000), at(4), window);
+        let usage = sampler.usage("abc", at(4), window).unwrap();
+        assert_eq!(usage.samples, 3);
+        assert_eq!(usage.window_ms, 4000);
+        let cpu = usage.cpu_percent.unwrap();
+        assert_eq!((cpu.min, cpu.avg, cpu.max), (50.0, 75.0, 100.0));
+        let memory = usage.memory_bytes.unwrap();
+        assert_eq!(
+            (memory.min, memory.avg, memory.max),
+            (1000.0, 2000.0, 3000.0)
+        );
+        assert_eq!(usage.memory_limit_bytes, 4096);
+        assert_eq!(usage.rx_bytes_per_sec.unwrap().max, 1000.0);
+        assert_eq!(usage.tx_bytes_per_sec.unwrap().max, 0.0);
+
+        // Counters of a restarted container give no rate
+        sampler.record("abc", &stats(0, 500, 0), at(6), window);
+        let usage = sampler.usage("abc", at(6), window).unwrap();
+        assert_eq!(usage.cpu_percent.unwrap().max, 100.0);
+        assert_eq!(usage.memory_bytes.unwrap().min, 500.0);
+
+        // Old samples leave the window, gone containers are forgotten
+        let usage = sampler.usage("abc", at(13), window).unwrap();
+        assert_eq!(usage.samples, 2);
+        assert!(sampler.usage("abc", at(20), window).is_none());
+        sampler.retain(&HashSet::new());
+        assert!(sampler.usage("abc", at(6), window).is_none());
+    }
+}
@@ -5,6 +5,7 @@
 
 //! Make files for Bluechi and copy to other nodes
 
+use common::spec::k8s::pod::PodNetwork;
 use common::spec::k8s::Pod;
 use std::io::Write;
 
@@ -13,18 +14,64 @@ use std::io::Write;
 /// ### Parametets
 /// * `pods: Vec<Pod>` - Vector of pods
 /// ### Description
-/// Make `.kube`, `.yaml` files for bluechi
+/// Make `.kube`, `.yaml` files for bluechi, and `.network` files for the
+/// networks the pods are attached to
 pub async fn make_files_from_pod(pods: Vec<Pod>, node: String) -> common::Result<()> {
     let storage_directory = &crate::config::Config::get().get_yaml_storage();
     if !std::path::Path::new(storage_directory).exists() {
         std::fs::create_dir_all(storage_directory)?;
     }
     for pod in pods {
+        if let Some(network) = pod.get_network() {
+            make_network_file(storage_directory, network)?;
+        }
         make_yaml_file(storage_directory, pod.clone())?;
     }
     Ok(())
 }
 
+/// Make .network file for a network of a Pod
+///
+/// ### Parametets
+/// * `dir: &str, network: &PodNetwork` - Pullpiri yaml directory path and network
+/// ### Description
+/// Make Quadlet `.network` unit, creating the network when systemd starts it
+fn make_network_file(dir: &str, network: &PodNetwork) -> common::Result<()> {
+    let network_file_path = format!("{}/{}.network", dir, network.name);
+    let mut network_file = std::fs::File::create(network_file_path)?;
+
+    network_file.write_all(network_unit(network).as_bytes())?;
+
+    Ok(())
+}
+
+/// Content of the Quadlet `.network` unit of a network
+fn network_unit(network: &PodNetwork) -> String {
+    let spec = &network.spec;
+    let mut unit = format!(
+        "[Network]\nNetworkName={}\nDriver={}\n",
+        network.name,
+        spec.get_driver().as_str()
+    );
+    let options = [
+        ("Subnet", spec.get_subnet()),
+        ("Gateway", spec.get_gateway()),
+        ("IPRange", spec.get_ip_range()),
+    ];
+    for (key, value) in options {
+        if let Some(value) = value {
+            unit.push_str(&format!("{}={}\n", key, value));
+        }
+    }
+    if let Some(parent) = spec.get_parent() {
+        unit.push_str(&format!("Options=parent={}\n", parent));
+    }
+    if spec.is_internal() {
+        unit.push_str("Internal=true\n");
+    }
+    unit
+}
+
 /// Make .yaml files for Pod
 ///
 /// ### Parametets
@@ -135,6 +182,39 @@ containers:
         fs::remove_file(&yaml_path).expect("Failed to remove YAML file after test");
     }
 
+    #[test]
+    fn test_network_unit() {
+        let network = PodNetwork {
+            name: "vd-net".to_string(),
+            spec: serde_yaml::from_str("driver: macvlan\nsubnet: 192.168.10.0/24\nparent: eth0")
+                .unwrap(),
+        };
+        assert_eq!(
+            network_unit(&network),
+            "[Network]\nNetworkName=vd-net\nDriver=macvlan\nSubnet=192.168.10.0/24\nOptions=parent=eth0\n"
+        );
+    }
+
+    /// Test that make_network_file() writes the unit named after the network
+    #[test]
+    fn test_make_network_file() {
+        let storage_dir = "/etc/pullpiri/yaml_network_test";
+        fs::create_dir_all(storage_dir).expect("Failed to create directory for testing");
+        let network = PodNetwork {
+            name: "vd-net".to_string(),
+            spec: serde_yaml::from_str("internal: true").unwrap(),
+        };
+
+        make_network_file(storage_dir, &network).expect("Failed to create network file");
+
+        let content = fs::read_to_string(format!("{}/vd-net.network", storage_dir))
+            .expect("Failed to read network file");
+        assert!(content.contains("Driver=bridge\n"));
+        assert!(content.contains("Internal=true\n"));
+
+        fs::remove_dir_all(storage_dir).expect("Failed to remove test directory");
+    }
+
     /// Negative test: make_yaml_file() with invalid directory (should fail)
     #[tokio::test]
     async fn test_make_yaml_file_invalid_dir() {
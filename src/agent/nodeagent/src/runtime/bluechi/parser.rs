@@ -5,7 +5,7 @@
 
 //! Create Model artifact from given Package information
 
-use common::spec::artifact::{Artifact, Model, Network, Package, Scenario, Volume};
+use common::spec::artifact::{Artifact, Model, Network, Package, Scenario, Secret, Volume};
 
 pub async fn yaml_split(body: &str) -> common::Result<(String, Vec<Model>)> {
     let docs: Vec<&str> = body.split("---").collect();
@@ -72,6 +72,7 @@ pub async fn get_complete_model(
             let model_name = mi.get_name();
             for model in models.iter() {
                 if model.get_name() == model_name {
+                    let mut model = model.clone();
                     if let Some(volume_name) = mi.get_resources().get_volume() {
                         let key = format!("Volume/{}", volume_name);
                         let volume_str: String = common::etcd::get(&key).await?;
@@ -79,7 +80,7 @@ pub async fn get_complete_model(
 
                         if let Some(volume_spec) = volume.get_spec() {
                             model
-                                .get_podspec()
+                                .get_podspec_mut()
                                 .volumes
                                 .clone_from(volume_spec.get_volume());
                         }
@@ -89,11 +90,18 @@ pub async fn get_complete_model(
                         let network_str = common::etcd::get(&key).await?;
                         let network: Network = serde_yaml::from_str(&network_str)?;
 
-                        if let Some(_network_spec) = network.get_spec() {
-                            // TODO
-                        }
+                        model.get_podspec_mut().attach_network(&network);
+                    }
+                    if let Some(secret_name) = mi.get_resources().get_secret() {
+                        let key = format!("Secret/{}", secret_name);
+                        let secret_str = common::etcd::get(&key).await?;
+                        let secret: Secret = serde_yaml::from_str(&secret_str)?;
+
+                        model
+                            .get_podspec_mut()
+                            .add_secret_ref(&secret.get_name(), secret.is_registry());
                     }
-                    base_models.push(model.clone());
+                    base_models.push(model);
                 } else {
                     println!("Model {} is not for this node {}", model.get_name(), node);
                     continue;
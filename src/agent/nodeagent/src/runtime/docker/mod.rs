@@ -0,0 +1,103 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Container inspection through the Docker Engine API
+
+use super::{
+    events_query, follow_logs_from, follow_query, get_from, get_json, get_logs, logs_query,
+    parse_events, ping_socket, ContainerRuntime,
+};
+use crate::resource::container::Result;
+use crate::resource::{Container, ContainerInspect, ContainerStats, RuntimeEvent};
+use hyper::Body;
+
+const DOCKER_SOCKET: &str = "/var/run/docker.sock";
+const DOCKER_API_VERSION: &str = "/v1.41";
+
+pub struct DockerRuntime {
+    socket: String,
+}
+
+impl DockerRuntime {
+    /// Use the given unix socket, or the default Docker socket if it is empty
+    pub fn new(socket: String) -> Self {
+        let socket = if socket.is_empty() {
+            DOCKER_SOCKET.to_string()
+        } else {
+            socket
+        };
+        Self { socket }
+    }
+}
+
+#[tonic::async_trait]
+impl ContainerRuntime for DockerRuntime {
+    fn name(&self) -> &'static str {
+        "docker"
+    }
+
+    async fn list(&self) -> Result<Vec<Container>> {
+        let path = format!("{}/containers/json?all=true", DOCKER_API_VERSION);
+        get_json(&self.socket, &path).await
+    }
+
+    async fn inspect(&self, id: &str) -> Result<ContainerInspect> {
+        let path = format!("{}/containers/{}/json", DOCKER_API_VERSION, id);
+        get_json(&self.socket, &path).await
+    }
+
+    async fn stats(&self, id: &str) -> Result<ContainerStats> {
+        let path = format!(
+            "{}/containers/{}/stats?stream=false",
+            DOCKER_API_VERSION, id
+        );
+        get_json(&self.socket, &path).await
+    }
+
+    async fn events(&self, since: i64) -> Result<Vec<RuntimeEvent>> {
+        let path = format!("{}/events?{}", DOCKER_API_VERSION, events_query(since));
+        let body = get_from(&self.socket, &path).await?;
+        parse_events(&body)
+    }
+
+    async fn logs(&self, id: &str, tail: u32) -> Result<String> {
+        let path = format!(
+            "{}/containers/{}/logs?{}",
+            DOCKER_API_VERSION,
+            id,
+            logs_query(tail)
+        );
+        get_logs(&self.socket, &path).await
+    }
+
+    async fn follow_logs(&self, id: &str, tail: u32) -> Result<Body> {
+        let path = format!(
+            "{}/containers/{}/logs?{}",
+            DOCKER_API_VERSION,
+            id,
+            follow_query(tail)
+        );
+        follow_logs_from(&self.socket, &path).await
+    }
+
+    async fn ping(&self) -> Result<()> {
+        let path = format!("{}/_ping", DOCKER_API_VERSION);
+        ping_socket(&self.socket, &path).await
+    }
+}
+
+//Unit tets cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_socket() {
+        assert_eq!(DockerRuntime::new(String::new()).socket, DOCKER_SOCKET);
+        assert_eq!(
+            DockerRuntime::new("/run/user/1000/docker.sock".to_string()).socket,
+            "/run/user/1000/docker.sock"
+        );
+    }
+}
@@ -0,0 +1,387 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! In-memory container and unit backends for development hosts
+//!
+//! Podman, Bluechi and systemd only run on Linux. Built with the
+//! `mock-runtime` feature and started with `--mock-runtime`, NodeAgent keeps
+//! its workloads in memory instead: the containers of a started pod are
+//! reported as running with the annotations of the pod, and pause, stop and
+//! restart change their state. Unit operations only record the unit state and
+//! images are never pulled, so that ApiServer, StateManager, ActionController
+//! and NodeAgent can run end to end on a laptop for integration tests.
+//!
+//! The containers are answered in the JSON of the libpod API, so everything
+//! above [`ContainerRuntime`] behaves as with a real engine.
+
+use super::ContainerRuntime;
+use crate::resource::container::Result;
+use crate::resource::{Container, ContainerInspect, ContainerStats, RuntimeEvent};
+use common::nodeagent::fromactioncontroller::{UnitCommand, WorkloadCommand};
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Events kept for `events`, the oldest are dropped first
+const MAX_EVENTS: usize = 1000;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+/// Containers by name
+static CONTAINERS: Lazy<Mutex<BTreeMap<String, MockContainer>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+static EVENTS: Lazy<Mutex<Vec<Value>>> = Lazy::new(|| Mutex::new(Vec::new()));
+/// Active state of the units by name
+static UNITS: Lazy<Mutex<HashMap<String, &'static str>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Keep workloads in memory from now on
+///
+/// Must be called before the first use of [`super::container_runtime`].
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Whether NodeAgent was started with `--mock-runtime`
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Container of a started pod
+#[derive(Debug, Clone)]
+struct MockContainer {
+    id: String,
+    name: String,
+    image: String,
+    /// State as named by Podman, e.g. `running` or `exited`
+    status: &'static str,
+    annotations: HashMap<String, String>,
+    started_at: i64,
+}
+
+impl MockContainer {
+    fn list_json(&self) -> Value {
+        json!({
+            "Id": self.id,
+            "Names": [self.name],
+            "Image": self.image,
+            "State": self.status,
+            "Status": self.status,
+        })
+    }
+
+    fn inspect_json(&self) -> Value {
+        json!({
+            "Id": self.id,
+            "Name": self.name,
+            "State": {
+                "Status": self.status,
+                "Running": self.status == "running",
+                "Paused": self.status == "paused",
+                "Restarting": false,
+                "OOMKilled": false,
+                "Dead": false,
+                "Pid": 0,
+                "ExitCode": 0,
+                "Error": "",
+                "StartedAt": self.started_at.to_string(),
+                "FinishedAt": "",
+            },
+            "Config": {
+                "Hostname": "",
+                "Domainname": "",
+                "User": "",
+                "AttachStdin": false,
+                "AttachStdout": false,
+                "AttachStderr": false,
+                "Tty": false,
+                "OpenStdin": false,
+                "StdinOnce": false,
+                "Image": self.image,
+                "WorkingDir": "",
+                "Annotations": self.annotations,
+            },
+        })
+    }
+}
+
+fn record_event(action: &str, id: &str) {
+    if let Ok(mut events) = EVENTS.lock() {
+        if events.len() >= MAX_EVENTS {
+            events.remove(0);
+        }
+        events.push(json!({
+            "Type": "container",
+            "Action": action,
+            "Actor": { "ID": id },
+            "time": now(),
+        }));
+    }
+}
+
+/// Containers of a pod, not created yet
+fn pod_containers(pod_yaml: &str) -> Result<Vec<MockContainer>> {
+    let pod = serde_yaml::from_str::<common::spec::k8s::Pod>(pod_yaml)?;
+    let pod_name = pod.get_name();
+    let pod_json = serde_json::to_value(&pod)?;
+    let annotations: HashMap<String, String> = pod_json["metadata"]["annotations"]
+        .as_object()
+        .map(|obj| {
+            obj.iter()
+                .filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+    let containers = pod_json["spec"]["containers"]
+        .as_array()
+        .ok_or("No containers found in spec")?;
+    containers
+        .iter()
+        .map(|container| {
+            let name = container["name"]
+                .as_str()
+                .ok_or("Container name field not found")?;
+            Ok(MockContainer {
+                id: String::new(),
+                name: format!("{}_{}", pod_name, name),
+                image: container["image"].as_str().unwrap_or_default().to_string(),
+                status: "created",
+                annotations: annotations.clone(),
+                started_at: 0,
+            })
+        })
+        .collect()
+}
+
+/// Run a workload command on the containers of a pod
+///
+/// ### Returns
+/// * `Vec<String>` - ids of the containers for `Create` and `Start`
+pub fn handle_workload(command: i32, pod_yaml: &str) -> Result<Vec<String>> {
+    let command = WorkloadCommand::try_from(command).map_err(|_| "unimplemented command")?;
+    let mut containers = CONTAINERS.lock().map_err(|e| e.to_string())?;
+    let pod = pod_containers(pod_yaml)?;
+    let count = pod.len();
+    let mut ids = Vec::new();
+    for template in pod {
+        let name = template.name.clone();
+        if let WorkloadCommand::Create | WorkloadCommand::Start = command {
+            let container = containers.entry(name.clone()).or_insert_with(|| {
+                let id = format!("mock{:012x}", NEXT_ID.fetch_add(1, Ordering::Relaxed));
+                record_event("create", &id);
+                MockContainer { id, ..template }
+            });
+            if command == WorkloadCommand::Start {
+                container.status = "running";
+                container.started_at = now();
+                record_event("start", &container.id);
+            }
+            ids.push(container.id.clone());
+            continue;
+        }
+
+        let Some(container) = containers.get_mut(&name) else {
+            return Err(format!("no such container: {}", name).into());
+        };
+        let action = match command {
+            WorkloadCommand::Pause => {
+                container.status = "paused";
+                "pause"
+            }
+            WorkloadCommand::Unpause => {
+                container.status = "running";
+                "unpause"
+            }
+            WorkloadCommand::Restart => {
+                container.status = "running";
+                container.started_at = now();
+                "restart"
+            }
+            // Like Podman, `stop` removes the containers
+            _ => "remove",
+        };
+        record_event(action, &container.id);
+        if action == "remove" {
+            containers.remove(&name);
+        }
+    }
+    println!(
+        "[MockRuntime] Workload command {} executed for {} container(s)",
+        command.as_str_name(),
+        count
+    );
+    Ok(ids)
+}
+
+/// Restart a container by id, as the reconciliation loop does
+pub fn restart(id: &str) -> Result<()> {
+    let mut containers = CONTAINERS.lock().map_err(|e| e.to_string())?;
+    let container = containers
+        .values_mut()
+        .find(|container| container.id == id)
+        .ok_or_else(|| format!("no such container: {}", id))?;
+    container.status = "running";
+    container.started_at = now();
+    record_event("restart", id);
+    Ok(())
+}
+
+/// Record the state a unit command leaves a unit in
+pub fn handle_unit(command: i32, unit: &str) -> Result<()> {
+    let command = UnitCommand::try_from(command).map_err(|_| "unsupported unit command")?;
+    let state = match command {
+        UnitCommand::DaemonReload => return Ok(()),
+        UnitCommand::Stop => "inactive",
+        _ => "active",
+    };
+    let previous = UNITS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(unit.to_string(), state);
+    println!(
+        "[MockRuntime] Unit {}: {} -> {}",
+        unit,
+        previous.unwrap_or("inactive"),
+        state
+    );
+    Ok(())
+}
+
+/// Containers kept in memory, answered in the JSON of the libpod API
+pub struct MockRuntime;
+
+impl MockRuntime {
+    fn find(&self, id: &str) -> Result<MockContainer> {
+        let containers = CONTAINERS.lock().map_err(|e| e.to_string())?;
+        containers
+            .values()
+            .find(|container| container.id == id || container.name == id)
+            .cloned()
+            .ok_or_else(|| format!("no such container: {}", id).into())
+    }
+}
+
+#[tonic::async_trait]
+impl ContainerRuntime for MockRuntime {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn list(&self) -> Result<Vec<Container>> {
+        let containers = CONTAINERS.lock().map_err(|e| e.to_string())?;
+        let list: Vec<Value> = containers.values().map(MockContainer::list_json).collect();
+        Ok(serde_json::from_value(Value::Array(list))?)
+    }
+
+    async fn inspect(&self, id: &str) -> Result<ContainerInspect> {
+        Ok(serde_json::from_value(self.find(id)?.inspect_json())?)
+    }
+
+    async fn stats(&self, id: &str) -> Result<ContainerStats> {
+        let container = self.find(id)?;
+        Ok(serde_json::from_value(json!({
+            "Id": container.id,
+            "name": container.name,
+            "cpu_stats": {
+                "cpu_usage": { "total_usage": 0, "usage_in_kernelmode": 0, "usage_in_usermode": 0 },
+                "online_cpus": 1,
+            },
+            "memory_stats": { "usage": 0, "limit": 0 },
+        }))?)
+    }
+
+    async fn events(&self, since: i64) -> Result<Vec<RuntimeEvent>> {
+        let events = EVENTS.lock().map_err(|e| e.to_string())?;
+        let events: Vec<Value> = events
+            .iter()
+            .filter(|event| event["time"].as_i64().unwrap_or_default() >= since)
+            .cloned()
+            .collect();
+        Ok(serde_json::from_value(Value::Array(events))?)
+    }
+
+    async fn logs(&self, id: &str, _tail: u32) -> Result<String> {
+        let container = self.find(id)?;
+        Ok(format!(
+            "{} runs in the mock runtime, it has no output\n",
+            container.name
+        ))
+    }
+
+    async fn follow_logs(&self, id: &str, _tail: u32) -> Result<hyper::Body> {
+        self.find(id)?;
+        Ok(hyper::Body::empty())
+    }
+
+    async fn ping(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POD: &str = r#"
+apiVersion: v1
+kind: Pod
+metadata:
+  name: mockpod
+  annotations:
+    io.piccolo.annotations.package-name: helloworld
+spec:
+  containers:
+    - name: app
+      image: helloworld:latest
+"#;
+
+    #[tokio::test]
+    async fn test_mock_workload_lifecycle() {
+        let runtime = MockRuntime;
+        let ids = handle_workload(WorkloadCommand::Start as i32, POD).unwrap();
+        assert_eq!(ids.len(), 1);
+
+        let list = runtime.list().await.unwrap();
+        let container = list.iter().find(|c| c.Id == ids[0]).unwrap();
+        assert_eq!(container.Names, vec!["mockpod_app"]);
+        assert_eq!(container.State, "running");
+
+        let inspect = runtime.inspect(&ids[0]).await.unwrap();
+        assert!(inspect.State.Running);
+        assert_eq!(
+            inspect.Config.Annotations.unwrap()["io.piccolo.annotations.package-name"],
+            "helloworld"
+        );
+        assert!(runtime.stats(&ids[0]).await.is_ok());
+
+        handle_workload(WorkloadCommand::Pause as i32, POD).unwrap();
+        assert!(runtime.inspect(&ids[0]).await.unwrap().State.Paused);
+        restart(&ids[0]).unwrap();
+        assert!(runtime.inspect(&ids[0]).await.unwrap().State.Running);
+
+        handle_workload(WorkloadCommand::Stop as i32, POD).unwrap();
+        assert!(runtime.inspect(&ids[0]).await.is_err());
+        assert!(handle_workload(WorkloadCommand::Pause as i32, POD).is_err());
+
+        let actions: Vec<String> = runtime
+            .events(0)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|event| event.Actor.ID == ids[0])
+            .map(|event| event.Action)
+            .collect();
+        assert_eq!(actions, ["create", "start", "pause", "restart", "remove"]);
+    }
+}
@@ -1,2 +1,357 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
 //pub mod bluechi;
+pub mod docker;
+#[cfg(feature = "mock-runtime")]
+pub mod mock;
 pub mod podman;
+pub mod systemd;
+
+use crate::config::{Config, ContainerRuntimeKind};
+use crate::resource::container::Result;
+use crate::resource::{Container, ContainerInspect, ContainerStats, RuntimeEvent};
+use hyper::{Body, Client, Uri};
+use hyperlocal::{UnixConnector, Uri as UnixUri};
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use std::sync::OnceLock;
+
+// A single `hyper::Client` is cheap to clone and manages its own connection
+// pool internally, so it is created once and reused for every request
+// instead of being rebuilt on each call to `get`/`post`/`delete`.
+static UNIX_CLIENT: Lazy<Client<UnixConnector, Body>> =
+    Lazy::new(|| Client::builder().build::<_, Body>(UnixConnector));
+
+/// Read-only access to the containers of the local container engine
+///
+/// Workloads are still created through `runtime::podman`, this trait only
+/// covers what is needed to report and probe containers.
+#[tonic::async_trait]
+pub trait ContainerRuntime: Send + Sync {
+    /// Name of the runtime as written in the settings
+    fn name(&self) -> &'static str;
+
+    /// All containers, including stopped ones
+    async fn list(&self) -> Result<Vec<Container>>;
+
+    /// Detailed state and config of one container
+    async fn inspect(&self, id: &str) -> Result<ContainerInspect>;
+
+    /// One sample of resource usage of a running container
+    async fn stats(&self, id: &str) -> Result<ContainerStats>;
+
+    /// Container events from `since` (unix seconds) until now
+    async fn events(&self, since: i64) -> Result<Vec<RuntimeEvent>>;
+
+    /// Stdout and stderr of a container, the last `tail` lines or all if 0
+    async fn logs(&self, id: &str, tail: u32) -> Result<String>;
+
+    /// Raw log stream of a container from its last `tail` lines, kept open
+    /// while the container runs; frames are split by [`LogDemuxer`]
+    async fn follow_logs(&self, id: &str, tail: u32) -> Result<Body>;
+
+    /// Check that the engine answers on its socket
+    async fn ping(&self) -> Result<()>;
+}
+
+/// Runtime selected by `container_runtime` in the nodeagent settings
+pub fn container_runtime() -> &'static dyn ContainerRuntime {
+    static RUNTIME: OnceLock<Box<dyn ContainerRuntime>> = OnceLock::new();
+    RUNTIME.get_or_init(|| from_config(&Config::get())).as_ref()
+}
+
+fn from_config(config: &Config) -> Box<dyn ContainerRuntime> {
+    #[cfg(feature = "mock-runtime")]
+    if mock::enabled() {
+        return Box::new(mock::MockRuntime);
+    }
+    let socket = config.nodeagent.runtime_socket.clone();
+    match config.nodeagent.container_runtime {
+        ContainerRuntimeKind::Podman => Box::new(podman::PodmanRuntime::new(socket)),
+        ContainerRuntimeKind::Docker => Box::new(docker::DockerRuntime::new(socket)),
+    }
+}
+
+/// Whether workloads are kept in memory by the `mock` runtime
+pub fn mock_enabled() -> bool {
+    #[cfg(feature = "mock-runtime")]
+    return mock::enabled();
+    #[cfg(not(feature = "mock-runtime"))]
+    false
+}
+
+/// Run a workload command with Podman, or in memory with `--mock-runtime`
+pub async fn handle_workload(
+    command: i32,
+    pod_yaml: &str,
+) -> std::result::Result<Vec<String>, Box<dyn std::error::Error>> {
+    #[cfg(feature = "mock-runtime")]
+    if mock::enabled() {
+        return mock::handle_workload(command, pod_yaml).map_err(|e| e.to_string().into());
+    }
+    podman::handle_workload(command, pod_yaml).await
+}
+
+pub async fn get_from(
+    socket: &str,
+    path: &str,
+) -> std::result::Result<hyper::body::Bytes, hyper::Error> {
+    let uri: Uri = UnixUri::new(socket, path).into();
+
+    let res = UNIX_CLIENT.get(uri).await?;
+    hyper::body::to_bytes(res).await
+}
+
+async fn get_json<T: DeserializeOwned>(socket: &str, path: &str) -> Result<T> {
+    let body = get_from(socket, path).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// Call a ping endpoint, failing unless it answers with a success status
+async fn ping_socket(socket: &str, path: &str) -> Result<()> {
+    let uri: Uri = UnixUri::new(socket, path).into();
+
+    let res = UNIX_CLIENT.get(uri).await?;
+    if !res.status().is_success() {
+        return Err(format!("ping of {} failed with status {}", socket, res.status()).into());
+    }
+    Ok(())
+}
+
+/// Read the logs endpoint, failing on an error status instead of returning its body
+async fn get_logs(socket: &str, path: &str) -> Result<String> {
+    let uri: Uri = UnixUri::new(socket, path).into();
+
+    let res = UNIX_CLIENT.get(uri).await?;
+    let status = res.status();
+    let body = hyper::body::to_bytes(res).await?;
+    if !status.is_success() {
+        return Err(format!(
+            "container logs request failed with status {}: {}",
+            status,
+            String::from_utf8_lossy(&body).trim()
+        )
+        .into());
+    }
+    Ok(demux_logs(&body))
+}
+
+/// Open the following logs endpoint, failing on an error status
+async fn follow_logs_from(socket: &str, path: &str) -> Result<Body> {
+    let uri: Uri = UnixUri::new(socket, path).into();
+
+    let res = UNIX_CLIENT.get(uri).await?;
+    let status = res.status();
+    if !status.is_success() {
+        let body = hyper::body::to_bytes(res).await?;
+        return Err(format!(
+            "container logs request failed with status {}: {}",
+            status,
+            String::from_utf8_lossy(&body).trim()
+        )
+        .into());
+    }
+    Ok(res.into_body())
+}
+
+/// Query parameters of [`logs_query`] that keep the stream open
+fn follow_query(tail: u32) -> String {
+    format!("{}&follow=true", logs_query(tail))
+}
+
+/// Query parameters selecting stdout and stderr, the last `tail` lines or all if 0
+fn logs_query(tail: u32) -> String {
+    let tail = if tail == 0 {
+        "all".to_string()
+    } else {
+        tail.to_string()
+    };
+    format!("stdout=true&stderr=true&tail={}", tail)
+}
+
+/// Strip the frame headers of a multiplexed log stream
+///
+/// Containers without a TTY send each chunk behind an 8 byte header: stream
+/// type (0, 1 or 2), three zero bytes and the big-endian payload length. The
+/// output of a TTY container is raw and returned as is.
+fn demux_logs(body: &[u8]) -> String {
+    let is_frame = |b: &[u8]| b.len() >= 8 && b[0] <= 2 && b[1..4] == [0, 0, 0];
+    if !is_frame(body) {
+        return String::from_utf8_lossy(body).into_owned();
+    }
+
+    let mut out = Vec::with_capacity(body.len());
+    let mut rest = body;
+    while is_frame(rest) {
+        let len = u32::from_be_bytes([rest[4], rest[5], rest[6], rest[7]]) as usize;
+        let end = (8 + len).min(rest.len());
+        out.extend_from_slice(&rest[8..end]);
+        rest = &rest[end..];
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Splits a followed log stream into lines
+///
+/// Unlike [`demux_logs`] the stream arrives in chunks that can end within a
+/// frame header, a frame or a line, so the unfinished part is kept for the
+/// next chunk. Whether the stream is multiplexed is decided by its first
+/// bytes.
+#[derive(Debug, Default)]
+pub struct LogDemuxer {
+    multiplexed: Option<bool>,
+    /// Received bytes not yet split into frames
+    pending: Vec<u8>,
+    /// Payload of the current line
+    line: Vec<u8>,
+}
+
+impl LogDemuxer {
+    /// Complete lines of the stream up to `chunk`, without line endings
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(chunk);
+        let multiplexed = match self.multiplexed {
+            Some(multiplexed) => multiplexed,
+            None if self.pending.len() < 8 && !self.pending.contains(&b'\n') => return Vec::new(),
+            None => {
+                let head = &self.pending;
+                let multiplexed = head.len() >= 8 && head[0] <= 2 && head[1..4] == [0, 0, 0];
+                self.multiplexed = Some(multiplexed);
+                multiplexed
+            }
+        };
+
+        let mut lines = Vec::new();
+        if multiplexed {
+            let mut consumed = 0;
+            while self.pending.len() - consumed >= 8 {
+                let header = &self.pending[consumed..consumed + 8];
+                let len = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+                if self.pending.len() - consumed - 8 < len {
+                    break;
+                }
+                let start = consumed + 8;
+                let payload = self.pending[start..start + len].to_vec();
+                self.split_lines(&payload, &mut lines);
+                consumed = start + len;
+            }
+            self.pending.drain(..consumed);
+        } else {
+            let payload = std::mem::take(&mut self.pending);
+            self.split_lines(&payload, &mut lines);
+        }
+        lines
+    }
+
+    fn split_lines(&mut self, payload: &[u8], lines: &mut Vec<String>) {
+        for &byte in payload {
+            if byte == b'\n' {
+                let line = std::mem::take(&mut self.line);
+                let line = line.strip_suffix(b"\r").unwrap_or(&line);
+                lines.push(String::from_utf8_lossy(line).into_owned());
+            } else {
+                self.line.push(byte);
+            }
+        }
+    }
+}
+
+/// Query parameters selecting events from `since` until now
+fn events_query(since: i64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    format!("since={}&until={}", since, now)
+}
+
+/// Parse the newline separated json of the events API, keeping container events
+fn parse_events(body: &[u8]) -> Result<Vec<RuntimeEvent>> {
+    let mut events = Vec::new();
+    for event in serde_json::Deserializer::from_slice(body).into_iter::<RuntimeEvent>() {
+        let event = event?;
+        if event.Type == "container" {
+            events.push(event);
+        }
+    }
+    Ok(events)
+}
+
+//Unit tets cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_config_selects_runtime() {
+        let mut config = Config::default();
+        assert_eq!(from_config(&config).name(), "podman");
+
+        config.nodeagent.container_runtime = ContainerRuntimeKind::Docker;
+        assert_eq!(from_config(&config).name(), "docker");
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let body = br#"{"Type":"container","Action":"oom","Actor":{"ID":"abc","Attributes":{"name":"web"}},"time":1700000000}
+{"Type":"image","Action":"pull","Actor":{"ID":"nginx"},"time":1700000001}
+{"Type":"container","Action":"die","Actor":{"ID":"def"},"time":1700000002}
+"#;
+        let events = parse_events(body).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].Action, "oom");
+        assert_eq!(events[0].Actor.ID, "abc");
+        assert_eq!(events[1].time, 1700000002);
+
+        assert!(parse_events(b"").unwrap().is_empty());
+        assert!(parse_events(b"not json").is_err());
+    }
+
+    #[test]
+    fn test_demux_logs() {
+        let mut body = vec![1, 0, 0, 0, 0, 0, 0, 6];
+        body.extend_from_slice(b"hello\n");
+        body.extend_from_slice(&[2, 0, 0, 0, 0, 0, 0, 4]);
+        body.extend_from_slice(b"err\n");
+        assert_eq!(demux_logs(&body), "hello\nerr\n");
+
+        // TTY output has no frame headers
+        assert_eq!(demux_logs(b"plain line\n"), "plain line\n");
+        assert_eq!(demux_logs(b""), "");
+    }
+
+    #[test]
+    fn test_logs_query() {
+        assert_eq!(logs_query(0), "stdout=true&stderr=true&tail=all");
+        assert_eq!(logs_query(100), "stdout=true&stderr=true&tail=100");
+        assert_eq!(
+            follow_query(10),
+            "stdout=true&stderr=true&tail=10&follow=true"
+        );
+    }
+
+    #[test]
+    fn test_log_demuxer_splits_chunks() {
+        let mut stream = vec![1, 0, 0, 0, 0, 0, 0, 9];
+        stream.extend_from_slice(b"one\ntwo\r\n");
+        stream.extend_from_slice(&[2, 0, 0, 0, 0, 0, 0, 5]);
+        stream.extend_from_slice(b"three");
+        stream.extend_from_slice(&[1, 0, 0, 0, 0, 0, 0, 1]);
+        stream.extend_from_slice(b"\n");
+
+        // Chunks end within headers and frames
+        let mut demuxer = LogDemuxer::default();
+        let mut lines = Vec::new();
+        for chunk in stream.chunks(3) {
+            lines.extend(demuxer.push(chunk));
+        }
+        assert_eq!(lines, vec!["one", "two", "three"]);
+
+        let mut demuxer = LogDemuxer::default();
+        assert!(demuxer.push(b"tty ").is_empty());
+        assert_eq!(demuxer.push(b"line\nnext"), vec!["tty line"]);
+        assert_eq!(demuxer.push(b"\n"), vec!["next"]);
+    }
+}
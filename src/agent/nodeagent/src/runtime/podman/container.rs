@@ -12,6 +12,8 @@
 //! - `start`: Create and start containers from a Pod YAML
 //! - `stop`: Stop and remove containers
 //! - `restart`: Restart running containers
+//! - `create`: Create containers from a Pod YAML without starting them
+//! - `pause` / `unpause`: Freeze and resume running containers
 //!
 //! # Architecture
 //! The module is organized into several logical sections:
@@ -20,8 +22,11 @@
 //! - Container specification building (image, command, environment, ports)
 //! - Podman API communication (create, start, stop, restart)
 //! - Image management (existence check, pull)
+//! - Networks of Network artifacts, created before the containers joining them
 
-use super::{get, post};
+use super::network::{ensure_network, pod_network};
+use super::secret;
+use super::{get, post, post_with_auth};
 use hyper::Body;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -154,6 +159,8 @@ fn build_host_config(
     // Network configuration
     if host_network {
         host_config.insert("NetworkMode".to_string(), json!("host"));
+    } else if let Some(network) = pod_network(spec) {
+        host_config.insert("NetworkMode".to_string(), json!(network.name));
     }
 
     // Security context (capabilities, privileged, user/group)
@@ -739,6 +746,7 @@ async fn create_container(
     spec: &serde_json::Value,
     host_network: bool,
     annotations: &std::collections::HashMap<String, String>,
+    registry_auth: Option<&str>,
 ) -> Result<String, Box<dyn std::error::Error>> {
     let image = container["image"]
         .as_str()
@@ -748,7 +756,7 @@ async fn create_container(
         .ok_or("Container name field not found")?;
 
     // Ensure image is available locally
-    ensure_image_available(image).await?;
+    ensure_image_available(image, registry_auth).await?;
 
     let name = format!("{}_{}", pod_name, container_name);
 
@@ -763,10 +771,13 @@ async fn create_container(
 }
 
 /// Ensure the container image is available locally (pull if needed)
-async fn ensure_image_available(image: &str) -> Result<(), Box<dyn std::error::Error>> {
+async fn ensure_image_available(
+    image: &str,
+    registry_auth: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
     if !image_exists(image).await? {
         println!("Image {} not found locally, pulling...", image);
-        pull_image(image).await?;
+        pull_image(image, registry_auth).await?;
         println!("Image {} pulled successfully", image);
     }
     Ok(())
@@ -869,16 +880,58 @@ async fn create_container_via_api(
     Ok(container_id)
 }
 
+pub async fn create(pod_yaml: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let resolved = secret::resolve(pod_yaml).await?;
+    let (pod_name, spec, annotations) = parse_pod(&resolved.pod_yaml)?;
+    let host_network = spec["hostNetwork"].as_bool().unwrap_or(false);
+    if let Some(network) = pod_network(&spec).filter(|_| !host_network) {
+        ensure_network(&network).await?;
+    }
+
+    let mut container_ids = Vec::new();
+
+    if let Some(containers) = spec["containers"].as_array() {
+        for container in containers.iter() {
+            // Create only; the container is left in the "created" state until started
+            let container_id = create_container(
+                &pod_name,
+                container,
+                &spec,
+                host_network,
+                &annotations,
+                resolved.registry_auth.as_deref(),
+            )
+            .await?;
+
+            println!("Container {} created successfully", container_id);
+            container_ids.push(container_id);
+        }
+    }
+
+    Ok(container_ids)
+}
+
 pub async fn start(pod_yaml: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    let (pod_name, spec, annotations) = parse_pod(pod_yaml)?;
+    let resolved = secret::resolve(pod_yaml).await?;
+    let (pod_name, spec, annotations) = parse_pod(&resolved.pod_yaml)?;
     let host_network = spec["hostNetwork"].as_bool().unwrap_or(false);
+    if let Some(network) = pod_network(&spec).filter(|_| !host_network) {
+        ensure_network(&network).await?;
+    }
 
     let mut container_ids = Vec::new();
 
     if let Some(containers) = spec["containers"].as_array() {
         for container in containers.iter() {
-            let container_id =
-                create_container(&pod_name, container, &spec, host_network, &annotations).await?;
+            let container_id = create_container(
+                &pod_name,
+                container,
+                &spec,
+                host_network,
+                &annotations,
+                resolved.registry_auth.as_deref(),
+            )
+            .await?;
 
             // Start the container
             println!("Starting container: {}", container_id);
@@ -960,6 +1013,40 @@ pub async fn restart(pod_yaml: &str) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+pub async fn pause(pod_yaml: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let (pod_name, spec, _annotations) = parse_pod(pod_yaml)?;
+    let container_names = get_container_names(&pod_name, &spec)?;
+
+    for full_container_name in container_names {
+        println!("Pausing container: {}", full_container_name);
+        let pause_path = format!(
+            "{}/containers/{}/pause",
+            PODMAN_API_VERSION, full_container_name
+        );
+        post(&pause_path, Body::empty()).await?;
+        println!("Container {} paused successfully", full_container_name);
+    }
+
+    Ok(())
+}
+
+pub async fn unpause(pod_yaml: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let (pod_name, spec, _annotations) = parse_pod(pod_yaml)?;
+    let container_names = get_container_names(&pod_name, &spec)?;
+
+    for full_container_name in container_names {
+        println!("Unpausing container: {}", full_container_name);
+        let unpause_path = format!(
+            "{}/containers/{}/unpause",
+            PODMAN_API_VERSION, full_container_name
+        );
+        post(&unpause_path, Body::empty()).await?;
+        println!("Container {} unpaused successfully", full_container_name);
+    }
+
+    Ok(())
+}
+
 /// Check if an image exists locally
 pub async fn image_exists(image_name: &str) -> Result<bool, Box<dyn std::error::Error>> {
     let path = "/v4.0.0/libpod/images/json";
@@ -978,10 +1065,13 @@ pub async fn image_exists(image_name: &str) -> Result<bool, Box<dyn std::error::
     Ok(false)
 }
 
-/// Pull an image from a registry
-pub async fn pull_image(image_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+/// Pull an image from a registry, with the credentials of a pull secret if given
+pub async fn pull_image(
+    image_name: &str,
+    registry_auth: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
     let path = format!("/v4.0.0/libpod/images/pull?reference={}", image_name);
-    post(&path, Body::empty()).await?;
+    post_with_auth(&path, Body::empty(), registry_auth).await?;
     Ok(())
 }
 
@@ -1001,6 +1091,23 @@ mod tests {
         assert_eq!(parse_memory("invalid"), None);
     }
 
+    #[test]
+    fn test_build_host_config_network_mode() {
+        let spec = json!({
+            "containers": [],
+            "network": {"name": "vd-net", "spec": {"driver": "bridge"}}
+        });
+        let container = json!({"name": "app", "image": "app:1.0"});
+        let host_config = build_host_config(&container, &spec, false);
+        assert_eq!(host_config["NetworkMode"], "vd-net");
+
+        let host_config = build_host_config(&container, &spec, true);
+        assert_eq!(host_config["NetworkMode"], "host");
+
+        let host_config = build_host_config(&container, &json!({"containers": []}), false);
+        assert!(host_config.get("NetworkMode").is_none());
+    }
+
     #[test]
     fn test_parse_gpu_count() {
         // String values
@@ -1179,4 +1286,63 @@ mod tests {
             assert!(has_nvidia_mount, "Should have NVIDIA library mount");
         }
     }
+
+    #[test]
+    fn test_sealed_secret_reaches_container_create_request() {
+        use common::spec::artifact::secret::{encrypt_value, ENCRYPTION_AES_256_GCM};
+        use common::spec::artifact::Secret;
+        use std::collections::HashMap;
+
+        let key = [7u8; 32];
+        let pod_yaml = r#"
+apiVersion: v1
+kind: Pod
+metadata:
+  name: app
+spec:
+  containers:
+    - name: app
+      image: app:1.0
+      envFrom:
+        - secretRef:
+            name: app-env
+"#;
+        let mut secret: Secret = serde_yaml::from_str(
+            r#"
+apiVersion: v1
+kind: Secret
+metadata:
+  name: app-env
+spec: {}
+"#,
+        )
+        .unwrap();
+        let data = HashMap::from([(
+            "PASSWORD".to_string(),
+            encrypt_value(&key, b"s3cr3t").unwrap(),
+        )]);
+        secret
+            .get_spec_mut()
+            .set_encoded_data(data, Some(ENCRYPTION_AES_256_GCM.to_string()));
+
+        let mut pod: common::spec::k8s::Pod = serde_yaml::from_str(pod_yaml).unwrap();
+        let registry_auth = secret::apply(&mut pod, &[secret], Some(&key)).unwrap();
+        assert!(registry_auth.is_none());
+
+        let (pod_name, spec, annotations) =
+            parse_pod(&serde_yaml::to_string(&pod).unwrap()).unwrap();
+        let container = &spec["containers"][0];
+        assert!(container["envFrom"].is_null());
+
+        let create_body = build_container_spec(
+            &format!("{}_app", pod_name),
+            "app:1.0",
+            container,
+            &spec,
+            false,
+            &annotations,
+        );
+        let env = create_body["Env"].as_array().unwrap();
+        assert!(env.contains(&json!("PASSWORD=s3cr3t")));
+    }
 }
@@ -0,0 +1,213 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Image pre-pull through the libpod API
+//!
+//! Images are pulled before a workload update needs them, so the update does
+//! not stall on the download. An image that is present locally with the
+//! expected digest is not pulled again.
+
+use super::{get, post, PODMAN_API_VERSION};
+use common::nodeagent::fromactioncontroller::{ImageToPull, PrePullState};
+use hyper::Body;
+
+/// Result of pulling one image
+#[derive(Debug, PartialEq)]
+pub struct PullOutcome {
+    pub state: PrePullState,
+    /// Digest of the local image, empty if it could not be pulled
+    pub digest: String,
+    pub message: String,
+}
+
+impl PullOutcome {
+    fn new(state: PrePullState, digest: String, message: String) -> Self {
+        Self {
+            state,
+            digest,
+            message,
+        }
+    }
+}
+
+/// Digest the image has to match, if any
+///
+/// The digest of the request wins over one in the image reference
+/// (`name@sha256:...`).
+fn expected_digest(image: &ImageToPull) -> Option<&str> {
+    if !image.digest.is_empty() {
+        return Some(image.digest.as_str());
+    }
+    image.image.split_once('@').map(|(_, digest)| digest)
+}
+
+/// Manifest digest of an inspected image
+fn image_digest(inspect: &serde_json::Value) -> String {
+    inspect["Digest"].as_str().unwrap_or_default().to_string()
+}
+
+/// Whether any digest of an inspected image is `expected`
+///
+/// Besides the manifest digest, the digests of every repository the image
+/// was pulled from are checked.
+fn digest_matches(inspect: &serde_json::Value, expected: &str) -> bool {
+    if inspect["Digest"].as_str() == Some(expected) {
+        return true;
+    }
+    inspect["RepoDigests"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|d| d.as_str())
+        .any(|d| d.rsplit_once('@').map(|(_, digest)| digest) == Some(expected))
+}
+
+/// Check the output of the pull endpoint for errors
+///
+/// The endpoint streams one json object per line and reports a failed pull
+/// in an `error` field, while request errors come as a single object with a
+/// `cause`.
+fn check_pull_output(body: &[u8]) -> Result<(), String> {
+    for line in body.split(|b| *b == b'\n') {
+        let Ok(value) = serde_json::from_slice::<serde_json::Value>(line) else {
+            continue;
+        };
+        if let Some(error) = value["error"].as_str().filter(|e| !e.is_empty()) {
+            return Err(error.to_string());
+        }
+        if value.get("cause").is_some() {
+            let message = value["message"].as_str().unwrap_or("pull request failed");
+            return Err(message.to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Inspect a local image, `None` if it does not exist
+async fn inspect_image(image: &str) -> Result<Option<serde_json::Value>, String> {
+    let path = format!("{}/libpod/images/{}/json", PODMAN_API_VERSION, image);
+    let body = get(&path).await.map_err(|e| e.to_string())?;
+    let value: serde_json::Value = serde_json::from_slice(&body).map_err(|e| e.to_string())?;
+    Ok(value.get("Id").is_some().then_some(value))
+}
+
+/// Pull an image unless it is already present with the expected digest
+///
+/// ### Parameters
+/// * `image: &ImageToPull` - image reference and optional expected digest
+/// ### Returns
+/// * `PullOutcome` - `Cached`, `Pulled`, `Failed` or `DigestMismatch`
+pub async fn prepull_image(image: &ImageToPull) -> PullOutcome {
+    if crate::runtime::mock_enabled() {
+        return PullOutcome::new(
+            PrePullState::Cached,
+            String::new(),
+            "images are not pulled with the mock runtime".to_string(),
+        );
+    }
+    let expected = expected_digest(image);
+    let matches = |inspect: &serde_json::Value| expected.is_none_or(|d| digest_matches(inspect, d));
+
+    match inspect_image(&image.image).await {
+        Ok(Some(inspect)) if matches(&inspect) => {
+            return PullOutcome::new(
+                PrePullState::Cached,
+                image_digest(&inspect),
+                "image is present locally".to_string(),
+            );
+        }
+        Ok(_) => {}
+        Err(e) => println!("[NodeAgent] Inspecting image {} failed: {}", image.image, e),
+    }
+
+    let path = format!(
+        "{}/libpod/images/pull?reference={}",
+        PODMAN_API_VERSION, image.image
+    );
+    let result = match post(&path, Body::empty()).await {
+        Ok(body) => check_pull_output(&body),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = result {
+        return PullOutcome::new(PrePullState::Failed, String::new(), e);
+    }
+
+    match inspect_image(&image.image).await {
+        Ok(Some(inspect)) if matches(&inspect) => PullOutcome::new(
+            PrePullState::Pulled,
+            image_digest(&inspect),
+            "image pulled".to_string(),
+        ),
+        Ok(Some(inspect)) => PullOutcome::new(
+            PrePullState::DigestMismatch,
+            image_digest(&inspect),
+            format!("expected digest {}", expected.unwrap_or_default()),
+        ),
+        Ok(None) => PullOutcome::new(
+            PrePullState::Failed,
+            String::new(),
+            "image not found after pull".to_string(),
+        ),
+        Err(e) => PullOutcome::new(PrePullState::Failed, String::new(), e),
+    }
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn image(image: &str, digest: &str) -> ImageToPull {
+        ImageToPull {
+            image: image.to_string(),
+            digest: digest.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_expected_digest() {
+        assert_eq!(
+            expected_digest(&image("quay.io/podman/hello:latest", "")),
+            None
+        );
+        assert_eq!(
+            expected_digest(&image("quay.io/podman/hello@sha256:abc", "")),
+            Some("sha256:abc")
+        );
+        assert_eq!(
+            expected_digest(&image("quay.io/podman/hello@sha256:abc", "sha256:def")),
+            Some("sha256:def")
+        );
+    }
+
+    #[test]
+    fn test_digest_matches() {
+        let inspect = json!({
+            "Id": "1234",
+            "Digest": "sha256:abc",
+            "RepoDigests": ["quay.io/podman/hello@sha256:def"]
+        });
+        assert!(digest_matches(&inspect, "sha256:abc"));
+        assert!(digest_matches(&inspect, "sha256:def"));
+        assert!(!digest_matches(&inspect, "sha256:000"));
+        assert_eq!(image_digest(&inspect), "sha256:abc");
+    }
+
+    #[test]
+    fn test_check_pull_output() {
+        let ok = b"{\"stream\":\"Trying to pull...\"}\n{\"images\":[\"1234\"],\"id\":\"1234\"}\n";
+        assert!(check_pull_output(ok).is_ok());
+
+        let failed = b"{\"stream\":\"Trying to pull...\"}\n{\"error\":\"manifest unknown\"}\n";
+        assert_eq!(check_pull_output(failed).unwrap_err(), "manifest unknown");
+
+        let rejected =
+            br#"{"cause":"bad reference","message":"invalid reference format","response":400}"#;
+        assert_eq!(
+            check_pull_output(rejected).unwrap_err(),
+            "invalid reference format"
+        );
+    }
+}
@@ -4,11 +4,19 @@
 */
 
 pub mod container;
-
+pub mod image;
+pub mod network;
+pub mod secret;
+
+use super::{
+    events_query, follow_logs_from, follow_query, get_from, get_json, get_logs, logs_query,
+    parse_events, ping_socket, ContainerRuntime, UNIX_CLIENT,
+};
+use crate::resource::container::Result as RuntimeResult;
+use crate::resource::{Container, ContainerInspect, ContainerStats, RuntimeEvent};
 use common::nodeagent::fromactioncontroller::WorkloadCommand;
-use hyper::{Body, Client, Method, Request, Uri};
-use hyperlocal::{UnixConnector, Uri as UnixUri};
-use once_cell::sync::Lazy;
+use hyper::{Body, Method, Request, Uri};
+use hyperlocal::Uri as UnixUri;
 
 // Modify this if you want to run without root authorization
 // or if you have a different socket path.
@@ -17,30 +25,31 @@ use once_cell::sync::Lazy;
 // Or if you run it as a user, you might use:
 // "/run/user/1000/podman/podman.sock"
 const PODMAN_SOCKET: &str = "/var/run/podman/podman.sock";
-
-// A single `hyper::Client` is cheap to clone and manages its own connection
-// pool internally, so it is created once and reused for every request
-// instead of being rebuilt on each call to `get`/`post`/`delete`.
-static PODMAN_CLIENT: Lazy<Client<UnixConnector, Body>> =
-    Lazy::new(|| Client::builder().build::<_, Body>(UnixConnector));
+const PODMAN_API_VERSION: &str = "/v4.0.0";
 
 pub async fn get(path: &str) -> Result<hyper::body::Bytes, hyper::Error> {
-    let uri: Uri = UnixUri::new(PODMAN_SOCKET, path).into();
-
-    let res = PODMAN_CLIENT.get(uri).await?;
-    hyper::body::to_bytes(res).await
+    get_from(PODMAN_SOCKET, path).await
 }
 
 pub async fn post(path: &str, body: Body) -> Result<hyper::body::Bytes, hyper::Error> {
+    post_with_auth(path, body, None).await
+}
+
+/// POST with an optional `X-Registry-Auth` header, for pulls from private registries
+pub async fn post_with_auth(
+    path: &str,
+    body: Body,
+    registry_auth: Option<&str>,
+) -> Result<hyper::body::Bytes, hyper::Error> {
     let uri: Uri = UnixUri::new(PODMAN_SOCKET, path).into();
 
-    let req = Request::builder()
-        .method(Method::POST)
-        .uri(uri)
-        .body(body)
-        .unwrap();
+    let mut builder = Request::builder().method(Method::POST).uri(uri);
+    if let Some(auth) = registry_auth {
+        builder = builder.header("X-Registry-Auth", auth);
+    }
+    let req = builder.body(body).unwrap();
 
-    let res = PODMAN_CLIENT.request(req).await?;
+    let res = UNIX_CLIENT.request(req).await?;
     hyper::body::to_bytes(res).await
 }
 
@@ -53,10 +62,88 @@ pub async fn delete(path: &str) -> Result<hyper::body::Bytes, hyper::Error> {
         .body(Body::empty())
         .unwrap();
 
-    let res = PODMAN_CLIENT.request(req).await?;
+    let res = UNIX_CLIENT.request(req).await?;
     hyper::body::to_bytes(res).await
 }
 
+/// Container inspection through the libpod API
+pub struct PodmanRuntime {
+    socket: String,
+}
+
+impl PodmanRuntime {
+    /// Use the given unix socket, or the default Podman socket if it is empty
+    pub fn new(socket: String) -> Self {
+        let socket = if socket.is_empty() {
+            PODMAN_SOCKET.to_string()
+        } else {
+            socket
+        };
+        Self { socket }
+    }
+}
+
+#[tonic::async_trait]
+impl ContainerRuntime for PodmanRuntime {
+    fn name(&self) -> &'static str {
+        "podman"
+    }
+
+    async fn list(&self) -> RuntimeResult<Vec<Container>> {
+        let path = format!("{}/libpod/containers/json?all=true", PODMAN_API_VERSION);
+        get_json(&self.socket, &path).await
+    }
+
+    async fn inspect(&self, id: &str) -> RuntimeResult<ContainerInspect> {
+        let path = format!(
+            "{}/libpod/containers/{}/json?all=true",
+            PODMAN_API_VERSION, id
+        );
+        get_json(&self.socket, &path).await
+    }
+
+    async fn stats(&self, id: &str) -> RuntimeResult<ContainerStats> {
+        let path = format!(
+            "{}/libpod/containers/{}/stats?stream=false",
+            PODMAN_API_VERSION, id
+        );
+        get_json(&self.socket, &path).await
+    }
+
+    async fn events(&self, since: i64) -> RuntimeResult<Vec<RuntimeEvent>> {
+        // The Docker-compatible endpoint reports events in the same format as Docker
+        let path = format!("{}/events?{}", PODMAN_API_VERSION, events_query(since));
+        let body = get_from(&self.socket, &path).await?;
+        parse_events(&body)
+    }
+
+    async fn logs(&self, id: &str, tail: u32) -> RuntimeResult<String> {
+        // The Docker-compatible endpoint multiplexes stdout and stderr like Docker
+        let path = format!(
+            "{}/containers/{}/logs?{}",
+            PODMAN_API_VERSION,
+            id,
+            logs_query(tail)
+        );
+        get_logs(&self.socket, &path).await
+    }
+
+    async fn follow_logs(&self, id: &str, tail: u32) -> RuntimeResult<Body> {
+        let path = format!(
+            "{}/containers/{}/logs?{}",
+            PODMAN_API_VERSION,
+            id,
+            follow_query(tail)
+        );
+        follow_logs_from(&self.socket, &path).await
+    }
+
+    async fn ping(&self) -> RuntimeResult<()> {
+        let path = format!("{}/libpod/_ping", PODMAN_API_VERSION);
+        ping_socket(&self.socket, &path).await
+    }
+}
+
 pub async fn handle_workload(
     command: i32,
     pod: &str,
@@ -66,11 +153,22 @@ pub async fn handle_workload(
         command
     );
     match command {
+        x if x == WorkloadCommand::Create as i32 => {
+            let container_ids = container::create(pod).await?;
+            return Ok(container_ids);
+        }
         x if x == WorkloadCommand::Start as i32 => {
             let container_ids = container::start(pod).await?;
             return Ok(container_ids);
         }
-        x if x == WorkloadCommand::Stop as i32 => {
+        x if x == WorkloadCommand::Pause as i32 => {
+            container::pause(pod).await?;
+        }
+        x if x == WorkloadCommand::Unpause as i32 => {
+            container::unpause(pod).await?;
+        }
+        // `stop` already force-removes the containers, so Remove shares its path
+        x if x == WorkloadCommand::Stop as i32 || x == WorkloadCommand::Remove as i32 => {
             container::stop(pod).await?;
         }
         x if x == WorkloadCommand::Restart as i32 => {
@@ -88,7 +186,7 @@ pub async fn handle_workload(
 //Unit tets cases
 #[cfg(test)]
 mod tests {
-    use super::get;
+    use super::{get, PodmanRuntime, PODMAN_SOCKET};
     use hyper::body::Bytes;
     use hyper::Error;
     use tokio;
@@ -100,4 +198,9 @@ mod tests {
         let bytes = result.unwrap();
         assert!(!bytes.is_empty());
     }
+
+    #[test]
+    fn test_podman_runtime_default_socket() {
+        assert_eq!(PodmanRuntime::new(String::new()).socket, PODMAN_SOCKET);
+    }
 }
@@ -0,0 +1,140 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+
+//! Podman networks of Network artifacts
+//!
+//! A pod attached to a bridge or macvlan Network artifact carries the network
+//! in its `network` field. The network is created through the Docker-compatible
+//! API before the containers of the pod, which then join it by `NetworkMode`.
+
+use super::{get, post};
+use common::spec::artifact::network::NetworkDriver;
+use common::spec::k8s::pod::PodNetwork;
+use hyper::Body;
+use serde_json::json;
+
+const PODMAN_API_VERSION: &str = "/v4.0.0";
+
+/// Network of a pod spec in JSON form, if it is attached to one
+pub fn pod_network(spec: &serde_json::Value) -> Option<PodNetwork> {
+    if spec["network"].is_null() {
+        return None;
+    }
+    serde_json::from_value(spec["network"].clone()).ok()
+}
+
+/// Body of `POST /networks/create`, as `podman network create` would send it
+pub fn create_body(network: &PodNetwork) -> serde_json::Value {
+    let spec = &network.spec;
+    let mut body = json!({
+        "Name": network.name,
+        "Driver": spec.get_driver().as_str(),
+        "Internal": spec.is_internal(),
+    });
+
+    if let Some(subnet) = spec.get_subnet() {
+        let mut config = json!({ "Subnet": subnet });
+        if let Some(gateway) = spec.get_gateway() {
+            config["Gateway"] = json!(gateway);
+        }
+        if let Some(ip_range) = spec.get_ip_range() {
+            config["IPRange"] = json!(ip_range);
+        }
+        body["IPAM"] = json!({ "Config": [config] });
+    }
+
+    if spec.get_driver() == NetworkDriver::Macvlan {
+        if let Some(parent) = spec.get_parent() {
+            body["Options"] = json!({ "parent": parent });
+        }
+    }
+
+    body
+}
+
+/// Check whether a network exists
+pub async fn network_exists(name: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    let path = format!("{}/libpod/networks/json", PODMAN_API_VERSION);
+
+    let result = get(&path).await?;
+    let networks: Vec<serde_json::Value> = serde_json::from_slice(&result)?;
+    Ok(networks
+        .iter()
+        .any(|network| network["name"].as_str() == Some(name)))
+}
+
+/// Create the network of a pod unless it already exists
+///
+/// An existing network is used as it is, its options are not compared.
+pub async fn ensure_network(network: &PodNetwork) -> Result<(), Box<dyn std::error::Error>> {
+    if network_exists(&network.name).await? {
+        return Ok(());
+    }
+
+    println!("Network {} not found, creating...", network.name);
+    let path = format!("{}/networks/create", PODMAN_API_VERSION);
+    let response = post(&path, Body::from(create_body(network).to_string())).await?;
+
+    let result: serde_json::Value = serde_json::from_slice(&response)?;
+    if result["Id"].as_str().is_none() {
+        return Err(format!(
+            "Failed to create network {}: {}",
+            network.name,
+            result["message"].as_str().unwrap_or("unknown error")
+        )
+        .into());
+    }
+    println!("Network {} created successfully", network.name);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network(spec: &str) -> PodNetwork {
+        PodNetwork {
+            name: "vd-net".to_string(),
+            spec: serde_yaml::from_str(spec).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_create_body_bridge() {
+        let body = create_body(&network(
+            "subnet: 10.89.0.0/24\ngateway: 10.89.0.1\nipRange: 10.89.0.128/25\ninternal: true",
+        ));
+        assert_eq!(body["Name"], "vd-net");
+        assert_eq!(body["Driver"], "bridge");
+        assert_eq!(body["Internal"], true);
+        assert_eq!(
+            body["IPAM"]["Config"][0],
+            json!({"Subnet": "10.89.0.0/24", "Gateway": "10.89.0.1", "IPRange": "10.89.0.128/25"})
+        );
+        assert!(body["Options"].is_null());
+    }
+
+    #[test]
+    fn test_create_body_macvlan() {
+        let body = create_body(&network("driver: macvlan\nparent: eth0"));
+        assert_eq!(body["Driver"], "macvlan");
+        assert_eq!(body["Options"]["parent"], "eth0");
+        assert!(body["IPAM"].is_null());
+    }
+
+    #[test]
+    fn test_pod_network() {
+        let spec = json!({
+            "containers": [],
+            "network": {"name": "vd-net", "spec": {"driver": "macvlan", "parent": "eth0"}}
+        });
+        let attached = pod_network(&spec).unwrap();
+        assert_eq!(attached.name, "vd-net");
+        assert_eq!(attached.spec.get_driver(), NetworkDriver::Macvlan);
+
+        assert!(pod_network(&json!({"containers": [], "network": null})).is_none());
+        assert!(pod_network(&json!({"containers": []})).is_none());
+    }
+}
@@ -0,0 +1,74 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+
+//! Secret artifacts referenced by a pod
+//!
+//! Pods stored in etcd only reference their secrets. The values are read and
+//! decrypted here, right before the containers are created, so plain values
+//! never leave the node. Sealed secrets need the same `PULLPIRI_SECRET_KEY`
+//! as ApiServer.
+
+use common::spec::artifact::secret::encryption_key;
+use common::spec::artifact::{Artifact, Secret};
+use common::spec::k8s::Pod;
+use common::spec::namespace;
+
+const KIND_SECRET: &str = "Secret";
+
+/// Pod with its secret references resolved
+pub struct ResolvedPod {
+    /// Pod YAML with the `envFrom` secrets replaced by `env` entries
+    pub pod_yaml: String,
+    /// `X-Registry-Auth` header built from `imagePullSecrets`
+    pub registry_auth: Option<String>,
+}
+
+/// Read the secrets referenced by a pod from etcd and apply their values
+pub async fn resolve(pod_yaml: &str) -> common::Result<ResolvedPod> {
+    let mut pod: Pod = serde_yaml::from_str(pod_yaml)?;
+    let names = pod.get_secret_names();
+    if names.is_empty() {
+        return Ok(ResolvedPod {
+            pod_yaml: pod_yaml.to_string(),
+            registry_auth: None,
+        });
+    }
+
+    let mut secrets = Vec::new();
+    for name in names {
+        let key = namespace::artifact_key(&pod.get_namespace(), KIND_SECRET, &name);
+        let secret_str = common::etcd::get(&key)
+            .await
+            .map_err(|e| format!("secret '{}' of pod '{}': {}", name, pod.get_name(), e))?;
+        secrets.push(serde_yaml::from_str::<Secret>(&secret_str)?);
+    }
+
+    let registry_auth = apply(&mut pod, &secrets, encryption_key()?.as_deref())?;
+    Ok(ResolvedPod {
+        pod_yaml: serde_yaml::to_string(&pod)?,
+        registry_auth,
+    })
+}
+
+/// Inject the decoded values of `secrets` into the pod
+///
+/// Returns the registry credentials of the first pull secret that has any.
+pub(super) fn apply(
+    pod: &mut Pod,
+    secrets: &[Secret],
+    key: Option<&[u8]>,
+) -> common::Result<Option<String>> {
+    let mut registry_auth = None;
+    for secret in secrets {
+        if pod.is_image_pull_secret(&secret.get_name()) {
+            if registry_auth.is_none() {
+                registry_auth = secret.registry_auth(key)?;
+            }
+        } else {
+            pod.inject_secret(&secret.get_name(), &secret.decode_data(key)?);
+        }
+    }
+    Ok(registry_auth)
+}
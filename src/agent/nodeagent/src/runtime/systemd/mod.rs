@@ -0,0 +1,317 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Workloads as local systemd units, for nodes without Bluechi
+//!
+//! With `workload_backend: systemd`, every pod of ActionController becomes a
+//! Quadlet `.kube` unit in `unit_directory` that plays the pod yaml stored in
+//! `yaml_storage`. After a daemon reload, the Quadlet generator turns it into
+//! `<pod>.service`, which is started, stopped and reloaded through the
+//! systemd D-Bus API like the Bluechi agent does.
+
+pub mod status;
+
+use crate::config::{Config, WorkloadBackend};
+use common::nodeagent::fromactioncontroller::{UnitCommand, WorkloadCommand};
+use common::spec::k8s::Pod;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tokio::sync::OnceCell;
+use zbus::zvariant::OwnedObjectPath;
+
+/// Job mode of unit operations, replacing conflicting queued jobs
+const JOB_MODE: &str = "replace";
+
+#[derive(Error, Debug)]
+pub enum UnitError {
+    #[error("systemd D-Bus error: {0}")]
+    DBus(#[from] zbus::Error),
+    #[error("unit file error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid pod yaml: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("unit {0} is not managed by NodeAgent")]
+    NotManaged(String),
+    #[error("command {0} is not supported by the systemd backend")]
+    Unsupported(i32),
+}
+
+pub type Result<T> = std::result::Result<T, UnitError>;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.systemd1.Manager",
+    default_service = "org.freedesktop.systemd1",
+    default_path = "/org/freedesktop/systemd1"
+)]
+trait Manager {
+    fn start_unit(&self, name: &str, mode: &str) -> zbus::Result<OwnedObjectPath>;
+    fn stop_unit(&self, name: &str, mode: &str) -> zbus::Result<OwnedObjectPath>;
+    fn restart_unit(&self, name: &str, mode: &str) -> zbus::Result<OwnedObjectPath>;
+    fn reload_or_restart_unit(&self, name: &str, mode: &str) -> zbus::Result<OwnedObjectPath>;
+    fn freeze_unit(&self, name: &str) -> zbus::Result<()>;
+    fn thaw_unit(&self, name: &str) -> zbus::Result<()>;
+    fn reload(&self) -> zbus::Result<()>;
+    fn load_unit(&self, name: &str) -> zbus::Result<OwnedObjectPath>;
+}
+
+/// Whether ActionController workloads run as systemd units on this node
+pub fn enabled() -> bool {
+    !super::mock_enabled() && Config::get().nodeagent.workload_backend == WorkloadBackend::Systemd
+}
+
+/// Connection to the system bus, opened once
+async fn connection() -> Result<&'static zbus::Connection> {
+    static CONNECTION: OnceCell<zbus::Connection> = OnceCell::const_new();
+    Ok(CONNECTION.get_or_try_init(zbus::Connection::system).await?)
+}
+
+/// Proxy of the systemd manager on the system bus
+async fn manager() -> Result<ManagerProxy<'static>> {
+    Ok(ManagerProxy::new(connection().await?).await?)
+}
+
+/// Name of the service Quadlet generates for a pod
+pub fn unit_name(pod_name: &str) -> String {
+    format!("{}.service", pod_name)
+}
+
+/// Content of the Quadlet `.kube` unit of a pod
+///
+/// The restart policy of the pod becomes the `Restart=` of the service, so
+/// systemd heals the workload instead of the NodeAgent reconciliation loop.
+fn kube_unit(pod: &Pod, yaml_path: &Path) -> String {
+    let restart = match pod.get_restart_policy() {
+        Some("Always") => "always",
+        Some("Never") => "no",
+        _ => "on-failure",
+    };
+    format!(
+        "[Unit]\nDescription=Pullpiri workload {}\n\n[Kube]\nYaml={}\n\n[Service]\nRestart={}\n\n[Install]\nWantedBy=default.target\n",
+        pod.get_name(),
+        yaml_path.display(),
+        restart
+    )
+}
+
+/// Directories of the unit files, from the NodeAgent config
+struct UnitFiles {
+    unit_directory: PathBuf,
+    yaml_storage: PathBuf,
+}
+
+impl UnitFiles {
+    fn from_config(config: &Config) -> Self {
+        Self {
+            unit_directory: PathBuf::from(&config.nodeagent.unit_directory),
+            yaml_storage: PathBuf::from(config.get_yaml_storage()),
+        }
+    }
+
+    fn kube_path(&self, pod_name: &str) -> PathBuf {
+        self.unit_directory.join(format!("{}.kube", pod_name))
+    }
+
+    fn yaml_path(&self, pod_name: &str) -> PathBuf {
+        self.yaml_storage.join(format!("{}.yaml", pod_name))
+    }
+
+    /// Write the pod yaml and its `.kube` unit
+    fn write(&self, pod: &Pod, pod_yaml: &str) -> Result<()> {
+        let name = pod.get_name();
+        std::fs::create_dir_all(&self.yaml_storage)?;
+        std::fs::create_dir_all(&self.unit_directory)?;
+
+        let yaml_path = self.yaml_path(&name);
+        std::fs::write(&yaml_path, pod_yaml)?;
+        std::fs::write(self.kube_path(&name), kube_unit(pod, &yaml_path))?;
+        Ok(())
+    }
+
+    /// Remove the files of a pod, missing files are ignored
+    fn remove(&self, pod_name: &str) -> Result<()> {
+        for path in [self.kube_path(pod_name), self.yaml_path(pod_name)] {
+            match std::fs::remove_file(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Names of the pods with a `.kube` file, sorted
+    fn pods(&self) -> Result<Vec<String>> {
+        let entries = match std::fs::read_dir(&self.unit_directory) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut pods: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let path = entry.path();
+                (path.extension()? == "kube")
+                    .then(|| path.file_stem()?.to_str().map(str::to_string))
+                    .flatten()
+            })
+            .collect();
+        pods.sort();
+        Ok(pods)
+    }
+
+    /// Whether a unit was generated from a `.kube` file of NodeAgent
+    fn manages(&self, unit: &str) -> bool {
+        unit.strip_suffix(".service")
+            .is_some_and(|pod_name| !pod_name.is_empty() && self.kube_path(pod_name).exists())
+    }
+}
+
+/// Run a workload command of ActionController as unit operation
+///
+/// ### Parameters
+/// * `command: i32` - `WorkloadCommand` of the request
+/// * `pod_yaml: &str` - yaml of the pod
+/// ### Returns
+/// * `Result<Vec<String>>` - name of the unit of the pod
+/// ### Description
+/// Create and Start write the unit files and reload systemd, Start also
+/// starts the unit. Pause and Unpause freeze and thaw the unit, Remove stops
+/// it and deletes its files.
+pub async fn handle_workload(command: i32, pod_yaml: &str) -> Result<Vec<String>> {
+    let pod: Pod = serde_yaml::from_str(pod_yaml)?;
+    let name = pod.get_name();
+    let unit = unit_name(&name);
+    let files = UnitFiles::from_config(&Config::get());
+
+    match WorkloadCommand::try_from(command) {
+        Ok(WorkloadCommand::Create) => {
+            files.write(&pod, pod_yaml)?;
+            manager().await?.reload().await?;
+        }
+        Ok(WorkloadCommand::Start) => {
+            files.write(&pod, pod_yaml)?;
+            let manager = manager().await?;
+            manager.reload().await?;
+            manager.start_unit(&unit, JOB_MODE).await?;
+        }
+        Ok(WorkloadCommand::Stop) => {
+            manager().await?.stop_unit(&unit, JOB_MODE).await?;
+        }
+        Ok(WorkloadCommand::Restart) => {
+            manager().await?.restart_unit(&unit, JOB_MODE).await?;
+        }
+        Ok(WorkloadCommand::Pause) => {
+            manager().await?.freeze_unit(&unit).await?;
+        }
+        Ok(WorkloadCommand::Unpause) => {
+            manager().await?.thaw_unit(&unit).await?;
+        }
+        Ok(WorkloadCommand::Remove) => {
+            let manager = manager().await?;
+            // The unit may already be stopped or never have been loaded
+            if let Err(e) = manager.stop_unit(&unit, JOB_MODE).await {
+                println!("[NodeAgent] Stopping {} before removal: {}", unit, e);
+            }
+            files.remove(&name)?;
+            manager.reload().await?;
+        }
+        Err(_) => return Err(UnitError::Unsupported(command)),
+    }
+    Ok(vec![unit])
+}
+
+/// Run a unit command of ActionController
+///
+/// ### Parameters
+/// * `command: i32` - `UnitCommand` of the request
+/// * `unit: &str` - name of the unit, ignored by `DaemonReload`
+/// ### Description
+/// Only the units generated by NodeAgent can be controlled. Reload falls
+/// back to a restart, as the services of `.kube` units cannot be reloaded.
+pub async fn handle_unit(command: i32, unit: &str) -> Result<()> {
+    let command = UnitCommand::try_from(command).map_err(|_| UnitError::Unsupported(command))?;
+    let manager = manager().await?;
+    if command == UnitCommand::DaemonReload {
+        manager.reload().await?;
+        return Ok(());
+    }
+
+    if !UnitFiles::from_config(&Config::get()).manages(unit) {
+        return Err(UnitError::NotManaged(unit.to_string()));
+    }
+    match command {
+        UnitCommand::Start => manager.start_unit(unit, JOB_MODE).await?,
+        UnitCommand::Stop => manager.stop_unit(unit, JOB_MODE).await?,
+        UnitCommand::Restart => manager.restart_unit(unit, JOB_MODE).await?,
+        UnitCommand::Reload => manager.reload_or_restart_unit(unit, JOB_MODE).await?,
+        UnitCommand::DaemonReload => unreachable!(),
+    };
+    Ok(())
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POD_YAML: &str = r#"
+apiVersion: v1
+kind: Pod
+metadata:
+  name: helloworld
+spec:
+  restartPolicy: Always
+  containers:
+    - name: helloworld
+      image: quay.io/podman/hello:latest
+"#;
+
+    fn temp_files(name: &str) -> UnitFiles {
+        let root =
+            std::env::temp_dir().join(format!("nodeagent-systemd-{}-{}", name, std::process::id()));
+        UnitFiles {
+            unit_directory: root.join("systemd"),
+            yaml_storage: root.join("yaml"),
+        }
+    }
+
+    #[test]
+    fn test_kube_unit() {
+        let pod: Pod = serde_yaml::from_str(POD_YAML).unwrap();
+        let unit = kube_unit(&pod, Path::new("/etc/pullpiri/yaml/helloworld.yaml"));
+
+        assert!(unit.contains("[Kube]\nYaml=/etc/pullpiri/yaml/helloworld.yaml\n"));
+        assert!(unit.contains("Restart=always\n"));
+        assert_eq!(unit_name(&pod.get_name()), "helloworld.service");
+
+        let never = POD_YAML.replace("Always", "Never");
+        let pod: Pod = serde_yaml::from_str(&never).unwrap();
+        assert!(kube_unit(&pod, Path::new("/tmp/x.yaml")).contains("Restart=no\n"));
+    }
+
+    #[test]
+    fn test_unit_files_write_and_remove() {
+        let files = temp_files("write");
+        let pod: Pod = serde_yaml::from_str(POD_YAML).unwrap();
+
+        assert!(!files.manages("helloworld.service"));
+        assert!(files.pods().unwrap().is_empty());
+        files.write(&pod, POD_YAML).unwrap();
+        assert_eq!(files.pods().unwrap(), vec!["helloworld"]);
+        assert!(files.manages("helloworld.service"));
+        assert!(!files.manages("helloworld.kube"));
+        assert!(!files.manages("sshd.service"));
+        assert_eq!(
+            std::fs::read_to_string(files.yaml_path("helloworld")).unwrap(),
+            POD_YAML
+        );
+
+        files.remove("helloworld").unwrap();
+        assert!(!files.manages("helloworld.service"));
+        // Removing again is not an error
+        files.remove("helloworld").unwrap();
+
+        let _ = std::fs::remove_dir_all(files.unit_directory.parent().unwrap());
+    }
+}
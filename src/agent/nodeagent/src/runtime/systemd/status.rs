@@ -0,0 +1,163 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! State of the workload units, read from their systemd properties
+//!
+//! Units run by the systemd backend or by the Bluechi agent only get start
+//! and stop requests. Their `ActiveState`, `SubState`, `FreezerState`,
+//! `NRestarts` and `Result` are polled here and reported to StateManager as
+//! one entry per unit in a container list, annotated with the model of the
+//! pod, so that the model state follows the unit and the properties show up
+//! in the metadata of the model.
+
+use super::{connection, manager, Result, UnitFiles};
+use crate::config::Config;
+use common::monitoringserver::ContainerInfo;
+use std::collections::HashMap;
+
+/// Config key of the unit entries, marking them apart from containers
+pub const UNIT_CONFIG_KEY: &str = "unit";
+
+#[zbus::proxy(
+    interface = "org.freedesktop.systemd1.Unit",
+    default_service = "org.freedesktop.systemd1"
+)]
+trait Unit {
+    #[zbus(property)]
+    fn active_state(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn sub_state(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn freezer_state(&self) -> zbus::Result<String>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.systemd1.Service",
+    default_service = "org.freedesktop.systemd1"
+)]
+trait Service {
+    #[zbus(property)]
+    fn n_restarts(&self) -> zbus::Result<u32>;
+    #[zbus(property)]
+    fn result(&self) -> zbus::Result<String>;
+}
+
+/// Properties of a workload unit
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UnitStatus {
+    pub unit: String,
+    pub active_state: String,
+    pub sub_state: String,
+    pub freezer_state: String,
+    pub restarts: u32,
+    pub result: String,
+}
+
+impl UnitStatus {
+    /// Container status the unit stands for
+    pub fn container_status(&self) -> &'static str {
+        if matches!(self.freezer_state.as_str(), "frozen" | "freezing") {
+            return "paused";
+        }
+        match self.active_state.as_str() {
+            "active" | "reloading" => "running",
+            "activating" => "created",
+            "inactive" | "deactivating" => "exited",
+            "failed" => "dead",
+            _ => "unknown",
+        }
+    }
+
+    /// Entry of the unit in the container list of the node
+    pub fn to_container_info(&self, model: &str) -> ContainerInfo {
+        ContainerInfo {
+            id: self.unit.clone(),
+            names: vec![self.unit.clone()],
+            state: HashMap::from([
+                ("Status".to_string(), self.container_status().to_string()),
+                ("ActiveState".to_string(), self.active_state.clone()),
+                ("SubState".to_string(), self.sub_state.clone()),
+                ("FreezerState".to_string(), self.freezer_state.clone()),
+                ("NRestarts".to_string(), self.restarts.to_string()),
+                ("Result".to_string(), self.result.clone()),
+            ]),
+            config: HashMap::from([(UNIT_CONFIG_KEY.to_string(), self.unit.clone())]),
+            annotation: HashMap::from([("model".to_string(), model.to_string())]),
+            ..Default::default()
+        }
+    }
+}
+
+/// Whether the workload units of this node are polled
+pub fn enabled() -> bool {
+    !crate::runtime::mock_enabled()
+        && (super::enabled() || Config::get().nodeagent.node_role == "bluechi")
+}
+
+/// Read the properties of a unit, loading it if needed
+pub async fn unit_status(unit: &str) -> Result<UnitStatus> {
+    let path = manager().await?.load_unit(unit).await?;
+    let connection = connection().await?;
+    let unit_proxy = UnitProxy::new(connection, path.clone()).await?;
+    let service_proxy = ServiceProxy::new(connection, path).await?;
+    Ok(UnitStatus {
+        unit: unit.to_string(),
+        active_state: unit_proxy.active_state().await?,
+        sub_state: unit_proxy.sub_state().await?,
+        // Older systemd versions have no freezer
+        freezer_state: unit_proxy.freezer_state().await.unwrap_or_default(),
+        restarts: service_proxy.n_restarts().await.unwrap_or_default(),
+        result: service_proxy.result().await.unwrap_or_default(),
+    })
+}
+
+/// Unit entries of all the pods with a `.kube` file on this node
+///
+/// Units whose properties cannot be read are left out.
+pub async fn unit_containers() -> Result<Vec<ContainerInfo>> {
+    let pods = UnitFiles::from_config(&Config::get()).pods()?;
+    let mut containers = Vec::with_capacity(pods.len());
+    for pod in pods {
+        match unit_status(&super::unit_name(&pod)).await {
+            Ok(status) => containers.push(status.to_container_info(&pod)),
+            Err(e) => eprintln!("[NodeAgent] Reading unit of {}: {}", pod, e),
+        }
+    }
+    Ok(containers)
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(active_state: &str, freezer_state: &str) -> UnitStatus {
+        UnitStatus {
+            unit: "helloworld.service".to_string(),
+            active_state: active_state.to_string(),
+            sub_state: "running".to_string(),
+            freezer_state: freezer_state.to_string(),
+            restarts: 2,
+            result: "success".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_unit_status_to_container_info() {
+        assert_eq!(status("active", "running").container_status(), "running");
+        assert_eq!(status("active", "frozen").container_status(), "paused");
+        assert_eq!(status("activating", "").container_status(), "created");
+        assert_eq!(status("inactive", "").container_status(), "exited");
+        assert_eq!(status("failed", "").container_status(), "dead");
+        assert_eq!(status("maintenance", "").container_status(), "unknown");
+
+        let info = status("failed", "running").to_container_info("helloworld");
+        assert_eq!(info.id, "helloworld.service");
+        assert_eq!(info.state["Status"], "dead");
+        assert_eq!(info.state["NRestarts"], "2");
+        assert_eq!(info.config[UNIT_CONFIG_KEY], "helloworld.service");
+        assert_eq!(info.annotation["model"], "helloworld");
+    }
+}
@@ -0,0 +1,161 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+//! Watchdog of the container runtime socket
+//!
+//! The socket of the container runtime is pinged every `CHECK_INTERVAL`.
+//! When it stops answering, the `RuntimeUnavailable` node condition is raised
+//! through the heartbeat, the `runtime` readiness check fails and the
+//! container reports pause instead of announcing every container as gone.
+//! The socket is then retried with exponential backoff, and everything is
+//! restored as soon as it answers again.
+
+use crate::runtime::container_runtime;
+use common::health;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::time::{sleep, timeout, Instant};
+
+/// Node condition raised while the runtime socket does not answer
+pub const CONDITION_RUNTIME_UNAVAILABLE: &str = "RuntimeUnavailable";
+/// Readiness check of the container runtime
+pub const CHECK_RUNTIME: &str = "runtime";
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+const PING_TIMEOUT: Duration = Duration::from_secs(3);
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+static AVAILABLE: AtomicBool = AtomicBool::new(true);
+
+/// `false` while the watchdog sees the runtime socket down
+pub fn runtime_available() -> bool {
+    AVAILABLE.load(Ordering::Relaxed)
+}
+
+/// Change of the runtime availability after a ping
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    None,
+    Lost,
+    Recovered(Duration),
+}
+
+/// Outage state of the runtime socket
+#[derive(Debug, Default)]
+pub struct Watchdog {
+    /// Failed pings in a row
+    failures: u32,
+    down_since: Option<Instant>,
+}
+
+impl Watchdog {
+    /// Record the result of a ping at `now`
+    pub fn record(&mut self, ok: bool, now: Instant) -> Change {
+        if ok {
+            self.failures = 0;
+            return match self.down_since.take() {
+                Some(since) => Change::Recovered(now.duration_since(since)),
+                None => Change::None,
+            };
+        }
+        self.failures += 1;
+        if self.down_since.is_none() {
+            self.down_since = Some(now);
+            return Change::Lost;
+        }
+        Change::None
+    }
+
+    /// Time until the next ping, shorter while retrying
+    pub fn next_delay(&self) -> Duration {
+        match self.failures {
+            0 => CHECK_INTERVAL,
+            failures => FIRST_RETRY_DELAY
+                .saturating_mul(2u32.saturating_pow(failures - 1))
+                .min(MAX_RETRY_DELAY),
+        }
+    }
+}
+
+/// Ping the runtime socket until the agent stops
+pub async fn run() {
+    let runtime = container_runtime();
+    let mut watchdog = Watchdog::default();
+    loop {
+        let result = match timeout(PING_TIMEOUT, runtime.ping()).await {
+            Ok(result) => result.map_err(|e| e.to_string()),
+            Err(_) => Err(format!("no answer within {}s", PING_TIMEOUT.as_secs())),
+        };
+
+        match watchdog.record(result.is_ok(), Instant::now()) {
+            Change::Lost => {
+                let reason = format!(
+                    "{} socket is not available: {}",
+                    runtime.name(),
+                    result
+                        .as_ref()
+                        .err()
+                        .map(String::as_str)
+                        .unwrap_or_default()
+                );
+                eprintln!("[Watchdog] {}", reason);
+                AVAILABLE.store(false, Ordering::Relaxed);
+                crate::heartbeat::set_condition(CONDITION_RUNTIME_UNAVAILABLE, Some(&reason));
+                health::set_not_ready(CHECK_RUNTIME, reason);
+            }
+            Change::Recovered(outage) => {
+                println!(
+                    "[Watchdog] {} socket is back after {}s",
+                    runtime.name(),
+                    outage.as_secs()
+                );
+                AVAILABLE.store(true, Ordering::Relaxed);
+                crate::heartbeat::set_condition(CONDITION_RUNTIME_UNAVAILABLE, None);
+                health::set_ready(CHECK_RUNTIME);
+            }
+            Change::None if result.is_ok() => health::set_ready(CHECK_RUNTIME),
+            Change::None => {}
+        }
+
+        let delay = watchdog.next_delay();
+        if result.is_err() {
+            println!(
+                "[Watchdog] Retrying the {} socket in {}s",
+                runtime.name(),
+                delay.as_secs()
+            );
+        }
+        sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watchdog_outage_and_recovery() {
+        let start = Instant::now();
+        let mut watchdog = Watchdog::default();
+        assert_eq!(watchdog.record(true, start), Change::None);
+        assert_eq!(watchdog.next_delay(), CHECK_INTERVAL);
+
+        assert_eq!(watchdog.record(false, start), Change::Lost);
+        assert_eq!(watchdog.next_delay(), Duration::from_secs(1));
+        assert_eq!(watchdog.record(false, start), Change::None);
+        assert_eq!(watchdog.next_delay(), Duration::from_secs(2));
+        for _ in 0..10 {
+            watchdog.record(false, start);
+        }
+        assert_eq!(watchdog.next_delay(), MAX_RETRY_DELAY);
+
+        let later = start + Duration::from_secs(42);
+        assert_eq!(
+            watchdog.record(true, later),
+            Change::Recovered(Duration::from_secs(42))
+        );
+        assert_eq!(watchdog.next_delay(), CHECK_INTERVAL);
+    }
+}
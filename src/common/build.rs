@@ -11,11 +11,16 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     tonic_build::configure()
         .type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]")
+        // Clusters are created over REST with only some of the fields set
+        .type_attribute(".apiserver.ClusterTopology", "#[serde(default)]")
         .protoc_arg("--experimental_allow_proto3_optional")
+        // Served by the reflection service of every gRPC server
+        .file_descriptor_set_path(out_dir.join("pullpiri_descriptor.bin"))
         .out_dir(out_dir)
         .compile_protos(
             &[
                 "proto/apiserver.proto",
+                "proto/eventbus.proto",
                 "proto/actioncontroller.proto",
                 "proto/filtergateway.proto",
                 "proto/monitoringserver.proto",
@@ -26,6 +31,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 "proto/external/pharos/pharos_service.proto",
                 "proto/external/timpani/schedinfo.proto",
                 "proto/rocksdbservice.proto", // Add RocksDB service proto
+                "proto/pullpiri/node/v1/node.proto",
+                "proto/pullpiri/node/v1alpha/node.proto",
             ],
             &["proto"],
         )?;
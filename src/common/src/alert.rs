@@ -0,0 +1,356 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Threshold alert rules
+//!
+//! SettingsService stores rules under [`ALERT_RULE_PREFIX`]. MonitoringServer
+//! evaluates them against every node report, stores firing alerts under
+//! [`ALERT_PREFIX`] and streams raised and resolved alerts to subscribers.
+//!
+//! ```json
+//! {
+//!   "id": "node-cpu-high",
+//!   "metric": "cpu_usage",
+//!   "operator": "gt",
+//!   "threshold": 90.0,
+//!   "severity": "critical",
+//!   "target": { "kind": "node", "name": "HPC" }
+//! }
+//! ```
+
+use crate::monitoringserver::{ContainerInfo, NodeInfo, UsageAggregate};
+use serde::{Deserialize, Serialize};
+
+/// etcd prefix of the alert rules, followed by the rule id
+pub const ALERT_RULE_PREFIX: &str = "/pullpiri/alerts/rules/";
+/// etcd prefix of the firing alerts, followed by `<rule id>/<target name>`
+pub const ALERT_PREFIX: &str = "/pullpiri/alerts/active/";
+
+/// Metrics of a node report
+pub const NODE_METRICS: &[&str] = &[
+    "cpu_usage",
+    "mem_usage",
+    "used_memory",
+    "rx_bytes",
+    "tx_bytes",
+    "read_bytes",
+    "write_bytes",
+];
+/// Metrics of a container report
+pub const CONTAINER_METRICS: &[&str] = &[
+    "cpu_total_usage",
+    "cpu_percent",
+    "memory_usage",
+    "memory_percent",
+    "rx_bytes_per_sec",
+    "tx_bytes_per_sec",
+];
+
+/// Condition on a metric that raises an alert
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub id: String,
+    /// One of [`NODE_METRICS`] or [`CONTAINER_METRICS`], by target kind
+    pub metric: String,
+    pub operator: AlertOperator,
+    pub threshold: f64,
+    #[serde(default)]
+    pub severity: AlertSeverity,
+    pub target: AlertTarget,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertOperator {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+}
+
+impl AlertOperator {
+    /// `true` if `value` violates `threshold`
+    pub fn apply(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            AlertOperator::Gt => value > threshold,
+            AlertOperator::Ge => value >= threshold,
+            AlertOperator::Lt => value < threshold,
+            AlertOperator::Le => value <= threshold,
+            AlertOperator::Eq => value == threshold,
+            AlertOperator::Ne => value != threshold,
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            AlertOperator::Gt => ">",
+            AlertOperator::Ge => ">=",
+            AlertOperator::Lt => "<",
+            AlertOperator::Le => "<=",
+            AlertOperator::Eq => "==",
+            AlertOperator::Ne => "!=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertSeverity {
+    Info,
+    #[default]
+    Warning,
+    Critical,
+}
+
+impl AlertSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertSeverity::Info => "info",
+            AlertSeverity::Warning => "warning",
+            AlertSeverity::Critical => "critical",
+        }
+    }
+
+    pub fn parse(severity: &str) -> Option<Self> {
+        match severity {
+            "info" => Some(AlertSeverity::Info),
+            "warning" => Some(AlertSeverity::Warning),
+            "critical" => Some(AlertSeverity::Critical),
+            _ => None,
+        }
+    }
+}
+
+/// Nodes or containers a rule applies to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertTarget {
+    pub kind: AlertTargetKind,
+    /// Node name, or container name or id. Empty for every node or container.
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertTargetKind {
+    Node,
+    Container,
+}
+
+impl AlertTargetKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertTargetKind::Node => "node",
+            AlertTargetKind::Container => "container",
+        }
+    }
+}
+
+impl AlertRule {
+    /// Check the rule before it is stored
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() || self.id.contains('/') {
+            return Err(format!("Invalid alert rule id '{}'", self.id));
+        }
+        if !self.threshold.is_finite() {
+            return Err("Alert threshold must be a finite number".to_string());
+        }
+        let metrics = match self.target.kind {
+            AlertTargetKind::Node => NODE_METRICS,
+            AlertTargetKind::Container => CONTAINER_METRICS,
+        };
+        if !metrics.contains(&self.metric.as_str()) {
+            return Err(format!(
+                "Unknown {} metric '{}', expected one of: {}",
+                self.target.kind.as_str(),
+                self.metric,
+                metrics.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// `true` if the rule is enabled for a target known by one of `names`
+    pub fn targets(&self, kind: AlertTargetKind, names: &[&str]) -> bool {
+        self.enabled
+            && self.target.kind == kind
+            && (self.target.name.is_empty() || names.contains(&self.target.name.as_str()))
+    }
+
+    /// `true` if `value` violates the threshold
+    pub fn is_violated(&self, value: f64) -> bool {
+        self.operator.apply(value, self.threshold)
+    }
+
+    /// Alert raised by `value` on `target_name`
+    pub fn alert(
+        &self,
+        target_name: &str,
+        value: f64,
+        fired_at: i64,
+    ) -> crate::monitoringserver::Alert {
+        crate::monitoringserver::Alert {
+            rule_id: self.id.clone(),
+            severity: self.severity.as_str().to_string(),
+            target_kind: self.target.kind.as_str().to_string(),
+            target_name: target_name.to_string(),
+            metric: self.metric.clone(),
+            value,
+            threshold: self.threshold,
+            message: format!(
+                "{} {} of {} '{}' is {} (threshold {} {})",
+                self.severity.as_str(),
+                self.metric,
+                self.target.kind.as_str(),
+                target_name,
+                value,
+                self.operator.symbol(),
+                self.threshold
+            ),
+            fired_at,
+            resolved: false,
+        }
+    }
+}
+
+/// etcd key of a firing alert
+pub fn alert_key(rule_id: &str, target_name: &str) -> String {
+    format!("{}{}/{}", ALERT_PREFIX, rule_id, target_name)
+}
+
+/// Value of a node metric
+pub fn node_metric(node: &NodeInfo, metric: &str) -> Option<f64> {
+    match metric {
+        "cpu_usage" => Some(node.cpu_usage),
+        "mem_usage" => Some(node.mem_usage),
+        "used_memory" => Some(node.used_memory as f64),
+        "rx_bytes" => Some(node.rx_bytes as f64),
+        "tx_bytes" => Some(node.tx_bytes as f64),
+        "read_bytes" => Some(node.read_bytes as f64),
+        "write_bytes" => Some(node.write_bytes as f64),
+        _ => None,
+    }
+}
+
+/// Value of a container metric, `None` if the container has no stats
+///
+/// Averages of the sampled usage are preferred over the raw stats, which
+/// only older NodeAgents report.
+pub fn container_metric(container: &ContainerInfo, metric: &str) -> Option<f64> {
+    let stat = |key: &str| container.stats.get(key)?.parse::<f64>().ok();
+    let usage = container.usage.as_ref();
+    let average = |aggregate: Option<&UsageAggregate>| aggregate.map(|a| a.avg);
+    match metric {
+        "cpu_total_usage" => stat("CpuTotalUsage"),
+        "cpu_percent" => average(usage?.cpu_percent.as_ref()),
+        "memory_usage" => {
+            average(usage.and_then(|u| u.memory_bytes.as_ref())).or_else(|| stat("MemoryUsage"))
+        }
+        "memory_percent" => {
+            let limit = usage
+                .map(|u| u.memory_limit_bytes as f64)
+                .or_else(|| stat("MemoryLimit"))
+                .filter(|limit| *limit > 0.0)?;
+            Some(container_metric(container, "memory_usage")? / limit * 100.0)
+        }
+        "rx_bytes_per_sec" => average(usage?.rx_bytes_per_sec.as_ref()),
+        "tx_bytes_per_sec" => average(usage?.tx_bytes_per_sec.as_ref()),
+        _ => None,
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitoringserver::ContainerUsage;
+
+    fn rule(json: &str) -> AlertRule {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_rule_validation_and_evaluation() {
+        let cpu = rule(
+            r#"{"id": "cpu", "metric": "cpu_usage", "operator": "gt", "threshold": 90,
+                "target": {"kind": "node"}}"#,
+        );
+        assert!(cpu.validate().is_ok());
+        assert_eq!(cpu.severity, AlertSeverity::Warning);
+        assert!(cpu.targets(AlertTargetKind::Node, &["HPC"]));
+        assert!(!cpu.targets(AlertTargetKind::Container, &["HPC"]));
+        assert!(cpu.is_violated(95.0));
+        assert!(!cpu.is_violated(90.0));
+
+        let alert = cpu.alert("HPC", 95.0, 10);
+        assert_eq!(alert.target_kind, "node");
+        assert_eq!(alert.severity, "warning");
+        assert!(alert.message.contains("> 90"));
+
+        let wrong_metric = rule(
+            r#"{"id": "mem", "metric": "cpu_usage", "operator": "ge", "threshold": 1,
+                "target": {"kind": "container", "name": "web"}}"#,
+        );
+        assert!(wrong_metric.validate().is_err());
+        assert!(AlertSeverity::Critical > AlertSeverity::Warning);
+        assert_eq!(AlertSeverity::parse("info"), Some(AlertSeverity::Info));
+    }
+
+    #[test]
+    fn test_metric_values() {
+        let node = NodeInfo {
+            cpu_usage: 42.5,
+            used_memory: 1024,
+            ..Default::default()
+        };
+        assert_eq!(node_metric(&node, "cpu_usage"), Some(42.5));
+        assert_eq!(node_metric(&node, "used_memory"), Some(1024.0));
+        assert_eq!(node_metric(&node, "gpu"), None);
+
+        let mut container = ContainerInfo::default();
+        assert_eq!(container_metric(&container, "memory_percent"), None);
+        container
+            .stats
+            .insert("MemoryUsage".to_string(), "256".to_string());
+        container
+            .stats
+            .insert("MemoryLimit".to_string(), "1024".to_string());
+        assert_eq!(container_metric(&container, "memory_percent"), Some(25.0));
+        assert_eq!(container_metric(&container, "memory_usage"), Some(256.0));
+        assert_eq!(container_metric(&container, "cpu_percent"), None);
+
+        // Sampled usage wins over the raw stats
+        let aggregate = |avg| {
+            Some(UsageAggregate {
+                min: 0.0,
+                avg,
+                max: avg * 2.0,
+            })
+        };
+        container.usage = Some(ContainerUsage {
+            cpu_percent: aggregate(12.5),
+            memory_bytes: aggregate(512.0),
+            memory_limit_bytes: 2048,
+            rx_bytes_per_sec: aggregate(100.0),
+            ..Default::default()
+        });
+        assert_eq!(container_metric(&container, "cpu_percent"), Some(12.5));
+        assert_eq!(container_metric(&container, "memory_usage"), Some(512.0));
+        assert_eq!(container_metric(&container, "memory_percent"), Some(25.0));
+        assert_eq!(
+            container_metric(&container, "rx_bytes_per_sec"),
+            Some(100.0)
+        );
+        assert_eq!(container_metric(&container, "tx_bytes_per_sec"), None);
+    }
+}
@@ -0,0 +1,357 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! CPU and memory reserved on the nodes by running models
+//!
+//! ActionController records the resources requested by the pod of a model
+//! under [`ALLOCATION_PREFIX`] when it starts the model and removes them when
+//! the model is terminated. A model is admitted to a node only while the
+//! allocations of the node stay within its allocatable resources, i.e. the
+//! capacity in the node registry (`cluster/nodes/<hostname>`) minus what the
+//! node keeps for itself.
+//!
+//! The reservation of a node is part of its registry metadata, as quantities
+//! like the requests of a pod:
+//!
+//! ```json
+//! { "reserved-cpu": "500m", "reserved-memory": "512Mi" }
+//! ```
+
+use crate::apiserver::NodeInfo;
+use crate::etcd::StoreError;
+use crate::spec::k8s::pod::{parse_cpu_millis, parse_memory_mb, ResourceRequest};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// etcd prefix of the allocations, followed by the model name
+pub const ALLOCATION_PREFIX: &str = "Allocation/";
+/// Node metadata key of the CPU kept for the system
+pub const NODE_RESERVED_CPU_KEY: &str = "reserved-cpu";
+/// Node metadata key of the memory kept for the system
+pub const NODE_RESERVED_MEMORY_KEY: &str = "reserved-memory";
+
+const CLUSTER_NODES_PREFIX: &str = "cluster/nodes/";
+
+/// Resources requested by a model on its node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Allocation {
+    pub model: String,
+    pub node: String,
+    /// Thousandths of a CPU core
+    pub cpu_millis: u64,
+    pub memory_mb: u64,
+}
+
+impl Allocation {
+    pub fn new(model: &str, node: &str, request: &ResourceRequest) -> Self {
+        Allocation {
+            model: model.to_string(),
+            node: node.to_string(),
+            cpu_millis: request.cpu_millis,
+            memory_mb: request.memory_mb,
+        }
+    }
+}
+
+/// Allocatable and allocated resources of a node
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeAllocation {
+    pub node: String,
+    /// `None` if the node has no registered CPU capacity, which is not limited
+    pub allocatable_cpu_millis: Option<u64>,
+    /// `None` if the node has no registered memory capacity, which is not limited
+    pub allocatable_memory_mb: Option<u64>,
+    pub allocated_cpu_millis: u64,
+    pub allocated_memory_mb: u64,
+    pub models: Vec<Allocation>,
+}
+
+impl NodeAllocation {
+    /// Allocatable resources of a registered node, with nothing allocated
+    pub fn from_node(node_info: &NodeInfo) -> Self {
+        let resources = node_info.resources.clone().unwrap_or_default();
+        let reserved = reserved(&node_info.metadata);
+        let allocatable = |capacity: u64, reserved: u64| {
+            (capacity > 0).then(|| capacity.saturating_sub(reserved))
+        };
+        NodeAllocation {
+            node: node_info.hostname.clone(),
+            allocatable_cpu_millis: allocatable(
+                resources.cpu_cores.max(0) as u64 * 1000,
+                reserved.cpu_millis,
+            ),
+            allocatable_memory_mb: allocatable(
+                resources.memory_mb.max(0) as u64,
+                reserved.memory_mb,
+            ),
+            ..Default::default()
+        }
+    }
+
+    fn add(&mut self, allocation: Allocation) {
+        self.allocated_cpu_millis += allocation.cpu_millis;
+        self.allocated_memory_mb += allocation.memory_mb;
+        self.models.push(allocation);
+    }
+
+    fn remove(&mut self, model: &str) {
+        let (removed, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.models)
+            .into_iter()
+            .partition(|a| a.model == model);
+        for allocation in removed {
+            self.allocated_cpu_millis -= allocation.cpu_millis;
+            self.allocated_memory_mb -= allocation.memory_mb;
+        }
+        self.models = kept;
+    }
+
+    /// Describe the resources allocated beyond the allocatable ones
+    fn overcommit(&self) -> Option<String> {
+        let mut excess = Vec::new();
+        if let Some(cpu) = self.allocatable_cpu_millis {
+            if self.allocated_cpu_millis > cpu {
+                excess.push(format!("{}m of {}m CPU", self.allocated_cpu_millis, cpu));
+            }
+        }
+        if let Some(memory) = self.allocatable_memory_mb {
+            if self.allocated_memory_mb > memory {
+                excess.push(format!(
+                    "{}MiB of {}MiB memory",
+                    self.allocated_memory_mb, memory
+                ));
+            }
+        }
+        (!excess.is_empty()).then(|| {
+            format!(
+                "node '{}' would allocate {}",
+                self.node,
+                excess.join(" and ")
+            )
+        })
+    }
+}
+
+/// Resources a node keeps for itself, from its metadata
+///
+/// Missing or invalid quantities count as zero.
+pub fn reserved(metadata: &std::collections::HashMap<String, String>) -> ResourceRequest {
+    ResourceRequest {
+        cpu_millis: metadata
+            .get(NODE_RESERVED_CPU_KEY)
+            .and_then(|q| parse_cpu_millis(q))
+            .unwrap_or(0),
+        memory_mb: metadata
+            .get(NODE_RESERVED_MEMORY_KEY)
+            .and_then(|q| parse_memory_mb(q))
+            .unwrap_or(0),
+    }
+}
+
+/// Allocation of every node, sorted by node name
+///
+/// Allocations on nodes missing from the registry get a node entry without
+/// allocatable resources.
+pub fn summarize(nodes: &[NodeInfo], allocations: Vec<Allocation>) -> Vec<NodeAllocation> {
+    let mut summary: BTreeMap<String, NodeAllocation> = nodes
+        .iter()
+        .map(|node_info| {
+            (
+                node_info.hostname.clone(),
+                NodeAllocation::from_node(node_info),
+            )
+        })
+        .collect();
+    for allocation in allocations {
+        summary
+            .entry(allocation.node.clone())
+            .or_insert_with(|| NodeAllocation {
+                node: allocation.node.clone(),
+                ..Default::default()
+            })
+            .add(allocation);
+    }
+    summary.into_values().collect()
+}
+
+/// Check that the nodes can take the requested allocations
+///
+/// A requested model replaces its current allocation, so relaunching or
+/// updating a model only counts the difference. Only nodes that receive a
+/// request are checked.
+///
+/// # Errors
+///
+/// Names every node whose allocations would exceed its allocatable resources.
+pub fn admit(nodes: Vec<NodeAllocation>, requests: &[Allocation]) -> Result<(), String> {
+    let mut nodes: BTreeMap<String, NodeAllocation> =
+        nodes.into_iter().map(|n| (n.node.clone(), n)).collect();
+    for request in requests {
+        for node in nodes.values_mut() {
+            node.remove(&request.model);
+        }
+    }
+    for request in requests {
+        nodes
+            .entry(request.node.clone())
+            .or_insert_with(|| NodeAllocation {
+                node: request.node.clone(),
+                ..Default::default()
+            })
+            .add(request.clone());
+    }
+
+    let errors: Vec<String> = nodes
+        .values()
+        .filter(|node| requests.iter().any(|r| r.node == node.node))
+        .filter_map(NodeAllocation::overcommit)
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Not enough resources: {}", errors.join("; ")))
+    }
+}
+
+/// Read all recorded allocations
+pub async fn load() -> Result<Vec<Allocation>, StoreError> {
+    let mut allocations = Vec::new();
+    for (key, value) in crate::etcd::get_all_with_prefix(ALLOCATION_PREFIX).await? {
+        match serde_json::from_str(&value) {
+            Ok(allocation) => allocations.push(allocation),
+            Err(e) => crate::logd!(4, "Warning: Invalid allocation '{}': {}", key, e),
+        }
+    }
+    Ok(allocations)
+}
+
+/// Record the resources of a model on its node, replacing earlier ones
+pub async fn record(allocation: &Allocation) -> Result<(), StoreError> {
+    let value = serde_json::to_string(allocation).map_err(|e| StoreError::Failed(e.to_string()))?;
+    crate::etcd::put(
+        &format!("{}{}", ALLOCATION_PREFIX, allocation.model),
+        &value,
+    )
+    .await
+}
+
+/// Forget the resources of a model
+pub async fn release(model: &str) -> Result<(), StoreError> {
+    crate::etcd::delete(&format!("{}{}", ALLOCATION_PREFIX, model)).await
+}
+
+/// Allocation of every registered node and every node with allocations
+pub async fn node_allocations() -> Result<Vec<NodeAllocation>, StoreError> {
+    let mut nodes = Vec::new();
+    for (key, value) in crate::etcd::get_all_with_prefix(CLUSTER_NODES_PREFIX).await? {
+        match serde_json::from_str::<NodeInfo>(&value) {
+            Ok(node_info) => nodes.push(node_info),
+            Err(e) => crate::logd!(4, "Warning: Invalid node entry '{}': {}", key, e),
+        }
+    }
+    Ok(summarize(&nodes, load().await?))
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodeagent::fromapiserver::ResourceInfo;
+    use std::collections::HashMap;
+
+    fn node_info(hostname: &str, cpu_cores: i32, memory_mb: i64) -> NodeInfo {
+        NodeInfo {
+            hostname: hostname.to_string(),
+            resources: Some(ResourceInfo {
+                cpu_cores,
+                memory_mb,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn allocation(model: &str, node: &str, cpu_millis: u64, memory_mb: u64) -> Allocation {
+        Allocation {
+            model: model.to_string(),
+            node: node.to_string(),
+            cpu_millis,
+            memory_mb,
+        }
+    }
+
+    #[test]
+    fn test_allocatable_subtracts_reservation() {
+        let mut hpc = node_info("hpc", 4, 4096);
+        hpc.metadata = HashMap::from([
+            (NODE_RESERVED_CPU_KEY.to_string(), "500m".to_string()),
+            (NODE_RESERVED_MEMORY_KEY.to_string(), "1Gi".to_string()),
+        ]);
+        let node = NodeAllocation::from_node(&hpc);
+        assert_eq!(node.allocatable_cpu_millis, Some(3500));
+        assert_eq!(node.allocatable_memory_mb, Some(3072));
+
+        let unknown = NodeAllocation::from_node(&node_info("zone", 0, 0));
+        assert_eq!(unknown.allocatable_cpu_millis, None);
+        assert_eq!(unknown.allocatable_memory_mb, None);
+    }
+
+    #[test]
+    fn test_summarize_groups_allocations_by_node() {
+        let summary = summarize(
+            &[node_info("hpc", 4, 4096), node_info("zone", 2, 2048)],
+            vec![
+                allocation("a", "hpc", 500, 256),
+                allocation("b", "hpc", 1000, 512),
+                allocation("c", "gone", 100, 64),
+            ],
+        );
+        let nodes: Vec<&str> = summary.iter().map(|n| n.node.as_str()).collect();
+        assert_eq!(nodes, vec!["gone", "hpc", "zone"]);
+        assert_eq!(summary[1].allocated_cpu_millis, 1500);
+        assert_eq!(summary[1].allocated_memory_mb, 768);
+        assert_eq!(summary[1].models.len(), 2);
+        assert_eq!(summary[0].allocatable_cpu_millis, None);
+        assert_eq!(summary[2].allocated_cpu_millis, 0);
+    }
+
+    #[test]
+    fn test_admit_rejects_requests_beyond_capacity() {
+        let nodes = || {
+            summarize(
+                &[node_info("hpc", 2, 2048), node_info("zone", 1, 1024)],
+                vec![allocation("a", "hpc", 1500, 1024)],
+            )
+        };
+
+        assert!(admit(nodes(), &[allocation("b", "hpc", 500, 1024)]).is_ok());
+        let err = admit(nodes(), &[allocation("b", "hpc", 600, 512)]).unwrap_err();
+        assert!(err.contains("node 'hpc' would allocate 2100m of 2000m CPU"));
+        assert!(!err.contains("memory"));
+
+        // A model moving to another node frees its old allocation
+        assert!(admit(
+            nodes(),
+            &[
+                allocation("a", "zone", 1000, 1024),
+                allocation("b", "hpc", 2000, 2048)
+            ]
+        )
+        .is_ok());
+        // Relaunching a model counts only its new request
+        assert!(admit(nodes(), &[allocation("a", "hpc", 2000, 2048)]).is_ok());
+        // Requests add up on the same node
+        let err = admit(
+            nodes(),
+            &[
+                allocation("b", "zone", 600, 512),
+                allocation("c", "zone", 600, 1024),
+            ],
+        )
+        .unwrap_err();
+        assert!(err.contains("1200m of 1000m CPU and 1536MiB of 1024MiB memory"));
+        // Nodes of unknown capacity take any request
+        assert!(admit(nodes(), &[allocation("b", "edge", 64000, 65536)]).is_ok());
+    }
+}
@@ -0,0 +1,253 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Token authentication and role-based access of the REST APIs
+//!
+//! Clients send `Authorization: Bearer <token>`. Tokens and their roles are
+//! configured in the `auth` section of settings.yaml; without it every
+//! request is allowed. Each route requires a [`Role`], and a higher role
+//! includes the lower ones:
+//!
+//! * `viewer` - reads
+//! * `operator` - changes that do not replace workloads or the cluster layout
+//! * `admin` - artifact apply and withdraw, cluster and node changes
+//!
+//! With the `axum` feature, [`require_role`] enforces the role of a route:
+//!
+//! ```ignore
+//! Router::new()
+//!     .route("/api/artifact", post(apply_artifact))
+//!     .route_layer(from_fn_with_state(Role::Admin, common::auth::require_role))
+//! ```
+
+use crate::setting::AuthSettings;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    Viewer,
+    Operator,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Operator => "operator",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+/// Holder of an accepted token
+#[derive(Debug, Clone, PartialEq)]
+pub struct Principal {
+    pub name: String,
+    pub role: Role,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthError {
+    MissingToken,
+    InvalidToken,
+    Forbidden { name: String, required: Role },
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthError::MissingToken => write!(f, "missing bearer token"),
+            AuthError::InvalidToken => write!(f, "invalid bearer token"),
+            AuthError::Forbidden { name, required } => {
+                write!(
+                    f,
+                    "'{}' is not allowed, {} role required",
+                    name,
+                    required.as_str()
+                )
+            }
+        }
+    }
+}
+
+/// Token of an `Authorization: Bearer <token>` header value
+pub fn bearer_token(authorization: &str) -> Option<&str> {
+    let (scheme, token) = authorization.trim().split_once(' ')?;
+    let token = token.trim();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+}
+
+/// Check the `Authorization` header of a request against a required role
+///
+/// ### Returns
+/// * `Ok(Some(principal))` - holder of the token, allowed
+/// * `Ok(None)` - authentication is disabled
+/// * `Err(AuthError)` - token missing, unknown or without the required role
+pub fn authorize(
+    settings: &AuthSettings,
+    authorization: Option<&str>,
+    required: Role,
+) -> Result<Option<Principal>, AuthError> {
+    if !settings.enabled {
+        return Ok(None);
+    }
+    let token = authorization
+        .and_then(bearer_token)
+        .ok_or(AuthError::MissingToken)?;
+    let entry = settings
+        .tokens
+        .iter()
+        .find(|entry| constant_time_eq(entry.token.as_bytes(), token.as_bytes()))
+        .ok_or(AuthError::InvalidToken)?;
+    if entry.role < required {
+        return Err(AuthError::Forbidden {
+            name: entry.name.clone(),
+            required,
+        });
+    }
+    Ok(Some(Principal {
+        name: entry.name.clone(),
+        role: entry.role,
+    }))
+}
+
+/// Compare tokens without leaking the length of the matching prefix
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(feature = "axum")]
+mod middleware {
+    use super::{authorize, AuthError, Role};
+    use crate::error::{ApiError, ErrorCode};
+    use axum::{
+        extract::{Request, State},
+        http::header,
+        middleware::Next,
+        response::{IntoResponse, Response},
+    };
+
+    impl IntoResponse for AuthError {
+        fn into_response(self) -> Response {
+            let code = match self {
+                AuthError::MissingToken | AuthError::InvalidToken => ErrorCode::Unauthorized,
+                AuthError::Forbidden { .. } => ErrorCode::Forbidden,
+            };
+            let body = ApiError::new(code, self.to_string());
+            ([(header::WWW_AUTHENTICATE, "Bearer")], body).into_response()
+        }
+    }
+
+    /// Reject requests whose token does not have the `required` role
+    ///
+    /// The [`super::Principal`] of an accepted token is added to the request
+    /// extensions. Settings are read per request, so token changes in
+    /// settings.yaml apply without a restart.
+    pub async fn require_role(
+        State(required): State<Role>,
+        mut request: Request,
+        next: Next,
+    ) -> Response {
+        let authorization = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok());
+        match authorize(&crate::setting::get_config().auth, authorization, required) {
+            Ok(Some(principal)) => {
+                request.extensions_mut().insert(principal);
+                next.run(request).await
+            }
+            Ok(None) => next.run(request).await,
+            Err(e) => {
+                crate::logd!(4, "Rejected {} {}: {}", request.method(), request.uri(), e);
+                e.into_response()
+            }
+        }
+    }
+}
+
+#[cfg(feature = "axum")]
+pub use middleware::require_role;
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::setting::TokenSettings;
+
+    fn settings() -> AuthSettings {
+        let token = |name: &str, role| TokenSettings {
+            name: name.to_string(),
+            token: format!("{}-token", name),
+            role,
+        };
+        AuthSettings {
+            enabled: true,
+            tokens: vec![
+                token("admin", Role::Admin),
+                token("operator", Role::Operator),
+                token("viewer", Role::Viewer),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_bearer_token() {
+        assert_eq!(bearer_token("Bearer abc"), Some("abc"));
+        assert_eq!(bearer_token("bearer  abc "), Some("abc"));
+        assert_eq!(bearer_token("Basic abc"), None);
+        assert_eq!(bearer_token("Bearer "), None);
+        assert_eq!(bearer_token("abc"), None);
+    }
+
+    #[test]
+    fn test_authorize_disabled_allows_everything() {
+        let result = authorize(&AuthSettings::default(), None, Role::Admin);
+        assert_eq!(result, Ok(None));
+    }
+
+    #[test]
+    fn test_authorize_roles() {
+        let settings = settings();
+        let viewer = authorize(&settings, Some("Bearer viewer-token"), Role::Viewer)
+            .unwrap()
+            .unwrap();
+        assert_eq!(viewer.name, "viewer");
+        assert!(authorize(&settings, Some("Bearer admin-token"), Role::Operator).is_ok());
+
+        let denied = authorize(&settings, Some("Bearer operator-token"), Role::Admin);
+        assert_eq!(
+            denied,
+            Err(AuthError::Forbidden {
+                name: "operator".to_string(),
+                required: Role::Admin
+            })
+        );
+    }
+
+    #[test]
+    fn test_authorize_rejects_missing_and_unknown_tokens() {
+        let settings = settings();
+        assert_eq!(
+            authorize(&settings, None, Role::Viewer),
+            Err(AuthError::MissingToken)
+        );
+        assert_eq!(
+            authorize(&settings, Some("Bearer admin-token2"), Role::Viewer),
+            Err(AuthError::InvalidToken)
+        );
+    }
+
+    #[test]
+    fn test_role_order_and_parse() {
+        assert!(Role::Admin > Role::Operator && Role::Operator > Role::Viewer);
+        let role: Role = serde_yaml::from_str("operator").unwrap();
+        assert_eq!(role, Role::Operator);
+        assert!(serde_yaml::from_str::<Role>("root").is_err());
+    }
+}
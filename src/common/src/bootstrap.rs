@@ -0,0 +1,85 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Bootstrap of guest nodes
+//!
+//! An admin issues a one-time token with `POST /api/v1/nodes/bootstrap-token`.
+//! NodeAgent started with `--bootstrap <token>` redeems it at [`REDEEM_PATH`]
+//! of the REST API of the master for a [`BootstrapBundle`], writes its config
+//! from the bundle and registers. ApiServer keeps the SHA-256 digest of every
+//! token under [`TOKEN_PREFIX`] until the token is redeemed or expires.
+//!
+//! ```json
+//! {
+//!   "masterIp": "192.168.10.201",
+//!   "apiEndpoint": "http://192.168.10.201:47098",
+//!   "caCert": "-----BEGIN CERTIFICATE-----\n...",
+//!   "nodeName": "zone-front",
+//!   "labels": { "zone": "front" }
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// etcd prefix of the issued tokens, followed by the digest of the token
+pub const TOKEN_PREFIX: &str = "BootstrapToken/";
+
+/// REST path a token is redeemed at, without authentication
+pub const REDEEM_PATH: &str = "/api/v1/nodes/bootstrap";
+
+/// Everything a guest node needs to join the master
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BootstrapBundle {
+    /// Address of the master, the `master_ip` of NodeAgent
+    pub master_ip: String,
+    /// gRPC endpoint of ApiServer
+    pub api_endpoint: String,
+    /// CA certificate the master is checked against, in PEM
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub ca_cert: String,
+    /// Name the node registers with, its hostname if empty
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub node_name: String,
+    /// Labels matched by the `nodeSelector` of models
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub labels: HashMap<String, String>,
+}
+
+/// Body of a request redeeming a token
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedeemRequest {
+    pub token: String,
+}
+
+/// URL a token is redeemed at on the master at `master_ip`
+pub fn redeem_url(master_ip: &str) -> String {
+    format!("http://{}:47099{}", master_ip, REDEEM_PATH)
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_json() {
+        let bundle: BootstrapBundle = serde_json::from_str(
+            r#"{"masterIp":"10.0.0.1","apiEndpoint":"http://10.0.0.1:47098","labels":{"zone":"front"}}"#,
+        )
+        .unwrap();
+        assert_eq!(bundle.master_ip, "10.0.0.1");
+        assert!(bundle.ca_cert.is_empty() && bundle.node_name.is_empty());
+        assert_eq!(bundle.labels["zone"], "front");
+
+        let json = serde_json::to_string(&bundle).unwrap();
+        assert!(!json.contains("caCert"));
+        assert_eq!(
+            redeem_url("10.0.0.1"),
+            "http://10.0.0.1:47099/api/v1/nodes/bootstrap"
+        );
+    }
+}
@@ -0,0 +1,219 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Conversions between the versioned node API and the in-tree messages
+//!
+//! ApiServer serves node registration twice: `pullpiri.node.v1` for current
+//! NodeAgents and the unversioned `apiserver.ApiServerConnection` for
+//! NodeAgents released before it. Both end in the same handler, which works
+//! on the in-tree `nodeagent.fromapiserver` messages; the `From` impls here
+//! translate the v1 messages in both directions. A NodeAgent in turn falls
+//! back to the unversioned call if [`is_unimplemented`] says its ApiServer
+//! predates v1.
+//!
+//! Enum values are copied as numbers, the v1 enums declare the same values.
+//! The agent version of v1 travels in the node metadata under
+//! [`AGENT_VERSION_KEY`], as the in-tree request has no field for it.
+
+use crate::nodeagent::fromapiserver as intree;
+use crate::pullpiri::node::v1;
+
+/// Node metadata key holding the release of the NodeAgent
+pub const AGENT_VERSION_KEY: &str = "agent_version";
+
+/// Whether a call failed because the server does not serve the API
+pub fn is_unimplemented(status: &tonic::Status) -> bool {
+    status.code() == tonic::Code::Unimplemented
+}
+
+impl From<v1::ResourceInfo> for intree::ResourceInfo {
+    fn from(r: v1::ResourceInfo) -> Self {
+        Self {
+            cpu_cores: r.cpu_cores,
+            memory_mb: r.memory_mb,
+            disk_gb: r.disk_gb,
+            architecture: r.architecture,
+            os_version: r.os_version,
+        }
+    }
+}
+
+impl From<intree::ResourceInfo> for v1::ResourceInfo {
+    fn from(r: intree::ResourceInfo) -> Self {
+        Self {
+            cpu_cores: r.cpu_cores,
+            memory_mb: r.memory_mb,
+            disk_gb: r.disk_gb,
+            architecture: r.architecture,
+            os_version: r.os_version,
+        }
+    }
+}
+
+impl From<v1::ClusterConfig> for intree::ClusterConfig {
+    fn from(c: v1::ClusterConfig) -> Self {
+        Self {
+            master_endpoint: c.master_endpoint,
+            heartbeat_interval: c.heartbeat_interval,
+            settings: c.settings,
+            liveness_window: c.liveness_window,
+        }
+    }
+}
+
+impl From<intree::ClusterConfig> for v1::ClusterConfig {
+    fn from(c: intree::ClusterConfig) -> Self {
+        Self {
+            master_endpoint: c.master_endpoint,
+            heartbeat_interval: c.heartbeat_interval,
+            settings: c.settings,
+            liveness_window: c.liveness_window,
+        }
+    }
+}
+
+impl From<v1::RegisterNodeRequest> for intree::NodeRegistrationRequest {
+    fn from(r: v1::RegisterNodeRequest) -> Self {
+        let mut metadata = r.metadata;
+        if !r.agent_version.is_empty() {
+            metadata
+                .entry(AGENT_VERSION_KEY.to_string())
+                .or_insert(r.agent_version);
+        }
+        Self {
+            node_id: r.node_id,
+            hostname: r.hostname,
+            ip_address: r.ip_address,
+            node_type: r.node_type,
+            node_role: r.node_role,
+            resources: r.resources.map(Into::into),
+            metadata,
+        }
+    }
+}
+
+impl From<intree::NodeRegistrationRequest> for v1::RegisterNodeRequest {
+    fn from(r: intree::NodeRegistrationRequest) -> Self {
+        Self {
+            agent_version: r
+                .metadata
+                .get(AGENT_VERSION_KEY)
+                .cloned()
+                .unwrap_or_default(),
+            node_id: r.node_id,
+            hostname: r.hostname,
+            ip_address: r.ip_address,
+            node_type: r.node_type,
+            node_role: r.node_role,
+            resources: r.resources.map(Into::into),
+            metadata: r.metadata,
+        }
+    }
+}
+
+impl From<v1::RegisterNodeResponse> for intree::NodeRegistrationResponse {
+    fn from(r: v1::RegisterNodeResponse) -> Self {
+        Self {
+            success: r.success,
+            message: r.message,
+            cluster_token: r.cluster_token,
+            cluster_config: r.cluster_config.map(Into::into),
+        }
+    }
+}
+
+impl From<intree::NodeRegistrationResponse> for v1::RegisterNodeResponse {
+    fn from(r: intree::NodeRegistrationResponse) -> Self {
+        Self {
+            success: r.success,
+            message: r.message,
+            cluster_token: r.cluster_token,
+            cluster_config: r.cluster_config.map(Into::into),
+        }
+    }
+}
+
+impl From<v1::HeartbeatRequest> for intree::HeartbeatRequest {
+    fn from(r: v1::HeartbeatRequest) -> Self {
+        Self {
+            node_id: r.node_id,
+            timestamp: r.timestamp,
+        }
+    }
+}
+
+impl From<intree::HeartbeatRequest> for v1::HeartbeatRequest {
+    fn from(r: intree::HeartbeatRequest) -> Self {
+        Self {
+            node_id: r.node_id,
+            timestamp: r.timestamp,
+        }
+    }
+}
+
+impl From<v1::HeartbeatResponse> for intree::HeartbeatResponse {
+    fn from(r: v1::HeartbeatResponse) -> Self {
+        Self {
+            ack: r.ack,
+            updated_config: r.updated_config.map(Into::into),
+        }
+    }
+}
+
+impl From<intree::HeartbeatResponse> for v1::HeartbeatResponse {
+    fn from(r: intree::HeartbeatResponse) -> Self {
+        Self {
+            ack: r.ack,
+            updated_config: r.updated_config.map(Into::into),
+        }
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    #[test]
+    fn test_registration_round_trip() {
+        let request = v1::RegisterNodeRequest {
+            node_id: "hpc-10.0.0.1".to_string(),
+            hostname: "hpc".to_string(),
+            ip_address: "10.0.0.1".to_string(),
+            node_type: v1::NodeType::Vehicle as i32,
+            node_role: v1::NodeRole::Nodeagent as i32,
+            resources: Some(v1::ResourceInfo {
+                cpu_cores: 4,
+                memory_mb: 8192,
+                ..Default::default()
+            }),
+            agent_version: "0.2.0".to_string(),
+            ..Default::default()
+        };
+        let legacy = intree::NodeRegistrationRequest::from(request.clone());
+        assert_eq!(legacy.node_type, intree::NodeType::Vehicle as i32);
+        assert_eq!(legacy.node_role, intree::NodeRole::Nodeagent as i32);
+        assert_eq!(legacy.metadata[AGENT_VERSION_KEY], "0.2.0");
+        let back = v1::RegisterNodeRequest::from(legacy);
+        assert_eq!(back.agent_version, request.agent_version);
+        assert_eq!(back.resources, request.resources);
+    }
+
+    #[test]
+    fn test_v1_wire_compatible_with_legacy() {
+        // An old NodeAgent's request decodes as v1 without an agent version
+        let legacy = intree::NodeRegistrationRequest {
+            node_id: "n1".to_string(),
+            hostname: "n1".to_string(),
+            node_role: intree::NodeRole::Bluechi as i32,
+            ..Default::default()
+        };
+        let decoded = v1::RegisterNodeRequest::decode(legacy.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(decoded.node_id, "n1");
+        assert_eq!(decoded.node_role, v1::NodeRole::Bluechi as i32);
+        assert!(decoded.agent_version.is_empty());
+    }
+}
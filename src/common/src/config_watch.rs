@@ -0,0 +1,127 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Hot reload of configuration files
+//!
+//! [`watch_file`] polls a file and publishes every successfully parsed,
+//! changed version on a [`watch`] channel. Components subscribe to the
+//! channel and apply new settings without a restart. A file that is missing
+//! or fails to parse keeps the last good configuration.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+
+/// Default interval between two checks of a watched file
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Modification time and size, compared to detect a changed file
+type Fingerprint = Option<(SystemTime, u64)>;
+
+fn fingerprint(path: &PathBuf) -> Fingerprint {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Watch a configuration file for changes
+///
+/// ### Parameters
+/// * `path: PathBuf` - file to watch
+/// * `initial: T` - configuration published until the file changes
+/// * `interval: Duration` - time between two checks of the file
+/// * `parse: F` - parser of the file contents
+/// ### Description
+/// Must be called within a tokio runtime. The polling task ends once every
+/// receiver is dropped.
+pub fn watch_file<T, F>(
+    path: PathBuf,
+    initial: T,
+    interval: Duration,
+    parse: F,
+) -> watch::Receiver<Arc<T>>
+where
+    T: PartialEq + Send + Sync + 'static,
+    F: Fn(&str) -> Result<T, String> + Send + 'static,
+{
+    let (tx, rx) = watch::channel(Arc::new(initial));
+    let mut last = fingerprint(&path);
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        ticker.tick().await;
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = tx.closed() => break,
+            }
+
+            let current = fingerprint(&path);
+            if current == last {
+                continue;
+            }
+            last = current;
+
+            let parsed = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| parse(&content));
+            match parsed {
+                Ok(config) => {
+                    tx.send_if_modified(|value| {
+                        if **value == config {
+                            return false;
+                        }
+                        *value = Arc::new(config);
+                        true
+                    });
+                }
+                Err(e) => eprintln!(
+                    "Keeping previous configuration, cannot reload {}: {}",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+    });
+
+    rx
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_number(content: &str) -> Result<u32, String> {
+        content.trim().parse().map_err(|e| format!("{:?}", e))
+    }
+
+    #[tokio::test]
+    async fn test_watch_file_publishes_changes() {
+        let path = std::env::temp_dir().join(format!("config_watch_{}.txt", std::process::id()));
+        std::fs::write(&path, "1").unwrap();
+
+        let mut rx = watch_file(path.clone(), 1, Duration::from_millis(20), parse_number);
+        assert_eq!(**rx.borrow(), 1);
+
+        // a longer file guarantees a new fingerprint despite coarse mtimes
+        std::fs::write(&path, "42").unwrap();
+        tokio::time::timeout(Duration::from_secs(5), rx.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(**rx.borrow_and_update(), 42);
+
+        // an unparsable file keeps the last good value
+        std::fs::write(&path, "not a number").unwrap();
+        let changed = tokio::time::timeout(Duration::from_millis(200), rx.changed()).await;
+        assert!(changed.is_err());
+        assert_eq!(**rx.borrow(), 42);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,161 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Container event streams of NodeAgent
+//!
+//! NodeAgent streams container events instead of full container lists to
+//! StateManager and MonitoringServer. Both rebuild the list of the node from
+//! the events with [`relay_container_events`] and handle it as before.
+
+use crate::monitoringserver::{
+    ContainerEvent, ContainerEventAck, ContainerEventList, ContainerEventType, ContainerList,
+};
+use std::future::Future;
+use tokio::sync::mpsc;
+use tokio_stream::{Stream, StreamExt};
+use tonic::Status;
+
+impl ContainerList {
+    /// Apply streamed container events to this list, returning how many were applied
+    ///
+    /// Containers are matched by id. `REMOVED` drops the container,
+    /// every other event type inserts or replaces it.
+    pub fn apply_events(&mut self, events: &[ContainerEvent]) -> u32 {
+        let mut applied = 0;
+        for event in events {
+            let Some(container) = &event.container else {
+                continue;
+            };
+            let position = self.containers.iter().position(|c| c.id == container.id);
+            match (event.event_type(), position) {
+                (ContainerEventType::Removed, Some(i)) => {
+                    self.containers.remove(i);
+                }
+                (ContainerEventType::Removed, None) => continue,
+                (_, Some(i)) => self.containers[i] = container.clone(),
+                (_, None) => self.containers.push(container.clone()),
+            }
+            applied += 1;
+        }
+        applied
+    }
+}
+
+/// Apply streamed container events and forward the resulting ContainerList
+///
+/// The container list is rebuilt per stream: nodeagent sends every existing
+/// container as CREATED when it opens the stream, then only the changes.
+/// An ack with the number of applied events is returned for every batch.
+/// The relay stops when the inbound stream ends or fails, or when
+/// `forward` fails, which is acked as `Unavailable`.
+pub async fn relay_container_events<S, F, Fut, E>(
+    mut inbound: S,
+    mut forward: F,
+    tx_ack: mpsc::Sender<Result<ContainerEventAck, Status>>,
+) where
+    S: Stream<Item = Result<ContainerEventList, Status>> + Unpin,
+    F: FnMut(ContainerList) -> Fut,
+    Fut: Future<Output = Result<(), E>>,
+    E: std::fmt::Display,
+{
+    let mut current = ContainerList::default();
+
+    while let Some(message) = inbound.next().await {
+        let batch = match message {
+            Ok(batch) => batch,
+            Err(status) => {
+                crate::logd!(4, "Container event stream error: {}", status);
+                break;
+            }
+        };
+        current.node_name = batch.node_name.clone();
+        let applied = current.apply_events(&batch.events);
+
+        let ack = match forward(current.clone()).await {
+            Ok(()) => Ok(ContainerEventAck {
+                node_name: batch.node_name,
+                applied,
+            }),
+            Err(e) => Err(Status::unavailable(format!(
+                "cannot send container list: {}",
+                e
+            ))),
+        };
+        let failed = ack.is_err();
+        if tx_ack.send(ack).await.is_err() || failed {
+            break;
+        }
+    }
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitoringserver::ContainerInfo;
+
+    fn container_event(id: &str, event_type: ContainerEventType) -> ContainerEvent {
+        ContainerEvent {
+            event_type: event_type as i32,
+            container: Some(ContainerInfo {
+                id: id.to_string(),
+                ..Default::default()
+            }),
+            timestamp_ns: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_relay_container_events_applies_batches() {
+        let (tx_container, mut rx_container) = mpsc::channel(4);
+        let (tx_ack, mut rx_ack) = mpsc::channel(4);
+        let inbound = tokio_stream::iter(vec![
+            Ok(ContainerEventList {
+                node_name: "node1".to_string(),
+                events: vec![
+                    container_event("a", ContainerEventType::Created),
+                    container_event("b", ContainerEventType::Created),
+                ],
+            }),
+            Ok(ContainerEventList {
+                node_name: "node1".to_string(),
+                events: vec![
+                    container_event("a", ContainerEventType::Removed),
+                    container_event("b", ContainerEventType::Oom),
+                    container_event("missing", ContainerEventType::Removed),
+                ],
+            }),
+        ]);
+
+        relay_container_events(inbound, |list| tx_container.send(list), tx_ack).await;
+
+        let first = rx_container.recv().await.unwrap();
+        assert_eq!(first.node_name, "node1");
+        assert_eq!(first.containers.len(), 2);
+        let second = rx_container.recv().await.unwrap();
+        assert_eq!(second.containers.len(), 1);
+        assert_eq!(second.containers[0].id, "b");
+
+        assert_eq!(rx_ack.recv().await.unwrap().unwrap().applied, 2);
+        assert_eq!(rx_ack.recv().await.unwrap().unwrap().applied, 2);
+        assert!(rx_ack.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_relay_container_events_reports_closed_channel() {
+        let (tx_container, rx_container) = mpsc::channel::<ContainerList>(1);
+        drop(rx_container);
+        let (tx_ack, mut rx_ack) = mpsc::channel(4);
+        let inbound = tokio_stream::iter(vec![Ok(ContainerEventList {
+            node_name: "node1".to_string(),
+            events: vec![],
+        })]);
+
+        relay_container_events(inbound, |list| tx_container.send(list), tx_ack).await;
+
+        let status = rx_ack.recv().await.unwrap().unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unavailable);
+    }
+}
@@ -4,18 +4,254 @@
 */
 pub type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
 
-// TODO - add custom error message types
-/*
-pub struct Error {
-    msg: Msg,
+use crate::etcd::StoreError;
+use serde::{Deserialize, Serialize};
+
+/// Category of a failed REST request, sent as `code` of [`ApiError`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// The request or its content is invalid
+    Validation,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    /// The request conflicts with the current state, e.g. a duplicate
+    Conflict,
+    PayloadTooLarge,
+    RateLimited,
+    Internal,
+    /// A component or store the request depends on cannot be reached
+    DependencyUnavailable,
 }
 
-struct Msg {
-    kind: ErrorKind,
-    desc: Box<std::error::Error+Send+Sync>,
+impl ErrorCode {
+    /// HTTP status of the code
+    pub fn http_status(self) -> u16 {
+        match self {
+            Self::Validation => 400,
+            Self::Unauthorized => 401,
+            Self::Forbidden => 403,
+            Self::NotFound => 404,
+            Self::Conflict => 409,
+            Self::PayloadTooLarge => 413,
+            Self::RateLimited => 429,
+            Self::Internal => 500,
+            Self::DependencyUnavailable => 503,
+        }
+    }
 }
 
-pub enum Errorkind {
-    ...
+/// Body of every failed REST response of ApiServer and SettingsService
+///
+/// ```json
+/// {
+///   "code": "NOT_FOUND",
+///   "message": "Scenario 'helloworld' not found",
+///   "details": null,
+///   "correlation_id": "4bf92f3577b34da6a3ce929d0e0e4736"
+/// }
+/// ```
+///
+/// `correlation_id` is the trace ID of the request, also returned in the
+/// `x-trace-id` header, to find its `logd!` lines.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(default)]
+    pub details: Option<serde_json::Value>,
+    #[serde(default)]
+    pub correlation_id: Option<String>,
+}
+
+impl ApiError {
+    /// Error of the request being handled, with its trace ID if it runs in a span
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: None,
+            correlation_id: crate::trace::current_trace_id(),
+        }
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Validation, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Conflict, message)
+    }
+
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::DependencyUnavailable, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// Response body of an error returned by a handler
+    ///
+    /// An `ApiError` is kept as is, etcd and gRPC failures are mapped by
+    /// their [`StoreError`] variant or status code. Any other error is taken
+    /// as a rejected request.
+    pub fn from_error(error: &(dyn std::error::Error + 'static)) -> Self {
+        if let Some(api_error) = error.downcast_ref::<ApiError>() {
+            api_error.clone()
+        } else if let Some(store_error) = error.downcast_ref::<StoreError>() {
+            store_error.clone().into()
+        } else if let Some(status) = error.downcast_ref::<tonic::Status>() {
+            Self::from_status(status)
+        } else {
+            Self::validation(error.to_string())
+        }
+    }
+
+    /// Response body of a failed gRPC call to another component
+    pub fn from_status(status: &tonic::Status) -> Self {
+        let code = match status.code() {
+            tonic::Code::InvalidArgument | tonic::Code::FailedPrecondition => ErrorCode::Validation,
+            tonic::Code::NotFound => ErrorCode::NotFound,
+            tonic::Code::AlreadyExists | tonic::Code::Aborted => ErrorCode::Conflict,
+            tonic::Code::Unavailable | tonic::Code::DeadlineExceeded => {
+                ErrorCode::DependencyUnavailable
+            }
+            _ => ErrorCode::Internal,
+        };
+        Self::new(code, status.message())
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl From<StoreError> for ApiError {
+    fn from(error: StoreError) -> Self {
+        let code = match error {
+            StoreError::NotFound => ErrorCode::NotFound,
+            StoreError::Unavailable(_) => ErrorCode::DependencyUnavailable,
+            StoreError::Failed(_) => ErrorCode::Internal,
+        };
+        Self::new(code, error.to_string())
+    }
+}
+
+impl From<Box<dyn std::error::Error>> for ApiError {
+    fn from(error: Box<dyn std::error::Error>) -> Self {
+        Self::from_error(error.as_ref())
+    }
+}
+
+impl From<Box<dyn std::error::Error + Send + Sync>> for ApiError {
+    fn from(error: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        Self::from_error(error.as_ref())
+    }
+}
+
+#[cfg(feature = "axum")]
+mod rest {
+    use super::ApiError;
+    use axum::{
+        http::StatusCode,
+        response::{IntoResponse, Response},
+        Json,
+    };
+
+    impl IntoResponse for ApiError {
+        fn into_response(self) -> Response {
+            let status = StatusCode::from_u16(self.code.http_status())
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            (status, Json(self)).into_response()
+        }
+    }
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_store_error() {
+        let api_error = ApiError::from(StoreError::NotFound);
+        assert_eq!(api_error.code, ErrorCode::NotFound);
+        assert_eq!(api_error.message, "Key not found");
+        assert_eq!(
+            ApiError::from(StoreError::Unavailable("connection refused".to_string())).code,
+            ErrorCode::DependencyUnavailable
+        );
+        assert_eq!(
+            ApiError::from(StoreError::Failed("Put failed".to_string())).code,
+            ErrorCode::Internal
+        );
+    }
+
+    #[test]
+    fn test_from_error_keeps_typed_errors() {
+        let error: Box<dyn std::error::Error> =
+            ApiError::conflict("Model 'a' is already deployed").into();
+        let api_error = ApiError::from_error(error.as_ref());
+        assert_eq!(api_error.code, ErrorCode::Conflict);
+        assert_eq!(api_error.code.http_status(), 409);
+
+        let error: Box<dyn std::error::Error> =
+            StoreError::Unavailable("Failed to create client".to_string()).into();
+        assert_eq!(ApiError::from(error).code, ErrorCode::DependencyUnavailable);
+
+        let error: Box<dyn std::error::Error + Send + Sync> =
+            tonic::Status::not_found("no container").into();
+        assert_eq!(ApiError::from(error).code, ErrorCode::NotFound);
+
+        // the message of an untyped error does not choose its code
+        let error: Box<dyn std::error::Error> = "Scenario 'x' not found".into();
+        let api_error = ApiError::from_error(error.as_ref());
+        assert_eq!(api_error.code, ErrorCode::Validation);
+        assert_eq!(api_error.message, "Scenario 'x' not found");
+    }
+
+    #[test]
+    fn test_from_status() {
+        let status = tonic::Status::unavailable("connection refused");
+        let api_error = ApiError::from_status(&status);
+        assert_eq!(api_error.code, ErrorCode::DependencyUnavailable);
+        assert_eq!(api_error.message, "connection refused");
+        assert_eq!(
+            ApiError::from_status(&tonic::Status::not_found("no container")).code,
+            ErrorCode::NotFound
+        );
+        assert_eq!(
+            ApiError::from_status(&tonic::Status::internal("boom")).code,
+            ErrorCode::Internal
+        );
+    }
+
+    #[test]
+    fn test_api_error_body() {
+        let error = ApiError::validation("bad input").with_details(serde_json::json!({"line": 3}));
+        let body = serde_json::to_value(&error).unwrap();
+        assert_eq!(body["code"], "VALIDATION");
+        assert_eq!(body["message"], "bad input");
+        assert_eq!(body["details"]["line"], 3);
+        assert!(body["correlation_id"].is_null());
+        assert_eq!(
+            serde_json::to_value(ErrorCode::DependencyUnavailable).unwrap(),
+            "DEPENDENCY_UNAVAILABLE"
+        );
+    }
 }
-*/
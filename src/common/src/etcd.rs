@@ -3,308 +3,781 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
+//! Key-value store of the Pullpiri modules, served by the RocksDB service
+//!
+//! One client per endpoint of the `etcd` section of settings.yaml is created
+//! on first use and shared by every request of the process. Requests go to
+//! the endpoint that answered last. An endpoint failing with a connection
+//! error or timeout is marked down and the request fails over to the next
+//! one; a down endpoint is tried again after `health_check_interval_ms`, or
+//! earlier if every other endpoint is down too. [`health_check`] probes all
+//! endpoints.
+//!
+//! Latencies, failovers and the health of each endpoint are recorded in
+//! [`crate::metrics`].
+//!
+//! Keys are given without the prefix of the instance, see [`key_prefix`]:
+//! it is added to the keys sent to the store and removed from the keys it
+//! returns.
+
 use crate::logd;
+use crate::metrics::{ETCD_ENDPOINT_UP, ETCD_FAILOVERS_TOTAL};
 use crate::rocksdbservice::{
     rocks_db_service_client::RocksDbServiceClient, BatchPutRequest, DeleteRequest,
-    GetByPrefixRequest, GetRequest, HealthRequest, KeyValue, PutRequest,
+    GetByPrefixRequest, GetRequest, HealthRequest, KeyValue, ListKeysRequest, PutRequest,
 };
+use crate::rpc::{RpcClient, RpcPolicy};
+use crate::setting::EtcdSettings;
+use crate::spec::namespace;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tonic::transport::Channel;
+use tonic::{Code, Status};
 
-lazy_static::lazy_static! {
-    static ref ROCKSDB_SERVICE_URL: String = {
-        std::env::var("ROCKSDB_SERVICE_URL")
-            .unwrap_or_else(|_| "http://localhost:47007".to_string())
-    };
-}
+static POOL: OnceLock<Pool> = OnceLock::new();
+static KEY_PREFIX: OnceLock<String> = OnceLock::new();
+
+/// Root of the keys of named instances
+///
+/// Kept apart from `/pullpiri/`, under which the modules store keys of their
+/// own, such as the settings of SettingsService.
+pub const NAMESPACE_ROOT: &str = "/pullpiri-instance/";
 
 const DEV: bool = false;
 
-/// Put a key-value pair into the gRPC RocksDB service
-pub async fn put(key: &str, value: &str) -> Result<(), String> {
-    if DEV {
-        logd!(
-            1,
-            "[RocksDB] Putting key '{}' to service: {}",
-            key,
-            *ROCKSDB_SERVICE_URL
-        );
+/// Failed request to the store
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoreError {
+    /// The key is not stored
+    NotFound,
+    /// No endpoint could be reached or answered in time
+    Unavailable(String),
+    /// The store refused or failed the request
+    Failed(String),
+}
+
+impl std::fmt::Display for StoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "Key not found"),
+            Self::Unavailable(e) | Self::Failed(e) => write!(f, "{}", e),
+        }
     }
+}
 
-    match RocksDbServiceClient::connect(ROCKSDB_SERVICE_URL.clone()).await {
-        Ok(mut client) => {
-            let request = tonic::Request::new(PutRequest {
-                key: key.to_string(),
-                value: value.to_string(),
-            });
-
-            match client.put(request).await {
-                Ok(response) => {
-                    let put_response = response.into_inner();
-                    if put_response.success {
-                        Ok(())
-                    } else {
-                        let error_msg = put_response.error;
-                        logd!(5, "[RocksDB] Put failed: {}", error_msg);
-                        Err(error_msg)
-                    }
-                }
-                Err(e) => {
-                    let error_msg = format!("gRPC request failed: {}", e);
-                    logd!(5, "[RocksDB] {}", error_msg);
-                    Err(error_msg)
+impl std::error::Error for StoreError {}
+
+impl From<StoreError> for String {
+    fn from(error: StoreError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Clients of the store endpoints, in the order they are configured
+struct Pool {
+    endpoints: Vec<PoolEndpoint>,
+    /// Index of the endpoint that answered last
+    active: AtomicUsize,
+    health_check_interval: Duration,
+}
+
+struct PoolEndpoint {
+    client: RpcClient,
+    /// Time until which the endpoint is skipped after a failure
+    down_until: Mutex<Option<Instant>>,
+}
+
+impl Pool {
+    fn new(settings: &EtcdSettings) -> Self {
+        let policy = RpcPolicy {
+            connect_timeout: Duration::from_millis(settings.connect_timeout_ms),
+            call_timeout: (settings.request_timeout_ms > 0)
+                .then(|| Duration::from_millis(settings.request_timeout_ms)),
+            // Failing over to the next endpoint replaces retries and breaking
+            max_retries: 0,
+            breaker_threshold: 0,
+            ..RpcPolicy::current()
+        };
+        let endpoints = endpoint_urls(settings)
+            .into_iter()
+            .map(|url| {
+                crate::metrics::set_gauge(
+                    ETCD_ENDPOINT_UP,
+                    "1 if the etcd endpoint answered its last request",
+                    &[("endpoint", &url)],
+                    1.0,
+                );
+                PoolEndpoint {
+                    client: RpcClient::new("RocksDB service", url).with_policy(policy.clone()),
+                    down_until: Mutex::new(None),
                 }
-            }
+            })
+            .collect();
+        Self {
+            endpoints,
+            active: AtomicUsize::new(0),
+            health_check_interval: Duration::from_millis(settings.health_check_interval_ms),
+        }
+    }
+
+    /// Indices of the endpoints in the order a request tries them
+    fn attempt_order(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let down: Vec<bool> = self
+            .endpoints
+            .iter()
+            .map(|endpoint| lock(&endpoint.down_until).is_some_and(|until| now < until))
+            .collect();
+        attempt_order(self.active.load(Ordering::Relaxed), &down)
+    }
+
+    fn mark_up(&self, index: usize) {
+        let endpoint = &self.endpoints[index];
+        if lock(&endpoint.down_until).take().is_some() {
+            logd!(3, "[RocksDB] Endpoint {} is back", endpoint.client.addr());
         }
-        Err(e) => {
-            let error_msg = format!("Failed to create client: {}", e);
-            logd!(5, "[RocksDB] {}", error_msg);
-            Err(error_msg)
+        set_endpoint_up(endpoint.client.addr(), true);
+        self.active.store(index, Ordering::Relaxed);
+    }
+
+    fn mark_down(&self, index: usize, error: &Status) {
+        let endpoint = &self.endpoints[index];
+        let was_up = lock(&endpoint.down_until)
+            .replace(Instant::now() + self.health_check_interval)
+            .is_none();
+        if was_up {
+            logd!(
+                4,
+                "[RocksDB] Endpoint {} is down: {}",
+                endpoint.client.addr(),
+                error.message()
+            );
         }
+        set_endpoint_up(endpoint.client.addr(), false);
     }
 }
 
-/// Get a value by key from the gRPC RocksDB service
-pub async fn get(key: &str) -> Result<String, String> {
-    if DEV {
-        logd!(
-            1,
-            "[RocksDB] Getting key '{}' from service: {}",
-            key,
-            *ROCKSDB_SERVICE_URL
-        );
+fn pool() -> &'static Pool {
+    POOL.get_or_init(|| Pool::new(&crate::setting::get_config().etcd))
+}
+
+/// Prefix of the keys of this instance
+///
+/// Keys are stored under `/pullpiri-instance/<instance>/` if the instance is named by
+/// `PULLPIRI_INSTANCE` or the `instance` setting, so stacks sharing one store
+/// do not see each other's keys. Without a name keys are stored as given.
+pub fn key_prefix() -> &'static str {
+    KEY_PREFIX.get_or_init(|| {
+        let instance = std::env::var("PULLPIRI_INSTANCE")
+            .unwrap_or_else(|_| crate::setting::get_config().etcd.instance.clone());
+        prefix_of(&instance)
+    })
+}
+
+fn prefix_of(instance: &str) -> String {
+    let instance = instance.trim().trim_matches('/');
+    if instance.is_empty() {
+        String::new()
+    } else {
+        format!("{}{}/", NAMESPACE_ROOT, instance)
     }
+}
 
-    match RocksDbServiceClient::connect(ROCKSDB_SERVICE_URL.clone()).await {
-        Ok(mut client) => {
-            let request = tonic::Request::new(GetRequest {
-                key: key.to_string(),
-            });
-
-            match client.get(request).await {
-                Ok(response) => {
-                    let get_response = response.into_inner();
-                    if get_response.success {
-                        if DEV {
-                            logd!(
-                                1,
-                                "[RocksDB] Successfully retrieved key: {} (value length: {})",
-                                key,
-                                get_response.value.len()
-                            );
-                        }
-                        Ok(get_response.value)
-                    } else {
-                        logd!(5, "[RocksDB] Key not found: {}", key);
-                        Err("Key not found".to_string())
-                    }
-                }
-                Err(e) => {
-                    let error_msg = format!("gRPC request failed: {}", e);
-                    logd!(5, "[RocksDB] {}", error_msg);
-                    Err(error_msg)
-                }
+fn namespaced(key: &str) -> String {
+    format!("{}{}", key_prefix(), key)
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+fn set_endpoint_up(url: &str, up: bool) {
+    crate::metrics::set_gauge(
+        ETCD_ENDPOINT_UP,
+        "1 if the etcd endpoint answered its last request",
+        &[("endpoint", url)],
+        if up { 1.0 } else { 0.0 },
+    );
+}
+
+/// Endpoints from `ROCKSDB_SERVICE_URL` if it is set, else from the settings
+fn endpoint_urls(settings: &EtcdSettings) -> Vec<String> {
+    let urls: Vec<String> = match std::env::var("ROCKSDB_SERVICE_URL") {
+        Ok(urls) => urls
+            .split(',')
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())
+            .collect(),
+        Err(_) => settings.endpoints.clone(),
+    };
+    if urls.is_empty() {
+        EtcdSettings::default().endpoints
+    } else {
+        urls
+    }
+}
+
+/// Order in which a request tries the endpoints
+///
+/// Endpoints that are up come first, starting with the active one. Endpoints
+/// that are down come last, so a request is sent even if all of them failed.
+fn attempt_order(active: usize, down: &[bool]) -> Vec<usize> {
+    let count = down.len();
+    let rotated = (0..count).map(|offset| (active + offset) % count);
+    let (up, down): (Vec<usize>, Vec<usize>) = rotated.partition(|&index| !down[index]);
+    up.into_iter().chain(down).collect()
+}
+
+/// Errors of endpoints that could not be reached or did not answer in time
+fn is_connection_error(status: &Status) -> bool {
+    matches!(status.code(), Code::Unavailable | Code::DeadlineExceeded)
+}
+
+/// Send a request to the first endpoint that answers
+///
+/// ### Parameters
+/// * `operation: &'static str` - name of the operation for the latency metric
+/// * `call: F` - sends the request with the given client, invoked again for
+///   every endpoint the request fails over to
+async fn request<T, F, Fut>(operation: &'static str, mut call: F) -> Result<T, StoreError>
+where
+    F: FnMut(RocksDbServiceClient<Channel>) -> Fut,
+    Fut: Future<Output = Result<tonic::Response<T>, Status>>,
+{
+    let _timer = crate::metrics::etcd_timer(operation);
+    let pool = pool();
+    let mut last_error = String::new();
+    for (attempt, index) in pool.attempt_order().into_iter().enumerate() {
+        if attempt > 0 {
+            crate::metrics::inc_counter(
+                ETCD_FAILOVERS_TOTAL,
+                "Requests sent to another etcd endpoint after a failure",
+                &[("operation", operation)],
+            );
+        }
+        let endpoint = &pool.endpoints[index];
+        let result = endpoint
+            .client
+            .call(|channel| call(RocksDbServiceClient::new(channel)))
+            .await;
+        match result {
+            Ok(response) => {
+                pool.mark_up(index);
+                return Ok(response.into_inner());
+            }
+            Err(e) if is_connection_error(&e) => {
+                pool.mark_down(index, &e);
+                last_error = format!("gRPC request failed: {}", e);
+            }
+            Err(e) => {
+                let error_msg = format!("gRPC request failed: {}", e);
+                logd!(5, "[RocksDB] {}", error_msg);
+                return Err(StoreError::Failed(error_msg));
             }
         }
-        Err(e) => {
-            let error_msg = format!("Failed to create client: {}", e);
-            logd!(5, "[RocksDB] {}", error_msg);
-            Err(error_msg)
+    }
+    logd!(5, "[RocksDB] {}", last_error);
+    Err(StoreError::Unavailable(last_error))
+}
+
+/// Put a key-value pair into the gRPC RocksDB service
+pub async fn put(key: &str, value: &str) -> Result<(), StoreError> {
+    if DEV {
+        logd!(1, "[RocksDB] Putting key '{}'", key);
+    }
+
+    let put_response = request("put", |mut client| {
+        let request = tonic::Request::new(PutRequest {
+            key: namespaced(key),
+            value: value.to_string(),
+        });
+        async move { client.put(request).await }
+    })
+    .await?;
+    if put_response.success {
+        Ok(())
+    } else {
+        let error_msg = put_response.error;
+        logd!(5, "[RocksDB] Put failed: {}", error_msg);
+        Err(StoreError::Failed(error_msg))
+    }
+}
+
+/// Get a value by key from the gRPC RocksDB service
+pub async fn get(key: &str) -> Result<String, StoreError> {
+    get_key(&namespaced(key)).await
+}
+
+async fn get_key(key: &str) -> Result<String, StoreError> {
+    if DEV {
+        logd!(1, "[RocksDB] Getting key '{}'", key);
+    }
+
+    let get_response = request("get", |mut client| {
+        let request = tonic::Request::new(GetRequest {
+            key: key.to_string(),
+        });
+        async move { client.get(request).await }
+    })
+    .await?;
+    if get_response.success {
+        if DEV {
+            logd!(
+                1,
+                "[RocksDB] Successfully retrieved key: {} (value length: {})",
+                key,
+                get_response.value.len()
+            );
         }
+        Ok(get_response.value)
+    } else {
+        logd!(5, "[RocksDB] Key not found: {}", key);
+        Err(StoreError::NotFound)
     }
 }
 
 /// Get all key-value pairs with the specified prefix using gRPC RocksDB service
-pub async fn get_all_with_prefix(prefix: &str) -> Result<Vec<(String, String)>, String> {
+pub async fn get_all_with_prefix(prefix: &str) -> Result<Vec<(String, String)>, StoreError> {
     if DEV {
-        logd!(
-            1,
-            "[RocksDB] Getting all keys with prefix '{}' from service: {}",
-            prefix,
-            *ROCKSDB_SERVICE_URL
-        );
+        logd!(1, "[RocksDB] Getting all keys with prefix '{}'", prefix);
     }
 
-    match RocksDbServiceClient::connect(ROCKSDB_SERVICE_URL.clone()).await {
-        Ok(mut client) => {
-            let request = tonic::Request::new(GetByPrefixRequest {
-                prefix: prefix.to_string(),
-                limit: 0, // 0 means no limit
-            });
-
-            match client.get_by_prefix(request).await {
-                Ok(response) => {
-                    let get_response = response.into_inner();
-                    if get_response.error.is_empty() {
-                        let result: Vec<(String, String)> = get_response
-                            .pairs
-                            .into_iter()
-                            .map(|kv| (kv.key, kv.value))
-                            .collect();
-                        if DEV {
-                            logd!(
-                                1,
-                                "[RocksDB] Successfully retrieved {} keys with prefix '{}'",
-                                result.len(),
-                                prefix
-                            );
-                        }
-                        Ok(result)
-                    } else {
-                        logd!(5, "[RocksDB] Error from service: {}", get_response.error);
-                        Err(get_response.error)
-                    }
-                }
-                Err(e) => {
-                    let error_msg = format!("gRPC request failed: {}", e);
-                    logd!(5, "[RocksDB] {}", error_msg);
-                    Err(error_msg)
-                }
-            }
+    let get_response = request("get_all_with_prefix", |mut client| {
+        let request = tonic::Request::new(GetByPrefixRequest {
+            prefix: namespaced(prefix),
+            limit: 0, // 0 means no limit
+        });
+        async move { client.get_by_prefix(request).await }
+    })
+    .await?;
+    if get_response.error.is_empty() {
+        let result: Vec<(String, String)> = get_response
+            .pairs
+            .into_iter()
+            .map(|kv| {
+                let key = match kv.key.strip_prefix(key_prefix()) {
+                    Some(key) => key.to_string(),
+                    None => kv.key,
+                };
+                (key, kv.value)
+            })
+            .collect();
+        if DEV {
+            logd!(
+                1,
+                "[RocksDB] Successfully retrieved {} keys with prefix '{}'",
+                result.len(),
+                prefix
+            );
         }
-        Err(e) => {
-            let error_msg = format!("Failed to create client: {}", e);
-            logd!(5, "[RocksDB] {}", error_msg);
-            Err(error_msg)
+        Ok(result)
+    } else {
+        logd!(5, "[RocksDB] Error from service: {}", get_response.error);
+        Err(StoreError::Failed(get_response.error))
+    }
+}
+
+/// Get the artifacts of a kind in the default and all registered namespaces
+///
+/// Namespaces are registered under [`namespace::REGISTRY_PREFIX`] when an
+/// artifact is applied to them, see [`namespace::kind_prefix`] for the keys
+/// scanned.
+pub async fn get_all_of_kind(kind: &str) -> Result<Vec<(String, String)>, StoreError> {
+    let registry = format!("{}/", namespace::REGISTRY_PREFIX);
+    let mut namespaces = vec![namespace::DEFAULT_NAMESPACE.to_string()];
+    for (key, _) in get_all_with_prefix(&registry).await? {
+        if let Some(name) = key.strip_prefix(&registry) {
+            namespaces.push(name.to_string());
         }
     }
+
+    let mut entries = Vec::new();
+    for ns in namespaces {
+        entries.extend(get_all_with_prefix(&namespace::kind_prefix(&ns, kind)).await?);
+    }
+    Ok(entries)
 }
 
 /// Delete a key from the gRPC RocksDB service
-pub async fn delete(key: &str) -> Result<(), String> {
+pub async fn delete(key: &str) -> Result<(), StoreError> {
+    delete_key(&namespaced(key)).await
+}
+
+async fn delete_key(key: &str) -> Result<(), StoreError> {
     if DEV {
-        logd!(
-            1,
-            "[RocksDB] Deleting key '{}' from service: {}",
-            key,
-            *ROCKSDB_SERVICE_URL
-        );
+        logd!(1, "[RocksDB] Deleting key '{}'", key);
     }
 
-    match RocksDbServiceClient::connect(ROCKSDB_SERVICE_URL.clone()).await {
-        Ok(mut client) => {
-            let request = tonic::Request::new(DeleteRequest {
-                key: key.to_string(),
-            });
-
-            match client.delete(request).await {
-                Ok(response) => {
-                    let delete_response = response.into_inner();
-                    if delete_response.success {
-                        if DEV {
-                            logd!(1, "[RocksDB] Successfully deleted key: {}", key);
-                        }
-                        Ok(())
-                    } else {
-                        let error_msg = delete_response.error;
-                        logd!(5, "[RocksDB] Delete failed: {}", error_msg);
-                        Err(error_msg)
-                    }
-                }
-                Err(e) => {
-                    let error_msg = format!("gRPC request failed: {}", e);
-                    logd!(5, "[RocksDB] {}", error_msg);
-                    Err(error_msg)
-                }
-            }
-        }
-        Err(e) => {
-            let error_msg = format!("Failed to create client: {}", e);
-            logd!(5, "[RocksDB] {}", error_msg);
-            Err(error_msg)
+    let delete_response = request("delete", |mut client| {
+        let request = tonic::Request::new(DeleteRequest {
+            key: key.to_string(),
+        });
+        async move { client.delete(request).await }
+    })
+    .await?;
+    if delete_response.success {
+        if DEV {
+            logd!(1, "[RocksDB] Successfully deleted key: {}", key);
         }
+        Ok(())
+    } else {
+        let error_msg = delete_response.error;
+        logd!(5, "[RocksDB] Delete failed: {}", error_msg);
+        Err(StoreError::Failed(error_msg))
     }
 }
 
 /// Batch put operation to store multiple key-value pairs using gRPC RocksDB service
-pub async fn batch_put(items: Vec<(String, String)>) -> Result<(), String> {
+pub async fn batch_put(items: Vec<(String, String)>) -> Result<(), StoreError> {
+    batch_put_keys(
+        items
+            .into_iter()
+            .map(|(key, value)| (namespaced(&key), value))
+            .collect(),
+    )
+    .await
+}
+
+async fn batch_put_keys(items: Vec<(String, String)>) -> Result<(), StoreError> {
     if DEV {
-        logd!(
-            1,
-            "[RocksDB] Batch putting {} items to service: {}",
-            items.len(),
-            *ROCKSDB_SERVICE_URL
-        );
+        logd!(1, "[RocksDB] Batch putting {} items", items.len());
     }
 
-    match RocksDbServiceClient::connect(ROCKSDB_SERVICE_URL.clone()).await {
-        Ok(mut client) => {
-            let pairs: Vec<KeyValue> = items
-                .into_iter()
-                .map(|(key, value)| KeyValue { key, value })
-                .collect();
-
-            let request = tonic::Request::new(BatchPutRequest { pairs });
-
-            match client.batch_put(request).await {
-                Ok(response) => {
-                    let batch_response = response.into_inner();
-                    if batch_response.success {
-                        if DEV {
-                            logd!(
-                                1,
-                                "[RocksDB] Successfully stored {} items in batch",
-                                batch_response.processed_count
-                            );
-                        }
-                        Ok(())
-                    } else {
-                        let error_msg = batch_response.error;
-                        logd!(5, "[RocksDB] Batch put failed: {}", error_msg);
-                        Err(error_msg)
-                    }
+    let pairs: Vec<KeyValue> = items
+        .into_iter()
+        .map(|(key, value)| KeyValue { key, value })
+        .collect();
+    let batch_response = request("batch_put", |mut client| {
+        let request = tonic::Request::new(BatchPutRequest {
+            pairs: pairs.clone(),
+        });
+        async move { client.batch_put(request).await }
+    })
+    .await?;
+    if batch_response.success {
+        if DEV {
+            logd!(
+                1,
+                "[RocksDB] Successfully stored {} items in batch",
+                batch_response.processed_count
+            );
+        }
+        Ok(())
+    } else {
+        let error_msg = batch_response.error;
+        logd!(5, "[RocksDB] Batch put failed: {}", error_msg);
+        Err(StoreError::Failed(error_msg))
+    }
+}
+
+/// Key a key stored without a prefix is moved to, None for keys of instances
+fn migration_target(key: &str, prefix: &str) -> Option<String> {
+    (!prefix.is_empty() && !key.starts_with(NAMESPACE_ROOT)).then(|| format!("{}{}", prefix, key))
+}
+
+/// Move the keys stored without a prefix under [`key_prefix`]
+///
+/// ### Description
+/// Lets a store used by a single unnamed stack be shared once the stack is
+/// given an instance name. Keys under [`NAMESPACE_ROOT`], of this instance or
+/// of others, are left alone. A key that already exists under the prefix is
+/// not overwritten and its unprefixed original is kept. Every value is
+/// copied before any original is deleted, so a failed migration can be run
+/// again.
+///
+/// ### Returns
+/// * `Ok(usize)` - number of keys moved
+/// * `Err(String)` - no instance is named, or the store failed
+pub async fn migrate_to_prefix() -> Result<usize, String> {
+    let prefix = key_prefix();
+    if prefix.is_empty() {
+        return Err("No instance is named, keys have no prefix to move to".to_string());
+    }
+    let list_response = request("list_keys", |mut client| {
+        let request = tonic::Request::new(ListKeysRequest {
+            prefix: String::new(),
+            limit: 0, // 0 means no limit
+        });
+        async move { client.list_keys(request).await }
+    })
+    .await?;
+    if !list_response.error.is_empty() {
+        return Err(list_response.error);
+    }
+
+    let mut moves = Vec::new();
+    for key in list_response.keys {
+        let Some(target) = migration_target(&key, prefix) else {
+            continue;
+        };
+        if get_key(&target).await.is_ok() {
+            logd!(4, "[RocksDB] Not moving '{}', '{}' exists", key, target);
+            continue;
+        }
+        let value = get_key(&key).await?;
+        moves.push((key, target, value));
+    }
+    if moves.is_empty() {
+        return Ok(0);
+    }
+
+    batch_put_keys(
+        moves
+            .iter()
+            .map(|(_, target, value)| (target.clone(), value.clone()))
+            .collect(),
+    )
+    .await?;
+    for (key, _, _) in &moves {
+        delete_key(key).await?;
+    }
+    logd!(3, "[RocksDB] Moved {} keys under '{}'", moves.len(), prefix);
+    Ok(moves.len())
+}
+
+/// Health check of every RocksDB service endpoint
+///
+/// Endpoints that answer are marked up and the others down, so requests
+/// fail over right away.
+///
+/// ### Returns
+/// * `Ok(true)` - some endpoint is healthy
+/// * `Ok(false)` - the reachable endpoints report they are not healthy
+/// * `Err(String)` - no endpoint could be reached
+pub async fn health_check() -> Result<bool, String> {
+    let pool = pool();
+    let mut reachable = false;
+    let mut last_error = String::new();
+    for (index, endpoint) in pool.endpoints.iter().enumerate() {
+        let result = endpoint
+            .client
+            .call(|channel| async move {
+                RocksDbServiceClient::new(channel)
+                    .health(tonic::Request::new(HealthRequest {}))
+                    .await
+            })
+            .await;
+        match result {
+            Ok(response) => {
+                reachable = true;
+                let status = response.into_inner().status;
+                if DEV {
+                    logd!(
+                        1,
+                        "[RocksDB] Health check of {}: {}",
+                        endpoint.client.addr(),
+                        status
+                    );
                 }
-                Err(e) => {
-                    let error_msg = format!("gRPC request failed: {}", e);
-                    logd!(5, "[RocksDB] {}", error_msg);
-                    Err(error_msg)
+                if status == "healthy" {
+                    pool.mark_up(index);
+                    return Ok(true);
                 }
             }
+            Err(e) => {
+                pool.mark_down(index, &e);
+                last_error = format!("Health check failed: {}", e);
+            }
         }
-        Err(e) => {
-            let error_msg = format!("Failed to create client: {}", e);
-            logd!(5, "[RocksDB] {}", error_msg);
-            Err(error_msg)
-        }
+    }
+    if reachable {
+        Ok(false)
+    } else {
+        logd!(5, "[RocksDB] {}", last_error);
+        Err(last_error)
     }
 }
 
-/// Health check for the gRPC RocksDB service
-pub async fn health_check() -> Result<bool, String> {
-    if DEV {
-        logd!(
-            1,
-            "[RocksDB] Health check for service: {}",
-            *ROCKSDB_SERVICE_URL
-        );
+/// Change of a key seen by [`watch_prefixes`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// The key was created or its value changed
+    Put { key: String, value: String },
+    /// The key was removed
+    Delete { key: String },
+}
+
+/// Changes between two reads of the same prefixes, ordered by key
+pub fn diff_snapshots(
+    old: &HashMap<String, String>,
+    new: &HashMap<String, String>,
+) -> Vec<WatchEvent> {
+    let mut events: Vec<WatchEvent> = new
+        .iter()
+        .filter(|(key, value)| old.get(*key) != Some(*value))
+        .map(|(key, value)| WatchEvent::Put {
+            key: key.clone(),
+            value: value.clone(),
+        })
+        .chain(
+            old.keys()
+                .filter(|key| !new.contains_key(*key))
+                .map(|key| WatchEvent::Delete { key: key.clone() }),
+        )
+        .collect();
+    events.sort_by(|a, b| watch_event_key(a).cmp(watch_event_key(b)));
+    events
+}
+
+fn watch_event_key(event: &WatchEvent) -> &str {
+    match event {
+        WatchEvent::Put { key, .. } | WatchEvent::Delete { key } => key,
     }
+}
 
-    match RocksDbServiceClient::connect(ROCKSDB_SERVICE_URL.clone()).await {
-        Ok(mut client) => {
-            let request = tonic::Request::new(HealthRequest {});
-
-            match client.health(request).await {
-                Ok(response) => {
-                    let health_response = response.into_inner();
-                    let is_healthy = health_response.status == "healthy";
-                    if DEV {
-                        logd!(
-                            1,
-                            "[RocksDB] Health check result: {}",
-                            health_response.status
-                        );
+async fn read_prefixes(prefixes: &[String]) -> Result<HashMap<String, String>, String> {
+    let mut snapshot = HashMap::new();
+    for prefix in prefixes {
+        snapshot.extend(get_all_with_prefix(prefix).await?);
+    }
+    Ok(snapshot)
+}
+
+/// Watch the keys under `prefixes` for changes
+///
+/// ### Description
+/// The RocksDB service has no change notifications, so the prefixes are
+/// read every `interval` and compared with the previous read. Keys present
+/// at the first read are not reported, and a key changed and changed back
+/// between two reads is missed. Failed reads are logged and retried at the
+/// next interval. Watching stops when the receiver is dropped.
+/// Must be called within a tokio runtime.
+pub fn watch_prefixes(prefixes: Vec<String>, interval: Duration) -> mpsc::Receiver<WatchEvent> {
+    let (tx, rx) = mpsc::channel(100);
+    tokio::spawn(async move {
+        let mut snapshot: Option<HashMap<String, String>> = None;
+        loop {
+            match read_prefixes(&prefixes).await {
+                Ok(current) => {
+                    if let Some(previous) = &snapshot {
+                        for event in diff_snapshots(previous, &current) {
+                            if tx.send(event).await.is_err() {
+                                return;
+                            }
+                        }
                     }
-                    Ok(is_healthy)
-                }
-                Err(e) => {
-                    let error_msg = format!("Health check failed: {}", e);
-                    logd!(5, "[RocksDB] {}", error_msg);
-                    Err(error_msg)
+                    snapshot = Some(current);
                 }
+                Err(e) => logd!(4, "[RocksDB] Watch of {:?} failed: {}", prefixes, e),
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = tx.closed() => return,
             }
         }
-        Err(e) => {
-            let error_msg = format!("Failed to create client: {}", e);
-            logd!(5, "[RocksDB] {}", error_msg);
-            Err(error_msg)
+    });
+    rx
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attempt_order() {
+        assert_eq!(attempt_order(0, &[false, false, false]), vec![0, 1, 2]);
+        assert_eq!(attempt_order(1, &[false, false, false]), vec![1, 2, 0]);
+        // Down endpoints are tried last, in case every endpoint is down
+        assert_eq!(attempt_order(0, &[true, false, false]), vec![1, 2, 0]);
+        assert_eq!(attempt_order(2, &[false, true, true]), vec![0, 2, 1]);
+        assert_eq!(attempt_order(0, &[true]), vec![0]);
+    }
+
+    #[test]
+    fn test_key_prefix_of_instance() {
+        assert_eq!(prefix_of(""), "");
+        assert_eq!(prefix_of("vehicle-a"), "/pullpiri-instance/vehicle-a/");
+        assert_eq!(prefix_of(" /vehicle-a/ "), "/pullpiri-instance/vehicle-a/");
+
+        let prefix = prefix_of("vehicle-a");
+        assert_eq!(
+            migration_target("Scenario/antipinch", &prefix).as_deref(),
+            Some("/pullpiri-instance/vehicle-a/Scenario/antipinch")
+        );
+        assert_eq!(
+            migration_target("/scenario/antipinch/state", &prefix).as_deref(),
+            Some("/pullpiri-instance/vehicle-a//scenario/antipinch/state")
+        );
+        // Keys the modules keep under /pullpiri/ are moved too
+        assert_eq!(
+            migration_target("/pullpiri/settings/configs/vehicle", &prefix).as_deref(),
+            Some("/pullpiri-instance/vehicle-a//pullpiri/settings/configs/vehicle")
+        );
+        assert_eq!(
+            migration_target("/pullpiri/metrics/cpu_usage", &prefix).as_deref(),
+            Some("/pullpiri-instance/vehicle-a//pullpiri/metrics/cpu_usage")
+        );
+        // Keys of this and other instances stay where they are
+        assert_eq!(
+            migration_target("/pullpiri-instance/vehicle-a/Scenario/a", &prefix),
+            None
+        );
+        assert_eq!(
+            migration_target("/pullpiri-instance/vehicle-b/Scenario/a", &prefix),
+            None
+        );
+        assert_eq!(migration_target("Scenario/antipinch", ""), None);
+    }
+
+    #[test]
+    fn test_endpoint_urls() {
+        let settings = EtcdSettings {
+            endpoints: vec!["http://a:47007".to_string(), "http://b:47007".to_string()],
+            ..Default::default()
+        };
+        if std::env::var("ROCKSDB_SERVICE_URL").is_err() {
+            assert_eq!(endpoint_urls(&settings), settings.endpoints);
+            let empty = EtcdSettings {
+                endpoints: Vec::new(),
+                ..Default::default()
+            };
+            assert_eq!(endpoint_urls(&empty), EtcdSettings::default().endpoints);
         }
     }
+
+    fn snapshot(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_snapshots() {
+        let old = snapshot(&[
+            ("/model/a/state", "Running"),
+            ("/model/b/state", "Running"),
+            ("/model/c/state", "Dead"),
+        ]);
+        let new = snapshot(&[
+            ("/model/a/state", "Running"),
+            ("/model/b/state", "Failed"),
+            ("/model/d/state", "Created"),
+        ]);
+        assert_eq!(
+            diff_snapshots(&old, &new),
+            vec![
+                WatchEvent::Put {
+                    key: "/model/b/state".to_string(),
+                    value: "Failed".to_string(),
+                },
+                WatchEvent::Delete {
+                    key: "/model/c/state".to_string(),
+                },
+                WatchEvent::Put {
+                    key: "/model/d/state".to_string(),
+                    value: "Created".to_string(),
+                },
+            ]
+        );
+        assert!(diff_snapshots(&new, &new).is_empty());
+    }
 }
@@ -0,0 +1,234 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Event bus for notifications between components
+//!
+//! Components publish typed [`Event`]s to the [`EventBroker`] hosted by the
+//! API Server on its gRPC port, and anyone interested subscribes to a stream
+//! of them, optionally filtered by [`EventKind`]. Events are not stored: a
+//! subscriber only receives events published while it is connected.
+//!
+//! ```ignore
+//! common::eventbus::publish(
+//!     Event::new(EventKind::ModelFailed, "statemanager", "helloworld-core", "Model is Dead")
+//!         .with_attribute("state", "Dead"),
+//! );
+//! ```
+
+include!("generated/eventbus.rs");
+
+use crate::logd;
+use event_bus_connection_client::EventBusConnectionClient;
+use event_bus_connection_server::EventBusConnection;
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+/// Events buffered per subscriber before it starts lagging
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// Address of the broker, hosted by the API Server
+pub fn connect_server() -> String {
+    crate::apiserver::connect_grpc_server()
+}
+
+impl Event {
+    /// Event published now by `source` about `resource_name`
+    pub fn new(
+        kind: EventKind,
+        source: &str,
+        resource_name: &str,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            kind: kind as i32,
+            source: source.to_string(),
+            resource_name: resource_name.to_string(),
+            message: message.into(),
+            timestamp_ns: chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default(),
+            attributes: Default::default(),
+        }
+    }
+
+    pub fn with_attribute(mut self, key: &str, value: &str) -> Self {
+        self.attributes.insert(key.to_string(), value.to_string());
+        self
+    }
+}
+
+/// Publish an event without waiting for its delivery
+///
+/// Failures are logged only, an unreachable bus must not block the publisher.
+/// Must be called within a tokio runtime.
+pub fn publish(event: Event) {
+    tokio::spawn(async move {
+        let kind = event.kind().as_str_name();
+        if let Err(e) = send(event).await {
+            logd!(3, "[EventBus] Failed to publish {}: {}", kind, e.message());
+        }
+    });
+}
+
+/// Publish an event and wait for the broker
+///
+/// ### Returns
+/// * `u32` - number of subscribers the event was delivered to
+pub async fn send(event: Event) -> Result<u32, Status> {
+    let mut client = EventBusConnectionClient::connect(connect_server())
+        .await
+        .map_err(|e| Status::unavailable(format!("Failed to connect to event bus: {}", e)))?;
+    let response = client.publish(Request::new(event)).await?;
+    Ok(response.into_inner().delivered)
+}
+
+/// Stream of the events published from now on
+///
+/// ### Parameters
+/// * `kinds: Vec<EventKind>` - kinds to receive, every kind if empty
+pub async fn subscribe(kinds: Vec<EventKind>) -> Result<tonic::Streaming<Event>, Status> {
+    let mut client = EventBusConnectionClient::connect(connect_server())
+        .await
+        .map_err(|e| Status::unavailable(format!("Failed to connect to event bus: {}", e)))?;
+    let request = EventSubscription {
+        kinds: kinds.into_iter().map(|kind| kind as i32).collect(),
+    };
+    Ok(client.subscribe(Request::new(request)).await?.into_inner())
+}
+
+/// In-process broker fanning published events out to every subscriber
+#[derive(Clone)]
+pub struct EventBroker {
+    events: broadcast::Sender<Event>,
+}
+
+impl Default for EventBroker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBroker {
+    pub fn new() -> Self {
+        Self {
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+        }
+    }
+
+    /// Deliver an event to the current subscribers, returning how many there are
+    pub fn publish(&self, event: Event) -> u32 {
+        // no subscriber is not an error
+        self.events.send(event).unwrap_or(0) as u32
+    }
+}
+
+/// Whether a subscription to `kinds` receives `event`
+fn wanted(kinds: &[i32], event: &Event) -> bool {
+    kinds.is_empty() || kinds.contains(&event.kind)
+}
+
+/// Forward the wanted events to one subscriber until it disconnects
+///
+/// A subscriber that lags behind skips the missed events.
+async fn relay_events(
+    mut events: broadcast::Receiver<Event>,
+    kinds: Vec<i32>,
+    tx: mpsc::Sender<Result<Event, Status>>,
+) {
+    loop {
+        match events.recv().await {
+            Ok(event) if wanted(&kinds, &event) => {
+                if tx.send(Ok(event)).await.is_err() {
+                    break;
+                }
+            }
+            Ok(_) => {}
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                logd!(
+                    4,
+                    "[EventBus] subscriber lagged, {} events skipped",
+                    skipped
+                );
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl EventBusConnection for EventBroker {
+    type SubscribeStream = ReceiverStream<Result<Event, Status>>;
+
+    async fn publish(&self, request: Request<Event>) -> Result<Response<PublishResponse>, Status> {
+        let delivered = EventBroker::publish(self, request.into_inner());
+        Ok(Response::new(PublishResponse { delivered }))
+    }
+
+    async fn subscribe(
+        &self,
+        request: Request<EventSubscription>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let kinds = request.into_inner().kinds;
+        let (tx, rx) = mpsc::channel(16);
+        tokio::spawn(relay_events(self.events.subscribe(), kinds, tx));
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_stream::StreamExt;
+
+    #[test]
+    fn test_event_new_with_attributes() {
+        let event = Event::new(EventKind::NodeRegistered, "apiserver", "HPC", "registered")
+            .with_attribute("ip_address", "10.0.0.1");
+        assert_eq!(event.kind(), EventKind::NodeRegistered);
+        assert_eq!(event.resource_name, "HPC");
+        assert_eq!(event.attributes["ip_address"], "10.0.0.1");
+        assert!(event.timestamp_ns > 0);
+    }
+
+    #[test]
+    fn test_wanted_filters_by_kind() {
+        let event = Event::new(EventKind::ModelFailed, "statemanager", "core", "");
+        assert!(wanted(&[], &event));
+        assert!(wanted(&[EventKind::ModelFailed as i32], &event));
+        assert!(!wanted(&[EventKind::ScenarioDenied as i32], &event));
+    }
+
+    #[test]
+    fn test_publish_without_subscriber() {
+        let broker = EventBroker::new();
+        let event = Event::new(EventKind::UpdateCompleted, "actioncontroller", "s", "");
+        assert_eq!(broker.publish(event), 0);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_receives_wanted_kinds() {
+        let broker = EventBroker::new();
+        let request = Request::new(EventSubscription {
+            kinds: vec![EventKind::ScenarioDenied as i32],
+        });
+        let mut stream = EventBusConnection::subscribe(&broker, request)
+            .await
+            .unwrap()
+            .into_inner();
+
+        let delivered = EventBusConnection::publish(
+            &broker,
+            Request::new(Event::new(EventKind::ModelFailed, "sm", "core", "")),
+        )
+        .await
+        .unwrap();
+        assert_eq!(delivered.into_inner().delivered, 1);
+        broker.publish(Event::new(EventKind::ScenarioDenied, "sm", "antipinch", ""));
+
+        let event = stream.next().await.unwrap().unwrap();
+        assert_eq!(event.kind(), EventKind::ScenarioDenied);
+        assert_eq!(event.resource_name, "antipinch");
+    }
+}
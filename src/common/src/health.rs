@@ -0,0 +1,258 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Liveness and readiness of a Pullpiri process
+//!
+//! Every process registers the checks its readiness depends on, e.g. `etcd`
+//! or `grpc_server`, with [`init`] and reports their result with
+//! [`set_ready`] and [`set_not_ready`]. Checks start as not ready.
+//!
+//! With the `axum` feature, [`router`] serves
+//! * `GET /healthz` - `200` as long as the process answers HTTP
+//! * `GET /readyz` - `200` if every check passed, `503` otherwise
+//!
+//! Both return the report as JSON, so systemd units and monitoring can gate
+//! the startup of dependent components on it:
+//!
+//! ```text
+//! {"component":"statemanager","ready":false,
+//!  "checks":{"etcd":{"ready":true},"engine":{"ready":false,"message":"starting"}}}
+//! ```
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// Check of the connection to the etcd (RocksDB) service, see [`watch_etcd`]
+pub const CHECK_ETCD: &str = "etcd";
+/// Check of the gRPC server of a component
+pub const CHECK_GRPC_SERVER: &str = "grpc_server";
+
+/// Message of checks that did not report yet
+const STARTING: &str = "starting";
+
+/// Result of one check
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CheckStatus {
+    pub ready: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Readiness of a process and of each of its checks
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HealthReport {
+    pub component: String,
+    pub ready: bool,
+    pub checks: BTreeMap<String, CheckStatus>,
+}
+
+/// Checks of one process
+#[derive(Debug, Default)]
+pub struct Registry {
+    component: String,
+    checks: BTreeMap<String, CheckStatus>,
+}
+
+impl Registry {
+    /// Name the component and register its checks as not ready
+    pub fn init(&mut self, component: &str, checks: &[&str]) {
+        self.component = component.to_string();
+        for check in checks {
+            self.checks.insert(
+                check.to_string(),
+                CheckStatus {
+                    ready: false,
+                    message: Some(STARTING.to_string()),
+                },
+            );
+        }
+    }
+
+    /// Record the result of a check, registering it if needed
+    pub fn set(&mut self, check: &str, result: Result<(), String>) {
+        let status = match result {
+            Ok(()) => CheckStatus {
+                ready: true,
+                message: None,
+            },
+            Err(message) => CheckStatus {
+                ready: false,
+                message: Some(message),
+            },
+        };
+        self.checks.insert(check.to_string(), status);
+    }
+
+    /// Current readiness, ready if every check passed
+    pub fn report(&self) -> HealthReport {
+        HealthReport {
+            component: self.component.clone(),
+            ready: self.checks.values().all(|status| status.ready),
+            checks: self.checks.clone(),
+        }
+    }
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+fn with_registry<T>(f: impl FnOnce(&mut Registry) -> T) -> T {
+    let mut registry = match registry().lock() {
+        Ok(registry) => registry,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    f(&mut registry)
+}
+
+/// Name the process and register the checks its readiness depends on
+pub fn init(component: &str, checks: &[&str]) {
+    with_registry(|registry| registry.init(component, checks));
+}
+
+/// Mark a check as passed
+pub fn set_ready(check: &str) {
+    with_registry(|registry| registry.set(check, Ok(())));
+}
+
+/// Mark a check as failed with the reason
+pub fn set_not_ready(check: &str, message: impl Into<String>) {
+    with_registry(|registry| registry.set(check, Err(message.into())));
+}
+
+/// Readiness of the process
+pub fn report() -> HealthReport {
+    with_registry(|registry| registry.report())
+}
+
+/// Keep the `etcd` check up to date by probing the service periodically
+pub async fn watch_etcd(interval: Duration) {
+    loop {
+        match crate::etcd::health_check().await {
+            Ok(true) => set_ready(CHECK_ETCD),
+            Ok(false) => set_not_ready(CHECK_ETCD, "unhealthy"),
+            Err(e) => set_not_ready(CHECK_ETCD, e),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(feature = "axum")]
+mod http {
+    use super::report;
+    use axum::{
+        http::{header, StatusCode},
+        response::IntoResponse,
+        routing::get,
+        Json, Router,
+    };
+
+    pub const HEALTHZ_PATH: &str = "/healthz";
+    pub const READYZ_PATH: &str = "/readyz";
+
+    /// Routes of the liveness and readiness endpoints
+    pub fn router() -> Router {
+        Router::new()
+            .route(HEALTHZ_PATH, get(healthz))
+            .route(READYZ_PATH, get(readyz))
+    }
+
+    /// Serve the health endpoints and the metrics of the process on `addr`
+    ///
+    /// For components without another HTTP listener.
+    pub async fn serve(addr: String) {
+        let listener = match tokio::net::TcpListener::bind(&addr).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                crate::logd!(5, "Failed to bind health endpoint {}: {}", addr, e);
+                return;
+            }
+        };
+        crate::logd!(3, "Health endpoint listening on {}", addr);
+        let router = router().route(
+            "/metrics",
+            get(|| async {
+                (
+                    [(header::CONTENT_TYPE, crate::metrics::CONTENT_TYPE)],
+                    crate::metrics::render(),
+                )
+            }),
+        );
+        if let Err(e) = axum::serve(listener, router).await {
+            crate::logd!(5, "Health endpoint error: {}", e);
+        }
+    }
+
+    pub(super) async fn healthz() -> impl IntoResponse {
+        (StatusCode::OK, Json(report()))
+    }
+
+    pub(super) async fn readyz() -> impl IntoResponse {
+        let report = report();
+        let code = if report.ready {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+        (code, Json(report))
+    }
+}
+
+#[cfg(feature = "axum")]
+pub use http::{router, serve, HEALTHZ_PATH, READYZ_PATH};
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_ready_when_all_checks_pass() {
+        let mut registry = Registry::default();
+        assert!(registry.report().ready);
+
+        registry.init("statemanager", &[CHECK_ETCD, "engine"]);
+        let report = registry.report();
+        assert_eq!(report.component, "statemanager");
+        assert!(!report.ready);
+        assert_eq!(report.checks["engine"].message.as_deref(), Some("starting"));
+
+        registry.set(CHECK_ETCD, Ok(()));
+        assert!(!registry.report().ready);
+        registry.set("engine", Ok(()));
+        assert!(registry.report().ready);
+
+        registry.set(CHECK_ETCD, Err("connection refused".to_string()));
+        let report = registry.report();
+        assert!(!report.ready);
+        assert_eq!(
+            serde_json::to_value(&report.checks[CHECK_ETCD]).unwrap(),
+            serde_json::json!({"ready": false, "message": "connection refused"})
+        );
+    }
+
+    #[cfg(feature = "axum")]
+    #[tokio::test]
+    async fn test_readyz_status_follows_checks() {
+        use axum::response::IntoResponse;
+
+        let check = "health_test_check";
+        set_not_ready(check, "starting");
+        assert_eq!(
+            http::readyz().await.into_response().status(),
+            axum::http::StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(
+            http::healthz().await.into_response().status(),
+            axum::http::StatusCode::OK
+        );
+
+        set_ready(check);
+        assert!(report().checks[check].ready);
+    }
+}
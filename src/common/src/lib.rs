@@ -4,10 +4,30 @@
  */
 pub use crate::error::Result;
 
+pub mod alert;
+pub mod allocation;
+pub mod auth;
+pub mod bootstrap;
+pub mod compat;
+pub mod config_watch;
+pub mod container_events;
 pub mod error;
 pub mod etcd;
+pub mod eventbus;
+pub mod health;
+pub mod limit;
+pub mod listing;
+pub mod metrics;
+pub mod quota;
+pub mod reflection;
+pub mod replica;
+pub mod rpc;
 pub mod setting;
 pub mod spec;
+pub mod startup;
+pub mod supervise;
+pub mod trace;
+pub mod vehicle_mode;
 
 // gRPC protobuf module for RocksDB service
 pub mod rocksdbservice {
@@ -25,7 +45,8 @@ fn open_server(port: u16) -> String {
 // }
 
 fn connect_server(port: u16) -> String {
-    let ip = crate::setting::get_config().host.ip.as_str();
+    let config = crate::setting::get_config();
+    let ip = config.host.ip.as_str();
     // 0.0.0.0 is for server binding only, use 127.0.0.1 for client connections
     let connect_ip = if ip == "0.0.0.0" { "127.0.0.1" } else { ip };
     format!("http://{}:{}", connect_ip, port)
@@ -48,6 +69,10 @@ pub mod actioncontroller {
     pub fn connect_server() -> String {
         super::connect_server(47001)
     }
+
+    pub fn open_health_server() -> String {
+        super::open_server(47011)
+    }
 }
 
 pub mod apiserver {
@@ -76,6 +101,10 @@ pub mod filtergateway {
     pub fn connect_server() -> String {
         super::connect_server(47002)
     }
+
+    pub fn open_health_server() -> String {
+        super::open_server(47012)
+    }
 }
 
 pub mod monitoringserver {
@@ -88,11 +117,19 @@ pub mod monitoringserver {
     pub fn connect_server() -> String {
         super::connect_server(47003)
     }
+
+    pub fn open_metrics_server() -> String {
+        super::open_server(47013)
+    }
 }
 
 pub mod nodeagent {
     include!("generated/nodeagent.rs");
 
+    pub fn open_health_server(node_ip: &str) -> String {
+        format!("{node_ip}:47014")
+    }
+
     pub mod fromactioncontroller {
         include!("generated/nodeagent.fromactioncontroller.rs");
 
@@ -128,6 +165,10 @@ pub mod statemanager {
     pub fn connect_server() -> String {
         super::connect_server(47006)
     }
+
+    pub fn open_metrics_server() -> String {
+        super::open_server(47016)
+    }
 }
 
 pub mod logd;
@@ -148,6 +189,19 @@ pub mod external {
     }
 }
 
+/// Versioned gRPC APIs, [`compat`] converts them to the in-tree messages
+pub mod pullpiri {
+    pub mod node {
+        pub mod v1 {
+            include!("generated/pullpiri.node.v1.rs");
+        }
+
+        pub mod v1alpha {
+            include!("generated/pullpiri.node.v1alpha.rs");
+        }
+    }
+}
+
 //Unit Test Cases
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,300 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Request rate and body size limits of the REST APIs
+//!
+//! Every client address has a token bucket holding up to `burst` tokens and
+//! refilled with `requests_per_sec` tokens per second. A request that finds
+//! the bucket empty is answered with `429 Too Many Requests` and a
+//! `Retry-After` header, a body larger than `max_body_kb` with
+//! `413 Payload Too Large`. Both carry a JSON body `{"error": "..."}`. The
+//! limits are set in the `limits` section of settings.yaml.
+//!
+//! With the `axum` feature, [`enforce_limits`] applies the limits to a router.
+//! The server must be started with connect info, otherwise all clients share
+//! one bucket:
+//!
+//! ```ignore
+//! let app = Router::new()
+//!     .merge(api::router())
+//!     .layer(from_fn_with_state(Arc::new(RateLimiter::default()), common::limit::enforce_limits))
+//!     .layer(DefaultBodyLimit::disable());
+//! axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await
+//! ```
+
+use crate::setting::LimitSettings;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Number of tracked clients above which idle ones are forgotten
+const MAX_TRACKED_CLIENTS: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LimitError {
+    TooManyRequests { retry_after_secs: u64 },
+    PayloadTooLarge { max_body_kb: u64 },
+}
+
+impl std::fmt::Display for LimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitError::TooManyRequests { retry_after_secs } => {
+                write!(f, "too many requests, retry in {} s", retry_after_secs)
+            }
+            LimitError::PayloadTooLarge { max_body_kb } => {
+                write!(f, "request body larger than {} KiB", max_body_kb)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets of the clients of one REST server
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+impl RateLimiter {
+    /// Take a token of `client` for a request arriving at `now`
+    ///
+    /// ### Returns
+    /// * `Ok(())` - request accepted, or rate limiting disabled
+    /// * `Err(LimitError::TooManyRequests)` - the bucket of the client is empty
+    pub fn check(
+        &self,
+        client: IpAddr,
+        settings: &LimitSettings,
+        now: Instant,
+    ) -> Result<(), LimitError> {
+        if settings.requests_per_sec == 0 {
+            return Ok(());
+        }
+        let rate = f64::from(settings.requests_per_sec);
+        let capacity = f64::from(settings.burst.max(1));
+
+        let mut buckets = match self.buckets.lock() {
+            Ok(buckets) => buckets,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            // A full bucket is the same as no bucket
+            buckets.retain(|_, bucket| {
+                now.saturating_duration_since(bucket.updated).as_secs_f64() * rate + bucket.tokens
+                    < capacity
+            });
+        }
+
+        let bucket = buckets.entry(client).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let wait = (1.0 - bucket.tokens) / rate;
+            Err(LimitError::TooManyRequests {
+                retry_after_secs: wait.ceil().max(1.0) as u64,
+            })
+        }
+    }
+}
+
+/// Check the announced size of a request body
+///
+/// Bodies without `Content-Length` are checked while they are read.
+pub fn check_body_size(
+    content_length: Option<u64>,
+    settings: &LimitSettings,
+) -> Result<(), LimitError> {
+    match content_length {
+        Some(length) if settings.max_body_kb > 0 && length > settings.max_body_kb * 1024 => {
+            Err(LimitError::PayloadTooLarge {
+                max_body_kb: settings.max_body_kb,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(feature = "axum")]
+mod middleware {
+    use super::{check_body_size, LimitError, RateLimiter};
+    use crate::error::{ApiError, ErrorCode};
+    use axum::{
+        body::Body,
+        extract::{ConnectInfo, Request, State},
+        http::header,
+        middleware::Next,
+        response::{IntoResponse, Response},
+    };
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    impl IntoResponse for LimitError {
+        fn into_response(self) -> Response {
+            match self {
+                LimitError::TooManyRequests { retry_after_secs } => (
+                    [(header::RETRY_AFTER, retry_after_secs.to_string())],
+                    ApiError::new(ErrorCode::RateLimited, self.to_string()),
+                )
+                    .into_response(),
+                LimitError::PayloadTooLarge { .. } => {
+                    ApiError::new(ErrorCode::PayloadTooLarge, self.to_string()).into_response()
+                }
+            }
+        }
+    }
+
+    /// Reject requests above the rate or body size limits of settings.yaml
+    ///
+    /// Settings are read per request, so limit changes apply without a
+    /// restart. Routers using this layer should disable axum's own body
+    /// limit with `DefaultBodyLimit::disable()`.
+    pub async fn enforce_limits(
+        State(limiter): State<Arc<RateLimiter>>,
+        request: Request,
+        next: Next,
+    ) -> Response {
+        let settings = crate::setting::get_config().limits.clone();
+        let client = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|info| info.0.ip())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+
+        if let Err(e) = limiter.check(client, &settings, Instant::now()) {
+            crate::logd!(
+                4,
+                "Rejected {} {} of {}: {}",
+                request.method(),
+                request.uri(),
+                client,
+                e
+            );
+            return e.into_response();
+        }
+
+        let content_length = request
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        if let Err(e) = check_body_size(content_length, &settings) {
+            crate::logd!(
+                4,
+                "Rejected {} {} of {}: {}",
+                request.method(),
+                request.uri(),
+                client,
+                e
+            );
+            return e.into_response();
+        }
+        if content_length.is_some() || settings.max_body_kb == 0 {
+            return next.run(request).await;
+        }
+
+        // Streamed bodies are read up to the limit before the handler runs
+        let (parts, body) = request.into_parts();
+        let max_bytes = usize::try_from(settings.max_body_kb * 1024).unwrap_or(usize::MAX);
+        match axum::body::to_bytes(body, max_bytes).await {
+            Ok(bytes) => {
+                next.run(Request::from_parts(parts, Body::from(bytes)))
+                    .await
+            }
+            Err(_) => LimitError::PayloadTooLarge {
+                max_body_kb: settings.max_body_kb,
+            }
+            .into_response(),
+        }
+    }
+}
+
+#[cfg(feature = "axum")]
+pub use middleware::enforce_limits;
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    fn settings(requests_per_sec: u32, burst: u32) -> LimitSettings {
+        LimitSettings {
+            requests_per_sec,
+            burst,
+            max_body_kb: 1,
+        }
+    }
+
+    #[test]
+    fn test_rate_limiter_allows_burst_then_throttles() {
+        let limiter = RateLimiter::default();
+        let settings = settings(2, 3);
+        let client = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            assert_eq!(limiter.check(client, &settings, start), Ok(()));
+        }
+        assert_eq!(
+            limiter.check(client, &settings, start),
+            Err(LimitError::TooManyRequests {
+                retry_after_secs: 1
+            })
+        );
+
+        // Half a second refills one token at 2 requests per second
+        let later = start + Duration::from_millis(500);
+        assert_eq!(limiter.check(client, &settings, later), Ok(()));
+        assert!(limiter.check(client, &settings, later).is_err());
+
+        // Other clients have their own bucket
+        let other = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        assert_eq!(limiter.check(other, &settings, later), Ok(()));
+    }
+
+    #[test]
+    fn test_rate_limiter_disabled() {
+        let limiter = RateLimiter::default();
+        let client = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let now = Instant::now();
+        for _ in 0..100 {
+            assert_eq!(limiter.check(client, &settings(0, 1), now), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_check_body_size() {
+        let settings = settings(0, 1);
+        assert_eq!(check_body_size(Some(1024), &settings), Ok(()));
+        assert_eq!(check_body_size(None, &settings), Ok(()));
+        assert_eq!(
+            check_body_size(Some(1025), &settings),
+            Err(LimitError::PayloadTooLarge { max_body_kb: 1 })
+        );
+
+        let unlimited = LimitSettings {
+            max_body_kb: 0,
+            ..settings
+        };
+        assert_eq!(check_body_size(Some(u64::MAX), &unlimited), Ok(()));
+    }
+}
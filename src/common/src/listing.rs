@@ -0,0 +1,204 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Pagination, label selectors and sorting of REST list endpoints
+//!
+//! List endpoints take these query parameters next to their own field
+//! selectors:
+//! - `limit`, `offset`: page of the sorted and filtered list. `page` (from 1)
+//!   and `page_size` are accepted as an alternative.
+//! - `label`: `key=value` pairs separated by commas, all must match
+//! - `sort`: field to sort by, `order`: `asc` (default) or `desc`
+//!
+//! Responses carry a [`ListMeta`] with the number of matching items and
+//! the offset of the next page.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Largest page returned at once
+pub const MAX_LIMIT: usize = 1000;
+
+/// Common query parameters of list endpoints
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct ListQuery {
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    pub page: Option<usize>,
+    pub page_size: Option<usize>,
+    pub label: Option<String>,
+    pub sort: Option<String>,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    /// Apply the order to the result of an ascending comparison
+    pub fn apply(&self, ordering: Ordering) -> Ordering {
+        match self {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+    }
+}
+
+/// Position of a page in the full list
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ListMeta {
+    /// Number of items matching the selectors
+    pub total: usize,
+    pub offset: usize,
+    pub limit: Option<usize>,
+    /// Offset of the following page, `None` on the last page
+    pub next_offset: Option<usize>,
+}
+
+impl ListQuery {
+    /// Offset and limit of the requested page
+    ///
+    /// `limit`/`offset` take precedence over `page`/`page_size`. The limit
+    /// is capped at [`MAX_LIMIT`]; no limit returns every item.
+    pub fn window(&self) -> (usize, Option<usize>) {
+        let limit = self.limit.or(self.page_size).map(|l| l.min(MAX_LIMIT));
+        let offset = match (self.offset, self.page, limit) {
+            (Some(offset), _, _) => offset,
+            (None, Some(page), Some(limit)) => page.saturating_sub(1).saturating_mul(limit),
+            _ => 0,
+        };
+        (offset, limit)
+    }
+
+    /// Parse the `label` selector
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for entries that are not `key=value`.
+    pub fn label_selector(&self) -> Result<Vec<(String, String)>, String> {
+        let Some(selector) = &self.label else {
+            return Ok(Vec::new());
+        };
+        selector
+            .split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .map(|entry| match entry.trim().split_once('=') {
+                Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+                _ => Err(format!(
+                    "Invalid label selector '{}', expected key=value",
+                    entry
+                )),
+            })
+            .collect()
+    }
+
+    /// Sort field, `default` if none was requested
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the field is not one of `fields`.
+    pub fn sort_field<'a>(&'a self, fields: &[&str], default: &'a str) -> Result<&'a str, String> {
+        match self.sort.as_deref() {
+            None => Ok(default),
+            Some(field) if fields.contains(&field) => Ok(field),
+            Some(field) => Err(format!(
+                "Cannot sort by '{}', expected one of: {}",
+                field,
+                fields.join(", ")
+            )),
+        }
+    }
+
+    /// Cut the requested page out of the sorted and filtered items
+    pub fn paginate<T>(&self, items: Vec<T>) -> (Vec<T>, ListMeta) {
+        let total = items.len();
+        let (offset, limit) = self.window();
+        let page: Vec<T> = items
+            .into_iter()
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .collect();
+        let end = offset.saturating_add(page.len());
+        let meta = ListMeta {
+            total,
+            offset,
+            limit,
+            next_offset: (limit.is_some() && end < total).then_some(end),
+        };
+        (page, meta)
+    }
+}
+
+/// `true` if `labels` contain every pair of the selector
+pub fn matches_labels(selector: &[(String, String)], labels: &HashMap<String, String>) -> bool {
+    selector
+        .iter()
+        .all(|(key, value)| labels.get(key) == Some(value))
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(params: &str) -> ListQuery {
+        serde_json::from_str(params).unwrap()
+    }
+
+    #[test]
+    fn test_paginate_with_limit_and_offset() {
+        let items: Vec<u32> = (0..25).collect();
+
+        let (page, meta) = query(r#"{"limit": 10, "offset": 10}"#).paginate(items.clone());
+        assert_eq!(page, (10..20).collect::<Vec<_>>());
+        assert_eq!(meta.total, 25);
+        assert_eq!(meta.next_offset, Some(20));
+
+        let (page, meta) = query(r#"{"limit": 10, "offset": 20}"#).paginate(items.clone());
+        assert_eq!(page.len(), 5);
+        assert_eq!(meta.next_offset, None);
+
+        // page/page_size select the same window
+        let (page, _) = query(r#"{"page": 2, "page_size": 10}"#).paginate(items.clone());
+        assert_eq!(page, (10..20).collect::<Vec<_>>());
+
+        let (page, meta) = ListQuery::default().paginate(items);
+        assert_eq!(page.len(), 25);
+        assert_eq!(meta.limit, None);
+        assert_eq!(meta.next_offset, None);
+
+        assert_eq!(query(r#"{"limit": 5000}"#).window(), (0, Some(MAX_LIMIT)));
+    }
+
+    #[test]
+    fn test_label_selector_and_sort() {
+        let q = query(r#"{"label": "zone=front, tier=adas", "sort": "name", "order": "desc"}"#);
+        let selector = q.label_selector().unwrap();
+        let labels = HashMap::from([
+            ("zone".to_string(), "front".to_string()),
+            ("tier".to_string(), "adas".to_string()),
+        ]);
+        assert!(matches_labels(&selector, &labels));
+        assert!(!matches_labels(&selector, &HashMap::new()));
+        assert!(query(r#"{"label": "zone"}"#).label_selector().is_err());
+
+        assert_eq!(q.sort_field(&["name", "status"], "status"), Ok("name"));
+        assert_eq!(q.order.apply(1.cmp(&2)), Ordering::Greater);
+        assert!(query(r#"{"sort": "ip"}"#)
+            .sort_field(&["name"], "name")
+            .is_err());
+        assert_eq!(
+            ListQuery::default().sort_field(&["name"], "name"),
+            Ok("name")
+        );
+    }
+}
@@ -151,19 +151,23 @@ pub async fn log(level: i32, message: String) {
 pub fn log_nowait(level: i32, message: String) {
     match Handle::try_current() {
         Ok(handle) => {
+            // The spawned task does not inherit the trace of the caller
+            let trace_id = crate::trace::current_trace_id().unwrap_or_default();
             handle.spawn(async move {
-                if let Err(err) = enqueue(level, message).await {
+                if let Err(err) = enqueue_traced(level, message, trace_id).await {
                     crate::logd!(6, "logger enqueue failed: {err}");
                 }
             });
         }
         Err(_) => {
-            crate::logd!(4, "logger not running inside a Tokio runtime; dropping log");
+            // logd! would come back here, outside a runtime there is only stderr
+            eprintln!("logger not running inside a Tokio runtime; dropping log");
         }
     }
 }
 
-/// Core enqueue function shared by `log` and `log_nowait`.
+/// Core enqueue function shared by `log` and `log_nowait`, stamping the
+/// trace ID of the running task.
 ///
 /// # Arguments
 /// * `level` - Severity level code.
@@ -173,6 +177,15 @@ pub fn log_nowait(level: i32, message: String) {
 /// Returns an error when the logger is not initialized or the notify
 /// channel has been closed.
 pub async fn enqueue(level: i32, message: String) -> std::io::Result<()> {
+    let trace_id = crate::trace::current_trace_id().unwrap_or_default();
+    enqueue_traced(level, message, trace_id).await
+}
+
+/// Enqueue a message logged for the trace `trace_id`, empty if none.
+///
+/// # Errors
+/// Same as [`enqueue`].
+async fn enqueue_traced(level: i32, message: String, trace_id: String) -> std::io::Result<()> {
     let Some(gl) = LOGGER.get() else {
         return Err(std::io::Error::other("logger not initialized"));
     };
@@ -182,6 +195,7 @@ pub async fn enqueue(level: i32, message: String) -> std::io::Result<()> {
         tag: gl.tag.clone(),
         level,
         message,
+        trace_id,
     };
 
     let q = gl.q.get(&Ch::Logd).unwrap();
@@ -285,7 +299,11 @@ fn print_stdout(env: &LogEnvelope) {
     let chrono_time: DateTime<Local> = DateTime::from(sys_time);
     let time_str = chrono_time.format("%Y-%m-%d %H:%M:%S%.3f");
     let tag = env.tag.clone();
-    let message = env.message.clone();
+    let message = if env.trace_id.is_empty() {
+        env.message.clone()
+    } else {
+        format!("[{}] {}", env.trace_id, env.message)
+    };
 
     let level = match env.level {
         1 => "V",
@@ -0,0 +1,341 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Operational metrics in the Prometheus text format
+//!
+//! Every process has one global registry. Services record counters, gauges,
+//! latencies and histograms with the functions below and serve `render()` on their
+//! `/metrics` HTTP endpoint.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+/// Content type of the Prometheus text exposition format
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Latency of etcd operations, recorded by `common::etcd`
+pub const ETCD_OPERATION_SECONDS: &str = "pullpiri_etcd_operation_duration_seconds";
+
+/// 1 if an etcd endpoint answered its last request, 0 if it is down
+pub const ETCD_ENDPOINT_UP: &str = "pullpiri_etcd_endpoint_up";
+
+/// Requests `common::etcd` sent to another endpoint after a failure
+pub const ETCD_FAILOVERS_TOTAL: &str = "pullpiri_etcd_failovers_total";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MetricType {
+    Counter,
+    Gauge,
+    Summary,
+    Histogram,
+}
+
+impl MetricType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MetricType::Counter => "counter",
+            MetricType::Gauge => "gauge",
+            MetricType::Summary => "summary",
+            MetricType::Histogram => "histogram",
+        }
+    }
+}
+
+/// Value of one labelled sample
+#[derive(Debug, Default)]
+struct Sample {
+    /// Value of counters and gauges, sum of observations of summaries
+    value: f64,
+    /// Number of observations of summaries and histograms
+    count: u64,
+    /// Upper bounds of histogram buckets with the cumulative count
+    buckets: Vec<(f64, u64)>,
+}
+
+#[derive(Debug)]
+struct Family {
+    help: String,
+    metric_type: MetricType,
+    /// Samples by label set, labels sorted by name
+    samples: BTreeMap<Vec<(String, String)>, Sample>,
+}
+
+/// Collection of metric families
+#[derive(Debug, Default)]
+pub struct Registry {
+    families: Mutex<BTreeMap<String, Family>>,
+}
+
+impl Registry {
+    fn update(
+        &self,
+        name: &str,
+        help: &str,
+        metric_type: MetricType,
+        labels: &[(&str, &str)],
+        apply: impl FnOnce(&mut Sample),
+    ) {
+        let mut labels: Vec<(String, String)> = labels
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        labels.sort();
+
+        let mut families = self.families.lock().unwrap_or_else(|e| e.into_inner());
+        let family = families.entry(name.to_string()).or_insert_with(|| Family {
+            help: help.to_string(),
+            metric_type,
+            samples: BTreeMap::new(),
+        });
+        // A name keeps the type it was first recorded with
+        if family.metric_type == metric_type {
+            apply(family.samples.entry(labels).or_default());
+        }
+    }
+
+    /// Add `value` to a counter
+    pub fn add_counter(&self, name: &str, help: &str, labels: &[(&str, &str)], value: f64) {
+        self.update(name, help, MetricType::Counter, labels, |s| {
+            s.value += value
+        });
+    }
+
+    /// Set a gauge to `value`
+    pub fn set_gauge(&self, name: &str, help: &str, labels: &[(&str, &str)], value: f64) {
+        self.update(name, help, MetricType::Gauge, labels, |s| s.value = value);
+    }
+
+    /// Record one observation, e.g. a latency in seconds
+    pub fn observe(&self, name: &str, help: &str, labels: &[(&str, &str)], value: f64) {
+        self.update(name, help, MetricType::Summary, labels, |s| {
+            s.value += value;
+            s.count += 1;
+        });
+    }
+
+    /// Record one observation in a histogram with the given bucket bounds
+    ///
+    /// The bounds of the first observation of a label set are kept.
+    pub fn observe_histogram(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+        buckets: &[f64],
+        value: f64,
+    ) {
+        self.update(name, help, MetricType::Histogram, labels, |s| {
+            if s.buckets.is_empty() {
+                s.buckets = buckets.iter().map(|bound| (*bound, 0)).collect();
+            }
+            for (bound, count) in s.buckets.iter_mut() {
+                if value <= *bound {
+                    *count += 1;
+                }
+            }
+            s.value += value;
+            s.count += 1;
+        });
+    }
+
+    /// Render all metrics in the Prometheus text format
+    pub fn render(&self) -> String {
+        let families = self.families.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = String::new();
+        for (name, family) in families.iter() {
+            let _ = writeln!(out, "# HELP {} {}", name, escape(&family.help, false));
+            let _ = writeln!(out, "# TYPE {} {}", name, family.metric_type.as_str());
+            for (labels, sample) in &family.samples {
+                if family.metric_type == MetricType::Histogram {
+                    let bounds = sample
+                        .buckets
+                        .iter()
+                        .map(|(bound, count)| (bound.to_string(), *count))
+                        .chain(std::iter::once(("+Inf".to_string(), sample.count)));
+                    for (bound, count) in bounds {
+                        let mut labels = labels.clone();
+                        labels.push(("le".to_string(), bound));
+                        let _ =
+                            writeln!(out, "{}_bucket{} {}", name, format_labels(&labels), count);
+                    }
+                }
+                let labels = format_labels(labels);
+                if family.metric_type == MetricType::Summary
+                    || family.metric_type == MetricType::Histogram
+                {
+                    let _ = writeln!(out, "{}_sum{} {}", name, labels, sample.value);
+                    let _ = writeln!(out, "{}_count{} {}", name, labels, sample.count);
+                } else {
+                    let _ = writeln!(out, "{}{} {}", name, labels, sample.value);
+                }
+            }
+        }
+        out
+    }
+}
+
+fn format_labels(labels: &[(String, String)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let pairs: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, escape(v, true)))
+        .collect();
+    format!("{{{}}}", pairs.join(","))
+}
+
+/// Escape backslashes and newlines, and quotes in label values
+fn escape(text: &str, quotes: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '"' if quotes => escaped.push_str("\\\""),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Registry of this process
+pub fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(Registry::default)
+}
+
+/// Add one to a counter of the process registry
+pub fn inc_counter(name: &str, help: &str, labels: &[(&str, &str)]) {
+    registry().add_counter(name, help, labels, 1.0);
+}
+
+/// Set a gauge of the process registry
+pub fn set_gauge(name: &str, help: &str, labels: &[(&str, &str)], value: f64) {
+    registry().set_gauge(name, help, labels, value);
+}
+
+/// Record an observation in the process registry
+pub fn observe(name: &str, help: &str, labels: &[(&str, &str)], value: f64) {
+    registry().observe(name, help, labels, value);
+}
+
+/// Record a histogram observation in the process registry
+pub fn observe_histogram(
+    name: &str,
+    help: &str,
+    labels: &[(&str, &str)],
+    buckets: &[f64],
+    value: f64,
+) {
+    registry().observe_histogram(name, help, labels, buckets, value);
+}
+
+/// Render the process registry
+pub fn render() -> String {
+    registry().render()
+}
+
+/// Records the time until it is dropped as an observation in seconds
+pub struct Timer {
+    name: &'static str,
+    help: &'static str,
+    labels: Vec<(&'static str, &'static str)>,
+    start: Instant,
+}
+
+impl Timer {
+    pub fn start(
+        name: &'static str,
+        help: &'static str,
+        labels: &[(&'static str, &'static str)],
+    ) -> Self {
+        Self {
+            name,
+            help,
+            labels: labels.to_vec(),
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        observe(
+            self.name,
+            self.help,
+            &self.labels,
+            self.start.elapsed().as_secs_f64(),
+        );
+    }
+}
+
+/// Time one etcd operation, see `ETCD_OPERATION_SECONDS`
+pub(crate) fn etcd_timer(operation: &'static str) -> Timer {
+    Timer::start(
+        ETCD_OPERATION_SECONDS,
+        "Latency of etcd operations in seconds",
+        &[("operation", operation)],
+    )
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counter_gauge_and_summary() {
+        let registry = Registry::default();
+        registry.add_counter("requests_total", "Requests", &[("code", "ok")], 1.0);
+        registry.add_counter("requests_total", "Requests", &[("code", "ok")], 2.0);
+        registry.set_gauge("containers", "Containers", &[("node", "a")], 5.0);
+        registry.set_gauge("containers", "Containers", &[("node", "a")], 3.0);
+        registry.observe("latency_seconds", "Latency", &[], 0.5);
+        registry.observe("latency_seconds", "Latency", &[], 1.5);
+
+        let text = registry.render();
+        assert!(text.contains("# TYPE requests_total counter\nrequests_total{code=\"ok\"} 3\n"));
+        assert!(text.contains("# TYPE containers gauge\ncontainers{node=\"a\"} 3\n"));
+        assert!(text.contains("latency_seconds_sum 2\nlatency_seconds_count 2\n"));
+    }
+
+    #[test]
+    fn test_render_histogram() {
+        let registry = Registry::default();
+        let buckets = [0.1, 1.0];
+        registry.observe_histogram("op_seconds", "Op", &[("op", "x")], &buckets, 0.25);
+        registry.observe_histogram("op_seconds", "Op", &[("op", "x")], &buckets, 0.5);
+        registry.observe_histogram("op_seconds", "Op", &[("op", "x")], &buckets, 2.0);
+
+        let text = registry.render();
+        assert!(text.contains("# TYPE op_seconds histogram\n"));
+        assert!(text.contains("op_seconds_bucket{op=\"x\",le=\"0.1\"} 0\n"));
+        assert!(text.contains("op_seconds_bucket{op=\"x\",le=\"1\"} 2\n"));
+        assert!(text.contains("op_seconds_bucket{op=\"x\",le=\"+Inf\"} 3\n"));
+        assert!(text.contains("op_seconds_sum{op=\"x\"} 2.75\nop_seconds_count{op=\"x\"} 3\n"));
+    }
+
+    #[test]
+    fn test_labels_are_sorted_and_escaped() {
+        let registry = Registry::default();
+        registry.set_gauge("g", "Help", &[("z", "1"), ("a", "say \"hi\"\n")], 1.0);
+
+        assert!(registry
+            .render()
+            .contains("g{a=\"say \\\"hi\\\"\\n\",z=\"1\"} 1\n"));
+    }
+
+    #[test]
+    fn test_type_of_a_name_is_kept() {
+        let registry = Registry::default();
+        registry.add_counter("m", "Help", &[], 1.0);
+        registry.set_gauge("m", "Help", &[], 10.0);
+
+        assert!(registry.render().contains("# TYPE m counter\nm 1\n"));
+    }
+}
@@ -0,0 +1,300 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Resource quotas of packages per node or node group
+//!
+//! A quota limits the CPU, memory and containers the models of one package
+//! may take on each node it applies to. It applies to a single node, to the
+//! nodes whose registry labels match its `nodeSelector`, or to every node if
+//! it names neither. ApiServer stores the quotas under [`QUOTA_PREFIX`] and
+//! ActionController checks them when a scenario is triggered.
+//!
+//! ```json
+//! {
+//!   "name": "zonal",
+//!   "nodeSelector": { "node-type": "zonal" },
+//!   "maxCpu": "500m",
+//!   "maxMemory": "256Mi",
+//!   "maxContainers": 2
+//! }
+//! ```
+
+use crate::allocation::Allocation;
+use crate::etcd::StoreError;
+use crate::spec::k8s::pod::{parse_cpu_millis, parse_memory_mb};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// etcd prefix of the quotas, followed by the quota name
+pub const QUOTA_PREFIX: &str = "Quota/";
+
+/// Limits of a package on the nodes of a node or node group
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceQuota {
+    pub name: String,
+    /// Hostname of the node the quota applies to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
+    /// Labels a node must have for the quota to apply
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub node_selector: HashMap<String, String>,
+    /// CPU quantity, e.g. `2` or `500m`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cpu: Option<String>,
+    /// Memory quantity, e.g. `512Mi` or `1Gi`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_containers: Option<u32>,
+}
+
+impl ResourceQuota {
+    /// Check the name, the quantities and that some limit is set
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() || self.name.contains('/') {
+            return Err(format!("Quota name '{}' is invalid", self.name));
+        }
+        if self.max_cpu.is_none() && self.max_memory.is_none() && self.max_containers.is_none() {
+            return Err(format!("Quota '{}' sets no limit", self.name));
+        }
+        if let Some(cpu) = &self.max_cpu {
+            parse_cpu_millis(cpu)
+                .ok_or_else(|| format!("Quota '{}' has invalid maxCpu '{}'", self.name, cpu))?;
+        }
+        if let Some(memory) = &self.max_memory {
+            parse_memory_mb(memory).ok_or_else(|| {
+                format!("Quota '{}' has invalid maxMemory '{}'", self.name, memory)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// `true` if the quota applies to the node with these registry labels
+    pub fn applies_to(&self, node: &str, labels: &HashMap<String, String>) -> bool {
+        self.node.as_deref().is_none_or(|n| n == node)
+            && self
+                .node_selector
+                .iter()
+                .all(|(key, value)| labels.get(key) == Some(value))
+    }
+
+    /// Describe each limit the usage of a package on a node goes beyond
+    fn violations(&self, usage: &PackageUsage) -> Vec<String> {
+        let mut excess = Vec::new();
+        if let Some(cpu) = self.max_cpu.as_deref().and_then(parse_cpu_millis) {
+            if usage.cpu_millis > cpu {
+                excess.push(format!("{}m of {}m CPU", usage.cpu_millis, cpu));
+            }
+        }
+        if let Some(memory) = self.max_memory.as_deref().and_then(parse_memory_mb) {
+            if usage.memory_mb > memory {
+                excess.push(format!("{}MiB of {}MiB memory", usage.memory_mb, memory));
+            }
+        }
+        if let Some(containers) = self.max_containers {
+            if usage.containers > containers {
+                excess.push(format!("{} of {} containers", usage.containers, containers));
+            }
+        }
+        excess
+    }
+}
+
+/// Resources the models of a package request on one node
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageUsage {
+    pub node: String,
+    /// Thousandths of a CPU core
+    pub cpu_millis: u64,
+    pub memory_mb: u64,
+    pub containers: u32,
+}
+
+/// Add up the requests of the models of a package by node, sorted by node
+///
+/// Each model comes with the number of containers of its pod.
+pub fn package_usage<'a>(
+    models: impl IntoIterator<Item = (&'a Allocation, u32)>,
+) -> Vec<PackageUsage> {
+    let mut usage: BTreeMap<&str, PackageUsage> = BTreeMap::new();
+    for (request, containers) in models {
+        let node_usage = usage
+            .entry(request.node.as_str())
+            .or_insert_with(|| PackageUsage {
+                node: request.node.clone(),
+                ..Default::default()
+            });
+        node_usage.cpu_millis += request.cpu_millis;
+        node_usage.memory_mb += request.memory_mb;
+        node_usage.containers += containers;
+    }
+    usage.into_values().collect()
+}
+
+/// Check the usage of a package against the quotas of its nodes
+///
+/// `labels` holds the registry labels of the nodes, a node missing from it
+/// only matches quotas without a `nodeSelector`.
+///
+/// # Errors
+///
+/// Names every quota that would be exceeded, with the node and the limits.
+pub fn check(
+    quotas: &[ResourceQuota],
+    labels: &HashMap<String, HashMap<String, String>>,
+    usage: &[PackageUsage],
+) -> Result<(), String> {
+    let no_labels = HashMap::new();
+    let mut errors = Vec::new();
+    for node_usage in usage {
+        let node_labels = labels.get(&node_usage.node).unwrap_or(&no_labels);
+        for quota in quotas
+            .iter()
+            .filter(|q| q.applies_to(&node_usage.node, node_labels))
+        {
+            let excess = quota.violations(node_usage);
+            if !excess.is_empty() {
+                errors.push(format!(
+                    "quota '{}' on node '{}' would be exceeded with {}",
+                    quota.name,
+                    node_usage.node,
+                    excess.join(" and ")
+                ));
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Quota exceeded: {}", errors.join("; ")))
+    }
+}
+
+/// Read all quotas, sorted by name
+pub async fn load() -> Result<Vec<ResourceQuota>, StoreError> {
+    let mut quotas = Vec::new();
+    for (key, value) in crate::etcd::get_all_with_prefix(QUOTA_PREFIX).await? {
+        match serde_json::from_str(&value) {
+            Ok(quota) => quotas.push(quota),
+            Err(e) => crate::logd!(4, "Warning: Invalid quota '{}': {}", key, e),
+        }
+    }
+    quotas.sort_by(|a: &ResourceQuota, b| a.name.cmp(&b.name));
+    Ok(quotas)
+}
+
+/// Read one quota, `None` if it does not exist
+pub async fn get(name: &str) -> Result<Option<ResourceQuota>, StoreError> {
+    match crate::etcd::get(&format!("{}{}", QUOTA_PREFIX, name)).await {
+        Ok(value) => serde_json::from_str(&value)
+            .map(Some)
+            .map_err(|e| StoreError::Failed(format!("Invalid quota '{}': {}", name, e))),
+        Err(StoreError::NotFound) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Store a quota checked with [`ResourceQuota::validate`], replacing one of
+/// the same name
+pub async fn save(quota: &ResourceQuota) -> Result<(), StoreError> {
+    let value = serde_json::to_string(quota).map_err(|e| StoreError::Failed(e.to_string()))?;
+    crate::etcd::put(&format!("{}{}", QUOTA_PREFIX, quota.name), &value).await
+}
+
+pub async fn delete(name: &str) -> Result<(), StoreError> {
+    crate::etcd::delete(&format!("{}{}", QUOTA_PREFIX, name)).await
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quota(json: &str) -> ResourceQuota {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn usage(node: &str, cpu_millis: u64, memory_mb: u64, containers: u32) -> PackageUsage {
+        PackageUsage {
+            node: node.to_string(),
+            cpu_millis,
+            memory_mb,
+            containers,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let zonal = quota(r#"{"name": "zonal", "maxCpu": "500m", "maxContainers": 2}"#);
+        assert!(zonal.validate().is_ok());
+
+        let err = quota(r#"{"name": "empty"}"#).validate().unwrap_err();
+        assert_eq!(err, "Quota 'empty' sets no limit");
+        let err = quota(r#"{"name": "bad", "maxMemory": "lots"}"#)
+            .validate()
+            .unwrap_err();
+        assert_eq!(err, "Quota 'bad' has invalid maxMemory 'lots'");
+        assert!(quota(r#"{"name": "a/b", "maxContainers": 1}"#)
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_package_usage_adds_up_by_node() {
+        let small = |model: &str, node: &str| Allocation {
+            model: model.to_string(),
+            node: node.to_string(),
+            cpu_millis: 250,
+            memory_mb: 128,
+        };
+        let models = [small("a", "zone"), small("b", "hpc"), small("c", "zone")];
+        let by_node = package_usage(models.iter().zip([1, 2, 1]));
+        assert_eq!(
+            by_node,
+            vec![usage("hpc", 250, 128, 2), usage("zone", 500, 256, 2)]
+        );
+    }
+
+    #[test]
+    fn test_check_names_exceeded_quotas() {
+        let quotas = vec![
+            quota(
+                r#"{"name": "zonal", "nodeSelector": {"node-type": "zonal"},
+                    "maxCpu": "500m", "maxMemory": "256Mi", "maxContainers": 2}"#,
+            ),
+            quota(r#"{"name": "hpc", "node": "hpc", "maxCpu": "4"}"#),
+        ];
+        let labels = HashMap::from([(
+            "zone-front".to_string(),
+            HashMap::from([("node-type".to_string(), "zonal".to_string())]),
+        )]);
+
+        assert!(check(
+            &quotas,
+            &labels,
+            &[
+                usage("zone-front", 500, 256, 2),
+                usage("hpc", 4000, 8192, 10)
+            ]
+        )
+        .is_ok());
+
+        let err = check(
+            &quotas,
+            &labels,
+            &[usage("hpc", 4500, 0, 1), usage("zone-front", 600, 256, 3)],
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            "Quota exceeded: quota 'hpc' on node 'hpc' would be exceeded with 4500m of 4000m CPU; \
+             quota 'zonal' on node 'zone-front' would be exceeded with 600m of 500m CPU and 3 of 2 containers"
+        );
+
+        // Nodes without the labels are not limited by the selector quota
+        assert!(check(&quotas, &labels, &[usage("zone-rear", 9000, 0, 9)]).is_ok());
+    }
+}
@@ -0,0 +1,51 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! gRPC server reflection
+//!
+//! Every gRPC server of Pullpiri adds [`service`], so that clients such as
+//! `grpcurl` or `grpcui` can list the services and describe their messages
+//! without the proto files:
+//!
+//! ```text
+//! grpcurl -plaintext localhost:47098 list
+//! grpcurl -plaintext localhost:47098 describe pullpiri.node.v1.NodeRegistration
+//! ```
+//!
+//! All protos of the common build are described, whichever services the
+//! server actually runs.
+
+use tonic_reflection::server::{ServerReflection, ServerReflectionServer};
+
+/// Descriptors of all protos compiled by the common build
+pub const FILE_DESCRIPTOR_SET: &[u8] = include_bytes!("generated/pullpiri_descriptor.bin");
+
+/// Reflection service to add to a gRPC server
+pub fn service() -> ServerReflectionServer<impl ServerReflection> {
+    tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .build_v1()
+        .expect("descriptor set of the common build is valid")
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_descriptor_set_has_versioned_packages() {
+        let contains = |name: &str| {
+            FILE_DESCRIPTOR_SET
+                .windows(name.len())
+                .any(|window| window == name.as_bytes())
+        };
+        assert!(contains("apiserver.proto"));
+        assert!(contains("pullpiri/node/v1/node.proto"));
+        assert!(contains("pullpiri/node/v1alpha/node.proto"));
+        // Building decodes and checks the whole set
+        let _ = service();
+    }
+}
@@ -0,0 +1,209 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Per-node workloads of packages with a selector or distributed pattern
+//!
+//! ActionController chooses the nodes of every model of such a package when
+//! the scenario is triggered and runs one workload per node, named by
+//! [`replica_name`]. The chosen nodes are recorded as a JSON array under
+//! `Replicas/<package>/<model>`:
+//!
+//! ```json
+//! ["node1", "node2"]
+//! ```
+//!
+//! Later actions reuse the recorded nodes, and StateManager derives the state
+//! of the package from the states of the workloads.
+//!
+//! A scenario with `nodeSelector` turns the package it targets into such a
+//! node group when triggered. The scenario and its selector are recorded as
+//! a [`Group`] under `Groups/<package>`, so StateManager sees the package the
+//! way ActionController ran it.
+
+use crate::spec::artifact::Package;
+use std::collections::BTreeMap;
+
+/// etcd prefix of the replica nodes, followed by `<package>/<model>`
+pub const REPLICA_PREFIX: &str = "Replicas/";
+
+/// etcd prefix of the node groups, followed by `<package>`
+pub const GROUP_PREFIX: &str = "Groups/";
+
+/// Nodes a scenario runs its target package on
+///
+/// ```json
+/// {"scenario": "door-control", "nodeSelector": {"zone": "front-left"}}
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Group {
+    /// Qualified name of the scenario
+    pub scenario: String,
+    pub node_selector: BTreeMap<String, String>,
+}
+
+impl Group {
+    /// Selector as comma separated `key=value` pairs, e.g. `zone=front-left`
+    pub fn selector(&self) -> String {
+        self.node_selector
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Let `package` run on the nodes of the group
+    pub fn apply(&self, package: &mut Package) {
+        package.target_nodes(&self.node_selector.clone().into_iter().collect());
+    }
+}
+
+/// Node group of a package, `None` if no scenario targets it by labels
+pub async fn load_group(package: &str) -> Result<Option<Group>, String> {
+    let key = format!("{}{}", GROUP_PREFIX, package);
+    let Some((_, value)) = crate::etcd::get_all_with_prefix(&key)
+        .await?
+        .into_iter()
+        .find(|(k, _)| *k == key)
+    else {
+        return Ok(None);
+    };
+    serde_json::from_str(&value)
+        .map(Some)
+        .map_err(|e| format!("Invalid group '{}': {}", key, e))
+}
+
+/// Record the node group of a package, or remove it with `None`
+pub async fn record_group(package: &str, group: Option<&Group>) -> Result<(), String> {
+    let key = format!("{}{}", GROUP_PREFIX, package);
+    match group {
+        Some(group) => {
+            let value = serde_json::to_string(group).map_err(|e| e.to_string())?;
+            Ok(crate::etcd::put(&key, &value).await?)
+        }
+        None => match load_group(package).await? {
+            Some(_) => Ok(crate::etcd::delete(&key).await?),
+            None => Ok(()),
+        },
+    }
+}
+
+/// Name of the workload of a model on one node
+pub fn replica_name(model: &str, node: &str) -> String {
+    format!("{}-{}", model, node)
+}
+
+/// Nodes of every model of a package, by model name
+pub async fn load(package: &str) -> Result<BTreeMap<String, Vec<String>>, String> {
+    let prefix = format!("{}{}/", REPLICA_PREFIX, package);
+    let mut replicas = BTreeMap::new();
+    for (key, value) in crate::etcd::get_all_with_prefix(&prefix).await? {
+        let model = key.strip_prefix(&prefix).unwrap_or(&key).to_string();
+        match serde_json::from_str::<Vec<String>>(&value) {
+            Ok(nodes) => {
+                replicas.insert(model, nodes);
+            }
+            Err(e) => crate::logd!(4, "Warning: Invalid replicas '{}': {}", key, e),
+        }
+    }
+    Ok(replicas)
+}
+
+/// Record the nodes a model of a package runs on, replacing earlier ones
+pub async fn record(package: &str, model: &str, nodes: &[String]) -> Result<(), String> {
+    let value = serde_json::to_string(nodes).map_err(|e| e.to_string())?;
+    Ok(crate::etcd::put(&format!("{}{}/{}", REPLICA_PREFIX, package, model), &value).await?)
+}
+
+/// Names of the workloads whose states make up the state of a package
+///
+/// A package with the plain pattern has one workload per model, named like
+/// the model. Otherwise every model has one workload per recorded node, and
+/// none before its nodes were chosen.
+pub fn workloads(package: &Package, replicas: &BTreeMap<String, Vec<String>>) -> Vec<String> {
+    let plain = package.get_pattern().is_plain();
+    let mut names = Vec::new();
+    for model_info in package.get_models() {
+        let model = model_info.get_name();
+        if plain {
+            names.push(model);
+        } else if let Some(nodes) = replicas.get(&model) {
+            names.extend(nodes.iter().map(|node| replica_name(&model, node)));
+        }
+    }
+    names
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(pattern: &str) -> Package {
+        serde_yaml::from_str(&format!(
+            r#"
+apiVersion: v1
+kind: Package
+metadata:
+  name: replicated
+spec:
+  pattern:
+    - type: {}
+      replicas: 2
+  models:
+    - name: model1
+      node: node1
+      resources: {{}}
+    - name: model2
+      node: node2
+      resources: {{}}
+"#,
+            pattern
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_workloads_of_plain_package() {
+        let replicas = BTreeMap::from([("model1".to_string(), vec!["node3".to_string()])]);
+        assert_eq!(
+            workloads(&package("plain"), &replicas),
+            vec!["model1".to_string(), "model2".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_workloads_of_distributed_package() {
+        let replicas = BTreeMap::from([(
+            "model1".to_string(),
+            vec!["node1".to_string(), "node3".to_string()],
+        )]);
+        assert_eq!(
+            workloads(&package("distributed"), &replicas),
+            vec!["model1-node1".to_string(), "model1-node3".to_string()]
+        );
+        assert!(workloads(&package("distributed"), &BTreeMap::new()).is_empty());
+    }
+
+    #[test]
+    fn test_group_of_plain_package() {
+        let group: Group = serde_json::from_str(
+            r#"{"scenario": "door-control", "nodeSelector": {"zone": "front-left", "tier": "a"}}"#,
+        )
+        .unwrap();
+        assert_eq!(group.selector(), "tier=a,zone=front-left");
+
+        let mut package = package("plain");
+        group.apply(&mut package);
+        let replicas = BTreeMap::from([(
+            "model1".to_string(),
+            vec!["zone1".to_string(), "zone2".to_string()],
+        )]);
+        assert_eq!(
+            workloads(&package, &replicas),
+            vec!["model1-zone1".to_string(), "model1-zone2".to_string()]
+        );
+    }
+}
@@ -0,0 +1,429 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Timeouts, retries and circuit breaking of gRPC clients
+//!
+//! [`RpcClient`] connects to a server with a connect timeout and runs calls
+//! with a deadline. Calls failing with `Unavailable` or `ResourceExhausted`
+//! are retried with exponential backoff, since the server did not handle
+//! them. Calls running into their deadline are not retried, because the
+//! server may have handled them already.
+//!
+//! After `breaker_threshold` calls to a server failed in a row, its circuit
+//! opens and calls are rejected right away with the last error for
+//! `breaker_cooldown_ms`. Then one call is let through again; the circuit
+//! closes once a call succeeds. The defaults are set in the `grpc` section
+//! of settings.yaml.
+//!
+//! ```ignore
+//! let request = &request;
+//! RpcClient::new("StateManager", connect_server())
+//!     .call(|channel| async move {
+//!         StateManagerConnectionClient::new(channel)
+//!             .send_action(request.clone())
+//!             .await
+//!     })
+//!     .await
+//! ```
+
+use crate::setting::GrpcSettings;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Status};
+
+static BREAKERS: OnceLock<Mutex<HashMap<String, Breaker>>> = OnceLock::new();
+
+/// Timeouts, retries and circuit breaking of calls to one server
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcPolicy {
+    pub connect_timeout: Duration,
+    /// Deadline of a single attempt, `None` for calls that run indefinitely
+    pub call_timeout: Option<Duration>,
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Failed calls in a row opening the circuit, never opened if 0
+    pub breaker_threshold: u32,
+    pub breaker_cooldown: Duration,
+}
+
+impl From<&GrpcSettings> for RpcPolicy {
+    fn from(settings: &GrpcSettings) -> Self {
+        Self {
+            connect_timeout: Duration::from_millis(settings.connect_timeout_ms),
+            call_timeout: (settings.call_timeout_ms > 0)
+                .then(|| Duration::from_millis(settings.call_timeout_ms)),
+            max_retries: settings.max_retries,
+            initial_backoff: Duration::from_millis(settings.initial_backoff_ms),
+            max_backoff: Duration::from_millis(settings.max_backoff_ms),
+            breaker_threshold: settings.breaker_threshold,
+            breaker_cooldown: Duration::from_millis(settings.breaker_cooldown_ms),
+        }
+    }
+}
+
+impl RpcPolicy {
+    /// Policy of the current settings
+    pub fn current() -> Self {
+        Self::from(&crate::setting::get_config().grpc)
+    }
+
+    /// Wait before retry `retry`, counted from 0
+    ///
+    /// The wait doubles with every retry up to `max_backoff`; a random part
+    /// of up to half of it keeps clients from retrying in lockstep.
+    fn backoff(&self, retry: u32) -> Duration {
+        let wait = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff);
+        wait.mul_f64(1.0 - random_fraction() / 2.0)
+    }
+}
+
+/// Client of one gRPC server
+///
+/// The channel is established by the first call and shared by all clones.
+#[derive(Debug, Clone)]
+pub struct RpcClient {
+    service: String,
+    addr: String,
+    policy: RpcPolicy,
+    channel: Arc<OnceCell<Channel>>,
+}
+
+impl RpcClient {
+    /// Client of `service` listening at `addr`, with the current policy
+    ///
+    /// ### Parameters
+    /// * `service: &str` - name of the server used in error messages
+    /// * `addr: impl Into<String>` - URL of the server, e.g. `http://127.0.0.1:47006`
+    pub fn new(service: &str, addr: impl Into<String>) -> Self {
+        Self {
+            service: service.to_string(),
+            addr: addr.into(),
+            policy: RpcPolicy::current(),
+            channel: Arc::new(OnceCell::new()),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: RpcPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Replace the deadline of calls, `None` for calls that run indefinitely
+    pub fn with_call_timeout(mut self, call_timeout: Option<Duration>) -> Self {
+        self.policy.call_timeout = call_timeout;
+        self
+    }
+
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    /// Channel to the server, connected on first use
+    ///
+    /// ### Returns
+    /// * `Ok(Channel)` - connected channel
+    /// * `Err(Status)` - `DeadlineExceeded` if connecting timed out,
+    ///   `Unavailable` if the connection failed
+    pub async fn channel(&self) -> Result<Channel, Status> {
+        self.channel
+            .get_or_try_init(|| self.connect())
+            .await
+            .cloned()
+    }
+
+    async fn connect(&self) -> Result<Channel, Status> {
+        let endpoint = Endpoint::from_shared(self.addr.clone()).map_err(|e| {
+            Status::invalid_argument(format!("Invalid address of {}: {}", self.service, e))
+        })?;
+        match tokio::time::timeout(self.policy.connect_timeout, endpoint.connect()).await {
+            Ok(Ok(channel)) => Ok(channel),
+            Ok(Err(e)) => Err(Status::unavailable(format!(
+                "Failed to connect to {} at {}: {}",
+                self.service, self.addr, e
+            ))),
+            Err(_) => Err(Status::deadline_exceeded(format!(
+                "Timeout while connecting to {} at {}",
+                self.service, self.addr
+            ))),
+        }
+    }
+
+    /// Run a call with the policy of the client
+    ///
+    /// ### Parameters
+    /// * `call: F` - makes one attempt of the call on the given channel, is
+    ///   invoked again for every retry
+    /// ### Returns
+    /// * `Result<T, Status>` - result of the last attempt, or the last error
+    ///   of the server while its circuit is open
+    pub async fn call<T, F, Fut>(&self, mut call: F) -> Result<T, Status>
+    where
+        F: FnMut(Channel) -> Fut,
+        Fut: Future<Output = Result<T, Status>>,
+    {
+        if let Some(e) = open_circuit(&self.addr) {
+            return Err(e);
+        }
+
+        let mut retry = 0;
+        loop {
+            let result = match self.channel().await {
+                Ok(channel) => self.with_deadline(call(channel)).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Err(e) if is_retryable(&e) && retry < self.policy.max_retries => {
+                    let wait = self.policy.backoff(retry);
+                    crate::logd!(
+                        3,
+                        "Call to {} failed, retrying in {:?}: {}",
+                        self.service,
+                        wait,
+                        e.message()
+                    );
+                    tokio::time::sleep(wait).await;
+                    retry += 1;
+                }
+                result => {
+                    record_result(&self.addr, &self.policy, result.as_ref().err());
+                    return result;
+                }
+            }
+        }
+    }
+
+    async fn with_deadline<T>(
+        &self,
+        call: impl Future<Output = Result<T, Status>>,
+    ) -> Result<T, Status> {
+        let Some(call_timeout) = self.policy.call_timeout else {
+            return call.await;
+        };
+        match tokio::time::timeout(call_timeout, call).await {
+            Ok(result) => result,
+            Err(_) => Err(Status::deadline_exceeded(format!(
+                "Timeout while waiting for {} at {} to respond",
+                self.service, self.addr
+            ))),
+        }
+    }
+}
+
+/// Errors of calls the server did not handle
+fn is_retryable(status: &Status) -> bool {
+    matches!(status.code(), Code::Unavailable | Code::ResourceExhausted)
+}
+
+/// Errors counting against the circuit of a server
+fn is_failure(status: &Status) -> bool {
+    matches!(status.code(), Code::Unavailable | Code::DeadlineExceeded)
+}
+
+/// Circuit of one server
+#[derive(Debug)]
+struct Breaker {
+    failures: u32,
+    open_until: Option<Instant>,
+    last_error: Status,
+}
+
+fn breakers() -> std::sync::MutexGuard<'static, HashMap<String, Breaker>> {
+    let breakers = BREAKERS.get_or_init(|| Mutex::new(HashMap::new()));
+    match breakers.lock() {
+        Ok(breakers) => breakers,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// Error rejecting a call while the circuit of `addr` is open
+fn open_circuit(addr: &str) -> Option<Status> {
+    match breakers().get(addr) {
+        Some(Breaker {
+            open_until: Some(open_until),
+            last_error,
+            ..
+        }) if Instant::now() < *open_until => Some(Status::new(
+            last_error.code(),
+            format!("{} (circuit open)", last_error.message()),
+        )),
+        _ => None,
+    }
+}
+
+/// Count the result of a call against the circuit of `addr`
+fn record_result(addr: &str, policy: &RpcPolicy, error: Option<&Status>) {
+    let mut breakers = breakers();
+    let Some(error) = error.filter(|e| is_failure(e)) else {
+        breakers.remove(addr);
+        return;
+    };
+    if policy.breaker_threshold == 0 {
+        return;
+    }
+    let breaker = breakers.entry(addr.to_string()).or_insert_with(|| Breaker {
+        failures: 0,
+        open_until: None,
+        last_error: error.clone(),
+    });
+    breaker.failures += 1;
+    breaker.last_error = error.clone();
+    if breaker.failures >= policy.breaker_threshold {
+        breaker.open_until = Some(Instant::now() + policy.breaker_cooldown);
+    }
+}
+
+/// Random value from 0.0 to 1.0
+fn random_fraction() -> f64 {
+    use std::hash::{BuildHasher, Hasher};
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_i64(chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default());
+    hasher.finish() as f64 / u64::MAX as f64
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn policy() -> RpcPolicy {
+        RpcPolicy {
+            connect_timeout: Duration::from_millis(500),
+            call_timeout: Some(Duration::from_millis(100)),
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+            breaker_threshold: 2,
+            breaker_cooldown: Duration::from_secs(60),
+        }
+    }
+
+    // Client with a channel that is never used by the scripted calls
+    fn client(addr: &str) -> RpcClient {
+        let channel = Endpoint::from_shared(addr.to_string())
+            .unwrap()
+            .connect_lazy();
+        RpcClient {
+            service: "Test".to_string(),
+            addr: addr.to_string(),
+            policy: policy(),
+            channel: Arc::new(OnceCell::new_with(Some(channel))),
+        }
+    }
+
+    #[test]
+    fn test_policy_from_settings() {
+        let policy = RpcPolicy::from(&GrpcSettings::default());
+        assert_eq!(policy.connect_timeout, Duration::from_secs(3));
+        assert_eq!(policy.call_timeout, Some(Duration::from_secs(30)));
+
+        let settings = GrpcSettings {
+            call_timeout_ms: 0,
+            ..Default::default()
+        };
+        assert_eq!(RpcPolicy::from(&settings).call_timeout, None);
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let policy = policy();
+        let first = policy.backoff(0);
+        assert!(first >= Duration::from_micros(500) && first <= Duration::from_millis(1));
+        let capped = policy.backoff(10);
+        assert!(capped >= Duration::from_millis(2) && capped <= Duration::from_millis(4));
+        assert!(policy.backoff(u32::MAX) <= Duration::from_millis(4));
+    }
+
+    #[tokio::test]
+    async fn test_call_retries_unavailable() {
+        let client = client("http://127.0.0.1:1");
+        let attempts = AtomicU32::new(0);
+        let attempts = &attempts;
+        let result = client
+            .call(|_| async move {
+                match attempts.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(Status::unavailable("starting")),
+                    _ => Ok("done"),
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), "done");
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_call_does_not_retry_other_errors() {
+        let client = client("http://127.0.0.1:2");
+        let attempts = AtomicU32::new(0);
+        let attempts = &attempts;
+        let result: Result<(), Status> = client
+            .call(|_| async move {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(Status::invalid_argument("bad request"))
+            })
+            .await;
+        assert_eq!(result.unwrap_err().code(), Code::InvalidArgument);
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_call_deadline() {
+        let client = client("http://127.0.0.1:3");
+        let result: Result<(), Status> = client
+            .call(|_| async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(())
+            })
+            .await;
+        let error = result.unwrap_err();
+        assert_eq!(error.code(), Code::DeadlineExceeded);
+        assert!(error.message().contains("Test at http://127.0.0.1:3"));
+    }
+
+    #[tokio::test]
+    async fn test_circuit_opens_after_failures() {
+        let client = client("http://127.0.0.1:4");
+        let attempts = AtomicU32::new(0);
+        let attempts = &attempts;
+        for _ in 0..2 {
+            let result: Result<(), Status> = client
+                .call(|_| async move {
+                    attempts.fetch_add(1, Ordering::SeqCst);
+                    Err(Status::unavailable("down"))
+                })
+                .await;
+            assert_eq!(result.unwrap_err().message(), "down");
+        }
+        assert_eq!(attempts.load(Ordering::SeqCst), 6);
+
+        let result: Result<(), Status> = client.call(|_| async { Ok(()) }).await;
+        let error = result.unwrap_err();
+        assert_eq!(error.code(), Code::Unavailable);
+        assert_eq!(error.message(), "down (circuit open)");
+        assert_eq!(attempts.load(Ordering::SeqCst), 6);
+    }
+
+    #[tokio::test]
+    async fn test_connect_failure() {
+        let client = RpcClient::new("Test", "http://127.0.0.1:1").with_policy(RpcPolicy {
+            max_retries: 0,
+            ..policy()
+        });
+        let error = client.channel().await.unwrap_err();
+        assert!(matches!(
+            error.code(),
+            Code::Unavailable | Code::DeadlineExceeded
+        ));
+        assert!(error.message().contains("Test at http://127.0.0.1:1"));
+    }
+}
@@ -2,16 +2,56 @@
 * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
 * SPDX-License-Identifier: Apache-2.0
 */
+use crate::config_watch;
 use serde::Deserialize;
-use std::sync::OnceLock;
-static SETTINGS: OnceLock<Settings> = OnceLock::new();
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock, RwLock};
+use tokio::sync::watch;
 
-#[derive(Deserialize)]
+/// Location of the settings file shared by all components
+pub const SETTINGS_PATH: &str = "/etc/pullpiri/settings.yaml";
+
+static SETTINGS: OnceLock<RwLock<Arc<Settings>>> = OnceLock::new();
+static WATCHER: OnceLock<watch::Receiver<Arc<Settings>>> = OnceLock::new();
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct Settings {
     pub host: HostSettings,
+    #[serde(default)]
+    pub recovery: RecoverySettings,
+    #[serde(default)]
+    pub backoff: BackoffSettings,
+    #[serde(default)]
+    pub action: ActionSettings,
+    #[serde(default)]
+    pub storage: StorageSettings,
+    #[serde(default)]
+    pub auth: AuthSettings,
+    #[serde(default)]
+    pub monitoring: MonitoringSettings,
+    #[serde(default)]
+    pub logging: LoggingSettings,
+    #[serde(default)]
+    pub limits: LimitSettings,
+    #[serde(default)]
+    pub state: StateSettings,
+    #[serde(default)]
+    pub trace: TraceSettings,
+    #[serde(default)]
+    pub grpc: GrpcSettings,
+    #[serde(default)]
+    pub etcd: EtcdSettings,
+    #[serde(default)]
+    pub heartbeat: HeartbeatSettings,
+    #[serde(default)]
+    pub gc: GcSettings,
+    #[serde(default)]
+    pub startup: StartupSettings,
+    #[serde(default)]
+    pub bootstrap: BootstrapSettings,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct HostSettings {
     pub name: String,
     pub ip: String,
@@ -19,30 +59,683 @@ pub struct HostSettings {
     pub role: String,
 }
 
-fn parse_settings_yaml() -> Settings {
-    let default_settings: Settings = Settings {
+/// Restart backoff of crashing models, applied by StateManager
+///
+/// ```yaml
+/// backoff:
+///   base_ms: 1000
+///   cap_ms: 300000
+///   jitter: 0.2
+///   max_retries: 6
+///   reset_after_ms: 600000
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct BackoffSettings {
+    /// Delay before the first restart
+    pub base_ms: u64,
+    /// Upper bound of the doubling delay
+    pub cap_ms: u64,
+    /// Random share of the delay added or removed, from 0.0 to 1.0
+    pub jitter: f64,
+    /// Restarts before a model is Failed for good
+    pub max_retries: u32,
+    /// A crash after this long without one starts counting from zero again
+    pub reset_after_ms: u64,
+}
+
+impl Default for BackoffSettings {
+    fn default() -> Self {
+        Self {
+            base_ms: 1_000,
+            cap_ms: 300_000,
+            jitter: 0.2,
+            max_retries: 6,
+            reset_after_ms: 600_000,
+        }
+    }
+}
+
+/// Recovery of failed models by StateManager
+///
+/// The strategies are tried in order, each `attempts` times, unless the
+/// package of the model lists its own in the `recovery.pullpiri.io/strategies`
+/// annotation. Attempts are forgotten once a model did not fail for
+/// `reset_after_secs`.
+///
+/// ```yaml
+/// recovery:
+///   strategies: [manual]   # restart, reschedule, rollback or manual
+///   attempts: 1
+///   reset_after_secs: 600
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RecoverySettings {
+    pub strategies: Vec<String>,
+    pub attempts: u32,
+    pub reset_after_secs: u64,
+}
+
+impl Default for RecoverySettings {
+    fn default() -> Self {
+        Self {
+            strategies: vec!["manual".to_string()],
+            attempts: 1,
+            reset_after_secs: 600,
+        }
+    }
+}
+
+/// Model operations of ActionController
+///
+/// ```yaml
+/// action:
+///   max_concurrent_models: 4
+///   stop_timeout_ms: 10000
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ActionSettings {
+    /// Models of a package started, stopped or updated at the same time
+    pub max_concurrent_models: usize,
+    /// Time a stopped model has to be reported as exited
+    pub stop_timeout_ms: u64,
+}
+
+impl Default for ActionSettings {
+    fn default() -> Self {
+        Self {
+            max_concurrent_models: 4,
+            stop_timeout_ms: 10_000,
+        }
+    }
+}
+
+/// Where ApiServer keeps artifacts and their versions
+///
+/// ```yaml
+/// storage:
+///   backend: s3            # etcd (default), file or s3
+///   path: /var/lib/pullpiri/artifacts
+///   s3:
+///     endpoint: http://10.0.0.5:9000
+///     bucket: pullpiri
+///     region: us-east-1
+///     access_key: pullpiri
+///     secret_key: secret
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct StorageSettings {
+    pub backend: StorageBackend,
+    /// Directory of the `file` backend
+    pub path: String,
+    /// Object store of the `s3` backend
+    pub s3: S3Settings,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// Key-value store shared with the other components
+    #[default]
+    Etcd,
+    /// One file per key in a local directory
+    File,
+    /// S3-compatible object store, for large artifacts such as maps or ML models
+    S3,
+}
+
+/// S3-compatible object store, addressed with path-style URLs
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct S3Settings {
+    /// Base URL of the service, e.g. `http://10.0.0.5:9000`
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+}
+
+/// Bearer tokens accepted by the REST APIs of ApiServer and SettingsService
+///
+/// ```yaml
+/// auth:
+///   enabled: true
+///   tokens:
+///     - name: ci
+///       token: 3f6c0b2e9d
+///       role: admin        # admin, operator or viewer
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct AuthSettings {
+    /// Every request is allowed when disabled
+    pub enabled: bool,
+    pub tokens: Vec<TokenSettings>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct TokenSettings {
+    /// Who the token was given to, for logs
+    pub name: String,
+    pub token: String,
+    pub role: crate::auth::Role,
+}
+
+/// Time series of container and node metrics kept by MonitoringServer
+///
+/// ```yaml
+/// monitoring:
+///   retention_secs: 86400
+///   persist_path: /var/lib/pullpiri/timeseries.json
+///   persist_interval_secs: 60
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct MonitoringSettings {
+    /// Samples older than this are dropped
+    pub retention_secs: u64,
+    /// File the samples are saved to and restored from, kept in memory only if empty
+    pub persist_path: String,
+    /// Time between two saves of the samples
+    pub persist_interval_secs: u64,
+}
+
+impl Default for MonitoringSettings {
+    fn default() -> Self {
+        Self {
+            retention_secs: 3_600,
+            persist_path: String::new(),
+            persist_interval_secs: 60,
+        }
+    }
+}
+
+/// Log outputs of SettingsService, in addition to stdout
+///
+/// ```yaml
+/// logging:
+///   modules:                 # levels of single modules, changeable at runtime
+///     settingsservice::settings_api: debug
+///   file:
+///     path: /var/log/pullpiri/settingsservice.log
+///     max_size_mb: 10
+///     rotation: daily        # never (default), hourly or daily
+///     max_files: 5
+///   journald: true
+///   tcp:
+///     address: 10.0.0.5:5170 # receives one JSON object per line
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct LoggingSettings {
+    /// Level of each module, by module path
+    pub modules: BTreeMap<String, String>,
+    /// Rotating log file, none if not set
+    pub file: Option<FileLogSettings>,
+    /// Send the logs to the systemd journal
+    pub journald: bool,
+    /// Forward the logs as JSON lines over TCP, none if not set
+    pub tcp: Option<TcpLogSettings>,
+}
+
+/// Log file, rotated by size and time
+///
+/// The current file is renamed to `<path>.1`, the older ones shifted up to
+/// `<path>.<max_files>`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct FileLogSettings {
+    pub path: String,
+    /// A file growing larger is rotated, never if 0
+    pub max_size_mb: u64,
+    pub rotation: LogRotation,
+    /// Rotated files kept besides the current one
+    pub max_files: usize,
+}
+
+impl Default for FileLogSettings {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            max_size_mb: 10,
+            rotation: LogRotation::Never,
+            max_files: 5,
+        }
+    }
+}
+
+/// Time-based rotation of a log file
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    /// Rotated by size only
+    #[default]
+    Never,
+    Hourly,
+    Daily,
+}
+
+/// Log collector receiving JSON lines over TCP
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct TcpLogSettings {
+    /// `host:port` of the collector
+    pub address: String,
+}
+
+/// Request rate and body size limits of the REST APIs of ApiServer and
+/// SettingsService
+///
+/// ```yaml
+/// limits:
+///   requests_per_sec: 20     # per client address, unlimited if 0
+///   burst: 40                # requests accepted at once before throttling
+///   max_body_kb: 2048        # larger request bodies are rejected, unlimited if 0
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct LimitSettings {
+    /// Rate a client's requests are accepted at in the long run
+    pub requests_per_sec: u32,
+    /// Requests a client may send in a row after being idle
+    pub burst: u32,
+    /// Largest accepted request body
+    pub max_body_kb: u64,
+}
+
+impl Default for LimitSettings {
+    fn default() -> Self {
+        Self {
+            requests_per_sec: 20,
+            burst: 40,
+            max_body_kb: 2048,
+        }
+    }
+}
+
+/// How StateManager treats resource states edited in etcd directly, and
+/// when it quarantines a flapping scenario
+///
+/// ```yaml
+/// state:
+///   drift_policy: trust-etcd   # trust-etcd (default) or trust-memory
+///   watch_interval_ms: 2000    # not watched if 0
+///   flap_window_secs: 300
+///   flap_threshold: 20         # never quarantined if 0
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct StateSettings {
+    /// Which side wins when etcd and StateManager disagree
+    pub drift_policy: DriftPolicy,
+    /// Interval the state keys in etcd are read at
+    pub watch_interval_ms: u64,
+    /// Sliding window the transitions of a resource are counted in
+    pub flap_window_secs: u64,
+    /// Transitions within the window that quarantine a scenario
+    pub flap_threshold: usize,
+}
+
+impl Default for StateSettings {
+    fn default() -> Self {
+        Self {
+            drift_policy: DriftPolicy::default(),
+            watch_interval_ms: 2_000,
+            flap_window_secs: 300,
+            flap_threshold: 20,
+        }
+    }
+}
+
+/// Resolution of a state that differs between etcd and StateManager
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum DriftPolicy {
+    /// StateManager adopts the state found in etcd
+    #[default]
+    TrustEtcd,
+    /// StateManager writes its own state back to etcd
+    TrustMemory,
+}
+
+/// Export of request traces, see [`crate::trace`]
+///
+/// ```yaml
+/// trace:
+///   otlp_endpoint: http://10.0.0.5:4318   # spans not exported if empty
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct TraceSettings {
+    /// Base URL of an OTLP/HTTP collector, spans go to `/v1/traces`
+    pub otlp_endpoint: String,
+}
+
+/// Timeouts, retries and circuit breaking of the gRPC clients, see
+/// [`crate::rpc`]
+///
+/// ```yaml
+/// grpc:
+///   connect_timeout_ms: 3000
+///   call_timeout_ms: 30000     # no deadline if 0
+///   max_retries: 2             # retries of unavailable servers
+///   initial_backoff_ms: 100
+///   max_backoff_ms: 2000
+///   breaker_threshold: 5       # failures in a row opening the circuit, never if 0
+///   breaker_cooldown_ms: 30000
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct GrpcSettings {
+    /// Time to establish a connection
+    pub connect_timeout_ms: u64,
+    /// Deadline of a single call
+    pub call_timeout_ms: u64,
+    /// Retries of a call after the first attempt
+    pub max_retries: u32,
+    /// Wait before the first retry, doubled on every further retry
+    pub initial_backoff_ms: u64,
+    /// Longest wait between retries
+    pub max_backoff_ms: u64,
+    /// Failed calls in a row after which a server is not called anymore
+    pub breaker_threshold: u32,
+    /// Time an open circuit rejects calls before one is let through again
+    pub breaker_cooldown_ms: u64,
+}
+
+impl Default for GrpcSettings {
+    fn default() -> Self {
+        Self {
+            connect_timeout_ms: 3_000,
+            call_timeout_ms: 30_000,
+            max_retries: 2,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 2_000,
+            breaker_threshold: 5,
+            breaker_cooldown_ms: 30_000,
+        }
+    }
+}
+
+/// Endpoints and timeouts of the key-value store behind [`crate::etcd`]
+///
+/// Requests go to the first healthy endpoint. An endpoint failing with a
+/// connection error or timeout is skipped for `health_check_interval_ms`,
+/// then it is checked again. The `ROCKSDB_SERVICE_URL` environment variable,
+/// a comma separated list, replaces `endpoints`.
+///
+/// Stacks sharing one store are kept apart by `instance`, replaced by the
+/// `PULLPIRI_INSTANCE` environment variable: the keys of an instance are
+/// stored under `/pullpiri-instance/<instance>/`.
+///
+/// ```yaml
+/// etcd:
+///   endpoints:
+///     - http://10.0.0.1:47007
+///     - http://10.0.0.2:47007
+///   connect_timeout_ms: 2000
+///   request_timeout_ms: 5000     # no deadline if 0
+///   health_check_interval_ms: 10000
+///   instance: vehicle-a          # keys are not prefixed if empty
+///   migrate_keys: true           # ApiServer moves unprefixed keys at startup
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct EtcdSettings {
+    pub endpoints: Vec<String>,
+    /// Time to establish a connection to an endpoint
+    pub connect_timeout_ms: u64,
+    /// Deadline of a single request
+    pub request_timeout_ms: u64,
+    /// Time a failed endpoint is skipped before it is checked again
+    pub health_check_interval_ms: u64,
+    /// Name of the stack, whose keys are stored under `/pullpiri-instance/<instance>/`
+    pub instance: String,
+    /// Whether ApiServer moves the keys stored without a prefix under the
+    /// prefix of the instance when it starts
+    pub migrate_keys: bool,
+}
+
+impl Default for EtcdSettings {
+    fn default() -> Self {
+        Self {
+            endpoints: vec![String::from("http://localhost:47007")],
+            connect_timeout_ms: 2_000,
+            request_timeout_ms: 5_000,
+            health_check_interval_ms: 10_000,
+            instance: String::new(),
+            migrate_keys: false,
+        }
+    }
+}
+
+/// Node heartbeats, pushed by ApiServer to every NodeAgent
+///
+/// A node is marked NotReady once it has not sent a heartbeat for
+/// `liveness_window_secs`, measured by the clock of ApiServer. Changes reach
+/// the agents with their next heartbeat acknowledgement.
+///
+/// ```yaml
+/// heartbeat:
+///   interval_secs: 30
+///   liveness_window_secs: 90
+///   max_clock_skew_secs: 5      # no alert if 0
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct HeartbeatSettings {
+    /// Time between two heartbeats of a node
+    pub interval_secs: u64,
+    /// Time without a heartbeat after which a node is NotReady
+    pub liveness_window_secs: u64,
+    /// Offset of a node clock from the ApiServer clock that raises an alert
+    pub max_clock_skew_secs: u64,
+}
+
+impl Default for HeartbeatSettings {
+    fn default() -> Self {
+        Self {
+            interval_secs: 30,
+            liveness_window_secs: 90,
+            max_clock_skew_secs: 5,
+        }
+    }
+}
+
+impl HeartbeatSettings {
+    /// Liveness window, at least two heartbeat intervals
+    pub fn liveness_window(&self) -> u64 {
+        self.liveness_window_secs.max(self.interval_secs.max(1) * 2)
+    }
+}
+
+/// Garbage collection of orphaned artifacts by ApiServer
+///
+/// ```yaml
+/// gc:
+///   interval_secs: 300          # not scanned if 0
+///   delete: false               # only reported if false
+///   grace_period_secs: 86400
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct GcSettings {
+    /// Time between two scans for orphaned artifacts
+    pub interval_secs: u64,
+    /// Whether orphaned artifacts are deleted
+    pub delete: bool,
+    /// Time an artifact must stay orphaned before it is deleted
+    pub grace_period_secs: u64,
+}
+
+impl Default for GcSettings {
+    fn default() -> Self {
+        Self {
+            interval_secs: 300,
+            delete: false,
+            grace_period_secs: 86_400,
+        }
+    }
+}
+
+/// Dependencies a component waits for before it starts, see [`crate::startup`]
+///
+/// ```yaml
+/// startup:
+///   timeout_secs: 60            # waits without limit if 0
+///   initial_delay_ms: 500
+///   max_delay_ms: 10000
+///   dependencies:               # defaults of the component if missing
+///     filtergateway: [etcd, actioncontroller]
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct StartupSettings {
+    /// Time after which a component starts without its dependencies
+    pub timeout_secs: u64,
+    /// Delay before probing a missing dependency again, doubled each time
+    pub initial_delay_ms: u64,
+    /// Upper bound of the delay between two probes
+    pub max_delay_ms: u64,
+    /// Dependencies by component name
+    pub dependencies: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for StartupSettings {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 60,
+            initial_delay_ms: 500,
+            max_delay_ms: 10_000,
+            dependencies: BTreeMap::new(),
+        }
+    }
+}
+
+/// Bootstrap bundles handed to guest nodes by ApiServer
+///
+/// ```yaml
+/// bootstrap:
+///   master_ip: 192.168.10.201   # host.ip if empty
+///   ca_cert: /etc/pullpiri/tls/ca.crt
+///   token_ttl_secs: 3600
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct BootstrapSettings {
+    /// Address guest nodes reach the master at
+    pub master_ip: String,
+    /// CA certificate guest nodes check the master against, in PEM
+    pub ca_cert: String,
+    /// Time a bootstrap token can be redeemed after it is issued
+    pub token_ttl_secs: u64,
+}
+
+impl Default for BootstrapSettings {
+    fn default() -> Self {
+        Self {
+            master_ip: String::new(),
+            ca_cert: String::new(),
+            token_ttl_secs: 3600,
+        }
+    }
+}
+
+fn default_settings() -> Settings {
+    Settings {
         host: HostSettings {
             name: String::from("HPC"),
             ip: String::from("0.0.0.0"),
             r#type: String::from("nodeagent"),
             role: String::from("master"),
         },
-    };
+        recovery: RecoverySettings::default(),
+        backoff: BackoffSettings::default(),
+        action: ActionSettings::default(),
+        storage: StorageSettings::default(),
+        auth: AuthSettings::default(),
+        monitoring: MonitoringSettings::default(),
+        logging: LoggingSettings::default(),
+        limits: LimitSettings::default(),
+        state: StateSettings::default(),
+        trace: TraceSettings::default(),
+        grpc: GrpcSettings::default(),
+        etcd: EtcdSettings::default(),
+        heartbeat: HeartbeatSettings::default(),
+        gc: GcSettings::default(),
+        startup: StartupSettings::default(),
+        bootstrap: BootstrapSettings::default(),
+    }
+}
 
+fn parse_settings_yaml() -> Settings {
     let settings = config::Config::builder()
-        .add_source(config::File::with_name("/etc/pullpiri/settings.yaml"))
+        .add_source(config::File::with_name(SETTINGS_PATH))
         .build();
 
     match settings {
         Ok(result) => result
             .try_deserialize::<Settings>()
-            .unwrap_or(default_settings),
-        Err(_) => default_settings,
+            .unwrap_or_else(|_| default_settings()),
+        Err(_) => default_settings(),
     }
 }
 
-pub fn get_config() -> &'static Settings {
-    SETTINGS.get_or_init(parse_settings_yaml)
+/// Parse the contents of a settings file
+pub fn parse_settings_str(content: &str) -> Result<Settings, String> {
+    config::Config::builder()
+        .add_source(config::File::from_str(content, config::FileFormat::Yaml))
+        .build()
+        .and_then(|result| result.try_deserialize::<Settings>())
+        .map_err(|e| e.to_string())
+}
+
+/// Current settings, including changes picked up by [`watch_config`]
+pub fn get_config() -> Arc<Settings> {
+    let settings = SETTINGS.get_or_init(|| RwLock::new(Arc::new(parse_settings_yaml())));
+    match settings.read() {
+        Ok(settings) => settings.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+/// Reload settings.yaml whenever it changes
+///
+/// ### Description
+/// The first call starts watching the file; every call returns a receiver
+/// of the reloaded settings. [`get_config`] returns the reloaded settings
+/// as well. Must be called within a tokio runtime.
+pub fn watch_config() -> watch::Receiver<Arc<Settings>> {
+    WATCHER
+        .get_or_init(|| {
+            let current = get_config();
+            let mut file = config_watch::watch_file(
+                SETTINGS_PATH.into(),
+                (*current).clone(),
+                config_watch::DEFAULT_POLL_INTERVAL,
+                parse_settings_str,
+            );
+            // get_config() is updated before subscribers are notified
+            let (tx, rx) = watch::channel(current);
+            tokio::spawn(async move {
+                while file.changed().await.is_ok() {
+                    let reloaded = file.borrow_and_update().clone();
+                    if let Some(settings) = SETTINGS.get() {
+                        match settings.write() {
+                            Ok(mut settings) => *settings = reloaded.clone(),
+                            Err(poisoned) => *poisoned.into_inner() = reloaded.clone(),
+                        }
+                    }
+                    tx.send_replace(reloaded);
+                }
+            });
+            rx
+        })
+        .clone()
 }
 
 //Unit Test Cases
@@ -60,6 +753,218 @@ mod tests {
         assert_eq!(settings.host.r#type, "nodeagent");
     }
 
+    // Test that an omitted backoff section or field falls back to the defaults
+    #[tokio::test]
+    async fn test_backoff_settings_defaults() {
+        let settings = parse_settings_yaml();
+        assert_eq!(settings.backoff, BackoffSettings::default());
+
+        let backoff: BackoffSettings = serde_yaml::from_str("cap_ms: 60000").unwrap();
+        assert_eq!(backoff.cap_ms, 60_000);
+        assert_eq!(backoff.base_ms, BackoffSettings::default().base_ms);
+    }
+
+    #[tokio::test]
+    async fn test_recovery_settings() {
+        let recovery: RecoverySettings =
+            serde_yaml::from_str("strategies: [restart, reschedule]").unwrap();
+        assert_eq!(recovery.strategies, vec!["restart", "reschedule"]);
+        assert_eq!(recovery.attempts, 1);
+        assert_eq!(recovery.reset_after_secs, 600);
+    }
+
+    // Test that an omitted action section or field falls back to the defaults
+    #[test]
+    fn test_action_settings_defaults() {
+        let action: ActionSettings = serde_yaml::from_str("max_concurrent_models: 8").unwrap();
+        assert_eq!(action.max_concurrent_models, 8);
+        assert_eq!(
+            action.stop_timeout_ms,
+            ActionSettings::default().stop_timeout_ms
+        );
+    }
+
+    // Test that the storage backend is etcd unless another one is selected
+    #[test]
+    fn test_storage_settings() {
+        assert_eq!(StorageSettings::default().backend, StorageBackend::Etcd);
+
+        let storage: StorageSettings =
+            serde_yaml::from_str("backend: s3\ns3:\n  bucket: pullpiri\n").unwrap();
+        assert_eq!(storage.backend, StorageBackend::S3);
+        assert_eq!(storage.s3.bucket, "pullpiri");
+        assert!(storage.path.is_empty());
+
+        assert!(serde_yaml::from_str::<StorageSettings>("backend: nfs").is_err());
+    }
+
+    // Test that authentication is off unless enabled with tokens
+    #[test]
+    fn test_auth_settings() {
+        assert!(!AuthSettings::default().enabled);
+
+        let settings = parse_settings_str(
+            "host:\n  name: HPC\n  ip: 10.0.0.1\n  type: nodeagent\n  role: master\n\
+             auth:\n  enabled: true\n  tokens:\n    - name: ci\n      token: abc\n      role: operator\n",
+        )
+        .unwrap();
+        assert!(settings.auth.enabled);
+        assert_eq!(settings.auth.tokens[0].name, "ci");
+        assert_eq!(settings.auth.tokens[0].role, crate::auth::Role::Operator);
+    }
+
+    #[test]
+    fn test_monitoring_settings() {
+        assert_eq!(MonitoringSettings::default().retention_secs, 3_600);
+
+        let settings = parse_settings_str(
+            "host:\n  name: HPC\n  ip: 10.0.0.1\n  type: nodeagent\n  role: master\n\
+             monitoring:\n  retention_secs: 600\n",
+        )
+        .unwrap();
+        assert_eq!(settings.monitoring.retention_secs, 600);
+        assert_eq!(settings.monitoring.persist_interval_secs, 60);
+        assert!(settings.monitoring.persist_path.is_empty());
+    }
+
+    #[test]
+    fn test_logging_settings() {
+        assert_eq!(LoggingSettings::default().file, None);
+
+        let settings = parse_settings_str(
+            "host:\n  name: HPC\n  ip: 10.0.0.1\n  type: nodeagent\n  role: master\n\
+             logging:\n  modules:\n    settingsservice::settings_api: debug\n  \
+             file:\n    path: /tmp/settingsservice.log\n    rotation: hourly\n",
+        )
+        .unwrap();
+        let logging = settings.logging;
+        assert_eq!(logging.modules["settingsservice::settings_api"], "debug");
+        let file = logging.file.unwrap();
+        assert_eq!(file.rotation, LogRotation::Hourly);
+        assert_eq!(file.max_size_mb, 10);
+        assert!(!logging.journald);
+        assert!(logging.tcp.is_none());
+    }
+
+    #[test]
+    fn test_limit_settings() {
+        let settings = parse_settings_str(
+            "host:\n  name: HPC\n  ip: 10.0.0.1\n  type: nodeagent\n  role: master\n\
+             limits:\n  requests_per_sec: 0\n  max_body_kb: 64\n",
+        )
+        .unwrap();
+        assert_eq!(settings.limits.requests_per_sec, 0);
+        assert_eq!(settings.limits.burst, 40);
+        assert_eq!(settings.limits.max_body_kb, 64);
+        assert_eq!(default_settings().limits, LimitSettings::default());
+    }
+
+    #[test]
+    fn test_state_settings() {
+        let settings = parse_settings_str(
+            "host:\n  name: HPC\n  ip: 10.0.0.1\n  type: nodeagent\n  role: master\n\
+             state:\n  drift_policy: trust-memory\n",
+        )
+        .unwrap();
+        assert_eq!(settings.state.drift_policy, DriftPolicy::TrustMemory);
+        assert_eq!(settings.state.watch_interval_ms, 2_000);
+        assert_eq!(
+            default_settings().state.drift_policy,
+            DriftPolicy::TrustEtcd
+        );
+        assert!(serde_yaml::from_str::<StateSettings>("drift_policy: trust-nobody").is_err());
+    }
+
+    #[test]
+    fn test_grpc_settings() {
+        let settings = parse_settings_str(
+            "host:\n  name: HPC\n  ip: 10.0.0.1\n  type: nodeagent\n  role: master\n\
+             grpc:\n  call_timeout_ms: 0\n  max_retries: 5\n",
+        )
+        .unwrap();
+        assert_eq!(settings.grpc.call_timeout_ms, 0);
+        assert_eq!(settings.grpc.max_retries, 5);
+        assert_eq!(settings.grpc.connect_timeout_ms, 3_000);
+        assert_eq!(default_settings().grpc, GrpcSettings::default());
+    }
+
+    #[test]
+    fn test_etcd_settings() {
+        let settings = parse_settings_str(
+            "host:\n  name: HPC\n  ip: 10.0.0.1\n  type: nodeagent\n  role: master\n\
+             etcd:\n  endpoints: [\"http://a:47007\", \"http://b:47007\"]\n  request_timeout_ms: 0\n  instance: vehicle-a\n",
+        )
+        .unwrap();
+        assert_eq!(
+            settings.etcd.endpoints,
+            ["http://a:47007", "http://b:47007"]
+        );
+        assert_eq!(settings.etcd.request_timeout_ms, 0);
+        assert_eq!(settings.etcd.connect_timeout_ms, 2_000);
+        assert_eq!(settings.etcd.instance, "vehicle-a");
+        assert!(!settings.etcd.migrate_keys);
+        assert_eq!(default_settings().etcd, EtcdSettings::default());
+    }
+
+    #[test]
+    fn test_heartbeat_settings() {
+        let settings = parse_settings_str(
+            "host:\n  name: HPC\n  ip: 10.0.0.1\n  type: nodeagent\n  role: master\n\
+             heartbeat:\n  interval_secs: 5\n",
+        )
+        .unwrap();
+        assert_eq!(settings.heartbeat.interval_secs, 5);
+        assert_eq!(settings.heartbeat.liveness_window(), 90);
+        assert_eq!(settings.heartbeat.max_clock_skew_secs, 5);
+
+        let heartbeat = HeartbeatSettings {
+            interval_secs: 60,
+            liveness_window_secs: 90,
+            max_clock_skew_secs: 0,
+        };
+        assert_eq!(heartbeat.liveness_window(), 120);
+    }
+
+    #[test]
+    fn test_gc_settings() {
+        let settings = parse_settings_str(
+            "host:\n  name: HPC\n  ip: 10.0.0.1\n  type: nodeagent\n  role: master\n\
+             gc:\n  delete: true\n",
+        )
+        .unwrap();
+        assert!(settings.gc.delete);
+        assert_eq!(settings.gc.interval_secs, 300);
+        assert_eq!(settings.gc.grace_period_secs, 86_400);
+        assert!(!default_settings().gc.delete);
+    }
+
+    #[test]
+    fn test_startup_settings() {
+        let settings = parse_settings_str(
+            "host:\n  name: HPC\n  ip: 10.0.0.1\n  type: nodeagent\n  role: master\n\
+             startup:\n  timeout_secs: 120\n  dependencies:\n    filtergateway: [etcd, \"tcp://10.0.0.5:7400\"]\n",
+        )
+        .unwrap();
+        assert_eq!(settings.startup.timeout_secs, 120);
+        assert_eq!(settings.startup.initial_delay_ms, 500);
+        assert_eq!(
+            settings.startup.dependencies["filtergateway"],
+            vec!["etcd".to_string(), "tcp://10.0.0.5:7400".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_bootstrap_settings() {
+        let settings = parse_settings_str(
+            "host:\n  name: HPC\n  ip: 10.0.0.1\n  type: nodeagent\n  role: master\n\
+             bootstrap:\n  ca_cert: /etc/pullpiri/tls/ca.crt\n",
+        )
+        .unwrap();
+        assert_eq!(settings.bootstrap.ca_cert, "/etc/pullpiri/tls/ca.crt");
+        assert!(settings.bootstrap.master_ip.is_empty());
+        assert_eq!(settings.bootstrap.token_ttl_secs, 3600);
+    }
+
     // Guest 설정 테스트 제거
 
     // Test lazy initialization of configuration
@@ -78,7 +983,7 @@ mod tests {
         // Verify that `get_config` returns the same instance every time
         let config1 = get_config();
         let config2 = get_config();
-        assert!(std::ptr::eq(config1, config2));
+        assert!(Arc::ptr_eq(&config1, &config2));
     }
 
     // Test concurrent access to `get_config`
@@ -149,4 +1054,17 @@ mod tests {
     }
 
     // Guest 관련 테스트 제거
+
+    // Test parsing reloaded settings
+    #[test]
+    fn test_parse_settings_str() {
+        let settings = parse_settings_str(
+            "host:\n  name: ZONE1\n  ip: 10.0.0.2\n  type: vehicle\n  role: nodeagent\n",
+        )
+        .unwrap();
+        assert_eq!(settings.host.name, "ZONE1");
+        assert_eq!(settings.backoff, BackoffSettings::default());
+
+        assert!(parse_settings_str("host:\n  name: ZONE1\n").is_err());
+    }
 }
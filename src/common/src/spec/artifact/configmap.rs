@@ -0,0 +1,87 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+use super::Artifact;
+use super::ConfigMap;
+use std::collections::HashMap;
+
+impl Artifact for ConfigMap {
+    fn get_name(&self) -> String {
+        self.metadata.name.clone()
+    }
+
+    fn get_namespace(&self) -> String {
+        self.metadata.get_namespace()
+    }
+}
+
+impl ConfigMap {
+    pub fn get_spec(&self) -> &ConfigMapSpec {
+        &self.spec
+    }
+
+    /// Values injected as environment variables, by variable name
+    pub fn get_data(&self) -> &HashMap<String, String> {
+        &self.spec.data
+    }
+
+    /// Check that every key can be used as an environment variable name
+    pub fn validate(&self) -> Result<(), String> {
+        let mut keys: Vec<&String> = self.spec.data.keys().collect();
+        keys.sort();
+        for key in keys {
+            if !is_env_name(key) {
+                return Err(format!(
+                    "ConfigMap '{}' has key '{}' which is not a valid environment variable name",
+                    self.get_name(),
+                    key
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct ConfigMapSpec {
+    #[serde(default)]
+    data: HashMap<String, String>,
+}
+
+/// `true` for names made of letters, digits and `_`, not starting with a digit
+fn is_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG_MAP_YAML: &str = r#"
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: vehicle
+spec:
+  data:
+    VIN: KMHXX00XXXX000000
+    REGION: eu
+"#;
+
+    #[test]
+    fn test_config_map_parse_and_validate() {
+        let config: ConfigMap = serde_yaml::from_str(CONFIG_MAP_YAML).unwrap();
+
+        assert_eq!(config.get_name(), "vehicle");
+        assert_eq!(config.get_data().get("REGION"), Some(&"eu".to_string()));
+        assert!(config.validate().is_ok());
+
+        let invalid = CONFIG_MAP_YAML.replace("REGION", "1-REGION");
+        let config: ConfigMap = serde_yaml::from_str(&invalid).unwrap();
+        assert!(config.validate().unwrap_err().contains("'1-REGION'"));
+    }
+}
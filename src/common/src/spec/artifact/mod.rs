@@ -2,6 +2,7 @@
 * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
 * SPDX-License-Identifier: Apache-2.0
 */
+pub mod configmap;
 pub mod model;
 pub mod network;
 pub mod node;
@@ -9,6 +10,7 @@ pub mod package;
 pub mod policy;
 pub mod scenario;
 pub mod schedule;
+pub mod secret;
 pub mod volume;
 
 use super::MetaData;
@@ -16,6 +18,13 @@ use serde::{Deserialize, Serialize};
 
 pub trait Artifact {
     fn get_name(&self) -> String;
+    fn get_namespace(&self) -> String;
+
+    /// Name of the artifact prefixed with its namespace, see
+    /// `namespace::qualified_name`
+    fn get_qualified_name(&self) -> String {
+        super::namespace::qualified_name(&self.get_namespace(), &self.get_name())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -84,6 +93,22 @@ pub struct Policy {
     spec: policy::PolicySpec,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Secret {
+    apiVersion: String,
+    kind: String,
+    metadata: MetaData,
+    spec: secret::SecretSpec,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConfigMap {
+    apiVersion: String,
+    kind: String,
+    metadata: MetaData,
+    spec: configmap::ConfigMapSpec,
+}
+
 //Unit Test Cases
 #[cfg(test)]
 mod tests {
@@ -112,6 +137,29 @@ mod tests {
         assert_eq!(scenario.get_targets(), "test-target");
     }
 
+    #[test]
+    fn test_artifact_namespace() {
+        let scenario: Scenario = serde_json::from_str(
+            r#"{"apiVersion": "v1", "kind": "Scenario",
+                "metadata": {"name": "core", "namespace": "team-a"},
+                "spec": {"action": "launch", "target": "core", "dependsOn": ["base"]}}"#,
+        )
+        .unwrap();
+        assert_eq!(scenario.get_namespace(), "team-a");
+        assert_eq!(scenario.get_qualified_name(), "team-a/core");
+        assert_eq!(scenario.get_qualified_depends_on(), vec!["team-a/base"]);
+
+        let volume: Volume = serde_json::from_str(
+            r#"{"apiVersion": "v1", "kind": "Volume", "metadata": {"name": "vol"}}"#,
+        )
+        .unwrap();
+        assert_eq!(volume.get_namespace(), "default");
+        assert_eq!(volume.get_qualified_name(), "vol");
+        assert!(!serde_json::to_string(&volume)
+            .unwrap()
+            .contains("namespace"));
+    }
+
     #[test]
     fn test_package_artifact_trait() {
         let package_json = r#"
@@ -11,6 +11,10 @@ impl Artifact for Model {
     fn get_name(&self) -> String {
         self.metadata.name.clone()
     }
+
+    fn get_namespace(&self) -> String {
+        self.metadata.get_namespace()
+    }
 }
 
 impl Model {
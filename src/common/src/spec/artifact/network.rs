@@ -9,22 +9,120 @@ impl Artifact for Network {
     fn get_name(&self) -> String {
         self.metadata.name.clone()
     }
+
+    fn get_namespace(&self) -> String {
+        self.metadata.get_namespace()
+    }
 }
 
 impl Network {
     pub fn get_spec(&self) -> &Option<NetworkSpec> {
         &self.spec
     }
+
+    /// Name of the network created on the node
+    pub fn get_network_name(&self) -> String {
+        self.spec
+            .as_ref()
+            .and_then(|spec| spec.get_network().clone())
+            .unwrap_or_else(|| self.get_name())
+    }
+
+    /// Check that the options fit the driver
+    pub fn validate(&self) -> Result<(), String> {
+        let Some(spec) = &self.spec else {
+            return Ok(());
+        };
+        match spec.driver {
+            NetworkDriver::Host if spec.subnet.is_some() || spec.parent.is_some() => {
+                return Err("host network does not take subnet or parent".to_string());
+            }
+            NetworkDriver::Macvlan if spec.parent.is_none() => {
+                return Err("macvlan network requires a parent interface".to_string());
+            }
+            _ => {}
+        }
+        if spec.subnet.is_none() && (spec.gateway.is_some() || spec.ipRange.is_some()) {
+            return Err("gateway and ipRange require a subnet".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Network driver, as in `podman network create --driver`
+#[derive(Debug, Clone, Copy, Default, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkDriver {
+    /// Private network behind a bridge on the node
+    #[default]
+    Bridge,
+    /// Network stack of the node itself
+    Host,
+    /// Own MAC address on a host interface
+    Macvlan,
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+impl NetworkDriver {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NetworkDriver::Bridge => "bridge",
+            NetworkDriver::Host => "host",
+            NetworkDriver::Macvlan => "macvlan",
+        }
+    }
+}
+
+/// ```yaml
+/// spec:
+///   name: vd-net        # network name on the node, artifact name if omitted
+///   driver: macvlan     # bridge (default), host or macvlan
+///   subnet: 192.168.10.0/24
+///   gateway: 192.168.10.1
+///   ipRange: 192.168.10.128/25
+///   parent: eth0        # host interface, macvlan only
+///   internal: false     # no external access
+/// ```
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct NetworkSpec {
-    dummy: Option<String>,
+    /// `dummy` is the name of this field in older artifacts
+    #[serde(alias = "dummy")]
+    name: Option<String>,
+    #[serde(default)]
+    driver: NetworkDriver,
+    subnet: Option<String>,
+    gateway: Option<String>,
+    ipRange: Option<String>,
+    parent: Option<String>,
+    internal: Option<bool>,
 }
 
 impl NetworkSpec {
     pub fn get_network(&self) -> &Option<String> {
-        &self.dummy
+        &self.name
+    }
+
+    pub fn get_driver(&self) -> NetworkDriver {
+        self.driver
+    }
+
+    pub fn get_subnet(&self) -> Option<&str> {
+        self.subnet.as_deref()
+    }
+
+    pub fn get_gateway(&self) -> Option<&str> {
+        self.gateway.as_deref()
+    }
+
+    pub fn get_ip_range(&self) -> Option<&str> {
+        self.ipRange.as_deref()
+    }
+
+    pub fn get_parent(&self) -> Option<&str> {
+        self.parent.as_deref()
+    }
+
+    pub fn is_internal(&self) -> bool {
+        self.internal.unwrap_or(false)
     }
 }
 
@@ -43,9 +141,11 @@ mod tests {
                 name: name.to_string(),
                 labels: None,
                 annotations: None,
+                namespace: None,
             },
             spec: dummy_value.map(|v| NetworkSpec {
-                dummy: Some(v.to_string()),
+                name: Some(v.to_string()),
+                ..Default::default()
             }),
         }
     }
@@ -83,7 +183,8 @@ mod tests {
     fn test_network_spec_get_network() {
         let dummy_value = "test-dummy-value";
         let network_spec = NetworkSpec {
-            dummy: Some(dummy_value.to_string()),
+            name: Some(dummy_value.to_string()),
+            ..Default::default()
         };
 
         // Test NetworkSpec's get_network method
@@ -92,7 +193,7 @@ mod tests {
 
     #[test]
     fn test_network_spec_get_network_none() {
-        let network_spec = NetworkSpec { dummy: None };
+        let network_spec = NetworkSpec::default();
 
         // Test NetworkSpec's get_network when dummy is None
         assert_eq!(network_spec.get_network(), &None);
@@ -110,7 +211,7 @@ mod tests {
         // Test deserialization
         let deserialized: Network = serde_json::from_str(&serialized).unwrap();
         assert_eq!(deserialized.metadata.name, "test-network");
-        assert_eq!(deserialized.spec.unwrap().dummy.unwrap(), "dummy-value");
+        assert_eq!(deserialized.spec.unwrap().name.unwrap(), "dummy-value");
     }
 
     #[test]
@@ -135,4 +236,58 @@ mod tests {
         assert!(debug_output.contains("debug-network"));
         assert!(debug_output.contains("debug-value"));
     }
+
+    #[test]
+    fn test_network_name_defaults_to_artifact_name() {
+        let named = create_test_network("vd-network", Some("vd-net"));
+        assert_eq!(named.get_network_name(), "vd-net");
+        let unnamed = create_test_network("vd-network", None);
+        assert_eq!(unnamed.get_network_name(), "vd-network");
+    }
+
+    #[test]
+    fn test_network_spec_accepts_dummy_as_name() {
+        let spec: NetworkSpec = serde_yaml::from_str("dummy: network123").unwrap();
+        assert_eq!(spec.get_network(), &Some("network123".to_string()));
+    }
+
+    #[test]
+    fn test_network_spec_parse_and_validate() {
+        let yaml = r#"
+apiVersion: v1
+kind: Network
+metadata:
+  name: vd-network
+spec:
+  driver: macvlan
+  subnet: 192.168.10.0/24
+  gateway: 192.168.10.1
+  parent: eth0
+"#;
+        let network: Network = serde_yaml::from_str(yaml).unwrap();
+        let spec = network.get_spec().as_ref().unwrap();
+        assert_eq!(spec.get_driver(), NetworkDriver::Macvlan);
+        assert_eq!(spec.get_parent(), Some("eth0"));
+        assert!(!spec.is_internal());
+        assert!(network.validate().is_ok());
+
+        // bridge is the default driver
+        let bridge: NetworkSpec = serde_yaml::from_str("subnet: 10.88.1.0/24").unwrap();
+        assert_eq!(bridge.get_driver(), NetworkDriver::Bridge);
+    }
+
+    #[test]
+    fn test_network_validate_rejects_mismatched_options() {
+        let invalid = [
+            "driver: macvlan",
+            "driver: host\nsubnet: 10.0.0.0/24",
+            "gateway: 10.0.0.1",
+        ];
+        for spec in invalid {
+            let mut network = create_test_network("vd-network", None);
+            network.spec = Some(serde_yaml::from_str(spec).unwrap());
+            assert!(network.validate().is_err(), "{} should be invalid", spec);
+        }
+        assert!(serde_yaml::from_str::<NetworkSpec>("driver: ipvlan").is_err());
+    }
 }
@@ -9,6 +9,10 @@ impl Artifact for Node {
     fn get_name(&self) -> String {
         self.metadata.name.clone()
     }
+
+    fn get_namespace(&self) -> String {
+        self.metadata.get_namespace()
+    }
 }
 
 impl Node {
@@ -36,6 +40,67 @@ pub struct NodeSpec {
     pub config: Option<std::collections::HashMap<String, String>>,
 }
 
+/// NodeInfo metadata entry that lists the taints of a node
+///
+/// All other metadata entries are labels matched by `nodeSelector`. The
+/// value is a comma separated list of `key[=value]:Effect`, for example
+/// `dedicated=adas:NoSchedule,maintenance:PreferNoSchedule`.
+pub const NODE_TAINTS_KEY: &str = "taints";
+
+/// Taint that keeps models without a matching toleration off a node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Taint {
+    pub key: String,
+    pub value: String,
+    pub effect: TaintEffect,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum TaintEffect {
+    /// Models must tolerate the taint to run on the node
+    NoSchedule,
+    /// Automatic placement avoids the node unless no other node fits
+    PreferNoSchedule,
+}
+
+impl Taint {
+    /// Parse one `key[=value]:Effect` entry
+    pub fn parse(taint: &str) -> Result<Self, String> {
+        let (key_value, effect) = taint
+            .trim()
+            .rsplit_once(':')
+            .ok_or_else(|| format!("Taint '{}' has no effect", taint))?;
+        let effect = match effect {
+            "NoSchedule" => TaintEffect::NoSchedule,
+            "PreferNoSchedule" => TaintEffect::PreferNoSchedule,
+            _ => return Err(format!("Taint '{}' has unknown effect '{}'", taint, effect)),
+        };
+        let (key, value) = key_value.split_once('=').unwrap_or((key_value, ""));
+        if key.is_empty() {
+            return Err(format!("Taint '{}' has no key", taint));
+        }
+        Ok(Self {
+            key: key.to_string(),
+            value: value.to_string(),
+            effect,
+        })
+    }
+
+    /// Taints listed in the metadata of a node, invalid entries are skipped
+    pub fn from_metadata(metadata: &std::collections::HashMap<String, String>) -> Vec<Self> {
+        metadata
+            .get(NODE_TAINTS_KEY)
+            .map(|taints| {
+                taints
+                    .split(',')
+                    .filter(|t| !t.trim().is_empty())
+                    .filter_map(|t| Self::parse(t).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct NodeResources {
     pub cpu_cores: Option<i32>,
@@ -78,3 +143,46 @@ impl NodeSpec {
         &self.config
     }
 }
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_taint_parse() {
+        assert_eq!(
+            Taint::parse("dedicated=adas:NoSchedule").unwrap(),
+            Taint {
+                key: "dedicated".to_string(),
+                value: "adas".to_string(),
+                effect: TaintEffect::NoSchedule,
+            }
+        );
+        let taint = Taint::parse("maintenance:PreferNoSchedule").unwrap();
+        assert_eq!(taint.value, "");
+        assert_eq!(taint.effect, TaintEffect::PreferNoSchedule);
+
+        assert!(Taint::parse("dedicated=adas").is_err());
+        assert!(Taint::parse("dedicated:NoExecute").is_err());
+        assert!(Taint::parse("=adas:NoSchedule").is_err());
+    }
+
+    #[test]
+    fn test_taints_from_metadata() {
+        let metadata = HashMap::from([
+            ("zone".to_string(), "front".to_string()),
+            (
+                NODE_TAINTS_KEY.to_string(),
+                "gpu:NoSchedule, broken, maintenance:PreferNoSchedule".to_string(),
+            ),
+        ]);
+        let taints = Taint::from_metadata(&metadata);
+        assert_eq!(taints.len(), 2);
+        assert_eq!(taints[0].key, "gpu");
+        assert_eq!(taints[1].key, "maintenance");
+
+        assert!(Taint::from_metadata(&HashMap::new()).is_empty());
+    }
+}
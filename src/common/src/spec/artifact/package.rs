@@ -2,20 +2,35 @@
 * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
 * SPDX-License-Identifier: Apache-2.0
 */
+use super::node::{Taint, TaintEffect};
 use super::Artifact;
 use super::Package;
+use std::collections::HashMap;
 
 impl Artifact for Package {
     fn get_name(&self) -> String {
         self.metadata.name.clone()
     }
+
+    fn get_namespace(&self) -> String {
+        self.metadata.get_namespace()
+    }
 }
 
 impl Package {
+    /// Annotations of the package, empty if it has none
+    pub fn get_annotations(&self) -> HashMap<String, String> {
+        self.metadata.annotations.clone().unwrap_or_default()
+    }
+
     pub fn get_models(&self) -> &Vec<ModelInfo> {
         &self.spec.models
     }
 
+    pub fn get_models_mut(&mut self) -> &mut Vec<ModelInfo> {
+        &mut self.spec.models
+    }
+
     pub fn get_schedule(&self) -> &Option<String> {
         &self.spec.schedule
     }
@@ -23,6 +38,314 @@ impl Package {
     pub fn get_policy(&self) -> &Option<String> {
         &self.spec.policy
     }
+
+    pub fn get_update_strategy(&self) -> &Option<UpdateStrategy> {
+        &self.spec.updateStrategy
+    }
+
+    /// Deployment pattern of the models, `plain` unless another one is given
+    ///
+    /// `plain` entries are skipped, the first other pattern applies.
+    pub fn get_pattern(&self) -> Pattern {
+        self.spec
+            .pattern
+            .iter()
+            .find(|p| !p.is_plain())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Check the canary stage of the update strategy
+    ///
+    /// # Errors
+    ///
+    /// Returns the reason why the canary stage cannot run.
+    pub fn validate_update_strategy(&self) -> Result<(), String> {
+        let Some(canary) = self
+            .spec
+            .updateStrategy
+            .as_ref()
+            .and_then(|s| s.get_canary())
+        else {
+            return Ok(());
+        };
+        for gate in canary.get_health_gates() {
+            if gate.metric.trim().is_empty() {
+                return Err(format!(
+                    "Package '{}' has a health gate without metric",
+                    self.get_name()
+                ));
+            }
+            match (gate.min, gate.max) {
+                (None, None) => {
+                    return Err(format!(
+                        "Health gate '{}' of package '{}' needs min or max",
+                        gate.metric,
+                        self.get_name()
+                    ))
+                }
+                (Some(min), Some(max)) if min > max => {
+                    return Err(format!(
+                        "Health gate '{}' of package '{}' has min {} above max {}",
+                        gate.metric,
+                        self.get_name(),
+                        min,
+                        max
+                    ))
+                }
+                _ => {}
+            }
+        }
+        // Other patterns and automatic placement choose the nodes later
+        let fixed_nodes =
+            self.get_pattern().is_plain() && !self.get_models().iter().any(|mi| mi.is_auto_node());
+        if let (true, Some(node)) = (fixed_nodes, &canary.node) {
+            if !self.get_models().iter().any(|mi| &mi.get_node() == node) {
+                return Err(format!(
+                    "Canary node '{}' of package '{}' runs none of its models",
+                    node,
+                    self.get_name()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the `pattern` entries of the package
+    ///
+    /// # Errors
+    ///
+    /// Returns the reason why the patterns cannot be applied.
+    pub fn validate_pattern(&self) -> Result<(), String> {
+        if self
+            .spec
+            .pattern
+            .iter()
+            .any(|p| p.r#type == PatternType::Unknown)
+        {
+            return Err(format!(
+                "Package '{}' has a pattern of unknown type, expected plain, selector or distributed",
+                self.get_name()
+            ));
+        }
+        let patterns: Vec<&Pattern> = self.spec.pattern.iter().filter(|p| !p.is_plain()).collect();
+        if patterns.len() > 1 {
+            return Err(format!(
+                "Package '{}' has {} selector or distributed patterns, only one is allowed",
+                self.get_name(),
+                patterns.len()
+            ));
+        }
+        match patterns.first() {
+            Some(p) if p.r#type == PatternType::Selector && p.nodeSelector.is_empty() => {
+                Err(format!(
+                    "Package '{}' has a selector pattern without nodeSelector",
+                    self.get_name()
+                ))
+            }
+            Some(p) if p.r#type == PatternType::Distributed && p.replicas.unwrap_or(0) == 0 => {
+                Err(format!(
+                    "Package '{}' has a distributed pattern without replicas",
+                    self.get_name()
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Run the models on the nodes with the labels of `selector`
+    ///
+    /// This is how a scenario with `nodeSelector` targets its package: the
+    /// labels are added to those of a selector or distributed pattern, and a
+    /// plain package gets a selector pattern.
+    pub fn target_nodes(&mut self, selector: &HashMap<String, String>) {
+        if selector.is_empty() {
+            return;
+        }
+        match self.spec.pattern.iter_mut().find(|p| !p.is_plain()) {
+            Some(pattern) => pattern.nodeSelector.extend(selector.clone()),
+            None => self.spec.pattern.push(Pattern {
+                r#type: PatternType::Selector,
+                nodeSelector: selector.clone(),
+                replicas: None,
+            }),
+        }
+    }
+
+    /// Models that must share the node of `model`
+    ///
+    /// `affinity` applies both ways and carries over, so a model placed with
+    /// a second one is also placed with the models of the second one.
+    pub fn get_colocated(&self, model: &str) -> Vec<String> {
+        let mut group = vec![model.to_string()];
+        let mut index = 0;
+        while index < group.len() {
+            let current = group[index].clone();
+            for mi in &self.spec.models {
+                let linked = if mi.name == current {
+                    mi.affinity.clone()
+                } else if mi.affinity.contains(&current) {
+                    vec![mi.name.clone()]
+                } else {
+                    continue;
+                };
+                for name in linked {
+                    if !group.contains(&name) {
+                        group.push(name);
+                    }
+                }
+            }
+            index += 1;
+        }
+        group.remove(0);
+        group
+    }
+
+    /// Models that must not share the node of `model`
+    ///
+    /// `antiAffinity` applies both ways and to the models colocated with
+    /// either side.
+    pub fn get_separated(&self, model: &str) -> Vec<String> {
+        let mut group = self.get_colocated(model);
+        group.push(model.to_string());
+        let mut separated: Vec<String> = Vec::new();
+        for mi in &self.spec.models {
+            let peers: Vec<&String> = if group.contains(&mi.name) {
+                mi.antiAffinity.iter().collect()
+            } else if mi.antiAffinity.iter().any(|name| group.contains(name)) {
+                vec![&mi.name]
+            } else {
+                continue;
+            };
+            for peer in peers {
+                let mut side = self.get_colocated(peer);
+                side.push(peer.clone());
+                for name in side {
+                    if !separated.contains(&name) {
+                        separated.push(name);
+                    }
+                }
+            }
+        }
+        separated.sort();
+        separated
+    }
+
+    /// Check the `affinity` and `antiAffinity` rules of the models
+    ///
+    /// The rules may only name other models of a plain package, a model
+    /// cannot be both colocated with and separated from another one, and
+    /// colocated models cannot require different values of a label.
+    ///
+    /// # Errors
+    ///
+    /// Returns the reason why the rules cannot be satisfied.
+    pub fn validate_affinity(&self) -> Result<(), String> {
+        let names: Vec<&String> = self.spec.models.iter().map(|mi| &mi.name).collect();
+        let mut has_rules = false;
+        for mi in &self.spec.models {
+            for peer in mi.affinity.iter().chain(&mi.antiAffinity) {
+                has_rules = true;
+                if peer == &mi.name {
+                    return Err(format!(
+                        "Model '{}' names itself in its affinity rules",
+                        mi.name
+                    ));
+                }
+                if !names.contains(&peer) {
+                    return Err(format!(
+                        "Model '{}' has an affinity rule for '{}' which is not a model of package '{}'",
+                        mi.name,
+                        peer,
+                        self.get_name()
+                    ));
+                }
+            }
+        }
+        if has_rules && !self.get_pattern().is_plain() {
+            return Err(format!(
+                "Package '{}' has affinity rules, which only apply to plain packages",
+                self.get_name()
+            ));
+        }
+
+        for mi in &self.spec.models {
+            let colocated = self.get_colocated(&mi.name);
+            let separated = self.get_separated(&mi.name);
+            if let Some(peer) = separated
+                .iter()
+                .find(|name| **name == mi.name || colocated.contains(name))
+            {
+                return Err(format!(
+                    "Model '{}' must run both on the same node as and apart from '{}'",
+                    mi.name, peer
+                ));
+            }
+            for peer in self
+                .spec
+                .models
+                .iter()
+                .filter(|m| colocated.contains(&m.name))
+            {
+                let mut selector: Vec<_> = mi.nodeSelector.iter().collect();
+                selector.sort();
+                if let Some((key, value)) = selector.into_iter().find(|(key, value)| {
+                    peer.nodeSelector
+                        .get(*key)
+                        .is_some_and(|other| other != *value)
+                }) {
+                    return Err(format!(
+                        "Models '{}' and '{}' must share a node but require '{}={}' and '{}={}'",
+                        mi.name, peer.name, key, value, key, peer.nodeSelector[key]
+                    ));
+                }
+            }
+        }
+        self.check_affinity()
+    }
+
+    /// Check the nodes assigned to the models against their affinity rules
+    ///
+    /// Models still on `auto` are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first rule the assigned nodes break.
+    pub fn check_affinity(&self) -> Result<(), String> {
+        let assigned: HashMap<&str, &str> = self
+            .spec
+            .models
+            .iter()
+            .filter(|mi| !mi.is_auto_node())
+            .map(|mi| (mi.name.as_str(), mi.node.as_str()))
+            .collect();
+        for mi in self.spec.models.iter().filter(|mi| !mi.is_auto_node()) {
+            for peer in self.get_colocated(&mi.name) {
+                if let Some(node) = assigned.get(peer.as_str()) {
+                    if *node != mi.node {
+                        return Err(format!(
+                            "Model '{}' must run on the same node as '{}' but they are on '{}' and '{}'",
+                            mi.name, peer, mi.node, node
+                        ));
+                    }
+                }
+            }
+            for peer in self.get_separated(&mi.name) {
+                if assigned.get(peer.as_str()) == Some(&mi.node.as_str()) {
+                    return Err(format!(
+                        "Model '{}' must not run on the same node as '{}' but both are on '{}'",
+                        mi.name, peer, mi.node
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// How nodes are picked for models with `node: auto`
+    pub fn get_scheduling_policy(&self) -> SchedulingPolicy {
+        self.spec.schedulingPolicy.clone().unwrap_or_default()
+    }
 }
 
 #[derive(Debug, serde::Deserialize, PartialEq)]
@@ -31,18 +354,283 @@ pub struct PackageSpec {
     policy: Option<String>,
     pattern: Vec<Pattern>,
     models: Vec<ModelInfo>,
+    updateStrategy: Option<UpdateStrategy>,
+    schedulingPolicy: Option<SchedulingPolicy>,
 }
 
-#[derive(Debug, serde::Deserialize, PartialEq)]
-struct Pattern {
-    r#type: String,
+/// Node name that lets ActionController choose the node of a model
+pub const AUTO_NODE: &str = "auto";
+
+/// How ActionController picks a node for models with `node: auto`
+///
+/// ```yaml
+/// schedulingPolicy: binpacking
+/// ```
+#[derive(Clone, Debug, Default, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SchedulingPolicy {
+    /// Fill the most used node that still fits, keeping other nodes free
+    BinPacking,
+    /// Use the least used node, balancing the load (default)
+    #[default]
+    Spreading,
+}
+
+/// Default number of models updated at the same time in a rolling update
+pub const DEFAULT_MAX_UNAVAILABLE: usize = 1;
+/// Default time for an updated model to reach Running
+pub const DEFAULT_UPDATE_TIMEOUT_SECS: u64 = 60;
+/// Default time the canary models must stay healthy
+pub const DEFAULT_CANARY_SOAK_SECS: u64 = 60;
+
+/// How models of a package are replaced by an `update` action
+///
+/// ```yaml
+/// updateStrategy:
+///   type: rolling
+///   maxUnavailable: 1
+///   timeoutSeconds: 60
+///   prePull: true
+/// ```
+///
+/// With `prePull`, the images of every model are pulled on their nodes
+/// before the first batch is updated, and the update does not start unless
+/// every image was pulled and matches its digest. With `canary`, the models
+/// of one node are updated first, see [`CanaryStrategy`].
+#[derive(Clone, Debug, serde::Deserialize, PartialEq)]
+pub struct UpdateStrategy {
+    r#type: UpdateStrategyType,
+    maxUnavailable: Option<usize>,
+    timeoutSeconds: Option<u64>,
+    prePull: Option<bool>,
+    canary: Option<CanaryStrategy>,
+}
+
+/// Canary stage of an update
+///
+/// ```yaml
+/// canary:
+///   node: zone-front
+///   soakSeconds: 120
+///   healthGates:
+///     - metric: cpu_usage
+///       max: 80
+///     - metric: soc_temperature
+///       min: 10
+///       max: 90
+/// ```
+///
+/// The models on `node`, by default the node of the first model, are
+/// updated first. They must reach Running and then stay Running for
+/// `soakSeconds` while every health gate holds on the node, otherwise they
+/// are rolled back and the other nodes keep the old version. A gate names a
+/// node metric reported to the monitoring server, either a field of the node
+/// info such as `cpu_usage` or `mem_usage` or a metric of a NodeAgent plugin.
+#[derive(Clone, Debug, serde::Deserialize, PartialEq)]
+pub struct CanaryStrategy {
+    node: Option<String>,
+    soakSeconds: Option<u64>,
+    #[serde(default)]
+    healthGates: Vec<HealthGate>,
+}
+
+/// Bounds a node metric must keep while the canary soaks
+#[derive(Clone, Debug, serde::Deserialize, PartialEq)]
+pub struct HealthGate {
+    metric: String,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateStrategyType {
+    /// Restart every model at once (default)
+    Recreate,
+    /// Update models in batches of `maxUnavailable`
+    Rolling,
+}
+
+impl UpdateStrategy {
+    pub fn is_rolling(&self) -> bool {
+        self.r#type == UpdateStrategyType::Rolling
+    }
+
+    /// Number of models updated per batch, at least one
+    pub fn get_max_unavailable(&self) -> usize {
+        self.maxUnavailable
+            .unwrap_or(DEFAULT_MAX_UNAVAILABLE)
+            .max(1)
+    }
+
+    /// Seconds each batch has to reach Running before it is rolled back
+    pub fn get_timeout_secs(&self) -> u64 {
+        self.timeoutSeconds.unwrap_or(DEFAULT_UPDATE_TIMEOUT_SECS)
+    }
+
+    /// Whether images are pulled on every node before the first batch
+    pub fn requires_pre_pull(&self) -> bool {
+        self.prePull.unwrap_or(false)
+    }
+
+    pub fn get_canary(&self) -> Option<&CanaryStrategy> {
+        self.canary.as_ref()
+    }
+
+    /// Whether the update runs in stages rather than all models at once
+    pub fn is_staged(&self) -> bool {
+        self.is_rolling() || self.canary.is_some()
+    }
+}
+
+impl CanaryStrategy {
+    /// Node updated first, the node of the first model if none is given
+    pub fn get_node(&self, models: &[ModelInfo]) -> Option<String> {
+        self.node
+            .clone()
+            .or_else(|| models.first().map(|mi| mi.get_node()))
+    }
+
+    /// Seconds the canary models must stay healthy
+    pub fn get_soak_secs(&self) -> u64 {
+        self.soakSeconds.unwrap_or(DEFAULT_CANARY_SOAK_SECS)
+    }
+
+    pub fn get_health_gates(&self) -> &Vec<HealthGate> {
+        &self.healthGates
+    }
+}
+
+impl HealthGate {
+    pub fn get_metric(&self) -> &str {
+        &self.metric
+    }
+
+    /// Check a value of the metric against the bounds of the gate
+    ///
+    /// # Errors
+    ///
+    /// Returns the bound the value is out of.
+    pub fn check(&self, value: f64) -> Result<(), String> {
+        if let Some(min) = self.min.filter(|min| value < *min) {
+            return Err(format!("{} is {}, below {}", self.metric, value, min));
+        }
+        if let Some(max) = self.max.filter(|max| value > *max) {
+            return Err(format!("{} is {}, above {}", self.metric, value, max));
+        }
+        Ok(())
+    }
+}
+
+/// How the models of a package are deployed to nodes
+///
+/// `plain` runs every model on its `node`. `selector` runs every model on
+/// each node with the labels of `nodeSelector`, `distributed` on
+/// `replicas` nodes chosen like for `node: auto`, optionally among the nodes
+/// with the labels of `nodeSelector`. The nodes are chosen when the scenario
+/// is triggered, the `node` of the models is not used.
+///
+/// ```yaml
+/// pattern:
+///   - type: distributed
+///     replicas: 3
+///     nodeSelector:
+///       zone: front
+/// ```
+#[derive(Clone, Debug, Default, serde::Deserialize, PartialEq)]
+pub struct Pattern {
+    r#type: PatternType,
+    #[serde(default)]
+    nodeSelector: HashMap<String, String>,
+    replicas: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, Default, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PatternType {
+    /// Every model runs on its own node (default)
+    #[default]
+    Plain,
+    /// Every model runs on each node matching the selector
+    Selector,
+    /// Every model runs on a number of nodes
+    Distributed,
+    /// Type this version does not know, deployed like `plain`
+    #[serde(other)]
+    Unknown,
+}
+
+impl PatternType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PatternType::Plain => "plain",
+            PatternType::Selector => "selector",
+            PatternType::Distributed => "distributed",
+            PatternType::Unknown => "unknown",
+        }
+    }
+}
+
+impl Pattern {
+    pub fn get_type(&self) -> PatternType {
+        self.r#type
+    }
+
+    /// `true` if the models run on the node they name
+    pub fn is_plain(&self) -> bool {
+        matches!(self.r#type, PatternType::Plain | PatternType::Unknown)
+    }
+
+    pub fn get_node_selector(&self) -> &HashMap<String, String> {
+        &self.nodeSelector
+    }
+
+    /// Number of nodes each model of a distributed package runs on
+    pub fn get_replicas(&self) -> usize {
+        self.replicas.unwrap_or(0)
+    }
+
+    /// `true` if the labels of a node have every label of `nodeSelector`
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        self.nodeSelector
+            .iter()
+            .all(|(key, value)| labels.get(key) == Some(value))
+    }
 }
 
+/// Model of a package and the node it runs on
+///
+/// ```yaml
+/// models:
+///   - name: adas-model
+///     node: auto
+///     nodeSelector:
+///       zone: front
+///     tolerations:
+///       - key: dedicated
+///         value: adas
+///         effect: NoSchedule
+///     affinity: [camera-pipeline]
+///     antiAffinity: [adas-model-backup]
+///     resources: {}
+/// ```
 #[derive(Debug, serde::Deserialize, PartialEq)]
 pub struct ModelInfo {
     name: String,
     node: String,
     resources: Resource,
+    /// Labels the node must have
+    #[serde(default)]
+    nodeSelector: HashMap<String, String>,
+    /// Taints of the node the model accepts
+    #[serde(default)]
+    tolerations: Vec<Toleration>,
+    /// Models of the package that must run on the same node
+    #[serde(default)]
+    affinity: Vec<String>,
+    /// Models of the package that must not run on the same node
+    #[serde(default)]
+    antiAffinity: Vec<String>,
 }
 
 impl ModelInfo {
@@ -54,15 +642,135 @@ impl ModelInfo {
         self.node.clone()
     }
 
+    /// `true` if ActionController chooses the node of the model
+    pub fn is_auto_node(&self) -> bool {
+        self.node == AUTO_NODE
+    }
+
+    /// Assign the node chosen for a model with `node: auto`
+    pub fn set_node(&mut self, node: &str) {
+        self.node = node.to_string();
+    }
+
+    /// Copy of the model running on `node` under its replica name
+    ///
+    /// Used for the per-node workloads of selector and distributed packages.
+    pub fn replica(&self, node: &str) -> ModelInfo {
+        ModelInfo {
+            name: crate::replica::replica_name(&self.name, node),
+            node: node.to_string(),
+            resources: self.resources.clone(),
+            nodeSelector: self.nodeSelector.clone(),
+            tolerations: self.tolerations.clone(),
+            affinity: self.affinity.clone(),
+            antiAffinity: self.antiAffinity.clone(),
+        }
+    }
+
     pub fn get_resources(&self) -> Resource {
         self.resources.clone()
     }
+
+    pub fn get_node_selector(&self) -> &HashMap<String, String> {
+        &self.nodeSelector
+    }
+
+    pub fn get_tolerations(&self) -> &Vec<Toleration> {
+        &self.tolerations
+    }
+
+    pub fn get_affinity(&self) -> &Vec<String> {
+        &self.affinity
+    }
+
+    pub fn get_anti_affinity(&self) -> &Vec<String> {
+        &self.antiAffinity
+    }
+
+    /// `true` if the model tolerates the taint
+    pub fn tolerates(&self, taint: &Taint) -> bool {
+        self.tolerations.iter().any(|t| t.tolerates(taint))
+    }
+
+    /// Check the `nodeSelector` and tolerations against a node
+    ///
+    /// `labels` are the metadata of the node, `taints` its parsed taints.
+    /// Only `NoSchedule` taints must be tolerated.
+    ///
+    /// # Errors
+    ///
+    /// Returns the reason why the model cannot run on the node.
+    pub fn check_node(
+        &self,
+        node: &str,
+        labels: &HashMap<String, String>,
+        taints: &[Taint],
+    ) -> Result<(), String> {
+        let mut selector: Vec<_> = self.nodeSelector.iter().collect();
+        selector.sort();
+        for (key, value) in selector {
+            if labels.get(key) != Some(value) {
+                return Err(format!(
+                    "Model '{}' requires label '{}={}' which node '{}' does not have",
+                    self.name, key, value, node
+                ));
+            }
+        }
+        if let Some(taint) = taints
+            .iter()
+            .find(|t| t.effect == TaintEffect::NoSchedule && !self.tolerates(t))
+        {
+            return Err(format!(
+                "Model '{}' does not tolerate taint '{}={}:NoSchedule' of node '{}'",
+                self.name, taint.key, taint.value, node
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Permission of a model to run on a node with a matching taint
+///
+/// An empty `key` with `operator: Exists` tolerates every taint, a missing
+/// `effect` every effect.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct Toleration {
+    #[serde(default)]
+    key: String,
+    #[serde(default)]
+    operator: TolerationOperator,
+    #[serde(default)]
+    value: String,
+    effect: Option<TaintEffect>,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize, PartialEq)]
+pub enum TolerationOperator {
+    /// Key and value must match the taint (default)
+    #[default]
+    Equal,
+    /// Only the key must match
+    Exists,
+}
+
+impl Toleration {
+    pub fn tolerates(&self, taint: &Taint) -> bool {
+        if self.effect.is_some_and(|effect| effect != taint.effect) {
+            return false;
+        }
+        match self.operator {
+            TolerationOperator::Exists => self.key.is_empty() || self.key == taint.key,
+            TolerationOperator::Equal => self.key == taint.key && self.value == taint.value,
+        }
+    }
 }
 
 #[derive(Clone, Debug, serde::Deserialize, PartialEq)]
 pub struct Resource {
     volume: Option<String>,
     network: Option<String>,
+    #[serde(default)]
+    secret: Option<String>,
 }
 
 impl Resource {
@@ -72,6 +780,9 @@ impl Resource {
     pub fn get_network(&self) -> Option<String> {
         self.network.clone()
     }
+    pub fn get_secret(&self) -> Option<String> {
+        self.secret.clone()
+    }
 }
 
 #[derive(Debug, serde::Deserialize, PartialEq)]
@@ -106,18 +817,23 @@ mod tests {
                 name: "test-package".to_string(),
                 labels: None,
                 annotations: None,
+                namespace: None,
             },
             spec: PackageSpec {
                 schedule: Some("schedule1".to_string()),
                 policy: Some("test-policy".to_string()),
                 pattern: vec![
                     Pattern {
-                        r#type: "type1".to_string(),
+                        r#type: PatternType::Plain,
+                        ..Default::default()
                     },
                     Pattern {
-                        r#type: "type2".to_string(),
+                        r#type: PatternType::Plain,
+                        ..Default::default()
                     },
                 ],
+                updateStrategy: None,
+                schedulingPolicy: None,
                 models: vec![
                     ModelInfo {
                         name: "model1".to_string(),
@@ -125,7 +841,12 @@ mod tests {
                         resources: Resource {
                             volume: Some("vol1".to_string()),
                             network: Some("net1".to_string()),
+                            secret: None,
                         },
+                        nodeSelector: HashMap::new(),
+                        tolerations: Vec::new(),
+                        affinity: Vec::new(),
+                        antiAffinity: Vec::new(),
                     },
                     ModelInfo {
                         name: "model2".to_string(),
@@ -133,7 +854,12 @@ mod tests {
                         resources: Resource {
                             volume: Some("vol2".to_string()),
                             network: None,
+                            secret: None,
                         },
+                        nodeSelector: HashMap::new(),
+                        tolerations: Vec::new(),
+                        affinity: Vec::new(),
+                        antiAffinity: Vec::new(),
                     },
                 ],
             },
@@ -175,7 +901,12 @@ mod tests {
             resources: Resource {
                 volume: Some("test-vol".to_string()),
                 network: Some("test-net".to_string()),
+                secret: None,
             },
+            nodeSelector: HashMap::new(),
+            tolerations: Vec::new(),
+            affinity: Vec::new(),
+            antiAffinity: Vec::new(),
         };
 
         assert_eq!(model.get_name(), "test-model");
@@ -191,16 +922,19 @@ mod tests {
         let resource_with_both = Resource {
             volume: Some("vol1".to_string()),
             network: Some("net1".to_string()),
+            secret: None,
         };
 
         let resource_with_volume_only = Resource {
             volume: Some("vol2".to_string()),
             network: None,
+            secret: None,
         };
 
         let resource_with_nothing = Resource {
             volume: None,
             network: None,
+            secret: None,
         };
 
         assert_eq!(resource_with_both.get_volume(), Some("vol1".to_string()));
@@ -225,12 +959,15 @@ mod tests {
                 name: "no-status-package".to_string(),
                 labels: None,
                 annotations: None,
+                namespace: None,
             },
             spec: PackageSpec {
                 schedule: None,
                 policy: None,
                 pattern: vec![],
                 models: vec![],
+                updateStrategy: None,
+                schedulingPolicy: None,
             },
             status: None,
         };
@@ -248,12 +985,15 @@ mod tests {
                 name: "empty-package".to_string(),
                 labels: None,
                 annotations: None,
+                namespace: None,
             },
             spec: PackageSpec {
                 schedule: None,
                 policy: None,
                 pattern: vec![],
                 models: vec![],
+                updateStrategy: None,
+                schedulingPolicy: None,
             },
             status: None,
         };
@@ -273,4 +1013,380 @@ mod tests {
         assert_eq!(none, ModelStatusState::None);
         assert_eq!(error, ModelStatusState::Error);
     }
+
+    #[test]
+    fn test_update_strategy_parse_and_defaults() {
+        let rolling: UpdateStrategy = serde_yaml::from_str(
+            r#"
+type: rolling
+maxUnavailable: 2
+timeoutSeconds: 30
+prePull: true
+"#,
+        )
+        .unwrap();
+        assert!(rolling.is_rolling());
+        assert_eq!(rolling.get_max_unavailable(), 2);
+        assert_eq!(rolling.get_timeout_secs(), 30);
+        assert!(rolling.requires_pre_pull());
+
+        let zero: UpdateStrategy =
+            serde_yaml::from_str("type: rolling\nmaxUnavailable: 0").unwrap();
+        assert_eq!(zero.get_max_unavailable(), 1);
+        assert_eq!(zero.get_timeout_secs(), DEFAULT_UPDATE_TIMEOUT_SECS);
+        assert!(!zero.requires_pre_pull());
+
+        let recreate: UpdateStrategy = serde_yaml::from_str("type: recreate").unwrap();
+        assert!(!recreate.is_rolling());
+        assert!(!recreate.is_staged());
+        assert!(serde_yaml::from_str::<UpdateStrategy>("type: bluegreen").is_err());
+    }
+
+    #[test]
+    fn test_canary_strategy() {
+        let package_with = |canary: &str| -> Package {
+            let yaml = format!(
+                r#"
+apiVersion: v1
+kind: Package
+metadata:
+  name: canary-package
+spec:
+  pattern:
+    - type: plain
+  updateStrategy:
+    type: recreate
+    canary: {}
+  models:
+    - name: model1
+      node: node1
+      resources: {{}}
+    - name: model2
+      node: node2
+      resources: {{}}
+"#,
+                canary
+            );
+            serde_yaml::from_str(&yaml).unwrap()
+        };
+        let package = package_with(
+            "{healthGates: [{metric: cpu_usage, max: 80}, {metric: soc_temperature, min: 10, max: 90}]}",
+        );
+        let strategy = package.get_update_strategy().clone().unwrap();
+        assert!(strategy.is_staged());
+        let canary = strategy.get_canary().unwrap();
+        assert_eq!(
+            canary.get_node(package.get_models()).as_deref(),
+            Some("node1")
+        );
+        assert_eq!(canary.get_soak_secs(), DEFAULT_CANARY_SOAK_SECS);
+        let gates = canary.get_health_gates();
+        assert!(gates[0].check(80.0).is_ok());
+        assert_eq!(
+            gates[0].check(85.5).unwrap_err(),
+            "cpu_usage is 85.5, above 80"
+        );
+        assert_eq!(
+            gates[1].check(5.0).unwrap_err(),
+            "soc_temperature is 5, below 10"
+        );
+        assert!(package.validate_update_strategy().is_ok());
+
+        let invalid = |canary: &str| package_with(canary).validate_update_strategy().unwrap_err();
+        assert!(invalid("{node: node3}").contains("runs none of its models"));
+        assert!(invalid("{healthGates: [{metric: cpu_usage}]}").contains("needs min or max"));
+        assert!(
+            invalid("{healthGates: [{metric: cpu_usage, min: 9, max: 1}]}").contains("above max")
+        );
+    }
+
+    #[test]
+    fn test_auto_node_and_scheduling_policy() {
+        let mut package: Package = serde_yaml::from_str(
+            r#"
+apiVersion: v1
+kind: Package
+metadata:
+  name: auto-package
+spec:
+  pattern:
+    - type: plain
+  schedulingPolicy: binpacking
+  models:
+    - name: model1
+      node: auto
+      resources: {}
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            package.get_scheduling_policy(),
+            SchedulingPolicy::BinPacking
+        );
+        assert!(package.get_models()[0].is_auto_node());
+
+        package.get_models_mut()[0].set_node("node1");
+        assert!(!package.get_models()[0].is_auto_node());
+        assert_eq!(package.get_models()[0].get_node(), "node1");
+
+        let package = create_test_package();
+        assert_eq!(package.get_scheduling_policy(), SchedulingPolicy::Spreading);
+    }
+
+    #[test]
+    fn test_node_selector_and_tolerations() {
+        let package: Package = serde_yaml::from_str(
+            r#"
+apiVersion: v1
+kind: Package
+metadata:
+  name: constrained-package
+spec:
+  pattern:
+    - type: plain
+  models:
+    - name: adas
+      node: auto
+      nodeSelector:
+        zone: front
+      tolerations:
+        - key: dedicated
+          value: adas
+          effect: NoSchedule
+        - key: maintenance
+          operator: Exists
+      resources: {}
+"#,
+        )
+        .unwrap();
+        let model = &package.get_models()[0];
+        let labels = HashMap::from([("zone".to_string(), "front".to_string())]);
+        let taint = |t: &str| Taint::parse(t).unwrap();
+
+        assert!(model.check_node("hpc", &labels, &[]).is_ok());
+        assert!(model
+            .check_node(
+                "hpc",
+                &labels,
+                &[
+                    taint("dedicated=adas:NoSchedule"),
+                    taint("maintenance=x:NoSchedule")
+                ]
+            )
+            .is_ok());
+        // Untolerated PreferNoSchedule taints do not block the node
+        assert!(model
+            .check_node("hpc", &labels, &[taint("gpu:PreferNoSchedule")])
+            .is_ok());
+
+        let err = model.check_node("hpc", &HashMap::new(), &[]).unwrap_err();
+        assert!(err.contains("zone=front"));
+        let err = model
+            .check_node("hpc", &labels, &[taint("dedicated=ivi:NoSchedule")])
+            .unwrap_err();
+        assert!(err.contains("dedicated=ivi:NoSchedule"));
+
+        // Models without constraints only avoid NoSchedule taints
+        let package = create_test_package();
+        let model = &package.get_models()[0];
+        assert!(model.check_node("node1", &HashMap::new(), &[]).is_ok());
+        assert!(model
+            .check_node("node1", &HashMap::new(), &[taint("gpu:NoSchedule")])
+            .is_err());
+    }
+
+    fn pattern_package(pattern: &str) -> Package {
+        serde_yaml::from_str(&format!(
+            r#"
+apiVersion: v1
+kind: Package
+metadata:
+  name: pattern-package
+spec:
+  pattern:
+{}
+  models:
+    - name: model1
+      node: auto
+      resources: {{}}
+"#,
+            pattern
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_pattern_parse_and_validate() {
+        let package = pattern_package("    - type: plain");
+        assert!(package.get_pattern().is_plain());
+        assert!(package.validate_pattern().is_ok());
+
+        let package = pattern_package(
+            "    - type: plain\n    - type: selector\n      nodeSelector:\n        zone: front",
+        );
+        let pattern = package.get_pattern();
+        assert_eq!(pattern.get_type(), PatternType::Selector);
+        assert!(package.validate_pattern().is_ok());
+        let mut labels = HashMap::from([("zone".to_string(), "front".to_string())]);
+        assert!(pattern.matches(&labels));
+        labels.insert("zone".to_string(), "rear".to_string());
+        assert!(!pattern.matches(&labels));
+
+        let package = pattern_package("    - type: distributed\n      replicas: 3");
+        let pattern = package.get_pattern();
+        assert_eq!(pattern.get_type(), PatternType::Distributed);
+        assert_eq!(pattern.get_replicas(), 3);
+        assert!(pattern.matches(&HashMap::new()));
+        assert!(package.validate_pattern().is_ok());
+
+        let err = pattern_package("    - type: selector")
+            .validate_pattern()
+            .unwrap_err();
+        assert!(err.contains("without nodeSelector"));
+        let err = pattern_package("    - type: distributed\n      replicas: 0")
+            .validate_pattern()
+            .unwrap_err();
+        assert!(err.contains("without replicas"));
+        let err = pattern_package(
+            "    - type: distributed\n      replicas: 2\n    - type: selector\n      nodeSelector:\n        zone: front",
+        )
+        .validate_pattern()
+        .unwrap_err();
+        assert!(err.contains("only one is allowed"));
+
+        let package = pattern_package("    - type: mesh");
+        assert!(package.get_pattern().is_plain());
+        assert!(package
+            .validate_pattern()
+            .unwrap_err()
+            .contains("unknown type"));
+    }
+
+    #[test]
+    fn test_target_nodes() {
+        let selector = HashMap::from([("zone".to_string(), "front-left".to_string())]);
+        let mut package = pattern_package("    - type: plain");
+        package.target_nodes(&selector);
+        let pattern = package.get_pattern();
+        assert_eq!(pattern.get_type(), PatternType::Selector);
+        assert_eq!(pattern.get_node_selector(), &selector);
+        assert!(package.validate_pattern().is_ok());
+
+        let mut package = pattern_package("    - type: distributed\n      replicas: 2");
+        package.target_nodes(&selector);
+        let pattern = package.get_pattern();
+        assert_eq!(pattern.get_type(), PatternType::Distributed);
+        assert_eq!(pattern.get_replicas(), 2);
+        assert_eq!(pattern.get_node_selector(), &selector);
+
+        package.target_nodes(&HashMap::new());
+        assert_eq!(package.get_pattern(), pattern);
+    }
+
+    #[test]
+    fn test_model_replica() {
+        let package = pattern_package("    - type: distributed\n      replicas: 2");
+        let replica = package.get_models()[0].replica("node1");
+        assert_eq!(replica.get_name(), "model1-node1");
+        assert_eq!(replica.get_node(), "node1");
+        assert_eq!(
+            replica.get_resources(),
+            package.get_models()[0].get_resources()
+        );
+    }
+
+    fn affinity_package(models: &str) -> Package {
+        serde_yaml::from_str(&format!(
+            r#"
+apiVersion: v1
+kind: Package
+metadata:
+  name: affinity-package
+spec:
+  pattern:
+    - type: plain
+  models:
+{}
+"#,
+            models
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_affinity_groups() {
+        let package = affinity_package(
+            r#"    - name: camera
+      node: auto
+      resources: {}
+    - name: inference
+      node: auto
+      affinity: [camera]
+      resources: {}
+    - name: overlay
+      node: auto
+      affinity: [inference]
+      resources: {}
+    - name: inference-backup
+      node: auto
+      antiAffinity: [inference]
+      resources: {}"#,
+        );
+        assert!(package.validate_affinity().is_ok());
+        assert_eq!(
+            package.get_colocated("camera"),
+            vec!["inference", "overlay"]
+        );
+        assert_eq!(package.get_separated("camera"), vec!["inference-backup"]);
+        assert_eq!(
+            package.get_separated("inference-backup"),
+            vec!["camera", "inference", "overlay"]
+        );
+        assert!(package.get_colocated("inference-backup").is_empty());
+    }
+
+    #[test]
+    fn test_validate_affinity_rejects_unsatisfiable_rules() {
+        let err = affinity_package(
+            "    - name: a\n      node: auto\n      affinity: [b]\n      resources: {}\n    - name: b\n      node: auto\n      antiAffinity: [a]\n      resources: {}",
+        )
+        .validate_affinity()
+        .unwrap_err();
+        assert!(err.contains("both on the same node as and apart from"));
+
+        let err = affinity_package(
+            "    - name: a\n      node: auto\n      affinity: [c]\n      resources: {}",
+        )
+        .validate_affinity()
+        .unwrap_err();
+        assert!(err.contains("'c' which is not a model"));
+
+        let err = affinity_package(
+            "    - name: a\n      node: auto\n      antiAffinity: [a]\n      resources: {}",
+        )
+        .validate_affinity()
+        .unwrap_err();
+        assert!(err.contains("names itself"));
+
+        let err = affinity_package(
+            "    - name: a\n      node: hpc\n      affinity: [b]\n      resources: {}\n    - name: b\n      node: zone\n      resources: {}",
+        )
+        .validate_affinity()
+        .unwrap_err();
+        assert!(err.contains("on 'hpc' and 'zone'"));
+
+        let err = affinity_package(
+            "    - name: a\n      node: hpc\n      antiAffinity: [b]\n      resources: {}\n    - name: b\n      node: hpc\n      resources: {}",
+        )
+        .validate_affinity()
+        .unwrap_err();
+        assert!(err.contains("both are on 'hpc'"));
+
+        let err = affinity_package(
+            "    - name: a\n      node: auto\n      nodeSelector:\n        zone: front\n      affinity: [b]\n      resources: {}\n    - name: b\n      node: auto\n      nodeSelector:\n        zone: rear\n      resources: {}",
+        )
+        .validate_affinity()
+        .unwrap_err();
+        assert!(err.contains("'zone=front' and 'zone=rear'"));
+    }
 }
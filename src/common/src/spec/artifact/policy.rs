@@ -2,6 +2,7 @@
  * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
  * SPDX-License-Identifier: Apache-2.0
  */
+use super::scenario::Condition;
 use super::Artifact;
 use super::Policy;
 
@@ -9,6 +10,10 @@ impl Artifact for Policy {
     fn get_name(&self) -> String {
         self.metadata.name.clone()
     }
+
+    fn get_namespace(&self) -> String {
+        self.metadata.get_namespace()
+    }
 }
 
 impl Policy {
@@ -19,15 +24,24 @@ impl Policy {
     pub fn get_procedure(&self) -> &Procedure {
         &self.spec.procedure
     }
+
+    pub fn get_rules(&self) -> &[Rule] {
+        self.spec.rules.as_deref().unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct PolicySpec {
+    #[serde(default)]
     pub placement: Placement,
+    #[serde(default)]
     pub procedure: Procedure,
+    /// Allow/deny rules checked by FilterGateway before a scenario action runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rules: Option<Vec<Rule>>,
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct Placement {
     /// List of nodes where the workload can be deployed.
     /// The first node is preferred, and remaining nodes serve as fallback options.
@@ -54,7 +68,7 @@ impl Placement {
     }
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct Procedure {
     pub r#type: String,
     pub strategy: String,
@@ -75,7 +89,7 @@ impl Procedure {
     }
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct Trigger {
     pub resourceThreshold: Option<ResourceThreshold>,
 }
@@ -96,6 +110,62 @@ impl ResourceThreshold {
     }
 }
 
+/// Scenario admission rule
+///
+/// ```yaml
+/// rules:
+///   - name: no-update-while-driving
+///     effect: deny
+///     actions: ["update"]
+///     condition:
+///       express: gt
+///       value: "0"
+///       operands:
+///         type: DDS
+///         name: speed
+///         value: vehicle/speed
+/// ```
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct Rule {
+    pub name: String,
+    pub effect: RuleEffect,
+    /// Scenario actions the rule applies to, all actions when empty
+    #[serde(default)]
+    pub actions: Vec<String>,
+    /// Scenario names the rule applies to, all scenarios when empty
+    #[serde(default)]
+    pub scenarios: Vec<String>,
+    /// Vehicle data condition, the rule always matches when absent
+    pub condition: Option<Condition>,
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleEffect {
+    Allow,
+    Deny,
+}
+
+impl Rule {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_effect(&self) -> RuleEffect {
+        self.effect
+    }
+
+    pub fn get_condition(&self) -> Option<&Condition> {
+        self.condition.as_ref()
+    }
+
+    /// Whether the rule covers the given scenario and action
+    pub fn applies_to(&self, scenario_name: &str, action: &str) -> bool {
+        (self.actions.is_empty() || self.actions.iter().any(|a| a == action))
+            && (self.scenarios.is_empty() || self.scenarios.iter().any(|s| s == scenario_name))
+    }
+}
+
 //Unit Test Cases
 #[cfg(test)]
 mod tests {
@@ -110,6 +180,7 @@ mod tests {
                 name: "test-policy".to_string(),
                 labels: None,
                 annotations: None,
+                namespace: None,
             },
             spec: PolicySpec {
                 placement: Placement {
@@ -125,6 +196,7 @@ mod tests {
                         }),
                     },
                 },
+                rules: None,
             },
         }
     }
@@ -206,4 +278,40 @@ mod tests {
         assert_eq!(policy.get_name(), "policy_helloworld");
         assert_eq!(policy.get_placement().get_preferred_node(), Some("HPC"));
     }
+
+    #[test]
+    fn test_policy_with_rules_only() {
+        let yaml = r#"
+apiVersion: v1
+kind: Policy
+metadata:
+  name: driving-policy
+spec:
+  rules:
+    - name: no-update-while-driving
+      effect: deny
+      actions: ["update"]
+      condition:
+        express: gt
+        value: "0"
+        operands:
+          type: DDS
+          name: speed
+          value: vehicle/speed
+"#;
+        let policy: Policy = serde_yaml::from_str(yaml).unwrap();
+        assert!(policy.get_placement().get_available_nodes().is_empty());
+
+        let rules = policy.get_rules();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].get_effect(), RuleEffect::Deny);
+        assert!(rules[0].applies_to("any-scenario", "update"));
+        assert!(!rules[0].applies_to("any-scenario", "launch"));
+        assert_eq!(
+            rules[0].get_condition().unwrap().get_operand_value(),
+            "vehicle/speed"
+        );
+
+        assert!(create_test_policy().get_rules().is_empty());
+    }
 }
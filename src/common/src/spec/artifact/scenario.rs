@@ -4,11 +4,20 @@
 */
 use super::Artifact;
 use super::Scenario;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+/// Scenario state that releases scenarios depending on it
+pub const DEPENDENCY_SATISFIED_STATE: &str = "SCENARIO_STATE_COMPLETED";
 
 impl Artifact for Scenario {
     fn get_name(&self) -> String {
         self.metadata.name.clone()
     }
+
+    fn get_namespace(&self) -> String {
+        self.metadata.get_namespace()
+    }
 }
 
 impl Scenario {
@@ -23,13 +32,235 @@ impl Scenario {
     pub fn get_targets(&self) -> String {
         self.spec.target.clone()
     }
+
+    /// Parameters of the action, checked by ActionController against the
+    /// schema of the action handler
+    pub fn get_action_params(&self) -> HashMap<String, serde_yaml::Value> {
+        self.spec.actionParams.clone()
+    }
+
+    pub fn get_depends_on(&self) -> Vec<String> {
+        self.spec.dependsOn.clone()
+    }
+
+    /// Prerequisites qualified by the namespace of the scenario
+    ///
+    /// A scenario can only depend on scenarios of its own namespace.
+    pub fn get_qualified_depends_on(&self) -> Vec<String> {
+        let namespace = self.get_namespace();
+        self.spec
+            .dependsOn
+            .iter()
+            .map(|name| crate::spec::namespace::qualified_name(&namespace, name))
+            .collect()
+    }
+
+    /// Time after a trigger during which the scenario is not triggered again
+    pub fn get_cooldown(&self) -> Duration {
+        Duration::from_secs(self.spec.cooldownSeconds.unwrap_or(0))
+    }
+
+    /// Time the condition has to stay met before the scenario is triggered
+    pub fn get_debounce(&self) -> Duration {
+        Duration::from_secs(self.spec.debounceSeconds.unwrap_or(0))
+    }
+
+    /// Vehicle modes the action may run in, any mode if empty
+    ///
+    /// See [`crate::vehicle_mode`] for the mode names.
+    pub fn get_allowed_modes(&self) -> Vec<String> {
+        self.spec.allowedModes.clone()
+    }
+
+    /// Time an action waits for an allowed vehicle mode, `None` to reject
+    /// it right away
+    pub fn get_mode_wait(&self) -> Option<Duration> {
+        self.spec
+            .modeWaitSeconds
+            .filter(|seconds| *seconds > 0)
+            .map(Duration::from_secs)
+    }
+
+    /// Labels of the nodes the target package runs on, the nodes of the
+    /// package if empty
+    pub fn get_node_selector(&self) -> &HashMap<String, String> {
+        &self.spec.nodeSelector
+    }
+
+    /// Time the scenario lives after it is applied, `None` if it does not
+    /// expire
+    pub fn get_ttl(&self) -> Option<Duration> {
+        self.spec
+            .ttlSeconds
+            .filter(|seconds| *seconds > 0)
+            .map(Duration::from_secs)
+    }
+
+    /// What happens to the scenario once its `ttlSeconds` are over
+    pub fn get_on_expiry(&self) -> OnExpiry {
+        self.spec.onExpiry.clone()
+    }
+}
+
+/// What happens to an expired scenario
+///
+/// Its workloads are terminated and its filter is removed in either case.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OnExpiry {
+    /// The scenario is removed from etcd
+    #[default]
+    Withdraw,
+    /// The scenario is kept in etcd but not triggered until applied again
+    Disable,
 }
 
+impl OnExpiry {
+    fn is_withdraw(&self) -> bool {
+        *self == OnExpiry::Withdraw
+    }
+}
+
+/// Scenario behavior
+///
+/// A scenario with `dependsOn` only runs after every listed scenario has
+/// completed:
+///
+/// ```yaml
+/// spec:
+///   action: update
+///   target: apply-update
+///   dependsOn:
+///     - download-update
+/// ```
+///
+/// Scenarios on noisy signals can be kept from flapping. The condition has to
+/// hold for `debounceSeconds` before the scenario is triggered, and after a
+/// trigger the scenario is not triggered again for `cooldownSeconds`:
+///
+/// ```yaml
+/// spec:
+///   debounceSeconds: 3
+///   cooldownSeconds: 60
+/// ```
+///
+/// Actions that must only run in some vehicle modes list them in
+/// `allowedModes`, and wait up to `modeWaitSeconds` for one of them:
+///
+/// ```yaml
+/// spec:
+///   allowedModes: [parked, ignition-off]
+///   modeWaitSeconds: 3600
+/// ```
+///
+/// Actions other than the workload ones take `actionParams`:
+///
+/// ```yaml
+/// spec:
+///   action: notify
+///   target: low-battery
+///   actionParams:
+///     message: Battery below 10%
+///     severity: warning
+/// ```
+///
+/// A scenario with `nodeSelector` runs its target package on every Ready
+/// node with these labels, like a package with a selector pattern. The nodes
+/// are chosen when the scenario is triggered:
+///
+/// ```yaml
+/// spec:
+///   action: launch
+///   target: door-controller
+///   nodeSelector:
+///     zone: front-left
+/// ```
+///
+/// A scenario with `ttlSeconds` expires that long after it is applied. Its
+/// workloads are terminated and it is withdrawn, or only kept from being
+/// triggered again with `onExpiry: disable`:
+///
+/// ```yaml
+/// spec:
+///   action: launch
+///   target: diagnostic-capture
+///   ttlSeconds: 3600
+///   onExpiry: disable
+/// ```
+#[allow(non_snake_case)]
 #[derive(Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct ScenarioSpec {
     condition: Option<Condition>,
     action: String,
     target: String,
+    /// Scenarios that must complete first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    dependsOn: Vec<String>,
+    /// Seconds after a trigger before the scenario can be triggered again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cooldownSeconds: Option<u64>,
+    /// Seconds the condition has to stay met before the scenario is triggered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    debounceSeconds: Option<u64>,
+    /// Parameters of the action, by parameter name
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    actionParams: HashMap<String, serde_yaml::Value>,
+    /// Vehicle modes the action may run in
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    allowedModes: Vec<String>,
+    /// Seconds an action waits for an allowed vehicle mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    modeWaitSeconds: Option<u64>,
+    /// Labels of the nodes the target package runs on
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    nodeSelector: HashMap<String, String>,
+    /// Seconds the scenario lives after it is applied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ttlSeconds: Option<u64>,
+    /// What happens to the scenario once it expires
+    #[serde(default, skip_serializing_if = "OnExpiry::is_withdraw")]
+    onExpiry: OnExpiry,
+}
+
+/// Find a cycle in a scenario dependency graph
+///
+/// ### Parameters
+/// * `graph: &HashMap<String, Vec<String>>` - scenario name to `dependsOn`
+/// ### Returns
+/// * `Option<Vec<String>>` - scenarios along the cycle, the first one
+///   repeated at the end, or `None` if the graph is acyclic
+pub fn find_dependency_cycle(graph: &HashMap<String, Vec<String>>) -> Option<Vec<String>> {
+    fn visit(
+        name: &str,
+        graph: &HashMap<String, Vec<String>>,
+        done: &mut HashSet<String>,
+        path: &mut Vec<String>,
+    ) -> Option<Vec<String>> {
+        if let Some(start) = path.iter().position(|n| n == name) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(name.to_string());
+            return Some(cycle);
+        }
+        if done.contains(name) {
+            return None;
+        }
+        path.push(name.to_string());
+        for dependency in graph.get(name).into_iter().flatten() {
+            if let Some(cycle) = visit(dependency, graph, done, path) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        done.insert(name.to_string());
+        None
+    }
+
+    let mut names: Vec<&String> = graph.keys().collect();
+    names.sort();
+    let mut done = HashSet::new();
+    names
+        .into_iter()
+        .find_map(|name| visit(name, graph, &mut done, &mut Vec::new()))
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, PartialEq)]
@@ -48,14 +279,104 @@ enum ScenarioState {
     Completed,
 }
 
+/// Scenario trigger
+///
+/// Data conditions (the default) compare a vehicle data field with `value`.
+/// Schedule conditions fire at a fixed time or on a cron schedule in UTC:
+///
+/// ```yaml
+/// condition:
+///   type: schedule
+///   schedule:
+///     cron: "0 2 * * *"   # or at: "2025-06-01T02:00:00Z"
+/// ```
+///
+/// Data conditions combine with `all` (AND), `any` (OR) and `not`, nested
+/// to any depth. `speed < 5 AND (gear == P OR ignition == off)`:
+///
+/// ```yaml
+/// condition:
+///   all:
+///     - express: lt
+///       value: "5"
+///       operands: { type: DDS, name: speed, value: /rt/vehicle/speed }
+///     - any:
+///         - express: eq
+///           value: P
+///           operands: { type: DDS, name: gear, value: /rt/vehicle/gear }
+///         - express: eq
+///           value: "off"
+///           operands: { type: DDS, name: ignition, value: /rt/vehicle/ignition }
+/// ```
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct Condition {
+    #[serde(default, skip_serializing_if = "ConditionType::is_data")]
+    r#type: ConditionType,
+    #[serde(default)]
     express: String,
+    #[serde(default)]
     value: String,
+    #[serde(default)]
     operands: Operand,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    schedule: Option<TriggerSchedule>,
+    /// Met when every condition is met
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    all: Vec<Condition>,
+    /// Met when one of the conditions is met
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    any: Vec<Condition>,
+    /// Met when the condition is not met
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    not: Option<Box<Condition>>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConditionType {
+    /// Vehicle data received over DDS
+    #[default]
+    Data,
+    /// Time based, see [`TriggerSchedule`]
+    Schedule,
+}
+
+impl ConditionType {
+    fn is_data(&self) -> bool {
+        *self == ConditionType::Data
+    }
+}
+
+/// When a `type: schedule` condition fires, exactly one field is set
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct TriggerSchedule {
+    /// Five field cron expression, e.g. `0 2 * * *`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cron: Option<String>,
+    /// One-shot RFC 3339 time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    at: Option<String>,
+}
+
+impl TriggerSchedule {
+    pub fn get_cron(&self) -> Option<String> {
+        self.cron.clone()
+    }
+
+    pub fn get_at(&self) -> Option<String> {
+        self.at.clone()
+    }
 }
 
 impl Condition {
+    pub fn is_schedule(&self) -> bool {
+        self.r#type == ConditionType::Schedule
+    }
+
+    pub fn get_schedule(&self) -> Option<TriggerSchedule> {
+        self.schedule.clone()
+    }
+
     pub fn get_express(&self) -> String {
         self.express.clone()
     }
@@ -71,9 +392,101 @@ impl Condition {
     pub fn get_operand_name(&self) -> String {
         self.operands.name.clone()
     }
+
+    /// Whether the condition combines other conditions
+    pub fn is_compound(&self) -> bool {
+        !self.all.is_empty() || !self.any.is_empty() || self.not.is_some()
+    }
+
+    pub fn get_all(&self) -> &[Condition] {
+        &self.all
+    }
+
+    pub fn get_any(&self) -> &[Condition] {
+        &self.any
+    }
+
+    pub fn get_not(&self) -> Option<&Condition> {
+        self.not.as_deref()
+    }
+
+    /// Vehicle data topics the condition reads, sorted and without duplicates
+    pub fn get_topics(&self) -> Vec<String> {
+        fn collect(condition: &Condition, topics: &mut Vec<String>) {
+            if !condition.is_compound() {
+                if !condition.is_schedule() {
+                    topics.push(condition.get_operand_value());
+                }
+                return;
+            }
+            for sub in condition.all.iter().chain(&condition.any) {
+                collect(sub, topics);
+            }
+            if let Some(sub) = condition.get_not() {
+                collect(sub, topics);
+            }
+        }
+
+        let mut topics = Vec::new();
+        collect(self, &mut topics);
+        topics.sort();
+        topics.dedup();
+        topics
+    }
+
+    /// Check that every compound condition uses exactly one of `all`, `any`
+    /// and `not`, and that schedules are not combined
+    ///
+    /// ### Returns
+    /// * `Err(String)` - what is wrong and where, e.g. `condition.all[1]`
+    pub fn validate(&self) -> Result<(), String> {
+        self.validate_at("condition")
+    }
+
+    fn validate_at(&self, path: &str) -> Result<(), String> {
+        if !self.is_compound() {
+            return Ok(());
+        }
+        let operators = [
+            !self.all.is_empty(),
+            !self.any.is_empty(),
+            self.not.is_some(),
+        ];
+        if operators.iter().filter(|set| **set).count() > 1 {
+            return Err(format!(
+                "{}: only one of 'all', 'any' and 'not' may be set",
+                path
+            ));
+        }
+        if self.is_schedule() || !self.express.is_empty() || !self.operands.value.is_empty() {
+            return Err(format!(
+                "{}: a compound condition cannot have a schedule or a comparison",
+                path
+            ));
+        }
+        let subs = self
+            .all
+            .iter()
+            .enumerate()
+            .map(|(index, sub)| (format!("{}.all[{}]", path, index), sub))
+            .chain(
+                self.any
+                    .iter()
+                    .enumerate()
+                    .map(|(index, sub)| (format!("{}.any[{}]", path, index), sub)),
+            )
+            .chain(self.get_not().map(|sub| (format!("{}.not", path), sub)));
+        for (sub_path, sub) in subs {
+            if sub.is_schedule() {
+                return Err(format!("{}: schedules cannot be combined", sub_path));
+            }
+            sub.validate_at(&sub_path)?;
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize, PartialEq)]
 struct Operand {
     r#type: String,
     name: String,
@@ -94,9 +507,11 @@ mod tests {
                 name: "test-scenario".to_string(),
                 labels: None,
                 annotations: None,
+                namespace: None,
             },
             spec: ScenarioSpec {
                 condition: Some(Condition {
+                    r#type: ConditionType::Data,
                     express: "eq".to_string(),
                     value: "ready".to_string(),
                     operands: Operand {
@@ -104,9 +519,22 @@ mod tests {
                         name: "test-pod".to_string(),
                         value: "status".to_string(),
                     },
+                    schedule: None,
+                    all: Vec::new(),
+                    any: Vec::new(),
+                    not: None,
                 }),
                 action: "start".to_string(),
                 target: "model-1".to_string(),
+                dependsOn: Vec::new(),
+                cooldownSeconds: None,
+                debounceSeconds: None,
+                actionParams: HashMap::new(),
+                allowedModes: Vec::new(),
+                modeWaitSeconds: None,
+                nodeSelector: HashMap::new(),
+                ttlSeconds: None,
+                onExpiry: OnExpiry::Withdraw,
             },
             status: Some(ScenarioStatus {
                 state: ScenarioState::None,
@@ -152,11 +580,21 @@ mod tests {
                 name: "no-condition-scenario".to_string(),
                 labels: None,
                 annotations: None,
+                namespace: None,
             },
             spec: ScenarioSpec {
                 condition: None,
                 action: "stop".to_string(),
                 target: "model-2".to_string(),
+                dependsOn: Vec::new(),
+                cooldownSeconds: None,
+                debounceSeconds: None,
+                actionParams: HashMap::new(),
+                allowedModes: Vec::new(),
+                modeWaitSeconds: None,
+                nodeSelector: HashMap::new(),
+                ttlSeconds: None,
+                onExpiry: OnExpiry::Withdraw,
             },
             status: None,
         };
@@ -215,6 +653,7 @@ mod tests {
     fn test_scenario_spec_serialization() {
         let spec = ScenarioSpec {
             condition: Some(Condition {
+                r#type: ConditionType::Data,
                 express: "gt".to_string(),
                 value: "5".to_string(),
                 operands: Operand {
@@ -222,9 +661,22 @@ mod tests {
                     name: "cpu_usage".to_string(),
                     value: "value".to_string(),
                 },
+                schedule: None,
+                all: Vec::new(),
+                any: Vec::new(),
+                not: None,
             }),
             action: "scale".to_string(),
             target: "deployment".to_string(),
+            dependsOn: vec!["download".to_string()],
+            cooldownSeconds: None,
+            debounceSeconds: None,
+            actionParams: HashMap::new(),
+            allowedModes: Vec::new(),
+            modeWaitSeconds: None,
+            nodeSelector: HashMap::new(),
+            ttlSeconds: None,
+            onExpiry: OnExpiry::Withdraw,
         };
 
         let serialized = serde_json::to_string(&spec).unwrap();
@@ -236,6 +688,7 @@ mod tests {
     #[test]
     fn test_condition_cloning() {
         let condition = Condition {
+            r#type: ConditionType::Data,
             express: "lt".to_string(),
             value: "10".to_string(),
             operands: Operand {
@@ -243,9 +696,246 @@ mod tests {
                 name: "memory_usage".to_string(),
                 value: "value".to_string(),
             },
+            schedule: None,
+            all: Vec::new(),
+            any: Vec::new(),
+            not: None,
         };
 
         let cloned = condition.clone();
         assert_eq!(condition, cloned);
     }
+
+    #[test]
+    fn test_schedule_condition() {
+        let condition: Condition = serde_yaml::from_str(
+            r#"
+type: schedule
+schedule:
+  cron: "0 2 * * *"
+"#,
+        )
+        .unwrap();
+        assert!(condition.is_schedule());
+        let schedule = condition.get_schedule().unwrap();
+        assert_eq!(schedule.get_cron(), Some("0 2 * * *".to_string()));
+        assert_eq!(schedule.get_at(), None);
+
+        // Existing data conditions default to `type: data`
+        let data: Condition = serde_yaml::from_str(
+            "express: eq\nvalue: D\noperands: {type: DDS, name: gear, value: vehicle/gear}",
+        )
+        .unwrap();
+        assert!(!data.is_schedule());
+        assert!(!serde_yaml::to_string(&data).unwrap().contains("type: data"));
+    }
+
+    #[test]
+    fn test_compound_condition() {
+        let condition: Condition = serde_yaml::from_str(
+            r#"
+all:
+  - express: lt
+    value: "5"
+    operands: {type: DDS, name: speed, value: vehicle/speed}
+  - any:
+      - express: eq
+        value: P
+        operands: {type: DDS, name: gear, value: vehicle/gear}
+      - not:
+          express: eq
+          value: "on"
+          operands: {type: DDS, name: ignition, value: vehicle/speed}
+"#,
+        )
+        .unwrap();
+        assert!(condition.is_compound());
+        assert_eq!(condition.get_all().len(), 2);
+        assert!(condition.get_all()[1].get_any()[1].get_not().is_some());
+        assert_eq!(
+            condition.get_topics(),
+            vec!["vehicle/gear".to_string(), "vehicle/speed".to_string()]
+        );
+        assert!(condition.validate().is_ok());
+
+        let serialized = serde_yaml::to_string(&condition).unwrap();
+        assert_eq!(
+            serde_yaml::from_str::<Condition>(&serialized).unwrap(),
+            condition
+        );
+    }
+
+    #[test]
+    fn test_compound_condition_validation() {
+        let both: Condition = serde_yaml::from_str(
+            "all:\n  - express: eq\n    value: P\n\
+             any:\n  - express: eq\n    value: D\n",
+        )
+        .unwrap();
+        assert!(both.validate().unwrap_err().contains("only one of"));
+
+        let nested_schedule: Condition = serde_yaml::from_str(
+            "any:\n  - all:\n      - type: schedule\n        schedule:\n          cron: \"0 2 * * *\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            nested_schedule.validate().unwrap_err(),
+            "condition.any[0].all[0]: schedules cannot be combined"
+        );
+
+        let with_comparison: Condition =
+            serde_yaml::from_str("express: eq\nvalue: P\nnot:\n  express: eq\n  value: D\n")
+                .unwrap();
+        assert!(with_comparison.validate().is_err());
+    }
+
+    #[test]
+    fn test_depends_on_and_cycle_detection() {
+        let scenario: Scenario = serde_yaml::from_str(
+            r#"
+apiVersion: v1
+kind: Scenario
+metadata:
+  name: apply-update
+spec:
+  action: update
+  target: apply-update
+  dependsOn:
+    - download-update
+"#,
+        )
+        .unwrap();
+        assert_eq!(scenario.get_depends_on(), vec!["download-update"]);
+        assert!(create_test_scenario().get_depends_on().is_empty());
+
+        let graph = |edges: &[(&str, &[&str])]| -> HashMap<String, Vec<String>> {
+            edges
+                .iter()
+                .map(|(name, deps)| {
+                    (
+                        name.to_string(),
+                        deps.iter().map(|d| d.to_string()).collect(),
+                    )
+                })
+                .collect()
+        };
+        let acyclic = graph(&[("c", &["b", "a"]), ("b", &["a"]), ("a", &[])]);
+        assert_eq!(find_dependency_cycle(&acyclic), None);
+
+        let cyclic = graph(&[("a", &["b"]), ("b", &["c"]), ("c", &["a"]), ("d", &["a"])]);
+        assert_eq!(
+            find_dependency_cycle(&cyclic),
+            Some(vec![
+                "a".to_string(),
+                "b".to_string(),
+                "c".to_string(),
+                "a".to_string()
+            ])
+        );
+        assert!(find_dependency_cycle(&graph(&[("a", &["a"])])).is_some());
+    }
+
+    #[test]
+    fn test_cooldown_and_debounce() {
+        let scenario: Scenario = serde_yaml::from_str(
+            r#"
+apiVersion: v1
+kind: Scenario
+metadata:
+  name: park-assist
+spec:
+  action: launch
+  target: park-assist
+  cooldownSeconds: 60
+  debounceSeconds: 3
+"#,
+        )
+        .unwrap();
+        assert_eq!(scenario.get_cooldown(), Duration::from_secs(60));
+        assert_eq!(scenario.get_debounce(), Duration::from_secs(3));
+
+        let plain = create_test_scenario();
+        assert_eq!(plain.get_cooldown(), Duration::ZERO);
+        assert_eq!(plain.get_debounce(), Duration::ZERO);
+        let serialized = serde_yaml::to_string(&plain).unwrap();
+        assert!(!serialized.contains("cooldownSeconds"));
+    }
+
+    #[test]
+    fn test_action_params() {
+        let scenario: Scenario = serde_yaml::from_str(
+            r#"
+apiVersion: v1
+kind: Scenario
+metadata:
+  name: low-battery
+spec:
+  action: notify
+  target: low-battery
+  actionParams:
+    message: Battery below 10%
+    repeat: 3
+"#,
+        )
+        .unwrap();
+        let params = scenario.get_action_params();
+        assert_eq!(params["message"].as_str(), Some("Battery below 10%"));
+        assert_eq!(params["repeat"].as_i64(), Some(3));
+
+        let serialized = serde_yaml::to_string(&create_test_scenario()).unwrap();
+        assert!(!serialized.contains("actionParams"));
+    }
+
+    #[test]
+    fn test_allowed_modes() {
+        let scenario: Scenario = serde_yaml::from_str(
+            r#"
+apiVersion: v1
+kind: Scenario
+metadata:
+  name: ota
+spec:
+  action: update
+  target: infotainment
+  allowedModes: [parked, ignition-off]
+  modeWaitSeconds: 3600
+"#,
+        )
+        .unwrap();
+        assert_eq!(scenario.get_allowed_modes(), vec!["parked", "ignition-off"]);
+        assert_eq!(scenario.get_mode_wait(), Some(Duration::from_secs(3600)));
+
+        let plain = create_test_scenario();
+        assert!(plain.get_allowed_modes().is_empty());
+        assert_eq!(plain.get_mode_wait(), None);
+        let serialized = serde_yaml::to_string(&plain).unwrap();
+        assert!(!serialized.contains("allowedModes"));
+    }
+
+    #[test]
+    fn test_ttl_and_on_expiry() {
+        let scenario: Scenario = serde_yaml::from_str(
+            r#"
+apiVersion: v1
+kind: Scenario
+metadata:
+  name: capture
+spec:
+  action: launch
+  target: diagnostic-capture
+  ttlSeconds: 3600
+  onExpiry: disable
+"#,
+        )
+        .unwrap();
+        assert_eq!(scenario.get_ttl(), Some(Duration::from_secs(3600)));
+        assert_eq!(scenario.get_on_expiry(), OnExpiry::Disable);
+
+        let plain = create_test_scenario();
+        assert_eq!(plain.get_ttl(), None);
+        assert_eq!(plain.get_on_expiry(), OnExpiry::Withdraw);
+        let serialized = serde_yaml::to_string(&plain).unwrap();
+        assert!(!serialized.contains("ttlSeconds"));
+        assert!(!serialized.contains("onExpiry"));
+    }
 }
@@ -0,0 +1,321 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+use super::Artifact;
+use super::Secret;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::{STANDARD, URL_SAFE};
+use base64::Engine;
+use std::collections::HashMap;
+
+/// Secret type for generic key/value credentials
+pub const SECRET_TYPE_OPAQUE: &str = "Opaque";
+/// Secret type for container registry credentials
+pub const SECRET_TYPE_REGISTRY: &str = "kubernetes.io/dockerconfigjson";
+/// Key of the registry credentials in the `data` of a registry secret
+pub const DOCKER_CONFIG_JSON_KEY: &str = ".dockerconfigjson";
+/// Environment variable holding the base64 encoded 256-bit encryption key
+pub const SECRET_KEY_ENV: &str = "PULLPIRI_SECRET_KEY";
+/// Value of `spec.encryption` for values sealed with AES-256-GCM
+pub const ENCRYPTION_AES_256_GCM: &str = "aes-256-gcm";
+
+const NONCE_LEN: usize = 12;
+
+/// Read the encryption key from `PULLPIRI_SECRET_KEY`, if configured
+pub fn encryption_key() -> crate::Result<Option<Vec<u8>>> {
+    let encoded = match std::env::var(SECRET_KEY_ENV) {
+        Ok(value) if !value.trim().is_empty() => value,
+        _ => return Ok(None),
+    };
+    let key = STANDARD.decode(encoded.trim())?;
+    if key.len() != 32 {
+        return Err(format!("{} must be a base64 encoded 32 byte key", SECRET_KEY_ENV).into());
+    }
+    Ok(Some(key))
+}
+
+/// Encrypt a value with AES-256-GCM into base64(nonce || ciphertext)
+pub fn encrypt_value(key: &[u8], value: &[u8]) -> crate::Result<String> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, value)
+        .map_err(|e| format!("failed to encrypt secret value: {}", e))?;
+    let mut buf = nonce.to_vec();
+    buf.extend_from_slice(&ciphertext);
+    Ok(STANDARD.encode(buf))
+}
+
+/// Decrypt a value sealed by [`encrypt_value`]
+pub fn decrypt_value(key: &[u8], sealed: &str) -> crate::Result<Vec<u8>> {
+    let buf = STANDARD.decode(sealed)?;
+    if buf.len() < NONCE_LEN {
+        return Err("sealed secret value is too short".into());
+    }
+    let (nonce, ciphertext) = buf.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|e| format!("failed to decrypt secret value: {}", e).into())
+}
+
+impl Artifact for Secret {
+    fn get_name(&self) -> String {
+        self.metadata.name.clone()
+    }
+
+    fn get_namespace(&self) -> String {
+        self.metadata.get_namespace()
+    }
+}
+
+impl Secret {
+    pub fn get_spec(&self) -> &SecretSpec {
+        &self.spec
+    }
+
+    pub fn get_spec_mut(&mut self) -> &mut SecretSpec {
+        &mut self.spec
+    }
+
+    /// Returns `true` if the secret holds container registry credentials
+    pub fn is_registry(&self) -> bool {
+        self.spec.get_type() == SECRET_TYPE_REGISTRY
+    }
+
+    /// Plain values of the secret
+    ///
+    /// `data` is decoded from base64 and, if the secret was sealed, decrypted
+    /// with `key`. A sealed secret without a key is an error. `stringData`
+    /// of a secret that was never sealed is merged in as is.
+    pub fn decode_data(&self, key: Option<&[u8]>) -> crate::Result<HashMap<String, String>> {
+        let spec = &self.spec;
+        let key = match spec.get_encryption().as_deref() {
+            None => None,
+            Some(ENCRYPTION_AES_256_GCM) => Some(key.ok_or_else(|| {
+                format!(
+                    "secret '{}' is sealed but {} is not set",
+                    self.get_name(),
+                    SECRET_KEY_ENV
+                )
+            })?),
+            Some(other) => {
+                return Err(format!(
+                    "secret '{}' uses unsupported encryption '{}'",
+                    self.get_name(),
+                    other
+                )
+                .into())
+            }
+        };
+
+        let mut values = HashMap::new();
+        for (name, value) in spec.get_data().iter().flatten() {
+            let decoded = match key {
+                Some(key) => decrypt_value(key, value)?,
+                None => STANDARD.decode(value)?,
+            };
+            let decoded = String::from_utf8(decoded)
+                .map_err(|_| format!("secret value '{}' is not valid UTF-8", name))?;
+            values.insert(name.clone(), decoded);
+        }
+        for (name, value) in spec.get_string_data().iter().flatten() {
+            values.insert(name.clone(), value.clone());
+        }
+        Ok(values)
+    }
+
+    /// `X-Registry-Auth` header value for image pulls
+    ///
+    /// The `auths` of the decoded `.dockerconfigjson` are converted into the
+    /// base64url encoded map of registry to credentials the Podman API expects.
+    /// Returns `None` if the secret holds no usable credentials.
+    pub fn registry_auth(&self, key: Option<&[u8]>) -> crate::Result<Option<String>> {
+        let data = self.decode_data(key)?;
+        let Some(config) = data.get(DOCKER_CONFIG_JSON_KEY) else {
+            return Ok(None);
+        };
+        let config: serde_json::Value = serde_json::from_str(config)?;
+        let mut auths = serde_json::Map::new();
+        for (registry, entry) in config["auths"].as_object().into_iter().flatten() {
+            let (username, password) = match entry["auth"].as_str() {
+                Some(auth) => {
+                    let auth = String::from_utf8(STANDARD.decode(auth)?)?;
+                    match auth.split_once(':') {
+                        Some((user, pass)) => (user.to_string(), pass.to_string()),
+                        None => continue,
+                    }
+                }
+                None => (
+                    entry["username"].as_str().unwrap_or_default().to_string(),
+                    entry["password"].as_str().unwrap_or_default().to_string(),
+                ),
+            };
+            auths.insert(
+                registry.clone(),
+                serde_json::json!({ "username": username, "password": password }),
+            );
+        }
+        if auths.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(
+            URL_SAFE.encode(serde_json::Value::Object(auths).to_string()),
+        ))
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct SecretSpec {
+    r#type: Option<String>,
+    /// Values encoded in base64 (or encrypted, see `encryption`)
+    data: Option<HashMap<String, String>>,
+    /// Plain values, converted into `data` when the secret is applied
+    stringData: Option<HashMap<String, String>>,
+    /// Algorithm used to encrypt `data`, set by apiserver
+    encryption: Option<String>,
+}
+
+impl SecretSpec {
+    pub fn get_type(&self) -> &str {
+        self.r#type.as_deref().unwrap_or(SECRET_TYPE_OPAQUE)
+    }
+
+    pub fn get_data(&self) -> &Option<HashMap<String, String>> {
+        &self.data
+    }
+
+    pub fn get_string_data(&self) -> &Option<HashMap<String, String>> {
+        &self.stringData
+    }
+
+    pub fn get_encryption(&self) -> &Option<String> {
+        &self.encryption
+    }
+
+    /// Replace the stored values with already encoded `data`
+    ///
+    /// `stringData` is dropped so that plain values are never persisted.
+    pub fn set_encoded_data(&mut self, data: HashMap<String, String>, encryption: Option<String>) {
+        self.data = Some(data);
+        self.stringData = None;
+        self.encryption = encryption;
+    }
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET_YAML: &str = r#"
+apiVersion: v1
+kind: Secret
+metadata:
+  name: registry-cred
+spec:
+  type: kubernetes.io/dockerconfigjson
+  data:
+    .dockerconfigjson: e30=
+  stringData:
+    token: plain-token
+"#;
+
+    #[test]
+    fn test_secret_parse_and_accessors() {
+        let secret: Secret = serde_yaml::from_str(SECRET_YAML).unwrap();
+
+        assert_eq!(secret.get_name(), "registry-cred");
+        assert!(secret.is_registry());
+        assert_eq!(
+            secret
+                .get_spec()
+                .get_data()
+                .as_ref()
+                .unwrap()
+                .get(".dockerconfigjson"),
+            Some(&"e30=".to_string())
+        );
+        assert!(secret.get_spec().get_string_data().is_some());
+        assert!(secret.get_spec().get_encryption().is_none());
+    }
+
+    #[test]
+    fn test_secret_default_type_is_opaque() {
+        let yaml = SECRET_YAML.replace("  type: kubernetes.io/dockerconfigjson\n", "");
+        let secret: Secret = serde_yaml::from_str(&yaml).unwrap();
+
+        assert_eq!(secret.get_spec().get_type(), SECRET_TYPE_OPAQUE);
+        assert!(!secret.is_registry());
+    }
+
+    #[test]
+    fn test_encrypt_and_decrypt_roundtrip() {
+        let key = [7u8; 32];
+
+        let sealed = encrypt_value(&key, b"s3cr3t").unwrap();
+
+        assert_ne!(sealed, STANDARD.encode("s3cr3t"));
+        assert_eq!(decrypt_value(&key, &sealed).unwrap(), b"s3cr3t".to_vec());
+        assert!(decrypt_value(&[8u8; 32], &sealed).is_err());
+    }
+
+    #[test]
+    fn test_decode_data_plain_and_sealed() {
+        let key = [7u8; 32];
+        let mut secret: Secret = serde_yaml::from_str(SECRET_YAML).unwrap();
+
+        let plain = secret.decode_data(None).unwrap();
+        assert_eq!(plain.get(".dockerconfigjson"), Some(&"{}".to_string()));
+        assert_eq!(plain.get("token"), Some(&"plain-token".to_string()));
+
+        let data = HashMap::from([(
+            "token".to_string(),
+            encrypt_value(&key, b"sealed-token").unwrap(),
+        )]);
+        secret
+            .get_spec_mut()
+            .set_encoded_data(data, Some(ENCRYPTION_AES_256_GCM.to_string()));
+
+        let sealed = secret.decode_data(Some(&key)).unwrap();
+        assert_eq!(sealed.get("token"), Some(&"sealed-token".to_string()));
+        assert!(secret.decode_data(None).is_err());
+        assert!(secret.decode_data(Some(&[8u8; 32])).is_err());
+    }
+
+    #[test]
+    fn test_registry_auth() {
+        let config = r#"{"auths":{"registry.local:5000":{"auth":"dXNlcjpwYXNz"}}}"#;
+        let yaml = SECRET_YAML.replace("e30=", &STANDARD.encode(config));
+        let secret: Secret = serde_yaml::from_str(&yaml).unwrap();
+
+        let header = secret.registry_auth(None).unwrap().unwrap();
+        let auths: serde_json::Value =
+            serde_json::from_slice(&URL_SAFE.decode(header).unwrap()).unwrap();
+
+        assert_eq!(auths["registry.local:5000"]["username"], "user");
+        assert_eq!(auths["registry.local:5000"]["password"], "pass");
+
+        let empty: Secret = serde_yaml::from_str(SECRET_YAML).unwrap();
+        assert!(empty.registry_auth(None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_set_encoded_data_drops_string_data() {
+        let mut secret: Secret = serde_yaml::from_str(SECRET_YAML).unwrap();
+        let data = HashMap::from([("token".to_string(), "cGxhaW4tdG9rZW4=".to_string())]);
+
+        secret
+            .get_spec_mut()
+            .set_encoded_data(data.clone(), Some("aes-256-gcm".to_string()));
+
+        assert_eq!(secret.get_spec().get_data(), &Some(data));
+        assert!(secret.get_spec().get_string_data().is_none());
+        assert_eq!(
+            secret.get_spec().get_encryption().as_deref(),
+            Some("aes-256-gcm")
+        );
+    }
+}
@@ -9,6 +9,10 @@ impl Artifact for Volume {
     fn get_name(&self) -> String {
         self.metadata.name.clone()
     }
+
+    fn get_namespace(&self) -> String {
+        self.metadata.get_namespace()
+    }
 }
 
 impl Volume {
@@ -42,7 +46,8 @@ mod tests {
             metadata: MetaData {
                 name: String::from("test-volume"),                   // Valid name
                 annotations: Some(std::collections::HashMap::new()), // Empty annotations
-                labels: Some(std::collections::HashMap::new()),      // Empty labels
+                namespace: None,
+                labels: Some(std::collections::HashMap::new()), // Empty labels
             },
             spec: None, // No spec provided
         };
@@ -58,7 +63,8 @@ mod tests {
             metadata: MetaData {
                 name: String::from(""),                              // Empty name
                 annotations: Some(std::collections::HashMap::new()), // Empty annotations
-                labels: Some(std::collections::HashMap::new()),      // Empty labels
+                namespace: None,
+                labels: Some(std::collections::HashMap::new()), // Empty labels
             },
             spec: None, // No spec provided
         };
@@ -74,7 +80,8 @@ mod tests {
             metadata: MetaData {
                 name: String::from("test-volume"),                   // Valid name
                 annotations: Some(std::collections::HashMap::new()), // Empty annotations
-                labels: Some(std::collections::HashMap::new()),      // Empty labels
+                namespace: None,
+                labels: Some(std::collections::HashMap::new()), // Empty labels
             },
             spec: None, // No spec provided
         };
@@ -93,7 +100,8 @@ mod tests {
             metadata: MetaData {
                 name: String::from("test-volume"),                   // Valid name
                 annotations: Some(std::collections::HashMap::new()), // Empty annotations
-                labels: Some(std::collections::HashMap::new()),      // Empty labels
+                namespace: None,
+                labels: Some(std::collections::HashMap::new()), // Empty labels
             },
             spec: Some(volume_spec.clone()), // Spec provided
         };
@@ -136,7 +144,8 @@ mod tests {
             metadata: MetaData {
                 name: String::from("test-volume"),                   // Valid name
                 annotations: Some(std::collections::HashMap::new()), // Empty annotations
-                labels: Some(std::collections::HashMap::new()),      // Empty labels
+                namespace: None,
+                labels: Some(std::collections::HashMap::new()), // Empty labels
             },
             spec: None, // No spec provided
         };
@@ -0,0 +1,73 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+
+use super::pod::PodSpec;
+use super::Deployment;
+use crate::spec::MetaData;
+
+impl Deployment {
+    /// Deployment running one replica of a pod
+    ///
+    /// `labels` are set on the deployment and the pod template and select
+    /// the pods of the deployment.
+    pub fn new(name: &str, labels: HashMap<String, String>, podspec: PodSpec) -> Deployment {
+        Deployment {
+            apiVersion: String::from("apps/v1"),
+            kind: String::from("Deployment"),
+            metadata: MetaData {
+                name: name.to_string(),
+                labels: Some(labels.clone()),
+                annotations: None,
+                namespace: None,
+            },
+            spec: DeploymentSpec {
+                replicas: 1,
+                selector: LabelSelector {
+                    matchLabels: labels.clone(),
+                },
+                template: PodTemplateSpec {
+                    metadata: TemplateMetaData {
+                        labels,
+                        annotations: None,
+                    },
+                    spec: podspec,
+                },
+            },
+        }
+    }
+
+    pub fn get_name(&self) -> String {
+        self.metadata.name.clone()
+    }
+
+    /// Set the annotations of the pods of the deployment
+    pub fn set_pod_annotations(&mut self, annotations: HashMap<String, String>) {
+        self.spec.template.metadata.annotations = Some(annotations);
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct DeploymentSpec {
+    replicas: i32,
+    selector: LabelSelector,
+    template: PodTemplateSpec,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct LabelSelector {
+    matchLabels: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct PodTemplateSpec {
+    metadata: TemplateMetaData,
+    spec: PodSpec,
+}
+
+/// Metadata of a pod template, which has no name of its own
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct TemplateMetaData {
+    labels: HashMap<String, String>,
+    annotations: Option<HashMap<String, String>>,
+}
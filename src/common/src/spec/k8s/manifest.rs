@@ -0,0 +1,249 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Kubernetes manifests of pods and deployments
+//!
+//! Pullpiri pod specs carry fields Kubernetes does not know. [`to_manifest`]
+//! turns a [`super::Pod`] or [`super::Deployment`] into a manifest kubectl
+//! accepts: `probeConfig` becomes the `livenessProbe` and `readinessProbe` of
+//! every container, `maxRestarts` and the Podman `network` are dropped,
+//! unset fields are left out and label-like maps are sorted so the output is
+//! stable.
+
+use crate::spec::artifact::package::ModelInfo;
+use serde_yaml::{Mapping, Value};
+
+/// Node label Kubernetes sets to the name of the node
+pub const HOSTNAME_LABEL: &str = "kubernetes.io/hostname";
+
+/// Maps whose keys are sorted in a manifest
+const SORTED_MAPS: &[&str] = &[
+    "labels",
+    "annotations",
+    "matchLabels",
+    "nodeSelector",
+    "limits",
+    "requests",
+];
+
+/// Probes of `probeConfig` and their Kubernetes names
+const PROBES: &[(&str, &str)] = &[
+    ("liveness", "livenessProbe"),
+    ("readiness", "readinessProbe"),
+];
+
+/// Probe handlers of a probe and their Kubernetes names
+const PROBE_HANDLERS: &[(&str, &str)] =
+    &[("http", "httpGet"), ("tcp", "tcpSocket"), ("exec", "exec")];
+
+const PROBE_TIMINGS: &[&str] = &[
+    "initialDelaySeconds",
+    "periodSeconds",
+    "timeoutSeconds",
+    "failureThreshold",
+];
+
+/// Kubernetes manifest of a pod or deployment
+pub fn to_manifest<T: serde::Serialize>(object: &T) -> Result<Value, serde_yaml::Error> {
+    let mut manifest = serde_yaml::to_value(object)?;
+    if let Some(spec) = pod_spec_mut(&mut manifest) {
+        convert_probe_config(spec);
+        // Kubernetes restarts pods without a limit
+        spec.remove("maxRestarts");
+        // Pods join the cluster network, a host network is kept as `hostNetwork`
+        spec.remove("network");
+    }
+    clean(&mut manifest);
+    Ok(manifest)
+}
+
+/// Keep the pods of a manifest on the nodes the model of a package allows
+///
+/// A model with a fixed node is bound to it by the `kubernetes.io/hostname`
+/// label, `nodeSelector` and tolerations are copied as they are.
+pub fn set_placement(manifest: &mut Value, model: &ModelInfo) -> Result<(), serde_yaml::Error> {
+    let Some(spec) = pod_spec_mut(manifest) else {
+        return Ok(());
+    };
+
+    let mut node_selector = model.get_node_selector().clone();
+    if !model.is_auto_node() {
+        node_selector.insert(HOSTNAME_LABEL.to_string(), model.get_node());
+    }
+    if !node_selector.is_empty() {
+        spec.insert("nodeSelector".into(), serde_yaml::to_value(node_selector)?);
+    }
+    if !model.get_tolerations().is_empty() {
+        spec.insert(
+            "tolerations".into(),
+            serde_yaml::to_value(model.get_tolerations())?,
+        );
+    }
+
+    clean(manifest);
+    Ok(())
+}
+
+/// The first mapping with `containers`, i.e. the pod spec
+fn pod_spec_mut(value: &mut Value) -> Option<&mut Mapping> {
+    let mapping = value.as_mapping_mut()?;
+    if mapping.contains_key("containers") {
+        return Some(mapping);
+    }
+    mapping.values_mut().find_map(pod_spec_mut)
+}
+
+fn convert_probe_config(spec: &mut Mapping) {
+    let Some(probe_config) = spec.remove("probeConfig") else {
+        return;
+    };
+
+    let mut probes = Vec::new();
+    for (name, k8s_name) in PROBES {
+        if let Some(config) = probe_config.get(*name).and_then(Value::as_mapping) {
+            probes.push((*k8s_name, k8s_probe(config)));
+        }
+    }
+
+    let containers = spec.get_mut("containers").and_then(Value::as_sequence_mut);
+    for container in containers.into_iter().flatten() {
+        if let Some(container) = container.as_mapping_mut() {
+            for (k8s_name, probe) in &probes {
+                container.insert((*k8s_name).into(), Value::Mapping(probe.clone()));
+            }
+        }
+    }
+}
+
+fn k8s_probe(config: &Mapping) -> Mapping {
+    let mut probe = Mapping::new();
+    for (handler, k8s_handler) in PROBE_HANDLERS {
+        if let Some(value) = config.get(*handler).filter(|value| !value.is_null()) {
+            probe.insert((*k8s_handler).into(), value.clone());
+        }
+    }
+    for timing in PROBE_TIMINGS {
+        if let Some(value) = config.get(*timing) {
+            probe.insert((*timing).into(), value.clone());
+        }
+    }
+    probe
+}
+
+/// Remove null fields and sort label-like maps
+fn clean(value: &mut Value) {
+    match value {
+        Value::Mapping(mapping) => {
+            mapping.retain(|_, value| !value.is_null());
+            for (key, value) in mapping.iter_mut() {
+                if key.as_str().is_some_and(|key| SORTED_MAPS.contains(&key)) {
+                    sort_keys(value);
+                }
+                clean(value);
+            }
+        }
+        Value::Sequence(sequence) => sequence.iter_mut().for_each(clean),
+        _ => {}
+    }
+}
+
+fn sort_keys(value: &mut Value) {
+    if let Value::Mapping(mapping) = value {
+        let mut entries: Vec<(Value, Value)> = std::mem::take(mapping).into_iter().collect();
+        entries.sort_by(|a, b| a.0.as_str().cmp(&b.0.as_str()));
+        *mapping = entries.into_iter().collect();
+    }
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec::k8s::Pod;
+
+    #[test]
+    fn test_to_manifest_converts_probe_config_and_drops_nulls() {
+        let spec = serde_yaml::from_str(
+            r#"
+hostNetwork: true
+containers:
+  - name: web
+    image: nginx
+restartPolicy: OnFailure
+maxRestarts: 3
+probeConfig:
+  liveness:
+    http:
+      path: /health
+      port: 8080
+"#,
+        )
+        .unwrap();
+        let manifest = to_manifest(&Pod::new("web", spec)).unwrap();
+
+        let spec = &manifest["spec"];
+        assert!(spec.get("probeConfig").is_none());
+        assert!(spec.get("volumes").is_none());
+        assert_eq!(spec["hostNetwork"], Value::Bool(true));
+        assert_eq!(spec["restartPolicy"], Value::from("OnFailure"));
+        assert!(spec.get("maxRestarts").is_none());
+        let probe = &spec["containers"][0]["livenessProbe"];
+        assert_eq!(probe["httpGet"]["path"], Value::from("/health"));
+        assert_eq!(probe["periodSeconds"], Value::from(10));
+        assert!(probe.get("tcpSocket").is_none());
+        assert!(spec["containers"][0].get("readinessProbe").is_none());
+        assert!(manifest["metadata"].get("labels").is_none());
+    }
+
+    #[test]
+    fn test_to_manifest_drops_network() {
+        let network: crate::spec::artifact::Network = serde_yaml::from_str(
+            r#"
+apiVersion: v1
+kind: Network
+metadata:
+  name: vd-network
+spec:
+  driver: bridge
+  subnet: 192.168.10.0/24
+"#,
+        )
+        .unwrap();
+        let mut spec: crate::spec::k8s::pod::PodSpec = serde_yaml::from_str(
+            r#"
+containers:
+  - name: web
+    image: nginx
+"#,
+        )
+        .unwrap();
+        spec.attach_network(&network);
+        let manifest = to_manifest(&Pod::new("web", spec)).unwrap();
+
+        assert!(manifest["spec"].get("network").is_none());
+        assert_eq!(manifest["spec"]["hostNetwork"], Value::Bool(false));
+    }
+
+    #[test]
+    fn test_to_manifest_converts_readiness_probe() {
+        let spec = serde_yaml::from_str(
+            r#"
+containers:
+  - name: web
+    image: nginx
+probeConfig:
+  readiness:
+    tcp:
+      port: 8080
+    periodSeconds: 2
+"#,
+        )
+        .unwrap();
+        let manifest = to_manifest(&Pod::new("web", spec)).unwrap();
+
+        let container = &manifest["spec"]["containers"][0];
+        assert!(container.get("livenessProbe").is_none());
+        let probe = &container["readinessProbe"];
+        assert_eq!(probe["tcpSocket"]["port"], Value::from(8080));
+        assert_eq!(probe["periodSeconds"], Value::from(2));
+    }
+}
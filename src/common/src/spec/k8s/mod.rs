@@ -1,5 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod deployment;
+pub mod manifest;
 pub mod pod;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
@@ -9,3 +11,11 @@ pub struct Pod {
     metadata: super::MetaData,
     spec: pod::PodSpec,
 }
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct Deployment {
+    apiVersion: String,
+    kind: String,
+    metadata: super::MetaData,
+    spec: deployment::DeploymentSpec,
+}
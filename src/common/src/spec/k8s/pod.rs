@@ -3,7 +3,8 @@
 use std::collections::HashMap;
 
 use super::Pod;
-use crate::spec::artifact::Model;
+use crate::spec::artifact::network::{NetworkDriver, NetworkSpec};
+use crate::spec::artifact::{Model, Network};
 use crate::spec::MetaData;
 
 impl Pod {
@@ -15,6 +16,7 @@ impl Pod {
                 name: name.to_string(),
                 labels: None,
                 annotations: None,
+                namespace: None,
             },
             spec: podspec,
         }
@@ -24,15 +26,73 @@ impl Pod {
         self.metadata.name.clone()
     }
 
+    /// Namespace of the package the pod was generated from
+    pub fn get_namespace(&self) -> String {
+        self.metadata.get_namespace()
+    }
+
+    /// Set the namespace of the pod, which stays unset in the default namespace
+    pub fn set_namespace(&mut self, namespace: &str) {
+        self.metadata.namespace =
+            (!crate::spec::namespace::is_default(namespace)).then(|| namespace.to_string());
+    }
+
+    pub fn set_labels(&mut self, labels: HashMap<String, String>) {
+        self.metadata.labels = Some(labels);
+    }
+
+    pub fn set_annotations(&mut self, annotations: HashMap<String, String>) {
+        self.metadata.annotations = Some(annotations);
+    }
+
     /// Returns the restart policy of the pod spec, if set.
     pub fn get_restart_policy(&self) -> Option<&str> {
-        self.spec.restartPolicy.as_deref()
+        self.spec.restartPolicy.map(|policy| policy.as_str())
     }
 
     /// Returns the probe configuration of the pod spec, if set.
     pub fn get_probe_config(&self) -> Option<&ProbeConfig> {
         self.spec.probeConfig.as_ref()
     }
+
+    /// Returns the network the pod is attached to, if set.
+    pub fn get_network(&self) -> Option<&PodNetwork> {
+        self.spec.get_network()
+    }
+
+    /// Returns the CPU and memory requested by the pod.
+    pub fn get_resource_request(&self) -> ResourceRequest {
+        self.spec.get_resource_request()
+    }
+
+    /// Returns the images of all containers of the pod.
+    pub fn get_images(&self) -> Vec<&str> {
+        self.spec.get_images()
+    }
+
+    /// Returns the number of containers of the pod, without init containers.
+    pub fn get_container_count(&self) -> u32 {
+        self.spec.containers.len() as u32
+    }
+
+    /// Returns the names of the secrets in `envFrom` and `imagePullSecrets`.
+    pub fn get_secret_names(&self) -> Vec<String> {
+        let mut names = self.spec.get_secret_refs();
+        names.extend(self.spec.get_image_pull_secrets());
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Returns `true` if the secret is listed in `imagePullSecrets`.
+    pub fn is_image_pull_secret(&self, name: &str) -> bool {
+        self.spec.get_image_pull_secrets().iter().any(|n| n == name)
+    }
+
+    /// Replaces the `envFrom` references to a secret with its values.
+    pub fn inject_secret(&mut self, name: &str, data: &HashMap<String, String>) {
+        self.spec.inject_secret(name, data);
+    }
 }
 
 impl From<Model> for Pod {
@@ -47,18 +107,91 @@ pub struct PodSpec {
     pub containers: Vec<Container>,
     pub volumes: Option<Vec<Volume>>,
     initContainers: Option<Vec<Container>>,
-    restartPolicy: Option<String>,
+    restartPolicy: Option<RestartPolicy>,
+    /// Restarts of a crashing model before it is Failed, the backoff setting if unset
+    maxRestarts: Option<u32>,
     terminationGracePeriodSeconds: Option<i32>,
     hostIPC: Option<bool>,
     runtimeClassName: Option<String>,
     securityContext: Option<PodSecurityContext>,
     pub probeConfig: Option<ProbeConfig>,
+    imagePullSecrets: Option<Vec<LocalObjectReference>>,
+    network: Option<PodNetwork>,
+}
+
+/// When the containers of a model are restarted, `Always` if not set
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize, PartialEq, Eq, Default)]
+pub enum RestartPolicy {
+    #[default]
+    Always,
+    OnFailure,
+    Never,
+}
+
+impl RestartPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RestartPolicy::Always => "Always",
+            RestartPolicy::OnFailure => "OnFailure",
+            RestartPolicy::Never => "Never",
+        }
+    }
+}
+
+/// Network artifact a pod is attached to, created on the node before the pod
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct PodNetwork {
+    pub name: String,
+    pub spec: NetworkSpec,
 }
 
 /// Configuration for health probes in the Pod YAML spec.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct ProbeConfig {
+    /// Restarts the container after `failureThreshold` consecutive failures.
     pub liveness: Option<LivenessProbeSpec>,
+    /// Keeps the model from being Running until the probe succeeds.
+    #[serde(default)]
+    pub readiness: Option<ReadinessProbeSpec>,
+}
+
+/// Readiness probe configuration, with the same fields as a liveness probe.
+///
+/// The container is ready after one success and not ready again after
+/// `failureThreshold` consecutive failures.
+pub type ReadinessProbeSpec = LivenessProbeSpec;
+
+/// Key of the liveness probe result in the `state` map of a container reported by NodeAgent.
+pub const LIVENESS_STATE_KEY: &str = "Liveness";
+/// Key of the readiness probe result in the `state` map of a container reported by NodeAgent.
+pub const READINESS_STATE_KEY: &str = "Readiness";
+
+/// Result of the probes of a container, as reported by NodeAgent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeResult {
+    /// Not probed yet, e.g. during `initialDelaySeconds`
+    Pending,
+    Success,
+    Failure,
+}
+
+impl ProbeResult {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProbeResult::Pending => "pending",
+            ProbeResult::Success => "success",
+            ProbeResult::Failure => "failure",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(ProbeResult::Pending),
+            "success" => Some(ProbeResult::Success),
+            "failure" => Some(ProbeResult::Failure),
+            _ => None,
+        }
+    }
 }
 
 /// Default probe period: 10 seconds (aligns with Kubernetes convention).
@@ -129,6 +262,7 @@ pub struct Container {
     securityContext: Option<SecurityContext>,
     stdin: Option<bool>,
     tty: Option<bool>,
+    envFrom: Option<Vec<EnvFromSource>>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
@@ -160,6 +294,19 @@ pub struct EnvVar {
     value: String,
 }
 
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct EnvFromSource {
+    secretRef: Option<LocalObjectReference>,
+    /// ConfigMap artifact, replaced by its values in `env` by ApiServer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    configMapRef: Option<LocalObjectReference>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct LocalObjectReference {
+    name: String,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct ContainerPort {
     containerPort: Option<i32>,
@@ -174,6 +321,52 @@ pub struct ResourceRequirements {
 
 type ResourceList = HashMap<String, String>;
 
+/// Resources a pod needs on its node
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceRequest {
+    /// Thousandths of a CPU core
+    pub cpu_millis: u64,
+    pub memory_mb: u64,
+}
+
+/// Parse a CPU quantity like `500m` or `2`
+pub(crate) fn parse_cpu_millis(quantity: &str) -> Option<u64> {
+    let quantity = quantity.trim();
+    match quantity.strip_suffix('m') {
+        Some(millis) => millis.parse().ok(),
+        None => quantity
+            .parse::<f64>()
+            .ok()
+            .filter(|cores| *cores >= 0.0)
+            .map(|cores| (cores * 1000.0).round() as u64),
+    }
+}
+
+/// Parse a memory quantity like `256Mi`, `1Gi` or `500M` into MiB
+pub(crate) fn parse_memory_mb(quantity: &str) -> Option<u64> {
+    const MIB: f64 = 1024.0 * 1024.0;
+    let quantity = quantity.trim();
+    let units: [(&str, f64); 8] = [
+        ("Ki", 1024.0),
+        ("Mi", MIB),
+        ("Gi", 1024.0 * MIB),
+        ("Ti", 1024.0 * 1024.0 * MIB),
+        ("k", 1e3),
+        ("M", 1e6),
+        ("G", 1e9),
+        ("T", 1e12),
+    ];
+    let (number, factor) = units
+        .iter()
+        .find_map(|(suffix, factor)| quantity.strip_suffix(suffix).map(|n| (n, *factor)))
+        .unwrap_or((quantity, 1.0));
+    number
+        .parse::<f64>()
+        .ok()
+        .filter(|n| *n >= 0.0)
+        .map(|n| (n * factor / MIB).ceil() as u64)
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct SecurityContext {
     privileged: Option<bool>,
@@ -197,9 +390,189 @@ impl PodSpec {
             .map(|container| container.image.as_str())
     }
 
+    /// Returns the images of all containers and init containers, without
+    /// duplicates and empty names.
+    pub fn get_images(&self) -> Vec<&str> {
+        let mut images: Vec<&str> = Vec::new();
+        let init = self.initContainers.iter().flatten();
+        for container in init.chain(self.containers.iter()) {
+            let image = container.image.as_str();
+            if !image.is_empty() && !images.contains(&image) {
+                images.push(image);
+            }
+        }
+        images
+    }
+
     pub fn get_volume(&mut self) -> &Option<Vec<Volume>> {
         &self.volumes
     }
+
+    /// CPU and memory requested by all containers of the PodSpec
+    ///
+    /// A container without `requests` counts with its `limits`. Quantities
+    /// that cannot be parsed count as zero.
+    pub fn get_resource_request(&self) -> ResourceRequest {
+        let mut total = ResourceRequest::default();
+        for container in &self.containers {
+            let Some(resources) = &container.resources else {
+                continue;
+            };
+            let Some(list) = resources.requests.as_ref().or(resources.limits.as_ref()) else {
+                continue;
+            };
+            if let Some(cpu) = list.get("cpu").and_then(|q| parse_cpu_millis(q)) {
+                total.cpu_millis += cpu;
+            }
+            if let Some(memory) = list.get("memory").and_then(|q| parse_memory_mb(q)) {
+                total.memory_mb += memory;
+            }
+        }
+        total
+    }
+
+    /// Restart policy of the containers, `Always` if not set
+    pub fn get_restart_policy(&self) -> RestartPolicy {
+        self.restartPolicy.unwrap_or_default()
+    }
+
+    /// Restarts allowed before a crashing model is Failed, if set
+    pub fn get_max_restarts(&self) -> Option<u32> {
+        self.maxRestarts
+    }
+
+    /// Network the pod joins, if any besides the default one
+    pub fn get_network(&self) -> Option<&PodNetwork> {
+        self.network.as_ref()
+    }
+
+    /// Attach the pod to a Network artifact.
+    ///
+    /// A `host` network only sets `hostNetwork`; other drivers are recorded
+    /// so the runtime of the node creates the network before the pod.
+    pub fn attach_network(&mut self, network: &Network) {
+        let spec = network.get_spec().clone().unwrap_or_default();
+        if spec.get_driver() == NetworkDriver::Host {
+            self.hostNetwork = Some(true);
+            self.network = None;
+            return;
+        }
+        self.hostNetwork = Some(false);
+        self.network = Some(PodNetwork {
+            name: network.get_network_name(),
+            spec,
+        });
+    }
+
+    /// Attach a reference to a Secret artifact.
+    ///
+    /// Registry secrets are added to `imagePullSecrets`, all other secrets
+    /// are exposed to every container through `envFrom`.
+    pub fn add_secret_ref(&mut self, name: &str, registry: bool) {
+        let reference = LocalObjectReference {
+            name: name.to_string(),
+        };
+        if registry {
+            let secrets = self.imagePullSecrets.get_or_insert_with(Vec::new);
+            if !secrets.contains(&reference) {
+                secrets.push(reference);
+            }
+            return;
+        }
+        let source = EnvFromSource {
+            secretRef: Some(reference),
+            configMapRef: None,
+        };
+        for container in self.containers.iter_mut() {
+            let env_from = container.envFrom.get_or_insert_with(Vec::new);
+            if !env_from.contains(&source) {
+                env_from.push(source.clone());
+            }
+        }
+    }
+
+    /// Names of the ConfigMap artifacts referenced in `envFrom`
+    pub fn get_config_map_refs(&self) -> Vec<String> {
+        self.env_from_refs(|source| source.configMapRef.as_ref())
+    }
+
+    /// Names of the Secret artifacts referenced in `envFrom`
+    pub fn get_secret_refs(&self) -> Vec<String> {
+        self.env_from_refs(|source| source.secretRef.as_ref())
+    }
+
+    /// Names of the Secret artifacts in `imagePullSecrets`
+    pub fn get_image_pull_secrets(&self) -> Vec<String> {
+        self.imagePullSecrets
+            .iter()
+            .flatten()
+            .map(|reference| reference.name.clone())
+            .collect()
+    }
+
+    fn env_from_refs(
+        &self,
+        reference: impl Fn(&EnvFromSource) -> Option<&LocalObjectReference>,
+    ) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .containers
+            .iter()
+            .flat_map(|c| c.envFrom.iter().flatten())
+            .filter_map(reference)
+            .map(|reference| reference.name.clone())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Replace the references to a ConfigMap artifact with its values
+    ///
+    /// Every container referencing `name` gets an `env` entry per value,
+    /// sorted by name, unless it sets a variable of that name itself.
+    pub fn inject_config_map(&mut self, name: &str, data: &HashMap<String, String>) {
+        self.inject_env_from(|source| source.configMapRef.as_ref(), name, data);
+    }
+
+    /// Replace the references to a Secret artifact with its decoded values
+    ///
+    /// Works like [`PodSpec::inject_config_map`]. Only done on the node that
+    /// runs the pod, so plain secret values are never written to etcd.
+    pub fn inject_secret(&mut self, name: &str, data: &HashMap<String, String>) {
+        self.inject_env_from(|source| source.secretRef.as_ref(), name, data);
+    }
+
+    fn inject_env_from(
+        &mut self,
+        reference: impl Fn(&EnvFromSource) -> Option<&LocalObjectReference>,
+        name: &str,
+        data: &HashMap<String, String>,
+    ) {
+        let mut keys: Vec<&String> = data.keys().collect();
+        keys.sort();
+        for container in self.containers.iter_mut() {
+            let Some(env_from) = container.envFrom.as_mut() else {
+                continue;
+            };
+            let before = env_from.len();
+            env_from.retain(|source| reference(source).map(|r| r.name.as_str()) != Some(name));
+            if env_from.len() == before {
+                continue;
+            }
+            if env_from.is_empty() {
+                container.envFrom = None;
+            }
+            let env = container.env.get_or_insert_with(Vec::new);
+            for key in &keys {
+                if !env.iter().any(|var| &&var.name == key) {
+                    env.push(EnvVar {
+                        name: key.to_string(),
+                        value: data[*key].clone(),
+                    });
+                }
+            }
+        }
+    }
 }
 
 //Unit Test Cases
@@ -224,6 +597,7 @@ mod tests {
             securityContext: None,
             stdin: None,
             tty: None,
+            envFrom: None,
         };
         let container2 = Container {
             name: String::from("container-2"),
@@ -238,6 +612,7 @@ mod tests {
             securityContext: None,
             stdin: None,
             tty: None,
+            envFrom: None,
         };
         let podspec = PodSpec {
             hostNetwork: None,
@@ -245,13 +620,17 @@ mod tests {
             volumes: None,
             initContainers: None,
             restartPolicy: None,
+            maxRestarts: None,
             terminationGracePeriodSeconds: None,
             hostIPC: None,
             runtimeClassName: None,
             securityContext: None,
             probeConfig: None,
+            imagePullSecrets: None,
+            network: None,
         };
         assert_eq!(podspec.get_image(), Some("image-1"));
+        assert_eq!(podspec.get_images(), vec!["image-1", "image-2"]);
     }
 
     // Negative Test: Validate that `get_image` returns `None` when no containers are present.
@@ -263,11 +642,14 @@ mod tests {
             volumes: None,
             initContainers: None,
             restartPolicy: None,
+            maxRestarts: None,
             terminationGracePeriodSeconds: None,
             hostIPC: None,
             runtimeClassName: None,
             securityContext: None,
             probeConfig: None,
+            imagePullSecrets: None,
+            network: None,
         };
         assert_eq!(podspec.get_image(), None);
     }
@@ -289,6 +671,7 @@ mod tests {
             securityContext: None,
             stdin: None,
             tty: None,
+            envFrom: None,
         };
         let podspec = PodSpec {
             hostNetwork: None,
@@ -296,11 +679,14 @@ mod tests {
             volumes: None,
             initContainers: None,
             restartPolicy: None,
+            maxRestarts: None,
             terminationGracePeriodSeconds: None,
             hostIPC: None,
             runtimeClassName: None,
             securityContext: None,
             probeConfig: None,
+            imagePullSecrets: None,
+            network: None,
         };
         assert_eq!(podspec.get_image(), Some(""));
     }
@@ -327,11 +713,14 @@ mod tests {
             volumes: Some(vec![volume1, volume2]),
             initContainers: None,
             restartPolicy: None,
+            maxRestarts: None,
             terminationGracePeriodSeconds: None,
             hostIPC: None,
             runtimeClassName: None,
             securityContext: None,
             probeConfig: None,
+            imagePullSecrets: None,
+            network: None,
         };
         assert_eq!(
             podspec.get_volume(),
@@ -361,11 +750,14 @@ mod tests {
             volumes: None,
             initContainers: None,
             restartPolicy: None,
+            maxRestarts: None,
             terminationGracePeriodSeconds: None,
             hostIPC: None,
             runtimeClassName: None,
             securityContext: None,
             probeConfig: None,
+            imagePullSecrets: None,
+            network: None,
         };
         assert_eq!(podspec.get_volume(), &None);
     }
@@ -379,11 +771,14 @@ mod tests {
             volumes: Some(vec![]),
             initContainers: None,
             restartPolicy: None,
+            maxRestarts: None,
             terminationGracePeriodSeconds: None,
             hostIPC: None,
             runtimeClassName: None,
             securityContext: None,
             probeConfig: None,
+            imagePullSecrets: None,
+            network: None,
         };
         assert_eq!(podspec.get_volume(), &Some(vec![]));
     }
@@ -403,11 +798,14 @@ mod tests {
             volumes: Some(vec![volume]),
             initContainers: None,
             restartPolicy: None,
+            maxRestarts: None,
             terminationGracePeriodSeconds: None,
             hostIPC: None,
             runtimeClassName: None,
             securityContext: None,
             probeConfig: None,
+            imagePullSecrets: None,
+            network: None,
         };
         assert_eq!(
             podspec.get_volume(),
@@ -437,6 +835,7 @@ mod tests {
             securityContext: None,
             stdin: None,
             tty: None,
+            envFrom: None,
         };
         let podspec = PodSpec {
             hostNetwork: None,
@@ -444,15 +843,119 @@ mod tests {
             volumes: None,
             initContainers: None,
             restartPolicy: None,
+            maxRestarts: None,
             terminationGracePeriodSeconds: None,
             hostIPC: None,
             runtimeClassName: None,
             securityContext: None,
             probeConfig: None,
+            imagePullSecrets: None,
+            network: None,
         };
         assert_eq!(podspec.get_image(), Some("special:image@tag"));
     }
 
+    // Positive Test: Validate that `add_secret_ref` places registry secrets in
+    // imagePullSecrets and other secrets in every container's envFrom.
+    #[test]
+    fn test_add_secret_ref() {
+        let yaml = r#"
+containers:
+  - name: app
+    image: app:1.0
+  - name: sidecar
+    image: sidecar:1.0
+"#;
+        let mut podspec: PodSpec = serde_yaml::from_str(yaml).unwrap();
+
+        podspec.add_secret_ref("registry-cred", true);
+        podspec.add_secret_ref("registry-cred", true);
+        podspec.add_secret_ref("app-env", false);
+
+        assert_eq!(
+            podspec.imagePullSecrets,
+            Some(vec![LocalObjectReference {
+                name: "registry-cred".to_string()
+            }])
+        );
+        for container in &podspec.containers {
+            assert_eq!(
+                container.envFrom,
+                Some(vec![EnvFromSource {
+                    secretRef: Some(LocalObjectReference {
+                        name: "app-env".to_string()
+                    }),
+                    configMapRef: None,
+                }])
+            );
+        }
+        assert_eq!(podspec.get_secret_refs(), vec!["app-env".to_string()]);
+        assert_eq!(
+            podspec.get_image_pull_secrets(),
+            vec!["registry-cred".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_inject_config_map() {
+        let yaml = r#"
+containers:
+  - name: app
+    image: app:1.0
+    env:
+      - name: REGION
+        value: us
+    envFrom:
+      - configMapRef:
+          name: vehicle
+  - name: sidecar
+    image: sidecar:1.0
+"#;
+        let mut podspec: PodSpec = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(podspec.get_config_map_refs(), vec!["vehicle".to_string()]);
+
+        let data = HashMap::from([
+            ("VIN".to_string(), "KMH0".to_string()),
+            ("REGION".to_string(), "eu".to_string()),
+        ]);
+        podspec.inject_config_map("vehicle", &data);
+
+        let app = &podspec.containers[0];
+        assert!(app.envFrom.is_none());
+        let env: Vec<(&str, &str)> = app
+            .env
+            .iter()
+            .flatten()
+            .map(|var| (var.name.as_str(), var.value.as_str()))
+            .collect();
+        assert_eq!(env, vec![("REGION", "us"), ("VIN", "KMH0")]);
+        assert!(podspec.containers[1].env.is_none());
+        assert!(podspec.get_config_map_refs().is_empty());
+    }
+
+    #[test]
+    fn test_attach_network() {
+        let network = |spec: &str| -> Network {
+            serde_yaml::from_str(&format!(
+                "apiVersion: v1\nkind: Network\nmetadata:\n  name: vd-network\nspec:\n{}",
+                spec
+            ))
+            .unwrap()
+        };
+        let mut podspec: PodSpec =
+            serde_yaml::from_str("hostNetwork: true\ncontainers: []").unwrap();
+
+        podspec.attach_network(&network("  driver: bridge\n  subnet: 10.89.0.0/24"));
+        assert_eq!(podspec.hostNetwork, Some(false));
+        let attached = podspec.get_network().unwrap();
+        assert_eq!(attached.name, "vd-network");
+        assert_eq!(attached.spec.get_subnet(), Some("10.89.0.0/24"));
+
+        podspec.attach_network(&network("  driver: host"));
+        assert_eq!(podspec.hostNetwork, Some(true));
+        assert!(podspec.get_network().is_none());
+    }
+
     // Test: probeConfig with all timing fields omitted uses sensible defaults.
     #[test]
     fn test_liveness_probe_spec_defaults_when_fields_omitted() {
@@ -515,4 +1018,124 @@ spec:
         assert!(liveness.tcp.is_some());
         assert_eq!(liveness.tcp.as_ref().unwrap().port, 8080);
     }
+
+    // Test: a readiness probe is parsed next to the liveness probe.
+    #[test]
+    fn test_pod_yaml_with_readiness_probe() {
+        let yaml = r#"
+apiVersion: v1
+kind: Pod
+metadata:
+  name: ready-pod
+spec:
+  containers:
+    - name: app
+      image: myapp:latest
+  probeConfig:
+    readiness:
+      http:
+        path: /ready
+        port: 8080
+      periodSeconds: 2
+"#;
+        let pod = serde_yaml::from_str::<crate::spec::k8s::Pod>(yaml).unwrap();
+        let probe_config = pod.get_probe_config().unwrap();
+        assert!(probe_config.liveness.is_none());
+        let readiness = probe_config.readiness.as_ref().unwrap();
+        assert_eq!(readiness.periodSeconds, 2);
+        assert_eq!(readiness.failureThreshold, 3);
+        assert_eq!(readiness.http.as_ref().unwrap().path, "/ready");
+
+        for result in [
+            ProbeResult::Pending,
+            ProbeResult::Success,
+            ProbeResult::Failure,
+        ] {
+            assert_eq!(ProbeResult::parse(result.as_str()), Some(result));
+        }
+        assert_eq!(ProbeResult::parse("ok"), None);
+    }
+
+    // Test: requests of all containers are summed, limits are used when requests are missing.
+    #[test]
+    fn test_pod_resource_request() {
+        let yaml = r#"
+apiVersion: v1
+kind: Pod
+metadata:
+  name: sized-pod
+spec:
+  containers:
+    - name: app
+      image: myapp:latest
+      resources:
+        requests:
+          cpu: 500m
+          memory: 256Mi
+        limits:
+          cpu: "4"
+    - name: sidecar
+      image: sidecar:latest
+      resources:
+        limits:
+          cpu: "0.25"
+          memory: 1Gi
+    - name: plain
+      image: plain:latest
+"#;
+        let pod = serde_yaml::from_str::<crate::spec::k8s::Pod>(yaml).unwrap();
+        assert_eq!(
+            pod.get_resource_request(),
+            ResourceRequest {
+                cpu_millis: 750,
+                memory_mb: 1280,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_quantities() {
+        assert_eq!(parse_cpu_millis("250m"), Some(250));
+        assert_eq!(parse_cpu_millis("1.5"), Some(1500));
+        assert_eq!(parse_cpu_millis("lots"), None);
+        assert_eq!(parse_memory_mb("512Mi"), Some(512));
+        assert_eq!(parse_memory_mb("2Gi"), Some(2048));
+        assert_eq!(parse_memory_mb("1000M"), Some(954));
+        assert_eq!(parse_memory_mb("1048576"), Some(1));
+        assert_eq!(parse_memory_mb("-1Mi"), None);
+    }
+
+    #[test]
+    fn test_restart_policy_and_max_restarts() {
+        let yaml = r#"
+apiVersion: v1
+kind: Model
+metadata:
+  name: crashy
+spec:
+  containers:
+    - name: app
+      image: app:latest
+  restartPolicy: OnFailure
+  maxRestarts: 2
+"#;
+        let model = serde_yaml::from_str::<Model>(yaml).unwrap();
+        let podspec = model.get_podspec();
+        assert_eq!(podspec.get_restart_policy(), RestartPolicy::OnFailure);
+        assert_eq!(podspec.get_max_restarts(), Some(2));
+
+        // Both fields are kept in the pod sent to NodeAgent
+        let pod = crate::spec::k8s::Pod::from(model);
+        assert_eq!(pod.get_restart_policy(), Some("OnFailure"));
+        let pod_yaml = serde_yaml::to_string(&pod).unwrap();
+        assert!(pod_yaml.contains("restartPolicy: OnFailure"));
+        assert!(pod_yaml.contains("maxRestarts: 2"));
+
+        let unset =
+            serde_yaml::from_str::<Model>(&yaml.replace("  restartPolicy: OnFailure\n", ""))
+                .unwrap()
+                .get_podspec();
+        assert_eq!(unset.get_restart_policy(), RestartPolicy::Always);
+        assert!(serde_yaml::from_str::<Model>(&yaml.replace("OnFailure", "Sometimes")).is_err());
+    }
 }
@@ -7,6 +7,7 @@
 
 pub mod artifact;
 pub mod k8s;
+pub mod namespace;
 
 use std::collections::HashMap;
 
@@ -15,6 +16,17 @@ struct MetaData {
     name: String,
     labels: Option<HashMap<String, String>>,
     annotations: Option<HashMap<String, String>>,
+    /// Namespace of the artifact, the default namespace if not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    namespace: Option<String>,
+}
+
+impl MetaData {
+    fn get_namespace(&self) -> String {
+        self.namespace
+            .clone()
+            .unwrap_or_else(|| namespace::DEFAULT_NAMESPACE.to_string())
+    }
 }
 
 //Unit Test Cases
@@ -37,6 +49,7 @@ mod tests {
                 (String::from("annotation1"), String::from("note1")),
                 (String::from("annotation2"), String::from("note2")),
             ])),
+            namespace: None,
         };
 
         assert_eq!(metadata.name, "TestObject");
@@ -54,6 +67,7 @@ mod tests {
                 String::from("value1"),
             )])),
             annotations: None,
+            namespace: None,
         };
 
         let serialized = serde_json::to_string(&metadata).unwrap();
@@ -87,6 +101,7 @@ mod tests {
                 String::from("value1"),
             )])),
             annotations: None,
+            namespace: None,
         };
 
         let metadata2 = MetaData {
@@ -96,6 +111,7 @@ mod tests {
                 String::from("value1"),
             )])),
             annotations: None,
+            namespace: None,
         };
 
         assert_eq!(metadata1, metadata2);
@@ -108,6 +124,7 @@ mod tests {
             name: String::from("TestObject"),
             labels: None,
             annotations: None,
+            namespace: None,
         };
 
         assert_eq!(metadata.name, "TestObject");
@@ -147,6 +164,7 @@ mod tests {
             name: String::from(""),
             labels: None,
             annotations: None,
+            namespace: None,
         };
 
         assert_eq!(metadata.name, ""); // Verify the name is empty
@@ -164,6 +182,7 @@ mod tests {
                 String::from("value1"),
             )])),
             annotations: None,
+            namespace: None,
         };
 
         // Simulate an invalid serialization scenario by tampering with the data
@@ -0,0 +1,131 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Artifact namespaces
+//!
+//! Artifacts of the default namespace keep the flat etcd layout
+//! (`Scenario/<name>`), so artifacts applied before namespaces existed are
+//! still found. Any other namespace prefixes the key
+//! (`<namespace>/Scenario/<name>`).
+//!
+//! Components that only pass a name around, like the scenario name sent to
+//! StateManager and ActionController, use the qualified name
+//! `<namespace>/<name>`, which is the plain name in the default namespace.
+
+/// Namespace of artifacts without `metadata.namespace`
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+/// etcd key prefix under which the namespaces holding artifacts are registered
+pub const REGISTRY_PREFIX: &str = "Namespace";
+
+/// Maximum length of a namespace, the length of a DNS label
+const MAX_NAMESPACE_LEN: usize = 63;
+
+/// etcd key of an artifact
+pub fn artifact_key(namespace: &str, kind: &str, name: &str) -> String {
+    if is_default(namespace) {
+        format!("{}/{}", kind, name)
+    } else {
+        format!("{}/{}/{}", namespace, kind, name)
+    }
+}
+
+/// etcd key prefix of all artifacts of a kind in a namespace
+pub fn kind_prefix(namespace: &str, kind: &str) -> String {
+    artifact_key(namespace, kind, "")
+}
+
+/// Name of an artifact qualified by its namespace
+pub fn qualified_name(namespace: &str, name: &str) -> String {
+    if is_default(namespace) {
+        name.to_string()
+    } else {
+        format!("{}/{}", namespace, name)
+    }
+}
+
+/// Split a qualified name into namespace and name
+pub fn split_qualified(qualified: &str) -> (&str, &str) {
+    qualified
+        .split_once('/')
+        .unwrap_or((DEFAULT_NAMESPACE, qualified))
+}
+
+/// etcd key of an artifact referenced by its qualified name
+pub fn qualified_key(kind: &str, qualified: &str) -> String {
+    let (namespace, name) = split_qualified(qualified);
+    artifact_key(namespace, kind, name)
+}
+
+/// Whether a namespace is the default namespace
+///
+/// An empty namespace, as sent by clients that do not know namespaces, is
+/// the default namespace.
+pub fn is_default(namespace: &str) -> bool {
+    namespace.is_empty() || namespace == DEFAULT_NAMESPACE
+}
+
+/// Check that a namespace is a lowercase DNS label
+///
+/// A namespace becomes the first segment of etcd keys, so it must not
+/// contain `/` and must not be mistaken for an artifact kind, which starts
+/// with an uppercase letter.
+pub fn validate(namespace: &str) -> Result<(), String> {
+    if namespace.is_empty() || namespace.len() > MAX_NAMESPACE_LEN {
+        return Err(format!(
+            "namespace '{}' must have 1 to {} characters",
+            namespace, MAX_NAMESPACE_LEN
+        ));
+    }
+    let valid_chars = namespace
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if !valid_chars || namespace.starts_with('-') || namespace.ends_with('-') {
+        return Err(format!(
+            "namespace '{}' must consist of lowercase letters, digits and '-', \
+             and start and end with a letter or digit",
+            namespace
+        ));
+    }
+    Ok(())
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_artifact_key() {
+        assert_eq!(artifact_key("default", "Scenario", "a"), "Scenario/a");
+        assert_eq!(artifact_key("", "Scenario", "a"), "Scenario/a");
+        assert_eq!(artifact_key("team-a", "Scenario", "a"), "team-a/Scenario/a");
+        assert_eq!(kind_prefix("team-a", "Package"), "team-a/Package/");
+        assert_eq!(
+            qualified_key("Package", "team-a/core"),
+            "team-a/Package/core"
+        );
+        assert_eq!(qualified_key("Package", "core"), "Package/core");
+    }
+
+    #[test]
+    fn test_qualified_name() {
+        assert_eq!(qualified_name("default", "core"), "core");
+        assert_eq!(qualified_name("team-a", "core"), "team-a/core");
+        assert_eq!(split_qualified("team-a/core"), ("team-a", "core"));
+        assert_eq!(split_qualified("core"), ("default", "core"));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("default").is_ok());
+        assert!(validate("team-a1").is_ok());
+        assert!(validate("").is_err());
+        assert!(validate("Team").is_err());
+        assert!(validate("team/a").is_err());
+        assert!(validate("-team").is_err());
+        assert!(validate(&"a".repeat(64)).is_err());
+    }
+}
@@ -0,0 +1,281 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Startup phase waiting for the dependencies of a component
+//!
+//! A component started before etcd or the servers it calls fails its first
+//! calls and crash-loops. [`wait_for_dependencies`] runs first in `main` and
+//! probes every dependency until it answers, doubling the delay between two
+//! probes from `initial_delay_ms` up to `max_delay_ms`. Each failed probe is
+//! logged with the dependency, its address and how long it has been waited
+//! on. After `timeout_secs` the component starts anyway, as it did before.
+//!
+//! Dependencies are named in the `startup` section of settings.yaml, else
+//! the defaults of the component apply:
+//! * `etcd` - the RocksDB service reports healthy
+//! * `apiserver`, `statemanager`, `actioncontroller`, `filtergateway`,
+//!   `monitoringserver`, `policymanager` - the gRPC server of the component
+//!   accepts a connection
+//! * `tcp://host:port` - a TCP connection can be opened
+//! * `grpc://host:port` or `http://host:port` - a gRPC channel can be opened
+//!
+//! ```yaml
+//! startup:
+//!   timeout_secs: 120
+//!   dependencies:
+//!     filtergateway: [etcd, actioncontroller, "tcp://10.0.0.5:7400"]
+//! ```
+
+use crate::logd;
+use crate::rpc::RpcClient;
+use crate::setting::StartupSettings;
+use std::time::{Duration, Instant};
+
+/// Something a component needs before it starts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dependency {
+    Etcd,
+    Tcp(String),
+    /// gRPC server by name and URL
+    Grpc {
+        name: String,
+        url: String,
+    },
+}
+
+impl Dependency {
+    /// Parse a dependency of the `startup` settings
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        if let Some(addr) = spec.strip_prefix("tcp://") {
+            return Ok(Dependency::Tcp(addr.to_string()));
+        }
+        if let Some(addr) = spec.strip_prefix("grpc://") {
+            return Ok(Dependency::Grpc {
+                name: addr.to_string(),
+                url: format!("http://{}", addr),
+            });
+        }
+        if let Some(addr) = spec.strip_prefix("http://") {
+            return Ok(Dependency::Grpc {
+                name: addr.to_string(),
+                url: spec.to_string(),
+            });
+        }
+        let url = match spec {
+            "etcd" => return Ok(Dependency::Etcd),
+            "apiserver" => crate::apiserver::connect_grpc_server(),
+            "statemanager" => crate::statemanager::connect_server(),
+            "actioncontroller" => crate::actioncontroller::connect_server(),
+            "filtergateway" => crate::filtergateway::connect_server(),
+            "monitoringserver" => crate::monitoringserver::connect_server(),
+            "policymanager" => crate::policymanager::connect_server(),
+            _ => return Err(format!("Unknown startup dependency '{}'", spec)),
+        };
+        Ok(Dependency::Grpc {
+            name: spec.to_string(),
+            url,
+        })
+    }
+
+    /// Name and address used in log messages
+    pub fn describe(&self) -> String {
+        match self {
+            Dependency::Etcd => format!("etcd ({})", crate::etcd::key_prefix()),
+            Dependency::Tcp(addr) => format!("tcp {}", addr),
+            Dependency::Grpc { name, url } => format!("{} ({})", name, url),
+        }
+    }
+
+    /// Check once whether the dependency answers within `timeout`
+    async fn probe(&self, timeout: Duration) -> Result<(), String> {
+        let probe = async {
+            match self {
+                Dependency::Etcd => match crate::etcd::health_check().await {
+                    Ok(true) => Ok(()),
+                    Ok(false) => Err("not healthy".to_string()),
+                    Err(e) => Err(e),
+                },
+                Dependency::Tcp(addr) => tokio::net::TcpStream::connect(addr.as_str())
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| e.to_string()),
+                Dependency::Grpc { name, url } => RpcClient::new(name, url.as_str())
+                    .channel()
+                    .await
+                    .map(|_| ())
+                    .map_err(|status| status.message().to_string()),
+            }
+        };
+        tokio::time::timeout(timeout, probe)
+            .await
+            .unwrap_or_else(|_| Err(format!("no answer within {:?}", timeout)))
+    }
+}
+
+/// Dependencies of a component without `startup` settings
+pub fn default_dependencies(component: &str) -> &'static [&'static str] {
+    match component {
+        "apiserver" | "statemanager" | "monitoringserver" | "policymanager" | "settingsservice" => {
+            &["etcd"]
+        }
+        "actioncontroller" => &["etcd", "statemanager"],
+        "filtergateway" => &["etcd", "actioncontroller"],
+        _ => &[],
+    }
+}
+
+/// Dependencies of a component by its `startup` settings or its defaults
+pub fn dependencies(
+    component: &str,
+    settings: &StartupSettings,
+) -> Result<Vec<Dependency>, String> {
+    match settings.dependencies.get(component) {
+        Some(specs) => specs.iter().map(|spec| Dependency::parse(spec)).collect(),
+        None => default_dependencies(component)
+            .iter()
+            .map(|spec| Dependency::parse(spec))
+            .collect(),
+    }
+}
+
+/// Delay before probe `attempt` again, counted from 0
+fn delay(settings: &StartupSettings, attempt: u32) -> Duration {
+    Duration::from_millis(
+        settings
+            .initial_delay_ms
+            .saturating_mul(2u64.saturating_pow(attempt))
+            .min(settings.max_delay_ms.max(settings.initial_delay_ms)),
+    )
+}
+
+/// Wait until every dependency of a component answers
+///
+/// ### Parameters
+/// * `component: &str` - name of the component, e.g. `statemanager`
+/// ### Returns
+/// * `Err(String)` - the dependencies still missing after `timeout_secs`,
+///   or an unknown dependency in the settings
+pub async fn wait_for_dependencies(component: &str) -> Result<(), String> {
+    let settings = crate::setting::get_config().startup.clone();
+    let dependencies = dependencies(component, &settings)?;
+    wait_for(component, &dependencies, &settings).await
+}
+
+/// Wait until the given dependencies answer, see [`wait_for_dependencies`]
+pub async fn wait_for(
+    component: &str,
+    dependencies: &[Dependency],
+    settings: &StartupSettings,
+) -> Result<(), String> {
+    let start = Instant::now();
+    let timeout = (settings.timeout_secs > 0).then(|| Duration::from_secs(settings.timeout_secs));
+    let probe_timeout = Duration::from_millis(settings.max_delay_ms.max(1_000));
+
+    for dependency in dependencies {
+        let waited_before = start.elapsed();
+        let mut attempt = 0;
+        loop {
+            match dependency.probe(probe_timeout).await {
+                Ok(()) => {
+                    if attempt > 0 {
+                        logd!(
+                            3,
+                            "startup: {} is available for {} after {:.1}s",
+                            dependency.describe(),
+                            component,
+                            (start.elapsed() - waited_before).as_secs_f64()
+                        );
+                    }
+                    break;
+                }
+                Err(e) => {
+                    let elapsed = start.elapsed();
+                    if timeout.is_some_and(|timeout| elapsed >= timeout) {
+                        let missing: Vec<String> = dependencies
+                            .iter()
+                            .skip_while(|d| *d != dependency)
+                            .map(|d| d.describe())
+                            .collect();
+                        let message = format!(
+                            "gave up waiting after {}s for {}: {}",
+                            settings.timeout_secs,
+                            missing.join(", "),
+                            e
+                        );
+                        logd!(5, "startup: {} {}", component, message);
+                        return Err(message);
+                    }
+                    let wait = delay(settings, attempt);
+                    logd!(
+                        4,
+                        "startup: {} is waiting for {}, {:.1}s so far, next try in {:.1}s: {}",
+                        component,
+                        dependency.describe(),
+                        (elapsed - waited_before).as_secs_f64(),
+                        wait.as_secs_f64(),
+                        e
+                    );
+                    tokio::time::sleep(wait).await;
+                    attempt = attempt.saturating_add(1);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dependencies() {
+        assert_eq!(Dependency::parse("etcd"), Ok(Dependency::Etcd));
+        assert_eq!(
+            Dependency::parse("tcp://10.0.0.5:7400"),
+            Ok(Dependency::Tcp("10.0.0.5:7400".to_string()))
+        );
+        assert_eq!(
+            Dependency::parse("grpc://10.0.0.5:47098"),
+            Ok(Dependency::Grpc {
+                name: "10.0.0.5:47098".to_string(),
+                url: "http://10.0.0.5:47098".to_string()
+            })
+        );
+        assert!(matches!(
+            Dependency::parse("statemanager"),
+            Ok(Dependency::Grpc { name, .. }) if name == "statemanager"
+        ));
+        assert!(Dependency::parse("zookeeper").is_err());
+
+        let settings = StartupSettings::default();
+        assert_eq!(
+            dependencies("apiserver", &settings),
+            Ok(vec![Dependency::Etcd])
+        );
+        assert_eq!(dependencies("rocksdbservice", &settings), Ok(vec![]));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_gives_up_after_timeout() {
+        // Nothing listens on the discard port of the loopback interface
+        let settings = StartupSettings {
+            timeout_secs: 1,
+            initial_delay_ms: 100,
+            max_delay_ms: 200,
+            dependencies: Default::default(),
+        };
+        let missing = Dependency::Tcp("127.0.0.1:9".to_string());
+
+        let error = wait_for("test", &[missing], &settings).await.unwrap_err();
+        assert!(error.contains("tcp 127.0.0.1:9"), "{}", error);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let present = Dependency::Tcp(listener.local_addr().unwrap().to_string());
+        assert_eq!(wait_for("test", &[present], &settings).await, Ok(()));
+    }
+}
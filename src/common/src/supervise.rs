@@ -0,0 +1,196 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Supervision of the background tasks of a component
+//!
+//! A panic in a task started with `tokio::spawn` only ends that task, and
+//! nobody notices until what it did is missed. [`spawn`] runs the task under a
+//! supervisor instead: a panic is logged with the component and task name and
+//! the task is started again after [`RestartPolicy::backoff`]. A task that
+//! panics more than [`RestartPolicy::max_restarts`] times within
+//! [`RestartPolicy::window`] is given up and reported on the event bus as
+//! `TaskFailed`. A task that returns is done and not restarted.
+//!
+//! ```ignore
+//! common::supervise::spawn("heartbeat", RestartPolicy::default(), move || {
+//!     heartbeat::run(node_id.clone(), interval)
+//! });
+//! ```
+
+use crate::eventbus::{Event, EventKind};
+use crate::logd;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+/// How often a panicking task is started again
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RestartPolicy {
+    /// Restarts allowed within `window`
+    pub max_restarts: usize,
+    pub window: Duration,
+    /// Delay before a restart
+    pub backoff: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: 5,
+            window: Duration::from_secs(60),
+            backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Recent restarts of one task
+#[derive(Debug)]
+pub struct Restarts {
+    policy: RestartPolicy,
+    times: VecDeque<Instant>,
+}
+
+impl Restarts {
+    pub fn new(policy: RestartPolicy) -> Self {
+        Self {
+            policy,
+            times: VecDeque::new(),
+        }
+    }
+
+    /// Record a restart at `now`, `false` if the policy allows no more
+    pub fn record(&mut self, now: Instant) -> bool {
+        while self
+            .times
+            .front()
+            .is_some_and(|time| now.duration_since(*time) >= self.policy.window)
+        {
+            self.times.pop_front();
+        }
+        if self.times.len() >= self.policy.max_restarts {
+            return false;
+        }
+        self.times.push_back(now);
+        true
+    }
+}
+
+/// Message of a panic payload
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+/// Run a task under a supervisor that restarts it after a panic
+///
+/// ### Parameters
+/// * `name: &str` - name of the task in logs and events
+/// * `policy: RestartPolicy` - restarts allowed before the task is given up
+/// * `task: F` - creates the future of the task, once per start
+/// ### Returns
+/// * `JoinHandle<()>` - handle of the supervisor, done when the task returns
+///   or is given up
+pub fn spawn<F, Fut>(name: &str, policy: RestartPolicy, mut task: F) -> JoinHandle<()>
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let name = name.to_string();
+    tokio::spawn(async move {
+        let component = crate::health::report().component;
+        let mut restarts = Restarts::new(policy);
+        loop {
+            let error = match tokio::spawn(task()).await {
+                Ok(()) => return,
+                Err(e) if e.is_panic() => e.into_panic(),
+                Err(_) => return,
+            };
+            let message = panic_message(error.as_ref()).to_string();
+            logd!(5, "[{}] Task '{}' panicked: {}", component, name, message);
+
+            if !restarts.record(Instant::now()) {
+                let message = format!(
+                    "Task '{}' panicked more than {} times within {}s, last: {}",
+                    name,
+                    policy.max_restarts,
+                    policy.window.as_secs(),
+                    message
+                );
+                logd!(5, "[{}] {}, giving up", component, message);
+                crate::eventbus::publish(
+                    Event::new(EventKind::TaskFailed, &component, &name, message)
+                        .with_attribute("restarts", &policy.max_restarts.to_string()),
+                );
+                return;
+            }
+            tokio::time::sleep(policy.backoff).await;
+            logd!(3, "[{}] Restarting task '{}'", component, name);
+        }
+    })
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_restarts_within_window() {
+        let policy = RestartPolicy {
+            max_restarts: 2,
+            window: Duration::from_secs(10),
+            backoff: Duration::ZERO,
+        };
+        let start = Instant::now();
+        let mut restarts = Restarts::new(policy);
+        assert!(restarts.record(start));
+        assert!(restarts.record(start + Duration::from_secs(1)));
+        assert!(!restarts.record(start + Duration::from_secs(2)));
+        // The first restart left the window
+        assert!(restarts.record(start + Duration::from_secs(10)));
+        assert!(!restarts.record(start + Duration::from_secs(10)));
+    }
+
+    #[tokio::test]
+    async fn test_spawn_restarts_panicking_task() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let policy = RestartPolicy {
+            max_restarts: 2,
+            window: Duration::from_secs(60),
+            backoff: Duration::ZERO,
+        };
+        let counter = runs.clone();
+        let supervisor = spawn("panicking", policy, move || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                panic!("boom");
+            }
+        });
+        supervisor.await.unwrap();
+        // First run and two restarts, then given up
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+
+        let counter = runs.clone();
+        spawn("returning", policy, move || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 4);
+    }
+}
@@ -0,0 +1,469 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Correlation of the work done for one request across components
+//!
+//! A trace is started where a request enters Pullpiri: a REST call to
+//! ApiServer, or a scenario triggered by vehicle data in FilterGateway. Its
+//! trace ID follows the request
+//! * between components as the W3C `traceparent` gRPC metadata or HTTP
+//!   header, see [`request`] and [`GrpcTraceLayer`]
+//! * through StateManager's queues in the `trace_id` of `StateChange`
+//! * into every `logd!` line written while handling it
+//!
+//! Work for a trace runs in spans created by [`in_span`]. With
+//! `trace.otlp_endpoint` set in settings.yaml and the `otlp` feature
+//! enabled, finished spans are exported over OTLP/HTTP:
+//!
+//! ```yaml
+//! trace:
+//!   otlp_endpoint: http://10.0.0.5:4318   # spans not exported if empty
+//! ```
+
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::BuildHasher;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::task::{Context, Poll};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tonic::codegen::http;
+use tonic::metadata::{MetadataMap, MetadataValue};
+
+/// W3C trace context header, used as gRPC metadata key as well
+pub const TRACEPARENT: &str = "traceparent";
+/// Response header of the REST APIs with the trace ID of the request
+pub const TRACE_ID_HEADER: &str = "x-trace-id";
+
+static EXPORTER: OnceLock<mpsc::Sender<FinishedSpan>> = OnceLock::new();
+
+tokio::task_local! {
+    static CONTEXT: TraceContext;
+}
+
+/// Trace and span a piece of work belongs to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    /// 32 lowercase hex digits, shared by all spans of the trace
+    pub trace_id: String,
+    /// 16 lowercase hex digits, empty if only the trace is known
+    pub span_id: String,
+}
+
+impl TraceContext {
+    /// Start a new trace
+    pub fn new_root() -> Self {
+        Self {
+            trace_id: format!("{:016x}{:016x}", random_u64(), random_u64()),
+            span_id: String::new(),
+        }
+    }
+
+    /// Continue a trace of which only the ID is known, e.g. from a `StateChange`
+    pub fn from_trace_id(trace_id: &str) -> Option<Self> {
+        is_hex_id(trace_id, 32).then(|| Self {
+            trace_id: trace_id.to_string(),
+            span_id: String::new(),
+        })
+    }
+
+    /// Parse a `traceparent` value, e.g.
+    /// `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`
+    pub fn from_traceparent(value: &str) -> Option<Self> {
+        let mut parts = value.trim().split('-');
+        let (version, trace_id, span_id) = (parts.next()?, parts.next()?, parts.next()?);
+        parts.next()?;
+        if !is_hex_id(version, 2) || !is_hex_id(trace_id, 32) || !is_hex_id(span_id, 16) {
+            return None;
+        }
+        Some(Self {
+            trace_id: trace_id.to_string(),
+            span_id: span_id.to_string(),
+        })
+    }
+
+    /// `traceparent` value of the context
+    pub fn to_traceparent(&self) -> String {
+        let span_id = if self.span_id.is_empty() {
+            "0000000000000001"
+        } else {
+            &self.span_id
+        };
+        format!("00-{}-{}-01", self.trace_id, span_id)
+    }
+
+    fn child(&self) -> Self {
+        Self {
+            trace_id: self.trace_id.clone(),
+            span_id: format!("{:016x}", random_u64()),
+        }
+    }
+}
+
+/// Valid W3C IDs are hex and not all zero
+fn is_hex_id(id: &str, len: usize) -> bool {
+    id.len() == len
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+        && (len == 2 || id.bytes().any(|b| b != b'0'))
+}
+
+fn random_u64() -> u64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    RandomState::new().hash_one((count, SystemTime::now()))
+}
+
+fn unix_time_ns() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64
+}
+
+/// Context of the running task, if it works for a trace
+pub fn current() -> Option<TraceContext> {
+    CONTEXT.try_with(|context| context.clone()).ok()
+}
+
+/// Trace ID of the running task, if it works for a trace
+pub fn current_trace_id() -> Option<String> {
+    CONTEXT.try_with(|context| context.trace_id.clone()).ok()
+}
+
+/// Run `future` in a new span named `name`
+///
+/// The span is a child of `parent`, of the current span without a parent,
+/// or starts a new trace if there is neither. Tasks spawned by `future`
+/// are not part of the span.
+pub async fn in_span<F: Future>(name: &str, parent: Option<TraceContext>, future: F) -> F::Output {
+    let parent = parent
+        .or_else(current)
+        .unwrap_or_else(TraceContext::new_root);
+    let context = parent.child();
+    let start_unix_ns = unix_time_ns();
+    let output = CONTEXT.scope(context.clone(), future).await;
+
+    if let Some(exporter) = EXPORTER.get() {
+        let _ = exporter.try_send(FinishedSpan {
+            trace_id: context.trace_id,
+            span_id: context.span_id,
+            parent_span_id: parent.span_id,
+            name: name.to_string(),
+            start_unix_ns,
+            end_unix_ns: unix_time_ns(),
+        });
+    }
+    output
+}
+
+/// Add the `traceparent` of the current span to gRPC metadata
+pub fn inject(metadata: &mut MetadataMap) {
+    if let Some(context) = current() {
+        if let Ok(value) = MetadataValue::try_from(context.to_traceparent()) {
+            metadata.insert(TRACEPARENT, value);
+        }
+    }
+}
+
+/// Trace context sent by the caller of a gRPC request
+pub fn extract(metadata: &MetadataMap) -> Option<TraceContext> {
+    metadata
+        .get(TRACEPARENT)
+        .and_then(|value| value.to_str().ok())
+        .and_then(TraceContext::from_traceparent)
+}
+
+/// gRPC request carrying the current trace context
+pub fn request<T>(message: T) -> tonic::Request<T> {
+    let mut request = tonic::Request::new(message);
+    inject(request.metadata_mut());
+    request
+}
+
+/// Layer of gRPC servers running every call in a span
+///
+/// The span continues the `traceparent` metadata of the caller and is named
+/// after the called method, e.g.
+/// `/actioncontroller.ActionControllerConnection/TriggerAction`.
+///
+/// ```ignore
+/// Server::builder().layer(common::trace::GrpcTraceLayer).add_service(..)
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GrpcTraceLayer;
+
+impl<S> tower::Layer<S> for GrpcTraceLayer {
+    type Service = GrpcTraceService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        GrpcTraceService { inner }
+    }
+}
+
+/// Service of [`GrpcTraceLayer`]
+#[derive(Debug, Clone)]
+pub struct GrpcTraceService<S> {
+    inner: S,
+}
+
+impl<S, B> tower::Service<http::Request<B>> for GrpcTraceService<S>
+where
+    S: tower::Service<http::Request<B>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let parent = request
+            .headers()
+            .get(TRACEPARENT)
+            .and_then(|value| value.to_str().ok())
+            .and_then(TraceContext::from_traceparent);
+        let name = request.uri().path().to_string();
+        let call = self.inner.call(request);
+        Box::pin(async move { in_span(&name, parent, call).await })
+    }
+}
+
+/// Span as exported over OTLP
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinishedSpan {
+    pub trace_id: String,
+    pub span_id: String,
+    /// Empty for the first span of a trace
+    pub parent_span_id: String,
+    pub name: String,
+    pub start_unix_ns: u64,
+    pub end_unix_ns: u64,
+}
+
+/// Export the finished spans of this process if an OTLP endpoint is configured
+///
+/// # Arguments
+/// * `service` - `service.name` of the exported spans, e.g. `statemanager`
+///
+/// Must be called within a tokio runtime.
+pub fn init_exporter(service: &str) {
+    let endpoint = crate::setting::get_config().trace.otlp_endpoint.clone();
+    if endpoint.is_empty() {
+        return;
+    }
+    #[cfg(feature = "otlp")]
+    {
+        let (tx, rx) = mpsc::channel(otlp::EXPORT_QUEUE);
+        if EXPORTER.set(tx).is_ok() {
+            tokio::spawn(otlp::export(service.to_string(), endpoint, rx));
+        }
+    }
+    #[cfg(not(feature = "otlp"))]
+    crate::logd!(
+        4,
+        "Not exporting spans of {} to {}, built without the otlp feature",
+        service,
+        endpoint
+    );
+}
+
+#[cfg(feature = "otlp")]
+mod otlp {
+    use super::FinishedSpan;
+    use crate::logd;
+    use serde_json::{json, Value};
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    /// Finished spans waiting for export, dropped when full
+    pub(super) const EXPORT_QUEUE: usize = 2048;
+    /// Spans sent in one request at most
+    const BATCH_SIZE: usize = 256;
+    /// Time a finished span waits for others to fill a batch
+    const BATCH_DELAY: Duration = Duration::from_secs(1);
+
+    /// OTLP/HTTP JSON request with the spans of one service
+    pub(super) fn traces_request(service: &str, spans: &[FinishedSpan]) -> Value {
+        let spans: Vec<Value> = spans
+            .iter()
+            .map(|span| {
+                let mut value = json!({
+                    "traceId": span.trace_id,
+                    "spanId": span.span_id,
+                    "name": span.name,
+                    "kind": 1,
+                    "startTimeUnixNano": span.start_unix_ns.to_string(),
+                    "endTimeUnixNano": span.end_unix_ns.to_string(),
+                });
+                if !span.parent_span_id.is_empty() {
+                    value["parentSpanId"] = json!(span.parent_span_id);
+                }
+                value
+            })
+            .collect();
+        json!({
+            "resourceSpans": [{
+                "resource": {
+                    "attributes": [{"key": "service.name", "value": {"stringValue": service}}]
+                },
+                "scopeSpans": [{"scope": {"name": "pullpiri"}, "spans": spans}]
+            }]
+        })
+    }
+
+    /// Send batches of finished spans to `{endpoint}/v1/traces`
+    pub(super) async fn export(
+        service: String,
+        endpoint: String,
+        mut rx: mpsc::Receiver<FinishedSpan>,
+    ) {
+        let url = format!("{}/v1/traces", endpoint.trim_end_matches('/'));
+        let client = reqwest::Client::new();
+        while let Some(first) = rx.recv().await {
+            let mut batch = vec![first];
+            let deadline = tokio::time::Instant::now() + BATCH_DELAY;
+            while batch.len() < BATCH_SIZE {
+                match tokio::time::timeout_at(deadline, rx.recv()).await {
+                    Ok(Some(span)) => batch.push(span),
+                    _ => break,
+                }
+            }
+
+            let body = traces_request(&service, &batch).to_string();
+            let result = client
+                .post(&url)
+                .header("content-type", "application/json")
+                .body(body)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = result {
+                logd!(
+                    4,
+                    "Failed to export {} spans to {}: {}",
+                    batch.len(),
+                    url,
+                    e
+                );
+            }
+        }
+    }
+}
+
+#[cfg(feature = "axum")]
+mod rest {
+    use super::{in_span, TraceContext, TRACEPARENT, TRACE_ID_HEADER};
+    use axum::{extract::Request, http::HeaderValue, middleware::Next, response::Response};
+
+    /// Run each REST request in a span and return its trace ID
+    ///
+    /// A `traceparent` header of the client is continued, otherwise the
+    /// request starts a new trace. The response carries the trace ID in the
+    /// `x-trace-id` header.
+    pub async fn trace_requests(request: Request, next: Next) -> Response {
+        let parent = request
+            .headers()
+            .get(TRACEPARENT)
+            .and_then(|value| value.to_str().ok())
+            .and_then(TraceContext::from_traceparent)
+            .unwrap_or_else(TraceContext::new_root);
+        let trace_id = parent.trace_id.clone();
+        let name = format!("{} {}", request.method(), request.uri().path());
+
+        let mut response = in_span(&name, Some(parent), next.run(request)).await;
+        if let Ok(value) = HeaderValue::from_str(&trace_id) {
+            response.headers_mut().insert(TRACE_ID_HEADER, value);
+        }
+        response
+    }
+}
+
+#[cfg(feature = "axum")]
+pub use rest::trace_requests;
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traceparent_round_trip() {
+        let value = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let context = TraceContext::from_traceparent(value).unwrap();
+        assert_eq!(context.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(context.span_id, "00f067aa0ba902b7");
+        assert_eq!(context.to_traceparent(), value);
+
+        for invalid in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+        ] {
+            assert!(
+                TraceContext::from_traceparent(invalid).is_none(),
+                "{}",
+                invalid
+            );
+        }
+
+        let root = TraceContext::new_root();
+        assert!(TraceContext::from_trace_id(&root.trace_id).is_some());
+        assert_ne!(root.trace_id, TraceContext::new_root().trace_id);
+        assert!(TraceContext::from_traceparent(&root.to_traceparent()).is_some());
+        assert!(TraceContext::from_trace_id("").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_spans_share_the_trace() {
+        assert!(current().is_none());
+        let parent = TraceContext::new_root();
+
+        let (outer, inner, metadata) = in_span("outer", Some(parent.clone()), async {
+            let outer = current().unwrap();
+            let inner = in_span("inner", None, async { current().unwrap() }).await;
+            (outer, inner, request(()).metadata().clone())
+        })
+        .await;
+
+        assert_eq!(outer.trace_id, parent.trace_id);
+        assert_eq!(inner.trace_id, parent.trace_id);
+        assert_ne!(inner.span_id, outer.span_id);
+        assert_eq!(extract(&metadata), Some(outer));
+        assert!(current().is_none());
+        assert!(extract(request(()).metadata()).is_none());
+    }
+
+    #[cfg(feature = "otlp")]
+    #[test]
+    fn test_otlp_traces_request() {
+        let span = FinishedSpan {
+            trace_id: "4bf92f3577b34da6a3ce929d0e0e4736".to_string(),
+            span_id: "00f067aa0ba902b7".to_string(),
+            parent_span_id: String::new(),
+            name: "POST /api/artifact".to_string(),
+            start_unix_ns: 1,
+            end_unix_ns: 2,
+        };
+        let request = otlp::traces_request("apiserver", &[span]);
+        let resource = &request["resourceSpans"][0];
+        assert_eq!(
+            resource["resource"]["attributes"][0]["value"]["stringValue"],
+            "apiserver"
+        );
+        let span = &resource["scopeSpans"][0]["spans"][0];
+        assert_eq!(span["traceId"], "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(span["endTimeUnixNano"], "2");
+        assert!(span.get("parentSpanId").is_none());
+    }
+}
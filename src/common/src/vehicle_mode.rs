@@ -0,0 +1,144 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Vehicle modes gating scenario actions
+//!
+//! FilterGateway tracks the ignition and motion of the vehicle from DDS
+//! signals and serves them with `GetVehicleMode`. A scenario listing
+//! `allowedModes` only runs while one of them is active:
+//!
+//! ```yaml
+//! spec:
+//!   action: update
+//!   target: infotainment
+//!   allowedModes: [parked, ignition-off]
+//!   modeWaitSeconds: 3600
+//! ```
+//!
+//! Without `modeWaitSeconds` the action is rejected right away if no listed
+//! mode is active, otherwise it is deferred until one becomes active or the
+//! wait runs out. A mode whose signal was not received yet is not active.
+
+use crate::filtergateway::{
+    filter_gateway_connection_client::FilterGatewayConnectionClient, Ignition, Motion, VehicleMode,
+    VehicleModeRequest,
+};
+use crate::rpc::RpcClient;
+
+pub const PARKED: &str = "parked";
+pub const DRIVING: &str = "driving";
+pub const IGNITION_OFF: &str = "ignition-off";
+pub const IGNITION_ON: &str = "ignition-on";
+
+/// Names of the modes a scenario can list in `allowedModes`
+pub const MODES: &[&str] = &[PARKED, DRIVING, IGNITION_OFF, IGNITION_ON];
+
+/// Modes active in a vehicle mode, e.g. `["parked", "ignition-off"]`
+pub fn active_modes(mode: &VehicleMode) -> Vec<&'static str> {
+    let motion = match mode.motion() {
+        Motion::Parked => Some(PARKED),
+        Motion::Driving => Some(DRIVING),
+        Motion::Unknown => None,
+    };
+    let ignition = match mode.ignition() {
+        Ignition::Off => Some(IGNITION_OFF),
+        Ignition::On => Some(IGNITION_ON),
+        Ignition::Unknown => None,
+    };
+    motion.into_iter().chain(ignition).collect()
+}
+
+/// `true` if no mode is required or one of the `allowed` modes is active
+pub fn is_allowed(allowed: &[String], mode: &VehicleMode) -> bool {
+    let active = active_modes(mode);
+    allowed.is_empty() || allowed.iter().any(|m| active.contains(&m.as_str()))
+}
+
+/// Describe the active modes for messages, `unknown` if there is none
+pub fn describe(mode: &VehicleMode) -> String {
+    let active = active_modes(mode);
+    if active.is_empty() {
+        "unknown".to_string()
+    } else {
+        active.join(", ")
+    }
+}
+
+/// Check that every mode of `allowedModes` is known
+pub fn validate(modes: &[String]) -> Result<(), String> {
+    match modes.iter().find(|m| !MODES.contains(&m.as_str())) {
+        Some(unknown) => Err(format!(
+            "Unknown vehicle mode '{}', expected one of {}",
+            unknown,
+            MODES.join(", ")
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Current vehicle mode, asked from FilterGateway
+pub async fn get() -> Result<VehicleMode, String> {
+    RpcClient::new("FilterGateway", crate::filtergateway::connect_server())
+        .call(|channel| async move {
+            FilterGatewayConnectionClient::new(channel)
+                .get_vehicle_mode(crate::trace::request(VehicleModeRequest {}))
+                .await
+        })
+        .await
+        .map(|response| response.into_inner())
+        .map_err(|e| format!("FilterGateway gRPC error: {}", e.message()))
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mode(ignition: Ignition, motion: Motion) -> VehicleMode {
+        VehicleMode {
+            ignition: ignition as i32,
+            motion: motion as i32,
+            changed_ns: 1,
+        }
+    }
+
+    fn modes(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_active_modes() {
+        assert_eq!(
+            active_modes(&mode(Ignition::Off, Motion::Parked)),
+            vec![PARKED, IGNITION_OFF]
+        );
+        assert_eq!(
+            active_modes(&mode(Ignition::On, Motion::Driving)),
+            vec![DRIVING, IGNITION_ON]
+        );
+        assert!(active_modes(&VehicleMode::default()).is_empty());
+        assert_eq!(describe(&VehicleMode::default()), "unknown");
+    }
+
+    #[test]
+    fn test_is_allowed() {
+        let parked = mode(Ignition::On, Motion::Parked);
+        let driving = mode(Ignition::On, Motion::Driving);
+        let allowed = modes(&[PARKED, IGNITION_OFF]);
+        assert!(is_allowed(&allowed, &parked));
+        assert!(!is_allowed(&allowed, &driving));
+        assert!(!is_allowed(&allowed, &VehicleMode::default()));
+        assert!(is_allowed(&[], &driving));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&modes(&[PARKED, IGNITION_ON])).is_ok());
+        assert_eq!(
+            validate(&modes(&["parked", "charging"])).unwrap_err(),
+            "Unknown vehicle mode 'charging', expected one of parked, driving, ignition-off, ignition-on"
+        );
+    }
+}
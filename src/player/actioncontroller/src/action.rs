@@ -0,0 +1,351 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Scenario action handlers
+//!
+//! The `action` of a scenario names a handler of the [`ActionRegistry`] of
+//! ActionControllerManager. Each handler declares the `actionParams` it
+//! takes, which are checked before it runs. The built-in handlers are the
+//! workload actions `launch`, `terminate`, `update` and `rollback`, which run
+//! on the models of the target package, and `notify`, which publishes a
+//! ScenarioNotification event on the event bus:
+//!
+//! ```yaml
+//! spec:
+//!   action: notify
+//!   target: battery-monitor
+//!   actionParams:
+//!     message: Battery below 10%
+//!     severity: warning
+//! ```
+//!
+//! Other verbs are added by registering an [`ActionHandler`] with
+//! `ActionControllerManager::register_action`.
+use std::{collections::HashMap, future::Future, pin::Pin, sync::Arc};
+
+use crate::manager::ActionControllerManager;
+use common::{
+    eventbus::{Event, EventKind},
+    logd,
+    spec::artifact::{Artifact, Package, Scenario},
+    Result,
+};
+
+/// Future returned by [`ActionHandler::run`]
+pub type ActionFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// Schema of one string parameter of an action
+#[derive(Debug, Clone)]
+pub struct ParamSpec {
+    pub name: &'static str,
+    pub required: bool,
+    /// Allowed values of the parameter, any value if empty
+    pub one_of: &'static [&'static str],
+}
+
+/// `actionParams` of a scenario, checked against the schema of its handler
+#[derive(Debug, Clone, Default)]
+pub struct ActionParams {
+    values: HashMap<String, serde_yaml::Value>,
+}
+
+impl ActionParams {
+    /// Check the parameters given to `action` against its schema
+    ///
+    /// # Errors
+    ///
+    /// Names the first unknown, missing or mistyped parameter.
+    pub fn validate(
+        action: &str,
+        schema: &[ParamSpec],
+        values: HashMap<String, serde_yaml::Value>,
+    ) -> std::result::Result<Self, String> {
+        let mut names: Vec<&String> = values.keys().collect();
+        names.sort();
+        for name in names {
+            if !schema.iter().any(|spec| spec.name == name) {
+                return Err(format!("Action '{}' takes no parameter '{}'", action, name));
+            }
+        }
+        for spec in schema {
+            let Some(value) = values.get(spec.name) else {
+                if spec.required {
+                    return Err(format!(
+                        "Action '{}' requires parameter '{}'",
+                        action, spec.name
+                    ));
+                }
+                continue;
+            };
+            let Some(text) = value.as_str() else {
+                return Err(format!(
+                    "Parameter '{}' of action '{}' must be a string",
+                    spec.name, action
+                ));
+            };
+            if !spec.one_of.is_empty() && !spec.one_of.contains(&text) {
+                return Err(format!(
+                    "Parameter '{}' of action '{}' must be one of {}, not '{}'",
+                    spec.name,
+                    action,
+                    spec.one_of.join(", "),
+                    text
+                ));
+            }
+        }
+        Ok(Self { values })
+    }
+
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.values.get(name).and_then(|v| v.as_str())
+    }
+}
+
+/// A triggered scenario handed to the handler of its action
+pub struct ActionRequest {
+    pub action: String,
+    /// Scenario name qualified by its namespace
+    pub scenario_name: String,
+    pub scenario: Scenario,
+    /// Target package, handlers may expand or place its models
+    pub package: Package,
+    pub params: ActionParams,
+    pub network_str: Option<String>,
+    pub node_str: Option<String>,
+}
+
+/// Handler of a scenario action verb
+pub trait ActionHandler: Send + Sync {
+    /// Parameters the action takes in `actionParams`
+    fn params(&self) -> &[ParamSpec] {
+        &[]
+    }
+
+    /// `true` if the action runs on the models of the target package
+    fn runs_workloads(&self) -> bool {
+        false
+    }
+
+    /// Carry out the action
+    ///
+    /// ActionControllerManager notifies StateManager and releases the
+    /// dependent scenarios once the returned future completes.
+    fn run<'a>(
+        &'a self,
+        manager: &'a ActionControllerManager,
+        request: &'a mut ActionRequest,
+    ) -> ActionFuture<'a>;
+}
+
+/// Handlers by action verb
+#[derive(Clone)]
+pub struct ActionRegistry {
+    handlers: HashMap<String, Arc<dyn ActionHandler>>,
+}
+
+impl Default for ActionRegistry {
+    /// Registry of the built-in actions
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        for action in ["launch", "terminate", "update", "rollback"] {
+            registry
+                .handlers
+                .insert(action.to_string(), Arc::new(WorkloadAction));
+        }
+        registry
+            .handlers
+            .insert("notify".to_string(), Arc::new(NotifyAction));
+        registry
+    }
+}
+
+impl ActionRegistry {
+    /// Registry without any action
+    pub fn empty() -> Self {
+        Self {
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// Add the handler of a new action verb
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is empty or already registered.
+    pub fn register(
+        &mut self,
+        name: &str,
+        handler: Arc<dyn ActionHandler>,
+    ) -> std::result::Result<(), String> {
+        if name.trim().is_empty() {
+            return Err("Action name cannot be empty".to_string());
+        }
+        if self.handlers.contains_key(name) {
+            return Err(format!("Action '{}' is already registered", name));
+        }
+        self.handlers.insert(name.to_string(), handler);
+        Ok(())
+    }
+
+    /// Handler of an action
+    ///
+    /// # Errors
+    ///
+    /// Lists the registered actions if `name` is not one of them.
+    pub fn get(&self, name: &str) -> std::result::Result<Arc<dyn ActionHandler>, String> {
+        self.handlers.get(name).cloned().ok_or_else(|| {
+            format!(
+                "Unknown action '{}', expected one of {}",
+                name,
+                self.names().join(", ")
+            )
+        })
+    }
+
+    /// Registered action verbs, sorted
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.handlers.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+/// `launch`, `terminate`, `update` and `rollback` on the models of a package
+struct WorkloadAction;
+
+impl ActionHandler for WorkloadAction {
+    fn runs_workloads(&self) -> bool {
+        true
+    }
+
+    fn run<'a>(
+        &'a self,
+        manager: &'a ActionControllerManager,
+        request: &'a mut ActionRequest,
+    ) -> ActionFuture<'a> {
+        Box::pin(manager.run_workload_action(request))
+    }
+}
+
+const NOTIFY_PARAMS: &[ParamSpec] = &[
+    ParamSpec {
+        name: "message",
+        required: true,
+        one_of: &[],
+    },
+    ParamSpec {
+        name: "severity",
+        required: false,
+        one_of: &["info", "warning", "critical"],
+    },
+];
+
+/// `notify`: publish a ScenarioNotification event about the scenario
+///
+/// The event carries the `severity` (`info` by default) and the name of the
+/// target package as attributes. Workloads are left untouched.
+struct NotifyAction;
+
+impl ActionHandler for NotifyAction {
+    fn params(&self) -> &[ParamSpec] {
+        NOTIFY_PARAMS
+    }
+
+    fn run<'a>(
+        &'a self,
+        _manager: &'a ActionControllerManager,
+        request: &'a mut ActionRequest,
+    ) -> ActionFuture<'a> {
+        Box::pin(async move {
+            let message = request.params.get_str("message").unwrap_or_default();
+            let severity = request.params.get_str("severity").unwrap_or("info");
+            let package = request.package.get_name();
+            logd!(
+                3,
+                "Scenario '{}' notifies ({}): {}",
+                request.scenario_name,
+                severity,
+                message
+            );
+            common::eventbus::publish(
+                Event::new(
+                    EventKind::ScenarioNotification,
+                    "actioncontroller",
+                    &request.scenario_name,
+                    message,
+                )
+                .with_attribute("severity", severity)
+                .with_attribute("package", &package),
+            );
+            Ok(())
+        })
+    }
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(yaml: &str) -> HashMap<String, serde_yaml::Value> {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_validate_params() {
+        let params =
+            ActionParams::validate("notify", NOTIFY_PARAMS, values("message: low battery"))
+                .unwrap();
+        assert_eq!(params.get_str("message"), Some("low battery"));
+        assert_eq!(params.get_str("severity"), None);
+
+        let err =
+            ActionParams::validate("notify", NOTIFY_PARAMS, values("severity: info")).unwrap_err();
+        assert_eq!(err, "Action 'notify' requires parameter 'message'");
+        let err =
+            ActionParams::validate("notify", NOTIFY_PARAMS, values("message: 3")).unwrap_err();
+        assert_eq!(
+            err,
+            "Parameter 'message' of action 'notify' must be a string"
+        );
+        let err = ActionParams::validate(
+            "notify",
+            NOTIFY_PARAMS,
+            values("{message: hi, severity: loud}"),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            "Parameter 'severity' of action 'notify' must be one of info, warning, critical, not 'loud'"
+        );
+        let err = ActionParams::validate("launch", &[], values("replicas: 2")).unwrap_err();
+        assert_eq!(err, "Action 'launch' takes no parameter 'replicas'");
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = ActionRegistry::default();
+        assert_eq!(
+            registry.names(),
+            vec!["launch", "notify", "rollback", "terminate", "update"]
+        );
+        assert!(registry.get("launch").unwrap().runs_workloads());
+        assert!(!registry.get("notify").unwrap().runs_workloads());
+
+        let err = registry
+            .register("notify", Arc::new(NotifyAction))
+            .unwrap_err();
+        assert_eq!(err, "Action 'notify' is already registered");
+        registry.register("alert", Arc::new(NotifyAction)).unwrap();
+        assert_eq!(registry.get("alert").unwrap().params().len(), 2);
+
+        let err = ActionRegistry::empty().get("pause").err().unwrap();
+        assert_eq!(err, "Unknown action 'pause', expected one of ");
+        let err = registry.get("pause").err().unwrap();
+        assert_eq!(
+            err,
+            "Unknown action 'pause', expected one of alert, launch, notify, rollback, terminate, update"
+        );
+    }
+}
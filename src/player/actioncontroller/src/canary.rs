@@ -0,0 +1,228 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Canary stage of package updates
+//!
+//! With `updateStrategy.canary`, the models on the canary node form the
+//! first batch of the update. Once StateManager reports them Running
+//! (`/model/<name>/state`), they soak for `soakSeconds`: they must stay
+//! Running and the health gates must hold on the metrics the node reports to
+//! the monitoring server (`/pullpiri/metrics/nodes/<node>` and
+//! `/pullpiri/metrics/node_metrics/<node>`). Only then are the other models
+//! updated, in batches for a rolling update or all at once otherwise.
+use common::monitoringserver::NodeMetrics;
+use common::spec::artifact::package::{CanaryStrategy, HealthGate, UpdateStrategy};
+use common::Result;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const ETCD_NODE_INFO_PREFIX: &str = "/pullpiri/metrics/nodes/";
+const ETCD_NODE_METRICS_PREFIX: &str = "/pullpiri/metrics/node_metrics/";
+/// Time between two checks of the soaking canary
+const CANARY_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Split the models of an update into batches
+///
+/// With a canary node, its models are the first batch. The other models are
+/// split by `maxUnavailable` for a rolling update, or form one batch.
+///
+/// ### Parameters
+/// * `strategy: &UpdateStrategy` - update strategy of the package
+/// * `canary_node: Option<&str>` - node of the canary stage, if any
+/// * `models: &[T]` - models to update, in package order
+/// * `node_of: impl Fn(&T) -> String` - node of a model
+pub fn update_batches<T: Copy>(
+    strategy: &UpdateStrategy,
+    canary_node: Option<&str>,
+    models: &[T],
+    node_of: impl Fn(&T) -> String,
+) -> Vec<Vec<T>> {
+    let mut batches = Vec::new();
+    let (canary, rest): (Vec<T>, Vec<T>) = models
+        .iter()
+        .partition(|model| canary_node.is_some_and(|node| node_of(model) == node));
+    if canary_node.is_some() {
+        batches.push(canary);
+    }
+    if rest.is_empty() {
+        return batches;
+    }
+    let batch_size = if strategy.is_rolling() {
+        strategy.get_max_unavailable()
+    } else {
+        rest.len()
+    };
+    batches.extend(rest.chunks(batch_size).map(<[T]>::to_vec));
+    batches
+}
+
+/// Numeric metrics a node reported to the monitoring server
+///
+/// Fields of the node info such as `cpu_usage` come first, metrics of the
+/// NodeAgent plugins with the same name replace them.
+pub async fn node_metric_values(node: &str) -> HashMap<String, f64> {
+    let mut values = HashMap::new();
+    let info = common::etcd::get(&format!("{}{}", ETCD_NODE_INFO_PREFIX, node)).await;
+    if let Ok(serde_json::Value::Object(fields)) = info
+        .as_deref()
+        .map(|json| serde_json::from_str(json).unwrap_or_default())
+    {
+        for (name, value) in fields {
+            if let Some(value) = value.as_f64() {
+                values.insert(name, value);
+            }
+        }
+    }
+    let metrics = common::etcd::get(&format!("{}{}", ETCD_NODE_METRICS_PREFIX, node)).await;
+    if let Some(node_metrics) = metrics
+        .ok()
+        .and_then(|json| serde_json::from_str::<NodeMetrics>(&json).ok())
+    {
+        for metric in node_metrics.metrics {
+            values.insert(metric.name, metric.value);
+        }
+    }
+    values
+}
+
+/// Check the health gates against the metrics of the canary node
+///
+/// # Errors
+///
+/// Returns the first gate that does not hold, or whose metric is missing.
+pub fn check_health_gates(
+    gates: &[HealthGate],
+    node: &str,
+    metrics: &HashMap<String, f64>,
+) -> Result<()> {
+    for gate in gates {
+        match metrics.get(gate.get_metric()) {
+            Some(value) => gate
+                .check(*value)
+                .map_err(|e| format!("health gate failed on node '{}': {}", node, e))?,
+            None => {
+                return Err(format!(
+                    "health gate failed on node '{}': metric {} is not reported",
+                    node,
+                    gate.get_metric()
+                )
+                .into())
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Watch the canary models until the soak time is over
+///
+/// # Errors
+///
+/// Fails as soon as StateManager no longer reports a canary model as
+/// Running, or a health gate does not hold on the canary node.
+pub async fn soak(canary: &CanaryStrategy, node: &str, model_names: &[String]) -> Result<()> {
+    let soak_time = Duration::from_secs(canary.get_soak_secs());
+    let deadline = tokio::time::Instant::now() + soak_time;
+    common::logd!(
+        2,
+        "Canary models {} on node '{}' soak for {:?}",
+        model_names.join(", "),
+        node,
+        soak_time
+    );
+    loop {
+        for model_name in model_names {
+            let key = format!("/model/{}/state", model_name);
+            match common::etcd::get(&key).await.as_deref() {
+                Ok("Running") => {}
+                Ok(state) => {
+                    return Err(format!("canary model '{}' is {}", model_name, state).into())
+                }
+                Err(_) => {
+                    return Err(
+                        format!("canary model '{}' has no reported state", model_name).into(),
+                    )
+                }
+            }
+        }
+        if !canary.get_health_gates().is_empty() {
+            let metrics = node_metric_values(node).await;
+            check_health_gates(canary.get_health_gates(), node, &metrics)?;
+        }
+
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            return Ok(());
+        }
+        tokio::time::sleep(CANARY_CHECK_INTERVAL.min(deadline - now)).await;
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strategy(yaml: &str) -> UpdateStrategy {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_update_batches() {
+        let models = [("m1", "n1"), ("m2", "n2"), ("m3", "n1"), ("m4", "n3")];
+        let node_of = |model: &(&str, &str)| model.1.to_string();
+        fn names(batches: Vec<Vec<(&'static str, &str)>>) -> Vec<Vec<&'static str>> {
+            batches
+                .into_iter()
+                .map(|batch| batch.into_iter().map(|(name, _)| name).collect())
+                .collect()
+        }
+
+        let rolling = strategy("type: rolling\nmaxUnavailable: 2\ncanary: {}");
+        assert_eq!(
+            names(update_batches(&rolling, Some("n1"), &models, node_of)),
+            vec![vec!["m1", "m3"], vec!["m2", "m4"]]
+        );
+        let rolling = strategy("type: rolling\nmaxUnavailable: 1");
+        assert_eq!(
+            names(update_batches(&rolling, None, &models, node_of)).len(),
+            4
+        );
+        let recreate = strategy("type: recreate\ncanary: {}");
+        assert_eq!(
+            names(update_batches(&recreate, Some("n2"), &models, node_of)),
+            vec![vec!["m2"], vec!["m1", "m3", "m4"]]
+        );
+        // A canary node without models gives an empty first batch
+        assert_eq!(
+            names(update_batches(&recreate, Some("n9"), &models, node_of))[0],
+            Vec::<&str>::new()
+        );
+    }
+
+    #[test]
+    fn test_check_health_gates() {
+        let canary: CanaryStrategy = serde_yaml::from_str(
+            "healthGates: [{metric: cpu_usage, max: 80}, {metric: temperature, min: 10}]",
+        )
+        .unwrap();
+        let gates = canary.get_health_gates();
+        let mut metrics = HashMap::from([
+            ("cpu_usage".to_string(), 42.0),
+            ("temperature".to_string(), 35.0),
+        ]);
+        assert!(check_health_gates(gates, "n1", &metrics).is_ok());
+
+        metrics.insert("cpu_usage".to_string(), 95.0);
+        let err = check_health_gates(gates, "n1", &metrics).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "health gate failed on node 'n1': cpu_usage is 95, above 80"
+        );
+
+        metrics.remove("temperature");
+        metrics.insert("cpu_usage".to_string(), 10.0);
+        let err = check_health_gates(gates, "n1", &metrics).unwrap_err();
+        assert!(err.to_string().contains("temperature is not reported"));
+    }
+}
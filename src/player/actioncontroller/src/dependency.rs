@@ -0,0 +1,127 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Ordered execution of scenarios with `dependsOn`
+//!
+//! A scenario runs only after every scenario it depends on has completed,
+//! as recorded by StateManager in `/scenario/<name>/state`. A scenario that
+//! is triggered too early is kept in `ScenarioBlocked/<name>` with its
+//! missing prerequisites and is run once the last of them completes.
+use common::spec::artifact::scenario::DEPENDENCY_SATISFIED_STATE;
+use common::spec::artifact::Scenario;
+use common::spec::namespace;
+use common::Result;
+use std::collections::HashMap;
+
+const ETCD_BLOCKED_PREFIX: &str = "ScenarioBlocked";
+const ETCD_SCENARIO_PREFIX: &str = "Scenario";
+
+/// Prerequisites of a scenario that have not completed yet
+///
+/// ### Parameters
+/// * `scenario: &Scenario` - scenario about to run
+/// * `completed: Option<&str>` - scenario that just completed, counted as
+///   completed even if StateManager has not stored its state yet
+pub async fn unmet_dependencies(scenario: &Scenario, completed: Option<&str>) -> Vec<String> {
+    let depends_on = scenario.get_qualified_depends_on();
+    let mut states = HashMap::new();
+    for dependency in &depends_on {
+        let key = format!("/scenario/{}/state", dependency);
+        if let Ok(state) = common::etcd::get(&key).await {
+            states.insert(dependency.clone(), state);
+        }
+    }
+    unmet(&depends_on, &states, completed)
+}
+
+fn unmet(
+    depends_on: &[String],
+    states: &HashMap<String, String>,
+    completed: Option<&str>,
+) -> Vec<String> {
+    depends_on
+        .iter()
+        .filter(|dependency| Some(dependency.as_str()) != completed)
+        .filter(|dependency| {
+            states.get(dependency.as_str()).map(String::as_str) != Some(DEPENDENCY_SATISFIED_STATE)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Keep a scenario until its prerequisites complete
+pub async fn block(scenario_name: &str, unmet: &[String]) -> Result<()> {
+    let key = format!("{}/{}", ETCD_BLOCKED_PREFIX, scenario_name);
+    common::etcd::put(&key, &unmet.join(",")).await?;
+    Ok(())
+}
+
+/// Forget a blocked scenario, e.g. because it runs now
+pub async fn unblock(scenario_name: &str) {
+    let key = format!("{}/{}", ETCD_BLOCKED_PREFIX, scenario_name);
+    let _ = common::etcd::delete(&key).await;
+}
+
+/// Scenarios released by the completion of `completed`
+///
+/// The returned scenarios have no unmet prerequisites left and are no
+/// longer blocked; the caller runs them.
+pub async fn released_by(completed: &str) -> Vec<String> {
+    let prefix = format!("{}/", ETCD_BLOCKED_PREFIX);
+    let Ok(blocked) = common::etcd::get_all_with_prefix(&prefix).await else {
+        return Vec::new();
+    };
+
+    let mut released = Vec::new();
+    for (key, waiting_for) in blocked {
+        if !waiting_for.split(',').any(|name| name == completed) {
+            continue;
+        }
+        let scenario_name = key.trim_start_matches(&prefix).to_string();
+        let scenario_key = namespace::qualified_key(ETCD_SCENARIO_PREFIX, &scenario_name);
+        let Some(scenario) = common::etcd::get(&scenario_key)
+            .await
+            .ok()
+            .and_then(|yaml| serde_yaml::from_str::<Scenario>(&yaml).ok())
+        else {
+            // the scenario was withdrawn while it waited
+            unblock(&scenario_name).await;
+            continue;
+        };
+
+        let unmet = unmet_dependencies(&scenario, Some(completed)).await;
+        if unmet.is_empty() {
+            unblock(&scenario_name).await;
+            released.push(scenario_name);
+        } else {
+            let _ = block(&scenario_name, &unmet).await;
+        }
+    }
+    released
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unmet_dependencies() {
+        let depends_on = vec!["download".to_string(), "verify".to_string()];
+        let mut states = HashMap::new();
+        states.insert(
+            "download".to_string(),
+            DEPENDENCY_SATISFIED_STATE.to_string(),
+        );
+        states.insert("verify".to_string(), "SCENARIO_STATE_ALLOWED".to_string());
+
+        assert_eq!(unmet(&depends_on, &states, None), vec!["verify"]);
+        assert!(unmet(&depends_on, &states, Some("verify")).is_empty());
+        assert_eq!(
+            unmet(&depends_on, &HashMap::new(), Some("verify")),
+            vec!["download"]
+        );
+        assert!(unmet(&[], &HashMap::new(), None).is_empty());
+    }
+}
@@ -27,18 +27,27 @@ use tonic::transport::Server;
 pub async fn init(manager: crate::manager::ActionControllerManager) -> common::Result<()> {
     let arc_manager = Arc::new(manager);
     let grpc_server = receiver::ActionControllerReceiver::new(arc_manager.clone());
+    tokio::spawn(crate::vehicle_mode::watch_deferred(arc_manager.clone()));
 
     let addr = common::actioncontroller::open_server().parse()?;
     logd!(1, "Starting gRPC server on {}", addr);
 
     tokio::spawn(async move {
-        if let Err(e) = Server::builder()
+        common::health::set_ready(common::health::CHECK_GRPC_SERVER);
+        let reason = match Server::builder()
+            .layer(common::trace::GrpcTraceLayer)
             .add_service(grpc_server.into_service())
+            .add_service(common::reflection::service())
             .serve(addr)
             .await
         {
-            logd!(5, "gRPC server error: {}", e);
-        }
+            Ok(()) => "stopped".to_string(),
+            Err(e) => {
+                logd!(5, "gRPC server error: {}", e);
+                e.to_string()
+            }
+        };
+        common::health::set_not_ready(common::health::CHECK_GRPC_SERVER, reason);
     });
 
     logd!(1, "gRPC server started and listening");
@@ -7,11 +7,13 @@ use tonic::{Request, Response, Status};
 
 // Import the generated protobuf code
 use crate::grpc::sender::statemanager::StateManagerSender;
+use crate::manager::MigrateError;
 use common::actioncontroller::{
     action_controller_connection_server::{
         ActionControllerConnection, ActionControllerConnectionServer,
     },
-    CompleteNetworkSettingRequest, CompleteNetworkSettingResponse, OffloadModelRequest,
+    CompleteNetworkSettingRequest, CompleteNetworkSettingResponse, DrainNodeRequest,
+    DrainNodeResponse, MigrateNodeRequest, MigrateNodeResponse, OffloadModelRequest,
     OffloadModelResponse, PodStatus as ActionStatus, ReconcileRequest, ReconcileResponse,
     TriggerActionRequest, TriggerActionResponse,
 };
@@ -23,6 +25,7 @@ use common::logd;
 /// the protobuf specification. Handles incoming requests from:
 /// - FilterGateway (trigger_action)
 /// - StateManager (reconcile)
+/// - ApiServer (drain_node, migrate_node)
 #[allow(dead_code)]
 pub struct ActionControllerReceiver {
     /// Reference to the ActionController manager
@@ -62,6 +65,10 @@ impl ActionControllerReceiver {
 impl ActionControllerConnection for ActionControllerReceiver {
     /// Handle trigger action requests from FilterGateway
     ///
+    /// A request with `action` set runs that action on the target package of
+    /// the scenario instead, e.g. StateManager rolling back a failed model or
+    /// terminating an expired scenario.
+    ///
     /// # Arguments
     ///
     /// * `request` - gRPC request containing scenario name to trigger
@@ -79,9 +86,25 @@ impl ActionControllerConnection for ActionControllerReceiver {
 
         logd!(1, "trigger_action in grpc receiver");
 
-        let scenario_name = request.into_inner().scenario_name;
+        let request = request.into_inner();
+        let scenario_name = request.scenario_name;
         logd!(2, "trigger_action scenario: {}", scenario_name);
 
+        // A dry run only returns what the trigger would do
+        if request.dry_run {
+            logd!(1, "   🧪 Dry run, planning scenario actions...");
+            let result = match self.manager.plan_manager_action(&scenario_name).await {
+                Ok(plan) => Ok(Response::new(TriggerActionResponse {
+                    status: 0,
+                    desc: "Dry run, nothing was executed".to_string(),
+                    plan: Some(plan),
+                })),
+                Err(e) => Err(trigger_error_status(e.to_string())),
+            };
+            logd!(1, "trigger_action: elapsed = {:?}", start.elapsed());
+            return result;
+        }
+
         logd!(
             1,
             "🔄 SCENARIO STATE TRANSITION: ActionController Processing"
@@ -101,28 +124,20 @@ impl ActionControllerConnection for ActionControllerReceiver {
         );
 
         logd!(1, "   🎯 Processing scenario actions...");
-        let result = match self.manager.trigger_manager_action(&scenario_name).await {
+        let triggered = if request.action.is_empty() {
+            self.manager.trigger_manager_action(&scenario_name).await
+        } else {
+            self.manager
+                .run_scenario_action(&scenario_name, &request.action)
+                .await
+        };
+        let result = match triggered {
             Ok(_) => Ok(Response::new(TriggerActionResponse {
                 status: 0,
                 desc: "Action triggered successfully".to_string(),
+                plan: None,
             })),
-            Err(e) => {
-                let err_msg = e.to_string();
-                let grpc_status = if err_msg.contains("Invalid scenario name") {
-                    Status::invalid_argument(err_msg)
-                } else if err_msg.contains("not found") {
-                    Status::not_found(err_msg)
-                } else if err_msg.contains("Failed to parse") {
-                    Status::invalid_argument(err_msg)
-                } else if err_msg.contains("Failed to start workload")
-                    || err_msg.contains("Failed to stop workload")
-                {
-                    Status::internal(err_msg)
-                } else {
-                    Status::unknown(err_msg)
-                };
-                Err(grpc_status)
-            }
+            Err(e) => Err(trigger_error_status(e.to_string())),
         };
 
         let elapsed = start.elapsed();
@@ -230,29 +245,41 @@ impl ActionControllerConnection for ActionControllerReceiver {
                 .as_millis()
         );
 
-        // Execute offloading: terminate on source, launch on target
-        match self
-            .manager
-            .offload_model(
-                &req.scenario_name,
-                &req.package_name,
-                &req.model_name,
-                &req.source_node,
-                &req.target_node,
-                &req.policy_name,
-            )
-            .await
-        {
-            Ok(_) => {
+        // Execute offloading: terminate on source, launch on target, which
+        // is chosen like for an `auto` model if the request names none
+        let result = if req.target_node.is_empty() {
+            self.manager
+                .reschedule_model(
+                    &req.scenario_name,
+                    &req.package_name,
+                    &req.model_name,
+                    &req.source_node,
+                )
+                .await
+        } else {
+            self.manager
+                .offload_model(
+                    &req.scenario_name,
+                    &req.package_name,
+                    &req.model_name,
+                    &req.source_node,
+                    &req.target_node,
+                    &req.policy_name,
+                )
+                .await
+                .map(|_| req.target_node.clone())
+        };
+        match result {
+            Ok(target_node) => {
                 println!(
                     "[ActionController] Successfully offloaded '{}' from '{}' to '{}'",
-                    req.model_name, req.source_node, req.target_node
+                    req.model_name, req.source_node, target_node
                 );
                 Ok(Response::new(OffloadModelResponse {
                     success: true,
                     message: format!(
                         "Model '{}' successfully migrated from '{}' to '{}'",
-                        req.model_name, req.source_node, req.target_node
+                        req.model_name, req.source_node, target_node
                     ),
                     transition_id,
                 }))
@@ -270,6 +297,102 @@ impl ActionControllerConnection for ActionControllerReceiver {
             }
         }
     }
+
+    /// Handle a drain request from ApiServer
+    ///
+    /// Moves the models off a node that was put in maintenance.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - gRPC request with the hostname of the node
+    ///
+    /// # Returns
+    ///
+    /// * `Response<DrainNodeResponse>` - outcome of every model of the node
+    /// * `Status` - gRPC status error if the node cannot be drained
+    async fn drain_node(
+        &self,
+        request: Request<DrainNodeRequest>,
+    ) -> Result<Response<DrainNodeResponse>, Status> {
+        let node = request.into_inner().node;
+        if node.trim().is_empty() {
+            return Err(Status::invalid_argument("Node name cannot be empty"));
+        }
+        logd!(3, "Draining node '{}'", node);
+
+        match self.manager.drain_node(&node).await {
+            Ok(models) => Ok(Response::new(DrainNodeResponse { models })),
+            Err(e) => Err(Status::internal(format!(
+                "Failed to drain node '{}': {}",
+                node, e
+            ))),
+        }
+    }
+
+    /// Handle a migration request from ApiServer
+    ///
+    /// Moves every model of a failed node to the target node, or to the
+    /// nodes chosen for them if no target is given.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - gRPC request with the source and target hostnames
+    ///
+    /// # Returns
+    ///
+    /// * `Response<MigrateNodeResponse>` - outcome of every model of the node
+    /// * `Status` - gRPC status error if the node cannot be migrated
+    async fn migrate_node(
+        &self,
+        request: Request<MigrateNodeRequest>,
+    ) -> Result<Response<MigrateNodeResponse>, Status> {
+        let req = request.into_inner();
+        if req.source_node.trim().is_empty() {
+            return Err(Status::invalid_argument("Source node cannot be empty"));
+        }
+        logd!(
+            3,
+            "Migrating node '{}' to '{}'",
+            req.source_node,
+            if req.target_node.is_empty() {
+                "auto"
+            } else {
+                &req.target_node
+            }
+        );
+
+        match self
+            .manager
+            .migrate_node(&req.source_node, &req.target_node)
+            .await
+        {
+            Ok(models) => Ok(Response::new(MigrateNodeResponse { models })),
+            Err(e @ (MigrateError::NotReady(_) | MigrateError::SameNode(_))) => {
+                Err(Status::failed_precondition(e.to_string()))
+            }
+            Err(MigrateError::Failed(e)) => Err(Status::internal(format!(
+                "Failed to migrate node '{}': {}",
+                req.source_node, e
+            ))),
+        }
+    }
+}
+
+/// gRPC status of a failed trigger, chosen from the error message
+fn trigger_error_status(err_msg: String) -> Status {
+    if err_msg.contains("Invalid scenario name") {
+        Status::invalid_argument(err_msg)
+    } else if err_msg.contains("not found") {
+        Status::not_found(err_msg)
+    } else if err_msg.contains("Failed to parse") {
+        Status::invalid_argument(err_msg)
+    } else if err_msg.contains("Failed to start workload")
+        || err_msg.contains("Failed to stop workload")
+    {
+        Status::internal(err_msg)
+    } else {
+        Status::unknown(err_msg)
+    }
 }
 
 fn i32_to_status(value: i32) -> ActionStatus {
@@ -370,12 +493,29 @@ mod tests {
 
         let request = Request::new(TriggerActionRequest {
             scenario_name: "invalid_scenario".to_string(),
+            dry_run: false,
+            action: String::new(),
         });
 
         let response = receiver.trigger_action(request).await.unwrap_err();
         assert!(response.message().contains("not found"));
     }
 
+    #[tokio::test]
+    async fn test_trigger_action_dry_run_empty_scenario() {
+        let manager = Arc::new(ActionControllerManager::new());
+        let receiver = ActionControllerReceiver::new(manager.clone());
+
+        let request = Request::new(TriggerActionRequest {
+            scenario_name: " ".to_string(),
+            dry_run: true,
+            action: String::new(),
+        });
+
+        let response = receiver.trigger_action(request).await.unwrap_err();
+        assert!(response.message().contains("cannot be empty"));
+    }
+
     #[tokio::test]
     async fn test_reconcile_when_states_equal() {
         let manager = Arc::new(ActionControllerManager::new());
@@ -1,19 +1,61 @@
 use common::nodeagent::fromactioncontroller::{
-    connect_server, HandleWorkloadRequest, HandleWorkloadResponse,
+    connect_server, HandleUnitRequest, HandleUnitResponse, HandleWorkloadRequest,
+    HandleWorkloadResponse, PrePullImagesRequest, PrePullProgress,
 };
 use common::nodeagent::node_agent_connection_client::NodeAgentConnectionClient;
-use tonic::{Request, Status};
+use common::rpc::RpcClient;
+use tonic::{Status, Streaming};
+
+fn node_agent(addr: &str) -> RpcClient {
+    RpcClient::new("NodeAgent", connect_server(addr))
+}
 
 pub async fn send_workload_handle_request(
     addr: &str,
     request: HandleWorkloadRequest,
 ) -> Result<HandleWorkloadResponse, Status> {
-    let mut client = NodeAgentConnectionClient::connect(connect_server(&addr))
-        .await
-        .unwrap();
+    // Creating and starting containers is not bounded by the call deadline
+    let request = &request;
+    let response = node_agent(addr)
+        .with_call_timeout(None)
+        .call(|channel| async move {
+            NodeAgentConnectionClient::new(channel)
+                .handle_workload(common::trace::request(request.clone()))
+                .await
+        })
+        .await?
+        .into_inner();
+    Ok(response)
+}
 
-    let response = client
-        .handle_workload(Request::new(request))
+pub async fn send_unit_handle_request(
+    addr: &str,
+    request: HandleUnitRequest,
+) -> Result<HandleUnitResponse, Status> {
+    let request = &request;
+    let response = node_agent(addr)
+        .with_call_timeout(None)
+        .call(|channel| async move {
+            NodeAgentConnectionClient::new(channel)
+                .handle_unit(common::trace::request(request.clone()))
+                .await
+        })
+        .await?
+        .into_inner();
+    Ok(response)
+}
+
+pub async fn send_prepull_request(
+    addr: &str,
+    request: PrePullImagesRequest,
+) -> Result<Streaming<PrePullProgress>, Status> {
+    let request = &request;
+    let response = node_agent(addr)
+        .call(|channel| async move {
+            NodeAgentConnectionClient::new(channel)
+                .pre_pull_images(common::trace::request(request.clone()))
+                .await
+        })
         .await?
         .into_inner();
     Ok(response)
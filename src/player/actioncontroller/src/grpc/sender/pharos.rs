@@ -12,6 +12,7 @@ use common::external::pharos::{
 };
 
 use common::logd;
+use common::rpc::RpcClient;
 use tonic::{Request, Response, Status};
 
 /// Send request to Pharos to set up network for a pod
@@ -36,8 +37,12 @@ pub async fn request_network_pod(
         pod_name,
         network_yamls,
     };
-    let mut client = PharosNetworkServiceConnectionClient::connect(connect_pharos_server())
+    let request = &request;
+    RpcClient::new("Pharos", connect_pharos_server())
+        .call(|channel| async move {
+            PharosNetworkServiceConnectionClient::new(channel)
+                .request_network_pod(Request::new(request.clone()))
+                .await
+        })
         .await
-        .unwrap();
-    client.request_network_pod(Request::new(request)).await
 }
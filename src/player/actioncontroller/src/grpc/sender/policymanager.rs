@@ -7,6 +7,7 @@ use common::policymanager::{
     policy_manager_connection_client::PolicyManagerConnectionClient, CheckNodePolicyRequest,
     CheckNodePolicyResponse,
 };
+use common::rpc::RpcClient;
 use common::Result;
 
 /// Response from policy check containing deployment decision
@@ -48,19 +49,19 @@ pub async fn check_node_policy(policy_name: &str, target_node: &str) -> Result<P
         addr
     );
 
-    let mut client = PolicyManagerConnectionClient::connect(addr)
-        .await
-        .map_err(|e| format!("Failed to connect to PolicyManager: {}", e))?;
-
-    let request = tonic::Request::new(CheckNodePolicyRequest {
+    let request = &CheckNodePolicyRequest {
         policy_name: policy_name.to_string(),
         target_node: target_node.to_string(),
-    });
+    };
 
-    let response: CheckNodePolicyResponse = client
-        .check_node_policy(request)
+    let response: CheckNodePolicyResponse = RpcClient::new("PolicyManager", addr)
+        .call(|channel| async move {
+            PolicyManagerConnectionClient::new(channel)
+                .check_node_policy(common::trace::request(request.clone()))
+                .await
+        })
         .await
-        .map_err(|e| format!("PolicyManager gRPC error: {}", e))?
+        .map_err(|e| format!("PolicyManager gRPC error: {}", e.message()))?
         .into_inner();
 
     let result = PolicyCheckResult {
@@ -13,11 +13,12 @@
 //! confirmations, and error conditions back to the StateManager for proper resource
 //! state tracking and recovery management.
 
+use common::rpc::RpcClient;
 use common::statemanager::{
     connect_server, state_manager_connection_client::StateManagerConnectionClient, ResourceType,
     StateChange, StateChangeResponse,
 };
-use tonic::{Request, Status};
+use tonic::Status;
 
 /// StateManager gRPC client for ActionController component.
 ///
@@ -45,12 +46,11 @@ use tonic::{Request, Status};
 /// - Includes context information for safety analysis and audit trails
 #[derive(Clone)]
 pub struct StateManagerSender {
-    /// Cached gRPC client connection to the StateManager service.
+    /// Client of the StateManager service.
     ///
-    /// This connection is established lazily on the first request and reused
-    /// for subsequent requests to optimize performance. Set to None initially
-    /// and populated when ensure_connected() is called.
-    client: Option<StateManagerConnectionClient<tonic::transport::Channel>>,
+    /// The channel is established lazily on the first request and reused
+    /// for subsequent requests to optimize performance.
+    rpc: RpcClient,
 }
 
 impl Default for StateManagerSender {
@@ -73,46 +73,8 @@ impl StateManagerSender {
     /// # Returns
     /// * `Self` - New StateManagerSender instance ready for use
     pub fn new() -> Self {
-        Self { client: None }
-    }
-
-    /// Ensures a gRPC connection to the StateManager exists and is ready for use.
-    ///
-    /// This method implements lazy connection establishment by checking if a connection
-    /// already exists and creating one if necessary. It uses the common::statemanager
-    /// configuration to determine the StateManager's network location.
-    ///
-    /// # Connection Process
-    /// 1. Check if a connection already exists
-    /// 2. If not, attempt to establish a new gRPC connection
-    /// 3. Store the connection for reuse in subsequent requests
-    /// 4. Return success or detailed error information
-    ///
-    /// # Returns
-    /// * `Result<(), Status>` - Success if connection is available, error otherwise
-    ///
-    /// # Errors
-    /// * `Status::unknown` - Connection establishment failed (network, service unavailable, etc.)
-    ///
-    /// # Future Enhancements
-    /// - Add connection health checking and automatic reconnection
-    /// - Implement exponential backoff for connection retries
-    /// - Add connection pooling for high-throughput scenarios
-    async fn ensure_connected(&mut self) -> Result<(), Status> {
-        if self.client.is_none() {
-            match StateManagerConnectionClient::connect(connect_server()).await {
-                Ok(client) => {
-                    self.client = Some(client);
-                    Ok(())
-                }
-                Err(e) => Err(Status::unknown(format!(
-                    "Failed to connect to StateManager: {}",
-                    e
-                ))),
-            }
-        } else {
-            // Connection already exists and ready for use
-            Ok(())
+        Self {
+            rpc: RpcClient::new("StateManager", connect_server()),
         }
     }
 
@@ -142,7 +104,6 @@ impl StateManagerSender {
     ///   - Error codes and details if applicable
     ///
     /// # Errors
-    /// * `Status::unknown` - Connection failure or client not connected
     /// * `Status::unavailable` - StateManager service unavailable
     /// * `Status::invalid_argument` - Malformed StateChange message
     /// * `Status::deadline_exceeded` - Request timeout (ASIL timing violation)
@@ -197,16 +158,14 @@ impl StateManagerSender {
         &mut self,
         state_change: StateChange,
     ) -> Result<tonic::Response<StateChangeResponse>, Status> {
-        // Ensure we have an active gRPC connection before sending
-        self.ensure_connected().await?;
-
-        if let Some(client) = &mut self.client {
-            // Send the state change message via gRPC
-            client.send_state_change(Request::new(state_change)).await
-        } else {
-            // This should never happen due to ensure_connected, but provide safety fallback
-            Err(Status::unknown("Client not connected"))
-        }
+        let state_change = &state_change;
+        self.rpc
+            .call(|channel| async move {
+                StateManagerConnectionClient::new(channel)
+                    .send_state_change(common::trace::request(state_change.clone()))
+                    .await
+            })
+            .await
     }
 
     /// Reports successful action execution to the StateManager.
@@ -253,9 +212,11 @@ impl StateManagerSender {
             resource_name: resource_name.to_string(),
             current_state: previous_state.to_string(),
             target_state: new_state.to_string(),
-            transition_id: transition_id.to_string(),
+            transition_id: format!("success-{}", transition_id), // Unique ID for success transition
             timestamp_ns: timestamp,
             source: "actioncontroller".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: common::trace::current_trace_id().unwrap_or_default(),
         };
 
         self.send_state_change(state_change).await
@@ -308,6 +269,8 @@ impl StateManagerSender {
             transition_id: format!("error-{}", transition_id), // Unique ID for error transition
             timestamp_ns: timestamp,
             source: "actioncontroller".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: common::trace::current_trace_id().unwrap_or_default(),
         };
 
         self.send_state_change(state_change).await
@@ -360,6 +323,8 @@ impl StateManagerSender {
             transition_id: format!("recovery-{}", recovery_id),
             timestamp_ns: timestamp,
             source: "actioncontroller".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: common::trace::current_trace_id().unwrap_or_default(),
         };
 
         self.send_state_change(state_change).await
@@ -411,6 +376,8 @@ mod tests {
             transition_id: format!("update-complete-{}", timestamp),
             timestamp_ns: timestamp,
             source: "actioncontroller".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: String::new(),
         };
 
         // Send the message and verify successful response
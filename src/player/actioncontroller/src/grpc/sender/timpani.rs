@@ -8,17 +8,20 @@ use common::external::timpani::{
     connect_timpani_server, sched_info_service_client::SchedInfoServiceClient, Response, SchedInfo,
 };
 use common::logd;
+use common::rpc::RpcClient;
 
 pub async fn add_sched_info(sched_info: SchedInfo) {
     logd!(1, "Connecting to Timpani server ....");
-    let mut client = SchedInfoServiceClient::connect(connect_timpani_server())
-        .await
-        .unwrap();
-
-    let response: Result<Response, tonic::Status> = client
-        .add_sched_info(sched_info)
-        .await
-        .map(|r| r.into_inner());
+    let sched_info = &sched_info;
+    let response: Result<Response, tonic::Status> =
+        RpcClient::new("Timpani", connect_timpani_server())
+            .call(|channel| async move {
+                SchedInfoServiceClient::new(channel)
+                    .add_sched_info(sched_info.clone())
+                    .await
+            })
+            .await
+            .map(|r| r.into_inner());
 
     match response {
         Ok(res) => {
@@ -2,13 +2,26 @@
 * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
 * SPDX-License-Identifier: Apache-2.0
 */
+use common::health;
 use common::logd;
 use common::logd::logger;
 use std::error::Error;
 
+mod action;
+mod canary;
+mod dependency;
 mod grpc;
 mod manager;
+mod pattern;
+mod placement;
+mod plan;
 mod runtime;
+mod vehicle_mode;
+
+/// Readiness check of the NodeAgent nodes the controller can place workloads on
+const CHECK_NODES: &str = "nodes";
+/// Interval of the etcd readiness probe
+const ETCD_PROBE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
 
 /// Initialize the ActionController component
 ///
@@ -47,15 +60,41 @@ async fn initialize(skip_grpc: bool) -> Result<(), Box<dyn Error>> {
         );
         manager.nodeagent_nodes.push(hostname.clone());
     }
+    if manager.nodeagent_nodes.is_empty() {
+        health::set_not_ready(CHECK_NODES, "no NodeAgent node in settings.yaml");
+    } else {
+        health::set_ready(CHECK_NODES);
+    }
 
     // gRPC 서버 초기화 (테스트 모드가 아닌 경우)
     if !skip_grpc {
         grpc::init(manager).await?;
+        tokio::spawn(watch_host_settings());
+        tokio::spawn(health::watch_etcd(ETCD_PROBE_INTERVAL));
+        tokio::spawn(health::serve(common::actioncontroller::open_health_server()));
     }
 
     Ok(())
 }
 
+/// Follow changes of the host in settings.yaml
+///
+/// The manager reads the current settings for every request, so a changed
+/// host name or IP is used from the next request on.
+async fn watch_host_settings() {
+    let mut settings = common::setting::watch_config();
+    while settings.changed().await.is_ok() {
+        let reloaded = settings.borrow_and_update().clone();
+        logd!(
+            2,
+            "Reloaded settings.yaml, host {} ({}) as {}",
+            reloaded.host.name,
+            reloaded.host.ip,
+            reloaded.host.r#type
+        );
+    }
+}
+
 /// Main function for the ActionController component
 ///
 /// Sets up and runs the ActionController service which:
@@ -70,7 +109,15 @@ async fn initialize(skip_grpc: bool) -> Result<(), Box<dyn Error>> {
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     let _ = logger::init_async_logger("actioncontroller").await;
+    common::trace::init_exporter("actioncontroller");
     logd!(1, "initiailize action controller");
+    if let Err(e) = common::startup::wait_for_dependencies("actioncontroller").await {
+        logd!(4, "actioncontroller starts without its dependencies, {}", e);
+    }
+    health::init(
+        "actioncontroller",
+        &[health::CHECK_ETCD, health::CHECK_GRPC_SERVER, CHECK_NODES],
+    );
 
     // Initialize the controller
     initialize(false).await?;
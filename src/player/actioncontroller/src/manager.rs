@@ -2,19 +2,30 @@
 * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
 * SPDX-License-Identifier: Apache-2.0
 */
-use std::{collections::HashMap, thread, time::Duration};
+use std::{collections::HashMap, future::Future, time::Duration};
 
+use crate::action::{ActionHandler, ActionParams, ActionRegistry, ActionRequest};
 use crate::grpc::sender::pharos::request_network_pod;
 use crate::grpc::sender::statemanager::StateManagerSender;
+use crate::plan::PlanBuilder;
+use crate::vehicle_mode::Decision;
 use common::logd;
 use common::{
-    actioncontroller::PodStatus as Status,
+    actioncontroller::{DrainedModel, ExecutionPlan, MigratedModel, PodStatus as Status},
+    allocation::Allocation,
+    eventbus::{Event, EventKind},
+    nodeagent::fromactioncontroller::UnitCommand,
     spec::artifact::{
-        package::ModelInfo, schedule::SchedPolicy, Artifact, Package, Scenario, Schedule,
+        package::{ModelInfo, UpdateStrategy},
+        schedule::SchedPolicy,
+        Artifact, Package, Scenario, Schedule,
     },
-    statemanager::{ResourceType, StateChange},
+    spec::k8s::Pod,
+    spec::namespace,
+    statemanager::{ResourceType, ScenarioState, StateChange},
     Result,
 };
+use futures::stream::{self, StreamExt};
 
 // ETCD key prefixes
 const ETCD_SCENARIO_PREFIX: &str = "Scenario";
@@ -25,6 +36,11 @@ const ETCD_NODE_PREFIX: &str = "Node";
 const ETCD_NODES_PREFIX: &str = "nodes";
 const ETCD_SCHED_PREFIX: &str = "Schedule";
 const ETCD_CLUSTER_NODES_PREFIX: &str = "cluster/nodes";
+const ETCD_POD_REVISION_PREFIX: &str = "PodRevision";
+const ETCD_PLACEMENT_PREFIX: &str = "Placement";
+
+// Interval between two checks of a model state reported by StateManager
+const MODEL_STATE_POLL_INTERVAL_MS: u64 = 1000;
 
 // Node types
 const NODE_TYPE_NODEAGENT: &str = "nodeagent";
@@ -42,6 +58,8 @@ pub struct ActionControllerManager {
     pub nodeagent_nodes: Vec<String>,
     /// StateManager sender for scenario state changes
     state_sender: StateManagerSender,
+    /// Handlers of the scenario actions
+    actions: ActionRegistry,
     // Add other fields as needed
 }
 #[allow(dead_code)]
@@ -60,9 +78,24 @@ impl ActionControllerManager {
         Self {
             nodeagent_nodes: Vec::new(),
             state_sender: StateManagerSender::new(),
+            actions: ActionRegistry::default(),
         }
     }
 
+    /// Registers the handler of a new scenario action
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the action is already registered.
+    pub fn register_action(
+        &mut self,
+        name: &str,
+        handler: std::sync::Arc<dyn ActionHandler>,
+    ) -> Result<()> {
+        self.actions.register(name, handler)?;
+        Ok(())
+    }
+
     /// Fetches node role information from etcd
     ///
     /// Retrieves node information from etcd to determine if it is a nodeagent node.
@@ -116,6 +149,19 @@ impl ActionControllerManager {
         Ok(role)
     }
 
+    /// Whether a node is managed by NodeAgent
+    ///
+    /// Besides the nodes known at startup, the host of the current
+    /// settings.yaml counts, so a reloaded host name is accepted without
+    /// a restart.
+    fn is_nodeagent_node(&self, node_name: &str) -> bool {
+        if self.nodeagent_nodes.iter().any(|node| node == node_name) {
+            return true;
+        }
+        let config = common::setting::get_config();
+        config.host.name == node_name && config.host.r#type != "bluechi"
+    }
+
     /// Get fallback node IP from settings.yaml
     fn get_fallback_node_ip(&self, node_name: &str) -> Result<String> {
         let config = common::setting::get_config();
@@ -159,7 +205,7 @@ impl ActionControllerManager {
                         model_node,
                         e
                     );
-                    if self.nodeagent_nodes.contains(&model_node) {
+                    if self.is_nodeagent_node(&model_node) {
                         node_roles.insert(model_node.clone(), NODE_TYPE_NODEAGENT.to_string());
                         logd!(
                             2,
@@ -174,36 +220,287 @@ impl ActionControllerManager {
         node_roles
     }
 
+    /// Assign nodes to the models of a package with `node: auto`
+    ///
+    /// `launch` places every such model with the scheduling policy of the
+    /// package and records the node under `Placement/<model>`. Other actions
+    /// reuse the recorded node, and `update`, `rollback` and `create` place
+    /// models that have none. Models still on `auto` afterwards are skipped
+    /// like models on unknown nodes. Models are placed in the order of the
+    /// package, each on the node of the models it must share once one of
+    /// them has a node, and off the nodes of the models it must avoid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the nodes cannot be read or no node satisfies the
+    /// placement constraints of a model and has enough free CPU and memory
+    /// for it.
+    ///
+    /// With `dry_run`, nodes are assigned without being recorded.
+    async fn place_auto_models(
+        &self,
+        package: &mut Package,
+        action: &str,
+        dry_run: bool,
+    ) -> Result<()> {
+        let mut unplaced = Vec::new();
+        for (index, mi) in package.get_models_mut().iter_mut().enumerate() {
+            if !mi.is_auto_node() {
+                continue;
+            }
+            if action != "launch" {
+                let placement_key = format!("{}/{}", ETCD_PLACEMENT_PREFIX, mi.get_name());
+                if let Ok(node) = common::etcd::get(&placement_key).await {
+                    mi.set_node(&node);
+                    continue;
+                }
+                if !matches!(action, "update" | "rollback" | "create") {
+                    logd!(
+                        4,
+                        "Warning: Model '{}' has no recorded node for '{}'",
+                        mi.get_name(),
+                        action
+                    );
+                    continue;
+                }
+            }
+            unplaced.push(index);
+        }
+        if unplaced.is_empty() {
+            return Ok(());
+        }
+
+        let policy = package.get_scheduling_policy();
+        let mut nodes = crate::placement::load_nodes().await?;
+        for index in unplaced {
+            let model_name = package.get_models()[index].get_name();
+            let rule = crate::placement::AffinityRule::of(package, &model_name);
+            let pod_str = common::etcd::get(&format!("{}/{}", ETCD_POD_PREFIX, model_name)).await?;
+            let pod: Pod = serde_yaml::from_str(&pod_str)
+                .map_err(|e| format!("Failed to parse pod of model '{}': {}", model_name, e))?;
+            let request = pod.get_resource_request();
+
+            let mi = &mut package.get_models_mut()[index];
+            let candidates: Vec<crate::placement::NodeCapacity> = nodes
+                .iter()
+                .filter(|node| rule.allows(&node.name))
+                .cloned()
+                .collect();
+            let chosen = crate::placement::select_node(&candidates, mi, &request, &policy)
+                .ok_or_else(|| {
+                    if candidates.is_empty() && !nodes.is_empty() {
+                        rule.reason()
+                    } else {
+                        crate::placement::unschedulable_reason(&candidates, mi, &request)
+                    }
+                })?;
+            let node = candidates[chosen].name.clone();
+            if let Some(capacity) = nodes.iter_mut().find(|n| n.name == node) {
+                capacity.reserve(&request);
+            }
+            logd!(
+                3,
+                "Model '{}' placed on node '{}' ({:?})",
+                model_name,
+                node,
+                policy
+            );
+
+            if !dry_run {
+                let placement_key = format!("{}/{}", ETCD_PLACEMENT_PREFIX, model_name);
+                if let Err(e) = common::etcd::put(&placement_key, &node).await {
+                    logd!(
+                        4,
+                        "Warning: Failed to record node of model '{}': {}",
+                        model_name,
+                        e
+                    );
+                }
+            }
+            mi.set_node(&node);
+        }
+
+        Ok(())
+    }
+
+    /// Reject a package whose models cannot run on their nodes
+    ///
+    /// Checks the `nodeSelector` and tolerations of every model against the
+    /// labels and taints of its node before `launch`, `update`, `rollback`
+    /// or `create` starts any workload, and that the nodes keep to the
+    /// `affinity` and `antiAffinity` rules of the package. Models placed by
+    /// `place_auto_models` already satisfy them; models still on `auto`
+    /// are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first constraint that a node does not satisfy.
+    async fn check_placement_constraints(&self, package: &Package, action: &str) -> Result<()> {
+        if !matches!(action, "launch" | "update" | "rollback" | "create") {
+            return Ok(());
+        }
+        for mi in package.get_models() {
+            if mi.is_auto_node() {
+                continue;
+            }
+            crate::placement::check_node(mi, &mi.get_node())
+                .await
+                .map_err(|e| format!("Package '{}' cannot be placed: {}", package.get_name(), e))?;
+        }
+        package
+            .check_affinity()
+            .map_err(|e| format!("Package '{}' cannot be placed: {}", package.get_name(), e))?;
+        Ok(())
+    }
+
+    /// Reject a package whose models do not fit the allocatable resources of their nodes
+    ///
+    /// Before `launch`, `update`, `rollback` or `create`, the CPU and memory
+    /// requested by the pod of every model are added to the allocations of
+    /// its node, replacing what the model had allocated before. The requests
+    /// of the package on each node must also stay within the quotas of the
+    /// node. Models still on `auto` and models without a stored pod are
+    /// skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the allocations cannot be read, a quota would be
+    /// exceeded or a node would allocate more than it can offer.
+    async fn admit_models(&self, package: &Package, action: &str) -> Result<()> {
+        if !matches!(action, "launch" | "update" | "rollback" | "create") {
+            return Ok(());
+        }
+        let mut requests = Vec::new();
+        let mut containers = Vec::new();
+        for mi in package.get_models() {
+            if mi.is_auto_node() {
+                continue;
+            }
+            let model_name = mi.get_name();
+            let Ok(pod_str) =
+                common::etcd::get(&format!("{}/{}", ETCD_POD_PREFIX, model_name)).await
+            else {
+                continue;
+            };
+            let pod: Pod = serde_yaml::from_str(&pod_str)
+                .map_err(|e| format!("Failed to parse pod of model '{}': {}", model_name, e))?;
+            requests.push(Allocation::new(
+                &model_name,
+                &mi.get_node(),
+                &pod.get_resource_request(),
+            ));
+            containers.push(pod.get_container_count());
+        }
+        if requests.is_empty() {
+            return Ok(());
+        }
+
+        let usage = common::quota::package_usage(requests.iter().zip(containers));
+        self.check_quotas(&usage)
+            .await
+            .map_err(|e| format!("Package '{}' cannot be admitted: {}", package.get_name(), e))?;
+
+        let nodes = common::allocation::node_allocations().await?;
+        common::allocation::admit(nodes, &requests)
+            .map_err(|e| format!("Package '{}' cannot be admitted: {}", package.get_name(), e))?;
+        Ok(())
+    }
+
+    /// Check the requests of a package on each node against the quotas
+    ///
+    /// The labels of the nodes are read from the node registry only if some
+    /// quota selects nodes by label.
+    async fn check_quotas(&self, usage: &[common::quota::PackageUsage]) -> Result<()> {
+        let quotas = common::quota::load().await?;
+        if quotas.is_empty() {
+            return Ok(());
+        }
+        let mut labels = HashMap::new();
+        if quotas.iter().any(|q| !q.node_selector.is_empty()) {
+            for node_usage in usage {
+                let key = format!("{}/{}", ETCD_CLUSTER_NODES_PREFIX, node_usage.node);
+                let Ok(json) = common::etcd::get(&key).await else {
+                    continue;
+                };
+                match serde_json::from_str::<common::apiserver::NodeInfo>(&json) {
+                    Ok(node_info) => {
+                        labels.insert(node_usage.node.clone(), node_info.metadata);
+                    }
+                    Err(e) => logd!(4, "Warning: Invalid node entry '{}': {}", key, e),
+                }
+            }
+        }
+        common::quota::check(&quotas, &labels, usage)?;
+        Ok(())
+    }
+
+    /// Record the resources of a started model on its node
+    async fn record_allocation(&self, model_name: &str, node: &str) {
+        let request = match common::etcd::get(&format!("{}/{}", ETCD_POD_PREFIX, model_name))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|pod| serde_yaml::from_str::<Pod>(&pod).map_err(|e| e.to_string()))
+        {
+            Ok(pod) => pod.get_resource_request(),
+            Err(e) => {
+                logd!(4, "Failed to read pod of model '{}': {}", model_name, e);
+                return;
+            }
+        };
+        let allocation = Allocation::new(model_name, node, &request);
+        if let Err(e) = common::allocation::record(&allocation).await {
+            logd!(
+                4,
+                "Failed to record allocation of model '{}': {}",
+                model_name,
+                e
+            );
+        }
+    }
+
     /// Get ETCD keys for scenario resources
+    ///
+    /// `scenario_name` is qualified by its namespace, the target package is
+    /// looked up in the namespace of the scenario and runs on the node group
+    /// of the scenario, if any.
     async fn get_scenario_resources(
         &self,
         scenario_name: &str,
     ) -> Result<(Scenario, Package, Option<String>, Option<String>)> {
-        let etcd_scenario_key = format!("{}/{}", ETCD_SCENARIO_PREFIX, scenario_name);
+        let etcd_scenario_key = namespace::qualified_key(ETCD_SCENARIO_PREFIX, scenario_name);
         let scenario_str = common::etcd::get(&etcd_scenario_key)
             .await
             .map_err(|e| format!("Scenario '{}' not found: {}", scenario_name, e))?;
         let scenario: Scenario = serde_yaml::from_str(&scenario_str)
             .map_err(|e| format!("Failed to parse scenario '{}': {}", scenario_name, e))?;
 
-        let etcd_package_key = format!("{}/{}", ETCD_PACKAGE_PREFIX, scenario.get_targets());
+        let etcd_package_key = namespace::artifact_key(
+            &scenario.get_namespace(),
+            ETCD_PACKAGE_PREFIX,
+            &scenario.get_targets(),
+        );
         let package_str = common::etcd::get(&etcd_package_key)
             .await
             .map_err(|e| format!("Package key '{}' not found: {}", etcd_package_key, e))?;
-        let package: Package = serde_yaml::from_str(&package_str).map_err(|e| {
+        let mut package: Package = serde_yaml::from_str(&package_str).map_err(|e| {
             format!(
                 "Failed to parse package '{}': {}",
                 scenario.get_targets(),
                 e
             )
         })?;
+        crate::pattern::target_group(&scenario, &mut package).await;
 
-        let network_str = common::etcd::get(&format!("{}/{}", ETCD_NETWORK_PREFIX, scenario_name))
-            .await
-            .ok();
-        let node_str = common::etcd::get(&format!("{}/{}", ETCD_NODE_PREFIX, scenario_name))
-            .await
-            .ok();
+        let network_str = common::etcd::get(&namespace::qualified_key(
+            ETCD_NETWORK_PREFIX,
+            scenario_name,
+        ))
+        .await
+        .ok();
+        let node_str =
+            common::etcd::get(&namespace::qualified_key(ETCD_NODE_PREFIX, scenario_name))
+                .await
+                .ok();
 
         Ok((scenario, package, network_str, node_str))
     }
@@ -222,7 +519,11 @@ impl ActionControllerManager {
     ) -> Result<()> {
         let model_name = model_info.get_name();
         let model_node = model_info.get_node();
-        let pod = common::etcd::get(&format!("{}/{}", ETCD_POD_PREFIX, model_name)).await?;
+        let pod = if action == "rollback" {
+            restore_pod_revision(&model_name).await?
+        } else {
+            common::etcd::get(&format!("{}/{}", ETCD_POD_PREFIX, model_name)).await?
+        };
 
         // Inject annotations into pod YAML for tracking
         let pod_with_annotations = self.inject_pod_annotations(
@@ -259,7 +560,7 @@ impl ActionControllerManager {
                     .await?;
             }
             _ => {
-                // Ignore unknown actions
+                return Err(format!("Action '{}' runs no workload operation", action).into());
             }
         }
 
@@ -343,7 +644,8 @@ impl ActionControllerManager {
     /// Handle realtime scheduling for a model
     async fn handle_realtime_sched(&self, sched: &str) -> Result<()> {
         use common::external::timpani::{SchedInfo, TaskInfo};
-        let sched_str = common::etcd::get(&format!("{}/{}", ETCD_SCHED_PREFIX, sched)).await?;
+        let sched_str =
+            common::etcd::get(&namespace::qualified_key(ETCD_SCHED_PREFIX, sched)).await?;
         let schedule: Schedule = serde_yaml::from_str(&sched_str)?;
         let spec_vec = schedule
             .get_spec()
@@ -394,6 +696,8 @@ impl ActionControllerManager {
             transition_id: format!("actioncontroller-processing-complete-{}", timestamp),
             timestamp_ns: timestamp,
             source: "actioncontroller".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: common::trace::current_trace_id().unwrap_or_default(),
         };
 
         if let Err(e) = self
@@ -428,9 +732,13 @@ impl ActionControllerManager {
     ) -> Result<()> {
         match node_type {
             NODE_TYPE_NODEAGENT => match operation {
+                "create" => crate::runtime::nodeagent::create_workload(pod, node_name).await?,
                 "start" => crate::runtime::nodeagent::start_workload(pod, node_name).await?,
                 "stop" => crate::runtime::nodeagent::stop_workload(pod, node_name).await?,
                 "restart" => crate::runtime::nodeagent::restart_workload(pod, node_name).await?,
+                "pause" => crate::runtime::nodeagent::pause_workload(pod, node_name).await?,
+                "unpause" => crate::runtime::nodeagent::unpause_workload(pod, node_name).await?,
+                "remove" => crate::runtime::nodeagent::remove_workload(pod, node_name).await?,
                 _ => return Err(format!("Unknown operation '{}'", operation).into()),
             },
             _ => {
@@ -444,6 +752,88 @@ impl ActionControllerManager {
         Ok(())
     }
 
+    /// Apply a workload operation to every model of a scenario's package
+    ///
+    /// Models whose node role cannot be determined are skipped, matching
+    /// the behavior of `trigger_manager_action`.
+    async fn execute_scenario_operation(&self, scenario_name: &str, operation: &str) -> Result<()> {
+        if scenario_name.trim().is_empty() {
+            return Err(format!("Scenario '{}' is invalid: cannot be empty", scenario_name).into());
+        }
+
+        let (_scenario, mut package, _network_str, _node_str) =
+            self.get_scenario_resources(scenario_name).await?;
+        crate::pattern::expand_models(&mut package, operation, false).await?;
+        self.place_auto_models(&mut package, operation, false)
+            .await?;
+        self.check_placement_constraints(&package, operation)
+            .await?;
+        self.admit_models(&package, operation).await?;
+        let node_roles = self.load_node_roles(&package).await;
+        let policy_name = package.get_policy().clone().unwrap_or_default();
+        let package_name = package.get_qualified_name();
+
+        let context = ModelActionContext {
+            action: operation,
+            scenario_name,
+            package_name: &package_name,
+            policy_name: &policy_name,
+            network_str: &None,
+            node_str: &None,
+            node_roles: &node_roles,
+        };
+        let operations = package
+            .get_models()
+            .iter()
+            .map(|mi| self.run_workload_operation(&context, mi))
+            .collect();
+        run_model_operations(operations).await
+    }
+
+    /// Apply a workload operation of a scenario to one model of its package
+    async fn run_workload_operation(
+        &self,
+        context: &ModelActionContext<'_>,
+        mi: &ModelInfo,
+    ) -> std::result::Result<(), String> {
+        let model_name = mi.get_name();
+        let model_node = mi.get_node();
+        let Some(node_type) = context.node_roles.get(&model_node) else {
+            logd!(
+                4,
+                "Warning: Node '{}' is not configured or cannot determine its role. Skipping '{}'.",
+                model_node,
+                context.action
+            );
+            return Ok(());
+        };
+
+        let result = async {
+            let pod = common::etcd::get(&format!("{}/{}", ETCD_POD_PREFIX, model_name)).await?;
+            let pod_with_annotations = self.inject_pod_annotations(
+                &pod,
+                context.scenario_name,
+                context.package_name,
+                context.policy_name,
+                &model_name,
+            )?;
+            self.execute_workload_operation(
+                context.action,
+                &pod_with_annotations,
+                &model_node,
+                node_type,
+            )
+            .await
+        }
+        .await;
+        result.map_err(|e| {
+            format!(
+                "Failed to {} workload for model '{}': {}",
+                context.action, model_name, e
+            )
+        })
+    }
+
     /// Processes a trigger action request for a specific scenario
     ///
     /// Retrieves scenario information from ETCD and performs the
@@ -462,6 +852,7 @@ impl ActionControllerManager {
     ///
     /// Returns an error if:
     /// - The scenario does not exist
+    /// - The action is not registered or its `actionParams` are invalid
     /// - The scenario is not allowed by policy
     /// - The runtime operation fails
     pub async fn trigger_manager_action(&self, scenario_name: &str) -> Result<()> {
@@ -471,148 +862,859 @@ impl ActionControllerManager {
             return Err(format!("Scenario '{}' is invalid: cannot be empty", scenario_name).into());
         }
 
+        // Expired scenarios are kept from running until they are applied again
+        let state_key = format!("/scenario/{}/state", scenario_name);
+        let expired = ScenarioState::Expired.as_str_name();
+        if common::etcd::get(&state_key).await.ok().as_deref() == Some(expired) {
+            return Err(format!(
+                "Scenario '{}' has expired, apply it again to restart it",
+                scenario_name
+            )
+            .into());
+        }
+
         let (scenario, package, network_str, node_str) =
             self.get_scenario_resources(scenario_name).await?;
         let action = scenario.get_actions();
-        let node_roles = self.load_node_roles(&package).await;
-
-        // Get policy name and package name for annotation injection
-        let policy_name = package.get_policy().clone().unwrap_or_default();
-        let package_name = package.get_name();
+        let handler = self.actions.get(&action)?;
+        let params =
+            ActionParams::validate(&action, handler.params(), scenario.get_action_params())?;
 
-        for mi in package.get_models() {
-            let model_name = mi.get_name();
-            let mut target_node = mi.get_node();
+        // Scenarios with dependsOn wait until their prerequisites complete
+        let unmet = crate::dependency::unmet_dependencies(&scenario, None).await;
+        if !unmet.is_empty() {
+            logd!(
+                3,
+                "Scenario '{}' is blocked until {} completed",
+                scenario_name,
+                unmet.join(", ")
+            );
+            crate::dependency::block(scenario_name, &unmet).await?;
+            return Ok(());
+        }
+        if !scenario.get_depends_on().is_empty() {
+            crate::dependency::unblock(scenario_name).await;
+        }
 
-            // Check policy only for launch action
-            if action == "launch" && !policy_name.is_empty() {
+        // Scenarios with allowedModes run only in one of those vehicle modes
+        let (decision, mode) = crate::vehicle_mode::check(scenario_name, &scenario).await?;
+        match decision {
+            Decision::Run => {}
+            Decision::Defer(deadline) => {
                 logd!(
-                    2,
-                    "Checking policy '{}' for model '{}' on node '{}'",
-                    policy_name,
-                    model_name,
-                    target_node
+                    3,
+                    "Scenario '{}' is deferred until the vehicle is {} (deadline {})",
+                    scenario_name,
+                    scenario.get_allowed_modes().join(" or "),
+                    deadline
                 );
-
-                match crate::grpc::sender::policymanager::check_node_policy(
-                    &policy_name,
-                    &target_node,
+                return Ok(());
+            }
+            Decision::Deny => {
+                self.notify_state_change(scenario_name, "allowed", "denied")
+                    .await;
+                return Err(format!(
+                    "Scenario '{}' requires vehicle mode {}, current mode is {}",
+                    scenario_name,
+                    scenario.get_allowed_modes().join(" or "),
+                    common::vehicle_mode::describe(&mode)
                 )
-                .await
-                {
-                    Ok(result) => {
-                        if !result.allowed {
-                            // Node not allowed, try suggested node
-                            if let Some(suggested) = result.suggested_node {
-                                logd!(
-                                    3,
-                                    "Node '{}' not allowed, using suggested node '{}'",
-                                    target_node,
-                                    suggested
-                                );
-                                target_node = suggested;
-                            } else {
-                                logd!(
-                                    4,
-                                    "Node '{}' not allowed and no suggested node available. Skipping model '{}'.",
-                                    target_node,
-                                    model_name
-                                );
-                                continue;
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        logd!(
-                            4,
-                            "Policy check failed: {}. Proceeding with original node '{}'.",
-                            e,
-                            target_node
-                        );
-                        // Fail-open: proceed with original node if policy check fails
-                    }
-                }
+                .into());
             }
+        }
 
-            let node_type = match node_roles.get(&target_node) {
-                Some(role) => {
-                    logd!(2, "Using node {} as {}", target_node, role);
-                    role.as_str()
-                }
-                None => {
-                    logd!(4, "Warning: Node '{}' is not configured or cannot determine its role. Skipping deployment.", target_node);
-                    continue;
-                }
-            };
+        let mut request = ActionRequest {
+            action,
+            scenario_name: scenario_name.to_string(),
+            scenario,
+            package,
+            params,
+            network_str,
+            node_str,
+        };
+        handler.run(self, &mut request).await?;
 
-            logd!(
-                2,
-                "Processing model '{}' on node '{}' with action '{}'",
-                model_name,
-                target_node,
-                action
-            );
+        self.finish_manager_action(scenario_name, &request.action, &request.package)
+            .await
+    }
 
-            self.execute_model_action(
-                &action,
-                &mi,
-                node_type,
-                scenario_name,
-                &package_name,
-                &policy_name,
-                &network_str,
-                &node_str,
-            )
+    /// Run an action other than its own on the target package of a scenario
+    ///
+    /// StateManager rolls back the models of a failed scenario and terminates
+    /// expired ones this way. Dependencies and vehicle modes are not checked,
+    /// and the action takes no parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the scenario does not exist, the action is not
+    /// registered or the runtime operation fails
+    pub async fn run_scenario_action(&self, scenario_name: &str, action: &str) -> Result<()> {
+        logd!(
+            2,
+            "run_scenario_action in manager {:?}: {}",
+            scenario_name,
+            action
+        );
+
+        let (scenario, package, network_str, node_str) =
+            self.get_scenario_resources(scenario_name).await?;
+        let handler = self.actions.get(action)?;
+        let params = ActionParams::validate(action, handler.params(), HashMap::new())?;
+
+        let mut request = ActionRequest {
+            action: action.to_string(),
+            scenario_name: scenario_name.to_string(),
+            scenario,
+            package,
+            params,
+            network_str,
+            node_str,
+        };
+        handler.run(self, &mut request).await?;
+
+        self.finish_manager_action(scenario_name, &request.action, &request.package)
             .await
-            .map_err(|e| {
-                format!(
-                    "Failed to execute action '{}' on model '{}': {}",
-                    action, model_name, e
-                )
-            })?;
-        }
+    }
 
-        // Delete policy from etcd when terminate action completes
-        if action == "terminate" && !policy_name.is_empty() {
-            let policy_key = format!("Policy/{}", policy_name);
-            match common::etcd::delete(&policy_key).await {
-                Ok(_) => {
-                    logd!(
-                        2,
-                        "Deleted policy '{}' from etcd after terminate action",
-                        policy_name
-                    );
-                }
-                Err(e) => {
-                    logd!(
-                        4,
-                        "Warning: Failed to delete policy '{}' from etcd: {}",
-                        policy_name,
-                        e
-                    );
-                    // Don't fail the whole operation if policy deletion fails
-                }
-            }
+    /// Run a workload action on every model of the target package
+    ///
+    /// The models are expanded, placed and admitted first. Rolling updates
+    /// replace the models batch by batch, other actions handle every model
+    /// at once.
+    pub(crate) async fn run_workload_action(&self, request: &mut ActionRequest) -> Result<()> {
+        let action = request.action.as_str();
+        let scenario_name = request.scenario_name.as_str();
+        let package = &mut request.package;
+        crate::pattern::expand_models(package, action, false).await?;
+        if action == "launch" {
+            crate::pattern::record_group(&request.scenario, package).await?;
         }
+        self.place_auto_models(package, action, false).await?;
+        self.check_placement_constraints(package, action).await?;
+        self.admit_models(package, action).await?;
+        let node_roles = self.load_node_roles(package).await;
 
-        if let Some(sched) = package.get_schedule() {
-            self.handle_realtime_sched(sched).await?;
+        // Get policy name and package name for annotation injection
+        let policy_name = package.get_policy().clone().unwrap_or_default();
+        let package_name = package.get_qualified_name();
+
+        // Rolling and canary updates replace models batch by batch instead of all at once
+        if action == "update" {
+            if let Some(strategy) = package
+                .get_update_strategy()
+                .as_ref()
+                .filter(|s| s.is_staged())
+            {
+                return self
+                    .rolling_update(scenario_name, package, strategy, &node_roles)
+                    .await;
+            }
         }
 
-        self.notify_state_change(scenario_name, "allowed", "completed")
-            .await;
-
-        Ok(())
+        let context = ModelActionContext {
+            action,
+            scenario_name,
+            package_name: &package_name,
+            policy_name: &policy_name,
+            network_str: &request.network_str,
+            node_str: &request.node_str,
+            node_roles: &node_roles,
+        };
+        let operations = package
+            .get_models()
+            .iter()
+            .map(|mi| self.run_model_action(&context, mi))
+            .collect();
+        run_model_operations(operations).await
     }
 
-    /// Reconciles current and desired states for a scenario
+    /// Plan the action of a scenario without executing it
     ///
-    /// Compares the current state with the desired state for a given scenario
-    /// and performs the necessary actions to align them.
+    /// Walks the same package resolution, node selection and admission as
+    /// `trigger_manager_action`, but automatic placements are not recorded
+    /// and no workload operation is sent. Models the real trigger would skip
+    /// are reported as warnings of the plan.
     ///
-    /// # Arguments
+    /// # Errors
     ///
-    /// * `scenario_name` - Name of the scenario
+    /// Returns an error if the scenario, its package or a node cannot be
+    /// resolved, if the action or its parameters are invalid, or if the
+    /// models would not be admitted.
+    pub async fn plan_manager_action(&self, scenario_name: &str) -> Result<ExecutionPlan> {
+        logd!(2, "plan_manager_action in manager {:?}", scenario_name);
+
+        if scenario_name.trim().is_empty() {
+            return Err(format!("Scenario '{}' is invalid: cannot be empty", scenario_name).into());
+        }
+
+        let (scenario, mut package, network_str, node_str) =
+            self.get_scenario_resources(scenario_name).await?;
+        let action = scenario.get_actions();
+        let package_name = package.get_qualified_name();
+        let handler = self.actions.get(&action)?;
+        ActionParams::validate(&action, handler.params(), scenario.get_action_params())?;
+        let mut plan = PlanBuilder::new(scenario_name, &package_name, &action);
+
+        let unmet = crate::dependency::unmet_dependencies(&scenario, None).await;
+        if !unmet.is_empty() {
+            plan.warn(format!(
+                "Scenario '{}' would be blocked until {} completed",
+                scenario_name,
+                unmet.join(", ")
+            ));
+            return Ok(plan.finish());
+        }
+
+        let allowed_modes = scenario.get_allowed_modes();
+        if !allowed_modes.is_empty() {
+            let mode = crate::vehicle_mode::current_mode().await;
+            if !common::vehicle_mode::is_allowed(&allowed_modes, &mode) {
+                plan.warn(format!(
+                    "Scenario '{}' requires vehicle mode {}, current mode is {}",
+                    scenario_name,
+                    allowed_modes.join(" or "),
+                    common::vehicle_mode::describe(&mode)
+                ));
+            }
+        }
+
+        if !handler.runs_workloads() {
+            plan.warn(format!("Action '{}' runs no workload operation", action));
+            return Ok(plan.finish());
+        }
+
+        let pattern = package.get_pattern();
+        crate::pattern::expand_models(&mut package, &action, true).await?;
+        let auto_models: Vec<String> = package
+            .get_models()
+            .iter()
+            .filter(|mi| mi.is_auto_node())
+            .map(|mi| mi.get_name())
+            .collect();
+        self.place_auto_models(&mut package, &action, true).await?;
+        self.check_placement_constraints(&package, &action).await?;
+        self.admit_models(&package, &action).await?;
+        let node_roles = self.load_node_roles(&package).await;
+
+        let Some(operation) = crate::plan::workload_operation(&action) else {
+            plan.warn(format!("Action '{}' runs no workload operation", action));
+            return Ok(plan.finish());
+        };
+
+        // Rolling and canary updates replace models batch by batch on their own nodes
+        if action == "update" {
+            if let Some(strategy) = package
+                .get_update_strategy()
+                .as_ref()
+                .filter(|s| s.is_staged())
+            {
+                let mut batch = 0;
+                let targets: Vec<&ModelInfo> = package
+                    .get_models()
+                    .iter()
+                    .filter(|mi| {
+                        let known = node_roles.contains_key(&mi.get_node());
+                        if !known {
+                            plan.warn(format!(
+                                "Node '{}' has no known role, update of '{}' would be skipped",
+                                mi.get_node(),
+                                mi.get_name()
+                            ));
+                        }
+                        known
+                    })
+                    .collect();
+                if strategy.requires_pre_pull() {
+                    for mi in &targets {
+                        let node = mi.get_node();
+                        plan.step(
+                            &node,
+                            &node_roles[&node],
+                            batch,
+                            &mi.get_name(),
+                            "prepull",
+                            "pull images before the first batch".to_string(),
+                        );
+                    }
+                }
+                let canary = strategy.get_canary();
+                let canary_node = canary.and_then(|c| c.get_node(package.get_models()));
+                let batches = crate::canary::update_batches(
+                    strategy,
+                    canary_node.as_deref(),
+                    &targets,
+                    |mi| mi.get_node(),
+                );
+                if let (Some(node), Some(first)) = (&canary_node, batches.first()) {
+                    if first.is_empty() {
+                        plan.warn(format!(
+                            "Canary node '{}' runs none of the models, the update would fail",
+                            node
+                        ));
+                    }
+                }
+                for (index, models) in batches.iter().enumerate() {
+                    batch += 1;
+                    let details = match canary {
+                        Some(canary) if index == 0 => format!(
+                            "canary, next batch waits until Running and healthy for {}s",
+                            canary.get_soak_secs()
+                        ),
+                        _ => "rolling update, next batch waits until Running".to_string(),
+                    };
+                    for mi in models {
+                        let node = mi.get_node();
+                        plan.step(
+                            &node,
+                            &node_roles[&node],
+                            batch,
+                            &mi.get_name(),
+                            operation,
+                            details.clone(),
+                        );
+                    }
+                }
+                return Ok(plan.finish());
+            }
+        }
+
+        let policy_name = package.get_policy().clone().unwrap_or_default();
+        let context = ModelActionContext {
+            action: &action,
+            scenario_name,
+            package_name: &package_name,
+            policy_name: &policy_name,
+            network_str: &network_str,
+            node_str: &node_str,
+            node_roles: &node_roles,
+        };
+        for mi in package.get_models() {
+            let model_name = mi.get_name();
+            let node = match self.target_node(&context, mi).await {
+                Ok(node) => node,
+                Err(reason) => {
+                    plan.warn(format!("{}, '{}' would be skipped", reason, model_name));
+                    continue;
+                }
+            };
+            let Some(node_type) = node_roles.get(&node) else {
+                plan.warn(format!(
+                    "Node '{}' has no known role, '{}' would be skipped",
+                    node, model_name
+                ));
+                continue;
+            };
+
+            let mut details = Vec::new();
+            if auto_models.contains(&model_name) {
+                details.push("placed automatically".to_string());
+            }
+            if !pattern.is_plain() {
+                details.push(format!("{} pattern replica", pattern.get_type().as_str()));
+            }
+            if node != mi.get_node() {
+                details.push(format!("moved from '{}' by policy", mi.get_node()));
+            }
+            if action == "launch" && network_str.is_some() && node_str.is_some() {
+                details.push("network pod requested".to_string());
+            }
+            plan.step(
+                &node,
+                node_type,
+                1,
+                &model_name,
+                operation,
+                details.join(", "),
+            );
+        }
+
+        Ok(plan.finish())
+    }
+
+    /// Node a model of a scenario action runs on
+    ///
+    /// For `launch`, the policy of the package may move the model to a
+    /// suggested node, which must satisfy the model's constraints too.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(node)` - the node to run the model on
+    /// * `Err(reason)` - why the model is skipped
+    async fn target_node(
+        &self,
+        context: &ModelActionContext<'_>,
+        mi: &ModelInfo,
+    ) -> std::result::Result<String, String> {
+        let model_name = mi.get_name();
+        let mut target_node = mi.get_node();
+
+        // Check policy only for launch action
+        if context.action == "launch" && !context.policy_name.is_empty() {
+            logd!(
+                2,
+                "Checking policy '{}' for model '{}' on node '{}'",
+                context.policy_name,
+                model_name,
+                target_node
+            );
+
+            match crate::grpc::sender::policymanager::check_node_policy(
+                context.policy_name,
+                &target_node,
+            )
+            .await
+            {
+                Ok(result) => {
+                    if !result.allowed {
+                        // Node not allowed, try suggested node
+                        if let Some(suggested) = result.suggested_node {
+                            logd!(
+                                3,
+                                "Node '{}' not allowed, using suggested node '{}'",
+                                target_node,
+                                suggested
+                            );
+                            target_node = suggested;
+                        } else {
+                            return Err(format!(
+                                "Node '{}' not allowed and no suggested node available",
+                                target_node
+                            ));
+                        }
+                    }
+                }
+                Err(e) => {
+                    logd!(
+                        4,
+                        "Policy check failed: {}. Proceeding with original node '{}'.",
+                        e,
+                        target_node
+                    );
+                    // Fail-open: proceed with original node if policy check fails
+                }
+            }
+        }
+
+        // A node suggested by the policy must satisfy the model's constraints too
+        if target_node != mi.get_node() {
+            if let Err(e) = crate::placement::check_node(mi, &target_node).await {
+                return Err(format!(
+                    "Suggested node '{}' is not usable: {}",
+                    target_node, e
+                ));
+            }
+        }
+
+        Ok(target_node)
+    }
+
+    /// Run the action of a scenario on one model of its package
+    ///
+    /// Models whose node is not allowed by the policy or whose node role is
+    /// unknown are skipped.
+    async fn run_model_action(
+        &self,
+        context: &ModelActionContext<'_>,
+        mi: &ModelInfo,
+    ) -> std::result::Result<(), String> {
+        let model_name = mi.get_name();
+        let target_node = match self.target_node(context, mi).await {
+            Ok(node) => node,
+            Err(reason) => {
+                logd!(4, "{}. Skipping model '{}'.", reason, model_name);
+                return Ok(());
+            }
+        };
+
+        let node_type = match context.node_roles.get(&target_node) {
+            Some(role) => {
+                logd!(2, "Using node {} as {}", target_node, role);
+                role.as_str()
+            }
+            None => {
+                logd!(4, "Warning: Node '{}' is not configured or cannot determine its role. Skipping deployment.", target_node);
+                return Ok(());
+            }
+        };
+
+        logd!(
+            2,
+            "Processing model '{}' on node '{}' with action '{}'",
+            model_name,
+            target_node,
+            context.action
+        );
+
+        self.execute_model_action(
+            context.action,
+            mi,
+            node_type,
+            context.scenario_name,
+            context.package_name,
+            context.policy_name,
+            context.network_str,
+            context.node_str,
+        )
+        .await
+        .map_err(|e| {
+            format!(
+                "Failed to execute action '{}' on model '{}': {}",
+                context.action, model_name, e
+            )
+        })?;
+
+        if context.action == "terminate" {
+            if let Err(e) = common::allocation::release(&model_name).await {
+                logd!(
+                    4,
+                    "Failed to release allocation of model '{}': {}",
+                    model_name,
+                    e
+                );
+            }
+        } else {
+            self.record_pod_revision(&model_name).await;
+            self.record_allocation(&model_name, &target_node).await;
+        }
+        Ok(())
+    }
+
+    /// Common steps once every model of a scenario action was handled
+    ///
+    /// Removes the policy after `terminate`, registers realtime scheduling
+    /// and notifies StateManager that the scenario action completed.
+    async fn finish_manager_action(
+        &self,
+        scenario_name: &str,
+        action: &str,
+        package: &Package,
+    ) -> Result<()> {
+        let policy_name = package.get_policy().clone().unwrap_or_default();
+
+        // Delete policy from etcd when terminate action completes
+        if action == "terminate" && !policy_name.is_empty() {
+            let policy_key = format!("Policy/{}", policy_name);
+            match common::etcd::delete(&policy_key).await {
+                Ok(_) => {
+                    logd!(
+                        2,
+                        "Deleted policy '{}' from etcd after terminate action",
+                        policy_name
+                    );
+                }
+                Err(e) => {
+                    logd!(
+                        4,
+                        "Warning: Failed to delete policy '{}' from etcd: {}",
+                        policy_name,
+                        e
+                    );
+                    // Don't fail the whole operation if policy deletion fails
+                }
+            }
+        }
+
+        // Models placed automatically get a new node on their next launch
+        if action == "terminate" {
+            for mi in package.get_models() {
+                let placement_key = format!("{}/{}", ETCD_PLACEMENT_PREFIX, mi.get_name());
+                let _ = common::etcd::delete(&placement_key).await;
+            }
+        }
+
+        if let Some(sched) = package.get_schedule() {
+            self.handle_realtime_sched(&namespace::qualified_name(&package.get_namespace(), sched))
+                .await?;
+        }
+
+        self.notify_state_change(scenario_name, "allowed", "completed")
+            .await;
+
+        if action == "update" {
+            common::eventbus::publish(
+                Event::new(
+                    EventKind::UpdateCompleted,
+                    "actioncontroller",
+                    scenario_name,
+                    format!("Package '{}' updated", package.get_name()),
+                )
+                .with_attribute("package", &package.get_name()),
+            );
+        }
+
+        for dependent in crate::dependency::released_by(scenario_name).await {
+            logd!(
+                2,
+                "Scenario '{}' completed, running dependent scenario '{}'",
+                scenario_name,
+                dependent
+            );
+            let result = Box::pin(self.trigger_manager_action(&dependent))
+                .await
+                .map_err(|e| e.to_string());
+            if let Err(e) = result {
+                logd!(4, "Dependent scenario '{}' failed: {}", dependent, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Updates the models of a package batch by batch
+    ///
+    /// Each batch of `maxUnavailable` models is restarted with the new pod,
+    /// then the model states reported by StateManager (`/model/<name>/state`)
+    /// are polled until every model of the batch is Running. With a canary
+    /// stage, the models of the canary node are the first batch and must stay
+    /// Running and healthy for the soak time before the others are updated.
+    /// If a batch fails, every model updated so far is restored from its last
+    /// recorded revision.
+    ///
+    /// # Arguments
+    ///
+    /// * `scenario_name` - Name of the scenario
+    /// * `package` - Package whose models are updated
+    /// * `strategy` - Rolling update settings of the package
+    /// * `node_roles` - Role of each node used by the package
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if every model reached Running
+    /// * `Err(...)` if a model failed and the update was rolled back
+    async fn rolling_update(
+        &self,
+        scenario_name: &str,
+        package: &Package,
+        strategy: &UpdateStrategy,
+        node_roles: &HashMap<String, String>,
+    ) -> Result<()> {
+        let policy_name = package.get_policy().clone().unwrap_or_default();
+        let package_name = package.get_qualified_name();
+        let timeout = Duration::from_secs(strategy.get_timeout_secs());
+
+        let targets: Vec<(&ModelInfo, &str)> = package
+            .get_models()
+            .iter()
+            .filter_map(|mi| match node_roles.get(&mi.get_node()) {
+                Some(role) => Some((mi, role.as_str())),
+                None => {
+                    logd!(4, "Warning: Node '{}' is not configured or cannot determine its role. Skipping update of '{}'.", mi.get_node(), mi.get_name());
+                    None
+                }
+            })
+            .collect();
+
+        if strategy.requires_pre_pull() {
+            self.prepull_models(&targets).await.map_err(|e| {
+                format!(
+                    "Rolling update of '{}' stopped before the first batch: {}",
+                    package_name, e
+                )
+            })?;
+        }
+
+        let canary = strategy.get_canary();
+        let canary_node = canary.and_then(|c| c.get_node(package.get_models()));
+        let batches =
+            crate::canary::update_batches(strategy, canary_node.as_deref(), &targets, |(mi, _)| {
+                mi.get_node()
+            });
+        if let (Some(node), Some(first)) = (&canary_node, batches.first()) {
+            if first.is_empty() {
+                return Err(format!(
+                    "Canary update of '{}' stopped: node '{}' runs none of its models",
+                    package_name, node
+                )
+                .into());
+            }
+        }
+
+        let mut updated: Vec<(&ModelInfo, &str)> = Vec::new();
+        for (index, batch) in batches.iter().enumerate() {
+            for &(mi, node_type) in batch {
+                // Forget the state of the old workload so only a fresh report counts
+                let _ = common::etcd::delete(&model_state_key(&mi.get_name())).await;
+                updated.push((mi, node_type));
+
+                // Errors are turned into strings before awaiting the rollback
+                let result = self
+                    .execute_model_action(
+                        "update",
+                        mi,
+                        node_type,
+                        scenario_name,
+                        &package_name,
+                        &policy_name,
+                        &None,
+                        &None,
+                    )
+                    .await
+                    .map_err(|e| e.to_string());
+                if let Err(e) = result {
+                    self.rollback_models(scenario_name, &package_name, &policy_name, &updated)
+                        .await;
+                    return Err(format!(
+                        "Rolling update of '{}' failed on model '{}': {}",
+                        package_name,
+                        mi.get_name(),
+                        e
+                    )
+                    .into());
+                }
+            }
+
+            for &(mi, _) in batch {
+                let result = wait_for_model_running(&mi.get_name(), timeout)
+                    .await
+                    .map_err(|e| e.to_string());
+                if let Err(e) = result {
+                    self.rollback_models(scenario_name, &package_name, &policy_name, &updated)
+                        .await;
+                    return Err(
+                        format!("Rolling update of '{}' failed: {}", package_name, e).into(),
+                    );
+                }
+            }
+
+            if let (Some(canary), Some(node), 0) = (canary, &canary_node, index) {
+                let model_names: Vec<String> = batch.iter().map(|(mi, _)| mi.get_name()).collect();
+                let result = crate::canary::soak(canary, node, &model_names)
+                    .await
+                    .map_err(|e| e.to_string());
+                if let Err(e) = result {
+                    self.rollback_models(scenario_name, &package_name, &policy_name, &updated)
+                        .await;
+                    return Err(
+                        format!("Canary update of '{}' rolled back: {}", package_name, e).into(),
+                    );
+                }
+                logd!(
+                    2,
+                    "Canary of '{}' on node '{}' is healthy, updating the other nodes",
+                    package_name,
+                    node
+                );
+            }
+
+            for &(mi, _) in batch {
+                self.record_pod_revision(&mi.get_name()).await;
+                self.record_allocation(&mi.get_name(), &mi.get_node()).await;
+                logd!(2, "Model '{}' updated and Running", mi.get_name());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Pulls the images of the models on their nodes
+    ///
+    /// Every node pulls the images of all its models at the same time as the
+    /// other nodes. Nodes not managed by NodeAgent are skipped.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if every image was pulled and matches its digest
+    /// * `Err(...)` with the failures of every node otherwise
+    async fn prepull_models(&self, models: &[(&ModelInfo, &str)]) -> Result<()> {
+        let mut images: HashMap<String, Vec<String>> = HashMap::new();
+        for &(mi, node_type) in models {
+            if node_type != NODE_TYPE_NODEAGENT {
+                logd!(
+                    4,
+                    "Pre-pull is not supported on node '{}' of type '{}', skipping '{}'",
+                    mi.get_node(),
+                    node_type,
+                    mi.get_name()
+                );
+                continue;
+            }
+            let pod_yaml = common::etcd::get(&format!("{}/{}", ETCD_POD_PREFIX, mi.get_name()))
+                .await
+                .map_err(|e| format!("Pod of model '{}' not found: {}", mi.get_name(), e))?;
+            let pod: Pod = serde_yaml::from_str(&pod_yaml)
+                .map_err(|e| format!("Invalid pod of model '{}': {}", mi.get_name(), e))?;
+            let node_images = images.entry(mi.get_node()).or_default();
+            for image in pod.get_images() {
+                if !node_images.iter().any(|i| i == image) {
+                    node_images.push(image.to_string());
+                }
+            }
+        }
+
+        let results = futures::future::join_all(images.iter().map(|(node, images)| async move {
+            crate::runtime::nodeagent::prepull_images(images, node)
+                .await
+                .map_err(|e| e.to_string())
+        }))
+        .await;
+        let errors: Vec<String> = results.into_iter().filter_map(|r| r.err()).collect();
+        if !errors.is_empty() {
+            return Err(errors.join("; ").into());
+        }
+        Ok(())
+    }
+
+    /// Restores models from their last revision verified as Running
+    ///
+    /// Models are restored in reverse update order through the same path as
+    /// the `rollback` action. Models without a recorded revision are left as
+    /// they are.
+    async fn rollback_models(
+        &self,
+        scenario_name: &str,
+        package_name: &str,
+        policy_name: &str,
+        models: &[(&ModelInfo, &str)],
+    ) {
+        for &(mi, node_type) in models.iter().rev() {
+            match self
+                .execute_model_action(
+                    "rollback",
+                    mi,
+                    node_type,
+                    scenario_name,
+                    package_name,
+                    policy_name,
+                    &None,
+                    &None,
+                )
+                .await
+                .map_err(|e| e.to_string())
+            {
+                Ok(()) => logd!(3, "Rolled back model '{}'", mi.get_name()),
+                Err(e) => logd!(5, "Failed to roll back model '{}': {}", mi.get_name(), e),
+            }
+        }
+    }
+
+    /// Remember the current pod of a model as its last good revision
+    async fn record_pod_revision(&self, model_name: &str) {
+        let pod = match common::etcd::get(&format!("{}/{}", ETCD_POD_PREFIX, model_name)).await {
+            Ok(pod) => pod,
+            Err(e) => {
+                logd!(4, "Failed to read pod of model '{}': {}", model_name, e);
+                return;
+            }
+        };
+        let revision_key = format!("{}/{}", ETCD_POD_REVISION_PREFIX, model_name);
+        if let Err(e) = common::etcd::put(&revision_key, &pod).await {
+            logd!(
+                4,
+                "Failed to record revision of model '{}': {}",
+                model_name,
+                e
+            );
+        }
+    }
+
+    /// Reconciles current and desired states for a scenario
+    ///
+    /// Compares the current state with the desired state for a given scenario
+    /// and performs the necessary actions to align them.
+    ///
+    /// # Arguments
+    ///
+    /// * `scenario_name` - Name of the scenario
     /// * `current` - Current state value
     /// * `desired` - Desired state value
     ///
@@ -663,7 +1765,7 @@ impl ActionControllerManager {
         for mi in package.get_models() {
             let model_name = format!("{}.service", mi.get_name());
             let model_node = mi.get_node();
-            let node_type = if self.nodeagent_nodes.contains(&model_node) {
+            let node_type = if self.is_nodeagent_node(&model_node) {
                 "nodeagent"
             } else {
                 // Log warning for unknown node types and skip processing
@@ -701,10 +1803,9 @@ impl ActionControllerManager {
     /// - The scenario does not exist
     /// - The workload already exists
     /// - The runtime operation fails
-    #[allow(unused)]
     pub async fn create_workload(&self, scenario_name: String) -> Result<()> {
-        // TODO: Implementation
-        Ok(())
+        self.execute_scenario_operation(&scenario_name, "create")
+            .await
     }
 
     /// Deletes an existing workload for the specified scenario
@@ -724,10 +1825,9 @@ impl ActionControllerManager {
     /// - The scenario does not exist
     /// - The workload does not exist
     /// - The runtime operation fails
-    #[allow(unused_variables)]
     pub async fn delete_workload(&self, scenario_name: String) -> Result<()> {
-        // TODO: Implementation
-        Ok(())
+        self.execute_scenario_operation(&scenario_name, "remove")
+            .await
     }
 
     /// Pauses an active workload for the specified scenario
@@ -748,10 +1848,9 @@ impl ActionControllerManager {
     /// - The workload does not exist
     /// - The workload is not in a pausable state
     /// - The runtime operation fails
-    #[allow(unused_variables)]
     pub async fn pause_workload(&self, scenario_name: String) -> Result<()> {
-        // TODO: Implementation
-        Ok(())
+        self.execute_scenario_operation(&scenario_name, "pause")
+            .await
     }
 
     /// Starts a paused or stopped workload for the specified scenario
@@ -822,9 +1921,29 @@ impl ActionControllerManager {
             .await
     }
 
-    pub async fn reload_all_node(&self, _model_name: &str, _model_node: &str) -> Result<()> {
-        thread::sleep(Duration::from_millis(100));
-        Ok(())
+    /// Reloads the systemd unit of a model on its node
+    ///
+    /// Only NodeAgents with the systemd workload backend run models as
+    /// units. Their unit files are re-read before the unit of the model is
+    /// reloaded, which restarts it with the updated pod.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_name` - Name of the model
+    /// * `model_node` - Node running the model
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the unit was reloaded
+    /// * `Err(...)` if the node is unknown or the reload failed
+    pub async fn reload_all_node(&self, model_name: &str, model_node: &str) -> Result<()> {
+        crate::runtime::nodeagent::handle_unit(UnitCommand::DaemonReload, "", model_node).await?;
+        crate::runtime::nodeagent::handle_unit(
+            UnitCommand::Reload,
+            &format!("{}.service", model_name),
+            model_node,
+        )
+        .await
     }
 
     /// Offloads (migrates) a model from source node to target node
@@ -860,7 +1979,7 @@ impl ActionControllerManager {
         );
 
         // Step 1: Get model info from package
-        let package_key = format!("{}/{}", ETCD_PACKAGE_PREFIX, package_name);
+        let package_key = namespace::qualified_key(ETCD_PACKAGE_PREFIX, package_name);
         let package_str = common::etcd::get(&package_key)
             .await
             .map_err(|e| format!("Failed to get package '{}': {}", package_name, e))?;
@@ -895,53 +2014,732 @@ impl ActionControllerManager {
             model_name,
         )?;
 
-        // Step 3: Determine node type (assume nodeagent for now)
-        let node_type = NODE_TYPE_NODEAGENT;
+        // Step 3: Determine node type (assume nodeagent for now)
+        let node_type = NODE_TYPE_NODEAGENT;
+
+        // Step 4: Stop the container on source node
+        println!(
+            "[ActionController] Stopping model '{}' on source node '{}'",
+            model_name, source_node
+        );
+        if let Err(e) = self.stop_workload(&pod_yaml, source_node, node_type).await {
+            eprintln!(
+                "[ActionController] Warning: Failed to stop workload on source node: {}",
+                e
+            );
+            // Continue anyway - the container might already be stopped or crashed
+        }
+
+        // Wait for the stop before the model runs twice
+        let stop_timeout =
+            Duration::from_millis(common::setting::get_config().action.stop_timeout_ms);
+        let stopped = wait_for_model_stopped(model_name, stop_timeout)
+            .await
+            .map_err(|e| e.to_string());
+        if let Err(e) = stopped {
+            eprintln!("[ActionController] Warning: {}", e);
+        }
+
+        // Step 5: Start the container on target node
+        println!(
+            "[ActionController] Starting model '{}' on target node '{}'",
+            model_name, target_node
+        );
+        self.start_workload(&pod_yaml, target_node, node_type)
+            .await
+            .map_err(|e| {
+                format!(
+                    "Failed to start workload on target node '{}': {}",
+                    target_node, e
+                )
+            })?;
+
+        println!(
+            "[ActionController] Successfully offloaded model '{}' from '{}' to '{}'",
+            model_name, source_node, target_node
+        );
+
+        // Note: State change notification is handled by the caller (StateManager)
+        // as it manages the overall state transitions
+
+        Ok(())
+    }
+
+    /// Move a failed model to the node chosen for it
+    ///
+    /// The node is chosen among the Ready nodes other than `source_node`
+    /// like an `auto` model is placed, then the model is offloaded to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `scenario_name` - Name of the scenario
+    /// * `package_name` - Qualified name of the package containing the model
+    /// * `model_name` - Name of the model to move
+    /// * `source_node` - Node the model failed on
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(node)` with the node the model was moved to
+    /// * `Err(...)` if no node can take the model or the offload failed
+    pub async fn reschedule_model(
+        &self,
+        scenario_name: &str,
+        package_name: &str,
+        model_name: &str,
+        source_node: &str,
+    ) -> Result<String> {
+        let package_key = namespace::qualified_key(ETCD_PACKAGE_PREFIX, package_name);
+        let package: Package = serde_yaml::from_str(&common::etcd::get(&package_key).await?)
+            .map_err(|e| format!("Failed to parse package '{}': {}", package_name, e))?;
+        let mi = package
+            .get_models()
+            .iter()
+            .find(|mi| mi.get_name() == model_name)
+            .ok_or_else(|| {
+                format!(
+                    "Model '{}' not found in package '{}'",
+                    model_name, package_name
+                )
+            })?;
+        let pod: Pod = serde_yaml::from_str(
+            &common::etcd::get(&format!("{}/{}", ETCD_POD_PREFIX, model_name)).await?,
+        )
+        .map_err(|e| format!("Invalid pod of model '{}': {}", model_name, e))?;
+        let request = pod.get_resource_request();
+
+        let mut nodes = crate::placement::load_nodes().await?;
+        nodes.retain(|n| n.name != source_node);
+        let policy = package.get_scheduling_policy();
+        let Some(chosen) = crate::placement::select_node(&nodes, mi, &request, &policy) else {
+            return Err(crate::placement::unschedulable_reason(&nodes, mi, &request).into());
+        };
+        let target = nodes[chosen].name.clone();
+
+        self.offload_model(
+            scenario_name,
+            package_name,
+            model_name,
+            source_node,
+            &target,
+            "",
+        )
+        .await?;
+        let placement_key = format!("{}/{}", ETCD_PLACEMENT_PREFIX, model_name);
+        if let Err(e) = common::etcd::put(&placement_key, &target).await {
+            logd!(
+                4,
+                "Warning: Failed to record node of model '{}': {}",
+                model_name,
+                e
+            );
+        }
+        self.record_allocation(model_name, &target).await;
+        Ok(target)
+    }
+
+    /// Move the models off a node in maintenance
+    ///
+    /// Every model allocated on the node is migrated if its package places
+    /// it automatically and another Ready node satisfies its constraints and
+    /// has room for it. Models pinned to the node, and models no node can
+    /// take, are stopped. The node must not be Ready anymore, so it is not
+    /// picked as a target.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - Hostname of the node to drain
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(models)` with the outcome of every model of the node
+    /// * `Err(...)` if the allocations, packages or nodes cannot be read
+    pub async fn drain_node(&self, node: &str) -> Result<Vec<DrainedModel>> {
+        let models: Vec<String> = common::allocation::load()
+            .await?
+            .into_iter()
+            .filter(|allocation| allocation.node == node)
+            .map(|allocation| allocation.model)
+            .collect();
+        if models.is_empty() {
+            logd!(2, "Node '{}' has no models to drain", node);
+            return Ok(Vec::new());
+        }
+
+        let packages: Vec<Package> = load_artifacts(ETCD_PACKAGE_PREFIX).await?;
+        let scenarios: Vec<Scenario> = load_artifacts(ETCD_SCENARIO_PREFIX).await?;
+        let mut nodes = crate::placement::load_nodes().await?;
+        nodes.retain(|n| n.name != node);
+
+        let mut drained = Vec::new();
+        for model_name in models {
+            let result = match find_model(&packages, &model_name) {
+                Some((package, mi)) => {
+                    let scenario = scenarios
+                        .iter()
+                        .find(|s| {
+                            s.get_namespace() == package.get_namespace()
+                                && s.get_targets() == package.get_name()
+                        })
+                        .map(|s| s.get_qualified_name())
+                        .unwrap_or_default();
+                    self.drain_model(node, package, mi, &scenario, &mut nodes)
+                        .await
+                }
+                None => {
+                    let reason = "model belongs to no package".to_string();
+                    self.stop_drained_model(node, &model_name, String::new(), reason)
+                        .await
+                }
+            };
+            logd!(
+                3,
+                "Drained model '{}' from node '{}': {} {}",
+                result.model_name,
+                node,
+                result.outcome,
+                result.message
+            );
+            drained.push(result);
+        }
+        Ok(drained)
+    }
+
+    /// Migrate one model off a drained node, or stop it if it cannot move
+    async fn drain_model(
+        &self,
+        node: &str,
+        package: &Package,
+        mi: &ModelInfo,
+        scenario_name: &str,
+        nodes: &mut [crate::placement::NodeCapacity],
+    ) -> DrainedModel {
+        let model_name = mi.get_name();
+        let package_name = package.get_qualified_name();
+        if !mi.is_auto_node() {
+            let reason = format!("model is pinned to node '{}'", mi.get_node());
+            return self
+                .stop_drained_model(node, &model_name, package_name, reason)
+                .await;
+        }
+
+        let request = match common::etcd::get(&format!("{}/{}", ETCD_POD_PREFIX, model_name))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|pod| serde_yaml::from_str::<Pod>(&pod).map_err(|e| e.to_string()))
+        {
+            Ok(pod) => pod.get_resource_request(),
+            Err(e) => return drained_model(&model_name, package_name, "failed", "", e),
+        };
+        let policy = package.get_scheduling_policy();
+        let Some(chosen) = crate::placement::select_node(nodes, mi, &request, &policy) else {
+            let reason = crate::placement::unschedulable_reason(nodes, mi, &request);
+            return self
+                .stop_drained_model(node, &model_name, package_name, reason)
+                .await;
+        };
+        nodes[chosen].reserve(&request);
+        let target = nodes[chosen].name.clone();
+
+        if let Err(e) = self
+            .offload_model(scenario_name, &package_name, &model_name, node, &target, "")
+            .await
+        {
+            return drained_model(&model_name, package_name, "failed", &target, e.to_string());
+        }
+
+        let placement_key = format!("{}/{}", ETCD_PLACEMENT_PREFIX, model_name);
+        if let Err(e) = common::etcd::put(&placement_key, &target).await {
+            logd!(
+                4,
+                "Warning: Failed to record node of model '{}': {}",
+                model_name,
+                e
+            );
+        }
+        self.record_allocation(&model_name, &target).await;
+        drained_model(
+            &model_name,
+            package_name,
+            "migrated",
+            &target,
+            format!("moved from node '{}'", node),
+        )
+    }
+
+    /// Stop a model of a drained node that cannot move
+    async fn stop_drained_model(
+        &self,
+        node: &str,
+        model_name: &str,
+        package_name: String,
+        reason: String,
+    ) -> DrainedModel {
+        let stopped = match common::etcd::get(&format!("{}/{}", ETCD_POD_PREFIX, model_name)).await
+        {
+            Ok(pod_yaml) => self
+                .stop_workload(&pod_yaml, node, NODE_TYPE_NODEAGENT)
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(format!("pod of the model not found: {}", e)),
+        };
+        match stopped {
+            Ok(()) => {
+                if let Err(e) = common::allocation::release(model_name).await {
+                    logd!(
+                        4,
+                        "Failed to release allocation of model '{}': {}",
+                        model_name,
+                        e
+                    );
+                }
+                drained_model(model_name, package_name, "stopped", "", reason)
+            }
+            Err(e) => drained_model(
+                model_name,
+                package_name,
+                "failed",
+                "",
+                format!("{}, stopping failed: {}", reason, e),
+            ),
+        }
+    }
+
+    /// Move every model of a failed node to another node
+    ///
+    /// Unlike a drain, models pinned to the source node move as well. Each
+    /// model goes to `target`, or if it is empty to the Ready node chosen by
+    /// the scheduling policy of its package, provided the node satisfies the
+    /// constraints of the model and has room for it. The workload is removed
+    /// from the source node if it can be reached, then started on the
+    /// target, which writes its unit files there, and its placement and
+    /// allocation are recorded on the target.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Hostname of the node the models leave
+    /// * `target` - Hostname of the node they move to, empty to choose one per model
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(models)` with the outcome of every model of the source node
+    /// * `Err(MigrateError)` if the target is not a Ready node or the
+    ///   allocations, packages or nodes cannot be read
+    pub async fn migrate_node(
+        &self,
+        source: &str,
+        target: &str,
+    ) -> std::result::Result<Vec<MigratedModel>, MigrateError> {
+        let models: Vec<String> = common::allocation::load()
+            .await?
+            .into_iter()
+            .filter(|allocation| allocation.node == source)
+            .map(|allocation| allocation.model)
+            .collect();
+        let mut nodes =
+            migration_candidates(crate::placement::load_nodes().await?, source, target)?;
+        if models.is_empty() {
+            logd!(2, "Node '{}' has no models to migrate", source);
+            return Ok(Vec::new());
+        }
+
+        let packages: Vec<Package> = load_artifacts(ETCD_PACKAGE_PREFIX).await?;
+        let scenarios: Vec<Scenario> = load_artifacts(ETCD_SCENARIO_PREFIX).await?;
+
+        let mut migrated = Vec::new();
+        for model_name in models {
+            let result = match find_model(&packages, &model_name) {
+                Some((package, mi)) => {
+                    let scenario = scenarios
+                        .iter()
+                        .find(|s| {
+                            s.get_namespace() == package.get_namespace()
+                                && s.get_targets() == package.get_name()
+                        })
+                        .map(|s| s.get_qualified_name())
+                        .unwrap_or_default();
+                    self.migrate_model(source, package, mi, &scenario, &mut nodes)
+                        .await
+                }
+                None => migrated_model(
+                    &model_name,
+                    String::new(),
+                    "failed",
+                    "",
+                    false,
+                    "model belongs to no package".to_string(),
+                ),
+            };
+            logd!(
+                3,
+                "Migrated model '{}' from node '{}': {} {} {}",
+                result.model_name,
+                source,
+                result.outcome,
+                result.target_node,
+                result.message
+            );
+            migrated.push(result);
+        }
+        Ok(migrated)
+    }
+
+    /// Move one model of a failed node to the node chosen among `nodes`
+    async fn migrate_model(
+        &self,
+        source: &str,
+        package: &Package,
+        mi: &ModelInfo,
+        scenario_name: &str,
+        nodes: &mut [crate::placement::NodeCapacity],
+    ) -> MigratedModel {
+        let model_name = mi.get_name();
+        let package_name = package.get_qualified_name();
+        let pod_yaml = match common::etcd::get(&format!("{}/{}", ETCD_POD_PREFIX, model_name)).await
+        {
+            Ok(pod_yaml) => pod_yaml,
+            Err(e) => {
+                let message = format!("pod of the model not found: {}", e);
+                return migrated_model(&model_name, package_name, "failed", "", false, message);
+            }
+        };
+        let request = match serde_yaml::from_str::<Pod>(&pod_yaml) {
+            Ok(pod) => pod.get_resource_request(),
+            Err(e) => {
+                let message = format!("invalid pod of the model: {}", e);
+                return migrated_model(&model_name, package_name, "failed", "", false, message);
+            }
+        };
+        let policy = package.get_scheduling_policy();
+        let Some(chosen) = crate::placement::select_node(nodes, mi, &request, &policy) else {
+            let reason = crate::placement::unschedulable_reason(nodes, mi, &request);
+            return migrated_model(&model_name, package_name, "failed", "", false, reason);
+        };
+        nodes[chosen].reserve(&request);
+        let target = nodes[chosen].name.clone();
+
+        let pod_yaml = match self.inject_pod_annotations(
+            &pod_yaml,
+            scenario_name,
+            &package_name,
+            "",
+            &model_name,
+        ) {
+            Ok(pod_yaml) => pod_yaml,
+            Err(e) => {
+                let message = e.to_string();
+                return migrated_model(
+                    &model_name,
+                    package_name,
+                    "failed",
+                    &target,
+                    false,
+                    message,
+                );
+            }
+        };
+
+        // Removing also deletes the unit files, so a node that comes back
+        // does not run the model a second time
+        let source_stopped = match self
+            .execute_workload_operation("remove", &pod_yaml, source, NODE_TYPE_NODEAGENT)
+            .await
+            .map_err(|e| e.to_string())
+        {
+            Ok(()) => {
+                let stop_timeout =
+                    Duration::from_millis(common::setting::get_config().action.stop_timeout_ms);
+                let stopped = wait_for_model_stopped(&model_name, stop_timeout)
+                    .await
+                    .map_err(|e| e.to_string());
+                if let Err(e) = stopped {
+                    logd!(4, "Warning: {}", e);
+                }
+                true
+            }
+            Err(e) => {
+                logd!(
+                    4,
+                    "Node '{}' is unreachable, model '{}' is not removed from it: {}",
+                    source,
+                    model_name,
+                    e
+                );
+                false
+            }
+        };
+
+        let started = self
+            .start_workload(&pod_yaml, &target, NODE_TYPE_NODEAGENT)
+            .await
+            .map_err(|e| e.to_string());
+        if let Err(e) = started {
+            let message = format!("starting on node '{}' failed: {}", target, e);
+            return migrated_model(
+                &model_name,
+                package_name,
+                "failed",
+                &target,
+                source_stopped,
+                message,
+            );
+        }
+
+        let placement_key = format!("{}/{}", ETCD_PLACEMENT_PREFIX, model_name);
+        if let Err(e) = common::etcd::put(&placement_key, &target).await {
+            logd!(
+                4,
+                "Warning: Failed to record node of model '{}': {}",
+                model_name,
+                e
+            );
+        }
+        self.record_allocation(&model_name, &target).await;
+        let message = if source_stopped {
+            format!("moved from node '{}'", source)
+        } else {
+            format!("moved from unreachable node '{}'", source)
+        };
+        migrated_model(
+            &model_name,
+            package_name,
+            "migrated",
+            &target,
+            source_stopped,
+            message,
+        )
+    }
+}
+
+/// Outcome of one model of a drained node
+fn drained_model(
+    model_name: &str,
+    package_name: String,
+    outcome: &str,
+    target_node: &str,
+    message: String,
+) -> DrainedModel {
+    DrainedModel {
+        model_name: model_name.to_string(),
+        package_name,
+        outcome: outcome.to_string(),
+        target_node: target_node.to_string(),
+        message,
+    }
+}
+
+/// Outcome of one model of a migrated node
+fn migrated_model(
+    model_name: &str,
+    package_name: String,
+    outcome: &str,
+    target_node: &str,
+    source_stopped: bool,
+    message: String,
+) -> MigratedModel {
+    MigratedModel {
+        model_name: model_name.to_string(),
+        package_name,
+        outcome: outcome.to_string(),
+        target_node: target_node.to_string(),
+        source_stopped,
+        message,
+    }
+}
+
+/// Reason the models of a node cannot be migrated
+#[derive(Debug)]
+pub enum MigrateError {
+    /// The target node is not one of the Ready nodes
+    NotReady(String),
+    /// The target node is the source node
+    SameNode(String),
+    /// The allocations, packages or nodes cannot be read
+    Failed(String),
+}
+
+impl std::fmt::Display for MigrateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotReady(node) => write!(f, "Target node '{}' is not a Ready node", node),
+            Self::SameNode(node) => write!(f, "Node '{}' cannot be migrated to itself", node),
+            Self::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for MigrateError {}
+
+impl From<Box<dyn std::error::Error>> for MigrateError {
+    fn from(error: Box<dyn std::error::Error>) -> Self {
+        Self::Failed(error.to_string())
+    }
+}
+
+impl From<common::etcd::StoreError> for MigrateError {
+    fn from(error: common::etcd::StoreError) -> Self {
+        Self::Failed(error.to_string())
+    }
+}
 
-        // Step 4: Stop the container on source node
-        println!(
-            "[ActionController] Stopping model '{}' on source node '{}'",
-            model_name, source_node
-        );
-        if let Err(e) = self.stop_workload(&pod_yaml, source_node, node_type).await {
-            eprintln!(
-                "[ActionController] Warning: Failed to stop workload on source node: {}",
-                e
-            );
-            // Continue anyway - the container might already be stopped or crashed
-        }
+/// Nodes the models of `source` may move to
+///
+/// Only `target` if it is set, which must be one of the Ready `nodes`.
+fn migration_candidates(
+    mut nodes: Vec<crate::placement::NodeCapacity>,
+    source: &str,
+    target: &str,
+) -> std::result::Result<Vec<crate::placement::NodeCapacity>, MigrateError> {
+    if target == source {
+        return Err(MigrateError::SameNode(source.to_string()));
+    }
+    nodes.retain(|n| n.name != source && (target.is_empty() || n.name == target));
+    if !target.is_empty() && nodes.is_empty() {
+        return Err(MigrateError::NotReady(target.to_string()));
+    }
+    Ok(nodes)
+}
 
-        // Brief delay to ensure cleanup
-        thread::sleep(Duration::from_millis(200));
+/// Package containing a model, with the model
+fn find_model<'a>(
+    packages: &'a [Package],
+    model_name: &str,
+) -> Option<(&'a Package, &'a ModelInfo)> {
+    packages.iter().find_map(|package| {
+        package
+            .get_models()
+            .iter()
+            .find(|mi| mi.get_name() == model_name)
+            .map(|mi| (package, mi))
+    })
+}
 
-        // Step 5: Start the container on target node
-        println!(
-            "[ActionController] Starting model '{}' on target node '{}'",
-            model_name, target_node
-        );
-        self.start_workload(&pod_yaml, target_node, node_type)
-            .await
-            .map_err(|e| {
-                format!(
-                    "Failed to start workload on target node '{}': {}",
-                    target_node, e
-                )
-            })?;
+/// Read the artifacts of a kind in the default and all registered namespaces
+///
+/// Entries that cannot be parsed are skipped.
+async fn load_artifacts<T: serde::de::DeserializeOwned>(kind: &str) -> Result<Vec<T>> {
+    let mut artifacts = Vec::new();
+    for (key, value) in common::etcd::get_all_of_kind(kind).await? {
+        match serde_yaml::from_str(&value) {
+            Ok(artifact) => artifacts.push(artifact),
+            Err(e) => logd!(4, "Warning: Invalid artifact '{}': {}", key, e),
+        }
+    }
+    Ok(artifacts)
+}
 
-        println!(
-            "[ActionController] Successfully offloaded model '{}' from '{}' to '{}'",
-            model_name, source_node, target_node
-        );
+/// Scenario action shared by the models of a package
+struct ModelActionContext<'a> {
+    action: &'a str,
+    scenario_name: &'a str,
+    package_name: &'a str,
+    policy_name: &'a str,
+    network_str: &'a Option<String>,
+    node_str: &'a Option<String>,
+    node_roles: &'a HashMap<String, String>,
+}
 
-        // Note: State change notification is handled by the caller (StateManager)
-        // as it manages the overall state transitions
+/// Wait for the operations on the models of a package, running at most
+/// `action.max_concurrent_models` of the settings at the same time
+///
+/// Every operation runs even if another one fails. The errors of all
+/// failed models are joined into the returned error.
+async fn run_model_operations<Fut>(operations: Vec<Fut>) -> Result<()>
+where
+    Fut: Future<Output = std::result::Result<(), String>>,
+{
+    let limit = common::setting::get_config().action.max_concurrent_models;
+    run_bounded(operations, limit).await
+}
 
-        Ok(())
+async fn run_bounded<Fut>(operations: Vec<Fut>, limit: usize) -> Result<()>
+where
+    Fut: Future<Output = std::result::Result<(), String>>,
+{
+    let total = operations.len();
+    let results: Vec<_> = stream::iter(operations)
+        .buffered(limit.max(1))
+        .collect()
+        .await;
+    let errors: Vec<String> = results.into_iter().filter_map(|r| r.err()).collect();
+    if errors.is_empty() {
+        return Ok(());
     }
+    Err(format!(
+        "{} of {} models failed: {}",
+        errors.len(),
+        total,
+        errors.join("; ")
+    )
+    .into())
 }
 
 //UNIT TEST SKELTON
 
+/// ETCD key where StateManager stores the state of a model
+fn model_state_key(model_name: &str) -> String {
+    format!("/model/{}/state", model_name)
+}
+
+/// Returns `true` for states a model does not leave without a new workload
+fn is_model_failed(state: &str) -> bool {
+    matches!(state, "Dead" | "Exited" | "Failed")
+}
+
+/// Wait until StateManager reports a model as Running
+///
+/// Fails as soon as the model is reported Dead, Exited or Failed, or when
+/// `timeout` elapses without a Running report.
+async fn wait_for_model_running(model_name: &str, timeout: Duration) -> Result<()> {
+    let key = model_state_key(model_name);
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        match common::etcd::get(&key).await.as_deref() {
+            Ok("Running") => return Ok(()),
+            Ok(state) if is_model_failed(state) => {
+                return Err(format!("model '{}' is {}", model_name, state).into())
+            }
+            _ => {}
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(format!(
+                "model '{}' did not reach Running within {:?}",
+                model_name, timeout
+            )
+            .into());
+        }
+        tokio::time::sleep(Duration::from_millis(MODEL_STATE_POLL_INTERVAL_MS)).await;
+    }
+}
+
+/// Make the last revision of a model verified as Running its pod again
+///
+/// The revision is written back to `Pod/<model>` so the restored spec is
+/// also what later actions start. Fails if the model has no revision.
+async fn restore_pod_revision(model_name: &str) -> Result<String> {
+    let revision_key = format!("{}/{}", ETCD_POD_REVISION_PREFIX, model_name);
+    let revision = common::etcd::get(&revision_key)
+        .await
+        .map_err(|e| format!("No previous revision of model '{}': {}", model_name, e))?;
+    common::etcd::put(&format!("{}/{}", ETCD_POD_PREFIX, model_name), &revision).await?;
+    logd!(3, "Restored the last revision of model '{}'", model_name);
+    Ok(revision)
+}
+
+/// Wait until StateManager no longer reports a model as Running
+///
+/// A model without a reported state counts as stopped.
+async fn wait_for_model_stopped(model_name: &str, timeout: Duration) -> Result<()> {
+    let key = model_state_key(model_name);
+    let deadline = tokio::time::Instant::now() + timeout;
+    while let Ok("Running") = common::etcd::get(&key).await.as_deref() {
+        if tokio::time::Instant::now() >= deadline {
+            return Err(format!("model '{}' still Running after {:?}", model_name, timeout).into());
+        }
+        tokio::time::sleep(Duration::from_millis(MODEL_STATE_POLL_INTERVAL_MS)).await;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1008,6 +2806,13 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("cannot be empty"));
     }
 
+    #[tokio::test]
+    async fn test_plan_manager_action_empty_scenario_name() {
+        let manager = ActionControllerManager::new();
+        let result = manager.plan_manager_action("  ").await;
+        assert!(result.is_err());
+    }
+
     #[tokio::test]
     async fn test_trigger_manager_action_scenario_not_found() {
         let manager = ActionControllerManager::new();
@@ -1153,6 +2958,7 @@ spec:
         let manager = ActionControllerManager {
             nodeagent_nodes: vec![],
             state_sender: StateManagerSender::new(),
+            actions: ActionRegistry::default(),
         };
 
         let result = manager.trigger_manager_action("launch-test").await;
@@ -1203,6 +3009,7 @@ spec:
         let manager = ActionControllerManager {
             nodeagent_nodes: vec![],
             state_sender: StateManagerSender::new(),
+            actions: ActionRegistry::default(),
         };
 
         let result = manager.trigger_manager_action("terminate-test").await;
@@ -1255,6 +3062,7 @@ spec:
         let manager = ActionControllerManager {
             nodeagent_nodes: vec![],
             state_sender: StateManagerSender::new(),
+            actions: ActionRegistry::default(),
         };
 
         let result = manager.trigger_manager_action("update-test").await;
@@ -1304,6 +3112,7 @@ spec:
         let manager = ActionControllerManager {
             nodeagent_nodes: vec![],
             state_sender: StateManagerSender::new(),
+            actions: ActionRegistry::default(),
         };
 
         let result = manager.trigger_manager_action("rollback-test").await;
@@ -1354,6 +3163,7 @@ spec:
         let manager = ActionControllerManager {
             nodeagent_nodes: vec![],
             state_sender: StateManagerSender::new(),
+            actions: ActionRegistry::default(),
         };
 
         let result = manager.trigger_manager_action("unknown-node-test").await;
@@ -1408,6 +3218,7 @@ spec:
         let manager = ActionControllerManager {
             nodeagent_nodes: vec!["ZONE".to_string()],
             state_sender: StateManagerSender::new(),
+            actions: ActionRegistry::default(),
         };
 
         let result = manager.trigger_manager_action("nodeagent-test").await;
@@ -1478,6 +3289,7 @@ spec:
         let manager = ActionControllerManager {
             nodeagent_nodes: vec!["ZONE".to_string()],
             state_sender: StateManagerSender::new(),
+            actions: ActionRegistry::default(),
         };
 
         let result = manager
@@ -1506,6 +3318,7 @@ spec:
         let manager = ActionControllerManager {
             nodeagent_nodes: vec!["ZONE".to_string()],
             state_sender: StateManagerSender::new(),
+            actions: ActionRegistry::default(),
         };
 
         let result = manager
@@ -1538,6 +3351,7 @@ spec:
         let manager = ActionControllerManager {
             nodeagent_nodes: vec![],
             state_sender: StateManagerSender::new(),
+            actions: ActionRegistry::default(),
         };
         let result = manager
             .reconcile_do("antipinch-enable".into(), Status::Running, Status::Running)
@@ -1588,6 +3402,7 @@ spec:
         let manager = ActionControllerManager {
             nodeagent_nodes: vec![],
             state_sender: StateManagerSender::new(),
+            actions: ActionRegistry::default(),
         };
 
         let result = manager.trigger_manager_action("antipinch-enable").await;
@@ -1613,6 +3428,7 @@ spec:
         let manager = ActionControllerManager {
             nodeagent_nodes: vec![],
             state_sender: StateManagerSender::new(),
+            actions: ActionRegistry::default(),
         };
 
         let result = manager.trigger_manager_action("invalid_scenario").await;
@@ -1624,6 +3440,7 @@ spec:
         let manager = ActionControllerManager {
             nodeagent_nodes: vec![],
             state_sender: StateManagerSender::new(),
+            actions: ActionRegistry::default(),
         };
 
         let result = manager
@@ -1637,6 +3454,7 @@ spec:
         let manager = ActionControllerManager {
             nodeagent_nodes: vec![],
             state_sender: StateManagerSender::new(),
+            actions: ActionRegistry::default(),
         };
 
         let result: std::result::Result<(), Box<dyn Error>> = manager
@@ -1650,6 +3468,7 @@ spec:
         let manager = ActionControllerManager {
             nodeagent_nodes: vec![],
             state_sender: StateManagerSender::new(),
+            actions: ActionRegistry::default(),
         };
 
         let result = manager
@@ -1666,15 +3485,27 @@ spec:
     }
 
     #[tokio::test]
-    async fn test_create_delete_restart_pause_are_noops() {
+    async fn test_create_delete_pause_reject_empty_scenario() {
         let manager = ActionControllerManager {
             nodeagent_nodes: vec![],
             state_sender: StateManagerSender::new(),
+            actions: ActionRegistry::default(),
         };
 
-        assert!(manager.create_workload("test".into()).await.is_ok());
-        assert!(manager.delete_workload("test".into()).await.is_ok());
-        assert!(manager.pause_workload("test".into()).await.is_ok());
+        assert!(manager.create_workload("".into()).await.is_err());
+        assert!(manager.delete_workload(" ".into()).await.is_err());
+        assert!(manager.pause_workload("".into()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_workload_operation_unknown_operation() {
+        let manager = ActionControllerManager::new();
+
+        let result = manager
+            .execute_workload_operation("migrate", "pod", "HPC", NODE_TYPE_NODEAGENT)
+            .await;
+
+        assert!(result.is_err());
     }
 
     #[test]
@@ -1682,8 +3513,316 @@ spec:
         let manager = ActionControllerManager {
             nodeagent_nodes: vec!["ZONE".to_string()],
             state_sender: StateManagerSender::new(),
+            actions: ActionRegistry::default(),
         };
 
         assert!(manager.nodeagent_nodes.contains(&"ZONE".to_string()));
     }
+
+    #[tokio::test]
+    async fn test_wait_for_model_running_times_out() {
+        let result = wait_for_model_running("no-such-model", Duration::ZERO).await;
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_is_model_failed() {
+        assert!(is_model_failed("Failed"));
+        assert!(is_model_failed("Dead"));
+        assert!(is_model_failed("Exited"));
+        assert!(!is_model_failed("Running"));
+        assert!(!is_model_failed("Created"));
+    }
+
+    #[tokio::test]
+    async fn test_restore_pod_revision_without_revision_fails() {
+        let result = restore_pod_revision("no-such-model").await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_model_stopped_without_state() {
+        let result = wait_for_model_stopped("no-such-model", Duration::ZERO).await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_run_bounded_limits_concurrency_and_collects_errors() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let operations = (0..6)
+            .map(|i| {
+                let (running, peak) = (&running, &peak);
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    if i % 3 == 0 {
+                        Err(format!("model-{} failed", i))
+                    } else {
+                        Ok(())
+                    }
+                }
+            })
+            .collect();
+
+        let err = run_bounded(operations, 2).await.unwrap_err().to_string();
+
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert_eq!(err, "2 of 6 models failed: model-0 failed; model-3 failed");
+        assert!(run_bounded(Vec::<std::future::Ready<_>>::new(), 0)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rolling_update_skips_unknown_nodes() {
+        let manager = ActionControllerManager::new();
+        let package: Package = serde_yaml::from_str(
+            r#"
+apiVersion: v1
+kind: Package
+metadata:
+  name: rolling-pkg
+spec:
+  pattern:
+    - type: plain
+  models:
+    - name: rolling-model
+      node: UNKNOWN-NODE
+      resources:
+        volume:
+        network:
+  updateStrategy:
+    type: rolling
+    maxUnavailable: 1
+"#,
+        )
+        .unwrap();
+        let strategy = package.get_update_strategy().clone().unwrap();
+
+        let result = manager
+            .rolling_update("rolling-scenario", &package, &strategy, &HashMap::new())
+            .await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_canary_update_stops_without_canary_models() {
+        let manager = ActionControllerManager::new();
+        let package: Package = serde_yaml::from_str(
+            r#"
+apiVersion: v1
+kind: Package
+metadata:
+  name: canary-pkg
+spec:
+  pattern:
+    - type: plain
+  models:
+    - name: canary-model
+      node: UNKNOWN-NODE
+      resources:
+        volume:
+        network:
+  updateStrategy:
+    type: recreate
+    canary:
+      soakSeconds: 1
+"#,
+        )
+        .unwrap();
+        let strategy = package.get_update_strategy().clone().unwrap();
+
+        let result = manager
+            .rolling_update("canary-scenario", &package, &strategy, &HashMap::new())
+            .await;
+
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("node 'UNKNOWN-NODE' runs none of its models"));
+    }
+
+    #[tokio::test]
+    async fn test_prepull_models_skips_other_node_types() {
+        let manager = ActionControllerManager::new();
+        let package: Package = serde_yaml::from_str(
+            r#"
+apiVersion: v1
+kind: Package
+metadata:
+  name: prepull-pkg
+spec:
+  pattern:
+    - type: plain
+  models:
+    - name: prepull-model
+      node: HPC
+      resources:
+        volume:
+        network:
+"#,
+        )
+        .unwrap();
+        let models: Vec<(&ModelInfo, &str)> = package
+            .get_models()
+            .iter()
+            .map(|mi| (mi, "bluechi"))
+            .collect();
+
+        assert!(manager.prepull_models(&models).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_place_auto_models_without_placement() {
+        let manager = ActionControllerManager::new();
+        let mut package: Package = serde_yaml::from_str(
+            r#"
+apiVersion: v1
+kind: Package
+metadata:
+  name: auto-pkg
+spec:
+  pattern:
+    - type: plain
+  schedulingPolicy: binpacking
+  models:
+    - name: fixed-model
+      node: HPC
+      resources: {}
+    - name: auto-placement-test-model
+      node: auto
+      resources: {}
+"#,
+        )
+        .unwrap();
+
+        // Nothing was launched, so there is no node to pause
+        let result = manager
+            .place_auto_models(&mut package, "pause", false)
+            .await;
+
+        assert!(result.is_ok());
+        assert_eq!(package.get_models()[0].get_node(), "HPC");
+        assert!(package.get_models()[1].is_auto_node());
+    }
+
+    #[tokio::test]
+    async fn test_check_placement_constraints_rejects_unknown_labels() {
+        let manager = ActionControllerManager::new();
+        let package: Package = serde_yaml::from_str(
+            r#"
+apiVersion: v1
+kind: Package
+metadata:
+  name: constrained-pkg
+spec:
+  pattern:
+    - type: plain
+  models:
+    - name: constrained-model
+      node: unregistered-constraint-node
+      nodeSelector:
+        zone: front
+      resources: {}
+"#,
+        )
+        .unwrap();
+
+        let err = manager
+            .check_placement_constraints(&package, "launch")
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Package 'constrained-pkg' cannot be placed"));
+        assert!(err.contains("unregistered-constraint-node"));
+
+        // Stopping workloads does not depend on the constraints
+        let result = manager
+            .check_placement_constraints(&package, "terminate")
+            .await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_is_nodeagent_node_includes_settings_host() {
+        let manager = ActionControllerManager {
+            nodeagent_nodes: vec!["zone1".to_string()],
+            state_sender: StateManagerSender::new(),
+            actions: ActionRegistry::default(),
+        };
+        assert!(manager.is_nodeagent_node("zone1"));
+        assert!(manager.is_nodeagent_node(&common::setting::get_config().host.name));
+        assert!(!manager.is_nodeagent_node("unknown-node"));
+    }
+
+    #[test]
+    fn test_find_model_across_packages() {
+        let packages: Vec<Package> = ["pkg-a", "pkg-b"]
+            .iter()
+            .map(|name| {
+                serde_yaml::from_str(&format!(
+                    r#"
+apiVersion: v1
+kind: Package
+metadata:
+  name: {name}
+spec:
+  pattern:
+    - type: plain
+  models:
+    - name: {name}-model
+      node: HPC
+      resources:
+        realtime: false
+      volume: {{}}
+      network: {{}}
+"#
+                ))
+                .unwrap()
+            })
+            .collect();
+
+        let (package, model) = find_model(&packages, "pkg-b-model").unwrap();
+        assert_eq!(package.get_name(), "pkg-b");
+        assert_eq!(model.get_node(), "HPC");
+        assert!(find_model(&packages, "missing-model").is_none());
+    }
+
+    #[test]
+    fn test_migration_candidates() {
+        let nodes: Vec<crate::placement::NodeCapacity> = ["zone1", "zone2", "zone3"]
+            .iter()
+            .map(|name| crate::placement::NodeCapacity {
+                name: name.to_string(),
+                cpu_millis: 4000,
+                memory_mb: 4096,
+                used_cpu_millis: 0,
+                used_memory_mb: 0,
+                labels: HashMap::new(),
+                taints: Vec::new(),
+            })
+            .collect();
+        let names = |nodes: Vec<crate::placement::NodeCapacity>| {
+            nodes.into_iter().map(|n| n.name).collect::<Vec<_>>()
+        };
+
+        let auto = migration_candidates(nodes.clone(), "zone1", "").unwrap();
+        assert_eq!(names(auto), vec!["zone2", "zone3"]);
+        let chosen = migration_candidates(nodes.clone(), "zone1", "zone3").unwrap();
+        assert_eq!(names(chosen), vec!["zone3"]);
+        assert!(matches!(
+            migration_candidates(nodes.clone(), "zone1", "zone1"),
+            Err(MigrateError::SameNode(_))
+        ));
+        let err = migration_candidates(nodes, "zone1", "zone9").unwrap_err();
+        assert!(matches!(err, MigrateError::NotReady(_)));
+        assert_eq!(err.to_string(), "Target node 'zone9' is not a Ready node");
+    }
 }
@@ -0,0 +1,365 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Per-node workloads of packages with a selector or distributed pattern
+//!
+//! The nodes of every model are chosen when the scenario is triggered:
+//! a selector package runs each model on every Ready node with the labels
+//! of the pattern, a distributed package on `replicas` nodes picked by the
+//! scheduling policy. Each node gets its own workload, a copy of the model's
+//! pod stored under `Pod/<model>-<node>`, and the chosen nodes are recorded
+//! in `Replicas/<package>/<model>` (see `common::replica`) for later actions
+//! and for StateManager.
+//!
+//! A scenario with `nodeSelector` makes a selector package of its target, see
+//! [`target_group`]. Its launch records the group in `Groups/<package>`.
+use crate::placement::{self, NodeCapacity};
+use common::logd;
+use common::replica::Group;
+use common::spec::artifact::package::{ModelInfo, Pattern, PatternType, SchedulingPolicy};
+use common::spec::artifact::{Artifact, Package, Scenario};
+use common::spec::k8s::pod::ResourceRequest;
+use common::spec::k8s::Pod;
+use common::Result;
+
+const ETCD_POD_PREFIX: &str = "Pod";
+
+/// Let a package run on the node group of the scenario that targets it
+///
+/// A scenario with `nodeSelector` adds its labels to the package. Other
+/// scenarios act on the group of the last launch, except a launch, which
+/// runs the package on its own nodes again.
+pub async fn target_group(scenario: &Scenario, package: &mut Package) {
+    let selector = scenario.get_node_selector();
+    if !selector.is_empty() {
+        package.target_nodes(selector);
+        return;
+    }
+    if scenario.get_actions() == "launch" {
+        return;
+    }
+    match common::replica::load_group(&package.get_name()).await {
+        Ok(Some(group)) => group.apply(package),
+        Ok(None) => {}
+        Err(e) => logd!(
+            4,
+            "Warning: Failed to read node group of package '{}': {}",
+            package.get_name(),
+            e
+        ),
+    }
+}
+
+/// Record the node group a launch runs its package on
+///
+/// The group is removed if the scenario has no `nodeSelector`.
+///
+/// # Errors
+///
+/// Returns an error if the group cannot be stored.
+pub async fn record_group(scenario: &Scenario, package: &Package) -> Result<()> {
+    let selector = scenario.get_node_selector();
+    let group = (!selector.is_empty()).then(|| Group {
+        scenario: scenario.get_qualified_name(),
+        node_selector: selector.clone().into_iter().collect(),
+    });
+    common::replica::record_group(&package.get_name(), group.as_ref()).await?;
+    Ok(())
+}
+
+/// Replace the models of a selector or distributed package by their per-node workloads
+///
+/// `launch` chooses the nodes of every model. Other actions reuse the
+/// recorded nodes, and `update`, `rollback` and `create` choose nodes for
+/// models that have none. Models without nodes are left out. Packages with
+/// the plain pattern are not changed.
+///
+/// With `dry_run`, the chosen nodes and the pods of the workloads are not
+/// stored.
+///
+/// # Errors
+///
+/// Returns an error if the pattern is invalid, the nodes or the pod of a
+/// model cannot be read or a model cannot get the nodes its pattern needs.
+pub async fn expand_models(package: &mut Package, action: &str, dry_run: bool) -> Result<()> {
+    let pattern = package.get_pattern();
+    if pattern.is_plain() {
+        return Ok(());
+    }
+    package.validate_pattern()?;
+
+    let package_name = package.get_name();
+    let policy = package.get_scheduling_policy();
+    let recorded = if action == "launch" {
+        Default::default()
+    } else {
+        common::replica::load(&package_name)
+            .await
+            .unwrap_or_else(|e| {
+                logd!(
+                    4,
+                    "Warning: Failed to read replicas of package '{}': {}",
+                    package_name,
+                    e
+                );
+                Default::default()
+            })
+    };
+
+    let mut nodes: Option<Vec<NodeCapacity>> = None;
+    let mut workloads = Vec::new();
+    for mi in package.get_models() {
+        let model_name = mi.get_name();
+        let pod_str = common::etcd::get(&format!("{}/{}", ETCD_POD_PREFIX, model_name)).await?;
+
+        let model_nodes = match recorded.get(&model_name) {
+            Some(model_nodes) => model_nodes.clone(),
+            None if matches!(action, "launch" | "update" | "rollback" | "create") => {
+                let nodes = match nodes.as_mut() {
+                    Some(nodes) => nodes,
+                    None => nodes.insert(placement::load_nodes().await?),
+                };
+                let pod: Pod = serde_yaml::from_str(&pod_str)
+                    .map_err(|e| format!("Failed to parse pod of model '{}': {}", model_name, e))?;
+                let chosen =
+                    choose_nodes(nodes, &pattern, mi, &pod.get_resource_request(), &policy)?;
+                logd!(
+                    3,
+                    "Model '{}' of {} package '{}' runs on {}",
+                    model_name,
+                    pattern.get_type().as_str(),
+                    package_name,
+                    chosen.join(", ")
+                );
+                if !dry_run {
+                    common::replica::record(&package_name, &model_name, &chosen).await?;
+                }
+                chosen
+            }
+            None => {
+                logd!(
+                    4,
+                    "Warning: Model '{}' has no recorded nodes for '{}'",
+                    model_name,
+                    action
+                );
+                continue;
+            }
+        };
+
+        for node in model_nodes {
+            let replica = mi.replica(&node);
+            if !dry_run {
+                let key = format!("{}/{}", ETCD_POD_PREFIX, replica.get_name());
+                let pod = replica_pod(&pod_str, &replica.get_name())?;
+                common::etcd::put(&key, &pod).await?;
+            }
+            workloads.push(replica);
+        }
+    }
+
+    *package.get_models_mut() = workloads;
+    Ok(())
+}
+
+/// Nodes a model runs on according to the pattern of its package
+///
+/// The nodes of a distributed model are reserved in `nodes`, so the next
+/// models see them fuller.
+///
+/// # Errors
+///
+/// Returns why the model cannot get the nodes its pattern needs.
+pub fn choose_nodes(
+    nodes: &mut [NodeCapacity],
+    pattern: &Pattern,
+    model: &ModelInfo,
+    request: &ResourceRequest,
+    policy: &SchedulingPolicy,
+) -> std::result::Result<Vec<String>, String> {
+    match pattern.get_type() {
+        PatternType::Plain | PatternType::Unknown => Ok(vec![model.get_node()]),
+        PatternType::Selector => {
+            let chosen: Vec<String> = nodes
+                .iter()
+                .filter(|node| pattern.matches(&node.labels))
+                .filter(|node| {
+                    model
+                        .check_node(&node.name, &node.labels, &node.taints)
+                        .is_ok()
+                })
+                .map(|node| node.name.clone())
+                .collect();
+            if chosen.is_empty() {
+                return Err(format!(
+                    "No node matches the selector pattern of model '{}'",
+                    model.get_name()
+                ));
+            }
+            Ok(chosen)
+        }
+        PatternType::Distributed => {
+            let mut chosen: Vec<String> = Vec::new();
+            while chosen.len() < pattern.get_replicas() {
+                let candidates: Vec<NodeCapacity> = nodes
+                    .iter()
+                    .filter(|node| pattern.matches(&node.labels) && !chosen.contains(&node.name))
+                    .cloned()
+                    .collect();
+                let Some(index) = placement::select_node(&candidates, model, request, policy)
+                else {
+                    return Err(format!(
+                        "Only {} of {} replicas of model '{}' can be placed: {}",
+                        chosen.len(),
+                        pattern.get_replicas(),
+                        model.get_name(),
+                        placement::unschedulable_reason(&candidates, model, request)
+                    ));
+                };
+                let name = candidates[index].name.clone();
+                if let Some(node) = nodes.iter_mut().find(|node| node.name == name) {
+                    node.reserve(request);
+                }
+                chosen.push(name);
+            }
+            Ok(chosen)
+        }
+    }
+}
+
+/// Pod of a model renamed for one of its workloads
+fn replica_pod(pod_yaml: &str, name: &str) -> Result<String> {
+    let mut pod: serde_yaml::Value =
+        serde_yaml::from_str(pod_yaml).map_err(|e| format!("Failed to parse pod YAML: {}", e))?;
+    let metadata = pod
+        .get_mut("metadata")
+        .and_then(|m| m.as_mapping_mut())
+        .ok_or("Pod has no metadata")?;
+    metadata.insert(
+        serde_yaml::Value::String("name".to_string()),
+        serde_yaml::Value::String(name.to_string()),
+    );
+    serde_yaml::to_string(&pod).map_err(|e| format!("Failed to serialize pod YAML: {}", e).into())
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn node(name: &str, zone: &str, used_cpu_millis: u64) -> NodeCapacity {
+        NodeCapacity {
+            name: name.to_string(),
+            cpu_millis: 4000,
+            memory_mb: 4096,
+            used_cpu_millis,
+            used_memory_mb: 0,
+            labels: HashMap::from([("zone".to_string(), zone.to_string())]),
+            taints: Vec::new(),
+        }
+    }
+
+    fn pattern_package(pattern: &str) -> Package {
+        serde_yaml::from_str(&format!(
+            r#"
+apiVersion: v1
+kind: Package
+metadata:
+  name: pattern-package
+spec:
+  pattern:
+    - {}
+  models:
+    - name: model1
+      node: auto
+      resources: {{}}
+"#,
+            pattern
+        ))
+        .unwrap()
+    }
+
+    fn request(cpu_millis: u64) -> ResourceRequest {
+        ResourceRequest {
+            cpu_millis,
+            memory_mb: 0,
+        }
+    }
+
+    #[test]
+    fn test_choose_nodes_selector() {
+        let mut nodes = vec![
+            node("node1", "front", 0),
+            node("node2", "rear", 0),
+            node("node3", "front", 0),
+        ];
+        let package = pattern_package("type: selector\n      nodeSelector:\n        zone: front");
+        let chosen = choose_nodes(
+            &mut nodes,
+            &package.get_pattern(),
+            &package.get_models()[0],
+            &request(100),
+            &SchedulingPolicy::Spreading,
+        )
+        .unwrap();
+        assert_eq!(chosen, vec!["node1".to_string(), "node3".to_string()]);
+
+        let package = pattern_package("type: selector\n      nodeSelector:\n        zone: roof");
+        assert!(choose_nodes(
+            &mut nodes,
+            &package.get_pattern(),
+            &package.get_models()[0],
+            &request(100),
+            &SchedulingPolicy::Spreading
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_choose_nodes_distributed() {
+        let mut nodes = vec![
+            node("node1", "front", 3000),
+            node("node2", "rear", 0),
+            node("node3", "front", 1000),
+        ];
+        let package = pattern_package("type: distributed\n      replicas: 2");
+        let chosen = choose_nodes(
+            &mut nodes,
+            &package.get_pattern(),
+            &package.get_models()[0],
+            &request(500),
+            &SchedulingPolicy::Spreading,
+        )
+        .unwrap();
+        assert_eq!(chosen, vec!["node2".to_string(), "node3".to_string()]);
+        assert_eq!(nodes[1].used_cpu_millis, 500);
+        assert_eq!(nodes[2].used_cpu_millis, 1500);
+
+        let package = pattern_package(
+            "type: distributed\n      replicas: 3\n      nodeSelector:\n        zone: front",
+        );
+        let err = choose_nodes(
+            &mut nodes,
+            &package.get_pattern(),
+            &package.get_models()[0],
+            &request(500),
+            &SchedulingPolicy::Spreading,
+        )
+        .unwrap_err();
+        assert!(err.contains("Only 2 of 3 replicas"));
+    }
+
+    #[test]
+    fn test_replica_pod_renames_pod() {
+        let pod = replica_pod(
+            "apiVersion: v1\nkind: Pod\nmetadata:\n  name: model1\nspec:\n  containers: []\n",
+            "model1-node1",
+        )
+        .unwrap();
+        let pod: serde_yaml::Value = serde_yaml::from_str(&pod).unwrap();
+        assert_eq!(pod["metadata"]["name"], "model1-node1");
+        assert!(replica_pod("kind: Pod\n", "model1-node1").is_err());
+    }
+}
@@ -0,0 +1,589 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Node selection for models with `node: auto`
+//!
+//! Capacity comes from the node registry (`cluster/nodes/<hostname>`), the
+//! current usage from the node metrics stored by MonitoringServer
+//! (`/pullpiri/metrics/nodes/<hostname>`). A node without metrics counts as
+//! idle.
+//!
+//! The metadata of a registered node holds its labels and taints, which
+//! restrict the nodes a model may use through `nodeSelector` and
+//! `tolerations`.
+//!
+//! The reservation of a node lowers its capacity, and the resources
+//! allocated to models count as used even before the metrics show them.
+//!
+//! The `affinity` and `antiAffinity` rules of a package tie a model to the
+//! node of the models it must share and keep it off the nodes of the models
+//! it must avoid, once those have a node.
+use common::logd;
+use common::nodeagent::fromapiserver::NodeStatus;
+use common::spec::artifact::node::{Taint, TaintEffect};
+use common::spec::artifact::package::{ModelInfo, SchedulingPolicy};
+use common::spec::artifact::Package;
+use common::spec::k8s::pod::ResourceRequest;
+use common::Result;
+use std::collections::HashMap;
+
+const ETCD_CLUSTER_NODES_PREFIX: &str = "cluster/nodes/";
+const ETCD_NODE_METRICS_PREFIX: &str = "/pullpiri/metrics/nodes/";
+const NODE_ROLE_NODEAGENT: i32 = 2;
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// Resources of a node that can run models
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeCapacity {
+    pub name: String,
+    /// Thousandths of a CPU core
+    pub cpu_millis: u64,
+    pub memory_mb: u64,
+    pub used_cpu_millis: u64,
+    pub used_memory_mb: u64,
+    pub labels: HashMap<String, String>,
+    pub taints: Vec<Taint>,
+}
+
+impl NodeCapacity {
+    /// `true` if the free CPU and memory cover the request
+    pub fn fits(&self, request: &ResourceRequest) -> bool {
+        self.used_cpu_millis + request.cpu_millis <= self.cpu_millis
+            && self.used_memory_mb + request.memory_mb <= self.memory_mb
+    }
+
+    /// Average share of CPU and memory in use once the request is placed
+    pub fn load_with(&self, request: &ResourceRequest) -> f64 {
+        let share = |used: u64, total: u64| {
+            if total == 0 {
+                1.0
+            } else {
+                used as f64 / total as f64
+            }
+        };
+        (share(self.used_cpu_millis + request.cpu_millis, self.cpu_millis)
+            + share(self.used_memory_mb + request.memory_mb, self.memory_mb))
+            / 2.0
+    }
+
+    /// Count the request as used, so later models see the node fuller
+    pub fn reserve(&mut self, request: &ResourceRequest) {
+        self.used_cpu_millis += request.cpu_millis;
+        self.used_memory_mb += request.memory_mb;
+    }
+
+    /// `true` if the node has a `PreferNoSchedule` taint the model does not tolerate
+    fn is_avoided_by(&self, model: &ModelInfo) -> bool {
+        self.taints
+            .iter()
+            .any(|t| t.effect == TaintEffect::PreferNoSchedule && !model.tolerates(t))
+    }
+}
+
+/// Nodes left to a model by the affinity rules of its package
+#[derive(Debug, Default, PartialEq)]
+pub struct AffinityRule {
+    model: String,
+    /// Node of a model that must share the node, if one has a node
+    node: Option<(String, String)>,
+    /// Nodes of the models that must not share the node
+    avoid: Vec<(String, String)>,
+}
+
+impl AffinityRule {
+    /// Rule of `model` from the nodes assigned to the other models
+    ///
+    /// Models still on `auto` do not restrict the node yet.
+    pub fn of(package: &Package, model: &str) -> Self {
+        let node_of = |name: &String| {
+            package
+                .get_models()
+                .iter()
+                .find(|mi| mi.get_name() == *name && !mi.is_auto_node())
+                .map(|mi| (name.clone(), mi.get_node()))
+        };
+        AffinityRule {
+            model: model.to_string(),
+            node: package.get_colocated(model).iter().find_map(node_of),
+            avoid: package
+                .get_separated(model)
+                .iter()
+                .filter_map(node_of)
+                .collect(),
+        }
+    }
+
+    /// `true` if the rules let the model run on `node`
+    pub fn allows(&self, node: &str) -> bool {
+        self.node
+            .as_ref()
+            .is_none_or(|(_, required)| required == node)
+            && !self.avoid.iter().any(|(_, avoided)| avoided == node)
+    }
+
+    /// Explain why no node is left to the model
+    pub fn reason(&self) -> String {
+        match &self.node {
+            Some((peer, node)) => format!(
+                "Model '{}' must run on node '{}' of model '{}', which cannot take it",
+                self.model, node, peer
+            ),
+            None => format!(
+                "Every node runs a model that model '{}' must avoid: {}",
+                self.model,
+                self.avoid
+                    .iter()
+                    .map(|(peer, node)| format!("'{}' on '{}'", peer, node))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+/// Pick the node for a model
+///
+/// Only nodes that satisfy the `nodeSelector` and tolerations of the model
+/// and have enough free CPU and memory are considered. Nodes with an
+/// untolerated `PreferNoSchedule` taint are used only if no other node
+/// fits. Bin-packing takes the node that is the most loaded after
+/// placement, spreading the least loaded one. Ties go to the earlier node
+/// in `nodes`.
+///
+/// # Returns
+///
+/// * `Some(index)` of the chosen node in `nodes`
+/// * `None` if no node fits
+pub fn select_node(
+    nodes: &[NodeCapacity],
+    model: &ModelInfo,
+    request: &ResourceRequest,
+    policy: &SchedulingPolicy,
+) -> Option<usize> {
+    let mut best: Option<(usize, bool, f64)> = None;
+    for (index, node) in nodes.iter().enumerate() {
+        if !node.fits(request)
+            || model
+                .check_node(&node.name, &node.labels, &node.taints)
+                .is_err()
+        {
+            continue;
+        }
+        let avoided = node.is_avoided_by(model);
+        let load = node.load_with(request);
+        let better = match best {
+            None => true,
+            Some((_, best_avoided, _)) if avoided != best_avoided => !avoided,
+            Some((_, _, best_load)) => match policy {
+                SchedulingPolicy::BinPacking => load > best_load,
+                SchedulingPolicy::Spreading => load < best_load,
+            },
+        };
+        if better {
+            best = Some((index, avoided, load));
+        }
+    }
+    best.map(|(index, _, _)| index)
+}
+
+/// Explain why no node can take a model
+///
+/// Names the constraint of each node the model does not satisfy, or the
+/// missing resources if the constraints allow some nodes.
+pub fn unschedulable_reason(
+    nodes: &[NodeCapacity],
+    model: &ModelInfo,
+    request: &ResourceRequest,
+) -> String {
+    let violations: Vec<String> = nodes
+        .iter()
+        .filter_map(|node| {
+            model
+                .check_node(&node.name, &node.labels, &node.taints)
+                .err()
+        })
+        .collect();
+    if nodes.is_empty() {
+        format!("No node is available for model '{}'", model.get_name())
+    } else if violations.len() == nodes.len() {
+        format!(
+            "No node satisfies the placement constraints of model '{}': {}",
+            model.get_name(),
+            violations.join("; ")
+        )
+    } else {
+        format!(
+            "No node has {}m CPU and {}MiB memory free for model '{}'",
+            request.cpu_millis,
+            request.memory_mb,
+            model.get_name()
+        )
+    }
+}
+
+/// Check the `nodeSelector` and tolerations of a model against its node
+///
+/// The labels and taints come from the node registry. A node that is not
+/// registered has neither, so only models with a `nodeSelector` are
+/// rejected for it. A node in maintenance takes no models.
+///
+/// # Errors
+///
+/// Returns the constraint the node does not satisfy.
+pub async fn check_node(model: &ModelInfo, node: &str) -> Result<()> {
+    let key = format!("{}{}", ETCD_CLUSTER_NODES_PREFIX, node);
+    let metadata = match common::etcd::get(&key).await {
+        Ok(json) => {
+            let node_info = serde_json::from_str::<common::apiserver::NodeInfo>(&json)
+                .map_err(|e| format!("Invalid node entry '{}': {}", key, e))?;
+            if node_info.status == NodeStatus::Maintenance as i32 {
+                return Err(format!("Node '{}' is in maintenance", node).into());
+            }
+            node_info.metadata
+        }
+        Err(_) if model.get_node_selector().is_empty() => return Ok(()),
+        Err(_) => {
+            return Err(format!(
+                "Model '{}' has a nodeSelector but the labels of node '{}' are unknown",
+                model.get_name(),
+                node
+            )
+            .into())
+        }
+    };
+    model.check_node(node, &metadata, &Taint::from_metadata(&metadata))?;
+    Ok(())
+}
+
+/// Read the Ready NodeAgent nodes with their capacity and usage, sorted by name
+pub async fn load_nodes() -> Result<Vec<NodeCapacity>> {
+    let mut allocated: HashMap<String, ResourceRequest> = HashMap::new();
+    for allocation in common::allocation::load().await? {
+        let total = allocated.entry(allocation.node).or_default();
+        total.cpu_millis += allocation.cpu_millis;
+        total.memory_mb += allocation.memory_mb;
+    }
+
+    let mut nodes = Vec::new();
+    for (key, value) in common::etcd::get_all_with_prefix(ETCD_CLUSTER_NODES_PREFIX).await? {
+        let node_info: common::apiserver::NodeInfo = match serde_json::from_str(&value) {
+            Ok(node_info) => node_info,
+            Err(e) => {
+                logd!(4, "Warning: Invalid node entry '{}': {}", key, e);
+                continue;
+            }
+        };
+        if node_info.status != NodeStatus::Ready as i32
+            || node_info.node_role != NODE_ROLE_NODEAGENT
+        {
+            continue;
+        }
+
+        let metrics_key = format!("{}{}", ETCD_NODE_METRICS_PREFIX, node_info.hostname);
+        let metrics = common::etcd::get(&metrics_key)
+            .await
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok());
+        if let Some(mut node) = node_capacity(&node_info, metrics.as_ref()) {
+            if let Some(allocated) = allocated.get(&node.name) {
+                node.used_cpu_millis = node.used_cpu_millis.max(allocated.cpu_millis);
+                node.used_memory_mb = node.used_memory_mb.max(allocated.memory_mb);
+            }
+            nodes.push(node);
+        }
+    }
+
+    nodes.sort_by(|a, b| a.name.cmp(&b.name));
+    logd!(2, "{} nodes available for automatic placement", nodes.len());
+    Ok(nodes)
+}
+
+/// Combine the registered resources of a node with its reported usage
+///
+/// Resources missing from the registry are taken from the metrics, and the
+/// reservation of the node is left out. Nodes whose CPU or memory is unknown
+/// cannot be used.
+fn node_capacity(
+    node_info: &common::apiserver::NodeInfo,
+    metrics: Option<&common::monitoringserver::NodeInfo>,
+) -> Option<NodeCapacity> {
+    let resources = node_info.resources.clone().unwrap_or_default();
+    let cpu_millis = match resources.cpu_cores {
+        cores if cores > 0 => cores as u64 * 1000,
+        _ => metrics.map(|m| m.cpu_count * 1000).unwrap_or(0),
+    };
+    let memory_mb = match resources.memory_mb {
+        memory if memory > 0 => memory as u64,
+        _ => metrics.map(|m| m.total_memory / BYTES_PER_MB).unwrap_or(0),
+    };
+    if cpu_millis == 0 || memory_mb == 0 {
+        logd!(
+            4,
+            "Warning: Resources of node '{}' are unknown, skipping it for placement",
+            node_info.hostname
+        );
+        return None;
+    }
+
+    let reserved = common::allocation::reserved(&node_info.metadata);
+    let cpu_millis = cpu_millis.saturating_sub(reserved.cpu_millis);
+    let memory_mb = memory_mb.saturating_sub(reserved.memory_mb);
+
+    let (used_cpu_millis, used_memory_mb) = match metrics {
+        Some(m) => (
+            (m.cpu_usage.clamp(0.0, 100.0) / 100.0 * cpu_millis as f64) as u64,
+            (m.used_memory / BYTES_PER_MB).min(memory_mb),
+        ),
+        None => (0, 0),
+    };
+
+    Some(NodeCapacity {
+        name: node_info.hostname.clone(),
+        cpu_millis,
+        memory_mb,
+        used_cpu_millis,
+        used_memory_mb,
+        labels: node_info.metadata.clone(),
+        taints: Taint::from_metadata(&node_info.metadata),
+    })
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, cpu_millis: u64, memory_mb: u64, used: (u64, u64)) -> NodeCapacity {
+        NodeCapacity {
+            name: name.to_string(),
+            cpu_millis,
+            memory_mb,
+            used_cpu_millis: used.0,
+            used_memory_mb: used.1,
+            labels: HashMap::new(),
+            taints: Vec::new(),
+        }
+    }
+
+    fn model(yaml: &str) -> ModelInfo {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn any_model() -> ModelInfo {
+        model("name: any\nnode: auto\nresources: {}")
+    }
+
+    fn request(cpu_millis: u64, memory_mb: u64) -> ResourceRequest {
+        ResourceRequest {
+            cpu_millis,
+            memory_mb,
+        }
+    }
+
+    #[test]
+    fn test_bin_packing_fills_busiest_node() {
+        let nodes = vec![
+            node("idle", 4000, 4096, (0, 0)),
+            node("busy", 4000, 4096, (3000, 3072)),
+            node("full", 4000, 4096, (3900, 4000)),
+        ];
+        let policy = SchedulingPolicy::BinPacking;
+
+        assert_eq!(
+            select_node(&nodes, &any_model(), &request(500, 512), &policy),
+            Some(1)
+        );
+        // The busy node has no room left, the idle one is the only fit
+        assert_eq!(
+            select_node(&nodes, &any_model(), &request(1500, 512), &policy),
+            Some(0)
+        );
+        assert_eq!(
+            select_node(&nodes, &any_model(), &request(5000, 512), &policy),
+            None
+        );
+    }
+
+    #[test]
+    fn test_spreading_balances_models() {
+        let mut nodes = vec![
+            node("node-a", 2000, 2048, (0, 0)),
+            node("node-b", 2000, 2048, (0, 0)),
+        ];
+        let policy = SchedulingPolicy::Spreading;
+        let model = request(500, 512);
+
+        let mut placed = Vec::new();
+        for _ in 0..4 {
+            let index = select_node(&nodes, &any_model(), &model, &policy).unwrap();
+            nodes[index].reserve(&model);
+            placed.push(nodes[index].name.clone());
+        }
+        assert_eq!(placed, vec!["node-a", "node-b", "node-a", "node-b"]);
+
+        // Bin-packing keeps filling the first node instead
+        let mut nodes = vec![
+            node("node-a", 2000, 2048, (0, 0)),
+            node("node-b", 2000, 2048, (0, 0)),
+        ];
+        let mut placed = Vec::new();
+        for _ in 0..4 {
+            let index =
+                select_node(&nodes, &any_model(), &model, &SchedulingPolicy::BinPacking).unwrap();
+            nodes[index].reserve(&model);
+            placed.push(nodes[index].name.clone());
+        }
+        assert_eq!(placed, vec!["node-a", "node-a", "node-a", "node-a"]);
+    }
+
+    #[tokio::test]
+    async fn test_node_capacity_from_registry_and_metrics() {
+        let node_info = common::apiserver::NodeInfo {
+            hostname: "hpc".to_string(),
+            resources: Some(common::nodeagent::fromapiserver::ResourceInfo {
+                cpu_cores: 4,
+                memory_mb: 0,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let metrics = common::monitoringserver::NodeInfo {
+            cpu_usage: 25.0,
+            cpu_count: 8,
+            total_memory: 8192 * BYTES_PER_MB,
+            used_memory: 2048 * BYTES_PER_MB,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            node_capacity(&node_info, Some(&metrics)),
+            Some(node("hpc", 4000, 8192, (1000, 2048)))
+        );
+        // Without metrics the memory size is unknown
+        assert_eq!(node_capacity(&node_info, None), None);
+    }
+
+    #[tokio::test]
+    async fn test_node_capacity_leaves_out_reservation() {
+        let node_info = common::apiserver::NodeInfo {
+            hostname: "hpc".to_string(),
+            resources: Some(common::nodeagent::fromapiserver::ResourceInfo {
+                cpu_cores: 4,
+                memory_mb: 4096,
+                ..Default::default()
+            }),
+            metadata: HashMap::from([
+                ("reserved-cpu".to_string(), "1".to_string()),
+                ("reserved-memory".to_string(), "512Mi".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let capacity = node_capacity(&node_info, None).unwrap();
+        assert_eq!((capacity.cpu_millis, capacity.memory_mb), (3000, 3584));
+    }
+
+    #[test]
+    fn test_select_node_honors_selector_and_taints() {
+        let mut nodes = vec![
+            node("front", 4000, 4096, (0, 0)),
+            node("rear", 4000, 4096, (0, 0)),
+            node("spare", 4000, 4096, (0, 0)),
+        ];
+        nodes[0]
+            .labels
+            .insert("zone".to_string(), "front".to_string());
+        nodes[1]
+            .labels
+            .insert("zone".to_string(), "rear".to_string());
+        nodes[1].taints = vec![Taint::parse("dedicated=adas:NoSchedule").unwrap()];
+        nodes[2].taints = vec![Taint::parse("maintenance:PreferNoSchedule").unwrap()];
+        let policy = SchedulingPolicy::Spreading;
+        let small = request(500, 512);
+
+        let front = model("name: m\nnode: auto\nresources: {}\nnodeSelector:\n  zone: front");
+        assert_eq!(select_node(&nodes, &front, &small, &policy), Some(0));
+        // Without a toleration the tainted node is never used
+        let rear = model("name: m\nnode: auto\nresources: {}\nnodeSelector:\n  zone: rear");
+        assert_eq!(select_node(&nodes, &rear, &small, &policy), None);
+        assert!(unschedulable_reason(&nodes, &rear, &small).contains("dedicated=adas:NoSchedule"));
+        let adas = model(
+            "name: m\nnode: auto\nresources: {}\ntolerations:\n  - key: dedicated\n    value: adas",
+        );
+        nodes[0].reserve(&request(3000, 3072));
+        assert_eq!(select_node(&nodes, &adas, &small, &policy), Some(1));
+
+        // The PreferNoSchedule node is the last resort
+        nodes[1].reserve(&request(4000, 4096));
+        assert_eq!(select_node(&nodes, &any_model(), &small, &policy), Some(0));
+        assert_eq!(
+            select_node(&nodes, &any_model(), &request(2000, 512), &policy),
+            Some(2)
+        );
+        assert!(
+            unschedulable_reason(&nodes, &any_model(), &request(8000, 512)).contains("8000m CPU")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_node_without_registry_entry() {
+        let plain = any_model();
+        assert!(check_node(&plain, "unregistered-node").await.is_ok());
+
+        let selective = model("name: m\nnode: x\nresources: {}\nnodeSelector:\n  zone: front");
+        let err = check_node(&selective, "unregistered-node")
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("labels of node 'unregistered-node' are unknown"));
+    }
+
+    #[test]
+    fn test_affinity_rule_limits_nodes() {
+        let package: Package = serde_yaml::from_str(
+            r#"
+apiVersion: v1
+kind: Package
+metadata:
+  name: camera
+spec:
+  pattern:
+    - type: plain
+  models:
+    - name: camera-pipeline
+      node: node-a
+      resources: {}
+    - name: inference
+      node: auto
+      affinity: [camera-pipeline]
+      resources: {}
+    - name: inference-backup
+      node: auto
+      antiAffinity: [inference]
+      resources: {}
+"#,
+        )
+        .unwrap();
+
+        let rule = AffinityRule::of(&package, "inference");
+        assert!(rule.allows("node-a"));
+        assert!(!rule.allows("node-b"));
+        assert!(rule
+            .reason()
+            .contains("node 'node-a' of model 'camera-pipeline'"));
+
+        // The backup avoids the node of the camera pipeline the inference joins
+        let rule = AffinityRule::of(&package, "inference-backup");
+        assert!(!rule.allows("node-a"));
+        assert!(rule.allows("node-b"));
+
+        assert_eq!(
+            AffinityRule::of(&package, "camera-pipeline"),
+            AffinityRule {
+                model: "camera-pipeline".to_string(),
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,131 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Execution plan of a scenario action
+//!
+//! A trigger with `dry_run` resolves the package, models and nodes of a
+//! scenario like a real trigger but records the workload operations it would
+//! run instead of sending them to NodeAgent. Steps are numbered in execution
+//! order and grouped per node; steps of the same batch run concurrently.
+use common::actioncontroller::{ExecutionPlan, NodePlan, PlannedStep};
+
+/// Workload operation run on each model for a scenario action
+///
+/// `None` for actions that do not touch workloads.
+pub fn workload_operation(action: &str) -> Option<&'static str> {
+    match action {
+        "launch" => Some("start"),
+        "terminate" => Some("stop"),
+        "update" | "rollback" => Some("restart"),
+        _ => None,
+    }
+}
+
+/// Collects the steps of a plan in execution order
+pub struct PlanBuilder {
+    plan: ExecutionPlan,
+    next_order: u32,
+}
+
+impl PlanBuilder {
+    pub fn new(scenario_name: &str, package_name: &str, action: &str) -> Self {
+        Self {
+            plan: ExecutionPlan {
+                scenario_name: scenario_name.to_string(),
+                package_name: package_name.to_string(),
+                action: action.to_string(),
+                nodes: Vec::new(),
+                warnings: Vec::new(),
+            },
+            next_order: 1,
+        }
+    }
+
+    /// Append a step to the plan of `node`
+    ///
+    /// Nodes are listed in the order of their first step.
+    pub fn step(
+        &mut self,
+        node: &str,
+        node_type: &str,
+        batch: u32,
+        model_name: &str,
+        operation: &str,
+        detail: String,
+    ) {
+        let index = match self.plan.nodes.iter().position(|n| n.node == node) {
+            Some(index) => index,
+            None => {
+                self.plan.nodes.push(NodePlan {
+                    node: node.to_string(),
+                    node_type: node_type.to_string(),
+                    steps: Vec::new(),
+                });
+                self.plan.nodes.len() - 1
+            }
+        };
+        self.plan.nodes[index].steps.push(PlannedStep {
+            order: self.next_order,
+            batch,
+            model_name: model_name.to_string(),
+            operation: operation.to_string(),
+            detail,
+        });
+        self.next_order += 1;
+    }
+
+    /// Record something the real trigger would skip or stop at
+    pub fn warn(&mut self, warning: String) {
+        self.plan.warnings.push(warning);
+    }
+
+    pub fn finish(self) -> ExecutionPlan {
+        self.plan
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workload_operation() {
+        assert_eq!(workload_operation("launch"), Some("start"));
+        assert_eq!(workload_operation("terminate"), Some("stop"));
+        assert_eq!(workload_operation("update"), Some("restart"));
+        assert_eq!(workload_operation("rollback"), Some("restart"));
+        assert_eq!(workload_operation("pause"), None);
+    }
+
+    #[test]
+    fn test_plan_groups_steps_per_node_in_order() {
+        let mut builder = PlanBuilder::new("antipinch", "antipinch-pkg", "launch");
+        builder.step("HPC", "nodeagent", 1, "core", "start", String::new());
+        builder.step("ZONE", "nodeagent", 1, "front", "start", String::new());
+        builder.step("HPC", "nodeagent", 2, "ui", "start", String::new());
+        builder.warn("Node 'ECU' is unknown".to_string());
+        let plan = builder.finish();
+
+        assert_eq!(plan.scenario_name, "antipinch");
+        assert_eq!(plan.action, "launch");
+        let nodes: Vec<&str> = plan.nodes.iter().map(|n| n.node.as_str()).collect();
+        assert_eq!(nodes, vec!["HPC", "ZONE"]);
+        let hpc: Vec<(u32, u32, &str)> = plan.nodes[0]
+            .steps
+            .iter()
+            .map(|s| (s.order, s.batch, s.model_name.as_str()))
+            .collect();
+        assert_eq!(hpc, vec![(1, 1, "core"), (3, 2, "ui")]);
+        assert_eq!(plan.nodes[1].steps[0].order, 2);
+        assert_eq!(plan.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_empty_plan() {
+        let plan = PlanBuilder::new("antipinch", "", "launch").finish();
+        assert!(plan.nodes.is_empty());
+        assert!(plan.warnings.is_empty());
+    }
+}
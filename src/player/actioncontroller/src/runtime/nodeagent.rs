@@ -3,7 +3,10 @@
 * SPDX-License-Identifier: Apache-2.0
 */
 use common::logd;
-use common::nodeagent::fromactioncontroller::{HandleWorkloadRequest, WorkloadCommand};
+use common::nodeagent::fromactioncontroller::{
+    HandleUnitRequest, HandleWorkloadRequest, ImageToPull, PrePullImagesRequest, PrePullState,
+    UnitCommand, WorkloadCommand,
+};
 use common::Result;
 /// Runtime implementation for NodeAgent API interactions
 ///
@@ -52,6 +55,94 @@ pub async fn restart_workload(pod: &str, node_name: &str) -> Result<()> {
     Ok(())
 }
 
+pub async fn pause_workload(pod: &str, node_name: &str) -> Result<()> {
+    let cmd = WorkloadCommand::Pause;
+    handle_workload(cmd, pod, node_name).await?;
+    Ok(())
+}
+
+pub async fn unpause_workload(pod: &str, node_name: &str) -> Result<()> {
+    let cmd = WorkloadCommand::Unpause;
+    handle_workload(cmd, pod, node_name).await?;
+    Ok(())
+}
+
+pub async fn remove_workload(pod: &str, node_name: &str) -> Result<()> {
+    let cmd = WorkloadCommand::Remove;
+    handle_workload(cmd, pod, node_name).await?;
+    Ok(())
+}
+
+/// Run a unit command on a NodeAgent with the systemd workload backend
+///
+/// `unit` is ignored by `UnitCommand::DaemonReload`.
+pub async fn handle_unit(cmd: UnitCommand, unit: &str, node_name: &str) -> Result<()> {
+    if let Some(addr) = get_node_name_from_hostname(node_name).await {
+        logd!(2, "node_name: {}, addr: {}", node_name, addr);
+
+        let request = HandleUnitRequest {
+            unit_command: cmd.into(),
+            unit: unit.to_string(),
+        };
+        crate::grpc::sender::nodeagent::send_unit_handle_request(&addr, request).await?;
+    } else {
+        logd!(2, "Node {} not found in DB", node_name);
+        return Err(format!("Node {} not found in DB", node_name).into());
+    }
+
+    Ok(())
+}
+
+/// Pull images on a node ahead of a workload update
+///
+/// Waits until NodeAgent has finished every image. Fails if an image could
+/// not be pulled or does not match the digest of its reference.
+pub async fn prepull_images(images: &[String], node_name: &str) -> Result<()> {
+    let Some(addr) = get_node_name_from_hostname(node_name).await else {
+        logd!(2, "Node {} not found in DB", node_name);
+        return Err(format!("Node {} not found in DB", node_name).into());
+    };
+
+    let request = PrePullImagesRequest {
+        images: images
+            .iter()
+            .map(|image| ImageToPull {
+                image: image.clone(),
+                digest: String::new(),
+            })
+            .collect(),
+    };
+    let mut stream = crate::grpc::sender::nodeagent::send_prepull_request(&addr, request).await?;
+
+    let mut failures = Vec::new();
+    while let Some(progress) = stream.message().await? {
+        let state = PrePullState::try_from(progress.state).unwrap_or(PrePullState::Failed);
+        logd!(
+            2,
+            "Pre-pull on {} [{}/{}]: {} {:?} {}",
+            node_name,
+            progress.completed,
+            progress.total,
+            progress.image,
+            state,
+            progress.digest
+        );
+        if matches!(state, PrePullState::Failed | PrePullState::DigestMismatch) {
+            failures.push(format!("{} ({})", progress.image, progress.message));
+        }
+    }
+
+    if !failures.is_empty() {
+        return Err(format!(
+            "Pre-pull on node {} failed: {}",
+            node_name,
+            failures.join(", ")
+        )
+        .into());
+    }
+    Ok(())
+}
+
 /// Find a node by IP address from simplified node keys
 async fn get_node_name_from_hostname(hostname: &str) -> Option<String> {
     logd!(2, "Checking node keys in etcd...");
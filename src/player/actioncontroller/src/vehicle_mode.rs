@@ -0,0 +1,205 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Vehicle mode gate of scenarios with `allowedModes`
+//!
+//! A triggered scenario runs only while one of its `allowedModes` is active
+//! according to FilterGateway. Otherwise it is denied, or with
+//! `modeWaitSeconds` kept in `ScenarioDeferred/<name>` with its deadline and
+//! allowed modes until a mode is allowed or the deadline passes.
+use crate::manager::ActionControllerManager;
+use common::filtergateway::VehicleMode;
+use common::spec::artifact::Scenario;
+use common::{logd, Result};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const ETCD_DEFERRED_PREFIX: &str = "ScenarioDeferred";
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// What to do with a triggered scenario
+#[derive(Debug, PartialEq)]
+pub enum Decision {
+    /// No mode is required or one of them is active
+    Run,
+    /// Wait for an allowed mode until the deadline, in Unix seconds
+    Defer(u64),
+    /// Reject the scenario, no allowed mode is active
+    Deny,
+}
+
+fn decide(
+    allowed: &[String],
+    mode: &VehicleMode,
+    wait: Option<Duration>,
+    deferred_until: Option<u64>,
+    now: u64,
+) -> Decision {
+    if common::vehicle_mode::is_allowed(allowed, mode) {
+        return Decision::Run;
+    }
+    match (deferred_until, wait) {
+        (Some(deadline), _) if now < deadline => Decision::Defer(deadline),
+        (None, Some(wait)) => Decision::Defer(now + wait.as_secs()),
+        _ => Decision::Deny,
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Current vehicle mode, unknown if FilterGateway cannot be reached
+pub async fn current_mode() -> VehicleMode {
+    common::vehicle_mode::get().await.unwrap_or_else(|e| {
+        logd!(4, "Vehicle mode unknown: {}", e);
+        VehicleMode::default()
+    })
+}
+
+/// Check the vehicle mode required by a scenario
+///
+/// A deferred scenario keeps its first deadline when it is triggered again.
+///
+/// ### Returns
+/// * `(Decision, VehicleMode)` - the decision and the mode it is based on
+pub async fn check(scenario_name: &str, scenario: &Scenario) -> Result<(Decision, VehicleMode)> {
+    let allowed = scenario.get_allowed_modes();
+    if allowed.is_empty() {
+        return Ok((Decision::Run, VehicleMode::default()));
+    }
+
+    let mode = current_mode().await;
+    let deferred_until = common::etcd::get(&deferred_key(scenario_name))
+        .await
+        .ok()
+        .and_then(|value| parse_deferred(&value).map(|(deadline, _)| deadline));
+    let decision = decide(
+        &allowed,
+        &mode,
+        scenario.get_mode_wait(),
+        deferred_until,
+        now(),
+    );
+    match &decision {
+        Decision::Defer(deadline) if deferred_until.is_none() => {
+            let value = format!("{}:{}", deadline, allowed.join(","));
+            common::etcd::put(&deferred_key(scenario_name), &value).await?;
+        }
+        Decision::Defer(_) => {}
+        Decision::Run | Decision::Deny => {
+            if deferred_until.is_some() {
+                let _ = common::etcd::delete(&deferred_key(scenario_name)).await;
+            }
+        }
+    }
+    Ok((decision, mode))
+}
+
+fn deferred_key(scenario_name: &str) -> String {
+    format!("{}/{}", ETCD_DEFERRED_PREFIX, scenario_name)
+}
+
+/// Deadline and allowed modes of a deferred scenario, `<deadline>:<modes>`
+fn parse_deferred(value: &str) -> Option<(u64, Vec<String>)> {
+    let (deadline, modes) = value.split_once(':')?;
+    let modes = modes
+        .split(',')
+        .filter(|m| !m.is_empty())
+        .map(str::to_string)
+        .collect();
+    Some((deadline.parse().ok()?, modes))
+}
+
+/// Trigger deferred scenarios again once their mode is allowed or their
+/// deadline passed, which denies them
+pub async fn watch_deferred(manager: Arc<ActionControllerManager>) {
+    let prefix = format!("{}/", ETCD_DEFERRED_PREFIX);
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let Ok(deferred) = common::etcd::get_all_with_prefix(&prefix).await else {
+            continue;
+        };
+        if deferred.is_empty() {
+            continue;
+        }
+
+        let mode = current_mode().await;
+        let now = now();
+        for (key, value) in deferred {
+            let scenario_name = key.trim_start_matches(&prefix);
+            let ready = match parse_deferred(&value) {
+                Some((deadline, modes)) => {
+                    now >= deadline || common::vehicle_mode::is_allowed(&modes, &mode)
+                }
+                None => true,
+            };
+            if !ready {
+                continue;
+            }
+            if let Err(e) = manager.trigger_manager_action(scenario_name).await {
+                logd!(4, "Deferred scenario '{}' failed: {}", scenario_name, e);
+            }
+        }
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::filtergateway::{Ignition, Motion};
+
+    fn modes(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_decide() {
+        let allowed = modes(&["parked"]);
+        let parked = VehicleMode {
+            ignition: Ignition::On as i32,
+            motion: Motion::Parked as i32,
+            changed_ns: 1,
+        };
+        let driving = VehicleMode {
+            motion: Motion::Driving as i32,
+            ..parked
+        };
+        let wait = Some(Duration::from_secs(60));
+
+        assert_eq!(decide(&allowed, &parked, None, None, 100), Decision::Run);
+        assert_eq!(
+            decide(&allowed, &parked, wait, Some(50), 100),
+            Decision::Run
+        );
+        assert_eq!(decide(&allowed, &driving, None, None, 100), Decision::Deny);
+        assert_eq!(
+            decide(&allowed, &driving, wait, None, 100),
+            Decision::Defer(160)
+        );
+        assert_eq!(
+            decide(&allowed, &driving, wait, Some(160), 130),
+            Decision::Defer(160)
+        );
+        assert_eq!(
+            decide(&allowed, &driving, wait, Some(160), 160),
+            Decision::Deny
+        );
+        assert_eq!(decide(&[], &driving, None, None, 100), Decision::Run);
+    }
+
+    #[test]
+    fn test_parse_deferred() {
+        assert_eq!(
+            parse_deferred("160:parked,ignition-off"),
+            Some((160, modes(&["parked", "ignition-off"])))
+        );
+        assert_eq!(parse_deferred("160:"), Some((160, vec![])));
+        assert_eq!(parse_deferred("soon:parked"), None);
+    }
+}
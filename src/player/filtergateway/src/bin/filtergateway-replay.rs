@@ -0,0 +1,343 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Replay a recorded vehicle data trace against scenarios
+//!
+//! ```text
+//! filtergateway-replay --trace drive.jsonl --scenario scenarios.yaml \
+//!     --speed 10 --expect park-slow
+//! ```
+//!
+//! Prints every trigger. With `--expect` or `--expect-none`, exits with
+//! status 1 unless exactly the expected scenarios were triggered.
+use clap::Parser;
+use common::logd;
+use common::spec::artifact::{Artifact, Scenario};
+use filtergateway::filter::evaluate_condition;
+use filtergateway::vehicle::record::RecordedSample;
+use filtergateway::DdsData;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(name = "filtergateway-replay")]
+#[command(about = "Replay recorded vehicle data through scenario conditions")]
+struct Cli {
+    /// Trace file recorded by the FilterGateway
+    #[arg(short, long)]
+    trace: PathBuf,
+
+    /// YAML file with the scenarios, other kinds are ignored
+    #[arg(short, long, required = true)]
+    scenario: Vec<PathBuf>,
+
+    /// Replay speed relative to the recording, 0 replays without waiting
+    #[arg(long, default_value = "1.0")]
+    speed: f64,
+
+    /// Scenario expected to trigger, may be repeated
+    #[arg(short, long)]
+    expect: Vec<String>,
+
+    /// Expect no scenario to trigger
+    #[arg(long, conflicts_with = "expect")]
+    expect_none: bool,
+}
+
+/// Scenarios of a multi-document YAML file
+fn load_scenarios(path: &Path) -> common::Result<Vec<Scenario>> {
+    let content = std::fs::read_to_string(path)?;
+    let mut scenarios = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(&content) {
+        let value = serde_yaml::Value::deserialize(document)?;
+        if value.get("kind").and_then(|kind| kind.as_str()) == Some("Scenario") {
+            scenarios.push(serde_yaml::from_value(value)?);
+        }
+    }
+    Ok(scenarios)
+}
+
+/// Read the samples of a trace file, empty lines are skipped
+fn read_trace(path: &Path) -> common::Result<Vec<RecordedSample>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut samples = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let sample = serde_json::from_str(&line)
+            .map_err(|e| format!("{}:{}: {}", path.display(), index + 1, e))?;
+        samples.push(sample);
+    }
+    Ok(samples)
+}
+
+/// Sample as delivered to the filters
+fn sample_data(sample: &RecordedSample) -> DdsData {
+    DdsData {
+        name: sample.topic.clone(),
+        value: sample.value.clone(),
+        fields: sample.fields.clone(),
+    }
+}
+
+/// Scenario whose condition was met by a replayed sample
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct Trigger {
+    scenario: String,
+    /// Timestamp of the sample that met the condition
+    timestamp_ms: i64,
+}
+
+/// Feed recorded samples through the conditions of the scenarios
+///
+/// Like the filters, a scenario is checked whenever a sample of one of its
+/// topics arrives, against the latest sample of every topic, and triggers
+/// each time its condition is met. Scenarios with schedule conditions are
+/// ignored.
+///
+/// # Arguments
+///
+/// * `samples` - Recorded samples in reception order
+/// * `scenarios` - Scenarios to evaluate
+/// * `speed` - Replay speed relative to the recording, e.g. `10.0` for ten
+///   times faster. `0` replays without waiting.
+///
+/// # Returns
+///
+/// * `Result<Vec<Trigger>>` - Triggers in replay order
+async fn replay(
+    samples: &[RecordedSample],
+    scenarios: &[Scenario],
+    speed: f64,
+) -> common::Result<Vec<Trigger>> {
+    let mut vehicle_data: HashMap<String, DdsData> = HashMap::new();
+    let mut triggers = Vec::new();
+    let mut previous: Option<i64> = None;
+
+    for sample in samples {
+        if let Some(previous) = previous {
+            let delay_ms = (sample.timestamp_ms - previous).max(0) as f64;
+            if speed > 0.0 && delay_ms > 0.0 {
+                tokio::time::sleep(Duration::from_secs_f64(delay_ms / 1000.0 / speed)).await;
+            }
+        }
+        previous = Some(sample.timestamp_ms);
+        vehicle_data.insert(sample.topic.clone(), sample_data(sample));
+
+        for scenario in scenarios {
+            let condition = match scenario.get_conditions() {
+                Some(condition) if !condition.is_schedule() => condition,
+                _ => continue,
+            };
+            if !condition.get_topics().contains(&sample.topic) {
+                continue;
+            }
+            match evaluate_condition(&condition, &vehicle_data) {
+                Ok(Some(true)) => triggers.push(Trigger {
+                    scenario: scenario.get_qualified_name(),
+                    timestamp_ms: sample.timestamp_ms,
+                }),
+                Ok(_) => {}
+                Err(e) => logd!(
+                    4,
+                    "Cannot evaluate scenario {} at {}: {}",
+                    scenario.get_name(),
+                    sample.timestamp_ms,
+                    e
+                ),
+            }
+        }
+    }
+    Ok(triggers)
+}
+
+/// Check that exactly the expected scenarios were triggered
+///
+/// # Returns
+///
+/// * `Err(String)` - Description of the missing and unexpected scenarios
+fn check_triggered(triggers: &[Trigger], expected: &[String]) -> std::result::Result<(), String> {
+    let triggered: BTreeSet<&str> = triggers.iter().map(|t| t.scenario.as_str()).collect();
+    let expected: BTreeSet<&str> = expected.iter().map(String::as_str).collect();
+
+    let missing: Vec<&str> = expected.difference(&triggered).copied().collect();
+    let unexpected: Vec<&str> = triggered.difference(&expected).copied().collect();
+    if missing.is_empty() && unexpected.is_empty() {
+        return Ok(());
+    }
+    let mut problems = Vec::new();
+    if !missing.is_empty() {
+        problems.push(format!("not triggered: {}", missing.join(", ")));
+    }
+    if !unexpected.is_empty() {
+        problems.push(format!("unexpectedly triggered: {}", unexpected.join(", ")));
+    }
+    Err(problems.join("; "))
+}
+
+async fn run(cli: &Cli) -> common::Result<bool> {
+    let samples = read_trace(&cli.trace)?;
+    let mut scenarios = Vec::new();
+    for path in &cli.scenario {
+        scenarios.extend(load_scenarios(path)?);
+    }
+    println!(
+        "Replaying {} samples against {} scenarios",
+        samples.len(),
+        scenarios.len()
+    );
+
+    let triggers = replay(&samples, &scenarios, cli.speed).await?;
+    for trigger in &triggers {
+        println!("{} triggered at {}", trigger.scenario, trigger.timestamp_ms);
+    }
+
+    if cli.expect.is_empty() && !cli.expect_none {
+        return Ok(true);
+    }
+    match check_triggered(&triggers, &cli.expect) {
+        Ok(()) => {
+            println!("PASS");
+            Ok(true)
+        }
+        Err(e) => {
+            eprintln!("FAIL: {}", e);
+            Ok(false)
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli).await {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("Replay failed: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scenario(yaml: &str) -> Scenario {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn sample(timestamp_ms: i64, topic: &str, field: &str, value: &str) -> RecordedSample {
+        RecordedSample {
+            timestamp_ms,
+            topic: topic.to_string(),
+            data_type: topic.to_string(),
+            value: format!("{{\"{}\":\"{}\"}}", field, value),
+            fields: HashMap::from([(field.to_string(), value.to_string())]),
+        }
+    }
+
+    #[test]
+    fn test_read_trace() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("drive.jsonl");
+        let first = sample(1, "speed", "value", "80");
+        let second = sample(5, "gear", "gear", "P");
+        let lines: Vec<String> = [&first, &second]
+            .iter()
+            .map(|s| serde_json::to_string(s).unwrap())
+            .collect();
+        std::fs::write(&path, format!("{}\n\n{}\n", lines[0], lines[1])).unwrap();
+
+        let samples = read_trace(&path).unwrap();
+        assert_eq!(samples, vec![first, second]);
+        assert_eq!(sample_data(&samples[0]).fields["value"], "80");
+
+        std::fs::write(&path, "{\"topic\":\"speed\"}\n").unwrap();
+        let error = read_trace(&path).unwrap_err().to_string();
+        assert!(error.contains(":1:"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_replay_reports_triggered_scenarios() {
+        let scenarios = vec![
+            scenario(
+                r#"
+apiVersion: v1
+kind: Scenario
+metadata:
+  name: park-slow
+spec:
+  condition:
+    all:
+      - express: lt
+        value: "5"
+        operands: { type: DDS, name: value, value: speed }
+      - express: eq
+        value: P
+        operands: { type: DDS, name: gear, value: gear }
+  action: update
+  target: park-assist
+"#,
+            ),
+            scenario(
+                r#"
+apiVersion: v1
+kind: Scenario
+metadata:
+  name: overspeed
+spec:
+  condition:
+    express: gt
+    value: "120"
+    operands: { type: DDS, name: value, value: speed }
+  action: update
+  target: warning
+"#,
+            ),
+        ];
+        let samples = vec![
+            sample(1_000, "speed", "value", "30"),
+            sample(2_000, "gear", "gear", "P"),
+            sample(3_000, "speed", "value", "3"),
+        ];
+
+        let triggers = replay(&samples, &scenarios, 0.0).await.unwrap();
+        assert_eq!(
+            triggers,
+            vec![Trigger {
+                scenario: "park-slow".to_string(),
+                timestamp_ms: 3_000,
+            }]
+        );
+        assert!(check_triggered(&triggers, &["park-slow".to_string()]).is_ok());
+        assert_eq!(
+            check_triggered(&triggers, &["overspeed".to_string()]).unwrap_err(),
+            "not triggered: overspeed; unexpectedly triggered: park-slow"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_speed_scales_delays() {
+        let samples = vec![
+            sample(0, "speed", "value", "30"),
+            sample(10_000, "speed", "value", "40"),
+        ];
+        // Ten seconds of recording replayed 100 times faster
+        let start = std::time::Instant::now();
+        replay(&samples, &[], 100.0).await.unwrap();
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(100), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(5), "{:?}", elapsed);
+    }
+}
@@ -4,11 +4,15 @@
 */
 use crate::grpc::sender::actioncontroller::FilterGatewaySender;
 use crate::grpc::sender::statemanager::StateManagerSender;
+use crate::policy::{PolicyDecision, PolicyEngine};
 use crate::vehicle::dds::DdsData;
 use common::logd;
+use common::spec::artifact::scenario::Condition;
 use common::spec::artifact::Scenario;
 use common::statemanager::{ResourceType, StateChange};
 use common::Result;
+use std::collections::HashMap;
+use std::time::Instant;
 // use dust_dds::infrastructure::wait_set::Condition;
 // use std::sync::Arc;
 // use tokio::sync::{mpsc, Mutex};
@@ -26,6 +30,12 @@ pub struct Filter {
     sender: FilterGatewaySender,
     /// gRPC sender for state manager
     state_sender: StateManagerSender,
+    /// Policy check before the scenario action is triggered
+    policy_engine: PolicyEngine,
+    /// Since when the condition has been met without interruption
+    condition_met_since: Option<Instant>,
+    /// When the condition last triggered the scenario
+    last_triggered: Option<Instant>,
 }
 
 #[allow(dead_code)]
@@ -38,6 +48,7 @@ impl Filter {
     /// * `scenario` - Full scenario definition
     /// * `rx_dds` - Receiver for DDS data
     /// * `sender` - Sender for gRPC calls
+    /// * `policy_engine` - Policy engine shared with the manager
     ///
     /// # Returns
     ///
@@ -47,6 +58,7 @@ impl Filter {
         scenario: Scenario,
         is_active: bool,
         sender: FilterGatewaySender,
+        policy_engine: PolicyEngine,
     ) -> Self {
         Self {
             scenario_name,
@@ -54,13 +66,17 @@ impl Filter {
             is_active,
             sender,
             state_sender: StateManagerSender::new(),
+            policy_engine,
+            condition_met_since: None,
+            last_triggered: None,
         }
     }
 
     /// Check if scenario conditions are met
     ///
     /// Evaluates if the received vehicle data meets the scenario conditions.
-    /// If conditions are met, triggers an action through ActionController.
+    /// If conditions are met and no policy denies the scenario, triggers an
+    /// action through ActionController.
     ///
     /// # Arguments
     ///
@@ -74,6 +90,9 @@ impl Filter {
         let start = Instant::now();
 
         let condition = self.scenario.get_conditions().unwrap();
+        if condition.is_compound() {
+            return self.meet_compound_condition(&condition, data).await;
+        }
         let topic = condition.get_operand_value();
         let value_name = condition.get_operand_name();
         let target_value = condition.get_value();
@@ -99,48 +118,12 @@ impl Filter {
             }
         };
 
-        let check: bool = match express.as_str() {
-            "eq" => target_value.to_lowercase() == field_value.to_lowercase(),
-            "lt" => {
-                let target_v = target_value
-                    .parse::<f32>()
-                    .map_err(|_| "target_value parse error")?;
-                let current_v = field_value
-                    .parse::<f32>()
-                    .map_err(|_| "field_value parse error")?;
-                current_v < target_v
-            }
-            "le" => {
-                let target_v = target_value
-                    .parse::<f32>()
-                    .map_err(|_| "target_value parse error")?;
-                let current_v = field_value
-                    .parse::<f32>()
-                    .map_err(|_| "field_value parse error")?;
-                current_v <= target_v
-            }
-            "ge" => {
-                let target_v = target_value
-                    .parse::<f32>()
-                    .map_err(|_| "target_value parse error")?;
-                let current_v = field_value
-                    .parse::<f32>()
-                    .map_err(|_| "field_value parse error")?;
-                current_v >= target_v
-            }
-            "gt" => {
-                let target_v = target_value
-                    .parse::<f32>()
-                    .map_err(|_| "target_value parse error")?;
-                let current_v = field_value
-                    .parse::<f32>()
-                    .map_err(|_| "field_value parse error")?;
-                current_v > target_v
-            }
-            _ => {
+        let check = match compare_values(&express, &target_value, field_value) {
+            Ok(check) => check,
+            Err(e) => {
                 let elapsed = start.elapsed();
                 logd!(3, "meet_scenario_condition: elapsed = {:?}", elapsed);
-                return Err("wrong expression in condition".into());
+                return Err(e);
             }
         };
 
@@ -149,73 +132,244 @@ impl Filter {
 
         if check {
             logd!(1, "Condition met for scenario: {}", self.scenario_name);
-            logd!(1, "🔄 SCENARIO STATE TRANSITION: FilterGateway Processing");
-            logd!(1, "   📋 Scenario: {}", self.scenario_name);
-            logd!(1, "   🔄 State Change: idle → waiting");
-            logd!(1, "   🔍 Reason: Scenario condition satisfied");
-
-            // 🔍 COMMENT 1: FilterGateway condition registration
-            // When scenario condition is met, FilterGateway triggers ActionController
-            // via gRPC call. This initiates the scenario processing workflow.
-            // The ActionController will then handle state changes with StateManager.
-
-            // Send state change to StateManager: waiting -> satisfied
-            let timestamp = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_nanos() as i64;
-
-            let state_change = StateChange {
-                resource_type: ResourceType::Scenario as i32,
-                resource_name: self.scenario_name.clone(),
-                current_state: "waiting".to_string(),
-                target_state: "satisfied".to_string(),
-                transition_id: format!("filtergateway-condition-satisfied-{}", timestamp),
-                timestamp_ns: timestamp,
-                source: "filtergateway".to_string(),
-            };
-
-            logd!(1, "   📤 Sending StateChange to StateManager:");
-            logd!(1, "      • Resource Type: SCENARIO");
-            logd!(1, "      • Resource Name: {}", state_change.resource_name);
-            logd!(1, "      • Current State: {}", state_change.current_state);
-            logd!(1, "      • Target State: {}", state_change.target_state);
-            logd!(1, "      • Transition ID: {}", state_change.transition_id);
-            logd!(1, "      • Source: {}", state_change.source);
-
-            if let Err(e) = self
-                .state_sender
-                .clone()
-                .send_state_change(state_change)
-                .await
-            {
-                logd!(
-                    5,
-                    "   ❌ Failed to send state change to StateManager: {:?}",
-                    e
-                );
-            } else {
+            self.trigger_when_settled().await
+        } else {
+            self.condition_met_since = None;
+            Err("cannot meet condition".into())
+        }
+    }
+
+    /// Check a compound scenario condition
+    ///
+    /// The signals of the condition may come from several topics, so the
+    /// latest value of each one is used, with `data` as the newest.
+    ///
+    /// # Arguments
+    ///
+    /// * `condition` - Compound condition of the scenario
+    /// * `data` - Vehicle message data just received
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Success or error result
+    async fn meet_compound_condition(
+        &mut self,
+        condition: &Condition,
+        data: &DdsData,
+    ) -> Result<()> {
+        self.policy_engine.update_vehicle_data(data).await;
+        let met = match self.policy_engine.evaluate_condition(condition).await {
+            Ok(met) => met,
+            Err(e) => return Err(e),
+        };
+
+        match met {
+            Some(true) => {
+                logd!(1, "Condition met for scenario: {}", self.scenario_name);
+                self.trigger_when_settled().await
+            }
+            Some(false) => {
+                self.condition_met_since = None;
+                Err("cannot meet condition".into())
+            }
+            None => {
                 logd!(
                     1,
-                    "   ✅ Successfully notified StateManager: scenario {} waiting → satisfied",
+                    "Condition of scenario {} waits for more vehicle data",
                     self.scenario_name
                 );
+                Err("cannot meet condition".into())
             }
+        }
+    }
 
-            logd!(1, "   📤 Triggering ActionController via gRPC...");
-            if let Err(e) = self.sender.trigger_action(self.scenario_name.clone()).await {
+    /// Trigger the scenario for a met condition unless it is debounced or cooling down
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Success, or "cannot meet condition" if the trigger was held back
+    async fn trigger_when_settled(&mut self) -> Result<()> {
+        let now = Instant::now();
+        if !self.settled(now) {
+            return Err("cannot meet condition".into());
+        }
+        self.condition_met_since = None;
+        self.last_triggered = Some(now);
+        self.trigger_scenario().await
+    }
+
+    /// Whether a met condition may trigger the scenario at `now`
+    ///
+    /// The condition has to be met for `debounceSeconds` in a row, and
+    /// `cooldownSeconds` have to pass since the last trigger.
+    fn settled(&mut self, now: Instant) -> bool {
+        let since = *self.condition_met_since.get_or_insert(now);
+        let debounce = self.scenario.get_debounce();
+        if now.duration_since(since) < debounce {
+            logd!(
+                1,
+                "Scenario {} debounced, condition met for {:?} of {:?}",
+                self.scenario_name,
+                now.duration_since(since),
+                debounce
+            );
+            return false;
+        }
+
+        let cooldown = self.scenario.get_cooldown();
+        if let Some(last) = self.last_triggered {
+            if now.duration_since(last) < cooldown {
                 logd!(
-                    5,
-                    "   ❌ Failed to trigger ActionController for scenario {}: {:?}. Continuing.",
+                    2,
+                    "Scenario {} cooling down, {:?} left",
                     self.scenario_name,
-                    e
+                    cooldown - now.duration_since(last)
                 );
-            } else {
-                logd!(2, "   ✅ ActionController triggered successfully");
+                return false;
             }
-            Ok(())
+        }
+        true
+    }
+
+    /// Report the scenario as satisfied and trigger its action
+    ///
+    /// Called when the data condition is met, and by the scheduler when a
+    /// schedule condition fires. Policies are checked before ActionController
+    /// is triggered. Every trigger starts a new trace, followed by the
+    /// requests to StateManager and ActionController.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Success or error result
+    pub async fn trigger_scenario(&mut self) -> Result<()> {
+        common::trace::in_span(
+            "filtergateway.trigger_scenario",
+            Some(common::trace::TraceContext::new_root()),
+            self.report_and_trigger(),
+        )
+        .await
+    }
+
+    async fn report_and_trigger(&mut self) -> Result<()> {
+        logd!(
+            3,
+            "Scenario {} triggered, trace {}",
+            self.scenario_name,
+            common::trace::current_trace_id().unwrap_or_default()
+        );
+        logd!(1, "🔄 SCENARIO STATE TRANSITION: FilterGateway Processing");
+        logd!(1, "   📋 Scenario: {}", self.scenario_name);
+        logd!(1, "   🔄 State Change: idle → waiting");
+        logd!(1, "   🔍 Reason: Scenario condition satisfied");
+
+        // 🔍 COMMENT 1: FilterGateway condition registration
+        // When scenario condition is met, FilterGateway triggers ActionController
+        // via gRPC call. This initiates the scenario processing workflow.
+        // The ActionController will then handle state changes with StateManager.
+
+        // Send state change to StateManager: waiting -> satisfied
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as i64;
+
+        let state_change = StateChange {
+            resource_type: ResourceType::Scenario as i32,
+            resource_name: self.scenario_name.clone(),
+            current_state: "waiting".to_string(),
+            target_state: "satisfied".to_string(),
+            transition_id: format!("filtergateway-condition-satisfied-{}", timestamp),
+            timestamp_ns: timestamp,
+            source: "filtergateway".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: common::trace::current_trace_id().unwrap_or_default(),
+        };
+
+        logd!(1, "   📤 Sending StateChange to StateManager:");
+        logd!(1, "      • Resource Type: SCENARIO");
+        logd!(1, "      • Resource Name: {}", state_change.resource_name);
+        logd!(1, "      • Current State: {}", state_change.current_state);
+        logd!(1, "      • Target State: {}", state_change.target_state);
+        logd!(1, "      • Transition ID: {}", state_change.transition_id);
+        logd!(1, "      • Source: {}", state_change.source);
+
+        if let Err(e) = self
+            .state_sender
+            .clone()
+            .send_state_change(state_change)
+            .await
+        {
+            logd!(
+                5,
+                "   ❌ Failed to send state change to StateManager: {:?}",
+                e
+            );
         } else {
-            Err("cannot meet condition".into())
+            logd!(
+                1,
+                "   ✅ Successfully notified StateManager: scenario {} waiting → satisfied",
+                self.scenario_name
+            );
+        }
+
+        if let PolicyDecision::Deny { policy, rule } =
+            self.policy_engine.check(&self.scenario).await
+        {
+            self.deny_scenario(&policy, &rule).await;
+            return Ok(());
+        }
+
+        logd!(1, "   📤 Triggering ActionController via gRPC...");
+        if let Err(e) = self.sender.trigger_action(self.scenario_name.clone()).await {
+            logd!(
+                5,
+                "   ❌ Failed to trigger ActionController for scenario {}: {:?}. Continuing.",
+                self.scenario_name,
+                e
+            );
+        } else {
+            logd!(2, "   ✅ ActionController triggered successfully");
+        }
+        Ok(())
+    }
+
+    /// Report a policy denial to StateManager: satisfied -> denied
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - Name of the denying policy
+    /// * `rule` - Name of the denying rule
+    async fn deny_scenario(&mut self, policy: &str, rule: &str) {
+        logd!(
+            3,
+            "   ⛔ Scenario {} denied by policy {} (rule {})",
+            self.scenario_name,
+            policy,
+            rule
+        );
+
+        if let Err(e) = self
+            .state_sender
+            .report_policy_decision(
+                ResourceType::Scenario,
+                &self.scenario_name,
+                "satisfied",
+                "denied",
+                &format!("{}-{}", policy, rule),
+            )
+            .await
+        {
+            logd!(
+                5,
+                "   ❌ Failed to send state change to StateManager: {:?}",
+                e
+            );
+        } else {
+            logd!(
+                1,
+                "   ✅ Successfully notified StateManager: scenario {} satisfied → denied",
+                self.scenario_name
+            );
         }
     }
 
@@ -295,8 +449,7 @@ impl Filter {
             None => return Ok(()), // No conditions case (already handled)
         };
 
-        let topic = condition.get_operand_value();
-        if !data.name.eq(&topic) {
+        if !condition.get_topics().contains(&data.name) {
             return Ok(()); // Ignore unrelated topics
         }
 
@@ -318,9 +471,126 @@ impl Filter {
         Ok(())
     }
 }
+/// Compare a received vehicle value against a condition value
+///
+/// # Arguments
+///
+/// * `express` - Comparison operator (`eq`, `lt`, `le`, `ge`, `gt`)
+/// * `target_value` - Value from the condition
+/// * `field_value` - Value received from vehicle data
+///
+/// # Returns
+///
+/// * `Result<bool>` - Comparison result, or error for unknown operators and non-numeric values
+pub fn compare_values(express: &str, target_value: &str, field_value: &str) -> Result<bool> {
+    if express == "eq" {
+        return Ok(target_value.to_lowercase() == field_value.to_lowercase());
+    }
+    if !matches!(express, "lt" | "le" | "ge" | "gt") {
+        return Err("wrong expression in condition".into());
+    }
+
+    let target_v = target_value
+        .parse::<f32>()
+        .map_err(|_| "target_value parse error")?;
+    let current_v = field_value
+        .parse::<f32>()
+        .map_err(|_| "field_value parse error")?;
+    Ok(match express {
+        "lt" => current_v < target_v,
+        "le" => current_v <= target_v,
+        "ge" => current_v >= target_v,
+        _ => current_v > target_v,
+    })
+}
+
+/// Evaluate a data condition against the latest vehicle data
+///
+/// `all` stops at the first condition that is not met and `any` at the
+/// first one that is. Every evaluated subexpression is traced with its
+/// path, e.g. `condition.all[1].any[0]`.
+///
+/// # Arguments
+///
+/// * `condition` - Condition, simple or compound
+/// * `vehicle_data` - Latest DDS data per topic
+///
+/// # Returns
+///
+/// * `Ok(Some(bool))` - Whether the condition is met
+/// * `Ok(None)` - A signal the result depends on has not been received yet
+/// * `Err(...)` - A comparison cannot be evaluated
+pub fn evaluate_condition(
+    condition: &Condition,
+    vehicle_data: &HashMap<String, DdsData>,
+) -> Result<Option<bool>> {
+    evaluate_at(condition, vehicle_data, "condition")
+}
+
+fn evaluate_at(
+    condition: &Condition,
+    vehicle_data: &HashMap<String, DdsData>,
+    path: &str,
+) -> Result<Option<bool>> {
+    let result = if !condition.get_all().is_empty() {
+        let mut result = Some(true);
+        for (index, sub) in condition.get_all().iter().enumerate() {
+            match evaluate_at(sub, vehicle_data, &format!("{}.all[{}]", path, index))? {
+                Some(true) => {}
+                Some(false) => {
+                    result = Some(false);
+                    break;
+                }
+                None => result = None,
+            }
+        }
+        result
+    } else if !condition.get_any().is_empty() {
+        let mut result = Some(false);
+        for (index, sub) in condition.get_any().iter().enumerate() {
+            match evaluate_at(sub, vehicle_data, &format!("{}.any[{}]", path, index))? {
+                Some(true) => {
+                    result = Some(true);
+                    break;
+                }
+                Some(false) => {}
+                None => result = None,
+            }
+        }
+        result
+    } else if let Some(sub) = condition.get_not() {
+        evaluate_at(sub, vehicle_data, &format!("{}.not", path))?.map(|met| !met)
+    } else {
+        let field_value = vehicle_data
+            .get(&condition.get_operand_value())
+            .and_then(|data| data.fields.get(&condition.get_operand_name()));
+        match field_value {
+            Some(field_value) => Some(compare_values(
+                &condition.get_express(),
+                &condition.get_value(),
+                field_value,
+            )?),
+            None => None,
+        }
+    };
+
+    logd!(
+        1,
+        "{}: {}",
+        path,
+        match result {
+            Some(true) => "met",
+            Some(false) => "not met",
+            None => "no data",
+        }
+    );
+    Ok(result)
+}
+
 //Unit Test Cases
 #[cfg(test)]
 mod tests {
+    use super::compare_values;
     use async_trait::async_trait;
     use common::Result;
     use mockall::{mock, predicate};
@@ -723,4 +993,120 @@ mod tests {
 
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_compare_values() {
+        assert!(compare_values("eq", "Drive", "drive").unwrap());
+        assert!(compare_values("gt", "0", "12.5").unwrap());
+        assert!(!compare_values("lt", "10", "10").unwrap());
+        assert!(compare_values("le", "10", "10").unwrap());
+        assert!(compare_values("ge", "10", "abc").is_err());
+        assert!(compare_values("ne", "1", "2").is_err());
+    }
+
+    fn vehicle_data(
+        signals: &[(&str, &str, &str)],
+    ) -> HashMap<String, crate::vehicle::dds::DdsData> {
+        let mut data: HashMap<String, crate::vehicle::dds::DdsData> = HashMap::new();
+        for (topic, field, value) in signals {
+            data.entry(topic.to_string())
+                .or_insert_with(|| crate::vehicle::dds::DdsData {
+                    name: topic.to_string(),
+                    value: String::new(),
+                    fields: HashMap::new(),
+                })
+                .fields
+                .insert(field.to_string(), value.to_string());
+        }
+        data
+    }
+
+    #[test]
+    fn test_evaluate_compound_condition() {
+        use super::evaluate_condition;
+
+        // speed < 5 AND (gear == P OR NOT ignition == on)
+        let condition: common::spec::artifact::scenario::Condition = serde_yaml::from_str(
+            r#"
+all:
+  - express: lt
+    value: "5"
+    operands: {type: DDS, name: speed, value: speed}
+  - any:
+      - express: eq
+        value: P
+        operands: {type: DDS, name: gear, value: gear}
+      - not:
+          express: eq
+          value: "on"
+          operands: {type: DDS, name: ignition, value: ignition}
+"#,
+        )
+        .unwrap();
+
+        let stopped = vehicle_data(&[("speed", "speed", "0"), ("gear", "gear", "p")]);
+        assert_eq!(
+            evaluate_condition(&condition, &stopped).unwrap(),
+            Some(true)
+        );
+
+        let ignition_off = vehicle_data(&[
+            ("speed", "speed", "3"),
+            ("gear", "gear", "D"),
+            ("ignition", "ignition", "off"),
+        ]);
+        assert_eq!(
+            evaluate_condition(&condition, &ignition_off).unwrap(),
+            Some(true)
+        );
+
+        // short-circuit: the gear is never read once the speed is too high
+        let driving = vehicle_data(&[("speed", "speed", "50")]);
+        assert_eq!(
+            evaluate_condition(&condition, &driving).unwrap(),
+            Some(false)
+        );
+
+        // the ignition has not been received, the gear does not decide alone
+        let unknown = vehicle_data(&[("speed", "speed", "0"), ("gear", "gear", "D")]);
+        assert_eq!(evaluate_condition(&condition, &unknown).unwrap(), None);
+
+        let invalid = vehicle_data(&[("speed", "speed", "fast")]);
+        assert!(evaluate_condition(&condition, &invalid).is_err());
+    }
+
+    fn flapping_filter(spec: &str) -> super::Filter {
+        let scenario: common::spec::artifact::Scenario = serde_yaml::from_str(&format!(
+            "apiVersion: v1\nkind: Scenario\nmetadata:\n  name: flapping\nspec:\n  action: launch\n  target: flapping\n{}",
+            spec
+        ))
+        .unwrap();
+        super::Filter::new(
+            "flapping".to_string(),
+            scenario,
+            true,
+            crate::grpc::sender::actioncontroller::FilterGatewaySender::new(),
+            crate::policy::PolicyEngine::new(),
+        )
+    }
+
+    #[test]
+    fn test_settled_debounce_and_cooldown() {
+        use std::time::{Duration, Instant};
+        let start = Instant::now();
+
+        let mut debounced = flapping_filter("  debounceSeconds: 3\n");
+        assert!(!debounced.settled(start));
+        assert!(!debounced.settled(start + Duration::from_secs(2)));
+        assert!(debounced.settled(start + Duration::from_secs(3)));
+        // an interruption restarts the debounce
+        debounced.condition_met_since = None;
+        assert!(!debounced.settled(start + Duration::from_secs(4)));
+
+        let mut cooling = flapping_filter("  cooldownSeconds: 60\n");
+        assert!(cooling.settled(start));
+        cooling.last_triggered = Some(start);
+        assert!(!cooling.settled(start + Duration::from_secs(59)));
+        assert!(cooling.settled(start + Duration::from_secs(60)));
+    }
 }
@@ -6,6 +6,8 @@
 use std::io::Error;
 
 use crate::manager::ScenarioParameter;
+use crate::policy::PolicyDecision;
+use crate::vehicle::subscription::{self, Reliability, Subscription, TopicOrigin, TopicQos};
 // use crate::vehicle::dds::DdsData;
 
 use common::logd;
@@ -17,9 +19,39 @@ use tonic::{Request, Response, Status};
 // Import the generated protobuf code from filtergateway.proto
 use common::filtergateway::{
     filter_gateway_connection_server::{FilterGatewayConnection, FilterGatewayConnectionServer},
-    HandleScenarioRequest, HandleScenarioResponse,
+    CheckPolicyRequest, CheckPolicyResponse, HandleScenarioRequest, HandleScenarioResponse,
+    ListTopicsRequest, ListTopicsResponse, RegisterDdsTypeRequest, RegisterDdsTypeResponse,
+    SubscribeTopicRequest, TopicRequest, TopicResponse, TopicSubscription, VehicleMode,
+    VehicleModeRequest,
 };
 
+/// Message of a subscription as sent over gRPC
+fn topic_subscription(subscription: Subscription, origin: TopicOrigin) -> TopicSubscription {
+    let reliability = match subscription.qos.reliability {
+        Reliability::BestEffort => common::filtergateway::Reliability::BestEffort,
+        Reliability::Reliable => common::filtergateway::Reliability::Reliable,
+    };
+    TopicSubscription {
+        topic: subscription.topic,
+        type_name: subscription.type_name,
+        domain_id: subscription.domain_id,
+        reliability: reliability.into(),
+        history_depth: subscription.qos.history_depth,
+        paused: subscription.paused,
+        origin: origin.as_str().to_string(),
+    }
+}
+
+fn topic_response(subscription: Subscription) -> Response<TopicResponse> {
+    Response::new(TopicResponse {
+        topic: Some(topic_subscription(subscription, TopicOrigin::Api)),
+    })
+}
+
+fn not_started() -> Status {
+    Status::unavailable("FilterGateway manager is not started")
+}
+
 /// FilterGateway gRPC service handler
 #[allow(dead_code)]
 pub struct FilterGatewayReceiver {
@@ -69,7 +101,11 @@ impl FilterGatewayReceiver {
         // Parse the scenario YAML string into a Scenario struct
         let scenario = serde_yaml::from_str::<Scenario>(&scenario_yaml_str)?;
 
-        let param = ScenarioParameter { action, scenario };
+        let param = ScenarioParameter {
+            action,
+            scenario,
+            trace: common::trace::current(),
+        };
 
         self.tx.send(param).await.map_err(|e| {
             logd!(5, "Failed to send scenario: {}", e);
@@ -110,6 +146,183 @@ impl FilterGatewayConnection for FilterGatewayReceiver {
             desc: "Successfully handled scenario".to_string(),
         }))
     }
+
+    async fn register_dds_type(
+        &self,
+        request: Request<RegisterDdsTypeRequest>,
+    ) -> std::result::Result<Response<RegisterDdsTypeResponse>, Status> {
+        let req = request.into_inner();
+        match crate::vehicle::dds::dynamic::register_idl(&req.idl) {
+            Ok(type_name) => {
+                logd!(2, "Registered DDS type '{}' at runtime", type_name);
+                Ok(Response::new(RegisterDdsTypeResponse {
+                    status: true,
+                    desc: format!("Registered DDS type '{}'", type_name),
+                    type_name,
+                }))
+            }
+            Err(e) => {
+                logd!(4, "Failed to register DDS type: {}", e);
+                Err(Status::invalid_argument(format!(
+                    "Failed to register DDS type: {}",
+                    e
+                )))
+            }
+        }
+    }
+
+    async fn list_topics(
+        &self,
+        _request: Request<ListTopicsRequest>,
+    ) -> std::result::Result<Response<ListTopicsResponse>, Status> {
+        let vehicle_manager = subscription::vehicle_manager().ok_or_else(not_started)?;
+        let topics = vehicle_manager
+            .lock()
+            .await
+            .list_topics()
+            .into_iter()
+            .map(|(subscription, origin)| topic_subscription(subscription, origin))
+            .collect();
+        Ok(Response::new(ListTopicsResponse { topics }))
+    }
+
+    async fn subscribe_topic(
+        &self,
+        request: Request<SubscribeTopicRequest>,
+    ) -> std::result::Result<Response<TopicResponse>, Status> {
+        let req = request.into_inner();
+        let vehicle_manager = subscription::vehicle_manager().ok_or_else(not_started)?;
+        let mut vehicle_manager = vehicle_manager.lock().await;
+
+        let reliability = match req.reliability() {
+            common::filtergateway::Reliability::BestEffort => Reliability::BestEffort,
+            common::filtergateway::Reliability::Reliable => Reliability::Reliable,
+        };
+        let subscription = Subscription {
+            topic: req.topic,
+            type_name: req.type_name,
+            domain_id: req.domain_id.unwrap_or(vehicle_manager.domain_id()),
+            qos: TopicQos {
+                reliability,
+                history_depth: req.history_depth.max(1),
+            },
+            paused: false,
+        };
+        vehicle_manager
+            .add_subscription(subscription.clone())
+            .await?;
+
+        // A subscription that would be lost on restart is not kept
+        if let Err(e) = subscription::save(&subscription).await {
+            logd!(
+                5,
+                "Failed to store subscription of {}: {}",
+                subscription.topic,
+                e
+            );
+            let _ = vehicle_manager
+                .remove_subscription(&subscription.topic)
+                .await;
+            return Err(Status::unavailable(format!(
+                "Failed to store subscription: {}",
+                e
+            )));
+        }
+        logd!(
+            2,
+            "Subscribed to topic '{}' of type '{}'",
+            subscription.topic,
+            subscription.type_name
+        );
+        Ok(topic_response(subscription))
+    }
+
+    async fn pause_topic(
+        &self,
+        request: Request<TopicRequest>,
+    ) -> std::result::Result<Response<TopicResponse>, Status> {
+        let topic = request.into_inner().topic;
+        let vehicle_manager = subscription::vehicle_manager().ok_or_else(not_started)?;
+        let subscription = vehicle_manager.lock().await.pause_topic(&topic).await?;
+        if let Err(e) = subscription::save(&subscription).await {
+            logd!(4, "Failed to store paused subscription of {}: {}", topic, e);
+        }
+        logd!(2, "Paused topic '{}'", topic);
+        Ok(topic_response(subscription))
+    }
+
+    async fn resume_topic(
+        &self,
+        request: Request<TopicRequest>,
+    ) -> std::result::Result<Response<TopicResponse>, Status> {
+        let topic = request.into_inner().topic;
+        let vehicle_manager = subscription::vehicle_manager().ok_or_else(not_started)?;
+        let subscription = vehicle_manager.lock().await.resume_topic(&topic).await?;
+        if let Err(e) = subscription::save(&subscription).await {
+            logd!(
+                4,
+                "Failed to store resumed subscription of {}: {}",
+                topic,
+                e
+            );
+        }
+        logd!(2, "Resumed topic '{}'", topic);
+        Ok(topic_response(subscription))
+    }
+
+    async fn unsubscribe_topic(
+        &self,
+        request: Request<TopicRequest>,
+    ) -> std::result::Result<Response<TopicResponse>, Status> {
+        let topic = request.into_inner().topic;
+        let vehicle_manager = subscription::vehicle_manager().ok_or_else(not_started)?;
+        let subscription = vehicle_manager
+            .lock()
+            .await
+            .remove_subscription(&topic)
+            .await?;
+        if let Err(e) = subscription::delete(&topic).await {
+            logd!(
+                4,
+                "Failed to delete stored subscription of {}: {}",
+                topic,
+                e
+            );
+        }
+        logd!(2, "Unsubscribed from topic '{}'", topic);
+        Ok(topic_response(subscription))
+    }
+
+    async fn get_vehicle_mode(
+        &self,
+        _request: Request<VehicleModeRequest>,
+    ) -> std::result::Result<Response<VehicleMode>, Status> {
+        Ok(Response::new(crate::vehicle::mode::current()))
+    }
+
+    /// Check the allow/deny policies for a scenario triggered by hand
+    async fn check_policy(
+        &self,
+        request: Request<CheckPolicyRequest>,
+    ) -> std::result::Result<Response<CheckPolicyResponse>, Status> {
+        let request = request.into_inner();
+        let scenario = serde_yaml::from_str::<Scenario>(&request.scenario)
+            .map_err(|e| Status::invalid_argument(format!("Invalid scenario: {}", e)))?;
+        let engine = crate::policy::engine().ok_or_else(not_started)?;
+
+        let response = match engine.check(&scenario).await {
+            PolicyDecision::Allow => CheckPolicyResponse {
+                allowed: true,
+                ..Default::default()
+            },
+            PolicyDecision::Deny { policy, rule } => CheckPolicyResponse {
+                allowed: false,
+                policy,
+                rule,
+            },
+        };
+        Ok(Response::new(response))
+    }
 }
 //Unit Test Cases
 #[cfg(test)]
@@ -255,4 +468,47 @@ mod tests {
             .await;
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_register_dds_type() {
+        use common::filtergateway::filter_gateway_connection_server::FilterGatewayConnection;
+        use common::filtergateway::RegisterDdsTypeRequest;
+
+        let (tx, _rx) = mpsc::channel(1);
+        let receiver = FilterGatewayReceiver::new(tx);
+
+        let response = receiver
+            .register_dds_type(tonic::Request::new(RegisterDdsTypeRequest {
+                idl: "struct CabinTemperature { float value; };".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(response.status);
+        assert_eq!(response.type_name, "CabinTemperature");
+
+        let status = receiver
+            .register_dds_type(tonic::Request::new(RegisterDdsTypeRequest {
+                idl: "enum Gear { P, R };".to_string(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    // Test case for a policy check of a document that is not a scenario
+    #[tokio::test]
+    async fn test_check_policy_with_invalid_yaml() {
+        use common::filtergateway::filter_gateway_connection_server::FilterGatewayConnection;
+        use common::filtergateway::CheckPolicyRequest;
+
+        let (tx, _rx) = mpsc::channel(1);
+        let receiver = FilterGatewayReceiver::new(tx);
+
+        let request = tonic::Request::new(CheckPolicyRequest {
+            scenario: "kind: [".to_string(),
+        });
+        let status = receiver.check_policy(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
 }
@@ -10,6 +10,7 @@ use common::actioncontroller::connect_server;
 
 // Import the generated protobuf code from actioncontroller.proto
 use common::actioncontroller::action_controller_connection_client::ActionControllerConnectionClient;
+use common::rpc::RpcClient;
 
 /// Sender for making gRPC requests to ActionController
 #[derive(Clone)]
@@ -45,16 +46,25 @@ impl FilterGatewaySender {
             return Err("Invalid scenario name: cannot be empty".into());
         }
         use common::actioncontroller::TriggerActionRequest;
-        let mut client = ActionControllerConnectionClient::connect(connect_server())
-            .await
-            .unwrap();
-
-        let request = TriggerActionRequest { scenario_name };
+        let request = &TriggerActionRequest {
+            scenario_name,
+            dry_run: false,
+            action: String::new(),
+        };
 
-        client.trigger_action(request).await.map_err(|e| {
-            common::logd!(5, "Failed to trigger action: {:?}", e);
-            anyhow::anyhow!("Failed to trigger action: {:?}", e)
-        })?;
+        // Starting the workloads takes as long as the slowest model
+        RpcClient::new("ActionController", connect_server())
+            .with_call_timeout(None)
+            .call(|channel| async move {
+                ActionControllerConnectionClient::new(channel)
+                    .trigger_action(common::trace::request(request.clone()))
+                    .await
+            })
+            .await
+            .map_err(|e| {
+                common::logd!(5, "Failed to trigger action: {:?}", e);
+                anyhow::anyhow!("Failed to trigger action: {:?}", e)
+            })?;
 
         Ok(())
     }
@@ -65,6 +75,10 @@ mod tests {
     use super::*;
     use common::actioncontroller::CompleteNetworkSettingRequest;
     use common::actioncontroller::CompleteNetworkSettingResponse;
+    use common::actioncontroller::DrainNodeRequest;
+    use common::actioncontroller::DrainNodeResponse;
+    use common::actioncontroller::MigrateNodeRequest;
+    use common::actioncontroller::MigrateNodeResponse;
     use common::actioncontroller::OffloadModelRequest;
     use common::actioncontroller::OffloadModelResponse;
     use common::actioncontroller::{
@@ -99,6 +113,7 @@ mod tests {
             Ok(Response::new(TriggerActionResponse {
                 desc: "OK".to_string(),
                 status: 0,
+                plan: None,
             }))
         }
 
@@ -133,6 +148,20 @@ mod tests {
                 transition_id: "mock-transition-id".to_string(),
             }))
         }
+
+        async fn drain_node(
+            &self,
+            _request: Request<DrainNodeRequest>,
+        ) -> std::result::Result<Response<DrainNodeResponse>, Status> {
+            Ok(Response::new(DrainNodeResponse::default()))
+        }
+
+        async fn migrate_node(
+            &self,
+            _request: Request<MigrateNodeRequest>,
+        ) -> std::result::Result<Response<MigrateNodeResponse>, Status> {
+            Ok(Response::new(MigrateNodeResponse::default()))
+        }
     }
 
     async fn spawn_mock_server(
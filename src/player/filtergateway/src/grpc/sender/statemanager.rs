@@ -13,11 +13,12 @@
 //! filtering decisions, access control results, and security policy enforcement
 //! outcomes to the StateManager for proper resource state tracking.
 
+use common::rpc::RpcClient;
 use common::statemanager::{
     connect_server, state_manager_connection_client::StateManagerConnectionClient, ResourceType,
     StateChange, StateChangeResponse,
 };
-use tonic::{Request, Status};
+use tonic::Status;
 
 /// StateManager gRPC client for FilterGateway component.
 ///
@@ -47,12 +48,11 @@ use tonic::{Request, Status};
 /// - Enforces security and access control policies
 #[derive(Clone)]
 pub struct StateManagerSender {
-    /// Cached gRPC client connection to the StateManager service.
+    /// Client of the StateManager service.
     ///
-    /// This connection is established lazily on the first request and reused
-    /// for subsequent requests to optimize performance. Set to None initially
-    /// and populated when ensure_connected() is called.
-    client: Option<StateManagerConnectionClient<tonic::transport::Channel>>,
+    /// The channel is established lazily on the first request and reused
+    /// for subsequent requests to optimize performance.
+    rpc: RpcClient,
 }
 
 impl Default for StateManagerSender {
@@ -76,41 +76,8 @@ impl StateManagerSender {
     /// # Returns
     /// * `Self` - New StateManagerSender instance ready for use
     pub fn new() -> Self {
-        Self { client: None }
-    }
-
-    /// Ensures a gRPC connection to the StateManager exists and is ready for use.
-    ///
-    /// This method implements lazy connection establishment by checking if a connection
-    /// already exists and creating one if necessary. It uses the common::statemanager
-    /// configuration to determine the StateManager's network location.
-    ///
-    /// # Connection Process
-    /// 1. Check if a connection already exists
-    /// 2. If not, attempt to establish a new gRPC connection
-    /// 3. Store the connection for reuse in subsequent requests
-    /// 4. Return success or detailed error information
-    ///
-    /// # Returns
-    /// * `Result<(), Status>` - Success if connection is available, error otherwise
-    ///
-    /// # Errors
-    /// * `Status::unknown` - Connection establishment failed (network, service unavailable, etc.)
-    async fn ensure_connected(&mut self) -> Result<(), Status> {
-        if self.client.is_none() {
-            match StateManagerConnectionClient::connect(connect_server()).await {
-                Ok(client) => {
-                    self.client = Some(client);
-                    Ok(())
-                }
-                Err(e) => Err(Status::unknown(format!(
-                    "Failed to connect to StateManager: {}",
-                    e
-                ))),
-            }
-        } else {
-            // Connection already exists and ready for use
-            Ok(())
+        Self {
+            rpc: RpcClient::new("StateManager", connect_server()),
         }
     }
 
@@ -140,7 +107,6 @@ impl StateManagerSender {
     ///   - Error codes and details if applicable
     ///
     /// # Errors
-    /// * `Status::unknown` - Connection failure or client not connected
     /// * `Status::unavailable` - StateManager service unavailable
     /// * `Status::invalid_argument` - Malformed StateChange message
     /// * `Status::deadline_exceeded` - Request timeout (ASIL timing violation)
@@ -155,16 +121,14 @@ impl StateManagerSender {
         &mut self,
         state_change: StateChange,
     ) -> Result<tonic::Response<StateChangeResponse>, Status> {
-        // Ensure we have an active gRPC connection before sending
-        self.ensure_connected().await?;
-
-        if let Some(client) = &mut self.client {
-            // Send the state change message via gRPC
-            client.send_state_change(Request::new(state_change)).await
-        } else {
-            // This should never happen due to ensure_connected, but provide safety fallback
-            Err(Status::unknown("Client not connected"))
-        }
+        let state_change = &state_change;
+        self.rpc
+            .call(|channel| async move {
+                StateManagerConnectionClient::new(channel)
+                    .send_state_change(common::trace::request(state_change.clone()))
+                    .await
+            })
+            .await
     }
 
     /// Reports policy enforcement decision to StateManager.
@@ -177,7 +141,8 @@ impl StateManagerSender {
     /// * `resource_name` - Name/identifier of the resource
     /// * `current_state` - Current state before policy decision
     /// * `policy_decision` - Result of policy decision ("allowed", "denied", "blocked")
-    /// * `policy_id` - Policy decision identifier for audit trails
+    /// * `policy_id` - Policy decision identifier for audit trails, made unique
+    ///   by the timestamp of the report
     ///
     /// # Returns
     /// * `Result<tonic::Response<StateChangeResponse>, Status>` - StateManager response
@@ -199,9 +164,11 @@ impl StateManagerSender {
             resource_name: resource_name.to_string(),
             current_state: current_state.to_string(),
             target_state: policy_decision.to_string(),
-            transition_id: format!("policy-{}", policy_id),
+            transition_id: format!("policy-{}-{}", policy_id, timestamp),
             timestamp_ns: timestamp,
             source: "filtergateway".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: common::trace::current_trace_id().unwrap_or_default(),
         };
 
         self.send_state_change(state_change).await
@@ -217,7 +184,8 @@ impl StateManagerSender {
     /// * `resource_name` - Name/identifier of the resource
     /// * `current_state` - Current state before access control
     /// * `access_decision` - Result of access control ("granted", "denied", "revoked")
-    /// * `access_control_id` - Access control decision identifier
+    /// * `access_control_id` - Access control decision identifier, made unique
+    ///   by the timestamp of the report
     ///
     /// # Returns
     /// * `Result<tonic::Response<StateChangeResponse>, Status>` - StateManager response
@@ -239,9 +207,11 @@ impl StateManagerSender {
             resource_name: resource_name.to_string(),
             current_state: current_state.to_string(),
             target_state: access_decision.to_string(),
-            transition_id: format!("access-{}", access_control_id),
+            transition_id: format!("access-{}-{}", access_control_id, timestamp),
             timestamp_ns: timestamp,
             source: "filtergateway".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: common::trace::current_trace_id().unwrap_or_default(),
         };
 
         self.send_state_change(state_change).await
@@ -257,7 +227,8 @@ impl StateManagerSender {
     /// * `resource_name` - Name/identifier of the resource
     /// * `current_state` - Current state when violation occurred
     /// * `violation_action` - Action taken ("blocked", "quarantined", "terminated")
-    /// * `violation_id` - Security violation identifier
+    /// * `violation_id` - Security violation identifier, made unique
+    ///   by the timestamp of the report
     ///
     /// # Returns
     /// * `Result<tonic::Response<StateChangeResponse>, Status>` - StateManager response
@@ -279,9 +250,11 @@ impl StateManagerSender {
             resource_name: resource_name.to_string(),
             current_state: current_state.to_string(),
             target_state: violation_action.to_string(),
-            transition_id: format!("violation-{}", violation_id),
+            transition_id: format!("violation-{}-{}", violation_id, timestamp),
             timestamp_ns: timestamp,
             source: "filtergateway".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: common::trace::current_trace_id().unwrap_or_default(),
         };
 
         self.send_state_change(state_change).await
@@ -297,7 +270,8 @@ impl StateManagerSender {
     /// * `resource_name` - Name/identifier of the resource
     /// * `current_state` - Current state before filtering
     /// * `filter_result` - Result of filtering ("passed", "filtered", "rejected")
-    /// * `filter_id` - Filter operation identifier
+    /// * `filter_id` - Filter operation identifier, made unique
+    ///   by the timestamp of the report
     ///
     /// # Returns
     /// * `Result<tonic::Response<StateChangeResponse>, Status>` - StateManager response
@@ -319,9 +293,11 @@ impl StateManagerSender {
             resource_name: resource_name.to_string(),
             current_state: current_state.to_string(),
             target_state: filter_result.to_string(),
-            transition_id: format!("filter-{}", filter_id),
+            transition_id: format!("filter-{}-{}", filter_id, timestamp),
             timestamp_ns: timestamp,
             source: "filtergateway".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: common::trace::current_trace_id().unwrap_or_default(),
         };
 
         self.send_state_change(state_change).await
@@ -373,6 +349,8 @@ mod tests {
             transition_id: format!("policy-decision-{}", timestamp),
             timestamp_ns: timestamp,
             source: "filtergateway".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: String::new(),
         };
 
         // Send the message and verify successful response
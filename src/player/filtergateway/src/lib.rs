@@ -5,9 +5,12 @@
 pub mod filter;
 pub mod grpc;
 pub mod manager;
+pub mod policy;
+pub mod scheduler;
 pub mod vehicle;
 
 // Re-export what you need in tests:
+use common::health;
 pub use common::spec::artifact::Scenario;
 pub use common::Result;
 pub use filter::Filter;
@@ -18,19 +21,25 @@ use tokio::sync::mpsc::{Receiver, Sender};
 pub use vehicle::dds::listener;
 pub use vehicle::dds::DdsData;
 pub use vehicle::dds::DdsTopicListener;
+
+/// Readiness check of the manager processing scenarios from the gRPC channel
+pub const CHECK_MANAGER: &str = "manager";
 pub async fn launch_manager(rx_grpc: Receiver<ScenarioParameter>) {
     let manager = manager::FilterGatewayManager::new(rx_grpc).await;
 
     match manager.initialize().await {
         Ok(_) => {
             println!("FilterGatewayManager successfully initialized");
+            health::set_ready(CHECK_MANAGER);
             // Only proceed to run if initialization was successful
             if let Err(e) = manager.run().await {
                 eprintln!("Error running FilterGatewayManager: {:?}", e);
             }
+            health::set_not_ready(CHECK_MANAGER, "stopped");
         }
         Err(e) => {
             eprintln!("Failed to initialize FilterGatewayManager: {:?}", e);
+            health::set_not_ready(CHECK_MANAGER, format!("initialization failed: {:?}", e));
         }
     }
 }
@@ -58,8 +67,16 @@ pub async fn initialize(tx_grpc: Sender<manager::ScenarioParameter>) {
 
     println!("Pullpirid gateway listening on {}", addr);
 
-    let _ = Server::builder()
+    health::set_ready(health::CHECK_GRPC_SERVER);
+    let result = Server::builder()
+        .layer(common::trace::GrpcTraceLayer)
         .add_service(FilterGatewayConnectionServer::new(server))
+        .add_service(common::reflection::service())
         .serve(addr)
         .await;
+    let reason = match result {
+        Ok(()) => "stopped".to_string(),
+        Err(e) => e.to_string(),
+    };
+    health::set_not_ready(health::CHECK_GRPC_SERVER, reason);
 }
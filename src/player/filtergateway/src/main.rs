@@ -5,6 +5,8 @@
 mod filter;
 mod grpc;
 mod manager;
+mod policy;
+mod scheduler;
 mod vehicle;
 
 // Moved `launch_manager` and `initialize` function from `main.rs` to `lib.rs` to:
@@ -19,17 +21,32 @@ mod vehicle;
 // Note: The `ScenarioParameter` type is re-exported from the manager module
 // via `lib.rs` to ensure a single source of truth and prevent type mismatches.
 use filtergateway::ScenarioParameter;
-use filtergateway::{initialize, launch_manager};
+use filtergateway::{initialize, launch_manager, CHECK_MANAGER};
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 
+use common::health;
 use common::logd;
 use common::logd::logger;
 
+/// Interval of the etcd readiness probe
+#[cfg(not(feature = "tarpaulin_include"))]
+const ETCD_PROBE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
 #[cfg(not(feature = "tarpaulin_include"))]
 #[tokio::main]
 async fn main() {
     let _ = logger::init_async_logger("filtergateway").await;
+    common::trace::init_exporter("filtergateway");
     logd!(1, "Initializing FilterGateway");
+    if let Err(e) = common::startup::wait_for_dependencies("filtergateway").await {
+        logd!(4, "filtergateway starts without its dependencies, {}", e);
+    }
+    health::init(
+        "filtergateway",
+        &[health::CHECK_ETCD, health::CHECK_GRPC_SERVER, CHECK_MANAGER],
+    );
+    tokio::spawn(health::watch_etcd(ETCD_PROBE_INTERVAL));
+    tokio::spawn(health::serve(common::filtergateway::open_health_server()));
 
     // Initialize tracing subscriber for logging
     let (tx_grpc, rx_grpc): (Sender<ScenarioParameter>, Receiver<ScenarioParameter>) = channel(100);
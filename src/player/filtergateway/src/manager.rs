@@ -5,7 +5,11 @@
 use crate::filter::Filter;
 use crate::grpc::sender::actioncontroller::FilterGatewaySender;
 use crate::grpc::sender::statemanager::StateManagerSender;
+use crate::policy::PolicyEngine;
+use crate::scheduler::Scheduler;
 use crate::vehicle::dds::DdsData;
+use crate::vehicle::mode::{ModeSettings, VehicleModeTracker};
+use crate::vehicle::record::Recorder;
 use crate::vehicle::VehicleManager;
 use common::logd;
 use common::spec::artifact::Scenario;
@@ -29,6 +33,8 @@ pub struct ScenarioParameter {
     pub action: i32,
     /// Vehicle message information
     pub scenario: Scenario,
+    /// Trace of the request, continued while handling the scenario
+    pub trace: Option<common::trace::TraceContext>,
 }
 #[allow(dead_code)]
 pub struct FilterGatewayManager {
@@ -42,6 +48,14 @@ pub struct FilterGatewayManager {
     pub sender: Arc<Mutex<FilterGatewaySender>>,
     /// Vehicle manager for handling vehicle data
     pub vehicle_manager: Arc<Mutex<VehicleManager>>,
+    /// Policy engine shared with all filters
+    pub policy_engine: PolicyEngine,
+    /// Scenarios with schedule conditions
+    pub scheduler: Scheduler,
+    /// Recorder of received vehicle data, if `signals.record` is set
+    pub recorder: Option<Arc<Mutex<Recorder>>>,
+    /// Vehicle mode derived from the `vehicle_mode` signals
+    pub mode_tracker: VehicleModeTracker,
 }
 #[allow(dead_code)]
 impl FilterGatewayManager {
@@ -63,13 +77,51 @@ impl FilterGatewayManager {
             logd!(4, "Warning: Failed to initialize vehicle manager: {:?}. Continuing with default settings.", e);
             // Continue (already using default values in VehicleManager::init())
         }
+        let recorder = vehicle_manager
+            .record_path()
+            .and_then(|path| match Recorder::open(&path) {
+                Ok(recorder) => {
+                    logd!(3, "Recording vehicle data to {}", path.display());
+                    Some(Arc::new(Mutex::new(recorder)))
+                }
+                Err(e) => {
+                    logd!(
+                        5,
+                        "Failed to open vehicle data trace {}: {:?}",
+                        path.display(),
+                        e
+                    );
+                    None
+                }
+            });
+
+        let vehicle_manager = Arc::new(Mutex::new(vehicle_manager));
+        crate::vehicle::subscription::register_vehicle_manager(vehicle_manager.clone());
+
+        let mode_settings = ModeSettings::load(None).unwrap_or_else(|e| {
+            logd!(
+                4,
+                "Invalid vehicle_mode settings: {:?}. Vehicle mode stays unknown.",
+                e
+            );
+            ModeSettings::default()
+        });
+        let mode_tracker = VehicleModeTracker::new(mode_settings);
+        crate::vehicle::mode::register_tracker(mode_tracker.clone());
+
+        let policy_engine = PolicyEngine::new();
+        crate::policy::register_engine(policy_engine.clone());
 
         Self {
             rx_grpc: Arc::new(Mutex::new(rx_grpc)),
             rx_dds: Arc::new(Mutex::new(rx_dds)),
             filters: Arc::new(Mutex::new(Vec::new())),
             sender: Arc::new(Mutex::new(FilterGatewaySender::new())),
-            vehicle_manager: Arc::new(Mutex::new(vehicle_manager)),
+            vehicle_manager,
+            policy_engine,
+            scheduler: Scheduler::new(),
+            recorder,
+            mode_tracker,
         }
     }
     /// Function to initialize the FilterGatewayManager
@@ -83,6 +135,8 @@ impl FilterGatewayManager {
     /// * `Result<()>` - Success or error result
     pub async fn initialize(&self) -> Result<()> {
         logd!(3, "FilterGatewayManager init");
+        // Topics subscribed over gRPC first, so conditions share their listeners
+        self.restore_subscriptions().await;
         // Initialize vehicle manager
         let etcd_scenario = Self::read_all_scenario_from_etcd()
             .await
@@ -91,29 +145,73 @@ impl FilterGatewayManager {
         for scenario in etcd_scenario {
             let scenario: Scenario = serde_yaml::from_str(&scenario)?;
             logd!(3, "Scenario: {:?}", scenario);
-            let topic_name = scenario
-                .get_conditions()
-                .as_ref()
-                .map(|cond| cond.get_operand_value())
-                .unwrap_or_default();
-            let data_type_name = scenario
-                .get_conditions()
-                .as_ref()
-                .map(|cond| cond.get_operand_value())
-                .unwrap_or_default();
-            let mut vehicle_manager = self.vehicle_manager.lock().await;
-            if let Err(e) = vehicle_manager
-                .subscribe_topic(topic_name, data_type_name)
-                .await
-            {
-                logd!(5, "Error subscribing to vehicle data: {:?}", e);
+            for topic_name in condition_topics(&scenario) {
+                let mut vehicle_manager = self.vehicle_manager.lock().await;
+                if let Err(e) = vehicle_manager
+                    .subscribe_topic(topic_name.clone(), topic_name)
+                    .await
+                {
+                    logd!(5, "Error subscribing to vehicle data: {:?}", e);
+                }
             }
             self.launch_scenario_filter(scenario).await?;
         }
+        self.subscribe_policy_topics().await;
+        self.subscribe_mode_topics().await;
 
         Ok(())
     }
 
+    /// Subscribe again to the topics subscribed over gRPC before a restart
+    async fn restore_subscriptions(&self) {
+        let subscriptions = match crate::vehicle::subscription::load_all().await {
+            Ok(subscriptions) => subscriptions,
+            Err(e) => {
+                logd!(4, "Failed to load topic subscriptions: {}", e);
+                return;
+            }
+        };
+
+        let mut vehicle_manager = self.vehicle_manager.lock().await;
+        for subscription in subscriptions {
+            let topic = subscription.topic.clone();
+            if let Err(e) = vehicle_manager.add_subscription(subscription).await {
+                logd!(5, "Failed to restore subscription of {}: {}", topic, e);
+            }
+        }
+    }
+
+    /// Subscribe to the vehicle data topics used by policy rules
+    ///
+    /// Called on startup and whenever a scenario is added, so rules of
+    /// policies applied later get their data before they are evaluated.
+    async fn subscribe_policy_topics(&self) {
+        let policies = match crate::policy::load_policies().await {
+            Ok(policies) => policies,
+            Err(e) => {
+                logd!(4, "Failed to load policies: {:?}", e);
+                return;
+            }
+        };
+
+        let mut vehicle_manager = self.vehicle_manager.lock().await;
+        for topic in crate::policy::condition_topics(&policies) {
+            if let Err(e) = vehicle_manager.subscribe_topic(topic.clone(), topic).await {
+                logd!(5, "Error subscribing to policy vehicle data: {:?}", e);
+            }
+        }
+    }
+
+    /// Subscribe to the vehicle data topics the vehicle mode is derived from
+    async fn subscribe_mode_topics(&self) {
+        let mut vehicle_manager = self.vehicle_manager.lock().await;
+        for topic in self.mode_tracker.topics() {
+            if let Err(e) = vehicle_manager.subscribe_topic(topic.clone(), topic).await {
+                logd!(5, "Error subscribing to vehicle mode data: {:?}", e);
+            }
+        }
+    }
+
     /// Function to receive subscribed DDS data and pass it to filters
     ///
     /// This function runs as a separate task to continuously receive and process DDS data.
@@ -142,6 +240,28 @@ impl FilterGatewayManager {
                         );
                     }
 
+                    if let Some(recorder) = &self.recorder {
+                        let data_type = self
+                            .vehicle_manager
+                            .lock()
+                            .await
+                            .data_type_of(&dds_data.name);
+                        if let Err(e) = recorder.lock().await.record(&dds_data, &data_type) {
+                            logd!(4, "Failed to record DDS data: {:?}", e);
+                        }
+                    }
+
+                    // Keep the latest value for policy evaluation
+                    self.policy_engine.update_vehicle_data(&dds_data).await;
+
+                    if let Some(mode) = self.mode_tracker.update(&dds_data) {
+                        logd!(
+                            3,
+                            "Vehicle mode changed: {}",
+                            common::vehicle_mode::describe(&mode)
+                        );
+                    }
+
                     // Forward data to all active filters
                     let mut filters = self.filters.lock().await;
                     for filter in filters.iter_mut() {
@@ -187,46 +307,13 @@ impl FilterGatewayManager {
             match scenario_parameter {
                 Some(param) => {
                     logd!(2, "Received scenario parameter: {:?}", param);
-                    match param.action {
-                        0 => {
-                            // Allow
-                            // Subscribe to vehicle data
-                            let topic_name = param
-                                .scenario
-                                .get_conditions()
-                                .as_ref()
-                                .map(|cond| cond.get_operand_value())
-                                .unwrap_or_default();
-                            let data_type_name = param
-                                .scenario
-                                .get_conditions()
-                                .as_ref()
-                                .map(|cond| cond.get_operand_value())
-                                .unwrap_or_default();
-                            let mut vehicle_manager = self.vehicle_manager.lock().await;
-                            if let Err(e) = vehicle_manager
-                                .subscribe_topic(topic_name, data_type_name)
-                                .await
-                            {
-                                logd!(5, "Error subscribing to vehicle data: {:?}", e);
-                            }
-                            self.launch_scenario_filter(param.scenario).await?;
-                        }
-                        1 => {
-                            // Withdraw
-                            // Unsubscribe from vehicle data
-                            let mut vehicle_manager = self.vehicle_manager.lock().await;
-                            if let Err(e) = vehicle_manager
-                                .unsubscribe_topic(param.scenario.get_name().clone())
-                                .await
-                            {
-                                logd!(5, "Error unsubscribing from vehicle data: {:?}", e);
-                            }
-                            self.remove_scenario_filter(param.scenario.get_name().clone())
-                                .await?;
-                        }
-                        _ => {}
-                    }
+                    let parent = param.trace.clone();
+                    common::trace::in_span(
+                        "filtergateway.handle_scenario",
+                        parent,
+                        self.handle_scenario_parameter(param),
+                    )
+                    .await?;
                 }
                 None => {
                     // Channel closed
@@ -239,6 +326,42 @@ impl FilterGatewayManager {
         Ok(())
     }
 
+    /// Apply or withdraw the scenario of a gRPC request
+    async fn handle_scenario_parameter(&self, param: ScenarioParameter) -> Result<()> {
+        match param.action {
+            0 => {
+                // Allow
+                // Subscribe to vehicle data
+                for topic_name in condition_topics(&param.scenario) {
+                    let mut vehicle_manager = self.vehicle_manager.lock().await;
+                    if let Err(e) = vehicle_manager
+                        .subscribe_topic(topic_name.clone(), topic_name)
+                        .await
+                    {
+                        logd!(5, "Error subscribing to vehicle data: {:?}", e);
+                    }
+                }
+                self.subscribe_policy_topics().await;
+                self.launch_scenario_filter(param.scenario).await?;
+            }
+            1 => {
+                // Withdraw
+                // Unsubscribe from vehicle data
+                let mut vehicle_manager = self.vehicle_manager.lock().await;
+                if let Err(e) = vehicle_manager
+                    .unsubscribe_topic(param.scenario.get_name().clone())
+                    .await
+                {
+                    logd!(5, "Error unsubscribing from vehicle data: {:?}", e);
+                }
+                self.remove_scenario_filter(param.scenario.get_qualified_name())
+                    .await?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
     /// Start the manager processing
     ///
     /// This function processes incoming scenario requests and
@@ -265,8 +388,14 @@ impl FilterGatewayManager {
             }
         });
 
+        // Fire scenarios with schedule conditions
+        let scheduler = arc_self.scheduler.clone();
+        let schedule_processor = tokio::spawn(async move {
+            scheduler.run().await;
+        });
+
         // 태스크 완료 대기
-        let _ = tokio::try_join!(dds_processor, grpc_processor);
+        let _ = tokio::try_join!(dds_processor, grpc_processor, schedule_processor);
 
         logd!(5, "FilterGatewayManager stopped");
 
@@ -344,13 +473,17 @@ impl FilterGatewayManager {
 
         // Check if the scenario has conditions
         if scenario.get_conditions().is_none() {
-            logd!(3, "No conditions for scenario: {}", scenario.get_name());
+            logd!(
+                3,
+                "No conditions for scenario: {}",
+                scenario.get_qualified_name()
+            );
             let mut sender = self.sender.lock().await;
-            if let Err(e) = sender.trigger_action(scenario.get_name().clone()).await {
+            if let Err(e) = sender.trigger_action(scenario.get_qualified_name()).await {
                 logd!(
                     5,
                     "Failed to trigger action for scenario {}: {:?}. Continuing with other scenarios.",
-                    scenario.get_name(),
+                    scenario.get_qualified_name(),
                     e
                 );
             }
@@ -364,7 +497,7 @@ impl FilterGatewayManager {
             1,
             "🔄 SCENARIO STATE TRANSITION: FilterGateway Condition Registration"
         );
-        logd!(1, "   📋 Scenario: {}", scenario.get_name());
+        logd!(1, "   📋 Scenario: {}", scenario.get_qualified_name());
         logd!(1, "   🔄 State Change: idle → waiting");
         logd!(
             1,
@@ -378,12 +511,14 @@ impl FilterGatewayManager {
 
         let state_change = StateChange {
             resource_type: ResourceType::Scenario as i32,
-            resource_name: scenario.get_name().clone(),
+            resource_name: scenario.get_qualified_name(),
             current_state: "idle".to_string(),
             target_state: "waiting".to_string(),
             transition_id: format!("filtergateway-condition-registered-{}", timestamp),
             timestamp_ns: timestamp,
             source: "filtergateway".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: common::trace::current_trace_id().unwrap_or_default(),
         };
 
         logd!(1, "   📤 Sending StateChange to StateManager:");
@@ -405,7 +540,7 @@ impl FilterGatewayManager {
             logd!(
                 2,
                 "   ✅ Successfully notified StateManager: scenario {} idle → waiting",
-                scenario.get_name()
+                scenario.get_qualified_name()
             );
         }
 
@@ -413,7 +548,27 @@ impl FilterGatewayManager {
             let sender_guard = self.sender.lock().await;
             sender_guard.clone()
         };
-        let filter = Filter::new(scenario.get_name().to_string(), scenario, true, sender);
+        let filter = Filter::new(
+            scenario.get_qualified_name(),
+            scenario,
+            true,
+            sender,
+            self.policy_engine.clone(),
+        );
+
+        // Schedule conditions are fired by the scheduler instead of vehicle data
+        if filter
+            .scenario
+            .get_conditions()
+            .is_some_and(|c| c.is_schedule())
+        {
+            if let Err(e) = self.scheduler.add(filter).await {
+                logd!(5, "Failed to schedule scenario: {:?}", e);
+            }
+            let elapsed = start.elapsed();
+            logd!(1, "launch_scenario_filter: elapsed = {:?}", elapsed);
+            return Ok(());
+        }
 
         // Add the filter to our managed collection
         {
@@ -461,22 +616,35 @@ impl FilterGatewayManager {
         if let Some(i) = index {
             filters.remove(i);
         }
+        drop(filters);
+        self.scheduler.remove(&scenario_name).await;
         Ok(())
     }
 
-    /// Read all scenario yaml string in etcd
+    /// Read the scenario yaml strings of every namespace in etcd
     ///
     /// ### Parameters
     /// * None
     /// ### Return
     /// * `Result<Vec<String>>` - `Ok(_)` contains scenario yaml string vector
     async fn read_all_scenario_from_etcd() -> common::Result<Vec<String>> {
-        let kv_scenario = common::etcd::get_all_with_prefix("Scenario").await?;
+        let kv_scenario = common::etcd::get_all_of_kind("Scenario").await?;
         let values = kv_scenario.into_iter().map(|kv| kv.1).collect();
 
         Ok(values)
     }
 }
+
+/// Vehicle data topics of a scenario's data condition
+///
+/// Scenarios without a condition or with a schedule condition do not
+/// subscribe to vehicle data.
+fn condition_topics(scenario: &Scenario) -> Vec<String> {
+    scenario
+        .get_conditions()
+        .map(|cond| cond.get_topics())
+        .unwrap_or_default()
+}
 //Unit Tets Cases
 #[cfg(test)]
 mod tests {
@@ -1506,6 +1674,8 @@ mod tests {
                 transition_id: format!("filtergateway-condition-registered-{}", timestamp),
                 timestamp_ns: timestamp,
                 source: "filtergateway".to_string(),
+                asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+                trace_id: String::new(),
             };
 
             if let Err(e) = state_sender.send_state_change(state_change).await {
@@ -1554,6 +1724,8 @@ mod tests {
             transition_id: "test-transition".to_string(),
             timestamp_ns: 123456789,
             source: "filtergateway".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: String::new(),
         };
 
         // Test error handling path (line 264)
@@ -0,0 +1,352 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Scenario admission policies
+//!
+//! Policies are read from etcd (`Policy/<name>`) when a scenario condition
+//! fires. Their `spec.rules` are checked against the latest vehicle data
+//! received over DDS before the scenario action is triggered.
+use crate::filter::evaluate_condition;
+use crate::vehicle::dds::DdsData;
+use common::logd;
+use common::spec::artifact::policy::{Rule, RuleEffect};
+use common::spec::artifact::scenario::Condition;
+use common::spec::artifact::{Artifact, Policy, Scenario};
+use common::Result;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::Mutex;
+
+/// etcd key prefix of Policy artifacts
+pub const POLICY_PREFIX: &str = "Policy";
+
+/// Rule of the decision denying a scenario whose policies cannot be read
+pub const UNAVAILABLE_RULE: &str = "unavailable";
+
+static ENGINE: OnceLock<PolicyEngine> = OnceLock::new();
+
+/// Outcome of checking the policies for a scenario
+#[derive(Debug, Clone, PartialEq)]
+pub enum PolicyDecision {
+    Allow,
+    Deny { policy: String, rule: String },
+}
+
+/// Evaluates policy rules against the latest vehicle data
+///
+/// Clones share the same vehicle data, so the manager updates it once
+/// and every filter sees the same values.
+#[derive(Clone, Default)]
+pub struct PolicyEngine {
+    /// Latest DDS data per topic
+    vehicle_data: Arc<Mutex<HashMap<String, DdsData>>>,
+}
+
+impl PolicyEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remember the latest value of a DDS topic
+    pub async fn update_vehicle_data(&self, data: &DdsData) {
+        let mut vehicle_data = self.vehicle_data.lock().await;
+        vehicle_data.insert(data.name.clone(), data.clone());
+    }
+
+    /// Evaluate a data condition against the latest vehicle data
+    ///
+    /// See [`evaluate_condition`] for the meaning of the result.
+    pub async fn evaluate_condition(&self, condition: &Condition) -> Result<Option<bool>> {
+        let vehicle_data = self.vehicle_data.lock().await;
+        evaluate_condition(condition, &vehicle_data)
+    }
+
+    /// Check every policy in etcd for the given scenario
+    ///
+    /// See [`decide`] for a scenario whose policies cannot be read.
+    pub async fn check(&self, scenario: &Scenario) -> PolicyDecision {
+        let policies = load_policies().await.map_err(|e| e.to_string());
+        let vehicle_data = self.vehicle_data.lock().await;
+        decide(
+            policies,
+            &scenario.get_qualified_name(),
+            &scenario.get_actions(),
+            &vehicle_data,
+        )
+    }
+}
+
+/// Make the engine of the manager available to the gRPC receiver
+pub fn register_engine(engine: PolicyEngine) {
+    if ENGINE.set(engine).is_err() {
+        logd!(4, "Policy engine is already registered");
+    }
+}
+
+/// Engine registered by the manager, if FilterGateway is started
+pub fn engine() -> Option<PolicyEngine> {
+    ENGINE.get().cloned()
+}
+
+/// Read the Policy artifacts of every namespace from etcd, skipping
+/// documents that do not parse
+pub async fn load_policies() -> Result<Vec<Policy>> {
+    let kvs = common::etcd::get_all_of_kind(POLICY_PREFIX).await?;
+    let policies = kvs
+        .into_iter()
+        .filter_map(
+            |(key, value)| match serde_yaml::from_str::<Policy>(&value) {
+                Ok(policy) => Some(policy),
+                Err(e) => {
+                    logd!(4, "Skipping invalid policy '{}': {:?}", key, e);
+                    None
+                }
+            },
+        )
+        .collect();
+    Ok(policies)
+}
+
+/// DDS topics referenced by policy rule conditions
+pub fn condition_topics(policies: &[Policy]) -> Vec<String> {
+    let mut topics: Vec<String> = policies
+        .iter()
+        .flat_map(|p| p.get_rules())
+        .filter_map(|r| r.get_condition())
+        .flat_map(|c| c.get_topics())
+        .collect();
+    topics.sort();
+    topics.dedup();
+    topics
+}
+
+/// Decide whether a scenario action may run with the policies read from etcd
+///
+/// If the policies cannot be read the scenario is denied by the
+/// [`UNAVAILABLE_RULE`] of every policy: a policy restricting it may exist,
+/// and running an action it forbids is worse than not running one.
+pub fn decide(
+    policies: std::result::Result<Vec<Policy>, String>,
+    scenario_name: &str,
+    action: &str,
+    vehicle_data: &HashMap<String, DdsData>,
+) -> PolicyDecision {
+    match policies {
+        Ok(policies) => evaluate(&policies, scenario_name, action, vehicle_data),
+        Err(e) => {
+            logd!(
+                4,
+                "Failed to load policies, denying scenario {}: {}",
+                scenario_name,
+                e
+            );
+            PolicyDecision::Deny {
+                policy: "*".to_string(),
+                rule: UNAVAILABLE_RULE.to_string(),
+            }
+        }
+    }
+}
+
+/// Decide whether a scenario action may run
+///
+/// A matching deny rule always wins. If allow rules apply to the scenario,
+/// at least one of them has to match. Conditions that cannot be evaluated
+/// (no data for the topic yet, unparsable values) count as a match for deny
+/// rules and as no match for allow rules.
+pub fn evaluate(
+    policies: &[Policy],
+    scenario_name: &str,
+    action: &str,
+    vehicle_data: &HashMap<String, DdsData>,
+) -> PolicyDecision {
+    let mut unmatched_allow: Option<PolicyDecision> = None;
+    let mut allowed = false;
+
+    for policy in policies {
+        for rule in policy
+            .get_rules()
+            .iter()
+            .filter(|r| r.applies_to(scenario_name, action))
+        {
+            let decision = PolicyDecision::Deny {
+                policy: policy.get_name(),
+                rule: rule.get_name().to_string(),
+            };
+            match (rule.get_effect(), rule_matches(rule, vehicle_data)) {
+                (RuleEffect::Deny, Some(false)) => {}
+                (RuleEffect::Deny, _) => return decision,
+                (RuleEffect::Allow, Some(true)) => allowed = true,
+                (RuleEffect::Allow, _) => {
+                    unmatched_allow.get_or_insert(decision);
+                }
+            }
+        }
+    }
+
+    match unmatched_allow {
+        Some(decision) if !allowed => decision,
+        _ => PolicyDecision::Allow,
+    }
+}
+
+/// Evaluate a rule condition, `None` when it cannot be evaluated
+fn rule_matches(rule: &Rule, vehicle_data: &HashMap<String, DdsData>) -> Option<bool> {
+    let Some(condition) = rule.get_condition() else {
+        return Some(true);
+    };
+    evaluate_condition(condition, vehicle_data).ok().flatten()
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY_YAML: &str = r#"
+apiVersion: v1
+kind: Policy
+metadata:
+  name: driving-policy
+spec:
+  rules:
+    - name: no-update-while-driving
+      effect: deny
+      actions: ["update"]
+      condition:
+        express: gt
+        value: "0"
+        operands:
+          type: DDS
+          name: speed
+          value: vehicle/speed
+"#;
+
+    const ALLOW_YAML: &str = r#"
+apiVersion: v1
+kind: Policy
+metadata:
+  name: parking-policy
+spec:
+  rules:
+    - name: launch-only-when-parked
+      effect: allow
+      actions: ["launch"]
+      condition:
+        express: eq
+        value: "P"
+        operands:
+          type: DDS
+          name: gear
+          value: vehicle/gear
+"#;
+
+    fn vehicle_data(topic: &str, field: &str, value: &str) -> HashMap<String, DdsData> {
+        let data = DdsData {
+            name: topic.to_string(),
+            value: value.to_string(),
+            fields: HashMap::from([(field.to_string(), value.to_string())]),
+        };
+        HashMap::from([(topic.to_string(), data)])
+    }
+
+    fn policies() -> Vec<Policy> {
+        vec![
+            serde_yaml::from_str(POLICY_YAML).unwrap(),
+            serde_yaml::from_str(ALLOW_YAML).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn test_deny_rule_blocks_update_while_driving() {
+        let driving = vehicle_data("vehicle/speed", "speed", "30");
+        let stopped = vehicle_data("vehicle/speed", "speed", "0");
+
+        assert_eq!(
+            evaluate(&policies(), "sc1", "update", &driving),
+            PolicyDecision::Deny {
+                policy: "driving-policy".to_string(),
+                rule: "no-update-while-driving".to_string(),
+            }
+        );
+        assert_eq!(
+            evaluate(&policies(), "sc1", "update", &stopped),
+            PolicyDecision::Allow
+        );
+        // Unknown speed is treated as driving
+        assert!(matches!(
+            evaluate(&policies(), "sc1", "update", &HashMap::new()),
+            PolicyDecision::Deny { .. }
+        ));
+        // Rule does not cover other actions
+        assert_eq!(
+            evaluate(&policies(), "sc1", "terminate", &driving),
+            PolicyDecision::Allow
+        );
+    }
+
+    #[test]
+    fn test_allow_rule_must_match() {
+        let parked = vehicle_data("vehicle/gear", "gear", "p");
+        let drive = vehicle_data("vehicle/gear", "gear", "D");
+
+        assert_eq!(
+            evaluate(&policies(), "sc1", "launch", &parked),
+            PolicyDecision::Allow
+        );
+        assert_eq!(
+            evaluate(&policies(), "sc1", "launch", &drive),
+            PolicyDecision::Deny {
+                policy: "parking-policy".to_string(),
+                rule: "launch-only-when-parked".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_unreadable_policies_deny() {
+        let driving = vehicle_data("vehicle/speed", "speed", "30");
+
+        assert_eq!(
+            decide(
+                Err("store unavailable".to_string()),
+                "sc1",
+                "terminate",
+                &driving
+            ),
+            PolicyDecision::Deny {
+                policy: "*".to_string(),
+                rule: UNAVAILABLE_RULE.to_string(),
+            }
+        );
+        assert_eq!(
+            decide(Ok(policies()), "sc1", "terminate", &driving),
+            PolicyDecision::Allow
+        );
+    }
+
+    #[test]
+    fn test_condition_topics() {
+        assert_eq!(
+            condition_topics(&policies()),
+            vec!["vehicle/gear".to_string(), "vehicle/speed".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_vehicle_data_keeps_latest_value() {
+        let engine = PolicyEngine::new();
+        let shared = engine.clone();
+        for speed in ["10", "0"] {
+            let data = vehicle_data("vehicle/speed", "speed", speed);
+            engine
+                .update_vehicle_data(data.get("vehicle/speed").unwrap())
+                .await;
+        }
+
+        let vehicle_data = shared.vehicle_data.lock().await;
+        assert_eq!(vehicle_data.len(), 1);
+        assert_eq!(vehicle_data["vehicle/speed"].value, "0");
+    }
+}
@@ -0,0 +1,258 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Five field cron expressions (`minute hour day-of-month month day-of-week`)
+//!
+//! Fields accept `*`, single values, ranges (`1-5`), steps (`*/15`, `0-30/10`)
+//! and comma separated lists. Day of week is `0-7` with both `0` and `7`
+//! meaning Sunday. The `@hourly`, `@daily`, `@weekly`, `@monthly` and
+//! `@yearly` shorthands are also understood. Times are evaluated in UTC.
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Timelike, Utc};
+use std::str::FromStr;
+
+/// How far ahead to search before giving up on an expression
+/// that never matches (e.g. `0 0 31 2 *`)
+const SEARCH_LIMIT_DAYS: i64 = 366 * 5;
+
+/// Parsed cron expression, one bit per allowed value of each field
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronExpr {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Both day fields are restricted, a day matches if either one does
+    day_or: bool,
+}
+
+impl FromStr for CronExpr {
+    type Err = String;
+
+    fn from_str(expr: &str) -> Result<Self, Self::Err> {
+        let expr = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!(
+                "cron expression '{}' must have 5 fields, found {}",
+                expr,
+                fields.len()
+            ));
+        }
+
+        let mut weekdays = parse_field(fields[4], 0, 7)?;
+        // 7 is an alias for Sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59)?,
+            hours: parse_field(fields[1], 0, 23)?,
+            days: parse_field(fields[2], 1, 31)?,
+            months: parse_field(fields[3], 1, 12)?,
+            weekdays,
+            day_or: !fields[2].starts_with('*') && !fields[4].starts_with('*'),
+        })
+    }
+}
+
+impl CronExpr {
+    /// First matching minute strictly after `after`
+    ///
+    /// Returns `None` if nothing matches within the next five years.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let limit = after + Duration::days(SEARCH_LIMIT_DAYS);
+        let mut t = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+
+        while t <= limit {
+            if !has(self.months, t.month()) {
+                t = start_of_next_month(t)?;
+            } else if !self.day_matches(t) {
+                t = start_of_day(t)? + Duration::days(1);
+            } else if !has(self.hours, t.hour()) {
+                t = t.with_minute(0)? + Duration::hours(1);
+            } else if !has(self.minutes, t.minute()) {
+                t += Duration::minutes(1);
+            } else {
+                return Some(t);
+            }
+        }
+        None
+    }
+
+    fn day_matches(&self, t: DateTime<Utc>) -> bool {
+        let day = has(self.days, t.day());
+        let weekday = has(self.weekdays, t.weekday().num_days_from_sunday());
+        if self.day_or {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+}
+
+fn has(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+fn start_of_day(t: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    t.with_hour(0)?.with_minute(0)
+}
+
+fn start_of_next_month(t: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let (year, month) = match t.month() {
+        12 => (t.year() + 1, 1),
+        m => (t.year(), m + 1),
+    };
+    let date = NaiveDate::from_ymd_opt(year, month, 1)?;
+    Some(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?))
+}
+
+/// Parse one field into a bit mask of the allowed values in `min..=max`
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("invalid step '{}' in cron field '{}'", step, field))?;
+                if step == 0 {
+                    return Err(format!("step must not be 0 in cron field '{}'", field));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => {
+                let (start, end) = match range.split_once('-') {
+                    Some((start, end)) => (parse_value(start, field)?, parse_value(end, field)?),
+                    // `5/10` means every 10 starting at 5
+                    None if step > 1 => (parse_value(range, field)?, max),
+                    None => {
+                        let value = parse_value(range, field)?;
+                        (value, value)
+                    }
+                };
+                if start < min || end > max || start > end {
+                    return Err(format!(
+                        "'{}' is out of range {}-{} in cron field '{}'",
+                        range, min, max, field
+                    ));
+                }
+                (start, end)
+            }
+        };
+
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_value(value: &str, field: &str) -> Result<u32, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{}' in cron field '{}'", value, field))
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn next(expr: &str, after: &str) -> Option<DateTime<Utc>> {
+        expr.parse::<CronExpr>().unwrap().next_after(utc(after))
+    }
+
+    #[test]
+    fn test_parse_field() {
+        assert_eq!(parse_field("*", 0, 3).unwrap(), 0b1111);
+        assert_eq!(parse_field("1,3", 0, 5).unwrap(), 0b1010);
+        assert_eq!(parse_field("1-3", 0, 5).unwrap(), 0b1110);
+        assert_eq!(parse_field("*/2", 0, 5).unwrap(), 0b010101);
+        assert_eq!(parse_field("1/2", 0, 5).unwrap(), 0b101010);
+        assert!(parse_field("6", 0, 5).is_err());
+        assert!(parse_field("3-1", 0, 5).is_err());
+        assert!(parse_field("*/0", 0, 5).is_err());
+        assert!(parse_field("a", 0, 5).is_err());
+    }
+
+    #[test]
+    fn test_parse_expression() {
+        assert!("0 2 * *".parse::<CronExpr>().is_err());
+        assert_eq!(
+            "@daily".parse::<CronExpr>().unwrap(),
+            "0 0 * * *".parse::<CronExpr>().unwrap()
+        );
+        // 7 and 0 are both Sunday
+        assert_eq!(
+            "0 0 * * 7".parse::<CronExpr>().unwrap(),
+            "0 0 * * 0".parse::<CronExpr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_next_after() {
+        // Nightly at 02:00
+        assert_eq!(
+            next("0 2 * * *", "2025-03-10T01:59:30Z"),
+            Some(utc("2025-03-10T02:00:00Z"))
+        );
+        assert_eq!(
+            next("0 2 * * *", "2025-03-10T02:00:00Z"),
+            Some(utc("2025-03-11T02:00:00Z"))
+        );
+        // Every 15 minutes
+        assert_eq!(
+            next("*/15 * * * *", "2025-03-10T10:16:00Z"),
+            Some(utc("2025-03-10T10:30:00Z"))
+        );
+        // Year rollover
+        assert_eq!(
+            next("@yearly", "2025-06-01T00:00:00Z"),
+            Some(utc("2026-01-01T00:00:00Z"))
+        );
+        // Leap day
+        assert_eq!(
+            next("0 0 29 2 *", "2025-01-01T00:00:00Z"),
+            Some(utc("2028-02-29T00:00:00Z"))
+        );
+        assert_eq!(next("0 0 31 2 *", "2025-01-01T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn test_day_of_month_or_day_of_week() {
+        // 2025-03-10 is a Monday
+        assert_eq!(
+            next("0 0 * * 5", "2025-03-10T00:00:00Z"),
+            Some(utc("2025-03-14T00:00:00Z"))
+        );
+        // Both restricted: the 15th or any Friday
+        assert_eq!(
+            next("0 0 15 * 5", "2025-03-14T00:00:00Z"),
+            Some(utc("2025-03-15T00:00:00Z"))
+        );
+        assert_eq!(
+            next("0 0 15 * 5", "2025-03-15T00:00:00Z"),
+            Some(utc("2025-03-21T00:00:00Z"))
+        );
+    }
+}
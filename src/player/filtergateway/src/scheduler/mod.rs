@@ -0,0 +1,295 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Time based scenario conditions
+//!
+//! Scenarios with `condition.type: schedule` are not driven by vehicle data.
+//! Their next fire time is kept in etcd (`scheduler/scenarios/<name>`) so a
+//! restarted filtergateway resumes the schedule, and a run missed while it
+//! was down fires once right after the restart.
+pub mod cron;
+
+use crate::filter::Filter;
+use chrono::{DateTime, Utc};
+use common::logd;
+use common::spec::artifact::scenario::TriggerSchedule;
+use common::Result;
+use cron::CronExpr;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// etcd key prefix of persisted next fire times
+pub const SCHEDULE_PREFIX: &str = "scheduler/scenarios";
+
+/// How often due schedules are checked
+const TICK: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// When a scheduled scenario fires
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trigger {
+    /// Once at the given time
+    At(DateTime<Utc>),
+    /// Every time the cron expression matches
+    Cron(CronExpr),
+}
+
+impl Trigger {
+    /// Build the trigger of a schedule, which sets exactly one of `cron` and `at`
+    pub fn from_schedule(schedule: &TriggerSchedule) -> Result<Self> {
+        match (schedule.get_cron(), schedule.get_at()) {
+            (Some(cron), None) => Ok(Trigger::Cron(cron.parse::<CronExpr>()?)),
+            (None, Some(at)) => Ok(Trigger::At(
+                DateTime::parse_from_rfc3339(&at)
+                    .map_err(|e| format!("invalid schedule time '{}': {}", at, e))?
+                    .with_timezone(&Utc),
+            )),
+            _ => Err("schedule needs exactly one of 'cron' or 'at'".into()),
+        }
+    }
+
+    /// Next fire time strictly after `now`
+    pub fn next_after(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Trigger::At(at) => (*at > now).then_some(*at),
+            Trigger::Cron(cron) => cron.next_after(now),
+        }
+    }
+}
+
+/// Next fire time of a scenario as stored in etcd
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct ScheduleState {
+    /// `cron` or `at` value the time was computed from
+    schedule: String,
+    /// Unix time in seconds
+    next_fire: i64,
+}
+
+struct Entry {
+    filter: Filter,
+    schedule: String,
+    trigger: Trigger,
+    next_fire: DateTime<Utc>,
+}
+
+/// Fires scheduled scenarios through their filter
+///
+/// Clones share the same entries, so the manager can add and remove
+/// scenarios while the clone in `run` fires them.
+#[derive(Clone, Default)]
+pub struct Scheduler {
+    entries: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a scenario with a schedule condition
+    ///
+    /// A fire time persisted for the same schedule is resumed, even if it
+    /// already passed. Otherwise the next run is computed from now, so a
+    /// one-shot time in the past never fires.
+    pub async fn add(&self, filter: Filter) -> Result<()> {
+        let name = filter.scenario_name.clone();
+        let schedule = filter
+            .scenario
+            .get_conditions()
+            .and_then(|c| c.get_schedule())
+            .ok_or_else(|| format!("scenario '{}' has no schedule", name))?;
+        let trigger = Trigger::from_schedule(&schedule)?;
+        let spec = schedule
+            .get_cron()
+            .or(schedule.get_at())
+            .unwrap_or_default();
+
+        let now = Utc::now();
+        let Some(next_fire) = resume_time(load_state(&name).await, &spec, &trigger, now) else {
+            logd!(3, "Schedule of scenario {} has no future run", name);
+            return Ok(());
+        };
+        save_state(&name, &spec, next_fire).await;
+        logd!(3, "Scenario {} scheduled at {}", name, next_fire);
+
+        self.entries.lock().await.insert(
+            name,
+            Entry {
+                filter,
+                schedule: spec,
+                trigger,
+                next_fire,
+            },
+        );
+        Ok(())
+    }
+
+    /// Stop firing a scenario and forget its persisted fire time
+    pub async fn remove(&self, scenario_name: &str) {
+        if self.entries.lock().await.remove(scenario_name).is_some() {
+            if let Err(e) = common::etcd::delete(&state_key(scenario_name)).await {
+                logd!(4, "Failed to delete schedule of {}: {}", scenario_name, e);
+            }
+        }
+    }
+
+    /// Fire due scenarios until the task is dropped
+    pub async fn run(&self) {
+        let mut interval = tokio::time::interval(TICK);
+        loop {
+            interval.tick().await;
+            self.fire_due(Utc::now()).await;
+        }
+    }
+
+    /// Trigger every scenario whose fire time has come and schedule its next run
+    async fn fire_due(&self, now: DateTime<Utc>) {
+        let mut entries = self.entries.lock().await;
+        let due: Vec<String> = entries
+            .iter()
+            .filter(|(_, entry)| entry.next_fire <= now)
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        for name in due {
+            let Some(entry) = entries.get_mut(&name) else {
+                continue;
+            };
+            logd!(3, "Schedule of scenario {} fired", name);
+            if let Err(e) = entry.filter.trigger_scenario().await {
+                logd!(5, "Failed to trigger scheduled scenario {}: {:?}", name, e);
+            }
+
+            match entry.trigger.next_after(now) {
+                Some(next_fire) => {
+                    entry.next_fire = next_fire;
+                    save_state(&name, &entry.schedule, next_fire).await;
+                }
+                None => {
+                    entries.remove(&name);
+                    if let Err(e) = common::etcd::delete(&state_key(&name)).await {
+                        logd!(4, "Failed to delete schedule of {}: {}", name, e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Fire time to use when a scenario is (re)registered
+fn resume_time(
+    state: Option<ScheduleState>,
+    schedule: &str,
+    trigger: &Trigger,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    match state {
+        Some(state) if state.schedule == schedule => DateTime::from_timestamp(state.next_fire, 0),
+        _ => trigger.next_after(now),
+    }
+}
+
+fn state_key(scenario_name: &str) -> String {
+    format!("{}/{}", SCHEDULE_PREFIX, scenario_name)
+}
+
+async fn load_state(scenario_name: &str) -> Option<ScheduleState> {
+    let value = common::etcd::get(&state_key(scenario_name)).await.ok()?;
+    serde_json::from_str(&value).ok()
+}
+
+/// Persist the next fire time, logging failures since the schedule keeps
+/// running in memory
+async fn save_state(scenario_name: &str, schedule: &str, next_fire: DateTime<Utc>) {
+    let state = ScheduleState {
+        schedule: schedule.to_string(),
+        next_fire: next_fire.timestamp(),
+    };
+    let value = match serde_json::to_string(&state) {
+        Ok(value) => value,
+        Err(e) => {
+            logd!(4, "Failed to encode schedule of {}: {:?}", scenario_name, e);
+            return;
+        }
+    };
+    if let Err(e) = common::etcd::put(&state_key(scenario_name), &value).await {
+        logd!(4, "Failed to persist schedule of {}: {}", scenario_name, e);
+    }
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn schedule(yaml: &str) -> TriggerSchedule {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_trigger_from_schedule() {
+        assert!(matches!(
+            Trigger::from_schedule(&schedule("cron: '0 2 * * *'")),
+            Ok(Trigger::Cron(_))
+        ));
+        assert_eq!(
+            Trigger::from_schedule(&schedule("at: '2025-06-01T02:00:00+02:00'")).unwrap(),
+            Trigger::At(utc("2025-06-01T00:00:00Z"))
+        );
+        assert!(Trigger::from_schedule(&schedule("at: tomorrow")).is_err());
+        assert!(Trigger::from_schedule(&schedule("cron: '0 2 * *'")).is_err());
+        assert!(Trigger::from_schedule(&schedule("{}")).is_err());
+        assert!(
+            Trigger::from_schedule(&schedule("{cron: '@daily', at: '2025-06-01T00:00:00Z'}"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_one_shot_fires_once() {
+        let at = Trigger::At(utc("2025-06-01T00:00:00Z"));
+        assert_eq!(
+            at.next_after(utc("2025-05-31T23:00:00Z")),
+            Some(utc("2025-06-01T00:00:00Z"))
+        );
+        assert_eq!(at.next_after(utc("2025-06-01T00:00:00Z")), None);
+    }
+
+    #[test]
+    fn test_resume_time() {
+        let trigger = Trigger::Cron("0 2 * * *".parse().unwrap());
+        let now = utc("2025-03-10T08:00:00Z");
+        let missed = ScheduleState {
+            schedule: "0 2 * * *".to_string(),
+            next_fire: utc("2025-03-10T02:00:00Z").timestamp(),
+        };
+
+        // A run missed while stopped is resumed and fires immediately
+        assert_eq!(
+            resume_time(Some(missed), "0 2 * * *", &trigger, now),
+            Some(utc("2025-03-10T02:00:00Z"))
+        );
+        // A changed schedule starts over
+        let stale = ScheduleState {
+            schedule: "0 3 * * *".to_string(),
+            next_fire: 0,
+        };
+        assert_eq!(
+            resume_time(Some(stale), "0 2 * * *", &trigger, now),
+            Some(utc("2025-03-11T02:00:00Z"))
+        );
+        assert_eq!(
+            resume_time(None, "0 2 * * *", &trigger, now),
+            Some(utc("2025-03-11T02:00:00Z"))
+        );
+        // A one-shot time that passed before it was ever registered is dropped
+        let past = Trigger::At(utc("2025-03-01T00:00:00Z"));
+        assert_eq!(resume_time(None, "2025-03-01T00:00:00Z", &past, now), None);
+    }
+}
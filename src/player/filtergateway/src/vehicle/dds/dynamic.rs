@@ -0,0 +1,393 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! DDS types registered at runtime from IDL
+//!
+//! Types generated by build.rs need a rebuild for every new topic. An IDL
+//! struct registered here is parsed when FilterGateway runs, and topics of
+//! that type are read as raw samples that are decoded with the parsed
+//! definition. Only flat structs of primitive and string members are
+//! supported, in plain (final) CDR or XCDR2 encoding.
+use anyhow::anyhow;
+use common::Result;
+use dust_dds::topic_definition::type_support::{DdsDeserialize, TypeSupport};
+use dust_dds::xtypes::dynamic_type::DynamicType;
+use dust_dds::xtypes::type_object::{
+    CompleteStructHeader, CompleteStructType, CompleteTypeDetail, CompleteTypeObject,
+    StructTypeFlag, TypeIdentifier,
+};
+use once_cell::sync::Lazy;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// Types registered at runtime, by struct name
+static RUNTIME_TYPES: Lazy<RwLock<HashMap<String, IdlStruct>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Primitive type of an IDL struct member
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdlType {
+    Boolean,
+    Octet,
+    Char,
+    Short,
+    UShort,
+    Long,
+    ULong,
+    LongLong,
+    ULongLong,
+    Float,
+    Double,
+    String,
+}
+
+impl IdlType {
+    /// Type of an IDL type name, with the same aliases as the build-time generator
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+        if name.starts_with("string<") && name.ends_with('>') {
+            return Some(IdlType::String);
+        }
+        match name.as_str() {
+            "boolean" => Some(IdlType::Boolean),
+            "octet" | "byte" | "uint8_t" => Some(IdlType::Octet),
+            "char" => Some(IdlType::Char),
+            "short" | "int16_t" => Some(IdlType::Short),
+            "unsigned short" | "uint16_t" => Some(IdlType::UShort),
+            "long" | "int32_t" => Some(IdlType::Long),
+            "unsigned long" | "uint32_t" => Some(IdlType::ULong),
+            "long long" | "int64_t" => Some(IdlType::LongLong),
+            "unsigned long long" | "uint64_t" => Some(IdlType::ULongLong),
+            "float" => Some(IdlType::Float),
+            "double" => Some(IdlType::Double),
+            "string" | "std::string" => Some(IdlType::String),
+            _ => None,
+        }
+    }
+}
+
+/// Struct parsed from IDL, with its members in declaration order
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdlStruct {
+    pub name: String,
+    pub members: Vec<(String, IdlType)>,
+}
+
+impl IdlStruct {
+    /// Parse the first struct of an IDL source
+    ///
+    /// Comments, annotations like `@key` and enclosing modules are ignored.
+    pub fn parse(source: &str) -> Result<Self> {
+        let source = strip_comments(source);
+        let start = source
+            .find("struct")
+            .ok_or_else(|| anyhow!("No struct found in IDL"))?;
+        let rest = &source[start + "struct".len()..];
+        let open = rest
+            .find('{')
+            .ok_or_else(|| anyhow!("Struct has no body"))?;
+        let name = rest[..open].trim().to_string();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(anyhow!("Invalid struct name '{}'", name).into());
+        }
+        let body = &rest[open + 1..];
+        let close = body
+            .find('}')
+            .ok_or_else(|| anyhow!("Struct '{}' is not closed", name))?;
+
+        let mut members = Vec::new();
+        for declaration in body[..close].split(';') {
+            let tokens: Vec<&str> = declaration
+                .split_whitespace()
+                .filter(|token| !token.starts_with('@'))
+                .collect();
+            let Some((member, type_tokens)) = tokens.split_last() else {
+                continue;
+            };
+            if member.contains('[') {
+                return Err(anyhow!("Array member '{}' is not supported", member).into());
+            }
+            let type_name = type_tokens.join(" ");
+            let idl_type = IdlType::parse(&type_name).ok_or_else(|| {
+                anyhow!(
+                    "Type '{}' of member '{}' is not supported",
+                    type_name,
+                    member
+                )
+            })?;
+            members.push((member.to_string(), idl_type));
+        }
+        if members.is_empty() {
+            return Err(anyhow!("Struct '{}' has no members", name).into());
+        }
+
+        Ok(Self { name, members })
+    }
+
+    /// Decode a serialized sample, including its encapsulation header
+    pub fn decode(&self, data: &[u8]) -> Result<Map<String, Value>> {
+        if data.len() < 4 {
+            return Err(anyhow!("Sample is shorter than its encapsulation header").into());
+        }
+        let (little_endian, max_align, delimited) = match [data[0], data[1]] {
+            [0x00, 0x00] => (false, 8, false),
+            [0x00, 0x01] => (true, 8, false),
+            [0x00, 0x06] => (false, 4, false),
+            [0x00, 0x07] => (true, 4, false),
+            [0x00, 0x08] => (false, 4, true),
+            [0x00, 0x09] => (true, 4, true),
+            id => return Err(anyhow!("Unsupported sample encoding {:02x?}", id).into()),
+        };
+        let mut reader = CdrReader {
+            data: &data[4..],
+            position: 0,
+            little_endian,
+            max_align,
+        };
+        if delimited {
+            // Appendable structs start with their size
+            reader.read::<4>()?;
+        }
+
+        let mut fields = Map::new();
+        for (member, idl_type) in &self.members {
+            fields.insert(member.clone(), reader.value(*idl_type)?);
+        }
+        Ok(fields)
+    }
+}
+
+/// Remove `//` and `/* */` comments
+fn strip_comments(source: &str) -> String {
+    let mut result = String::new();
+    let mut rest = source;
+    while let Some(start) = rest.find('/') {
+        result.push_str(&rest[..start]);
+        let comment = &rest[start..];
+        if comment.starts_with("//") {
+            rest = comment.find('\n').map_or("", |end| &comment[end..]);
+        } else if comment.starts_with("/*") {
+            rest = comment.find("*/").map_or("", |end| &comment[end + 2..]);
+        } else {
+            result.push('/');
+            rest = &comment[1..];
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Reader of CDR primitives, aligned relative to the start of the payload
+struct CdrReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    little_endian: bool,
+    /// Largest alignment, 8 in CDR and 4 in XCDR2
+    max_align: usize,
+}
+
+impl CdrReader<'_> {
+    fn read<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let align = N.clamp(1, self.max_align);
+        self.position = self.position.div_ceil(align) * align;
+        let bytes = self
+            .data
+            .get(self.position..self.position + N)
+            .ok_or_else(|| anyhow!("Sample ends before all members were read"))?;
+        self.position += N;
+        let mut value: [u8; N] = bytes.try_into().expect("slice has length N");
+        if self.little_endian != cfg!(target_endian = "little") {
+            value.reverse();
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self, idl_type: IdlType) -> Result<Value> {
+        let value = match idl_type {
+            IdlType::Boolean => Value::Bool(self.read::<1>()?[0] != 0),
+            IdlType::Octet => Value::from(self.read::<1>()?[0]),
+            IdlType::Char => Value::from((self.read::<1>()?[0] as char).to_string()),
+            IdlType::Short => Value::from(i16::from_ne_bytes(self.read()?)),
+            IdlType::UShort => Value::from(u16::from_ne_bytes(self.read()?)),
+            IdlType::Long => Value::from(i32::from_ne_bytes(self.read()?)),
+            IdlType::ULong => Value::from(u32::from_ne_bytes(self.read()?)),
+            IdlType::LongLong => Value::from(i64::from_ne_bytes(self.read()?)),
+            IdlType::ULongLong => Value::from(u64::from_ne_bytes(self.read()?)),
+            IdlType::Float => Value::from(f32::from_ne_bytes(self.read()?) as f64),
+            IdlType::Double => Value::from(f64::from_ne_bytes(self.read()?)),
+            IdlType::String => {
+                // The length counts the terminating NUL
+                let length = u32::from_ne_bytes(self.read()?) as usize;
+                let bytes = self
+                    .data
+                    .get(self.position..self.position + length)
+                    .ok_or_else(|| anyhow!("Sample ends inside a string"))?;
+                self.position += length;
+                let text = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+                Value::from(String::from_utf8_lossy(text).into_owned())
+            }
+        };
+        Ok(value)
+    }
+}
+
+/// Register an IDL struct so topics of its type can be listened to
+///
+/// A struct registered again replaces the earlier definition.
+///
+/// # Returns
+///
+/// The name of the registered type
+pub fn register_idl(source: &str) -> Result<String> {
+    let idl_struct = IdlStruct::parse(source)?;
+    let name = idl_struct.name.clone();
+    RUNTIME_TYPES
+        .write()
+        .map_err(|_| anyhow!("Runtime type registry is poisoned"))?
+        .insert(name.clone(), idl_struct);
+    Ok(name)
+}
+
+/// Definition of a type registered at runtime
+pub fn runtime_type(name: &str) -> Option<IdlStruct> {
+    RUNTIME_TYPES.read().ok()?.get(name).cloned()
+}
+
+/// Names of the types registered at runtime
+pub fn runtime_types() -> Vec<String> {
+    RUNTIME_TYPES
+        .read()
+        .map(|types| types.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Sample of a runtime type, kept serialized until it is decoded
+#[derive(Debug, Clone, Default)]
+pub struct DynamicSample(pub Vec<u8>);
+
+impl TypeSupport for DynamicSample {
+    fn get_type_name() -> &'static str {
+        "DynamicSample"
+    }
+
+    /// A keyless struct, so every sample belongs to the same instance
+    fn get_type() -> impl DynamicType {
+        CompleteTypeObject::TkStructure {
+            struct_type: CompleteStructType {
+                struct_flags: StructTypeFlag {
+                    is_final: true,
+                    is_appendable: false,
+                    is_mutable: false,
+                    is_nested: false,
+                    is_autoid_hash: false,
+                },
+                header: CompleteStructHeader {
+                    base_type: TypeIdentifier::TkNone,
+                    detail: CompleteTypeDetail {
+                        ann_builtin: None,
+                        ann_custom: None,
+                        type_name: Self::get_type_name().to_string(),
+                    },
+                },
+                member_seq: Vec::new(),
+            },
+        }
+    }
+}
+
+impl<'de> DdsDeserialize<'de> for DynamicSample {
+    fn deserialize_data(
+        serialized_data: &'de [u8],
+    ) -> dust_dds::infrastructure::error::DdsResult<Self> {
+        Ok(Self(serialized_data.to_vec()))
+    }
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dust_dds::topic_definition::type_support::{DdsSerialize, DdsType};
+    // Temporarily shadow the conflicting `Result` alias
+    type Result<T, E> = std::result::Result<T, E>;
+
+    const IDL: &str = r#"
+module vehicle {
+    /* Status of a seat */
+    struct SeatStatus {
+        // position in steps
+        @key long seat;
+        boolean occupied;
+        double temperature;
+        string<32> occupant;
+        unsigned long long updated;
+    };
+};
+"#;
+
+    #[derive(Debug, Clone, DdsType)]
+    struct SeatStatus {
+        seat: i32,
+        occupied: bool,
+        temperature: f64,
+        occupant: String,
+        updated: u64,
+    }
+
+    #[test]
+    fn test_parse_idl_struct() {
+        let seat = IdlStruct::parse(IDL).unwrap();
+        assert_eq!(seat.name, "SeatStatus");
+        assert_eq!(
+            seat.members,
+            vec![
+                ("seat".to_string(), IdlType::Long),
+                ("occupied".to_string(), IdlType::Boolean),
+                ("temperature".to_string(), IdlType::Double),
+                ("occupant".to_string(), IdlType::String),
+                ("updated".to_string(), IdlType::ULongLong),
+            ]
+        );
+
+        assert!(IdlStruct::parse("struct A { sequence<long> values; };").is_err());
+        assert!(IdlStruct::parse("struct A { long values[4]; };").is_err());
+        assert!(IdlStruct::parse("enum Gear { P, R };").is_err());
+    }
+
+    #[test]
+    fn test_decode_matches_generated_type() {
+        let sample = SeatStatus {
+            seat: 2,
+            occupied: true,
+            temperature: 21.5,
+            occupant: "driver".to_string(),
+            updated: 1_700_000_000_000,
+        };
+        let data = sample.serialize_data().unwrap();
+
+        let fields = IdlStruct::parse(IDL).unwrap().decode(&data).unwrap();
+        assert_eq!(fields["seat"], Value::from(2));
+        assert_eq!(fields["occupied"], Value::Bool(true));
+        assert_eq!(fields["temperature"], Value::from(21.5));
+        assert_eq!(fields["occupant"], Value::from("driver"));
+        assert_eq!(fields["updated"], Value::from(1_700_000_000_000u64));
+
+        // A truncated sample is an error, not a partial result
+        assert!(IdlStruct::parse(IDL)
+            .unwrap()
+            .decode(&data[..data.len() - 4])
+            .is_err());
+    }
+
+    #[test]
+    fn test_register_idl() {
+        let name = register_idl("struct RuntimeGear { octet gear; };").unwrap();
+        assert_eq!(name, "RuntimeGear");
+        assert!(runtime_types().contains(&name));
+        assert_eq!(runtime_type(&name).unwrap().members.len(), 1);
+        assert!(runtime_type("Unregistered").is_none());
+        assert!(register_idl("struct Broken { map<long, long> m; };").is_err());
+    }
+}
@@ -2,6 +2,7 @@
 * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
 * SPDX-License-Identifier: Apache-2.0
 */
+use crate::vehicle::dds::dynamic::{DynamicSample, IdlStruct};
 use crate::vehicle::dds::DdsData;
 use common::Result;
 use std::collections::HashMap;
@@ -14,6 +15,9 @@ pub trait DdsTopicListener: Send + Sync {
     async fn stop(&mut self) -> Result<()>;
     fn get_topic_name(&self) -> &str;
     fn is_topic(&self, topic_name: &str) -> bool;
+    /// QoS of the data reader created by `start`, ignored by listeners
+    /// without a data reader
+    fn set_reader_qos(&mut self, _qos: DataReaderQos) {}
 }
 
 #[allow(unused_variables, unused_imports)]
@@ -21,7 +25,7 @@ use dust_dds::{
     domain::domain_participant::DomainParticipant,
     domain::domain_participant_factory::DomainParticipantFactory,
     infrastructure::{
-        qos::QosKind,
+        qos::{DataReaderQos, QosKind},
         qos_policy::{DataRepresentationQosPolicy, XCDR2_DATA_REPRESENTATION},
         status::NO_STATUS,
         time::Duration,
@@ -243,6 +247,8 @@ pub struct GenericTopicListener<
     tx: Sender<DdsData>,
     /// DDS domain ID
     domain_id: i32,
+    /// QoS of the data reader, the DDS default if `None`
+    reader_qos: Option<DataReaderQos>,
     /// Listener task handle
     listener_task: Option<JoinHandle<()>>,
     /// Running state
@@ -274,6 +280,7 @@ impl<
             data_type_name,
             tx,
             domain_id,
+            reader_qos: None,
             listener_task: None,
             is_running: false,
             _marker: std::marker::PhantomData,
@@ -287,6 +294,7 @@ impl<
         data_type_name: String,
         tx: Sender<DdsData>,
         domain_id: i32,
+        reader_qos: Option<DataReaderQos>,
     ) -> Result<()> {
         // 도메인 참여자 생성
         let domain_participant_factory = DomainParticipantFactory::get_instance();
@@ -305,7 +313,12 @@ impl<
 
         // 데이터 리더 생성
         let data_reader = subscriber
-            .create_datareader::<T>(&topic, QosKind::Default, None, NO_STATUS)
+            .create_datareader::<T>(
+                &topic,
+                reader_qos.map_or(QosKind::Default, QosKind::Specific),
+                None,
+                NO_STATUS,
+            )
             .map_err(|e| anyhow!("Failed to create data reader: {:?}", e))?;
 
         logd!(
@@ -391,11 +404,18 @@ impl<
         let data_type_name = self.data_type_name.clone();
         let tx = self.tx.clone();
         let domain_id = self.domain_id;
+        let reader_qos = self.reader_qos.clone();
 
         // 리스너 태스크 시작
         let task = tokio::spawn(async move {
-            if let Err(e) =
-                Self::typed_listener_loop(topic_name.clone(), data_type_name, tx, domain_id).await
+            if let Err(e) = Self::typed_listener_loop(
+                topic_name.clone(),
+                data_type_name,
+                tx,
+                domain_id,
+                reader_qos,
+            )
+            .await
             {
                 logd!(
                     5,
@@ -428,7 +448,185 @@ impl<
     fn is_topic(&self, topic_name: &str) -> bool {
         self.topic_name == topic_name
     }
+
+    fn set_reader_qos(&mut self, qos: DataReaderQos) {
+        self.reader_qos = Some(qos);
+    }
+}
+
+/// Listener of a topic whose type was registered at runtime
+///
+/// Samples are read as [`DynamicSample`] and decoded with the IDL definition
+/// of the type.
+pub struct DynamicTopicListener {
+    topic_name: String,
+    definition: IdlStruct,
+    tx: Sender<DdsData>,
+    domain_id: i32,
+    reader_qos: Option<DataReaderQos>,
+    listener_task: Option<JoinHandle<()>>,
+    is_running: bool,
 }
+
+impl DynamicTopicListener {
+    pub fn new(
+        topic_name: String,
+        definition: IdlStruct,
+        tx: Sender<DdsData>,
+        domain_id: i32,
+    ) -> Self {
+        Self {
+            topic_name,
+            definition,
+            tx,
+            domain_id,
+            reader_qos: None,
+            listener_task: None,
+            is_running: false,
+        }
+    }
+
+    async fn dynamic_listener_loop(
+        topic_name: String,
+        definition: IdlStruct,
+        tx: Sender<DdsData>,
+        domain_id: i32,
+        reader_qos: Option<DataReaderQos>,
+    ) -> Result<()> {
+        let domain_participant_factory = DomainParticipantFactory::get_instance();
+        let participant = domain_participant_factory
+            .create_participant(domain_id, QosKind::Default, None, NO_STATUS)
+            .map_err(|e| anyhow!("Failed to create domain participant: {:?}", e))?;
+        let subscriber = participant
+            .create_subscriber(QosKind::Default, None, NO_STATUS)
+            .map_err(|e| anyhow!("Failed to create subscriber: {:?}", e))?;
+        let topic = participant
+            .create_topic::<DynamicSample>(
+                &topic_name,
+                &definition.name,
+                QosKind::Default,
+                None,
+                NO_STATUS,
+            )
+            .map_err(|e| anyhow!("Failed to create topic: {:?}", e))?;
+        let data_reader = subscriber
+            .create_datareader::<DynamicSample>(
+                &topic,
+                reader_qos.map_or(QosKind::Default, QosKind::Specific),
+                None,
+                NO_STATUS,
+            )
+            .map_err(|e| anyhow!("Failed to create data reader: {:?}", e))?;
+
+        logd!(
+            3,
+            "Reading topic '{}' with runtime type '{}'",
+            topic_name,
+            definition.name
+        );
+
+        let mut interval = time::interval(time::Duration::from_millis(2000));
+        loop {
+            interval.tick().await;
+
+            let samples =
+                match data_reader.take(1, ANY_SAMPLE_STATE, ANY_VIEW_STATE, ANY_INSTANCE_STATE) {
+                    Ok(samples) => samples,
+                    Err(e) => {
+                        logd!(5, "No new samples available: {:?}", e);
+                        continue;
+                    }
+                };
+            for sample in samples {
+                let Ok(DynamicSample(data)) = sample.data() else {
+                    continue;
+                };
+                let map = match definition.decode(&data) {
+                    Ok(map) => map,
+                    Err(e) => {
+                        logd!(4, "Failed to decode sample of '{}': {}", topic_name, e);
+                        continue;
+                    }
+                };
+                let fields = map
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.to_string()))
+                    .collect();
+                let dds_data = DdsData {
+                    name: definition.name.clone(),
+                    value: Value::Object(map).to_string(),
+                    fields,
+                };
+                if tx.send(dds_data).await.is_err() {
+                    logd!(4, "Channel closed, stopping listener for {}", topic_name);
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl DdsTopicListener for DynamicTopicListener {
+    fn is_running(&self) -> bool {
+        self.is_running
+    }
+
+    async fn start(&mut self) -> Result<()> {
+        if self.is_running {
+            return Ok(());
+        }
+
+        let topic_name = self.topic_name.clone();
+        let definition = self.definition.clone();
+        let tx = self.tx.clone();
+        let domain_id = self.domain_id;
+        let reader_qos = self.reader_qos.clone();
+        let task = tokio::spawn(async move {
+            if let Err(e) = Self::dynamic_listener_loop(
+                topic_name.clone(),
+                definition,
+                tx,
+                domain_id,
+                reader_qos,
+            )
+            .await
+            {
+                logd!(
+                    5,
+                    "Error in dynamic listener loop for {}: {:?}",
+                    topic_name,
+                    e
+                );
+            }
+        });
+
+        self.listener_task = Some(task);
+        self.is_running = true;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        if let Some(task) = self.listener_task.take() {
+            task.abort();
+        }
+        self.is_running = false;
+        Ok(())
+    }
+
+    fn get_topic_name(&self) -> &str {
+        &self.topic_name
+    }
+
+    fn is_topic(&self, topic_name: &str) -> bool {
+        self.topic_name == topic_name
+    }
+
+    fn set_reader_qos(&mut self, qos: DataReaderQos) {
+        self.reader_qos = Some(qos);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -525,6 +723,7 @@ mod tests {
                 "ADASObstacleDetectionIsWarning".to_string(),
                 tx,
                 100,
+                None,
             )
             .await
         })
@@ -716,6 +915,7 @@ mod tests {
                 "DDS".to_string(),
                 tx,
                 100,
+                None,
             )
             .await
             .map_err(|e| anyhow::anyhow!(e.to_string())) // convert error to string before anyhow
@@ -793,6 +993,7 @@ mod tests {
                 "DDS".to_string(),
                 tx,
                 100, // Will work if DDS setup is OK, but reading might fail (no data)
+                None,
             )
             .await;
         });
@@ -814,6 +1015,7 @@ mod tests {
                 "DDS".to_string(),
                 tx,
                 100,
+                None,
             )
             .await;
 
@@ -852,6 +1054,7 @@ mod tests {
             "DDS".to_string(),
             tx,
             100,
+            None,
         )
         .await;
 
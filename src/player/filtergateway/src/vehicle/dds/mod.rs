@@ -2,9 +2,11 @@
 * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
 * SPDX-License-Identifier: Apache-2.0
 */
+use super::source::{SignalProtocol, SignalSource};
 use anyhow::anyhow;
 use common::logd;
 use common::Result;
+use dust_dds::infrastructure::qos::DataReaderQos;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env;
@@ -13,10 +15,11 @@ use std::path::{Path, PathBuf};
 use tokio::sync::mpsc::{self, Receiver, Sender};
 use tokio::sync::Mutex;
 
+pub mod dynamic;
 pub mod listener;
 
 // Re-export the modules
-pub use listener::{create_idl_listener, DdsTopicListener};
+pub use listener::{create_idl_listener, DdsTopicListener, DynamicTopicListener};
 
 // DdsData structure to represent parsed IDL data
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -82,6 +85,22 @@ impl DdsManager {
         &mut self,
         topic_name: String,
         data_type_name: String,
+    ) -> Result<()> {
+        self.create_listener_with_qos(topic_name, data_type_name, self.domain_id, None)
+            .await
+    }
+
+    /// Create a typed listener in a given domain with a given reader QoS
+    ///
+    /// Used for topics subscribed at runtime, see [`super::subscription`].
+    /// The default domain and reader QoS are used by
+    /// [`Self::create_typed_listener`].
+    pub async fn create_listener_with_qos(
+        &mut self,
+        topic_name: String,
+        data_type_name: String,
+        domain_id: i32,
+        reader_qos: Option<DataReaderQos>,
     ) -> Result<()> {
         // 이미 존재하는 리스너인지 확인
         if self.listeners.contains_key(&topic_name) {
@@ -99,8 +118,11 @@ impl DdsManager {
             &data_type_name,
             topic_name.clone(),
             self.tx.clone(),
-            self.domain_id,
+            domain_id,
         ) {
+            if let Some(qos) = reader_qos {
+                typed_listener.set_reader_qos(qos);
+            }
             // 리스너 시작
             typed_listener
                 .start()
@@ -119,18 +141,66 @@ impl DdsManager {
             return Ok(());
         }
 
+        // Types registered at runtime are decoded from their IDL
+        if let Some(definition) = dynamic::runtime_type(&data_type_name) {
+            let mut listener = DynamicTopicListener::new(
+                topic_name.clone(),
+                definition,
+                self.tx.clone(),
+                domain_id,
+            );
+            if let Some(qos) = reader_qos {
+                listener.set_reader_qos(qos);
+            }
+            listener
+                .start()
+                .await
+                .map_err(|e| anyhow!("Failed to start dynamic listener: {:?}", e))?;
+            logd!(
+                2,
+                "Started dynamic listener for {} with runtime type {}",
+                topic_name,
+                data_type_name
+            );
+            self.listeners.insert(topic_name, Box::new(listener));
+            return Ok(());
+        }
+
         // Create generic listener if no type-specific listener is found
         logd!(
             4,
             "No specific type handler for '{}', using generic listener",
             data_type_name
         );
-        self.create_listener(topic_name, data_type_name).await
+        let mut listener = create_idl_listener(
+            topic_name.clone(),
+            data_type_name,
+            self.tx.clone(),
+            domain_id,
+        );
+        listener
+            .start()
+            .await
+            .map_err(|e| anyhow!("Failed to start listener: {:?}", e))?;
+        self.listeners.insert(topic_name, listener);
+        Ok(())
     }
 
-    /// Get list of available DDS types
+    /// Whether a listener of the topic is running
+    pub fn has_listener(&self, topic_name: &str) -> bool {
+        self.listeners.contains_key(topic_name)
+    }
+
+    /// DDS domain ID of new listeners
+    pub fn domain_id(&self) -> i32 {
+        self.domain_id
+    }
+
+    /// Get list of available DDS types, built-in and registered at runtime
     pub fn list_available_types(&self) -> Vec<String> {
-        dds_type_metadata::get_available_types()
+        let mut types = dds_type_metadata::get_available_types();
+        types.extend(dynamic::runtime_types());
+        types
     }
 
     /// Set DDS domain ID
@@ -254,6 +324,21 @@ impl DdsManager {
     }
 }
 
+#[async_trait::async_trait]
+impl SignalSource for DdsManager {
+    fn protocol(&self) -> SignalProtocol {
+        SignalProtocol::Dds
+    }
+
+    async fn subscribe(&mut self, topic_name: String, data_type_name: String) -> Result<()> {
+        self.create_typed_listener(topic_name, data_type_name).await
+    }
+
+    async fn unsubscribe(&mut self, topic_name: &str) -> Result<()> {
+        self.remove_listener(topic_name).await
+    }
+}
+
 // Include generated DDS types at runtime
 #[allow(unused)]
 #[allow(non_snake_case)]
@@ -3,19 +3,38 @@
 * SPDX-License-Identifier: Apache-2.0
 */
 pub mod dds;
+pub mod mode;
+pub mod record;
+pub mod someip;
+pub mod source;
+pub mod subscription;
 
 use common::logd;
 use common::Result;
 use dds::DdsData;
+use source::{SignalProtocol, SignalSettings, SignalSource};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use subscription::{Subscription, SubscriptionError, TopicOrigin};
 use tokio::sync::mpsc::Sender;
 
 /// Vehicle data management module
 ///
-/// Manages vehicle data through DDS communication
+/// Manages vehicle data through DDS or SOME/IP communication
 #[allow(dead_code)]
 pub struct VehicleManager {
     /// DDS Manager instance
     dds_manager: dds::DdsManager,
+    /// SOME/IP signal source
+    someip_source: someip::SomeIpSource,
+    /// Transport selection per topic
+    signal_settings: SignalSettings,
+    /// Data type name of each subscribed topic
+    data_types: HashMap<String, String>,
+    /// Topics subscribed over gRPC
+    subscriptions: HashMap<String, Subscription>,
+    /// Number of scenario and policy conditions using each topic
+    scenario_users: HashMap<String, usize>,
 }
 #[allow(dead_code)]
 impl VehicleManager {
@@ -26,13 +45,19 @@ impl VehicleManager {
     /// A new VehicleManager instance
     pub fn new(tx: Sender<DdsData>) -> Self {
         Self {
-            dds_manager: dds::DdsManager::new(tx),
+            dds_manager: dds::DdsManager::new(tx.clone()),
+            someip_source: someip::SomeIpSource::new(Default::default(), tx),
+            signal_settings: SignalSettings::default(),
+            data_types: HashMap::new(),
+            subscriptions: HashMap::new(),
+            scenario_users: HashMap::new(),
         }
     }
 
     /// Initializes the vehicle data system
     ///
-    /// Sets up the DDS system and prepares for topic subscriptions
+    /// Sets up the DDS system, reads the transport of each topic and
+    /// prepares for topic subscriptions
     ///
     /// # Returns
     ///
@@ -47,11 +72,30 @@ impl VehicleManager {
                 self.set_domain_id(100); // Set default domain ID
             }
         }
+
+        match SignalSettings::load(None) {
+            Ok(settings) => self.signal_settings = settings,
+            Err(e) => {
+                logd!(
+                    5,
+                    "Failed to read signal settings: {}. Using DDS for all topics.",
+                    e
+                );
+                self.signal_settings = SignalSettings::default();
+            }
+        }
+        self.someip_source = someip::SomeIpSource::new(
+            self.signal_settings.someip.clone(),
+            self.dds_manager.get_sender(),
+        );
         Ok(())
     }
 
     /// Subscribes to a vehicle data topic
     ///
+    /// Every condition using the topic subscribes to it, the listener is
+    /// created for the first one.
+    ///
     /// # Arguments
     ///
     /// * `topic_name` - Name of the topic to subscribe to
@@ -68,16 +112,46 @@ impl VehicleManager {
         use std::time::Instant;
         let start = Instant::now();
 
-        self.dds_manager
-            .create_typed_listener(topic_name, data_type_name)
-            .await?;
+        let users = self.scenario_users.entry(topic_name.clone()).or_insert(0);
+        *users += 1;
+        // The listener of a topic subscribed over gRPC is shared and keeps its QoS
+        if *users > 1 || self.subscriptions.contains_key(&topic_name) {
+            return Ok(());
+        }
+        self.data_types
+            .insert(topic_name.clone(), data_type_name.clone());
+        let source = self.source_for(&topic_name);
+        let protocol = source.protocol();
+        if let Err(e) = source.subscribe(topic_name.clone(), data_type_name).await {
+            self.scenario_users.remove(&topic_name);
+            self.data_types.remove(&topic_name);
+            return Err(e);
+        }
 
         let elapsed = start.elapsed();
-        logd!(1, "subscribe_topic: elapsed = {:?}", elapsed);
+        logd!(
+            1,
+            "subscribe_topic ({:?}): elapsed = {:?}",
+            protocol,
+            elapsed
+        );
 
         Ok(())
     }
 
+    /// Data type name a topic was subscribed with, the topic name if unknown
+    pub fn data_type_of(&self, topic_name: &str) -> String {
+        self.data_types
+            .get(topic_name)
+            .cloned()
+            .unwrap_or_else(|| topic_name.to_string())
+    }
+
+    /// Trace file of received samples, if recording is configured
+    pub fn record_path(&self) -> Option<PathBuf> {
+        self.signal_settings.record.clone()
+    }
+
     /// Get list of available DDS types
     pub fn list_available_types(&self) -> Vec<String> {
         self.dds_manager.list_available_types()
@@ -85,6 +159,9 @@ impl VehicleManager {
 
     /// Unsubscribes from a vehicle data topic
     ///
+    /// The listener is removed once no condition uses the topic anymore,
+    /// unless it was also subscribed over gRPC.
+    ///
     /// # Arguments
     ///
     /// * `topic_name` - Name of the topic to unsubscribe from
@@ -93,11 +170,194 @@ impl VehicleManager {
     ///
     /// * `Result<()>` - Success or error result
     pub async fn unsubscribe_topic(&mut self, topic_name: String) -> Result<()> {
-        // TODO: Implementation
-        self.dds_manager.remove_listener(&topic_name).await?;
+        if let Some(users) = self.scenario_users.get_mut(&topic_name) {
+            *users -= 1;
+            if *users > 0 {
+                return Ok(());
+            }
+            self.scenario_users.remove(&topic_name);
+        }
+        if self.subscriptions.contains_key(&topic_name) {
+            return Ok(());
+        }
+        self.data_types.remove(&topic_name);
+        self.source_for(&topic_name)
+            .unsubscribe(&topic_name)
+            .await?;
+        Ok(())
+    }
+
+    /// Topics listened to, sorted by name
+    ///
+    /// Topics of scenario and policy conditions are listed with the default
+    /// domain and QoS.
+    pub fn list_topics(&self) -> Vec<(Subscription, TopicOrigin)> {
+        let mut topics: Vec<_> = self
+            .data_types
+            .iter()
+            .filter(|(topic, _)| !self.subscriptions.contains_key(*topic))
+            .map(|(topic, type_name)| {
+                let subscription = Subscription {
+                    topic: topic.clone(),
+                    type_name: type_name.clone(),
+                    domain_id: self.dds_manager.domain_id(),
+                    qos: Default::default(),
+                    paused: false,
+                };
+                (subscription, TopicOrigin::Scenario)
+            })
+            .chain(
+                self.subscriptions
+                    .values()
+                    .map(|subscription| (subscription.clone(), TopicOrigin::Api)),
+            )
+            .collect();
+        topics.sort_by(|a, b| a.0.topic.cmp(&b.0.topic));
+        topics
+    }
+
+    /// Subscribe to a DDS topic requested over gRPC
+    ///
+    /// The type must be built in or registered at runtime. A paused
+    /// subscription is only recorded, it is listened to once resumed.
+    pub async fn add_subscription(
+        &mut self,
+        subscription: Subscription,
+    ) -> std::result::Result<(), SubscriptionError> {
+        let topic = subscription.topic.clone();
+        if topic.is_empty() {
+            return Err(SubscriptionError::Invalid(
+                "Topic name must not be empty".to_string(),
+            ));
+        }
+        if !self
+            .list_available_types()
+            .contains(&subscription.type_name)
+        {
+            return Err(SubscriptionError::Invalid(format!(
+                "DDS type '{}' is not registered",
+                subscription.type_name
+            )));
+        }
+        if self.signal_settings.protocol_for(&topic) != SignalProtocol::Dds {
+            return Err(SubscriptionError::Invalid(format!(
+                "Topic '{}' is not received over DDS",
+                topic
+            )));
+        }
+        if self.subscriptions.contains_key(&topic) || self.dds_manager.has_listener(&topic) {
+            return Err(SubscriptionError::AlreadySubscribed(topic));
+        }
+
+        if !subscription.paused {
+            self.start_listener(&subscription).await?;
+        }
+        self.data_types
+            .insert(topic.clone(), subscription.type_name.clone());
+        self.subscriptions.insert(topic, subscription);
         Ok(())
     }
 
+    /// Stop listening to a topic subscribed over gRPC, keeping the subscription
+    pub async fn pause_topic(
+        &mut self,
+        topic_name: &str,
+    ) -> std::result::Result<Subscription, SubscriptionError> {
+        let subscription = self.managed_subscription(topic_name)?;
+        if !subscription.paused {
+            self.dds_manager
+                .remove_listener(topic_name)
+                .await
+                .map_err(|e| SubscriptionError::Failed(e.to_string()))?;
+        }
+        let subscription = self.subscriptions.get_mut(topic_name).unwrap();
+        subscription.paused = true;
+        Ok(subscription.clone())
+    }
+
+    /// Listen again to a paused topic
+    pub async fn resume_topic(
+        &mut self,
+        topic_name: &str,
+    ) -> std::result::Result<Subscription, SubscriptionError> {
+        let subscription = self.managed_subscription(topic_name)?;
+        if subscription.paused {
+            self.start_listener(&subscription).await?;
+        }
+        let subscription = self.subscriptions.get_mut(topic_name).unwrap();
+        subscription.paused = false;
+        Ok(subscription.clone())
+    }
+
+    /// Unsubscribe from a topic subscribed over gRPC
+    ///
+    /// A topic still used by a condition keeps its listener and is listed as
+    /// a scenario topic again.
+    pub async fn remove_subscription(
+        &mut self,
+        topic_name: &str,
+    ) -> std::result::Result<Subscription, SubscriptionError> {
+        let subscription = self.managed_subscription(topic_name)?;
+        if self.scenario_users.contains_key(topic_name) {
+            if subscription.paused {
+                self.dds_manager
+                    .subscribe(topic_name.to_string(), subscription.type_name.clone())
+                    .await
+                    .map_err(|e| SubscriptionError::Failed(e.to_string()))?;
+            }
+        } else {
+            self.dds_manager
+                .remove_listener(topic_name)
+                .await
+                .map_err(|e| SubscriptionError::Failed(e.to_string()))?;
+            self.data_types.remove(topic_name);
+        }
+        self.subscriptions.remove(topic_name);
+        Ok(subscription)
+    }
+
+    /// Subscription of a topic, if it was subscribed over gRPC
+    fn managed_subscription(
+        &self,
+        topic_name: &str,
+    ) -> std::result::Result<Subscription, SubscriptionError> {
+        match self.subscriptions.get(topic_name) {
+            Some(subscription) => Ok(subscription.clone()),
+            None if self.data_types.contains_key(topic_name) => {
+                Err(SubscriptionError::NotManaged(topic_name.to_string()))
+            }
+            None => Err(SubscriptionError::NotFound(topic_name.to_string())),
+        }
+    }
+
+    async fn start_listener(
+        &mut self,
+        subscription: &Subscription,
+    ) -> std::result::Result<(), SubscriptionError> {
+        self.dds_manager
+            .create_listener_with_qos(
+                subscription.topic.clone(),
+                subscription.type_name.clone(),
+                subscription.domain_id,
+                subscription.qos.reader_qos(),
+            )
+            .await
+            .map_err(|e| SubscriptionError::Failed(e.to_string()))
+    }
+
+    /// DDS domain ID of topics subscribed without one
+    pub fn domain_id(&self) -> i32 {
+        self.dds_manager.domain_id()
+    }
+
+    /// Signal source configured for a topic
+    fn source_for(&mut self, topic_name: &str) -> &mut dyn SignalSource {
+        match self.signal_settings.protocol_for(topic_name) {
+            SignalProtocol::Dds => &mut self.dds_manager,
+            SignalProtocol::SomeIp => &mut self.someip_source,
+        }
+    }
+
     /// Gets the DDS data sender
     ///
     /// # Returns
@@ -145,6 +405,8 @@ mod tests {
             .subscribe_topic("vehicle_data".to_string(), "VehicleType".to_string())
             .await;
         assert!(result.is_ok());
+        assert_eq!(vehicle_manager.data_type_of("vehicle_data"), "VehicleType");
+        assert_eq!(vehicle_manager.data_type_of("other"), "other");
     }
 
     #[tokio::test] // Test unsubscribing from a topic successfully
@@ -172,4 +434,135 @@ mod tests {
         vehicle_manager.set_domain_id(200);
         assert!(true); // Placeholder assertion for domain ID setting
     }
+
+    #[tokio::test] // Test that topics are routed to the configured signal source
+    async fn test_vehicle_manager_source_per_topic() {
+        let (tx, _rx) = mpsc::channel(10);
+        let mut vehicle_manager = VehicleManager::new(tx);
+        vehicle_manager.signal_settings =
+            SignalSettings::parse("signals:\n  topics:\n    speed: someip\n").unwrap();
+
+        assert_eq!(
+            vehicle_manager.source_for("speed").protocol(),
+            SignalProtocol::SomeIp
+        );
+        assert_eq!(
+            vehicle_manager.source_for("gear").protocol(),
+            SignalProtocol::Dds
+        );
+        // No SOME/IP binding is configured for the topic
+        let result = vehicle_manager
+            .subscribe_topic("speed".to_string(), "Speed".to_string())
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test] // Test managing a topic subscribed over gRPC
+    async fn test_vehicle_manager_subscriptions() {
+        let (tx, _rx) = mpsc::channel(10);
+        let mut vehicle_manager = VehicleManager::new(tx);
+        let type_name = dds::dynamic::register_idl("struct WiperSpeed { long value; };").unwrap();
+        let subscription = Subscription {
+            topic: "wiper".to_string(),
+            type_name,
+            domain_id: 100,
+            qos: Default::default(),
+            paused: false,
+        };
+
+        vehicle_manager
+            .add_subscription(subscription.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            vehicle_manager.add_subscription(subscription).await,
+            Err(SubscriptionError::AlreadySubscribed("wiper".to_string()))
+        );
+        assert!(vehicle_manager.dds_manager.has_listener("wiper"));
+
+        assert!(vehicle_manager.pause_topic("wiper").await.unwrap().paused);
+        assert!(!vehicle_manager.dds_manager.has_listener("wiper"));
+        assert!(!vehicle_manager.resume_topic("wiper").await.unwrap().paused);
+        assert!(vehicle_manager.dds_manager.has_listener("wiper"));
+
+        vehicle_manager.remove_subscription("wiper").await.unwrap();
+        assert!(!vehicle_manager.dds_manager.has_listener("wiper"));
+        assert_eq!(
+            vehicle_manager.pause_topic("wiper").await,
+            Err(SubscriptionError::NotFound("wiper".to_string()))
+        );
+    }
+
+    #[tokio::test] // Test a topic used by a subscription over gRPC and conditions
+    async fn test_vehicle_manager_topic_shared_with_scenarios() {
+        let (tx, _rx) = mpsc::channel(10);
+        let mut vehicle_manager = VehicleManager::new(tx);
+        let type_name = dds::dynamic::register_idl("struct DoorState { long value; };").unwrap();
+        vehicle_manager
+            .add_subscription(Subscription {
+                topic: "door".to_string(),
+                type_name: type_name.clone(),
+                domain_id: 100,
+                qos: Default::default(),
+                paused: false,
+            })
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            vehicle_manager
+                .subscribe_topic("door".to_string(), type_name.clone())
+                .await
+                .unwrap();
+        }
+
+        // The conditions keep listening once the gRPC subscription is gone
+        vehicle_manager.remove_subscription("door").await.unwrap();
+        assert!(vehicle_manager.dds_manager.has_listener("door"));
+        let topics = vehicle_manager.list_topics();
+        assert_eq!(topics.len(), 1);
+        assert_eq!(topics[0].1, TopicOrigin::Scenario);
+        assert_eq!(vehicle_manager.data_type_of("door"), type_name);
+
+        vehicle_manager
+            .unsubscribe_topic("door".to_string())
+            .await
+            .unwrap();
+        assert!(vehicle_manager.dds_manager.has_listener("door"));
+        vehicle_manager
+            .unsubscribe_topic("door".to_string())
+            .await
+            .unwrap();
+        assert!(!vehicle_manager.dds_manager.has_listener("door"));
+        assert!(vehicle_manager.list_topics().is_empty());
+    }
+
+    #[tokio::test] // Test that topics of conditions cannot be changed over gRPC
+    async fn test_vehicle_manager_condition_topics() {
+        let (tx, _rx) = mpsc::channel(10);
+        let mut vehicle_manager = VehicleManager::new(tx);
+        vehicle_manager
+            .subscribe_topic("gear".to_string(), "gear".to_string())
+            .await
+            .unwrap();
+
+        let topics = vehicle_manager.list_topics();
+        assert_eq!(topics.len(), 1);
+        assert_eq!(topics[0].0.topic, "gear");
+        assert_eq!(topics[0].1, TopicOrigin::Scenario);
+        assert_eq!(
+            vehicle_manager.remove_subscription("gear").await,
+            Err(SubscriptionError::NotManaged("gear".to_string()))
+        );
+
+        let result = vehicle_manager
+            .add_subscription(Subscription {
+                topic: "speed".to_string(),
+                type_name: "NoSuchType".to_string(),
+                domain_id: 100,
+                qos: Default::default(),
+                paused: false,
+            })
+            .await;
+        assert!(matches!(result, Err(SubscriptionError::Invalid(_))));
+    }
 }
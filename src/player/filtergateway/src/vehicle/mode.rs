@@ -0,0 +1,357 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Vehicle mode tracked from DDS signals
+//!
+//! The ignition and motion of the vehicle are derived from the signals named
+//! in the `vehicle_mode` section of the settings file and served to the other
+//! modules with `GetVehicleMode`, which gate scenarios with `allowedModes` on
+//! them:
+//!
+//! ```yaml
+//! vehicle_mode:
+//!   ignition:
+//!     topic: /rt/vehicle/ignition
+//!     field: value
+//!     off: ["off", "0"]
+//!   gear:
+//!     topic: /rt/vehicle/gear
+//!     field: value
+//!     parked: ["P"]
+//!   speed:
+//!     topic: /rt/vehicle/speed
+//!     field: value
+//!     max_parked: 0.5
+//! ```
+//!
+//! The vehicle is parked while the gear is a parked one and the speed is at
+//! most `max_parked`; either signal may be left out. Modes stay unknown
+//! until their signals are received.
+use super::dds::DdsData;
+use common::filtergateway::{Ignition, Motion, VehicleMode};
+use common::Result;
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+
+static TRACKER: OnceLock<VehicleModeTracker> = OnceLock::new();
+
+/// Signal telling whether the ignition is off
+#[derive(Debug, Clone, Deserialize)]
+pub struct IgnitionSignal {
+    pub topic: String,
+    #[serde(default = "default_field")]
+    pub field: String,
+    /// Values meaning off, compared case-insensitively; any other is on
+    #[serde(default = "default_ignition_off")]
+    pub off: Vec<String>,
+}
+
+/// Signal telling whether the gear is a parked one
+#[derive(Debug, Clone, Deserialize)]
+pub struct GearSignal {
+    pub topic: String,
+    #[serde(default = "default_field")]
+    pub field: String,
+    /// Values meaning parked, compared case-insensitively
+    #[serde(default = "default_gear_parked")]
+    pub parked: Vec<String>,
+}
+
+/// Signal of the vehicle speed
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpeedSignal {
+    pub topic: String,
+    #[serde(default = "default_field")]
+    pub field: String,
+    /// Highest speed at which the vehicle counts as parked
+    #[serde(default)]
+    pub max_parked: f64,
+}
+
+fn default_field() -> String {
+    "value".to_string()
+}
+
+fn default_ignition_off() -> Vec<String> {
+    vec!["off".to_string(), "0".to_string(), "false".to_string()]
+}
+
+fn default_gear_parked() -> Vec<String> {
+    vec!["P".to_string()]
+}
+
+/// `vehicle_mode` section of the settings file
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ModeSettings {
+    #[serde(default)]
+    pub ignition: Option<IgnitionSignal>,
+    #[serde(default)]
+    pub gear: Option<GearSignal>,
+    #[serde(default)]
+    pub speed: Option<SpeedSignal>,
+}
+
+impl ModeSettings {
+    /// Read the `vehicle_mode` section, defaults if the file or section is missing
+    pub fn load(settings_path: Option<PathBuf>) -> Result<Self> {
+        let settings_path = settings_path.unwrap_or_else(|| {
+            std::env::var("PULLPIRI_SETTINGS_PATH")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| PathBuf::from("/etc/pullpiri/settings.yaml"))
+        });
+        let content = match std::fs::read_to_string(&settings_path) {
+            Ok(content) => content,
+            Err(_) => return Ok(Self::default()),
+        };
+        Self::parse(&content)
+    }
+
+    /// Parse the `vehicle_mode` section of a YAML or JSON settings document
+    pub fn parse(content: &str) -> Result<Self> {
+        let document: serde_yaml::Value = serde_yaml::from_str(content)?;
+        match document.get("vehicle_mode") {
+            Some(section) => Ok(serde_yaml::from_value(section.clone())?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Topics of the configured signals, sorted
+    pub fn topics(&self) -> Vec<String> {
+        let mut topics: Vec<String> = self
+            .ignition
+            .iter()
+            .map(|s| s.topic.clone())
+            .chain(self.gear.iter().map(|s| s.topic.clone()))
+            .chain(self.speed.iter().map(|s| s.topic.clone()))
+            .collect();
+        topics.sort();
+        topics.dedup();
+        topics
+    }
+}
+
+/// Latest values of the mode signals
+#[derive(Debug, Default)]
+struct Signals {
+    ignition: Ignition,
+    gear_parked: Option<bool>,
+    speed_parked: Option<bool>,
+    mode: VehicleMode,
+}
+
+/// Tracks the vehicle mode, clones share the same state
+#[derive(Clone, Default)]
+pub struct VehicleModeTracker {
+    settings: Arc<ModeSettings>,
+    signals: Arc<Mutex<Signals>>,
+}
+
+impl VehicleModeTracker {
+    pub fn new(settings: ModeSettings) -> Self {
+        Self {
+            settings: Arc::new(settings),
+            signals: Arc::default(),
+        }
+    }
+
+    /// Topics the tracker needs to be subscribed to
+    pub fn topics(&self) -> Vec<String> {
+        self.settings.topics()
+    }
+
+    /// Current mode
+    pub fn current(&self) -> VehicleMode {
+        lock(&self.signals).mode
+    }
+
+    /// Update the mode from a received sample
+    ///
+    /// ### Returns
+    /// * `Option<VehicleMode>` - the new mode if it changed
+    pub fn update(&self, data: &DdsData) -> Option<VehicleMode> {
+        let mut signals = lock(&self.signals);
+        if let Some(ignition) = &self.settings.ignition {
+            if let Some(value) = field_value(ignition, data) {
+                signals.ignition = if contains(&ignition.off, value) {
+                    Ignition::Off
+                } else {
+                    Ignition::On
+                };
+            }
+        }
+        if let Some(gear) = &self.settings.gear {
+            if let Some(value) = field_value(gear, data) {
+                signals.gear_parked = Some(contains(&gear.parked, value));
+            }
+        }
+        if let Some(speed) = &self.settings.speed {
+            if let Some(value) = field_value(speed, data) {
+                if let Ok(value) = value.trim().parse::<f64>() {
+                    signals.speed_parked = Some(value.abs() <= speed.max_parked);
+                }
+            }
+        }
+
+        let motion = motion(signals.gear_parked, signals.speed_parked);
+        if signals.mode.ignition() == signals.ignition && signals.mode.motion() == motion {
+            return None;
+        }
+        signals.mode = VehicleMode {
+            ignition: signals.ignition as i32,
+            motion: motion as i32,
+            changed_ns: chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default(),
+        };
+        Some(signals.mode)
+    }
+}
+
+/// Topic and field of a mode signal
+trait ModeSignal {
+    fn topic(&self) -> &str;
+    fn field(&self) -> &str;
+}
+
+impl ModeSignal for IgnitionSignal {
+    fn topic(&self) -> &str {
+        &self.topic
+    }
+    fn field(&self) -> &str {
+        &self.field
+    }
+}
+
+impl ModeSignal for GearSignal {
+    fn topic(&self) -> &str {
+        &self.topic
+    }
+    fn field(&self) -> &str {
+        &self.field
+    }
+}
+
+impl ModeSignal for SpeedSignal {
+    fn topic(&self) -> &str {
+        &self.topic
+    }
+    fn field(&self) -> &str {
+        &self.field
+    }
+}
+
+/// Value of the signal in a sample, if the sample is of its topic
+fn field_value<'a>(signal: &impl ModeSignal, data: &'a DdsData) -> Option<&'a str> {
+    if data.name != signal.topic() {
+        return None;
+    }
+    data.fields.get(signal.field()).map(String::as_str)
+}
+
+fn contains(values: &[String], value: &str) -> bool {
+    values.iter().any(|v| v.eq_ignore_ascii_case(value.trim()))
+}
+
+/// Motion from the gear and speed, a moving vehicle is driving in any gear
+fn motion(gear_parked: Option<bool>, speed_parked: Option<bool>) -> Motion {
+    match (gear_parked, speed_parked) {
+        (Some(false), _) | (_, Some(false)) => Motion::Driving,
+        (Some(true), _) | (None, Some(true)) => Motion::Parked,
+        (None, None) => Motion::Unknown,
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// Make the tracker of the manager available to the gRPC receiver
+pub fn register_tracker(tracker: VehicleModeTracker) {
+    if TRACKER.set(tracker).is_err() {
+        common::logd!(4, "Vehicle mode tracker is already registered");
+    }
+}
+
+/// Current vehicle mode, unknown if FilterGateway is not started
+pub fn current() -> VehicleMode {
+    TRACKER
+        .get()
+        .map(VehicleModeTracker::current)
+        .unwrap_or_default()
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn sample(topic: &str, value: &str) -> DdsData {
+        DdsData {
+            name: topic.to_string(),
+            value: value.to_string(),
+            fields: HashMap::from([("value".to_string(), value.to_string())]),
+        }
+    }
+
+    fn tracker() -> VehicleModeTracker {
+        VehicleModeTracker::new(
+            ModeSettings::parse(
+                r#"
+vehicle_mode:
+  ignition:
+    topic: ignition
+  gear:
+    topic: gear
+    parked: ["P", "park"]
+  speed:
+    topic: speed
+    max_parked: 0.5
+"#,
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_settings() {
+        let settings = tracker().settings;
+        assert_eq!(settings.topics(), vec!["gear", "ignition", "speed"]);
+        assert_eq!(
+            settings.ignition.as_ref().unwrap().off,
+            default_ignition_off()
+        );
+        assert!(ModeSettings::parse("host:\n  name: HPC\n")
+            .unwrap()
+            .topics()
+            .is_empty());
+        assert!(ModeSettings::parse("vehicle_mode:\n  gear:\n    field: value\n").is_err());
+    }
+
+    #[test]
+    fn test_update_tracks_ignition_and_motion() {
+        let tracker = tracker();
+        assert_eq!(tracker.current(), VehicleMode::default());
+        assert!(tracker.update(&sample("other", "P")).is_none());
+
+        let mode = tracker.update(&sample("ignition", "OFF")).unwrap();
+        assert_eq!(mode.ignition(), Ignition::Off);
+        assert_eq!(mode.motion(), Motion::Unknown);
+        let mode = tracker.update(&sample("gear", "park")).unwrap();
+        assert_eq!(mode.motion(), Motion::Parked);
+        assert!(tracker.update(&sample("speed", "0.2")).is_none());
+
+        // Rolling in a parked gear still counts as driving
+        let mode = tracker.update(&sample("speed", "3.0")).unwrap();
+        assert_eq!(mode.motion(), Motion::Driving);
+        tracker.update(&sample("speed", "0"));
+        tracker.update(&sample("ignition", "on"));
+        let mode = tracker.update(&sample("gear", "D")).unwrap();
+        assert_eq!(mode.ignition(), Ignition::On);
+        assert_eq!(mode.motion(), Motion::Driving);
+        assert_eq!(tracker.current(), mode);
+    }
+}
@@ -0,0 +1,121 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Recording of vehicle data
+//!
+//! With `signals.record` set in the settings file, every sample received by
+//! the FilterGateway is appended to that file as one JSON line:
+//!
+//! ```text
+//! {"timestamp_ms":1760000000000,"topic":"speed","data_type":"speed","value":"{\"value\":80}","fields":{"value":"80"}}
+//! ```
+//!
+//! The `filtergateway-replay` tool feeds such a trace back through the
+//! condition evaluator and reports the scenarios that would have triggered,
+//! without sending anything to ActionController.
+use super::dds::DdsData;
+use common::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{LineWriter, Write};
+use std::path::Path;
+
+/// One received vehicle data sample
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedSample {
+    /// Reception time in milliseconds since the Unix epoch
+    pub timestamp_ms: i64,
+    pub topic: String,
+    pub data_type: String,
+    /// Payload as delivered by the signal source
+    pub value: String,
+    #[serde(default)]
+    pub fields: HashMap<String, String>,
+}
+
+impl RecordedSample {
+    pub fn new(data: &DdsData, data_type: &str, timestamp_ms: i64) -> Self {
+        Self {
+            timestamp_ms,
+            topic: data.name.clone(),
+            data_type: data_type.to_string(),
+            value: data.value.clone(),
+            fields: data.fields.clone(),
+        }
+    }
+}
+
+/// Appends received samples to a trace file
+pub struct Recorder {
+    file: LineWriter<File>,
+}
+
+impl Recorder {
+    /// Open the trace file for appending, creating it if needed
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: LineWriter::new(file),
+        })
+    }
+
+    /// Record a sample received now
+    pub fn record(&mut self, data: &DdsData, data_type: &str) -> Result<()> {
+        let sample = RecordedSample::new(data, data_type, chrono::Utc::now().timestamp_millis());
+        self.write(&sample)
+    }
+
+    /// Append a sample to the trace
+    pub fn write(&mut self, sample: &RecordedSample) -> Result<()> {
+        let line = serde_json::to_string(sample)?;
+        writeln!(self.file, "{}", line)?;
+        Ok(())
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_appends_json_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("traces/drive.jsonl");
+        let data = DdsData {
+            name: "speed".to_string(),
+            value: "{\"value\":80}".to_string(),
+            fields: HashMap::from([("value".to_string(), "80".to_string())]),
+        };
+        let gear = RecordedSample {
+            timestamp_ms: 5,
+            topic: "gear".to_string(),
+            data_type: "gear".to_string(),
+            value: "{\"gear\":\"P\"}".to_string(),
+            fields: HashMap::from([("gear".to_string(), "P".to_string())]),
+        };
+
+        let mut recorder = Recorder::open(&path).unwrap();
+        recorder.record(&data, "VehicleSpeed").unwrap();
+        recorder.write(&gear).unwrap();
+        drop(recorder);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let samples: Vec<RecordedSample> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].topic, "speed");
+        assert_eq!(samples[0].data_type, "VehicleSpeed");
+        assert_eq!(samples[0].fields["value"], "80");
+        assert_eq!(samples[1], gear);
+    }
+}
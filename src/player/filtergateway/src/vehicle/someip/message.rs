@@ -0,0 +1,315 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! SOME/IP and SOME/IP-SD wire format
+//!
+//! Only what a signal consumer needs: the SOME/IP header, decoding of event
+//! payloads and SubscribeEventgroup entries with an IPv4 endpoint option.
+//! All values are big-endian.
+use super::{FieldType, PayloadField};
+use anyhow::anyhow;
+use common::Result;
+use std::collections::HashMap;
+use std::net::SocketAddrV4;
+
+/// Length of the SOME/IP header
+pub const HEADER_LEN: usize = 16;
+/// Message ID of SOME/IP-SD messages
+const SD_SERVICE_ID: u16 = 0xFFFF;
+const SD_METHOD_ID: u16 = 0x8100;
+const PROTOCOL_VERSION: u8 = 0x01;
+const MESSAGE_TYPE_NOTIFICATION: u8 = 0x02;
+const ENTRY_SUBSCRIBE_EVENTGROUP: u8 = 0x06;
+const OPTION_IPV4_ENDPOINT: u8 = 0x04;
+const L4_PROTO_UDP: u8 = 0x11;
+/// SD flags: reboot and unicast supported
+const SD_FLAGS: u8 = 0xC0;
+
+/// Header of a SOME/IP message
+#[derive(Debug, Clone, PartialEq)]
+pub struct Header {
+    pub service_id: u16,
+    pub method_id: u16,
+    /// Payload length plus the 8 bytes after the length field
+    pub length: u32,
+    pub client_id: u16,
+    pub session_id: u16,
+    pub interface_version: u8,
+    pub message_type: u8,
+    pub return_code: u8,
+}
+
+impl Header {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < HEADER_LEN {
+            return Err(anyhow!("SOME/IP message too short: {} bytes", data.len()).into());
+        }
+        if data[12] != PROTOCOL_VERSION {
+            return Err(anyhow!("Unsupported SOME/IP protocol version {}", data[12]).into());
+        }
+        Ok(Self {
+            service_id: u16::from_be_bytes([data[0], data[1]]),
+            method_id: u16::from_be_bytes([data[2], data[3]]),
+            length: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+            client_id: u16::from_be_bytes([data[8], data[9]]),
+            session_id: u16::from_be_bytes([data[10], data[11]]),
+            interface_version: data[13],
+            message_type: data[14],
+            return_code: data[15],
+        })
+    }
+
+    pub fn is_notification(&self) -> bool {
+        // The TP flag (0x20) marks segmented notifications
+        self.message_type & !0x20 == MESSAGE_TYPE_NOTIFICATION
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.service_id.to_be_bytes());
+        out.extend_from_slice(&self.method_id.to_be_bytes());
+        out.extend_from_slice(&self.length.to_be_bytes());
+        out.extend_from_slice(&self.client_id.to_be_bytes());
+        out.extend_from_slice(&self.session_id.to_be_bytes());
+        out.push(PROTOCOL_VERSION);
+        out.push(self.interface_version);
+        out.push(self.message_type);
+        out.push(self.return_code);
+    }
+}
+
+/// Payload of a message whose header was parsed from `data`
+pub fn payload<'a>(header: &Header, data: &'a [u8]) -> Result<&'a [u8]> {
+    let end = 8 + header.length as usize;
+    if header.length < 8 || data.len() < end {
+        return Err(anyhow!(
+            "SOME/IP length {} does not match {} received bytes",
+            header.length,
+            data.len()
+        )
+        .into());
+    }
+    Ok(&data[HEADER_LEN..end])
+}
+
+/// Encode a SOME/IP message
+pub fn encode(header: &Header, payload: &[u8]) -> Vec<u8> {
+    let header = Header {
+        length: payload.len() as u32 + 8,
+        ..header.clone()
+    };
+    let mut out = Vec::with_capacity(HEADER_LEN + payload.len());
+    header.encode(&mut out);
+    out.extend_from_slice(payload);
+    out
+}
+
+/// Decode an event payload into named fields
+///
+/// Fields are read in order; `string` takes the rest of the payload.
+pub fn decode_fields(fields: &[PayloadField], payload: &[u8]) -> Result<HashMap<String, String>> {
+    let mut values = HashMap::new();
+    let mut offset = 0;
+    for field in fields {
+        let size = match field.r#type {
+            FieldType::Bool | FieldType::U8 | FieldType::I8 => 1,
+            FieldType::U16 | FieldType::I16 => 2,
+            FieldType::U32 | FieldType::I32 | FieldType::F32 => 4,
+            FieldType::U64 | FieldType::I64 | FieldType::F64 => 8,
+            FieldType::String => payload.len().saturating_sub(offset),
+        };
+        let bytes = payload.get(offset..offset + size).ok_or_else(|| {
+            anyhow!(
+                "Payload of {} bytes ends before field '{}'",
+                payload.len(),
+                field.name
+            )
+        })?;
+        offset += size;
+
+        let value = match field.r#type {
+            FieldType::Bool => (bytes[0] != 0).to_string(),
+            FieldType::U8 => bytes[0].to_string(),
+            FieldType::I8 => (bytes[0] as i8).to_string(),
+            FieldType::U16 => u16::from_be_bytes(bytes.try_into()?).to_string(),
+            FieldType::I16 => i16::from_be_bytes(bytes.try_into()?).to_string(),
+            FieldType::U32 => u32::from_be_bytes(bytes.try_into()?).to_string(),
+            FieldType::I32 => i32::from_be_bytes(bytes.try_into()?).to_string(),
+            FieldType::U64 => u64::from_be_bytes(bytes.try_into()?).to_string(),
+            FieldType::I64 => i64::from_be_bytes(bytes.try_into()?).to_string(),
+            FieldType::F32 => f32::from_be_bytes(bytes.try_into()?).to_string(),
+            FieldType::F64 => f64::from_be_bytes(bytes.try_into()?).to_string(),
+            FieldType::String => String::from_utf8_lossy(bytes)
+                .trim_end_matches('\0')
+                .to_string(),
+        };
+        values.insert(field.name.clone(), value);
+    }
+    Ok(values)
+}
+
+/// SubscribeEventgroup entry of SOME/IP-SD
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subscription {
+    pub service_id: u16,
+    pub instance_id: u16,
+    pub major_version: u8,
+    pub eventgroup_id: u16,
+    /// Lifetime in seconds, 0 stops the subscription
+    pub ttl: u32,
+    /// Where the provider sends the events
+    pub endpoint: SocketAddrV4,
+}
+
+impl Subscription {
+    /// Encode as SOME/IP-SD message
+    pub fn encode(&self, session_id: u16) -> Vec<u8> {
+        let mut sd = vec![SD_FLAGS, 0, 0, 0];
+
+        let mut entry = vec![ENTRY_SUBSCRIBE_EVENTGROUP, 0, 0, 0x10];
+        entry.extend_from_slice(&self.service_id.to_be_bytes());
+        entry.extend_from_slice(&self.instance_id.to_be_bytes());
+        entry.push(self.major_version);
+        entry.extend_from_slice(&self.ttl.min(0x00FF_FFFF).to_be_bytes()[1..]);
+        entry.extend_from_slice(&[0, 0]);
+        entry.extend_from_slice(&self.eventgroup_id.to_be_bytes());
+        sd.extend_from_slice(&(entry.len() as u32).to_be_bytes());
+        sd.extend_from_slice(&entry);
+
+        let mut option = vec![0x00, 0x09, OPTION_IPV4_ENDPOINT, 0];
+        option.extend_from_slice(&self.endpoint.ip().octets());
+        option.extend_from_slice(&[0, L4_PROTO_UDP]);
+        option.extend_from_slice(&self.endpoint.port().to_be_bytes());
+        sd.extend_from_slice(&(option.len() as u32).to_be_bytes());
+        sd.extend_from_slice(&option);
+
+        let header = Header {
+            service_id: SD_SERVICE_ID,
+            method_id: SD_METHOD_ID,
+            length: 0,
+            client_id: 0,
+            session_id,
+            interface_version: 0x01,
+            message_type: MESSAGE_TYPE_NOTIFICATION,
+            return_code: 0,
+        };
+        encode(&header, &sd)
+    }
+
+    /// Decode the first SubscribeEventgroup entry of a SOME/IP-SD message
+    ///
+    /// Only providers read subscriptions, so this serves the tests.
+    #[cfg(test)]
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let header = Header::parse(data)?;
+        if header.service_id != SD_SERVICE_ID || header.method_id != SD_METHOD_ID {
+            return Err(anyhow!("Not a SOME/IP-SD message").into());
+        }
+        let sd = payload(&header, data)?;
+        let read_u32 = |at: usize| -> Result<u32> {
+            let bytes = sd
+                .get(at..at + 4)
+                .ok_or_else(|| anyhow!("Truncated SOME/IP-SD message"))?;
+            Ok(u32::from_be_bytes(bytes.try_into()?))
+        };
+
+        let entries_len = read_u32(4)? as usize;
+        let entry = sd
+            .get(8..8 + 16)
+            .filter(|e| entries_len >= 16 && e[0] == ENTRY_SUBSCRIBE_EVENTGROUP)
+            .ok_or_else(|| anyhow!("No SubscribeEventgroup entry"))?;
+        let options_at = 8 + entries_len;
+        let options_len = read_u32(options_at)? as usize;
+        let option = sd
+            .get(options_at + 4..options_at + 4 + 12)
+            .filter(|o| options_len >= 12 && o[2] == OPTION_IPV4_ENDPOINT)
+            .ok_or_else(|| anyhow!("No IPv4 endpoint option"))?;
+
+        Ok(Self {
+            service_id: u16::from_be_bytes([entry[4], entry[5]]),
+            instance_id: u16::from_be_bytes([entry[6], entry[7]]),
+            major_version: entry[8],
+            ttl: u32::from_be_bytes([0, entry[9], entry[10], entry[11]]),
+            eventgroup_id: u16::from_be_bytes([entry[14], entry[15]]),
+            endpoint: SocketAddrV4::new(
+                [option[4], option[5], option[6], option[7]].into(),
+                u16::from_be_bytes([option[10], option[11]]),
+            ),
+        })
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, r#type: FieldType) -> PayloadField {
+        PayloadField {
+            name: name.to_string(),
+            r#type,
+        }
+    }
+
+    #[test]
+    fn test_header_roundtrip() {
+        let header = Header {
+            service_id: 0x1234,
+            method_id: 0x8001,
+            length: 0,
+            client_id: 0,
+            session_id: 7,
+            interface_version: 1,
+            message_type: MESSAGE_TYPE_NOTIFICATION,
+            return_code: 0,
+        };
+        let data = encode(&header, &[1, 2, 3]);
+        assert_eq!(data.len(), HEADER_LEN + 3);
+
+        let parsed = Header::parse(&data).unwrap();
+        assert_eq!(parsed.length, 11);
+        assert!(parsed.is_notification());
+        assert_eq!(payload(&parsed, &data).unwrap(), &[1, 2, 3]);
+
+        assert!(Header::parse(&data[..10]).is_err());
+        assert!(payload(&parsed, &data[..HEADER_LEN + 1]).is_err());
+    }
+
+    #[test]
+    fn test_decode_fields() {
+        let fields = vec![
+            field("warning", FieldType::Bool),
+            field("distance", FieldType::U16),
+            field("speed", FieldType::F32),
+            field("label", FieldType::String),
+        ];
+        let mut payload = vec![1, 0x01, 0x2C];
+        payload.extend_from_slice(&12.5f32.to_be_bytes());
+        payload.extend_from_slice(b"front\0");
+
+        let values = decode_fields(&fields, &payload).unwrap();
+        assert_eq!(values["warning"], "true");
+        assert_eq!(values["distance"], "300");
+        assert_eq!(values["speed"], "12.5");
+        assert_eq!(values["label"], "front");
+
+        assert!(decode_fields(&fields[..3], &payload[..4]).is_err());
+    }
+
+    #[test]
+    fn test_subscription_roundtrip() {
+        let subscription = Subscription {
+            service_id: 0x1234,
+            instance_id: 1,
+            major_version: 1,
+            eventgroup_id: 2,
+            ttl: 5,
+            endpoint: "192.168.10.2:40000".parse().unwrap(),
+        };
+        let data = subscription.encode(3);
+        assert_eq!(Header::parse(&data).unwrap().session_id, 3);
+        assert_eq!(Subscription::decode(&data).unwrap(), subscription);
+
+        assert!(Subscription::decode(&encode(&Header::parse(&data).unwrap(), &[])).is_err());
+    }
+}
@@ -0,0 +1,432 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! SOME/IP signal source
+//!
+//! Each subscribed topic is bound to an event of a SOME/IP service. The
+//! source subscribes to the eventgroup through SOME/IP service discovery and
+//! renews the subscription before its TTL runs out. Received notifications
+//! are decoded with the field list of the binding and sent as [`DdsData`].
+pub mod message;
+
+use super::dds::DdsData;
+use super::source::{SignalProtocol, SignalSource};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use common::logd;
+use common::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc::Sender;
+use tokio::task::JoinHandle;
+
+/// Default multicast endpoint of SOME/IP service discovery
+const DEFAULT_SD_ADDRESS: &str = "224.224.224.245:30490";
+/// Default lifetime of a subscription in seconds
+const DEFAULT_TTL_SECS: u32 = 3;
+const MAX_DATAGRAM: usize = 65_535;
+
+/// Session IDs of SD messages, 0 is not allowed
+static SD_SESSION: AtomicU16 = AtomicU16::new(1);
+
+/// `signals.someip` section of the settings file
+#[derive(Debug, Clone, Deserialize)]
+pub struct SomeIpSettings {
+    /// Address announced to providers for event delivery
+    #[serde(default = "default_unicast_address")]
+    pub unicast_address: Ipv4Addr,
+    /// Where SubscribeEventgroup messages are sent
+    #[serde(default = "default_sd_address")]
+    pub sd_address: SocketAddr,
+    /// Lifetime of a subscription, renewed at half of it
+    #[serde(default = "default_ttl")]
+    pub ttl_secs: u32,
+    /// Event binding per topic name
+    #[serde(default)]
+    pub services: HashMap<String, ServiceBinding>,
+}
+
+impl Default for SomeIpSettings {
+    fn default() -> Self {
+        Self {
+            unicast_address: default_unicast_address(),
+            sd_address: default_sd_address(),
+            ttl_secs: default_ttl(),
+            services: HashMap::new(),
+        }
+    }
+}
+
+fn default_unicast_address() -> Ipv4Addr {
+    Ipv4Addr::LOCALHOST
+}
+
+fn default_sd_address() -> SocketAddr {
+    DEFAULT_SD_ADDRESS.parse().unwrap()
+}
+
+fn default_ttl() -> u32 {
+    DEFAULT_TTL_SECS
+}
+
+/// SOME/IP event that carries a topic
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServiceBinding {
+    pub service_id: u16,
+    pub instance_id: u16,
+    #[serde(default = "default_major_version")]
+    pub major_version: u8,
+    pub eventgroup_id: u16,
+    pub event_id: u16,
+    /// Layout of the event payload
+    #[serde(default)]
+    pub fields: Vec<PayloadField>,
+}
+
+fn default_major_version() -> u8 {
+    1
+}
+
+/// One value in an event payload
+#[derive(Debug, Clone, Deserialize)]
+pub struct PayloadField {
+    pub name: String,
+    pub r#type: FieldType,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    Bool,
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    F32,
+    F64,
+    String,
+}
+
+struct ActiveSubscription {
+    task: JoinHandle<()>,
+    /// Endpoint announced to the provider, needed to stop the subscription
+    endpoint: SocketAddrV4,
+}
+
+/// Delivers vehicle signals received as SOME/IP events
+pub struct SomeIpSource {
+    settings: SomeIpSettings,
+    tx: Sender<DdsData>,
+    subscriptions: HashMap<String, ActiveSubscription>,
+}
+
+impl SomeIpSource {
+    pub fn new(settings: SomeIpSettings, tx: Sender<DdsData>) -> Self {
+        Self {
+            settings,
+            tx,
+            subscriptions: HashMap::new(),
+        }
+    }
+
+    fn subscription(
+        &self,
+        binding: &ServiceBinding,
+        endpoint: SocketAddrV4,
+        ttl: u32,
+    ) -> message::Subscription {
+        message::Subscription {
+            service_id: binding.service_id,
+            instance_id: binding.instance_id,
+            major_version: binding.major_version,
+            eventgroup_id: binding.eventgroup_id,
+            ttl,
+            endpoint,
+        }
+    }
+}
+
+#[async_trait]
+impl SignalSource for SomeIpSource {
+    fn protocol(&self) -> SignalProtocol {
+        SignalProtocol::SomeIp
+    }
+
+    async fn subscribe(&mut self, topic_name: String, data_type_name: String) -> Result<()> {
+        if self.subscriptions.contains_key(&topic_name) {
+            logd!(
+                4,
+                "SOME/IP subscription for '{}' already exists",
+                topic_name
+            );
+            return Ok(());
+        }
+        let binding = self
+            .settings
+            .services
+            .get(&topic_name)
+            .cloned()
+            .ok_or_else(|| anyhow!("No SOME/IP service binding for topic '{}'", topic_name))?;
+
+        let socket = UdpSocket::bind((self.settings.unicast_address, 0)).await?;
+        let endpoint =
+            SocketAddrV4::new(self.settings.unicast_address, socket.local_addr()?.port());
+        let subscription = self.subscription(&binding, endpoint, self.settings.ttl_secs);
+        let renew_every = Duration::from_millis(u64::from(self.settings.ttl_secs.max(1)) * 500);
+        let sd_address = self.settings.sd_address;
+        let tx = self.tx.clone();
+
+        logd!(
+            2,
+            "Subscribing SOME/IP service 0x{:04x} eventgroup 0x{:04x} for topic '{}'",
+            binding.service_id,
+            binding.eventgroup_id,
+            topic_name
+        );
+        let task = tokio::spawn(async move {
+            receive_events(
+                socket,
+                subscription,
+                sd_address,
+                renew_every,
+                binding,
+                data_type_name,
+                tx,
+            )
+            .await;
+        });
+        self.subscriptions
+            .insert(topic_name, ActiveSubscription { task, endpoint });
+        Ok(())
+    }
+
+    async fn unsubscribe(&mut self, topic_name: &str) -> Result<()> {
+        let Some(active) = self.subscriptions.remove(topic_name) else {
+            return Ok(());
+        };
+        active.task.abort();
+
+        // A subscription with TTL 0 tells the provider to stop sending
+        if let Some(binding) = self.settings.services.get(topic_name) {
+            let stop = self.subscription(binding, active.endpoint, 0);
+            let socket = UdpSocket::bind((self.settings.unicast_address, 0)).await?;
+            socket
+                .send_to(&stop.encode(next_session()), self.settings.sd_address)
+                .await?;
+        }
+        logd!(2, "Stopped SOME/IP subscription for topic '{}'", topic_name);
+        Ok(())
+    }
+}
+
+fn next_session() -> u16 {
+    let session = SD_SESSION.fetch_add(1, Ordering::Relaxed);
+    if session == 0 {
+        SD_SESSION.fetch_add(1, Ordering::Relaxed)
+    } else {
+        session
+    }
+}
+
+/// Keep a subscription alive and forward its events until the task is aborted
+async fn receive_events(
+    socket: UdpSocket,
+    subscription: message::Subscription,
+    sd_address: SocketAddr,
+    renew_every: Duration,
+    binding: ServiceBinding,
+    data_type_name: String,
+    tx: Sender<DdsData>,
+) {
+    let mut renew = tokio::time::interval(renew_every);
+    let mut buffer = vec![0u8; MAX_DATAGRAM];
+    loop {
+        tokio::select! {
+            _ = renew.tick() => {
+                if let Err(e) = socket.send_to(&subscription.encode(next_session()), sd_address).await {
+                    logd!(4, "Failed to send SOME/IP subscription to {}: {}", sd_address, e);
+                }
+            }
+            received = socket.recv_from(&mut buffer) => {
+                let len = match received {
+                    Ok((len, _)) => len,
+                    Err(e) => {
+                        logd!(4, "SOME/IP receive error: {}", e);
+                        continue;
+                    }
+                };
+                // Box<dyn Error> is not Send, so the result must not live across the await
+                let decoded = decode_event(&binding, &data_type_name, &buffer[..len])
+                    .map_err(|e| e.to_string());
+                match decoded {
+                    Ok(Some(data)) => {
+                        if tx.send(data).await.is_err() {
+                            logd!(4, "Channel closed, stopping SOME/IP subscription for {}", data_type_name);
+                            return;
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => logd!(4, "Invalid SOME/IP event for {}: {}", data_type_name, e),
+                }
+            }
+        }
+    }
+}
+
+/// Convert a notification of the bound event, `None` for other messages
+fn decode_event(
+    binding: &ServiceBinding,
+    data_type_name: &str,
+    data: &[u8],
+) -> Result<Option<DdsData>> {
+    let header = message::Header::parse(data)?;
+    if header.service_id != binding.service_id
+        || header.method_id != binding.event_id
+        || !header.is_notification()
+    {
+        return Ok(None);
+    }
+    let payload = message::payload(&header, data)?;
+    let fields = message::decode_fields(&binding.fields, payload)?;
+    let value = serde_json::to_string(&fields)?;
+    Ok(Some(DdsData {
+        name: data_type_name.to_string(),
+        value,
+        fields,
+    }))
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    fn binding() -> ServiceBinding {
+        ServiceBinding {
+            service_id: 0x1234,
+            instance_id: 1,
+            major_version: 1,
+            eventgroup_id: 1,
+            event_id: 0x8001,
+            fields: vec![PayloadField {
+                name: "value".to_string(),
+                r#type: FieldType::Bool,
+            }],
+        }
+    }
+
+    fn notification(service_id: u16, event_id: u16, payload: &[u8]) -> Vec<u8> {
+        message::encode(
+            &message::Header {
+                service_id,
+                method_id: event_id,
+                length: 0,
+                client_id: 0,
+                session_id: 1,
+                interface_version: 1,
+                message_type: 0x02,
+                return_code: 0,
+            },
+            payload,
+        )
+    }
+
+    #[test]
+    fn test_decode_event_filters_other_events() {
+        let binding = binding();
+        let data = decode_event(&binding, "Warning", &notification(0x1234, 0x8001, &[1]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(data.name, "Warning");
+        assert_eq!(data.fields["value"], "true");
+
+        let other = notification(0x1234, 0x8002, &[1]);
+        assert!(decode_event(&binding, "Warning", &other).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_settings_parse() {
+        let settings: SomeIpSettings = serde_yaml::from_str(
+            r#"
+unicast_address: 192.168.10.2
+services:
+  speed:
+    service_id: 0x1234
+    instance_id: 1
+    eventgroup_id: 1
+    event_id: 0x8001
+    fields:
+      - name: kmh
+        type: f32
+"#,
+        )
+        .unwrap();
+        assert_eq!(settings.sd_address, default_sd_address());
+        assert_eq!(settings.ttl_secs, DEFAULT_TTL_SECS);
+        let speed = &settings.services["speed"];
+        assert_eq!(speed.service_id, 0x1234);
+        assert_eq!(speed.major_version, 1);
+        assert_eq!(speed.fields[0].r#type, FieldType::F32);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_and_receive_over_loopback() {
+        // The test plays the SOME/IP provider and its service discovery
+        let provider = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let settings = SomeIpSettings {
+            sd_address: provider.local_addr().unwrap(),
+            services: HashMap::from([("Warning".to_string(), binding())]),
+            ..Default::default()
+        };
+        let (tx, mut rx) = mpsc::channel(1);
+        let mut source = SomeIpSource::new(settings, tx);
+        assert!(source
+            .subscribe("Unknown".to_string(), "Unknown".to_string())
+            .await
+            .is_err());
+        source
+            .subscribe("Warning".to_string(), "Warning".to_string())
+            .await
+            .unwrap();
+
+        let mut buffer = vec![0u8; 1024];
+        let (len, _) = provider.recv_from(&mut buffer).await.unwrap();
+        let subscription = message::Subscription::decode(&buffer[..len]).unwrap();
+        assert_eq!(subscription.service_id, 0x1234);
+        assert_eq!(subscription.ttl, DEFAULT_TTL_SECS);
+
+        provider
+            .send_to(
+                &notification(0x1234, 0x8001, &[1]),
+                SocketAddr::V4(subscription.endpoint),
+            )
+            .await
+            .unwrap();
+        let data = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(data.fields["value"], "true");
+
+        // Unsubscribing sends a subscription with TTL 0
+        source.unsubscribe("Warning").await.unwrap();
+        loop {
+            let (len, _) = provider.recv_from(&mut buffer).await.unwrap();
+            let message = message::Subscription::decode(&buffer[..len]).unwrap();
+            if message.ttl == 0 {
+                assert_eq!(message.endpoint, subscription.endpoint);
+                break;
+            }
+        }
+    }
+}
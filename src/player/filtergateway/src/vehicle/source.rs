@@ -0,0 +1,153 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Transport-independent access to vehicle signals
+//!
+//! Every transport (DDS, SOME/IP) implements [`SignalSource`] and delivers
+//! received samples as [`DdsData`](super::dds::DdsData) on the channel of the FilterGateway, so
+//! scenario conditions do not depend on where a signal comes from.
+//!
+//! The transport of each topic is chosen in the `signals` section of the
+//! settings file:
+//!
+//! ```yaml
+//! signals:
+//!   default: dds
+//!   topics:
+//!     ADASObstacleDetectionIsWarning: someip
+//!   someip:
+//!     unicast_address: 192.168.10.2
+//!     sd_address: 224.224.224.245:30490
+//!     services:
+//!       ADASObstacleDetectionIsWarning:
+//!         service_id: 0x1234
+//!         instance_id: 1
+//!         eventgroup_id: 1
+//!         event_id: 0x8001
+//!         fields:
+//!           - name: value
+//!             type: bool
+//!   record: /var/log/pullpiri/vehicle-data.jsonl
+//! ```
+//!
+//! `record` is optional and writes every received sample to a trace file,
+//! see [`record`](super::record).
+use super::someip::SomeIpSettings;
+use async_trait::async_trait;
+use common::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Transport of a vehicle signal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignalProtocol {
+    #[default]
+    Dds,
+    SomeIp,
+}
+
+/// Source of vehicle signals for one transport
+#[async_trait]
+pub trait SignalSource: Send + Sync {
+    /// Transport implemented by the source
+    fn protocol(&self) -> SignalProtocol;
+
+    /// Start delivering samples of a topic
+    async fn subscribe(&mut self, topic_name: String, data_type_name: String) -> Result<()>;
+
+    /// Stop delivering samples of a topic, unknown topics are ignored
+    async fn unsubscribe(&mut self, topic_name: &str) -> Result<()>;
+}
+
+/// `signals` section of the settings file
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SignalSettings {
+    /// Transport of topics not listed in `topics`
+    #[serde(default)]
+    pub default: SignalProtocol,
+    /// Transport per topic name
+    #[serde(default)]
+    pub topics: HashMap<String, SignalProtocol>,
+    #[serde(default)]
+    pub someip: SomeIpSettings,
+    /// Trace file of received samples, recording is off if unset
+    #[serde(default)]
+    pub record: Option<PathBuf>,
+}
+
+impl SignalSettings {
+    /// Read the `signals` section, defaults if the file or section is missing
+    pub fn load(settings_path: Option<PathBuf>) -> Result<Self> {
+        let settings_path = settings_path.unwrap_or_else(|| {
+            std::env::var("PULLPIRI_SETTINGS_PATH")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| PathBuf::from("/etc/pullpiri/settings.yaml"))
+        });
+        let content = match std::fs::read_to_string(&settings_path) {
+            Ok(content) => content,
+            Err(_) => return Ok(Self::default()),
+        };
+        Self::parse(&content)
+    }
+
+    /// Parse the `signals` section of a YAML or JSON settings document
+    pub fn parse(content: &str) -> Result<Self> {
+        let document: serde_yaml::Value = serde_yaml::from_str(content)?;
+        match document.get("signals") {
+            Some(section) => Ok(serde_yaml::from_value(section.clone())?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Transport that delivers a topic
+    pub fn protocol_for(&self, topic_name: &str) -> SignalProtocol {
+        self.topics.get(topic_name).copied().unwrap_or(self.default)
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_per_topic() {
+        let settings = SignalSettings::parse(
+            r#"
+host:
+  name: HPC
+signals:
+  topics:
+    speed: someip
+    gear: dds
+"#,
+        )
+        .unwrap();
+        assert_eq!(settings.protocol_for("speed"), SignalProtocol::SomeIp);
+        assert_eq!(settings.protocol_for("gear"), SignalProtocol::Dds);
+        assert_eq!(settings.protocol_for("other"), SignalProtocol::Dds);
+
+        let settings = SignalSettings::parse("signals:\n  default: someip\n").unwrap();
+        assert_eq!(settings.protocol_for("other"), SignalProtocol::SomeIp);
+        assert!(settings.record.is_none());
+
+        let settings = SignalSettings::parse("signals:\n  record: /tmp/trace.jsonl\n").unwrap();
+        assert_eq!(settings.record, Some(PathBuf::from("/tmp/trace.jsonl")));
+    }
+
+    #[test]
+    fn test_missing_section_and_file_use_defaults() {
+        let settings = SignalSettings::parse(r#"{"dds": {"domain_id": 7}}"#).unwrap();
+        assert!(settings.topics.is_empty());
+        assert_eq!(settings.default, SignalProtocol::Dds);
+
+        let settings =
+            SignalSettings::load(Some(PathBuf::from("/nonexistent/settings.yaml"))).unwrap();
+        assert!(settings.someip.services.is_empty());
+
+        assert!(SignalSettings::parse("signals:\n  default: can\n").is_err());
+    }
+}
@@ -0,0 +1,246 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! DDS topics subscribed at runtime
+//!
+//! Besides the topics of scenario and policy conditions, topics can be
+//! subscribed, paused and unsubscribed over gRPC. Those subscriptions are
+//! stored in etcd (`TopicSubscription/<topic>`) and subscribed again when
+//! FilterGateway starts.
+use super::VehicleManager;
+use common::logd;
+use dust_dds::infrastructure::qos::DataReaderQos;
+use dust_dds::infrastructure::qos_policy::{
+    HistoryQosPolicy, HistoryQosPolicyKind, ReliabilityQosPolicy, ReliabilityQosPolicyKind,
+};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// etcd key prefix of the subscriptions
+pub const SUBSCRIPTION_PREFIX: &str = "TopicSubscription";
+
+/// Vehicle manager of the running FilterGateway, used by the gRPC receiver
+static VEHICLE_MANAGER: OnceCell<Arc<Mutex<VehicleManager>>> = OnceCell::new();
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reliability {
+    #[default]
+    BestEffort,
+    Reliable,
+}
+
+/// QoS of the data reader of a subscription
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopicQos {
+    #[serde(default)]
+    pub reliability: Reliability,
+    /// Samples kept by the reader
+    #[serde(default = "default_history_depth")]
+    pub history_depth: u32,
+}
+
+fn default_history_depth() -> u32 {
+    1
+}
+
+impl Default for TopicQos {
+    fn default() -> Self {
+        Self {
+            reliability: Reliability::default(),
+            history_depth: default_history_depth(),
+        }
+    }
+}
+
+impl TopicQos {
+    /// QoS of the data reader, `None` to keep the DDS default
+    pub fn reader_qos(&self) -> Option<DataReaderQos> {
+        if *self == Self::default() {
+            return None;
+        }
+        let kind = match self.reliability {
+            Reliability::BestEffort => ReliabilityQosPolicyKind::BestEffort,
+            Reliability::Reliable => ReliabilityQosPolicyKind::Reliable,
+        };
+        let default = DataReaderQos::default();
+        Some(DataReaderQos {
+            reliability: ReliabilityQosPolicy {
+                kind,
+                max_blocking_time: default.reliability.max_blocking_time,
+            },
+            history: HistoryQosPolicy {
+                kind: HistoryQosPolicyKind::KeepLast(self.history_depth.max(1)),
+            },
+            ..default
+        })
+    }
+}
+
+/// Why a topic is listened to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopicOrigin {
+    /// Condition of a scenario or policy
+    Scenario,
+    /// Subscribed over gRPC
+    Api,
+}
+
+impl TopicOrigin {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Scenario => "scenario",
+            Self::Api => "api",
+        }
+    }
+}
+
+/// A topic subscribed over gRPC
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Subscription {
+    pub topic: String,
+    pub type_name: String,
+    pub domain_id: i32,
+    #[serde(default)]
+    pub qos: TopicQos,
+    /// The listener is stopped but the subscription is kept
+    #[serde(default)]
+    pub paused: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SubscriptionError {
+    /// The topic is not subscribed
+    NotFound(String),
+    /// The topic is already listened to
+    AlreadySubscribed(String),
+    /// The topic is listened to for a scenario or policy condition
+    NotManaged(String),
+    Invalid(String),
+    Failed(String),
+}
+
+impl std::fmt::Display for SubscriptionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(topic) => write!(f, "Topic '{}' is not subscribed", topic),
+            Self::AlreadySubscribed(topic) => write!(f, "Topic '{}' is already subscribed", topic),
+            Self::NotManaged(topic) => write!(
+                f,
+                "Topic '{}' is used by a scenario or policy condition",
+                topic
+            ),
+            Self::Invalid(e) | Self::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<SubscriptionError> for tonic::Status {
+    fn from(error: SubscriptionError) -> Self {
+        let message = error.to_string();
+        match error {
+            SubscriptionError::NotFound(_) => tonic::Status::not_found(message),
+            SubscriptionError::AlreadySubscribed(_) => tonic::Status::already_exists(message),
+            SubscriptionError::NotManaged(_) => tonic::Status::failed_precondition(message),
+            SubscriptionError::Invalid(_) => tonic::Status::invalid_argument(message),
+            SubscriptionError::Failed(_) => tonic::Status::internal(message),
+        }
+    }
+}
+
+/// Make the vehicle manager reachable from the gRPC receiver
+pub fn register_vehicle_manager(vehicle_manager: Arc<Mutex<VehicleManager>>) {
+    if VEHICLE_MANAGER.set(vehicle_manager).is_err() {
+        logd!(4, "Vehicle manager is already registered");
+    }
+}
+
+/// Vehicle manager of the running FilterGateway, if it is started
+pub fn vehicle_manager() -> Option<Arc<Mutex<VehicleManager>>> {
+    VEHICLE_MANAGER.get().cloned()
+}
+
+fn key(topic: &str) -> String {
+    format!("{}/{}", SUBSCRIPTION_PREFIX, topic)
+}
+
+/// Store a subscription so it is restored on the next start
+pub async fn save(subscription: &Subscription) -> Result<(), String> {
+    let value = serde_json::to_string(subscription).map_err(|e| e.to_string())?;
+    Ok(common::etcd::put(&key(&subscription.topic), &value).await?)
+}
+
+pub async fn delete(topic: &str) -> Result<(), String> {
+    Ok(common::etcd::delete(&key(topic)).await?)
+}
+
+/// Subscriptions stored in etcd, skipping entries that cannot be read
+pub async fn load_all() -> Result<Vec<Subscription>, String> {
+    let kvs = common::etcd::get_all_with_prefix(&format!("{}/", SUBSCRIPTION_PREFIX)).await?;
+    Ok(kvs
+        .into_iter()
+        .filter_map(|(key, value)| match serde_json::from_str(&value) {
+            Ok(subscription) => Some(subscription),
+            Err(e) => {
+                logd!(4, "Ignoring invalid subscription {}: {:?}", key, e);
+                None
+            }
+        })
+        .collect())
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reader_qos() {
+        assert!(TopicQos::default().reader_qos().is_none());
+
+        let qos = TopicQos {
+            reliability: Reliability::Reliable,
+            history_depth: 5,
+        };
+        let reader_qos = qos.reader_qos().unwrap();
+        assert_eq!(
+            reader_qos.reliability.kind,
+            ReliabilityQosPolicyKind::Reliable
+        );
+        assert_eq!(reader_qos.history.kind, HistoryQosPolicyKind::KeepLast(5));
+    }
+
+    #[test]
+    fn test_subscription_defaults() {
+        let subscription: Subscription = serde_json::from_str(
+            r#"{"topic": "cabin_temp", "type_name": "CabinTemperature", "domain_id": 100}"#,
+        )
+        .unwrap();
+        assert_eq!(subscription.qos, TopicQos::default());
+        assert!(!subscription.paused);
+
+        let value = serde_json::to_value(Subscription {
+            paused: true,
+            qos: TopicQos {
+                reliability: Reliability::Reliable,
+                history_depth: 3,
+            },
+            ..subscription
+        })
+        .unwrap();
+        assert_eq!(value["qos"]["reliability"], "reliable");
+        assert_eq!(value["paused"], true);
+    }
+
+    #[test]
+    fn test_error_status() {
+        let status = tonic::Status::from(SubscriptionError::NotFound("a".to_string()));
+        assert_eq!(status.code(), tonic::Code::NotFound);
+        assert_eq!(status.message(), "Topic 'a' is not subscribed");
+        let status = tonic::Status::from(SubscriptionError::NotManaged("a".to_string()));
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+    }
+}
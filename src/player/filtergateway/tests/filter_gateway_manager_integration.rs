@@ -97,6 +97,7 @@ async fn test_run_manager_with_allow_action() {
     let param = ScenarioParameter {
         action: 0,
         scenario,
+        trace: None,
     };
 
     tx.send(param).await.unwrap();
@@ -192,6 +193,7 @@ async fn test_run_manager_with_withdraw_action() {
     tx.send(ScenarioParameter {
         action: 1,
         scenario,
+        trace: None,
     })
     .await
     .unwrap();
@@ -300,6 +302,7 @@ async fn test_run_manager_with_withdraw_action_none() {
     tx.send(ScenarioParameter {
         action: 3,
         scenario,
+        trace: None,
     })
     .await
     .unwrap();
@@ -416,6 +419,7 @@ async fn test_run_manager_with_invalid_action_value() {
     let param = ScenarioParameter {
         action: 99,
         scenario,
+        trace: None,
     }; // invalid action
 
     tx.send(param).await.unwrap();
@@ -5,6 +5,7 @@
 use common::spec::artifact::Scenario;
 use filtergateway::filter::Filter;
 use filtergateway::grpc::sender::actioncontroller::FilterGatewaySender;
+use filtergateway::policy::PolicyEngine;
 use filtergateway::vehicle::dds::DdsData;
 use std::collections::HashMap;
 use tokio;
@@ -92,7 +93,13 @@ spec:
     let dds = build_dds_data("TestTopic", "temperature", "true");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("test_eq".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "test_eq".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     assert!(filter.process_data(&dds).await.is_ok());
     common::etcd::delete("Scenario/test_eq").await.unwrap();
@@ -140,7 +147,13 @@ spec:
     let dds = build_dds_data("TestTopic_wrong", "temperature", "true");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("test_eq1".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "test_eq1".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     assert!(filter.meet_scenario_condition(&dds).await.is_err());
     common::etcd::delete("Scenario/test_eq1").await.unwrap();
@@ -188,7 +201,13 @@ spec:
     let dds = build_dds_data("TestTopic", "temperature", "5");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("test_lt".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "test_lt".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     assert!(filter.process_data(&dds).await.is_ok());
     common::etcd::delete("Scenario/test_lt").await.unwrap();
@@ -238,7 +257,13 @@ spec:
     let dds = build_dds_data("TestTopic", "temperature", "abc");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("test_field_parse".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "test_field_parse".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     let result = filter.meet_scenario_condition(&dds).await;
     assert!(result.is_err());
@@ -268,7 +293,13 @@ spec:
     let dds = build_dds_data("TestTopic", "temperature", "abc");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("test_field_parse".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "test_field_parse".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     let result = filter.process_data(&dds).await;
     assert!(true);
@@ -315,7 +346,13 @@ spec:
     let dds = build_dds_data("TestTopic", "temperature", "10");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("test_le".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "test_le".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     assert!(filter.process_data(&dds).await.is_ok());
     common::etcd::delete("Scenario/test_le").await.unwrap();
@@ -363,7 +400,13 @@ spec:
     let dds = build_dds_data("TestTopic", "temperature", "11");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("test_ge".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "test_ge".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     assert!(filter.process_data(&dds).await.is_ok());
     common::etcd::delete("Scenario/test_ge").await.unwrap();
@@ -411,7 +454,13 @@ spec:
     let dds = build_dds_data("TestTopic", "temperature", "15");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("test_gt".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "test_gt".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     assert!(filter.process_data(&dds).await.is_ok());
     common::etcd::delete("Scenario/test_gt").await.unwrap();
@@ -463,7 +512,13 @@ spec:
     let dds = build_dds_data("TestTopic", "temperature", "on");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("invalid_expr".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "invalid_expr".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     // Should log error but still return Ok from process_data
     assert!(filter.process_data(&dds).await.is_ok());
@@ -514,7 +569,13 @@ spec:
     let dds = build_dds_data("WrongTopic", "temperature", "true");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("topic_mismatch".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "topic_mismatch".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     assert!(filter.process_data(&dds).await.is_ok());
     common::etcd::delete("Scenario/topic_mismatch")
@@ -566,7 +627,13 @@ spec:
     let dds = build_dds_data("TestTopic", "temperature", "15");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("test_gt".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "test_gt".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     assert!(filter.process_data(&dds).await.is_ok());
     common::etcd::delete("Scenario/test_gt").await.unwrap();
@@ -615,7 +682,13 @@ spec:
     let dds = build_dds_data("TestTopic", "unknown_field", "true");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("missing_field".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "missing_field".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     // Logs error, returns Ok
     assert!(filter.process_data(&dds).await.is_ok());
@@ -670,7 +743,13 @@ spec:
     let dds = build_dds_data("TestTopic", "temperature", "5");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("parse_error".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "parse_error".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     assert!(filter.process_data(&dds).await.is_ok());
     common::etcd::delete("Scenario/parse_field_error")
@@ -724,7 +803,13 @@ spec:
     let dds = build_dds_data("TestTopic", "temperature", "5");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("parse_error".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "parse_error".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     assert!(filter.process_data(&dds).await.is_ok());
     common::etcd::delete("Scenario/parse_field_error")
@@ -778,7 +863,13 @@ spec:
     let dds = build_dds_data("TestTopic", "temperature", "5");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("parse_error".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "parse_error".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     assert!(filter.process_data(&dds).await.is_ok());
     common::etcd::delete("Scenario/parse_field_error")
@@ -832,7 +923,13 @@ spec:
     let dds = build_dds_data("TestTopic", "temperature", "5");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("parse_error".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "parse_error".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     assert!(filter.process_data(&dds).await.is_ok());
     common::etcd::delete("Scenario/parse_field_error")
@@ -886,7 +983,13 @@ spec:
     let dds = build_dds_data("TestTopic", "temperature", "not_a_number");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("parse_field_error".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "parse_field_error".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     assert!(filter.process_data(&dds).await.is_ok());
     common::etcd::delete("Scenario/parse_field_error")
@@ -940,7 +1043,13 @@ spec:
     let dds = build_dds_data("TestTopic", "temperature", "not_a_number");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("parse_field_error".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "parse_field_error".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     assert!(filter.process_data(&dds).await.is_ok());
     common::etcd::delete("Scenario/parse_field_error")
@@ -994,7 +1103,13 @@ spec:
     let dds = build_dds_data("TestTopic", "temperature", "not_a_number");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("parse_field_error".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "parse_field_error".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     assert!(filter.process_data(&dds).await.is_ok());
     common::etcd::delete("Scenario/parse_field_error")
@@ -1048,7 +1163,13 @@ spec:
     let dds = build_dds_data("TestTopic", "temperature", "not_a_number");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("parse_field_error".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "parse_field_error".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     assert!(filter.process_data(&dds).await.is_ok());
     common::etcd::delete("Scenario/parse_field_error")
@@ -1103,7 +1224,13 @@ spec:
     let dds = build_dds_data("TestTopic", "temperature", "on");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("inactive".into(), scenario, false, sender);
+    let mut filter = Filter::new(
+        "inactive".into(),
+        scenario,
+        false,
+        sender,
+        PolicyEngine::new(),
+    );
 
     assert!(filter.process_data(&dds).await.is_ok());
     common::etcd::delete("Scenario/inactive").await.unwrap();
@@ -1155,7 +1282,13 @@ spec:
         .unwrap();
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("pause_resume".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "pause_resume".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     assert!(filter.is_active());
     filter.pause_scenario_filter().await.unwrap();
@@ -1214,7 +1347,13 @@ spec:
     let dds = build_dds_data("TestTopic", "status", "true");
 
     let sender = FilterGatewaySender::new();
-    let mut filter = Filter::new("helloworld".into(), scenario, true, sender);
+    let mut filter = Filter::new(
+        "helloworld".into(),
+        scenario,
+        true,
+        sender,
+        PolicyEngine::new(),
+    );
 
     assert!(filter.process_data(&dds).await.is_ok());
     common::etcd::delete("Scenario/helloworld").await.unwrap();
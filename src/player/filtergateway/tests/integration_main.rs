@@ -143,6 +143,7 @@ spec:
     let scenario_param = ScenarioParameter {
         action: 0,
         scenario,
+        trace: None,
     };
 
     let (tx_grpc, rx_grpc) = channel(100);
@@ -193,6 +194,7 @@ spec:
     let scenario_param = ScenarioParameter {
         action: 3,
         scenario,
+        trace: None,
     };
 
     let (tx_grpc, rx_grpc) = channel(100);
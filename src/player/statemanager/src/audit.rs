@@ -0,0 +1,323 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Audit trail of state transitions
+//!
+//! Every StateChange processed by the StateManager is written as one record
+//! under `/audit/transitions/`. Keys start with the zero-padded receive time
+//! and a sequence number, so records are never overwritten and sort in the
+//! order they were written.
+//!
+//! Records are queried with `GET /api/v1/audit` on the metrics endpoint:
+//!
+//! ```text
+//! GET /api/v1/audit?resource=scenario-a&since=<ns>&until=<ns>&result=failure&limit=50
+//! ```
+
+use crate::types::TransitionResult;
+use axum::{
+    extract::Query,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use common::logd;
+use common::statemanager::{AsilLevel, ErrorCode, ResourceType, StateChange};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// etcd prefix of the audit records
+pub const AUDIT_PREFIX: &str = "/audit/transitions/";
+
+/// Records returned by a query without `limit`
+const DEFAULT_QUERY_LIMIT: usize = 100;
+
+/// Distinguishes records written in the same nanosecond
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// Whether a transition was applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+/// One processed StateChange
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// When the StateManager finished processing, Unix time in nanoseconds
+    pub recorded_at_ns: i64,
+    /// Timestamp set by the source component
+    pub requested_at_ns: i64,
+    /// Processing time in microseconds
+    pub duration_us: u64,
+    pub resource_type: String,
+    pub resource_name: String,
+    pub current_state: String,
+    pub target_state: String,
+    pub transition_id: String,
+    pub source: String,
+    pub asil_level: String,
+    pub outcome: AuditOutcome,
+    pub error_code: String,
+    pub message: String,
+}
+
+impl AuditRecord {
+    /// Record of a StateChange processed by the state machine
+    pub fn new(state_change: &StateChange, result: &TransitionResult, duration: Duration) -> Self {
+        let outcome = if result.is_success() {
+            AuditOutcome::Success
+        } else {
+            AuditOutcome::Failure
+        };
+        Self::build(
+            state_change,
+            outcome,
+            result.error_code,
+            &result.message,
+            duration,
+        )
+    }
+
+    /// Record of a StateChange refused before reaching the state machine
+    pub fn rejected(state_change: &StateChange, message: &str, duration: Duration) -> Self {
+        Self::build(
+            state_change,
+            AuditOutcome::Failure,
+            ErrorCode::InvalidRequest,
+            message,
+            duration,
+        )
+    }
+
+    fn build(
+        state_change: &StateChange,
+        outcome: AuditOutcome,
+        error_code: ErrorCode,
+        message: &str,
+        duration: Duration,
+    ) -> Self {
+        Self {
+            recorded_at_ns: chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default(),
+            requested_at_ns: state_change.timestamp_ns,
+            duration_us: duration.as_micros() as u64,
+            resource_type: ResourceType::try_from(state_change.resource_type)
+                .map(|t| t.as_str_name())
+                .unwrap_or("UNKNOWN")
+                .to_string(),
+            resource_name: state_change.resource_name.clone(),
+            current_state: state_change.current_state.clone(),
+            target_state: state_change.target_state.clone(),
+            transition_id: state_change.transition_id.clone(),
+            source: state_change.source.clone(),
+            asil_level: AsilLevel::try_from(state_change.asil_level)
+                .unwrap_or(AsilLevel::Unspecified)
+                .as_str_name()
+                .to_string(),
+            outcome,
+            error_code: error_code.as_str_name().to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    /// etcd key of the record, unique and ordered by time
+    fn key(&self) -> String {
+        format!(
+            "{}{:020}-{:010}",
+            AUDIT_PREFIX,
+            self.recorded_at_ns.max(0),
+            SEQUENCE.fetch_add(1, Ordering::Relaxed)
+        )
+    }
+}
+
+/// Append a record to the audit trail
+///
+/// Failures are logged only, an unavailable store must not block transitions.
+pub async fn record(entry: &AuditRecord) {
+    let json = match serde_json::to_string(entry) {
+        Ok(json) => json,
+        Err(e) => {
+            logd!(5, "Failed to serialize audit record: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = common::etcd::put(&entry.key(), &json).await {
+        logd!(
+            5,
+            "Failed to write audit record of transition {}: {}",
+            entry.transition_id,
+            e
+        );
+    }
+}
+
+/// Filters of an audit query, all optional
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditQuery {
+    /// Resource name
+    pub resource: Option<String>,
+    /// Resource type, e.g. `scenario` or `RESOURCE_TYPE_SCENARIO`
+    pub resource_type: Option<String>,
+    /// Earliest `recorded_at_ns`, inclusive
+    pub since: Option<i64>,
+    /// Latest `recorded_at_ns`, inclusive
+    pub until: Option<i64>,
+    /// `success` or `failure`
+    pub result: Option<AuditOutcome>,
+    /// Maximum number of records, the newest are returned
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditRecord) -> bool {
+        self.resource
+            .as_ref()
+            .is_none_or(|name| &entry.resource_name == name)
+            && self.resource_type.as_ref().is_none_or(|t| {
+                let t = t.to_ascii_uppercase();
+                entry.resource_type == t || entry.resource_type == format!("RESOURCE_TYPE_{}", t)
+            })
+            && self.since.is_none_or(|since| entry.recorded_at_ns >= since)
+            && self.until.is_none_or(|until| entry.recorded_at_ns <= until)
+            && self.result.is_none_or(|result| entry.outcome == result)
+    }
+}
+
+/// Read the records matching a query, oldest first
+pub async fn query(filter: &AuditQuery) -> common::Result<Vec<AuditRecord>> {
+    let mut pairs = common::etcd::get_all_with_prefix(AUDIT_PREFIX).await?;
+    pairs.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut records: Vec<AuditRecord> = pairs
+        .iter()
+        .filter_map(|(_, value)| serde_json::from_str(value).ok())
+        .filter(|entry| filter.matches(entry))
+        .collect();
+    let limit = filter.limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+    if records.len() > limit {
+        records.drain(..records.len() - limit);
+    }
+    Ok(records)
+}
+
+pub fn router() -> Router {
+    Router::new().route("/api/v1/audit", get(list_records))
+}
+
+async fn list_records(Query(filter): Query<AuditQuery>) -> Response {
+    match query(&filter).await {
+        Ok(records) => Json(records).into_response(),
+        Err(e) => {
+            logd!(4, "Failed to query audit records: {}", e);
+            (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response()
+        }
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_change(name: &str) -> StateChange {
+        StateChange {
+            resource_type: ResourceType::Scenario as i32,
+            resource_name: name.to_string(),
+            current_state: "idle".to_string(),
+            target_state: "waiting".to_string(),
+            transition_id: format!("audit-test-{}", name),
+            timestamp_ns: 42,
+            source: "unittest".to_string(),
+            asil_level: AsilLevel::B as i32,
+            trace_id: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_record_fields_and_keys() {
+        let result = TransitionResult {
+            new_state: 2,
+            error_code: ErrorCode::Success,
+            message: "ok".to_string(),
+            actions_to_execute: vec![],
+            transition_id: "audit-test-a".to_string(),
+            error_details: String::new(),
+        };
+        let entry = AuditRecord::new(&state_change("a"), &result, Duration::from_millis(3));
+        assert_eq!(entry.outcome, AuditOutcome::Success);
+        assert_eq!(entry.resource_type, "RESOURCE_TYPE_SCENARIO");
+        assert_eq!(entry.asil_level, "ASIL_LEVEL_B");
+        assert_eq!(entry.requested_at_ns, 42);
+        assert_eq!(entry.duration_us, 3_000);
+
+        // Two records of the same instant still get distinct, ordered keys
+        let first = entry.key();
+        let second = entry.key();
+        assert!(first.starts_with(AUDIT_PREFIX));
+        assert!(first < second);
+
+        let rejected = AuditRecord::rejected(&state_change("b"), "bad type", Duration::ZERO);
+        assert_eq!(rejected.outcome, AuditOutcome::Failure);
+        assert_eq!(rejected.error_code, "ERROR_CODE_INVALID_REQUEST");
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let mut entry = AuditRecord::rejected(&state_change("filtered"), "denied", Duration::ZERO);
+        entry.recorded_at_ns = 1_000;
+
+        assert!(AuditQuery::default().matches(&entry));
+        let by_name = AuditQuery {
+            resource: Some("filtered".to_string()),
+            resource_type: Some("scenario".to_string()),
+            result: Some(AuditOutcome::Failure),
+            ..Default::default()
+        };
+        assert!(by_name.matches(&entry));
+        let other_name = AuditQuery {
+            resource: Some("other".to_string()),
+            ..Default::default()
+        };
+        assert!(!other_name.matches(&entry));
+        let in_range = AuditQuery {
+            since: Some(1_000),
+            until: Some(2_000),
+            ..Default::default()
+        };
+        assert!(in_range.matches(&entry));
+        let too_late = AuditQuery {
+            since: Some(1_001),
+            ..Default::default()
+        };
+        assert!(!too_late.matches(&entry));
+        let successes = AuditQuery {
+            result: Some(AuditOutcome::Success),
+            ..Default::default()
+        };
+        assert!(!successes.matches(&entry));
+    }
+
+    #[tokio::test]
+    async fn test_audit_endpoint_rejects_bad_result() {
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let response = router()
+            .oneshot(
+                Request::get("/api/v1/audit?result=maybe")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
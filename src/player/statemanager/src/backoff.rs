@@ -0,0 +1,299 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! BackoffManager: restart backoff for crashing models (CrashLoopBackOff)
+//!
+//! Every crash of a model doubles the delay before ActionController is asked
+//! to recreate it, from `base_ms` up to `cap_ms`, with random jitter so many
+//! models crashing together do not restart in lockstep. After `max_retries`
+//! restarts, or the `maxRestarts` of the model spec, the model is Failed and
+//! no longer restarted automatically.
+//!
+//! Retry counters are stored in etcd under `/backoff/model/<name>`, so a
+//! restarted StateManager continues the loop instead of starting over.
+
+use common::logd;
+use common::setting::BackoffSettings;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// etcd prefix of persisted retry counters
+const BACKOFF_PREFIX: &str = "/backoff";
+
+/// Retry counter of one resource
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RetryState {
+    /// Restarts since the crash loop began
+    pub retries: u32,
+    /// Unix time of the last crash in milliseconds
+    pub last_failure_ms: i64,
+    /// `true` once the retries are used up
+    pub failed: bool,
+}
+
+/// What to do after a crash
+#[derive(Debug, Clone, PartialEq)]
+pub enum BackoffDecision {
+    /// Restart after the delay
+    Retry { attempt: u32, delay: Duration },
+    /// Stop restarting, the resource is Failed
+    GiveUp { retries: u32 },
+}
+
+/// Tracks crash loops and decides when resources are restarted
+pub struct BackoffManager {
+    settings: BackoffSettings,
+    /// Counters loaded from or written to etcd, keyed by etcd key
+    states: HashMap<String, RetryState>,
+}
+
+impl BackoffManager {
+    pub fn new(settings: BackoffSettings) -> Self {
+        Self {
+            settings,
+            states: HashMap::new(),
+        }
+    }
+
+    /// Delay before restart number `attempt`, starting at 1
+    ///
+    /// `jitter` from -1.0 to 1.0 scales the random part of the delay.
+    pub fn delay(&self, attempt: u32, jitter: f64) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(63);
+        let delay = self
+            .settings
+            .base_ms
+            .saturating_mul(1u64 << exponent)
+            .min(self.settings.cap_ms) as f64;
+        let spread = delay * self.settings.jitter.clamp(0.0, 1.0) * jitter.clamp(-1.0, 1.0);
+        Duration::from_millis((delay + spread).max(0.0) as u64)
+    }
+
+    /// Count a crash of a model and decide whether it is restarted
+    ///
+    /// `max_retries` of the model replaces the one of the settings if set.
+    pub async fn record_failure(
+        &mut self,
+        model_name: &str,
+        max_retries: Option<u32>,
+    ) -> BackoffDecision {
+        let key = retry_key(model_name);
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let mut state = self.load(&key).await;
+        let decision = self.next_decision(&mut state, now_ms, random_jitter(), max_retries);
+
+        if let Err(e) = self.save(&key, &state).await {
+            logd!(
+                4,
+                "Failed to persist retry counter of {}: {}",
+                model_name,
+                e
+            );
+        }
+        self.states.insert(key, state);
+        decision
+    }
+
+    /// Forget the crash loop of a model, e.g. after it was recovered by hand
+    pub async fn reset(&mut self, model_name: &str) {
+        let key = retry_key(model_name);
+        if self.states.remove(&key).is_some() || common::etcd::get(&key).await.is_ok() {
+            if let Err(e) = common::etcd::delete(&key).await {
+                logd!(4, "Failed to delete retry counter of {}: {}", model_name, e);
+            }
+        }
+    }
+
+    /// `true` if a model used up its retries
+    pub async fn is_failed(&mut self, model_name: &str) -> bool {
+        let key = retry_key(model_name);
+        let state = self.load(&key).await;
+        let failed = state.failed;
+        self.states.insert(key, state);
+        failed
+    }
+
+    /// Update a counter for a crash at `now_ms`
+    fn next_decision(
+        &self,
+        state: &mut RetryState,
+        now_ms: i64,
+        jitter: f64,
+        max_retries: Option<u32>,
+    ) -> BackoffDecision {
+        if state.failed {
+            return BackoffDecision::GiveUp {
+                retries: state.retries,
+            };
+        }
+        // A model that ran long enough starts a new loop
+        if now_ms - state.last_failure_ms > self.settings.reset_after_ms as i64 {
+            state.retries = 0;
+        }
+        state.last_failure_ms = now_ms;
+
+        if state.retries >= max_retries.unwrap_or(self.settings.max_retries) {
+            state.failed = true;
+            return BackoffDecision::GiveUp {
+                retries: state.retries,
+            };
+        }
+        state.retries += 1;
+        BackoffDecision::Retry {
+            attempt: state.retries,
+            delay: self.delay(state.retries, jitter),
+        }
+    }
+
+    async fn load(&self, key: &str) -> RetryState {
+        if let Some(state) = self.states.get(key) {
+            return state.clone();
+        }
+        match common::etcd::get(key).await {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_default(),
+            Err(_) => RetryState::default(),
+        }
+    }
+
+    async fn save(&self, key: &str, state: &RetryState) -> std::result::Result<(), String> {
+        let json = serde_json::to_string(state).map_err(|e| e.to_string())?;
+        Ok(common::etcd::put(key, &json).await?)
+    }
+}
+
+fn retry_key(model_name: &str) -> String {
+    format!("{}/model/{}", BACKOFF_PREFIX, model_name)
+}
+
+/// Random value from -1.0 to 1.0
+fn random_jitter() -> f64 {
+    use std::hash::{BuildHasher, Hasher};
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_i64(chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default());
+    hasher.finish() as f64 / u64::MAX as f64 * 2.0 - 1.0
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager() -> BackoffManager {
+        BackoffManager::new(BackoffSettings {
+            base_ms: 1_000,
+            cap_ms: 10_000,
+            jitter: 0.5,
+            max_retries: 3,
+            reset_after_ms: 60_000,
+        })
+    }
+
+    #[test]
+    fn test_delay_doubles_up_to_cap() {
+        let manager = manager();
+        let delays: Vec<u64> = (1..=6)
+            .map(|attempt| manager.delay(attempt, 0.0).as_millis() as u64)
+            .collect();
+        assert_eq!(delays, vec![1_000, 2_000, 4_000, 8_000, 10_000, 10_000]);
+        assert_eq!(manager.delay(100, 0.0), Duration::from_millis(10_000));
+    }
+
+    #[test]
+    fn test_delay_jitter_bounds() {
+        let manager = manager();
+        assert_eq!(manager.delay(2, 1.0), Duration::from_millis(3_000));
+        assert_eq!(manager.delay(2, -1.0), Duration::from_millis(1_000));
+        for _ in 0..100 {
+            let delay = manager.delay(2, random_jitter());
+            assert!(delay >= Duration::from_millis(1_000));
+            assert!(delay <= Duration::from_millis(3_000));
+        }
+    }
+
+    #[test]
+    fn test_gives_up_after_max_retries() {
+        let manager = manager();
+        let mut state = RetryState::default();
+        let mut now = 1_000_000;
+
+        for attempt in 1..=3 {
+            assert!(matches!(
+                manager.next_decision(&mut state, now, 0.0, None),
+                BackoffDecision::Retry { attempt: a, .. } if a == attempt
+            ));
+            now += 1_000;
+        }
+        assert_eq!(
+            manager.next_decision(&mut state, now, 0.0, None),
+            BackoffDecision::GiveUp { retries: 3 }
+        );
+        assert!(state.failed);
+
+        // Failed is permanent, even after a long quiet period
+        assert_eq!(
+            manager.next_decision(&mut state, now + 3_600_000, 0.0, None),
+            BackoffDecision::GiveUp { retries: 3 }
+        );
+    }
+
+    #[test]
+    fn test_model_max_restarts_overrides_settings() {
+        let manager = manager();
+        let mut state = RetryState::default();
+
+        assert!(matches!(
+            manager.next_decision(&mut state, 1_000_000, 0.0, Some(1)),
+            BackoffDecision::Retry { attempt: 1, .. }
+        ));
+        assert_eq!(
+            manager.next_decision(&mut state, 1_001_000, 0.0, Some(1)),
+            BackoffDecision::GiveUp { retries: 1 }
+        );
+
+        let mut never = RetryState::default();
+        assert_eq!(
+            manager.next_decision(&mut never, 1_000_000, 0.0, Some(0)),
+            BackoffDecision::GiveUp { retries: 0 }
+        );
+    }
+
+    #[test]
+    fn test_quiet_period_resets_counter() {
+        let manager = manager();
+        let mut state = RetryState {
+            retries: 2,
+            last_failure_ms: 1_000_000,
+            failed: false,
+        };
+
+        assert_eq!(
+            manager.next_decision(&mut state, 1_000_000 + 60_001, 0.0, None),
+            BackoffDecision::Retry {
+                attempt: 1,
+                delay: Duration::from_millis(1_000)
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_counter_survives_restart() {
+        let model = "backoff-restart-test-model";
+        let mut first = manager();
+        first.reset(model).await;
+        let decision = first.record_failure(model, None).await;
+        assert!(matches!(
+            decision,
+            BackoffDecision::Retry { attempt: 1, .. }
+        ));
+
+        // A new manager reads the counter back when etcd is reachable
+        let mut second = manager();
+        if let BackoffDecision::Retry { attempt, .. } = second.record_failure(model, None).await {
+            assert!(attempt == 1 || attempt == 2);
+        }
+        second.reset(model).await;
+    }
+}
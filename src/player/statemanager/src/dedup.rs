@@ -0,0 +1,257 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Deduplication of StateChanges by their transition_id
+//!
+//! A sender that retries a StateChange after a lost response delivers it
+//! twice. The `transition_id` is an idempotency key: the last
+//! [`IDS_PER_RESOURCE`] ids of every resource are kept with the response of
+//! their first delivery, and a StateChange whose id is among them is not
+//! processed again. The duplicate gets the original response, which holds
+//! the outcome of the transition once it was processed. Resources beyond
+//! [`MAX_RESOURCES`] forget the ids of the least recently used resource.
+//! A StateChange without a transition_id cannot be told apart from another
+//! one and is always processed.
+
+use common::statemanager::{ErrorCode, ResourceType, StateChange, StateChangeResponse};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+/// Transition ids remembered per resource
+pub const IDS_PER_RESOURCE: usize = 64;
+
+/// Resources whose transition ids are remembered
+pub const MAX_RESOURCES: usize = 4096;
+
+/// Recently processed transition ids of a resource, most recent last
+struct ResourceIds {
+    entries: VecDeque<(String, StateChangeResponse)>,
+    last_used: u64,
+}
+
+/// Bounded LRU of transition ids per resource
+pub struct IdempotencyCache {
+    resources: HashMap<String, ResourceIds>,
+    ids_per_resource: usize,
+    max_resources: usize,
+    clock: u64,
+}
+
+impl IdempotencyCache {
+    pub fn new(ids_per_resource: usize, max_resources: usize) -> Self {
+        Self {
+            resources: HashMap::new(),
+            ids_per_resource: ids_per_resource.max(1),
+            max_resources: max_resources.max(1),
+            clock: 0,
+        }
+    }
+
+    /// Claim a transition id for a resource
+    ///
+    /// ### Returns
+    /// * `Some(response)` - the id was seen, with the response of its first delivery
+    /// * `None` - the id is new and is remembered with `response`, or empty
+    pub fn claim(
+        &mut self,
+        resource: &str,
+        transition_id: &str,
+        response: &StateChangeResponse,
+    ) -> Option<StateChangeResponse> {
+        if transition_id.is_empty() {
+            return None;
+        }
+        self.clock += 1;
+        let clock = self.clock;
+        if !self.resources.contains_key(resource) && self.resources.len() >= self.max_resources {
+            self.evict_resource();
+        }
+        let ids = self
+            .resources
+            .entry(resource.to_string())
+            .or_insert_with(|| ResourceIds {
+                entries: VecDeque::new(),
+                last_used: clock,
+            });
+        ids.last_used = clock;
+
+        if let Some(index) = ids.entries.iter().position(|(id, _)| id == transition_id) {
+            // A duplicate makes the id the most recently used one
+            let entry = ids.entries.remove(index)?;
+            let original = entry.1.clone();
+            ids.entries.push_back(entry);
+            return Some(original);
+        }
+        if ids.entries.len() >= self.ids_per_resource {
+            ids.entries.pop_front();
+        }
+        ids.entries
+            .push_back((transition_id.to_string(), response.clone()));
+        None
+    }
+
+    /// Replace the response remembered for a transition id, if it is still known
+    pub fn update(&mut self, resource: &str, transition_id: &str, response: StateChangeResponse) {
+        if let Some(entry) = self
+            .resources
+            .get_mut(resource)
+            .and_then(|ids| ids.entries.iter_mut().find(|(id, _)| id == transition_id))
+        {
+            entry.1 = response;
+        }
+    }
+
+    /// Forget a transition id, so a retry of it is processed
+    pub fn release(&mut self, resource: &str, transition_id: &str) {
+        if let Some(ids) = self.resources.get_mut(resource) {
+            ids.entries.retain(|(id, _)| id != transition_id);
+        }
+    }
+
+    fn evict_resource(&mut self) {
+        if let Some(oldest) = self
+            .resources
+            .iter()
+            .min_by_key(|(_, ids)| ids.last_used)
+            .map(|(resource, _)| resource.clone())
+        {
+            self.resources.remove(&oldest);
+        }
+    }
+}
+
+fn cache() -> &'static Mutex<IdempotencyCache> {
+    static CACHE: OnceLock<Mutex<IdempotencyCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(IdempotencyCache::new(IDS_PER_RESOURCE, MAX_RESOURCES)))
+}
+
+fn with_cache<R>(f: impl FnOnce(&mut IdempotencyCache) -> R) -> R {
+    let mut cache = cache().lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut cache)
+}
+
+/// Key of the resource of a StateChange
+fn resource_key(state_change: &StateChange) -> String {
+    let resource_type =
+        ResourceType::try_from(state_change.resource_type).unwrap_or(ResourceType::Unspecified);
+    format!(
+        "{}/{}",
+        resource_type.as_str_name(),
+        state_change.resource_name
+    )
+}
+
+/// Claim the transition id of a StateChange before it is queued
+///
+/// ### Returns
+/// * `Some(response)` - the StateChange is a duplicate, answered with the
+///   response of its first delivery; the dedup hit is counted
+/// * `None` - the StateChange is new, `response` answers its duplicates
+pub fn claim(
+    state_change: &StateChange,
+    response: &StateChangeResponse,
+) -> Option<StateChangeResponse> {
+    let original = with_cache(|cache| {
+        cache.claim(
+            &resource_key(state_change),
+            &state_change.transition_id,
+            response,
+        )
+    })?;
+    let resource_type =
+        ResourceType::try_from(state_change.resource_type).unwrap_or(ResourceType::Unspecified);
+    crate::metrics::record_dedup_hit(resource_type);
+    Some(original)
+}
+
+/// Forget a claimed StateChange that could not be queued
+pub fn release(state_change: &StateChange) {
+    with_cache(|cache| cache.release(&resource_key(state_change), &state_change.transition_id));
+}
+
+/// Remember the outcome of a processed StateChange for its duplicates
+pub fn complete(state_change: &StateChange, error_code: ErrorCode, message: &str, details: &str) {
+    let response = StateChangeResponse {
+        message: message.to_string(),
+        transition_id: state_change.transition_id.clone(),
+        timestamp_ns: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as i64,
+        error_code: error_code as i32,
+        error_details: details.to_string(),
+    };
+    with_cache(|cache| {
+        cache.update(
+            &resource_key(state_change),
+            &state_change.transition_id,
+            response,
+        )
+    });
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(message: &str) -> StateChangeResponse {
+        StateChangeResponse {
+            message: message.to_string(),
+            transition_id: String::new(),
+            timestamp_ns: 0,
+            error_code: ErrorCode::Success as i32,
+            error_details: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_duplicate_gets_original_response() {
+        let mut cache = IdempotencyCache::new(2, 2);
+
+        assert_eq!(cache.claim("Model/a", "t1", &response("first")), None);
+        assert_eq!(
+            cache.claim("Model/a", "t1", &response("retry")),
+            Some(response("first"))
+        );
+        // The same id of another resource is a different transition
+        assert_eq!(cache.claim("Model/b", "t1", &response("other")), None);
+
+        cache.update("Model/a", "t1", response("processed"));
+        assert_eq!(
+            cache.claim("Model/a", "t1", &response("retry")),
+            Some(response("processed"))
+        );
+
+        cache.release("Model/b", "t1");
+        assert_eq!(cache.claim("Model/b", "t1", &response("again")), None);
+    }
+
+    #[test]
+    fn test_empty_transition_id_is_never_a_duplicate() {
+        let mut cache = IdempotencyCache::new(2, 2);
+        assert_eq!(cache.claim("Model/a", "", &response("first")), None);
+        assert_eq!(cache.claim("Model/a", "", &response("second")), None);
+        assert!(cache.resources.is_empty());
+    }
+
+    #[test]
+    fn test_cache_is_bounded() {
+        let mut cache = IdempotencyCache::new(2, 2);
+        cache.claim("Model/a", "t1", &response("1"));
+        cache.claim("Model/a", "t2", &response("2"));
+        // t1 is used again, so t2 is the least recently used id
+        assert!(cache.claim("Model/a", "t1", &response("1")).is_some());
+        cache.claim("Model/a", "t3", &response("3"));
+        assert!(cache.claim("Model/a", "t2", &response("2")).is_none());
+
+        // Model/a was used last, so Model/b is forgotten for Model/c
+        cache.claim("Model/b", "t1", &response("b"));
+        cache.claim("Model/a", "t9", &response("a"));
+        cache.claim("Model/c", "t1", &response("c"));
+        assert_eq!(cache.resources.len(), 2);
+        assert!(cache.claim("Model/b", "t1", &response("b")).is_none());
+    }
+}
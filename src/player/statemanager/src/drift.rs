@@ -0,0 +1,359 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Reconciliation of resource states edited in etcd
+//!
+//! StateManager writes the states it tracks to `/{type}/{name}/state`. When
+//! an operator or another tool edits such a key directly, the state in etcd
+//! and the one held by the state machine drift apart. [`watch_state_drift`]
+//! watches the state keys and resolves each difference according to the
+//! `state.drift_policy` setting:
+//! * `trust-etcd` - the state machine adopts the state found in etcd and
+//!   forgets resources whose state was deleted
+//! * `trust-memory` - the state of the state machine is written back to etcd
+//!
+//! States of resources the state machine does not track yet are adopted
+//! under both policies. A resource that changed state within the last watch
+//! interval is left alone, since its own write to etcd may still be pending.
+
+use crate::state_machine::StateMachine;
+use common::etcd::WatchEvent;
+use common::logd;
+use common::setting::{DriftPolicy, StateSettings};
+use common::statemanager::{ModelState, NodeState, PackageState, ResourceType, ScenarioState};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Prefixes of the state keys written by StateManager
+pub const STATE_PREFIXES: [&str; 4] = ["/scenario/", "/package/", "/model/", "/node/"];
+
+/// State of a resource as found in etcd
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EtcdState {
+    State(i32),
+    /// The value is not a state of the resource type
+    Invalid,
+    Deleted,
+}
+
+/// What to do about a state changed in etcd
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// etcd and the state machine agree
+    InSync,
+    /// Nothing can or should be done
+    Ignore,
+    /// Set the state machine to the state from etcd
+    Adopt(i32),
+    /// Write this state of the state machine back to etcd
+    Rewrite(i32),
+    /// Stop tracking the resource
+    Forget,
+}
+
+impl Resolution {
+    /// Label of the drift metric
+    fn as_str(&self) -> &'static str {
+        match self {
+            Resolution::InSync => "in_sync",
+            Resolution::Ignore => "ignore",
+            Resolution::Adopt(_) => "adopt",
+            Resolution::Rewrite(_) => "rewrite",
+            Resolution::Forget => "forget",
+        }
+    }
+}
+
+/// Resource of a state key, e.g. `/model/antipinch/state`
+pub fn parse_state_key(key: &str) -> Option<(ResourceType, String)> {
+    let mut parts = key.strip_prefix('/')?.split('/');
+    let (kind, name, leaf) = (parts.next()?, parts.next()?, parts.next()?);
+    if leaf != "state" || name.is_empty() || parts.next().is_some() {
+        return None;
+    }
+    let resource_type = match kind {
+        "scenario" => ResourceType::Scenario,
+        "package" => ResourceType::Package,
+        "model" => ResourceType::Model,
+        "node" => ResourceType::Node,
+        _ => return None,
+    };
+    Some((resource_type, name.to_string()))
+}
+
+/// State stored in etcd, with or without the enum prefix, e.g.
+/// `PACKAGE_STATE_RUNNING`, `Running` or `running`
+pub fn parse_state_value(resource_type: ResourceType, value: &str) -> Option<i32> {
+    let prefix = state_prefix(resource_type)?;
+    let mut name = value.trim().to_ascii_uppercase().replace('-', "_");
+    if !name.starts_with(prefix) {
+        name = format!("{}{}", prefix, name);
+    }
+    let state = match resource_type {
+        ResourceType::Scenario => ScenarioState::from_str_name(&name).map(|s| s as i32),
+        ResourceType::Package => PackageState::from_str_name(&name).map(|s| s as i32),
+        ResourceType::Model => ModelState::from_str_name(&name).map(|s| s as i32),
+        ResourceType::Node => NodeState::from_str_name(&name).map(|s| s as i32),
+        _ => None,
+    }?;
+    // Unspecified is the default of the enums, never a stored state
+    (state != 0).then_some(state)
+}
+
+/// State in the format StateManager writes it to etcd
+///
+/// Models are stored as `Running`, the other resources with the full enum
+/// name, e.g. `SCENARIO_STATE_WAITING`.
+pub fn state_value(resource_type: ResourceType, state: i32) -> Option<String> {
+    let name = match resource_type {
+        ResourceType::Scenario => ScenarioState::try_from(state).ok()?.as_str_name(),
+        ResourceType::Package => PackageState::try_from(state).ok()?.as_str_name(),
+        ResourceType::Node => NodeState::try_from(state).ok()?.as_str_name(),
+        ResourceType::Model => {
+            let name = ModelState::try_from(state).ok()?.as_str_name();
+            let short = name.strip_prefix(state_prefix(resource_type)?)?;
+            let mut chars = short.chars();
+            let first = chars.next()?;
+            return Some(first.to_string() + &chars.as_str().to_ascii_lowercase());
+        }
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+fn state_prefix(resource_type: ResourceType) -> Option<&'static str> {
+    match resource_type {
+        ResourceType::Scenario => Some("SCENARIO_STATE_"),
+        ResourceType::Package => Some("PACKAGE_STATE_"),
+        ResourceType::Model => Some("MODEL_STATE_"),
+        ResourceType::Node => Some("NODE_STATE_"),
+        _ => None,
+    }
+}
+
+/// Decide how to resolve a state changed in etcd
+///
+/// # Arguments
+/// * `policy` - Which side wins a difference
+/// * `memory` - State held by the state machine, if the resource is tracked
+/// * `recent` - Whether the state machine changed the state within the last
+///   watch interval
+/// * `etcd` - State found in etcd
+pub fn resolve(
+    policy: DriftPolicy,
+    memory: Option<i32>,
+    recent: bool,
+    etcd: EtcdState,
+) -> Resolution {
+    let memory = match memory {
+        Some(memory) => memory,
+        None => {
+            return match etcd {
+                EtcdState::State(state) => Resolution::Adopt(state),
+                _ => Resolution::Ignore,
+            }
+        }
+    };
+    if etcd == EtcdState::State(memory) {
+        return Resolution::InSync;
+    }
+    if recent {
+        return Resolution::Ignore;
+    }
+    match (policy, etcd) {
+        (DriftPolicy::TrustMemory, _) => Resolution::Rewrite(memory),
+        (DriftPolicy::TrustEtcd, EtcdState::State(state)) => Resolution::Adopt(state),
+        (DriftPolicy::TrustEtcd, EtcdState::Deleted) => Resolution::Forget,
+        (DriftPolicy::TrustEtcd, EtcdState::Invalid) => Resolution::Ignore,
+    }
+}
+
+/// Apply the changes of the state keys in etcd to the state machine
+///
+/// Runs until the owning task is aborted.
+pub async fn watch_state_drift(state_machine: Arc<StateMachine>, settings: StateSettings) {
+    let interval = Duration::from_millis(settings.watch_interval_ms);
+    let prefixes = STATE_PREFIXES.iter().map(|p| p.to_string()).collect();
+    let mut events = common::etcd::watch_prefixes(prefixes, interval);
+    logd!(
+        3,
+        "Watching state keys in etcd every {:?}, drift policy {:?}",
+        interval,
+        settings.drift_policy
+    );
+
+    while let Some(event) = events.recv().await {
+        let key = match &event {
+            WatchEvent::Put { key, .. } | WatchEvent::Delete { key } => key,
+        };
+        let Some((resource_type, name)) = parse_state_key(key) else {
+            continue;
+        };
+        let etcd = match &event {
+            WatchEvent::Put { value, .. } => parse_state_value(resource_type, value)
+                .map(EtcdState::State)
+                .unwrap_or(EtcdState::Invalid),
+            WatchEvent::Delete { .. } => EtcdState::Deleted,
+        };
+
+        let resolution = {
+            let memory = state_machine
+                .get_resource_state(&name, resource_type)
+                .map(|rs| {
+                    (
+                        rs.current_state,
+                        rs.last_transition_time.elapsed() < interval,
+                    )
+                });
+            let resolution = resolve(
+                settings.drift_policy,
+                memory.map(|(state, _)| state),
+                memory.is_some_and(|(_, recent)| recent),
+                etcd,
+            );
+            match resolution {
+                Resolution::Adopt(state) => {
+                    state_machine.apply_external_state(resource_type, &name, state)
+                }
+                Resolution::Forget => {
+                    state_machine.forget_resource(resource_type, &name);
+                }
+                _ => {}
+            }
+            if memory.is_some() && !matches!(resolution, Resolution::InSync) {
+                crate::metrics::record_state_drift(resource_type, resolution.as_str());
+            }
+            resolution
+        };
+
+        match resolution {
+            Resolution::InSync => {}
+            Resolution::Ignore => {
+                if etcd == EtcdState::Invalid {
+                    logd!(4, "Ignoring invalid state in etcd: {:?}", event);
+                }
+            }
+            Resolution::Adopt(_) | Resolution::Forget => {
+                logd!(3, "Applied state edited in etcd: {:?}", event);
+            }
+            Resolution::Rewrite(state) => {
+                let Some(value) = state_value(resource_type, state) else {
+                    continue;
+                };
+                logd!(3, "Restoring {} to {} after edit in etcd", key, value);
+                if let Err(e) = common::etcd::put(key, &value).await {
+                    logd!(4, "Failed to restore {}: {}", key, e);
+                }
+            }
+        }
+    }
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_state_key() {
+        assert_eq!(
+            parse_state_key("/model/antipinch-core/state"),
+            Some((ResourceType::Model, "antipinch-core".to_string()))
+        );
+        assert_eq!(
+            parse_state_key("/node/HPC/state"),
+            Some((ResourceType::Node, "HPC".to_string()))
+        );
+        assert_eq!(parse_state_key("/package/p1/models"), None);
+        assert_eq!(parse_state_key("/package//state"), None);
+        assert_eq!(parse_state_key("/volume/v1/state"), None);
+        assert_eq!(parse_state_key("/model/a/b/state"), None);
+    }
+
+    #[test]
+    fn test_state_values_round_trip() {
+        assert_eq!(
+            parse_state_value(ResourceType::Package, "PACKAGE_STATE_RUNNING"),
+            Some(PackageState::Running as i32)
+        );
+        assert_eq!(
+            parse_state_value(ResourceType::Package, "running"),
+            Some(PackageState::Running as i32)
+        );
+        assert_eq!(
+            parse_state_value(ResourceType::Node, "not-ready"),
+            Some(NodeState::NotReady as i32)
+        );
+        assert_eq!(parse_state_value(ResourceType::Model, "Sleeping"), None);
+        assert_eq!(parse_state_value(ResourceType::Model, "unspecified"), None);
+
+        assert_eq!(
+            state_value(ResourceType::Model, ModelState::Running as i32).as_deref(),
+            Some("Running")
+        );
+        assert_eq!(
+            state_value(ResourceType::Scenario, ScenarioState::Waiting as i32).as_deref(),
+            Some("SCENARIO_STATE_WAITING")
+        );
+        for (resource_type, state) in [
+            (ResourceType::Model, ModelState::Dead as i32),
+            (ResourceType::Package, PackageState::Degraded as i32),
+            (ResourceType::Node, NodeState::Ready as i32),
+        ] {
+            let value = state_value(resource_type, state).unwrap();
+            assert_eq!(parse_state_value(resource_type, &value), Some(state));
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        use DriftPolicy::{TrustEtcd, TrustMemory};
+        let running = ModelState::Running as i32;
+        let dead = ModelState::Dead as i32;
+
+        // Untracked resources are adopted under both policies
+        for policy in [TrustEtcd, TrustMemory] {
+            assert_eq!(
+                resolve(policy, None, false, EtcdState::State(dead)),
+                Resolution::Adopt(dead)
+            );
+            assert_eq!(
+                resolve(policy, None, false, EtcdState::Deleted),
+                Resolution::Ignore
+            );
+            assert_eq!(
+                resolve(policy, Some(dead), false, EtcdState::State(dead)),
+                Resolution::InSync
+            );
+            assert_eq!(
+                resolve(policy, Some(running), true, EtcdState::State(dead)),
+                Resolution::Ignore
+            );
+        }
+
+        assert_eq!(
+            resolve(TrustEtcd, Some(running), false, EtcdState::State(dead)),
+            Resolution::Adopt(dead)
+        );
+        assert_eq!(
+            resolve(TrustEtcd, Some(running), false, EtcdState::Deleted),
+            Resolution::Forget
+        );
+        assert_eq!(
+            resolve(TrustEtcd, Some(running), false, EtcdState::Invalid),
+            Resolution::Ignore
+        );
+        for etcd in [
+            EtcdState::State(dead),
+            EtcdState::Deleted,
+            EtcdState::Invalid,
+        ] {
+            assert_eq!(
+                resolve(TrustMemory, Some(running), false, etcd),
+                Resolution::Rewrite(running)
+            );
+        }
+    }
+}
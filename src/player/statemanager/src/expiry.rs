@@ -0,0 +1,238 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Expiry of scenarios with `ttlSeconds`
+//!
+//! When ApiServer applies a scenario, the time it expires is stored at
+//! `/scenario/<name>/expires_at` in unix seconds. Scenarios without
+//! `ttlSeconds` have no such key, and applying a scenario again starts its
+//! time over.
+//!
+//! [`run`] looks for expired scenarios every few seconds. An expired scenario
+//! is moved to Expired, its workloads are terminated by ActionController, its
+//! filter is removed from FilterGateway and the `ScenarioExpired` event is
+//! published. With `onExpiry: withdraw`, the default, its artifact is removed
+//! from etcd as well. With `onExpiry: disable` the artifact is kept, but the
+//! scenario is not triggered again until it is applied again.
+
+use crate::grpc::sender;
+use crate::state_machine::StateMachine;
+use common::actioncontroller::TriggerActionRequest;
+use common::eventbus::{Event, EventKind};
+use common::logd;
+use common::spec::artifact::{scenario::OnExpiry, Scenario};
+use common::spec::namespace;
+use common::statemanager::ScenarioState;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Interval between two looks for expired scenarios
+const SWEEP_INTERVAL: Duration = Duration::from_secs(5);
+const SCENARIO_KEY_PREFIX: &str = "/scenario/";
+const EXPIRES_AT_SUFFIX: &str = "/expires_at";
+
+/// Key of the expiry time of a scenario
+pub fn expires_at_key(scenario_name: &str) -> String {
+    format!(
+        "{}{}{}",
+        SCENARIO_KEY_PREFIX, scenario_name, EXPIRES_AT_SUFFIX
+    )
+}
+
+/// Scenario of an expiry time key, `None` for other keys
+pub fn parse_expires_at_key(key: &str) -> Option<&str> {
+    key.strip_prefix(SCENARIO_KEY_PREFIX)?
+        .strip_suffix(EXPIRES_AT_SUFFIX)
+        .filter(|name| !name.is_empty())
+}
+
+/// Scenarios whose expiry time is not after `now`
+///
+/// ### Parameters
+/// * `entries: &[(String, String)]` - keys and values under `/scenario/`
+/// * `now: u64` - unix seconds
+pub fn due(entries: &[(String, String)], now: u64) -> Vec<String> {
+    entries
+        .iter()
+        .filter_map(|(key, value)| {
+            let name = parse_expires_at_key(key)?;
+            let expires_at = value.trim().parse::<u64>().ok()?;
+            (expires_at <= now).then(|| name.to_string())
+        })
+        .collect()
+}
+
+fn unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Artifact of a scenario as stored in etcd, with its yaml
+async fn read_scenario(scenario_name: &str) -> Option<(Scenario, String)> {
+    let key = namespace::qualified_key("Scenario", scenario_name);
+    let yaml = common::etcd::get(&key).await.ok()?;
+    match serde_yaml::from_str::<Scenario>(&yaml) {
+        Ok(scenario) => Some((scenario, yaml)),
+        Err(e) => {
+            logd!(4, "Failed to parse scenario {}: {:?}", scenario_name, e);
+            None
+        }
+    }
+}
+
+/// Start the time of a scenario ApiServer has just applied
+///
+/// The expiry time is stored for a scenario with `ttlSeconds` and removed
+/// for one without.
+pub async fn track(scenario_name: &str) {
+    let key = expires_at_key(scenario_name);
+    let ttl = read_scenario(scenario_name)
+        .await
+        .and_then(|(scenario, _)| scenario.get_ttl());
+    match ttl {
+        Some(ttl) => {
+            let expires_at = unix_secs().saturating_add(ttl.as_secs());
+            if let Err(e) = common::etcd::put(&key, &expires_at.to_string()).await {
+                logd!(
+                    4,
+                    "Failed to store expiry of scenario {}: {:?}",
+                    scenario_name,
+                    e
+                );
+            } else {
+                logd!(
+                    2,
+                    "Scenario {} expires in {}s",
+                    scenario_name,
+                    ttl.as_secs()
+                );
+            }
+        }
+        None => {
+            let _ = common::etcd::delete(&key).await;
+        }
+    }
+}
+
+/// Expire the scenarios that lived past their `ttlSeconds`
+pub async fn run(state_machine: Arc<StateMachine>) {
+    loop {
+        tokio::time::sleep(SWEEP_INTERVAL).await;
+        let entries = match common::etcd::get_all_with_prefix(SCENARIO_KEY_PREFIX).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                logd!(4, "Failed to read scenario expiry times: {}", e);
+                continue;
+            }
+        };
+        for scenario_name in due(&entries, unix_secs()) {
+            expire(&state_machine, &scenario_name).await;
+        }
+    }
+}
+
+/// Terminate an expired scenario and withdraw or disable it
+async fn expire(state_machine: &StateMachine, scenario_name: &str) {
+    // Removed first, so a scenario failing to expire is not retried forever
+    let _ = common::etcd::delete(&expires_at_key(scenario_name)).await;
+    if let Err(e) = state_machine.expire(scenario_name) {
+        logd!(3, "{}", e);
+        return;
+    }
+    let expired = ScenarioState::Expired.as_str_name();
+    let state_key = format!("/scenario/{}/state", scenario_name);
+    if let Err(e) = common::etcd::put(&state_key, expired).await {
+        logd!(4, "Failed to save scenario state to ETCD: {:?}", e);
+    }
+
+    let request = TriggerActionRequest {
+        scenario_name: scenario_name.to_string(),
+        dry_run: false,
+        action: "terminate".to_string(),
+    };
+    if let Err(e) = sender::trigger_action(request).await {
+        logd!(
+            4,
+            "Failed to terminate expired scenario {}: {}",
+            scenario_name,
+            e.message()
+        );
+    }
+
+    let artifact = read_scenario(scenario_name).await;
+    let on_expiry = artifact
+        .as_ref()
+        .map(|(scenario, _)| scenario.get_on_expiry())
+        .unwrap_or_default();
+    if let Some((_, yaml)) = artifact {
+        if on_expiry == OnExpiry::Withdraw {
+            let key = namespace::qualified_key("Scenario", scenario_name);
+            if let Err(e) = common::etcd::delete(&key).await {
+                logd!(4, "Failed to withdraw scenario {}: {:?}", scenario_name, e);
+            }
+        }
+        if let Err(e) = sender::withdraw_scenario(yaml).await {
+            logd!(
+                4,
+                "Failed to remove the filter of scenario {}: {}",
+                scenario_name,
+                e.message()
+            );
+        }
+    }
+
+    let on_expiry = match on_expiry {
+        OnExpiry::Withdraw => "withdraw",
+        OnExpiry::Disable => "disable",
+    };
+    common::eventbus::publish(
+        Event::new(
+            EventKind::ScenarioExpired,
+            "statemanager",
+            scenario_name,
+            format!("Scenario expired and was terminated ({})", on_expiry),
+        )
+        .with_attribute("state", expired)
+        .with_attribute("on_expiry", on_expiry),
+    );
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expires_at_keys() {
+        assert_eq!(expires_at_key("capture"), "/scenario/capture/expires_at");
+        assert_eq!(
+            parse_expires_at_key("/scenario/diag/capture/expires_at"),
+            Some("diag/capture")
+        );
+        assert_eq!(parse_expires_at_key("/scenario/capture/state"), None);
+        assert_eq!(parse_expires_at_key("/scenario//expires_at"), None);
+    }
+
+    #[test]
+    fn test_due_scenarios() {
+        let entries = vec![
+            ("/scenario/past/expires_at".to_string(), "100".to_string()),
+            ("/scenario/now/expires_at".to_string(), "200".to_string()),
+            ("/scenario/later/expires_at".to_string(), "300".to_string()),
+            (
+                "/scenario/broken/expires_at".to_string(),
+                "soon".to_string(),
+            ),
+            (
+                "/scenario/past/state".to_string(),
+                "SCENARIO_STATE_WAITING".to_string(),
+            ),
+        ];
+        assert_eq!(due(&entries, 200), vec!["past", "now"]);
+        assert!(due(&entries, 50).is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Flap detection of resources
+//!
+//! Every transition of a resource is counted over a sliding window of
+//! `state.flap_window_secs`. The count is the flap score of the resource and
+//! is shown as `flap_score` in its metadata.
+//!
+//! A scenario whose next transition would bring its score to
+//! `state.flap_threshold` keeps oscillating, e.g. between Allowed and Denied,
+//! and starting its action again each time. It is moved to Quarantined
+//! instead and takes no transition until it is released by the
+//! `ResetQuarantine` call, which also clears its score.
+
+use common::setting::StateSettings;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Transition times of each resource within the window
+#[derive(Debug, Default)]
+pub struct FlapDetector {
+    transitions: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl FlapDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn window(settings: &StateSettings) -> Duration {
+        Duration::from_secs(settings.flap_window_secs)
+    }
+
+    /// Count a transition of a resource at `now`
+    ///
+    /// ### Returns
+    /// * `usize` - flap score of the resource, this transition included
+    pub fn record(&self, resource_key: &str, now: Instant, settings: &StateSettings) -> usize {
+        let window = Self::window(settings);
+        let mut transitions = self.transitions.lock().unwrap_or_else(|e| e.into_inner());
+        // Resources that went quiet are forgotten along the way
+        transitions.retain(|key, times| {
+            key == resource_key
+                || times
+                    .back()
+                    .is_some_and(|last| now.duration_since(*last) < window)
+        });
+        let times = transitions.entry(resource_key.to_string()).or_default();
+        times.push_back(now);
+        while times
+            .front()
+            .is_some_and(|first| now.duration_since(*first) >= window)
+        {
+            times.pop_front();
+        }
+        times.len()
+    }
+
+    /// Transitions of a resource within the window ending at `now`
+    pub fn score(&self, resource_key: &str, now: Instant, settings: &StateSettings) -> usize {
+        let window = Self::window(settings);
+        self.transitions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(resource_key)
+            .map(|times| {
+                times
+                    .iter()
+                    .filter(|time| now.duration_since(**time) < window)
+                    .count()
+            })
+            .unwrap_or(0)
+    }
+
+    /// Forget the transitions of a resource
+    pub fn reset(&self, resource_key: &str) {
+        self.transitions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(resource_key);
+    }
+}
+
+/// Whether a score quarantines a scenario
+pub fn is_flapping(score: usize, settings: &StateSettings) -> bool {
+    settings.flap_threshold > 0 && score >= settings.flap_threshold
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flap_score_over_sliding_window() {
+        let settings = StateSettings {
+            flap_window_secs: 10,
+            flap_threshold: 3,
+            ..Default::default()
+        };
+        let detector = FlapDetector::new();
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(detector.record("scenario::a", at(0), &settings), 1);
+        assert_eq!(detector.record("scenario::a", at(4), &settings), 2);
+        assert!(!is_flapping(2, &settings));
+        // The first transition left the window
+        assert_eq!(detector.record("scenario::a", at(10), &settings), 2);
+        assert_eq!(detector.record("scenario::a", at(12), &settings), 3);
+        assert!(is_flapping(3, &settings));
+        assert_eq!(detector.score("scenario::a", at(15), &settings), 2);
+
+        // Other resources are counted apart and quiet ones dropped
+        assert_eq!(detector.record("scenario::b", at(30), &settings), 1);
+        assert_eq!(detector.score("scenario::a", at(30), &settings), 0);
+        detector.reset("scenario::b");
+        assert_eq!(detector.score("scenario::b", at(30), &settings), 0);
+
+        let disabled = StateSettings {
+            flap_threshold: 0,
+            ..Default::default()
+        };
+        assert!(!is_flapping(100, &disabled));
+    }
+}
@@ -13,16 +13,18 @@
 //! including state changes, resource queries, recovery management, and event notifications.
 pub mod timpani;
 
+use common::container_events::relay_container_events;
+use common::eventbus::{Event, EventKind};
 use common::logd;
-use common::monitoringserver::{ContainerList, SendContainerListResponse};
+use common::monitoringserver::{
+    ContainerEventAck, ContainerEventList, ContainerList, SendContainerListResponse,
+};
 use common::statemanager::{
     state_manager_connection_server::StateManagerConnection,
     Action,
     ErrorCode,
-    // // State Query API message types
-    // ResourceStateRequest, ResourceStateResponse,
-    // ResourceStateHistoryRequest, ResourceStateHistoryResponse,
-    // ListResourcesByStateRequest, ListResourcesByStateResponse,
+    ListResourceStatesRequest,
+    ListResourceStatesResponse,
 
     // // State Management API message types
     // UpdateDesiredStateRequest, TriggerStateTransitionRequest, ForceSynchronizationRequest,
@@ -32,17 +34,33 @@ use common::statemanager::{
     // RecoveryResponse, RecoveryStatusResponse,
 
     // // Event and Notification API message types
-    // StateChangeSubscriptionRequest, StateChangeEvent,
     // AcknowledgeAlertRequest, AlertResponse,
     // GetPendingAlertsRequest, GetPendingAlertsResponse,
     OffloadingRequest,
     OffloadingResponse,
+    ResetQuarantineRequest,
+    ResetQuarantineResponse,
+    ResourceStateHistoryRequest,
+    ResourceStateHistoryResponse,
+    ResourceStateRequest,
+    ResourceStateResponse,
     ResourceType,
+    ScenarioState,
     StateChange,
+    StateChangeEvent,
     StateChangeResponse,
+    StateChangeSubscriptionRequest,
 };
-use tokio::sync::mpsc;
-use tonic::{Request, Status};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Status, Streaming};
+
+use crate::state_machine::StateMachine;
+
+/// Capacity of the outbound queue of each state change subscription
+const SUBSCRIPTION_QUEUE_CAPACITY: usize = 64;
 
 /// StateManager gRPC service handler.
 ///
@@ -64,13 +82,21 @@ pub struct StateManagerReceiver {
     /// Channel sender for StateChange messages from various components.
     /// Used to forward state transition requests to the StateManager's state machine engine.
     pub tx_state_change: mpsc::Sender<StateChange>,
+
+    /// State machine of the StateManager engine.
+    /// Read by state queries and subscriptions; the receiver only changes it
+    /// to release quarantined scenarios.
+    pub state_machine: Arc<StateMachine>,
 }
 
 #[tonic::async_trait]
 impl StateManagerConnection for StateManagerReceiver {
     /// Stream type for state change event subscriptions.
     /// Uses ReceiverStream to provide async streaming of state change events to subscribers.
-    /// type SubscribeToStateChangesStream = ReceiverStream<Result<StateChangeEvent, Status>>;
+    type SubscribeStateChangesStream = ReceiverStream<Result<StateChangeEvent, Status>>;
+    /// Stream type for acknowledgements of streamed container events.
+    type StreamContainerEventsStream = ReceiverStream<Result<ContainerEventAck, Status>>;
+
     /// Handles action requests (legacy implementation).
     ///
     /// # Arguments
@@ -120,7 +146,7 @@ impl StateManagerConnection for StateManagerReceiver {
     ) -> Result<tonic::Response<SendContainerListResponse>, Status> {
         let req: ContainerList = request.into_inner();
 
-        match self.tx.send(req).await {
+        match crate::ingest::send(&self.tx, "container_list", req).await {
             Ok(_) => Ok(tonic::Response::new(SendContainerListResponse {
                 resp: "Successfully processed ContainerList".to_string(),
             })),
@@ -171,8 +197,12 @@ impl StateManagerConnection for StateManagerReceiver {
         &self,
         request: Request<StateChange>,
     ) -> Result<tonic::Response<StateChangeResponse>, Status> {
-        let req = request.into_inner();
+        let mut req = request.into_inner();
         let transition_id = req.transition_id.clone();
+        // The trace of the request follows the change through the queue
+        if req.trace_id.is_empty() {
+            req.trace_id = common::trace::current_trace_id().unwrap_or_default();
+        }
 
         // 🔍 COMMENT 5: StateManager receiving scenario state change requests
         // This method receives state change requests from multiple components:
@@ -212,24 +242,36 @@ impl StateManagerConnection for StateManagerReceiver {
         );
         logd!(1, "  ID: {}, Source: {}", req.transition_id, req.source);
 
+        // Generate ASIL-compliant success response
+        let accepted = StateChangeResponse {
+            message: "StateChange successfully received and queued for processing".to_string(),
+            transition_id: transition_id.clone(), // Preserve original ID for tracking
+            timestamp_ns: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos() as i64, // Nanosecond precision for ASIL
+            error_code: ErrorCode::Success as i32,
+            error_details: String::new(), // No error details for success
+        };
+
+        // A retried StateChange gets the result of its first delivery
+        if let Some(original) = crate::dedup::claim(&req, &accepted) {
+            logd!(
+                2,
+                "Duplicate StateChange {} for {} ignored",
+                transition_id,
+                req.resource_name
+            );
+            return Ok(tonic::Response::new(original));
+        }
+        let claimed = req.clone();
+
         // Forward StateChange to StateManager's state machine engine
-        match self.tx_state_change.send(req).await {
-            Ok(_) => {
-                // Generate ASIL-compliant success response
-                Ok(tonic::Response::new(StateChangeResponse {
-                    message: "StateChange successfully received and queued for processing"
-                        .to_string(),
-                    transition_id, // Preserve original ID for tracking
-                    timestamp_ns: std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap_or_default()
-                        .as_nanos() as i64, // Nanosecond precision for ASIL
-                    error_code: ErrorCode::Success as i32,
-                    error_details: String::new(), // No error details for success
-                }))
-            }
+        match crate::ingest::send(&self.tx_state_change, "state_change", req).await {
+            Ok(_) => Ok(tonic::Response::new(accepted)),
             Err(e) => {
                 // Channel send failed - StateManager unavailable or overloaded
+                crate::dedup::release(&claimed);
                 logd!(5, "Failed to forward StateChange to StateManager: {e}");
                 Ok(tonic::Response::new(StateChangeResponse {
                     message: "StateManager service unavailable".to_string(),
@@ -245,6 +287,206 @@ impl StateManagerConnection for StateManagerReceiver {
         }
     }
 
+    /// Returns the last transitions of a resource, oldest first.
+    ///
+    /// Entries come from the bounded history kept under `/history/` and can be
+    /// narrowed by a time range; `limit` keeps only the newest entries.
+    async fn get_resource_state_history(
+        &self,
+        request: Request<ResourceStateHistoryRequest>,
+    ) -> Result<tonic::Response<ResourceStateHistoryResponse>, Status> {
+        let req = request.into_inner();
+
+        let resource_type = match ResourceType::try_from(req.resource_type) {
+            Ok(ResourceType::Unspecified) | Err(_) => {
+                return Err(Status::invalid_argument(format!(
+                    "Invalid resource type: {}",
+                    req.resource_type
+                )));
+            }
+            Ok(resource_type) => resource_type,
+        };
+        if req.resource_name.trim().is_empty() {
+            return Err(Status::invalid_argument("Resource name cannot be empty"));
+        }
+
+        let history = crate::history::load(resource_type, &req.resource_name)
+            .await
+            .map_err(|e| {
+                logd!(
+                    4,
+                    "Failed to read history of '{}': {}",
+                    req.resource_name,
+                    e
+                );
+                Status::unavailable(format!("Cannot read state history: {e}"))
+            })?;
+        let entries = crate::history::select(
+            history,
+            req.start_time_ns,
+            req.end_time_ns,
+            req.limit.max(0) as usize,
+        );
+
+        Ok(tonic::Response::new(ResourceStateHistoryResponse {
+            message: format!(
+                "{} transition(s) of {} {}",
+                entries.len(),
+                self.resource_type_to_string(req.resource_type),
+                req.resource_name
+            ),
+            history: entries.iter().map(|entry| entry.to_proto()).collect(),
+            success: true,
+        }))
+    }
+
+    /// Returns the current state of one resource.
+    ///
+    /// The state is read from the in-memory state machine, so it reflects
+    /// transitions as soon as the StateManager has processed them.
+    ///
+    /// # Returns
+    /// * `success: false` - the resource has no tracked state yet
+    async fn get_resource_state(
+        &self,
+        request: Request<ResourceStateRequest>,
+    ) -> Result<tonic::Response<ResourceStateResponse>, Status> {
+        let req = request.into_inner();
+        let resource_type = parse_resource_type(req.resource_type)
+            .map_err(Status::invalid_argument)?
+            .ok_or_else(|| Status::invalid_argument("Resource type must be specified"))?;
+        if req.resource_name.trim().is_empty() {
+            return Err(Status::invalid_argument("Resource name cannot be empty"));
+        }
+
+        let resource_state = self
+            .state_machine
+            .get_resource_state_proto(&req.resource_name, resource_type);
+        let message = match &resource_state {
+            Some(state) => format!(
+                "{} {} is {}",
+                self.resource_type_to_string(req.resource_type),
+                req.resource_name,
+                state.current_state
+            ),
+            None => format!(
+                "{} {} has no tracked state",
+                self.resource_type_to_string(req.resource_type),
+                req.resource_name
+            ),
+        };
+        Ok(tonic::Response::new(ResourceStateResponse {
+            success: resource_state.is_some(),
+            resource_state,
+            message,
+        }))
+    }
+
+    /// Lists the current states of resources.
+    ///
+    /// Resources can be filtered by type, state and node; `limit` keeps the
+    /// first resources sorted by type and name while `total_count` still
+    /// counts every match.
+    async fn list_resource_states(
+        &self,
+        request: Request<ListResourceStatesRequest>,
+    ) -> Result<tonic::Response<ListResourceStatesResponse>, Status> {
+        let req = request.into_inner();
+        let resource_type =
+            parse_resource_type(req.resource_type).map_err(Status::invalid_argument)?;
+
+        let mut resources =
+            self.state_machine
+                .list_resource_states(resource_type, &req.state, &req.node);
+        let total_count = resources.len() as i32;
+        if req.limit > 0 {
+            resources.truncate(req.limit as usize);
+        }
+
+        Ok(tonic::Response::new(ListResourceStatesResponse {
+            message: format!("{} of {} resource(s)", resources.len(), total_count),
+            resources,
+            success: true,
+            total_count,
+        }))
+    }
+
+    /// Releases a scenario quarantined for flapping.
+    ///
+    /// The scenario goes back to Waiting, which is stored in etcd, and the
+    /// release is announced on the event bus.
+    ///
+    /// # Returns
+    /// * `success: false` - the scenario is not quarantined
+    async fn reset_quarantine(
+        &self,
+        request: Request<ResetQuarantineRequest>,
+    ) -> Result<tonic::Response<ResetQuarantineResponse>, Status> {
+        let req = request.into_inner();
+        if req.scenario_name.trim().is_empty() {
+            return Err(Status::invalid_argument("Scenario name cannot be empty"));
+        }
+        let reason = if req.reason.trim().is_empty() {
+            "manual reset".to_string()
+        } else {
+            req.reason
+        };
+
+        let result = self
+            .state_machine
+            .reset_quarantine(&req.scenario_name, &reason);
+        let state = self
+            .state_machine
+            .get_resource_state_proto(&req.scenario_name, ResourceType::Scenario)
+            .map(|state| state.current_state)
+            .unwrap_or_default();
+        if let Err(message) = result {
+            return Ok(tonic::Response::new(ResetQuarantineResponse {
+                success: false,
+                message,
+                state,
+            }));
+        }
+
+        let stored_state = ScenarioState::Waiting.as_str_name();
+        let etcd_key = format!("/scenario/{}/state", req.scenario_name);
+        if let Err(e) = common::etcd::put(&etcd_key, stored_state).await {
+            logd!(4, "Failed to save scenario state to ETCD: {:?}", e);
+        }
+        common::eventbus::publish(
+            Event::new(
+                EventKind::ScenarioReleased,
+                "statemanager",
+                &req.scenario_name,
+                format!("Released from quarantine: {}", reason),
+            )
+            .with_attribute("state", stored_state),
+        );
+        Ok(tonic::Response::new(ResetQuarantineResponse {
+            success: true,
+            message: format!("Scenario {} released from quarantine", req.scenario_name),
+            state,
+        }))
+    }
+
+    /// Streams the state changes of resources as they happen.
+    ///
+    /// Only changes matching the type, name and node of the request are
+    /// sent. A subscriber that cannot keep up skips the oldest changes; the
+    /// stream ends when the client disconnects.
+    async fn subscribe_state_changes(
+        &self,
+        request: Request<StateChangeSubscriptionRequest>,
+    ) -> Result<tonic::Response<Self::SubscribeStateChangesStream>, Status> {
+        let req = request.into_inner();
+        parse_resource_type(req.resource_type).map_err(Status::invalid_argument)?;
+
+        let events = self.state_machine.subscribe();
+        let (tx_event, rx_event) = mpsc::channel(SUBSCRIPTION_QUEUE_CAPACITY);
+        tokio::spawn(relay_state_changes(events, req, tx_event));
+        Ok(tonic::Response::new(ReceiverStream::new(rx_event)))
+    }
+
     /// Handles TriggerOffloading requests from PolicyManager.
     ///
     /// This method receives offloading requests when resource thresholds are exceeded
@@ -329,6 +571,91 @@ impl StateManagerConnection for StateManagerReceiver {
             }
         }
     }
+
+    /// Handles a stream of incremental container events from nodeagent.
+    ///
+    /// Replaces the periodic `SendChangedContainerList` push with a long-lived
+    /// stream, so container state changes reach the StateManager as soon as
+    /// nodeagent observes them.
+    ///
+    /// # Arguments
+    /// * `request` - gRPC request carrying the inbound ContainerEventList stream
+    ///
+    /// # Returns
+    /// * `Result<tonic::Response<Self::StreamContainerEventsStream>, Status>` - Ack stream, one ack per batch
+    ///
+    /// # Processing Flow
+    /// 1. Apply each batch of events to the ContainerList kept for this stream
+    /// 2. Forward the updated ContainerList through the existing container channel
+    /// 3. Acknowledge the batch with the number of applied events
+    async fn stream_container_events(
+        &self,
+        request: Request<Streaming<ContainerEventList>>,
+    ) -> Result<tonic::Response<Self::StreamContainerEventsStream>, Status> {
+        let inbound = request.into_inner();
+        let (tx_ack, rx_ack) = mpsc::channel(16);
+        let tx = self.tx.clone();
+        tokio::spawn(async move {
+            relay_container_events(
+                inbound,
+                |list| crate::ingest::send(&tx, "container_list", list),
+                tx_ack,
+            )
+            .await
+        });
+        Ok(tonic::Response::new(ReceiverStream::new(rx_ack)))
+    }
+}
+
+/// Parses the resource type filter of a query.
+///
+/// # Returns
+/// * `Ok(None)` - the type is unspecified, i.e. every type matches
+/// * `Err(String)` - the value is not a resource type
+fn parse_resource_type(resource_type: i32) -> Result<Option<ResourceType>, String> {
+    match ResourceType::try_from(resource_type) {
+        Ok(ResourceType::Unspecified) => Ok(None),
+        Ok(resource_type) => Ok(Some(resource_type)),
+        Err(_) => Err(format!("Invalid resource type: {resource_type}")),
+    }
+}
+
+/// Whether a state change event matches the filters of a subscription.
+fn subscription_matches(
+    request: &StateChangeSubscriptionRequest,
+    event: &StateChangeEvent,
+) -> bool {
+    let Some(state) = &event.resource_state else {
+        return false;
+    };
+    (request.resource_type == ResourceType::Unspecified as i32
+        || request.resource_type == state.resource_type)
+        && (request.resource_name.is_empty() || request.resource_name == state.resource_name)
+        && (request.node.is_empty() || request.node == state.node)
+}
+
+/// Forwards the matching state changes to a subscriber until it disconnects.
+async fn relay_state_changes(
+    mut events: broadcast::Receiver<StateChangeEvent>,
+    request: StateChangeSubscriptionRequest,
+    tx_event: mpsc::Sender<Result<StateChangeEvent, Status>>,
+) {
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                logd!(
+                    4,
+                    "State change subscriber fell behind, {skipped} change(s) skipped"
+                );
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        if subscription_matches(&request, &event) && tx_event.send(Ok(event)).await.is_err() {
+            break;
+        }
+    }
 }
 
 impl StateManagerReceiver {
@@ -412,6 +739,7 @@ mod tests {
     use super::*;
     use common::monitoringserver::ContainerList;
     use common::statemanager::{ErrorCode, ResourceType, StateChange};
+    use tokio_stream::StreamExt;
     use tonic::Request;
 
     #[test]
@@ -421,6 +749,7 @@ mod tests {
         let receiver = StateManagerReceiver {
             tx,
             tx_state_change,
+            state_machine: Default::default(),
         };
 
         // Valid state change
@@ -432,6 +761,8 @@ mod tests {
             transition_id: "t1".to_string(),
             timestamp_ns: 1,
             source: "unittest".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: String::new(),
         };
         assert!(receiver.validate_state_change(&sc).is_ok());
 
@@ -453,6 +784,51 @@ mod tests {
         assert_eq!(receiver.resource_type_to_string(9999), "Unknown");
     }
 
+    #[tokio::test]
+    async fn test_relay_container_events_forwards_full_list() {
+        use common::monitoringserver::{ContainerEvent, ContainerEventType, ContainerInfo};
+
+        let event = |id: &str, event_type: ContainerEventType| ContainerEvent {
+            event_type: event_type as i32,
+            container: Some(ContainerInfo {
+                id: id.to_string(),
+                ..Default::default()
+            }),
+            timestamp_ns: 1,
+        };
+        let (tx, mut rx) = mpsc::channel::<ContainerList>(4);
+        let (tx_ack, mut rx_ack) = mpsc::channel(4);
+        let inbound = tokio_stream::iter(vec![
+            Ok(ContainerEventList {
+                node_name: "n1".to_string(),
+                events: vec![event("c1", ContainerEventType::Created)],
+            }),
+            Ok(ContainerEventList {
+                node_name: "n1".to_string(),
+                events: vec![event("c1", ContainerEventType::Died)],
+            }),
+            Err(Status::cancelled("agent gone")),
+            Ok(ContainerEventList::default()),
+        ]);
+
+        relay_container_events(
+            inbound,
+            |list| crate::ingest::send(&tx, "container_list", list),
+            tx_ack,
+        )
+        .await;
+        drop(tx);
+
+        assert_eq!(rx.recv().await.unwrap().containers.len(), 1);
+        let second = rx.recv().await.unwrap();
+        assert_eq!(second.node_name, "n1");
+        assert_eq!(second.containers.len(), 1);
+        assert!(rx.recv().await.is_none());
+        assert_eq!(rx_ack.recv().await.unwrap().unwrap().applied, 1);
+        assert_eq!(rx_ack.recv().await.unwrap().unwrap().applied, 1);
+        assert!(rx_ack.recv().await.is_none());
+    }
+
     #[tokio::test]
     async fn test_send_changed_container_list_success_and_failure() {
         // Success path: receiver present
@@ -461,6 +837,7 @@ mod tests {
         let receiver = StateManagerReceiver {
             tx: tx.clone(),
             tx_state_change: tx_state_change.clone(),
+            state_machine: Default::default(),
         };
 
         let cl = ContainerList {
@@ -476,6 +853,7 @@ mod tests {
         let receiver2 = StateManagerReceiver {
             tx: bad_tx,
             tx_state_change: tx_state_change.clone(),
+            state_machine: Default::default(),
         };
         let cl2 = ContainerList {
             node_name: "n2".to_string(),
@@ -494,6 +872,7 @@ mod tests {
         let receiver = StateManagerReceiver {
             tx: tx.clone(),
             tx_state_change: tx_state_change.clone(),
+            state_machine: Default::default(),
         };
 
         let cl = ContainerList {
@@ -513,6 +892,7 @@ mod tests {
         let receiver2 = StateManagerReceiver {
             tx: bad_tx,
             tx_state_change,
+            state_machine: Default::default(),
         };
         let cl2 = ContainerList {
             node_name: "n2".to_string(),
@@ -537,6 +917,7 @@ mod tests {
         let receiver = StateManagerReceiver {
             tx: tx.clone(),
             tx_state_change: tx_state_change.clone(),
+            state_machine: Default::default(),
         };
 
         let sc = StateChange {
@@ -547,6 +928,8 @@ mod tests {
             transition_id: "t2".to_string(),
             timestamp_ns: 1,
             source: "unittest".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: String::new(),
         };
 
         let resp = receiver.send_state_change(Request::new(sc.clone())).await;
@@ -564,6 +947,7 @@ mod tests {
         let receiver2 = StateManagerReceiver {
             tx: tx.clone(),
             tx_state_change: bad_tx,
+            state_machine: Default::default(),
         };
 
         let sc2 = StateChange {
@@ -585,6 +969,7 @@ mod tests {
         let receiver = StateManagerReceiver {
             tx,
             tx_state_change,
+            state_machine: Default::default(),
         };
 
         let action = common::statemanager::Action {
@@ -605,6 +990,7 @@ mod tests {
         let receiver = StateManagerReceiver {
             tx,
             tx_state_change,
+            state_machine: Default::default(),
         };
 
         // Build an invalid StateChange (timestamp_ns <= 0)
@@ -616,6 +1002,8 @@ mod tests {
             transition_id: "bad-tid".to_string(),
             timestamp_ns: 0,
             source: "unittest".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: String::new(),
         };
 
         let resp = receiver.send_state_change(Request::new(sc)).await;
@@ -631,6 +1019,7 @@ mod tests {
         let receiver = StateManagerReceiver {
             tx,
             tx_state_change,
+            state_machine: Default::default(),
         };
 
         let sc = StateChange {
@@ -641,6 +1030,8 @@ mod tests {
             transition_id: "tid-invalid".to_string(),
             timestamp_ns: 1,
             source: "unittest".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: String::new(),
         };
 
         let resp = receiver.send_state_change(Request::new(sc)).await;
@@ -649,6 +1040,149 @@ mod tests {
         assert_eq!(inner.error_code, ErrorCode::InvalidRequest as i32);
     }
 
+    #[tokio::test]
+    async fn test_get_resource_state_history_rejects_invalid_request() {
+        let (tx, _rx) = mpsc::channel::<ContainerList>(1);
+        let (tx_state_change, _rx2) = mpsc::channel::<StateChange>(1);
+        let receiver = StateManagerReceiver {
+            tx,
+            tx_state_change,
+            state_machine: Default::default(),
+        };
+
+        let unspecified = ResourceStateHistoryRequest {
+            resource_type: ResourceType::Unspecified as i32,
+            resource_name: "res".to_string(),
+            ..Default::default()
+        };
+        let status = receiver
+            .get_resource_state_history(Request::new(unspecified))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        let unnamed = ResourceStateHistoryRequest {
+            resource_type: ResourceType::Scenario as i32,
+            resource_name: " ".to_string(),
+            ..Default::default()
+        };
+        let status = receiver
+            .get_resource_state_history(Request::new(unnamed))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    fn query_receiver() -> StateManagerReceiver {
+        let (tx, _rx) = mpsc::channel::<ContainerList>(1);
+        let (tx_state_change, _rx2) = mpsc::channel::<StateChange>(1);
+        StateManagerReceiver {
+            tx,
+            tx_state_change,
+            state_machine: Default::default(),
+        }
+    }
+
+    fn waiting_scenario(name: &str) -> StateChange {
+        StateChange {
+            resource_type: ResourceType::Scenario as i32,
+            resource_name: name.to_string(),
+            current_state: "Idle".to_string(),
+            target_state: "Waiting".to_string(),
+            transition_id: format!("{name}-t1"),
+            timestamp_ns: 1,
+            source: "unittest".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_and_list_resource_states() {
+        let receiver = query_receiver();
+        {
+            let state_machine = &receiver.state_machine;
+            state_machine.process_state_change(waiting_scenario("scenario-a"));
+            state_machine.process_state_change(waiting_scenario("scenario-b"));
+        }
+
+        let found = receiver
+            .get_resource_state(Request::new(ResourceStateRequest {
+                resource_type: ResourceType::Scenario as i32,
+                resource_name: "scenario-a".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(found.success);
+        assert_eq!(found.resource_state.unwrap().current_state, "WAITING");
+
+        let missing = receiver
+            .get_resource_state(Request::new(ResourceStateRequest {
+                resource_type: ResourceType::Package as i32,
+                resource_name: "scenario-a".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!missing.success);
+        assert!(missing.resource_state.is_none());
+
+        let status = receiver
+            .get_resource_state(Request::new(ResourceStateRequest {
+                resource_type: ResourceType::Unspecified as i32,
+                resource_name: "scenario-a".to_string(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        let listed = receiver
+            .list_resource_states(Request::new(ListResourceStatesRequest {
+                state: "waiting".to_string(),
+                limit: 1,
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(listed.total_count, 2);
+        assert_eq!(listed.resources.len(), 1);
+        assert_eq!(listed.resources[0].resource_name, "scenario-a");
+
+        let status = receiver
+            .list_resource_states(Request::new(ListResourceStatesRequest {
+                resource_type: 9999,
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_state_changes_filters_by_name() {
+        let receiver = query_receiver();
+        let mut stream = receiver
+            .subscribe_state_changes(Request::new(StateChangeSubscriptionRequest {
+                resource_name: "scenario-b".to_string(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        {
+            let state_machine = &receiver.state_machine;
+            state_machine.process_state_change(waiting_scenario("scenario-a"));
+            state_machine.process_state_change(waiting_scenario("scenario-b"));
+        }
+
+        let event = stream.next().await.unwrap().unwrap();
+        assert_eq!(event.resource_state.unwrap().resource_name, "scenario-b");
+        assert_eq!(event.transition_id, "scenario-b-t1");
+    }
+
     #[test]
     fn test_resource_type_to_string_variants() {
         let (tx, _rx) = mpsc::channel::<ContainerList>(1);
@@ -656,6 +1190,7 @@ mod tests {
         let receiver = StateManagerReceiver {
             tx,
             tx_state_change,
+            state_machine: Default::default(),
         };
 
         assert_eq!(
@@ -693,22 +1228,6 @@ mod tests {
 // the following additional methods will be implemented to complete the
 // StateManagerConnection trait:
 //
-// STATE QUERY API:
-// - get_resource_state(ResourceStateRequest) -> ResourceStateResponse
-//   * Query current state and health status of specific resources
-//   * Support for ResourceType filtering and metadata retrieval
-//   * ASIL compliance tracking and audit trail access
-//
-// - get_resource_state_history(ResourceStateHistoryRequest) -> ResourceStateHistoryResponse
-//   * Retrieve complete state transition history with timing analysis
-//   * Support for time range filtering and audit trail generation
-//   * Performance metrics and transition success rates
-//
-// - list_resources_by_state(ListResourcesByStateRequest) -> ListResourcesByStateResponse
-//   * Filter resources by current state with label selectors
-//   * Bulk operations support and pagination
-//   * Health status aggregation and reporting
-//
 // STATE MANAGEMENT API:
 // - update_desired_state(UpdateDesiredStateRequest) -> StateChangeResponse
 //   * Update target states with validation and dependency checking
@@ -742,16 +1261,6 @@ mod tests {
 //   * Failure analysis and retry strategy reporting
 //
 // EVENT AND NOTIFICATION API:
-// - type SubscribeToStateChangesStream = ReceiverStream<Result<StateChangeEvent, Status>>
-//   * Real-time event streaming with filtering capabilities
-//   * Subscription management and event routing
-//   * Health and recovery event integration
-//
-// - subscribe_to_state_changes(StateChangeSubscriptionRequest) -> SubscribeToStateChangesStream
-//   * Event subscription with comprehensive filtering options
-//   * Resource type and severity level filtering
-//   * Metadata-based event routing and delivery
-//
 // - acknowledge_alert(AcknowledgeAlertRequest) -> AlertResponse
 //   * Alert lifecycle management and acknowledgment tracking
 //   * Escalation prevention and status updates
@@ -763,11 +1272,9 @@ mod tests {
 //   * Health status integration and correlation
 //
 // IMPLEMENTATION PRIORITY:
-// 1. State Query API - Fundamental read operations
-// 2. Event Streaming - Real-time monitoring capabilities
-// 3. Advanced State Management - Enhanced write operations
-// 4. Recovery Management - Failure handling and automation
-// 5. Alert Management - Comprehensive notification system
+// 1. Advanced State Management - Enhanced write operations
+// 2. Recovery Management - Failure handling and automation
+// 3. Alert Management - Comprehensive notification system
 //
 // Each implementation phase will include:
 // - Comprehensive validation and error handling
@@ -6,9 +6,19 @@
 use common::actioncontroller::{
     action_controller_connection_client::ActionControllerConnectionClient, connect_server,
     OffloadModelRequest, OffloadModelResponse, ReconcileRequest, ReconcileResponse,
+    TriggerActionRequest, TriggerActionResponse,
 };
+use common::filtergateway::{
+    filter_gateway_connection_client::FilterGatewayConnectionClient, Action, HandleScenarioRequest,
+    HandleScenarioResponse,
+};
+use common::rpc::RpcClient;
 use std::env;
-use tonic::{Request, Response, Status};
+use tonic::{Response, Status};
+
+fn action_controller() -> RpcClient {
+    RpcClient::new("ActionController", connect_server())
+}
 
 pub async fn _send(condition: ReconcileRequest) -> Result<Response<ReconcileResponse>, Status> {
     // Test mode bypass: return a fake successful response when env var is set
@@ -19,10 +29,42 @@ pub async fn _send(condition: ReconcileRequest) -> Result<Response<ReconcileResp
         };
         return Ok(Response::new(resp));
     }
-    let mut client = ActionControllerConnectionClient::connect(connect_server())
+    let condition = &condition;
+    action_controller()
+        .call(|channel| async move {
+            ActionControllerConnectionClient::new(channel)
+                .reconcile(common::trace::request(condition.clone()))
+                .await
+        })
+        .await
+}
+
+/// Send trigger action request to ActionController
+///
+/// This starts the workloads of the package targeted by the scenario
+pub async fn trigger_action(
+    request: TriggerActionRequest,
+) -> Result<Response<TriggerActionResponse>, Status> {
+    // Test mode bypass
+    if env::var("PULLPIRI_TEST_MODE").is_ok() {
+        let resp = TriggerActionResponse {
+            status: 0,
+            desc: "mock trigger".to_string(),
+            plan: None,
+        };
+        return Ok(Response::new(resp));
+    }
+
+    // Starting the workloads takes as long as the slowest model
+    let request = &request;
+    action_controller()
+        .with_call_timeout(None)
+        .call(|channel| async move {
+            ActionControllerConnectionClient::new(channel)
+                .trigger_action(common::trace::request(request.clone()))
+                .await
+        })
         .await
-        .unwrap();
-    client.reconcile(Request::new(condition)).await
 }
 
 /// Send offload model request to ActionController
@@ -41,21 +83,50 @@ pub async fn offload_model(
         return Ok(Response::new(resp));
     }
 
-    let client = ActionControllerConnectionClient::connect(connect_server()).await;
-
-    match client {
-        Ok(mut client) => client.offload_model(Request::new(request)).await,
-        Err(e) => {
-            eprintln!(
-                "[StateManager] Failed to connect to ActionController: {}",
-                e
-            );
-            Err(Status::unavailable(format!(
-                "Failed to connect to ActionController: {}",
-                e
-            )))
-        }
+    let request = &request;
+    let result = action_controller()
+        .with_call_timeout(None)
+        .call(|channel| async move {
+            ActionControllerConnectionClient::new(channel)
+                .offload_model(common::trace::request(request.clone()))
+                .await
+        })
+        .await;
+    if let Err(e) = &result {
+        eprintln!(
+            "[StateManager] Offload request to ActionController failed: {}",
+            e.message()
+        );
     }
+    result
+}
+
+/// Remove the filter of a scenario from FilterGateway
+///
+/// ### Parameters
+/// * `scenario: String` - yaml of the scenario artifact
+pub async fn withdraw_scenario(
+    scenario: String,
+) -> Result<Response<HandleScenarioResponse>, Status> {
+    // Test mode bypass
+    if env::var("PULLPIRI_TEST_MODE").is_ok() {
+        return Ok(Response::new(HandleScenarioResponse {
+            status: true,
+            desc: "mock withdraw".to_string(),
+        }));
+    }
+
+    let request = &HandleScenarioRequest {
+        action: Action::Withdraw.into(),
+        scenario,
+    };
+    RpcClient::new("FilterGateway", common::filtergateway::connect_server())
+        .call(|channel| async move {
+            FilterGatewayConnectionClient::new(channel)
+                .handle_scenario(common::trace::request(request.clone()))
+                .await
+        })
+        .await
 }
 
 #[cfg(test)]
@@ -80,4 +151,21 @@ mod tests {
 
         env::remove_var("PULLPIRI_TEST_MODE");
     }
+
+    #[tokio::test]
+    async fn test_trigger_action_in_test_mode_returns_mock_response() {
+        env::set_var("PULLPIRI_TEST_MODE", "1");
+
+        let req = TriggerActionRequest {
+            scenario_name: "s1".to_string(),
+            dry_run: false,
+            action: String::new(),
+        };
+
+        let res = trigger_action(req).await;
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap().get_ref().status, 0);
+
+        env::remove_var("PULLPIRI_TEST_MODE");
+    }
 }
@@ -0,0 +1,229 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Bounded transition history of each resource
+//!
+//! The audit trail keeps every transition of the system. The history keeps
+//! only the last `MAX_HISTORY_ENTRIES` transitions of one resource, as a single
+//! JSON list under `/history/<type>/<name>` ordered oldest first, so the
+//! recent transitions of a scenario, package or model are one etcd read away.
+//!
+//! The history is served by the `GetResourceStateHistory` gRPC call.
+
+use crate::audit::{AuditOutcome, AuditRecord};
+use common::etcd::StoreError;
+use common::logd;
+use common::statemanager::{ResourceType, StateTransitionHistory};
+use serde::{Deserialize, Serialize};
+
+/// etcd prefix of the history lists
+pub const HISTORY_PREFIX: &str = "/history/";
+
+/// Transitions kept per resource, older ones are dropped
+pub const MAX_HISTORY_ENTRIES: usize = 50;
+
+/// One transition of a resource
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransitionEntry {
+    /// When the StateManager finished processing, Unix time in nanoseconds
+    pub timestamp_ns: i64,
+    pub from: String,
+    pub to: String,
+    /// Event the transition was matched with
+    pub event: String,
+    pub source: String,
+    pub transition_id: String,
+    pub result: AuditOutcome,
+    pub message: String,
+    pub duration_ms: i64,
+}
+
+impl TransitionEntry {
+    /// Entry of a processed transition, taken from its audit record
+    pub fn from_audit(record: &AuditRecord, event: &str) -> Self {
+        Self {
+            timestamp_ns: record.recorded_at_ns,
+            from: record.current_state.clone(),
+            to: record.target_state.clone(),
+            event: event.to_string(),
+            source: record.source.clone(),
+            transition_id: record.transition_id.clone(),
+            result: record.outcome,
+            message: record.message.clone(),
+            duration_ms: (record.duration_us / 1_000) as i64,
+        }
+    }
+
+    pub fn to_proto(&self) -> StateTransitionHistory {
+        StateTransitionHistory {
+            from_state: self.from.clone(),
+            to_state: self.to.clone(),
+            timestamp_ns: self.timestamp_ns,
+            transition_id: self.transition_id.clone(),
+            reason: self.message.clone(),
+            source: self.source.clone(),
+            duration_ms: self.duration_ms,
+            success: self.result == AuditOutcome::Success,
+            event: self.event.clone(),
+        }
+    }
+}
+
+/// etcd key of the history of a resource, e.g. `/history/scenario/antipinch`
+pub fn history_key(resource_type: ResourceType, resource_name: &str) -> String {
+    let kind = resource_type.as_str_name();
+    let kind = kind.strip_prefix("RESOURCE_TYPE_").unwrap_or(kind);
+    format!(
+        "{}{}/{}",
+        HISTORY_PREFIX,
+        kind.to_ascii_lowercase(),
+        resource_name
+    )
+}
+
+/// Append an entry, dropping the oldest ones beyond `max`
+pub fn push_bounded(history: &mut Vec<TransitionEntry>, entry: TransitionEntry, max: usize) {
+    history.push(entry);
+    if history.len() > max {
+        history.drain(..history.len() - max);
+    }
+}
+
+/// Entries within `[since, until]`, at most the newest `limit`
+///
+/// A bound or limit of 0 is not applied.
+pub fn select(
+    history: Vec<TransitionEntry>,
+    since: i64,
+    until: i64,
+    limit: usize,
+) -> Vec<TransitionEntry> {
+    let mut entries: Vec<TransitionEntry> = history
+        .into_iter()
+        .filter(|entry| since == 0 || entry.timestamp_ns >= since)
+        .filter(|entry| until == 0 || entry.timestamp_ns <= until)
+        .collect();
+    if limit > 0 && entries.len() > limit {
+        entries.drain(..entries.len() - limit);
+    }
+    entries
+}
+
+/// Read the history of a resource, oldest first
+///
+/// A resource without any recorded transition has an empty history.
+pub async fn load(
+    resource_type: ResourceType,
+    resource_name: &str,
+) -> Result<Vec<TransitionEntry>, String> {
+    match common::etcd::get(&history_key(resource_type, resource_name)).await {
+        Ok(value) => serde_json::from_str(&value).map_err(|e| e.to_string()),
+        Err(StoreError::NotFound) => Ok(Vec::new()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Append a transition to the history of its resource
+///
+/// Failures are logged only, an unavailable store must not block transitions.
+pub async fn record(resource_type: ResourceType, resource_name: &str, entry: TransitionEntry) {
+    let mut history = match load(resource_type, resource_name).await {
+        Ok(history) => history,
+        Err(e) => {
+            logd!(5, "Failed to read history of '{}': {}", resource_name, e);
+            return;
+        }
+    };
+    push_bounded(&mut history, entry, MAX_HISTORY_ENTRIES);
+
+    let json = match serde_json::to_string(&history) {
+        Ok(json) => json,
+        Err(e) => {
+            logd!(
+                5,
+                "Failed to serialize history of '{}': {}",
+                resource_name,
+                e
+            );
+            return;
+        }
+    };
+    if let Err(e) = common::etcd::put(&history_key(resource_type, resource_name), &json).await {
+        logd!(5, "Failed to write history of '{}': {}", resource_name, e);
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(timestamp_ns: i64) -> TransitionEntry {
+        TransitionEntry {
+            timestamp_ns,
+            from: "idle".to_string(),
+            to: "waiting".to_string(),
+            event: "scenario_activation".to_string(),
+            source: "unittest".to_string(),
+            transition_id: format!("history-test-{}", timestamp_ns),
+            result: AuditOutcome::Success,
+            message: String::new(),
+            duration_ms: 1,
+        }
+    }
+
+    #[test]
+    fn test_history_key() {
+        assert_eq!(
+            history_key(ResourceType::Scenario, "antipinch"),
+            "/history/scenario/antipinch"
+        );
+        assert_eq!(
+            history_key(ResourceType::Model, "helloworld-core"),
+            "/history/model/helloworld-core"
+        );
+    }
+
+    #[test]
+    fn test_push_bounded_drops_oldest() {
+        let mut history = Vec::new();
+        for ts in 1..=5 {
+            push_bounded(&mut history, entry(ts), 3);
+        }
+        let timestamps: Vec<i64> = history.iter().map(|e| e.timestamp_ns).collect();
+        assert_eq!(timestamps, vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn test_select_bounds_and_limit() {
+        let history: Vec<TransitionEntry> = (1..=10).map(entry).collect();
+
+        assert_eq!(select(history.clone(), 0, 0, 0).len(), 10);
+
+        let newest: Vec<i64> = select(history.clone(), 0, 0, 2)
+            .iter()
+            .map(|e| e.timestamp_ns)
+            .collect();
+        assert_eq!(newest, vec![9, 10]);
+
+        let window: Vec<i64> = select(history, 3, 6, 0)
+            .iter()
+            .map(|e| e.timestamp_ns)
+            .collect();
+        assert_eq!(window, vec![3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_entry_to_proto() {
+        let mut failed = entry(7);
+        failed.result = AuditOutcome::Failure;
+        failed.message = "invalid transition".to_string();
+        let proto = failed.to_proto();
+        assert!(!proto.success);
+        assert_eq!(proto.reason, "invalid transition");
+        assert_eq!(proto.event, "scenario_activation");
+        assert_eq!(proto.from_state, "idle");
+    }
+}
@@ -0,0 +1,253 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Backpressure-aware ingestion of StateManager inputs
+//!
+//! Every input source has its own bounded channel. A producer that finds its
+//! channel full is throttled: it waits for room instead of growing the queue,
+//! the throttling is counted in the metrics and an alert is published on the
+//! event bus, at most once per [`OVERFLOW_ALERT_INTERVAL`] and source.
+//!
+//! Messages are processed by a pool of workers. Each message is routed by the
+//! key of its resource, so messages of the same resource are handled by the
+//! same worker in the order they arrived while different resources are
+//! processed in parallel.
+
+use crate::metrics;
+use common::eventbus::{Event, EventKind};
+use common::logd;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{self, error::SendError, error::TrySendError};
+use tokio::task::JoinHandle;
+
+/// Capacity of the ContainerList channel from nodeagent
+pub const CONTAINER_CHANNEL_CAPACITY: usize = 100;
+
+/// Capacity of the StateChange channel from ApiServer, FilterGateway and ActionController
+pub const STATE_CHANGE_CHANNEL_CAPACITY: usize = 100;
+
+/// Workers processing StateChanges, keyed by resource
+pub const STATE_CHANGE_WORKERS: usize = 4;
+
+/// Workers processing ContainerLists, keyed by node
+pub const CONTAINER_WORKERS: usize = 2;
+
+/// Capacity of the queue of each worker
+pub const WORKER_QUEUE_CAPACITY: usize = 32;
+
+/// Minimum time between two overflow alerts of the same source
+pub const OVERFLOW_ALERT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Send a message to a bounded channel, waiting for room when it is full
+///
+/// A full channel throttles the producer; the throttling is recorded under
+/// `source` before waiting.
+///
+/// ### Returns
+/// * `Err(SendError)` - the receiving side is closed
+pub async fn send<T>(tx: &mpsc::Sender<T>, source: &str, message: T) -> Result<(), SendError<T>> {
+    match tx.try_send(message) {
+        Ok(()) => Ok(()),
+        Err(TrySendError::Closed(message)) => Err(SendError(message)),
+        Err(TrySendError::Full(message)) => {
+            report_overflow(source, tx.max_capacity());
+            tx.send(message).await
+        }
+    }
+}
+
+/// Count a throttled producer and raise an alert if none was raised lately
+fn report_overflow(source: &str, capacity: usize) {
+    metrics::record_ingest_throttled(source);
+    if !overflow_alert_due(source, Instant::now()) {
+        return;
+    }
+    let message = format!(
+        "{} queue is full ({} messages), producers are throttled",
+        source, capacity
+    );
+    logd!(4, "[Ingest] {}", message);
+    common::eventbus::publish(
+        Event::new(EventKind::AlertRaised, "statemanager", source, message)
+            .with_attribute("severity", "warning")
+            .with_attribute("reason", "ingest_overflow"),
+    );
+}
+
+/// Whether an overflow alert of `source` may be raised at `now`
+fn overflow_alert_due(source: &str, now: Instant) -> bool {
+    static LAST_ALERTS: OnceLock<Mutex<HashMap<String, Instant>>> = OnceLock::new();
+    let mut last_alerts = LAST_ALERTS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    match last_alerts.get(source) {
+        Some(last) if now.duration_since(*last) < OVERFLOW_ALERT_INTERVAL => false,
+        _ => {
+            last_alerts.insert(source.to_string(), now);
+            true
+        }
+    }
+}
+
+/// Index of the worker handling the messages of `key`
+fn worker_index(key: &str, workers: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    (hasher.finish() % workers as u64) as usize
+}
+
+/// Pool of workers processing the messages of each key in order
+pub struct KeyedWorkers<T> {
+    name: &'static str,
+    queues: Vec<mpsc::Sender<T>>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl<T: Send + 'static> KeyedWorkers<T> {
+    /// Spawn `workers` tasks running `handler` on the messages routed to them
+    ///
+    /// ### Parameters
+    /// * `name` - name of the pool, used in logs and metrics
+    /// * `workers` - number of workers, at least one is spawned
+    /// * `handler` - processing of one message
+    pub fn spawn<F, Fut>(name: &'static str, workers: usize, handler: F) -> Self
+    where
+        F: Fn(T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        let (queues, handles) = (0..workers.max(1))
+            .map(|_| {
+                let (tx, mut rx) = mpsc::channel::<T>(WORKER_QUEUE_CAPACITY);
+                let handler = Arc::clone(&handler);
+                let handle = tokio::spawn(async move {
+                    while let Some(message) = rx.recv().await {
+                        handler(message).await;
+                    }
+                });
+                (tx, handle)
+            })
+            .unzip();
+        Self {
+            name,
+            queues,
+            handles,
+        }
+    }
+
+    /// Queue a message behind the earlier messages of the same key
+    ///
+    /// Waits while the queue of the worker is full.
+    ///
+    /// ### Returns
+    /// * `false` - the worker has stopped, the message was dropped
+    pub async fn dispatch(&self, key: &str, message: T) -> bool {
+        let queue = &self.queues[worker_index(key, self.queues.len())];
+        send(queue, self.name, message).await.is_ok()
+    }
+
+    /// Wait until the workers have processed every queued message
+    pub async fn shutdown(self) {
+        drop(self.queues);
+        for handle in self.handles {
+            if let Err(e) = handle.await {
+                logd!(4, "[Ingest] {} worker stopped with error: {e:?}", self.name);
+            }
+        }
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::Mutex as AsyncMutex;
+
+    #[test]
+    fn test_worker_index_is_stable() {
+        let index = worker_index("Model/helloworld-core", 4);
+        assert!(index < 4);
+        assert_eq!(worker_index("Model/helloworld-core", 4), index);
+        assert_eq!(worker_index("anything", 1), 0);
+    }
+
+    #[test]
+    fn test_overflow_alert_rate_limited() {
+        let now = Instant::now();
+        assert!(overflow_alert_due("test-source", now));
+        assert!(!overflow_alert_due(
+            "test-source",
+            now + Duration::from_secs(1)
+        ));
+        assert!(overflow_alert_due("other-source", now));
+        assert!(overflow_alert_due(
+            "test-source",
+            now + OVERFLOW_ALERT_INTERVAL
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_send_waits_when_full() {
+        let (tx, mut rx) = mpsc::channel::<u32>(1);
+        send(&tx, "test-full", 1).await.unwrap();
+
+        let sender = tokio::spawn(async move { send(&tx, "test-full", 2).await });
+        // let the sender find the channel full
+        tokio::task::yield_now().await;
+        assert!(!sender.is_finished());
+        assert_eq!(rx.recv().await, Some(1));
+        assert!(sender.await.unwrap().is_ok());
+        assert_eq!(rx.recv().await, Some(2));
+
+        let text = common::metrics::render();
+        assert!(text.contains(&format!(
+            "{}{{source=\"test-full\"}}",
+            metrics::INGEST_THROTTLED_TOTAL
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_send_fails_when_closed() {
+        let (tx, rx) = mpsc::channel::<u32>(1);
+        drop(rx);
+        assert!(send(&tx, "test-closed", 1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_keyed_workers_keep_order_per_key() {
+        let processed = Arc::new(AsyncMutex::new(Vec::new()));
+        let sink = Arc::clone(&processed);
+        let workers = KeyedWorkers::spawn("test-workers", 3, move |(key, n): (String, u32)| {
+            let sink = Arc::clone(&sink);
+            async move {
+                sink.lock().await.push((key, n));
+            }
+        });
+
+        for n in 0..20 {
+            for key in ["a", "b", "c", "d"] {
+                assert!(workers.dispatch(key, (key.to_string(), n)).await);
+            }
+        }
+        workers.shutdown().await;
+
+        let processed = processed.lock().await;
+        assert_eq!(processed.len(), 80);
+        for key in ["a", "b", "c", "d"] {
+            let order: Vec<u32> = processed
+                .iter()
+                .filter(|(k, _)| k == key)
+                .map(|(_, n)| *n)
+                .collect();
+            assert_eq!(order, (0..20).collect::<Vec<u32>>());
+        }
+    }
+}
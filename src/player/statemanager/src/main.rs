@@ -12,21 +12,42 @@
 //! The StateManager service is a core component of the Pullpiri framework, responsible for managing
 //! resource state transitions, monitoring container health, and ensuring ASIL-compliant operation.
 
+use common::health;
 use common::logd;
 use common::logd::logger;
 use common::monitoringserver::ContainerList;
 use common::statemanager::{
     state_manager_connection_server::StateManagerConnectionServer, StateChange,
 };
+use state_machine::StateMachine;
 use std::env;
+use std::sync::Arc;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 use tonic::transport::Server;
 
+pub mod audit;
+pub mod backoff;
+pub mod dedup;
+pub mod drift;
+pub mod expiry;
+pub mod flap;
 pub mod grpc;
+pub mod history;
+pub mod ingest;
 pub mod manager;
+pub mod metrics;
+pub mod recovery;
+pub mod state_cache;
 pub mod state_machine;
+pub mod timing;
+pub mod transitions;
 pub mod types;
 
+/// Readiness check of the processing engine and its input channels
+const CHECK_ENGINE: &str = "engine";
+/// Interval of the etcd readiness probe
+const ETCD_PROBE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
 /// Launches the StateManagerManager in an asynchronous task.
 ///
 /// This function creates the StateManager engine, initializes it with proper configuration,
@@ -36,6 +57,7 @@ pub mod types;
 /// # Arguments
 /// * `rx_container` - Channel receiver for ContainerList messages from nodeagent
 /// * `rx_state_change` - Channel receiver for StateChange messages from various components
+/// * `state_machine` - State machine shared with the gRPC server for state queries
 ///
 /// # Processing Flow
 /// 1. Create StateManagerManager instance with provided channels
@@ -50,6 +72,7 @@ pub mod types;
 async fn launch_manager(
     rx_container: Receiver<ContainerList>,
     rx_state_change: Receiver<StateChange>,
+    state_machine: Arc<StateMachine>,
 ) {
     // In test mode we short-circuit heavy startup to keep unit tests fast
     // In test builds or when `PULLPIRI_TEST_MODE` is set we short-circuit heavy startup
@@ -60,7 +83,11 @@ async fn launch_manager(
     logd!(3, "=== StateManagerManager Starting ===");
 
     // Create the StateManager engine with async channel receivers
-    let mut manager = manager::StateManagerManager::new(rx_container, rx_state_change).await;
+    let mut manager = manager::StateManagerManager::with_state_machine(
+        rx_container,
+        rx_state_change,
+        state_machine,
+    );
 
     // Initialize the manager with configuration and persistent state
     match manager.initialize().await {
@@ -72,7 +99,10 @@ async fn launch_manager(
 
             // Run the main processing loop
             logd!(3, "Starting StateManagerManager main processing loop...");
-            if let Err(e) = manager.run().await {
+            health::set_ready(CHECK_ENGINE);
+            let result = manager.run().await;
+            health::set_not_ready(CHECK_ENGINE, "processing loop stopped");
+            if let Err(e) = result {
                 logd!(5, "StateManagerManager stopped with error: {e:?}");
                 logd!(
                     5,
@@ -84,6 +114,7 @@ async fn launch_manager(
         }
         Err(e) => {
             logd!(5, "Failed to initialize StateManagerManager: {e:?}");
+            health::set_not_ready(CHECK_ENGINE, format!("initialization failed: {e}"));
             logd!(
                 5,
                 "StateManager service cannot start - check configuration and dependencies"
@@ -104,6 +135,7 @@ async fn launch_manager(
 /// # Arguments
 /// * `tx_container` - Channel sender for ContainerList messages to StateManager engine
 /// * `tx_state_change` - Channel sender for StateChange messages to StateManager engine
+/// * `state_machine` - State machine of the engine, read by state queries and subscriptions
 ///
 /// # Server Configuration
 /// - Binds to address specified in common::statemanager::open_server()
@@ -119,6 +151,7 @@ async fn launch_manager(
 async fn initialize_grpc_server(
     tx_container: Sender<ContainerList>,
     tx_state_change: Sender<StateChange>,
+    state_machine: Arc<StateMachine>,
 ) {
     // Allow tests to opt-out of starting the actual gRPC server
     // Skip starting the real gRPC server when running tests or explicitly requested
@@ -132,6 +165,7 @@ async fn initialize_grpc_server(
     let server = grpc::receiver::StateManagerReceiver {
         tx: tx_container,
         tx_state_change,
+        state_machine,
     };
     logd!(3, "StateManagerReceiver instance created successfully");
 
@@ -153,11 +187,15 @@ async fn initialize_grpc_server(
 
     // Start the gRPC server with comprehensive error handling
     logd!(3, "Starting StateManager gRPC server...");
-    match Server::builder()
+    health::set_ready(health::CHECK_GRPC_SERVER);
+    let result = Server::builder()
+        .layer(common::trace::GrpcTraceLayer)
         .add_service(StateManagerConnectionServer::new(server))
+        .add_service(common::reflection::service())
         .serve(addr)
-        .await
-    {
+        .await;
+    health::set_not_ready(health::CHECK_GRPC_SERVER, "stopped");
+    match result {
         Ok(_) => {
             logd!(4, "StateManager gRPC server stopped gracefully");
         }
@@ -207,6 +245,7 @@ async fn initialize_timpani_server() {
                 timpani_server,
             ),
         )
+        .add_service(common::reflection::service())
         .serve(addr)
         .await
     {
@@ -240,9 +279,9 @@ async fn initialize_timpani_server() {
 /// - Supports graceful termination handling
 ///
 /// # Channel Configuration
-/// - ContainerList channel: 100 message buffer for nodeagent communication
-/// - StateChange channel: 100 message buffer for component communication
-/// - Async processing prevents blocking between message types
+/// - ContainerList channel: bounded buffer for nodeagent communication
+/// - StateChange channel: bounded buffer for component communication
+/// - Producers wait when a channel is full, see [`ingest`]
 ///
 /// # Error Handling
 /// - Both components run independently to prevent cascading failures
@@ -251,25 +290,47 @@ async fn initialize_timpani_server() {
 #[tokio::main]
 async fn main() {
     let _ = logger::init_async_logger("statemanager").await;
+    common::trace::init_exporter("statemanager");
     logd!(1, "initiailize statemanager...");
+    if !cfg!(test) && env::var("PULLPIRI_TEST_MODE").is_err() {
+        if let Err(e) = common::startup::wait_for_dependencies("statemanager").await {
+            logd!(4, "statemanager starts without its dependencies, {}", e);
+        }
+    }
+    health::init(
+        "statemanager",
+        &[health::CHECK_ETCD, health::CHECK_GRPC_SERVER, CHECK_ENGINE],
+    );
+    if !cfg!(test) && env::var("PULLPIRI_TEST_MODE").is_err() {
+        tokio::spawn(health::watch_etcd(ETCD_PROBE_INTERVAL));
+    }
 
-    // Create async channels for communication between gRPC server and processing engine
-    // Buffer size of 100 provides good throughput while preventing excessive memory usage
-    let (tx_container, rx_container) = channel::<ContainerList>(100);
-    let (tx_state_change, rx_state_change) = channel::<StateChange>(100);
+    // Create bounded channels per input source between gRPC server and processing engine
+    // A full channel throttles its producers instead of growing without limit
+    let (tx_container, rx_container) = channel::<ContainerList>(ingest::CONTAINER_CHANNEL_CAPACITY);
+    let (tx_state_change, rx_state_change) =
+        channel::<StateChange>(ingest::STATE_CHANGE_CHANNEL_CAPACITY);
+
+    // The engine updates the state machine, the gRPC server answers queries from it
+    let mut state_machine = StateMachine::new();
+    transitions::load(&mut state_machine).await;
+    let state_machine = Arc::new(state_machine);
 
     // Launch StateManager processing engine
-    let manager_task = launch_manager(rx_container, rx_state_change);
+    let manager_task = launch_manager(rx_container, rx_state_change, Arc::clone(&state_machine));
 
     // Launch gRPC server for external communication
-    let grpc_task = initialize_grpc_server(tx_container, tx_state_change);
+    let grpc_task = initialize_grpc_server(tx_container, tx_state_change, state_machine);
 
     // Launch gRPC server for timpani deadline miss
     let timpani_task = initialize_timpani_server();
 
-    // Run both components concurrently until shutdown
-    // tokio::join! ensures both tasks complete before main exits
-    tokio::join!(manager_task, grpc_task, timpani_task);
+    // Launch Prometheus metrics and health endpoints
+    let metrics_task = metrics::launch_metrics_server();
+
+    // Run all components concurrently until shutdown
+    // tokio::join! ensures all tasks complete before main exits
+    tokio::join!(manager_task, grpc_task, timpani_task, metrics_task);
 
     // Both tasks return (), but we log completion for monitoring
     logd!(6, "statemanager service stopped");
@@ -292,7 +353,7 @@ mod tests {
         // Should return quickly because test mode short-circuits startup
         let res = timeout(
             Duration::from_secs(1),
-            launch_manager(rx_container, rx_state_change),
+            launch_manager(rx_container, rx_state_change, Default::default()),
         )
         .await;
         assert!(res.is_ok(), "launch_manager did not return in test mode");
@@ -314,7 +375,7 @@ mod tests {
         // Should return quickly because test mode short-circuits server startup
         let res = timeout(
             Duration::from_secs(1),
-            initialize_grpc_server(tx_container, tx_state_change),
+            initialize_grpc_server(tx_container, tx_state_change, Default::default()),
         )
         .await;
         assert!(
@@ -360,8 +421,8 @@ mod tests {
         // Both futures should return quickly because cfg!(test) is true
         let fut = async move {
             tokio::join!(
-                launch_manager(rx_container, rx_state_change),
-                initialize_grpc_server(tx_container, tx_state_change),
+                launch_manager(rx_container, rx_state_change, Default::default()),
+                initialize_grpc_server(tx_container, tx_state_change, Default::default()),
             );
         };
 
@@ -382,8 +443,8 @@ mod tests {
         // Run manager, grpc server and timpani concurrently and ensure they all return quickly
         let fut = async move {
             tokio::join!(
-                launch_manager(rx_container, rx_state_change),
-                initialize_grpc_server(tx_container, tx_state_change),
+                launch_manager(rx_container, rx_state_change, Default::default()),
+                initialize_grpc_server(tx_container, tx_state_change, Default::default()),
                 initialize_timpani_server(),
             );
         };
@@ -13,22 +13,35 @@
 //! state transitions, monitoring, reconciliation, and recovery for all resource types
 //! (Scenario, Package, Model, Volume, Network, Node).
 
+use crate::backoff::{BackoffDecision, BackoffManager};
 use crate::grpc::sender;
+use crate::ingest::{self, KeyedWorkers};
 use crate::state_machine::StateMachine;
+use crate::timing::TransitionTiming;
 use crate::types::{ActionCommand, TransitionResult};
 use common::monitoringserver::ContainerList;
 use common::spec::artifact::Artifact;
+use common::spec::k8s::pod::RestartPolicy;
 
 use common::statemanager::{
-    ErrorCode, ModelState, PackageState, ResourceType, ScenarioState, StateChange,
+    ErrorCode, ModelState, NodeState, PackageState, ResourceType, ScenarioState, StateChange,
 };
 
+use common::eventbus::{Event, EventKind};
 use common::logd;
+use common::trace::{self, TraceContext};
 use common::Result;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::{mpsc, Mutex};
 use tokio::task;
 
+/// etcd prefix under which ApiServer stores registered nodes
+const NODE_INFO_PREFIX: &str = "cluster/nodes/";
+
+/// Interval between two evaluations of node heartbeats
+const NODE_MONITOR_INTERVAL_SECS: u64 = 5;
+
 /// Core state management engine for the StateManager service.
 ///
 /// This struct orchestrates all state management operations by receiving messages
@@ -44,11 +57,11 @@ use tokio::task;
 ///
 /// # Threading Model
 /// - Uses Arc<Mutex<mpsc::Receiver>> for safe multi-threaded access
-/// - Spawns dedicated async tasks for each message type
-/// - Ensures lock-free message processing with proper channel patterns
+/// - Spawns dedicated async tasks for each message type, each reading its receiver
+/// - Processes messages in worker pools keyed by resource, see [`crate::ingest`]
 pub struct StateManagerManager {
     /// State machine for processing state transitions
-    state_machine: Arc<Mutex<StateMachine>>,
+    state_machine: Arc<StateMachine>,
 
     /// Channel receiver for container status updates from nodeagent.
     ///
@@ -64,6 +77,9 @@ pub struct StateManagerManager {
     /// - FilterGateway: Policy-driven state transitions and filtering decisions
     /// - ActionController: Action execution results and state confirmations
     rx_state_change: Arc<Mutex<mpsc::Receiver<StateChange>>>,
+
+    /// Restart backoff of crashing models
+    backoff: Arc<Mutex<BackoffManager>>,
 }
 
 impl StateManagerManager {
@@ -81,11 +97,31 @@ impl StateManagerManager {
     pub async fn new(
         rx_container: mpsc::Receiver<ContainerList>,
         rx_state_change: mpsc::Receiver<StateChange>,
+    ) -> Self {
+        Self::with_state_machine(rx_container, rx_state_change, Arc::new(StateMachine::new()))
+    }
+
+    /// Creates a StateManagerManager driving a state machine shared with others.
+    ///
+    /// The gRPC server answers state queries and subscriptions from the same
+    /// state machine the manager updates.
+    ///
+    /// # Arguments
+    /// * `rx_container` - Channel receiver for ContainerList messages from nodeagent
+    /// * `rx_state_change` - Channel receiver for StateChange messages from components
+    /// * `state_machine` - State machine to process the messages with
+    pub fn with_state_machine(
+        rx_container: mpsc::Receiver<ContainerList>,
+        rx_state_change: mpsc::Receiver<StateChange>,
+        state_machine: Arc<StateMachine>,
     ) -> Self {
         Self {
-            state_machine: Arc::new(Mutex::new(StateMachine::new())),
+            state_machine,
             rx_container: Arc::new(Mutex::new(rx_container)),
             rx_state_change: Arc::new(Mutex::new(rx_state_change)),
+            backoff: Arc::new(Mutex::new(BackoffManager::new(
+                common::setting::get_config().backoff.clone(),
+            ))),
         }
     }
 
@@ -109,17 +145,15 @@ impl StateManagerManager {
         logd!(3, "StateManagerManager initializing...");
 
         // Initialize the state machine with async action executor
-        let action_receiver = {
-            let mut state_machine = self.state_machine.lock().await;
-            state_machine.initialize_action_executor()
-        };
+        let action_receiver = self.state_machine.initialize_action_executor();
 
         // Start the async action executor
+        let state_machine = Arc::clone(&self.state_machine);
         tokio::spawn(async move {
-            run_action_executor(action_receiver).await;
+            run_action_executor(action_receiver, state_machine).await;
         });
 
-        logd!(3, "State machine initialized with transition tables for Scenario, Package, and Model resources");
+        logd!(3, "State machine initialized with transition tables for Scenario, Package, Model, and Node resources");
         logd!(
             3,
             "Async action executor started for non-blocking action processing"
@@ -177,6 +211,8 @@ impl StateManagerManager {
     /// This method is async and uses internal locking for state machine access.
     /// Multiple concurrent calls are safe but will be serialized at the state machine level.
     async fn process_state_change(&self, state_change: StateChange) {
+        let started = std::time::Instant::now();
+
         // ========================================
         // STEP 1: RESOURCE TYPE VALIDATION
         // ========================================
@@ -190,9 +226,16 @@ impl StateManagerManager {
                     state_change.resource_type,
                     state_change.resource_name
                 );
+                crate::audit::record(&crate::audit::AuditRecord::rejected(
+                    &state_change,
+                    "Invalid resource type",
+                    started.elapsed(),
+                ))
+                .await;
                 return; // Early return - cannot process invalid resource types
             }
         };
+        crate::metrics::record_state_change(resource_type);
 
         // NOTE: ASIL level parsing is commented out pending implementation of ASILLevel enum
         // This will be needed for safety-critical processing validation
@@ -255,7 +298,7 @@ impl StateManagerManager {
         // PHASE 3: PRE-TRANSITION SAFETY CHECKS
         //    - Execute resource-specific pre-transition validation hooks
         //    - Perform safety checks based on ASIL level (A, B, C, D, or QM)
-        //    - Validate timing constraints and deadlines for real-time requirements
+        //    ✓ Validate timing constraints and deadlines for real-time requirements (timing.rs)
         //    - Check system resource availability (CPU, memory, storage, network)
         //    - Verify external system readiness (databases, services, hardware)
         //
@@ -268,7 +311,7 @@ impl StateManagerManager {
         //
         // PHASE 5: PERSISTENT STORAGE AND AUDIT
         //    - Update resource state in persistent storage (etcd cluster, database)
-        //    - Record detailed state transition history for compliance auditing
+        //    ✓ Record detailed state transition history for compliance auditing (audit.rs)
         //    - Update health status and monitoring data with new state information
         //    - Maintain state generation counters for optimistic concurrency control
         //    - Store performance metrics and timing data for analysis
@@ -294,6 +337,18 @@ impl StateManagerManager {
         //    - Maintain system stability during error conditions and cascading failures
         //    - Implement circuit breaker patterns for failing external dependencies
 
+        // A scenario applied by ApiServer starts its ttlSeconds over
+        if resource_type == ResourceType::Scenario && state_change.source == "apiserver" {
+            crate::expiry::track(&state_change.resource_name).await;
+        }
+
+        // The cooldown of a scenario is read from its artifact before it is triggered
+        let cooldown = if StateMachine::is_scenario_trigger(&state_change) {
+            Some(scenario_cooldown(&state_change.resource_name).await)
+        } else {
+            None
+        };
+
         // ========================================
         // STEP 3: STATE MACHINE PROCESSING
         // ========================================
@@ -304,12 +359,53 @@ impl StateManagerManager {
         // - Condition evaluation for conditional transitions
         // - Action scheduling for follow-up operations
         // - Error detection and reporting
-        let result = {
-            // Acquire exclusive lock on the state machine for this transition
-            // Note: This serializes all state transitions to maintain consistency
-            let mut state_machine = self.state_machine.lock().await;
-            state_machine.process_state_change(state_change.clone())
-        }; // Lock is automatically released here
+        // The state machine only locks the shard of this resource, so
+        // transitions of other resources are not held up
+        if let Some(cooldown) = cooldown {
+            self.state_machine
+                .set_scenario_cooldown(&state_change.resource_name, cooldown);
+        }
+        let event = self.state_machine.transition_event(&state_change);
+        let result = self
+            .state_machine
+            .process_state_change(state_change.clone());
+
+        // Check the transition against the deadline of its ASIL level
+        let timing = TransitionTiming::measure(&state_change, started);
+        crate::metrics::record_transition_duration(
+            resource_type,
+            timing.asil_level,
+            timing.elapsed.as_secs_f64(),
+        );
+
+        // Every processed transition goes to the audit trail, whatever its outcome
+        let mut audit_record =
+            crate::audit::AuditRecord::new(&state_change, &result, started.elapsed());
+        if timing.missed() {
+            self.handle_deadline_miss(&state_change, resource_type, &timing)
+                .await;
+            audit_record.error_code = ErrorCode::Timeout.as_str_name().to_string();
+            audit_record.message = format!(
+                "{} (deadline of {:?} exceeded, took {:?})",
+                audit_record.message,
+                timing.deadline.unwrap_or_default(),
+                timing.elapsed
+            );
+        }
+        crate::audit::record(&audit_record).await;
+        crate::history::record(
+            resource_type,
+            &state_change.resource_name,
+            crate::history::TransitionEntry::from_audit(&audit_record, &event),
+        )
+        .await;
+        // Duplicates of this StateChange are answered with its outcome
+        crate::dedup::complete(
+            &state_change,
+            result.error_code,
+            &result.message,
+            &result.error_details,
+        );
 
         // ========================================
         // STEP 4: RESULT PROCESSING AND RESPONSE
@@ -332,10 +428,24 @@ impl StateManagerManager {
                 ResourceType::Model => ModelState::try_from(result.new_state)
                     .map(|s| s.as_str_name())
                     .unwrap_or("UNKNOWN"),
+                ResourceType::Node => NodeState::try_from(result.new_state)
+                    .map(|s| s.as_str_name())
+                    .unwrap_or("UNKNOWN"),
                 _ => "UNKNOWN",
             };
             logd!(2, "    Final State: {new_state_str}");
             logd!(2, "    Success Message: {}", result.message);
+
+            // Denied and quarantined scenarios and failed models are announced
+            // on the event bus
+            if let Some(event) = transition_event(
+                resource_type,
+                &state_change.resource_name,
+                new_state_str,
+                &result.message,
+            ) {
+                common::eventbus::publish(event);
+            }
             logd!(1, "    Transition ID: {}", result.transition_id);
 
             // 🔍 COMMENT 6: Save scenario state changes to ETCD
@@ -370,6 +480,21 @@ impl StateManagerManager {
                 }
             }
 
+            // Node states are stored for ApiServer and other components
+            if resource_type == ResourceType::Node {
+                let etcd_key = format!("/node/{}/state", state_change.resource_name);
+                if let Err(e) = common::etcd::put(&etcd_key, new_state_str).await {
+                    logd!(4, "   ❌ Failed to save node state to ETCD: {:?}", e);
+                } else {
+                    logd!(
+                        1,
+                        "   ✅ Saved node state to ETCD: {} → {}",
+                        etcd_key,
+                        new_state_str
+                    );
+                }
+            }
+
             // Log any actions that were queued for asynchronous execution
             // Actions are processed separately to keep state transitions fast
             if !result.actions_to_execute.is_empty() {
@@ -403,6 +528,9 @@ impl StateManagerManager {
                 ResourceType::Model => ModelState::try_from(result.new_state)
                     .map(|s| s.as_str_name())
                     .unwrap_or("UNKNOWN"),
+                ResourceType::Node => NodeState::try_from(result.new_state)
+                    .map(|s| s.as_str_name())
+                    .unwrap_or("UNKNOWN"),
                 _ => "UNKNOWN",
             };
             logd!(4, "    Error Code: {:?}", result.error_code);
@@ -421,6 +549,48 @@ impl StateManagerManager {
         logd!(1, "================================");
     }
 
+    /// Report a transition that completed after the deadline of its ASIL level
+    ///
+    /// The transition itself stays applied. The miss is counted as a Timeout
+    /// failure and stored as an alert for the resource.
+    async fn handle_deadline_miss(
+        &self,
+        state_change: &StateChange,
+        resource_type: ResourceType,
+        timing: &TransitionTiming,
+    ) {
+        let reason = format!(
+            "Transition {} -> {} took {:?}, {} allows {:?}",
+            state_change.current_state,
+            state_change.target_state,
+            timing.elapsed,
+            timing.asil_level.as_str_name(),
+            timing.deadline.unwrap_or_default()
+        );
+        logd!(
+            5,
+            "    DEADLINE EXCEEDED for {} {}: {}",
+            resource_type.as_str_name(),
+            state_change.resource_name,
+            reason
+        );
+        crate::metrics::record_deadline_miss(resource_type, timing.asil_level);
+        crate::metrics::record_transition_failure(resource_type, ErrorCode::Timeout);
+
+        let alert = serde_json::json!({
+            "severity": "critical",
+            "reason": reason,
+            "error_code": ErrorCode::Timeout.as_str_name(),
+            "asil_level": timing.asil_level.as_str_name(),
+            "resource": state_change.resource_name,
+            "transition_id": state_change.transition_id,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        });
+        if let Err(e) = store_alert(resource_type, &state_change.resource_name, &alert).await {
+            logd!(4, "    {}", e);
+        }
+    }
+
     /// Handle state transition failures
     async fn handle_transition_failure(
         &self,
@@ -435,6 +605,9 @@ impl StateManagerManager {
         logd!(4, "      Error: {}", result.message);
         logd!(4, "      Error code: {:?}", result.error_code);
         logd!(4, "      Error details: {}", result.error_details);
+        if let Ok(resource_type) = ResourceType::try_from(state_change.resource_type) {
+            crate::metrics::record_transition_failure(resource_type, result.error_code);
+        }
 
         // Generate appropriate error responses based on error type
         match result.error_code {
@@ -460,7 +633,6 @@ impl StateManagerManager {
         }
 
         // In a real implementation, this would:
-        // - Log to audit trail
         // - Generate alerts
         // - Trigger recovery procedures
         // - Update monitoring metrics
@@ -494,9 +666,13 @@ impl StateManagerManager {
             logd!(2, "  Processing model: {}", model_name);
 
             // Process the state evaluation and transition through the state machine
-            let mut state_machine = self.state_machine.lock().await;
-            let transition_result =
-                state_machine.process_model_state_update(&model_name, &containers);
+            self.state_machine
+                .set_model_node(&model_name, &container_list.node_name);
+            let transition_result = self
+                .state_machine
+                .process_model_state_update(&model_name, &containers);
+            self.state_machine
+                .record_unit_status(&model_name, &containers);
 
             if transition_result.is_success() {
                 // Check if state actually changed by looking at actions_to_execute
@@ -510,17 +686,21 @@ impl StateManagerManager {
                     );
 
                     // Extract the new model state from the transition result
-                    let new_model_state = match transition_result.new_state {
+                    let mut new_model_state = match transition_result.new_state {
                         1 => common::statemanager::ModelState::Created,
                         2 => common::statemanager::ModelState::Paused,
                         3 => common::statemanager::ModelState::Exited,
                         4 => common::statemanager::ModelState::Dead,
                         5 => common::statemanager::ModelState::Running,
+                        6 => common::statemanager::ModelState::Failed,
                         _ => common::statemanager::ModelState::Running,
                     };
 
+                    // A dead model is restarted after its backoff delay, or
+                    // becomes Failed once the retries are used up
+                    let restart_delay = self.apply_backoff(&model_name, &mut new_model_state).await;
+
                     // Save the new model state to ETCD
-                    drop(state_machine); // Release the lock before async operation
                     if let Err(e) = self
                         .save_model_state_to_etcd(&model_name, new_model_state)
                         .await
@@ -531,7 +711,15 @@ impl StateManagerManager {
 
                         // Trigger package state evaluation based on model state change
                         // This implements the chain reaction described in the Korean documentation
-                        self.trigger_package_state_evaluation(&model_name).await;
+                        self.trigger_package_state_evaluation(&model_name, restart_delay)
+                            .await;
+
+                        if new_model_state == common::statemanager::ModelState::Failed {
+                            let model_name = model_name.clone();
+                            tokio::spawn(async move {
+                                crate::recovery::recover_model(&model_name).await;
+                            });
+                        }
                     }
                 } else {
                     logd!(
@@ -553,6 +741,69 @@ impl StateManagerManager {
         logd!(2, "=====================================");
     }
 
+    /// Applies the restart backoff to a model that changed state
+    ///
+    /// A Dead model counts as one crash. Once the model crashed more often
+    /// than `maxRestarts` of its spec, or `max_retries` of the settings,
+    /// `model_state` is changed to Failed. A model with `restartPolicy: Never`
+    /// is Failed at its first crash. A Failed model that runs again was
+    /// recovered by hand and starts with a clean counter.
+    ///
+    /// # Returns
+    /// * `Some(delay)` - Time to wait before ActionController restarts the model
+    /// * `None` - The model must not be restarted
+    async fn apply_backoff(
+        &self,
+        model_name: &str,
+        model_state: &mut common::statemanager::ModelState,
+    ) -> Option<Duration> {
+        let mut backoff = self.backoff.lock().await;
+        match model_state {
+            common::statemanager::ModelState::Dead => {
+                let (policy, max_restarts) = model_restart_policy(model_name).await;
+                if policy == RestartPolicy::Never {
+                    logd!(
+                        4,
+                        "    Model {} crashed, restartPolicy Never, marking it Failed",
+                        model_name
+                    );
+                    *model_state = common::statemanager::ModelState::Failed;
+                    return None;
+                }
+                match backoff.record_failure(model_name, max_restarts).await {
+                    BackoffDecision::Retry { attempt, delay } => {
+                        logd!(
+                            3,
+                            "    Model {} crashed, restart {} in {:?}",
+                            model_name,
+                            attempt,
+                            delay
+                        );
+                        Some(delay)
+                    }
+                    BackoffDecision::GiveUp { retries } => {
+                        logd!(
+                            5,
+                            "    Model {} crashed after {} restarts, marking it Failed",
+                            model_name,
+                            retries
+                        );
+                        *model_state = common::statemanager::ModelState::Failed;
+                        None
+                    }
+                }
+            }
+            common::statemanager::ModelState::Running => {
+                if backoff.is_failed(model_name).await {
+                    logd!(3, "    Failed model {} is running again", model_name);
+                    backoff.reset(model_name).await;
+                }
+                Some(Duration::ZERO)
+            }
+            _ => Some(Duration::ZERO),
+        }
+    }
+
     /// Groups containers by their associated model based on annotations or naming conventions
     async fn group_containers_by_model<'a>(
         &self,
@@ -617,6 +868,7 @@ impl StateManagerManager {
             common::statemanager::ModelState::Exited => "Exited",
             common::statemanager::ModelState::Dead => "Dead",
             common::statemanager::ModelState::Running => "Running",
+            common::statemanager::ModelState::Failed => "Failed",
             _ => "Unknown",
         };
 
@@ -667,7 +919,14 @@ impl StateManagerManager {
     /// This function implements the chain reaction described in the Korean documentation:
     /// When a model state changes, it triggers package state evaluation to see if the
     /// package state should also change based on the states of all models in the package.
-    async fn trigger_package_state_evaluation(&self, changed_model_name: &str) {
+    ///
+    /// A package that became Error or Degraded is reconciled by ActionController
+    /// after `restart_delay`, or not at all if it is `None`.
+    async fn trigger_package_state_evaluation(
+        &self,
+        changed_model_name: &str,
+        restart_delay: Option<Duration>,
+    ) {
         logd!(
             2,
             "  Triggering package state evaluation for model: {}",
@@ -691,14 +950,12 @@ impl StateManagerManager {
 
         // Evaluate and update state for each package using state machine
         for package_name in packages {
-            let state_machine = self.state_machine.lock().await;
-            match state_machine
+            match self
+                .state_machine
                 .evaluate_and_update_package_state(&package_name)
                 .await
             {
                 Ok((state_changed, new_state)) => {
-                    drop(state_machine); // Release lock before async operations
-
                     if state_changed {
                         // Save new state to ETCD
                         if let Err(e) = self
@@ -708,20 +965,46 @@ impl StateManagerManager {
                             logd!(5, "      Failed to save package state: {:?}", e);
                             continue;
                         }
+                        self.state_machine
+                            .record_package_state(&package_name, new_state);
 
                         // If package is in error or degraded state, trigger ActionController reconcile
                         if new_state == common::statemanager::PackageState::Error
                             || new_state == common::statemanager::PackageState::Degraded
                         {
-                            if let Err(e) = self
-                                .trigger_action_controller_reconcile_internal(&package_name)
-                                .await
-                            {
-                                logd!(
-                                    5,
-                                    "      Failed to trigger ActionController reconcile: {:?}",
-                                    e
-                                );
+                            match restart_delay {
+                                Some(delay) if delay.is_zero() => {
+                                    if let Err(e) = self
+                                        .trigger_action_controller_reconcile_internal(&package_name)
+                                        .await
+                                    {
+                                        logd!(
+                                            5,
+                                            "      Failed to trigger ActionController reconcile: {:?}",
+                                            e
+                                        );
+                                    }
+                                }
+                                Some(delay) => {
+                                    let package_name = package_name.clone();
+                                    tokio::spawn(async move {
+                                        tokio::time::sleep(delay).await;
+                                        if let Err(e) = reconcile_package(&package_name).await {
+                                            logd!(
+                                                5,
+                                                "      Failed to trigger ActionController reconcile: {:?}",
+                                                e
+                                            );
+                                        }
+                                    });
+                                }
+                                None => {
+                                    logd!(
+                                        4,
+                                        "      Package {} is not reconciled, a model has failed",
+                                        package_name
+                                    );
+                                }
                             }
                         }
 
@@ -763,90 +1046,92 @@ impl StateManagerManager {
         &self,
         package_name: &str,
     ) -> std::result::Result<(), String> {
-        logd!(
-            3,
-            "      Triggering ActionController reconcile for package: {}",
-            package_name
-        );
+        reconcile_package(package_name).await
+    }
 
-        // Find scenario that contains this package
-        let scenario_name = match self.find_scenario_for_package(package_name).await {
-            Ok(Some(name)) => name,
-            Ok(None) => {
-                logd!(4, "      No scenario found for package: {}", package_name);
-                return Err(format!("No scenario found for package: {}", package_name));
-            }
+    /// Periodically evaluates node heartbeats recorded by ApiServer.
+    ///
+    /// Runs until the owning task is aborted. Each tick reads the registered
+    /// nodes from etcd and drives their Node state machine.
+    async fn monitor_node_heartbeats(&self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(NODE_MONITOR_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            self.check_node_heartbeats(chrono::Utc::now().timestamp())
+                .await;
+        }
+    }
+
+    /// Evaluates the last heartbeat of every registered node.
+    ///
+    /// # Arguments
+    /// * `now` - Current Unix timestamp in seconds
+    async fn check_node_heartbeats(&self, now: i64) {
+        let nodes = match common::etcd::get_all_with_prefix(NODE_INFO_PREFIX).await {
+            Ok(nodes) => nodes,
             Err(e) => {
-                logd!(
-                    4,
-                    "      Failed to find scenario for package {}: {:?}",
-                    package_name,
-                    e
-                );
-                return Err(format!("Failed to find scenario for package: {}", e));
+                logd!(4, "Failed to read nodes from ETCD: {:?}", e);
+                return;
             }
         };
 
-        // Create reconcile request using the gRPC sender
-        let reconcile_request = common::actioncontroller::ReconcileRequest {
-            scenario_name: scenario_name.clone(),
-            current: common::actioncontroller::PodStatus::Failed.into(),
-            desired: common::actioncontroller::PodStatus::Running.into(),
-        };
-
-        match sender::_send(reconcile_request).await {
-            Ok(response) => {
-                logd!(
-                    2,
-                    "      Successfully sent reconcile request for scenario: {}",
-                    scenario_name
-                );
-                logd!(
-                    1,
-                    "      ActionController response: status={:?}",
-                    response.get_ref().status
-                );
-                Ok(())
-            }
-            Err(e) => {
-                let error_msg = format!(
-                    "Failed to send reconcile request to ActionController: {:?}",
-                    e
-                );
-                logd!(5, "      {}", error_msg);
-                Err(error_msg)
+        for (key, value) in nodes {
+            match serde_json::from_str::<common::apiserver::NodeInfo>(&value) {
+                Ok(node) => {
+                    crate::metrics::record_heartbeat_age(&node.hostname, now - node.last_heartbeat);
+                    self.update_node_state(&node.hostname, node.last_heartbeat, now)
+                        .await
+                }
+                Err(e) => logd!(4, "Failed to parse node info {}: {:?}", key, e),
             }
         }
     }
 
-    /// Find scenario that contains the given package
-    async fn find_scenario_for_package(
-        &self,
-        package_name: &str,
-    ) -> std::result::Result<Option<String>, String> {
-        // Get all scenarios from ETCD
-        match common::etcd::get_all_with_prefix("Scenario/").await {
-            Ok(scenario_entries) => {
-                for kv in scenario_entries {
-                    match serde_yaml::from_str::<common::spec::artifact::Scenario>(&kv.1) {
-                        Ok(scenario) => {
-                            // Check if this scenario references the package
-                            if scenario.get_targets() == package_name {
-                                return Ok(Some(scenario.get_name()));
-                            }
-                        }
-                        Err(e) => {
-                            logd!(4, "      Failed to parse scenario {}: {:?}", kv.0, e);
-                        }
-                    }
-                }
-                Ok(None) // No scenario found containing this package
-            }
-            Err(e) => {
-                logd!(4, "      Failed to get scenarios from ETCD: {:?}", e);
-                Err(format!("Failed to get scenarios from ETCD: {:?}", e))
-            }
+    /// Moves a node towards the state matching the age of its last heartbeat.
+    ///
+    /// Missed heartbeats move a node Ready -> NotReady -> Unknown, a fresh
+    /// heartbeat moves it back to Ready. Since heartbeats are polled, a node
+    /// that went silent between two checks still passes through NotReady.
+    ///
+    /// # Arguments
+    /// * `node_name` - Hostname of the node
+    /// * `last_heartbeat` - Unix timestamp (seconds) of the last heartbeat
+    /// * `now` - Current Unix timestamp in seconds
+    async fn update_node_state(&self, node_name: &str, last_heartbeat: i64, now: i64) {
+        let current = self
+            .state_machine
+            .get_resource_state(node_name, ResourceType::Node)
+            .and_then(|rs| NodeState::try_from(rs.current_state).ok())
+            .unwrap_or(NodeState::Unspecified);
+
+        let mut target = StateMachine::evaluate_node_state_from_heartbeat(
+            last_heartbeat,
+            now,
+            &common::setting::get_config().heartbeat,
+        );
+        if target == current {
+            return;
         }
+        if current == NodeState::Ready && target == NodeState::Unknown {
+            target = NodeState::NotReady;
+        }
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as i64;
+        let state_change = StateChange {
+            resource_type: ResourceType::Node as i32,
+            resource_name: node_name.to_string(),
+            current_state: node_state_name(current).to_string(),
+            target_state: node_state_name(target).to_string(),
+            transition_id: format!("statemanager-node-{}-{}", node_name, timestamp),
+            timestamp_ns: timestamp,
+            source: "statemanager".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: common::trace::current_trace_id().unwrap_or_default(),
+        };
+        self.process_state_change(state_change).await;
     }
 
     /// Main message processing loop for handling gRPC requests.
@@ -855,16 +1140,18 @@ impl StateManagerManager {
     /// 1. Container status processing task
     /// 2. State change processing task
     ///
-    /// Each task runs independently to ensure optimal throughput and prevent
-    /// blocking between different message types.
+    /// Each task hands its messages to a pool of workers keyed by resource
+    /// (node for ContainerLists, resource type and name for StateChanges), so
+    /// messages of one resource keep their order while different resources are
+    /// processed in parallel.
     ///
     /// # Returns
     /// * `Result<()>` - Success or processing error
     ///
     /// # Architecture Notes
     /// - Uses separate tasks to prevent cross-contamination between message types
-    /// - Maintains proper async patterns for high-throughput processing
-    /// - Ensures graceful shutdown when channels are closed
+    /// - Full worker queues throttle the channel readers, which in turn throttle producers
+    /// - Ensures graceful shutdown when channels are closed, after queued messages are processed
     pub async fn process_grpc_requests(&self) -> Result<()> {
         let rx_container = Arc::clone(&self.rx_container);
         let rx_state_change = Arc::clone(&self.rx_state_change);
@@ -876,26 +1163,31 @@ impl StateManagerManager {
         let container_task = {
             let state_manager = self.clone_for_task();
             tokio::spawn(async move {
-                loop {
-                    let container_list_opt = {
-                        let mut rx = rx_container.lock().await;
-                        rx.recv().await
-                    };
-                    match container_list_opt {
-                        Some(container_list) => {
-                            // Process container status update with comprehensive analysis
-                            state_manager.process_container_list(container_list).await;
-                        }
-                        None => {
-                            // Channel closed - graceful shutdown
-                            logd!(
-                                4,
-                                "Container channel closed - shutting down container processing"
-                            );
-                            break;
-                        }
+                let workers = KeyedWorkers::spawn(
+                    "container_workers",
+                    ingest::CONTAINER_WORKERS,
+                    move |container_list: ContainerList| {
+                        let state_manager = state_manager.clone_for_task();
+                        async move { state_manager.process_container_list(container_list).await }
+                    },
+                );
+                // The receiver is only read by this task, hold it for the whole loop
+                let mut rx = rx_container.lock().await;
+                while let Some(container_list) = rx.recv().await {
+                    let key = container_list.node_name.clone();
+                    if !workers.dispatch(&key, container_list).await {
+                        logd!(
+                            5,
+                            "Container workers stopped - dropping ContainerList of {key}"
+                        );
                     }
                 }
+                // Channel closed - graceful shutdown
+                logd!(
+                    4,
+                    "Container channel closed - shutting down container processing"
+                );
+                workers.shutdown().await;
                 logd!(4, "ContainerList processing task stopped");
             })
         };
@@ -907,26 +1199,42 @@ impl StateManagerManager {
         let state_change_task = {
             let state_manager = self.clone_for_task();
             tokio::spawn(async move {
-                loop {
-                    let state_change_opt = {
-                        let mut rx = rx_state_change.lock().await;
-                        rx.recv().await
-                    };
-                    match state_change_opt {
-                        Some(state_change) => {
-                            // Process state change with comprehensive Pullpiri compliance
-                            state_manager.process_state_change(state_change).await;
-                        }
-                        None => {
-                            // Channel closed - graceful shutdown
-                            logd!(
-                                4,
-                                "StateChange channel closed - shutting down state processing"
-                            );
-                            break;
+                let workers = KeyedWorkers::spawn(
+                    "state_change_workers",
+                    ingest::STATE_CHANGE_WORKERS,
+                    move |state_change: StateChange| {
+                        let state_manager = state_manager.clone_for_task();
+                        // Continue the trace of the request that queued the change
+                        let parent = TraceContext::from_trace_id(&state_change.trace_id);
+                        async move {
+                            trace::in_span(
+                                "statemanager.process_state_change",
+                                parent,
+                                state_manager.process_state_change(state_change),
+                            )
+                            .await
                         }
+                    },
+                );
+                let mut rx = rx_state_change.lock().await;
+                while let Some(state_change) = rx.recv().await {
+                    let key = format!(
+                        "{}/{}",
+                        state_change.resource_type, state_change.resource_name
+                    );
+                    if !workers.dispatch(&key, state_change).await {
+                        logd!(
+                            5,
+                            "StateChange workers stopped - dropping StateChange of {key}"
+                        );
                     }
                 }
+                // Channel closed - graceful shutdown
+                logd!(
+                    4,
+                    "StateChange channel closed - shutting down state processing"
+                );
+                workers.shutdown().await;
                 logd!(4, "StateChange processing task stopped");
             })
         };
@@ -957,6 +1265,7 @@ impl StateManagerManager {
             state_machine: Arc::clone(&self.state_machine),
             rx_container: Arc::clone(&self.rx_container),
             rx_state_change: Arc::clone(&self.rx_state_change),
+            backoff: Arc::clone(&self.backoff),
         }
     }
 
@@ -982,6 +1291,25 @@ impl StateManagerManager {
         // Wrap self in Arc for shared ownership across async tasks
         let arc_self = Arc::new(self);
         let grpc_manager = Arc::clone(&arc_self);
+        let node_manager = Arc::clone(&arc_self);
+
+        // Spawn the node heartbeat monitor
+        let node_monitor = tokio::spawn(async move {
+            node_manager.monitor_node_heartbeats().await;
+        });
+
+        // Spawn the watch of state keys edited in etcd
+        let state_settings = common::setting::get_config().state.clone();
+        let drift_watcher = (state_settings.watch_interval_ms > 0).then(|| {
+            let state_machine = Arc::clone(&arc_self.state_machine);
+            tokio::spawn(crate::drift::watch_state_drift(
+                state_machine,
+                state_settings,
+            ))
+        });
+
+        // Spawn the expiry of scenarios with ttlSeconds
+        let expiry_sweeper = tokio::spawn(crate::expiry::run(Arc::clone(&arc_self.state_machine)));
 
         // Spawn the main gRPC processing task
         let grpc_processor = tokio::spawn(async move {
@@ -992,6 +1320,11 @@ impl StateManagerManager {
 
         // Wait for the processing task to complete
         let result = grpc_processor.await;
+        node_monitor.abort();
+        expiry_sweeper.abort();
+        if let Some(drift_watcher) = drift_watcher {
+            drift_watcher.abort();
+        }
         match result {
             Ok(_) => {
                 logd!(4, "StateManagerManager stopped gracefully");
@@ -1009,16 +1342,22 @@ impl StateManagerManager {
 ///
 /// This function handles the execution of actions triggered by state transitions.
 /// Actions are executed asynchronously to ensure state transitions remain fast and non-blocking.
-pub async fn run_action_executor(mut receiver: mpsc::UnboundedReceiver<ActionCommand>) {
+/// Once an action finishes, its outcome is reported back to the state machine.
+pub async fn run_action_executor(
+    mut receiver: mpsc::UnboundedReceiver<ActionCommand>,
+    state_machine: Arc<StateMachine>,
+) {
     logd!(
         3,
         "Action executor started - processing actions asynchronously"
     );
 
     while let Some(action_command) = receiver.recv().await {
+        let state_machine = Arc::clone(&state_machine);
         // Execute action asynchronously without blocking state transitions
         task::spawn(async move {
-            execute_action(action_command).await;
+            let outcome = execute_action(&action_command).await;
+            state_machine.record_action_result(&action_command, &outcome);
         });
     }
 
@@ -1026,7 +1365,13 @@ pub async fn run_action_executor(mut receiver: mpsc::UnboundedReceiver<ActionCom
 }
 
 /// Execute individual action asynchronously
-async fn execute_action(command: ActionCommand) {
+///
+/// Maps the action name from the transition table to a concrete operation:
+/// - ActionController requests for actions that change workloads
+/// - Alerts stored in etcd for conditions that need attention
+/// - Acknowledgement only for steps driven by other components
+///   (condition evaluation in FilterGateway, container state from NodeAgent, ...)
+async fn execute_action(command: &ActionCommand) -> std::result::Result<(), String> {
     logd!(
         3,
         " Executing action: {} for resource: {}",
@@ -1034,188 +1379,410 @@ async fn execute_action(command: ActionCommand) {
         command.resource_key
     );
 
-    match command.action.as_str() {
-        "start_condition_evaluation" => {
-            logd!(
-                2,
-                " Starting condition evaluation for scenario: {}",
-                command.resource_key
-            );
-            // Would integrate with policy engine or condition evaluator
-        }
-        "start_policy_verification" => {
-            logd!(
-                2,
-                " Starting policy verification for scenario: {}",
-                command.resource_key
-            );
-            // Would integrate with policy manager
-        }
-        "execute_action_on_target_package" => {
-            logd!(
-                2,
-                " Executing action on target package for scenario: {}",
-                command.resource_key
-            );
-            // Would trigger package operations
-        }
+    let resource_name = action_resource_name(command);
+    let outcome = match command.action.as_str() {
+        "execute_action_on_target_package" => trigger_scenario_action(&resource_name).await,
         "log_denial_generate_alert" => {
-            logd!(
-                2,
-                " Logging denial and generating alert for scenario: {}",
-                command.resource_key
-            );
-            // Would integrate with alerting system
-        }
-        "start_model_creation_allocate_resources" => {
-            logd!(
-                2,
-                " Starting model creation and resource allocation for package: {}",
-                command.resource_key
-            );
-            // Would integrate with resource allocation system
-        }
-        "update_state_announce_availability" => {
-            logd!(
-                2,
-                " Updating state and announcing availability for: {}",
-                command.resource_key
-            );
-            // Would update service discovery and announce availability
+            raise_alert(command, "warning", "Scenario denied by policy verification").await
         }
         "log_warning_activate_partial_functionality" => {
-            logd!(
-                2,
-                " Logging warning and activating partial functionality for: {}",
-                command.resource_key
-            );
-            // Would configure degraded mode operation
+            raise_alert(command, "warning", "Running with partial functionality").await
         }
         "log_error_attempt_recovery" => {
-            logd!(
-                2,
-                " Logging error and attempting recovery for: {}",
-                command.resource_key
-            );
-            // Would trigger automated recovery procedures
+            match raise_alert(command, "error", "Attempting automatic recovery").await {
+                Ok(()) => recover_resource(command.resource_type, &resource_name).await,
+                Err(e) => Err(e),
+            }
         }
-        "pause_models_preserve_state" => {
-            logd!(
-                2,
-                " Pausing models and preserving state for: {}",
-                command.resource_key
-            );
-            // Would pause container execution and save state
+        "log_error_notify_for_manual_intervention" => {
+            raise_alert(command, "critical", "Manual intervention required").await
         }
-        "resume_models_restore_state" => {
-            logd!(
-                2,
-                " Resuming models and restoring state for: {}",
-                command.resource_key
-            );
-            // Would resume container execution and restore state
+        "start_model_recreation" => recover_resource(command.resource_type, &resource_name).await,
+        "log_warning_node_not_ready" => {
+            raise_alert(command, "warning", "Node missed heartbeats").await
         }
-        "start_node_selection_and_allocation" => {
-            logd!(
-                2,
-                " Starting node selection and allocation for model: {}",
-                command.resource_key
-            );
-            // Would integrate with scheduler for node allocation
+        "log_error_node_unreachable" => {
+            raise_alert(command, "critical", "Node is unreachable").await
         }
-        "pull_container_images_mount_volumes" => {
+        "reschedule_models_on_node" => reschedule_models_on_node(&resource_name).await,
+        "start_condition_evaluation"
+        | "start_policy_verification"
+        | "finalize_scenario"
+        | "start_model_creation_allocate_resources"
+        | "update_state_announce_availability"
+        | "pause_models_preserve_state"
+        | "resume_models_restore_state"
+        | "start_node_selection_and_allocation"
+        | "pull_container_images_mount_volumes"
+        | "update_state_start_readiness_checks"
+        | "log_completion_clean_up_resources"
+        | "set_backoff_timer_collect_logs"
+        | "attempt_diagnostics_restore_communication"
+        | "resume_monitoring_reset_counter"
+        | "synchronize_state_recover_if_needed"
+        | "register_node_start_monitoring" => {
             logd!(
                 2,
-                " Pulling container images and mounting volumes for model: {}",
+                " Action '{}' acknowledged for: {}",
+                command.action,
                 command.resource_key
             );
-            // Would trigger container image pulls and volume mounts
+            Ok(())
         }
-        "update_state_start_readiness_checks" => {
-            logd!(
-                2,
-                " Updating state and starting readiness checks for model: {}",
-                command.resource_key
-            );
-            // Would start health/readiness checks
+        _ => Err(format!("Unknown action: {}", command.action)),
+    };
+
+    // Print context information if available
+    if !command.context.is_empty() {
+        logd!(2, "    Context: {:?}", command.context);
+    }
+
+    match &outcome {
+        Ok(()) => logd!(
+            2,
+            "  ✓ Action '{}' completed for: {}",
+            command.action,
+            command.resource_key
+        ),
+        Err(e) => logd!(
+            4,
+            "  ✗ Action '{}' failed for {}: {}",
+            command.action,
+            command.resource_key,
+            e
+        ),
+    }
+    outcome
+}
+
+/// Resource name of an action, taken from its context or resource key
+fn action_resource_name(command: &ActionCommand) -> String {
+    match command.context.get("resource_name") {
+        Some(name) => name.clone(),
+        None => command
+            .resource_key
+            .rsplit("::")
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+    }
+}
+
+/// Ask ActionController to run the workloads targeted by a scenario
+async fn trigger_scenario_action(scenario_name: &str) -> std::result::Result<(), String> {
+    let request = common::actioncontroller::TriggerActionRequest {
+        scenario_name: scenario_name.to_string(),
+        dry_run: false,
+        action: String::new(),
+    };
+
+    match sender::trigger_action(request).await {
+        Ok(response) if response.get_ref().status == 0 => Ok(()),
+        Ok(response) => Err(format!(
+            "ActionController rejected scenario {}: {}",
+            scenario_name,
+            response.get_ref().desc
+        )),
+        Err(e) => Err(format!(
+            "Failed to trigger scenario {}: {}",
+            scenario_name, e
+        )),
+    }
+}
+
+/// Reconcile the scenarios owning a failed resource back to running
+async fn recover_resource(
+    resource_type: ResourceType,
+    resource_name: &str,
+) -> std::result::Result<(), String> {
+    let scenarios = match resource_type {
+        ResourceType::Scenario => vec![resource_name.to_string()],
+        ResourceType::Package => scenario_for_package(resource_name)
+            .await?
+            .into_iter()
+            .collect(),
+        ResourceType::Model => {
+            let mut scenarios = Vec::new();
+            for package in StateMachine::find_packages_containing_model(resource_name).await? {
+                if let Some(scenario) = scenario_for_package(&package).await? {
+                    scenarios.push(scenario);
+                }
+            }
+            scenarios
         }
-        "log_completion_clean_up_resources" => {
-            logd!(
-                2,
-                " Logging completion and cleaning up resources for model: {}",
-                command.resource_key
-            );
-            // Would clean up completed job resources
+        _ => {
+            return Err(format!(
+                "Recovery is not supported for resource type {:?}",
+                resource_type
+            ))
         }
-        "set_backoff_timer_collect_logs" => {
-            logd!(
-                2,
-                " Setting backoff timer and collecting logs for model: {}",
-                command.resource_key
-            );
-            // Would set exponential backoff and collect diagnostic logs
+    };
+
+    if scenarios.is_empty() {
+        return Err(format!("No scenario found for {}", resource_name));
+    }
+
+    for scenario_name in scenarios {
+        reconcile_scenario(&scenario_name).await?;
+    }
+    Ok(())
+}
+
+/// Reschedule the models assigned to a node that became Ready again
+///
+/// Every scenario whose package places a model on the node is reconciled
+/// back to running, so workloads lost while the node was away are recreated.
+async fn reschedule_models_on_node(node_name: &str) -> std::result::Result<(), String> {
+    let packages = common::etcd::get_all_of_kind("Package")
+        .await
+        .map_err(|e| format!("Failed to get packages from ETCD: {:?}", e))?;
+
+    let mut scenarios: Vec<String> = Vec::new();
+    for kv in packages {
+        let package = match serde_yaml::from_str::<common::spec::artifact::Package>(&kv.1) {
+            Ok(package) => package,
+            Err(e) => {
+                logd!(4, "      Failed to parse package {}: {:?}", kv.0, e);
+                continue;
+            }
+        };
+        if !package
+            .get_models()
+            .iter()
+            .any(|model| model.get_node() == node_name)
+        {
+            continue;
         }
-        "attempt_diagnostics_restore_communication" => {
-            logd!(
-                2,
-                " Attempting diagnostics and restoring communication for model: {}",
-                command.resource_key
-            );
-            // Would run diagnostic checks and restore node communication
+        if let Some(scenario) = scenario_for_package(&package.get_qualified_name()).await? {
+            if !scenarios.contains(&scenario) {
+                scenarios.push(scenario);
+            }
         }
-        "resume_monitoring_reset_counter" => {
-            logd!(
-                2,
-                " Resuming monitoring and resetting counter for model: {}",
-                command.resource_key
-            );
-            // Would resume monitoring and reset failure counters
+    }
+
+    if scenarios.is_empty() {
+        logd!(2, " No models scheduled on node {}", node_name);
+        return Ok(());
+    }
+
+    for scenario_name in scenarios {
+        reconcile_scenario(&scenario_name).await?;
+    }
+    Ok(())
+}
+
+/// Ask ActionController to reconcile the scenario that contains a package
+pub(crate) async fn reconcile_package(package_name: &str) -> std::result::Result<(), String> {
+    logd!(
+        3,
+        "      Triggering ActionController reconcile for package: {}",
+        package_name
+    );
+
+    // Find scenario that contains this package
+    let scenario_name = match scenario_for_package(package_name).await {
+        Ok(Some(name)) => name,
+        Ok(None) => {
+            logd!(4, "      No scenario found for package: {}", package_name);
+            return Err(format!("No scenario found for package: {}", package_name));
         }
-        "log_error_notify_for_manual_intervention" => {
+        Err(e) => {
             logd!(
-                2,
-                " Logging error and notifying for manual intervention for model: {}",
-                command.resource_key
+                4,
+                "      Failed to find scenario for package {}: {:?}",
+                package_name,
+                e
             );
-            // Would log critical error and notify operations team
+            return Err(format!("Failed to find scenario for package: {}", e));
         }
-        "synchronize_state_recover_if_needed" => {
+    };
+
+    // Create reconcile request using the gRPC sender
+    let reconcile_request = common::actioncontroller::ReconcileRequest {
+        scenario_name: scenario_name.clone(),
+        current: common::actioncontroller::PodStatus::Failed.into(),
+        desired: common::actioncontroller::PodStatus::Running.into(),
+    };
+
+    match sender::_send(reconcile_request).await {
+        Ok(response) => {
             logd!(
                 2,
-                " Synchronizing state and recovering if needed for model: {}",
-                command.resource_key
+                "      Successfully sent reconcile request for scenario: {}",
+                scenario_name
             );
-            // Would synchronize state and trigger recovery if necessary
-        }
-        "start_model_recreation" => {
             logd!(
-                2,
-                " Starting model recreation for: {}",
-                command.resource_key
+                1,
+                "      ActionController response: status={:?}",
+                response.get_ref().status
             );
-            // Would start complete model recreation process
+            Ok(())
         }
-        _ => {
-            logd!(
-                4,
-                " Unknown action: {} for resource: {}",
-                command.action,
-                command.resource_key
+        Err(e) => {
+            let error_msg = format!(
+                "Failed to send reconcile request to ActionController: {:?}",
+                e
             );
+            logd!(5, "      {}", error_msg);
+            Err(error_msg)
         }
     }
+}
 
-    // Print context information if available
-    if !command.context.is_empty() {
-        logd!(2, "    Context: {:?}", command.context);
-    }
+/// Ask ActionController to bring a scenario back to running
+async fn reconcile_scenario(scenario_name: &str) -> std::result::Result<(), String> {
+    let request = common::actioncontroller::ReconcileRequest {
+        scenario_name: scenario_name.to_string(),
+        current: common::actioncontroller::PodStatus::Failed.into(),
+        desired: common::actioncontroller::PodStatus::Running.into(),
+    };
+    sender::_send(request)
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("Failed to reconcile scenario {}: {}", scenario_name, e))
+}
+
+/// State name of a node as used in StateChange requests (e.g. "NOT_READY")
+fn node_state_name(state: NodeState) -> &'static str {
+    state
+        .as_str_name()
+        .strip_prefix("NODE_STATE_")
+        .unwrap_or(state.as_str_name())
+}
+
+/// Event bus notification of a completed transition, if it needs one
+fn transition_event(
+    resource_type: ResourceType,
+    resource_name: &str,
+    new_state: &str,
+    message: &str,
+) -> Option<Event> {
+    let kind = match (resource_type, new_state) {
+        (ResourceType::Scenario, "SCENARIO_STATE_DENIED") => EventKind::ScenarioDenied,
+        (ResourceType::Scenario, "SCENARIO_STATE_QUARANTINED") => EventKind::ScenarioQuarantined,
+        (ResourceType::Model, "MODEL_STATE_DEAD" | "MODEL_STATE_FAILED") => EventKind::ModelFailed,
+        _ => return None,
+    };
+    Some(
+        Event::new(kind, "statemanager", resource_name, message).with_attribute("state", new_state),
+    )
+}
+
+/// Store an alert for the resource of an action in etcd
+///
+/// The latest alert per resource is kept at `/alert/<resource type>/<name>`.
+async fn raise_alert(
+    command: &ActionCommand,
+    severity: &str,
+    reason: &str,
+) -> std::result::Result<(), String> {
+    let resource_name = action_resource_name(command);
+    let alert = serde_json::json!({
+        "severity": severity,
+        "reason": reason,
+        "action": command.action,
+        "resource": resource_name,
+        "transition_id": command.transition_id,
+        "context": command.context,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
 
     logd!(
-        2,
-        "  ✓ Action '{}' completed for: {}",
-        command.action,
-        command.resource_key
+        4,
+        " ALERT [{}] {}: {}",
+        severity,
+        command.resource_key,
+        reason
     );
+    store_alert(command.resource_type, &resource_name, &alert).await
+}
+
+/// Store the latest alert of a resource at `/alert/<resource type>/<name>`
+pub(crate) async fn store_alert(
+    resource_type: ResourceType,
+    resource_name: &str,
+    alert: &serde_json::Value,
+) -> std::result::Result<(), String> {
+    let key = format!(
+        "/alert/{}/{}",
+        format!("{:?}", resource_type).to_lowercase(),
+        resource_name
+    );
+    common::etcd::put(&key, &alert.to_string())
+        .await
+        .map_err(|e| format!("Failed to store alert {}: {}", key, e))
+}
+
+/// Restart policy and restart limit of a model
+///
+/// Models missing in etcd are restarted like `restartPolicy: Always` within
+/// the `max_retries` of the settings.
+async fn model_restart_policy(model_name: &str) -> (RestartPolicy, Option<u32>) {
+    let key = format!("Model/{}", model_name);
+    match common::etcd::get(&key).await {
+        Ok(yaml) => match serde_yaml::from_str::<common::spec::artifact::Model>(&yaml) {
+            Ok(model) => {
+                let spec = model.get_podspec();
+                (spec.get_restart_policy(), spec.get_max_restarts())
+            }
+            Err(e) => {
+                logd!(4, "      Failed to parse model {}: {:?}", model_name, e);
+                (RestartPolicy::default(), None)
+            }
+        },
+        Err(_) => (RestartPolicy::default(), None),
+    }
+}
+
+/// `cooldownSeconds` of a scenario, zero if the scenario is missing in etcd
+async fn scenario_cooldown(scenario_name: &str) -> std::time::Duration {
+    let key = common::spec::namespace::qualified_key("Scenario", scenario_name);
+    match common::etcd::get(&key).await {
+        Ok(yaml) => match serde_yaml::from_str::<common::spec::artifact::Scenario>(&yaml) {
+            Ok(scenario) => scenario.get_cooldown(),
+            Err(e) => {
+                logd!(
+                    4,
+                    "      Failed to parse scenario {}: {:?}",
+                    scenario_name,
+                    e
+                );
+                std::time::Duration::ZERO
+            }
+        },
+        Err(_) => std::time::Duration::ZERO,
+    }
+}
+
+/// Find scenario that contains the given package
+pub(crate) async fn scenario_for_package(
+    package_name: &str,
+) -> std::result::Result<Option<String>, String> {
+    // Get all scenarios of the package namespace from ETCD
+    let (namespace, package_name) = common::spec::namespace::split_qualified(package_name);
+    let prefix = common::spec::namespace::kind_prefix(namespace, "Scenario");
+    match common::etcd::get_all_with_prefix(&prefix).await {
+        Ok(scenario_entries) => {
+            for kv in scenario_entries {
+                match serde_yaml::from_str::<common::spec::artifact::Scenario>(&kv.1) {
+                    Ok(scenario) => {
+                        // Check if this scenario references the package
+                        if scenario.get_targets() == package_name {
+                            return Ok(Some(scenario.get_qualified_name()));
+                        }
+                    }
+                    Err(e) => {
+                        logd!(4, "      Failed to parse scenario {}: {:?}", kv.0, e);
+                    }
+                }
+            }
+            Ok(None) // No scenario found containing this package
+        }
+        Err(e) => {
+            logd!(4, "      Failed to get scenarios from ETCD: {:?}", e);
+            Err(format!("Failed to get scenarios from ETCD: {:?}", e))
+        }
+    }
 }
 
 // ========================================
@@ -1278,7 +1845,8 @@ mod integration_tests {
         action_controller_connection_server::{
             ActionControllerConnection, ActionControllerConnectionServer,
         },
-        CompleteNetworkSettingRequest, CompleteNetworkSettingResponse, OffloadModelRequest,
+        CompleteNetworkSettingRequest, CompleteNetworkSettingResponse, DrainNodeRequest,
+        DrainNodeResponse, MigrateNodeRequest, MigrateNodeResponse, OffloadModelRequest,
         OffloadModelResponse, ReconcileRequest, ReconcileResponse, TriggerActionRequest,
         TriggerActionResponse,
     };
@@ -1312,6 +1880,7 @@ mod integration_tests {
             Ok(Response::new(TriggerActionResponse {
                 status: 0,
                 desc: "Mock trigger action success".to_string(),
+                plan: None,
             }))
         }
 
@@ -1353,6 +1922,20 @@ mod integration_tests {
                 transition_id: "mock-transition-id".to_string(),
             }))
         }
+
+        async fn drain_node(
+            &self,
+            _request: Request<DrainNodeRequest>,
+        ) -> std::result::Result<Response<DrainNodeResponse>, Status> {
+            Ok(Response::new(DrainNodeResponse::default()))
+        }
+
+        async fn migrate_node(
+            &self,
+            _request: Request<MigrateNodeRequest>,
+        ) -> std::result::Result<Response<MigrateNodeResponse>, Status> {
+            Ok(Response::new(MigrateNodeResponse::default()))
+        }
     }
 
     #[tokio::test]
@@ -1500,6 +2083,38 @@ mod unit_tests {
     use tokio::sync::mpsc;
     use tokio::time::{timeout, Duration};
 
+    #[test]
+    fn test_transition_event_kinds() {
+        let denied = transition_event(
+            ResourceType::Scenario,
+            "antipinch",
+            "SCENARIO_STATE_DENIED",
+            "policy",
+        )
+        .unwrap();
+        assert_eq!(denied.kind(), EventKind::ScenarioDenied);
+        assert_eq!(denied.attributes["state"], "SCENARIO_STATE_DENIED");
+
+        let failed =
+            transition_event(ResourceType::Model, "core", "MODEL_STATE_FAILED", "").unwrap();
+        assert_eq!(failed.kind(), EventKind::ModelFailed);
+        assert_eq!(failed.resource_name, "core");
+
+        let quarantined = transition_event(
+            ResourceType::Scenario,
+            "antipinch",
+            "SCENARIO_STATE_QUARANTINED",
+            "flapping",
+        )
+        .unwrap();
+        assert_eq!(quarantined.kind(), EventKind::ScenarioQuarantined);
+
+        assert!(transition_event(ResourceType::Model, "core", "MODEL_STATE_RUNNING", "").is_none());
+        assert!(
+            transition_event(ResourceType::Package, "pkg", "PACKAGE_STATE_ERROR", "").is_none()
+        );
+    }
+
     #[tokio::test]
     async fn test_group_containers_by_model_groups_correctly() {
         let (tx_container, rx_container) = mpsc::channel::<ContainerList>(1);
@@ -1519,6 +2134,7 @@ mod unit_tests {
             config: HashMap::new(),
             annotation: annotation.clone(),
             stats: HashMap::new(),
+            usage: None,
         };
 
         let c2 = common::monitoringserver::ContainerInfo {
@@ -1529,6 +2145,7 @@ mod unit_tests {
             config: HashMap::new(),
             annotation: annotation.clone(),
             stats: HashMap::new(),
+            usage: None,
         };
 
         let containers = vec![c1.clone(), c2.clone()];
@@ -1548,7 +2165,10 @@ mod unit_tests {
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<ActionCommand>();
 
         // Spawn the executor
-        let handle = tokio::spawn(async move { run_action_executor(rx).await });
+        let handle =
+            tokio::spawn(
+                async move { run_action_executor(rx, Arc::new(StateMachine::new())).await },
+            );
 
         // Send a single action command
         let mut ctx = HashMap::new();
@@ -1588,6 +2208,7 @@ mod unit_tests {
             config: HashMap::new(),
             annotation: HashMap::new(),
             stats: HashMap::new(),
+            usage: None,
         };
 
         let extracted = manager.extract_model_name_from_container(&container).await;
@@ -1612,6 +2233,7 @@ mod unit_tests {
             config: HashMap::new(),
             annotation: ann1,
             stats: HashMap::new(),
+            usage: None,
         };
 
         let mut ann2 = HashMap::new();
@@ -1624,6 +2246,7 @@ mod unit_tests {
             config: HashMap::new(),
             annotation: ann2,
             stats: HashMap::new(),
+            usage: None,
         };
 
         let containers = vec![c1.clone(), c2.clone()];
@@ -1638,7 +2261,10 @@ mod unit_tests {
     #[tokio::test]
     async fn test_run_action_executor_handles_unknown_action_gracefully() {
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<ActionCommand>();
-        let handle = tokio::spawn(async move { run_action_executor(rx).await });
+        let handle =
+            tokio::spawn(
+                async move { run_action_executor(rx, Arc::new(StateMachine::new())).await },
+            );
 
         let cmd = ActionCommand {
             action: "nonexistent_action_xyz".to_string(),
@@ -1693,7 +2319,7 @@ mod unit_tests {
         };
 
         // Known action should execute without panic
-        super::execute_action(cmd_known).await;
+        let _ = super::execute_action(&cmd_known).await;
 
         // Unknown action should hit the default branch and not panic
         let cmd_unknown = ActionCommand {
@@ -1704,7 +2330,29 @@ mod unit_tests {
             context: HashMap::new(),
         };
 
-        super::execute_action(cmd_unknown).await;
+        assert!(super::execute_action(&cmd_unknown).await.is_err());
+    }
+
+    #[test]
+    fn test_action_resource_name_prefers_context() {
+        let mut cmd = ActionCommand {
+            action: "start_model_recreation".to_string(),
+            resource_key: "Model::m1".to_string(),
+            resource_type: common::statemanager::ResourceType::Model,
+            transition_id: "t1".to_string(),
+            context: HashMap::new(),
+        };
+        assert_eq!(super::action_resource_name(&cmd), "m1");
+
+        cmd.context
+            .insert("resource_name".to_string(), "model-a".to_string());
+        assert_eq!(super::action_resource_name(&cmd), "model-a");
+    }
+
+    #[tokio::test]
+    async fn test_recover_resource_rejects_unsupported_type() {
+        let res = super::recover_resource(common::statemanager::ResourceType::Volume, "vol1").await;
+        assert!(res.is_err());
     }
 
     #[tokio::test]
@@ -1752,10 +2400,50 @@ mod unit_tests {
                 transition_id: format!("t-{}", i),
                 context: HashMap::new(),
             };
-            super::execute_action(cmd).await;
+            let _ = super::execute_action(&cmd).await;
         }
     }
 
+    #[tokio::test]
+    async fn test_update_node_state_follows_heartbeats() {
+        let (_tx_container, rx_container) = mpsc::channel::<ContainerList>(1);
+        let (_tx_state_change, rx_state_change) =
+            mpsc::channel::<common::statemanager::StateChange>(1);
+        let manager = StateManagerManager::new(rx_container, rx_state_change).await;
+        let now = 10_000;
+
+        let node_state = |manager: &StateManagerManager| {
+            let state_machine = Arc::clone(&manager.state_machine);
+            async move {
+                state_machine
+                    .get_resource_state("node-hb", ResourceType::Node)
+                    .map(|rs| rs.current_state)
+            }
+        };
+
+        manager.update_node_state("node-hb", now - 1, now).await;
+        assert_eq!(node_state(&manager).await, Some(NodeState::Ready as i32));
+
+        // Ready never jumps straight to Unknown
+        manager.update_node_state("node-hb", now - 300, now).await;
+        assert_eq!(node_state(&manager).await, Some(NodeState::NotReady as i32));
+
+        manager.update_node_state("node-hb", now - 300, now).await;
+        assert_eq!(node_state(&manager).await, Some(NodeState::Unknown as i32));
+
+        manager.update_node_state("node-hb", now, now).await;
+        assert_eq!(node_state(&manager).await, Some(NodeState::Ready as i32));
+    }
+
+    #[test]
+    fn test_node_state_name_strips_prefix() {
+        assert_eq!(super::node_state_name(NodeState::NotReady), "NOT_READY");
+        assert_eq!(
+            super::node_state_name(NodeState::Unspecified),
+            "UNSPECIFIED"
+        );
+    }
+
     #[tokio::test]
     async fn test_handle_transition_failure_variants() {
         let (tx_container, rx_container) = mpsc::channel::<ContainerList>(1);
@@ -1771,6 +2459,8 @@ mod unit_tests {
             transition_id: "tid".to_string(),
             source: "test".to_string(),
             timestamp_ns: 0,
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: common::trace::current_trace_id().unwrap_or_default(),
         };
 
         use common::statemanager::ErrorCode;
@@ -1816,6 +2506,7 @@ mod unit_tests {
             config: HashMap::new(),
             annotation: ann,
             stats: HashMap::new(),
+            usage: None,
         };
 
         let cl = ContainerList {
@@ -1844,6 +2535,8 @@ mod unit_tests {
             transition_id: "t".to_string(),
             source: "s".to_string(),
             timestamp_ns: 0,
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: common::trace::current_trace_id().unwrap_or_default(),
         };
 
         manager.process_state_change(bad).await;
@@ -1970,6 +2663,8 @@ mod unit_tests {
             transition_id: "t1".to_string(),
             source: "test".to_string(),
             timestamp_ns: 0,
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: common::trace::current_trace_id().unwrap_or_default(),
         };
 
         tx_state_change
@@ -2003,6 +2698,8 @@ mod unit_tests {
             transition_id: "t-etcd".to_string(),
             timestamp_ns: 1,
             source: "unittest".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: common::trace::current_trace_id().unwrap_or_default(),
         };
 
         manager.process_state_change(sc.clone()).await;
@@ -2028,7 +2725,7 @@ mod unit_tests {
 
         // Should run without panic even if no packages found
         manager
-            .trigger_package_state_evaluation("no-packages")
+            .trigger_package_state_evaluation("no-packages", Some(Duration::ZERO))
             .await;
     }
 
@@ -2051,7 +2748,9 @@ mod unit_tests {
         let _ = common::etcd::put("/package/pkg-update/state", "running").await;
 
         // Trigger evaluation
-        manager.trigger_package_state_evaluation("mup").await;
+        manager
+            .trigger_package_state_evaluation("mup", Some(Duration::ZERO))
+            .await;
 
         // After evaluation, the package state should be updated (Error expected)
         let state = StateMachine::get_current_package_state("pkg-update").await;
@@ -2060,17 +2759,11 @@ mod unit_tests {
     }
 
     #[tokio::test]
-    async fn test_find_scenario_for_package_no_scenarios() {
-        let (tx_container, rx_container) = mpsc::channel::<ContainerList>(1);
-        let (tx_state_change, rx_state_change) =
-            mpsc::channel::<common::statemanager::StateChange>(1);
-
-        let manager = StateManagerManager::new(rx_container, rx_state_change).await;
-
+    async fn test_scenario_for_package_no_scenarios() {
         // Ensure no scenarios present
         let _ = common::etcd::delete("Scenario/nonexistent").await;
 
-        let res = manager.find_scenario_for_package("no-scn").await;
+        let res = scenario_for_package("no-scn").await;
         assert!(res.is_ok());
         let opt = res.unwrap();
         assert!(opt.is_none());
@@ -2087,4 +2780,60 @@ mod unit_tests {
         let res = manager.initialize().await;
         assert!(res.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_apply_backoff_fails_model_after_max_retries() {
+        let (_tx_container, rx_container) = mpsc::channel::<ContainerList>(1);
+        let (_tx_state_change, rx_state_change) =
+            mpsc::channel::<common::statemanager::StateChange>(1);
+
+        let manager = StateManagerManager::new(rx_container, rx_state_change).await;
+        let model = "backoff-manager-test-model";
+        manager.backoff.lock().await.reset(model).await;
+        let max_retries = common::setting::get_config().backoff.max_retries;
+
+        for _ in 0..max_retries {
+            let mut state = ModelState::Dead;
+            assert!(manager.apply_backoff(model, &mut state).await.is_some());
+            assert_eq!(state, ModelState::Dead);
+        }
+        let mut state = ModelState::Dead;
+        assert_eq!(manager.apply_backoff(model, &mut state).await, None);
+        assert_eq!(state, ModelState::Failed);
+
+        // Running again clears the Failed counter
+        let mut state = ModelState::Running;
+        assert_eq!(
+            manager.apply_backoff(model, &mut state).await,
+            Some(Duration::ZERO)
+        );
+        assert!(!manager.backoff.lock().await.is_failed(model).await);
+    }
+
+    #[tokio::test]
+    async fn test_process_state_change_counts_deadline_miss() {
+        let (_tx_container, rx_container) = mpsc::channel::<ContainerList>(1);
+        let (_tx_state_change, rx_state_change) =
+            mpsc::channel::<common::statemanager::StateChange>(1);
+        let manager = StateManagerManager::new(rx_container, rx_state_change).await;
+
+        // Sent one second ago, far beyond the ASIL D deadline
+        let sent_ns = chrono::Utc::now().timestamp_nanos_opt().unwrap() - 1_000_000_000;
+        let sc = StateChange {
+            resource_type: ResourceType::Package as i32,
+            resource_name: "late-package".to_string(),
+            current_state: "idle".to_string(),
+            target_state: "running".to_string(),
+            transition_id: "t-late".to_string(),
+            timestamp_ns: sent_ns,
+            source: "unittest".to_string(),
+            asil_level: common::statemanager::AsilLevel::D as i32,
+            trace_id: common::trace::current_trace_id().unwrap_or_default(),
+        };
+        manager.process_state_change(sc).await;
+
+        assert!(common::metrics::render().contains(
+            "pullpiri_state_transition_deadline_misses_total{asil_level=\"ASIL_LEVEL_D\",resource_type=\"RESOURCE_TYPE_PACKAGE\"}"
+        ));
+    }
 }
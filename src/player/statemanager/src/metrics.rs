@@ -0,0 +1,220 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Prometheus `/metrics` endpoint of the StateManager
+//!
+//! Exports processed StateChanges, transition failures by error code,
+//! transition durations and deadline misses by ASIL level, the age of node
+//! heartbeats, producers throttled by full input queues, states edited in
+//! etcd behind its back, recoveries of failed models by [`crate::recovery`],
+//! duplicate StateChanges answered by [`crate::dedup`] and the etcd latencies
+//! recorded by `common::etcd`.
+//! The same listener serves the audit query API of [`crate::audit`] and the
+//! `/healthz` and `/readyz` endpoints of [`common::health`].
+
+use axum::{http::header, response::IntoResponse, routing::get, Router};
+use common::logd;
+use common::metrics;
+use common::statemanager::{AsilLevel, ErrorCode, ResourceType};
+
+pub const STATE_CHANGES_TOTAL: &str = "pullpiri_state_changes_total";
+pub const TRANSITION_FAILURES_TOTAL: &str = "pullpiri_state_transition_failures_total";
+pub const NODE_HEARTBEAT_AGE_SECONDS: &str = "pullpiri_node_heartbeat_age_seconds";
+pub const TRANSITION_DURATION_SECONDS: &str = "pullpiri_state_transition_duration_seconds";
+pub const DEADLINE_MISSES_TOTAL: &str = "pullpiri_state_transition_deadline_misses_total";
+pub const INGEST_THROTTLED_TOTAL: &str = "pullpiri_statemanager_ingest_throttled_total";
+pub const STATE_DRIFT_TOTAL: &str = "pullpiri_state_drift_total";
+pub const RECOVERIES_TOTAL: &str = "pullpiri_model_recoveries_total";
+pub const DEDUP_HITS_TOTAL: &str = "pullpiri_state_change_dedup_hits_total";
+
+/// Bucket bounds of transition durations in seconds
+const TRANSITION_DURATION_BUCKETS: [f64; 10] =
+    [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5];
+
+/// Count a StateChange taken up for processing
+pub fn record_state_change(resource_type: ResourceType) {
+    metrics::inc_counter(
+        STATE_CHANGES_TOTAL,
+        "StateChange requests processed",
+        &[("resource_type", resource_type.as_str_name())],
+    );
+}
+
+/// Count a failed state transition
+pub fn record_transition_failure(resource_type: ResourceType, error_code: ErrorCode) {
+    metrics::inc_counter(
+        TRANSITION_FAILURES_TOTAL,
+        "State transitions that failed, by error code",
+        &[
+            ("resource_type", resource_type.as_str_name()),
+            ("error_code", error_code.as_str_name()),
+        ],
+    );
+}
+
+/// Record how long a state transition took, by ASIL level
+pub fn record_transition_duration(
+    resource_type: ResourceType,
+    asil_level: AsilLevel,
+    seconds: f64,
+) {
+    metrics::observe_histogram(
+        TRANSITION_DURATION_SECONDS,
+        "Time from the StateChange request to the completed transition",
+        &[
+            ("resource_type", resource_type.as_str_name()),
+            ("asil_level", asil_level.as_str_name()),
+        ],
+        &TRANSITION_DURATION_BUCKETS,
+        seconds,
+    );
+}
+
+/// Count a transition that completed after the deadline of its ASIL level
+pub fn record_deadline_miss(resource_type: ResourceType, asil_level: AsilLevel) {
+    metrics::inc_counter(
+        DEADLINE_MISSES_TOTAL,
+        "State transitions that missed the deadline of their ASIL level",
+        &[
+            ("resource_type", resource_type.as_str_name()),
+            ("asil_level", asil_level.as_str_name()),
+        ],
+    );
+}
+
+/// Record the seconds since the last heartbeat of a node
+pub fn record_heartbeat_age(node_name: &str, age_seconds: i64) {
+    metrics::set_gauge(
+        NODE_HEARTBEAT_AGE_SECONDS,
+        "Seconds since the last heartbeat of a node",
+        &[("node", node_name)],
+        age_seconds.max(0) as f64,
+    );
+}
+
+/// Count a state edited in etcd that differed from the StateManager's
+pub fn record_state_drift(resource_type: ResourceType, resolution: &str) {
+    metrics::inc_counter(
+        STATE_DRIFT_TOTAL,
+        "Resource states in etcd that differed from the StateManager, by resolution",
+        &[
+            ("resource_type", resource_type.as_str_name()),
+            ("resolution", resolution),
+        ],
+    );
+}
+
+/// Count a message whose producer waited for room in a full queue
+pub fn record_ingest_throttled(source: &str) {
+    metrics::inc_counter(
+        INGEST_THROTTLED_TOTAL,
+        "Messages whose producer was throttled by a full input queue",
+        &[("source", source)],
+    );
+}
+
+/// Count a recovery strategy carried out for a failed model
+pub fn record_recovery(strategy: &str, result: &str) {
+    metrics::inc_counter(
+        RECOVERIES_TOTAL,
+        "Recovery strategies carried out for failed models",
+        &[("strategy", strategy), ("result", result)],
+    );
+}
+
+/// Count a duplicate StateChange answered without processing it again
+pub fn record_dedup_hit(resource_type: ResourceType) {
+    metrics::inc_counter(
+        DEDUP_HITS_TOTAL,
+        "StateChanges whose transition_id was already processed",
+        &[("resource_type", resource_type.as_str_name())],
+    );
+}
+
+pub fn router() -> Router {
+    Router::new().route("/metrics", get(render))
+}
+
+async fn render() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, metrics::CONTENT_TYPE)],
+        metrics::render(),
+    )
+}
+
+/// Serve the metrics endpoint and the audit query API
+pub async fn launch_metrics_server() {
+    // Skip binding the metrics port when running tests or explicitly requested
+    if cfg!(test) || std::env::var("PULLPIRI_TEST_MODE").is_ok() {
+        logd!(1, "Test mode: skipping metrics server startup");
+        return;
+    }
+    let addr = common::statemanager::open_metrics_server();
+    let listener = match tokio::net::TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            logd!(5, "Failed to bind metrics endpoint {}: {}", addr, e);
+            return;
+        }
+    };
+    logd!(3, "StateManager metrics listening on {}", addr);
+
+    let app = router()
+        .merge(crate::audit::router())
+        .merge(common::health::router());
+    if let Err(e) = axum::serve(listener, app).await {
+        logd!(5, "Metrics server error: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_change_metrics() {
+        record_state_change(ResourceType::Model);
+        record_transition_failure(ResourceType::Model, ErrorCode::PreconditionFailed);
+        record_heartbeat_age("metrics-node", 12);
+        record_heartbeat_age("clock-skew-node", -3);
+        record_transition_duration(ResourceType::Volume, AsilLevel::D, 0.02);
+        record_deadline_miss(ResourceType::Volume, AsilLevel::D);
+
+        let text = metrics::render();
+        assert!(
+            text.contains("pullpiri_state_changes_total{resource_type=\"RESOURCE_TYPE_MODEL\"}")
+        );
+        assert!(text.contains(
+            "pullpiri_state_transition_failures_total{error_code=\"ERROR_CODE_PRECONDITION_FAILED\",resource_type=\"RESOURCE_TYPE_MODEL\"}"
+        ));
+        assert!(text.contains("pullpiri_node_heartbeat_age_seconds{node=\"metrics-node\"} 12\n"));
+        assert!(text.contains("pullpiri_node_heartbeat_age_seconds{node=\"clock-skew-node\"} 0\n"));
+        assert!(text.contains(
+            "pullpiri_state_transition_duration_seconds_bucket{asil_level=\"ASIL_LEVEL_D\",resource_type=\"RESOURCE_TYPE_VOLUME\",le=\"0.025\"} 1\n"
+        ));
+        assert!(text.contains(
+            "pullpiri_state_transition_deadline_misses_total{asil_level=\"ASIL_LEVEL_D\",resource_type=\"RESOURCE_TYPE_VOLUME\"} 1\n"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint() {
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode};
+        use tower::ServiceExt;
+
+        record_state_change(ResourceType::Scenario);
+        let response = router()
+            .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).contains(STATE_CHANGES_TOTAL));
+    }
+}
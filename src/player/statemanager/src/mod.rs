@@ -7,9 +7,20 @@
 //!
 //! This module provides the public interface for the StateManager component
 
+pub mod audit;
+pub mod backoff;
+pub mod drift;
+pub mod expiry;
+pub mod flap;
 pub mod grpc;
+pub mod history;
+pub mod ingest;
 pub mod manager;
+pub mod metrics;
+pub mod recovery;
+pub mod state_cache;
 pub mod state_machine;
+pub mod timing;
 pub mod types;
 
 // Re-export main types for easier access
@@ -0,0 +1,533 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Recovery of failed models
+//!
+//! A model is Failed once its restart backoff gave up. Its recovery tries the
+//! strategies listed in the [`STRATEGIES_ANNOTATION`] of its package, else
+//! the `recovery.strategies` of the settings, in order and each
+//! [`ATTEMPTS_ANNOTATION`] (else `recovery.attempts`) times, one attempt per
+//! failure:
+//!
+//! - `restart`: ActionController reconciles the scenario of the package
+//! - `reschedule`: ActionController moves the model to another Ready node
+//! - `rollback`: ActionController restarts the models of the scenario from
+//!   their last revision verified as Running
+//! - `manual`: an alert asks for manual intervention and recovery stops
+//!
+//! A strategy that cannot be carried out is skipped for the next one. Once
+//! every strategy was tried an alert escalates the failure. Attempts are
+//! forgotten when the model did not fail for `recovery.reset_after_secs`.
+
+use crate::grpc::sender;
+use common::logd;
+use common::setting::RecoverySettings;
+use common::statemanager::ResourceType;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Package annotation listing the strategies, e.g. `restart,reschedule,manual`
+pub const STRATEGIES_ANNOTATION: &str = "recovery.pullpiri.io/strategies";
+
+/// Package annotation with the attempts of each strategy
+pub const ATTEMPTS_ANNOTATION: &str = "recovery.pullpiri.io/attempts";
+
+/// How a failed model is recovered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryType {
+    Restart,
+    Reschedule,
+    Rollback,
+    Manual,
+}
+
+impl RecoveryType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Restart => "restart",
+            Self::Reschedule => "reschedule",
+            Self::Rollback => "rollback",
+            Self::Manual => "manual",
+        }
+    }
+}
+
+impl std::str::FromStr for RecoveryType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "restart" => Ok(Self::Restart),
+            "reschedule" => Ok(Self::Reschedule),
+            "rollback" => Ok(Self::Rollback),
+            "manual" => Ok(Self::Manual),
+            other => Err(format!(
+                "Unknown recovery strategy '{}', expected restart, reschedule, rollback or manual",
+                other
+            )),
+        }
+    }
+}
+
+/// Strategies of a model and the attempts of each
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecoveryPlan {
+    pub strategies: Vec<RecoveryType>,
+    pub attempts: u32,
+}
+
+impl RecoveryPlan {
+    /// Plan of the settings, overridden by the annotations of the package
+    ///
+    /// # Errors
+    ///
+    /// Returns why an annotation or the settings are not valid.
+    pub fn new(
+        annotations: &HashMap<String, String>,
+        settings: &RecoverySettings,
+    ) -> Result<Self, String> {
+        let strategies = match annotations.get(STRATEGIES_ANNOTATION) {
+            Some(list) => list
+                .split(',')
+                .filter(|s| !s.trim().is_empty())
+                .map(str::parse)
+                .collect::<Result<Vec<_>, _>>()?,
+            None => settings
+                .strategies
+                .iter()
+                .map(|s| s.parse())
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+        let attempts = match annotations.get(ATTEMPTS_ANNOTATION) {
+            Some(attempts) => attempts.trim().parse().map_err(|_| {
+                format!(
+                    "{} must be a number, not '{}'",
+                    ATTEMPTS_ANNOTATION, attempts
+                )
+            })?,
+            None => settings.attempts,
+        };
+        Ok(Self {
+            strategies,
+            attempts: attempts.max(1),
+        })
+    }
+}
+
+/// Next step of the recovery of a model
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    /// Carry out the strategy, `attempt` counting from 1 per strategy
+    Attempt {
+        strategy: RecoveryType,
+        attempt: u32,
+    },
+    /// Every strategy was tried, the failure is escalated once
+    Escalate { attempts: u32 },
+    /// Recovery was escalated or handed over to an operator
+    Stopped,
+}
+
+/// Attempts made for one model
+#[derive(Debug)]
+struct Attempts {
+    made: u32,
+    last: Instant,
+    stopped: bool,
+    running: bool,
+}
+
+/// Recovery attempts of every failed model
+#[derive(Debug, Default)]
+pub struct RecoveryTracker {
+    models: HashMap<String, Attempts>,
+}
+
+impl RecoveryTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start recovering a model, `false` if its recovery is already running
+    ///
+    /// Attempts made more than `reset_after` before `now` are forgotten.
+    pub fn begin(&mut self, model: &str, now: Instant, reset_after: Duration) -> bool {
+        let attempts = self.models.entry(model.to_string()).or_insert(Attempts {
+            made: 0,
+            last: now,
+            stopped: false,
+            running: false,
+        });
+        if attempts.running {
+            return false;
+        }
+        if now.saturating_duration_since(attempts.last) >= reset_after {
+            attempts.made = 0;
+            attempts.stopped = false;
+        }
+        attempts.running = true;
+        true
+    }
+
+    /// Take the next step of a model whose recovery began
+    pub fn next(&mut self, model: &str, plan: &RecoveryPlan, now: Instant) -> Step {
+        let Some(attempts) = self.models.get_mut(model) else {
+            return Step::Stopped;
+        };
+        if attempts.stopped {
+            return Step::Stopped;
+        }
+        attempts.last = now;
+        let index = (attempts.made / plan.attempts) as usize;
+        let Some(&strategy) = plan.strategies.get(index) else {
+            attempts.stopped = true;
+            return Step::Escalate {
+                attempts: attempts.made,
+            };
+        };
+        attempts.made += 1;
+        if strategy == RecoveryType::Manual {
+            attempts.stopped = true;
+        }
+        Step::Attempt {
+            strategy,
+            attempt: attempts.made - index as u32 * plan.attempts,
+        }
+    }
+
+    /// Skip the remaining attempts of the strategy that was just tried
+    pub fn skip_strategy(&mut self, model: &str, plan: &RecoveryPlan) {
+        if let Some(attempts) = self.models.get_mut(model) {
+            attempts.made = attempts.made.div_ceil(plan.attempts) * plan.attempts;
+        }
+    }
+
+    /// End the recovery of a model until it fails again
+    pub fn end(&mut self, model: &str) {
+        if let Some(attempts) = self.models.get_mut(model) {
+            attempts.running = false;
+        }
+    }
+}
+
+fn with_tracker<R>(f: impl FnOnce(&mut RecoveryTracker) -> R) -> R {
+    static TRACKER: OnceLock<Mutex<RecoveryTracker>> = OnceLock::new();
+    let mut tracker = TRACKER
+        .get_or_init(|| Mutex::new(RecoveryTracker::new()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    f(&mut tracker)
+}
+
+/// Recover a model that became Failed
+///
+/// ### Parameters
+/// * `model_name: &str` - name of the failed model
+/// ### Description
+/// Carries out the next strategy of the model; a strategy that fails is
+/// skipped for the following one within the same call.
+pub async fn recover_model(model_name: &str) {
+    let settings = common::setting::get_config().recovery.clone();
+    let reset_after = Duration::from_secs(settings.reset_after_secs);
+    if !with_tracker(|tracker| tracker.begin(model_name, Instant::now(), reset_after)) {
+        return;
+    }
+
+    let package = match crate::state_machine::StateMachine::find_packages_containing_model(
+        model_name,
+    )
+    .await
+    {
+        Ok(packages) => packages.into_iter().next(),
+        Err(e) => {
+            logd!(
+                4,
+                "Cannot find the package of failed model {}: {}",
+                model_name,
+                e
+            );
+            None
+        }
+    };
+    let annotations = match &package {
+        Some(package) => package_annotations(package).await,
+        None => HashMap::new(),
+    };
+    let plan = RecoveryPlan::new(&annotations, &settings).unwrap_or_else(|e| {
+        logd!(
+            4,
+            "Invalid recovery of model {}, using the settings: {}",
+            model_name,
+            e
+        );
+        RecoveryPlan::new(&HashMap::new(), &settings).unwrap_or(RecoveryPlan {
+            strategies: vec![RecoveryType::Manual],
+            attempts: 1,
+        })
+    });
+
+    loop {
+        let step = with_tracker(|tracker| tracker.next(model_name, &plan, Instant::now()));
+        let (strategy, attempt) = match step {
+            Step::Attempt { strategy, attempt } => (strategy, attempt),
+            Step::Escalate { attempts } => {
+                let reason = format!(
+                    "Recovery failed after {} attempts, manual intervention required",
+                    attempts
+                );
+                alert(model_name, "critical", &reason, None).await;
+                break;
+            }
+            Step::Stopped => break,
+        };
+        logd!(
+            4,
+            "Recovering failed model {}: {} (attempt {}/{})",
+            model_name,
+            strategy.as_str(),
+            attempt,
+            plan.attempts
+        );
+        let result = match (strategy, package.as_deref()) {
+            (RecoveryType::Manual, _) => {
+                alert(
+                    model_name,
+                    "critical",
+                    "Manual intervention required",
+                    Some(strategy),
+                )
+                .await;
+                Ok(())
+            }
+            (_, None) => Err("the model belongs to no package".to_string()),
+            (RecoveryType::Restart, Some(package)) => {
+                crate::manager::reconcile_package(package).await
+            }
+            (RecoveryType::Reschedule, Some(package)) => reschedule(model_name, package).await,
+            (RecoveryType::Rollback, Some(package)) => rollback(package).await,
+        };
+        match result {
+            Ok(()) => {
+                crate::metrics::record_recovery(strategy.as_str(), "started");
+                break;
+            }
+            Err(e) => {
+                crate::metrics::record_recovery(strategy.as_str(), "failed");
+                logd!(
+                    4,
+                    "Recovery of model {} by {} failed: {}",
+                    model_name,
+                    strategy.as_str(),
+                    e
+                );
+                with_tracker(|tracker| tracker.skip_strategy(model_name, &plan));
+            }
+        }
+    }
+    with_tracker(|tracker| tracker.end(model_name));
+}
+
+/// Annotations of a package, empty if it cannot be read
+async fn package_annotations(package_name: &str) -> HashMap<String, String> {
+    load_package(package_name)
+        .await
+        .map(|package| package.get_annotations())
+        .unwrap_or_default()
+}
+
+/// Package stored in etcd
+async fn load_package(package_name: &str) -> Result<common::spec::artifact::Package, String> {
+    let yaml = common::etcd::get(&common::spec::namespace::qualified_key(
+        "Package",
+        package_name,
+    ))
+    .await
+    .map_err(|e| format!("Failed to read package {}: {}", package_name, e))?;
+    serde_yaml::from_str(&yaml).map_err(|e| format!("Invalid package {}: {}", package_name, e))
+}
+
+/// Node a model of a package runs on, the one it was placed on if `auto`
+async fn node_of(model_name: &str, package_name: &str) -> Result<String, String> {
+    if let Ok(node) = common::etcd::get(&format!("Placement/{}", model_name)).await {
+        return Ok(node);
+    }
+    load_package(package_name)
+        .await?
+        .get_models()
+        .iter()
+        .find(|mi| mi.get_name() == model_name && !mi.is_auto_node())
+        .map(|mi| mi.get_node())
+        .ok_or_else(|| format!("No node is known for model {}", model_name))
+}
+
+/// Scenario of a package, failing if there is none
+async fn scenario_of(package_name: &str) -> Result<String, String> {
+    crate::manager::scenario_for_package(package_name)
+        .await?
+        .ok_or_else(|| format!("No scenario found for package {}", package_name))
+}
+
+/// Ask ActionController to move the model off the node it failed on
+async fn reschedule(model_name: &str, package_name: &str) -> Result<(), String> {
+    let source_node = node_of(model_name, package_name).await?;
+    let request = common::actioncontroller::OffloadModelRequest {
+        scenario_name: scenario_of(package_name).await?,
+        package_name: package_name.to_string(),
+        model_name: model_name.to_string(),
+        source_node,
+        target_node: String::new(),
+        policy_name: String::new(),
+        reason: "recovery of a failed model".to_string(),
+    };
+    let response = sender::offload_model(request)
+        .await
+        .map_err(|e| e.message().to_string())?
+        .into_inner();
+    if response.success {
+        Ok(())
+    } else {
+        Err(response.message)
+    }
+}
+
+/// Ask ActionController to restore the last good revision of the models
+async fn rollback(package_name: &str) -> Result<(), String> {
+    let request = common::actioncontroller::TriggerActionRequest {
+        scenario_name: scenario_of(package_name).await?,
+        dry_run: false,
+        action: "rollback".to_string(),
+    };
+    let response = sender::trigger_action(request)
+        .await
+        .map_err(|e| e.message().to_string())?
+        .into_inner();
+    if response.status == 0 {
+        Ok(())
+    } else {
+        Err(response.desc)
+    }
+}
+
+/// Store an alert about the recovery of a model
+async fn alert(model_name: &str, severity: &str, reason: &str, strategy: Option<RecoveryType>) {
+    logd!(4, " ALERT [{}] Model::{}: {}", severity, model_name, reason);
+    let alert = serde_json::json!({
+        "severity": severity,
+        "reason": reason,
+        "action": "recovery",
+        "resource": model_name,
+        "context": { "strategy": strategy.map(|s| s.as_str()).unwrap_or_default() },
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+    if let Err(e) = crate::manager::store_alert(ResourceType::Model, model_name, &alert).await {
+        logd!(5, "{}", e);
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(strategies: &[RecoveryType], attempts: u32) -> RecoveryPlan {
+        RecoveryPlan {
+            strategies: strategies.to_vec(),
+            attempts,
+        }
+    }
+
+    #[test]
+    fn test_plan_from_annotations() {
+        let settings = RecoverySettings::default();
+        assert_eq!(
+            RecoveryPlan::new(&HashMap::new(), &settings).unwrap(),
+            plan(&[RecoveryType::Manual], 1)
+        );
+
+        let annotations = HashMap::from([
+            (
+                STRATEGIES_ANNOTATION.to_string(),
+                "restart, reschedule,rollback".to_string(),
+            ),
+            (ATTEMPTS_ANNOTATION.to_string(), "2".to_string()),
+        ]);
+        assert_eq!(
+            RecoveryPlan::new(&annotations, &settings).unwrap(),
+            plan(
+                &[
+                    RecoveryType::Restart,
+                    RecoveryType::Reschedule,
+                    RecoveryType::Rollback
+                ],
+                2
+            )
+        );
+
+        let invalid = HashMap::from([(STRATEGIES_ANNOTATION.to_string(), "reboot".to_string())]);
+        assert!(RecoveryPlan::new(&invalid, &settings).is_err());
+    }
+
+    #[test]
+    fn test_strategies_are_tried_in_order_then_escalated() {
+        let plan = plan(&[RecoveryType::Restart, RecoveryType::Reschedule], 2);
+        let mut tracker = RecoveryTracker::new();
+        let now = Instant::now();
+        let reset_after = Duration::from_secs(600);
+
+        let mut steps = Vec::new();
+        for _ in 0..6 {
+            assert!(tracker.begin("m", now, reset_after));
+            // A running recovery is not started twice
+            assert!(!tracker.begin("m", now, reset_after));
+            steps.push(tracker.next("m", &plan, now));
+            tracker.end("m");
+        }
+        let attempt = |strategy, attempt| Step::Attempt { strategy, attempt };
+        assert_eq!(
+            steps,
+            vec![
+                attempt(RecoveryType::Restart, 1),
+                attempt(RecoveryType::Restart, 2),
+                attempt(RecoveryType::Reschedule, 1),
+                attempt(RecoveryType::Reschedule, 2),
+                Step::Escalate { attempts: 4 },
+                Step::Stopped,
+            ]
+        );
+
+        // Attempts are forgotten after a quiet period
+        assert!(tracker.begin("m", now + reset_after, reset_after));
+        assert_eq!(
+            tracker.next("m", &plan, now + reset_after),
+            attempt(RecoveryType::Restart, 1)
+        );
+    }
+
+    #[test]
+    fn test_failed_strategy_is_skipped_and_manual_stops() {
+        let plan = plan(&[RecoveryType::Reschedule, RecoveryType::Manual], 3);
+        let mut tracker = RecoveryTracker::new();
+        let now = Instant::now();
+        tracker.begin("m", now, Duration::from_secs(600));
+
+        assert_eq!(
+            tracker.next("m", &plan, now),
+            Step::Attempt {
+                strategy: RecoveryType::Reschedule,
+                attempt: 1
+            }
+        );
+        tracker.skip_strategy("m", &plan);
+        assert_eq!(
+            tracker.next("m", &plan, now),
+            Step::Attempt {
+                strategy: RecoveryType::Manual,
+                attempt: 1
+            }
+        );
+        assert_eq!(tracker.next("m", &plan, now), Step::Stopped);
+    }
+}
@@ -0,0 +1,166 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Sharded cache of resource states
+//!
+//! The states tracked by the [`StateMachine`](crate::state_machine::StateMachine)
+//! are split into shards by resource key, each with its own lock. Transitions
+//! of resources in different shards run in parallel, while every change of a
+//! resource goes through the lock of its shard and is applied in order.
+
+use crate::types::ResourceState;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Mutex, MutexGuard};
+
+/// Number of shards of [`StateCache::new`]
+pub const DEFAULT_SHARDS: usize = 16;
+
+type Shard = HashMap<String, ResourceState>;
+
+/// Resource states keyed by resource key, see [`crate::state_machine`]
+pub struct StateCache {
+    shards: Vec<Mutex<Shard>>,
+}
+
+impl StateCache {
+    pub fn new() -> Self {
+        Self::with_shards(DEFAULT_SHARDS)
+    }
+
+    /// Cache with the given number of shards, at least one
+    pub fn with_shards(count: usize) -> Self {
+        Self {
+            shards: (0..count.max(1))
+                .map(|_| Mutex::new(HashMap::new()))
+                .collect(),
+        }
+    }
+
+    fn lock(shard: &Mutex<Shard>) -> MutexGuard<'_, Shard> {
+        // A panic while a shard was locked leaves at most one resource half
+        // updated, which is better than losing every state of the shard
+        shard.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn shard(&self, resource_key: &str) -> MutexGuard<'_, Shard> {
+        let mut hasher = DefaultHasher::new();
+        resource_key.hash(&mut hasher);
+        let index = hasher.finish() as usize % self.shards.len();
+        Self::lock(&self.shards[index])
+    }
+
+    /// Run `f` on the shard of a resource, holding its lock
+    ///
+    /// Used for read-modify-write changes of a resource, which must not be
+    /// interleaved with other changes of the same resource. `f` must not
+    /// access the cache again.
+    pub fn with_shard<R>(&self, resource_key: &str, f: impl FnOnce(&mut Shard) -> R) -> R {
+        f(&mut self.shard(resource_key))
+    }
+
+    /// Copy of the state of a resource
+    pub fn get(&self, resource_key: &str) -> Option<ResourceState> {
+        self.shard(resource_key).get(resource_key).cloned()
+    }
+
+    pub fn remove(&self, resource_key: &str) -> Option<ResourceState> {
+        self.shard(resource_key).remove(resource_key)
+    }
+
+    /// Copy of every state, locking one shard at a time
+    pub fn values(&self) -> Vec<ResourceState> {
+        self.shards
+            .iter()
+            .flat_map(|shard| Self::lock(shard).values().cloned().collect::<Vec<_>>())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| Self::lock(shard).len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for StateCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::HealthStatus;
+    use common::statemanager::ResourceType;
+    use std::sync::Arc;
+    use tokio::time::Instant;
+
+    fn resource(name: &str) -> ResourceState {
+        ResourceState {
+            resource_type: ResourceType::Model,
+            resource_name: name.to_string(),
+            current_state: 0,
+            desired_state: None,
+            last_transition_time: Instant::now(),
+            transition_count: 0,
+            metadata: HashMap::new(),
+            health_status: HealthStatus {
+                healthy: true,
+                status_message: "Healthy".to_string(),
+                last_check: Instant::now(),
+                consecutive_failures: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_state_cache_keeps_every_key() {
+        let cache = StateCache::with_shards(4);
+        for i in 0..100 {
+            let key = format!("model::m{i}");
+            cache.with_shard(&key, |shard| shard.insert(key.clone(), resource(&key)));
+        }
+        assert_eq!(cache.len(), 100);
+        assert_eq!(cache.values().len(), 100);
+        assert_eq!(cache.get("model::m7").unwrap().resource_name, "model::m7");
+        assert!(cache.remove("model::m7").is_some());
+        assert!(cache.get("model::m7").is_none());
+        assert_eq!(cache.len(), 99);
+    }
+
+    #[test]
+    fn test_state_cache_orders_changes_of_a_resource() {
+        let cache = Arc::new(StateCache::new());
+        cache.with_shard("model::shared", |shard| {
+            shard.insert("model::shared".to_string(), resource("shared"))
+        });
+
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let cache = Arc::clone(&cache);
+                std::thread::spawn(move || {
+                    for _ in 0..1000 {
+                        cache.with_shard("model::shared", |shard| {
+                            shard.get_mut("model::shared").unwrap().transition_count += 1;
+                        });
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(cache.get("model::shared").unwrap().transition_count, 8000);
+    }
+}
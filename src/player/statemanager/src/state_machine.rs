@@ -5,7 +5,7 @@
 
 //! State Machine Implementation for Pullpiri Resource State Management
 //!
-//! This module implements the core state transition logic for Scenario, Package, and Model resources
+//! This module implements the core state transition logic for Scenario, Package, Model, and Node resources
 //! according to the Pullpiri specification. It provides efficient data structures and algorithms
 //! for managing state changes and enforcing the defined state transition tables.
 //!
@@ -21,21 +21,32 @@
 //! # Usage Example
 //!
 //! ```rust
-//! let mut state_machine = StateMachine::new();
+//! let state_machine = StateMachine::new();
 //! let state_change = StateChange { /* ... */ };
 //! let result = state_machine.process_state_change(state_change);
 //! ```
+//!
+//! # Concurrency
+//!
+//! Resource states are kept in a [`StateCache`] split into shards. A
+//! transition locks only the shard of its resource, so transitions of
+//! unrelated resources run in parallel and the state machine is shared as
+//! `Arc<StateMachine>` without an outer lock.
 
+use crate::flap::{self, FlapDetector};
+use crate::state_cache::StateCache;
 use crate::types::{
     ActionCommand, ContainerState, HealthStatus, ResourceState, StateTransition, TransitionResult,
 };
 use common::logd;
 use common::spec::artifact::Artifact;
+use common::spec::k8s::pod::{ProbeResult, READINESS_STATE_KEY};
 use common::statemanager::{
-    ErrorCode, ModelState, PackageState, ResourceType, ScenarioState, StateChange,
+    ErrorCode, ModelState, NodeState, PackageState, ResourceType, ScenarioState, StateChange,
 };
 use std::collections::HashMap;
-use tokio::sync::mpsc;
+use std::sync::{Mutex, MutexGuard};
+use tokio::sync::{broadcast, mpsc};
 use tokio::time::Instant;
 
 // ========================================
@@ -45,6 +56,21 @@ use tokio::time::Instant;
 /// Maximum consecutive failures before marking resource as unhealthy
 const MAX_CONSECUTIVE_FAILURES: u32 = 3;
 
+/// State change events kept for subscribers that fall behind
+pub const STATE_EVENT_CAPACITY: usize = 256;
+
+/// Config key marking the container list entry of a systemd unit
+pub const UNIT_CONFIG_KEY: &str = "unit";
+
+/// Unit properties reported by NodeAgent and their model metadata keys
+const UNIT_PROPERTIES: [(&str, &str); 5] = [
+    ("ActiveState", "unit_active_state"),
+    ("SubState", "unit_sub_state"),
+    ("FreezerState", "unit_freezer_state"),
+    ("NRestarts", "unit_restarts"),
+    ("Result", "unit_result"),
+];
+
 impl TransitionResult {
     /// Check if the transition was successful
     pub fn is_success(&self) -> bool {
@@ -84,8 +110,9 @@ impl TransitionResult {
 /// - **Extensible**: New resource types can be added with their own transition tables
 ///
 /// # Thread Safety
-/// This implementation is not thread-safe. External synchronization is required
-/// for concurrent access across multiple threads.
+/// All methods take `&self`. Changes of a resource are serialized by the
+/// shard of the [`StateCache`] holding it, the other maps have their own
+/// short-lived locks.
 pub struct StateMachine {
     /// State transition tables indexed by resource type
     ///
@@ -97,10 +124,35 @@ pub struct StateMachine {
     ///
     /// Resources are keyed by a unique identifier (typically resource name)
     /// and contain complete state information including metadata and health status.
-    resource_states: HashMap<String, ResourceState>,
+    resource_states: StateCache,
 
     /// Action command sender for async execution
-    action_sender: Option<mpsc::UnboundedSender<ActionCommand>>,
+    action_sender: Mutex<Option<mpsc::UnboundedSender<ActionCommand>>>,
+
+    /// Node each model last reported its containers from
+    model_nodes: Mutex<HashMap<String, String>>,
+
+    /// Every resource state change, for `SubscribeStateChanges`
+    state_events: broadcast::Sender<common::statemanager::StateChangeEvent>,
+
+    /// `cooldownSeconds` of each scenario, loaded before it is triggered
+    scenario_cooldowns: Mutex<HashMap<String, std::time::Duration>>,
+
+    /// When each scenario last became Satisfied
+    scenario_triggered: Mutex<HashMap<String, Instant>>,
+
+    /// Metadata of the packages a scenario runs on a node group
+    package_groups: Mutex<HashMap<String, HashMap<String, String>>>,
+
+    /// Recent transitions of each resource, to quarantine flapping scenarios
+    flaps: FlapDetector,
+}
+
+/// Lock one of the maps of the state machine
+///
+/// The maps stay usable if a thread panicked while holding the lock.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
 }
 
 impl StateMachine {
@@ -119,20 +171,27 @@ impl StateMachine {
     pub fn new() -> Self {
         let mut state_machine = StateMachine {
             transition_tables: HashMap::new(),
-            resource_states: HashMap::new(),
-            action_sender: None,
+            resource_states: StateCache::new(),
+            action_sender: Mutex::new(None),
+            model_nodes: Mutex::new(HashMap::new()),
+            state_events: broadcast::channel(STATE_EVENT_CAPACITY).0,
+            scenario_cooldowns: Mutex::new(HashMap::new()),
+            scenario_triggered: Mutex::new(HashMap::new()),
+            package_groups: Mutex::new(HashMap::new()),
+            flaps: FlapDetector::new(),
         };
 
         // Initialize transition tables for each resource type
         state_machine.initialize_scenario_transitions();
+        state_machine.initialize_node_transitions();
 
         state_machine
     }
 
     /// Initialize async action executor
-    pub fn initialize_action_executor(&mut self) -> mpsc::UnboundedReceiver<ActionCommand> {
+    pub fn initialize_action_executor(&self) -> mpsc::UnboundedReceiver<ActionCommand> {
         let (sender, receiver) = mpsc::unbounded_channel();
-        self.action_sender = Some(sender);
+        *lock(&self.action_sender) = Some(sender);
         receiver
     }
 
@@ -191,16 +250,120 @@ impl StateMachine {
                 condition: None,
                 action: "finalize_scenario".to_string(),
             },
+            // ActionController found none of the allowedModes of the scenario active
+            StateTransition {
+                from_state: ScenarioState::Allowed as i32,
+                event: "vehicle_mode_denied".to_string(),
+                to_state: ScenarioState::Denied as i32,
+                condition: None,
+                action: "log_denial_generate_alert".to_string(),
+            },
+            // ApiServer found the workload of a played scenario gone on startup
+            StateTransition {
+                from_state: ScenarioState::Allowed as i32,
+                event: "workload_lost".to_string(),
+                to_state: ScenarioState::Waiting as i32,
+                condition: None,
+                action: "start_condition_evaluation".to_string(),
+            },
+            StateTransition {
+                from_state: ScenarioState::Completed as i32,
+                event: "workload_lost".to_string(),
+                to_state: ScenarioState::Waiting as i32,
+                condition: None,
+                action: "start_condition_evaluation".to_string(),
+            },
         ];
         self.transition_tables
             .insert(ResourceType::Scenario, scenario_transitions);
     }
 
+    /// Initialize the state transition table for Node resources
+    ///
+    /// Node states are driven by the heartbeats NodeAgent sends to ApiServer:
+    /// - Missed heartbeats move a node Ready -> NotReady -> Unknown
+    /// - A fresh heartbeat moves it back to Ready and reschedules its models
+    /// - Nodes seen for the first time enter the table from Unspecified
+    fn initialize_node_transitions(&mut self) {
+        let node_transitions = vec![
+            StateTransition {
+                from_state: NodeState::Unspecified as i32,
+                event: "node_registered".to_string(),
+                to_state: NodeState::Ready as i32,
+                condition: None,
+                action: "register_node_start_monitoring".to_string(),
+            },
+            StateTransition {
+                from_state: NodeState::Unspecified as i32,
+                event: "heartbeat_missed".to_string(),
+                to_state: NodeState::NotReady as i32,
+                condition: None,
+                action: "log_warning_node_not_ready".to_string(),
+            },
+            StateTransition {
+                from_state: NodeState::Unspecified as i32,
+                event: "heartbeat_lost".to_string(),
+                to_state: NodeState::Unknown as i32,
+                condition: None,
+                action: "log_error_node_unreachable".to_string(),
+            },
+            StateTransition {
+                from_state: NodeState::Ready as i32,
+                event: "heartbeat_missed".to_string(),
+                to_state: NodeState::NotReady as i32,
+                condition: None,
+                action: "log_warning_node_not_ready".to_string(),
+            },
+            StateTransition {
+                from_state: NodeState::NotReady as i32,
+                event: "heartbeat_lost".to_string(),
+                to_state: NodeState::Unknown as i32,
+                condition: None,
+                action: "log_error_node_unreachable".to_string(),
+            },
+            StateTransition {
+                from_state: NodeState::NotReady as i32,
+                event: "heartbeat_recovered".to_string(),
+                to_state: NodeState::Ready as i32,
+                condition: None,
+                action: "reschedule_models_on_node".to_string(),
+            },
+            StateTransition {
+                from_state: NodeState::Unknown as i32,
+                event: "heartbeat_recovered".to_string(),
+                to_state: NodeState::Ready as i32,
+                condition: None,
+                action: "reschedule_models_on_node".to_string(),
+            },
+        ];
+        self.transition_tables
+            .insert(ResourceType::Node, node_transitions);
+    }
+
+    /// Transition table of a resource type
+    pub fn transition_table(&self, resource_type: ResourceType) -> Option<&Vec<StateTransition>> {
+        self.transition_tables.get(&resource_type)
+    }
+
+    /// Replace the transition tables with the compiled ones tuned by `config`
+    ///
+    /// # Errors
+    ///
+    /// Returns why the configuration is not valid; the tables are then left
+    /// unchanged.
+    pub fn load_transitions(
+        &mut self,
+        config: &crate::transitions::TransitionConfig,
+    ) -> Result<(), String> {
+        self.transition_tables = config.apply(&Self::new().transition_tables)?;
+        Ok(())
+    }
+
     // ========================================
     // CORE STATE PROCESSING
     // ========================================
     /// Process a state change request with non-blocking action execution
-    pub fn process_state_change(&mut self, state_change: StateChange) -> TransitionResult {
+    pub fn process_state_change(&self, state_change: StateChange) -> TransitionResult {
         // Validate input parameters
         if let Err(validation_error) = self.validate_state_change(&state_change) {
             return TransitionResult {
@@ -239,8 +402,53 @@ impl StateMachine {
 
         let resource_key = self.generate_resource_key(resource_type, &state_change.resource_name);
 
+        // A scenario in its cooldown is not triggered again
+        if Self::is_scenario_trigger(&state_change) {
+            let remaining = self.cooldown_remaining(&state_change.resource_name);
+            if !remaining.is_zero() {
+                let current_state = self
+                    .resource_states
+                    .get(&resource_key)
+                    .map(|rs| rs.current_state)
+                    .unwrap_or_else(|| {
+                        Self::state_str_to_enum(
+                            state_change.current_state.as_str(),
+                            state_change.resource_type,
+                        )
+                    });
+                return TransitionResult {
+                    new_state: current_state,
+                    error_code: ErrorCode::PreconditionFailed,
+                    message: format!(
+                        "Scenario {} is cooling down, {}s remaining",
+                        state_change.resource_name,
+                        remaining.as_secs_f64().ceil()
+                    ),
+                    actions_to_execute: vec![],
+                    transition_id: state_change.transition_id.clone(),
+                    error_details: format!("Cooldown remaining: {remaining:?}"),
+                };
+            }
+        }
+
+        self.resource_states.with_shard(&resource_key, |states| {
+            self.apply_transition(states, &resource_key, &state_change, resource_type)
+        })
+    }
+
+    /// Move a resource along its transition table
+    ///
+    /// Runs with the shard of the resource locked, so the state it starts
+    /// from cannot change before the new state is stored.
+    fn apply_transition(
+        &self,
+        states: &mut HashMap<String, ResourceState>,
+        resource_key: &str,
+        state_change: &StateChange,
+        resource_type: ResourceType,
+    ) -> TransitionResult {
         // Get current state - use provided current_state for new resources
-        let current_state = match self.resource_states.get(&resource_key) {
+        let current_state = match states.get(resource_key) {
             Some(existing_state) => existing_state.current_state,
             None => Self::state_str_to_enum(
                 state_change.current_state.as_str(),
@@ -248,30 +456,49 @@ impl StateMachine {
             ),
         };
 
-        // Special state-specific handling removed - using simplified state model
+        // A quarantined scenario takes no transition until it is reset
+        if resource_type == ResourceType::Scenario
+            && current_state == ScenarioState::Quarantined as i32
+        {
+            return TransitionResult {
+                new_state: current_state,
+                error_code: ErrorCode::PreconditionFailed,
+                message: format!(
+                    "Scenario {} is quarantined for flapping, reset it to resume",
+                    state_change.resource_name
+                ),
+                actions_to_execute: vec![],
+                transition_id: state_change.transition_id.clone(),
+                error_details: "Scenario is quarantined".to_string(),
+            };
+        }
 
         // Find valid transition
-        let transition_event = self.infer_event_from_states(
-            current_state,
-            Self::state_str_to_enum(
-                state_change.target_state.as_str(),
-                state_change.resource_type,
-            ),
-            resource_type,
+        let target_state = Self::state_str_to_enum(
+            state_change.target_state.as_str(),
+            state_change.resource_type,
         );
 
-        if let Some(transition) = self.find_valid_transition(
-            resource_type,
-            current_state,
-            &transition_event,
-            Self::state_str_to_enum(
-                state_change.target_state.as_str(),
-                state_change.resource_type,
-            ),
-        ) {
+        // An expired scenario only starts over when it is applied again
+        if resource_type == ResourceType::Scenario && current_state == ScenarioState::Expired as i32
+        {
+            return self.restart_expired(states, resource_key, state_change, target_state);
+        }
+        let transition_event =
+            self.infer_event_from_states(current_state, target_state, resource_type);
+
+        if let Some(transition) = self
+            .find_valid_transition(
+                resource_type,
+                current_state,
+                &transition_event,
+                target_state,
+            )
+            .or_else(|| self.find_configured_transition(resource_type, current_state, target_state))
+        {
             // Check conditions if any
             if let Some(ref condition) = transition.condition {
-                if !self.evaluate_condition(condition, &state_change) {
+                if !self.evaluate_condition(condition, state_change) {
                     return TransitionResult {
                         new_state: current_state,
                         error_code: ErrorCode::PreconditionFailed,
@@ -283,22 +510,39 @@ impl StateMachine {
                 }
             }
 
+            // A scenario that keeps changing state is held instead
+            let config = common::setting::get_config();
+            let score = self
+                .flaps
+                .record(resource_key, Instant::now(), &config.state);
+            if resource_type == ResourceType::Scenario && flap::is_flapping(score, &config.state) {
+                return self.quarantine(states, resource_key, state_change, score);
+            }
+
             // Execute transition - this is immediate and non-blocking
             self.update_resource_state(
-                &resource_key,
-                &state_change,
+                states,
+                resource_key,
+                state_change,
                 transition.to_state,
                 resource_type,
             );
+            if resource_type == ResourceType::Scenario
+                && transition.to_state == ScenarioState::Satisfied as i32
+            {
+                lock(&self.scenario_triggered)
+                    .insert(state_change.resource_name.clone(), Instant::now());
+            }
 
             // **NON-BLOCKING ACTION EXECUTION** - Queue action for async execution
-            if let Some(ref sender) = self.action_sender {
+            let sender = lock(&self.action_sender).clone();
+            if let Some(sender) = sender {
                 let action_command = ActionCommand {
                     action: transition.action.clone(),
-                    resource_key: resource_key.clone(),
+                    resource_key: resource_key.to_string(),
                     resource_type,
                     transition_id: state_change.transition_id.clone(),
-                    context: self.build_action_context(&state_change, &transition),
+                    context: self.build_action_context(state_change, &transition),
                 };
 
                 // Send action for async execution (non-blocking)
@@ -317,6 +561,9 @@ impl StateMachine {
                 ResourceType::Model => ModelState::try_from(transition.to_state)
                     .map(|s| s.as_str_name())
                     .unwrap_or("UNKNOWN"),
+                ResourceType::Node => NodeState::try_from(transition.to_state)
+                    .map(|s| s.as_str_name())
+                    .unwrap_or("UNKNOWN"),
                 _ => "UNKNOWN",
             };
 
@@ -330,7 +577,7 @@ impl StateMachine {
                 error_details: String::new(),
             };
 
-            self.update_health_status(&resource_key, &transition_result);
+            Self::update_health_status(states, resource_key, &transition_result);
 
             // State-specific logic removed for simplified state model
 
@@ -346,6 +593,9 @@ impl StateMachine {
                 ResourceType::Model => ModelState::try_from(current_state)
                     .map(|s| s.as_str_name())
                     .unwrap_or("UNKNOWN"),
+                ResourceType::Node => NodeState::try_from(current_state)
+                    .map(|s| s.as_str_name())
+                    .unwrap_or("UNKNOWN"),
                 _ => "UNKNOWN",
             };
 
@@ -389,6 +639,19 @@ impl StateMachine {
                         .map(|s| s.as_str_name())
                         .unwrap_or("UNKNOWN")
                 }
+                ResourceType::Node => {
+                    let normalized = format!(
+                        "NODE_STATE_{}",
+                        state_change
+                            .target_state
+                            .trim()
+                            .to_ascii_uppercase()
+                            .replace('-', "_")
+                    );
+                    NodeState::from_str_name(&normalized)
+                        .map(|s| s.as_str_name())
+                        .unwrap_or("UNKNOWN")
+                }
                 _ => "UNKNOWN",
             };
 
@@ -405,7 +668,7 @@ impl StateMachine {
                 ),
             };
 
-            self.update_health_status(&resource_key, &transition_result);
+            Self::update_health_status(states, resource_key, &transition_result);
             transition_result
         }
     }
@@ -423,7 +686,7 @@ impl StateMachine {
     /// - `TransitionResult`: Results of the state evaluation and transition attempt
     ///   - Contains whether state changed, the new state, and transition details
     pub fn process_model_state_update(
-        &mut self,
+        &self,
         model_name: &str,
         containers: &[&common::monitoringserver::ContainerInfo],
     ) -> TransitionResult {
@@ -436,25 +699,45 @@ impl StateMachine {
         // Evaluate the new model state based on container states
         let new_model_state = self.evaluate_model_state_from_containers(containers);
 
+        self.resource_states.with_shard(&resource_key, |states| {
+            self.apply_model_state(
+                states,
+                &resource_key,
+                model_name,
+                new_model_state,
+                timestamp_ns,
+            )
+        })
+    }
+
+    /// Store the state evaluated for a model, with its shard locked
+    fn apply_model_state(
+        &self,
+        states: &mut HashMap<String, ResourceState>,
+        resource_key: &str,
+        model_name: &str,
+        new_model_state: ModelState,
+        timestamp_ns: i64,
+    ) -> TransitionResult {
         // Create a pseudo state change for internal processing
         let state_change = StateChange {
             resource_type: ResourceType::Model as i32,
             resource_name: model_name.to_string(),
-            current_state: self
-                .resource_states
-                .get(&resource_key)
+            current_state: states
+                .get(resource_key)
                 .map(|rs| self.state_enum_to_str(rs.current_state, ResourceType::Model))
                 .unwrap_or_else(|| "Created".to_string()),
             target_state: self.model_state_to_str(new_model_state),
             transition_id: format!("model_update_{}_{}", model_name, timestamp_ns),
             timestamp_ns,
             source: "container_analysis".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: common::trace::current_trace_id().unwrap_or_default(),
         };
 
         // Get current state from existing resource or default to Created
-        let current_state = self
-            .resource_states
-            .get(&resource_key)
+        let current_state = states
+            .get(resource_key)
             .map(|rs| rs.current_state)
             .unwrap_or(ModelState::Created as i32);
 
@@ -474,7 +757,8 @@ impl StateMachine {
 
         // Update internal state tracking
         self.update_resource_state(
-            &resource_key,
+            states,
+            resource_key,
             &state_change,
             target_state,
             ResourceType::Model,
@@ -495,6 +779,33 @@ impl StateMachine {
         }
     }
 
+    /// Evaluates the node state from the age of its last heartbeat
+    ///
+    /// # Parameters
+    /// - `last_heartbeat`: Unix timestamp (seconds) of the last heartbeat received by ApiServer
+    /// - `now`: Current Unix timestamp (seconds)
+    /// - `heartbeat`: Heartbeat settings pushed to the agents by ApiServer
+    ///
+    /// # Returns
+    /// - `Ready` while heartbeats arrive within the liveness window
+    /// - `NotReady` until twice the liveness window has passed
+    /// - `Unknown` afterwards
+    pub fn evaluate_node_state_from_heartbeat(
+        last_heartbeat: i64,
+        now: i64,
+        heartbeat: &common::setting::HeartbeatSettings,
+    ) -> NodeState {
+        let not_ready_after = heartbeat.liveness_window() as i64;
+        let elapsed = now.saturating_sub(last_heartbeat);
+        if elapsed >= not_ready_after * 2 {
+            NodeState::Unknown
+        } else if elapsed >= not_ready_after {
+            NodeState::NotReady
+        } else {
+            NodeState::Ready
+        }
+    }
+
     /// Evaluates the model state based on container states according to the state transition rules
     fn evaluate_model_state_from_containers(
         &self,
@@ -542,10 +853,29 @@ impl StateMachine {
             return ModelState::Exited;
         }
 
-        // Rule 4: Running - default state (none of above conditions met)
+        // Rule 4: Created - a running container has a readiness probe that has
+        // not succeeded yet, so the model is not Running until it is ready
+        if containers
+            .iter()
+            .any(|container| !Self::is_container_ready(container))
+        {
+            return ModelState::Created;
+        }
+
+        // Rule 5: Running - default state (none of above conditions met)
         ModelState::Running
     }
 
+    /// Whether a container passed its readiness probe, reported by NodeAgent
+    ///
+    /// Containers without a readiness probe are always ready.
+    fn is_container_ready(container: &common::monitoringserver::ContainerInfo) -> bool {
+        match container.state.get(READINESS_STATE_KEY) {
+            Some(readiness) => ProbeResult::parse(readiness) == Some(ProbeResult::Success),
+            None => true,
+        }
+    }
+
     /// Evaluates package state based on model states according to Korean documentation requirements
     ///
     /// This function implements the package state transition rules defined in StateManager_Package.md:
@@ -579,7 +909,8 @@ impl StateMachine {
             match model_state {
                 ModelState::Paused => paused_count += 1,
                 ModelState::Exited => exited_count += 1,
-                ModelState::Dead => dead_count += 1,
+                // A Failed model is a dead model that is no longer restarted
+                ModelState::Dead | ModelState::Failed => dead_count += 1,
                 _ => {} // Other states don't directly impact package state rules
             }
         }
@@ -612,12 +943,14 @@ impl StateMachine {
     /// Retrieves all model states for models that belong to a given package
     ///
     /// This function queries ETCD to get all model states and filters them
-    /// to find models that belong to the specified package.
+    /// to find models that belong to the specified package. A package with a
+    /// selector or distributed pattern is made of one workload per model and
+    /// node, so the package state aggregates the states of all replicas.
     pub async fn get_models_for_package(
         package_name: &str,
     ) -> std::result::Result<Vec<(String, common::statemanager::ModelState)>, String> {
         // Get package definition from ETCD to find its models
-        let package_key = format!("Package/{}", package_name);
+        let package_key = common::spec::namespace::qualified_key("Package", package_name);
         let package_yaml = match common::etcd::get(&package_key).await {
             Ok(yaml) => yaml,
             Err(e) => {
@@ -627,19 +960,23 @@ impl StateMachine {
         };
 
         // Parse package YAML to extract model names
-        let package: common::spec::artifact::Package = match serde_yaml::from_str(&package_yaml) {
+        let mut package: common::spec::artifact::Package = match serde_yaml::from_str(&package_yaml)
+        {
             Ok(pkg) => pkg,
             Err(e) => {
                 logd!(4, "    Failed to parse package YAML: {:?}", e);
                 return Ok(Vec::new());
             }
         };
+        if let Some(group) = Self::load_group(&package.get_name()).await {
+            group.apply(&mut package);
+        }
 
         let mut model_states = Vec::new();
 
-        // Get state for each model in the package
-        for model_info in package.get_models() {
-            let model_name = model_info.get_name();
+        // Get state for each workload of the package
+        let replicas = Self::load_replicas(&package).await;
+        for model_name in common::replica::workloads(&package, &replicas) {
             let model_state_key = format!("/model/{}/state", model_name);
 
             match common::etcd::get(&model_state_key).await {
@@ -650,6 +987,7 @@ impl StateMachine {
                         "Exited" => common::statemanager::ModelState::Exited,
                         "Dead" => common::statemanager::ModelState::Dead,
                         "Running" => common::statemanager::ModelState::Running,
+                        "Failed" => common::statemanager::ModelState::Failed,
                         _ => common::statemanager::ModelState::Running, // Default to Running
                     };
                     model_states.push((model_name, model_state));
@@ -665,23 +1003,30 @@ impl StateMachine {
     }
 
     /// Find all packages that contain the given model
+    ///
+    /// Packages outside the default namespace are returned by their
+    /// qualified name.
     pub async fn find_packages_containing_model(
         model_name: &str,
     ) -> std::result::Result<Vec<String>, String> {
         let mut packages = Vec::new();
 
-        // Get all packages from ETCD with prefix
-        match common::etcd::get_all_with_prefix("Package/").await {
+        // Get the packages of every namespace from ETCD
+        match common::etcd::get_all_of_kind("Package").await {
             Ok(package_entries) => {
                 for kv in package_entries {
                     match serde_yaml::from_str::<common::spec::artifact::Package>(&kv.1) {
-                        Ok(package) => {
-                            // Check if this package contains the model
-                            for model_info in package.get_models() {
-                                if model_info.get_name() == model_name {
-                                    packages.push(package.get_name());
-                                    break;
-                                }
+                        Ok(mut package) => {
+                            if let Some(group) = Self::load_group(&package.get_name()).await {
+                                group.apply(&mut package);
+                            }
+                            // Check if this package contains the model or one of its replicas
+                            let replicas = Self::load_replicas(&package).await;
+                            if common::replica::workloads(&package, &replicas)
+                                .iter()
+                                .any(|name| name == model_name)
+                            {
+                                packages.push(package.get_qualified_name());
                             }
                         }
                         Err(e) => {
@@ -699,6 +1044,43 @@ impl StateMachine {
         Ok(packages)
     }
 
+    /// Node group a scenario runs a package on, if any
+    async fn load_group(package_name: &str) -> Option<common::replica::Group> {
+        common::replica::load_group(package_name)
+            .await
+            .unwrap_or_else(|e| {
+                logd!(
+                    4,
+                    "    Failed to get node group of package {}: {:?}",
+                    package_name,
+                    e
+                );
+                None
+            })
+    }
+
+    /// Nodes of the models of a selector or distributed package
+    ///
+    /// Packages with the plain pattern have no replicas and are not looked up.
+    async fn load_replicas(
+        package: &common::spec::artifact::Package,
+    ) -> std::collections::BTreeMap<String, Vec<String>> {
+        if package.get_pattern().is_plain() {
+            return Default::default();
+        }
+        common::replica::load(&package.get_name())
+            .await
+            .unwrap_or_else(|e| {
+                logd!(
+                    4,
+                    "    Failed to get replicas of package {}: {:?}",
+                    package.get_name(),
+                    e
+                );
+                Default::default()
+            })
+    }
+
     /// Get current package state from ETCD
     pub async fn get_current_package_state(
         package_name: &str,
@@ -751,6 +1133,7 @@ impl StateMachine {
                     common::statemanager::ModelState::Exited => ModelState::Exited,
                     common::statemanager::ModelState::Dead => ModelState::Dead,
                     common::statemanager::ModelState::Running => ModelState::Running,
+                    common::statemanager::ModelState::Failed => ModelState::Failed,
                     _ => ModelState::Running,
                 };
                 (name.clone(), converted_state)
@@ -765,6 +1148,24 @@ impl StateMachine {
         // Evaluate new package state using state machine
         let evaluated_state = self.evaluate_package_state_from_models(&model_states_for_evaluation);
 
+        let group = match Self::load_group(package_name).await {
+            Some(group) => {
+                let nodes = common::replica::load(package_name)
+                    .await
+                    .unwrap_or_default()
+                    .into_values()
+                    .flatten()
+                    .collect::<std::collections::BTreeSet<String>>();
+                Some(group_metadata(
+                    &group,
+                    &nodes.into_iter().collect::<Vec<_>>(),
+                    &model_states_for_evaluation,
+                ))
+            }
+            None => None,
+        };
+        self.record_package_group(package_name, group);
+
         // Convert back to common::statemanager::PackageState
         let new_package_state = match evaluated_state {
             PackageState::Idle => common::statemanager::PackageState::Idle,
@@ -835,6 +1236,7 @@ impl StateMachine {
             ModelState::Exited => "Exited".to_string(),
             ModelState::Dead => "Dead".to_string(),
             ModelState::Running => "Running".to_string(),
+            ModelState::Failed => "Failed".to_string(),
             _ => "Unknown".to_string(),
         }
     }
@@ -881,6 +1283,23 @@ impl StateMachine {
         None
     }
 
+    /// Find a rule between two states whatever its event
+    ///
+    /// Events are inferred from the compiled tables, so rules added by the
+    /// transition configuration are matched on their states only.
+    fn find_configured_transition(
+        &self,
+        resource_type: ResourceType,
+        from_state: i32,
+        to_state: i32,
+    ) -> Option<StateTransition> {
+        self.transition_tables
+            .get(&resource_type)?
+            .iter()
+            .find(|t| t.from_state == from_state && t.to_state == to_state)
+            .cloned()
+    }
+
     /// Validate state change request parameters
     fn validate_state_change(&self, state_change: &StateChange) -> Result<(), String> {
         if state_change.resource_name.trim().is_empty() {
@@ -930,6 +1349,9 @@ impl StateMachine {
             ResourceType::Model => ModelState::try_from(transition.from_state)
                 .map(|s| s.as_str_name())
                 .unwrap_or("UNKNOWN"),
+            ResourceType::Node => NodeState::try_from(transition.from_state)
+                .map(|s| s.as_str_name())
+                .unwrap_or("UNKNOWN"),
             _ => "UNKNOWN",
         };
 
@@ -943,6 +1365,9 @@ impl StateMachine {
             ResourceType::Model => ModelState::try_from(transition.to_state)
                 .map(|s| s.as_str_name())
                 .unwrap_or("UNKNOWN"),
+            ResourceType::Node => NodeState::try_from(transition.to_state)
+                .map(|s| s.as_str_name())
+                .unwrap_or("UNKNOWN"),
             _ => "UNKNOWN",
         };
 
@@ -962,8 +1387,12 @@ impl StateMachine {
     }
 
     /// Updates health status based on transition result
-    fn update_health_status(&mut self, resource_key: &str, transition_result: &TransitionResult) {
-        if let Some(resource_state) = self.resource_states.get_mut(resource_key) {
+    fn update_health_status(
+        states: &mut HashMap<String, ResourceState>,
+        resource_key: &str,
+        transition_result: &TransitionResult,
+    ) {
+        if let Some(resource_state) = states.get_mut(resource_key) {
             let now = Instant::now();
             resource_state.health_status.last_check = now;
 
@@ -983,38 +1412,320 @@ impl StateMachine {
         }
     }
 
-    /// Infer the appropriate event type from state transition
+    /// Move a flapping scenario to Quarantined instead of its transition
     ///
-    /// When an explicit event is not provided, this method attempts to
-    /// determine the most appropriate event based on the current and target states.
+    /// No action is queued, and the scenario is unhealthy until it is reset.
+    fn quarantine(
+        &self,
+        states: &mut HashMap<String, ResourceState>,
+        resource_key: &str,
+        state_change: &StateChange,
+        score: usize,
+    ) -> TransitionResult {
+        let quarantined = ScenarioState::Quarantined as i32;
+        let message = format!(
+            "Scenario {} changed state {} times within {}s and is quarantined",
+            state_change.resource_name,
+            score,
+            common::setting::get_config().state.flap_window_secs
+        );
+        logd!(4, "{message}");
+        self.update_resource_state(
+            states,
+            resource_key,
+            state_change,
+            quarantined,
+            ResourceType::Scenario,
+        );
+        if let Some(resource_state) = states.get_mut(resource_key) {
+            resource_state.health_status.healthy = false;
+            resource_state.health_status.status_message = message.clone();
+        }
+        TransitionResult {
+            new_state: quarantined,
+            error_code: ErrorCode::Success,
+            message,
+            actions_to_execute: vec![],
+            transition_id: state_change.transition_id.clone(),
+            error_details: String::new(),
+        }
+    }
+
+    /// Release a quarantined scenario
     ///
-    /// # Parameters
-    /// - `current_state`: The current state of the resource
-    /// - `target_state`: The desired target state
+    /// The scenario goes back to Waiting, so its condition is evaluated
+    /// again, and its flap score starts over.
     ///
-    /// # Returns
-    /// A string representing the inferred event type
+    /// # Errors
     ///
-    /// # Examples
-    /// - "Inactive" -> "Active" might infer "activate"
-    /// - "Running" -> "Stopped" might infer "stop"
-    /// - Any state -> "Failed" might infer "error"
+    /// Returns why the scenario cannot be released, e.g. it is not quarantined.
+    pub fn reset_quarantine(&self, scenario_name: &str, reason: &str) -> Result<(), String> {
+        let resource_key = self.generate_resource_key(ResourceType::Scenario, scenario_name);
+        self.resource_states.with_shard(&resource_key, |states| {
+            let current_state = match states.get(&resource_key) {
+                Some(rs) if rs.current_state == ScenarioState::Quarantined as i32 => {
+                    rs.current_state
+                }
+                Some(rs) => {
+                    return Err(format!(
+                        "Scenario {} is {}, not quarantined",
+                        scenario_name,
+                        self.state_enum_to_str(rs.current_state, ResourceType::Scenario)
+                    ))
+                }
+                None => return Err(format!("Scenario {} has no tracked state", scenario_name)),
+            };
+            let timestamp_ns = unix_time_ns(std::time::SystemTime::now());
+            let state_change = StateChange {
+                resource_type: ResourceType::Scenario as i32,
+                resource_name: scenario_name.to_string(),
+                current_state: self.state_enum_to_str(current_state, ResourceType::Scenario),
+                target_state: self
+                    .state_enum_to_str(ScenarioState::Waiting as i32, ResourceType::Scenario),
+                transition_id: format!("quarantine_reset_{}_{}", scenario_name, timestamp_ns),
+                timestamp_ns,
+                source: "quarantine_reset".to_string(),
+                asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+                trace_id: common::trace::current_trace_id().unwrap_or_default(),
+            };
+            if let Some(resource_state) = states.get_mut(&resource_key) {
+                resource_state.health_status = HealthStatus {
+                    healthy: true,
+                    status_message: "Healthy".to_string(),
+                    last_check: Instant::now(),
+                    consecutive_failures: 0,
+                };
+                resource_state
+                    .metadata
+                    .insert("quarantine_reset_reason".to_string(), reason.to_string());
+            }
+            self.update_resource_state(
+                states,
+                &resource_key,
+                &state_change,
+                ScenarioState::Waiting as i32,
+                ResourceType::Scenario,
+            );
+            Ok(())
+        })?;
+        self.flaps.reset(&resource_key);
+        logd!(
+            3,
+            "Scenario {} released from quarantine: {}",
+            scenario_name,
+            reason
+        );
+        Ok(())
+    }
+
+    /// Move a scenario that lived past its `ttlSeconds` to Expired
     ///
-    /// # Fallback Behavior
-    /// If no specific event can be inferred, returns a generic event name
-    /// based on the target state (e.g., "transition_to_active").
-    fn infer_event_from_states(
+    /// No action is queued, the caller terminates the workloads of the
+    /// scenario. Untracked scenarios expire as well.
+    ///
+    /// # Errors
+    ///
+    /// Returns why the scenario cannot expire, i.e. it already has.
+    pub fn expire(&self, scenario_name: &str) -> Result<(), String> {
+        let resource_key = self.generate_resource_key(ResourceType::Scenario, scenario_name);
+        let expired = ScenarioState::Expired as i32;
+        self.resource_states.with_shard(&resource_key, |states| {
+            let current_state = states
+                .get(&resource_key)
+                .map(|rs| rs.current_state)
+                .unwrap_or(ScenarioState::Unspecified as i32);
+            if current_state == expired {
+                return Err(format!("Scenario {} has already expired", scenario_name));
+            }
+            let timestamp_ns = unix_time_ns(std::time::SystemTime::now());
+            let state_change = StateChange {
+                resource_type: ResourceType::Scenario as i32,
+                resource_name: scenario_name.to_string(),
+                current_state: self.state_enum_to_str(current_state, ResourceType::Scenario),
+                target_state: self.state_enum_to_str(expired, ResourceType::Scenario),
+                transition_id: format!("expiry_{}_{}", scenario_name, timestamp_ns),
+                timestamp_ns,
+                source: "expiry".to_string(),
+                asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+                trace_id: String::new(),
+            };
+            self.update_resource_state(
+                states,
+                &resource_key,
+                &state_change,
+                expired,
+                ResourceType::Scenario,
+            );
+            Ok(())
+        })?;
+        self.flaps.reset(&resource_key);
+        logd!(3, "Scenario {} has expired", scenario_name);
+        Ok(())
+    }
+
+    /// Start an expired scenario over as Idle when ApiServer applies it again
+    ///
+    /// Every other transition of an expired scenario is rejected, so it is
+    /// not triggered while its artifact is kept.
+    fn restart_expired(
         &self,
-        current_state: i32,
+        states: &mut HashMap<String, ResourceState>,
+        resource_key: &str,
+        state_change: &StateChange,
         target_state: i32,
-        resource_type: ResourceType,
-    ) -> String {
-        match resource_type {
-            ResourceType::Scenario => match (current_state, target_state) {
-                (x, y) if x == ScenarioState::Idle as i32 && y == ScenarioState::Waiting as i32 => {
-                    "scenario_activation".to_string()
-                }
-                (x, y)
+    ) -> TransitionResult {
+        let idle = ScenarioState::Idle as i32;
+        if state_change.source != "apiserver" || target_state != idle {
+            return TransitionResult {
+                new_state: ScenarioState::Expired as i32,
+                error_code: ErrorCode::PreconditionFailed,
+                message: format!(
+                    "Scenario {} has expired, apply it again to restart it",
+                    state_change.resource_name
+                ),
+                actions_to_execute: vec![],
+                transition_id: state_change.transition_id.clone(),
+                error_details: "Scenario has expired".to_string(),
+            };
+        }
+        self.update_resource_state(
+            states,
+            resource_key,
+            state_change,
+            idle,
+            ResourceType::Scenario,
+        );
+        TransitionResult {
+            new_state: idle,
+            error_code: ErrorCode::Success,
+            message: format!(
+                "Expired scenario {} was applied again",
+                state_change.resource_name
+            ),
+            actions_to_execute: vec![],
+            transition_id: state_change.transition_id.clone(),
+            error_details: String::new(),
+        }
+    }
+
+    /// Record the outcome of an asynchronously executed transition action
+    ///
+    /// Called by the action executor once an action queued by
+    /// `process_state_change` has finished. The outcome is stored in the
+    /// resource metadata and feeds the same health tracking used for
+    /// transition results, so repeated action failures mark the resource
+    /// unhealthy.
+    pub fn record_action_result(
+        &self,
+        command: &ActionCommand,
+        outcome: &std::result::Result<(), String>,
+    ) {
+        self.resource_states
+            .with_shard(&command.resource_key, |states| {
+                Self::apply_action_result(states, command, outcome)
+            });
+    }
+
+    fn apply_action_result(
+        states: &mut HashMap<String, ResourceState>,
+        command: &ActionCommand,
+        outcome: &std::result::Result<(), String>,
+    ) {
+        let Some(resource_state) = states.get_mut(&command.resource_key) else {
+            logd!(
+                1,
+                "No tracked state for {}, dropping result of action '{}'",
+                command.resource_key,
+                command.action
+            );
+            return;
+        };
+
+        let (status, message) = match outcome {
+            Ok(()) => ("succeeded", String::new()),
+            Err(e) => ("failed", e.clone()),
+        };
+        let metadata = &mut resource_state.metadata;
+        metadata.insert("last_action".to_string(), command.action.clone());
+        metadata.insert("last_action_status".to_string(), status.to_string());
+        metadata.insert("last_action_message".to_string(), message.clone());
+        metadata.insert(
+            "last_action_transition_id".to_string(),
+            command.transition_id.clone(),
+        );
+
+        let transition_result = TransitionResult {
+            new_state: resource_state.current_state,
+            error_code: if outcome.is_ok() {
+                ErrorCode::Success
+            } else {
+                ErrorCode::InternalError
+            },
+            message: if outcome.is_ok() {
+                format!("Action '{}' completed", command.action)
+            } else {
+                format!("Action '{}' failed: {}", command.action, message)
+            },
+            actions_to_execute: vec![],
+            transition_id: command.transition_id.clone(),
+            error_details: message,
+        };
+        Self::update_health_status(states, &command.resource_key, &transition_result);
+    }
+
+    /// Name of the event a StateChange triggers from the tracked state
+    ///
+    /// Call before processing the change, which moves the tracked state.
+    pub fn transition_event(&self, state_change: &StateChange) -> String {
+        let Ok(resource_type) = ResourceType::try_from(state_change.resource_type) else {
+            return "unknown".to_string();
+        };
+        let resource_key = self.generate_resource_key(resource_type, &state_change.resource_name);
+        let current_state = match self.resource_states.get(&resource_key) {
+            Some(existing_state) => existing_state.current_state,
+            None => {
+                Self::state_str_to_enum(&state_change.current_state, state_change.resource_type)
+            }
+        };
+        self.infer_event_from_states(
+            current_state,
+            Self::state_str_to_enum(&state_change.target_state, state_change.resource_type),
+            resource_type,
+        )
+    }
+
+    /// Infer the appropriate event type from state transition
+    ///
+    /// When an explicit event is not provided, this method attempts to
+    /// determine the most appropriate event based on the current and target states.
+    ///
+    /// # Parameters
+    /// - `current_state`: The current state of the resource
+    /// - `target_state`: The desired target state
+    ///
+    /// # Returns
+    /// A string representing the inferred event type
+    ///
+    /// # Examples
+    /// - "Inactive" -> "Active" might infer "activate"
+    /// - "Running" -> "Stopped" might infer "stop"
+    /// - Any state -> "Failed" might infer "error"
+    ///
+    /// # Fallback Behavior
+    /// If no specific event can be inferred, returns a generic event name
+    /// based on the target state (e.g., "transition_to_active").
+    fn infer_event_from_states(
+        &self,
+        current_state: i32,
+        target_state: i32,
+        resource_type: ResourceType,
+    ) -> String {
+        match resource_type {
+            ResourceType::Scenario => match (current_state, target_state) {
+                (x, y) if x == ScenarioState::Idle as i32 && y == ScenarioState::Waiting as i32 => {
+                    "scenario_activation".to_string()
+                }
+                (x, y)
                     if x == ScenarioState::Waiting as i32
                         && y == ScenarioState::Satisfied as i32 =>
                 {
@@ -1038,6 +1749,18 @@ impl StateMachine {
                 {
                     "scenario_completion".to_string()
                 }
+                (x, y)
+                    if x == ScenarioState::Allowed as i32 && y == ScenarioState::Denied as i32 =>
+                {
+                    "vehicle_mode_denied".to_string()
+                }
+                (x, y)
+                    if (x == ScenarioState::Allowed as i32
+                        || x == ScenarioState::Completed as i32)
+                        && y == ScenarioState::Waiting as i32 =>
+                {
+                    "workload_lost".to_string()
+                }
                 _ => format!("transition_{current_state}_{target_state}"),
             },
             ResourceType::Package => match (current_state, target_state) {
@@ -1134,6 +1857,15 @@ impl StateMachine {
                 }
                 _ => format!("transition_{current_state}_{target_state}"),
             },
+            ResourceType::Node => match (current_state, target_state) {
+                (x, y) if x == NodeState::Unspecified as i32 && y == NodeState::Ready as i32 => {
+                    "node_registered".to_string()
+                }
+                (_, y) if y == NodeState::NotReady as i32 => "heartbeat_missed".to_string(),
+                (_, y) if y == NodeState::Unknown as i32 => "heartbeat_lost".to_string(),
+                (_, y) if y == NodeState::Ready as i32 => "heartbeat_recovered".to_string(),
+                _ => format!("transition_{current_state}_{target_state}"),
+            },
             _ => format!("transition_{current_state}_{target_state}"),
         }
     }
@@ -1206,7 +1938,8 @@ impl StateMachine {
     /// - Clears any active backoff timers on successful transition
     /// - Updates health status if applicable
     fn update_resource_state(
-        &mut self,
+        &self,
+        states: &mut HashMap<String, ResourceState>,
         resource_key: &str,
         state_change: &StateChange,
 
@@ -1215,31 +1948,32 @@ impl StateMachine {
     ) {
         let now = Instant::now();
 
-        let resource_state = self
-            .resource_states
-            .entry(resource_key.to_string())
-            .or_insert_with(|| ResourceState {
-                resource_type,
-                resource_name: state_change.resource_name.clone(),
-                current_state: Self::state_str_to_enum(
-                    state_change.current_state.as_str(),
-                    state_change.resource_type,
-                ),
-                desired_state: Some(Self::state_str_to_enum(
-                    state_change.target_state.as_str(),
-                    state_change.resource_type,
-                )),
-                last_transition_time: now,
-                transition_count: 0,
-                metadata: HashMap::new(),
-                health_status: HealthStatus {
-                    healthy: true,
-                    status_message: "Healthy".to_string(),
-                    last_check: now,
-                    consecutive_failures: 0,
-                },
-            });
-
+        let resource_state =
+            states
+                .entry(resource_key.to_string())
+                .or_insert_with(|| ResourceState {
+                    resource_type,
+                    resource_name: state_change.resource_name.clone(),
+                    current_state: Self::state_str_to_enum(
+                        state_change.current_state.as_str(),
+                        state_change.resource_type,
+                    ),
+                    desired_state: Some(Self::state_str_to_enum(
+                        state_change.target_state.as_str(),
+                        state_change.resource_type,
+                    )),
+                    last_transition_time: now,
+                    transition_count: 0,
+                    metadata: HashMap::new(),
+                    health_status: HealthStatus {
+                        healthy: true,
+                        status_message: "Healthy".to_string(),
+                        last_check: now,
+                        consecutive_failures: 0,
+                    },
+                });
+
+        let previous_state = resource_state.current_state;
         resource_state.current_state = new_state;
         resource_state.last_transition_time = now;
         resource_state.transition_count += 1;
@@ -1250,6 +1984,208 @@ impl StateMachine {
         resource_state
             .metadata
             .insert("source".to_string(), state_change.source.clone());
+
+        // Nobody may be subscribed, which is not an error
+        let resource_state = self.to_proto(&states[resource_key]);
+        let _ = self
+            .state_events
+            .send(common::statemanager::StateChangeEvent {
+                previous_state: self.state_enum_to_str(previous_state, resource_type),
+                resource_state: Some(resource_state),
+                transition_id: state_change.transition_id.clone(),
+                source: state_change.source.clone(),
+                event_timestamp_ns: unix_time_ns(std::time::SystemTime::now()),
+            });
+    }
+
+    /// Record the node a model reported its containers from
+    ///
+    /// The node is part of the state of the model returned by queries and
+    /// state change events.
+    pub fn set_model_node(&self, model_name: &str, node_name: &str) {
+        let mut model_nodes = lock(&self.model_nodes);
+        if model_nodes.get(model_name).map(String::as_str) != Some(node_name) {
+            model_nodes.insert(model_name.to_string(), node_name.to_string());
+        }
+    }
+
+    /// Keep the properties of the systemd unit running a model
+    ///
+    /// Units of the systemd backend or Bluechi are reported as container
+    /// entries with a `unit` config key. Their properties are stored as
+    /// `unit_*` metadata of the model, which must already be tracked.
+    pub fn record_unit_status(
+        &self,
+        model_name: &str,
+        containers: &[&common::monitoringserver::ContainerInfo],
+    ) {
+        let Some((unit_name, unit)) = containers.iter().find_map(|container| {
+            container
+                .config
+                .get(UNIT_CONFIG_KEY)
+                .map(|unit_name| (unit_name, container))
+        }) else {
+            return;
+        };
+        let resource_key = self.generate_resource_key(ResourceType::Model, model_name);
+        self.resource_states.with_shard(&resource_key, |states| {
+            let Some(resource_state) = states.get_mut(&resource_key) else {
+                return;
+            };
+            let metadata = &mut resource_state.metadata;
+            metadata.insert(UNIT_CONFIG_KEY.to_string(), unit_name.clone());
+            for (property, key) in UNIT_PROPERTIES {
+                if let Some(value) = unit.state.get(property) {
+                    metadata.insert(key.to_string(), value.clone());
+                }
+            }
+        });
+    }
+
+    /// Track the state of a package evaluated from its models
+    ///
+    /// Package states are derived rather than requested, so the change is
+    /// recorded without a transition table lookup.
+    pub fn record_package_state(&self, package_name: &str, package_state: PackageState) {
+        let resource_key = self.generate_resource_key(ResourceType::Package, package_name);
+        self.resource_states.with_shard(&resource_key, |states| {
+            self.apply_package_state(states, &resource_key, package_name, package_state)
+        });
+    }
+
+    /// Track the node group of a package, or forget it with `None`
+    ///
+    /// The metadata is shown with the state of the package.
+    pub fn record_package_group(&self, package_name: &str, group: Option<HashMap<String, String>>) {
+        let mut groups = lock(&self.package_groups);
+        match group {
+            Some(metadata) => {
+                groups.insert(package_name.to_string(), metadata);
+            }
+            None => {
+                groups.remove(package_name);
+            }
+        }
+    }
+
+    fn apply_package_state(
+        &self,
+        states: &mut HashMap<String, ResourceState>,
+        resource_key: &str,
+        package_name: &str,
+        package_state: PackageState,
+    ) {
+        let current_state = states
+            .get(resource_key)
+            .map(|rs| self.state_enum_to_str(rs.current_state, ResourceType::Package))
+            .unwrap_or_else(|| "Idle".to_string());
+        let timestamp_ns = unix_time_ns(std::time::SystemTime::now());
+        let state_change = StateChange {
+            resource_type: ResourceType::Package as i32,
+            resource_name: package_name.to_string(),
+            current_state,
+            target_state: self.state_enum_to_str(package_state as i32, ResourceType::Package),
+            transition_id: format!("package_update_{}_{}", package_name, timestamp_ns),
+            timestamp_ns,
+            source: "model_analysis".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: common::trace::current_trace_id().unwrap_or_default(),
+        };
+        self.update_resource_state(
+            states,
+            resource_key,
+            &state_change,
+            package_state as i32,
+            ResourceType::Package,
+        );
+    }
+
+    /// Adopt a state written to etcd by someone else
+    ///
+    /// Like package states, the change is recorded without a transition
+    /// table lookup; subscribers see it with the source `etcd_watch`.
+    pub fn apply_external_state(
+        &self,
+        resource_type: ResourceType,
+        resource_name: &str,
+        state: i32,
+    ) {
+        let resource_key = self.generate_resource_key(resource_type, resource_name);
+        self.resource_states.with_shard(&resource_key, |states| {
+            self.apply_state(states, &resource_key, resource_type, resource_name, state)
+        });
+    }
+
+    fn apply_state(
+        &self,
+        states: &mut HashMap<String, ResourceState>,
+        resource_key: &str,
+        resource_type: ResourceType,
+        resource_name: &str,
+        state: i32,
+    ) {
+        let target_state = self.state_enum_to_str(state, resource_type);
+        let current_state = states
+            .get(resource_key)
+            .map(|rs| self.state_enum_to_str(rs.current_state, resource_type))
+            .unwrap_or_else(|| target_state.clone());
+        let timestamp_ns = unix_time_ns(std::time::SystemTime::now());
+        let state_change = StateChange {
+            resource_type: resource_type as i32,
+            resource_name: resource_name.to_string(),
+            current_state,
+            target_state,
+            transition_id: format!("etcd_watch_{}_{}", resource_name, timestamp_ns),
+            timestamp_ns,
+            source: "etcd_watch".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: common::trace::current_trace_id().unwrap_or_default(),
+        };
+        self.update_resource_state(states, resource_key, &state_change, state, resource_type);
+    }
+
+    /// Stop tracking a resource, e.g. after its state was deleted from etcd
+    ///
+    /// # Returns
+    /// - `true` if the resource was tracked
+    pub fn forget_resource(&self, resource_type: ResourceType, resource_name: &str) -> bool {
+        let resource_key = self.generate_resource_key(resource_type, resource_name);
+        if resource_type == ResourceType::Scenario {
+            lock(&self.scenario_cooldowns).remove(resource_name);
+            lock(&self.scenario_triggered).remove(resource_name);
+        }
+        self.flaps.reset(&resource_key);
+        self.resource_states.remove(&resource_key).is_some()
+    }
+
+    /// Whether a state change triggers a scenario, i.e. makes it Satisfied
+    pub fn is_scenario_trigger(state_change: &StateChange) -> bool {
+        state_change.resource_type == ResourceType::Scenario as i32
+            && Self::state_str_to_enum(&state_change.target_state, state_change.resource_type)
+                == ScenarioState::Satisfied as i32
+    }
+
+    /// Set the `cooldownSeconds` of a scenario
+    ///
+    /// A zero cooldown lets the scenario be triggered again at once.
+    pub fn set_scenario_cooldown(&self, scenario_name: &str, cooldown: std::time::Duration) {
+        let mut cooldowns = lock(&self.scenario_cooldowns);
+        if cooldown.is_zero() {
+            cooldowns.remove(scenario_name);
+        } else {
+            cooldowns.insert(scenario_name.to_string(), cooldown);
+        }
+    }
+
+    /// Time until a scenario can be triggered again, zero outside its cooldown
+    pub fn cooldown_remaining(&self, scenario_name: &str) -> std::time::Duration {
+        match (
+            lock(&self.scenario_cooldowns).get(scenario_name),
+            lock(&self.scenario_triggered).get(scenario_name),
+        ) {
+            (Some(cooldown), Some(triggered)) => cooldown.saturating_sub(triggered.elapsed()),
+            _ => std::time::Duration::ZERO,
+        }
     }
 
     // ========================================
@@ -1266,7 +2202,7 @@ impl StateMachine {
     /// - `resource_type`: The type of the resource (for validation)
     ///
     /// # Returns
-    /// - `Some(&ResourceState)`: If the resource exists and types match
+    /// - `Some(ResourceState)`: A copy of the state, if the resource exists and types match
     /// - `None`: If the resource doesn't exist or type mismatch
     ///
     /// # Usage
@@ -1278,7 +2214,7 @@ impl StateMachine {
         &self,
         resource_name: &str,
         resource_type: ResourceType,
-    ) -> Option<&ResourceState> {
+    ) -> Option<ResourceState> {
         let resource_key = self.generate_resource_key(resource_type, resource_name);
         self.resource_states.get(&resource_key)
     }
@@ -1293,7 +2229,7 @@ impl StateMachine {
     /// - `state`: The state to filter by
     ///
     /// # Returns
-    /// A vector of references to all matching resource states
+    /// A vector of copies of all matching resource states
     ///
     /// # Performance Note
     /// This method performs a linear scan of all resources. For large numbers
@@ -1307,9 +2243,10 @@ impl StateMachine {
         resource_type: Option<ResourceType>,
 
         state: i32,
-    ) -> Vec<&ResourceState> {
+    ) -> Vec<ResourceState> {
         self.resource_states
             .values()
+            .into_iter()
             .filter(|resource| {
                 resource.current_state == state
                     && (resource_type.is_none() || resource_type == Some(resource.resource_type))
@@ -1317,8 +2254,108 @@ impl StateMachine {
             .collect()
     }
 
+    /// Current state of a resource as returned by the query API
+    pub fn get_resource_state_proto(
+        &self,
+        resource_name: &str,
+        resource_type: ResourceType,
+    ) -> Option<common::statemanager::ResourceState> {
+        self.get_resource_state(resource_name, resource_type)
+            .map(|rs| self.to_proto(&rs))
+    }
+
+    /// List resources matching the filters, sorted by type and name
+    ///
+    /// # Parameters
+    /// - `resource_type`: Only resources of this type, `None` for all types
+    /// - `state`: Only resources in this state, e.g. `Running`, empty for all
+    /// - `node`: Only models and nodes of this node, empty for all
+    pub fn list_resource_states(
+        &self,
+        resource_type: Option<ResourceType>,
+        state: &str,
+        node: &str,
+    ) -> Vec<common::statemanager::ResourceState> {
+        let mut resources: Vec<common::statemanager::ResourceState> = self
+            .resource_states
+            .values()
+            .iter()
+            .filter(|rs| resource_type.is_none_or(|t| t == rs.resource_type))
+            .map(|rs| self.to_proto(rs))
+            .filter(|rs| state.is_empty() || same_state(&rs.current_state, state))
+            .filter(|rs| node.is_empty() || rs.node == node)
+            .collect();
+        resources.sort_by(|a, b| {
+            (a.resource_type, &a.resource_name).cmp(&(b.resource_type, &b.resource_name))
+        });
+        resources
+    }
+
+    /// Receive every resource state change from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<common::statemanager::StateChangeEvent> {
+        self.state_events.subscribe()
+    }
+
+    /// Metadata of a resource with its flap score and, for a package, its
+    /// node group
+    fn proto_metadata(&self, rs: &ResourceState) -> HashMap<String, String> {
+        let mut metadata = rs.metadata.clone();
+        if rs.resource_type == ResourceType::Package {
+            if let Some(group) = lock(&self.package_groups).get(&rs.resource_name) {
+                metadata.extend(group.clone());
+            }
+        }
+        let resource_key = self.generate_resource_key(rs.resource_type, &rs.resource_name);
+        let score = self.flaps.score(
+            &resource_key,
+            Instant::now(),
+            &common::setting::get_config().state,
+        );
+        if score > 0 {
+            metadata.insert("flap_score".to_string(), score.to_string());
+        }
+        metadata
+    }
+
+    /// Convert a tracked resource state to its proto form
+    fn to_proto(&self, rs: &ResourceState) -> common::statemanager::ResourceState {
+        let node = match rs.resource_type {
+            ResourceType::Node => rs.resource_name.clone(),
+            ResourceType::Model => lock(&self.model_nodes)
+                .get(&rs.resource_name)
+                .cloned()
+                .unwrap_or_default(),
+            _ => String::new(),
+        };
+        let last_transition_time = std::time::SystemTime::now()
+            .checked_sub(rs.last_transition_time.elapsed())
+            .unwrap_or(std::time::UNIX_EPOCH);
+        common::statemanager::ResourceState {
+            resource_type: rs.resource_type as i32,
+            resource_name: rs.resource_name.clone(),
+            current_state: self.state_enum_to_str(rs.current_state, rs.resource_type),
+            desired_state: rs
+                .desired_state
+                .map(|state| self.state_enum_to_str(state, rs.resource_type))
+                .unwrap_or_default(),
+            last_transition_time_ns: unix_time_ns(last_transition_time),
+            state_generation: rs.transition_count as i64,
+            node,
+            metadata: self.proto_metadata(rs),
+            healthy: rs.health_status.healthy,
+            health_message: rs.health_status.status_message.clone(),
+            cooldown_remaining_seconds: match rs.resource_type {
+                ResourceType::Scenario => self
+                    .cooldown_remaining(&rs.resource_name)
+                    .as_secs_f64()
+                    .ceil() as i64,
+                _ => 0,
+            },
+        }
+    }
+
     // Utility: Convert state string to proto enum value
-    fn state_str_to_enum(state: &str, resource_type: i32) -> i32 {
+    pub(crate) fn state_str_to_enum(state: &str, resource_type: i32) -> i32 {
         // Map "idle" -> "SCENARIO_STATE_IDLE", etc.
         let normalized = match ResourceType::try_from(resource_type) {
             Ok(ResourceType::Scenario) => format!(
@@ -1333,6 +2370,10 @@ impl StateMachine {
                 "MODEL_STATE_{}",
                 state.trim().to_ascii_uppercase().replace('-', "_")
             ),
+            Ok(ResourceType::Node) => format!(
+                "NODE_STATE_{}",
+                state.trim().to_ascii_uppercase().replace('-', "_")
+            ),
             _ => state.trim().to_ascii_uppercase().replace('-', "_"),
         };
         match ResourceType::try_from(resource_type) {
@@ -1345,6 +2386,9 @@ impl StateMachine {
             Ok(ResourceType::Model) => ModelState::from_str_name(&normalized)
                 .map(|s| s as i32)
                 .unwrap_or(ModelState::Unspecified as i32),
+            Ok(ResourceType::Node) => NodeState::from_str_name(&normalized)
+                .map(|s| s as i32)
+                .unwrap_or(NodeState::Unspecified as i32),
             _ => 0,
         }
     }
@@ -1376,15 +2420,68 @@ impl StateMachine {
                         .to_string()
                 })
                 .unwrap_or_else(|_| "Unknown".to_string()),
+            ResourceType::Node => NodeState::try_from(state)
+                .map(|s| {
+                    s.as_str_name()
+                        .strip_prefix("NODE_STATE_")
+                        .unwrap_or(s.as_str_name())
+                        .to_string()
+                })
+                .unwrap_or_else(|_| "Unknown".to_string()),
             _ => "Unknown".to_string(),
         }
     }
 }
 
+/// Nanoseconds since the Unix epoch
+fn unix_time_ns(time: std::time::SystemTime) -> i64 {
+    time.duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as i64
+}
+
+/// Compare state names ignoring case and `-`/`_`, e.g. `not-ready` and `NOT_READY`
+fn same_state(a: &str, b: &str) -> bool {
+    let normalize = |state: &str| state.trim().to_ascii_uppercase().replace('-', "_");
+    normalize(a) == normalize(b)
+}
+
 /// Default implementation that creates a new StateMachine
 ///
 /// Provides a convenient way to create a StateMachine with default
 /// configuration using the `Default` trait.
+/// Package metadata of a node group
+///
+/// `group_workloads` counts the workloads of the group, one per model and
+/// node, `group_running` and `group_dead` those running and dead or failed.
+pub fn group_metadata(
+    group: &common::replica::Group,
+    nodes: &[String],
+    model_states: &[(String, ModelState)],
+) -> HashMap<String, String> {
+    let count = |states: &[ModelState]| {
+        model_states
+            .iter()
+            .filter(|(_, state)| states.contains(state))
+            .count()
+            .to_string()
+    };
+    HashMap::from([
+        ("group_selector".to_string(), group.selector()),
+        ("group_scenario".to_string(), group.scenario.clone()),
+        ("group_nodes".to_string(), nodes.join(",")),
+        (
+            "group_workloads".to_string(),
+            model_states.len().to_string(),
+        ),
+        ("group_running".to_string(), count(&[ModelState::Running])),
+        (
+            "group_dead".to_string(),
+            count(&[ModelState::Dead, ModelState::Failed]),
+        ),
+    ])
+}
+
 impl Default for StateMachine {
     fn default() -> Self {
         Self::new()
@@ -1501,6 +2598,7 @@ mod tests {
             config: HashMap::new(),
             annotation: HashMap::new(),
             stats: HashMap::new(),
+            usage: None,
         };
         let result = state_machine.parse_container_state(&container_info);
         assert_eq!(result, ContainerState::Initialized);
@@ -1516,6 +2614,7 @@ mod tests {
             config: HashMap::new(),
             annotation: HashMap::new(),
             stats: HashMap::new(),
+            usage: None,
         };
         let result = state_machine.parse_container_state(&container_info);
         assert_eq!(result, ContainerState::Unknown);
@@ -1531,6 +2630,7 @@ mod tests {
             config: HashMap::new(),
             annotation: HashMap::new(),
             stats: HashMap::new(),
+            usage: None,
         };
         let result = state_machine.parse_container_state(&container_info);
         assert_eq!(result, ContainerState::Unknown);
@@ -1540,7 +2640,7 @@ mod tests {
     fn test_process_state_change_queues_action_and_updates_state() {
         use common::statemanager::ResourceType;
 
-        let mut state_machine = StateMachine::new();
+        let state_machine = StateMachine::new();
 
         // Initialize action executor so actions are queued to receiver
         let mut action_receiver = state_machine.initialize_action_executor();
@@ -1554,6 +2654,8 @@ mod tests {
             transition_id: "t-1".to_string(),
             timestamp_ns: 1,
             source: "unittest".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: String::new(),
         };
 
         let result = state_machine.process_state_change(state_change.clone());
@@ -1575,7 +2677,7 @@ mod tests {
     fn test_process_state_change_invalid_transition_returns_error() {
         use common::statemanager::{ErrorCode, ResourceType};
 
-        let mut state_machine = StateMachine::new();
+        let state_machine = StateMachine::new();
 
         // Build a StateChange with an unknown target state -> should produce InvalidStateTransition
         let state_change = StateChange {
@@ -1586,17 +2688,179 @@ mod tests {
             transition_id: "t-2".to_string(),
             timestamp_ns: 2,
             source: "unittest".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: String::new(),
+        };
+
+        let result = state_machine.process_state_change(state_change);
+        assert_eq!(result.error_code, ErrorCode::InvalidStateTransition);
+    }
+
+    #[test]
+    fn test_completed_scenario_returns_to_waiting_when_workload_lost() {
+        use common::statemanager::ResourceType;
+
+        let state_machine = StateMachine::new();
+        let mut action_receiver = state_machine.initialize_action_executor();
+
+        // ApiServer reconciliation after a restart: nothing of the scenario runs
+        let state_change = StateChange {
+            resource_type: ResourceType::Scenario as i32,
+            resource_name: "lost-scenario".to_string(),
+            current_state: "completed".to_string(),
+            target_state: "waiting".to_string(),
+            transition_id: "t-3".to_string(),
+            timestamp_ns: 3,
+            source: "apiserver".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: String::new(),
         };
 
         let result = state_machine.process_state_change(state_change);
+        assert!(result.is_success(), "{}", result.message);
+        assert_eq!(result.new_state, ScenarioState::Waiting as i32);
+        let action = action_receiver.try_recv().expect("expected an action");
+        assert_eq!(action.action, "start_condition_evaluation");
+    }
+
+    #[test]
+    fn test_scenario_cooldown_rejects_trigger() {
+        use common::statemanager::ResourceType;
+
+        let state_machine = StateMachine::new();
+        let change = |current: &str, target: &str| StateChange {
+            resource_type: ResourceType::Scenario as i32,
+            resource_name: "flapping".to_string(),
+            current_state: current.to_string(),
+            target_state: target.to_string(),
+            transition_id: format!("{current}-{target}"),
+            timestamp_ns: 1,
+            source: "filtergateway".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: String::new(),
+        };
+        state_machine.set_scenario_cooldown("flapping", std::time::Duration::from_secs(60));
+
+        assert!(StateMachine::is_scenario_trigger(&change(
+            "waiting",
+            "satisfied"
+        )));
+        for (current, target) in [
+            ("waiting", "satisfied"),
+            ("satisfied", "allowed"),
+            ("allowed", "completed"),
+            ("completed", "waiting"),
+        ] {
+            let result = state_machine.process_state_change(change(current, target));
+            assert!(result.is_success(), "{}", result.message);
+        }
+
+        let result = state_machine.process_state_change(change("waiting", "satisfied"));
+        assert_eq!(result.error_code, ErrorCode::PreconditionFailed);
+        assert_eq!(result.new_state, ScenarioState::Waiting as i32);
+        let proto = state_machine
+            .get_resource_state_proto("flapping", ResourceType::Scenario)
+            .unwrap();
+        assert!(proto.cooldown_remaining_seconds > 0 && proto.cooldown_remaining_seconds <= 60);
+
+        state_machine.set_scenario_cooldown("flapping", std::time::Duration::ZERO);
+        let result = state_machine.process_state_change(change("waiting", "satisfied"));
+        assert!(result.is_success(), "{}", result.message);
+    }
+
+    #[test]
+    fn test_node_heartbeat_transitions() {
+        let state_machine = StateMachine::new();
+        let mut action_receiver = state_machine.initialize_action_executor();
+
+        let steps = [
+            ("Unspecified", "Ready", "register_node_start_monitoring"),
+            ("Ready", "Not_Ready", "log_warning_node_not_ready"),
+            ("Not_Ready", "Unknown", "log_error_node_unreachable"),
+            ("Unknown", "Ready", "reschedule_models_on_node"),
+        ];
+        for (i, (from, to, action)) in steps.iter().enumerate() {
+            let result = state_machine.process_state_change(StateChange {
+                resource_type: ResourceType::Node as i32,
+                resource_name: "node-a".to_string(),
+                current_state: from.to_string(),
+                target_state: to.to_string(),
+                transition_id: format!("node-{i}"),
+                timestamp_ns: i as i64,
+                source: "unittest".to_string(),
+                asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+                trace_id: String::new(),
+            });
+            assert!(result.is_success(), "{from} -> {to}: {}", result.message);
+            assert_eq!(action_receiver.try_recv().unwrap().action, *action);
+        }
+
+        let rs = state_machine
+            .get_resource_state("node-a", ResourceType::Node)
+            .unwrap();
+        assert_eq!(rs.current_state, NodeState::Ready as i32);
+
+        // A node cannot skip NotReady on the way down
+        let result = state_machine.process_state_change(StateChange {
+            resource_type: ResourceType::Node as i32,
+            resource_name: "node-a".to_string(),
+            current_state: "Ready".to_string(),
+            target_state: "Unknown".to_string(),
+            transition_id: "node-skip".to_string(),
+            timestamp_ns: 10,
+            source: "unittest".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: String::new(),
+        });
         assert_eq!(result.error_code, ErrorCode::InvalidStateTransition);
     }
 
+    #[test]
+    fn test_evaluate_node_state_from_heartbeat() {
+        let heartbeat = common::setting::HeartbeatSettings {
+            interval_secs: 5,
+            liveness_window_secs: 15,
+            max_clock_skew_secs: 5,
+        };
+        let evaluate = |last_heartbeat| {
+            StateMachine::evaluate_node_state_from_heartbeat(last_heartbeat, 1_000, &heartbeat)
+        };
+        assert_eq!(evaluate(999), NodeState::Ready);
+        assert_eq!(evaluate(1_000 - 15), NodeState::NotReady);
+        assert_eq!(evaluate(1_000 - 30), NodeState::Unknown);
+        // Clock skew must not mark a node as lost
+        assert_eq!(evaluate(1_005), NodeState::Ready);
+    }
+
+    #[test]
+    fn test_node_with_long_heartbeat_interval_stays_ready() {
+        // A 30s interval with a shorter configured window still allows two
+        // intervals before a node is NotReady
+        let heartbeat = common::setting::HeartbeatSettings {
+            interval_secs: 30,
+            liveness_window_secs: 15,
+            max_clock_skew_secs: 5,
+        };
+        let now = 1_000;
+        for missed in [0, 29, 30, 45, 59] {
+            assert_eq!(
+                StateMachine::evaluate_node_state_from_heartbeat(now - missed, now, &heartbeat),
+                NodeState::Ready,
+                "node without a heartbeat for {}s",
+                missed
+            );
+        }
+        assert_eq!(
+            StateMachine::evaluate_node_state_from_heartbeat(now - 60, now, &heartbeat),
+            NodeState::NotReady
+        );
+    }
+
     #[test]
     fn test_update_health_status_marks_unhealthy_after_retries() {
         use common::statemanager::ResourceType;
 
-        let mut state_machine = StateMachine::new();
+        let state_machine = StateMachine::new();
 
         // Prepare a resource state with 2 consecutive failures already
         let resource_key =
@@ -1620,7 +2884,9 @@ mod tests {
 
         state_machine
             .resource_states
-            .insert(resource_key.clone(), rs);
+            .with_shard(&resource_key, |states| {
+                states.insert(resource_key.clone(), rs)
+            });
 
         // Create a failing TransitionResult
         let fail_result = TransitionResult {
@@ -1633,13 +2899,71 @@ mod tests {
         };
 
         // Call update_health_status (private) — accessible inside this test module
-        state_machine.update_health_status(&resource_key, &fail_result);
+        state_machine
+            .resource_states
+            .with_shard(&resource_key, |states| {
+                StateMachine::update_health_status(states, &resource_key, &fail_result)
+            });
 
         let updated = state_machine.resource_states.get(&resource_key).unwrap();
         assert_eq!(updated.health_status.consecutive_failures, 3);
         assert!(!updated.health_status.healthy);
     }
 
+    #[test]
+    fn test_record_action_result_updates_metadata_and_health() {
+        use common::statemanager::ResourceType;
+
+        let state_machine = StateMachine::new();
+        let mut action_receiver = state_machine.initialize_action_executor();
+
+        let state_change = StateChange {
+            resource_type: ResourceType::Scenario as i32,
+            resource_name: "action-scenario".to_string(),
+            current_state: "Idle".to_string(),
+            target_state: "Waiting".to_string(),
+            transition_id: "t-action".to_string(),
+            timestamp_ns: 1,
+            source: "unittest".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: String::new(),
+        };
+        assert!(state_machine
+            .process_state_change(state_change)
+            .is_success());
+        let command = action_receiver
+            .try_recv()
+            .expect("expected an action queued");
+
+        let failure = Err("actioncontroller unavailable".to_string());
+        for _ in 0..MAX_CONSECUTIVE_FAILURES {
+            state_machine.record_action_result(&command, &failure);
+        }
+        let rs = state_machine
+            .get_resource_state("action-scenario", ResourceType::Scenario)
+            .unwrap();
+        assert_eq!(
+            rs.metadata.get("last_action_status"),
+            Some(&"failed".to_string())
+        );
+        assert!(!rs.health_status.healthy);
+
+        state_machine.record_action_result(&command, &Ok(()));
+        let rs = state_machine
+            .get_resource_state("action-scenario", ResourceType::Scenario)
+            .unwrap();
+        assert_eq!(
+            rs.metadata.get("last_action"),
+            Some(&"start_condition_evaluation".to_string())
+        );
+        assert_eq!(
+            rs.metadata.get("last_action_status"),
+            Some(&"succeeded".to_string())
+        );
+        assert!(rs.health_status.healthy);
+        assert_eq!(rs.health_status.consecutive_failures, 0);
+    }
+
     #[test]
     fn test_evaluate_model_state_from_containers_variants() {
         use common::monitoringserver::ContainerInfo;
@@ -1662,6 +2986,7 @@ mod tests {
             config: HashMap::new(),
             annotation: HashMap::new(),
             stats: HashMap::new(),
+            usage: None,
         };
         let res = state_machine.evaluate_model_state_from_containers(&[&container_dead]);
         assert_eq!(res, ModelState::Dead);
@@ -1677,6 +3002,7 @@ mod tests {
             config: HashMap::new(),
             annotation: HashMap::new(),
             stats: HashMap::new(),
+            usage: None,
         };
         let mut s2 = HashMap::new();
         s2.insert("Status".to_string(), "paused".to_string());
@@ -1688,6 +3014,7 @@ mod tests {
             config: HashMap::new(),
             annotation: HashMap::new(),
             stats: HashMap::new(),
+            usage: None,
         };
         let res = state_machine.evaluate_model_state_from_containers(&[&c1, &c2]);
         assert_eq!(res, ModelState::Paused);
@@ -1703,6 +3030,7 @@ mod tests {
             config: HashMap::new(),
             annotation: HashMap::new(),
             stats: HashMap::new(),
+            usage: None,
         };
         let mut e2 = HashMap::new();
         e2.insert("Status".to_string(), "exited".to_string());
@@ -1714,6 +3042,7 @@ mod tests {
             config: HashMap::new(),
             annotation: HashMap::new(),
             stats: HashMap::new(),
+            usage: None,
         };
         let res = state_machine.evaluate_model_state_from_containers(&[&ce1, &ce2]);
         assert_eq!(res, ModelState::Exited);
@@ -1729,6 +3058,7 @@ mod tests {
             config: HashMap::new(),
             annotation: HashMap::new(),
             stats: HashMap::new(),
+            usage: None,
         };
         let mut cr2m = HashMap::new();
         cr2m.insert("Status".to_string(), "initialized".to_string());
@@ -1740,9 +3070,24 @@ mod tests {
             config: HashMap::new(),
             annotation: HashMap::new(),
             stats: HashMap::new(),
+            usage: None,
         };
         let res = state_machine.evaluate_model_state_from_containers(&[&cr1, &cr2]);
         assert_eq!(res, ModelState::Running);
+
+        // Running but not ready -> Created until the readiness probe succeeds
+        let mut probing = cr1.clone();
+        probing
+            .state
+            .insert(READINESS_STATE_KEY.to_string(), "pending".to_string());
+        let res = state_machine.evaluate_model_state_from_containers(&[&probing, &cr2]);
+        assert_eq!(res, ModelState::Created);
+
+        probing
+            .state
+            .insert(READINESS_STATE_KEY.to_string(), "success".to_string());
+        let res = state_machine.evaluate_model_state_from_containers(&[&probing, &cr2]);
+        assert_eq!(res, ModelState::Running);
     }
 
     #[test]
@@ -1751,7 +3096,7 @@ mod tests {
         use common::statemanager::ResourceType;
         use std::collections::HashMap;
 
-        let mut state_machine = StateMachine::new();
+        let state_machine = StateMachine::new();
 
         let mut s = HashMap::new();
         s.insert("Status".to_string(), "running".to_string());
@@ -1763,6 +3108,7 @@ mod tests {
             config: HashMap::new(),
             annotation: HashMap::new(),
             stats: HashMap::new(),
+            usage: None,
         };
 
         let result = state_machine.process_model_state_update("model-x", &[&container]);
@@ -1774,6 +3120,48 @@ mod tests {
         assert!(rs.is_some());
     }
 
+    #[test]
+    fn test_record_unit_status_sets_model_metadata() {
+        use common::monitoringserver::ContainerInfo;
+        use common::statemanager::ResourceType;
+        use std::collections::HashMap;
+
+        let state_machine = StateMachine::new();
+        let unit = ContainerInfo {
+            id: "model-u.service".to_string(),
+            names: vec!["model-u.service".to_string()],
+            state: HashMap::from([
+                ("Status".to_string(), "dead".to_string()),
+                ("ActiveState".to_string(), "failed".to_string()),
+                ("SubState".to_string(), "failed".to_string()),
+                ("NRestarts".to_string(), "3".to_string()),
+                ("Result".to_string(), "exit-code".to_string()),
+            ]),
+            config: HashMap::from([(UNIT_CONFIG_KEY.to_string(), "model-u.service".to_string())]),
+            annotation: HashMap::from([("model".to_string(), "model-u".to_string())]),
+            ..Default::default()
+        };
+
+        // Untracked models are left alone
+        state_machine.record_unit_status("model-u", &[&unit]);
+        assert!(state_machine
+            .get_resource_state("model-u", ResourceType::Model)
+            .is_none());
+
+        let result = state_machine.process_model_state_update("model-u", &[&unit]);
+        assert!(result.is_success());
+        state_machine.record_unit_status("model-u", &[&unit]);
+        let rs = state_machine
+            .get_resource_state("model-u", ResourceType::Model)
+            .unwrap();
+        assert_eq!(rs.current_state, ModelState::Dead as i32);
+        assert_eq!(rs.metadata["unit"], "model-u.service");
+        assert_eq!(rs.metadata["unit_active_state"], "failed");
+        assert_eq!(rs.metadata["unit_restarts"], "3");
+        assert_eq!(rs.metadata["unit_result"], "exit-code");
+        assert!(!rs.metadata.contains_key("unit_freezer_state"));
+    }
+
     #[test]
     fn test_parse_container_state_running_fallback() {
         use common::monitoringserver::ContainerInfo;
@@ -1791,6 +3179,7 @@ mod tests {
             config: HashMap::new(),
             annotation: HashMap::new(),
             stats: HashMap::new(),
+            usage: None,
         };
 
         let res = state_machine.parse_container_state(&container);
@@ -1801,7 +3190,7 @@ mod tests {
     fn test_get_resource_state_and_list_resources_by_state() {
         use common::statemanager::{ResourceType, ScenarioState};
 
-        let mut state_machine = StateMachine::new();
+        let state_machine = StateMachine::new();
 
         // Create a scenario via process_state_change (Idle -> Waiting)
         let state_change = StateChange {
@@ -1812,6 +3201,8 @@ mod tests {
             transition_id: "lt-1".to_string(),
             timestamp_ns: 1,
             source: "unittest".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: String::new(),
         };
 
         let _ = state_machine.process_state_change(state_change);
@@ -1826,6 +3217,242 @@ mod tests {
         assert!(!list.is_empty());
     }
 
+    #[test]
+    fn test_list_resource_states_filters_and_publishes_events() {
+        use common::statemanager::{PackageState, ResourceType};
+
+        let state_machine = StateMachine::new();
+        let mut events = state_machine.subscribe();
+
+        let _ = state_machine.process_state_change(StateChange {
+            resource_type: ResourceType::Scenario as i32,
+            resource_name: "query-scenario".to_string(),
+            current_state: "Idle".to_string(),
+            target_state: "Waiting".to_string(),
+            transition_id: "q-1".to_string(),
+            timestamp_ns: 1,
+            source: "unittest".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: String::new(),
+        });
+        state_machine.record_package_state("query-package", PackageState::Degraded);
+
+        let event = events.try_recv().expect("expected a scenario event");
+        let state = event.resource_state.unwrap();
+        assert_eq!(state.resource_name, "query-scenario");
+        assert_eq!(state.current_state, "WAITING");
+        assert_eq!(event.previous_state, "IDLE");
+        let event = events.try_recv().expect("expected a package event");
+        assert_eq!(event.resource_state.unwrap().resource_name, "query-package");
+        assert_eq!(event.source, "model_analysis");
+
+        assert_eq!(state_machine.list_resource_states(None, "", "").len(), 2);
+        let packages =
+            state_machine.list_resource_states(Some(ResourceType::Package), "DEGRADED", "");
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].resource_name, "query-package");
+        assert!(state_machine
+            .list_resource_states(Some(ResourceType::Scenario), "running", "")
+            .is_empty());
+        assert!(state_machine
+            .list_resource_states(None, "", "node1")
+            .is_empty());
+
+        let proto = state_machine
+            .get_resource_state_proto("query-scenario", ResourceType::Scenario)
+            .unwrap();
+        assert_eq!(proto.resource_type, ResourceType::Scenario as i32);
+        assert!(state_machine
+            .get_resource_state_proto("query-scenario", ResourceType::Package)
+            .is_none());
+    }
+
+    #[test]
+    fn test_package_group_metadata() {
+        let state_machine = StateMachine::new();
+        state_machine.record_package_state("door-package", PackageState::Degraded);
+
+        let group = common::replica::Group {
+            scenario: "door-control".to_string(),
+            node_selector: [("zone".to_string(), "front-left".to_string())].into(),
+        };
+        let model_states = vec![
+            ("door-zone1".to_string(), ModelState::Running),
+            ("door-zone2".to_string(), ModelState::Failed),
+            ("door-zone3".to_string(), ModelState::Created),
+        ];
+        let nodes = ["zone1", "zone2", "zone3"].map(String::from);
+        state_machine.record_package_group(
+            "door-package",
+            Some(group_metadata(&group, &nodes, &model_states)),
+        );
+
+        let metadata = state_machine
+            .get_resource_state_proto("door-package", ResourceType::Package)
+            .unwrap()
+            .metadata;
+        assert_eq!(metadata["group_selector"], "zone=front-left");
+        assert_eq!(metadata["group_scenario"], "door-control");
+        assert_eq!(metadata["group_nodes"], "zone1,zone2,zone3");
+        assert_eq!(metadata["group_workloads"], "3");
+        assert_eq!(metadata["group_running"], "1");
+        assert_eq!(metadata["group_dead"], "1");
+
+        state_machine.record_package_group("door-package", None);
+        let metadata = state_machine
+            .get_resource_state_proto("door-package", ResourceType::Package)
+            .unwrap()
+            .metadata;
+        assert!(!metadata.contains_key("group_selector"));
+    }
+
+    #[test]
+    fn test_flapping_scenario_is_quarantined_until_reset() {
+        let state_machine = StateMachine::new();
+        let threshold = common::setting::get_config().state.flap_threshold;
+        let change = |from: &str, to: &str| StateChange {
+            resource_type: ResourceType::Scenario as i32,
+            resource_name: "flappy".to_string(),
+            current_state: from.to_string(),
+            target_state: to.to_string(),
+            transition_id: format!("{from}-{to}"),
+            timestamp_ns: 1,
+            source: "unittest".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: String::new(),
+        };
+        assert!(state_machine
+            .reset_quarantine("flappy", "test")
+            .unwrap_err()
+            .contains("no tracked state"));
+
+        // Waiting -> Satisfied -> Allowed -> Waiting, over and over
+        let cycle = [
+            ("Idle", "Waiting"),
+            ("Waiting", "Satisfied"),
+            ("Satisfied", "Allowed"),
+            ("Allowed", "Waiting"),
+        ];
+        let mut transitions = 0;
+        let result = loop {
+            let (from, to) = cycle[if transitions == 0 {
+                0
+            } else {
+                1 + (transitions - 1) % 3
+            }];
+            let result = state_machine.process_state_change(change(from, to));
+            transitions += 1;
+            if result.new_state == ScenarioState::Quarantined as i32 || transitions > threshold {
+                break result;
+            }
+            assert!(result.is_success(), "{}", result.message);
+        };
+        assert_eq!(transitions, threshold);
+        assert!(result.is_success());
+        assert!(result.actions_to_execute.is_empty());
+        let proto = state_machine
+            .get_resource_state_proto("flappy", ResourceType::Scenario)
+            .unwrap();
+        assert_eq!(proto.current_state, "QUARANTINED");
+        assert!(!proto.healthy);
+        assert_eq!(proto.metadata["flap_score"], threshold.to_string());
+
+        let rejected = state_machine.process_state_change(change("Allowed", "Waiting"));
+        assert_eq!(rejected.error_code, ErrorCode::PreconditionFailed);
+        assert!(rejected.message.contains("quarantined"));
+
+        state_machine.reset_quarantine("flappy", "fixed").unwrap();
+        let proto = state_machine
+            .get_resource_state_proto("flappy", ResourceType::Scenario)
+            .unwrap();
+        assert_eq!(proto.current_state, "WAITING");
+        assert!(proto.healthy);
+        assert!(!proto.metadata.contains_key("flap_score"));
+        assert!(state_machine
+            .process_state_change(change("Waiting", "Satisfied"))
+            .is_success());
+        assert!(state_machine
+            .reset_quarantine("flappy", "again")
+            .unwrap_err()
+            .contains("not quarantined"));
+    }
+
+    #[test]
+    fn test_expired_scenario_restarts_only_when_applied_again() {
+        let state_machine = StateMachine::new();
+        let change = |from: &str, to: &str, source: &str| StateChange {
+            resource_type: ResourceType::Scenario as i32,
+            resource_name: "capture".to_string(),
+            current_state: from.to_string(),
+            target_state: to.to_string(),
+            transition_id: format!("{from}-{to}"),
+            timestamp_ns: 1,
+            source: source.to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: String::new(),
+        };
+        assert!(state_machine
+            .process_state_change(change("Idle", "Waiting", "unittest"))
+            .is_success());
+
+        state_machine.expire("capture").unwrap();
+        let proto = state_machine
+            .get_resource_state_proto("capture", ResourceType::Scenario)
+            .unwrap();
+        assert_eq!(proto.current_state, "EXPIRED");
+        assert!(state_machine
+            .expire("capture")
+            .unwrap_err()
+            .contains("already expired"));
+
+        let rejected =
+            state_machine.process_state_change(change("Waiting", "Satisfied", "filtergateway"));
+        assert_eq!(rejected.error_code, ErrorCode::PreconditionFailed);
+        assert!(rejected.message.contains("expired"));
+
+        let restarted = state_machine.process_state_change(change("", "idle", "apiserver"));
+        assert!(restarted.is_success(), "{}", restarted.message);
+        assert_eq!(restarted.new_state, ScenarioState::Idle as i32);
+        assert!(state_machine
+            .process_state_change(change("Idle", "Waiting", "filtergateway"))
+            .is_success());
+    }
+
+    #[test]
+    fn test_apply_external_state_and_forget_resource() {
+        use common::statemanager::{ModelState, ResourceType};
+
+        let state_machine = StateMachine::new();
+        let mut events = state_machine.subscribe();
+
+        state_machine.apply_external_state(
+            ResourceType::Model,
+            "ext-model",
+            ModelState::Running as i32,
+        );
+        state_machine.apply_external_state(
+            ResourceType::Model,
+            "ext-model",
+            ModelState::Dead as i32,
+        );
+        let rs = state_machine
+            .get_resource_state("ext-model", ResourceType::Model)
+            .unwrap();
+        assert_eq!(rs.current_state, ModelState::Dead as i32);
+        assert_eq!(rs.metadata["source"], "etcd_watch");
+
+        let _ = events.try_recv().expect("expected the first event");
+        let event = events.try_recv().expect("expected the second event");
+        assert_eq!(event.source, "etcd_watch");
+        assert_eq!(event.previous_state, "RUNNING");
+
+        assert!(state_machine.forget_resource(ResourceType::Model, "ext-model"));
+        assert!(!state_machine.forget_resource(ResourceType::Model, "ext-model"));
+        assert!(state_machine
+            .get_resource_state("ext-model", ResourceType::Model)
+            .is_none());
+    }
+
     #[test]
     fn test_infer_event_from_states_scenario() {
         let sm = StateMachine::new();
@@ -1835,6 +3462,12 @@ mod tests {
             ResourceType::Scenario,
         );
         assert_eq!(evt, "scenario_activation");
+        let evt = sm.infer_event_from_states(
+            ScenarioState::Allowed as i32,
+            ScenarioState::Denied as i32,
+            ResourceType::Scenario,
+        );
+        assert_eq!(evt, "vehicle_mode_denied");
     }
 
     #[test]
@@ -1850,6 +3483,8 @@ mod tests {
                 transition_id: "t".to_string(),
                 timestamp_ns: 0,
                 source: "test".to_string(),
+                asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+                trace_id: String::new(),
             }
         ));
 
@@ -1864,6 +3499,8 @@ mod tests {
                 transition_id: "t".to_string(),
                 timestamp_ns: 0,
                 source: "test".to_string(),
+                asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+                trace_id: String::new(),
             }
         ));
     }
@@ -1879,6 +3516,8 @@ mod tests {
             transition_id: "t".to_string(),
             timestamp_ns: 0,
             source: "test".to_string(),
+            asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+            trace_id: String::new(),
         };
         assert!(!sm.evaluate_condition("critical_models_failed", &sc));
         assert!(!sm.evaluate_condition("timeout_or_error", &sc));
@@ -2066,6 +3705,22 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_find_packages_containing_model_in_namespace() {
+        let pkg_key = "team-a/Package/pkg-in-team";
+        let pkg_yaml = r#"{"apiVersion":"v1","kind":"Package","metadata":{"name":"pkg-in-team","namespace":"team-a"},"spec":{"pattern":[],"models":[{"name":"team_model","node":"n","resources":{"volume":"","network":"","realtime":false}}]}}"#;
+
+        common::etcd::put("Namespace/team-a", "").await.unwrap();
+        common::etcd::put(pkg_key, pkg_yaml).await.unwrap();
+
+        let pkgs = StateMachine::find_packages_containing_model("team_model")
+            .await
+            .unwrap();
+        assert_eq!(pkgs, vec!["team-a/pkg-in-team".to_string()]);
+
+        let _ = common::etcd::delete(pkg_key).await;
+    }
+
     #[tokio::test]
     async fn test_get_current_package_state_none_when_missing() {
         // Ensure no state key exists for this package
@@ -2104,4 +3759,92 @@ mod tests {
         assert!(!changed);
         assert_eq!(state, common::statemanager::PackageState::Idle);
     }
+
+    /// Drive every resource through a full scenario cycle from `threads` threads
+    fn run_scenario_cycles(
+        threads: usize,
+        resources: usize,
+        transition: impl Fn(StateChange) -> TransitionResult + Send + Sync + 'static,
+    ) -> std::time::Duration {
+        const CYCLE: [(&str, &str); 5] = [
+            ("Idle", "Waiting"),
+            ("Waiting", "Satisfied"),
+            ("Satisfied", "Allowed"),
+            ("Allowed", "Completed"),
+            ("Completed", "Waiting"),
+        ];
+        let transition = std::sync::Arc::new(transition);
+        let started = std::time::Instant::now();
+        let workers: Vec<_> = (0..threads)
+            .map(|t| {
+                let transition = std::sync::Arc::clone(&transition);
+                std::thread::spawn(move || {
+                    for i in (t..resources).step_by(threads) {
+                        for (step, (from, to)) in CYCLE.iter().enumerate() {
+                            let result = transition(StateChange {
+                                resource_type: ResourceType::Scenario as i32,
+                                resource_name: format!("bench-{i}"),
+                                current_state: from.to_string(),
+                                target_state: to.to_string(),
+                                transition_id: format!("bench-{i}-{step}"),
+                                timestamp_ns: step as i64,
+                                source: "bench".to_string(),
+                                asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+                                trace_id: String::new(),
+                            });
+                            assert!(result.is_success(), "{from} -> {to}: {}", result.message);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        started.elapsed()
+    }
+
+    /// Throughput of transitions with a lock around the whole state machine,
+    /// as before the state cache was sharded, and with per-shard locks
+    ///
+    /// Run with `cargo test --release -p statemanager -- --ignored bench_`
+    #[test]
+    #[ignore]
+    fn bench_parallel_transitions() {
+        const RESOURCES: usize = 4000;
+        // Gains need as many cores as threads
+        let threads = 8;
+        let transitions = (RESOURCES * 5) as f64;
+
+        let serialized = std::sync::Arc::new(std::sync::Mutex::new(StateMachine::new()));
+        let locked = std::sync::Arc::clone(&serialized);
+        let serialized_time = run_scenario_cycles(threads, RESOURCES, move |change| {
+            locked.lock().unwrap().process_state_change(change)
+        });
+
+        let sharded = std::sync::Arc::new(StateMachine::new());
+        let shared = std::sync::Arc::clone(&sharded);
+        let sharded_time = run_scenario_cycles(threads, RESOURCES, move |change| {
+            shared.process_state_change(change)
+        });
+
+        println!(
+            "{} transitions on {} threads: single lock {:.0}/s, sharded {:.0}/s",
+            RESOURCES * 5,
+            threads,
+            transitions / serialized_time.as_secs_f64(),
+            transitions / sharded_time.as_secs_f64()
+        );
+        for state_machine in [&*serialized.lock().unwrap(), &*sharded] {
+            assert_eq!(
+                state_machine
+                    .list_resources_by_state(
+                        Some(ResourceType::Scenario),
+                        ScenarioState::Waiting as i32
+                    )
+                    .len(),
+                RESOURCES
+            );
+        }
+    }
 }
@@ -0,0 +1,121 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Deadlines of state transitions by ASIL level
+//!
+//! A transition starts at the `timestamp_ns` set by the source component, so
+//! the time spent in the StateManager queue counts against the deadline. A
+//! timestamp that is missing or lies in the future is not trusted; the
+//! processing time in the StateManager is used instead.
+
+use common::statemanager::{AsilLevel, StateChange};
+use std::time::{Duration, Instant};
+
+/// Time a transition of the given ASIL level may take, `None` if unbounded
+pub fn deadline(asil_level: AsilLevel) -> Option<Duration> {
+    match asil_level {
+        AsilLevel::D => Some(Duration::from_millis(50)),
+        AsilLevel::C => Some(Duration::from_millis(100)),
+        AsilLevel::B => Some(Duration::from_millis(200)),
+        AsilLevel::A => Some(Duration::from_millis(500)),
+        AsilLevel::Qm | AsilLevel::Unspecified => None,
+    }
+}
+
+/// Duration of one transition compared to its deadline
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransitionTiming {
+    pub asil_level: AsilLevel,
+    pub elapsed: Duration,
+    pub deadline: Option<Duration>,
+}
+
+impl TransitionTiming {
+    /// Measure a transition that started processing at `started`
+    pub fn measure(state_change: &StateChange, started: Instant) -> Self {
+        let now_ns = chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default();
+        Self::at(state_change, now_ns, started.elapsed())
+    }
+
+    fn at(state_change: &StateChange, now_ns: i64, processing: Duration) -> Self {
+        let asil_level =
+            AsilLevel::try_from(state_change.asil_level).unwrap_or(AsilLevel::Unspecified);
+        let requested_ns = state_change.timestamp_ns;
+        let elapsed = if requested_ns > 0 && requested_ns <= now_ns {
+            Duration::from_nanos((now_ns - requested_ns) as u64).max(processing)
+        } else {
+            processing
+        };
+        Self {
+            asil_level,
+            elapsed,
+            deadline: deadline(asil_level),
+        }
+    }
+
+    /// `true` if the transition took longer than its ASIL level allows
+    pub fn missed(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| self.elapsed > deadline)
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_change(asil_level: AsilLevel, timestamp_ns: i64) -> StateChange {
+        StateChange {
+            resource_name: "timed".to_string(),
+            timestamp_ns,
+            asil_level: asil_level as i32,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_deadlines_tighten_with_asil_level() {
+        assert!(deadline(AsilLevel::D) < deadline(AsilLevel::C));
+        assert!(deadline(AsilLevel::C) < deadline(AsilLevel::B));
+        assert!(deadline(AsilLevel::B) < deadline(AsilLevel::A));
+        assert_eq!(deadline(AsilLevel::Qm), None);
+        assert_eq!(deadline(AsilLevel::Unspecified), None);
+    }
+
+    #[test]
+    fn test_elapsed_from_request_timestamp() {
+        let now_ns = 10_000_000_000;
+        let processing = Duration::from_millis(1);
+
+        // 80 ms since the request: within ASIL A, too late for ASIL D
+        let sent = now_ns - 80_000_000;
+        let timing = TransitionTiming::at(&state_change(AsilLevel::A, sent), now_ns, processing);
+        assert_eq!(timing.elapsed, Duration::from_millis(80));
+        assert!(!timing.missed());
+        let timing = TransitionTiming::at(&state_change(AsilLevel::D, sent), now_ns, processing);
+        assert!(timing.missed());
+
+        // QM transitions never miss a deadline
+        let timing = TransitionTiming::at(&state_change(AsilLevel::Qm, 1), now_ns, processing);
+        assert!(!timing.missed());
+    }
+
+    #[test]
+    fn test_untrusted_timestamp_uses_processing_time() {
+        let now_ns = 10_000_000_000;
+        let processing = Duration::from_millis(70);
+
+        for timestamp_ns in [0, now_ns + 1] {
+            let timing = TransitionTiming::at(
+                &state_change(AsilLevel::D, timestamp_ns),
+                now_ns,
+                processing,
+            );
+            assert_eq!(timing.elapsed, processing);
+            assert!(timing.missed());
+        }
+    }
+}
@@ -0,0 +1,373 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Transition tables loaded from configuration
+//!
+//! The tables compiled into the state machine are the defaults. At startup
+//! they can be tuned with a YAML document stored in etcd under
+//! [`TRANSITIONS_KEY`], or else read from `PULLPIRI_TRANSITIONS_PATH`
+//! (`/etc/pullpiri/transitions.yaml` by default):
+//!
+//! ```yaml
+//! scenario:
+//!   mode: merge
+//!   transitions:
+//!     - from: satisfied
+//!       event: policy_verification_failure
+//!       to: waiting
+//!       action: start_condition_evaluation
+//! node:
+//!   mode: replace
+//!   transitions: [...]
+//! ```
+//!
+//! With `merge` (default) a rule replaces the compiled rule with the same
+//! `from` and `event` and is added otherwise; with `replace` the rules are
+//! the whole table. States are named like in `StateChange` requests. A
+//! document with unknown states, duplicate rules or states that cannot be
+//! reached from the initial state is rejected and the compiled tables stay
+//! in use.
+
+use crate::state_machine::StateMachine;
+use crate::types::StateTransition;
+use common::logd;
+use common::statemanager::{NodeState, ResourceType, ScenarioState};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+/// etcd key of the transition document
+pub const TRANSITIONS_KEY: &str = "/statemanager/transitions";
+
+const DEFAULT_TRANSITIONS_PATH: &str = "/etc/pullpiri/transitions.yaml";
+
+/// How configured rules combine with the compiled table
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TableMode {
+    #[default]
+    Merge,
+    Replace,
+}
+
+/// One configured rule
+#[derive(Debug, Clone, Deserialize)]
+pub struct TransitionRule {
+    pub from: String,
+    pub event: String,
+    pub to: String,
+    #[serde(default)]
+    pub condition: Option<String>,
+    pub action: String,
+}
+
+/// Configured rules of one resource type
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TableConfig {
+    #[serde(default)]
+    pub mode: TableMode,
+    #[serde(default)]
+    pub transitions: Vec<TransitionRule>,
+}
+
+/// Transition document, one entry per resource type with a table
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransitionConfig {
+    #[serde(default)]
+    pub scenario: Option<TableConfig>,
+    #[serde(default)]
+    pub node: Option<TableConfig>,
+}
+
+impl TransitionConfig {
+    pub fn parse(document: &str) -> Result<Self, String> {
+        serde_yaml::from_str(document).map_err(|e| format!("Invalid transition document: {e}"))
+    }
+
+    /// Tables of `defaults` with the configured rules applied
+    ///
+    /// # Errors
+    ///
+    /// Returns the first rule or table that is not valid.
+    pub fn apply(
+        &self,
+        defaults: &HashMap<ResourceType, Vec<StateTransition>>,
+    ) -> Result<HashMap<ResourceType, Vec<StateTransition>>, String> {
+        let mut tables = defaults.clone();
+        for (resource_type, config) in [
+            (ResourceType::Scenario, &self.scenario),
+            (ResourceType::Node, &self.node),
+        ] {
+            let Some(config) = config else {
+                continue;
+            };
+            let table = merge_table(
+                resource_type,
+                defaults.get(&resource_type).cloned().unwrap_or_default(),
+                config,
+            )?;
+            validate_table(resource_type, &table)?;
+            tables.insert(resource_type, table);
+        }
+        Ok(tables)
+    }
+}
+
+fn merge_table(
+    resource_type: ResourceType,
+    mut table: Vec<StateTransition>,
+    config: &TableConfig,
+) -> Result<Vec<StateTransition>, String> {
+    let type_name = resource_type.as_str_name();
+    let mut seen = HashSet::new();
+    let mut rules = Vec::new();
+    for rule in &config.transitions {
+        let transition = to_transition(resource_type, rule)?;
+        if !seen.insert((transition.from_state, transition.event.clone())) {
+            return Err(format!(
+                "{type_name} has more than one rule from '{}' on event '{}'",
+                rule.from, rule.event
+            ));
+        }
+        rules.push(transition);
+    }
+
+    if config.mode == TableMode::Replace {
+        return Ok(rules);
+    }
+    for rule in rules {
+        match table
+            .iter_mut()
+            .find(|t| t.from_state == rule.from_state && t.event == rule.event)
+        {
+            Some(existing) => *existing = rule,
+            None => table.push(rule),
+        }
+    }
+    Ok(table)
+}
+
+fn to_transition(
+    resource_type: ResourceType,
+    rule: &TransitionRule,
+) -> Result<StateTransition, String> {
+    let state = |name: &str| {
+        let state = StateMachine::state_str_to_enum(name, resource_type as i32);
+        if state == 0 && !name.trim().eq_ignore_ascii_case("unspecified") {
+            return Err(format!(
+                "{} has no state '{}'",
+                resource_type.as_str_name(),
+                name
+            ));
+        }
+        Ok(state)
+    };
+    if rule.event.trim().is_empty() || rule.action.trim().is_empty() {
+        return Err(format!(
+            "Rule from '{}' to '{}' needs an event and an action",
+            rule.from, rule.to
+        ));
+    }
+    Ok(StateTransition {
+        from_state: state(&rule.from)?,
+        event: rule.event.trim().to_string(),
+        to_state: state(&rule.to)?,
+        condition: rule.condition.clone().filter(|c| !c.trim().is_empty()),
+        action: rule.action.trim().to_string(),
+    })
+}
+
+/// State every resource of the type starts in
+fn initial_state(resource_type: ResourceType) -> i32 {
+    match resource_type {
+        ResourceType::Scenario => ScenarioState::Idle as i32,
+        ResourceType::Node => NodeState::Unspecified as i32,
+        _ => 0,
+    }
+}
+
+fn state_name(resource_type: ResourceType, state: i32) -> String {
+    let name = match resource_type {
+        ResourceType::Scenario => ScenarioState::try_from(state).map(|s| s.as_str_name()),
+        ResourceType::Node => NodeState::try_from(state).map(|s| s.as_str_name()),
+        _ => return state.to_string(),
+    };
+    name.map(str::to_string)
+        .unwrap_or_else(|_| state.to_string())
+}
+
+/// Check that a table has no duplicate rules and no unreachable states
+///
+/// # Errors
+///
+/// Returns the duplicate rule or the first state that cannot be reached.
+pub fn validate_table(
+    resource_type: ResourceType,
+    table: &[StateTransition],
+) -> Result<(), String> {
+    let type_name = resource_type.as_str_name();
+    let mut seen = HashSet::new();
+    for transition in table {
+        if !seen.insert((transition.from_state, transition.event.as_str())) {
+            return Err(format!(
+                "{type_name} has more than one rule from {} on event '{}'",
+                state_name(resource_type, transition.from_state),
+                transition.event
+            ));
+        }
+    }
+
+    let mut reachable = HashSet::from([initial_state(resource_type)]);
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for transition in table {
+            if reachable.contains(&transition.from_state) {
+                changed |= reachable.insert(transition.to_state);
+            }
+        }
+    }
+    let mut unreachable: Vec<i32> = table
+        .iter()
+        .map(|t| t.from_state)
+        .filter(|state| !reachable.contains(state))
+        .collect();
+    unreachable.sort();
+    match unreachable.first() {
+        Some(state) => Err(format!(
+            "{type_name} state {} cannot be reached from {}",
+            state_name(resource_type, *state),
+            state_name(resource_type, initial_state(resource_type))
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Read the transition document from etcd, or else from its file
+async fn read_document() -> Option<(String, String)> {
+    if let Ok(document) = common::etcd::get(TRANSITIONS_KEY).await {
+        return Some((TRANSITIONS_KEY.to_string(), document));
+    }
+    let path = std::env::var("PULLPIRI_TRANSITIONS_PATH")
+        .unwrap_or_else(|_| DEFAULT_TRANSITIONS_PATH.to_string());
+    std::fs::read_to_string(&path)
+        .ok()
+        .map(|document| (path, document))
+}
+
+/// Apply the configured transitions to a new state machine
+///
+/// The compiled tables are kept if there is no document or it is not valid.
+pub async fn load(state_machine: &mut StateMachine) {
+    let Some((source, document)) = read_document().await else {
+        return;
+    };
+    match TransitionConfig::parse(&document)
+        .and_then(|config| state_machine.load_transitions(&config))
+    {
+        Ok(()) => logd!(3, "Loaded transition tables from {}", source),
+        Err(e) => logd!(
+            5,
+            "Ignoring transition tables of {}, using the defaults: {}",
+            source,
+            e
+        ),
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compiled_tables_are_valid() {
+        let state_machine = StateMachine::new();
+        for resource_type in [ResourceType::Scenario, ResourceType::Node] {
+            let table = state_machine.transition_table(resource_type).unwrap();
+            assert!(validate_table(resource_type, table).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_merge_overrides_and_adds_rules() {
+        let mut state_machine = StateMachine::new();
+        let config = TransitionConfig::parse(
+            r#"
+scenario:
+  transitions:
+    - from: satisfied
+      event: policy_verification_failure
+      to: waiting
+      action: start_condition_evaluation
+    - from: denied
+      event: retry
+      to: waiting
+      condition: retry_limit_reached
+      action: start_condition_evaluation
+"#,
+        )
+        .unwrap();
+        let before = state_machine
+            .transition_table(ResourceType::Scenario)
+            .unwrap()
+            .len();
+        state_machine.load_transitions(&config).unwrap();
+
+        let table = state_machine
+            .transition_table(ResourceType::Scenario)
+            .unwrap();
+        assert_eq!(table.len(), before + 1);
+        let overridden = table
+            .iter()
+            .find(|t| t.event == "policy_verification_failure")
+            .unwrap();
+        assert_eq!(overridden.to_state, ScenarioState::Waiting as i32);
+        assert_eq!(
+            table.last().unwrap().condition.as_deref(),
+            Some("retry_limit_reached")
+        );
+        // Node table untouched
+        assert_eq!(
+            state_machine.transition_table(ResourceType::Node),
+            StateMachine::new().transition_table(ResourceType::Node)
+        );
+    }
+
+    #[test]
+    fn test_invalid_documents_are_rejected() {
+        let mut state_machine = StateMachine::new();
+        let reject = |document: &str| {
+            TransitionConfig::parse(document)
+                .and_then(|config| config.apply(&HashMap::new()))
+                .unwrap_err()
+        };
+
+        assert!(reject(
+            "scenario:\n  transitions:\n    - {from: idle, event: go, to: running, action: a}\n"
+        )
+        .contains("no state 'running'"));
+        assert!(reject(
+            "scenario:\n  transitions:\n    - {from: idle, event: go, to: waiting, action: a}\n    - {from: idle, event: go, to: satisfied, action: b}\n"
+        )
+        .contains("more than one rule"));
+        assert!(reject(
+            "node:\n  mode: replace\n  transitions:\n    - {from: unspecified, event: node_registered, to: ready, action: a}\n    - {from: unknown, event: heartbeat_recovered, to: ready, action: b}\n"
+        )
+        .contains("cannot be reached"));
+        assert!(TransitionConfig::parse("package: {}\n").is_err());
+
+        let compiled = StateMachine::new();
+        let config = TransitionConfig::parse(
+            "scenario:\n  mode: replace\n  transitions:\n    - {from: waiting, event: condition_met, to: satisfied, action: a}\n",
+        )
+        .unwrap();
+        assert!(state_machine.load_transitions(&config).is_err());
+        assert_eq!(
+            state_machine.transition_table(ResourceType::Scenario),
+            compiled.transition_table(ResourceType::Scenario)
+        );
+    }
+}
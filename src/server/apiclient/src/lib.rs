@@ -0,0 +1,468 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Typed client of the Pullpiri REST API
+//!
+//! Written against `openapi.json`, the document ApiServer serves at
+//! `GET /api/openapi.json`. The ApiServer tests fail when the copy in this
+//! crate is outdated, and the tests of this crate fail when an operation of
+//! [`Client`] is not in the document.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), apiclient::Error> {
+//! let client = apiclient::Client::new("http://localhost:47099")?.with_token("secret");
+//! let scenarios = client.list_artifacts("Scenario", None, &Default::default()).await?;
+//! for scenario in scenarios.artifacts {
+//!     println!("{}", scenario.name);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod types;
+
+pub use types::*;
+
+use reqwest::{Method, RequestBuilder, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+/// OpenAPI document the client is written against
+pub const OPENAPI: &str = include_str!("../openapi.json");
+
+/// Method and path of an operation of `openapi.json`
+struct Operation {
+    method: Method,
+    path: &'static str,
+}
+
+macro_rules! operations {
+    ($($name:ident: $method:ident $path:literal,)*) => {
+        $(const $name: Operation = Operation { method: Method::$method, path: $path };)*
+        #[cfg(test)]
+        const OPERATIONS: &[Operation] = &[$($name),*];
+    };
+}
+
+operations! {
+    APPLY_ARTIFACT: POST "/api/artifact",
+    WITHDRAW_ARTIFACT: DELETE "/api/artifact",
+    VALIDATE_ARTIFACT: POST "/api/artifact/validate",
+    APPLY_ARTIFACT_TRANSACTION: POST "/api/artifact/transaction",
+    LIST_NAMESPACES: GET "/api/namespaces",
+    LIST_ARTIFACTS: GET "/api/artifact/{kind}",
+    GET_ARTIFACT: GET "/api/artifact/{kind}/{name}",
+    LIST_VERSIONS: GET "/api/artifact/{kind}/{name}/versions",
+    DIFF_VERSIONS: GET "/api/artifact/{kind}/{name}/diff",
+    EXPORT_ARTIFACT: GET "/api/artifact/{kind}/{name}/export",
+    ROLLBACK_ARTIFACT: POST "/api/artifact/{kind}/{name}/rollback/{version}",
+    LIST_CLUSTERS: GET "/api/clusters",
+    CREATE_CLUSTER: POST "/api/clusters",
+    GET_CLUSTER: GET "/api/clusters/{id}",
+    DELETE_CLUSTER: DELETE "/api/clusters/{id}",
+    GET_CLUSTER_HEALTH: GET "/api/clusters/{id}/health",
+    ASSIGN_NODE: PUT "/api/clusters/{id}/nodes/{node}",
+    TRIGGER_SCENARIO: POST "/api/v1/scenarios/{name}/trigger",
+    RESET_SCENARIO: POST "/api/v1/scenarios/{name}/reset",
+}
+
+/// Failure of a request
+#[derive(Debug)]
+pub enum Error {
+    /// The base URL is invalid
+    Url(String),
+    /// The request could not be sent or its response not read
+    Http(reqwest::Error),
+    /// ApiServer answered with an error
+    Api { status: StatusCode, error: ApiError },
+    /// The response is not what the document describes
+    Decode(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Url(e) => write!(f, "invalid URL: {}", e),
+            Error::Http(e) => write!(f, "request failed: {}", e),
+            Error::Api { status, error } => {
+                write!(f, "{} {}: {}", status.as_u16(), error.code, error.message)
+            }
+            Error::Decode(e) => write!(f, "unexpected response: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Http(e)
+    }
+}
+
+/// Client of one ApiServer
+#[derive(Debug, Clone)]
+pub struct Client {
+    base: Url,
+    token: Option<String>,
+    http: reqwest::Client,
+}
+
+impl Client {
+    /// Client of the ApiServer at `base`, e.g. `http://localhost:47099`
+    pub fn new(base: &str) -> Result<Self, Error> {
+        let base = Url::parse(base).map_err(|e| Error::Url(e.to_string()))?;
+        if base.cannot_be_a_base() {
+            return Err(Error::Url(format!("{} cannot be a base", base)));
+        }
+        Ok(Self {
+            base,
+            token: None,
+            http: reqwest::Client::new(),
+        })
+    }
+
+    /// Send `token` as bearer token
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// URL of an operation, with `params` in place of its path parameters
+    fn url(&self, operation: &Operation, params: &[&str]) -> Url {
+        let mut url = self.base.clone();
+        {
+            let mut segments = url.path_segments_mut().expect("checked by Client::new");
+            segments.pop_if_empty();
+            let mut params = params.iter();
+            for segment in operation.path.split('/').skip(1) {
+                if segment.starts_with('{') {
+                    segments.push(params.next().expect("missing path parameter"));
+                } else {
+                    segments.push(segment);
+                }
+            }
+        }
+        url
+    }
+
+    fn request(&self, operation: &Operation, params: &[&str]) -> RequestBuilder {
+        let request = self
+            .http
+            .request(operation.method.clone(), self.url(operation, params));
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Error of a response that is not a success
+    fn error(status: StatusCode, body: &[u8]) -> Error {
+        match serde_json::from_slice::<ApiError>(body) {
+            Ok(error) => Error::Api { status, error },
+            Err(_) => Error::Decode(format!(
+                "{} {}",
+                status.as_u16(),
+                String::from_utf8_lossy(body)
+            )),
+        }
+    }
+
+    /// Send a request, a response with a status of `accepted` is decoded as
+    /// `T` even if it is not a success
+    async fn send<T: DeserializeOwned>(
+        request: RequestBuilder,
+        accepted: &[StatusCode],
+    ) -> Result<T, Error> {
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        if status.is_success() || accepted.contains(&status) {
+            return serde_json::from_slice(&body).map_err(|e| Error::Decode(e.to_string()));
+        }
+        Err(Self::error(status, &body))
+    }
+
+    /// Send a request answered with a yaml body
+    async fn send_text(request: RequestBuilder) -> Result<String, Error> {
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response.text().await?);
+        }
+        let body = response.bytes().await?;
+        Err(Self::error(status, &body))
+    }
+
+    fn yaml(request: RequestBuilder, yaml: &str) -> RequestBuilder {
+        request
+            .header(reqwest::header::CONTENT_TYPE, "application/yaml")
+            .body(yaml.to_string())
+    }
+
+    fn namespace(request: RequestBuilder, namespace: Option<&str>) -> RequestBuilder {
+        match namespace {
+            Some(namespace) => request.query(&[("namespace", namespace)]),
+            None => request,
+        }
+    }
+
+    /// Apply the artifacts of a multi-document yaml
+    pub async fn apply_artifact(&self, yaml: &str) -> Result<(), Error> {
+        let request = Self::yaml(self.request(&APPLY_ARTIFACT, &[]), yaml);
+        Self::send::<String>(request, &[]).await.map(|_| ())
+    }
+
+    /// Withdraw the scenario of a multi-document yaml
+    pub async fn withdraw_artifact(&self, yaml: &str) -> Result<(), Error> {
+        let request = Self::yaml(self.request(&WITHDRAW_ARTIFACT, &[]), yaml);
+        Self::send::<String>(request, &[]).await.map(|_| ())
+    }
+
+    /// Validate artifacts without applying them, an invalid yaml is not an
+    /// error but a report with `valid` false
+    pub async fn validate_artifact(&self, yaml: &str) -> Result<ValidationReport, Error> {
+        let request = Self::yaml(self.request(&VALIDATE_ARTIFACT, &[]), yaml);
+        Self::send(request, &[StatusCode::UNPROCESSABLE_ENTITY]).await
+    }
+
+    /// Apply artifacts all together or not at all, a transaction that was
+    /// rolled back is not an error but a report with `committed` false
+    pub async fn apply_artifact_transaction(&self, yaml: &str) -> Result<ApplyReport, Error> {
+        let request = Self::yaml(self.request(&APPLY_ARTIFACT_TRANSACTION, &[]), yaml);
+        Self::send(
+            request,
+            &[
+                StatusCode::UNPROCESSABLE_ENTITY,
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ],
+        )
+        .await
+    }
+
+    /// Namespaces holding artifacts, the default one first
+    pub async fn list_namespaces(&self) -> Result<Vec<String>, Error> {
+        Self::send(self.request(&LIST_NAMESPACES, &[]), &[]).await
+    }
+
+    /// Stored artifacts of a kind
+    pub async fn list_artifacts(
+        &self,
+        kind: &str,
+        namespace: Option<&str>,
+        query: &ListQuery,
+    ) -> Result<ArtifactList, Error> {
+        let request = self.request(&LIST_ARTIFACTS, &[kind]).query(query);
+        Self::send(Self::namespace(request, namespace), &[]).await
+    }
+
+    /// Yaml of a stored artifact
+    pub async fn get_artifact(
+        &self,
+        kind: &str,
+        name: &str,
+        namespace: Option<&str>,
+    ) -> Result<String, Error> {
+        let request = self.request(&GET_ARTIFACT, &[kind, name]);
+        Self::send_text(Self::namespace(request, namespace)).await
+    }
+
+    /// Stored versions of an artifact
+    pub async fn list_versions(
+        &self,
+        kind: &str,
+        name: &str,
+        namespace: Option<&str>,
+    ) -> Result<VersionHistory, Error> {
+        let request = self.request(&LIST_VERSIONS, &[kind, name]);
+        Self::send(Self::namespace(request, namespace), &[]).await
+    }
+
+    /// Line diff of two stored versions of an artifact
+    pub async fn diff_versions(
+        &self,
+        kind: &str,
+        name: &str,
+        from: u64,
+        to: u64,
+        namespace: Option<&str>,
+    ) -> Result<VersionDiff, Error> {
+        let request = self
+            .request(&DIFF_VERSIONS, &[kind, name])
+            .query(&[("from", from), ("to", to)]);
+        Self::send(Self::namespace(request, namespace), &[]).await
+    }
+
+    /// Manifests of a stored package for another orchestrator
+    ///
+    /// ### Parameters
+    /// * `format: &str` - `k8s`
+    /// * `workload: Option<&str>` - `deployment`, the default, or `pod`
+    pub async fn export_artifact(
+        &self,
+        kind: &str,
+        name: &str,
+        format: &str,
+        workload: Option<&str>,
+        namespace: Option<&str>,
+    ) -> Result<String, Error> {
+        let mut request = self
+            .request(&EXPORT_ARTIFACT, &[kind, name])
+            .query(&[("format", format)]);
+        if let Some(workload) = workload {
+            request = request.query(&[("workload", workload)]);
+        }
+        Self::send_text(Self::namespace(request, namespace)).await
+    }
+
+    /// Re-activate a stored version of an artifact
+    pub async fn rollback_artifact(
+        &self,
+        kind: &str,
+        name: &str,
+        version: u64,
+        namespace: Option<&str>,
+    ) -> Result<(), Error> {
+        let version = version.to_string();
+        let request = self.request(&ROLLBACK_ARTIFACT, &[kind, name, &version]);
+        Self::send::<String>(Self::namespace(request, namespace), &[])
+            .await
+            .map(|_| ())
+    }
+
+    /// The default cluster and all named clusters
+    pub async fn list_clusters(&self, query: &ListQuery) -> Result<ClusterList, Error> {
+        Self::send(self.request(&LIST_CLUSTERS, &[]).query(query), &[]).await
+    }
+
+    /// Create a named cluster
+    pub async fn create_cluster(
+        &self,
+        cluster: &ClusterTopology,
+    ) -> Result<ClusterTopology, Error> {
+        Self::send(self.request(&CREATE_CLUSTER, &[]).json(cluster), &[]).await
+    }
+
+    /// Topology of a cluster
+    pub async fn get_cluster(&self, id: &str) -> Result<ClusterTopology, Error> {
+        Self::send(self.request(&GET_CLUSTER, &[id]), &[]).await
+    }
+
+    /// Delete a named cluster, its nodes return to the default cluster
+    pub async fn delete_cluster(&self, id: &str) -> Result<ClusterTopology, Error> {
+        Self::send(self.request(&DELETE_CLUSTER, &[id]), &[]).await
+    }
+
+    /// Health summary of the nodes of a cluster
+    pub async fn get_cluster_health(&self, id: &str) -> Result<ClusterHealth, Error> {
+        Self::send(self.request(&GET_CLUSTER_HEALTH, &[id]), &[]).await
+    }
+
+    /// Move a node into a cluster
+    pub async fn assign_node(&self, id: &str, node: &str) -> Result<ClusterTopology, Error> {
+        Self::send(self.request(&ASSIGN_NODE, &[id, node]), &[]).await
+    }
+
+    /// Execution plan of the action of a scenario, without running it
+    pub async fn plan_scenario(
+        &self,
+        name: &str,
+        namespace: Option<&str>,
+    ) -> Result<serde_json::Value, Error> {
+        let request = self
+            .request(&TRIGGER_SCENARIO, &[name])
+            .query(&[("dry_run", true)]);
+        Self::send(Self::namespace(request, namespace), &[]).await
+    }
+
+    /// Run the action of a scenario now and track its models
+    ///
+    /// With `wait`, the result is returned once the models reached a terminal
+    /// state or the wait timed out, which is not an error but a result with
+    /// status `timed_out`.
+    pub async fn trigger_scenario(
+        &self,
+        name: &str,
+        wait: Option<std::time::Duration>,
+        namespace: Option<&str>,
+    ) -> Result<TriggerResult, Error> {
+        let mut request = self.request(&TRIGGER_SCENARIO, &[name]);
+        if let Some(wait) = wait {
+            request = request
+                .query(&[("wait", "true")])
+                .query(&[("timeout", wait.as_secs())]);
+        }
+        Self::send(
+            Self::namespace(request, namespace),
+            &[StatusCode::GATEWAY_TIMEOUT],
+        )
+        .await
+    }
+
+    /// Release a scenario quarantined for flapping
+    pub async fn reset_scenario(
+        &self,
+        name: &str,
+        reason: Option<&str>,
+        namespace: Option<&str>,
+    ) -> Result<ResetResult, Error> {
+        let mut request = self.request(&RESET_SCENARIO, &[name]);
+        if let Some(reason) = reason {
+            request = request.query(&[("reason", reason)]);
+        }
+        Self::send(Self::namespace(request, namespace), &[]).await
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operations_are_in_the_document() {
+        let document: serde_json::Value = serde_json::from_str(OPENAPI).unwrap();
+        for operation in OPERATIONS {
+            let method = operation.method.as_str().to_lowercase();
+            assert!(
+                document["paths"][operation.path][&method].is_object(),
+                "{} {} is not in openapi.json",
+                operation.method,
+                operation.path
+            );
+        }
+    }
+
+    #[test]
+    fn test_url_of_operation() {
+        let client = Client::new("http://localhost:47099/").unwrap();
+        assert_eq!(
+            client
+                .url(&ROLLBACK_ARTIFACT, &["Scenario", "diag/capture", "3"])
+                .as_str(),
+            "http://localhost:47099/api/artifact/Scenario/diag%2Fcapture/rollback/3"
+        );
+
+        let client = Client::new("http://gateway/pullpiri").unwrap();
+        assert_eq!(
+            client.url(&LIST_CLUSTERS, &[]).as_str(),
+            "http://gateway/pullpiri/api/clusters"
+        );
+        assert!(Client::new("not a url").is_err());
+    }
+
+    #[test]
+    fn test_api_error_response() {
+        let error: ApiError = serde_json::from_str(
+            r#"{"code":"NOT_FOUND","message":"no such cluster","correlation_id":"abc"}"#,
+        )
+        .unwrap();
+        let error = Error::Api {
+            status: StatusCode::NOT_FOUND,
+            error,
+        };
+        assert_eq!(error.to_string(), "404 NOT_FOUND: no such cluster");
+    }
+}
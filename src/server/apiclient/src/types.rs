@@ -0,0 +1,193 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Schemas of `openapi.json`
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Body of a failed request
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ApiError {
+    /// e.g. `VALIDATION` or `NOT_FOUND`
+    pub code: String,
+    pub message: String,
+    #[serde(default)]
+    pub details: Option<serde_json::Value>,
+    #[serde(default)]
+    pub correlation_id: Option<String>,
+}
+
+/// `limit`, `offset`, `sort`, `order` and `label` of a listing
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ListQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    /// `asc` or `desc`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ArtifactSummary {
+    pub name: String,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+}
+
+/// Page of `listArtifacts`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ArtifactList {
+    pub artifacts: Vec<ArtifactSummary>,
+    pub total: usize,
+    pub offset: usize,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub next_offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ArtifactRef {
+    pub document: usize,
+    pub kind: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ValidationIssue {
+    #[serde(default)]
+    pub document: Option<usize>,
+    #[serde(default)]
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub message: String,
+}
+
+/// Result of `validateArtifact`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ValidationReport {
+    pub valid: bool,
+    pub artifacts: Vec<ArtifactRef>,
+    pub errors: Vec<ValidationIssue>,
+    pub warnings: Vec<ValidationIssue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DocumentResult {
+    pub document: usize,
+    #[serde(default)]
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// `applied`, `updated`, `unchanged`, `invalid`, `superseded` or
+    /// `not_applied`
+    pub status: String,
+    #[serde(default)]
+    pub version: Option<u64>,
+    #[serde(default)]
+    pub messages: Vec<String>,
+}
+
+/// Result of `applyArtifactTransaction`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ApplyReport {
+    pub committed: bool,
+    pub documents: Vec<DocumentResult>,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct VersionHistory {
+    pub latest: u64,
+    pub versions: Vec<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct VersionDiff {
+    pub from: u64,
+    pub to: u64,
+    pub lines: Vec<String>,
+}
+
+/// Cluster of `createCluster`, `getCluster` and `listClusters`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClusterTopology {
+    pub cluster_id: String,
+    pub cluster_name: String,
+    /// TopologyType of apiserver.proto
+    #[serde(rename = "type")]
+    pub topology_type: i32,
+    pub master_nodes: Vec<serde_json::Value>,
+    pub sub_nodes: Vec<serde_json::Value>,
+    pub parent_cluster: String,
+    pub config: HashMap<String, String>,
+}
+
+/// Page of `listClusters`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ClusterList {
+    pub clusters: Vec<ClusterTopology>,
+    pub total: usize,
+    pub offset: usize,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub next_offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ClusterHealth {
+    pub cluster_id: String,
+    pub total_nodes: u32,
+    pub healthy_nodes: u32,
+    pub unhealthy_nodes: u32,
+    pub master_nodes: u32,
+    pub nodeagent_nodes: u32,
+    pub ready_nodes: u32,
+    /// `Healthy`, `Degraded` or `Critical`
+    pub status: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ModelResult {
+    pub model: String,
+    pub node: String,
+    pub operation: String,
+    pub state: String,
+    /// `pending`, `succeeded` or `failed`
+    pub outcome: String,
+}
+
+/// Result of `triggerScenario`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TriggerResult {
+    pub transition_id: String,
+    pub scenario: String,
+    /// `accepted`, `succeeded`, `failed` or `timed_out`
+    pub status: String,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub models: Vec<ModelResult>,
+}
+
+/// Result of `resetScenario`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ResetResult {
+    pub scenario: String,
+    pub state: String,
+    pub message: String,
+}
@@ -0,0 +1,530 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Offline bundles for vehicles without registry access
+//!
+//! A bundle is a single file holding the stored artifacts and the container
+//! images their models use, so a vehicle can be provisioned from a USB
+//! stick or a local path. The file consists of
+//!
+//! ```text
+//! PULLPIRI-BUNDLE/1\n
+//! <manifest as one line of JSON>\n
+//! <base64 Ed25519 signature of the manifest line>\n
+//! <image archives of `podman save`, in manifest order>
+//! ```
+//!
+//! The manifest lists the artifact yamls and the size and SHA-256 digest of
+//! every image archive, so the signature covers the whole file. Bundles are
+//! signed with the PKCS#8 key in `PULLPIRI_BUNDLE_SIGNING_KEY` and verified
+//! with the raw public key in `PULLPIRI_BUNDLE_PUBLIC_KEY`, both base64.
+//! Secrets are not exported, their values are sealed with the key of the
+//! vehicle they were applied to.
+
+use super::{KINDS, KIND_MODEL, KIND_SECRET, YAML_SEPARATOR};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use common::logd;
+use common::spec::artifact::Model;
+use ring::signature::{Ed25519KeyPair, UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Environment variable holding the base64 PKCS#8 Ed25519 signing key
+pub const SIGNING_KEY_ENV: &str = "PULLPIRI_BUNDLE_SIGNING_KEY";
+/// Environment variable holding the base64 Ed25519 public key
+pub const PUBLIC_KEY_ENV: &str = "PULLPIRI_BUNDLE_PUBLIC_KEY";
+
+/// First line of every bundle file
+const MAGIC: &str = "PULLPIRI-BUNDLE/1";
+const FORMAT_VERSION: u32 = 1;
+/// Longest manifest line accepted, larger bundles are rejected unread
+const MAX_MANIFEST_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Signed table of contents of a bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    pub created: String,
+    /// Yaml of every exported artifact
+    pub artifacts: Vec<String>,
+    pub images: Vec<BundleImage>,
+}
+
+/// Image archive stored after the manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleImage {
+    pub name: String,
+    pub size: u64,
+    /// Hex SHA-256 digest of the archive
+    pub sha256: String,
+}
+
+/// Summary of an export
+#[derive(Debug, Serialize)]
+pub struct ExportSummary {
+    pub path: String,
+    pub artifacts: usize,
+    pub images: Vec<String>,
+}
+
+/// Export the stored artifacts and their images into a bundle file
+///
+/// ### Parameters
+/// * `path: &str` - file to write, replaced if it exists
+/// ### Returns
+/// * `Result<ExportSummary>` - what the bundle contains
+/// ### Description
+/// Every image of a Model is saved with `podman save`, so the images must be
+/// present on the host running ApiServer.
+pub async fn export(path: &str) -> common::Result<ExportSummary> {
+    let key = signing_key()?;
+    let artifacts = stored_artifacts().await?;
+    let images = images_of(&artifacts);
+    logd!(
+        2,
+        "Exporting bundle {} with {} artifact(s) and {} image(s)",
+        path,
+        artifacts.len(),
+        images.len()
+    );
+
+    let count = artifacts.len();
+    let target = PathBuf::from(path);
+    let saved = images.clone();
+    tokio::task::spawn_blocking(move || -> Result<(), String> {
+        let work_dir = work_dir("export")?;
+        let result = save_images(&saved, &work_dir).and_then(|archives| {
+            let manifest = Manifest {
+                version: FORMAT_VERSION,
+                created: chrono::Utc::now().to_rfc3339(),
+                artifacts,
+                images: archives.iter().map(|(image, _)| image.clone()).collect(),
+            };
+            let paths: Vec<PathBuf> = archives.into_iter().map(|(_, path)| path).collect();
+            let file = File::create(&target)
+                .map_err(|e| format!("Cannot create {}: {}", target.display(), e))?;
+            let mut out = BufWriter::new(file);
+            write_bundle(&mut out, &manifest, &paths, &key)?;
+            out.flush().map_err(|e| e.to_string())
+        });
+        let _ = std::fs::remove_dir_all(&work_dir);
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    Ok(ExportSummary {
+        path: path.to_string(),
+        artifacts: count,
+        images,
+    })
+}
+
+/// Verify a bundle file, load its images and apply its artifacts
+///
+/// ### Parameters
+/// * `path: &str` - bundle file, e.g. on a mounted USB stick
+/// ### Returns
+/// * `Result<String>` - artifacts to apply as one multi-document yaml
+/// ### Description
+/// Nothing is loaded unless the signature and every image digest match.
+/// The caller applies the returned artifacts in one transaction.
+pub async fn import(path: &str) -> common::Result<String> {
+    let public_key = public_key()?;
+    let source = PathBuf::from(path);
+
+    let manifest = tokio::task::spawn_blocking(move || -> Result<Manifest, String> {
+        let file =
+            File::open(&source).map_err(|e| format!("Cannot open {}: {}", source.display(), e))?;
+        let work_dir = work_dir("import")?;
+        let result = read_bundle(&mut BufReader::new(file), &public_key, &work_dir).and_then(
+            |(manifest, archives)| {
+                for (image, archive) in manifest.images.iter().zip(archives) {
+                    load_image(&image.name, &archive)?;
+                }
+                Ok(manifest)
+            },
+        );
+        let _ = std::fs::remove_dir_all(&work_dir);
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    logd!(
+        2,
+        "Imported bundle {} created {}: {} artifact(s), {} image(s)",
+        path,
+        manifest.created,
+        manifest.artifacts.len(),
+        manifest.images.len()
+    );
+    Ok(manifest.artifacts.join(&format!("{}\n", YAML_SEPARATOR)))
+}
+
+/// Yaml of every stored artifact of all namespaces, Secrets excluded
+async fn stored_artifacts() -> common::Result<Vec<String>> {
+    let namespaces = super::data::read_namespaces().await?;
+    let mut artifacts = Vec::new();
+    for namespace in &namespaces {
+        for kind in KINDS.iter().filter(|kind| **kind != KIND_SECRET) {
+            let prefix = common::spec::namespace::kind_prefix(namespace, kind);
+            for (key, value) in super::storage::storage()
+                .get_all_with_prefix(&prefix)
+                .await?
+            {
+                // Keys below an artifact hold its versions
+                let name = key.strip_prefix(&prefix).unwrap_or_default();
+                if !name.is_empty() && !name.contains('/') {
+                    artifacts.push(value);
+                }
+            }
+        }
+    }
+    Ok(artifacts)
+}
+
+/// Images used by the Models among the artifacts, without duplicates
+fn images_of(artifacts: &[String]) -> Vec<String> {
+    let mut images: Vec<String> = Vec::new();
+    for yaml in artifacts {
+        let value: serde_yaml::Value = match serde_yaml::from_str(yaml) {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        if value.get("kind").and_then(|kind| kind.as_str()) != Some(KIND_MODEL) {
+            continue;
+        }
+        let Ok(model) = serde_yaml::from_value::<Model>(value) else {
+            continue;
+        };
+        for image in model.get_podspec().get_images() {
+            if !images.iter().any(|known| known == image) {
+                images.push(image.to_string());
+            }
+        }
+    }
+    images
+}
+
+/// Write a signed bundle
+///
+/// `archives` are the files of the images of the manifest, in the same order.
+fn write_bundle<W: Write>(
+    out: &mut W,
+    manifest: &Manifest,
+    archives: &[PathBuf],
+    key: &Ed25519KeyPair,
+) -> Result<(), String> {
+    let manifest_line = serde_json::to_string(manifest).map_err(|e| e.to_string())?;
+    let signature = key.sign(manifest_line.as_bytes());
+
+    writeln!(out, "{}", MAGIC).map_err(|e| e.to_string())?;
+    writeln!(out, "{}", manifest_line).map_err(|e| e.to_string())?;
+    writeln!(out, "{}", STANDARD.encode(signature.as_ref())).map_err(|e| e.to_string())?;
+    for archive in archives {
+        let mut file =
+            File::open(archive).map_err(|e| format!("Cannot open {}: {}", archive.display(), e))?;
+        std::io::copy(&mut file, out).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Read and verify a bundle, extracting its image archives into `dir`
+///
+/// ### Returns
+/// * `Ok((manifest, archives))` - verified manifest and the extracted files
+/// * `Err(_)` - wrong format, bad signature or an image digest mismatch
+fn read_bundle<R: BufRead>(
+    input: &mut R,
+    public_key: &[u8],
+    dir: &Path,
+) -> Result<(Manifest, Vec<PathBuf>), String> {
+    if read_line(input, MAGIC.len() as u64 + 1)? != MAGIC {
+        return Err("Not a Pullpiri bundle".to_string());
+    }
+    let manifest_line = read_line(input, MAX_MANIFEST_BYTES)?;
+    let signature = STANDARD
+        .decode(read_line(input, 1024)?)
+        .map_err(|e| format!("Invalid bundle signature encoding: {}", e))?;
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(manifest_line.as_bytes(), &signature)
+        .map_err(|_| "Bundle signature does not match".to_string())?;
+
+    let manifest: Manifest =
+        serde_json::from_str(&manifest_line).map_err(|e| format!("Invalid manifest: {}", e))?;
+    if manifest.version != FORMAT_VERSION {
+        return Err(format!("Unsupported bundle version {}", manifest.version));
+    }
+
+    let mut archives = Vec::new();
+    for (index, image) in manifest.images.iter().enumerate() {
+        let path = dir.join(format!("image-{}.tar", index));
+        extract_archive(input, image, &path)?;
+        archives.push(path);
+    }
+    if input.fill_buf().map_err(|e| e.to_string())?.is_empty() {
+        Ok((manifest, archives))
+    } else {
+        Err("Bundle has data after the last image".to_string())
+    }
+}
+
+/// Read one line without its newline, at most `limit` bytes long
+fn read_line<R: BufRead>(input: &mut R, limit: u64) -> Result<String, String> {
+    let mut line = String::new();
+    input
+        .by_ref()
+        .take(limit + 1)
+        .read_line(&mut line)
+        .map_err(|e| format!("Cannot read bundle: {}", e))?;
+    match line.strip_suffix('\n') {
+        Some(line) => Ok(line.to_string()),
+        None => Err("Bundle header is truncated or too long".to_string()),
+    }
+}
+
+/// Copy the archive of an image to `path`, checking its size and digest
+fn extract_archive<R: Read>(input: &mut R, image: &BundleImage, path: &Path) -> Result<(), String> {
+    let mut file =
+        File::create(path).map_err(|e| format!("Cannot create {}: {}", path.display(), e))?;
+    let mut digest = ring::digest::Context::new(&ring::digest::SHA256);
+    let mut remaining = image.size;
+    let mut buffer = vec![0u8; 64 * 1024];
+    while remaining > 0 {
+        let wanted = remaining.min(buffer.len() as u64) as usize;
+        let read = input
+            .read(&mut buffer[..wanted])
+            .map_err(|e| format!("Cannot read image {}: {}", image.name, e))?;
+        if read == 0 {
+            return Err(format!("Bundle is truncated in image {}", image.name));
+        }
+        digest.update(&buffer[..read]);
+        file.write_all(&buffer[..read])
+            .map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+        remaining -= read as u64;
+    }
+    if hex(digest.finish().as_ref()) != image.sha256 {
+        return Err(format!("Digest of image {} does not match", image.name));
+    }
+    Ok(())
+}
+
+/// Save each image with `podman save` and describe the archives
+fn save_images(images: &[String], dir: &Path) -> Result<Vec<(BundleImage, PathBuf)>, String> {
+    let mut archives = Vec::new();
+    for (index, name) in images.iter().enumerate() {
+        let path = dir.join(format!("image-{}.tar", index));
+        let output = Command::new("podman")
+            .arg("save")
+            .arg("-o")
+            .arg(&path)
+            .arg(name)
+            .output()
+            .map_err(|e| format!("Cannot run podman: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "podman save {} failed: {}",
+                name,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        archives.push((describe_archive(name, &path)?, path));
+    }
+    Ok(archives)
+}
+
+/// Size and digest of an image archive
+fn describe_archive(name: &str, path: &Path) -> Result<BundleImage, String> {
+    let mut file =
+        File::open(path).map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+    let mut digest = ring::digest::Context::new(&ring::digest::SHA256);
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        digest.update(&buffer[..read]);
+        size += read as u64;
+    }
+    Ok(BundleImage {
+        name: name.to_string(),
+        size,
+        sha256: hex(digest.finish().as_ref()),
+    })
+}
+
+/// Load an image archive with `podman load`
+fn load_image(name: &str, archive: &Path) -> Result<(), String> {
+    let output = Command::new("podman")
+        .arg("load")
+        .arg("-i")
+        .arg(archive)
+        .output()
+        .map_err(|e| format!("Cannot run podman: {}", e))?;
+    if output.status.success() {
+        logd!(2, "Loaded image {}", name);
+        Ok(())
+    } else {
+        Err(format!(
+            "podman load of {} failed: {}",
+            name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Empty scratch directory for image archives
+fn work_dir(purpose: &str) -> Result<PathBuf, String> {
+    let dir = std::env::temp_dir().join(format!(
+        "pullpiri-bundle-{}-{}-{}",
+        purpose,
+        std::process::id(),
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+fn signing_key() -> Result<Ed25519KeyPair, String> {
+    let pkcs8 = key_from_env(SIGNING_KEY_ENV)?;
+    Ed25519KeyPair::from_pkcs8(&pkcs8)
+        .map_err(|_| format!("{} is not a PKCS#8 Ed25519 key", SIGNING_KEY_ENV))
+}
+
+fn public_key() -> Result<Vec<u8>, String> {
+    let key = key_from_env(PUBLIC_KEY_ENV)?;
+    if key.len() != 32 {
+        return Err(format!("{} must be a 32 byte Ed25519 key", PUBLIC_KEY_ENV));
+    }
+    Ok(key)
+}
+
+fn key_from_env(name: &str) -> Result<Vec<u8>, String> {
+    match std::env::var(name) {
+        Ok(value) if !value.trim().is_empty() => STANDARD
+            .decode(value.trim())
+            .map_err(|e| format!("{} is not valid base64: {}", name, e)),
+        _ => Err(format!("{} is not set", name)),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::signature::KeyPair;
+
+    const MODEL_YAML: &str = r#"apiVersion: v1
+kind: Model
+metadata:
+  name: helloworld-core
+spec:
+  initContainers:
+    - name: init
+      image: quay.io/podman/hello:latest
+  containers:
+    - name: helloworld
+      image: quay.io/podman/hello:latest
+    - name: sidecar
+      image: quay.io/podman/sidecar:1.0
+"#;
+
+    fn key_pair() -> Ed25519KeyPair {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new()).unwrap();
+        Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+    }
+
+    /// Bundle with one fake image archive, and the directory holding it
+    fn bundle(key: &Ed25519KeyPair) -> (Vec<u8>, PathBuf) {
+        let dir = work_dir("test").unwrap();
+        let archive = dir.join("source.tar");
+        std::fs::write(&archive, b"image layers").unwrap();
+        let manifest = Manifest {
+            version: FORMAT_VERSION,
+            created: "2024-01-01T00:00:00+00:00".to_string(),
+            artifacts: vec![MODEL_YAML.to_string()],
+            images: vec![describe_archive("quay.io/podman/hello:latest", &archive).unwrap()],
+        };
+        let mut out = Vec::new();
+        write_bundle(&mut out, &manifest, &[archive], key).unwrap();
+        (out, dir)
+    }
+
+    #[test]
+    fn test_images_of_models() {
+        let artifacts = vec![
+            MODEL_YAML.to_string(),
+            "apiVersion: v1\nkind: Volume\nmetadata:\n  name: v\nspec: {}\n".to_string(),
+        ];
+        assert_eq!(
+            images_of(&artifacts),
+            vec!["quay.io/podman/hello:latest", "quay.io/podman/sidecar:1.0"]
+        );
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let key = key_pair();
+        let (bytes, dir) = bundle(&key);
+
+        let (manifest, archives) =
+            read_bundle(&mut bytes.as_slice(), key.public_key().as_ref(), &dir).unwrap();
+        assert_eq!(manifest.artifacts, vec![MODEL_YAML.to_string()]);
+        assert_eq!(manifest.images[0].size, 12);
+        assert_eq!(std::fs::read(&archives[0]).unwrap(), b"image layers");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_bundle_rejects_tampering() {
+        let key = key_pair();
+        let (bytes, dir) = bundle(&key);
+
+        // Signed by another key
+        let other = key_pair();
+        let result = read_bundle(&mut bytes.as_slice(), other.public_key().as_ref(), &dir);
+        assert_eq!(result.unwrap_err(), "Bundle signature does not match");
+
+        // Modified artifact
+        let text = String::from_utf8(bytes.clone()).unwrap();
+        let tampered = text.replace("helloworld-core", "helloworld-evil");
+        let result = read_bundle(&mut tampered.as_bytes(), key.public_key().as_ref(), &dir);
+        assert_eq!(result.unwrap_err(), "Bundle signature does not match");
+
+        // Modified image archive of the same size
+        let mut corrupted = bytes.clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 0xff;
+        let result = read_bundle(&mut corrupted.as_slice(), key.public_key().as_ref(), &dir);
+        assert!(result.unwrap_err().contains("Digest of image"));
+
+        // Truncated and extended files
+        let result = read_bundle(
+            &mut &bytes[..bytes.len() - 1],
+            key.public_key().as_ref(),
+            &dir,
+        );
+        assert!(result.unwrap_err().contains("truncated"));
+        let mut extended = bytes.clone();
+        extended.push(b'x');
+        let result = read_bundle(&mut extended.as_slice(), key.public_key().as_ref(), &dir);
+        assert!(result.is_err());
+
+        let result = read_bundle(&mut "garbage\n".as_bytes(), key.public_key().as_ref(), &dir);
+        assert_eq!(result.unwrap_err(), "Not a Pullpiri bundle");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
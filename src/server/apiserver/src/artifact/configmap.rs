@@ -0,0 +1,127 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! ConfigMap artifacts injected into the environment of models
+//!
+//! A container of a Model references a ConfigMap of its namespace with
+//! `envFrom.configMapRef`, and the values are written into its `env` when the
+//! pods of a package are saved. A changed ConfigMap saves the pods of every
+//! package using it again and restarts the models of its running scenarios.
+
+use super::{data, KIND_MODEL, KIND_PACKAGE};
+use common::logd;
+use common::spec::artifact::{Artifact, Model, Package, Scenario};
+use common::spec::namespace;
+use common::statemanager::ScenarioState;
+
+/// Qualified name and yaml of the packages with a model using a ConfigMap
+async fn packages_using(name: &str) -> common::Result<Vec<(String, String)>> {
+    let (ns, config_name) = namespace::split_qualified(name);
+    let mut packages = Vec::new();
+    let stored = data::read_artifacts_of_kind(ns, KIND_PACKAGE).await?;
+    for (package_name, package_str) in stored {
+        let package: Package = match serde_yaml::from_str(&package_str) {
+            Ok(package) => package,
+            Err(_) => continue,
+        };
+        for model_info in package.get_models() {
+            let key = namespace::artifact_key(ns, KIND_MODEL, &model_info.get_name());
+            let Ok(model_str) = data::read_from_etcd(&key).await else {
+                continue;
+            };
+            let uses = serde_yaml::from_str::<Model>(&model_str)
+                .map(|model| {
+                    model
+                        .get_podspec()
+                        .get_config_map_refs()
+                        .iter()
+                        .any(|r| r == config_name)
+                })
+                .unwrap_or(false);
+            if uses {
+                packages.push((package_name, package_str));
+                break;
+            }
+        }
+    }
+    Ok(packages)
+}
+
+/// Qualified names of the scenarios of a package whose models run
+async fn running_scenarios(package_name: &str) -> common::Result<Vec<String>> {
+    let (ns, package_name) = namespace::split_qualified(package_name);
+    let mut running = Vec::new();
+    let stored = data::read_all_scenario_from_etcd().await?;
+    for yaml in stored {
+        let Ok(scenario) = serde_yaml::from_str::<Scenario>(&yaml) else {
+            continue;
+        };
+        if scenario.get_namespace() != ns
+            || scenario.get_targets() != package_name
+            || !crate::reconcile::launches_workload(&scenario.get_actions())
+        {
+            continue;
+        }
+        let name = scenario.get_qualified_name();
+        let state = common::etcd::get(&format!("/scenario/{}/state", name))
+            .await
+            .ok()
+            .and_then(|value| ScenarioState::from_str_name(&value));
+        if state.is_some_and(crate::reconcile::is_playing) {
+            running.push(name);
+        }
+    }
+    Ok(running)
+}
+
+/// Save the pods using a ConfigMap again
+///
+/// ### Parameters
+/// * `name: &str` - qualified name of the ConfigMap
+/// ### Returns
+/// * `Vec<String>` - running scenarios whose models must restart
+pub async fn refresh(name: &str) -> common::Result<Vec<String>> {
+    let mut scenarios = Vec::new();
+    let packages = packages_using(name).await?;
+    for (package_name, package_str) in packages {
+        super::save_pod_yaml_from_package(&package_str).await?;
+        scenarios.extend(running_scenarios(&package_name).await?);
+    }
+    scenarios.sort();
+    scenarios.dedup();
+    Ok(scenarios)
+}
+
+/// Save the pods using changed ConfigMaps and restart their running models
+///
+/// Failures are logged, the ConfigMaps are already stored.
+pub async fn restart_users(names: &[String]) {
+    for name in names {
+        let scenarios = match refresh(name).await {
+            Ok(scenarios) => scenarios,
+            Err(e) => {
+                logd!(4, "Failed to save pods using ConfigMap {}: {}", name, e);
+                continue;
+            }
+        };
+        for scenario in scenarios {
+            logd!(
+                3,
+                "ConfigMap {} changed, restarting models of {}",
+                name,
+                scenario
+            );
+            if let Err(e) = crate::grpc::sender::actioncontroller::restart_scenario(&scenario).await
+            {
+                logd!(
+                    4,
+                    "Failed to restart models of {}: {}",
+                    scenario,
+                    e.message()
+                );
+            }
+        }
+    }
+}
@@ -3,9 +3,103 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-//! Read/Write/Delete artifact data in etcd
+//! Read/Write/Delete artifact data in the artifact storage
+//!
+//! Functions keep their `etcd` names, the data goes to the backend selected
+//! in settings.yaml (see [`super::storage`]).
 
+use super::storage::storage;
 use common::logd;
+use common::spec::namespace;
+
+/// etcd key prefix of stored artifact versions
+///
+/// Versions of `<Kind>/<name>` are kept at `History/<Kind>/<name>/v<N>` and
+/// `History/<Kind>/<name>/latest` holds the number of the active version.
+/// They are not stored below `<Kind>/` so prefix reads of artifacts are unaffected.
+const HISTORY_PREFIX: &str = "History";
+
+/// etcd key prefix of the namespaces that hold artifacts
+///
+/// `Namespace/<namespace>` is written when the first artifact of a namespace
+/// is applied. The default namespace is not registered.
+const NAMESPACE_PREFIX: &str = namespace::REGISTRY_PREFIX;
+
+/// Stored versions of an artifact
+#[derive(Debug, Default, PartialEq, serde::Serialize)]
+pub struct VersionHistory {
+    /// Version that was applied or rolled back to last, 0 if none
+    pub latest: u64,
+    /// Stored version numbers in ascending order
+    pub versions: Vec<u64>,
+}
+
+/// Name and labels of a stored artifact
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ArtifactSummary {
+    pub name: String,
+    pub labels: std::collections::HashMap<String, String>,
+}
+
+/// Read name and labels of every stored artifact of a kind in a namespace
+///
+/// ### Parameters
+/// * `namespace: &str` - namespace of the artifacts
+/// * `kind: &str` - kind of the artifacts, e.g. `Scenario`
+/// ### Return
+/// * `Result<Vec<ArtifactSummary>>` - `Ok(_)` contains one entry per artifact
+pub async fn read_all_of_kind(namespace: &str, kind: &str) -> common::Result<Vec<ArtifactSummary>> {
+    let prefix = namespace::kind_prefix(namespace, kind);
+    let entries = storage().get_all_with_prefix(&prefix).await?;
+
+    Ok(entries
+        .into_iter()
+        .filter_map(|(key, value)| artifact_summary(&prefix, &key, &value))
+        .collect())
+}
+
+/// Read qualified name and yaml string of every stored artifact of a kind
+/// in a namespace
+///
+/// ### Parameters
+/// * `namespace: &str` - namespace of the artifacts
+/// * `kind: &str` - kind of the artifacts, e.g. `Scenario`
+/// ### Return
+/// * `Result<Vec<(String, String)>>` - `Ok(_)` contains one entry per artifact
+pub async fn read_artifacts_of_kind(
+    namespace: &str,
+    kind: &str,
+) -> common::Result<Vec<(String, String)>> {
+    let prefix = namespace::kind_prefix(namespace, kind);
+    let entries = storage().get_all_with_prefix(&prefix).await?;
+
+    Ok(entries
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let name = key.strip_prefix(&prefix)?;
+            (!name.is_empty() && !name.contains('/'))
+                .then(|| (namespace::qualified_name(namespace, name), value))
+        })
+        .collect())
+}
+
+/// Summary of the artifact stored at `key`, `None` for keys below an artifact
+fn artifact_summary(prefix: &str, key: &str, artifact_str: &str) -> Option<ArtifactSummary> {
+    let name = key.strip_prefix(prefix)?;
+    if name.is_empty() || name.contains('/') {
+        return None;
+    }
+    let labels = serde_yaml::from_str::<serde_yaml::Value>(artifact_str)
+        .ok()
+        .and_then(|value| value.get("metadata")?.get("labels").cloned())
+        .and_then(|labels| serde_yaml::from_value(labels).ok())
+        .unwrap_or_default();
+
+    Some(ArtifactSummary {
+        name: name.to_string(),
+        labels,
+    })
+}
 
 /// Read yaml string of artifacts from etcd
 ///
@@ -13,9 +107,8 @@ use common::logd;
 /// * `artifact_name: &str` - name of the newly released artifact
 /// ### Return
 /// * `Result<(String)>` - `Ok()` contains yaml string if success
-#[allow(dead_code)]
 pub async fn read_from_etcd(artifact_name: &str) -> common::Result<String> {
-    let raw = common::etcd::get(artifact_name).await?;
+    let raw = storage().get(artifact_name).await?;
     Ok(raw)
 }
 
@@ -24,14 +117,95 @@ pub async fn read_from_etcd(artifact_name: &str) -> common::Result<String> {
 /// ### Parameters
 /// * None
 /// ### Return
-/// * `Result<Vec<String>>` - `Ok(_)` contains scenario yaml string vector
+/// * `Result<Vec<String>>` - `Ok(_)` contains scenario yaml string vector of
+///   all namespaces
 pub async fn read_all_scenario_from_etcd() -> common::Result<Vec<String>> {
-    let kv_scenario = common::etcd::get_all_with_prefix("Scenario").await?;
-    let values = kv_scenario.into_iter().map(|kv| kv.1).collect();
+    let namespaces = read_namespaces().await?;
+    let mut values = Vec::new();
+    for namespace in namespaces {
+        let prefix = namespace::kind_prefix(&namespace, "Scenario");
+        let kv_scenario = storage().get_all_with_prefix(&prefix).await?;
+        values.extend(kv_scenario.into_iter().map(|kv| kv.1));
+    }
 
     Ok(values)
 }
 
+/// Read the namespaces that hold artifacts
+///
+/// ### Parameters
+/// * None
+/// ### Return
+/// * `Result<Vec<String>>` - `Ok(_)` contains the default namespace followed
+///   by the registered namespaces
+pub async fn read_namespaces() -> common::Result<Vec<String>> {
+    let prefix = format!("{}/", NAMESPACE_PREFIX);
+    let kvs = storage().get_all_with_prefix(&prefix).await?;
+    let mut namespaces = vec![namespace::DEFAULT_NAMESPACE.to_string()];
+    namespaces.extend(
+        kvs.into_iter()
+            .filter_map(|(key, _)| key.strip_prefix(&prefix).map(str::to_string)),
+    );
+    Ok(namespaces)
+}
+
+/// etcd entry that registers a namespace, `None` for the default namespace
+pub fn namespace_entry(namespace: &str) -> Option<(String, String)> {
+    (!namespace::is_default(namespace))
+        .then(|| (format!("{}/{}", NAMESPACE_PREFIX, namespace), String::new()))
+}
+
+/// Register the namespace of an artifact
+///
+/// ### Parameters
+/// * `namespace: &str` - namespace of the artifact
+/// ### Return
+/// * `Result<()>` - `Ok` if success, `Err` otherwise
+pub async fn register_namespace(namespace: &str) -> common::Result<()> {
+    if let Some((key, value)) = namespace_entry(namespace) {
+        storage().put(&key, &value).await?;
+    }
+    Ok(())
+}
+
+/// How an artifact differs from the one stored at its key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// Nothing is stored at the key
+    Created,
+    /// The stored artifact has other content
+    Updated,
+    /// The stored artifact has the same content
+    Unchanged,
+}
+
+/// Compare yaml string of an artifact with the one stored at its key
+///
+/// ### Parameters
+/// * `key: &str, artifact_str: &str` - etcd key and yaml string of the artifact
+/// ### Return
+/// * `Change` - `Created` if nothing can be read at the key
+/// ### Description
+/// The yaml strings are compared as parsed yaml, so the order of mapping
+/// keys and the formatting do not matter.
+pub async fn compare_with_stored(key: &str, artifact_str: &str) -> Change {
+    match storage().get(key).await {
+        Ok(stored) if same_content(&stored, artifact_str) => Change::Unchanged,
+        Ok(_) => Change::Updated,
+        Err(_) => Change::Created,
+    }
+}
+
+fn same_content(stored: &str, artifact_str: &str) -> bool {
+    match (
+        serde_yaml::from_str::<serde_yaml::Value>(stored),
+        serde_yaml::from_str::<serde_yaml::Value>(artifact_str),
+    ) {
+        (Ok(stored), Ok(artifact)) => stored == artifact,
+        _ => stored == artifact_str,
+    }
+}
+
 /// Write yaml string of artifacts to etcd
 ///
 /// ### Parameters
@@ -42,7 +216,7 @@ pub async fn write_to_etcd(key: &str, artifact_str: &str) -> common::Result<()>
     use std::time::Instant;
     let start = Instant::now();
 
-    let result = common::etcd::put(key, artifact_str).await;
+    let result = storage().put(key, artifact_str).await;
     let elapsed = start.elapsed();
 
     logd!(1, "write_to_etcd: elapsed = {:?}", elapsed);
@@ -58,7 +232,114 @@ pub async fn write_to_etcd(key: &str, artifact_str: &str) -> common::Result<()>
 /// ### Return
 /// * `Result<()>` - `Ok` if success, `Err` otherwise
 pub async fn delete_at_etcd(key: &str) -> common::Result<()> {
-    common::etcd::delete(key).await?;
+    storage().delete(key).await?;
+    Ok(())
+}
+
+fn history_prefix(key: &str) -> String {
+    format!("{}/{}/", HISTORY_PREFIX, key)
+}
+
+/// Read the version history of an artifact
+///
+/// ### Parameters
+/// * `key: &str` - artifact key such as `Scenario/helloworld`
+/// ### Return
+/// * `Result<VersionHistory>` - `Ok(_)` contains stored version numbers
+pub async fn read_versions(key: &str) -> common::Result<VersionHistory> {
+    let prefix = history_prefix(key);
+    let kvs = storage().get_all_with_prefix(&prefix).await?;
+    Ok(parse_versions(&prefix, &kvs))
+}
+
+fn parse_versions(prefix: &str, kvs: &[(String, String)]) -> VersionHistory {
+    let mut history = VersionHistory::default();
+    for (key, value) in kvs {
+        match key.strip_prefix(prefix) {
+            Some("latest") => history.latest = value.parse().unwrap_or(0),
+            Some(version) => {
+                if let Some(n) = version.strip_prefix('v').and_then(|n| n.parse().ok()) {
+                    history.versions.push(n);
+                }
+            }
+            None => {}
+        }
+    }
+    history.versions.sort_unstable();
+    history
+}
+
+/// Read yaml string of one stored artifact version
+///
+/// ### Parameters
+/// * `key: &str, version: u64` - artifact key and version number
+/// ### Return
+/// * `Result<String>` - `Ok()` contains yaml string if success
+pub async fn read_version(key: &str, version: u64) -> common::Result<String> {
+    let raw = storage()
+        .get(&format!("{}v{}", history_prefix(key), version))
+        .await?;
+    Ok(raw)
+}
+
+/// Store yaml string of an artifact as a new version
+///
+/// ### Parameters
+/// * `key: &str, artifact_str: &str` - artifact key and yaml string
+/// ### Return
+/// * `Result<u64>` - `Ok()` contains the version number of the yaml string
+/// ### Description
+/// Re-applying the same yaml string as the latest version does not add a version.
+pub async fn write_version(key: &str, artifact_str: &str) -> common::Result<u64> {
+    let (version, entries) = version_entries(key, artifact_str).await?;
+    for (entry_key, value) in entries {
+        storage().put(&entry_key, &value).await?;
+    }
+    Ok(version)
+}
+
+/// etcd entries that store yaml string of an artifact as a new version
+///
+/// ### Parameters
+/// * `key: &str, artifact_str: &str` - artifact key and yaml string
+/// ### Return
+/// * `Result<(u64, Vec<(String, String)>)>` - version number and the entries to write
+/// ### Description
+/// The entries are empty if the yaml string equals the latest version.
+pub async fn version_entries(
+    key: &str,
+    artifact_str: &str,
+) -> common::Result<(u64, Vec<(String, String)>)> {
+    let history = read_versions(key).await?;
+    if history.latest != 0 {
+        if let Ok(latest) = read_version(key, history.latest).await {
+            if latest == artifact_str {
+                return Ok((history.latest, Vec::new()));
+            }
+        }
+    }
+
+    let version = history.versions.last().copied().unwrap_or(0) + 1;
+    let entries = vec![
+        (
+            format!("{}v{}", history_prefix(key), version),
+            artifact_str.to_string(),
+        ),
+        (
+            format!("{}latest", history_prefix(key)),
+            version.to_string(),
+        ),
+    ];
+    Ok((version, entries))
+}
+
+/// Mark a stored version as the active version of an artifact
+///
+/// ### Parameters
+/// * `key: &str, version: u64` - artifact key and version number
+pub async fn set_latest_version(key: &str, version: u64) -> common::Result<()> {
+    let latest_key = format!("{}latest", history_prefix(key));
+    storage().put(&latest_key, &version.to_string()).await?;
     Ok(())
 }
 
@@ -216,4 +497,67 @@ spec:
             "Expected delete_at_etcd with empty key to fail but got Ok"
         );
     }
+
+    #[test]
+    fn test_parse_versions() {
+        let prefix = history_prefix("Scenario/helloworld");
+        let kvs = vec![
+            (format!("{}v10", prefix), "yaml".to_string()),
+            (format!("{}latest", prefix), "2".to_string()),
+            (format!("{}v2", prefix), "yaml".to_string()),
+            (format!("{}vx", prefix), "yaml".to_string()),
+            ("Scenario/helloworld".to_string(), "yaml".to_string()),
+        ];
+
+        let history = parse_versions(&prefix, &kvs);
+        assert_eq!(history.latest, 2);
+        assert_eq!(history.versions, vec![2, 10]);
+    }
+
+    #[test]
+    fn test_artifact_summary_reads_labels() {
+        let scenario = TEST_YAML.split("---").next().unwrap();
+        let summary = artifact_summary("Scenario/", "Scenario/helloworld", scenario).unwrap();
+        assert_eq!(summary.name, "helloworld");
+        assert!(summary.labels.is_empty());
+
+        let model = TEST_YAML.split("---").nth(2).unwrap();
+        let summary = artifact_summary("Model/", "Model/helloworld-core", model).unwrap();
+        assert_eq!(
+            summary.labels.get("app"),
+            Some(&"helloworld-core".to_string())
+        );
+
+        assert!(artifact_summary("Model/", "Model/helloworld-core/state", model).is_none());
+        assert!(artifact_summary("Model/", "Package/helloworld", model).is_none());
+    }
+
+    #[test]
+    fn test_namespace_entry() {
+        assert_eq!(namespace_entry("default"), None);
+        assert_eq!(namespace_entry(""), None);
+        assert_eq!(
+            namespace_entry("team-a"),
+            Some(("Namespace/team-a".to_string(), String::new()))
+        );
+    }
+
+    #[test]
+    fn test_same_content_ignores_formatting() {
+        let scenario = TEST_YAML.split("---").next().unwrap();
+        let reordered = r#"
+kind: Scenario
+apiVersion: v1
+spec:
+  target: helloworld
+  action: update
+  condition:
+metadata: { name: helloworld }
+"#;
+        assert!(same_content(scenario, reordered));
+        assert!(!same_content(
+            scenario,
+            &reordered.replace("action: update", "action: launch")
+        ));
+    }
 }
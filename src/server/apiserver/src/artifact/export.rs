@@ -0,0 +1,232 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Export of packages as Kubernetes manifests
+//!
+//! Every model of a package becomes a Deployment, or a bare Pod, named after
+//! the model. Volumes, secrets and placement of the package are kept; the
+//! network of a model is attached by the Multus `k8s.v1.cni.cncf.io/networks`
+//! annotation.
+
+use super::{load_model_with_resources, KIND_PACKAGE};
+use common::spec::artifact::{Artifact, Model, Package};
+use common::spec::k8s::{manifest, Deployment, Pod};
+use std::collections::HashMap;
+
+/// Only supported export format
+pub const FORMAT_K8S: &str = "k8s";
+
+/// Pod annotation of the Multus CNI listing the networks of a pod
+const NETWORKS_ANNOTATION: &str = "k8s.v1.cni.cncf.io/networks";
+/// Label of every exported object with the package it came from
+const PACKAGE_LABEL: &str = "pullpiri.io/package";
+
+/// Kubernetes object a model is exported as
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Workload {
+    #[default]
+    Deployment,
+    Pod,
+}
+
+/// Export a stored package
+///
+/// ### Parameters
+/// * `name: &str` - name of the package, qualified by its namespace
+/// * `format: &str` - output format, only `k8s`
+/// * `workload: Workload` - object each model is exported as
+/// ### Returns
+/// * `Result<String>` - manifests as a multi-document YAML string
+pub async fn export_package(
+    name: &str,
+    format: &str,
+    workload: Workload,
+) -> common::Result<String> {
+    if format != FORMAT_K8S {
+        return Err(format!(
+            "Unsupported export format '{}', expected '{}'",
+            format, FORMAT_K8S
+        )
+        .into());
+    }
+
+    let package_str = super::storage::storage()
+        .get(&common::spec::namespace::qualified_key(KIND_PACKAGE, name))
+        .await?;
+    let package: Package = serde_yaml::from_str(&package_str)?;
+    let mut models = Vec::new();
+    for model_info in package.get_models() {
+        models.push(load_model_with_resources(model_info, &package.get_namespace()).await?);
+    }
+
+    to_k8s(&package, models, workload)
+}
+
+/// Kubernetes manifests of a package and its loaded models
+///
+/// `models` are in the order of the package models, with their volumes and
+/// secrets applied.
+pub fn to_k8s(package: &Package, models: Vec<Model>, workload: Workload) -> common::Result<String> {
+    let package_name = package.get_name();
+    let mut documents = Vec::new();
+
+    for (model_info, model) in package.get_models().iter().zip(models) {
+        let name = k8s_name(&model.get_name());
+        let labels = HashMap::from([
+            ("app".to_string(), name.clone()),
+            (PACKAGE_LABEL.to_string(), package_name.clone()),
+        ]);
+        let annotations = model_info
+            .get_resources()
+            .get_network()
+            .map(|network| HashMap::from([(NETWORKS_ANNOTATION.to_string(), network)]));
+
+        let mut document = match workload {
+            Workload::Deployment => {
+                let mut deployment = Deployment::new(&name, labels, model.get_podspec());
+                if let Some(annotations) = annotations {
+                    deployment.set_pod_annotations(annotations);
+                }
+                manifest::to_manifest(&deployment)?
+            }
+            Workload::Pod => {
+                let mut pod = Pod::new(&name, model.get_podspec());
+                pod.set_labels(labels);
+                if let Some(annotations) = annotations {
+                    pod.set_annotations(annotations);
+                }
+                manifest::to_manifest(&pod)?
+            }
+        };
+        manifest::set_placement(&mut document, model_info)?;
+        documents.push(serde_yaml::to_string(&document)?);
+    }
+
+    Ok(documents.join("---\n"))
+}
+
+/// Object name valid in Kubernetes, a lowercase DNS-1123 subdomain
+fn k8s_name(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>()
+        .trim_matches('-')
+        .to_string()
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACKAGE_YAML: &str = r#"
+apiVersion: v1
+kind: Package
+metadata:
+  name: helloworld
+spec:
+  pattern:
+    - type: plain
+  models:
+    - name: helloworld_core
+      node: HPC
+      resources:
+        volume: helloworld-volume
+        network: helloworld-network
+    - name: helloworld-agent
+      node: auto
+      nodeSelector:
+        zone: front
+      tolerations:
+        - key: dedicated
+          value: adas
+          effect: NoSchedule
+      resources: {}
+"#;
+
+    const CORE_MODEL_YAML: &str = r#"
+apiVersion: v1
+kind: Model
+metadata:
+  name: helloworld_core
+spec:
+  hostNetwork: true
+  containers:
+    - name: helloworld
+      image: quay.io/podman/hello:latest
+      volumeMounts:
+        - name: data
+          mountPath: /data
+      resources:
+        requests:
+          memory: 64Mi
+          cpu: 100m
+  volumes:
+    - name: data
+      hostPath:
+        path: /var/lib/helloworld
+  probeConfig:
+    liveness:
+      tcp:
+        port: 8080
+      initialDelaySeconds: 5
+  terminationGracePeriodSeconds: 0
+"#;
+
+    const AGENT_MODEL_YAML: &str = r#"
+apiVersion: v1
+kind: Model
+metadata:
+  name: helloworld-agent
+spec:
+  containers:
+    - name: agent
+      image: quay.io/pullpiri/agent:1.0
+      args: ["--verbose"]
+  restartPolicy: Always
+"#;
+
+    fn export(workload: Workload) -> String {
+        let package: Package = serde_yaml::from_str(PACKAGE_YAML).unwrap();
+        let models = vec![
+            serde_yaml::from_str(CORE_MODEL_YAML).unwrap(),
+            serde_yaml::from_str(AGENT_MODEL_YAML).unwrap(),
+        ];
+        to_k8s(&package, models, workload).unwrap()
+    }
+
+    #[test]
+    fn test_export_deployments_matches_golden_file() {
+        let golden = include_str!("../../tests/golden/helloworld_deployment.yaml");
+        assert_eq!(export(Workload::Deployment), golden);
+    }
+
+    #[test]
+    fn test_export_pods_matches_golden_file() {
+        let golden = include_str!("../../tests/golden/helloworld_pod.yaml");
+        assert_eq!(export(Workload::Pod), golden);
+    }
+
+    #[test]
+    fn test_k8s_name() {
+        assert_eq!(k8s_name("helloworld_core"), "helloworld-core");
+        assert_eq!(k8s_name("Front.Camera"), "front.camera");
+        assert_eq!(k8s_name("_model_"), "model");
+    }
+
+    #[tokio::test]
+    async fn test_export_package_rejects_unknown_format() {
+        let result = export_package("helloworld", "compose", Workload::Pod).await;
+        assert!(result.unwrap_err().to_string().contains("Unsupported"));
+    }
+}
@@ -0,0 +1,175 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Version history, diff and rollback of applied artifacts
+
+use super::data::{self, VersionHistory};
+use super::{KIND_CONFIG_MAP, KIND_PACKAGE, KIND_SCENARIO};
+use common::logd;
+use common::spec::artifact::{Artifact, Scenario};
+use common::spec::namespace;
+
+/// Line diff between two versions of an artifact
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct VersionDiff {
+    pub from: u64,
+    pub to: u64,
+    /// Lines prefixed with ` ` (unchanged), `-` (removed) or `+` (added)
+    pub lines: Vec<String>,
+}
+
+fn artifact_key(kind: &str, name: &str) -> String {
+    namespace::qualified_key(kind, name)
+}
+
+/// Read the stored versions of an artifact
+///
+/// ### Parameters
+/// * `kind: &str, name: &str` - kind and qualified name of the artifact
+pub async fn versions(kind: &str, name: &str) -> common::Result<VersionHistory> {
+    data::read_versions(&artifact_key(kind, name)).await
+}
+
+/// Compare two stored versions of an artifact
+///
+/// ### Parameters
+/// * `kind: &str, name: &str` - kind and qualified name of the artifact
+/// * `from: u64, to: u64` - versions to compare
+pub async fn diff(kind: &str, name: &str, from: u64, to: u64) -> common::Result<VersionDiff> {
+    let key = artifact_key(kind, name);
+    let old = data::read_version(&key, from).await?;
+    let new = data::read_version(&key, to).await?;
+
+    Ok(VersionDiff {
+        from,
+        to,
+        lines: diff_lines(&old, &new),
+    })
+}
+
+/// Re-activate a stored version of an artifact
+///
+/// ### Parameters
+/// * `kind: &str, name: &str` - kind and qualified name of the artifact
+/// * `version: u64` - version to re-activate
+/// ### Returns
+/// * `Result<Vec<String>>` - names of the scenarios to re-deploy
+/// ### Description
+/// The stored version is written back to the artifact key and becomes the
+/// latest version. Rolling back a package also regenerates its pod yaml,
+/// rolling back a ConfigMap the pods using it and restarts their models.
+pub async fn rollback(kind: &str, name: &str, version: u64) -> common::Result<Vec<String>> {
+    let key = artifact_key(kind, name);
+    let artifact_str = data::read_version(&key, version).await?;
+
+    data::write_to_etcd(&key, &artifact_str).await?;
+    data::set_latest_version(&key, version).await?;
+    logd!(2, "Rolled back {} to version {}", key, version);
+
+    match kind {
+        KIND_SCENARIO => Ok(vec![name.to_string()]),
+        KIND_PACKAGE => {
+            super::save_pod_yaml_from_package(&artifact_str).await?;
+            scenarios_targeting(name).await
+        }
+        KIND_CONFIG_MAP => {
+            super::configmap::restart_users(&[name.to_string()]).await;
+            Ok(vec![])
+        }
+        _ => Ok(vec![]),
+    }
+}
+
+/// Qualified names of the applied scenarios whose target is the given package
+async fn scenarios_targeting(package_name: &str) -> common::Result<Vec<String>> {
+    let (namespace, package_name) = namespace::split_qualified(package_name);
+    let scenarios = data::read_all_scenario_from_etcd().await?;
+    Ok(scenarios
+        .iter()
+        .filter_map(|yaml| serde_yaml::from_str::<Scenario>(yaml).ok())
+        .filter(|scenario| {
+            scenario.get_namespace() == namespace && scenario.get_targets() == package_name
+        })
+        .map(|scenario| scenario.get_qualified_name())
+        .collect())
+}
+
+/// Line based diff of two yaml strings using their longest common subsequence
+pub fn diff_lines(old: &str, new: &str) -> Vec<String> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] is the length of the common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for (i, old_line) in old.iter().enumerate().rev() {
+        for (j, new_line) in new.iter().enumerate().rev() {
+            lcs[i][j] = if old_line == new_line {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            lines.push(format!(" {}", old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(format!("-{}", old[i]));
+            i += 1;
+        } else {
+            lines.push(format!("+{}", new[j]));
+            j += 1;
+        }
+    }
+    lines.extend(old[i..].iter().map(|line| format!("-{}", line)));
+    lines.extend(new[j..].iter().map(|line| format!("+{}", line)));
+    lines
+}
+
+//UNIT TEST CASES
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_lines() {
+        let old = "kind: Scenario\nspec:\n  action: update\n  target: helloworld\n";
+        let new = "kind: Scenario\nspec:\n  action: launch\n  target: helloworld\n";
+
+        assert_eq!(
+            diff_lines(old, new),
+            vec![
+                " kind: Scenario",
+                " spec:",
+                "-  action: update",
+                "+  action: launch",
+                "   target: helloworld",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_lines_added_and_removed_tail() {
+        assert_eq!(diff_lines("a\nb", "a\nb\nc"), vec![" a", " b", "+c"]);
+        assert_eq!(diff_lines("a\nb", "a"), vec![" a", "-b"]);
+        assert_eq!(diff_lines("", "a"), vec!["+a"]);
+        assert!(diff_lines("same", "same")
+            .iter()
+            .all(|l| l.starts_with(' ')));
+    }
+
+    #[tokio::test]
+    async fn test_rollback_missing_version() {
+        // Fails whether or not etcd is available, the version was never stored
+        let result = rollback(KIND_SCENARIO, "unit-test-no-history", 999).await;
+        assert!(result.is_err());
+    }
+}
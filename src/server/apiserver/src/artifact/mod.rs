@@ -5,13 +5,24 @@
 
 //! Convert string-type artifacts to struct and access etcd
 
+pub mod bundle;
+pub mod configmap;
 pub mod data;
-
+pub mod export;
+pub mod history;
+pub mod secret;
+pub mod storage;
+pub mod template;
+pub mod transaction;
+pub mod validate;
+
+use common::error::ApiError;
 use common::logd;
 use common::spec::artifact::{
-    Artifact, Model, Network, Node, Package, Policy, Scenario, Schedule, Volume,
+    Artifact, ConfigMap, Model, Network, Node, Package, Policy, Scenario, Schedule, Secret, Volume,
 };
 use common::spec::k8s::Pod;
+use common::spec::namespace;
 
 // Artifact kind constants
 const KIND_SCENARIO: &str = "Scenario";
@@ -22,45 +33,82 @@ const KIND_NODE: &str = "Node";
 const KIND_MODEL: &str = "Model";
 const KIND_SCHEDULE: &str = "Schedule";
 const KIND_POLICY: &str = "Policy";
+const KIND_SECRET: &str = "Secret";
+const KIND_CONFIG_MAP: &str = "ConfigMap";
+
+/// Kinds whose stored artifacts can be listed
+const KINDS: [&str; 10] = [
+    KIND_SCENARIO,
+    KIND_PACKAGE,
+    KIND_VOLUME,
+    KIND_NETWORK,
+    KIND_NODE,
+    KIND_MODEL,
+    KIND_SCHEDULE,
+    KIND_POLICY,
+    KIND_SECRET,
+    KIND_CONFIG_MAP,
+];
+
+/// Kinds that apply to the whole cluster and have no namespace
+const CLUSTER_KINDS: [&str; 2] = [KIND_NODE, KIND_POLICY];
 
 // YAML document separator
 const YAML_SEPARATOR: &str = "---";
 
-/// Parse artifact kind and name from YAML value
+/// Parse artifact kind and name qualified by its namespace from YAML value
 fn parse_artifact_info(value: &serde_yaml::Value) -> Option<(String, String)> {
     let kind = value.get("kind")?.as_str()?;
 
     let name = match kind {
         KIND_SCENARIO => serde_yaml::from_value::<Scenario>(value.clone())
             .ok()?
-            .get_name(),
+            .get_qualified_name(),
         KIND_PACKAGE => serde_yaml::from_value::<Package>(value.clone())
             .ok()?
-            .get_name(),
+            .get_qualified_name(),
         KIND_VOLUME => serde_yaml::from_value::<Volume>(value.clone())
             .ok()?
-            .get_name(),
+            .get_qualified_name(),
         KIND_NETWORK => serde_yaml::from_value::<Network>(value.clone())
             .ok()?
-            .get_name(),
+            .get_qualified_name(),
         KIND_NODE => serde_yaml::from_value::<Node>(value.clone())
             .ok()?
-            .get_name(),
+            .get_qualified_name(),
         KIND_MODEL => serde_yaml::from_value::<Model>(value.clone())
             .ok()?
-            .get_name(),
+            .get_qualified_name(),
         KIND_SCHEDULE => serde_yaml::from_value::<Schedule>(value.clone())
             .ok()?
-            .get_name(),
+            .get_qualified_name(),
         KIND_POLICY => serde_yaml::from_value::<Policy>(value.clone())
             .ok()?
-            .get_name(),
+            .get_qualified_name(),
+        KIND_SECRET => serde_yaml::from_value::<Secret>(value.clone())
+            .ok()?
+            .get_qualified_name(),
+        KIND_CONFIG_MAP => serde_yaml::from_value::<ConfigMap>(value.clone())
+            .ok()?
+            .get_qualified_name(),
         _ => return None,
     };
 
     Some((kind.to_string(), name))
 }
 
+/// Check the namespace of an artifact given by kind and qualified name
+fn check_namespace(kind: &str, name: &str) -> Result<(), String> {
+    let namespace = namespace::split_qualified(name).0;
+    if CLUSTER_KINDS.contains(&kind) && !namespace::is_default(namespace) {
+        return Err(format!(
+            "{} applies to the whole cluster and cannot have a namespace",
+            kind
+        ));
+    }
+    namespace::validate(namespace)
+}
+
 /// Send initial state change notification to StateManager
 async fn notify_scenario_state(scenario_name: &str, target_state: &str) {
     let timestamp = std::time::SystemTime::now()
@@ -76,6 +124,8 @@ async fn notify_scenario_state(scenario_name: &str, target_state: &str) {
         transition_id: format!("apiserver-scenario-init-{}", timestamp),
         timestamp_ns: timestamp,
         source: "apiserver".to_string(),
+        asil_level: common::statemanager::AsilLevel::Unspecified as i32,
+        trace_id: common::trace::current_trace_id().unwrap_or_default(),
     };
 
     logd!(
@@ -102,13 +152,16 @@ async fn notify_scenario_state(scenario_name: &str, target_state: &str) {
     }
 }
 
-/// Process and store a single artifact document
-async fn process_artifact_document(doc: &str) -> common::Result<Option<(String, String)>> {
+/// Parse a single artifact document into its kind, qualified name and stored yaml string
+///
+/// Returns `None` for documents that are not a known artifact, and an error
+/// for artifacts with an invalid namespace.
+fn prepare_artifact_document(doc: &str) -> common::Result<Option<(String, String, String)>> {
     use std::time::Instant;
 
     let parse_start = Instant::now();
     let value: serde_yaml::Value = serde_yaml::from_str(doc)?;
-    let artifact_str = serde_yaml::to_string(&value)?;
+    let mut artifact_str = serde_yaml::to_string(&value)?;
     logd!(
         1,
         "process_artifact: YAML parse elapsed = {:?}",
@@ -122,15 +175,52 @@ async fn process_artifact_document(doc: &str) -> common::Result<Option<(String,
             return Ok(None);
         }
     };
+    check_namespace(&kind, &name)?;
+
+    // Secret values are never stored as plain text
+    if kind == KIND_SECRET {
+        let mut secret: Secret = serde_yaml::from_value(value)?;
+        secret::seal(&mut secret)?;
+        artifact_str = serde_yaml::to_string(&secret)?;
+    }
+
+    Ok(Some((kind, name, artifact_str)))
+}
+
+/// Process and store a single artifact document
+///
+/// Nothing is written if the artifact equals the stored one.
+/// ### Returns
+/// * `Option<(String, String, String, data::Change)>` - kind, qualified
+///   name and yaml of the artifact, and how it differs from the stored one
+async fn process_artifact_document(
+    doc: &str,
+) -> common::Result<Option<(String, String, String, data::Change)>> {
+    use std::time::Instant;
+
+    let Some((kind, name, artifact_str)) = prepare_artifact_document(doc)? else {
+        return Ok(None);
+    };
 
-    let key = format!("{}/{}", kind, name);
+    let key = namespace::qualified_key(&kind, &name);
+    let change = data::compare_with_stored(&key, &artifact_str).await;
+    if change == data::Change::Unchanged {
+        logd!(1, "process_artifact: {} is unchanged, nothing written", key);
+        if kind == KIND_SCENARIO {
+            notify_scenario_state(&name, "idle").await;
+        }
+        return Ok(Some((kind, name, artifact_str, change)));
+    }
 
     let etcd_start = Instant::now();
+    data::register_namespace(namespace::split_qualified(&name).0).await?;
     data::write_to_etcd(&key, &artifact_str).await?;
+    let version = data::write_version(&key, &artifact_str).await?;
     logd!(
         1,
-        "process_artifact: etcd write elapsed for {} = {:?}",
+        "process_artifact: etcd write elapsed for {} (version {}) = {:?}",
         key,
+        version,
         etcd_start.elapsed()
     );
 
@@ -138,7 +228,46 @@ async fn process_artifact_document(doc: &str) -> common::Result<Option<(String,
         notify_scenario_state(&name, "idle").await;
     }
 
-    Ok(Some((kind, artifact_str)))
+    Ok(Some((kind, name, artifact_str, change)))
+}
+
+/// List name and labels of the stored artifacts of a kind in a namespace
+///
+/// ### Parametets
+/// * `kind: &str` - artifact kind, e.g. `Scenario`
+/// * `namespace: &str` - namespace of the artifacts
+/// ### Returns
+/// * `Result<Vec<ArtifactSummary>>` - the artifacts, or an error for unknown kinds
+pub async fn list(kind: &str, namespace: &str) -> common::Result<Vec<data::ArtifactSummary>> {
+    if !KINDS.contains(&kind) {
+        return Err(format!(
+            "Unknown artifact kind '{}', expected one of: {}",
+            kind,
+            KINDS.join(", ")
+        )
+        .into());
+    }
+    namespace::validate(namespace)?;
+    data::read_all_of_kind(namespace, kind).await
+}
+
+/// Read the yaml of a stored artifact
+///
+/// ### Parametets
+/// * `kind: &str, name: &str` - kind and name of the artifact, qualified by
+///   its namespace
+/// ### Returns
+/// * `Result<String>` - the yaml, or an error for unknown kinds and missing artifacts
+pub async fn get(kind: &str, name: &str) -> common::Result<String> {
+    if !KINDS.contains(&kind) {
+        return Err(format!(
+            "Unknown artifact kind '{}', expected one of: {}",
+            kind,
+            KINDS.join(", ")
+        )
+        .into());
+    }
+    data::read_from_etcd(&namespace::qualified_key(kind, name)).await
 }
 
 /// Apply downloaded artifact to etcd
@@ -156,12 +285,18 @@ pub async fn apply(body: &str) -> common::Result<String> {
     let docs: Vec<&str> = body.split(YAML_SEPARATOR).collect();
     let mut scenario_str = String::new();
     let mut package_str = String::new();
+    let mut changed_config_maps = Vec::new();
+
+    check_dependency_cycle(&docs).await?;
 
     for doc in docs {
-        if let Some((kind, artifact_str)) = process_artifact_document(doc).await? {
+        if let Some((kind, name, artifact_str, change)) = process_artifact_document(doc).await? {
             match kind.as_str() {
                 KIND_SCENARIO => scenario_str = artifact_str,
                 KIND_PACKAGE => package_str = artifact_str,
+                KIND_CONFIG_MAP if change == data::Change::Updated => {
+                    changed_config_maps.push(name)
+                }
                 _ => continue,
             }
         }
@@ -175,10 +310,33 @@ pub async fn apply(body: &str) -> common::Result<String> {
         Err("There is not any package in yaml string".into())
     } else {
         save_pod_yaml_from_package(&package_str).await?;
+        configmap::restart_users(&changed_config_maps).await;
         Ok(scenario_str)
     }
 }
 
+/// Reject scenarios whose `dependsOn` would close a cycle
+///
+/// ### Parametets
+/// * `docs: &[&str]` - yaml documents of the artifact
+/// ### Description
+/// Runs before anything is written, so a rejected artifact leaves etcd
+/// unchanged.
+async fn check_dependency_cycle(docs: &[&str]) -> common::Result<()> {
+    let scenarios: Vec<Scenario> = docs
+        .iter()
+        .filter_map(|doc| serde_yaml::from_str::<serde_yaml::Value>(doc).ok())
+        .filter(|value| value.get("kind").and_then(|k| k.as_str()) == Some(KIND_SCENARIO))
+        .filter_map(|value| serde_yaml::from_value(value).ok())
+        .collect();
+    let scenarios: Vec<&Scenario> = scenarios.iter().collect();
+
+    match validate::dependency_cycle(&scenarios).await {
+        Some(cycle) => Err(format!("Scenario dependency cycle: {}", cycle.join(" -> ")).into()),
+        None => Ok(()),
+    }
+}
+
 /// Delete downloaded artifact to etcd
 ///
 /// ### Parametets
@@ -186,36 +344,62 @@ pub async fn apply(body: &str) -> common::Result<String> {
 /// ### Returns
 /// * `Result(String)` - scenario yaml in downloaded artifact
 /// ### Description
-/// Delete scenario yaml only, because other scenario can use a package with same name
+/// Delete scenario yaml and any secrets in the artifact.
+/// Packages are kept, because other scenario can use a package with same name
 pub async fn withdraw(body: &str) -> common::Result<String> {
     let docs: Vec<&str> = body.split(YAML_SEPARATOR).collect();
+    let mut scenario_str = None;
 
     for doc in docs {
         let value: serde_yaml::Value = serde_yaml::from_str(doc)?;
 
         if let Some((kind, name)) = parse_artifact_info(&value) {
-            if kind == KIND_SCENARIO {
-                let artifact_str = serde_yaml::to_string(&value)?;
-                let key = format!("{}/{}", KIND_SCENARIO, name);
-                data::delete_at_etcd(&key).await?;
-                return Ok(artifact_str);
+            match kind.as_str() {
+                KIND_SCENARIO if scenario_str.is_none() => {
+                    let artifact_str = serde_yaml::to_string(&value)?;
+                    let key = namespace::qualified_key(KIND_SCENARIO, &name);
+                    data::delete_at_etcd(&key).await?;
+                    scenario_str = Some(artifact_str);
+                }
+                KIND_SECRET => {
+                    let key = namespace::qualified_key(KIND_SECRET, &name);
+                    if let Err(e) = data::delete_at_etcd(&key).await {
+                        logd!(4, "withdraw: failed to delete {}: {}", key, e);
+                    }
+                }
+                _ => continue,
             }
         }
     }
 
-    Err("There is not any scenario in yaml string".into())
+    scenario_str.ok_or_else(|| "There is not any scenario in yaml string".into())
 }
 
 /// Load model with optional volume and network resources
+///
+/// The model and its resources are looked up in the namespace of the package.
 async fn load_model_with_resources(
     model_info: &common::spec::artifact::package::ModelInfo,
+    namespace: &str,
 ) -> common::Result<Model> {
-    let model_str = common::etcd::get(&format!("{}/{}", KIND_MODEL, model_info.get_name())).await?;
+    let model_str = storage::storage()
+        .get(&namespace::artifact_key(
+            namespace,
+            KIND_MODEL,
+            &model_info.get_name(),
+        ))
+        .await?;
     let mut model: Model = serde_yaml::from_str(&model_str)?;
 
     // Load volume if specified
     if let Some(volume_name) = model_info.get_resources().get_volume() {
-        let volume_str = common::etcd::get(&format!("{}/{}", KIND_VOLUME, volume_name)).await?;
+        let volume_str = storage::storage()
+            .get(&namespace::artifact_key(
+                namespace,
+                KIND_VOLUME,
+                &volume_name,
+            ))
+            .await?;
         let volume: Volume = serde_yaml::from_str(&volume_str)?;
 
         if let Some(volume_spec) = volume.get_spec() {
@@ -228,35 +412,103 @@ async fn load_model_with_resources(
 
     // Load network if specified
     if let Some(network_name) = model_info.get_resources().get_network() {
-        let network_str = common::etcd::get(&format!("{}/{}", KIND_NETWORK, network_name)).await?;
-        let _network: Network = serde_yaml::from_str(&network_str)?;
-        // TODO: Apply network configuration
+        let network_str = storage::storage()
+            .get(&namespace::artifact_key(
+                namespace,
+                KIND_NETWORK,
+                &network_name,
+            ))
+            .await?;
+        let network: Network = serde_yaml::from_str(&network_str)?;
+        model.get_podspec_mut().attach_network(&network);
+    }
+
+    // Reference secret if specified, NodeAgent resolves the values when it
+    // creates the containers so they are never stored in plain text
+    if let Some(secret_name) = model_info.get_resources().get_secret() {
+        let secret_str = storage::storage()
+            .get(&namespace::artifact_key(
+                namespace,
+                KIND_SECRET,
+                &secret_name,
+            ))
+            .await?;
+        let secret: Secret = serde_yaml::from_str(&secret_str)?;
+        model
+            .get_podspec_mut()
+            .add_secret_ref(&secret.get_name(), secret.is_registry());
+    }
+
+    // Values of referenced config maps are written into the pod
+    for config_name in model.get_podspec().get_config_map_refs() {
+        let config_str = storage::storage()
+            .get(&namespace::artifact_key(
+                namespace,
+                KIND_CONFIG_MAP,
+                &config_name,
+            ))
+            .await?;
+        let config: ConfigMap = serde_yaml::from_str(&config_str)?;
+        model
+            .get_podspec_mut()
+            .inject_config_map(&config_name, config.get_data());
     }
 
     Ok(model)
 }
 
 /// Save Pod YAML for all models in a package
+///
+/// Pods are stored by model name in every namespace, so a model that is
+/// already deployed from another namespace is rejected. Pods equal to the
+/// stored ones are not written again.
 async fn save_pod_yaml_from_package(package_str: &str) -> common::Result<()> {
     let package: Package = serde_yaml::from_str(package_str)?;
+    let namespace = package.get_namespace();
     let mut models = Vec::new();
 
     for model_info in package.get_models() {
-        let model = load_model_with_resources(&model_info).await?;
+        let model = load_model_with_resources(model_info, &namespace).await?;
         models.push(model);
     }
 
-    let pods: Vec<Pod> = models.into_iter().map(Pod::from).collect();
+    let mut pods: Vec<Pod> = models.into_iter().map(Pod::from).collect();
+    for pod in &mut pods {
+        pod.set_namespace(&namespace);
+        check_pod_namespace(pod).await?;
+    }
 
     for pod in pods {
         let pod_yaml = serde_yaml::to_string(&pod)?;
         let key = format!("{}/{}", "Pod", pod.get_name());
-        data::write_to_etcd(&key, &pod_yaml).await?;
+        if data::compare_with_stored(&key, &pod_yaml).await != data::Change::Unchanged {
+            data::write_to_etcd(&key, &pod_yaml).await?;
+        }
     }
 
     Ok(())
 }
 
+/// Reject a pod whose name is taken by a pod of another namespace
+async fn check_pod_namespace(pod: &Pod) -> common::Result<()> {
+    let key = format!("{}/{}", "Pod", pod.get_name());
+    let Ok(stored) = storage::storage().get(&key).await else {
+        return Ok(());
+    };
+    let Ok(stored) = serde_yaml::from_str::<Pod>(&stored) else {
+        return Ok(());
+    };
+    if stored.get_namespace() != pod.get_namespace() {
+        return Err(ApiError::conflict(format!(
+            "Model '{}' is already deployed from namespace '{}'",
+            pod.get_name(),
+            stored.get_namespace()
+        ))
+        .into());
+    }
+    Ok(())
+}
+
 //UNIT TEST CASES
 
 #[cfg(test)]
@@ -421,6 +673,27 @@ spec:
         );
     }
 
+    /// Test parse_artifact_info() recognizes Secret artifacts
+    #[test]
+    fn test_parse_artifact_info_secret() {
+        let yaml = r#"
+apiVersion: v1
+kind: Secret
+metadata:
+  name: registry-cred
+spec:
+  type: kubernetes.io/dockerconfigjson
+  stringData:
+    .dockerconfigjson: "{}"
+"#;
+        let value: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(
+            parse_artifact_info(&value),
+            Some((KIND_SECRET.to_string(), "registry-cred".to_string()))
+        );
+    }
+
     // -- withdraw() tests --
 
     /// Test withdraw() with valid artifact YAML (Scenario present)
@@ -466,4 +739,14 @@ spec:
             "withdraw() unexpectedly succeeded with empty YAML"
         );
     }
+
+    /// Test list() with a kind that is not an artifact
+    #[tokio::test]
+    async fn test_list_unknown_kind() {
+        let result = list("Deployment", "default").await;
+
+        // Assert: should fail before reading etcd
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("Unknown artifact kind 'Deployment'"));
+    }
 }
@@ -0,0 +1,133 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Encode Secret artifact values before they are stored in etcd
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use common::error::ApiError;
+use common::spec::artifact::secret::{encrypt_value, encryption_key, ENCRYPTION_AES_256_GCM};
+use common::spec::artifact::{Artifact, Secret};
+use std::collections::HashMap;
+
+/// Normalize the values of a Secret before it is written to etcd
+///
+/// ### Parameters
+/// * `secret: &mut Secret` - Secret artifact parsed from the request body
+/// ### Description
+/// `stringData` values are base64 encoded and merged into `data`.
+/// When `PULLPIRI_SECRET_KEY` is set, every value is additionally
+/// encrypted with AES-256-GCM and stored as base64(nonce || ciphertext).
+pub fn seal(secret: &mut Secret) -> common::Result<()> {
+    if secret.get_spec().get_encryption().is_some() {
+        return Err(ApiError::conflict(format!(
+            "secret '{}' is already sealed",
+            secret.get_name()
+        ))
+        .into());
+    }
+
+    let mut plain: HashMap<String, Vec<u8>> = HashMap::new();
+    if let Some(data) = secret.get_spec().get_data() {
+        for (key, value) in data {
+            let decoded = STANDARD
+                .decode(value)
+                .map_err(|e| format!("secret data '{}' is not valid base64: {}", key, e))?;
+            plain.insert(key.clone(), decoded);
+        }
+    }
+    if let Some(string_data) = secret.get_spec().get_string_data() {
+        for (key, value) in string_data {
+            plain.insert(key.clone(), value.as_bytes().to_vec());
+        }
+    }
+
+    let (data, encryption) = match encryption_key()? {
+        Some(key) => (encrypt_values(&key, plain)?, Some(ENCRYPTION_AES_256_GCM)),
+        None => (
+            plain
+                .into_iter()
+                .map(|(k, v)| (k, STANDARD.encode(v)))
+                .collect(),
+            None,
+        ),
+    };
+
+    secret
+        .get_spec_mut()
+        .set_encoded_data(data, encryption.map(str::to_string));
+    Ok(())
+}
+
+fn encrypt_values(
+    key: &[u8],
+    plain: HashMap<String, Vec<u8>>,
+) -> common::Result<HashMap<String, String>> {
+    let mut sealed = HashMap::new();
+    for (name, value) in plain {
+        let value =
+            encrypt_value(key, &value).map_err(|e| format!("secret value '{}': {}", name, e))?;
+        sealed.insert(name, value);
+    }
+    Ok(sealed)
+}
+
+//UNIT TEST CASES
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::spec::artifact::secret::{decrypt_value, SECRET_KEY_ENV};
+
+    const SECRET_YAML: &str = r#"
+apiVersion: v1
+kind: Secret
+metadata:
+  name: app-env
+spec:
+  data:
+    user: YWRtaW4=
+  stringData:
+    password: s3cr3t
+"#;
+
+    #[test]
+    fn test_encrypt_and_decrypt_roundtrip() {
+        let key = [7u8; 32];
+        let plain = HashMap::from([("password".to_string(), b"s3cr3t".to_vec())]);
+
+        let sealed = encrypt_values(&key, plain).unwrap();
+        let value = sealed.get("password").unwrap();
+
+        assert_ne!(value, &STANDARD.encode("s3cr3t"));
+        assert_eq!(decrypt_value(&key, value).unwrap(), b"s3cr3t".to_vec());
+        assert!(decrypt_value(&[8u8; 32], value).is_err());
+    }
+
+    #[test]
+    fn test_seal_merges_string_data_as_base64() {
+        // Only exercise the plain path; the encrypted path is covered above
+        if std::env::var(SECRET_KEY_ENV).is_ok() {
+            return;
+        }
+        let mut secret: Secret = serde_yaml::from_str(SECRET_YAML).unwrap();
+
+        seal(&mut secret).unwrap();
+
+        let data = secret.get_spec().get_data().as_ref().unwrap();
+        assert_eq!(data.get("user"), Some(&"YWRtaW4=".to_string()));
+        assert_eq!(data.get("password"), Some(&STANDARD.encode("s3cr3t")));
+        assert!(secret.get_spec().get_string_data().is_none());
+        assert!(secret.get_spec().get_encryption().is_none());
+    }
+
+    #[test]
+    fn test_seal_rejects_invalid_base64() {
+        let yaml = SECRET_YAML.replace("YWRtaW4=", "not base64!");
+        let mut secret: Secret = serde_yaml::from_str(&yaml).unwrap();
+
+        assert!(seal(&mut secret).is_err());
+    }
+}
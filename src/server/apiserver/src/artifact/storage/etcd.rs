@@ -0,0 +1,38 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Artifacts in the key-value store shared with the other components
+
+use super::ArtifactStorage;
+use common::etcd::StoreError;
+
+/// Backend on top of `common::etcd`
+pub struct EtcdStorage;
+
+#[async_trait::async_trait]
+impl ArtifactStorage for EtcdStorage {
+    async fn get(&self, key: &str) -> Result<String, StoreError> {
+        common::etcd::get(key).await
+    }
+
+    async fn put(&self, key: &str, value: &str) -> Result<(), StoreError> {
+        common::etcd::put(key, value).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StoreError> {
+        common::etcd::delete(key).await
+    }
+
+    async fn get_all_with_prefix(&self, prefix: &str) -> Result<Vec<(String, String)>, StoreError> {
+        let mut kvs = common::etcd::get_all_with_prefix(prefix).await?;
+        kvs.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(kvs)
+    }
+
+    /// Written in a single transaction
+    async fn batch_put(&self, items: Vec<(String, String)>) -> Result<(), StoreError> {
+        common::etcd::batch_put(items).await
+    }
+}
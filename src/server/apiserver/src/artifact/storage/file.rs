@@ -0,0 +1,251 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Artifacts as files in a local directory
+//!
+//! Each key is one file directly in the directory. The file name is the key
+//! with every character except letters, digits, `-` and `_` percent-encoded,
+//! so a key can be stored next to keys below it, e.g. `Model/a` and
+//! `Model/a/state`. File names holding a key never contain a `.`.
+
+use super::{check_key, ArtifactStorage};
+use common::etcd::StoreError;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Suffix of files being written, they are ignored when reading
+const TEMP_SUFFIX: &str = ".tmp";
+
+/// Backend storing one file per key
+pub struct FileStorage {
+    dir: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(encode_key(key))
+    }
+
+    /// Write a value next to its file, to be renamed into place
+    async fn write_temp(&self, key: &str, value: &str) -> Result<PathBuf, StoreError> {
+        check_key(key)?;
+        tokio::fs::create_dir_all(&self.dir).await.map_err(|e| {
+            StoreError::Failed(format!("Failed to create {}: {}", self.dir.display(), e))
+        })?;
+        let temp = self.dir.join(format!("{}{}", encode_key(key), TEMP_SUFFIX));
+        tokio::fs::write(&temp, value)
+            .await
+            .map_err(|e| StoreError::Failed(format!("Failed to write key '{}': {}", key, e)))?;
+        Ok(temp)
+    }
+
+    async fn commit(&self, key: &str, temp: PathBuf) -> Result<(), StoreError> {
+        tokio::fs::rename(&temp, self.path(key))
+            .await
+            .map_err(|e| StoreError::Failed(format!("Failed to write key '{}': {}", key, e)))
+    }
+}
+
+#[async_trait::async_trait]
+impl ArtifactStorage for FileStorage {
+    async fn get(&self, key: &str) -> Result<String, StoreError> {
+        check_key(key)?;
+        match tokio::fs::read_to_string(self.path(key)).await {
+            Ok(value) => Ok(value),
+            Err(e) if e.kind() == ErrorKind::NotFound => Err(StoreError::NotFound),
+            Err(e) => Err(StoreError::Failed(format!(
+                "Failed to read key '{}': {}",
+                key, e
+            ))),
+        }
+    }
+
+    async fn put(&self, key: &str, value: &str) -> Result<(), StoreError> {
+        let temp = self.write_temp(key, value).await?;
+        self.commit(key, temp).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StoreError> {
+        check_key(key)?;
+        match tokio::fs::remove_file(self.path(key)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Err(StoreError::NotFound),
+            Err(e) => Err(StoreError::Failed(format!(
+                "Failed to delete key '{}': {}",
+                key, e
+            ))),
+        }
+    }
+
+    async fn get_all_with_prefix(&self, prefix: &str) -> Result<Vec<(String, String)>, StoreError> {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(StoreError::Failed(format!(
+                    "Failed to list {}: {}",
+                    self.dir.display(),
+                    e
+                )))
+            }
+        };
+
+        let mut kvs = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| StoreError::Failed(e.to_string()))?
+        {
+            let file_name = entry.file_name();
+            let Some(key) = file_name.to_str().and_then(decode_key) else {
+                continue;
+            };
+            if !key.starts_with(prefix) {
+                continue;
+            }
+            match tokio::fs::read_to_string(entry.path()).await {
+                Ok(value) => kvs.push((key, value)),
+                // Deleted since the directory was listed
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(StoreError::Failed(format!(
+                        "Failed to read key '{}': {}",
+                        key, e
+                    )))
+                }
+            }
+        }
+        kvs.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(kvs)
+    }
+
+    /// Every value is written before the first one is renamed into place
+    async fn batch_put(&self, items: Vec<(String, String)>) -> Result<(), StoreError> {
+        let mut temps = Vec::new();
+        for (key, value) in &items {
+            match self.write_temp(key, value).await {
+                Ok(temp) => temps.push((key, temp)),
+                Err(e) => {
+                    for (_, temp) in temps {
+                        let _ = tokio::fs::remove_file(temp).await;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        for (key, temp) in temps {
+            self.commit(key, temp).await?;
+        }
+        Ok(())
+    }
+}
+
+/// File name of a key
+fn encode_key(key: &str) -> String {
+    let mut name = String::with_capacity(key.len());
+    for byte in key.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_') {
+            name.push(byte as char);
+        } else {
+            name.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    name
+}
+
+/// Key of a file name, `None` for files that do not hold a key
+fn decode_key(name: &str) -> Option<String> {
+    if name.contains('.') {
+        return None;
+    }
+    let bytes = name.as_bytes();
+    let mut key = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = name.get(i + 1..i + 3)?;
+            key.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            key.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(key).ok()
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_storage(name: &str) -> (FileStorage, PathBuf) {
+        let dir = std::env::temp_dir().join(format!(
+            "pullpiri-file-storage-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        (FileStorage::new(&dir), dir)
+    }
+
+    #[test]
+    fn test_encode_and_decode_key() {
+        for key in [
+            "Scenario/helloworld",
+            "History/Model/a b/v1",
+            "100%",
+            "..",
+            "모델",
+        ] {
+            let name = encode_key(key);
+            assert!(!name.contains('/') && !name.contains('.'));
+            assert_eq!(decode_key(&name).as_deref(), Some(key));
+        }
+        assert_eq!(decode_key("Scenario%2Fa.tmp"), None);
+        assert_eq!(decode_key("bad%2"), None);
+    }
+
+    #[tokio::test]
+    async fn test_prefix_read_includes_nested_keys() {
+        let (storage, dir) = temp_storage("prefix");
+        storage
+            .batch_put(vec![
+                ("Model/a".to_string(), "a".to_string()),
+                ("Model/a/state".to_string(), "running".to_string()),
+                ("Package/a".to_string(), "p".to_string()),
+            ])
+            .await
+            .unwrap();
+
+        let models = storage.get_all_with_prefix("Model/").await.unwrap();
+        assert_eq!(
+            models,
+            vec![
+                ("Model/a".to_string(), "a".to_string()),
+                ("Model/a/state".to_string(), "running".to_string()),
+            ]
+        );
+        assert_eq!(
+            storage.delete("Model/b").await.unwrap_err(),
+            StoreError::NotFound
+        );
+        assert!(storage.put("", "empty").await.is_err());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_prefix_read_of_missing_directory_is_empty() {
+        let (storage, _) = temp_storage("missing");
+        assert!(storage.get_all_with_prefix("").await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,141 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Storage backends of artifacts
+//!
+//! Artifacts and their versions are read and written through [`ArtifactStorage`].
+//! The backend is chosen by the `storage` section of settings.yaml when ApiServer
+//! starts:
+//!
+//! * `etcd` - the key-value store shared with the other components (default)
+//! * `file` - one file per key in a local directory, for deployments without etcd
+//! * `s3` - an S3-compatible object store, for large artifacts such as maps or
+//!   ML models
+//!
+//! Every backend reports its failures as a [`StoreError`], like `common::etcd`.
+
+mod etcd;
+mod file;
+mod s3;
+
+pub use etcd::EtcdStorage;
+pub use file::FileStorage;
+pub use s3::S3Storage;
+
+use common::etcd::StoreError;
+use common::logd;
+use common::setting::{StorageBackend, StorageSettings};
+use std::sync::OnceLock;
+
+/// Directory of the `file` backend when settings.yaml has no `storage.path`
+const DEFAULT_FILE_PATH: &str = "/var/lib/pullpiri/artifacts";
+
+static STORAGE: OnceLock<Box<dyn ArtifactStorage>> = OnceLock::new();
+
+/// Key-value access to stored artifacts
+#[async_trait::async_trait]
+pub trait ArtifactStorage: Send + Sync {
+    /// Value of a key, `Err(StoreError::NotFound)` if it is not stored
+    async fn get(&self, key: &str) -> Result<String, StoreError>;
+
+    async fn put(&self, key: &str, value: &str) -> Result<(), StoreError>;
+
+    async fn delete(&self, key: &str) -> Result<(), StoreError>;
+
+    /// Every key starting with `prefix` and its value, sorted by key
+    async fn get_all_with_prefix(&self, prefix: &str) -> Result<Vec<(String, String)>, StoreError>;
+
+    /// Write several keys at once
+    ///
+    /// Backends without transactions write the keys one after another.
+    async fn batch_put(&self, items: Vec<(String, String)>) -> Result<(), StoreError> {
+        for (key, value) in items {
+            self.put(&key, &value).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Backend selected in settings.yaml, created on first use
+///
+/// Changing the backend takes effect after a restart of ApiServer.
+pub fn storage() -> &'static dyn ArtifactStorage {
+    STORAGE
+        .get_or_init(|| from_settings(&common::setting::get_config().storage))
+        .as_ref()
+}
+
+/// Create the backend described by the storage settings
+pub fn from_settings(settings: &StorageSettings) -> Box<dyn ArtifactStorage> {
+    match settings.backend {
+        StorageBackend::Etcd => Box::new(EtcdStorage),
+        StorageBackend::File => {
+            let path = if settings.path.is_empty() {
+                DEFAULT_FILE_PATH
+            } else {
+                &settings.path
+            };
+            logd!(2, "Artifacts are stored in directory {}", path);
+            Box::new(FileStorage::new(path))
+        }
+        StorageBackend::S3 => {
+            logd!(
+                2,
+                "Artifacts are stored in bucket '{}' at {}",
+                settings.s3.bucket,
+                settings.s3.endpoint
+            );
+            Box::new(S3Storage::new(settings.s3.clone()))
+        }
+    }
+}
+
+/// Reject keys no backend can store
+fn check_key(key: &str) -> Result<(), StoreError> {
+    if key.is_empty() {
+        return Err(StoreError::Failed("Key cannot be empty".to_string()));
+    }
+    if key.contains('\0') {
+        return Err(StoreError::Failed(format!(
+            "Key '{}' contains a null byte",
+            key.escape_debug()
+        )));
+    }
+    Ok(())
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_key() {
+        assert!(check_key("Scenario/helloworld").is_ok());
+        assert!(check_key("").is_err());
+        assert!(check_key("\0badkey").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_from_settings_file_backend() {
+        let dir = std::env::temp_dir().join(format!("pullpiri-storage-{}", std::process::id()));
+        let settings = StorageSettings {
+            backend: StorageBackend::File,
+            path: dir.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        let storage = from_settings(&settings);
+
+        storage.put("Scenario/helloworld", "yaml").await.unwrap();
+        assert_eq!(storage.get("Scenario/helloworld").await.unwrap(), "yaml");
+        storage.delete("Scenario/helloworld").await.unwrap();
+        assert_eq!(
+            storage.get("Scenario/helloworld").await.unwrap_err(),
+            StoreError::NotFound
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,316 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Substitute template parameters of an artifact before it is parsed
+//!
+//! An artifact becomes a template by adding a `Parameters` document:
+//!
+//! ```yaml
+//! apiVersion: v1
+//! kind: Parameters
+//! parameters:
+//!   - name: image_tag
+//!     default: "1.0"
+//!   - name: node
+//! ```
+//!
+//! `${image_tag}` and `${node}` in the other documents are replaced by the
+//! given values or the defaults, `$${` is written as a literal `${`.
+//! Artifacts without a `Parameters` document are not changed.
+
+use super::YAML_SEPARATOR;
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+
+const KIND_PARAMETERS: &str = "Parameters";
+
+/// `Parameters` document of a template
+#[derive(Debug, Deserialize)]
+struct ParametersDocument {
+    #[serde(default)]
+    parameters: Vec<Parameter>,
+}
+
+/// Declared parameter of a template
+#[derive(Debug, Deserialize)]
+struct Parameter {
+    name: String,
+    /// Value used when none is given, the parameter is required without one
+    #[serde(default)]
+    default: Option<serde_yaml::Value>,
+}
+
+/// Text of a scalar default, e.g. `3` or `true`
+fn scalar_to_string(name: &str, value: &serde_yaml::Value) -> common::Result<String> {
+    match value {
+        serde_yaml::Value::String(s) => Ok(s.clone()),
+        serde_yaml::Value::Number(n) => Ok(n.to_string()),
+        serde_yaml::Value::Bool(b) => Ok(b.to_string()),
+        _ => Err(format!("default of parameter '{}' must be a scalar", name).into()),
+    }
+}
+
+fn is_parameter_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Replace the `${name}` placeholders of a document
+///
+/// Names of the placeholders are added to `used`, placeholders without a
+/// value are dropped.
+fn substitute(
+    doc: &str,
+    values: &HashMap<String, String>,
+    used: &mut BTreeSet<String>,
+) -> common::Result<String> {
+    let mut rendered = String::with_capacity(doc.len());
+    let mut rest = doc;
+    while let Some(start) = rest.find('$') {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start..];
+        if let Some(escaped) = after.strip_prefix("$${") {
+            rendered.push_str("${");
+            rest = escaped;
+        } else if let Some(placeholder) = after.strip_prefix("${") {
+            let end = placeholder.find('}').ok_or_else(|| {
+                format!(
+                    "unterminated placeholder '{}'",
+                    after.lines().next().unwrap_or(after)
+                )
+            })?;
+            let name = &placeholder[..end];
+            if !is_parameter_name(name) {
+                return Err(format!("invalid parameter name '{}'", name).into());
+            }
+            used.insert(name.to_string());
+            if let Some(value) = values.get(name) {
+                rendered.push_str(value);
+            }
+            rest = &placeholder[end + 1..];
+        } else {
+            rendered.push('$');
+            rest = &after[1..];
+        }
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// Substitute the parameters of an artifact
+///
+/// ### Parametets
+/// * `body: &str` - whole yaml string of pullpiri artifact
+/// * `values: &HashMap<String, String>` - values of the parameters
+/// ### Returns
+/// * `Result<String>` - the artifact with every placeholder replaced
+/// ### Description
+/// The `Parameters` documents are left empty, so the other documents keep
+/// their index. It is an error if a parameter has neither a value nor a
+/// default, if a value is given for an undeclared parameter, or if a
+/// declared parameter is not used by any document.
+pub fn render(body: &str, values: &HashMap<String, String>) -> common::Result<String> {
+    let docs: Vec<&str> = body.split(YAML_SEPARATOR).collect();
+
+    let mut declared: Vec<Parameter> = Vec::new();
+    let mut templates = Vec::new();
+    for (index, doc) in docs.iter().enumerate() {
+        let Ok(value) = serde_yaml::from_str::<serde_yaml::Value>(doc) else {
+            continue;
+        };
+        if value.get("kind").and_then(|k| k.as_str()) != Some(KIND_PARAMETERS) {
+            continue;
+        }
+        let parameters: ParametersDocument = serde_yaml::from_value(value)?;
+        for parameter in parameters.parameters {
+            if !is_parameter_name(&parameter.name) {
+                return Err(format!("invalid parameter name '{}'", parameter.name).into());
+            }
+            if declared.iter().any(|p| p.name == parameter.name) {
+                return Err(format!("parameter '{}' is declared twice", parameter.name).into());
+            }
+            declared.push(parameter);
+        }
+        templates.push(index);
+    }
+
+    if templates.is_empty() {
+        if !values.is_empty() {
+            let mut names: Vec<&str> = values.keys().map(String::as_str).collect();
+            names.sort();
+            return Err(format!(
+                "artifact declares no parameters, unused values: {}",
+                names.join(", ")
+            )
+            .into());
+        }
+        return Ok(body.to_string());
+    }
+
+    let mut resolved = HashMap::new();
+    for parameter in &declared {
+        let value = match (values.get(&parameter.name), &parameter.default) {
+            (Some(value), _) => Some(value.clone()),
+            (None, Some(default)) => Some(scalar_to_string(&parameter.name, default)?),
+            (None, None) => None,
+        };
+        if let Some(value) = value {
+            resolved.insert(parameter.name.clone(), value);
+        }
+    }
+
+    let mut used = BTreeSet::new();
+    let mut rendered = Vec::with_capacity(docs.len());
+    for (index, doc) in docs.iter().enumerate() {
+        if templates.contains(&index) {
+            rendered.push("\n".to_string());
+        } else {
+            rendered.push(
+                substitute(doc, &resolved, &mut used)
+                    .map_err(|e| format!("document {}: {}", index, e))?,
+            );
+        }
+    }
+
+    let mut errors = Vec::new();
+    let undeclared: Vec<&str> = used
+        .iter()
+        .filter(|name| !declared.iter().any(|p| &p.name == *name))
+        .map(String::as_str)
+        .collect();
+    if !undeclared.is_empty() {
+        errors.push(format!("undeclared parameters: {}", undeclared.join(", ")));
+    }
+    let missing: Vec<&str> = declared
+        .iter()
+        .filter(|p| !resolved.contains_key(&p.name))
+        .map(|p| p.name.as_str())
+        .collect();
+    if !missing.is_empty() {
+        errors.push(format!(
+            "missing values for parameters: {}",
+            missing.join(", ")
+        ));
+    }
+    let mut unused: BTreeSet<&str> = values
+        .keys()
+        .filter(|name| !declared.iter().any(|p| &p.name == *name))
+        .map(String::as_str)
+        .collect();
+    unused.extend(
+        declared
+            .iter()
+            .filter(|p| !used.contains(&p.name))
+            .map(|p| p.name.as_str()),
+    );
+    if !unused.is_empty() {
+        errors.push(format!(
+            "unused parameters: {}",
+            unused.into_iter().collect::<Vec<_>>().join(", ")
+        ));
+    }
+    if !errors.is_empty() {
+        return Err(format!("Invalid artifact template: {}", errors.join("; ")).into());
+    }
+
+    Ok(rendered.join(YAML_SEPARATOR))
+}
+
+//UNIT TEST CASES
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE_YAML: &str = r#"apiVersion: v1
+kind: Parameters
+parameters:
+  - name: image_tag
+    default: "1.0"
+  - name: node
+---
+apiVersion: v1
+kind: Package
+metadata:
+  name: helloworld
+spec:
+  pattern:
+    - type: plain
+  models:
+    - name: helloworld-core
+      node: ${node}
+      resources:
+        volume:
+        network:
+---
+apiVersion: v1
+kind: Model
+metadata:
+  name: helloworld-core
+spec:
+  containers:
+    - name: helloworld
+      image: quay.io/podman/hello:${image_tag}
+      command: ["sh", "-c", "echo $${HOME} $$"]
+"#;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_substitutes_values_and_defaults() {
+        let rendered = render(TEMPLATE_YAML, &values(&[("node", "HPC")])).unwrap();
+        let docs: Vec<&str> = rendered.split(YAML_SEPARATOR).collect();
+
+        assert_eq!(docs.len(), 3);
+        assert!(docs[0].trim().is_empty());
+        assert!(docs[1].contains("node: HPC"));
+        assert!(docs[2].contains("quay.io/podman/hello:1.0"));
+        assert!(docs[2].contains("echo ${HOME} $"));
+    }
+
+    #[test]
+    fn test_render_reports_missing_and_unused_parameters() {
+        let err = render(TEMPLATE_YAML, &HashMap::new())
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("missing values for parameters: node"),
+            "{}",
+            err
+        );
+
+        let err = render(TEMPLATE_YAML, &values(&[("node", "HPC"), ("tag", "2.0")]))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("unused parameters: tag"), "{}", err);
+
+        let unused_declaration = TEMPLATE_YAML.replace("${image_tag}", "1.0");
+        let err = render(&unused_declaration, &values(&[("node", "HPC")]))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("unused parameters: image_tag"), "{}", err);
+
+        let undeclared = TEMPLATE_YAML.replace("${node}", "${zone}");
+        let err = render(&undeclared, &values(&[("node", "HPC")]))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("undeclared parameters: zone"), "{}", err);
+    }
+
+    #[test]
+    fn test_render_without_parameters() {
+        let plain = "kind: Model\nspec:\n  command: echo ${HOME}\n";
+        assert_eq!(render(plain, &HashMap::new()).unwrap(), plain);
+        assert!(render(plain, &values(&[("node", "HPC")])).is_err());
+    }
+}
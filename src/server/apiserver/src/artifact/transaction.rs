@@ -0,0 +1,374 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Transactional apply: validate every document, then write all of them at once
+
+use super::validate::{validate, ValidationReport};
+use super::{data, KIND_CONFIG_MAP, KIND_PACKAGE, KIND_SCENARIO, YAML_SEPARATOR};
+use common::logd;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Result of applying a multi-document artifact in a single transaction
+#[derive(Debug, Default, Serialize)]
+pub struct ApplyReport {
+    /// `true` when every document was written
+    pub committed: bool,
+    /// Outcome of each YAML document in the request body
+    pub documents: Vec<DocumentResult>,
+    /// Problems that do not belong to a single document
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    /// Yaml strings of the committed scenarios
+    #[serde(skip)]
+    pub scenarios: Vec<String>,
+}
+
+/// Outcome of one YAML document
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DocumentResult {
+    /// Index of the YAML document in the request body
+    pub document: usize,
+    pub kind: Option<String>,
+    pub name: Option<String>,
+    pub status: DocumentStatus,
+    /// Stored version of the artifact once committed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u64>,
+    /// Validation errors and warnings of the document
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub messages: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentStatus {
+    /// Written, nothing was stored before
+    Applied,
+    /// Written as a new version of the stored artifact
+    Updated,
+    /// Equal to the stored artifact, nothing was written
+    Unchanged,
+    /// Failed validation
+    Invalid,
+    /// A later document has the same kind and name
+    Superseded,
+    /// Valid, but nothing was written because the transaction was aborted
+    NotApplied,
+}
+
+/// Artifact prepared for the batch write
+struct Prepared {
+    /// Position in `ApplyReport::documents`
+    result: usize,
+    kind: String,
+    key: String,
+    namespace: String,
+    artifact_str: String,
+}
+
+/// Apply artifacts all-or-nothing
+///
+/// ### Parametets
+/// * `body: &str` - whole yaml string of pullpiri artifact
+/// ### Returns
+/// * `ApplyReport` - per-document results and whether anything was written
+/// ### Description
+/// All documents are validated like `validate` does. Only if none has an
+/// error, the artifacts and their new versions are written in one batch,
+/// which the RocksDB service commits atomically. Artifacts equal to the
+/// stored ones are left out of the batch, so re-applying them does not touch
+/// etcd. Pod yaml of the packages and scenario state notifications follow
+/// after the commit, and running models using a changed ConfigMap restart.
+pub async fn apply(body: &str) -> ApplyReport {
+    let validation = validate(body).await;
+    let mut report = ApplyReport::from_validation(&validation);
+    if !validation.valid {
+        report.abort(None);
+        return report;
+    }
+
+    let docs: Vec<&str> = body.split(YAML_SEPARATOR).collect();
+    let mut prepared = Vec::new();
+    for (i, result) in report.documents.iter().enumerate() {
+        if result.status != DocumentStatus::NotApplied {
+            continue;
+        }
+        match super::prepare_artifact_document(docs[result.document]) {
+            Ok(Some((kind, name, artifact_str))) => prepared.push(Prepared {
+                result: i,
+                key: common::spec::namespace::qualified_key(&kind, &name),
+                namespace: common::spec::namespace::split_qualified(&name)
+                    .0
+                    .to_string(),
+                kind,
+                artifact_str,
+            }),
+            Ok(None) => {}
+            Err(e) => {
+                report.abort(Some(format!(
+                    "Failed to prepare document {}: {}",
+                    result.document, e
+                )));
+                return report;
+            }
+        }
+    }
+
+    let mut items = Vec::new();
+    let mut versions = Vec::new();
+    for artifact in &prepared {
+        let change = data::compare_with_stored(&artifact.key, &artifact.artifact_str).await;
+        if change == data::Change::Unchanged {
+            let version = match data::read_versions(&artifact.key).await {
+                Ok(history) => (history.latest != 0).then_some(history.latest),
+                Err(_) => None,
+            };
+            versions.push((artifact.result, version, DocumentStatus::Unchanged));
+            continue;
+        }
+        match data::version_entries(&artifact.key, &artifact.artifact_str).await {
+            Ok((version, entries)) => {
+                let status = match change {
+                    data::Change::Created => DocumentStatus::Applied,
+                    _ => DocumentStatus::Updated,
+                };
+                versions.push((artifact.result, Some(version), status));
+                items.push((artifact.key.clone(), artifact.artifact_str.clone()));
+                items.extend(entries);
+                items.extend(data::namespace_entry(&artifact.namespace));
+            }
+            Err(e) => {
+                report.abort(Some(format!(
+                    "Failed to read versions of {}: {}",
+                    artifact.key, e
+                )));
+                return report;
+            }
+        }
+    }
+
+    if items.is_empty() {
+        logd!(2, "apply transaction: every artifact is unchanged");
+    } else if let Err(e) = super::storage::storage().batch_put(items).await {
+        report.abort(Some(format!(
+            "Transaction failed, nothing was written: {}",
+            e
+        )));
+        return report;
+    }
+    report.committed = true;
+    for (i, version, status) in versions {
+        report.documents[i].status = status;
+        report.documents[i].version = version;
+    }
+
+    let unchanged = report
+        .documents
+        .iter()
+        .filter(|d| d.status == DocumentStatus::Unchanged)
+        .count();
+    let count = prepared.len() - unchanged;
+    let mut changed_config_maps = Vec::new();
+    for artifact in prepared {
+        match artifact.kind.as_str() {
+            // Also for unchanged scenarios, applying one again restarts it
+            KIND_SCENARIO => {
+                if let Some(name) = &report.documents[artifact.result].name {
+                    super::notify_scenario_state(name, "idle").await;
+                }
+                report.scenarios.push(artifact.artifact_str);
+            }
+            KIND_PACKAGE => {
+                if let Err(e) = super::save_pod_yaml_from_package(&artifact.artifact_str).await {
+                    report
+                        .warnings
+                        .push(format!("Failed to save pods of {}: {}", artifact.key, e));
+                }
+            }
+            KIND_CONFIG_MAP
+                if report.documents[artifact.result].status == DocumentStatus::Updated =>
+            {
+                changed_config_maps.extend(report.documents[artifact.result].name.clone());
+            }
+            _ => {}
+        }
+    }
+    // After the packages, whose pods may use the changed values already
+    super::configmap::restart_users(&changed_config_maps).await;
+
+    logd!(
+        2,
+        "apply transaction: committed {} artifacts, {} unchanged",
+        count,
+        unchanged
+    );
+    report
+}
+
+impl ApplyReport {
+    /// Per-document results of a validation, before anything is written
+    ///
+    /// Documents with errors are `Invalid`, valid ones start as `NotApplied`.
+    /// Earlier documents with the same kind and name as a later one are
+    /// `Superseded` and never written.
+    fn from_validation(validation: &ValidationReport) -> Self {
+        let mut report = ApplyReport::default();
+        let mut last: HashMap<(&str, &str), usize> = HashMap::new();
+        for artifact in &validation.artifacts {
+            last.insert((&artifact.kind, &artifact.name), artifact.document);
+        }
+
+        for artifact in &validation.artifacts {
+            let status =
+                if last[&(artifact.kind.as_str(), artifact.name.as_str())] == artifact.document {
+                    DocumentStatus::NotApplied
+                } else {
+                    DocumentStatus::Superseded
+                };
+            report.documents.push(DocumentResult {
+                document: artifact.document,
+                kind: Some(artifact.kind.clone()),
+                name: Some(artifact.name.clone()),
+                status,
+                version: None,
+                messages: Vec::new(),
+            });
+        }
+
+        for (issues, is_error) in [(&validation.errors, true), (&validation.warnings, false)] {
+            for issue in issues {
+                let Some(document) = issue.document else {
+                    let messages = if is_error {
+                        &mut report.errors
+                    } else {
+                        &mut report.warnings
+                    };
+                    messages.push(issue.message.clone());
+                    continue;
+                };
+                let result = report.document(document, issue.kind.clone());
+                if is_error {
+                    result.status = DocumentStatus::Invalid;
+                }
+                result.messages.push(issue.message.clone());
+            }
+        }
+
+        report.documents.sort_by_key(|d| d.document);
+        report
+    }
+
+    /// Result of a document, added if the document could not be parsed
+    fn document(&mut self, document: usize, kind: Option<String>) -> &mut DocumentResult {
+        let position = match self.documents.iter().position(|d| d.document == document) {
+            Some(position) => position,
+            None => {
+                self.documents.push(DocumentResult {
+                    document,
+                    kind,
+                    name: None,
+                    status: DocumentStatus::Invalid,
+                    version: None,
+                    messages: Vec::new(),
+                });
+                self.documents.len() - 1
+            }
+        };
+        &mut self.documents[position]
+    }
+
+    /// Give up on the transaction, nothing has been written
+    fn abort(&mut self, error: Option<String>) {
+        if let Some(error) = error {
+            logd!(5, "apply transaction: {}", error);
+            self.errors.push(error);
+        }
+        self.committed = false;
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENARIO_YAML: &str = r#"
+apiVersion: v1
+kind: Scenario
+metadata:
+  name: helloworld
+spec:
+  condition:
+  action: update
+  target: helloworld
+"#;
+
+    const MODEL_YAML: &str = r#"
+apiVersion: v1
+kind: Model
+metadata:
+  name: helloworld-core
+spec:
+  hostNetwork: true
+  containers:
+    - name: helloworld
+      image: helloworld
+  terminationGracePeriodSeconds: 0
+"#;
+
+    #[tokio::test]
+    async fn test_apply_invalid_document_writes_nothing() {
+        let broken_package = r#"
+apiVersion: v1
+kind: Package
+metadata:
+  name: helloworld
+spec:
+  models: []
+"#;
+        let body = format!("{}---{}---{}", SCENARIO_YAML, broken_package, MODEL_YAML);
+        let report = apply(&body).await;
+
+        assert!(!report.committed);
+        assert!(report.scenarios.is_empty());
+        let statuses: Vec<DocumentStatus> = report.documents.iter().map(|d| d.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                DocumentStatus::NotApplied,
+                DocumentStatus::Invalid,
+                DocumentStatus::NotApplied
+            ]
+        );
+        assert!(!report.documents[1].messages.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_from_validation_marks_superseded_and_unparsed_documents() {
+        let body = format!(
+            "{}---{}---{}---kind: Unknown",
+            SCENARIO_YAML, MODEL_YAML, MODEL_YAML
+        );
+        let report = ApplyReport::from_validation(&validate(&body).await);
+
+        let statuses: Vec<(usize, DocumentStatus)> = report
+            .documents
+            .iter()
+            .map(|d| (d.document, d.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (0, DocumentStatus::NotApplied),
+                (1, DocumentStatus::Superseded),
+                (2, DocumentStatus::NotApplied),
+                (3, DocumentStatus::Invalid),
+            ]
+        );
+        assert_eq!(report.documents[3].kind.as_deref(), Some("Unknown"));
+        assert!(report.errors.iter().any(|e| e.contains("any package")));
+    }
+}
@@ -0,0 +1,716 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Dry-run validation of artifacts without writing to etcd
+
+use super::{
+    check_namespace, CLUSTER_KINDS, KIND_CONFIG_MAP, KIND_MODEL, KIND_NETWORK, KIND_NODE,
+    KIND_PACKAGE, KIND_POLICY, KIND_SCENARIO, KIND_SCHEDULE, KIND_SECRET, KIND_VOLUME,
+    YAML_SEPARATOR,
+};
+use common::etcd::StoreError;
+use common::spec::artifact::scenario::find_dependency_cycle;
+use common::spec::artifact::{
+    Artifact, ConfigMap, Model, Network, Node, Package, Policy, Scenario, Schedule, Secret, Volume,
+};
+use common::spec::namespace;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Result of validating a multi-document artifact
+#[derive(Debug, Default, Serialize)]
+pub struct ValidationReport {
+    /// `true` when no errors were found (warnings are allowed)
+    pub valid: bool,
+    /// Artifacts that were parsed successfully
+    pub artifacts: Vec<ArtifactRef>,
+    pub errors: Vec<ValidationIssue>,
+    pub warnings: Vec<ValidationIssue>,
+}
+
+/// Kind and name of a parsed artifact
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ArtifactRef {
+    /// Index of the YAML document in the request body
+    pub document: usize,
+    pub kind: String,
+    /// Name qualified by the namespace of the artifact
+    pub name: String,
+}
+
+impl ArtifactRef {
+    /// Qualify the name of an artifact referenced from this one
+    ///
+    /// References resolve within the namespace of the referrer, except for
+    /// kinds of the whole cluster.
+    fn qualify(&self, kind: &str, name: &str) -> String {
+        if CLUSTER_KINDS.contains(&kind) {
+            return name.to_string();
+        }
+        namespace::qualified_name(namespace::split_qualified(&self.name).0, name)
+    }
+}
+
+/// Single problem found during validation
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationIssue {
+    /// Index of the YAML document the issue belongs to, if any
+    pub document: Option<usize>,
+    pub kind: Option<String>,
+    pub name: Option<String>,
+    pub message: String,
+}
+
+impl ValidationReport {
+    /// Report of an artifact rejected before its documents are parsed
+    pub fn invalid(message: String) -> Self {
+        Self {
+            errors: vec![ValidationIssue::new(None, message)],
+            ..Default::default()
+        }
+    }
+
+    fn error(&mut self, artifact: Option<&ArtifactRef>, message: String) {
+        self.errors.push(ValidationIssue::new(artifact, message));
+    }
+
+    fn warning(&mut self, artifact: Option<&ArtifactRef>, message: String) {
+        self.warnings.push(ValidationIssue::new(artifact, message));
+    }
+
+    fn contains(&self, kind: &str, name: &str) -> bool {
+        self.artifacts
+            .iter()
+            .any(|a| a.kind == kind && a.name == name)
+    }
+}
+
+impl ValidationIssue {
+    fn new(artifact: Option<&ArtifactRef>, message: String) -> Self {
+        Self {
+            document: artifact.map(|a| a.document),
+            kind: artifact.map(|a| a.kind.clone()),
+            name: artifact.map(|a| a.name.clone()),
+            message,
+        }
+    }
+}
+
+/// Outcome of looking up a referenced artifact in etcd
+enum Lookup {
+    Found,
+    Missing,
+    Unavailable(String),
+}
+
+/// Validate artifacts the same way `apply` would, without storing anything
+///
+/// ### Parametets
+/// * `body: &str` - whole yaml string of pullpiri artifact
+/// ### Returns
+/// * `ValidationReport` - parsed artifacts with errors and warnings
+/// ### Description
+/// Parses every document, checks that references between artifacts
+/// (Scenario→Package, Package→Model/Volume/Network/Schedule/Policy,
+/// Model→ConfigMap) resolve
+/// either within the body or in etcd, checks model node assignments and
+/// rejects `dependsOn` cycles between scenarios.
+pub async fn validate(body: &str) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let mut scenarios = Vec::new();
+    let mut packages = Vec::new();
+    let mut models = Vec::new();
+
+    for (index, doc) in body.split(YAML_SEPARATOR).enumerate() {
+        if doc.trim().is_empty() {
+            continue;
+        }
+
+        let value: serde_yaml::Value = match serde_yaml::from_str(doc) {
+            Ok(value) => value,
+            Err(e) => {
+                report.errors.push(ValidationIssue {
+                    document: Some(index),
+                    kind: None,
+                    name: None,
+                    message: format!("Invalid YAML: {}", e),
+                });
+                continue;
+            }
+        };
+        if value.is_null() {
+            continue;
+        }
+
+        let Some(kind) = value.get("kind").and_then(|k| k.as_str()) else {
+            report.errors.push(ValidationIssue {
+                document: Some(index),
+                kind: None,
+                name: None,
+                message: "Missing 'kind' field".to_string(),
+            });
+            continue;
+        };
+
+        let parsed = match kind {
+            KIND_SCENARIO => parse::<Scenario>(&value)
+                .and_then(|s| {
+                    s.get_conditions()
+                        .map_or(Ok(()), |c| c.validate())
+                        .and_then(|_| common::vehicle_mode::validate(&s.get_allowed_modes()))
+                        .map(|_| s)
+                })
+                .map(|s| {
+                    let name = s.get_qualified_name();
+                    scenarios.push((index, s));
+                    name
+                }),
+            KIND_PACKAGE => parse::<Package>(&value).map(|p| {
+                let name = p.get_qualified_name();
+                packages.push((index, p));
+                name
+            }),
+            KIND_VOLUME => parse::<Volume>(&value).map(|v| v.get_qualified_name()),
+            KIND_NETWORK => {
+                parse::<Network>(&value).and_then(|n| n.validate().map(|_| n.get_qualified_name()))
+            }
+            KIND_NODE => parse::<Node>(&value).map(|n| n.get_qualified_name()),
+            KIND_MODEL => parse::<Model>(&value).map(|m| {
+                let name = m.get_qualified_name();
+                models.push((index, m));
+                name
+            }),
+            KIND_SCHEDULE => parse::<Schedule>(&value).map(|s| s.get_qualified_name()),
+            KIND_POLICY => parse::<Policy>(&value).map(|p| p.get_qualified_name()),
+            KIND_SECRET => parse::<Secret>(&value).map(|s| s.get_qualified_name()),
+            KIND_CONFIG_MAP => parse::<ConfigMap>(&value)
+                .and_then(|c| c.validate().map(|_| c.get_qualified_name())),
+            _ => Err(format!("Unknown artifact kind '{}'", kind)),
+        };
+        let parsed = parsed.and_then(|name| check_namespace(kind, &name).map(|_| name));
+
+        match parsed {
+            Ok(name) => {
+                let artifact = ArtifactRef {
+                    document: index,
+                    kind: kind.to_string(),
+                    name,
+                };
+                if report.contains(&artifact.kind, &artifact.name) {
+                    report.warning(
+                        Some(&artifact),
+                        "Duplicate artifact, the last document wins".to_string(),
+                    );
+                }
+                report.artifacts.push(artifact);
+            }
+            Err(message) => report.errors.push(ValidationIssue {
+                document: Some(index),
+                kind: Some(kind.to_string()),
+                name: None,
+                message,
+            }),
+        }
+    }
+
+    if scenarios.is_empty() {
+        report.error(None, "There is not any scenario in yaml string".to_string());
+    }
+    if packages.is_empty() {
+        report.error(None, "There is not any package in yaml string".to_string());
+    }
+
+    for (index, scenario) in &scenarios {
+        let artifact = ArtifactRef {
+            document: *index,
+            kind: KIND_SCENARIO.to_string(),
+            name: scenario.get_qualified_name(),
+        };
+        check_reference(
+            &mut report,
+            &artifact,
+            KIND_PACKAGE,
+            &scenario.get_targets(),
+        )
+        .await;
+        // a prerequisite may be applied later, the scenario waits for it
+        for dependency in scenario.get_depends_on() {
+            check_optional_reference(&mut report, &artifact, KIND_SCENARIO, &dependency).await;
+        }
+    }
+    let body_scenarios: Vec<&Scenario> = scenarios.iter().map(|(_, s)| s).collect();
+    if let Some(cycle) = dependency_cycle(&body_scenarios).await {
+        report.error(
+            None,
+            format!("Scenario dependency cycle: {}", cycle.join(" -> ")),
+        );
+    }
+
+    let known_nodes = known_nodes(&mut report).await;
+    for (index, package) in &packages {
+        let artifact = ArtifactRef {
+            document: *index,
+            kind: KIND_PACKAGE.to_string(),
+            name: package.get_qualified_name(),
+        };
+        check_package(&mut report, &artifact, package, known_nodes.as_ref()).await;
+    }
+    for (index, model) in &models {
+        let artifact = ArtifactRef {
+            document: *index,
+            kind: KIND_MODEL.to_string(),
+            name: model.get_qualified_name(),
+        };
+        for config_map in model.get_podspec().get_config_map_refs() {
+            check_reference(&mut report, &artifact, KIND_CONFIG_MAP, &config_map).await;
+        }
+    }
+
+    report.valid = report.errors.is_empty();
+    report
+}
+
+/// Find a `dependsOn` cycle among the given and the stored scenarios
+///
+/// ### Parameters
+/// * `scenarios: &[&Scenario]` - scenarios about to be applied, they
+///   replace stored scenarios of the same name
+/// ### Returns
+/// * `Option<Vec<String>>` - scenarios along the cycle, if any
+pub async fn dependency_cycle(scenarios: &[&Scenario]) -> Option<Vec<String>> {
+    // removing dependencies cannot close a cycle
+    if scenarios.iter().all(|s| s.get_depends_on().is_empty()) {
+        return None;
+    }
+
+    let mut graph: HashMap<String, Vec<String>> = HashMap::new();
+    if let Ok(stored) = crate::artifact::data::read_all_scenario_from_etcd().await {
+        for yaml in stored {
+            if let Ok(scenario) = serde_yaml::from_str::<Scenario>(&yaml) {
+                graph.insert(
+                    scenario.get_qualified_name(),
+                    scenario.get_qualified_depends_on(),
+                );
+            }
+        }
+    }
+    for scenario in scenarios {
+        graph.insert(
+            scenario.get_qualified_name(),
+            scenario.get_qualified_depends_on(),
+        );
+    }
+    find_dependency_cycle(&graph)
+}
+
+/// Deserialize an artifact, turning the serde error into a message
+fn parse<T: serde::de::DeserializeOwned>(value: &serde_yaml::Value) -> Result<T, String> {
+    serde_yaml::from_value::<T>(value.clone()).map_err(|e| e.to_string())
+}
+
+/// Check the models and resources referenced by a package
+async fn check_package(
+    report: &mut ValidationReport,
+    artifact: &ArtifactRef,
+    package: &Package,
+    known_nodes: Option<&HashSet<String>>,
+) {
+    if package.get_models().is_empty() {
+        report.error(Some(artifact), "Package has no models".to_string());
+    }
+    if let Err(e) = package.validate_pattern() {
+        report.error(Some(artifact), e);
+    }
+    if let Err(e) = package.validate_affinity() {
+        report.error(Some(artifact), e);
+    }
+    if let Err(e) = package.validate_update_strategy() {
+        report.error(Some(artifact), e);
+    }
+    // Selector and distributed packages choose the nodes of their models
+    let plain = package.get_pattern().is_plain();
+
+    for model_info in package.get_models() {
+        let model_name = model_info.get_name();
+        check_reference(report, artifact, KIND_MODEL, &model_name).await;
+
+        let resources = model_info.get_resources();
+        if let Some(volume) = resources.get_volume() {
+            check_reference(report, artifact, KIND_VOLUME, &volume).await;
+        }
+        if let Some(network) = resources.get_network() {
+            check_reference(report, artifact, KIND_NETWORK, &network).await;
+        }
+        if let Some(secret) = resources.get_secret() {
+            check_reference(report, artifact, KIND_SECRET, &secret).await;
+        }
+
+        let node = model_info.get_node();
+        if node.trim().is_empty() {
+            report.error(
+                Some(artifact),
+                format!("Model '{}' is not assigned to any node", model_name),
+            );
+        } else if model_info.is_auto_node() || !plain {
+            // ActionController picks a node when the scenario is launched
+        } else if let Some(nodes) = known_nodes {
+            if !nodes.contains(&node) && !report.contains(KIND_NODE, &node) {
+                report.warning(
+                    Some(artifact),
+                    format!(
+                        "Model '{}' is assigned to node '{}' which is not registered",
+                        model_name, node
+                    ),
+                );
+            }
+        }
+    }
+
+    if let Some(schedule) = package.get_schedule() {
+        check_optional_reference(report, artifact, KIND_SCHEDULE, schedule).await;
+    }
+    if let Some(policy) = package.get_policy() {
+        check_optional_reference(report, artifact, KIND_POLICY, policy).await;
+    }
+}
+
+/// Report an error if a referenced artifact exists neither in the body nor in etcd
+async fn check_reference(
+    report: &mut ValidationReport,
+    artifact: &ArtifactRef,
+    kind: &str,
+    name: &str,
+) {
+    let name = &artifact.qualify(kind, name);
+    if report.contains(kind, name) {
+        return;
+    }
+    match lookup(kind, name).await {
+        Lookup::Found => {}
+        Lookup::Missing => report.error(
+            Some(artifact),
+            format!("Referenced {} '{}' does not exist", kind, name),
+        ),
+        Lookup::Unavailable(e) => report.warning(
+            Some(artifact),
+            format!("Could not verify {} '{}' in etcd: {}", kind, name, e),
+        ),
+    }
+}
+
+/// Like `check_reference`, but a missing artifact is only a warning
+async fn check_optional_reference(
+    report: &mut ValidationReport,
+    artifact: &ArtifactRef,
+    kind: &str,
+    name: &str,
+) {
+    let name = &artifact.qualify(kind, name);
+    if report.contains(kind, name) {
+        return;
+    }
+    match lookup(kind, name).await {
+        Lookup::Found => {}
+        Lookup::Missing => report.warning(
+            Some(artifact),
+            format!("Referenced {} '{}' does not exist", kind, name),
+        ),
+        Lookup::Unavailable(e) => report.warning(
+            Some(artifact),
+            format!("Could not verify {} '{}' in etcd: {}", kind, name, e),
+        ),
+    }
+}
+
+/// Look up an artifact by its kind and qualified name in etcd
+async fn lookup(kind: &str, name: &str) -> Lookup {
+    match super::storage::storage()
+        .get(&namespace::qualified_key(kind, name))
+        .await
+    {
+        Ok(_) => Lookup::Found,
+        Err(StoreError::NotFound) => Lookup::Missing,
+        Err(e) => Lookup::Unavailable(e.to_string()),
+    }
+}
+
+/// Hostnames of registered nodes, or `None` if they could not be loaded
+async fn known_nodes(report: &mut ValidationReport) -> Option<HashSet<String>> {
+    let node_manager = crate::node::NodeManager::new().ok()?;
+    match node_manager.get_all_nodes().await {
+        Ok(nodes) => {
+            let mut names: HashSet<String> = nodes.into_iter().map(|n| n.hostname).collect();
+            names.insert(common::setting::get_config().host.name.clone());
+            Some(names)
+        }
+        Err(e) => {
+            report.warning(
+                None,
+                format!(
+                    "Could not load registered nodes, node assignment not checked: {}",
+                    e
+                ),
+            );
+            None
+        }
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID_ARTIFACT_YAML: &str = r#"
+apiVersion: v1
+kind: Scenario
+metadata:
+  name: helloworld
+spec:
+  condition:
+  action: update
+  target: helloworld
+---
+apiVersion: v1
+kind: Package
+metadata:
+  label: null
+  name: helloworld
+spec:
+  pattern:
+    - type: plain
+  models:
+    - name: helloworld-core
+      node: HPC
+      resources:
+        volume:
+        network:
+---
+apiVersion: v1
+kind: Model
+metadata:
+  name: helloworld-core
+  annotations:
+    io.pullpiri.annotations.package-type: helloworld-core
+    io.pullpiri.annotations.package-name: helloworld
+    io.pullpiri.annotations.package-network: default
+  labels:
+    app: helloworld-core
+spec:
+  hostNetwork: true
+  containers:
+    - name: helloworld
+      image: helloworld
+  terminationGracePeriodSeconds: 0
+"#;
+
+    #[tokio::test]
+    async fn test_validate_valid_artifact() {
+        let report = validate(VALID_ARTIFACT_YAML).await;
+
+        assert!(report.valid, "unexpected errors: {:?}", report.errors);
+        assert_eq!(report.artifacts.len(), 3);
+        assert!(report.contains(KIND_SCENARIO, "helloworld"));
+        assert!(report.contains(KIND_PACKAGE, "helloworld"));
+        assert!(report.contains(KIND_MODEL, "helloworld-core"));
+    }
+
+    #[tokio::test]
+    async fn test_validate_reports_parse_errors() {
+        let body = r#"
+apiVersion: v1
+kind: Scenario
+metadata:
+  name: broken
+spec:
+  condition:
+  target: helloworld
+---
+kind: Unknown
+metadata:
+  name: x
+---
+metadata:
+  name: no-kind
+"#;
+        let report = validate(body).await;
+
+        assert!(!report.valid);
+        assert!(report
+            .errors
+            .iter()
+            .any(|e| e.kind.as_deref() == Some(KIND_SCENARIO)));
+        assert!(report
+            .errors
+            .iter()
+            .any(|e| e.message.contains("Unknown artifact kind")));
+        assert!(report
+            .errors
+            .iter()
+            .any(|e| e.message.contains("Missing 'kind'")));
+        assert!(report
+            .errors
+            .iter()
+            .any(|e| e.message.contains("any package")));
+    }
+
+    #[tokio::test]
+    async fn test_validate_reports_invalid_network_options() {
+        let body = format!(
+            "{}---\napiVersion: v1\nkind: Network\nmetadata:\n  name: vd-network\nspec:\n  driver: macvlan\n",
+            VALID_ARTIFACT_YAML
+        );
+        let report = validate(&body).await;
+
+        assert!(!report.valid);
+        let issue = report
+            .errors
+            .iter()
+            .find(|e| e.kind.as_deref() == Some(KIND_NETWORK))
+            .unwrap();
+        assert!(issue.message.contains("parent interface"));
+    }
+
+    #[tokio::test]
+    async fn test_validate_config_map_reference() {
+        let body = VALID_ARTIFACT_YAML.replace(
+            "      image: helloworld\n",
+            "      image: helloworld\n      envFrom:\n        - configMapRef:\n            name: vehicle\n",
+        ) + "---\napiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: vehicle\nspec:\n  data:\n    VIN: KMH0\n";
+        let report = validate(&body).await;
+        assert!(report.valid, "unexpected errors: {:?}", report.errors);
+        assert!(report.contains(KIND_CONFIG_MAP, "vehicle"));
+
+        let report = validate(&body.replace("VIN:", "VIN-1:")).await;
+        assert!(!report.valid);
+        assert!(report
+            .errors
+            .iter()
+            .any(|e| e.kind.as_deref() == Some(KIND_CONFIG_MAP)));
+    }
+
+    #[tokio::test]
+    async fn test_validate_reports_missing_node_assignment() {
+        let body = VALID_ARTIFACT_YAML.replace("node: HPC", "node: \"\"");
+        let report = validate(&body).await;
+
+        assert!(!report.valid);
+        assert!(report
+            .errors
+            .iter()
+            .any(|e| e.message.contains("not assigned to any node")));
+    }
+
+    #[tokio::test]
+    async fn test_validate_reports_invalid_pattern() {
+        let body = VALID_ARTIFACT_YAML.replace("- type: plain", "- type: selector");
+        let report = validate(&body).await;
+
+        assert!(!report.valid);
+        assert!(report
+            .errors
+            .iter()
+            .any(|e| e.message.contains("selector pattern without nodeSelector")));
+    }
+
+    #[tokio::test]
+    async fn test_validate_reports_unknown_affinity_model() {
+        let body = VALID_ARTIFACT_YAML.replace(
+            "      node: HPC\n",
+            "      node: HPC\n      affinity: [camera-pipeline]\n",
+        );
+        let report = validate(&body).await;
+
+        assert!(!report.valid);
+        assert!(report
+            .errors
+            .iter()
+            .any(|e| e.message.contains("'camera-pipeline' which is not a model")));
+    }
+
+    #[tokio::test]
+    async fn test_validate_reports_invalid_condition() {
+        let body = VALID_ARTIFACT_YAML.replace(
+            "  condition:\n",
+            "  condition:\n    all:\n      - express: eq\n        value: P\n    any:\n      - express: eq\n        value: D\n",
+        );
+        let report = validate(&body).await;
+
+        assert!(!report.valid);
+        assert!(report
+            .errors
+            .iter()
+            .any(|e| e.message.contains("only one of 'all', 'any' and 'not'")));
+    }
+
+    #[tokio::test]
+    async fn test_validate_warns_on_duplicate_artifacts() {
+        let body = format!(
+            "{}---{}",
+            VALID_ARTIFACT_YAML,
+            VALID_ARTIFACT_YAML.split("---").last().unwrap()
+        );
+        let report = validate(&body).await;
+
+        assert!(report
+            .warnings
+            .iter()
+            .any(|w| w.message.contains("Duplicate artifact")));
+    }
+
+    #[tokio::test]
+    async fn test_validate_reports_dependency_cycle() {
+        let cyclic = VALID_ARTIFACT_YAML.replace(
+            "  target: helloworld\n",
+            "  target: helloworld\n  dependsOn:\n    - helloworld-next\n",
+        ) + r#"---
+apiVersion: v1
+kind: Scenario
+metadata:
+  name: helloworld-next
+spec:
+  condition:
+  action: update
+  target: helloworld
+  dependsOn:
+    - helloworld
+"#;
+        let report = validate(&cyclic).await;
+
+        assert!(!report.valid);
+        assert!(report.errors.iter().any(|e| e.message
+            == "Scenario dependency cycle: helloworld -> helloworld-next -> helloworld"));
+    }
+
+    #[tokio::test]
+    async fn test_validate_namespaced_artifacts() {
+        let namespaced = VALID_ARTIFACT_YAML.replace(
+            "  name: helloworld\n",
+            "  name: helloworld\n  namespace: team-a\n",
+        );
+        let report = validate(&namespaced).await;
+        // the package refers to a model of another namespace
+        assert!(report
+            .artifacts
+            .iter()
+            .any(|a| a.name == "team-a/helloworld"));
+        assert!(report
+            .errors
+            .iter()
+            .chain(&report.warnings)
+            .any(|e| e.message.contains("Model 'team-a/helloworld-core'")));
+
+        let invalid = VALID_ARTIFACT_YAML.replace(
+            "  name: helloworld\n",
+            "  name: helloworld\n  namespace: Team_A\n",
+        );
+        let report = validate(&invalid).await;
+        assert!(!report.valid);
+        assert!(report
+            .errors
+            .iter()
+            .any(|e| e.message.contains("namespace 'Team_A'")));
+    }
+}
@@ -0,0 +1,433 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Garbage collection of orphaned artifacts
+//!
+//! Every `gc.interval_secs` the stored artifacts are scanned for
+//! - packages that name a model which is not stored in their namespace,
+//! - scenarios whose target package is not stored,
+//! - scenarios whose package pins a model to a node that is not registered.
+//!
+//! The orphans found by the last scan are served at `GET /api/v1/orphans`.
+//! With `gc.delete`, an artifact that stayed orphaned for
+//! `gc.grace_period_secs` is deleted: a scenario is withdrawn like
+//! `DELETE /api/artifact` does, a package is removed from etcd. The time an
+//! orphan was first seen is kept in memory, so a restart of ApiServer starts
+//! the grace period over.
+
+use crate::artifact::data;
+use common::logd;
+use common::spec::artifact::{Artifact, Package, Scenario};
+use common::spec::namespace;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+static STATE: OnceLock<Mutex<OrphanReport>> = OnceLock::new();
+
+/// Why an artifact is an orphan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrphanReason {
+    /// A model of the package is not stored
+    MissingModel,
+    /// The target package of the scenario is not stored
+    MissingPackage,
+    /// A model of the target package is pinned to a node that is not registered
+    UnregisteredNode,
+}
+
+/// Artifact found by a scan
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Orphan {
+    /// `Scenario` or `Package`
+    pub kind: String,
+    /// Name qualified by the namespace
+    pub name: String,
+    pub reason: OrphanReason,
+    /// Missing model, package or node
+    pub missing: String,
+    /// Unix seconds of the first scan that found the orphan
+    pub first_seen: u64,
+    /// Unix seconds after which the orphan is deleted, if `gc.delete` is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delete_after: Option<u64>,
+}
+
+/// Result of the last scan
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct OrphanReport {
+    /// Unix seconds of the last scan, 0 before the first one
+    pub scanned_at: u64,
+    /// Whether orphans are deleted after the grace period
+    pub delete: bool,
+    pub grace_period_secs: u64,
+    pub orphans: Vec<Orphan>,
+}
+
+/// Stored artifacts and registered nodes a scan looks at
+#[derive(Debug, Default)]
+pub struct Inventory {
+    /// Qualified name and yaml of every stored scenario
+    pub scenarios: Vec<(String, String)>,
+    /// Stored packages by qualified name
+    pub packages: HashMap<String, Package>,
+    /// Qualified names of the stored models
+    pub models: HashSet<String>,
+    /// Hostnames of the registered nodes
+    pub nodes: HashSet<String>,
+}
+
+/// Orphan found in an inventory, before its time is tracked
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Finding {
+    pub kind: &'static str,
+    pub name: String,
+    pub reason: OrphanReason,
+    pub missing: String,
+}
+
+/// Orphaned artifacts of an inventory
+///
+/// Models placed by ActionController (`node: auto` or no node) and packages
+/// with a `selector` or `distributed` pattern are not checked for their
+/// nodes. Without any registered node, nodes are not checked at all.
+pub fn find_orphans(inventory: &Inventory) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    let mut packages: Vec<(&String, &Package)> = inventory.packages.iter().collect();
+    packages.sort_by_key(|(name, _)| name.as_str());
+    for (name, package) in packages {
+        let namespace = package.get_namespace();
+        for model in package.get_models() {
+            let model_name = namespace::qualified_name(&namespace, &model.get_name());
+            if !inventory.models.contains(&model_name) {
+                findings.push(Finding {
+                    kind: "Package",
+                    name: name.clone(),
+                    reason: OrphanReason::MissingModel,
+                    missing: model_name,
+                });
+            }
+        }
+    }
+
+    for (name, yaml) in &inventory.scenarios {
+        let Ok(scenario) = serde_yaml::from_str::<Scenario>(yaml) else {
+            continue;
+        };
+        let package_name =
+            namespace::qualified_name(&scenario.get_namespace(), &scenario.get_targets());
+        let Some(package) = inventory.packages.get(&package_name) else {
+            findings.push(Finding {
+                kind: "Scenario",
+                name: name.clone(),
+                reason: OrphanReason::MissingPackage,
+                missing: package_name,
+            });
+            continue;
+        };
+        if inventory.nodes.is_empty() || !package.get_pattern().is_plain() {
+            continue;
+        }
+        let unregistered = package.get_models().iter().find(|model| {
+            let node = model.get_node();
+            !node.is_empty() && !model.is_auto_node() && !inventory.nodes.contains(&node)
+        });
+        if let Some(model) = unregistered {
+            findings.push(Finding {
+                kind: "Scenario",
+                name: name.clone(),
+                reason: OrphanReason::UnregisteredNode,
+                missing: model.get_node(),
+            });
+        }
+    }
+
+    findings
+}
+
+impl OrphanReport {
+    /// Replace the orphans with the findings of a scan
+    ///
+    /// Orphans found before keep the time they were first seen, orphans no
+    /// longer found are dropped.
+    fn update(&mut self, findings: Vec<Finding>, now: u64, delete: bool, grace: u64) {
+        let first_seen: HashMap<(String, String, OrphanReason), u64> = self
+            .orphans
+            .iter()
+            .map(|o| ((o.kind.clone(), o.name.clone(), o.reason), o.first_seen))
+            .collect();
+
+        self.scanned_at = now;
+        self.delete = delete;
+        self.grace_period_secs = grace;
+        self.orphans = findings
+            .into_iter()
+            .map(|finding| {
+                let key = (
+                    finding.kind.to_string(),
+                    finding.name.clone(),
+                    finding.reason,
+                );
+                let first_seen = first_seen.get(&key).copied().unwrap_or(now);
+                Orphan {
+                    kind: finding.kind.to_string(),
+                    name: finding.name,
+                    reason: finding.reason,
+                    missing: finding.missing,
+                    first_seen,
+                    delete_after: delete.then(|| first_seen.saturating_add(grace)),
+                }
+            })
+            .collect();
+    }
+
+    /// Orphans whose grace period is over
+    fn due(&self, now: u64) -> Vec<Orphan> {
+        let mut due: Vec<Orphan> = self
+            .orphans
+            .iter()
+            .filter(|o| o.delete_after.is_some_and(|after| after <= now))
+            .cloned()
+            .collect();
+        due.dedup_by(|a, b| a.kind == b.kind && a.name == b.name);
+        due
+    }
+}
+
+fn state() -> &'static Mutex<OrphanReport> {
+    STATE.get_or_init(|| Mutex::new(OrphanReport::default()))
+}
+
+fn unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Orphans found by the last scan
+pub fn report() -> OrphanReport {
+    match state().lock() {
+        Ok(report) => report.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+/// Read the stored artifacts and the registered nodes
+async fn read_inventory() -> common::Result<Inventory> {
+    let mut inventory = Inventory::default();
+    let namespaces = data::read_namespaces().await?;
+    for ns in namespaces {
+        inventory
+            .scenarios
+            .extend(data::read_artifacts_of_kind(&ns, "Scenario").await?);
+        for (name, yaml) in data::read_artifacts_of_kind(&ns, "Package").await? {
+            match serde_yaml::from_str::<Package>(&yaml) {
+                Ok(package) => {
+                    inventory.packages.insert(name, package);
+                }
+                Err(e) => logd!(3, "gc: skipping package {}: {}", name, e),
+            }
+        }
+        for (name, _) in data::read_artifacts_of_kind(&ns, "Model").await? {
+            inventory.models.insert(name);
+        }
+    }
+
+    let manager = crate::node::manager::NodeManager::new().map_err(|e| e.to_string())?;
+    inventory.nodes = manager
+        .get_all_nodes()
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|node| node.hostname)
+        .collect();
+    Ok(inventory)
+}
+
+/// Scan for orphans once and delete the ones whose grace period is over
+pub async fn scan() -> common::Result<OrphanReport> {
+    let settings = common::setting::get_config().gc.clone();
+    let findings = find_orphans(&read_inventory().await?);
+    let now = unix_secs();
+    let (count, due) = {
+        let mut report = match state().lock() {
+            Ok(report) => report,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        report.update(findings, now, settings.delete, settings.grace_period_secs);
+        (report.orphans.len(), report.due(now))
+    };
+    if count > 0 {
+        logd!(
+            3,
+            "gc: {} orphaned artifacts, {} to delete",
+            count,
+            due.len()
+        );
+    }
+    for orphan in due {
+        if let Err(e) = delete(&orphan).await {
+            logd!(
+                4,
+                "gc: failed to delete {} {}: {}",
+                orphan.kind,
+                orphan.name,
+                e
+            );
+        }
+    }
+    Ok(report())
+}
+
+/// Delete an orphaned artifact
+async fn delete(orphan: &Orphan) -> common::Result<()> {
+    let key = namespace::qualified_key(&orphan.kind, &orphan.name);
+    if orphan.kind == "Scenario" {
+        let yaml = data::read_from_etcd(&key).await?;
+        crate::manager::withdraw_artifact(&yaml).await?;
+    } else {
+        data::delete_at_etcd(&key).await?;
+    }
+    logd!(
+        3,
+        "gc: deleted {} {} ({:?} {})",
+        orphan.kind,
+        orphan.name,
+        orphan.reason,
+        orphan.missing
+    );
+    Ok(())
+}
+
+/// Scan for orphans every `gc.interval_secs`, not at all if it is 0
+pub async fn run() {
+    loop {
+        let interval = common::setting::get_config().gc.interval_secs;
+        if interval == 0 {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            continue;
+        }
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+        if let Err(e) = scan().await {
+            logd!(4, "gc: scan failed: {}", e);
+        }
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, models: &[(&str, &str)]) -> Package {
+        let models: String = models
+            .iter()
+            .map(|(model, node)| {
+                format!(
+                    "    - name: {}\n      node: {}\n      resources: {{}}\n",
+                    model, node
+                )
+            })
+            .collect();
+        serde_yaml::from_str(&format!(
+            "apiVersion: v1\nkind: Package\nmetadata:\n  name: {}\nspec:\n  pattern:\n    - type: plain\n  models:\n{}",
+            name, models
+        ))
+        .unwrap()
+    }
+
+    fn scenario(name: &str, target: &str) -> (String, String) {
+        (
+            name.to_string(),
+            format!(
+                "apiVersion: v1\nkind: Scenario\nmetadata:\n  name: {}\nspec:\n  condition:\n  action: update\n  target: {}\n",
+                name, target
+            ),
+        )
+    }
+
+    fn inventory() -> Inventory {
+        Inventory {
+            scenarios: vec![
+                scenario("ok", "core"),
+                scenario("lost", "gone"),
+                scenario("pinned", "edge"),
+            ],
+            packages: HashMap::from([
+                ("core".to_string(), package("core", &[("core-a", "HPC")])),
+                (
+                    "edge".to_string(),
+                    package("edge", &[("edge-a", "auto"), ("edge-b", "ZONE9")]),
+                ),
+            ]),
+            models: HashSet::from(["core-a".to_string(), "edge-a".to_string()]),
+            nodes: HashSet::from(["HPC".to_string()]),
+        }
+    }
+
+    #[test]
+    fn test_find_orphans() {
+        let findings: Vec<(&str, String, OrphanReason, String)> = find_orphans(&inventory())
+            .into_iter()
+            .map(|f| (f.kind, f.name, f.reason, f.missing))
+            .collect();
+        assert_eq!(
+            findings,
+            vec![
+                (
+                    "Package",
+                    "edge".to_string(),
+                    OrphanReason::MissingModel,
+                    "edge-b".to_string()
+                ),
+                (
+                    "Scenario",
+                    "lost".to_string(),
+                    OrphanReason::MissingPackage,
+                    "gone".to_string()
+                ),
+                (
+                    "Scenario",
+                    "pinned".to_string(),
+                    OrphanReason::UnregisteredNode,
+                    "ZONE9".to_string()
+                ),
+            ]
+        );
+
+        let mut without_nodes = inventory();
+        without_nodes.nodes.clear();
+        assert!(find_orphans(&without_nodes)
+            .iter()
+            .all(|f| f.reason != OrphanReason::UnregisteredNode));
+    }
+
+    #[test]
+    fn test_grace_period_starts_when_first_seen() {
+        let findings = find_orphans(&inventory());
+        let mut report = OrphanReport::default();
+        report.update(findings.clone(), 100, true, 50);
+        assert!(report.due(149).is_empty());
+
+        // Still orphaned later: first seen stays at 100
+        report.update(findings[1..].to_vec(), 120, true, 50);
+        assert_eq!(report.orphans.len(), 2);
+        assert!(report.orphans.iter().all(|o| o.first_seen == 100));
+        let due: Vec<String> = report.due(150).into_iter().map(|o| o.name).collect();
+        assert_eq!(due, vec!["lost", "pinned"]);
+
+        // Found again after it was resolved: the grace period starts over
+        report.update(findings, 160, true, 50);
+        assert_eq!(report.orphans[0].first_seen, 160);
+
+        report.update(Vec::new(), 170, false, 50);
+        assert!(report.orphans.is_empty());
+        assert!(report.due(1000).is_empty());
+    }
+}
@@ -3,65 +3,25 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
+use crate::node::registry::NodeRegistry;
 use crate::node::NodeManager;
-use base64::Engine;
 use common::apiserver::api_server_connection_server::ApiServerConnection;
 use common::apiserver::{
-    ClusterTopology, GetNodeRequest, GetNodeResponse, GetNodesRequest, GetNodesResponse,
-    GetTopologyRequest, GetTopologyResponse, TopologyType, UpdateTopologyRequest,
-    UpdateTopologyResponse,
+    AssignNodeToClusterRequest, ClusterResponse, ClusterTopology, CreateClusterRequest,
+    DeleteClusterRequest, GetClusterHealthRequest, GetClusterHealthResponse, GetNodeRequest,
+    GetNodeResponse, GetNodesRequest, GetNodesResponse, GetTopologyRequest, GetTopologyResponse,
+    ListClustersRequest, ListClustersResponse, UpdateTopologyRequest, UpdateTopologyResponse,
 };
-use common::etcd;
+use common::eventbus::{Event, EventKind};
 use common::logd;
 use common::nodeagent::fromapiserver::{
-    NodeRegistrationRequest, NodeRegistrationResponse, NodeStatus,
+    ClusterConfig, HeartbeatAck, HeartbeatRequest, HeartbeatResponse, NodeRegistrationRequest,
+    NodeRegistrationResponse, NodeStatus, NodeStatusDelta,
 };
-use prost::Message;
-use tonic::{Request, Response, Status};
-
-/// Simple registry embedded in receiver
-#[derive(Clone)]
-struct NodeRegistry;
-
-impl NodeRegistry {
-    async fn get_topology(
-        &self,
-    ) -> Result<ClusterTopology, Box<dyn std::error::Error + Send + Sync>> {
-        let topology_key = "cluster/topology";
-
-        match etcd::get(topology_key).await {
-            Ok(encoded) => {
-                let buf = base64::engine::general_purpose::STANDARD.decode(&encoded)?;
-                let topology = ClusterTopology::decode(&buf[..])?;
-                Ok(topology)
-            }
-            Err(_) => Ok(ClusterTopology {
-                cluster_id: "default-cluster".to_string(),
-                cluster_name: "Pullpiri Cluster".to_string(),
-                r#type: TopologyType::Embedded.into(),
-                master_nodes: vec![],
-                sub_nodes: vec![],
-                parent_cluster: String::new(),
-                config: std::collections::HashMap::new(),
-            }),
-        }
-    }
-
-    async fn update_topology(
-        &self,
-        topology: ClusterTopology,
-    ) -> Result<ClusterTopology, Box<dyn std::error::Error + Send + Sync>> {
-        let topology_key = "cluster/topology";
-
-        // 인코딩을 제거하고 json string으로 변환
-        let topology_json = serde_json::to_string(&topology)?;
-
-        etcd::put(topology_key, &topology_json).await?;
-
-        logd!(2, "Updated cluster topology: {}", topology.cluster_name);
-        Ok(topology)
-    }
-}
+use common::pullpiri::node::{v1, v1alpha};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status, Streaming};
 
 /// API Server gRPC service handler for clustering functionality
 #[derive(Clone)]
@@ -194,11 +154,7 @@ impl ApiServerConnection for ApiServerReceiver {
                 logd!(1, "Node IP stored at hostname key: nodes/{}", req.hostname);
 
                 // Immediately update the node status to Ready
-                if let Err(e) = self
-                    .node_manager
-                    .update_status(&req.node_id, NodeStatus::Ready)
-                    .await
-                {
+                if let Err(e) = self.node_manager.mark_ready(&req.node_id).await {
                     logd!(5, "Warning: Failed to update node status to Ready: {}", e);
                 } else {
                     logd!(1, "Successfully updated node status to Ready");
@@ -209,14 +165,23 @@ impl ApiServerConnection for ApiServerReceiver {
                     "Node registration successful, cluster token: {}",
                     cluster_token
                 );
+                common::eventbus::publish(
+                    Event::new(
+                        EventKind::NodeRegistered,
+                        "apiserver",
+                        &req.hostname,
+                        "Node registered successfully",
+                    )
+                    .with_attribute("node_id", &req.node_id)
+                    .with_attribute("ip_address", &req.ip_address),
+                );
                 Ok(Response::new(NodeRegistrationResponse {
                     success: true,
                     message: "Node registered successfully".to_string(),
                     cluster_token,
-                    cluster_config: Some(common::nodeagent::fromapiserver::ClusterConfig {
+                    cluster_config: Some(ClusterConfig {
                         master_endpoint: "localhost:47099".to_string(), // apiserver endpoint
-                        heartbeat_interval: 30,
-                        settings: std::collections::HashMap::new(),
+                        ..crate::node::heartbeat::cluster_config()
                     }),
                 }))
             }
@@ -232,16 +197,76 @@ impl ApiServerConnection for ApiServerReceiver {
         }
     }
 
+    async fn heartbeat(
+        &self,
+        request: Request<HeartbeatRequest>,
+    ) -> Result<Response<HeartbeatResponse>, Status> {
+        let req = request.into_inner();
+        logd!(1, "Received Heartbeat from node {}", req.node_id);
+
+        match self.node_manager.get_node(&req.node_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return Err(Status::not_found(format!(
+                    "Node {} is not registered",
+                    req.node_id
+                )))
+            }
+            Err(e) => return Err(Status::unavailable(format!("Failed to load node: {}", e))),
+        }
+
+        if let Err(e) = self
+            .node_manager
+            .report_heartbeat(&req.node_id, req.timestamp)
+            .await
+        {
+            logd!(5, "Failed to update heartbeat for {}: {}", req.node_id, e);
+            return Err(Status::unavailable(format!(
+                "Failed to update heartbeat: {}",
+                e
+            )));
+        }
+
+        Ok(Response::new(HeartbeatResponse {
+            ack: true,
+            updated_config: Some(crate::node::heartbeat::cluster_config()),
+        }))
+    }
+
+    type HeartbeatStreamStream = ReceiverStream<Result<HeartbeatAck, Status>>;
+
+    /// Handles the heartbeat stream of a node, one ack per delta
+    async fn heartbeat_stream(
+        &self,
+        request: Request<Streaming<NodeStatusDelta>>,
+    ) -> Result<Response<Self::HeartbeatStreamStream>, Status> {
+        let inbound = request.into_inner();
+        let (tx_ack, rx_ack) = mpsc::channel(16);
+        tokio::spawn(crate::node::heartbeat::relay_heartbeats(
+            inbound,
+            self.node_manager.clone(),
+            tx_ack,
+        ));
+        Ok(Response::new(ReceiverStream::new(rx_ack)))
+    }
+
     async fn get_topology(
         &self,
-        _request: Request<GetTopologyRequest>,
+        request: Request<GetTopologyRequest>,
     ) -> Result<Response<GetTopologyResponse>, Status> {
-        match self.registry.get_topology().await {
-            Ok(topology) => Ok(Response::new(GetTopologyResponse {
+        let req = request.into_inner();
+
+        match self.registry.get_cluster(&req.cluster_id).await {
+            Ok(Some(topology)) => Ok(Response::new(GetTopologyResponse {
                 topology: Some(topology),
                 success: true,
                 message: "Successfully retrieved topology".to_string(),
             })),
+            Ok(None) => Ok(Response::new(GetTopologyResponse {
+                topology: None,
+                success: false,
+                message: format!("Cluster {} not found", req.cluster_id),
+            })),
             Err(e) => Ok(Response::new(GetTopologyResponse {
                 topology: None,
                 success: false,
@@ -257,7 +282,7 @@ impl ApiServerConnection for ApiServerReceiver {
         let req = request.into_inner();
 
         if let Some(topology) = req.topology {
-            match self.registry.update_topology(topology).await {
+            match self.registry.update_cluster(topology).await {
                 Ok(updated_topology) => Ok(Response::new(UpdateTopologyResponse {
                     updated_topology: Some(updated_topology),
                     success: true,
@@ -277,13 +302,156 @@ impl ApiServerConnection for ApiServerReceiver {
             }))
         }
     }
+
+    async fn list_clusters(
+        &self,
+        _request: Request<ListClustersRequest>,
+    ) -> Result<Response<ListClustersResponse>, Status> {
+        match self.registry.list_clusters().await {
+            Ok(clusters) => Ok(Response::new(ListClustersResponse {
+                clusters,
+                success: true,
+                message: "Successfully retrieved clusters".to_string(),
+            })),
+            Err(e) => Ok(Response::new(ListClustersResponse {
+                clusters: vec![],
+                success: false,
+                message: format!("Failed to retrieve clusters: {}", e),
+            })),
+        }
+    }
+
+    async fn create_cluster(
+        &self,
+        request: Request<CreateClusterRequest>,
+    ) -> Result<Response<ClusterResponse>, Status> {
+        let req = request.into_inner();
+
+        let Some(topology) = req.topology else {
+            return Ok(Response::new(cluster_response(
+                Err("No topology provided in request".into()),
+                "create",
+            )));
+        };
+        let result = self.registry.create_cluster(topology).await;
+        Ok(Response::new(cluster_response(result, "create")))
+    }
+
+    async fn delete_cluster(
+        &self,
+        request: Request<DeleteClusterRequest>,
+    ) -> Result<Response<ClusterResponse>, Status> {
+        let req = request.into_inner();
+
+        let result = self.registry.delete_cluster(&req.cluster_id).await;
+        Ok(Response::new(cluster_response(result, "delete")))
+    }
+
+    async fn assign_node_to_cluster(
+        &self,
+        request: Request<AssignNodeToClusterRequest>,
+    ) -> Result<Response<ClusterResponse>, Status> {
+        let req = request.into_inner();
+        logd!(
+            1,
+            "Assigning node {} to cluster {}",
+            req.node_id,
+            req.cluster_id
+        );
+
+        let result = self
+            .registry
+            .assign_node(&req.cluster_id, &req.node_id)
+            .await;
+        Ok(Response::new(cluster_response(result, "assign node to")))
+    }
+
+    async fn get_cluster_health(
+        &self,
+        request: Request<GetClusterHealthRequest>,
+    ) -> Result<Response<GetClusterHealthResponse>, Status> {
+        let req = request.into_inner();
+
+        match self
+            .registry
+            .get_cluster_health(&req.cluster_id, crate::node::heartbeat::liveness_window())
+            .await
+        {
+            Ok(health) => Ok(Response::new(GetClusterHealthResponse {
+                health: Some(health),
+                success: true,
+                message: "Successfully retrieved cluster health".to_string(),
+            })),
+            Err(e) => Ok(Response::new(GetClusterHealthResponse {
+                health: None,
+                success: false,
+                message: format!("Failed to retrieve cluster health: {}", e),
+            })),
+        }
+    }
+}
+
+/// Node registration of `pullpiri.node.v1`, served next to the unversioned
+/// calls of older NodeAgents by the same handlers
+#[tonic::async_trait]
+impl v1::node_registration_server::NodeRegistration for ApiServerReceiver {
+    async fn register_node(
+        &self,
+        request: Request<v1::RegisterNodeRequest>,
+    ) -> Result<Response<v1::RegisterNodeResponse>, Status> {
+        let request = Request::new(NodeRegistrationRequest::from(request.into_inner()));
+        let response = ApiServerConnection::register_node(self, request).await?;
+        Ok(Response::new(response.into_inner().into()))
+    }
+
+    async fn heartbeat(
+        &self,
+        request: Request<v1::HeartbeatRequest>,
+    ) -> Result<Response<v1::HeartbeatResponse>, Status> {
+        let request = Request::new(HeartbeatRequest::from(request.into_inner()));
+        let response = ApiServerConnection::heartbeat(self, request).await?;
+        Ok(Response::new(response.into_inner().into()))
+    }
+}
+
+#[tonic::async_trait]
+impl v1alpha::node_heartbeat_server::NodeHeartbeat for ApiServerReceiver {
+    type HeartbeatStreamStream = ReceiverStream<Result<HeartbeatAck, Status>>;
+
+    async fn heartbeat_stream(
+        &self,
+        request: Request<Streaming<NodeStatusDelta>>,
+    ) -> Result<Response<Self::HeartbeatStreamStream>, Status> {
+        ApiServerConnection::heartbeat_stream(self, request).await
+    }
+}
+
+/// Build the response of a cluster management request
+///
+/// `operation` completes "Failed to ... cluster" in error messages.
+fn cluster_response(
+    result: Result<ClusterTopology, Box<dyn std::error::Error + Send + Sync>>,
+    operation: &str,
+) -> ClusterResponse {
+    match result {
+        Ok(topology) => ClusterResponse {
+            message: format!("Successfully processed cluster {}", topology.cluster_id),
+            topology: Some(topology),
+            success: true,
+        },
+        Err(e) => ClusterResponse {
+            topology: None,
+            success: false,
+            message: format!("Failed to {} cluster: {}", operation, e),
+        },
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use common::apiserver::NodeInfo;
-    use common::apiserver::TopologyType;
+    use common::apiserver::{ClusterTopology, TopologyType};
     use common::nodeagent::fromapiserver::{NodeRole, NodeType, ResourceInfo};
     use std::collections::HashMap;
     use tokio;
@@ -428,6 +596,21 @@ mod tests {
         assert!(response.node.is_none());
     }
 
+    #[tokio::test]
+    async fn test_heartbeat_unknown_node_rejected() {
+        let receiver = ApiServerReceiver::new();
+        let request = Request::new(HeartbeatRequest {
+            node_id: "non-existent-node".to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+        });
+
+        let status = receiver.heartbeat(request).await.unwrap_err();
+        assert!(matches!(
+            status.code(),
+            tonic::Code::NotFound | tonic::Code::Unavailable
+        ));
+    }
+
     #[tokio::test]
     async fn test_register_node_success() {
         let receiver = ApiServerReceiver::new();
@@ -483,7 +666,7 @@ mod tests {
     #[tokio::test]
     async fn test_get_topology_success() {
         let receiver = ApiServerReceiver::new();
-        let request = Request::new(GetTopologyRequest {});
+        let request = Request::new(GetTopologyRequest::default());
 
         let result = receiver.get_topology(request).await;
     }
@@ -596,8 +779,8 @@ mod tests {
         let receiver2 = receiver1.clone();
 
         // Test that ApiServerReceiver can be cloned
-        let request1 = Request::new(GetTopologyRequest {});
-        let request2 = Request::new(GetTopologyRequest {});
+        let request1 = Request::new(GetTopologyRequest::default());
+        let request2 = Request::new(GetTopologyRequest::default());
 
         let result1 = receiver1.get_topology(request1).await;
         let result2 = receiver2.get_topology(request2).await;
@@ -0,0 +1,101 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Running gRPC message sending to actioncontroller
+
+use common::actioncontroller::{
+    action_controller_connection_client::ActionControllerConnectionClient, connect_server,
+    DrainNodeRequest, DrainNodeResponse, MigrateNodeRequest, MigrateNodeResponse,
+    TriggerActionRequest, TriggerActionResponse,
+};
+use common::rpc::RpcClient;
+use tonic::{Response, Status};
+
+/// Ask actioncontroller to run the action of a scenario again
+///
+/// ### Parametets
+/// * `scenario_name: &str` - name of the scenario to re-deploy
+/// * `dry_run: bool` - only return the execution plan of the action
+pub async fn trigger_action(
+    scenario_name: &str,
+    dry_run: bool,
+) -> Result<Response<TriggerActionResponse>, Status> {
+    // Re-deploying a scenario takes as long as starting its models
+    RpcClient::new("ActionController", connect_server())
+        .with_call_timeout(None)
+        .call(|channel| async move {
+            ActionControllerConnectionClient::new(channel)
+                .trigger_action(common::trace::request(TriggerActionRequest {
+                    scenario_name: scenario_name.to_string(),
+                    dry_run,
+                    action: String::new(),
+                }))
+                .await
+        })
+        .await
+}
+
+/// Ask actioncontroller to restart the models of a scenario with their saved pods
+///
+/// ### Parametets
+/// * `scenario_name: &str` - name of the scenario whose models restart
+pub async fn restart_scenario(
+    scenario_name: &str,
+) -> Result<Response<TriggerActionResponse>, Status> {
+    // The update action restarts every model of the target package
+    RpcClient::new("ActionController", connect_server())
+        .with_call_timeout(None)
+        .call(|channel| async move {
+            ActionControllerConnectionClient::new(channel)
+                .trigger_action(common::trace::request(TriggerActionRequest {
+                    scenario_name: scenario_name.to_string(),
+                    dry_run: false,
+                    action: "update".to_string(),
+                }))
+                .await
+        })
+        .await
+}
+
+/// Ask actioncontroller to move the models off a node in maintenance
+///
+/// ### Parametets
+/// * `node: &str` - hostname of the node
+pub async fn drain_node(node: &str) -> Result<Response<DrainNodeResponse>, Status> {
+    // Migrating models takes as long as stopping and starting them
+    RpcClient::new("ActionController", connect_server())
+        .with_call_timeout(None)
+        .call(|channel| async move {
+            ActionControllerConnectionClient::new(channel)
+                .drain_node(common::trace::request(DrainNodeRequest {
+                    node: node.to_string(),
+                }))
+                .await
+        })
+        .await
+}
+
+/// Ask actioncontroller to move every model of a failed node to another node
+///
+/// ### Parametets
+/// * `source: &str` - hostname of the node the models leave
+/// * `target: &str` - hostname of the node they move to, empty to choose one per model
+pub async fn migrate_node(
+    source: &str,
+    target: &str,
+) -> Result<Response<MigrateNodeResponse>, Status> {
+    // Migrating models takes as long as stopping and starting them
+    RpcClient::new("ActionController", connect_server())
+        .with_call_timeout(None)
+        .call(|channel| async move {
+            ActionControllerConnectionClient::new(channel)
+                .migrate_node(common::trace::request(MigrateNodeRequest {
+                    source_node: source.to_string(),
+                    target_node: target.to_string(),
+                }))
+                .await
+        })
+        .await
+}
@@ -7,9 +7,11 @@
 
 use common::filtergateway::{
     connect_server, filter_gateway_connection_client::FilterGatewayConnectionClient,
-    HandleScenarioRequest, HandleScenarioResponse,
+    CheckPolicyRequest, CheckPolicyResponse, HandleScenarioRequest, HandleScenarioResponse,
+    ListTopicsRequest, ListTopicsResponse, SubscribeTopicRequest, TopicRequest, TopicResponse,
 };
-use tonic::{Request, Response, Status};
+use common::rpc::RpcClient;
+use tonic::{Response, Status};
 
 /// Send scenario information to filtergateway via gRPC
 ///
@@ -24,10 +26,14 @@ pub async fn send(
     use std::time::Instant;
     let start = Instant::now();
 
-    let mut client = FilterGatewayConnectionClient::connect(connect_server())
-        .await
-        .map_err(|e| Status::unavailable(format!("Failed to connect to FilterGateway: {}", e)))?;
-    let response = client.handle_scenario(Request::new(scenario)).await;
+    let scenario = &scenario;
+    let response = RpcClient::new("FilterGateway", connect_server())
+        .call(|channel| async move {
+            FilterGatewayConnectionClient::new(channel)
+                .handle_scenario(common::trace::request(scenario.clone()))
+                .await
+        })
+        .await;
 
     let elapsed = start.elapsed();
     common::logd!(1, "send: elapsed = {:?}", elapsed);
@@ -35,6 +41,74 @@ pub async fn send(
     response
 }
 
+/// Check a scenario against the allow/deny policies of FilterGateway
+pub async fn check_policy(scenario: &str) -> Result<Response<CheckPolicyResponse>, Status> {
+    let request = &CheckPolicyRequest {
+        scenario: scenario.to_string(),
+    };
+    RpcClient::new("FilterGateway", connect_server())
+        .call(|channel| async move {
+            FilterGatewayConnectionClient::new(channel)
+                .check_policy(common::trace::request(request.clone()))
+                .await
+        })
+        .await
+}
+
+/// List the DDS topics FilterGateway listens to
+pub async fn list_topics() -> Result<Response<ListTopicsResponse>, Status> {
+    RpcClient::new("FilterGateway", connect_server())
+        .call(|channel| async move {
+            FilterGatewayConnectionClient::new(channel)
+                .list_topics(common::trace::request(ListTopicsRequest {}))
+                .await
+        })
+        .await
+}
+
+/// Subscribe FilterGateway to a DDS topic, kept across its restarts
+pub async fn subscribe_topic(
+    request: SubscribeTopicRequest,
+) -> Result<Response<TopicResponse>, Status> {
+    let request = &request;
+    RpcClient::new("FilterGateway", connect_server())
+        .call(|channel| async move {
+            FilterGatewayConnectionClient::new(channel)
+                .subscribe_topic(common::trace::request(request.clone()))
+                .await
+        })
+        .await
+}
+
+/// Change of a topic subscribed with [`subscribe_topic`]
+#[derive(Debug, Clone, Copy)]
+pub enum TopicChange {
+    Pause,
+    Resume,
+    Unsubscribe,
+}
+
+/// Pause, resume or unsubscribe a topic subscribed with [`subscribe_topic`]
+pub async fn change_topic(
+    topic: &str,
+    change: TopicChange,
+) -> Result<Response<TopicResponse>, Status> {
+    let request = &TopicRequest {
+        topic: topic.to_string(),
+    };
+    RpcClient::new("FilterGateway", connect_server())
+        .call(|channel| async move {
+            let mut client = FilterGatewayConnectionClient::new(channel);
+            let request = common::trace::request(request.clone());
+            match change {
+                TopicChange::Pause => client.pause_topic(request).await,
+                TopicChange::Resume => client.resume_topic(request).await,
+                TopicChange::Unsubscribe => client.unsubscribe_topic(request).await,
+            }
+        })
+        .await
+}
+
 //UNIT TEST CASES
 
 #[cfg(test)]
@@ -44,7 +118,12 @@ mod tests {
         filter_gateway_connection_server::{
             FilterGatewayConnection, FilterGatewayConnectionServer,
         },
-        Action, HandleScenarioRequest, HandleScenarioResponse,
+        Action, HandleScenarioRequest, HandleScenarioResponse, RegisterDdsTypeRequest,
+        RegisterDdsTypeResponse,
+    };
+    use common::filtergateway::{
+        CheckPolicyRequest, CheckPolicyResponse, ListTopicsRequest, ListTopicsResponse,
+        SubscribeTopicRequest, TopicRequest, TopicResponse, VehicleMode, VehicleModeRequest,
     };
     use std::net::SocketAddr;
     use tokio::net::TcpListener;
@@ -122,6 +201,65 @@ spec:
                 desc: format!("Mock handled: {:?}", req.action),
             }))
         }
+
+        async fn register_dds_type(
+            &self,
+            _request: Request<RegisterDdsTypeRequest>,
+        ) -> Result<Response<RegisterDdsTypeResponse>, Status> {
+            Err(Status::unimplemented("Mock does not register DDS types"))
+        }
+
+        async fn list_topics(
+            &self,
+            _request: Request<ListTopicsRequest>,
+        ) -> Result<Response<ListTopicsResponse>, Status> {
+            Ok(Response::new(ListTopicsResponse { topics: vec![] }))
+        }
+
+        async fn subscribe_topic(
+            &self,
+            _request: Request<SubscribeTopicRequest>,
+        ) -> Result<Response<TopicResponse>, Status> {
+            Err(Status::unimplemented("Mock does not subscribe topics"))
+        }
+
+        async fn pause_topic(
+            &self,
+            _request: Request<TopicRequest>,
+        ) -> Result<Response<TopicResponse>, Status> {
+            Err(Status::unimplemented("Mock does not pause topics"))
+        }
+
+        async fn resume_topic(
+            &self,
+            _request: Request<TopicRequest>,
+        ) -> Result<Response<TopicResponse>, Status> {
+            Err(Status::unimplemented("Mock does not resume topics"))
+        }
+
+        async fn unsubscribe_topic(
+            &self,
+            _request: Request<TopicRequest>,
+        ) -> Result<Response<TopicResponse>, Status> {
+            Err(Status::unimplemented("Mock does not unsubscribe topics"))
+        }
+
+        async fn get_vehicle_mode(
+            &self,
+            _request: Request<VehicleModeRequest>,
+        ) -> Result<Response<VehicleMode>, Status> {
+            Ok(Response::new(VehicleMode::default()))
+        }
+
+        async fn check_policy(
+            &self,
+            _request: Request<CheckPolicyRequest>,
+        ) -> Result<Response<CheckPolicyResponse>, Status> {
+            Ok(Response::new(CheckPolicyResponse {
+                allowed: true,
+                ..Default::default()
+            }))
+        }
     }
 
     /// Starts a mock gRPC server on a random available port
@@ -151,7 +289,9 @@ spec:
         let mut client = FilterGatewayConnectionClient::connect(format!("http://{}", addr))
             .await
             .unwrap();
-        client.handle_scenario(Request::new(scenario)).await
+        client
+            .handle_scenario(common::trace::request(scenario))
+            .await
     }
 
     // === TEST CASES ===
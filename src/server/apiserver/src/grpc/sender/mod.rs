@@ -5,6 +5,8 @@
 
 //! Running gRPC message sending
 
+pub mod actioncontroller;
 pub mod filtergateway;
+pub mod monitoringserver;
 pub mod nodeagent;
 pub mod statemanager;
@@ -0,0 +1,27 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Running gRPC message sending to monitoringserver
+
+use common::monitoringserver::{
+    connect_server, monitoring_server_connection_client::MonitoringServerConnectionClient,
+    GetContainerLogsRequest, GetContainerLogsResponse,
+};
+use common::rpc::RpcClient;
+use tonic::{Response, Status};
+
+/// Read the last lines of a container streamed by its NodeAgent
+pub async fn get_container_logs(
+    request: GetContainerLogsRequest,
+) -> Result<Response<GetContainerLogsResponse>, Status> {
+    let request = &request;
+    RpcClient::new("MonitoringServer", connect_server())
+        .call(|channel| async move {
+            MonitoringServerConnectionClient::new(channel)
+                .get_container_logs(common::trace::request(request.clone()))
+                .await
+        })
+        .await
+}
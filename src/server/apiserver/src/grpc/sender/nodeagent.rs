@@ -3,79 +3,74 @@
 * SPDX-License-Identifier: Apache-2.0
 */
 use common::logd;
-use common::nodeagent::fromapiserver::{HandleYamlRequest, HandleYamlResponse};
+use common::nodeagent::fromapiserver::{
+    ContainerLogsRequest, ContainerLogsResponse, HandleYamlRequest, HandleYamlResponse,
+    ListContainersRequest, ListContainersResponse,
+};
 use common::nodeagent::node_agent_connection_client::NodeAgentConnectionClient;
+use common::rpc::RpcClient;
 use tonic::{Request, Response, Status};
 
+/// Client of the NodeAgent of a node
+fn node_client(node_ip: &str) -> RpcClient {
+    // Fix 0.0.0.0 to actual host IP for NodeAgent connection
+    let fixed_ip = if node_ip == "0.0.0.0" {
+        "127.0.0.1"
+    } else {
+        node_ip
+    };
+    RpcClient::new("NodeAgent", format!("http://{}:47004", fixed_ip))
+}
+
 // Send to a specific node using its IP address
 pub async fn send_to_node(
     action: HandleYamlRequest,
     node_ip: String,
 ) -> Result<Response<HandleYamlResponse>, Status> {
-    // Fix 0.0.0.0 to actual host IP for NodeAgent connection
-    let fixed_ip = if node_ip == "0.0.0.0" {
-        "127.0.0.1".to_string()
-    } else {
-        node_ip.clone()
-    };
-    let addr = format!("http://{}:47004", fixed_ip);
-
-    logd!(2, "Attempting to connect to NodeAgent at: {}", addr);
-
-    // Attempting to connect with a timeout
-    let client_result = tokio::time::timeout(
-        std::time::Duration::from_secs(5),
-        NodeAgentConnectionClient::connect(addr.clone()),
-    )
-    .await;
-
-    match client_result {
-        Ok(Ok(mut client)) => {
-            logd!(2, "Successfully connected to NodeAgent, sending request...");
-            match tokio::time::timeout(
-                std::time::Duration::from_secs(1),
-                client.handle_yaml(Request::new(action)),
-            )
-            .await
-            {
-                Ok(result) => match result {
-                    Ok(response) => {
-                        logd!(1, "Request to NodeAgent successful");
-                        Ok(response)
-                    }
-                    Err(e) => {
-                        logd!(5, "Error calling NodeAgent handle_yaml: {}", e);
-                        Err(Status::internal(format!(
-                            "Error calling NodeAgent handle_yaml: {}",
-                            e
-                        )))
-                    }
-                },
-                Err(_) => {
-                    logd!(5, "Timeout while waiting for NodeAgent to respond");
-                    Err(Status::deadline_exceeded(
-                        "Timeout while waiting for NodeAgent to respond",
-                    ))
-                }
-            }
-        }
-        Ok(Err(e)) => {
-            logd!(5, "Error connecting to NodeAgent at {}: {}", addr, e);
-            logd!(5, "Connection error details: {:?}", e);
-            Err(Status::unavailable(format!(
-                "Failed to connect to NodeAgent at {}: {}",
-                addr, e
-            )))
-        }
-        Err(_) => {
-            logd!(5, "Timeout while connecting to NodeAgent at {}", addr);
-            Err(Status::deadline_exceeded(format!(
-                "Timeout while connecting to NodeAgent at {}",
-                addr
-            )))
-        }
+    let client = node_client(&node_ip);
+    logd!(2, "Sending request to NodeAgent at: {}", client.addr());
+
+    let action = &action;
+    let result = client
+        .call(|channel| async move {
+            NodeAgentConnectionClient::new(channel)
+                .handle_yaml(common::trace::request(action.clone()))
+                .await
+        })
+        .await;
+    match &result {
+        Ok(_) => logd!(1, "Request to NodeAgent successful"),
+        Err(e) => logd!(5, "Error calling NodeAgent handle_yaml: {}", e.message()),
     }
+    result
 }
+
+/// Read the logs of a container from the NodeAgent of its node
+pub async fn get_container_logs(
+    request: ContainerLogsRequest,
+    node_ip: String,
+) -> Result<Response<ContainerLogsResponse>, Status> {
+    let request = &request;
+    node_client(&node_ip)
+        .call(|channel| async move {
+            NodeAgentConnectionClient::new(channel)
+                .get_container_logs(Request::new(request.clone()))
+                .await
+        })
+        .await
+}
+
+/// List the containers of a node with their current state
+pub async fn list_containers(node_ip: String) -> Result<Response<ListContainersResponse>, Status> {
+    node_client(&node_ip)
+        .call(|channel| async move {
+            NodeAgentConnectionClient::new(channel)
+                .list_containers(Request::new(ListContainersRequest {}))
+                .await
+        })
+        .await
+}
+
 #[allow(dead_code)]
 pub async fn send(action: HandleYamlRequest) -> Result<Response<HandleYamlResponse>, Status> {
     // Use the node lookup module to get the node IP
@@ -193,6 +188,16 @@ spec:
         }
     }
 
+    #[tokio::test]
+    async fn test_get_container_logs_connection_failure() {
+        let request = ContainerLogsRequest {
+            container_id: "nginx".to_string(),
+            tail: 10,
+        };
+        let result = get_container_logs(request, "127.0.0.1:1".to_string()).await;
+        assert!(result.is_err());
+    }
+
     #[tokio::test]
     async fn test_send_to_node_connection_failure() {
         let action = create_test_handle_yaml_request();
@@ -9,13 +9,16 @@
 //! the StateManager service via gRPC. It manages connection lifecycle, handles
 //! request routing, and provides ASIL-compliant state change messaging capabilities.
 //!
-//! The client implements lazy connection establishment, automatic retry logic,
-//! and comprehensive error handling to ensure reliable communication with the
+//! The client implements lazy connection establishment, retries with backoff
+//! through [`common::rpc`], and comprehensive error handling to ensure reliable communication with the
 //! StateManager in the Pullpiri framework.
 
+use common::rpc::RpcClient;
 use common::statemanager::{
-    connect_server, state_manager_connection_client::StateManagerConnectionClient, StateChange,
-    StateChangeResponse,
+    connect_server, state_manager_connection_client::StateManagerConnectionClient,
+    ListResourceStatesRequest, ListResourceStatesResponse, ResetQuarantineRequest,
+    ResetQuarantineResponse, ResourceStateHistoryRequest, ResourceStateHistoryResponse,
+    StateChange, StateChangeResponse,
 };
 use tonic::{Request, Status};
 
@@ -38,12 +41,11 @@ use tonic::{Request, Status};
 /// - Includes context information for safety analysis and audit trails
 #[derive(Clone)]
 pub struct StateManagerSender {
-    /// Cached gRPC client connection to the StateManager service.
+    /// Client of the StateManager service.
     ///
-    /// This connection is established lazily on the first request and reused
-    /// for subsequent requests to optimize performance. Set to None initially
-    /// and populated when ensure_connected() is called.
-    client: Option<StateManagerConnectionClient<tonic::transport::Channel>>,
+    /// The channel is established lazily on the first request and reused
+    /// for subsequent requests to optimize performance.
+    rpc: RpcClient,
 }
 
 impl Default for StateManagerSender {
@@ -66,46 +68,8 @@ impl StateManagerSender {
     /// # Returns
     /// * `Self` - New StateManagerSender instance ready for use
     pub fn new() -> Self {
-        Self { client: None }
-    }
-
-    /// Ensures a gRPC connection to the StateManager exists and is ready for use.
-    ///
-    /// This method implements lazy connection establishment by checking if a connection
-    /// already exists and creating one if necessary. It uses the common::statemanager
-    /// configuration to determine the StateManager's network location.
-    ///
-    /// # Connection Process
-    /// 1. Check if a connection already exists
-    /// 2. If not, attempt to establish a new gRPC connection
-    /// 3. Store the connection for reuse in subsequent requests
-    /// 4. Return success or detailed error information
-    ///
-    /// # Returns
-    /// * `Result<(), Status>` - Success if connection is available, error otherwise
-    ///
-    /// # Errors
-    /// * `Status::unknown` - Connection establishment failed (network, service unavailable, etc.)
-    ///
-    /// # Future Enhancements
-    /// - Add connection health checking and automatic reconnection
-    /// - Implement exponential backoff for connection retries
-    /// - Add connection pooling for high-throughput scenarios
-    async fn ensure_connected(&mut self) -> Result<(), Status> {
-        if self.client.is_none() {
-            match StateManagerConnectionClient::connect(connect_server()).await {
-                Ok(client) => {
-                    self.client = Some(client);
-                    Ok(())
-                }
-                Err(e) => Err(Status::unknown(format!(
-                    "Failed to connect to StateManager: {}",
-                    e
-                ))),
-            }
-        } else {
-            // Connection already exists and ready for use
-            Ok(())
+        Self {
+            rpc: RpcClient::new("StateManager", connect_server()),
         }
     }
 
@@ -135,7 +99,6 @@ impl StateManagerSender {
     ///   - Error codes and details if applicable
     ///
     /// # Errors
-    /// * `Status::unknown` - Connection failure or client not connected
     /// * `Status::unavailable` - StateManager service unavailable
     /// * `Status::invalid_argument` - Malformed StateChange message
     /// * `Status::deadline_exceeded` - Request timeout (ASIL timing violation)
@@ -149,16 +112,80 @@ impl StateManagerSender {
         &mut self,
         state_change: StateChange,
     ) -> Result<tonic::Response<StateChangeResponse>, Status> {
-        // Ensure we have an active gRPC connection before sending
-        self.ensure_connected().await?;
+        let state_change = &state_change;
+        self.rpc
+            .call(|channel| async move {
+                StateManagerConnectionClient::new(channel)
+                    .send_state_change(common::trace::request(state_change.clone()))
+                    .await
+            })
+            .await
+    }
 
-        if let Some(client) = &mut self.client {
-            // Send the state change message via gRPC
-            client.send_state_change(Request::new(state_change)).await
-        } else {
-            // This should never happen due to ensure_connected, but provide safety fallback
-            Err(Status::unknown("Client not connected"))
-        }
+    /// Reads the last state transitions of a resource from the StateManager.
+    ///
+    /// # Arguments
+    /// * `request` - resource type and name, optional time range and limit
+    ///
+    /// # Returns
+    /// * `Result<tonic::Response<ResourceStateHistoryResponse>, Status>` - transitions,
+    ///   oldest first
+    pub async fn get_resource_state_history(
+        &mut self,
+        request: ResourceStateHistoryRequest,
+    ) -> Result<tonic::Response<ResourceStateHistoryResponse>, Status> {
+        let request = &request;
+        self.rpc
+            .call(|channel| async move {
+                StateManagerConnectionClient::new(channel)
+                    .get_resource_state_history(Request::new(request.clone()))
+                    .await
+            })
+            .await
+    }
+
+    /// Reads the current state of the resources known to the StateManager.
+    ///
+    /// # Arguments
+    /// * `request` - optional resource type, state and node filters and limit
+    ///
+    /// # Returns
+    /// * `Result<tonic::Response<ListResourceStatesResponse>, Status>` - matching
+    ///   resources
+    pub async fn list_resource_states(
+        &mut self,
+        request: ListResourceStatesRequest,
+    ) -> Result<tonic::Response<ListResourceStatesResponse>, Status> {
+        let request = &request;
+        self.rpc
+            .call(|channel| async move {
+                StateManagerConnectionClient::new(channel)
+                    .list_resource_states(Request::new(request.clone()))
+                    .await
+            })
+            .await
+    }
+
+    /// Releases a scenario the StateManager quarantined for flapping.
+    ///
+    /// # Arguments
+    /// * `request` - name of the scenario and why it is released
+    ///
+    /// # Returns
+    /// * `Result<tonic::Response<ResetQuarantineResponse>, Status>` - whether the
+    ///   scenario was quarantined and its state afterwards
+    pub async fn reset_quarantine(
+        &mut self,
+        request: ResetQuarantineRequest,
+    ) -> Result<tonic::Response<ResetQuarantineResponse>, Status> {
+        let request = &request;
+        self.rpc
+            .call(|channel| async move {
+                StateManagerConnectionClient::new(channel)
+                    .reset_quarantine(Request::new(request.clone()))
+                    .await
+            })
+            .await
     }
 }
 
@@ -4,7 +4,11 @@
 */
 pub mod artifact;
 pub mod diagnostics;
+pub mod gc;
 pub mod grpc;
 pub mod manager;
 pub mod node;
+pub mod reconcile;
 pub mod route;
+pub mod summary;
+pub mod trigger;
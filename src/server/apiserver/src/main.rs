@@ -15,10 +15,14 @@
 //!   that a filter can be created.
 
 mod artifact;
+mod gc;
 mod grpc;
 mod manager;
 mod node;
+mod reconcile;
 mod route;
+mod summary;
+mod trigger;
 
 use common::logd;
 use common::logd::logger;
@@ -33,7 +37,11 @@ fn main() {
 #[tokio::main]
 async fn main() {
     let _ = logger::init_async_logger("apiserver").await;
+    common::trace::init_exporter("apiserver");
     logd!(1, "initiailize api server");
+    if let Err(e) = common::startup::wait_for_dependencies("apiserver").await {
+        logd!(4, "apiserver starts without its dependencies, {}", e);
+    }
 
     manager::initialize().await
 }
@@ -4,15 +4,51 @@
  */
 
 //! Controls the flow of data between each module.
-use crate::node::node_lookup::{find_guest_nodes, find_node_by_hostname, get_node_ip};
+use crate::node::registry::NodeRegistry;
 use common::apiserver::api_server_connection_server::ApiServerConnectionServer;
+use common::eventbus::{event_bus_connection_server::EventBusConnectionServer, EventBroker};
 use common::filtergateway::{Action, HandleScenarioRequest};
+use common::health;
 use common::logd;
-use common::nodeagent::fromapiserver::HandleYamlRequest;
+use common::pullpiri::node::v1::node_registration_server::NodeRegistrationServer;
+use common::pullpiri::node::v1alpha::node_heartbeat_server::NodeHeartbeatServer;
+use common::supervise::{self, RestartPolicy};
+use std::collections::HashMap;
 use tonic::transport::Server;
 
+/// Readiness check of the node registry restored from etcd
+const CHECK_NODE_REGISTRY: &str = "node_registry";
+/// Readiness check of the reconciliation and reload of stored scenarios
+const CHECK_SCENARIOS: &str = "scenarios";
+/// Interval of the etcd readiness probe
+const ETCD_PROBE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
 /// Launch REST API listener, gRPC server, and reload scenario data in etcd
 pub async fn initialize() {
+    health::init(
+        "apiserver",
+        &[
+            health::CHECK_ETCD,
+            health::CHECK_GRPC_SERVER,
+            CHECK_NODE_REGISTRY,
+            CHECK_SCENARIOS,
+        ],
+    );
+    tokio::spawn(health::watch_etcd(ETCD_PROBE_INTERVAL));
+
+    // Keys written before the stack was given an instance name are moved
+    // under its prefix before anything reads them.
+    if common::setting::get_config().etcd.migrate_keys {
+        match common::etcd::migrate_to_prefix().await {
+            Ok(count) => logd!(2, "Moved {} etcd keys under the instance prefix", count),
+            Err(e) => logd!(
+                5,
+                "Failed to move etcd keys under the instance prefix: {}",
+                e
+            ),
+        }
+    }
+
     // 먼저 호스트 노드를 etcd에 등록합니다.
     if let Err(e) = register_host_node().await {
         logd!(5, "Failed to register host node: {:?}", e);
@@ -20,14 +56,73 @@ pub async fn initialize() {
         logd!(2, "Host node registered successfully");
     }
 
+    // etcd에 저장된 노드 정보로 클러스터 상태를 복원합니다.
+    match NodeRegistry
+        .rebuild_from_etcd(crate::node::heartbeat::liveness_window())
+        .await
+    {
+        Ok(_) => health::set_ready(CHECK_NODE_REGISTRY),
+        Err(e) => {
+            logd!(5, "Failed to rebuild node registry: {:?}", e);
+            health::set_not_ready(CHECK_NODE_REGISTRY, format!("{:?}", e));
+        }
+    }
+    supervise::spawn("stale_nodes", RestartPolicy::default(), monitor_stale_nodes);
+    supervise::spawn("gc", RestartPolicy::default(), crate::gc::run);
+    supervise::spawn(
+        "host_settings",
+        RestartPolicy::default(),
+        watch_host_settings,
+    );
+
     tokio::join!(
         crate::route::launch_tcp_listener(),
         start_grpc_server(),
-        reload()
+        async {
+            crate::reconcile::reconcile().await;
+            reload().await;
+            health::set_ready(CHECK_SCENARIOS);
+        }
     );
 }
 
-/// Start gRPC server for node communications
+/// Periodically mark nodes that stopped sending heartbeats as NotReady
+///
+/// Nodes are checked three times per liveness window of the `heartbeat`
+/// settings, which may change while running.
+async fn monitor_stale_nodes() {
+    loop {
+        let window = crate::node::heartbeat::liveness_window();
+        tokio::time::sleep(tokio::time::Duration::from_secs((window / 3).max(1))).await;
+        if let Err(e) = NodeRegistry.mark_stale_nodes(window).await {
+            logd!(4, "Stale node check failed: {:?}", e);
+        }
+    }
+}
+
+/// Register the host node again whenever its settings.yaml entry changes
+async fn watch_host_settings() {
+    let mut settings = common::setting::watch_config();
+    let mut host = settings.borrow().host.clone();
+    while settings.changed().await.is_ok() {
+        let reloaded = settings.borrow_and_update().host.clone();
+        if reloaded == host {
+            continue;
+        }
+        logd!(
+            2,
+            "Host settings changed to {} ({}), registering host node again",
+            reloaded.name,
+            reloaded.ip
+        );
+        host = reloaded;
+        if let Err(e) = register_host_node().await {
+            logd!(5, "Failed to register reloaded host node: {:?}", e);
+        }
+    }
+}
+
+/// Start gRPC server for node communications and the event bus
 async fn start_grpc_server() {
     let addr = common::apiserver::open_grpc_server()
         .parse()
@@ -37,10 +132,21 @@ async fn start_grpc_server() {
 
     logd!(3, "ApiServer gRPC listening on {}", addr);
 
-    let _ = Server::builder()
-        .add_service(ApiServerConnectionServer::new(grpc_service))
+    health::set_ready(health::CHECK_GRPC_SERVER);
+    let result = Server::builder()
+        .layer(common::trace::GrpcTraceLayer)
+        .add_service(ApiServerConnectionServer::new(grpc_service.clone()))
+        .add_service(NodeRegistrationServer::new(grpc_service.clone()))
+        .add_service(NodeHeartbeatServer::new(grpc_service))
+        .add_service(EventBusConnectionServer::new(EventBroker::new()))
+        .add_service(common::reflection::service())
         .serve(addr)
         .await;
+    let reason = match result {
+        Ok(()) => "stopped".to_string(),
+        Err(e) => e.to_string(),
+    };
+    health::set_not_ready(health::CHECK_GRPC_SERVER, reason);
 }
 
 /// (under construction) Send request message to pullpiri cloud
@@ -134,12 +240,15 @@ async fn reload() {
 ///
 /// ### Parameters
 /// * `body: &str` - whole yaml string of pullpiri artifact
+/// * `values: &HashMap<String, String>` - values of the template parameters
 /// ### Description
+/// substitute the template parameters of the artifact
 /// write artifact in etcd
 /// (optional) make yaml, kube files for Bluechi
 /// send a gRPC message to gateway
-pub async fn apply_artifact(body: &str) -> common::Result<()> {
-    let scenario = crate::artifact::apply(body).await?;
+pub async fn apply_artifact(body: &str, values: &HashMap<String, String>) -> common::Result<()> {
+    let body = crate::artifact::template::render(body, values)?;
+    let scenario = crate::artifact::apply(&body).await?;
 
     let req: HandleScenarioRequest = HandleScenarioRequest {
         action: Action::Apply.into(),
@@ -149,6 +258,78 @@ pub async fn apply_artifact(body: &str) -> common::Result<()> {
     Ok(())
 }
 
+/// Apply artifact all-or-nothing
+///
+/// ### Parameters
+/// * `body: &str` - whole yaml string of pullpiri artifact
+/// * `values: &HashMap<String, String>` - values of the template parameters
+/// ### Description
+/// substitute the template parameters of the artifact
+/// validate every document, then write all of them in one etcd batch
+/// send a gRPC message to gateway for each committed scenario
+pub async fn apply_artifact_transaction(
+    body: &str,
+    values: &HashMap<String, String>,
+) -> crate::artifact::transaction::ApplyReport {
+    let body = match crate::artifact::template::render(body, values) {
+        Ok(body) => body,
+        Err(e) => {
+            return crate::artifact::transaction::ApplyReport {
+                errors: vec![e.to_string()],
+                ..Default::default()
+            }
+        }
+    };
+    let mut report = crate::artifact::transaction::apply(&body).await;
+
+    for scenario in report.scenarios.clone() {
+        let req = HandleScenarioRequest {
+            action: Action::Apply.into(),
+            scenario,
+        };
+        if let Err(e) = crate::grpc::sender::filtergateway::send(req).await {
+            report
+                .warnings
+                .push(format!("Failed to notify filtergateway: {}", e));
+        }
+    }
+    report
+}
+
+/// Import an offline bundle
+///
+/// ### Parameters
+/// * `path: &str` - bundle file on the local file system
+/// ### Description
+/// verify the bundle and load its container images
+/// apply its artifacts like `apply_artifact_transaction`
+pub async fn import_bundle(
+    path: &str,
+) -> common::Result<crate::artifact::transaction::ApplyReport> {
+    let body = crate::artifact::bundle::import(path).await?;
+    Ok(apply_artifact_transaction(&body, &HashMap::new()).await)
+}
+
+/// Validate artifact without applying it
+///
+/// ### Parameters
+/// * `body: &str` - whole yaml string of pullpiri artifact
+/// * `values: &HashMap<String, String>` - values of the template parameters
+/// ### Description
+/// dry-run of `apply_artifact`: substitute the template parameters, then
+/// parse and cross-check artifacts
+/// nothing is written to etcd and no gRPC message is sent
+pub async fn validate_artifact(
+    body: &str,
+    values: &HashMap<String, String>,
+) -> crate::artifact::validate::ValidationReport {
+    let body = match crate::artifact::template::render(body, values) {
+        Ok(body) => body,
+        Err(e) => return crate::artifact::validate::ValidationReport::invalid(e.to_string()),
+    };
+    crate::artifact::validate::validate(&body).await
+}
+
 /// Withdraw downloaded artifact
 ///
 /// ### Parameters
@@ -169,6 +350,23 @@ pub async fn withdraw_artifact(body: &str) -> common::Result<()> {
     Ok(())
 }
 
+/// Roll an applied artifact back to a stored version
+///
+/// ### Parameters
+/// * `kind: &str, name: &str` - kind and name of the artifact
+/// * `version: u64` - version to re-activate
+/// ### Description
+/// write the stored version as the active artifact in etcd
+/// send a gRPC message to actioncontroller to re-deploy affected scenarios
+pub async fn rollback_artifact(kind: &str, name: &str, version: u64) -> common::Result<()> {
+    let scenarios = crate::artifact::history::rollback(kind, name, version).await?;
+
+    for scenario in scenarios {
+        crate::grpc::sender::actioncontroller::trigger_action(&scenario, false).await?;
+    }
+    Ok(())
+}
+
 //UNIT Test Cases
 #[cfg(test)]
 mod tests {
@@ -178,7 +376,12 @@ mod tests {
         filter_gateway_connection_server::{
             FilterGatewayConnection, FilterGatewayConnectionServer,
         },
-        Action, HandleScenarioRequest, HandleScenarioResponse,
+        Action, HandleScenarioRequest, HandleScenarioResponse, RegisterDdsTypeRequest,
+        RegisterDdsTypeResponse,
+    };
+    use common::filtergateway::{
+        CheckPolicyRequest, CheckPolicyResponse, ListTopicsRequest, ListTopicsResponse,
+        SubscribeTopicRequest, TopicRequest, TopicResponse, VehicleMode, VehicleModeRequest,
     };
     use std::net::SocketAddr;
     use tokio::net::TcpListener;
@@ -631,6 +834,65 @@ spec:
                 desc: "Success".to_string(),
             }))
         }
+
+        async fn register_dds_type(
+            &self,
+            _request: Request<RegisterDdsTypeRequest>,
+        ) -> Result<Response<RegisterDdsTypeResponse>, Status> {
+            Err(Status::unimplemented("Mock does not register DDS types"))
+        }
+
+        async fn list_topics(
+            &self,
+            _request: Request<ListTopicsRequest>,
+        ) -> Result<Response<ListTopicsResponse>, Status> {
+            Ok(Response::new(ListTopicsResponse { topics: vec![] }))
+        }
+
+        async fn subscribe_topic(
+            &self,
+            _request: Request<SubscribeTopicRequest>,
+        ) -> Result<Response<TopicResponse>, Status> {
+            Err(Status::unimplemented("Mock does not subscribe topics"))
+        }
+
+        async fn pause_topic(
+            &self,
+            _request: Request<TopicRequest>,
+        ) -> Result<Response<TopicResponse>, Status> {
+            Err(Status::unimplemented("Mock does not pause topics"))
+        }
+
+        async fn resume_topic(
+            &self,
+            _request: Request<TopicRequest>,
+        ) -> Result<Response<TopicResponse>, Status> {
+            Err(Status::unimplemented("Mock does not resume topics"))
+        }
+
+        async fn unsubscribe_topic(
+            &self,
+            _request: Request<TopicRequest>,
+        ) -> Result<Response<TopicResponse>, Status> {
+            Err(Status::unimplemented("Mock does not unsubscribe topics"))
+        }
+
+        async fn get_vehicle_mode(
+            &self,
+            _request: Request<VehicleModeRequest>,
+        ) -> Result<Response<VehicleMode>, Status> {
+            Ok(Response::new(VehicleMode::default()))
+        }
+
+        async fn check_policy(
+            &self,
+            _request: Request<CheckPolicyRequest>,
+        ) -> Result<Response<CheckPolicyResponse>, Status> {
+            Ok(Response::new(CheckPolicyResponse {
+                allowed: true,
+                ..Default::default()
+            }))
+        }
     }
 
     /// Starts the mock gRPC server asynchronously on a random port.
@@ -0,0 +1,319 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! One-time bootstrap tokens of guest nodes
+//!
+//! Issuing a token stores the bootstrap bundle of the new node with the
+//! expiry of the token, keyed by the digest of the token so etcd never holds
+//! the token itself. Redeeming a token deletes it, whether or not it has
+//! expired. Expired tokens that were never redeemed are removed whenever a
+//! new token is issued.
+
+use common::bootstrap::{BootstrapBundle, TOKEN_PREFIX};
+use common::error::{ApiError, ErrorCode};
+use common::etcd::StoreError;
+use common::logd;
+use common::setting::Settings;
+use ring::rand::SecureRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+/// Request of a bootstrap token
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TokenRequest {
+    /// Name the node registers with, its hostname if empty
+    #[serde(default)]
+    pub node_name: String,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// Time the token can be redeemed, `bootstrap.token_ttl_secs` if missing
+    #[serde(default)]
+    pub ttl_secs: Option<u64>,
+}
+
+/// Issued token, only returned once
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssuedToken {
+    pub token: String,
+    /// Unix time after which the token is refused
+    pub expires_at: i64,
+    pub bundle: BootstrapBundle,
+}
+
+/// Bundle of a token as stored in etcd
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredToken {
+    expires_at: i64,
+    bundle: BootstrapBundle,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BootstrapError {
+    InvalidRequest(String),
+    InvalidToken,
+    /// etcd cannot be reached
+    Unavailable(String),
+    Failed(String),
+}
+
+impl std::fmt::Display for BootstrapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidRequest(e) => write!(f, "{}", e),
+            Self::InvalidToken => write!(f, "Bootstrap token is invalid, used or expired"),
+            Self::Unavailable(e) => write!(f, "{}", e),
+            Self::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<BootstrapError> for ApiError {
+    fn from(error: BootstrapError) -> Self {
+        let message = error.to_string();
+        match error {
+            BootstrapError::InvalidRequest(_) => ApiError::validation(message),
+            BootstrapError::InvalidToken => ApiError::new(ErrorCode::Unauthorized, message),
+            BootstrapError::Unavailable(_) => ApiError::unavailable(message),
+            BootstrapError::Failed(_) => ApiError::internal(message),
+        }
+    }
+}
+
+impl From<StoreError> for BootstrapError {
+    fn from(error: StoreError) -> Self {
+        match error {
+            StoreError::Unavailable(e) => Self::Unavailable(e),
+            e => Self::Failed(e.to_string()),
+        }
+    }
+}
+
+/// Redemptions are serialized, so a token is handed out at most once
+static REDEEM_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Issue a one-time token for a new node
+///
+/// ### Parameters
+/// * `request: TokenRequest` - name and labels of the node
+/// ### Description
+/// The bundle holds the master address of `bootstrap.master_ip`, else
+/// `host.ip`, and the CA certificate read from `bootstrap.ca_cert`.
+pub async fn issue(request: TokenRequest) -> Result<IssuedToken, BootstrapError> {
+    let settings = common::setting::get_config();
+    let bundle = bundle(&request, &settings)?;
+    let ttl = request
+        .ttl_secs
+        .unwrap_or(settings.bootstrap.token_ttl_secs);
+    if ttl == 0 {
+        return Err(BootstrapError::InvalidRequest(
+            "ttlSecs must be greater than 0".to_string(),
+        ));
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    remove_expired(now).await;
+
+    let token = generate_token()?;
+    let stored = StoredToken {
+        expires_at: now + ttl as i64,
+        bundle,
+    };
+    let value =
+        serde_json::to_string(&stored).map_err(|e| BootstrapError::Failed(e.to_string()))?;
+    common::etcd::put(&token_key(&token), &value).await?;
+    logd!(
+        3,
+        "Issued bootstrap token for node '{}', valid for {}s",
+        stored.bundle.node_name,
+        ttl
+    );
+    Ok(IssuedToken {
+        token,
+        expires_at: stored.expires_at,
+        bundle: stored.bundle,
+    })
+}
+
+/// Exchange a token for its bootstrap bundle
+///
+/// ### Parameters
+/// * `token: &str` - token returned by [`issue`]
+/// ### Description
+/// The token is deleted, so a second redemption fails.
+pub async fn redeem(token: &str) -> Result<BootstrapBundle, BootstrapError> {
+    if token.is_empty() {
+        return Err(BootstrapError::InvalidToken);
+    }
+    let _guard = REDEEM_LOCK.lock().await;
+    let key = token_key(token);
+    let value = match common::etcd::get(&key).await {
+        Ok(value) => value,
+        Err(StoreError::NotFound) => return Err(BootstrapError::InvalidToken),
+        Err(e) => return Err(e.into()),
+    };
+    common::etcd::delete(&key).await?;
+
+    let stored: StoredToken =
+        serde_json::from_str(&value).map_err(|_| BootstrapError::InvalidToken)?;
+    if stored.expires_at < chrono::Utc::now().timestamp() {
+        return Err(BootstrapError::InvalidToken);
+    }
+    logd!(
+        3,
+        "Bootstrap token of node '{}' redeemed",
+        stored.bundle.node_name
+    );
+    Ok(stored.bundle)
+}
+
+/// Bundle of a requested node
+fn bundle(request: &TokenRequest, settings: &Settings) -> Result<BootstrapBundle, BootstrapError> {
+    if request.node_name.contains('/') || request.node_name.trim() != request.node_name {
+        return Err(BootstrapError::InvalidRequest(format!(
+            "Node name '{}' is invalid",
+            request.node_name
+        )));
+    }
+    if request.labels.keys().any(|key| key.trim().is_empty()) {
+        return Err(BootstrapError::InvalidRequest(
+            "Label keys must not be empty".to_string(),
+        ));
+    }
+
+    let master_ip = if settings.bootstrap.master_ip.is_empty() {
+        settings.host.ip.clone()
+    } else {
+        settings.bootstrap.master_ip.clone()
+    };
+    if master_ip.is_empty() || master_ip == "0.0.0.0" {
+        return Err(BootstrapError::Failed(
+            "bootstrap.master_ip must be set when the master listens on 0.0.0.0".to_string(),
+        ));
+    }
+    let ca_cert = if settings.bootstrap.ca_cert.is_empty() {
+        String::new()
+    } else {
+        std::fs::read_to_string(&settings.bootstrap.ca_cert).map_err(|e| {
+            BootstrapError::Failed(format!(
+                "Cannot read CA certificate '{}': {}",
+                settings.bootstrap.ca_cert, e
+            ))
+        })?
+    };
+
+    Ok(BootstrapBundle {
+        api_endpoint: format!("http://{}:47098", master_ip),
+        master_ip,
+        ca_cert,
+        node_name: request.node_name.clone(),
+        labels: request.labels.clone(),
+    })
+}
+
+/// Remove the tokens that expired before `now`
+async fn remove_expired(now: i64) {
+    let Ok(tokens) = common::etcd::get_all_with_prefix(TOKEN_PREFIX).await else {
+        return;
+    };
+    for (key, value) in tokens {
+        let expired = serde_json::from_str::<StoredToken>(&value)
+            .map(|stored| stored.expires_at < now)
+            .unwrap_or(true);
+        if expired {
+            if let Err(e) = common::etcd::delete(&key).await {
+                logd!(4, "Cannot remove expired bootstrap token: {}", e);
+            }
+        }
+    }
+}
+
+fn generate_token() -> Result<String, BootstrapError> {
+    let mut bytes = [0u8; 32];
+    ring::rand::SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| BootstrapError::Failed("Cannot generate a token".to_string()))?;
+    Ok(hex(&bytes))
+}
+
+fn token_key(token: &str) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, token.as_bytes());
+    format!("{}{}", TOKEN_PREFIX, hex(digest.as_ref()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(master_ip: &str, host_ip: &str) -> Settings {
+        let mut settings = common::setting::parse_settings_str(&format!(
+            "host:\n  name: HPC\n  ip: {}\n  type: nodeagent\n  role: master\n",
+            host_ip
+        ))
+        .unwrap();
+        settings.bootstrap.master_ip = master_ip.to_string();
+        settings
+    }
+
+    #[test]
+    fn test_bundle_of_request() {
+        let request = TokenRequest {
+            node_name: "zone-front".to_string(),
+            labels: HashMap::from([("zone".to_string(), "front".to_string())]),
+            ttl_secs: None,
+        };
+        let issued = bundle(&request, &settings("", "10.0.0.1")).unwrap();
+        assert_eq!(issued.master_ip, "10.0.0.1");
+        assert_eq!(issued.api_endpoint, "http://10.0.0.1:47098");
+        assert_eq!(issued.node_name, "zone-front");
+        assert!(issued.ca_cert.is_empty());
+
+        let issued = bundle(&request, &settings("192.168.0.9", "0.0.0.0")).unwrap();
+        assert_eq!(issued.master_ip, "192.168.0.9");
+        assert!(matches!(
+            bundle(&request, &settings("", "0.0.0.0")),
+            Err(BootstrapError::Failed(_))
+        ));
+
+        let invalid = TokenRequest {
+            node_name: "a/b".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            bundle(&invalid, &settings("", "10.0.0.1")),
+            Err(BootstrapError::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_tokens_are_random_and_stored_as_digest() {
+        let token = generate_token().unwrap();
+        assert_eq!(token.len(), 64);
+        assert_ne!(token, generate_token().unwrap());
+
+        let key = token_key(&token);
+        assert!(key.starts_with(TOKEN_PREFIX));
+        assert!(!key.contains(&token));
+        assert_eq!(key, token_key(&token));
+    }
+
+    #[test]
+    fn test_store_errors() {
+        let err = BootstrapError::from(StoreError::Unavailable("etcd is down".to_string()));
+        assert_eq!(err, BootstrapError::Unavailable("etcd is down".to_string()));
+        assert_eq!(ApiError::from(err).code, ErrorCode::DependencyUnavailable);
+
+        let err = BootstrapError::from(StoreError::Failed("Put failed".to_string()));
+        assert_eq!(ApiError::from(err).code, ErrorCode::Internal);
+    }
+}
@@ -0,0 +1,381 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Streamed node heartbeats with delta status updates
+//!
+//! NodeAgent keeps one `HeartbeatStream` call open and sends a
+//! `NodeStatusDelta` every heartbeat interval, carrying only the metrics that
+//! changed since the previous one. The first delta of a stream is full. A
+//! delta that does not follow the previous one is answered with `resync`, and
+//! the agent sends all of its metrics again. Every ack carries the current
+//! `heartbeat` settings, so changed intervals reach the agents without a
+//! restart. A stream that stays silent for the liveness window is closed and
+//! its node marked NotReady right away.
+//!
+//! Liveness only depends on the time a heartbeat is received. The timestamp
+//! sent by the node is compared with it to measure the offset of the node
+//! clock, which is kept in the node metadata under [`CLOCK_SKEW_KEY`] and
+//! raises a [`CLOCK_SKEW_ALERT`] alert beyond `max_clock_skew_secs`.
+
+use crate::node::NodeManager;
+use common::logd;
+use common::monitoringserver::Alert;
+use common::nodeagent::fromapiserver::{ClusterConfig, HeartbeatAck, NodeStatus, NodeStatusDelta};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::{Stream, StreamExt};
+use tonic::Status;
+
+/// Node metadata key of the clock offset in seconds, positive if ahead
+pub const CLOCK_SKEW_KEY: &str = "clock_skew_secs";
+/// Rule id of the alert raised for a skewed node clock
+pub const CLOCK_SKEW_ALERT: &str = "clock-skew";
+
+/// Seconds without a heartbeat after which a node is stale
+pub fn liveness_window() -> u64 {
+    common::setting::get_config().heartbeat.liveness_window()
+}
+
+/// Heartbeat settings sent to the agents
+pub fn cluster_config() -> ClusterConfig {
+    let heartbeat = common::setting::get_config().heartbeat.clone();
+    ClusterConfig {
+        master_endpoint: common::apiserver::connect_grpc_server(),
+        heartbeat_interval: heartbeat.interval_secs as i32,
+        settings: HashMap::new(),
+        liveness_window: heartbeat.liveness_window() as i32,
+    }
+}
+
+/// Offset of the node clock, `None` if the node sent no timestamp
+///
+/// ### Parameters
+/// * `reported: i64` - unix seconds sent by the node
+/// * `received: i64` - unix seconds of ApiServer when the heartbeat arrived
+pub fn clock_skew(reported: i64, received: i64) -> Option<i64> {
+    (reported > 0).then(|| reported - received)
+}
+
+/// Alert of a skewed node clock, `None` within `max_skew_secs`
+pub fn skew_alert(hostname: &str, skew: i64, max_skew_secs: u64, now: i64) -> Option<Alert> {
+    if max_skew_secs == 0 || skew.unsigned_abs() <= max_skew_secs {
+        return None;
+    }
+    Some(Alert {
+        rule_id: CLOCK_SKEW_ALERT.to_string(),
+        severity: common::alert::AlertSeverity::Warning.as_str().to_string(),
+        target_kind: common::alert::AlertTargetKind::Node.as_str().to_string(),
+        target_name: hostname.to_string(),
+        metric: CLOCK_SKEW_KEY.to_string(),
+        value: skew as f64,
+        threshold: max_skew_secs as f64,
+        message: format!(
+            "clock of node '{}' is {}s {} ApiServer (threshold {}s)",
+            hostname,
+            skew.unsigned_abs(),
+            if skew > 0 { "ahead of" } else { "behind" },
+            max_skew_secs
+        ),
+        fired_at: now,
+        resolved: false,
+    })
+}
+
+/// Raise or resolve the clock skew alert of a node
+///
+/// Only a skew crossing the threshold since the `previous` one touches etcd.
+pub async fn update_skew_alert(hostname: &str, previous: Option<i64>, skew: i64, now: i64) {
+    let max_skew_secs = common::setting::get_config().heartbeat.max_clock_skew_secs;
+    let firing = skew_alert(hostname, skew, max_skew_secs, now);
+    let was_firing = previous
+        .and_then(|previous| skew_alert(hostname, previous, max_skew_secs, now))
+        .is_some();
+    let key = common::alert::alert_key(CLOCK_SKEW_ALERT, hostname);
+    let result = match (firing, was_firing) {
+        (Some(alert), false) => {
+            logd!(4, "Clock skew: {}", alert.message);
+            match serde_json::to_string(&alert) {
+                Ok(json) => common::etcd::put(&key, &json).await.map_err(String::from),
+                Err(e) => Err(e.to_string()),
+            }
+        }
+        (None, true) => {
+            logd!(3, "Clock skew of node {} is back to {}s", hostname, skew);
+            common::etcd::delete(&key).await.map_err(String::from)
+        }
+        _ => Ok(()),
+    };
+    if let Err(e) = result {
+        logd!(
+            4,
+            "Failed to update clock skew alert of {}: {}",
+            hostname,
+            e
+        );
+    }
+}
+
+/// Outcome of a delta applied to the status of a node
+#[derive(Debug, PartialEq)]
+pub enum Applied {
+    Changed,
+    Unchanged,
+    /// A delta was missed, the status stays as it was until a full one
+    Resync,
+}
+
+/// Status of the node of one heartbeat stream
+pub struct StatusSession {
+    node_id: String,
+    sequence: u64,
+    synced: bool,
+    metrics: HashMap<String, String>,
+}
+
+impl StatusSession {
+    pub fn new(node_id: &str) -> Self {
+        Self {
+            node_id: node_id.to_string(),
+            sequence: 0,
+            synced: false,
+            metrics: HashMap::new(),
+        }
+    }
+
+    pub fn metrics(&self) -> &HashMap<String, String> {
+        &self.metrics
+    }
+
+    /// Apply a delta to the status
+    pub fn apply(&mut self, delta: &NodeStatusDelta) -> Applied {
+        if delta.full {
+            let changed = self.metrics != delta.changed_metrics || !self.synced;
+            self.metrics = delta.changed_metrics.clone();
+            self.synced = true;
+            self.sequence = delta.sequence;
+            return if changed {
+                Applied::Changed
+            } else {
+                Applied::Unchanged
+            };
+        }
+        if !self.synced || delta.sequence != self.sequence + 1 {
+            return Applied::Resync;
+        }
+
+        self.sequence = delta.sequence;
+        let mut changed = false;
+        for (name, value) in &delta.changed_metrics {
+            changed |= self.metrics.insert(name.clone(), value.clone()).as_ref() != Some(value);
+        }
+        for name in &delta.removed_metrics {
+            changed |= self.metrics.remove(name).is_some();
+        }
+        if changed {
+            Applied::Changed
+        } else {
+            Applied::Unchanged
+        }
+    }
+}
+
+/// Applies streamed heartbeats and acknowledges each of them
+///
+/// The relay stops when the stream ends, fails or stays silent for the
+/// liveness window.
+pub async fn relay_heartbeats<S>(
+    mut inbound: S,
+    node_manager: NodeManager,
+    tx_ack: mpsc::Sender<Result<HeartbeatAck, Status>>,
+) where
+    S: Stream<Item = Result<NodeStatusDelta, Status>> + Unpin,
+{
+    let mut session: Option<StatusSession> = None;
+    loop {
+        let window = liveness_window();
+        let delta = match tokio::time::timeout(Duration::from_secs(window), inbound.next()).await {
+            Ok(Some(Ok(delta))) => delta,
+            Ok(Some(Err(status))) => {
+                logd!(4, "Heartbeat stream error: {}", status);
+                break;
+            }
+            Ok(None) => break,
+            Err(_) => {
+                if let Some(session) = &session {
+                    logd!(
+                        4,
+                        "Node {} sent no heartbeat for {}s, marking NotReady",
+                        session.node_id,
+                        window
+                    );
+                    mark_not_ready(&node_manager, &session.node_id).await;
+                }
+                break;
+            }
+        };
+
+        let ack = handle_delta(&node_manager, &mut session, &delta).await;
+        let failed = ack.is_err();
+        if tx_ack.send(ack).await.is_err() || failed {
+            break;
+        }
+    }
+}
+
+async fn handle_delta(
+    node_manager: &NodeManager,
+    session: &mut Option<StatusSession>,
+    delta: &NodeStatusDelta,
+) -> Result<HeartbeatAck, Status> {
+    match session {
+        Some(session) if session.node_id != delta.node_id => {
+            return Err(Status::invalid_argument(format!(
+                "Heartbeat stream of node {} cannot carry node {}",
+                session.node_id, delta.node_id
+            )));
+        }
+        Some(_) => {}
+        None => match node_manager.get_node(&delta.node_id).await {
+            Ok(Some(_)) => *session = Some(StatusSession::new(&delta.node_id)),
+            Ok(None) => {
+                return Err(Status::not_found(format!(
+                    "Node {} is not registered",
+                    delta.node_id
+                )))
+            }
+            Err(e) => return Err(Status::unavailable(format!("Failed to load node: {}", e))),
+        },
+    }
+    let Some(session) = session.as_mut() else {
+        return Err(Status::internal("Heartbeat stream has no session"));
+    };
+
+    let applied = session.apply(delta);
+    if let Err(e) = node_manager
+        .report_heartbeat(&delta.node_id, delta.timestamp)
+        .await
+    {
+        logd!(5, "Failed to update heartbeat for {}: {}", delta.node_id, e);
+        return Err(Status::unavailable(format!(
+            "Failed to update heartbeat: {}",
+            e
+        )));
+    }
+    if applied == Applied::Changed {
+        if let Err(e) = node_manager
+            .put_status_metrics(&delta.node_id, session.metrics())
+            .await
+        {
+            logd!(4, "Failed to store status of {}: {}", delta.node_id, e);
+        }
+    }
+
+    Ok(HeartbeatAck {
+        sequence: delta.sequence,
+        resync: applied == Applied::Resync,
+        config: Some(cluster_config()),
+    })
+}
+
+/// Mark a node NotReady unless it is in maintenance or terminating
+async fn mark_not_ready(node_manager: &NodeManager, node_id: &str) {
+    let Ok(Some(node)) = node_manager.get_node(node_id).await else {
+        return;
+    };
+    if node.status == NodeStatus::Maintenance as i32
+        || node.status == NodeStatus::Terminating as i32
+        || node.status == NodeStatus::NotReady as i32
+    {
+        return;
+    }
+    if let Err(e) = node_manager
+        .update_status(node_id, NodeStatus::NotReady)
+        .await
+    {
+        logd!(4, "Failed to mark node {} NotReady: {}", node_id, e);
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delta(
+        sequence: u64,
+        full: bool,
+        changed: &[(&str, &str)],
+        removed: &[&str],
+    ) -> NodeStatusDelta {
+        NodeStatusDelta {
+            node_id: "node-1".to_string(),
+            timestamp: 0,
+            sequence,
+            full,
+            changed_metrics: changed
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            removed_metrics: removed.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_apply_deltas() {
+        let mut session = StatusSession::new("node-1");
+        assert_eq!(
+            session.apply(&delta(1, false, &[("cpu_usage", "10")], &[])),
+            Applied::Resync
+        );
+        assert_eq!(
+            session.apply(&delta(1, true, &[("cpu_usage", "10"), ("gpu", "1")], &[])),
+            Applied::Changed
+        );
+        assert_eq!(
+            session.apply(&delta(2, false, &[], &[])),
+            Applied::Unchanged
+        );
+        assert_eq!(
+            session.apply(&delta(3, false, &[("cpu_usage", "12")], &["gpu"])),
+            Applied::Changed
+        );
+        assert_eq!(session.metrics().len(), 1);
+        assert_eq!(session.metrics()["cpu_usage"], "12");
+    }
+
+    #[test]
+    fn test_clock_skew_alert() {
+        assert_eq!(clock_skew(0, 1_000), None);
+        assert_eq!(clock_skew(990, 1_000), Some(-10));
+        assert!(skew_alert("HPC", -5, 5, 1_000).is_none());
+        assert!(skew_alert("HPC", 60, 0, 1_000).is_none());
+
+        let alert = skew_alert("HPC", -10, 5, 1_000).unwrap();
+        assert_eq!(alert.rule_id, CLOCK_SKEW_ALERT);
+        assert_eq!(alert.target_name, "HPC");
+        assert_eq!(alert.value, -10.0);
+        assert!(alert.message.contains("10s behind"));
+    }
+
+    #[test]
+    fn test_missed_delta_requires_full_status() {
+        let mut session = StatusSession::new("node-1");
+        session.apply(&delta(1, true, &[("cpu_usage", "10")], &[]));
+        assert_eq!(
+            session.apply(&delta(3, false, &[("cpu_usage", "50")], &[])),
+            Applied::Resync
+        );
+        assert_eq!(session.metrics()["cpu_usage"], "10");
+        assert_eq!(
+            session.apply(&delta(4, true, &[("cpu_usage", "10")], &[])),
+            Applied::Unchanged
+        );
+        assert_eq!(
+            session.apply(&delta(5, false, &[("mem_usage", "40")], &[])),
+            Applied::Changed
+        );
+    }
+}
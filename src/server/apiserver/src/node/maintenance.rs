@@ -0,0 +1,400 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Maintenance mode of nodes
+//!
+//! Draining a node marks it `Maintenance` and asks ActionController to move
+//! its models to other nodes. A node in maintenance keeps its status while
+//! it sends heartbeats or registers again, so no model is scheduled on it
+//! until it is uncordoned.
+//!
+//! Migrating a node that failed for good moves all of its models, including
+//! those pinned to it, and pins them to their new node in their packages.
+
+use super::NodeManager;
+use common::actioncontroller::{DrainedModel, MigratedModel};
+use common::apiserver::NodeInfo;
+use common::error::ApiError;
+use common::etcd::StoreError;
+use common::logd;
+use common::nodeagent::fromapiserver::NodeStatus;
+use common::spec::artifact::Package;
+use common::spec::namespace;
+
+/// Result of draining a node
+#[derive(Debug, serde::Serialize)]
+pub struct DrainReport {
+    pub node: String,
+    /// Outcome of every model that ran on the node
+    pub models: Vec<DrainedModel>,
+}
+
+/// Result of migrating a node
+#[derive(Debug, serde::Serialize)]
+pub struct MigrationReport {
+    pub source: String,
+    /// Requested target node, empty if chosen for each model
+    pub target: String,
+    /// Outcome of every model that ran on the source node
+    pub models: Vec<MigratedModel>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MaintenanceError {
+    NodeNotFound(String),
+    NotInMaintenance(String),
+    InvalidTarget(String),
+    /// etcd or ActionController cannot be reached
+    Unavailable(String),
+    Failed(String),
+}
+
+impl std::fmt::Display for MaintenanceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NodeNotFound(node) => write!(f, "Node '{}' not found", node),
+            Self::NotInMaintenance(node) => write!(f, "Node '{}' is not in maintenance", node),
+            Self::InvalidTarget(e) => write!(f, "{}", e),
+            Self::Unavailable(e) => write!(f, "{}", e),
+            Self::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<MaintenanceError> for ApiError {
+    fn from(error: MaintenanceError) -> Self {
+        let message = error.to_string();
+        match error {
+            MaintenanceError::NodeNotFound(_) => ApiError::not_found(message),
+            MaintenanceError::NotInMaintenance(_) => ApiError::conflict(message),
+            MaintenanceError::InvalidTarget(_) => ApiError::validation(message),
+            MaintenanceError::Unavailable(_) => ApiError::unavailable(message),
+            MaintenanceError::Failed(_) => ApiError::internal(message),
+        }
+    }
+}
+
+impl From<Box<dyn std::error::Error + Send + Sync>> for MaintenanceError {
+    fn from(error: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        match error.downcast_ref::<StoreError>() {
+            Some(StoreError::Unavailable(e)) => Self::Unavailable(e.clone()),
+            _ => Self::Failed(error.to_string()),
+        }
+    }
+}
+
+impl From<tonic::Status> for MaintenanceError {
+    fn from(status: tonic::Status) -> Self {
+        match status.code() {
+            tonic::Code::Unavailable | tonic::Code::DeadlineExceeded => {
+                Self::Unavailable(status.message().to_string())
+            }
+            _ => Self::Failed(status.message().to_string()),
+        }
+    }
+}
+
+/// Status of a node that reports in with a heartbeat or registration
+///
+/// A node in maintenance stays in maintenance, every other node is Ready.
+pub fn reported_status(current: i32) -> NodeStatus {
+    if current == NodeStatus::Maintenance as i32 {
+        NodeStatus::Maintenance
+    } else {
+        NodeStatus::Ready
+    }
+}
+
+async fn find_node(node_id: &str) -> Result<(NodeManager, NodeInfo), MaintenanceError> {
+    let node_manager = NodeManager::new()?;
+    match node_manager.get_node(node_id).await {
+        Ok(Some(node)) => Ok((node_manager, node)),
+        Ok(None) => Err(MaintenanceError::NodeNotFound(node_id.to_string())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Put a node in maintenance and move its models off it
+///
+/// ### Parameters
+/// * `node_id: &str` - id or hostname of the node
+/// ### Description
+/// The node is marked `Maintenance` before ActionController is asked to
+/// drain it, so none of its models is placed on it again. If draining
+/// fails, the node stays in maintenance and can be drained again.
+pub async fn drain(node_id: &str) -> Result<DrainReport, MaintenanceError> {
+    let (node_manager, node) = find_node(node_id).await?;
+    node_manager
+        .update_status(node_id, NodeStatus::Maintenance)
+        .await?;
+    logd!(3, "Node {} is in maintenance, draining it", node.hostname);
+
+    let response = crate::grpc::sender::actioncontroller::drain_node(&node.hostname).await?;
+    Ok(DrainReport {
+        node: node.hostname,
+        models: response.into_inner().models,
+    })
+}
+
+/// Take a node out of maintenance
+///
+/// ### Parameters
+/// * `node_id: &str` - id or hostname of the node
+/// ### Description
+/// The node becomes `Ready` and takes new models again. Models stopped by
+/// the drain are not restarted.
+pub async fn uncordon(node_id: &str) -> Result<(), MaintenanceError> {
+    let (node_manager, node) = find_node(node_id).await?;
+    if node.status != NodeStatus::Maintenance as i32 {
+        return Err(MaintenanceError::NotInMaintenance(node_id.to_string()));
+    }
+    node_manager
+        .update_status(node_id, NodeStatus::Ready)
+        .await?;
+    logd!(3, "Node {} is out of maintenance", node.hostname);
+    Ok(())
+}
+
+/// Move every model of a failed node to another node
+///
+/// ### Parameters
+/// * `node_id: &str` - id or hostname of the failed node
+/// * `target_id: Option<&str>` - id or hostname of the node the models move
+///   to, else ActionController chooses one for each model
+/// ### Description
+/// The source node is marked `Maintenance` first, as for a drain, so no
+/// model is placed on it again. ActionController removes the models from
+/// the source node if it can reach it and starts them on the target. The
+/// packages that pinned a migrated model to the source node pin it to its
+/// new node, so the next launch does not go back to the failed node.
+pub async fn migrate(
+    node_id: &str,
+    target_id: Option<&str>,
+) -> Result<MigrationReport, MaintenanceError> {
+    let (node_manager, node) = find_node(node_id).await?;
+    let target = match target_id {
+        Some(target_id) => {
+            let (_, target) = find_node(target_id).await?;
+            if target.hostname == node.hostname {
+                return Err(MaintenanceError::InvalidTarget(format!(
+                    "Node '{}' cannot be migrated to itself",
+                    node.hostname
+                )));
+            }
+            if target.status != NodeStatus::Ready as i32 {
+                return Err(MaintenanceError::InvalidTarget(format!(
+                    "Target node '{}' is not Ready",
+                    target.hostname
+                )));
+            }
+            target.hostname
+        }
+        None => String::new(),
+    };
+    node_manager
+        .update_status(node_id, NodeStatus::Maintenance)
+        .await?;
+    logd!(3, "Node {} is in maintenance, migrating it", node.hostname);
+
+    let response =
+        crate::grpc::sender::actioncontroller::migrate_node(&node.hostname, &target).await?;
+    let models = response.into_inner().models;
+    repin_packages(&node.hostname, &models).await;
+    Ok(MigrationReport {
+        source: node.hostname,
+        target,
+        models,
+    })
+}
+
+/// Pin the migrated models of a package that were pinned to `source`
+///
+/// ### Parameters
+/// * `package: &mut serde_yaml::Value` - stored yaml of the package, edited
+///   in place so the fields unknown to [`Package`] are kept
+/// ### Returns
+/// * `bool` - `true` if a model of the package changed node
+fn repin(package: &mut serde_yaml::Value, source: &str, models: &[MigratedModel]) -> bool {
+    let Some(model_list) = package
+        .get_mut("spec")
+        .and_then(|spec| spec.get_mut("models"))
+        .and_then(|models| models.as_sequence_mut())
+    else {
+        return false;
+    };
+    let mut changed = false;
+    for model in model_list {
+        if model.get("node").and_then(|node| node.as_str()) != Some(source) {
+            continue;
+        }
+        let name = model.get("name").and_then(|name| name.as_str());
+        if let Some(migrated) = models
+            .iter()
+            .find(|m| m.outcome == "migrated" && Some(m.model_name.as_str()) == name)
+        {
+            model["node"] = serde_yaml::Value::from(migrated.target_node.as_str());
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Store the packages of the migrated models pinned to `source` again
+///
+/// Failures are logged, the models already run on their new node.
+async fn repin_packages(source: &str, models: &[MigratedModel]) {
+    let mut package_names: Vec<&str> = models
+        .iter()
+        .filter(|m| m.outcome == "migrated" && !m.package_name.is_empty())
+        .map(|m| m.package_name.as_str())
+        .collect();
+    package_names.sort_unstable();
+    package_names.dedup();
+
+    for package_name in package_names {
+        let key = namespace::qualified_key("Package", package_name);
+        let result = async {
+            let stored = crate::artifact::data::read_from_etcd(&key).await?;
+            let mut package: serde_yaml::Value = serde_yaml::from_str(&stored)?;
+            if !repin(&mut package, source, models) {
+                return Ok(false);
+            }
+            let package_str = serde_yaml::to_string(&package)?;
+            // The stored package stays a valid Package
+            serde_yaml::from_str::<Package>(&package_str)?;
+            crate::artifact::data::write_to_etcd(&key, &package_str).await?;
+            crate::artifact::data::write_version(&key, &package_str).await?;
+            common::Result::Ok(true)
+        }
+        .await
+        .map_err(|e| e.to_string());
+        match result {
+            Ok(true) => logd!(3, "Package {} is pinned off node {}", package_name, source),
+            Ok(false) => {}
+            Err(e) => logd!(
+                4,
+                "Failed to pin models of package {} off node {}: {}",
+                package_name,
+                source,
+                e
+            ),
+        }
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::error::ErrorCode;
+
+    #[test]
+    fn test_reported_status_keeps_maintenance() {
+        assert_eq!(
+            reported_status(NodeStatus::Maintenance as i32),
+            NodeStatus::Maintenance
+        );
+        assert_eq!(
+            reported_status(NodeStatus::NotReady as i32),
+            NodeStatus::Ready
+        );
+        assert_eq!(
+            reported_status(NodeStatus::Pending as i32),
+            NodeStatus::Ready
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_node() {
+        let err = drain("no-such-node-for-drain").await.unwrap_err();
+        assert_eq!(
+            err,
+            MaintenanceError::NodeNotFound("no-such-node-for-drain".to_string())
+        );
+        assert_eq!(err.to_string(), "Node 'no-such-node-for-drain' not found");
+        assert_eq!(ApiError::from(err).code, ErrorCode::NotFound);
+        assert!(uncordon("no-such-node-for-drain").await.is_err());
+        assert!(migrate("no-such-node-for-drain", None).await.is_err());
+    }
+
+    #[test]
+    fn test_failures_keep_their_cause() {
+        let err = MaintenanceError::from(tonic::Status::unavailable("connection refused"));
+        assert_eq!(
+            err,
+            MaintenanceError::Unavailable("connection refused".to_string())
+        );
+        assert_eq!(ApiError::from(err).code, ErrorCode::DependencyUnavailable);
+
+        let err = MaintenanceError::from(tonic::Status::internal("no such model"));
+        assert_eq!(ApiError::from(err).code, ErrorCode::Internal);
+
+        let error: Box<dyn std::error::Error + Send + Sync> =
+            StoreError::Unavailable("etcd is down".to_string()).into();
+        assert_eq!(
+            MaintenanceError::from(error),
+            MaintenanceError::Unavailable("etcd is down".to_string())
+        );
+        let error: Box<dyn std::error::Error + Send + Sync> = "invalid node json".into();
+        assert_eq!(
+            MaintenanceError::from(error),
+            MaintenanceError::Failed("invalid node json".to_string())
+        );
+    }
+
+    #[test]
+    fn test_repin_migrated_models() {
+        let mut package: serde_yaml::Value = serde_yaml::from_str(
+            r#"
+apiVersion: v1
+kind: Package
+metadata:
+  name: helloworld
+spec:
+  pattern:
+    - type: plain
+  models:
+    - name: pinned-core
+      node: zone1
+      resources:
+        realtime: false
+      volume: {}
+      network: {}
+    - name: failed-core
+      node: zone1
+      resources:
+        realtime: false
+      volume: {}
+      network: {}
+    - name: other-core
+      node: zone3
+      resources:
+        realtime: false
+      volume: {}
+      network: {}
+"#,
+        )
+        .unwrap();
+        let model = |name: &str, outcome: &str, target: &str| MigratedModel {
+            model_name: name.to_string(),
+            package_name: "helloworld".to_string(),
+            outcome: outcome.to_string(),
+            target_node: target.to_string(),
+            source_stopped: false,
+            message: String::new(),
+        };
+        let models = vec![
+            model("pinned-core", "migrated", "zone2"),
+            model("failed-core", "failed", ""),
+            model("other-core", "migrated", "zone2"),
+        ];
+
+        assert!(repin(&mut package, "zone1", &models));
+        let parsed: Package = serde_yaml::from_value(package.clone()).unwrap();
+        let nodes: Vec<String> = parsed.get_models().iter().map(|m| m.get_node()).collect();
+        assert_eq!(nodes, vec!["zone2", "zone1", "zone3"]);
+        assert!(!repin(&mut package, "zone1", &models));
+    }
+}
@@ -9,6 +9,14 @@ use common::apiserver::NodeInfo;
 use common::etcd;
 use common::logd;
 use common::nodeagent::fromapiserver::{NodeRegistrationRequest, NodeStatus};
+use std::collections::HashMap;
+
+/// etcd prefix under which per-node heartbeat history is stored
+const HEARTBEAT_HISTORY_PREFIX: &str = "cluster/heartbeats/";
+/// Maximum number of heartbeat timestamps kept per node
+pub const HEARTBEAT_HISTORY_LIMIT: usize = 20;
+/// etcd prefix under which the status metrics streamed by each node are stored
+const STATUS_METRICS_PREFIX: &str = "cluster/status/";
 
 /// Node manager for handling cluster node operations
 #[derive(Clone)]
@@ -27,6 +35,11 @@ impl NodeManager {
     ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
         // node_id 대신 hostname(node_name)을 키로 사용합니다
         let node_key = format!("cluster/nodes/{}", request.hostname);
+        // A node in maintenance stays there when it registers again
+        let in_maintenance = matches!(
+            self.get_node(&request.hostname).await,
+            Ok(Some(node)) if node.status == NodeStatus::Maintenance as i32
+        );
 
         // Create node info
         let node_info = NodeInfo {
@@ -35,7 +48,11 @@ impl NodeManager {
             ip_address: request.ip_address.clone(),
             node_type: request.node_type,
             node_role: request.node_role,
-            status: NodeStatus::Pending.into(),
+            status: if in_maintenance {
+                NodeStatus::Maintenance.into()
+            } else {
+                NodeStatus::Pending.into()
+            },
             resources: request.resources,
             last_heartbeat: chrono::Utc::now().timestamp(),
             created_at: chrono::Utc::now().timestamp(),
@@ -86,6 +103,9 @@ impl NodeManager {
     }
 
     /// Get a specific node by ID
+    ///
+    /// Nodes are stored under their hostname, so the id is first tried as a
+    /// hostname and then matched against the stored `node_id` fields.
     pub async fn get_node(
         &self,
         node_id: &str,
@@ -93,11 +113,13 @@ impl NodeManager {
         // node_id를 직접 사용 (hostname으로 간주)
         let node_key = format!("cluster/nodes/{}", node_id);
 
-        match etcd::get(&node_key).await {
-            Ok(json_str) => {
-                let node_info = serde_json::from_str::<NodeInfo>(&json_str)?;
-                Ok(Some(node_info))
-            }
+        if let Ok(json_str) = etcd::get(&node_key).await {
+            let node_info = serde_json::from_str::<NodeInfo>(&json_str)?;
+            return Ok(Some(node_info));
+        }
+
+        match self.get_all_nodes().await {
+            Ok(nodes) => Ok(nodes.into_iter().find(|node| node.node_id == node_id)),
             Err(_) => Ok(None), // Node not found
         }
     }
@@ -106,22 +128,115 @@ impl NodeManager {
     pub async fn update_heartbeat(
         &self,
         node_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.report_heartbeat(node_id, 0).await
+    }
+
+    /// Update node heartbeat with the timestamp sent by the node
+    ///
+    /// `last_heartbeat` is always the receive time of ApiServer. A non-zero
+    /// `reported_at` updates the clock skew of the node.
+    pub async fn report_heartbeat(
+        &self,
+        node_id: &str,
+        reported_at: i64,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         if let Some(mut node) = self.get_node(node_id).await? {
-            node.last_heartbeat = chrono::Utc::now().timestamp();
-            node.status = NodeStatus::Ready.into();
+            let received = chrono::Utc::now().timestamp();
+            node.last_heartbeat = received;
+            node.status = super::maintenance::reported_status(node.status).into();
+            if let Some(skew) = super::heartbeat::clock_skew(reported_at, received) {
+                let previous = node
+                    .metadata
+                    .insert(
+                        super::heartbeat::CLOCK_SKEW_KEY.to_string(),
+                        skew.to_string(),
+                    )
+                    .and_then(|previous| previous.parse::<i64>().ok());
+                super::heartbeat::update_skew_alert(&node.hostname, previous, skew, received).await;
+            }
 
             // node_name으로 키 생성
             let node_key = format!("cluster/nodes/{}", node.hostname);
             let node_json = serde_json::to_string(&node)?;
             etcd::put(&node_key, &node_json).await?;
 
+            self.record_heartbeat(&node.hostname, node.last_heartbeat)
+                .await?;
+
             logd!(1, "Updated heartbeat for node {}", node_id);
         }
         Ok(())
     }
 
+    /// Get the persisted heartbeat history of a node, oldest first
+    pub async fn get_heartbeat_history(
+        &self,
+        node_id: &str,
+    ) -> Result<Vec<i64>, Box<dyn std::error::Error + Send + Sync>> {
+        let hostname = match self.get_node(node_id).await? {
+            Some(node) => node.hostname,
+            None => return Ok(vec![]),
+        };
+
+        let history_key = format!("{}{}", HEARTBEAT_HISTORY_PREFIX, hostname);
+        match etcd::get(&history_key).await {
+            Ok(json_str) => Ok(serde_json::from_str::<Vec<i64>>(&json_str)?),
+            Err(_) => Ok(vec![]),
+        }
+    }
+
+    /// Append a heartbeat timestamp to the node's bounded history
+    async fn record_heartbeat(
+        &self,
+        hostname: &str,
+        timestamp: i64,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let history_key = format!("{}{}", HEARTBEAT_HISTORY_PREFIX, hostname);
+        let history = match etcd::get(&history_key).await {
+            Ok(json_str) => serde_json::from_str::<Vec<i64>>(&json_str).unwrap_or_default(),
+            Err(_) => vec![],
+        };
+
+        let history = append_heartbeat(history, timestamp);
+        etcd::put(&history_key, &serde_json::to_string(&history)?).await?;
+        Ok(())
+    }
+
+    /// Get the status metrics last streamed by a node
+    pub async fn get_status_metrics(
+        &self,
+        node_id: &str,
+    ) -> Result<HashMap<String, String>, Box<dyn std::error::Error + Send + Sync>> {
+        let hostname = match self.get_node(node_id).await? {
+            Some(node) => node.hostname,
+            None => return Ok(HashMap::new()),
+        };
+
+        let metrics_key = format!("{}{}", STATUS_METRICS_PREFIX, hostname);
+        match etcd::get(&metrics_key).await {
+            Ok(json_str) => Ok(serde_json::from_str(&json_str)?),
+            Err(_) => Ok(HashMap::new()),
+        }
+    }
+
+    /// Store the status metrics of a node, replacing the previous ones
+    pub async fn put_status_metrics(
+        &self,
+        node_id: &str,
+        metrics: &HashMap<String, String>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(node) = self.get_node(node_id).await? {
+            let metrics_key = format!("{}{}", STATUS_METRICS_PREFIX, node.hostname);
+            etcd::put(&metrics_key, &serde_json::to_string(metrics)?).await?;
+        }
+        Ok(())
+    }
+
     /// Update node status
+    ///
+    /// The last heartbeat is left untouched so that a status change does not
+    /// hide a node that has stopped sending heartbeats.
     pub async fn update_status(
         &self,
         node_id: &str,
@@ -129,7 +244,6 @@ impl NodeManager {
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         if let Some(mut node) = self.get_node(node_id).await? {
             node.status = status.into();
-            node.last_heartbeat = chrono::Utc::now().timestamp();
 
             // node.hostname을 사용하여 키 생성 (node_id 대신)
             let node_key = format!("cluster/nodes/{}", node.hostname);
@@ -141,6 +255,18 @@ impl NodeManager {
         Ok(())
     }
 
+    /// Mark a node that reported in as Ready, unless it is in maintenance
+    pub async fn mark_ready(
+        &self,
+        node_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(node) = self.get_node(node_id).await? {
+            let status = super::maintenance::reported_status(node.status);
+            self.update_status(node_id, status).await?;
+        }
+        Ok(())
+    }
+
     /// Remove a node from the cluster
     pub async fn remove_node(
         &self,
@@ -151,6 +277,9 @@ impl NodeManager {
             let node_key = format!("cluster/nodes/{}", node.hostname);
             etcd::delete(&node_key).await?;
 
+            let history_key = format!("{}{}", HEARTBEAT_HISTORY_PREFIX, node.hostname);
+            let _ = etcd::delete(&history_key).await;
+
             logd!(2, "Removed node {} from cluster", node_id);
             return Ok(());
         }
@@ -160,6 +289,16 @@ impl NodeManager {
     }
 }
 
+/// Append `timestamp` to `history`, keeping only the newest entries
+fn append_heartbeat(mut history: Vec<i64>, timestamp: i64) -> Vec<i64> {
+    history.push(timestamp);
+    if history.len() > HEARTBEAT_HISTORY_LIMIT {
+        let excess = history.len() - HEARTBEAT_HISTORY_LIMIT;
+        history.drain(..excess);
+    }
+    history
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -708,4 +847,29 @@ mod tests {
         );
         assert_eq!(request.metadata.get("rack"), Some(&"A-42".to_string()));
     }
+
+    #[test]
+    fn test_append_heartbeat_keeps_newest_entries() {
+        let mut history = vec![];
+        for ts in 0..(HEARTBEAT_HISTORY_LIMIT as i64 + 5) {
+            history = append_heartbeat(history, ts);
+        }
+
+        assert_eq!(history.len(), HEARTBEAT_HISTORY_LIMIT);
+        assert_eq!(history.first(), Some(&5));
+        assert_eq!(history.last(), Some(&(HEARTBEAT_HISTORY_LIMIT as i64 + 4)));
+    }
+
+    #[tokio::test]
+    async fn test_get_heartbeat_history_unknown_node() {
+        let manager = NodeManager::new().expect("Failed to create NodeManager");
+
+        match manager.get_heartbeat_history("nonexistent-node-999").await {
+            Ok(history) => assert!(history.is_empty()),
+            Err(e) => {
+                // Expected if etcd is not available during testing
+                logd!(4, "Expected etcd connection error: {}", e);
+            }
+        }
+    }
 }
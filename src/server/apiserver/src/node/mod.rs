@@ -5,6 +5,9 @@
 
 //! Node management modules
 
+pub mod bootstrap;
+pub mod heartbeat;
+pub mod maintenance;
 pub mod manager;
 pub mod node_lookup;
 pub mod registry;
@@ -5,11 +5,21 @@
 
 //! Node registry for cluster membership management
 
+use crate::node::status::{ClusterHealthSummary, NodeStatusManager};
+use crate::node::NodeManager;
 use base64::Engine;
-use common::apiserver::{ClusterTopology, TopologyType};
-use common::etcd;
+use common::apiserver::{ClusterHealth, ClusterTopology, NodeInfo, TopologyType};
+use common::error::ApiError;
+use common::etcd::{self, StoreError};
 use common::logd;
+use common::nodeagent::fromapiserver::{NodeRole, NodeStatus};
 use prost::Message;
+use std::collections::HashSet;
+
+/// etcd key holding the default cluster topology
+const TOPOLOGY_KEY: &str = "cluster/topology";
+/// etcd prefix holding the topologies of named clusters
+const CLUSTER_PREFIX: &str = "cluster/topologies/";
 
 /// Node registry for managing cluster topology
 #[derive(Clone)]
@@ -21,27 +31,79 @@ impl NodeRegistry {
     pub async fn get_topology(
         &self,
     ) -> Result<ClusterTopology, Box<dyn std::error::Error + Send + Sync>> {
-        let topology_key = "cluster/topology";
-
-        match etcd::get(topology_key).await {
-            Ok(encoded) => {
-                let buf = base64::engine::general_purpose::STANDARD.decode(&encoded)?;
-                let topology = ClusterTopology::decode(&buf[..])?;
-                Ok(topology)
-            }
-            Err(_) => {
-                // Return default topology if not found
-                Ok(ClusterTopology {
-                    cluster_id: "default-cluster".to_string(),
-                    cluster_name: "Pullpiri Cluster".to_string(),
-                    r#type: TopologyType::Embedded.into(),
-                    master_nodes: vec![],
-                    sub_nodes: vec![],
-                    parent_cluster: String::new(),
-                    config: std::collections::HashMap::new(),
+        match etcd::get(TOPOLOGY_KEY).await {
+            Ok(stored) => decode_topology(&stored),
+            // Return default topology if not found
+            Err(_) => Ok(default_topology()),
+        }
+    }
+
+    /// Rebuild the cluster view from etcd after an apiserver restart
+    ///
+    /// Nodes whose last persisted heartbeat is older than
+    /// `heartbeat_timeout_seconds` are marked `NotReady`, and the topology
+    /// master/sub node lists are regenerated from the registered nodes.
+    pub async fn rebuild_from_etcd(
+        &self,
+        heartbeat_timeout_seconds: u64,
+    ) -> Result<ClusterTopology, Box<dyn std::error::Error + Send + Sync>> {
+        let stale = self.mark_stale_nodes(heartbeat_timeout_seconds).await?;
+        let nodes = NodeManager::new()?.get_all_nodes().await?;
+
+        // Nodes assigned to a named cluster stay there, the rest belong to the default one
+        let mut assigned = HashSet::new();
+        for mut cluster in self.get_named_clusters().await? {
+            assigned.extend(member_ids(&cluster));
+            refresh_members(&mut cluster, &nodes);
+            self.put_named_cluster(&cluster).await?;
+        }
+
+        let mut topology = self.get_topology().await?;
+        topology.master_nodes = vec![];
+        topology.sub_nodes = vec![];
+        for node in nodes.iter().filter(|n| !assigned.contains(&n.node_id)) {
+            add_member(&mut topology, node.clone());
+        }
+
+        logd!(
+            2,
+            "Rebuilt node registry from etcd: {} nodes, {} stale",
+            nodes.len(),
+            stale.len()
+        );
+        self.update_topology(topology).await
+    }
+
+    /// Mark nodes without a recent heartbeat as `NotReady`
+    ///
+    /// Returns the ids of the nodes whose status was changed.
+    pub async fn mark_stale_nodes(
+        &self,
+        heartbeat_timeout_seconds: u64,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+        let node_manager = NodeManager::new()?;
+        let nodes = node_manager.get_all_nodes().await?;
+
+        let stale: Vec<String> = NodeStatusManager
+            .get_unhealthy_nodes(&nodes, heartbeat_timeout_seconds)
+            .into_iter()
+            .filter(|node_id| {
+                nodes.iter().any(|node| {
+                    &node.node_id == node_id
+                        && node.status != NodeStatus::NotReady as i32
+                        && node.status != NodeStatus::Maintenance as i32
+                        && node.status != NodeStatus::Terminating as i32
                 })
-            }
+            })
+            .collect();
+
+        for node_id in &stale {
+            logd!(4, "Node {} missed heartbeats, marking NotReady", node_id);
+            node_manager
+                .update_status(node_id, NodeStatus::NotReady)
+                .await?;
         }
+        Ok(stale)
     }
 
     /// Update the cluster topology
@@ -49,12 +111,10 @@ impl NodeRegistry {
         &self,
         topology: ClusterTopology,
     ) -> Result<ClusterTopology, Box<dyn std::error::Error + Send + Sync>> {
-        let topology_key = "cluster/topology";
-
         // 인코딩을 제거하고 json string으로 변환
         let topology_json = serde_json::to_string(&topology)?;
 
-        etcd::put(topology_key, &topology_json).await?;
+        etcd::put(TOPOLOGY_KEY, &topology_json).await?;
 
         logd!(2, "Updated cluster topology: {}", topology.cluster_name);
         Ok(topology)
@@ -82,6 +142,291 @@ impl NodeRegistry {
         self.update_topology(default_topology).await?;
         Ok(())
     }
+
+    /// List the default cluster followed by all named clusters
+    pub async fn list_clusters(
+        &self,
+    ) -> Result<Vec<ClusterTopology>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut clusters = vec![self.get_topology().await?];
+        clusters.extend(self.get_named_clusters().await?);
+        Ok(clusters)
+    }
+
+    /// Get a cluster by id, an empty id selects the default cluster
+    pub async fn get_cluster(
+        &self,
+        cluster_id: &str,
+    ) -> Result<Option<ClusterTopology>, Box<dyn std::error::Error + Send + Sync>> {
+        if cluster_id.is_empty() {
+            return Ok(Some(self.get_topology().await?));
+        }
+        if let Ok(stored) = etcd::get(&cluster_key(cluster_id)).await {
+            return Ok(Some(decode_topology(&stored)?));
+        }
+
+        let default = self.get_topology().await?;
+        Ok((default.cluster_id == cluster_id).then_some(default))
+    }
+
+    /// Create a new named cluster
+    ///
+    /// The cluster id must be unique and, if set, `parent_cluster` must refer
+    /// to an existing cluster.
+    pub async fn create_cluster(
+        &self,
+        topology: ClusterTopology,
+    ) -> Result<ClusterTopology, Box<dyn std::error::Error + Send + Sync>> {
+        validate_cluster_id(&topology.cluster_id)?;
+        if self.get_cluster(&topology.cluster_id).await?.is_some() {
+            return Err(ApiError::conflict(format!(
+                "cluster '{}' already exists",
+                topology.cluster_id
+            ))
+            .into());
+        }
+        if !topology.parent_cluster.is_empty()
+            && self.get_cluster(&topology.parent_cluster).await?.is_none()
+        {
+            return Err(ApiError::not_found(format!(
+                "parent cluster '{}' does not exist",
+                topology.parent_cluster
+            ))
+            .into());
+        }
+
+        self.put_named_cluster(&topology).await?;
+        logd!(2, "Created cluster: {}", topology.cluster_id);
+        Ok(topology)
+    }
+
+    /// Update a named cluster, or the default cluster if no named cluster has the id
+    pub async fn update_cluster(
+        &self,
+        topology: ClusterTopology,
+    ) -> Result<ClusterTopology, Box<dyn std::error::Error + Send + Sync>> {
+        if etcd::get(&cluster_key(&topology.cluster_id)).await.is_ok() {
+            self.put_named_cluster(&topology).await?;
+            Ok(topology)
+        } else {
+            self.update_topology(topology).await
+        }
+    }
+
+    /// Delete a named cluster and move its nodes to the default cluster
+    pub async fn delete_cluster(
+        &self,
+        cluster_id: &str,
+    ) -> Result<ClusterTopology, Box<dyn std::error::Error + Send + Sync>> {
+        let key = cluster_key(cluster_id);
+        let removed = match etcd::get(&key).await {
+            Ok(stored) => decode_topology(&stored)?,
+            Err(StoreError::NotFound) => {
+                return Err(
+                    ApiError::not_found(format!("cluster '{}' does not exist", cluster_id)).into(),
+                )
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let children: Vec<String> = self
+            .get_named_clusters()
+            .await?
+            .into_iter()
+            .filter(|c| c.parent_cluster == cluster_id)
+            .map(|c| c.cluster_id)
+            .collect();
+        if !children.is_empty() {
+            return Err(ApiError::conflict(format!(
+                "cluster '{}' is the parent of {}",
+                cluster_id,
+                children.join(", ")
+            ))
+            .into());
+        }
+
+        etcd::delete(&key).await?;
+
+        let mut default = self.get_topology().await?;
+        for node in removed.master_nodes.iter().chain(removed.sub_nodes.iter()) {
+            add_member(&mut default, node.clone());
+        }
+        self.update_topology(default).await?;
+
+        logd!(2, "Deleted cluster: {}", cluster_id);
+        Ok(removed)
+    }
+
+    /// Move a node into a cluster, removing it from any other cluster
+    pub async fn assign_node(
+        &self,
+        cluster_id: &str,
+        node_id: &str,
+    ) -> Result<ClusterTopology, Box<dyn std::error::Error + Send + Sync>> {
+        let node = NodeManager::new()?
+            .get_node(node_id)
+            .await?
+            .ok_or_else(|| ApiError::not_found(format!("node '{}' not found", node_id)))?;
+        let mut target = self.get_cluster(cluster_id).await?.ok_or_else(|| {
+            ApiError::not_found(format!("cluster '{}' does not exist", cluster_id))
+        })?;
+
+        for mut cluster in self.list_clusters().await? {
+            if cluster.cluster_id != target.cluster_id && remove_member(&mut cluster, &node.node_id)
+            {
+                self.update_cluster(cluster).await?;
+            }
+        }
+
+        remove_member(&mut target, &node.node_id);
+        add_member(&mut target, node);
+        logd!(
+            2,
+            "Assigned node {} to cluster {}",
+            node_id,
+            target.cluster_id
+        );
+        self.update_cluster(target).await
+    }
+
+    /// Health of the nodes in a cluster, based on their latest heartbeats
+    pub async fn get_cluster_health(
+        &self,
+        cluster_id: &str,
+        heartbeat_timeout_seconds: u64,
+    ) -> Result<ClusterHealth, Box<dyn std::error::Error + Send + Sync>> {
+        let mut topology = self.get_cluster(cluster_id).await?.ok_or_else(|| {
+            ApiError::not_found(format!("cluster '{}' does not exist", cluster_id))
+        })?;
+        let nodes = NodeManager::new()?.get_all_nodes().await?;
+        refresh_members(&mut topology, &nodes);
+
+        let members: Vec<NodeInfo> = topology
+            .master_nodes
+            .into_iter()
+            .chain(topology.sub_nodes)
+            .collect();
+        let summary =
+            NodeStatusManager.get_cluster_health_summary(&members, heartbeat_timeout_seconds);
+        Ok(to_cluster_health(&topology.cluster_id, &summary))
+    }
+
+    /// Read all named clusters, skipping entries that cannot be decoded
+    async fn get_named_clusters(
+        &self,
+    ) -> Result<Vec<ClusterTopology>, Box<dyn std::error::Error + Send + Sync>> {
+        let kvs = etcd::get_all_with_prefix(CLUSTER_PREFIX).await?;
+        let mut clusters = Vec::new();
+        for (key, value) in kvs {
+            match decode_topology(&value) {
+                Ok(topology) => clusters.push(topology),
+                Err(e) => logd!(5, "Failed to parse cluster topology {}: {}", key, e),
+            }
+        }
+        Ok(clusters)
+    }
+
+    async fn put_named_cluster(
+        &self,
+        topology: &ClusterTopology,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let topology_json = serde_json::to_string(topology)?;
+        etcd::put(&cluster_key(&topology.cluster_id), &topology_json).await?;
+        Ok(())
+    }
+}
+
+fn cluster_key(cluster_id: &str) -> String {
+    format!("{}{}", CLUSTER_PREFIX, cluster_id)
+}
+
+/// Cluster ids are used in etcd keys and REST paths
+fn validate_cluster_id(cluster_id: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let valid = !cluster_id.is_empty()
+        && cluster_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+    if valid {
+        Ok(())
+    } else {
+        Err(ApiError::validation(format!("invalid cluster id '{}'", cluster_id)).into())
+    }
+}
+
+/// Ids of the nodes listed in a topology
+fn member_ids(topology: &ClusterTopology) -> HashSet<String> {
+    topology
+        .master_nodes
+        .iter()
+        .chain(topology.sub_nodes.iter())
+        .map(|node| node.node_id.clone())
+        .collect()
+}
+
+/// Add a node to the master or sub node list depending on its role
+fn add_member(topology: &mut ClusterTopology, node: NodeInfo) {
+    if node.node_role == NodeRole::Master as i32 {
+        topology.master_nodes.push(node);
+    } else {
+        topology.sub_nodes.push(node);
+    }
+}
+
+/// Remove a node from a topology, returning whether it was a member
+fn remove_member(topology: &mut ClusterTopology, node_id: &str) -> bool {
+    let before = topology.master_nodes.len() + topology.sub_nodes.len();
+    topology.master_nodes.retain(|node| node.node_id != node_id);
+    topology.sub_nodes.retain(|node| node.node_id != node_id);
+    before != topology.master_nodes.len() + topology.sub_nodes.len()
+}
+
+/// Replace the node snapshots of a topology with the registered node info
+fn refresh_members(topology: &mut ClusterTopology, nodes: &[NodeInfo]) {
+    for member in topology
+        .master_nodes
+        .iter_mut()
+        .chain(topology.sub_nodes.iter_mut())
+    {
+        if let Some(node) = nodes.iter().find(|n| n.node_id == member.node_id) {
+            *member = node.clone();
+        }
+    }
+}
+
+fn to_cluster_health(cluster_id: &str, summary: &ClusterHealthSummary) -> ClusterHealth {
+    ClusterHealth {
+        cluster_id: cluster_id.to_string(),
+        total_nodes: summary.total_nodes as u32,
+        healthy_nodes: summary.healthy_nodes as u32,
+        unhealthy_nodes: summary.unhealthy_nodes as u32,
+        master_nodes: summary.master_nodes as u32,
+        nodeagent_nodes: summary.nodeagent_nodes as u32,
+        ready_nodes: summary.ready_nodes as u32,
+        status: format!("{:?}", summary.cluster_status),
+    }
+}
+
+/// Decode a stored topology, accepting the legacy base64 protobuf format
+fn decode_topology(
+    stored: &str,
+) -> Result<ClusterTopology, Box<dyn std::error::Error + Send + Sync>> {
+    if let Ok(topology) = serde_json::from_str::<ClusterTopology>(stored) {
+        return Ok(topology);
+    }
+
+    let buf = base64::engine::general_purpose::STANDARD.decode(stored)?;
+    Ok(ClusterTopology::decode(&buf[..])?)
+}
+
+/// Topology used when nothing has been stored yet
+fn default_topology() -> ClusterTopology {
+    ClusterTopology {
+        cluster_id: "default-cluster".to_string(),
+        cluster_name: "Pullpiri Cluster".to_string(),
+        r#type: TopologyType::Embedded.into(),
+        master_nodes: vec![],
+        sub_nodes: vec![],
+        parent_cluster: String::new(),
+        config: std::collections::HashMap::new(),
+    }
 }
 
 #[cfg(test)]
@@ -731,4 +1076,96 @@ mod tests {
             Some(&"10".to_string())
         );
     }
+
+    #[test]
+    fn test_decode_topology_json_and_legacy_formats() {
+        let topology = create_test_topology("json-cluster", "JSON Cluster", TopologyType::Embedded);
+
+        let json = serde_json::to_string(&topology).unwrap();
+        let decoded = decode_topology(&json).unwrap();
+        assert_eq!(decoded.cluster_id, "json-cluster");
+
+        let legacy = base64::engine::general_purpose::STANDARD.encode(topology.encode_to_vec());
+        let decoded = decode_topology(&legacy).unwrap();
+        assert_eq!(decoded.cluster_name, "JSON Cluster");
+
+        assert!(decode_topology("not a topology").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_from_etcd() {
+        let registry = NodeRegistry;
+
+        match registry
+            .rebuild_from_etcd(crate::node::heartbeat::liveness_window())
+            .await
+        {
+            Ok(topology) => {
+                assert!(!topology.cluster_id.is_empty());
+                assert!(topology
+                    .master_nodes
+                    .iter()
+                    .all(|node| node.node_role == NodeRole::Master as i32));
+            }
+            Err(e) => {
+                // Expected if etcd is not available during testing
+                logd!(4, "Expected error rebuilding registry: {}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cluster_membership_helpers() {
+        let mut topology = ClusterTopology {
+            cluster_id: "zone-a".to_string(),
+            ..Default::default()
+        };
+        let master = create_test_node_info("m1", "master-1", "10.0.0.1", NodeRole::Master);
+        let sub = create_test_node_info("s1", "sub-1", "10.0.0.2", NodeRole::Nodeagent);
+
+        add_member(&mut topology, master);
+        add_member(&mut topology, sub.clone());
+        assert_eq!(topology.master_nodes.len(), 1);
+        assert_eq!(topology.sub_nodes.len(), 1);
+        assert_eq!(
+            member_ids(&topology),
+            HashSet::from(["m1".to_string(), "s1".to_string()])
+        );
+
+        let mut updated = sub;
+        updated.ip_address = "10.0.0.99".to_string();
+        refresh_members(&mut topology, &[updated]);
+        assert_eq!(topology.sub_nodes[0].ip_address, "10.0.0.99");
+
+        assert!(remove_member(&mut topology, "s1"));
+        assert!(!remove_member(&mut topology, "s1"));
+        assert!(topology.sub_nodes.is_empty());
+    }
+
+    #[test]
+    fn test_validate_cluster_id() {
+        assert!(validate_cluster_id("zone-a_1.eu").is_ok());
+        assert!(validate_cluster_id("").is_err());
+        assert!(validate_cluster_id("zone/a").is_err());
+        assert!(validate_cluster_id("zone a").is_err());
+    }
+
+    #[test]
+    fn test_to_cluster_health() {
+        let summary = ClusterHealthSummary {
+            total_nodes: 3,
+            healthy_nodes: 2,
+            unhealthy_nodes: 1,
+            master_nodes: 1,
+            nodeagent_nodes: 2,
+            ready_nodes: 2,
+            cluster_status: crate::node::status::ClusterStatus::Degraded,
+        };
+
+        let health = to_cluster_health("zone-a", &summary);
+        assert_eq!(health.cluster_id, "zone-a");
+        assert_eq!(health.total_nodes, 3);
+        assert_eq!(health.unhealthy_nodes, 1);
+        assert_eq!(health.status, "Degraded");
+    }
 }
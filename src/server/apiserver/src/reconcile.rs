@@ -0,0 +1,387 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Startup reconciliation of the states recorded in etcd with the workloads
+//! that actually run on the nodes.
+//!
+//! After an ApiServer crash etcd may still claim that a scenario is playing
+//! while none of its containers exists anymore. Before the scenarios are
+//! handed to FilterGateway again, every registered NodeAgent is asked for its
+//! containers. A playing scenario whose target package has no running model is
+//! sent back to Waiting through StateManager, so that its condition is
+//! evaluated and its action is executed again.
+
+use common::apiserver::NodeInfo;
+use common::logd;
+use common::monitoringserver::ContainerInfo;
+use common::spec::artifact::package::ModelInfo;
+use common::spec::artifact::{Artifact, Package, Scenario};
+use common::statemanager::{AsilLevel, ResourceType, ScenarioState, StateChange};
+use std::collections::HashSet;
+
+/// Annotation ActionController adds to the containers of a model
+const MODEL_ANNOTATION: &str = "io.pullpiri.annotations.model";
+
+/// Actual state of a workload as seen by the NodeAgents
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Observed {
+    /// At least one container of the workload is running
+    Running,
+    /// The workload has no running container
+    Stopped,
+    /// The NodeAgent of a node the workload may run on did not answer
+    Unknown,
+}
+
+/// Containers reported by the NodeAgents of the cluster
+#[derive(Debug, Default)]
+struct Observation {
+    /// Nodes whose NodeAgent answered
+    reachable: HashSet<String>,
+    /// Registered nodes whose NodeAgent did not answer
+    unreachable: HashSet<String>,
+    /// Models with at least one running container
+    running: HashSet<String>,
+}
+
+impl Observation {
+    /// Record the containers reported by the NodeAgent of `node`
+    fn add_node(&mut self, node: &str, containers: &[ContainerInfo]) {
+        self.reachable.insert(node.to_string());
+        for container in containers {
+            if !is_running(container) {
+                continue;
+            }
+            if let Some(model) = model_of(container) {
+                self.running.insert(model);
+            }
+        }
+    }
+
+    /// Record a node whose NodeAgent could not be queried
+    fn add_unreachable(&mut self, node: &str) {
+        self.unreachable.insert(node.to_string());
+    }
+
+    /// Actual state of a model of a package
+    fn model_state(&self, model: &ModelInfo) -> Observed {
+        if self.running.contains(&model.get_name()) {
+            return Observed::Running;
+        }
+        let node = model.get_node();
+        if self.reachable.contains(&node) {
+            Observed::Stopped
+        } else if self.unreachable.contains(&node) {
+            Observed::Unknown
+        } else if self.unreachable.is_empty() {
+            // Chosen at launch time or not registered: no answering node runs it
+            Observed::Stopped
+        } else {
+            Observed::Unknown
+        }
+    }
+
+    /// Actual state of a package, `Running` as soon as one model runs
+    fn package_state(&self, package: &Package) -> Observed {
+        let states: Vec<Observed> = package
+            .get_models()
+            .iter()
+            .map(|model| self.model_state(model))
+            .collect();
+        if states.is_empty() || states.contains(&Observed::Running) {
+            Observed::Running
+        } else if states.contains(&Observed::Unknown) {
+            Observed::Unknown
+        } else {
+            Observed::Stopped
+        }
+    }
+}
+
+/// `true` if the container reported by NodeAgent is running
+fn is_running(container: &ContainerInfo) -> bool {
+    container.state.get("Status").map(String::as_str) == Some("running")
+}
+
+/// Name of the model a container belongs to
+///
+/// ActionController annotates the containers it starts. Containers without
+/// the annotation are named `{model}_{container}` by NodeAgent.
+fn model_of(container: &ContainerInfo) -> Option<String> {
+    if let Some(model) = container.annotation.get(MODEL_ANNOTATION) {
+        return Some(model.clone());
+    }
+    let name = container.names.first()?.trim_start_matches('/');
+    let (model, _) = name.split_once('_')?;
+    Some(model.to_string())
+}
+
+/// `true` if the scenario state means that its action was executed
+pub(crate) fn is_playing(state: ScenarioState) -> bool {
+    matches!(state, ScenarioState::Allowed | ScenarioState::Completed)
+}
+
+/// `true` if the action keeps workloads running once it was executed
+pub(crate) fn launches_workload(action: &str) -> bool {
+    matches!(action, "launch" | "update" | "rollback" | "create")
+}
+
+/// Short state name StateManager accepts in a StateChange, e.g. `completed`
+fn short_state_name(state: ScenarioState) -> String {
+    state
+        .as_str_name()
+        .trim_start_matches("SCENARIO_STATE_")
+        .to_ascii_lowercase()
+}
+
+/// Compare the recorded scenario states with the running workloads and
+/// correct the scenarios whose workload is gone
+///
+/// ### Description
+/// This function is called once when the apiserver starts, before the
+/// scenarios are reloaded into FilterGateway.
+pub async fn reconcile() {
+    let scenarios = match crate::artifact::data::read_all_scenario_from_etcd().await {
+        Ok(scenarios) => scenarios,
+        Err(e) => {
+            logd!(4, "Reconciliation skipped, cannot read scenarios: {:?}", e);
+            return;
+        }
+    };
+    if scenarios.is_empty() {
+        return;
+    }
+
+    let observation = observe_nodes().await;
+    logd!(
+        2,
+        "Reconciliation: {} node(s) answered, {} unreachable, {} running model(s)",
+        observation.reachable.len(),
+        observation.unreachable.len(),
+        observation.running.len()
+    );
+
+    for yaml in scenarios {
+        let scenario: Scenario = match serde_yaml::from_str(&yaml) {
+            Ok(scenario) => scenario,
+            Err(e) => {
+                logd!(4, "Reconciliation: invalid scenario in etcd: {}", e);
+                continue;
+            }
+        };
+        reconcile_scenario(&scenario, &observation).await;
+    }
+}
+
+/// Ask the NodeAgent of every registered node for its containers
+async fn observe_nodes() -> Observation {
+    let mut observation = Observation::default();
+    let nodes: Vec<NodeInfo> = match crate::node::NodeManager::new() {
+        Ok(manager) => manager.get_all_nodes().await.unwrap_or_default(),
+        Err(e) => {
+            logd!(4, "Reconciliation: cannot read nodes: {:?}", e);
+            Vec::new()
+        }
+    };
+
+    for node in nodes {
+        match crate::grpc::sender::nodeagent::list_containers(node.ip_address.clone()).await {
+            Ok(response) => {
+                observation.add_node(&node.hostname, &response.into_inner().containers);
+            }
+            Err(e) => {
+                logd!(
+                    4,
+                    "Reconciliation: NodeAgent of {} did not answer: {}",
+                    node.hostname,
+                    e.message()
+                );
+                observation.add_unreachable(&node.hostname);
+            }
+        }
+    }
+    observation
+}
+
+/// Send a playing scenario back to Waiting if its target package stopped
+async fn reconcile_scenario(scenario: &Scenario, observation: &Observation) {
+    let name = scenario.get_qualified_name();
+    let recorded = match common::etcd::get(&format!("/scenario/{}/state", name)).await {
+        Ok(value) => ScenarioState::from_str_name(&value).unwrap_or(ScenarioState::Unspecified),
+        Err(_) => return,
+    };
+    if !is_playing(recorded) || !launches_workload(&scenario.get_actions()) {
+        return;
+    }
+
+    let package_key = common::spec::namespace::artifact_key(
+        &scenario.get_namespace(),
+        "Package",
+        &scenario.get_targets(),
+    );
+    let package: Package = match crate::artifact::data::read_from_etcd(&package_key)
+        .await
+        .ok()
+        .and_then(|yaml| serde_yaml::from_str(&yaml).ok())
+    {
+        Some(package) => package,
+        None => {
+            logd!(4, "Reconciliation: cannot read {} of {}", package_key, name);
+            return;
+        }
+    };
+
+    match observation.package_state(&package) {
+        Observed::Running => {}
+        Observed::Unknown => logd!(
+            3,
+            "Reconciliation: state of scenario {} unknown, a node did not answer",
+            name
+        ),
+        Observed::Stopped => {
+            logd!(
+                3,
+                "Reconciliation: scenario {} is {} but package {} does not run",
+                name,
+                recorded.as_str_name(),
+                package.get_name()
+            );
+            send_correction(&name, recorded, ScenarioState::Waiting).await;
+        }
+    }
+}
+
+/// Ask StateManager to move a scenario to the state that matches reality
+async fn send_correction(name: &str, recorded: ScenarioState, target: ScenarioState) {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as i64;
+
+    let state_change = StateChange {
+        resource_type: ResourceType::Scenario as i32,
+        resource_name: name.to_string(),
+        current_state: short_state_name(recorded),
+        target_state: short_state_name(target),
+        transition_id: format!("apiserver-reconcile-{}", timestamp),
+        timestamp_ns: timestamp,
+        source: "apiserver".to_string(),
+        asil_level: AsilLevel::Unspecified as i32,
+        trace_id: common::trace::current_trace_id().unwrap_or_default(),
+    };
+
+    let mut sender = crate::grpc::sender::statemanager::StateManagerSender::new();
+    match sender.send_state_change(state_change).await {
+        Ok(_) => logd!(
+            2,
+            "Reconciliation: scenario {} corrected to {}",
+            name,
+            target.as_str_name()
+        ),
+        Err(e) => logd!(
+            5,
+            "Reconciliation: failed to correct scenario {}: {:?}",
+            name,
+            e
+        ),
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn container(name: &str, status: &str, model: Option<&str>) -> ContainerInfo {
+        let mut annotation = HashMap::new();
+        if let Some(model) = model {
+            annotation.insert(MODEL_ANNOTATION.to_string(), model.to_string());
+        }
+        ContainerInfo {
+            id: name.to_string(),
+            names: vec![format!("/{}", name)],
+            state: HashMap::from([("Status".to_string(), status.to_string())]),
+            annotation,
+            ..Default::default()
+        }
+    }
+
+    fn package(models: &[(&str, &str)]) -> Package {
+        let models: String = models
+            .iter()
+            .map(|(name, node)| {
+                format!(
+                    "\n    - name: {}\n      node: {}\n      resources: {{}}",
+                    name, node
+                )
+            })
+            .collect();
+        serde_yaml::from_str(&format!(
+            "apiVersion: v1\nkind: Package\nmetadata:\n  name: pkg\nspec:\n  pattern:\n    - type: plain\n  models:{}",
+            models
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_model_of_prefers_annotation() {
+        assert_eq!(
+            model_of(&container("pod_app", "running", Some("helloworld"))),
+            Some("helloworld".to_string())
+        );
+        assert_eq!(
+            model_of(&container("helloworld_app", "running", None)),
+            Some("helloworld".to_string())
+        );
+        assert_eq!(model_of(&container("unrelated", "running", None)), None);
+    }
+
+    #[test]
+    fn test_package_state_from_observation() {
+        let mut observation = Observation::default();
+        observation.add_node(
+            "node-a",
+            &[
+                container("a_app", "running", None),
+                container("b_app", "exited", None),
+            ],
+        );
+
+        assert_eq!(
+            observation.package_state(&package(&[("a", "node-a"), ("b", "node-a")])),
+            Observed::Running
+        );
+        assert_eq!(
+            observation.package_state(&package(&[("b", "node-a")])),
+            Observed::Stopped
+        );
+        assert_eq!(
+            observation.package_state(&package(&[("c", "node-x")])),
+            Observed::Stopped
+        );
+
+        observation.add_unreachable("node-b");
+        assert_eq!(
+            observation.package_state(&package(&[("b", "node-a"), ("c", "node-b")])),
+            Observed::Unknown
+        );
+        assert_eq!(
+            observation.package_state(&package(&[("c", "node-x")])),
+            Observed::Unknown
+        );
+    }
+
+    #[test]
+    fn test_playing_scenarios_and_state_names() {
+        assert!(is_playing(ScenarioState::Completed));
+        assert!(is_playing(ScenarioState::Allowed));
+        assert!(!is_playing(ScenarioState::Waiting));
+        assert!(launches_workload("launch"));
+        assert!(!launches_workload("terminate"));
+        assert_eq!(short_state_name(ScenarioState::Completed), "completed");
+        assert_eq!(short_state_name(ScenarioState::Waiting), "waiting");
+    }
+}
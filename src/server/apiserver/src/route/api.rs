@@ -5,21 +5,173 @@
 
 //! Handler functions of Pullpiri REST API
 
+use crate::grpc::sender::filtergateway::{change_topic, TopicChange};
+use crate::node::registry::NodeRegistry;
 use axum::{
-    response::Response,
-    routing::{delete, get, post},
-    Router,
+    extract::{Path, Query},
+    handler::Handler,
+    http::{Method, StatusCode},
+    middleware::from_fn_with_state,
+    response::{IntoResponse, Response},
+    routing::{on, MethodFilter, MethodRouter},
+    Json, Router,
 };
+use common::apiserver::ClusterTopology;
+use common::auth::{require_role, Role};
+use common::error::ApiError;
+use common::etcd::StoreError;
+use common::filtergateway::{Reliability, SubscribeTopicRequest, TopicSubscription};
+use common::listing::{self, ListMeta, ListQuery};
+use common::spec::namespace;
+use std::collections::HashMap;
+
+/// Route of the API
+pub(super) struct Route {
+    pub method: Method,
+    pub path: &'static str,
+    /// Handler for the method of the route
+    handler: Box<dyn FnOnce(MethodFilter) -> MethodRouter>,
+}
+
+fn route<H, T>(method: Method, path: &'static str, handler: H) -> Route
+where
+    H: Handler<T, ()>,
+    T: 'static,
+{
+    Route {
+        method,
+        path,
+        handler: Box::new(|filter| on(filter, handler)),
+    }
+}
+
+/// Routes of the API by the role they need, `None` for no role
+///
+/// Reads need the viewer role, triggering scenarios and pausing topics the
+/// operator role, and artifact, cluster, quota and subscription changes the
+/// admin role. Bootstrap tokens are redeemed without a role, the token is
+/// the credential. The OpenAPI document is checked against these routes.
+pub(super) fn routes() -> Vec<(Option<Role>, Vec<Route>)> {
+    let read = vec![
+        route(Method::GET, "/api/openapi.json", super::openapi::serve),
+        route(Method::GET, "/api/notify", notify),
+        route(Method::POST, "/api/artifact/validate", validate_artifact),
+        route(Method::GET, "/api/namespaces", list_namespaces),
+        route(Method::GET, "/api/artifact/:kind", list_artifacts),
+        route(Method::GET, "/api/artifact/:kind/:name", get_artifact),
+        route(
+            Method::GET,
+            "/api/artifact/:kind/:name/versions",
+            list_versions,
+        ),
+        route(Method::GET, "/api/artifact/:kind/:name/diff", diff_versions),
+        route(
+            Method::GET,
+            "/api/artifact/:kind/:name/export",
+            export_artifact,
+        ),
+        route(Method::GET, "/api/clusters", list_clusters),
+        route(Method::GET, "/api/clusters/:id", get_cluster),
+        route(Method::GET, "/api/clusters/:id/health", get_cluster_health),
+        route(Method::GET, "/api/nodes/allocation", get_node_allocation),
+        route(Method::GET, "/api/quotas", list_quotas),
+        route(Method::GET, "/api/quotas/:name", get_quota),
+        route(
+            Method::GET,
+            "/api/v1/containers/:id/logs",
+            get_collected_logs,
+        ),
+        route(
+            Method::GET,
+            "/api/state/:kind/:name/history",
+            get_state_history,
+        ),
+        route(Method::GET, "/api/topics", list_topics),
+        route(Method::GET, "/api/v1/summary", get_summary),
+        route(Method::GET, "/api/v1/orphans", get_orphans),
+    ];
+
+    let operate = vec![
+        route(
+            Method::POST,
+            "/api/v1/scenarios/:name/trigger",
+            trigger_scenario,
+        ),
+        route(
+            Method::POST,
+            "/api/v1/scenarios/:name/reset",
+            reset_scenario,
+        ),
+        route(Method::POST, "/api/topics/:topic/pause", pause_topic),
+        route(Method::POST, "/api/topics/:topic/resume", resume_topic),
+    ];
+
+    let admin = vec![
+        route(Method::POST, "/api/artifact", apply_artifact),
+        route(Method::DELETE, "/api/artifact", withdraw_artifact),
+        route(
+            Method::POST,
+            "/api/artifact/transaction",
+            apply_artifact_transaction,
+        ),
+        route(
+            Method::POST,
+            "/api/artifact/:kind/:name/rollback/:version",
+            rollback_artifact,
+        ),
+        route(Method::POST, "/api/bundle/export", export_bundle),
+        route(Method::POST, "/api/bundle/import", import_bundle),
+        route(Method::POST, "/api/clusters", create_cluster),
+        route(Method::DELETE, "/api/clusters/:id", delete_cluster),
+        route(Method::PUT, "/api/clusters/:id/nodes/:node", assign_node),
+        route(Method::POST, "/api/v1/nodes/:id/drain", drain_node),
+        route(Method::POST, "/api/v1/nodes/:id/uncordon", uncordon_node),
+        route(Method::POST, "/api/v1/nodes/:id/migrate", migrate_node),
+        route(
+            Method::POST,
+            "/api/v1/nodes/bootstrap-token",
+            issue_bootstrap_token,
+        ),
+        route(Method::POST, "/api/topics", subscribe_topic),
+        route(Method::DELETE, "/api/topics/:topic", unsubscribe_topic),
+        route(Method::PUT, "/api/quotas/:name", put_quota),
+        route(Method::DELETE, "/api/quotas/:name", delete_quota),
+    ];
+
+    let bootstrap = vec![route(
+        Method::POST,
+        common::bootstrap::REDEEM_PATH,
+        redeem_bootstrap_token,
+    )];
+
+    vec![
+        (Some(Role::Viewer), read),
+        (Some(Role::Operator), operate),
+        (Some(Role::Admin), admin),
+        (None, bootstrap),
+    ]
+}
 
 /// Make router type for composing handler and Pullpiri service
 ///
 /// ### Parametets
 /// None
+/// ### Description
+/// Each group of [`routes`] is behind the check of its role.
 pub fn router() -> Router {
-    Router::new()
-        .route("/api/notify", get(notify))
-        .route("/api/artifact", post(apply_artifact))
-        .route("/api/artifact", delete(withdraw_artifact))
+    let mut router = Router::new();
+    for (role, routes) in routes() {
+        let mut group = Router::new();
+        for route in routes {
+            let filter = MethodFilter::try_from(route.method).expect("method of a route");
+            group = group.route(route.path, (route.handler)(filter));
+        }
+        if let Some(role) = role {
+            group = group.route_layer(from_fn_with_state(role, require_role));
+        }
+        router = router.merge(group);
+    }
+    router
 }
 
 /// Notify of new artifact release in the cloud
@@ -36,12 +188,69 @@ async fn notify(artifact_name: String) -> Response {
 ///
 /// ### Parameters
 /// * `body: String` - the string in yaml format
-async fn apply_artifact(body: String) -> Response {
-    let result = crate::manager::apply_artifact(&body).await;
+/// * `values: HashMap<String, String>` - values of the template parameters,
+///   given as query parameters, e.g. `?image_tag=1.2&node=HPC`
+async fn apply_artifact(Query(values): Query<HashMap<String, String>>, body: String) -> Response {
+    let result = crate::manager::apply_artifact(&body, &values).await;
 
     super::status(result)
 }
 
+/// Apply the artifacts in a single transaction
+///
+/// ### Parameters
+/// * `body: String` - the string in yaml format
+/// * `values: HashMap<String, String>` - values of the template parameters,
+///   given as query parameters
+/// ### Description
+/// Returns the result of each document as json. If a document is invalid,
+/// nothing is written and the status is 422. If the write itself fails,
+/// nothing is written either and the status is 500.
+async fn apply_artifact_transaction(
+    Query(values): Query<HashMap<String, String>>,
+    body: String,
+) -> Response {
+    use crate::artifact::transaction::DocumentStatus;
+
+    let report = crate::manager::apply_artifact_transaction(&body, &values).await;
+    let code = if report.committed {
+        StatusCode::OK
+    } else if report.errors.is_empty()
+        && report
+            .documents
+            .iter()
+            .all(|d| d.status != DocumentStatus::Invalid)
+    {
+        StatusCode::INTERNAL_SERVER_ERROR
+    } else {
+        StatusCode::UNPROCESSABLE_ENTITY
+    };
+
+    (code, Json(report)).into_response()
+}
+
+/// Validate the artifacts without applying them
+///
+/// ### Parameters
+/// * `body: String` - the string in yaml format
+/// * `values: HashMap<String, String>` - values of the template parameters,
+///   given as query parameters
+/// ### Description
+/// Returns the validation report as json, with 422 status if it has errors
+async fn validate_artifact(
+    Query(values): Query<HashMap<String, String>>,
+    body: String,
+) -> Response {
+    let report = crate::manager::validate_artifact(&body, &values).await;
+    let code = if report.valid {
+        StatusCode::OK
+    } else {
+        StatusCode::UNPROCESSABLE_ENTITY
+    };
+
+    (code, Json(report)).into_response()
+}
+
 /// Withdraw the applied scenario
 ///
 /// ### Parameters
@@ -52,6 +261,868 @@ async fn withdraw_artifact(body: String) -> Response {
     super::status(result)
 }
 
+/// Namespace of the artifacts a request refers to
+///
+/// Given as `namespace` query parameter, the default namespace if missing.
+#[derive(Debug, Default, serde::Deserialize)]
+struct NamespaceQuery {
+    namespace: Option<String>,
+}
+
+impl NamespaceQuery {
+    fn namespace(&self) -> &str {
+        self.namespace
+            .as_deref()
+            .unwrap_or(namespace::DEFAULT_NAMESPACE)
+    }
+
+    /// Name of an artifact qualified by the requested namespace
+    fn qualify(&self, name: &str) -> String {
+        namespace::qualified_name(self.namespace(), name)
+    }
+}
+
+/// Fields of `/api/artifact/:kind` that can be sorted by
+const ARTIFACT_SORT_FIELDS: &[&str] = &["name"];
+/// Fields of `/api/clusters` that can be sorted by
+const CLUSTER_SORT_FIELDS: &[&str] = &["id", "name", "nodes"];
+
+/// Page of stored artifacts
+#[derive(Debug, serde::Serialize)]
+struct ArtifactList {
+    artifacts: Vec<crate::artifact::data::ArtifactSummary>,
+    #[serde(flatten)]
+    meta: ListMeta,
+}
+
+/// Page of clusters
+#[derive(Debug, serde::Serialize)]
+struct ClusterList {
+    clusters: Vec<ClusterTopology>,
+    #[serde(flatten)]
+    meta: ListMeta,
+}
+
+/// List the namespaces that hold artifacts, starting with the default one
+async fn list_namespaces() -> Response {
+    json_status(crate::artifact::data::read_namespaces().await)
+}
+
+/// List the stored artifacts of a kind
+///
+/// ### Parameters
+/// * `kind: String` - kind of the artifacts, e.g. `Scenario`
+/// * `list: ListQuery` - `limit`, `offset`, `label`, `sort` (`name`) and
+///   `order` as query parameters
+/// * `ns: NamespaceQuery` - namespace of the artifacts, given as query parameter
+async fn list_artifacts(
+    Path(kind): Path<String>,
+    Query(list): Query<ListQuery>,
+    Query(ns): Query<NamespaceQuery>,
+) -> Response {
+    let result = match crate::artifact::list(&kind, ns.namespace()).await {
+        Ok(artifacts) => select_artifacts(artifacts, &list).map_err(|e| e.into()),
+        Err(e) => Err(e),
+    };
+    json_status(result)
+}
+
+/// Get the yaml of a stored artifact
+///
+/// ### Parameters
+/// * `kind: String, name: String` - kind and name of the artifact
+/// * `ns: NamespaceQuery` - namespace of the artifact, given as query parameter
+async fn get_artifact(
+    Path((kind, name)): Path<(String, String)>,
+    Query(ns): Query<NamespaceQuery>,
+) -> Response {
+    let name = ns.qualify(&name);
+    match crate::artifact::get(&kind, &name).await {
+        Ok(yaml) => (
+            StatusCode::OK,
+            [(axum::http::header::CONTENT_TYPE, "application/yaml")],
+            yaml,
+        )
+            .into_response(),
+        Err(e) if e.downcast_ref::<StoreError>() == Some(&StoreError::NotFound) => {
+            ApiError::not_found(format!("{} '{}' not found", kind, name)).into_response()
+        }
+        Err(e) => ApiError::from_error(e.as_ref()).into_response(),
+    }
+}
+
+/// Filter, sort and paginate stored artifacts
+fn select_artifacts(
+    artifacts: Vec<crate::artifact::data::ArtifactSummary>,
+    list: &ListQuery,
+) -> Result<ArtifactList, String> {
+    let selector = list.label_selector()?;
+    list.sort_field(ARTIFACT_SORT_FIELDS, "name")?;
+
+    let mut artifacts: Vec<_> = artifacts
+        .into_iter()
+        .filter(|a| listing::matches_labels(&selector, &a.labels))
+        .collect();
+    artifacts.sort_by(|a, b| list.order.apply(a.name.cmp(&b.name)));
+
+    let (artifacts, meta) = list.paginate(artifacts);
+    Ok(ArtifactList { artifacts, meta })
+}
+
+/// List the stored versions of an artifact
+///
+/// ### Parameters
+/// * `kind: String, name: String` - kind and name of the artifact
+/// * `ns: NamespaceQuery` - namespace of the artifact, given as query parameter
+async fn list_versions(
+    Path((kind, name)): Path<(String, String)>,
+    Query(ns): Query<NamespaceQuery>,
+) -> Response {
+    json_status(crate::artifact::history::versions(&kind, &ns.qualify(&name)).await)
+}
+
+/// Versions compared by `diff_versions`
+#[derive(serde::Deserialize)]
+struct DiffQuery {
+    from: u64,
+    to: u64,
+}
+
+/// Compare two stored versions of an artifact
+///
+/// ### Parameters
+/// * `kind: String, name: String` - kind and name of the artifact
+/// * `from: u64, to: u64` - versions to compare, given as query parameters
+/// * `ns: NamespaceQuery` - namespace of the artifact, given as query parameter
+async fn diff_versions(
+    Path((kind, name)): Path<(String, String)>,
+    Query(query): Query<DiffQuery>,
+    Query(ns): Query<NamespaceQuery>,
+) -> Response {
+    let name = ns.qualify(&name);
+    json_status(crate::artifact::history::diff(&kind, &name, query.from, query.to).await)
+}
+
+/// Output requested from `export_artifact`
+#[derive(serde::Deserialize)]
+struct ExportQuery {
+    format: String,
+    #[serde(default)]
+    workload: crate::artifact::export::Workload,
+}
+
+/// Export a stored package as manifests of another orchestrator
+///
+/// ### Parameters
+/// * `kind: String, name: String` - kind and name of the artifact, only
+///   packages can be exported
+/// * `format: String` - output format (`k8s`), given as query parameter
+/// * `workload: Workload` - `deployment` (default) or `pod`, given as query
+///   parameter
+/// * `ns: NamespaceQuery` - namespace of the package, given as query parameter
+async fn export_artifact(
+    Path((kind, name)): Path<(String, String)>,
+    Query(query): Query<ExportQuery>,
+    Query(ns): Query<NamespaceQuery>,
+) -> Response {
+    if kind != "Package" {
+        let msg = format!("Only packages can be exported, not '{}'", kind);
+        return ApiError::validation(msg).into_response();
+    }
+    let name = ns.qualify(&name);
+    match crate::artifact::export::export_package(&name, &query.format, query.workload).await {
+        Ok(yaml) => (
+            StatusCode::OK,
+            [(axum::http::header::CONTENT_TYPE, "application/yaml")],
+            yaml,
+        )
+            .into_response(),
+        Err(e) => ApiError::from_error(e.as_ref()).into_response(),
+    }
+}
+
+/// Local file of an offline bundle
+#[derive(serde::Deserialize)]
+struct BundleRequest {
+    path: String,
+}
+
+/// Write all stored artifacts and their images into a signed bundle
+///
+/// ### Parameters
+/// * `request: BundleRequest` - json body with the `path` to write
+async fn export_bundle(Json(request): Json<BundleRequest>) -> Response {
+    match crate::artifact::bundle::export(&request.path).await {
+        Ok(summary) => (StatusCode::OK, Json(summary)).into_response(),
+        Err(e) => ApiError::internal(e.to_string()).into_response(),
+    }
+}
+
+/// Verify an offline bundle, load its images and apply its artifacts
+///
+/// ### Parameters
+/// * `request: BundleRequest` - json body with the `path` of the bundle
+/// ### Description
+/// Returns 400 for bundles that cannot be verified, otherwise the report of
+/// the transactional apply.
+async fn import_bundle(Json(request): Json<BundleRequest>) -> Response {
+    match crate::manager::import_bundle(&request.path).await {
+        Ok(report) => {
+            let code = if report.committed {
+                StatusCode::OK
+            } else {
+                StatusCode::UNPROCESSABLE_ENTITY
+            };
+            (code, Json(report)).into_response()
+        }
+        Err(e) => ApiError::validation(e.to_string()).into_response(),
+    }
+}
+
+/// Re-activate a stored version of an artifact and re-deploy it
+///
+/// ### Parameters
+/// * `kind: String, name: String` - kind and name of the artifact
+/// * `version: u64` - version to roll back to
+/// * `ns: NamespaceQuery` - namespace of the artifact, given as query parameter
+async fn rollback_artifact(
+    Path((kind, name, version)): Path<(String, String, u64)>,
+    Query(ns): Query<NamespaceQuery>,
+) -> Response {
+    let result = crate::manager::rollback_artifact(&kind, &ns.qualify(&name), version).await;
+
+    super::status(result)
+}
+
+/// List the default cluster and all named clusters
+///
+/// ### Parameters
+/// * `list: ListQuery` - `limit`, `offset`, `sort` (`id`, `name` or
+///   `nodes`) and `order` as query parameters. Without `sort` the default
+///   cluster comes first.
+async fn list_clusters(Query(list): Query<ListQuery>) -> Response {
+    let result = match NodeRegistry.list_clusters().await {
+        Ok(clusters) => select_clusters(clusters, &list).map_err(|e| e.into()),
+        Err(e) => Err(e),
+    };
+    json_status(result)
+}
+
+/// Sort and paginate clusters
+fn select_clusters(
+    clusters: Vec<ClusterTopology>,
+    list: &ListQuery,
+) -> Result<ClusterList, String> {
+    if list.label.is_some() {
+        return Err("Clusters have no labels to select by".to_string());
+    }
+    let mut clusters = clusters;
+    if list.sort.is_some() {
+        let node_count = |c: &ClusterTopology| c.master_nodes.len() + c.sub_nodes.len();
+        let sort = list.sort_field(CLUSTER_SORT_FIELDS, "id")?;
+        clusters.sort_by(|a, b| {
+            let ordering = match sort {
+                "name" => a.cluster_name.cmp(&b.cluster_name),
+                "nodes" => node_count(a).cmp(&node_count(b)),
+                _ => std::cmp::Ordering::Equal,
+            };
+            list.order
+                .apply(ordering.then_with(|| a.cluster_id.cmp(&b.cluster_id)))
+        });
+    }
+
+    let (clusters, meta) = list.paginate(clusters);
+    Ok(ClusterList { clusters, meta })
+}
+
+/// Create a named cluster
+///
+/// ### Parameters
+/// * `topology: ClusterTopology` - the new cluster in json format
+async fn create_cluster(Json(topology): Json<ClusterTopology>) -> Response {
+    json_status(NodeRegistry.create_cluster(topology).await)
+}
+
+/// Get the topology of a cluster
+///
+/// ### Parameters
+/// * `id: String` - cluster id
+async fn get_cluster(Path(id): Path<String>) -> Response {
+    match NodeRegistry.get_cluster(&id).await {
+        Ok(Some(topology)) => (StatusCode::OK, Json(topology)).into_response(),
+        Ok(None) => ApiError::not_found(format!("cluster '{}' does not exist", id)).into_response(),
+        Err(e) => json_status::<(), _>(Err(e)),
+    }
+}
+
+/// Delete a named cluster, its nodes return to the default cluster
+///
+/// ### Parameters
+/// * `id: String` - cluster id
+async fn delete_cluster(Path(id): Path<String>) -> Response {
+    json_status(NodeRegistry.delete_cluster(&id).await)
+}
+
+/// Move a node into a cluster
+///
+/// ### Parameters
+/// * `id: String` - cluster id
+/// * `node: String` - node id or hostname
+async fn assign_node(Path((id, node)): Path<(String, String)>) -> Response {
+    json_status(NodeRegistry.assign_node(&id, &node).await)
+}
+
+/// Get the health summary of a cluster
+///
+/// ### Parameters
+/// * `id: String` - cluster id
+async fn get_cluster_health(Path(id): Path<String>) -> Response {
+    json_status(
+        NodeRegistry
+            .get_cluster_health(&id, crate::node::heartbeat::liveness_window())
+            .await,
+    )
+}
+
+/// Put a node in maintenance and move its models to other nodes
+///
+/// ### Parameters
+/// * `id: String` - id or hostname of the node
+/// ### Description
+/// Returns the outcome of every model of the node as json. Models that
+/// cannot run elsewhere are stopped. The node takes no models until it is
+/// uncordoned.
+async fn drain_node(Path(id): Path<String>) -> Response {
+    match crate::node::maintenance::drain(&id).await {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+/// Take a node out of maintenance, so it takes new models again
+///
+/// ### Parameters
+/// * `id: String` - id or hostname of the node
+async fn uncordon_node(Path(id): Path<String>) -> Response {
+    match crate::node::maintenance::uncordon(&id).await {
+        Ok(()) => super::status(Ok(())),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+/// Move every model of a failed node to another node
+///
+/// ### Parameters
+/// * `id: String` - id or hostname of the failed node
+/// * `target` - id or hostname of the node the models move to, given as a
+///   query parameter, else a node is chosen for each model
+/// ### Description
+/// Returns the outcome of every model of the node as json. The node stays
+/// in maintenance.
+async fn migrate_node(
+    Path(id): Path<String>,
+    Query(values): Query<HashMap<String, String>>,
+) -> Response {
+    let target = values.get("target").map(String::as_str);
+    match crate::node::maintenance::migrate(&id, target).await {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+/// Issue a one-time token a guest node joins the master with
+///
+/// ### Parameters
+/// * `request: TokenRequest` - optional `nodeName`, `labels` and `ttlSecs`
+/// ### Description
+/// Returns the token, its expiry and the bootstrap bundle as json. The
+/// token is not stored and cannot be read again.
+async fn issue_bootstrap_token(
+    Json(request): Json<crate::node::bootstrap::TokenRequest>,
+) -> Response {
+    match crate::node::bootstrap::issue(request).await {
+        Ok(issued) => (StatusCode::OK, Json(issued)).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+/// Exchange a bootstrap token for the bootstrap bundle of its node
+///
+/// ### Parameters
+/// * `request: RedeemRequest` - the token
+/// ### Description
+/// Called by NodeAgent started with `--bootstrap`. The token is used up.
+async fn redeem_bootstrap_token(Json(request): Json<common::bootstrap::RedeemRequest>) -> Response {
+    match crate::node::bootstrap::redeem(&request.token).await {
+        Ok(bundle) => (StatusCode::OK, Json(bundle)).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+/// Get the allocatable and allocated CPU and memory of every node
+///
+/// ### Description
+/// Lists the models holding resources on each node, as recorded by
+/// ActionController when it starts them.
+async fn get_node_allocation() -> Response {
+    json_status(common::allocation::node_allocations().await)
+}
+
+/// List the resource quotas of packages, sorted by name
+async fn list_quotas() -> Response {
+    json_status(common::quota::load().await)
+}
+
+/// Get a resource quota
+///
+/// ### Parameters
+/// * `name: String` - quota name
+async fn get_quota(Path(name): Path<String>) -> Response {
+    match common::quota::get(&name).await {
+        Ok(Some(quota)) => (StatusCode::OK, Json(quota)).into_response(),
+        Ok(None) => ApiError::not_found(format!("quota '{}' does not exist", name)).into_response(),
+        Err(e) => json_status::<(), _>(Err(e)),
+    }
+}
+
+/// Create or replace a resource quota
+///
+/// ### Parameters
+/// * `name: String` - quota name, replacing the name in the body
+/// * `quota: ResourceQuota` - limits and the node or node labels they apply to
+async fn put_quota(
+    Path(name): Path<String>,
+    Json(mut quota): Json<common::quota::ResourceQuota>,
+) -> Response {
+    quota.name = name;
+    if let Err(e) = quota.validate() {
+        return ApiError::validation(e).into_response();
+    }
+    json_status(common::quota::save(&quota).await.map(|()| quota))
+}
+
+/// Delete a resource quota
+///
+/// ### Parameters
+/// * `name: String` - quota name
+async fn delete_quota(Path(name): Path<String>) -> Response {
+    match common::quota::get(&name).await {
+        Ok(Some(_)) => json_status(common::quota::delete(&name).await),
+        Ok(None) => ApiError::not_found(format!("quota '{}' does not exist", name)).into_response(),
+        Err(e) => json_status::<(), _>(Err(e)),
+    }
+}
+
+/// Time range and size of a state history, all optional
+#[derive(serde::Deserialize)]
+struct HistoryQuery {
+    limit: Option<i32>,
+    since: Option<i64>,
+    until: Option<i64>,
+}
+
+/// Get the last state transitions of a scenario, package or model
+///
+/// ### Parameters
+/// * `kind: String, name: String` - kind (`scenario`, `package` or `model`)
+///   and name of the resource
+/// * `limit, since, until` - newest transitions to return and time range in
+///   Unix nanoseconds, given as query parameters
+async fn get_state_history(
+    Path((kind, name)): Path<(String, String)>,
+    Query(query): Query<HistoryQuery>,
+) -> Response {
+    use common::statemanager::{ResourceStateHistoryRequest, ResourceType};
+
+    let resource_type = match kind.to_ascii_lowercase().as_str() {
+        "scenario" => ResourceType::Scenario,
+        "package" => ResourceType::Package,
+        "model" => ResourceType::Model,
+        _ => {
+            let msg = format!("No state history for kind '{}'", kind);
+            return ApiError::validation(msg).into_response();
+        }
+    };
+    let request = ResourceStateHistoryRequest {
+        resource_type: resource_type as i32,
+        resource_name: name,
+        start_time_ns: query.since.unwrap_or_default(),
+        end_time_ns: query.until.unwrap_or_default(),
+        limit: query.limit.unwrap_or_default(),
+    };
+
+    let mut sender = crate::grpc::sender::statemanager::StateManagerSender::new();
+    match sender.get_resource_state_history(request).await {
+        Ok(response) => (StatusCode::OK, Json(response.into_inner().history)).into_response(),
+        Err(e) => ApiError::from_status(&e).into_response(),
+    }
+}
+
+/// Get the state summary of the cluster and its scenarios for dashboards
+///
+/// ### Description
+/// Sources that cannot be read are listed in `unavailable` of the summary,
+/// see [`crate::summary`].
+async fn get_summary() -> Response {
+    (StatusCode::OK, Json(crate::summary::summary().await)).into_response()
+}
+
+/// Get the orphaned artifacts found by the last garbage collection scan
+///
+/// ### Description
+/// The first request before any scan scans right away, see [`crate::gc`].
+async fn get_orphans() -> Response {
+    let report = crate::gc::report();
+    if report.scanned_at != 0 {
+        return (StatusCode::OK, Json(report)).into_response();
+    }
+    match crate::gc::scan().await {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => ApiError::from_error(e.as_ref()).into_response(),
+    }
+}
+
+/// Query parameters of `trigger_scenario`
+#[derive(serde::Deserialize)]
+struct TriggerQuery {
+    #[serde(default)]
+    dry_run: bool,
+    #[serde(default)]
+    wait: bool,
+    /// Seconds to wait, capped at `trigger::MAX_TIMEOUT`
+    timeout: Option<u64>,
+}
+
+/// Run the action of a scenario now, without waiting for its condition
+///
+/// ### Parameters
+/// * `name: String` - name of the scenario
+/// * `dry_run: bool` - return the execution plan instead of running the
+///   action, given as query parameter
+/// * `wait: bool` - block until the models reached a terminal state and
+///   return the outcome of each, given as query parameter
+/// * `timeout: u64` - seconds to wait, 60 if omitted, given as query
+///   parameter
+/// * `ns: NamespaceQuery` - namespace of the scenario, given as query parameter
+/// ### Description
+/// Returns the transition id with 202 without `wait`, 200 with a terminal
+/// result and 504 if the wait timed out.
+async fn trigger_scenario(
+    Path(name): Path<String>,
+    Query(query): Query<TriggerQuery>,
+    Query(ns): Query<NamespaceQuery>,
+) -> Response {
+    use crate::trigger::{TriggerStatus, DEFAULT_TIMEOUT, MAX_TIMEOUT};
+
+    let name = ns.qualify(&name);
+    if query.dry_run {
+        return match crate::trigger::plan(&name).await {
+            Ok(plan) => (StatusCode::OK, Json(plan)).into_response(),
+            Err(e) => e.into_response(),
+        };
+    }
+    let wait = query.wait.then(|| {
+        query
+            .timeout
+            .map(std::time::Duration::from_secs)
+            .unwrap_or(DEFAULT_TIMEOUT)
+            .min(MAX_TIMEOUT)
+    });
+    match crate::trigger::trigger(&name, wait).await {
+        Ok(result) => {
+            let status = match result.status {
+                TriggerStatus::Accepted => StatusCode::ACCEPTED,
+                TriggerStatus::TimedOut => StatusCode::GATEWAY_TIMEOUT,
+                _ => StatusCode::OK,
+            };
+            (status, Json(result)).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Query parameters of `reset_scenario`
+#[derive(serde::Deserialize)]
+struct ResetQuery {
+    #[serde(default)]
+    reason: String,
+}
+
+/// Result of `reset_scenario`
+#[derive(serde::Serialize)]
+struct ResetResult {
+    scenario: String,
+    /// State of the scenario after the reset
+    state: String,
+    message: String,
+}
+
+/// Release a scenario the StateManager quarantined for flapping
+///
+/// ### Parameters
+/// * `name: String` - name of the scenario
+/// * `reason: String` - why it is released, given as query parameter
+/// ### Description
+/// Returns the state of the scenario afterwards, or 409 if it is not
+/// quarantined.
+async fn reset_scenario(
+    Path(name): Path<String>,
+    Query(query): Query<ResetQuery>,
+    Query(ns): Query<NamespaceQuery>,
+) -> Response {
+    let name = ns.qualify(&name);
+    let request = common::statemanager::ResetQuarantineRequest {
+        scenario_name: name.clone(),
+        reason: query.reason,
+    };
+    let response = match crate::grpc::sender::statemanager::StateManagerSender::new()
+        .reset_quarantine(request)
+        .await
+    {
+        Ok(response) => response.into_inner(),
+        Err(e) => return ApiError::from_status(&e).into_response(),
+    };
+    if !response.success {
+        return ApiError::conflict(response.message).into_response();
+    }
+    Json(ResetResult {
+        scenario: name,
+        state: response.state,
+        message: response.message,
+    })
+    .into_response()
+}
+
+/// Read the logs of a container from the NodeAgent of its node
+///
+/// ### Parameters
+/// * `node: &str, id: String` - hostname of the node and id or name of the
+///   container
+/// * `tail: u32` - number of last lines, all lines if 0
+async fn logs_from_node(node: &str, id: String, tail: u32) -> Response {
+    use common::nodeagent::fromapiserver::ContainerLogsRequest;
+
+    let Some(node_info) = crate::node::node_lookup::find_node_by_hostname(node).await else {
+        return ApiError::not_found(format!("Node '{}' not found", node)).into_response();
+    };
+    let request = ContainerLogsRequest {
+        container_id: id,
+        tail,
+    };
+
+    match crate::grpc::sender::nodeagent::get_container_logs(request, node_info.ip_address).await {
+        Ok(response) => (
+            StatusCode::OK,
+            [(axum::http::header::CONTENT_TYPE, "text/plain")],
+            response.into_inner().logs,
+        )
+            .into_response(),
+        Err(e) => ApiError::from_status(&e).into_response(),
+    }
+}
+
+/// Query parameters of `get_collected_logs`
+#[derive(serde::Deserialize)]
+struct CollectedLogsQuery {
+    #[serde(default)]
+    node: String,
+    #[serde(default)]
+    tail: u32,
+}
+
+/// Get the logs of a container collected by MonitoringServer
+///
+/// ### Parameters
+/// * `id: String` - id, id prefix or name of the container
+/// * `node: String` - hostname of the node, needed if the container is
+///   found on several nodes, given as query parameter
+/// * `tail: u32` - number of last lines, all kept lines if omitted, given
+///   as query parameter
+/// ### Description
+/// MonitoringServer keeps the last lines streamed by the NodeAgents. When it
+/// has no lines of the container or is unreachable and `node` is given, the
+/// logs are read from the NodeAgent of the node instead.
+async fn get_collected_logs(
+    Path(id): Path<String>,
+    Query(query): Query<CollectedLogsQuery>,
+) -> Response {
+    use common::monitoringserver::GetContainerLogsRequest;
+
+    let request = GetContainerLogsRequest {
+        node_name: query.node.clone(),
+        container_id: id.clone(),
+        tail: query.tail,
+    };
+    match crate::grpc::sender::monitoringserver::get_container_logs(request).await {
+        Ok(response) => {
+            let response = response.into_inner();
+            let mut logs = String::new();
+            for line in response.lines {
+                logs.push_str(&line.line);
+                logs.push('\n');
+            }
+            (
+                StatusCode::OK,
+                [
+                    (axum::http::header::CONTENT_TYPE, "text/plain".to_string()),
+                    (
+                        axum::http::header::HeaderName::from_static("x-pullpiri-node"),
+                        response.node_name,
+                    ),
+                ],
+                logs,
+            )
+                .into_response()
+        }
+        Err(e)
+            if !query.node.is_empty()
+                && matches!(e.code(), tonic::Code::NotFound | tonic::Code::Unavailable) =>
+        {
+            common::logd!(
+                2,
+                "No collected logs of container '{}' ({}), reading them from node '{}'",
+                id,
+                e.message(),
+                query.node
+            );
+            logs_from_node(&query.node, id, query.tail).await
+        }
+        Err(e) => ApiError::from_status(&e).into_response(),
+    }
+}
+
+/// Reliability of the data reader of a DDS topic
+#[derive(Debug, Default, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TopicReliability {
+    #[default]
+    BestEffort,
+    Reliable,
+}
+
+/// A DDS topic FilterGateway listens to
+#[derive(Debug, serde::Serialize)]
+struct Topic {
+    topic: String,
+    type_name: String,
+    domain_id: i32,
+    reliability: TopicReliability,
+    history_depth: u32,
+    paused: bool,
+    origin: String,
+}
+
+impl From<TopicSubscription> for Topic {
+    fn from(topic: TopicSubscription) -> Self {
+        let reliability = match topic.reliability() {
+            Reliability::BestEffort => TopicReliability::BestEffort,
+            Reliability::Reliable => TopicReliability::Reliable,
+        };
+        Self {
+            topic: topic.topic,
+            type_name: topic.type_name,
+            domain_id: topic.domain_id,
+            reliability,
+            history_depth: topic.history_depth,
+            paused: topic.paused,
+            origin: topic.origin,
+        }
+    }
+}
+
+/// Body of `subscribe_topic`
+#[derive(serde::Deserialize)]
+struct SubscribeTopicBody {
+    topic: String,
+    type_name: String,
+    /// Domain of FilterGateway if omitted
+    domain_id: Option<i32>,
+    #[serde(default)]
+    reliability: TopicReliability,
+    /// Samples kept by the reader, 1 if omitted
+    #[serde(default)]
+    history_depth: u32,
+}
+
+/// List the DDS topics FilterGateway listens to
+///
+/// ### Description
+/// Topics of scenario and policy conditions have the origin `scenario`,
+/// topics subscribed over this API the origin `api`.
+async fn list_topics() -> Response {
+    match crate::grpc::sender::filtergateway::list_topics().await {
+        Ok(response) => {
+            let topics: Vec<Topic> = response
+                .into_inner()
+                .topics
+                .into_iter()
+                .map(Topic::from)
+                .collect();
+            (StatusCode::OK, Json(topics)).into_response()
+        }
+        Err(e) => ApiError::from_status(&e).into_response(),
+    }
+}
+
+/// Subscribe FilterGateway to a DDS topic
+///
+/// ### Description
+/// The type must be built into FilterGateway or registered with its IDL.
+/// The subscription is kept until it is unsubscribed, also when
+/// FilterGateway restarts.
+async fn subscribe_topic(Json(body): Json<SubscribeTopicBody>) -> Response {
+    let reliability = match body.reliability {
+        TopicReliability::BestEffort => Reliability::BestEffort,
+        TopicReliability::Reliable => Reliability::Reliable,
+    };
+    let request = SubscribeTopicRequest {
+        topic: body.topic,
+        type_name: body.type_name,
+        domain_id: body.domain_id,
+        reliability: reliability.into(),
+        history_depth: body.history_depth,
+    };
+    topic_status(crate::grpc::sender::filtergateway::subscribe_topic(request).await)
+}
+
+/// Stop listening to a subscribed topic until it is resumed
+async fn pause_topic(Path(topic): Path<String>) -> Response {
+    topic_status(change_topic(&topic, TopicChange::Pause).await)
+}
+
+/// Listen again to a paused topic
+async fn resume_topic(Path(topic): Path<String>) -> Response {
+    topic_status(change_topic(&topic, TopicChange::Resume).await)
+}
+
+/// Unsubscribe from a topic subscribed with `subscribe_topic`
+async fn unsubscribe_topic(Path(topic): Path<String>) -> Response {
+    topic_status(change_topic(&topic, TopicChange::Unsubscribe).await)
+}
+
+/// Response of a topic change, the topic as json on success
+fn topic_status(
+    result: Result<tonic::Response<common::filtergateway::TopicResponse>, tonic::Status>,
+) -> Response {
+    match result {
+        Ok(response) => match response.into_inner().topic {
+            Some(topic) => (StatusCode::OK, Json(Topic::from(topic))).into_response(),
+            None => ApiError::internal("FilterGateway returned no topic").into_response(),
+        },
+        Err(e) => ApiError::from_status(&e).into_response(),
+    }
+}
+
+/// Generate the response of a request returning data, the value as json on success
+///
+/// Errors are mapped by their type, see [`ApiError::from_error`].
+fn json_status<T: serde::Serialize, E: Into<ApiError>>(result: Result<T, E>) -> Response {
+    match result {
+        Ok(value) => (StatusCode::OK, Json(value)).into_response(),
+        Err(e) => e.into().into_response(),
+    }
+}
+
 //UNIT TEST CASES
 #[cfg(test)]
 mod tests {
@@ -283,4 +1354,286 @@ spec:
         let response = app.oneshot(req).await.unwrap();
         assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
     }
+
+    // -------------------
+    // Validate Artifact Tests (POST)
+    // -------------------
+
+    /// Negative test: POST /api/artifact/validate with invalid YAML returns 422 and a report
+    #[tokio::test]
+    async fn test_validate_artifact_invalid_returns_report() {
+        let app = super::router();
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/artifact/validate")
+            .header("Content-Type", "text/plain")
+            .body(Body::from("kind: Unknown\nmetadata:\n  name: x\n"))
+            .unwrap();
+
+        let response = app.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(report["valid"], false);
+        assert!(!report["errors"].as_array().unwrap().is_empty());
+    }
+
+    /// Negative test: POST /api/artifact/validate with a value for an undeclared parameter returns 422
+    #[tokio::test]
+    async fn test_validate_artifact_unused_template_value() {
+        let app = super::router();
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/artifact/validate?node=HPC")
+            .header("Content-Type", "text/plain")
+            .body(Body::from("kind: Model\nmetadata:\n  name: x\n"))
+            .unwrap();
+
+        let response = app.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(report["errors"][0]["message"]
+            .as_str()
+            .unwrap()
+            .contains("unused values: node"));
+    }
+
+    /// Negative test: POST /api/artifact/transaction with an invalid document writes nothing
+    #[tokio::test]
+    async fn test_apply_artifact_transaction_invalid_returns_report() {
+        let app = super::router();
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/artifact/transaction")
+            .header("Content-Type", "text/plain")
+            .body(Body::from("kind: Unknown\nmetadata:\n  name: x\n"))
+            .unwrap();
+
+        let response = app.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(report["committed"], false);
+        assert_eq!(report["documents"][0]["status"], "invalid");
+    }
+
+    /// Negative test: POST /api/clusters with an invalid cluster id returns 400
+    #[tokio::test]
+    async fn test_create_cluster_invalid_id() {
+        let app = super::router();
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/clusters")
+            .header("Content-Type", "application/json")
+            .body(Body::from(
+                r#"{"cluster_id":"bad/id","cluster_name":"bad"}"#,
+            ))
+            .unwrap();
+
+        let response = app.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    /// Negative test: GET /api/artifact/<kind>/<name>/diff without versions returns 400
+    #[tokio::test]
+    async fn test_diff_versions_missing_query() {
+        let app = super::router();
+
+        let req = Request::builder()
+            .method("GET")
+            .uri("/api/artifact/Scenario/helloworld/diff")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    /// Negative test: only packages can be exported
+    #[tokio::test]
+    async fn test_export_artifact_rejects_other_kinds() {
+        let app = super::router();
+
+        let req = Request::builder()
+            .method("GET")
+            .uri("/api/artifact/Scenario/helloworld/export?format=k8s")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    /// Negative test: state history exists only for scenarios, packages and models
+    #[tokio::test]
+    async fn test_state_history_rejects_other_kinds() {
+        let app = super::router();
+
+        let req = Request::builder()
+            .method("GET")
+            .uri("/api/state/volume/helloworld/history?limit=10")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    /// Negative test: a path served by routers of different roles still rejects other methods
+    #[tokio::test]
+    async fn test_router_merges_methods_of_a_path() {
+        let req = Request::builder()
+            .method("PUT")
+            .uri("/api/clusters/edge")
+            .body(Body::empty())
+            .unwrap();
+        let response = super::router().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        // POST of the admin router and GET of the viewer router share the path
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/clusters")
+            .header("Content-Type", "application/json")
+            .body(Body::from(r#"{"cluster_id": ""}"#))
+            .unwrap();
+        let response = super::router().oneshot(req).await.unwrap();
+        assert_ne!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    /// Negative test: an empty bootstrap token is refused without a role
+    #[tokio::test]
+    async fn test_redeem_bootstrap_token_rejects_empty_token() {
+        let req = Request::builder()
+            .method("POST")
+            .uri(common::bootstrap::REDEEM_PATH)
+            .header("Content-Type", "application/json")
+            .body(Body::from(r#"{"token": ""}"#))
+            .unwrap();
+        let response = super::router().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    /// Negative test: unknown kinds cannot be read
+    #[tokio::test]
+    async fn test_get_artifact_rejects_unknown_kind() {
+        let app = super::router();
+
+        let req = Request::builder()
+            .method("GET")
+            .uri("/api/artifact/Unknown/helloworld")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    /// Negative test: artifacts cannot be listed in an invalid namespace
+    #[tokio::test]
+    async fn test_list_artifacts_rejects_invalid_namespace() {
+        let app = super::router();
+
+        let req = Request::builder()
+            .method("GET")
+            .uri("/api/artifact/Scenario?namespace=Team_A")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    /// Names are qualified by the namespace query parameter
+    #[test]
+    fn test_namespace_query_qualifies_names() {
+        let ns = super::NamespaceQuery::default();
+        assert_eq!(ns.qualify("helloworld"), "helloworld");
+
+        let ns = super::NamespaceQuery {
+            namespace: Some("team-a".to_string()),
+        };
+        assert_eq!(ns.qualify("helloworld"), "team-a/helloworld");
+    }
+
+    // -------------------
+    // Listing Tests
+    // -------------------
+
+    /// Artifacts are filtered by label, sorted by name and paginated
+    #[test]
+    fn test_select_artifacts_filters_sorts_and_paginates() {
+        use crate::artifact::data::ArtifactSummary;
+        use std::collections::HashMap;
+
+        let artifact = |name: &str, zone: &str| ArtifactSummary {
+            name: name.to_string(),
+            labels: HashMap::from([("zone".to_string(), zone.to_string())]),
+        };
+        let artifacts = vec![
+            artifact("wiper", "front"),
+            artifact("antipinch", "front"),
+            artifact("trunk", "rear"),
+            artifact("bms", "front"),
+        ];
+        let list: common::listing::ListQuery =
+            serde_json::from_str(r#"{"label": "zone=front", "order": "desc", "limit": 2}"#)
+                .unwrap();
+
+        let page = super::select_artifacts(artifacts.clone(), &list).unwrap();
+        let names: Vec<_> = page.artifacts.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["wiper", "bms"]);
+        assert_eq!(page.meta.total, 3);
+        assert_eq!(page.meta.next_offset, Some(2));
+
+        let list: common::listing::ListQuery = serde_json::from_str(r#"{"sort": "zone"}"#).unwrap();
+        assert!(super::select_artifacts(artifacts, &list).is_err());
+    }
+
+    /// Clusters keep the registry order unless a sort field is given
+    #[test]
+    fn test_select_clusters_sorts_and_paginates() {
+        use common::apiserver::ClusterTopology;
+
+        let cluster = |id: &str, name: &str| ClusterTopology {
+            cluster_id: id.to_string(),
+            cluster_name: name.to_string(),
+            ..Default::default()
+        };
+        let clusters = vec![
+            cluster("default", "default"),
+            cluster("zone-b", "body"),
+            cluster("zone-a", "adas"),
+        ];
+
+        let list = common::listing::ListQuery::default();
+        let page = super::select_clusters(clusters.clone(), &list).unwrap();
+        assert_eq!(page.clusters[0].cluster_id, "default");
+        assert_eq!(page.meta.total, 3);
+
+        let list: common::listing::ListQuery =
+            serde_json::from_str(r#"{"sort": "name", "limit": 1, "offset": 1}"#).unwrap();
+        let page = super::select_clusters(clusters.clone(), &list).unwrap();
+        assert_eq!(page.clusters.len(), 1);
+        assert_eq!(page.clusters[0].cluster_name, "body");
+        assert_eq!(page.meta.next_offset, Some(2));
+
+        let list: common::listing::ListQuery =
+            serde_json::from_str(r#"{"label": "zone=front"}"#).unwrap();
+        assert!(super::select_clusters(clusters, &list).is_err());
+    }
 }
@@ -6,13 +6,20 @@
 //! Access point of Pullpiri REST API
 
 pub mod api;
+pub mod openapi;
 
 use axum::{
+    extract::DefaultBodyLimit,
     http::StatusCode,
+    middleware::{from_fn, from_fn_with_state},
     response::{IntoResponse, Response},
     Json, Router,
 };
+use common::error::ApiError;
+use common::limit::{enforce_limits, RateLimiter};
 use common::logd;
+use std::net::SocketAddr;
+use std::sync::Arc;
 use tokio::net::TcpListener;
 use tower_http::cors::{Any, CorsLayer};
 
@@ -22,6 +29,10 @@ use tower_http::cors::{Any, CorsLayer};
 /// None
 /// ### Description
 /// CORS layer needs to be considerd.
+/// Request rate and body size are limited per client address by the
+/// `limits` section of settings.yaml. The `/healthz` and `/readyz` probes
+/// are exempt from the limits and from authentication. API requests
+/// continue the trace of a `traceparent` header or start a new one.
 pub async fn launch_tcp_listener() {
     let addr = common::apiserver::open_rest_server();
     let listener = TcpListener::bind(addr).await.unwrap();
@@ -29,7 +40,16 @@ pub async fn launch_tcp_listener() {
         .allow_origin(Any)
         .allow_methods(Any)
         .allow_headers(Any);
-    let app = Router::new().merge(api::router()).layer(cors);
+    let app = Router::new()
+        .merge(api::router())
+        .layer(from_fn_with_state(
+            Arc::new(RateLimiter::default()),
+            enforce_limits,
+        ))
+        .layer(DefaultBodyLimit::disable())
+        .layer(from_fn(common::trace::trace_requests))
+        .merge(common::health::router())
+        .layer(cors);
 
     logd!(
         2,
@@ -41,7 +61,12 @@ pub async fn launch_tcp_listener() {
         "http api listening on {}",
         listener.local_addr().unwrap()
     );
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
 
 /// Generate appropriate API response based on handler execution result
@@ -49,10 +74,10 @@ pub async fn launch_tcp_listener() {
 /// ### Parametets
 /// * `result: Result<()>` - result of API handler logic
 /// ### Description
-/// Additional StatusCode may be added depending on the error.
+/// Errors are returned as [`ApiError`] body, with the status of its code.
 pub fn status(result: common::Result<()>) -> Response {
-    if let Err(msg) = result {
-        (StatusCode::METHOD_NOT_ALLOWED, Json(msg.to_string())).into_response()
+    if let Err(e) = result {
+        ApiError::from_error(e.as_ref()).into_response()
     } else {
         (StatusCode::OK, Json(String::from("Ok"))).into_response()
     }
@@ -83,7 +108,13 @@ mod tests {
         // Negative case: Error response
         let err = Box::new(std::io::Error::other("test error")) as Box<dyn StdError + Send + Sync>;
         let err_response = status(Err(err));
-        assert_eq!(err_response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(err_response.status(), StatusCode::BAD_REQUEST);
+
+        let err: Box<dyn StdError> = ApiError::not_found("Scenario 'x' not found").into();
+        assert_eq!(status(Err(err)).status(), StatusCode::NOT_FOUND);
+        let err: Box<dyn StdError> =
+            common::etcd::StoreError::Unavailable("transport error".to_string()).into();
+        assert_eq!(status(Err(err)).status(), StatusCode::SERVICE_UNAVAILABLE);
     }
 
     // Test successful TCP listener launch (Positive)
@@ -215,6 +246,57 @@ mod tests {
         );
     }
 
+    // ❌ Negative test: rate and body size limits
+    #[tokio::test]
+    async fn test_limits_reject_large_bodies_and_request_floods() {
+        let limits = common::setting::get_config().limits.clone();
+        let app = Router::new()
+            .route(
+                "/api/artifact",
+                post(|body: String| async move { body.len().to_string() }),
+            )
+            .layer(from_fn_with_state(
+                Arc::new(RateLimiter::default()),
+                enforce_limits,
+            ))
+            .layer(DefaultBodyLimit::disable());
+        let request = |body: Vec<u8>| {
+            Request::builder()
+                .method("POST")
+                .uri("/api/artifact")
+                .header("content-length", body.len())
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let too_large = vec![b'a'; (limits.max_body_kb * 1024 + 1) as usize];
+        let response = app.clone().oneshot(request(too_large)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "PAYLOAD_TOO_LARGE");
+        assert!(body["message"].as_str().unwrap().contains("KiB"));
+
+        // Requests beyond the burst are throttled
+        let mut throttled = None;
+        for _ in 0..limits.burst * 2 {
+            let response = app
+                .clone()
+                .oneshot(request(b"kind: Scenario".to_vec()))
+                .await
+                .unwrap();
+            if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                throttled = Some(response);
+                break;
+            }
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let throttled = throttled.expect("requests beyond the burst must be throttled");
+        assert!(throttled.headers().contains_key("retry-after"));
+    }
+
     // Test CORS headers (Positive)
     #[tokio::test]
     async fn test_cors_headers() {
@@ -0,0 +1,1264 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! OpenAPI 3 document of the Pullpiri REST API
+//!
+//! Covers every route of [`super::api`] and is served at
+//! `GET /api/openapi.json`. The `apiclient` crate of the
+//! workspace keeps a copy of the document as `openapi.json` and is written
+//! against it. The copy is checked by the tests of this module, run them with
+//! `UPDATE_OPENAPI=1` to refresh it after changing the document.
+
+use axum::Json;
+use serde_json::{json, Map, Value};
+
+/// Operation of the document
+struct Operation {
+    method: &'static str,
+    path: &'static str,
+    id: &'static str,
+    tag: &'static str,
+    summary: &'static str,
+    parameters: Vec<Value>,
+    body: Option<Value>,
+    responses: Value,
+}
+
+fn path_param(name: &str, description: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "path",
+        "required": true,
+        "description": description,
+        "schema": { "type": "string" }
+    })
+}
+
+fn query_param(name: &str, schema: Value, description: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "query",
+        "required": false,
+        "description": description,
+        "schema": schema
+    })
+}
+
+fn namespace_param() -> Value {
+    query_param(
+        "namespace",
+        json!({ "type": "string" }),
+        "Namespace of the artifact, the default namespace if omitted",
+    )
+}
+
+/// `limit`, `offset`, `sort` and `order` of listings, with `label` if the
+/// items have labels
+fn list_params(labels: bool) -> Vec<Value> {
+    let mut params = vec![
+        query_param(
+            "limit",
+            json!({ "type": "integer", "minimum": 0 }),
+            "Maximum number of items",
+        ),
+        query_param(
+            "offset",
+            json!({ "type": "integer", "minimum": 0 }),
+            "Number of items to skip",
+        ),
+        query_param(
+            "page",
+            json!({ "type": "integer", "minimum": 1 }),
+            "Page number, ignored if `limit` or `offset` is given",
+        ),
+        query_param(
+            "page_size",
+            json!({ "type": "integer", "minimum": 0 }),
+            "Items per page",
+        ),
+        query_param("sort", json!({ "type": "string" }), "Field to sort by"),
+        query_param(
+            "order",
+            json!({ "type": "string", "enum": ["asc", "desc"] }),
+            "Sort order",
+        ),
+    ];
+    if labels {
+        params.push(query_param(
+            "label",
+            json!({ "type": "string" }),
+            "Label selector, e.g. `app=brake,tier`",
+        ));
+    }
+    params
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn json_content(schema: Value) -> Value {
+    json!({ "application/json": { "schema": schema } })
+}
+
+fn yaml_body(description: &str) -> Option<Value> {
+    Some(json!({
+        "required": true,
+        "description": description,
+        "content": { "application/yaml": { "schema": { "type": "string" } } }
+    }))
+}
+
+fn json_body(schema: Value) -> Option<Value> {
+    Some(json!({ "required": true, "content": json_content(schema) }))
+}
+
+/// Responses of `status` ok plus the error response
+fn ok_responses() -> Value {
+    json!({
+        "200": {
+            "description": "Done",
+            "content": json_content(json!({ "type": "string", "example": "Ok" }))
+        },
+        "default": { "$ref": "#/components/responses/Error" }
+    })
+}
+
+/// `code` response with a json body of `schema`, plus the error response
+fn json_responses(codes: &[(&str, &str)], schema: Value) -> Value {
+    let mut responses = Map::new();
+    for (code, description) in codes {
+        responses.insert(
+            code.to_string(),
+            json!({ "description": description, "content": json_content(schema.clone()) }),
+        );
+    }
+    responses.insert(
+        "default".to_string(),
+        json!({ "$ref": "#/components/responses/Error" }),
+    );
+    Value::Object(responses)
+}
+
+fn yaml_responses(description: &str) -> Value {
+    json!({
+        "200": {
+            "description": description,
+            "content": { "application/yaml": { "schema": { "type": "string" } } }
+        },
+        "default": { "$ref": "#/components/responses/Error" }
+    })
+}
+
+fn text_responses(description: &str) -> Value {
+    json!({
+        "200": {
+            "description": description,
+            "content": { "text/plain": { "schema": { "type": "string" } } }
+        },
+        "default": { "$ref": "#/components/responses/Error" }
+    })
+}
+
+fn template_params() -> Vec<Value> {
+    vec![json!({
+        "name": "values",
+        "in": "query",
+        "required": false,
+        "description": "Values of the template parameters, e.g. `image_tag=1.2`",
+        "style": "form",
+        "explode": true,
+        "schema": { "type": "object", "additionalProperties": { "type": "string" } }
+    })]
+}
+
+fn artifact_params(extra: Vec<Value>) -> Vec<Value> {
+    let mut params = vec![
+        path_param("kind", "Kind of the artifact, e.g. `Scenario`"),
+        path_param("name", "Name of the artifact"),
+    ];
+    params.extend(extra);
+    params.push(namespace_param());
+    params
+}
+
+fn quota_param() -> Vec<Value> {
+    vec![path_param("name", "Name of the quota")]
+}
+
+fn topic_param() -> Vec<Value> {
+    vec![path_param("topic", "Name of the DDS topic")]
+}
+
+fn scenario_params(extra: Vec<Value>) -> Vec<Value> {
+    let mut params = vec![path_param("name", "Name of the scenario")];
+    params.extend(extra);
+    params.push(namespace_param());
+    params
+}
+
+fn operations() -> Vec<Operation> {
+    vec![
+        Operation {
+            method: "get",
+            path: "/api/openapi.json",
+            id: "getOpenApi",
+            tag: "meta",
+            summary: "This document",
+            parameters: vec![],
+            body: None,
+            responses: json_responses(&[("200", "OpenAPI document")], json!({ "type": "object" })),
+        },
+        Operation {
+            method: "get",
+            path: "/api/notify",
+            id: "notifyRelease",
+            tag: "meta",
+            summary: "Notify of a new artifact release in the cloud",
+            parameters: vec![],
+            body: Some(json!({
+                "required": true,
+                "description": "Name of the released artifact",
+                "content": { "text/plain": { "schema": { "type": "string" } } }
+            })),
+            responses: ok_responses(),
+        },
+        // Artifacts
+        Operation {
+            method: "post",
+            path: "/api/artifact",
+            id: "applyArtifact",
+            tag: "artifact",
+            summary: "Apply the artifacts of a multi-document yaml",
+            parameters: template_params(),
+            body: yaml_body("Artifacts separated by `---`"),
+            responses: ok_responses(),
+        },
+        Operation {
+            method: "delete",
+            path: "/api/artifact",
+            id: "withdrawArtifact",
+            tag: "artifact",
+            summary: "Withdraw the scenario of a multi-document yaml",
+            parameters: vec![],
+            body: yaml_body("Artifacts separated by `---`"),
+            responses: ok_responses(),
+        },
+        Operation {
+            method: "post",
+            path: "/api/artifact/validate",
+            id: "validateArtifact",
+            tag: "artifact",
+            summary: "Validate artifacts without applying them",
+            parameters: template_params(),
+            body: yaml_body("Artifacts separated by `---`"),
+            responses: json_responses(
+                &[("200", "Valid"), ("422", "Invalid")],
+                schema_ref("ValidationReport"),
+            ),
+        },
+        Operation {
+            method: "post",
+            path: "/api/artifact/transaction",
+            id: "applyArtifactTransaction",
+            tag: "artifact",
+            summary: "Apply artifacts all together or not at all",
+            parameters: template_params(),
+            body: yaml_body("Artifacts separated by `---`"),
+            responses: json_responses(
+                &[
+                    ("200", "Committed"),
+                    ("422", "A document is invalid, nothing was written"),
+                    ("500", "The write failed, nothing was written"),
+                ],
+                schema_ref("ApplyReport"),
+            ),
+        },
+        Operation {
+            method: "get",
+            path: "/api/namespaces",
+            id: "listNamespaces",
+            tag: "artifact",
+            summary: "Namespaces holding artifacts, the default one first",
+            parameters: vec![],
+            body: None,
+            responses: json_responses(
+                &[("200", "Namespaces")],
+                json!({ "type": "array", "items": { "type": "string" } }),
+            ),
+        },
+        Operation {
+            method: "get",
+            path: "/api/artifact/{kind}",
+            id: "listArtifacts",
+            tag: "artifact",
+            summary: "Stored artifacts of a kind",
+            parameters: {
+                let mut params = vec![path_param("kind", "Kind of the artifacts")];
+                params.extend(list_params(true));
+                params.push(namespace_param());
+                params
+            },
+            body: None,
+            responses: json_responses(&[("200", "Page of artifacts")], schema_ref("ArtifactList")),
+        },
+        Operation {
+            method: "get",
+            path: "/api/artifact/{kind}/{name}",
+            id: "getArtifact",
+            tag: "artifact",
+            summary: "Yaml of a stored artifact",
+            parameters: artifact_params(vec![]),
+            body: None,
+            responses: yaml_responses("Artifact"),
+        },
+        Operation {
+            method: "get",
+            path: "/api/artifact/{kind}/{name}/versions",
+            id: "listVersions",
+            tag: "artifact",
+            summary: "Stored versions of an artifact",
+            parameters: artifact_params(vec![]),
+            body: None,
+            responses: json_responses(&[("200", "Versions")], schema_ref("VersionHistory")),
+        },
+        Operation {
+            method: "get",
+            path: "/api/artifact/{kind}/{name}/diff",
+            id: "diffVersions",
+            tag: "artifact",
+            summary: "Line diff of two stored versions of an artifact",
+            parameters: artifact_params(vec![
+                json!({ "name": "from", "in": "query", "required": true, "schema": { "type": "integer", "minimum": 0 } }),
+                json!({ "name": "to", "in": "query", "required": true, "schema": { "type": "integer", "minimum": 0 } }),
+            ]),
+            body: None,
+            responses: json_responses(&[("200", "Diff")], schema_ref("VersionDiff")),
+        },
+        Operation {
+            method: "get",
+            path: "/api/artifact/{kind}/{name}/export",
+            id: "exportArtifact",
+            tag: "artifact",
+            summary: "Manifests of a stored package for another orchestrator",
+            parameters: artifact_params(vec![
+                json!({ "name": "format", "in": "query", "required": true, "schema": { "type": "string", "enum": ["k8s"] } }),
+                query_param(
+                    "workload",
+                    json!({ "type": "string", "enum": ["deployment", "pod"] }),
+                    "Kind of the exported workloads, `deployment` if omitted",
+                ),
+            ]),
+            body: None,
+            responses: yaml_responses("Manifests"),
+        },
+        Operation {
+            method: "post",
+            path: "/api/artifact/{kind}/{name}/rollback/{version}",
+            id: "rollbackArtifact",
+            tag: "artifact",
+            summary: "Re-activate a stored version of an artifact",
+            parameters: {
+                let mut params = artifact_params(vec![]);
+                params.insert(2, path_param("version", "Version to roll back to"));
+                params
+            },
+            body: None,
+            responses: ok_responses(),
+        },
+        Operation {
+            method: "post",
+            path: "/api/bundle/export",
+            id: "exportBundle",
+            tag: "artifact",
+            summary: "Write all stored artifacts and their images into a signed bundle",
+            parameters: vec![],
+            body: json_body(schema_ref("BundleRequest")),
+            responses: json_responses(&[("200", "Written bundle")], schema_ref("ExportSummary")),
+        },
+        Operation {
+            method: "post",
+            path: "/api/bundle/import",
+            id: "importBundle",
+            tag: "artifact",
+            summary: "Verify a bundle, load its images and apply its artifacts",
+            parameters: vec![],
+            body: json_body(schema_ref("BundleRequest")),
+            responses: json_responses(
+                &[
+                    ("200", "Committed"),
+                    ("422", "A document is invalid, nothing was written"),
+                ],
+                schema_ref("ApplyReport"),
+            ),
+        },
+        // Clusters
+        Operation {
+            method: "get",
+            path: "/api/clusters",
+            id: "listClusters",
+            tag: "cluster",
+            summary: "The default cluster and all named clusters",
+            parameters: list_params(false),
+            body: None,
+            responses: json_responses(&[("200", "Page of clusters")], schema_ref("ClusterList")),
+        },
+        Operation {
+            method: "post",
+            path: "/api/clusters",
+            id: "createCluster",
+            tag: "cluster",
+            summary: "Create a named cluster",
+            parameters: vec![],
+            body: json_body(schema_ref("ClusterTopology")),
+            responses: json_responses(&[("200", "Created cluster")], schema_ref("ClusterTopology")),
+        },
+        Operation {
+            method: "get",
+            path: "/api/clusters/{id}",
+            id: "getCluster",
+            tag: "cluster",
+            summary: "Topology of a cluster",
+            parameters: vec![path_param("id", "Cluster id")],
+            body: None,
+            responses: json_responses(&[("200", "Cluster")], schema_ref("ClusterTopology")),
+        },
+        Operation {
+            method: "delete",
+            path: "/api/clusters/{id}",
+            id: "deleteCluster",
+            tag: "cluster",
+            summary: "Delete a named cluster, its nodes return to the default cluster",
+            parameters: vec![path_param("id", "Cluster id")],
+            body: None,
+            responses: json_responses(&[("200", "Deleted cluster")], schema_ref("ClusterTopology")),
+        },
+        Operation {
+            method: "get",
+            path: "/api/clusters/{id}/health",
+            id: "getClusterHealth",
+            tag: "cluster",
+            summary: "Health summary of the nodes of a cluster",
+            parameters: vec![path_param("id", "Cluster id")],
+            body: None,
+            responses: json_responses(&[("200", "Health")], schema_ref("ClusterHealth")),
+        },
+        Operation {
+            method: "put",
+            path: "/api/clusters/{id}/nodes/{node}",
+            id: "assignNode",
+            tag: "cluster",
+            summary: "Move a node into a cluster",
+            parameters: vec![
+                path_param("id", "Cluster id"),
+                path_param("node", "Node id or hostname"),
+            ],
+            body: None,
+            responses: json_responses(&[("200", "Updated cluster")], schema_ref("ClusterTopology")),
+        },
+        // Nodes
+        Operation {
+            method: "get",
+            path: "/api/nodes/allocation",
+            id: "getNodeAllocation",
+            tag: "node",
+            summary: "Allocatable and allocated CPU and memory of every node",
+            parameters: vec![],
+            body: None,
+            responses: json_responses(
+                &[("200", "Allocation of each node")],
+                json!({ "type": "array", "items": schema_ref("NodeAllocation") }),
+            ),
+        },
+        Operation {
+            method: "post",
+            path: "/api/v1/nodes/{id}/drain",
+            id: "drainNode",
+            tag: "node",
+            summary: "Put a node in maintenance and move its models to other nodes",
+            parameters: vec![path_param("id", "Node id or hostname")],
+            body: None,
+            responses: json_responses(
+                &[("200", "Outcome of each model")],
+                schema_ref("DrainReport"),
+            ),
+        },
+        Operation {
+            method: "post",
+            path: "/api/v1/nodes/{id}/uncordon",
+            id: "uncordonNode",
+            tag: "node",
+            summary: "Take a node out of maintenance",
+            parameters: vec![path_param("id", "Node id or hostname")],
+            body: None,
+            responses: ok_responses(),
+        },
+        Operation {
+            method: "post",
+            path: "/api/v1/nodes/{id}/migrate",
+            id: "migrateNode",
+            tag: "node",
+            summary: "Move every model of a failed node to another node",
+            parameters: vec![
+                path_param("id", "Node id or hostname"),
+                query_param(
+                    "target",
+                    json!({ "type": "string" }),
+                    "Node the models move to, chosen for each model if omitted",
+                ),
+            ],
+            body: None,
+            responses: json_responses(
+                &[("200", "Outcome of each model")],
+                schema_ref("MigrationReport"),
+            ),
+        },
+        Operation {
+            method: "post",
+            path: "/api/v1/nodes/bootstrap-token",
+            id: "issueBootstrapToken",
+            tag: "node",
+            summary: "Issue a one-time token a guest node joins the master with",
+            parameters: vec![],
+            body: json_body(schema_ref("TokenRequest")),
+            responses: json_responses(&[("200", "Issued token")], schema_ref("IssuedToken")),
+        },
+        Operation {
+            method: "post",
+            path: common::bootstrap::REDEEM_PATH,
+            id: "redeemBootstrapToken",
+            tag: "node",
+            summary: "Exchange a bootstrap token for the bootstrap bundle of its node",
+            parameters: vec![],
+            body: json_body(json!({
+                "type": "object",
+                "required": ["token"],
+                "properties": { "token": { "type": "string" } }
+            })),
+            responses: json_responses(&[("200", "Bundle")], schema_ref("BootstrapBundle")),
+        },
+        Operation {
+            method: "get",
+            path: "/api/v1/containers/{id}/logs",
+            id: "getContainerLogs",
+            tag: "node",
+            summary: "Last lines of a container, read from its node if not collected",
+            parameters: vec![
+                path_param("id", "Id, id prefix or name of the container"),
+                query_param(
+                    "node",
+                    json!({ "type": "string" }),
+                    "Hostname of the node, needed if the container is on several nodes",
+                ),
+                query_param(
+                    "tail",
+                    json!({ "type": "integer", "minimum": 0 }),
+                    "Number of last lines, all kept lines if omitted",
+                ),
+            ],
+            body: None,
+            responses: text_responses("Log lines"),
+        },
+        // Quotas
+        Operation {
+            method: "get",
+            path: "/api/quotas",
+            id: "listQuotas",
+            tag: "quota",
+            summary: "Resource quotas of packages, sorted by name",
+            parameters: vec![],
+            body: None,
+            responses: json_responses(
+                &[("200", "Quotas")],
+                json!({ "type": "array", "items": schema_ref("ResourceQuota") }),
+            ),
+        },
+        Operation {
+            method: "get",
+            path: "/api/quotas/{name}",
+            id: "getQuota",
+            tag: "quota",
+            summary: "A resource quota",
+            parameters: quota_param(),
+            body: None,
+            responses: json_responses(&[("200", "Quota")], schema_ref("ResourceQuota")),
+        },
+        Operation {
+            method: "put",
+            path: "/api/quotas/{name}",
+            id: "putQuota",
+            tag: "quota",
+            summary: "Create or replace a resource quota",
+            parameters: quota_param(),
+            body: json_body(schema_ref("ResourceQuota")),
+            responses: json_responses(&[("200", "Stored quota")], schema_ref("ResourceQuota")),
+        },
+        Operation {
+            method: "delete",
+            path: "/api/quotas/{name}",
+            id: "deleteQuota",
+            tag: "quota",
+            summary: "Delete a resource quota",
+            parameters: quota_param(),
+            body: None,
+            responses: json_responses(&[("200", "Deleted")], json!({ "nullable": true })),
+        },
+        // States
+        Operation {
+            method: "get",
+            path: "/api/state/{kind}/{name}/history",
+            id: "getStateHistory",
+            tag: "state",
+            summary: "Last state transitions of a scenario, package or model",
+            parameters: vec![
+                path_param("kind", "`scenario`, `package` or `model`"),
+                path_param("name", "Name of the resource"),
+                query_param(
+                    "limit",
+                    json!({ "type": "integer", "minimum": 0 }),
+                    "Newest transitions to return",
+                ),
+                query_param(
+                    "since",
+                    json!({ "type": "integer" }),
+                    "Start of the time range in Unix nanoseconds",
+                ),
+                query_param(
+                    "until",
+                    json!({ "type": "integer" }),
+                    "End of the time range in Unix nanoseconds",
+                ),
+            ],
+            body: None,
+            responses: json_responses(
+                &[("200", "Transitions")],
+                json!({ "type": "array", "items": schema_ref("StateTransition") }),
+            ),
+        },
+        Operation {
+            method: "get",
+            path: "/api/v1/summary",
+            id: "getSummary",
+            tag: "state",
+            summary: "State summary of the cluster and its scenarios for dashboards",
+            parameters: vec![],
+            body: None,
+            responses: json_responses(&[("200", "Summary")], schema_ref("Summary")),
+        },
+        Operation {
+            method: "get",
+            path: "/api/v1/orphans",
+            id: "getOrphans",
+            tag: "state",
+            summary: "Orphaned artifacts found by the last garbage collection scan",
+            parameters: vec![],
+            body: None,
+            responses: json_responses(&[("200", "Last scan")], schema_ref("OrphanReport")),
+        },
+        // Topics
+        Operation {
+            method: "get",
+            path: "/api/topics",
+            id: "listTopics",
+            tag: "topic",
+            summary: "DDS topics FilterGateway listens to",
+            parameters: vec![],
+            body: None,
+            responses: json_responses(
+                &[("200", "Topics")],
+                json!({ "type": "array", "items": schema_ref("Topic") }),
+            ),
+        },
+        Operation {
+            method: "post",
+            path: "/api/topics",
+            id: "subscribeTopic",
+            tag: "topic",
+            summary: "Subscribe FilterGateway to a DDS topic",
+            parameters: vec![],
+            body: json_body(schema_ref("SubscribeTopic")),
+            responses: json_responses(&[("200", "Subscribed topic")], schema_ref("Topic")),
+        },
+        Operation {
+            method: "delete",
+            path: "/api/topics/{topic}",
+            id: "unsubscribeTopic",
+            tag: "topic",
+            summary: "Unsubscribe from a topic subscribed over this API",
+            parameters: topic_param(),
+            body: None,
+            responses: json_responses(&[("200", "Unsubscribed topic")], schema_ref("Topic")),
+        },
+        Operation {
+            method: "post",
+            path: "/api/topics/{topic}/pause",
+            id: "pauseTopic",
+            tag: "topic",
+            summary: "Stop listening to a subscribed topic until it is resumed",
+            parameters: topic_param(),
+            body: None,
+            responses: json_responses(&[("200", "Paused topic")], schema_ref("Topic")),
+        },
+        Operation {
+            method: "post",
+            path: "/api/topics/{topic}/resume",
+            id: "resumeTopic",
+            tag: "topic",
+            summary: "Listen again to a paused topic",
+            parameters: topic_param(),
+            body: None,
+            responses: json_responses(&[("200", "Resumed topic")], schema_ref("Topic")),
+        },
+        // Scenarios
+        Operation {
+            method: "post",
+            path: "/api/v1/scenarios/{name}/trigger",
+            id: "triggerScenario",
+            tag: "scenario",
+            summary: "Run the action of a scenario now and track its models",
+            parameters: scenario_params(vec![
+                query_param(
+                    "dry_run",
+                    json!({ "type": "boolean" }),
+                    "Return the execution plan instead of running the action",
+                ),
+                query_param(
+                    "wait",
+                    json!({ "type": "boolean" }),
+                    "Wait until the models reached a terminal state",
+                ),
+                query_param(
+                    "timeout",
+                    json!({ "type": "integer", "minimum": 0 }),
+                    "Seconds to wait, 60 if omitted",
+                ),
+            ]),
+            body: None,
+            responses: json_responses(
+                &[
+                    ("200", "Terminal result, or the execution plan of a dry run"),
+                    ("202", "Started, not waited for"),
+                    ("504", "The wait timed out"),
+                ],
+                json!({ "oneOf": [schema_ref("TriggerResult"), schema_ref("ExecutionPlan")] }),
+            ),
+        },
+        Operation {
+            method: "post",
+            path: "/api/v1/scenarios/{name}/reset",
+            id: "resetScenario",
+            tag: "scenario",
+            summary: "Release a scenario quarantined for flapping",
+            parameters: scenario_params(vec![query_param(
+                "reason",
+                json!({ "type": "string" }),
+                "Why the scenario is released",
+            )]),
+            body: None,
+            responses: json_responses(&[("200", "Released")], schema_ref("ResetResult")),
+        },
+    ]
+}
+
+fn schemas() -> Value {
+    let mut schemas = json!({
+        "ApiError": {
+            "type": "object",
+            "required": ["code", "message"],
+            "properties": {
+                "code": {
+                    "type": "string",
+                    "enum": [
+                        "VALIDATION", "UNAUTHORIZED", "FORBIDDEN", "NOT_FOUND", "CONFLICT",
+                        "PAYLOAD_TOO_LARGE", "RATE_LIMITED", "INTERNAL", "DEPENDENCY_UNAVAILABLE"
+                    ]
+                },
+                "message": { "type": "string" },
+                "details": {},
+                "correlation_id": { "type": "string", "nullable": true }
+            }
+        },
+        "ArtifactSummary": {
+            "type": "object",
+            "required": ["name", "labels"],
+            "properties": {
+                "name": { "type": "string" },
+                "labels": { "type": "object", "additionalProperties": { "type": "string" } }
+            }
+        },
+        "ArtifactList": {
+            "type": "object",
+            "required": ["artifacts", "total", "offset"],
+            "properties": {
+                "artifacts": { "type": "array", "items": schema_ref("ArtifactSummary") },
+                "total": { "type": "integer" },
+                "offset": { "type": "integer" },
+                "limit": { "type": "integer", "nullable": true },
+                "next_offset": { "type": "integer", "nullable": true }
+            }
+        },
+        "ArtifactRef": {
+            "type": "object",
+            "required": ["document", "kind", "name"],
+            "properties": {
+                "document": { "type": "integer" },
+                "kind": { "type": "string" },
+                "name": { "type": "string" }
+            }
+        },
+        "ValidationIssue": {
+            "type": "object",
+            "required": ["message"],
+            "properties": {
+                "document": { "type": "integer", "nullable": true },
+                "kind": { "type": "string", "nullable": true },
+                "name": { "type": "string", "nullable": true },
+                "message": { "type": "string" }
+            }
+        },
+        "ValidationReport": {
+            "type": "object",
+            "required": ["valid", "artifacts", "errors", "warnings"],
+            "properties": {
+                "valid": { "type": "boolean" },
+                "artifacts": { "type": "array", "items": schema_ref("ArtifactRef") },
+                "errors": { "type": "array", "items": schema_ref("ValidationIssue") },
+                "warnings": { "type": "array", "items": schema_ref("ValidationIssue") }
+            }
+        },
+        "DocumentResult": {
+            "type": "object",
+            "required": ["document", "status"],
+            "properties": {
+                "document": { "type": "integer" },
+                "kind": { "type": "string", "nullable": true },
+                "name": { "type": "string", "nullable": true },
+                "status": {
+                    "type": "string",
+                    "enum": ["applied", "updated", "unchanged", "invalid", "superseded", "not_applied"]
+                },
+                "version": { "type": "integer" },
+                "messages": { "type": "array", "items": { "type": "string" } }
+            }
+        },
+        "ApplyReport": {
+            "type": "object",
+            "required": ["committed", "documents", "errors", "warnings"],
+            "properties": {
+                "committed": { "type": "boolean" },
+                "documents": { "type": "array", "items": schema_ref("DocumentResult") },
+                "errors": { "type": "array", "items": { "type": "string" } },
+                "warnings": { "type": "array", "items": { "type": "string" } }
+            }
+        },
+        "VersionHistory": {
+            "type": "object",
+            "required": ["latest", "versions"],
+            "properties": {
+                "latest": { "type": "integer" },
+                "versions": { "type": "array", "items": { "type": "integer" } }
+            }
+        },
+        "VersionDiff": {
+            "type": "object",
+            "required": ["from", "to", "lines"],
+            "properties": {
+                "from": { "type": "integer" },
+                "to": { "type": "integer" },
+                "lines": { "type": "array", "items": { "type": "string" } }
+            }
+        },
+        "ClusterTopology": {
+            "type": "object",
+            "properties": {
+                "cluster_id": { "type": "string" },
+                "cluster_name": { "type": "string" },
+                "type": { "type": "integer", "description": "TopologyType of apiserver.proto" },
+                "master_nodes": { "type": "array", "items": { "type": "object" } },
+                "sub_nodes": { "type": "array", "items": { "type": "object" } },
+                "parent_cluster": { "type": "string" },
+                "config": { "type": "object", "additionalProperties": { "type": "string" } }
+            }
+        },
+        "ClusterList": {
+            "type": "object",
+            "required": ["clusters", "total", "offset"],
+            "properties": {
+                "clusters": { "type": "array", "items": schema_ref("ClusterTopology") },
+                "total": { "type": "integer" },
+                "offset": { "type": "integer" },
+                "limit": { "type": "integer", "nullable": true },
+                "next_offset": { "type": "integer", "nullable": true }
+            }
+        },
+        "ClusterHealth": {
+            "type": "object",
+            "properties": {
+                "cluster_id": { "type": "string" },
+                "total_nodes": { "type": "integer" },
+                "healthy_nodes": { "type": "integer" },
+                "unhealthy_nodes": { "type": "integer" },
+                "master_nodes": { "type": "integer" },
+                "nodeagent_nodes": { "type": "integer" },
+                "ready_nodes": { "type": "integer" },
+                "status": { "type": "string", "enum": ["Healthy", "Degraded", "Critical"] }
+            }
+        },
+        "ExecutionPlan": {
+            "type": "object",
+            "description": "ExecutionPlan of actioncontroller.proto"
+        },
+        "ModelResult": {
+            "type": "object",
+            "required": ["model", "node", "operation", "state", "outcome"],
+            "properties": {
+                "model": { "type": "string" },
+                "node": { "type": "string" },
+                "operation": { "type": "string" },
+                "state": { "type": "string" },
+                "outcome": { "type": "string", "enum": ["pending", "succeeded", "failed"] }
+            }
+        },
+        "TriggerResult": {
+            "type": "object",
+            "required": ["transition_id", "scenario", "status"],
+            "properties": {
+                "transition_id": { "type": "string" },
+                "scenario": { "type": "string" },
+                "status": {
+                    "type": "string",
+                    "enum": ["accepted", "succeeded", "failed", "timed_out"]
+                },
+                "message": { "type": "string" },
+                "models": { "type": "array", "items": schema_ref("ModelResult") }
+            }
+        },
+        "ResetResult": {
+            "type": "object",
+            "required": ["scenario", "state", "message"],
+            "properties": {
+                "scenario": { "type": "string" },
+                "state": { "type": "string" },
+                "message": { "type": "string" }
+            }
+        }
+    });
+    if let (Some(schemas), Value::Object(more)) = (schemas.as_object_mut(), more_schemas()) {
+        schemas.extend(more);
+    }
+    schemas
+}
+
+/// Schemas of the bundle, node, quota, state and topic endpoints
+fn more_schemas() -> Value {
+    json!({
+        "BundleRequest": {
+            "type": "object",
+            "required": ["path"],
+            "properties": {
+                "path": { "type": "string", "description": "Local file of the bundle" }
+            }
+        },
+        "ExportSummary": {
+            "type": "object",
+            "required": ["path", "artifacts", "images"],
+            "properties": {
+                "path": { "type": "string" },
+                "artifacts": { "type": "integer" },
+                "images": { "type": "array", "items": { "type": "string" } }
+            }
+        },
+        "Allocation": {
+            "type": "object",
+            "required": ["model", "node", "cpu_millis", "memory_mb"],
+            "properties": {
+                "model": { "type": "string" },
+                "node": { "type": "string" },
+                "cpu_millis": { "type": "integer" },
+                "memory_mb": { "type": "integer" }
+            }
+        },
+        "NodeAllocation": {
+            "type": "object",
+            "required": ["node", "allocated_cpu_millis", "allocated_memory_mb", "models"],
+            "properties": {
+                "node": { "type": "string" },
+                "allocatable_cpu_millis": { "type": "integer", "nullable": true },
+                "allocatable_memory_mb": { "type": "integer", "nullable": true },
+                "allocated_cpu_millis": { "type": "integer" },
+                "allocated_memory_mb": { "type": "integer" },
+                "models": { "type": "array", "items": schema_ref("Allocation") }
+            }
+        },
+        "DrainReport": {
+            "type": "object",
+            "required": ["node", "models"],
+            "properties": {
+                "node": { "type": "string" },
+                "models": { "type": "array", "items": { "type": "object" } }
+            }
+        },
+        "MigrationReport": {
+            "type": "object",
+            "required": ["source", "target", "models"],
+            "properties": {
+                "source": { "type": "string" },
+                "target": { "type": "string" },
+                "models": { "type": "array", "items": { "type": "object" } }
+            }
+        },
+        "TokenRequest": {
+            "type": "object",
+            "properties": {
+                "nodeName": { "type": "string" },
+                "labels": { "type": "object", "additionalProperties": { "type": "string" } },
+                "ttlSecs": { "type": "integer", "minimum": 0 }
+            }
+        },
+        "BootstrapBundle": {
+            "type": "object",
+            "required": ["masterIp", "apiEndpoint"],
+            "properties": {
+                "masterIp": { "type": "string" },
+                "apiEndpoint": { "type": "string" },
+                "caCert": { "type": "string" },
+                "nodeName": { "type": "string" },
+                "labels": { "type": "object", "additionalProperties": { "type": "string" } }
+            }
+        },
+        "IssuedToken": {
+            "type": "object",
+            "required": ["token", "expiresAt", "bundle"],
+            "properties": {
+                "token": { "type": "string" },
+                "expiresAt": { "type": "integer", "description": "Unix seconds" },
+                "bundle": schema_ref("BootstrapBundle")
+            }
+        },
+        "ResourceQuota": {
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "name": { "type": "string" },
+                "node": { "type": "string" },
+                "nodeSelector": { "type": "object", "additionalProperties": { "type": "string" } },
+                "maxCpu": { "type": "string", "example": "500m" },
+                "maxMemory": { "type": "string", "example": "512Mi" },
+                "maxContainers": { "type": "integer", "minimum": 0 }
+            }
+        },
+        "StateTransition": {
+            "type": "object",
+            "description": "StateTransitionHistory of statemanager.proto"
+        },
+        "Summary": {
+            "type": "object",
+            "properties": {
+                "nodes": { "type": "object", "additionalProperties": { "type": "integer" } },
+                "scenarios": { "type": "object", "additionalProperties": { "type": "integer" } },
+                "degraded_packages": { "type": "array", "items": { "type": "string" } },
+                "error_packages": { "type": "array", "items": { "type": "string" } },
+                "failed_transitions": { "type": "array", "items": { "type": "object" } },
+                "alerts": { "type": "array", "items": { "type": "object" } },
+                "unavailable": { "type": "array", "items": { "type": "string" } }
+            }
+        },
+        "Orphan": {
+            "type": "object",
+            "required": ["kind", "name", "reason", "missing", "first_seen"],
+            "properties": {
+                "kind": { "type": "string", "enum": ["Scenario", "Package"] },
+                "name": { "type": "string" },
+                "reason": {
+                    "type": "string",
+                    "enum": ["missing_model", "missing_package", "unregistered_node"]
+                },
+                "missing": { "type": "string" },
+                "first_seen": { "type": "integer" },
+                "delete_after": { "type": "integer" }
+            }
+        },
+        "OrphanReport": {
+            "type": "object",
+            "required": ["scanned_at", "delete", "grace_period_secs", "orphans"],
+            "properties": {
+                "scanned_at": { "type": "integer" },
+                "delete": { "type": "boolean" },
+                "grace_period_secs": { "type": "integer" },
+                "orphans": { "type": "array", "items": schema_ref("Orphan") }
+            }
+        },
+        "Topic": {
+            "type": "object",
+            "required": ["topic", "type_name", "domain_id", "reliability", "history_depth", "paused", "origin"],
+            "properties": {
+                "topic": { "type": "string" },
+                "type_name": { "type": "string" },
+                "domain_id": { "type": "integer" },
+                "reliability": { "type": "string", "enum": ["best_effort", "reliable"] },
+                "history_depth": { "type": "integer" },
+                "paused": { "type": "boolean" },
+                "origin": { "type": "string", "enum": ["scenario", "api"] }
+            }
+        },
+        "SubscribeTopic": {
+            "type": "object",
+            "required": ["topic", "type_name"],
+            "properties": {
+                "topic": { "type": "string" },
+                "type_name": { "type": "string" },
+                "domain_id": { "type": "integer", "description": "Domain of FilterGateway if omitted" },
+                "reliability": { "type": "string", "enum": ["best_effort", "reliable"] },
+                "history_depth": { "type": "integer", "minimum": 0 }
+            }
+        }
+    })
+}
+
+/// Operations called without a token
+const PUBLIC: &[&str] = &["redeemBootstrapToken"];
+
+/// OpenAPI document of the REST API
+pub fn document() -> Value {
+    let mut paths = Map::new();
+    for op in operations() {
+        let mut operation = json!({
+            "operationId": op.id,
+            "tags": [op.tag],
+            "summary": op.summary,
+            "responses": op.responses,
+        });
+        if !op.parameters.is_empty() {
+            operation["parameters"] = Value::Array(op.parameters);
+        }
+        if let Some(body) = op.body {
+            operation["requestBody"] = body;
+        }
+        if PUBLIC.contains(&op.id) {
+            operation["security"] = json!([]);
+        }
+        let item = paths
+            .entry(op.path.to_string())
+            .or_insert_with(|| json!({}));
+        item[op.method] = operation;
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Pullpiri REST API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Reads need the viewer role, triggering scenarios and pausing topics the operator role and changes the admin role."
+        },
+        "servers": [{ "url": "http://localhost:47099" }],
+        "security": [{ "bearer": [] }],
+        "paths": paths,
+        "components": {
+            "securitySchemes": {
+                "bearer": { "type": "http", "scheme": "bearer" }
+            },
+            "responses": {
+                "Error": {
+                    "description": "Failed request",
+                    "content": json_content(schema_ref("ApiError"))
+                }
+            },
+            "schemas": schemas()
+        }
+    })
+}
+
+/// Serve the OpenAPI document
+pub async fn serve() -> Json<Value> {
+    Json(document())
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn refs(value: &Value, found: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    match (key.as_str(), value) {
+                        ("$ref", Value::String(target)) => found.push(target.clone()),
+                        _ => refs(value, found),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|item| refs(item, found)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_operations_are_unique_and_refs_resolve() {
+        let document = document();
+        let mut ids = HashSet::new();
+        for op in operations() {
+            assert!(ids.insert(op.id), "duplicate operationId {}", op.id);
+            assert!(document["paths"][op.path][op.method].is_object());
+        }
+
+        let mut found = Vec::new();
+        refs(&document, &mut found);
+        assert!(!found.is_empty());
+        for target in found {
+            let pointer = target.trim_start_matches('#');
+            assert!(document.pointer(pointer).is_some(), "{} is missing", target);
+        }
+    }
+
+    #[test]
+    fn test_document_covers_every_route() {
+        let document = document();
+        let mut routes = HashSet::new();
+        for (_, group) in crate::route::api::routes() {
+            for route in group {
+                let path = route
+                    .path
+                    .split('/')
+                    .map(|segment| match segment.strip_prefix(':') {
+                        Some(param) => format!("{{{}}}", param),
+                        None => segment.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("/");
+                routes.insert((route.method.as_str().to_ascii_lowercase(), path));
+            }
+        }
+
+        let mut documented = HashSet::new();
+        for (path, item) in document["paths"].as_object().unwrap() {
+            for method in item.as_object().unwrap().keys() {
+                documented.insert((method.clone(), path.clone()));
+            }
+        }
+        for route in &routes {
+            assert!(documented.contains(route), "{:?} is not documented", route);
+        }
+        for operation in &documented {
+            assert!(routes.contains(operation), "{:?} is not routed", operation);
+        }
+    }
+
+    #[test]
+    fn test_client_copy_is_up_to_date() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../apiclient/openapi.json");
+        let generated = serde_json::to_string_pretty(&document()).unwrap() + "\n";
+        if std::env::var("UPDATE_OPENAPI").is_ok() {
+            std::fs::write(path, &generated).unwrap();
+        }
+        let copy = std::fs::read_to_string(path).unwrap();
+        assert!(
+            copy == generated,
+            "{} is outdated, run the apiserver tests with UPDATE_OPENAPI=1",
+            path
+        );
+    }
+}
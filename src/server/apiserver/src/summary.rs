@@ -0,0 +1,273 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Cluster and scenario state summary for dashboards
+//!
+//! `GET /api/v1/summary` returns in one response the node counts by status,
+//! the scenario counts by state, the degraded and failed packages, the last
+//! failed state transitions and the active alerts. The sources are read
+//! concurrently: nodes from the registry in etcd, resource states from the
+//! StateManager query API, transitions from the StateManager audit log and
+//! alerts from the MonitoringServer. A source that cannot be read is listed
+//! in `unavailable` and the rest of the summary is still returned.
+
+use crate::grpc::sender::statemanager::StateManagerSender;
+use crate::node::status::NodeStatusManager;
+use common::alert::ALERT_PREFIX;
+use common::apiserver::NodeInfo;
+use common::logd;
+use common::monitoringserver::Alert;
+use common::nodeagent::fromapiserver::NodeStatus;
+use common::statemanager::{ListResourceStatesRequest, ResourceState, ResourceType};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// etcd prefix of the transitions recorded by the StateManager
+const AUDIT_PREFIX: &str = "/audit/transitions/";
+/// Failed transitions returned, newest first
+const RECENT_FAILURES: usize = 10;
+
+/// State of the cluster and its scenarios
+#[derive(Debug, Default, Serialize)]
+pub struct Summary {
+    /// Nodes by status, a Ready node without recent heartbeat counts as NOT_READY
+    pub nodes: BTreeMap<String, usize>,
+    /// Scenarios by state
+    pub scenarios: BTreeMap<String, usize>,
+    pub degraded_packages: Vec<String>,
+    pub error_packages: Vec<String>,
+    pub failed_transitions: Vec<FailedTransition>,
+    pub alerts: Vec<Alert>,
+    /// Sources that could not be read, with the reason
+    pub unavailable: Vec<String>,
+}
+
+/// State transition refused by the StateManager
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedTransition {
+    pub recorded_at_ns: i64,
+    pub resource_type: String,
+    pub resource_name: String,
+    pub current_state: String,
+    pub target_state: String,
+    pub transition_id: String,
+    pub error_code: String,
+    pub message: String,
+    #[serde(default, skip_serializing)]
+    outcome: String,
+}
+
+/// Build the summary, reading all its sources at once
+pub async fn summary() -> Summary {
+    let (nodes, scenarios, packages, transitions, alerts) = tokio::join!(
+        read_nodes(),
+        read_resource_states(ResourceType::Scenario),
+        read_resource_states(ResourceType::Package),
+        read_failed_transitions(),
+        read_alerts(),
+    );
+
+    let mut summary = Summary::default();
+    let mut unavailable = |source: &str, e: String| {
+        logd!(4, "Summary without {}: {}", source, e);
+        summary.unavailable.push(format!("{}: {}", source, e));
+    };
+    let nodes = nodes
+        .map_err(|e| unavailable("nodes", e))
+        .unwrap_or_default();
+    let scenarios = scenarios
+        .map_err(|e| unavailable("scenarios", e))
+        .unwrap_or_default();
+    let packages = packages
+        .map_err(|e| unavailable("packages", e))
+        .unwrap_or_default();
+    let transitions = transitions
+        .map_err(|e| unavailable("transitions", e))
+        .unwrap_or_default();
+    let alerts = alerts
+        .map_err(|e| unavailable("alerts", e))
+        .unwrap_or_default();
+
+    summary.nodes = count_nodes(&nodes, crate::node::heartbeat::liveness_window());
+    summary.scenarios = count_states(&scenarios);
+    summary.degraded_packages = names_in_state(&packages, "DEGRADED");
+    summary.error_packages = names_in_state(&packages, "ERROR");
+    summary.failed_transitions = recent_failures(transitions, RECENT_FAILURES);
+    summary.alerts = alerts;
+    summary
+}
+
+async fn read_nodes() -> Result<Vec<NodeInfo>, String> {
+    let manager = crate::node::manager::NodeManager::new().map_err(|e| e.to_string())?;
+    manager.get_all_nodes().await.map_err(|e| e.to_string())
+}
+
+async fn read_resource_states(resource_type: ResourceType) -> Result<Vec<ResourceState>, String> {
+    let request = ListResourceStatesRequest {
+        resource_type: resource_type as i32,
+        ..Default::default()
+    };
+    let response = StateManagerSender::new()
+        .list_resource_states(request)
+        .await
+        .map_err(|e| e.message().to_string())?
+        .into_inner();
+    if !response.success {
+        return Err(response.message);
+    }
+    Ok(response.resources)
+}
+
+async fn read_failed_transitions() -> Result<Vec<(String, String)>, String> {
+    Ok(common::etcd::get_all_with_prefix(AUDIT_PREFIX).await?)
+}
+
+async fn read_alerts() -> Result<Vec<Alert>, String> {
+    let kvs = common::etcd::get_all_with_prefix(ALERT_PREFIX).await?;
+    Ok(kvs
+        .iter()
+        .filter_map(|(_, json)| serde_json::from_str::<Alert>(json).ok())
+        .filter(|alert| !alert.resolved)
+        .collect())
+}
+
+/// Count the nodes by status
+///
+/// A Ready node whose last heartbeat is older than `liveness_window` seconds
+/// counts as NOT_READY, as the registry only marks it so on its next sweep.
+pub fn count_nodes(nodes: &[NodeInfo], liveness_window: u64) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for node in nodes {
+        let mut status = NodeStatus::try_from(node.status).unwrap_or(NodeStatus::Unspecified);
+        if status == NodeStatus::Ready && !NodeStatusManager.is_node_healthy(node, liveness_window)
+        {
+            status = NodeStatus::NotReady;
+        }
+        let name = status.as_str_name();
+        let name = name.strip_prefix("NODE_STATUS_").unwrap_or(name);
+        *counts.entry(name.to_string()).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Count the resources by current state
+pub fn count_states(resources: &[ResourceState]) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for resource in resources {
+        *counts.entry(resource.current_state.clone()).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn names_in_state(resources: &[ResourceState], state: &str) -> Vec<String> {
+    let mut names: Vec<String> = resources
+        .iter()
+        .filter(|resource| resource.current_state.eq_ignore_ascii_case(state))
+        .map(|resource| resource.resource_name.clone())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Newest failed transitions of the audit log
+///
+/// Audit keys start with the zero-padded record time, so they sort in the
+/// order the transitions were processed.
+pub fn recent_failures(mut records: Vec<(String, String)>, limit: usize) -> Vec<FailedTransition> {
+    records.sort_by(|a, b| b.0.cmp(&a.0));
+    records
+        .iter()
+        .filter_map(|(_, json)| serde_json::from_str::<FailedTransition>(json).ok())
+        .filter(|record| record.outcome == "failure")
+        .take(limit)
+        .collect()
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> i64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64
+    }
+
+    fn node(id: &str, status: NodeStatus, last_heartbeat: i64) -> NodeInfo {
+        NodeInfo {
+            node_id: id.to_string(),
+            status: status as i32,
+            last_heartbeat,
+            ..Default::default()
+        }
+    }
+
+    fn resource(name: &str, state: &str) -> ResourceState {
+        ResourceState {
+            resource_name: name.to_string(),
+            current_state: state.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_count_nodes_and_states() {
+        let nodes = [
+            node("n1", NodeStatus::Ready, now()),
+            node("n2", NodeStatus::Ready, now() - 600),
+            node("n3", NodeStatus::Maintenance, now()),
+            node("n4", NodeStatus::Ready, now()),
+        ];
+        let counts = count_nodes(&nodes, 30);
+        assert_eq!(counts["READY"], 2);
+        assert_eq!(counts["NOT_READY"], 1);
+        assert_eq!(counts["MAINTENANCE"], 1);
+
+        let packages = [
+            resource("p2", "DEGRADED"),
+            resource("p1", "DEGRADED"),
+            resource("p3", "ERROR"),
+            resource("p4", "RUNNING"),
+        ];
+        assert_eq!(count_states(&packages)["DEGRADED"], 2);
+        assert_eq!(names_in_state(&packages, "Degraded"), vec!["p1", "p2"]);
+        assert_eq!(names_in_state(&packages, "ERROR"), vec!["p3"]);
+    }
+
+    #[test]
+    fn test_recent_failures() {
+        let record = |time: i64, outcome: &str| {
+            (
+                format!("{}{:020}-0", AUDIT_PREFIX, time),
+                serde_json::json!({
+                    "recorded_at_ns": time,
+                    "resource_type": "Scenario",
+                    "resource_name": "s1",
+                    "current_state": "IDLE",
+                    "target_state": "RUNNING",
+                    "transition_id": format!("t{}", time),
+                    "outcome": outcome,
+                    "error_code": "INVALID_STATE_TRANSITION",
+                    "message": "not allowed",
+                })
+                .to_string(),
+            )
+        };
+        let records = vec![
+            record(1, "failure"),
+            record(2, "success"),
+            record(3, "failure"),
+            record(4, "failure"),
+            ("/audit/transitions/x".to_string(), "not json".to_string()),
+        ];
+        let failures = recent_failures(records, 2);
+        let times: Vec<i64> = failures.iter().map(|f| f.recorded_at_ns).collect();
+        assert_eq!(times, vec![4, 3]);
+        let json = serde_json::to_value(&failures[0]).unwrap();
+        assert!(json.get("outcome").is_none());
+    }
+}
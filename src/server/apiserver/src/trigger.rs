@@ -0,0 +1,370 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Scenario triggers with an optional synchronous result
+//!
+//! `POST /api/v1/scenarios/:name/trigger` plans the action of the scenario
+//! with a dry run, so that an unknown scenario is refused at once, and then
+//! runs it in the background. Before it runs, the scenario is checked
+//! against the allow/deny policies of FilterGateway, as when its condition
+//! fires, and a denied trigger is refused. The transition id returned is the trace id of
+//! the request, which the StateManager records with each state change and
+//! which finds the `logd!` lines of the trigger.
+//!
+//! With `dry_run`, only the plan is returned and nothing is run.
+//!
+//! With `wait`, the request blocks until every model of the plan reached the
+//! state its operation leads to, or failed, and returns the outcome of each.
+//! The models are followed through the StateManager query API. The action
+//! keeps running if the wait times out.
+
+use crate::grpc::sender::statemanager::StateManagerSender;
+use common::actioncontroller::ExecutionPlan;
+use common::error::{ApiError, ErrorCode};
+use common::logd;
+use common::statemanager::{ListResourceStatesRequest, ResourceState, ResourceType};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// How often the model states are read while waiting
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Wait used if the request sets none
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+/// Longest wait a request may ask for
+pub const MAX_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Where a triggered action stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerStatus {
+    /// Running in the background, the caller did not wait
+    Accepted,
+    Succeeded,
+    Failed,
+    /// Some models did not reach a terminal state within the timeout
+    TimedOut,
+}
+
+/// Outcome of one model of the action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelOutcome {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+/// State of one model of the action
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelResult {
+    pub model: String,
+    pub node: String,
+    /// Workload operation of the plan, e.g. `start`
+    pub operation: String,
+    /// State reported by the StateManager, empty if it knows no such model
+    pub state: String,
+    pub outcome: ModelOutcome,
+}
+
+/// Result of `POST /api/v1/scenarios/:name/trigger`
+#[derive(Debug, Clone, Serialize)]
+pub struct TriggerResult {
+    pub transition_id: String,
+    pub scenario: String,
+    pub status: TriggerStatus,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub models: Vec<ModelResult>,
+}
+
+/// Trigger the action of a scenario
+///
+/// ### Parameters
+/// * `scenario: &str` - qualified name of the scenario
+/// * `wait: Option<Duration>` - how long to wait for the result of the
+///   models, `None` to return once the action started
+/// ### Returns
+/// * `Result<TriggerResult, ApiError>` - `Accepted` without wait, the
+///   outcome of each model with wait, or the error of the dry run or of
+///   the policy check
+pub async fn trigger(scenario: &str, wait: Option<Duration>) -> Result<TriggerResult, ApiError> {
+    let context = common::trace::current().unwrap_or_else(common::trace::TraceContext::new_root);
+    let mut result = TriggerResult {
+        transition_id: context.trace_id.clone(),
+        scenario: scenario.to_string(),
+        status: TriggerStatus::Accepted,
+        message: String::new(),
+        models: Vec::new(),
+    };
+
+    let plan = plan(scenario).await?;
+    check_policy(scenario).await?;
+
+    let started_ns = unix_time_ns();
+    // Dropping a running gRPC call would cancel the action, so it runs in
+    // its own task whether or not the caller waits
+    let name = scenario.to_string();
+    let action = tokio::spawn(common::trace::in_span(
+        "trigger_action",
+        Some(context),
+        async move { crate::grpc::sender::actioncontroller::trigger_action(&name, false).await },
+    ));
+    logd!(
+        2,
+        "Triggered scenario '{}', transition {}",
+        scenario,
+        result.transition_id
+    );
+    let Some(timeout) = wait else {
+        return Ok(result);
+    };
+
+    let follow = async {
+        match action.await {
+            Ok(Ok(_)) => {}
+            Ok(Err(status)) => return Err(status.message().to_string()),
+            Err(e) => return Err(e.to_string()),
+        }
+        loop {
+            // Without states a stopped model would look gone, so wait for them
+            if let Ok(states) = read_model_states().await {
+                let models = model_results(&plan, &states, started_ns);
+                if models.iter().all(|m| m.outcome != ModelOutcome::Pending) {
+                    return Ok(models);
+                }
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    };
+    match tokio::time::timeout(timeout, follow).await {
+        Ok(Ok(models)) => {
+            result.status = if models.iter().any(|m| m.outcome == ModelOutcome::Failed) {
+                TriggerStatus::Failed
+            } else {
+                TriggerStatus::Succeeded
+            };
+            result.models = models;
+        }
+        Ok(Err(message)) => {
+            result.status = TriggerStatus::Failed;
+            result.message = message;
+        }
+        Err(_) => {
+            result.status = TriggerStatus::TimedOut;
+            result.message = format!(
+                "No terminal result within {}s, the action keeps running",
+                timeout.as_secs()
+            );
+            if let Ok(states) = read_model_states().await {
+                result.models = model_results(&plan, &states, started_ns);
+            }
+        }
+    }
+    logd!(
+        2,
+        "Trigger of scenario '{}' finished as {:?}",
+        scenario,
+        result.status
+    );
+    Ok(result)
+}
+
+/// Execution plan of the action of a scenario, from a dry run
+pub async fn plan(scenario: &str) -> Result<ExecutionPlan, ApiError> {
+    Ok(
+        crate::grpc::sender::actioncontroller::trigger_action(scenario, true)
+            .await
+            .map_err(|e| ApiError::from_status(&e))?
+            .into_inner()
+            .plan
+            .unwrap_or_default(),
+    )
+}
+
+/// Refuse a scenario that the FilterGateway policies deny
+///
+/// Fails closed: if FilterGateway cannot be asked, the scenario does not run.
+async fn check_policy(scenario: &str) -> Result<(), ApiError> {
+    let yaml = crate::artifact::get("Scenario", scenario)
+        .await
+        .map_err(|e| ApiError::from_error(e.as_ref()))?;
+    let decision = crate::grpc::sender::filtergateway::check_policy(&yaml)
+        .await
+        .map_err(|e| ApiError::from_status(&e))?
+        .into_inner();
+    if decision.allowed {
+        return Ok(());
+    }
+    logd!(
+        3,
+        "Trigger of scenario '{}' denied by policy '{}', rule '{}'",
+        scenario,
+        decision.policy,
+        decision.rule
+    );
+    Err(ApiError::new(
+        ErrorCode::Forbidden,
+        format!(
+            "Scenario '{}' is denied by rule '{}' of policy '{}'",
+            scenario, decision.rule, decision.policy
+        ),
+    )
+    .with_details(serde_json::json!({
+        "policy": decision.policy,
+        "rule": decision.rule,
+    })))
+}
+
+async fn read_model_states() -> Result<Vec<ResourceState>, String> {
+    let request = ListResourceStatesRequest {
+        resource_type: ResourceType::Model as i32,
+        ..Default::default()
+    };
+    let response = StateManagerSender::new()
+        .list_resource_states(request)
+        .await
+        .map_err(|e| e.message().to_string())?
+        .into_inner();
+    if !response.success {
+        return Err(response.message);
+    }
+    Ok(response.resources)
+}
+
+fn unix_time_ns() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as i64)
+        .unwrap_or_default()
+}
+
+/// Outcome of each model of a plan in the current model states
+///
+/// Pre-pull steps are left out, as they change no model state.
+pub fn model_results(
+    plan: &ExecutionPlan,
+    states: &[ResourceState],
+    started_ns: i64,
+) -> Vec<ModelResult> {
+    let states: HashMap<&str, &ResourceState> = states
+        .iter()
+        .map(|state| (state.resource_name.as_str(), state))
+        .collect();
+    let mut results = Vec::new();
+    for node in &plan.nodes {
+        for step in &node.steps {
+            if step.operation == "prepull" {
+                continue;
+            }
+            let state = states.get(step.model_name.as_str()).copied();
+            results.push(ModelResult {
+                model: step.model_name.clone(),
+                node: node.node.clone(),
+                operation: step.operation.clone(),
+                state: state.map(|s| s.current_state.clone()).unwrap_or_default(),
+                outcome: outcome(&step.operation, state, started_ns),
+            });
+        }
+    }
+    results
+}
+
+/// Outcome of one operation on a model
+///
+/// A model that runs has started, whenever it did, as a restart may leave
+/// it running throughout. A failure only counts if it was reported after
+/// `started_ns`, earlier ones belong to the previous run. A stopped model
+/// has exited or is not known any more.
+fn outcome(operation: &str, state: Option<&ResourceState>, started_ns: i64) -> ModelOutcome {
+    let is = |s: &ResourceState, name: &str| s.current_state.eq_ignore_ascii_case(name);
+    let failed = |s: &ResourceState| {
+        s.last_transition_time_ns >= started_ns && ["FAILED", "DEAD"].iter().any(|n| is(s, n))
+    };
+    match operation {
+        "stop" => match state {
+            None => ModelOutcome::Succeeded,
+            Some(s) if is(s, "EXITED") => ModelOutcome::Succeeded,
+            Some(s) if failed(s) => ModelOutcome::Failed,
+            Some(_) => ModelOutcome::Pending,
+        },
+        _ => match state {
+            Some(s) if is(s, "RUNNING") => ModelOutcome::Succeeded,
+            Some(s) if failed(s) => ModelOutcome::Failed,
+            Some(s) if is(s, "EXITED") && s.last_transition_time_ns >= started_ns => {
+                ModelOutcome::Failed
+            }
+            _ => ModelOutcome::Pending,
+        },
+    }
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::actioncontroller::{NodePlan, PlannedStep};
+
+    fn step(model: &str, operation: &str) -> PlannedStep {
+        PlannedStep {
+            model_name: model.to_string(),
+            operation: operation.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn state(model: &str, current: &str, at: i64) -> ResourceState {
+        ResourceState {
+            resource_name: model.to_string(),
+            current_state: current.to_string(),
+            last_transition_time_ns: at,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_model_results() {
+        let plan = ExecutionPlan {
+            nodes: vec![NodePlan {
+                node: "HPC".to_string(),
+                steps: vec![
+                    step("m1", "prepull"),
+                    step("m1", "start"),
+                    step("m2", "start"),
+                    step("m3", "restart"),
+                    step("m4", "start"),
+                    step("m5", "stop"),
+                    step("m6", "stop"),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let states = [
+            state("m1", "RUNNING", 50),
+            // Failed before the trigger, the new run has not reported yet
+            state("m2", "FAILED", 50),
+            state("m3", "RUNNING", 50),
+            state("m4", "DEAD", 150),
+            state("m5", "EXITED", 150),
+        ];
+        let outcomes: Vec<(String, ModelOutcome)> = model_results(&plan, &states, 100)
+            .into_iter()
+            .map(|r| (r.model, r.outcome))
+            .collect();
+        let expected = [
+            ("m1", ModelOutcome::Succeeded),
+            ("m2", ModelOutcome::Pending),
+            ("m3", ModelOutcome::Succeeded),
+            ("m4", ModelOutcome::Failed),
+            ("m5", ModelOutcome::Succeeded),
+            ("m6", ModelOutcome::Succeeded),
+        ];
+        let expected: Vec<(String, ModelOutcome)> =
+            expected.iter().map(|(m, o)| (m.to_string(), *o)).collect();
+        assert_eq!(outcomes, expected);
+    }
+}
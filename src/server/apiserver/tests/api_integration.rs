@@ -127,7 +127,7 @@ async fn test_apply_artifact_missing_body() {
         .unwrap();
 
     let res = app.oneshot(req).await.unwrap();
-    assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED); // Manager likely returns an error for empty string
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST); // Manager rejects an empty string
 }
 
 // Test: GET /api/artifact (method not allowed)
@@ -172,7 +172,7 @@ async fn test_withdraw_artifact_empty_body() {
         .unwrap();
 
     let res = app.oneshot(req).await.unwrap();
-    assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
 }
 
 // Test: PUT /api/artifact (not allowed method)
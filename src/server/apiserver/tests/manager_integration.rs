@@ -3,6 +3,7 @@
 * SPDX-License-Identifier: Apache-2.0
 */
 use apiserver::manager::{apply_artifact, initialize};
+use std::collections::HashMap;
 
 /// Correct valid YAML artifact (Scenario + Package + Model)
 const VALID_ARTIFACT_YAML: &str = r#"
@@ -430,7 +431,7 @@ async fn test_initialize_runs_successfully() {
 
 #[tokio::test]
 async fn test_apply_invalid_missing_action() {
-    let result = apply_artifact(INVALID_ARTIFACT_YAML_MISSING_ACTION).await;
+    let result = apply_artifact(INVALID_ARTIFACT_YAML_MISSING_ACTION, &HashMap::new()).await;
     assert!(
         result.is_err(),
         "Expected apply_artifact to fail for missing action"
@@ -439,7 +440,11 @@ async fn test_apply_invalid_missing_action() {
 
 #[tokio::test]
 async fn test_apply_invalid_required_fields() {
-    let result = apply_artifact(INVALID_ARTIFACT_YAML_MISSING_REQUIRED_FIELDS).await;
+    let result = apply_artifact(
+        INVALID_ARTIFACT_YAML_MISSING_REQUIRED_FIELDS,
+        &HashMap::new(),
+    )
+    .await;
     assert!(
         result.is_err(),
         "Expected apply_artifact to fail for missing required fields"
@@ -448,7 +453,7 @@ async fn test_apply_invalid_required_fields() {
 
 #[tokio::test]
 async fn test_apply_malformed_structure() {
-    let result = apply_artifact(INVALID_ARTIFACT_YAML_MALFORMED_STRUCTURE).await;
+    let result = apply_artifact(INVALID_ARTIFACT_YAML_MALFORMED_STRUCTURE, &HashMap::new()).await;
     assert!(
         result.is_err(),
         "Expected apply_artifact to fail for malformed YAML"
@@ -457,7 +462,7 @@ async fn test_apply_malformed_structure() {
 
 #[tokio::test]
 async fn test_apply_invalid_extra_fields() {
-    let result = apply_artifact(INVALID_ARTIFACT_YAML_EXTRA_FIELDS).await;
+    let result = apply_artifact(INVALID_ARTIFACT_YAML_EXTRA_FIELDS, &HashMap::new()).await;
     assert!(
         result.is_err(),
         "Expected apply_artifact to fail for misplaced fields"
@@ -466,7 +471,7 @@ async fn test_apply_invalid_extra_fields() {
 
 #[tokio::test]
 async fn test_apply_unknown_kind() {
-    let result = apply_artifact(INVALID_ARTIFACT_YAML_UNKNOWN).await;
+    let result = apply_artifact(INVALID_ARTIFACT_YAML_UNKNOWN, &HashMap::new()).await;
     assert!(
         result.is_err(),
         "Expected apply_artifact to fail for unknown kind"
@@ -475,7 +480,7 @@ async fn test_apply_unknown_kind() {
 
 #[tokio::test]
 async fn test_apply_empty_yaml() {
-    let result = apply_artifact(INVALID_ARTIFACT_YAML_EMPTY).await;
+    let result = apply_artifact(INVALID_ARTIFACT_YAML_EMPTY, &HashMap::new()).await;
     assert!(
         result.is_err(),
         "Expected apply_artifact to fail for empty input"
@@ -493,7 +498,11 @@ async fn test_apply_empty_yaml() {
 
 #[tokio::test]
 async fn test_apply_known_unknown_without_scenario() {
-    let result = apply_artifact(INVALID_ARTIFACT_YAML_KNOWN_UNKNOWN_WITHOUT_SCENARIO).await;
+    let result = apply_artifact(
+        INVALID_ARTIFACT_YAML_KNOWN_UNKNOWN_WITHOUT_SCENARIO,
+        &HashMap::new(),
+    )
+    .await;
     assert!(
         result.is_err(),
         "Expected failure for missing Scenario in known/unknown"
@@ -502,7 +511,11 @@ async fn test_apply_known_unknown_without_scenario() {
 
 #[tokio::test]
 async fn test_apply_known_unknown_without_package() {
-    let result = apply_artifact(INVALID_ARTIFACT_YAML_KNOWN_UNKNOWN_WITHOUT_PACKAGE).await;
+    let result = apply_artifact(
+        INVALID_ARTIFACT_YAML_KNOWN_UNKNOWN_WITHOUT_PACKAGE,
+        &HashMap::new(),
+    )
+    .await;
     assert!(
         result.is_err(),
         "Expected failure for missing Package in known/unknown"
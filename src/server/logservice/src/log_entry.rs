@@ -12,4 +12,7 @@ pub struct LogEvent {
     pub level: String,
     pub tag: String,
     pub message: String,
+    /// Trace of the request the line was logged for, if any
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub trace_id: String,
 }
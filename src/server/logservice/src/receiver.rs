@@ -76,6 +76,7 @@ pub async fn run(
             level: level.to_string(),
             tag: env.tag.clone(),
             message: env.message.clone(),
+            trace_id: env.trace_id.clone(),
         };
 
         {
@@ -86,10 +87,17 @@ pub async fn run(
             }
         }
 
-        println!(
-            "{:<24} │ {:<2} │ {:<30} │ {}",
-            entry.timestamp, entry.level, entry.tag, entry.message
-        );
+        if entry.trace_id.is_empty() {
+            println!(
+                "{:<24} │ {:<2} │ {:<30} │ {}",
+                entry.timestamp, entry.level, entry.tag, entry.message
+            );
+        } else {
+            println!(
+                "{:<24} │ {:<2} │ {:<30} │ [{}] {}",
+                entry.timestamp, entry.level, entry.tag, entry.trace_id, entry.message
+            );
+        }
 
         let _ = log_tx.send(entry);
     }
@@ -10,7 +10,7 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use axum::{
-    extract::State,
+    extract::{Query, State},
     response::{
         sse::{Event, KeepAlive, Sse},
         Html,
@@ -18,6 +18,7 @@ use axum::{
     routing::get,
     Router,
 };
+use serde::Deserialize;
 use tokio::net::TcpListener;
 use tokio::sync::{broadcast, Mutex};
 use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
@@ -31,6 +32,20 @@ pub struct WebState {
     pub log_history: Arc<Mutex<VecDeque<LogEvent>>>,
 }
 
+/// Query of `/logs`, `?trace_id=` streams only the lines of one trace.
+#[derive(Debug, Default, Deserialize)]
+pub struct LogFilter {
+    pub trace_id: Option<String>,
+}
+
+impl LogFilter {
+    fn matches(&self, entry: &LogEvent) -> bool {
+        self.trace_id
+            .as_ref()
+            .is_none_or(|trace_id| entry.trace_id == *trace_id)
+    }
+}
+
 /// Default address (`0.0.0.0:47097`) for the built-in log viewer.
 pub fn default_http_addr() -> SocketAddr {
     //SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 47097)
@@ -263,11 +278,13 @@ async fn serve_index() -> Html<&'static str> {
 
 async fn stream_logs(
     State(state): State<WebState>,
+    Query(filter): Query<LogFilter>,
 ) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
     let history_events = {
         let history = state.log_history.lock().await;
         history
             .iter()
+            .filter(|entry| filter.matches(entry))
             .filter_map(|entry| match Event::default().json_data(entry) {
                 Ok(event) => Some(Ok(event)),
                 Err(err) => {
@@ -280,16 +297,18 @@ async fn stream_logs(
 
     let history_stream = tokio_stream::iter(history_events);
 
-    let live_stream = BroadcastStream::new(state.log_tx.subscribe()).filter_map(|msg| match msg {
-        Ok(entry) => match Event::default().json_data(&entry) {
-            Ok(event) => Some(Ok(event)),
-            Err(err) => {
-                eprintln!("[aggregator] failed to encode log for SSE: {err}");
-                None
-            }
-        },
-        Err(_) => None,
-    });
+    let live_stream =
+        BroadcastStream::new(state.log_tx.subscribe()).filter_map(move |msg| match msg {
+            Ok(entry) if !filter.matches(&entry) => None,
+            Ok(entry) => match Event::default().json_data(&entry) {
+                Ok(event) => Some(Ok(event)),
+                Err(err) => {
+                    eprintln!("[aggregator] failed to encode log for SSE: {err}");
+                    None
+                }
+            },
+            Err(_) => None,
+        });
 
     let stream = history_stream.chain(live_stream);
 
@@ -0,0 +1,272 @@
+/*
+ * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Alert rule evaluation
+//!
+//! Rules are managed by SettingsService in etcd and evaluated against every
+//! node report and the containers of that node. An alert fires once when a
+//! rule is violated and is resolved when the value is back within the
+//! threshold, the container is gone or the rule was removed. Firing alerts
+//! are kept in etcd and every change is published to gRPC subscribers and
+//! to the event bus.
+
+use common::alert::{self, AlertRule, AlertTargetKind};
+use common::eventbus::{Event, EventKind};
+use common::monitoringserver::{Alert, ContainerInfo, NodeInfo};
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+
+/// Alerts buffered per subscriber before it starts lagging
+const ALERT_CHANNEL_CAPACITY: usize = 64;
+
+/// Channel of raised and resolved alerts
+pub fn publisher() -> &'static broadcast::Sender<Alert> {
+    static PUBLISHER: OnceLock<broadcast::Sender<Alert>> = OnceLock::new();
+    PUBLISHER.get_or_init(|| broadcast::channel(ALERT_CHANNEL_CAPACITY).0)
+}
+
+/// Firing alerts and the node they were found on
+#[derive(Debug, Default)]
+pub struct AlertEngine {
+    firing: HashMap<String, (String, Alert)>,
+}
+
+impl AlertEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Evaluate the rules against one node report
+    ///
+    /// ### Parameters
+    /// * `rules: &[AlertRule]` - every stored rule
+    /// * `node: &NodeInfo` - reported node
+    /// * `containers: &[&ContainerInfo]` - containers running on the node
+    /// * `now: i64` - unix seconds stored in raised alerts
+    /// ### Returns
+    /// * `Vec<Alert>` - alerts raised or resolved by this report
+    pub fn evaluate(
+        &mut self,
+        rules: &[AlertRule],
+        node: &NodeInfo,
+        containers: &[&ContainerInfo],
+        now: i64,
+    ) -> Vec<Alert> {
+        let mut changes = Vec::new();
+        let mut violated = HashSet::new();
+
+        for rule in rules {
+            if rule.targets(AlertTargetKind::Node, &[node.node_name.as_str()]) {
+                let value = alert::node_metric(node, &rule.metric);
+                if let Some(value) = value.filter(|value| rule.is_violated(*value)) {
+                    let (key, raised) =
+                        self.violate(rule, &node.node_name, &node.node_name, value, now);
+                    violated.insert(key);
+                    changes.extend(raised);
+                }
+            }
+            for container in containers {
+                let name = container.names.first().unwrap_or(&container.id);
+                if !rule.targets(AlertTargetKind::Container, &[name, &container.id]) {
+                    continue;
+                }
+                let value = alert::container_metric(container, &rule.metric);
+                if let Some(value) = value.filter(|value| rule.is_violated(*value)) {
+                    let (key, raised) = self.violate(rule, &node.node_name, name, value, now);
+                    violated.insert(key);
+                    changes.extend(raised);
+                }
+            }
+        }
+
+        let resolved: Vec<String> = self
+            .firing
+            .iter()
+            .filter(|(key, (node_name, _))| {
+                *node_name == node.node_name && !violated.contains(*key)
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in resolved {
+            if let Some((_, mut alert)) = self.firing.remove(&key) {
+                alert.resolved = true;
+                changes.push(alert);
+            }
+        }
+        changes
+    }
+
+    /// Record a violation, returns its key and the alert if it starts firing
+    fn violate(
+        &mut self,
+        rule: &AlertRule,
+        node_name: &str,
+        target_name: &str,
+        value: f64,
+        now: i64,
+    ) -> (String, Option<Alert>) {
+        let key = alert::alert_key(&rule.id, target_name);
+        if self.firing.contains_key(&key) {
+            return (key, None);
+        }
+        let alert = rule.alert(target_name, value, now);
+        self.firing
+            .insert(key.clone(), (node_name.to_string(), alert.clone()));
+        (key, Some(alert))
+    }
+}
+
+/// Read the alert rules, `None` if etcd is unavailable
+pub async fn load_rules() -> Option<Vec<AlertRule>> {
+    let kvs = match common::etcd::get_all_with_prefix(alert::ALERT_RULE_PREFIX).await {
+        Ok(kvs) => kvs,
+        Err(e) => {
+            eprintln!("[MonitoringServer] Failed to load alert rules: {}", e);
+            return None;
+        }
+    };
+    let rules = kvs
+        .into_iter()
+        .filter_map(
+            |(key, value)| match serde_json::from_str::<AlertRule>(&value) {
+                Ok(rule) => Some(rule),
+                Err(e) => {
+                    eprintln!("[MonitoringServer] Invalid alert rule {}: {}", key, e);
+                    None
+                }
+            },
+        )
+        .collect();
+    Some(rules)
+}
+
+/// Event bus notification of a raised or resolved alert
+fn alert_event(alert: &Alert) -> Event {
+    let kind = if alert.resolved {
+        EventKind::AlertResolved
+    } else {
+        EventKind::AlertRaised
+    };
+    Event::new(kind, "monitoringserver", &alert.target_name, &alert.message)
+        .with_attribute("rule_id", &alert.rule_id)
+        .with_attribute("severity", &alert.severity)
+        .with_attribute("target_kind", &alert.target_kind)
+}
+
+/// Store or remove the changed alerts in etcd and notify subscribers
+pub async fn publish(changes: Vec<Alert>) {
+    for alert in changes {
+        let key = alert::alert_key(&alert.rule_id, &alert.target_name);
+        let stored = if alert.resolved {
+            println!("[MonitoringServer] RESOLVED: {}", alert.message);
+            common::etcd::delete(&key).await.map_err(String::from)
+        } else {
+            println!("[MonitoringServer] ALERT: {}", alert.message);
+            match serde_json::to_string(&alert) {
+                Ok(json) => common::etcd::put(&key, &json).await.map_err(String::from),
+                Err(e) => Err(e.to_string()),
+            }
+        };
+        if let Err(e) = stored {
+            eprintln!("[MonitoringServer] Failed to store alert {}: {}", key, e);
+        }
+        common::eventbus::publish(alert_event(&alert));
+        // no subscriber is not an error
+        let _ = publisher().send(alert);
+    }
+}
+
+/// Alerts firing at the moment, as stored in etcd
+pub async fn firing_alerts() -> Vec<Alert> {
+    common::etcd::get_all_with_prefix(alert::ALERT_PREFIX)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(_, value)| serde_json::from_str(&value).ok())
+        .collect()
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(json: &str) -> AlertRule {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn node(cpu_usage: f64) -> NodeInfo {
+        NodeInfo {
+            node_name: "HPC".to_string(),
+            cpu_usage,
+            ..Default::default()
+        }
+    }
+
+    fn container(name: &str, memory_usage: u64) -> ContainerInfo {
+        let mut container = ContainerInfo {
+            id: format!("{}-id", name),
+            names: vec![name.to_string()],
+            ..Default::default()
+        };
+        container
+            .stats
+            .insert("MemoryUsage".to_string(), memory_usage.to_string());
+        container
+    }
+
+    #[test]
+    fn test_alert_fires_once_and_resolves() {
+        let rules = vec![rule(
+            r#"{"id": "cpu", "metric": "cpu_usage", "operator": "gt", "threshold": 80,
+                "severity": "critical", "target": {"kind": "node"}}"#,
+        )];
+        let mut engine = AlertEngine::new();
+
+        let raised = engine.evaluate(&rules, &node(95.0), &[], 1);
+        assert_eq!(raised.len(), 1);
+        assert_eq!(raised[0].severity, "critical");
+        assert!(!raised[0].resolved);
+
+        // still violated, nothing new
+        assert!(engine.evaluate(&rules, &node(90.0), &[], 2).is_empty());
+
+        let resolved = engine.evaluate(&rules, &node(10.0), &[], 3);
+        assert_eq!(resolved.len(), 1);
+        assert!(resolved[0].resolved);
+        assert_eq!(resolved[0].fired_at, 1);
+
+        let raised = alert_event(&raised[0]);
+        assert_eq!(raised.kind(), EventKind::AlertRaised);
+        assert_eq!(raised.resource_name, "HPC");
+        assert_eq!(raised.attributes["severity"], "critical");
+        assert_eq!(alert_event(&resolved[0]).kind(), EventKind::AlertResolved);
+    }
+
+    #[test]
+    fn test_container_alerts_resolve_when_container_or_rule_is_gone() {
+        let rules = vec![rule(
+            r#"{"id": "mem", "metric": "memory_usage", "operator": "ge", "threshold": 100,
+                "target": {"kind": "container", "name": "web"}}"#,
+        )];
+        let web = container("web", 512);
+        let db = container("db", 512);
+        let mut engine = AlertEngine::new();
+
+        let raised = engine.evaluate(&rules, &node(0.0), &[&web, &db], 1);
+        assert_eq!(raised.len(), 1);
+        assert_eq!(raised[0].target_name, "web");
+
+        let resolved = engine.evaluate(&rules, &node(0.0), &[&db], 2);
+        assert_eq!(resolved.len(), 1);
+        assert!(resolved[0].resolved);
+
+        engine.evaluate(&rules, &node(0.0), &[&web], 3);
+        let resolved = engine.evaluate(&[], &node(0.0), &[&web], 4);
+        assert_eq!(resolved.len(), 1);
+        assert!(resolved[0].resolved);
+    }
+}
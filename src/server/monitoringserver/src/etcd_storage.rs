@@ -6,7 +6,7 @@
 //! Store and retrieve monitoring data in etcd
 
 use crate::data_structures::{BoardInfo, SocInfo};
-use common::monitoringserver::{ContainerInfo, NodeInfo}; // Use protobuf types
+use common::monitoringserver::{ContainerInfo, NodeInfo, NodeMetrics}; // Use protobuf types
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json::Value;
 
@@ -79,6 +79,11 @@ pub async fn store_node_info(node_info: &NodeInfo) -> common::Result<()> {
     store_info("nodes", &node_info.node_name, node_info).await
 }
 
+/// Store the latest plugin metrics of a node in etcd
+pub async fn store_node_metrics(node_metrics: &NodeMetrics) -> common::Result<()> {
+    store_info("node_metrics", &node_metrics.node_name, node_metrics).await
+}
+
 /// Store SocInfo in etcd
 pub async fn store_soc_info(soc_info: &SocInfo) -> common::Result<()> {
     store_info("socs", &soc_info.soc_id, soc_info).await
@@ -100,6 +105,7 @@ pub async fn store_container_info(container_info: &ContainerInfo) -> common::Res
         "config": container_info.config,
         "annotation": container_info.annotation,
         "stats": container_info.stats,
+        "usage": container_info.usage,
     });
 
     store_info("containers", &container_info.id, &json_value).await
@@ -110,6 +116,11 @@ pub async fn get_node_info(node_name: &str) -> common::Result<NodeInfo> {
     get_info("nodes", node_name).await
 }
 
+/// Retrieve the latest plugin metrics of a node from etcd
+pub async fn get_node_metrics(node_name: &str) -> common::Result<NodeMetrics> {
+    get_info("node_metrics", node_name).await
+}
+
 /// Retrieve SocInfo from etcd
 pub async fn get_soc_info(soc_id: &str) -> common::Result<SocInfo> {
     get_info("socs", soc_id).await
@@ -158,6 +169,7 @@ pub async fn get_container_info(container_id: &str) -> common::Result<ContainerI
             .iter()
             .map(|(k, v)| (k.clone(), v.as_str().unwrap_or_default().to_string()))
             .collect(),
+        usage: serde_json::from_value(json_value["usage"].clone()).unwrap_or_default(),
     };
 
     Ok(container_info)
@@ -220,6 +232,7 @@ pub async fn get_all_containers() -> common::Result<Vec<ContainerInfo>> {
                         .iter()
                         .map(|(k, v)| (k.clone(), v.as_str().unwrap_or_default().to_string()))
                         .collect(),
+                    usage: serde_json::from_value(json_value["usage"].clone()).unwrap_or_default(),
                 };
                 containers.push(container_info);
             }
@@ -2,13 +2,18 @@
 * SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
 * SPDX-License-Identifier: Apache-2.0
 */
+use common::alert::AlertSeverity;
+use common::container_events::relay_container_events;
 use common::monitoringserver::monitoring_server_connection_server::MonitoringServerConnection;
 use common::monitoringserver::{
-    ContainerList, NodeInfo, SendContainerListResponse, SendNodeInfoResponse,
-    StressMonitoringMetric, StressMonitoringMetricResponse,
+    Alert, AlertSubscription, ContainerEventAck, ContainerEventList, ContainerList,
+    ContainerLogBatch, GetContainerLogsRequest, GetContainerLogsResponse, NodeInfo, NodeMetrics,
+    SendContainerListResponse, SendNodeInfoResponse, SendNodeMetricsResponse,
+    StreamContainerLogsResponse, StressMonitoringMetric, StressMonitoringMetricResponse,
 };
-use tokio::sync::mpsc;
-use tonic::{Request, Response, Status};
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status, Streaming};
 
 use serde::Deserialize;
 use serde_json;
@@ -68,6 +73,75 @@ pub fn parse_stress_metric_json(
     serde_json::from_str(s)
 }
 
+/// Forward alerts of at least `min_severity` to one subscriber
+///
+/// The alerts firing when the subscription starts are sent first, then every
+/// raised and resolved alert. A subscriber that lags behind skips the missed alerts.
+pub async fn relay_alerts(
+    firing: Vec<Alert>,
+    mut alerts: broadcast::Receiver<Alert>,
+    min_severity: AlertSeverity,
+    tx: mpsc::Sender<Result<Alert, Status>>,
+) {
+    let wanted = |alert: &Alert| {
+        AlertSeverity::parse(&alert.severity).is_some_and(|severity| severity >= min_severity)
+    };
+
+    for alert in firing.into_iter().filter(|alert| wanted(alert)) {
+        if tx.send(Ok(alert)).await.is_err() {
+            return;
+        }
+    }
+    loop {
+        match alerts.recv().await {
+            Ok(alert) if wanted(&alert) => {
+                if tx.send(Ok(alert)).await.is_err() {
+                    break;
+                }
+            }
+            Ok(_) => {}
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                eprintln!(
+                    "[MonitoringServer] alert subscriber lagged, {} alerts skipped",
+                    skipped
+                );
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Whether a name can be used as Prometheus metric or label name
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Check the metrics of the collector plugins of a node
+///
+/// Names and label keys must be valid Prometheus names and values finite.
+pub fn validate_node_metrics(node_metrics: &NodeMetrics) -> Result<(), String> {
+    if node_metrics.node_name.is_empty() {
+        return Err("node_name is empty".to_string());
+    }
+    for metric in &node_metrics.metrics {
+        if !is_valid_name(&metric.name) {
+            return Err(format!("invalid metric name '{}'", metric.name));
+        }
+        if !metric.value.is_finite() {
+            return Err(format!("value of metric '{}' is not finite", metric.name));
+        }
+        if let Some(key) = metric.labels.keys().find(|key| !is_valid_name(key)) {
+            return Err(format!(
+                "invalid label '{}' of metric '{}'",
+                key, metric.name
+            ));
+        }
+    }
+    Ok(())
+}
+
 /// MonitoringServer gRPC service handler
 #[derive(Clone)]
 pub struct MonitoringServerReceiver {
@@ -78,6 +152,9 @@ pub struct MonitoringServerReceiver {
 
 #[tonic::async_trait]
 impl MonitoringServerConnection for MonitoringServerReceiver {
+    type StreamContainerEventsStream = ReceiverStream<Result<ContainerEventAck, Status>>;
+    type SubscribeAlertsStream = ReceiverStream<Result<Alert, Status>>;
+
     /// Handle a ContainerList message from nodeagent
     ///
     /// Receives a ContainerList from nodeagent and forwards it to the MonitoringServer manager for processing.
@@ -140,6 +217,106 @@ impl MonitoringServerConnection for MonitoringServerReceiver {
             )),
         }
     }
+
+    /// Keep the lines streamed by the log collector of a nodeagent
+    ///
+    /// The response counts the received lines once the nodeagent closes the
+    /// stream.
+    async fn stream_container_logs<'life>(
+        &'life self,
+        request: Request<Streaming<ContainerLogBatch>>,
+    ) -> Result<Response<StreamContainerLogsResponse>, Status> {
+        let mut inbound = request.into_inner();
+        let mut received = 0;
+        while let Some(batch) = inbound.message().await? {
+            received += crate::logs::record(&batch) as u64;
+        }
+        Ok(Response::new(StreamContainerLogsResponse { received }))
+    }
+
+    /// Return the last kept lines of a container
+    async fn get_container_logs<'life>(
+        &'life self,
+        request: Request<GetContainerLogsRequest>,
+    ) -> Result<Response<GetContainerLogsResponse>, Status> {
+        let req = request.into_inner();
+        if req.container_id.is_empty() {
+            return Err(Status::invalid_argument("container_id must not be empty"));
+        }
+        match crate::logs::tail(&req.node_name, &req.container_id, req.tail as usize) {
+            Ok((node_name, lines)) => {
+                Ok(Response::new(GetContainerLogsResponse { node_name, lines }))
+            }
+            Err(e @ crate::logs::LookupError::NotFound(_)) => Err(Status::not_found(e.to_string())),
+            Err(e) => Err(Status::invalid_argument(e.to_string())),
+        }
+    }
+
+    /// Handle the metrics of the collector plugins of a node
+    ///
+    /// The metrics are exported on the metrics endpoint, kept in the
+    /// inventory and the latest ones of each node are stored in etcd.
+    async fn send_node_metrics<'life>(
+        &'life self,
+        request: Request<NodeMetrics>,
+    ) -> Result<Response<SendNodeMetricsResponse>, Status> {
+        let req: NodeMetrics = request.into_inner();
+        validate_node_metrics(&req)
+            .map_err(|e| Status::invalid_argument(format!("invalid node metrics: {}", e)))?;
+
+        crate::metrics::record_node_metrics(&req);
+        crate::inventory::record_node_metrics(&req);
+        if let Err(e) = crate::etcd_storage::store_node_metrics(&req).await {
+            return Err(Status::unavailable(format!(
+                "cannot store node metrics: {}",
+                e
+            )));
+        }
+        Ok(Response::new(SendNodeMetricsResponse {
+            resp: "Successfully processed NodeMetrics".to_string(),
+        }))
+    }
+
+    /// Handle a stream of container events from nodeagent
+    ///
+    /// Events are applied to a per-stream ContainerList which is forwarded to the
+    /// MonitoringServer manager after every batch, so the manager keeps seeing full lists.
+    async fn stream_container_events<'life>(
+        &'life self,
+        request: Request<Streaming<ContainerEventList>>,
+    ) -> Result<Response<Self::StreamContainerEventsStream>, Status> {
+        let inbound = request.into_inner();
+        let (tx_ack, rx_ack) = mpsc::channel(16);
+        let tx_container = self.tx_container.clone();
+        tokio::spawn(async move {
+            relay_container_events(inbound, |list| tx_container.send(list), tx_ack).await
+        });
+        Ok(Response::new(ReceiverStream::new(rx_ack)))
+    }
+
+    /// Stream alerts raised and resolved by the alert rules
+    ///
+    /// An empty `min_severity` subscribes to every alert.
+    async fn subscribe_alerts<'life>(
+        &'life self,
+        request: Request<AlertSubscription>,
+    ) -> Result<Response<Self::SubscribeAlertsStream>, Status> {
+        let req = request.into_inner();
+        let min_severity = if req.min_severity.is_empty() {
+            AlertSeverity::Info
+        } else {
+            AlertSeverity::parse(&req.min_severity).ok_or_else(|| {
+                Status::invalid_argument(format!("invalid severity '{}'", req.min_severity))
+            })?
+        };
+
+        // subscribe before reading etcd so no alert falls in between
+        let alerts = crate::alerts::publisher().subscribe();
+        let firing = crate::alerts::firing_alerts().await;
+        let (tx, rx) = mpsc::channel(16);
+        tokio::spawn(relay_alerts(firing, alerts, min_severity, tx));
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
 }
 
 #[cfg(test)]
@@ -289,6 +466,132 @@ mod tests {
         assert!(received.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_send_node_metrics_rejects_invalid_metrics() {
+        use common::monitoringserver::NodeMetric;
+
+        let receiver = MonitoringServerReceiver {
+            tx_container: mpsc::channel(1).0,
+            tx_node: mpsc::channel(1).0,
+            tx_stress: mpsc::channel(1).0,
+        };
+        let metric = NodeMetric {
+            name: "gpu_temperature".to_string(),
+            value: 61.5,
+            unit: "celsius".to_string(),
+            labels: [("gpu".to_string(), "0".to_string())].into(),
+            collector: "gpu-temp".to_string(),
+        };
+        let metrics = NodeMetrics {
+            node_name: "node1".to_string(),
+            metrics: vec![metric.clone()],
+            timestamp: 0,
+        };
+        assert!(validate_node_metrics(&metrics).is_ok());
+
+        let invalid = [
+            NodeMetrics {
+                node_name: String::new(),
+                ..metrics.clone()
+            },
+            NodeMetrics {
+                metrics: vec![NodeMetric {
+                    name: "gpu-temperature".to_string(),
+                    ..metric.clone()
+                }],
+                ..metrics.clone()
+            },
+            NodeMetrics {
+                metrics: vec![NodeMetric {
+                    value: f64::NAN,
+                    ..metric.clone()
+                }],
+                ..metrics.clone()
+            },
+            NodeMetrics {
+                metrics: vec![NodeMetric {
+                    labels: [("0gpu".to_string(), "0".to_string())].into(),
+                    ..metric.clone()
+                }],
+                ..metrics.clone()
+            },
+        ];
+        for metrics in invalid {
+            let status = receiver
+                .send_node_metrics(Request::new(metrics))
+                .await
+                .unwrap_err();
+            assert_eq!(status.code(), Code::InvalidArgument);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_container_logs_of_unknown_container() {
+        let (tx_container, _rx_container) = mpsc::channel(1);
+        let (tx_node, _rx_node) = mpsc::channel(1);
+        let (tx_stress, _rx_stress) = mpsc::channel(1);
+        let receiver = MonitoringServerReceiver {
+            tx_container,
+            tx_node,
+            tx_stress,
+        };
+
+        let request = |container_id: &str| {
+            Request::new(GetContainerLogsRequest {
+                container_id: container_id.to_string(),
+                ..Default::default()
+            })
+        };
+        let status = receiver.get_container_logs(request("")).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        let status = receiver
+            .get_container_logs(request("never-logged"))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_relay_alerts_filters_by_severity() {
+        let alert = |rule_id: &str, severity: &str| Alert {
+            rule_id: rule_id.to_string(),
+            severity: severity.to_string(),
+            ..Default::default()
+        };
+        let (tx_alert, rx_alert) = broadcast::channel(4);
+        let (tx, mut rx) = mpsc::channel(4);
+        let firing = vec![alert("old-info", "info"), alert("old-critical", "critical")];
+
+        tx_alert.send(alert("new-info", "info")).unwrap();
+        tx_alert.send(alert("new-warning", "warning")).unwrap();
+        drop(tx_alert);
+        relay_alerts(firing, rx_alert, AlertSeverity::Warning, tx).await;
+
+        assert_eq!(rx.recv().await.unwrap().unwrap().rule_id, "old-critical");
+        assert_eq!(rx.recv().await.unwrap().unwrap().rule_id, "new-warning");
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_alerts_rejects_invalid_severity() {
+        let (tx_container, _rx_container) = mpsc::channel(1);
+        let (tx_node, _rx_node) = mpsc::channel(1);
+        let (tx_stress, _rx_stress) = mpsc::channel(1);
+        let receiver = MonitoringServerReceiver {
+            tx_container,
+            tx_node,
+            tx_stress,
+        };
+
+        let status = receiver
+            .subscribe_alerts(Request::new(AlertSubscription {
+                min_severity: "fatal".to_string(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
     #[tokio::test]
     async fn test_send_stress_metric_roundtrip() {
         use crate::etcd_storage;
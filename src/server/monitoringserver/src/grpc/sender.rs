@@ -10,6 +10,7 @@ use common::policymanager::policy_manager_connection_client::PolicyManagerConnec
 use common::policymanager::{
     connect_server, ReportNodeMetricsRequest, ReportNodeMetricsResponse, RunningContainer,
 };
+use common::rpc::RpcClient;
 use tonic::{Request, Response, Status};
 
 /// Send node metrics to PolicyManager for threshold-based policy evaluation
@@ -17,30 +18,25 @@ pub async fn report_node_metrics(
     node_info: NodeInfo,
     running_containers: Vec<RunningContainer>,
 ) -> Result<Response<ReportNodeMetricsResponse>, Status> {
-    let addr = connect_server();
-
-    let client = PolicyManagerConnectionClient::connect(addr).await;
-
-    match client {
-        Ok(mut client) => {
-            let request = ReportNodeMetricsRequest {
-                node_info: Some(node_info),
-                running_containers,
-            };
-            client.report_node_metrics(Request::new(request)).await
-        }
-        Err(e) => {
-            // Log but don't fail - PolicyManager might not be running
-            eprintln!(
-                "[MonitoringServer] Failed to connect to PolicyManager: {}",
-                e
-            );
-            Err(Status::unavailable(format!(
-                "Failed to connect to PolicyManager: {}",
-                e
-            )))
-        }
+    let request = &ReportNodeMetricsRequest {
+        node_info: Some(node_info),
+        running_containers,
+    };
+    let result = RpcClient::new("PolicyManager", connect_server())
+        .call(|channel| async move {
+            PolicyManagerConnectionClient::new(channel)
+                .report_node_metrics(Request::new(request.clone()))
+                .await
+        })
+        .await;
+    if let Err(e) = &result {
+        // Log but don't fail - PolicyManager might not be running
+        eprintln!(
+            "[MonitoringServer] Reporting metrics to PolicyManager failed: {}",
+            e.message()
+        );
     }
+    result
 }
 
 #[cfg(test)]
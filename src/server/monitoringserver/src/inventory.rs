@@ -0,0 +1,437 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Latest node and container inventory
+//!
+//! The last NodeInfo, container list and collector plugin metrics reported
+//! by each node are kept in memory, so the Web GUI can read the current
+//! inventory without direct etcd access. Nodes stay listed after they stop
+//! reporting; `last_report_ms` tells how old their data is.
+//!
+//! REST API, served next to `/metrics`:
+//! * `GET /api/inventory/nodes` - known nodes with their latest usage and
+//!   container counts
+//! * `GET /api/inventory/nodes/:node` - latest NodeInfo and containers of
+//!   one node
+//! * `GET /api/inventory/nodes/:node/containers` - containers of one node
+//! * `GET /api/inventory/containers?node=` - containers of every node, or of
+//!   one
+//! * `GET /api/inventory/stats?node=` - latest usage, container stats and
+//!   plugin metrics of every node, or of one
+
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use common::monitoringserver::{
+    ContainerInfo, ContainerList, ContainerUsage, NodeInfo, NodeMetric, NodeMetrics,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Latest reports of one node, with their Unix time in milliseconds
+#[derive(Debug, Default, Clone)]
+struct NodeEntry {
+    info: Option<NodeInfo>,
+    info_ms: Option<i64>,
+    containers: Vec<ContainerInfo>,
+    containers_ms: Option<i64>,
+    metrics: Vec<NodeMetric>,
+    metrics_ms: Option<i64>,
+}
+
+impl NodeEntry {
+    fn last_report_ms(&self) -> Option<i64> {
+        [self.info_ms, self.containers_ms, self.metrics_ms]
+            .into_iter()
+            .flatten()
+            .max()
+    }
+
+    fn running(&self) -> usize {
+        self.containers.iter().filter(|c| is_running(c)).count()
+    }
+}
+
+fn is_running(container: &ContainerInfo) -> bool {
+    container.state.get("Running").is_some_and(|r| r == "true")
+}
+
+/// Node of `GET /api/inventory/nodes`
+#[derive(Debug, Serialize, PartialEq)]
+pub struct NodeSummary {
+    pub node_name: String,
+    pub ip: String,
+    pub os: String,
+    pub arch: String,
+    pub cpu_usage: f64,
+    pub mem_usage: f64,
+    pub containers: usize,
+    pub running_containers: usize,
+    pub last_report_ms: Option<i64>,
+}
+
+/// Node of `GET /api/inventory/nodes/:node`
+#[derive(Debug, Serialize)]
+pub struct NodeDetail {
+    pub node_name: String,
+    pub info: Option<NodeInfo>,
+    pub containers: Vec<ContainerInfo>,
+    pub last_report_ms: Option<i64>,
+}
+
+/// Container with the node that reported it
+#[derive(Debug, Serialize)]
+pub struct NodeContainer {
+    pub node_name: String,
+    #[serde(flatten)]
+    pub container: ContainerInfo,
+}
+
+/// Latest stats of one node
+#[derive(Debug, Serialize)]
+pub struct StatsSnapshot {
+    pub node_name: String,
+    pub node: Option<NodeInfo>,
+    pub node_ms: Option<i64>,
+    /// Stats of each container, by container id
+    pub containers: BTreeMap<String, HashMap<String, String>>,
+    /// Sampled usage of each running container, by container id
+    pub usage: BTreeMap<String, ContainerUsage>,
+    pub containers_ms: Option<i64>,
+    pub metrics: Vec<NodeMetric>,
+    pub metrics_ms: Option<i64>,
+}
+
+/// Latest reports of every node, by node name
+#[derive(Debug, Default)]
+pub struct Inventory {
+    nodes: BTreeMap<String, NodeEntry>,
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update_node_info(&mut self, node_info: &NodeInfo, now_ms: i64) {
+        let entry = self.nodes.entry(node_info.node_name.clone()).or_default();
+        entry.info = Some(node_info.clone());
+        entry.info_ms = Some(now_ms);
+    }
+
+    /// Replace the containers of the node that sent the list
+    pub fn update_containers(&mut self, container_list: &ContainerList, now_ms: i64) {
+        let entry = self
+            .nodes
+            .entry(container_list.node_name.clone())
+            .or_default();
+        entry.containers = container_list.containers.clone();
+        entry.containers_ms = Some(now_ms);
+    }
+
+    pub fn update_metrics(&mut self, node_metrics: &NodeMetrics, now_ms: i64) {
+        let entry = self
+            .nodes
+            .entry(node_metrics.node_name.clone())
+            .or_default();
+        entry.metrics = node_metrics.metrics.clone();
+        entry.metrics_ms = Some(now_ms);
+    }
+
+    /// Known nodes, sorted by name
+    pub fn nodes(&self) -> Vec<NodeSummary> {
+        self.nodes
+            .iter()
+            .map(|(name, entry)| {
+                let info = entry.info.clone().unwrap_or_default();
+                NodeSummary {
+                    node_name: name.clone(),
+                    ip: info.ip,
+                    os: info.os,
+                    arch: info.arch,
+                    cpu_usage: info.cpu_usage,
+                    mem_usage: info.mem_usage,
+                    containers: entry.containers.len(),
+                    running_containers: entry.running(),
+                    last_report_ms: entry.last_report_ms(),
+                }
+            })
+            .collect()
+    }
+
+    pub fn node(&self, node_name: &str) -> Option<NodeDetail> {
+        self.nodes.get(node_name).map(|entry| NodeDetail {
+            node_name: node_name.to_string(),
+            info: entry.info.clone(),
+            containers: entry.containers.clone(),
+            last_report_ms: entry.last_report_ms(),
+        })
+    }
+
+    /// Containers of every node, or of `node` only
+    pub fn containers(&self, node: Option<&str>) -> Vec<NodeContainer> {
+        self.entries(node)
+            .flat_map(|(name, entry)| {
+                entry.containers.iter().map(|container| NodeContainer {
+                    node_name: name.clone(),
+                    container: container.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Latest stats of every node, or of `node` only
+    pub fn stats(&self, node: Option<&str>) -> Vec<StatsSnapshot> {
+        self.entries(node)
+            .map(|(name, entry)| StatsSnapshot {
+                node_name: name.clone(),
+                node: entry.info.clone(),
+                node_ms: entry.info_ms,
+                containers: entry
+                    .containers
+                    .iter()
+                    .map(|c| (c.id.clone(), c.stats.clone()))
+                    .collect(),
+                usage: entry
+                    .containers
+                    .iter()
+                    .filter_map(|c| Some((c.id.clone(), c.usage?)))
+                    .collect(),
+                containers_ms: entry.containers_ms,
+                metrics: entry.metrics.clone(),
+                metrics_ms: entry.metrics_ms,
+            })
+            .collect()
+    }
+
+    fn entries<'a>(
+        &'a self,
+        node: Option<&'a str>,
+    ) -> impl Iterator<Item = (&'a String, &'a NodeEntry)> + 'a {
+        self.nodes
+            .iter()
+            .filter(move |(name, _)| node.is_none_or(|node| node == name.as_str()))
+    }
+}
+
+fn inventory() -> MutexGuard<'static, Inventory> {
+    static INVENTORY: OnceLock<Mutex<Inventory>> = OnceLock::new();
+    INVENTORY
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+/// Keep the NodeInfo as the latest one of its node
+pub fn record_node_info(node_info: &NodeInfo) {
+    inventory().update_node_info(node_info, now_ms());
+}
+
+/// Keep the containers as the current ones of their node
+pub fn record_container_list(container_list: &ContainerList) {
+    inventory().update_containers(container_list, now_ms());
+}
+
+/// Keep the plugin metrics as the latest ones of their node
+pub fn record_node_metrics(node_metrics: &NodeMetrics) {
+    inventory().update_metrics(node_metrics, now_ms());
+}
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/api/inventory/nodes", get(list_nodes))
+        .route("/api/inventory/nodes/:node", get(get_node))
+        .route(
+            "/api/inventory/nodes/:node/containers",
+            get(node_containers),
+        )
+        .route("/api/inventory/containers", get(list_containers))
+        .route("/api/inventory/stats", get(list_stats))
+}
+
+#[derive(Debug, Deserialize)]
+struct NodeQuery {
+    node: Option<String>,
+}
+
+async fn list_nodes() -> Json<Vec<NodeSummary>> {
+    Json(inventory().nodes())
+}
+
+async fn get_node(Path(node): Path<String>) -> Response {
+    match inventory().node(&node) {
+        Some(detail) => Json(detail).into_response(),
+        None => unknown_node(),
+    }
+}
+
+async fn node_containers(Path(node): Path<String>) -> Response {
+    match inventory().node(&node) {
+        Some(detail) => Json(detail.containers).into_response(),
+        None => unknown_node(),
+    }
+}
+
+async fn list_containers(Query(query): Query<NodeQuery>) -> Json<Vec<NodeContainer>> {
+    Json(inventory().containers(query.node.as_deref()))
+}
+
+async fn list_stats(Query(query): Query<NodeQuery>) -> Json<Vec<StatsSnapshot>> {
+    Json(inventory().stats(query.node.as_deref()))
+}
+
+fn unknown_node() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(serde_json::json!({"error": "unknown node"})),
+    )
+        .into_response()
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn container(id: &str, running: bool) -> ContainerInfo {
+        ContainerInfo {
+            id: id.to_string(),
+            state: HashMap::from([("Running".to_string(), running.to_string())]),
+            stats: HashMap::from([("CpuTotalUsage".to_string(), "10".to_string())]),
+            ..Default::default()
+        }
+    }
+
+    fn container_list(node_name: &str, containers: Vec<ContainerInfo>) -> ContainerList {
+        ContainerList {
+            node_name: node_name.to_string(),
+            containers,
+        }
+    }
+
+    #[test]
+    fn test_inventory_keeps_latest_reports() {
+        let mut inventory = Inventory::new();
+        inventory.update_node_info(
+            &NodeInfo {
+                node_name: "HPC".to_string(),
+                ip: "10.0.0.1".to_string(),
+                cpu_usage: 12.5,
+                ..Default::default()
+            },
+            1000,
+        );
+        inventory.update_containers(
+            &container_list("HPC", vec![container("c1", true), container("c2", false)]),
+            2000,
+        );
+        inventory.update_containers(&container_list("zone", vec![container("c3", true)]), 1500);
+
+        let nodes = inventory.nodes();
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(
+            nodes[0],
+            NodeSummary {
+                node_name: "HPC".to_string(),
+                ip: "10.0.0.1".to_string(),
+                os: String::new(),
+                arch: String::new(),
+                cpu_usage: 12.5,
+                mem_usage: 0.0,
+                containers: 2,
+                running_containers: 1,
+                last_report_ms: Some(2000),
+            }
+        );
+        assert!(nodes[1].ip.is_empty());
+
+        // A new list replaces the containers of its node only
+        inventory.update_containers(&container_list("HPC", vec![container("c2", true)]), 3000);
+        let containers = inventory.containers(None);
+        assert_eq!(containers.len(), 2);
+        assert_eq!(containers[0].container.id, "c2");
+        assert_eq!(containers[1].node_name, "zone");
+        assert_eq!(inventory.containers(Some("zone")).len(), 1);
+        assert!(inventory.node("unknown").is_none());
+
+        let stats = inventory.stats(Some("HPC"));
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].node_ms, Some(1000));
+        assert_eq!(stats[0].containers["c2"]["CpuTotalUsage"], "10");
+        assert!(stats[0].usage.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_inventory_endpoints() {
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        record_container_list(&container_list(
+            "inventory-node",
+            vec![container("c1", true)],
+        ));
+        record_node_metrics(&NodeMetrics {
+            node_name: "inventory-node".to_string(),
+            metrics: vec![NodeMetric {
+                name: "can_rx_errors".to_string(),
+                value: 3.0,
+                ..Default::default()
+            }],
+            timestamp: 0,
+        });
+
+        let response = router()
+            .oneshot(
+                Request::get("/api/inventory/nodes/inventory-node/containers")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body[0]["id"], "c1");
+
+        let response = router()
+            .oneshot(
+                Request::get("/api/inventory/stats?node=inventory-node")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body[0]["metrics"][0]["name"], "can_rx_errors");
+
+        let response = router()
+            .oneshot(
+                Request::get("/api/inventory/nodes/missing-node")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
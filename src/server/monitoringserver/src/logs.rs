@@ -0,0 +1,232 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Container logs followed by the NodeAgents
+//!
+//! NodeAgent streams the lines written by its containers with
+//! `StreamContainerLogs`. The last [`LINES_PER_CONTAINER`] lines of every
+//! container are kept in memory and served to ApiServer by
+//! `GetContainerLogs`. Beyond [`MAX_CONTAINERS`] containers, the lines of
+//! the container that logged least recently are dropped.
+
+use common::monitoringserver::{ContainerLogBatch, ContainerLogLine};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+/// Lines kept per container
+pub const LINES_PER_CONTAINER: usize = 1000;
+/// Containers whose lines are kept
+pub const MAX_CONTAINERS: usize = 512;
+
+/// Kept lines of one container
+#[derive(Debug)]
+struct ContainerLog {
+    container_name: String,
+    lines: VecDeque<ContainerLogLine>,
+    /// Order of the last append, to find the least recently logging container
+    last_append: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LookupError {
+    NotFound(String),
+    /// The container matched on several nodes, which are listed
+    Ambiguous(String, Vec<String>),
+}
+
+impl std::fmt::Display for LookupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(container) => write!(f, "No logs of container '{}'", container),
+            Self::Ambiguous(container, nodes) => write!(
+                f,
+                "Container '{}' has logs on nodes {}, name the node",
+                container,
+                nodes.join(", ")
+            ),
+        }
+    }
+}
+
+/// Last lines of the containers of every node
+#[derive(Debug)]
+pub struct LogStore {
+    /// Keyed by node name and container id
+    containers: HashMap<(String, String), ContainerLog>,
+    lines_per_container: usize,
+    max_containers: usize,
+    appends: u64,
+}
+
+impl LogStore {
+    pub fn new(lines_per_container: usize, max_containers: usize) -> Self {
+        Self {
+            containers: HashMap::new(),
+            lines_per_container: lines_per_container.max(1),
+            max_containers: max_containers.max(1),
+            appends: 0,
+        }
+    }
+
+    /// Keep the lines of a batch, returning the number of lines
+    pub fn append(&mut self, batch: &ContainerLogBatch) -> usize {
+        for line in &batch.lines {
+            self.appends += 1;
+            let key = (batch.node_name.clone(), line.container_id.clone());
+            if !self.containers.contains_key(&key) && self.containers.len() >= self.max_containers {
+                self.evict();
+            }
+            let log = self.containers.entry(key).or_insert_with(|| ContainerLog {
+                container_name: String::new(),
+                lines: VecDeque::new(),
+                last_append: 0,
+            });
+            if !line.container_name.is_empty() {
+                log.container_name = line.container_name.clone();
+            }
+            if log.lines.len() >= self.lines_per_container {
+                log.lines.pop_front();
+            }
+            log.lines.push_back(line.clone());
+            log.last_append = self.appends;
+        }
+        batch.lines.len()
+    }
+
+    /// Last `tail` lines of a container, all kept lines if 0
+    ///
+    /// The container is found by id, id prefix or name, on `node` or on any
+    /// node if it is empty. Returns the node of the container with the lines.
+    pub fn tail(
+        &self,
+        node: &str,
+        container: &str,
+        tail: usize,
+    ) -> Result<(String, Vec<ContainerLogLine>), LookupError> {
+        let matches: Vec<(&(String, String), &ContainerLog)> = self
+            .containers
+            .iter()
+            .filter(|((node_name, id), log)| {
+                (node.is_empty() || node_name == node)
+                    && !container.is_empty()
+                    && (id.starts_with(container) || log.container_name == container)
+            })
+            .collect();
+        let ((node_name, _), log) = match matches.as_slice() {
+            [] => return Err(LookupError::NotFound(container.to_string())),
+            [found] => *found,
+            found => {
+                let mut nodes: Vec<String> = found.iter().map(|((n, _), _)| n.clone()).collect();
+                nodes.sort();
+                nodes.dedup();
+                return Err(LookupError::Ambiguous(container.to_string(), nodes));
+            }
+        };
+        let skip = if tail == 0 {
+            0
+        } else {
+            log.lines.len().saturating_sub(tail)
+        };
+        Ok((
+            node_name.clone(),
+            log.lines.iter().skip(skip).cloned().collect(),
+        ))
+    }
+
+    fn evict(&mut self) {
+        if let Some(oldest) = self
+            .containers
+            .iter()
+            .min_by_key(|(_, log)| log.last_append)
+            .map(|(key, _)| key.clone())
+        {
+            self.containers.remove(&oldest);
+        }
+    }
+}
+
+fn store() -> MutexGuard<'static, LogStore> {
+    static STORE: OnceLock<Mutex<LogStore>> = OnceLock::new();
+    STORE
+        .get_or_init(|| Mutex::new(LogStore::new(LINES_PER_CONTAINER, MAX_CONTAINERS)))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// Keep the lines of a batch streamed by a NodeAgent
+pub fn record(batch: &ContainerLogBatch) -> usize {
+    store().append(batch)
+}
+
+/// Last lines of a container, see [`LogStore::tail`]
+pub fn tail(
+    node: &str,
+    container: &str,
+    tail: usize,
+) -> Result<(String, Vec<ContainerLogLine>), LookupError> {
+    store().tail(node, container, tail)
+}
+
+//UNIT TEST CASES
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(node: &str, id: &str, name: &str, lines: &[&str]) -> ContainerLogBatch {
+        ContainerLogBatch {
+            node_name: node.to_string(),
+            lines: lines
+                .iter()
+                .map(|line| ContainerLogLine {
+                    container_id: id.to_string(),
+                    container_name: name.to_string(),
+                    line: line.to_string(),
+                    timestamp_ns: 0,
+                })
+                .collect(),
+        }
+    }
+
+    fn text(lines: &[ContainerLogLine]) -> Vec<&str> {
+        lines.iter().map(|line| line.line.as_str()).collect()
+    }
+
+    #[test]
+    fn test_tail_keeps_last_lines() {
+        let mut store = LogStore::new(3, 8);
+        assert_eq!(store.append(&batch("hpc", "abc123", "web", &["1", "2"])), 2);
+        store.append(&batch("hpc", "abc123", "web", &["3", "4"]));
+
+        let (node, lines) = store.tail("", "abc", 0).unwrap();
+        assert_eq!(node, "hpc");
+        assert_eq!(text(&lines), vec!["2", "3", "4"]);
+        let (_, lines) = store.tail("hpc", "web", 1).unwrap();
+        assert_eq!(text(&lines), vec!["4"]);
+        assert_eq!(
+            store.tail("zone1", "web", 0),
+            Err(LookupError::NotFound("web".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_tail_needs_node_of_ambiguous_container() {
+        let mut store = LogStore::new(3, 2);
+        store.append(&batch("zone1", "aaa", "web", &["a"]));
+        store.append(&batch("zone2", "bbb", "web", &["b"]));
+        assert_eq!(
+            store.tail("", "web", 0),
+            Err(LookupError::Ambiguous(
+                "web".to_string(),
+                vec!["zone1".to_string(), "zone2".to_string()]
+            ))
+        );
+        let (_, lines) = store.tail("zone2", "web", 0).unwrap();
+        assert_eq!(text(&lines), vec!["b"]);
+
+        // zone1 logged least recently, so its container is dropped
+        store.append(&batch("zone3", "ccc", "db", &["c"]));
+        assert!(store.tail("zone1", "web", 0).is_err());
+        assert!(store.tail("", "db", 0).is_ok());
+    }
+}
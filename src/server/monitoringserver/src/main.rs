@@ -8,10 +8,15 @@
 //! and launches both concurrently. It also provides unit tests for initialization.
 
 use common::monitoringserver::{ContainerList, NodeInfo};
+pub mod alerts;
 pub mod data_structures;
 pub mod etcd_storage;
 pub mod grpc;
+pub mod inventory;
+pub mod logs;
 pub mod manager;
+pub mod metrics;
+pub mod timeseries;
 
 use common::logd;
 use common::logd::logger;
@@ -64,7 +69,9 @@ async fn initialize(
     logd!(3, "MonitoringServer listening on {}", addr);
 
     if let Err(e) = Server::builder()
+        .layer(common::trace::GrpcTraceLayer)
         .add_service(MonitoringServerConnectionServer::new(server))
+        .add_service(common::reflection::service())
         .serve(addr)
         .await
     {
@@ -75,7 +82,11 @@ async fn initialize(
 #[tokio::main]
 async fn main() {
     let _ = logger::init_async_logger("monitoringserver").await;
+    common::trace::init_exporter("monitoringserver");
     logd!(1, "initiailize monitoring server");
+    if let Err(e) = common::startup::wait_for_dependencies("monitoringserver").await {
+        logd!(4, "monitoringserver starts without its dependencies, {}", e);
+    }
 
     let (tx_container, rx_container) = channel::<ContainerList>(100);
     let (tx_node, rx_node) = channel::<NodeInfo>(100);
@@ -85,8 +96,10 @@ async fn main() {
 
     let mgr = launch_manager(rx_container, rx_node, rx_stress);
     let grpc = initialize(tx_container, tx_node, tx_stress);
+    let metrics = metrics::launch_metrics_server();
+    let timeseries = timeseries::run_persistence();
 
-    tokio::join!(mgr, grpc);
+    tokio::join!(mgr, grpc, metrics, timeseries);
 }
 
 #[cfg(test)]
@@ -7,8 +7,9 @@
 //! This struct manages scenario requests received via gRPC, and provides
 //! a gRPC sender for communicating with the nodeagent or other services.
 //! It is designed to be thread-safe and run in an async context.
+use crate::alerts::AlertEngine;
 use crate::data_structures::{BoardInfo, DataStore, SocInfo};
-use common::monitoringserver::{ContainerList, NodeInfo}; // Use protobuf types
+use common::monitoringserver::{ContainerInfo, ContainerList, NodeInfo}; // Use protobuf types
 use common::Result;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -26,6 +27,8 @@ pub struct MonitoringServerManager {
     rx_stress: Arc<Mutex<mpsc::Receiver<String>>>,
     /// Data store for managing NodeInfo, SocInfo, and BoardInfo
     data_store: Arc<Mutex<DataStore>>,
+    /// Alerts firing for the alert rules
+    alert_engine: Arc<Mutex<AlertEngine>>,
 }
 
 impl MonitoringServerManager {
@@ -40,6 +43,7 @@ impl MonitoringServerManager {
             rx_node: Arc::new(Mutex::new(rx_node)),
             rx_stress: Arc::new(Mutex::new(rx_stress)),
             data_store: Arc::new(Mutex::new(DataStore::new())),
+            alert_engine: Arc::new(Mutex::new(AlertEngine::new())),
         }
     }
 
@@ -67,6 +71,9 @@ impl MonitoringServerManager {
             container_list.node_name,
             container_list.containers.len()
         );
+        crate::metrics::record_container_list(&container_list);
+        crate::timeseries::record_container_list(&container_list);
+        crate::inventory::record_container_list(&container_list);
 
         let current_container_ids: Vec<String> = container_list
             .containers
@@ -263,6 +270,10 @@ impl MonitoringServerManager {
     ///
     /// This function handles the received NodeInfo and processes it accordingly.
     async fn handle_node_info(&self, node_info: NodeInfo) {
+        crate::metrics::record_node_report(&node_info.node_name);
+        crate::timeseries::record_node_info(&node_info);
+        crate::inventory::record_node_info(&node_info);
+
         // Print detailed NodeInfo first
         self.print_node_info(&node_info);
 
@@ -296,16 +307,42 @@ impl MonitoringServerManager {
             // Get running containers on this node and report to PolicyManager
             let running_containers =
                 self.build_running_containers_list(&data_store, &node_info.node_name);
+            let containers: Vec<ContainerInfo> = data_store
+                .get_containers_by_node(&node_info.node_name)
+                .into_iter()
+                .cloned()
+                .collect();
             drop(data_store); // Release lock before async call
 
             // Report to PolicyManager for threshold-based policy evaluation
             self.report_to_policy_manager(node_info.clone(), running_containers)
                 .await;
+
+            self.evaluate_alerts(&node_info, &containers).await;
         }
 
         println!("{}", "=".repeat(80));
     }
 
+    /// Evaluate the alert rules against a node report and its containers
+    async fn evaluate_alerts(&self, node_info: &NodeInfo, containers: &[ContainerInfo]) {
+        let Some(rules) = crate::alerts::load_rules().await else {
+            return;
+        };
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
+        let containers: Vec<&ContainerInfo> = containers.iter().collect();
+
+        let changes = self
+            .alert_engine
+            .lock()
+            .await
+            .evaluate(&rules, node_info, &containers, now);
+        crate::alerts::publish(changes).await;
+    }
+
     /// Build a list of running containers for a specific node
     fn build_running_containers_list(
         &self,
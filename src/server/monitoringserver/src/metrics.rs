@@ -0,0 +1,208 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Prometheus `/metrics` endpoint of the MonitoringServer
+//!
+//! Exports container counts per node, the interval between node reports and
+//! the etcd latencies recorded by `common::etcd`, and the metrics of the
+//! collector plugins of the nodes. The same listener serves
+//! the time series API of [`crate::timeseries`] and the inventory API of
+//! [`crate::inventory`].
+
+use axum::{http::header, response::IntoResponse, routing::get, Router};
+use common::logd;
+use common::metrics;
+use common::monitoringserver::{ContainerList, NodeMetrics};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+pub const NODE_CONTAINERS: &str = "pullpiri_node_containers";
+pub const NODE_REPORT_INTERVAL_SECONDS: &str = "pullpiri_node_report_interval_seconds";
+pub const NODE_PLUGIN_METRIC: &str = "pullpiri_node_plugin_metric";
+
+/// Update the container gauges of the node that sent the list
+pub fn record_container_list(container_list: &ContainerList) {
+    let running = container_list
+        .containers
+        .iter()
+        .filter(|c| c.state.get("Running").is_some_and(|r| r == "true"))
+        .count();
+    let node = container_list.node_name.as_str();
+
+    metrics::set_gauge(
+        NODE_CONTAINERS,
+        "Number of containers reported by a node",
+        &[("node", node), ("state", "all")],
+        container_list.containers.len() as f64,
+    );
+    metrics::set_gauge(
+        NODE_CONTAINERS,
+        "Number of containers reported by a node",
+        &[("node", node), ("state", "running")],
+        running as f64,
+    );
+}
+
+/// Record the time since the previous report of a node
+pub fn record_node_report(node_name: &str) {
+    static LAST_REPORT: OnceLock<Mutex<HashMap<String, Instant>>> = OnceLock::new();
+    let now = Instant::now();
+    let previous = LAST_REPORT
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(node_name.to_string(), now);
+
+    if let Some(previous) = previous {
+        metrics::observe(
+            NODE_REPORT_INTERVAL_SECONDS,
+            "Seconds between two node info reports of a node",
+            &[("node", node_name)],
+            now.duration_since(previous).as_secs_f64(),
+        );
+    }
+}
+
+/// Update the gauges of the metrics collected by the plugins of a node
+///
+/// Every metric is one series of [`NODE_PLUGIN_METRIC`], labelled with the
+/// node, the collector, the metric name and unit and the labels of the metric.
+pub fn record_node_metrics(node_metrics: &NodeMetrics) {
+    for metric in &node_metrics.metrics {
+        let mut labels = vec![
+            ("node", node_metrics.node_name.as_str()),
+            ("collector", metric.collector.as_str()),
+            ("metric", metric.name.as_str()),
+            ("unit", metric.unit.as_str()),
+        ];
+        labels.extend(
+            metric
+                .labels
+                .iter()
+                .filter(|(key, _)| {
+                    !matches!(key.as_str(), "node" | "collector" | "metric" | "unit")
+                })
+                .map(|(key, value)| (key.as_str(), value.as_str())),
+        );
+        metrics::set_gauge(
+            NODE_PLUGIN_METRIC,
+            "Metric reported by a collector plugin of a node",
+            &labels,
+            metric.value,
+        );
+    }
+}
+
+pub fn router() -> Router {
+    Router::new().route("/metrics", get(render))
+}
+
+async fn render() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, metrics::CONTENT_TYPE)],
+        metrics::render(),
+    )
+}
+
+/// Serve the metrics endpoint, the time series API and the inventory API
+pub async fn launch_metrics_server() {
+    let addr = common::monitoringserver::open_metrics_server();
+    let listener = match tokio::net::TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            logd!(5, "Failed to bind metrics endpoint {}: {}", addr, e);
+            return;
+        }
+    };
+    logd!(3, "MonitoringServer metrics listening on {}", addr);
+
+    let app = router()
+        .merge(crate::timeseries::router())
+        .merge(crate::inventory::router());
+    if let Err(e) = axum::serve(listener, app).await {
+        logd!(5, "Metrics server error: {}", e);
+    }
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::monitoringserver::ContainerInfo;
+
+    #[test]
+    fn test_record_container_list() {
+        let running = ContainerInfo {
+            id: "c1".to_string(),
+            state: HashMap::from([("Running".to_string(), "true".to_string())]),
+            ..Default::default()
+        };
+        let stopped = ContainerInfo {
+            id: "c2".to_string(),
+            ..Default::default()
+        };
+        record_container_list(&ContainerList {
+            node_name: "metrics-node".to_string(),
+            containers: vec![running, stopped],
+        });
+
+        let text = metrics::render();
+        assert!(text.contains("pullpiri_node_containers{node=\"metrics-node\",state=\"all\"} 2\n"));
+        assert!(
+            text.contains("pullpiri_node_containers{node=\"metrics-node\",state=\"running\"} 1\n")
+        );
+    }
+
+    #[test]
+    fn test_record_node_report() {
+        record_node_report("report-node");
+        record_node_report("report-node");
+
+        assert!(metrics::render()
+            .contains("pullpiri_node_report_interval_seconds_count{node=\"report-node\"} 1\n"));
+    }
+
+    #[test]
+    fn test_record_node_metrics() {
+        use common::monitoringserver::NodeMetric;
+
+        record_node_metrics(&NodeMetrics {
+            node_name: "plugin-node".to_string(),
+            metrics: vec![NodeMetric {
+                name: "can_rx_errors".to_string(),
+                value: 3.0,
+                unit: "count".to_string(),
+                labels: HashMap::from([
+                    ("bus".to_string(), "can0".to_string()),
+                    ("node".to_string(), "spoofed".to_string()),
+                ]),
+                collector: "can-stats".to_string(),
+            }],
+            timestamp: 0,
+        });
+
+        assert!(metrics::render().contains(
+            "pullpiri_node_plugin_metric{bus=\"can0\",collector=\"can-stats\",metric=\"can_rx_errors\",node=\"plugin-node\",unit=\"count\"} 3\n"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint() {
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode};
+        use tower::ServiceExt;
+
+        metrics::inc_counter("endpoint_test_total", "Test counter", &[]);
+        let response = router()
+            .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            metrics::CONTENT_TYPE
+        );
+    }
+}
@@ -0,0 +1,589 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Time series of container and node metrics
+//!
+//! Every numeric container stat reported by nodeagent and the usage figures
+//! of each NodeInfo are kept as samples, one ring buffer per node, container
+//! and metric. Node figures are stored with an empty container name. Samples
+//! older than `monitoring.retention_secs` of settings.yaml are dropped, and
+//! the samples are saved to `monitoring.persist_path` periodically so graphs
+//! survive a restart.
+//!
+//! REST API, served next to `/metrics`:
+//! * `GET /api/timeseries/series?node=` - known series with their sample count
+//! * `GET /api/timeseries/range?node=&container=&metric=&from=&to=&step=` -
+//!   samples of one series between `from` and `to` (Unix ms), averaged over
+//!   `step` ms when given
+
+use axum::{
+    extract::Query,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use common::monitoringserver::{ContainerList, NodeInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Samples kept per series at most, whatever the retention
+pub const MAX_SAMPLES_PER_SERIES: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    /// Unix time in milliseconds
+    pub timestamp_ms: i64,
+    pub value: f64,
+}
+
+/// Node, container and metric a series belongs to
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SeriesKey {
+    pub node: String,
+    /// Empty for metrics of the node itself
+    pub container: String,
+    pub metric: String,
+}
+
+impl SeriesKey {
+    pub fn new(node: &str, container: &str, metric: &str) -> Self {
+        Self {
+            node: node.to_string(),
+            container: container.to_string(),
+            metric: metric.to_string(),
+        }
+    }
+}
+
+/// Summary of a series for `GET /api/timeseries/series`
+#[derive(Debug, Serialize)]
+pub struct SeriesInfo {
+    #[serde(flatten)]
+    pub key: SeriesKey,
+    pub samples: usize,
+    pub last_timestamp_ms: Option<i64>,
+}
+
+/// Ring buffers of samples per series
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TimeSeriesStore {
+    series: BTreeMap<String, (SeriesKey, VecDeque<Sample>)>,
+}
+
+fn storage_key(key: &SeriesKey) -> String {
+    format!("{}/{}/{}", key.node, key.container, key.metric)
+}
+
+impl TimeSeriesStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a sample, dropping the oldest one when the series is full
+    pub fn record(&mut self, key: SeriesKey, sample: Sample) {
+        let (_, samples) = self
+            .series
+            .entry(storage_key(&key))
+            .or_insert_with(|| (key, VecDeque::new()));
+        if samples.len() >= MAX_SAMPLES_PER_SERIES {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+
+    /// Drop samples taken before `oldest_ms` and series left empty
+    pub fn prune(&mut self, oldest_ms: i64) {
+        for (_, samples) in self.series.values_mut() {
+            while samples.front().is_some_and(|s| s.timestamp_ms < oldest_ms) {
+                samples.pop_front();
+            }
+        }
+        self.series.retain(|_, (_, samples)| !samples.is_empty());
+    }
+
+    /// Series of a node, or of every node if `node` is `None`
+    pub fn list(&self, node: Option<&str>) -> Vec<SeriesInfo> {
+        self.series
+            .values()
+            .filter(|(key, _)| node.is_none_or(|node| key.node == node))
+            .map(|(key, samples)| SeriesInfo {
+                key: key.clone(),
+                samples: samples.len(),
+                last_timestamp_ms: samples.back().map(|s| s.timestamp_ms),
+            })
+            .collect()
+    }
+
+    /// Samples of a series from `from_ms` to `to_ms` inclusive
+    ///
+    /// With a `step_ms`, samples are averaged per step, each average stamped
+    /// with the start of its step.
+    ///
+    /// ### Returns
+    /// * `None` - the series is unknown
+    pub fn range(
+        &self,
+        key: &SeriesKey,
+        from_ms: i64,
+        to_ms: i64,
+        step_ms: Option<i64>,
+    ) -> Option<Vec<Sample>> {
+        let (_, samples) = self.series.get(&storage_key(key))?;
+        let in_range = samples
+            .iter()
+            .filter(|s| s.timestamp_ms >= from_ms && s.timestamp_ms <= to_ms);
+        let Some(step) = step_ms.filter(|step| *step > 0) else {
+            return Some(in_range.copied().collect());
+        };
+
+        let mut buckets: Vec<(i64, f64, u32)> = Vec::new();
+        for sample in in_range {
+            let start = from_ms + (sample.timestamp_ms - from_ms) / step * step;
+            match buckets.last_mut() {
+                Some((bucket, sum, count)) if *bucket == start => {
+                    *sum += sample.value;
+                    *count += 1;
+                }
+                _ => buckets.push((start, sample.value, 1)),
+            }
+        }
+        Some(
+            buckets
+                .into_iter()
+                .map(|(timestamp_ms, sum, count)| Sample {
+                    timestamp_ms,
+                    value: sum / count as f64,
+                })
+                .collect(),
+        )
+    }
+
+    /// Record the numeric stats and the sampled usage averages of every
+    /// container of a node
+    pub fn record_container_list(&mut self, container_list: &ContainerList, timestamp_ms: i64) {
+        for container in &container_list.containers {
+            let name = container
+                .names
+                .first()
+                .map(|n| n.trim_start_matches('/'))
+                .filter(|n| !n.is_empty())
+                .unwrap_or(&container.id);
+            for (metric, value) in &container.stats {
+                if let Ok(value) = value.parse::<f64>() {
+                    self.record(
+                        SeriesKey::new(&container_list.node_name, name, metric),
+                        Sample {
+                            timestamp_ms,
+                            value,
+                        },
+                    );
+                }
+            }
+            let Some(usage) = &container.usage else {
+                continue;
+            };
+            let averages = [
+                ("cpu_percent", &usage.cpu_percent),
+                ("memory_bytes", &usage.memory_bytes),
+                ("rx_bytes_per_sec", &usage.rx_bytes_per_sec),
+                ("tx_bytes_per_sec", &usage.tx_bytes_per_sec),
+            ];
+            for (metric, aggregate) in averages {
+                if let Some(aggregate) = aggregate {
+                    self.record(
+                        SeriesKey::new(&container_list.node_name, name, metric),
+                        Sample {
+                            timestamp_ms,
+                            value: aggregate.avg,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    /// Record the usage figures of a node
+    pub fn record_node_info(&mut self, node_info: &NodeInfo, timestamp_ms: i64) {
+        let figures = [
+            ("cpu_usage", node_info.cpu_usage),
+            ("mem_usage", node_info.mem_usage),
+            ("used_memory", node_info.used_memory as f64),
+            ("rx_bytes", node_info.rx_bytes as f64),
+            ("tx_bytes", node_info.tx_bytes as f64),
+            ("read_bytes", node_info.read_bytes as f64),
+            ("write_bytes", node_info.write_bytes as f64),
+        ];
+        for (metric, value) in figures {
+            self.record(
+                SeriesKey::new(&node_info.node_name, "", metric),
+                Sample {
+                    timestamp_ms,
+                    value,
+                },
+            );
+        }
+    }
+
+    /// Save the samples to a file, replacing it at once
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        save_snapshot(path, &serde_json::to_vec(self)?)
+    }
+
+    /// Samples saved by [`TimeSeriesStore::save`]
+    pub fn load(path: &str) -> std::io::Result<Self> {
+        let content = std::fs::read(path)?;
+        Ok(serde_json::from_slice(&content)?)
+    }
+}
+
+/// Store shared by the manager and the REST API
+pub fn store() -> MutexGuard<'static, TimeSeriesStore> {
+    static STORE: OnceLock<Mutex<TimeSeriesStore>> = OnceLock::new();
+    STORE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+/// Oldest timestamp kept with the retention of settings.yaml
+fn retention_start_ms(now_ms: i64) -> i64 {
+    let retention_secs = common::setting::get_config().monitoring.retention_secs;
+    now_ms.saturating_sub(retention_secs.saturating_mul(1000) as i64)
+}
+
+/// Record a ContainerList received now, dropping expired samples
+pub fn record_container_list(container_list: &ContainerList) {
+    let now = now_ms();
+    let mut store = store();
+    store.record_container_list(container_list, now);
+    store.prune(retention_start_ms(now));
+}
+
+/// Record a NodeInfo received now, dropping expired samples
+pub fn record_node_info(node_info: &NodeInfo) {
+    let now = now_ms();
+    let mut store = store();
+    store.record_node_info(node_info, now);
+    store.prune(retention_start_ms(now));
+}
+
+/// Restore the saved samples and save them periodically
+///
+/// Does nothing when `monitoring.persist_path` is not set.
+pub async fn run_persistence() {
+    let settings = common::setting::get_config().monitoring.clone();
+    if settings.persist_path.is_empty() {
+        return;
+    }
+
+    match TimeSeriesStore::load(&settings.persist_path) {
+        Ok(mut saved) => {
+            saved.prune(retention_start_ms(now_ms()));
+            println!(
+                "[TimeSeries] Restored {} series from {}",
+                saved.series.len(),
+                settings.persist_path
+            );
+            let mut store = store();
+            // samples received before the restore are newer, keep them last
+            for (key, samples) in std::mem::take(&mut store.series).into_values() {
+                for sample in samples {
+                    saved.record(key.clone(), sample);
+                }
+            }
+            *store = saved;
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => eprintln!(
+            "[TimeSeries] Failed to restore {}: {}",
+            settings.persist_path, e
+        ),
+    }
+
+    let interval = std::time::Duration::from_secs(settings.persist_interval_secs.max(1));
+    loop {
+        tokio::time::sleep(interval).await;
+        let path = common::setting::get_config()
+            .monitoring
+            .persist_path
+            .clone();
+        if path.is_empty() {
+            continue;
+        }
+        let snapshot = {
+            let mut store = store();
+            store.prune(retention_start_ms(now_ms()));
+            serde_json::to_vec(&*store)
+        };
+        let result = snapshot
+            .map_err(std::io::Error::from)
+            .and_then(|snapshot| save_snapshot(&path, &snapshot));
+        if let Err(e) = result {
+            eprintln!("[TimeSeries] Failed to save {}: {}", path, e);
+        }
+    }
+}
+
+/// Write a serialized store without holding its lock
+fn save_snapshot(path: &str, snapshot: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp_path = format!("{}.tmp", path);
+    std::fs::write(&tmp_path, snapshot)?;
+    std::fs::rename(tmp_path, path)
+}
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/api/timeseries/series", get(list_series))
+        .route("/api/timeseries/range", get(query_range))
+}
+
+#[derive(Debug, Deserialize)]
+struct SeriesQuery {
+    node: Option<String>,
+}
+
+async fn list_series(Query(query): Query<SeriesQuery>) -> Json<Vec<SeriesInfo>> {
+    Json(store().list(query.node.as_deref()))
+}
+
+#[derive(Debug, Deserialize)]
+struct RangeQuery {
+    node: String,
+    #[serde(default)]
+    container: String,
+    metric: String,
+    from: Option<i64>,
+    to: Option<i64>,
+    step: Option<i64>,
+}
+
+/// Samples of a series, by default over the whole retention period
+async fn query_range(Query(query): Query<RangeQuery>) -> Response {
+    let to = query.to.unwrap_or_else(now_ms);
+    let from = query.from.unwrap_or_else(|| retention_start_ms(to));
+    if from > to {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "'from' is after 'to'"})),
+        )
+            .into_response();
+    }
+
+    let key = SeriesKey::new(&query.node, &query.container, &query.metric);
+    match store().range(&key, from, to, query.step) {
+        Some(samples) => Json(serde_json::json!({
+            "node": key.node,
+            "container": key.container,
+            "metric": key.metric,
+            "from": from,
+            "to": to,
+            "samples": samples,
+        }))
+        .into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "unknown series"})),
+        )
+            .into_response(),
+    }
+}
+
+//Unit Test Cases
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::monitoringserver::{ContainerInfo, ContainerUsage, UsageAggregate};
+    use std::collections::HashMap;
+
+    fn sample(timestamp_ms: i64, value: f64) -> Sample {
+        Sample {
+            timestamp_ms,
+            value,
+        }
+    }
+
+    #[test]
+    fn test_record_prune_and_range() {
+        let mut store = TimeSeriesStore::new();
+        let key = SeriesKey::new("HPC", "core", "CpuTotalUsage");
+        for t in 0..10 {
+            store.record(key.clone(), sample(t * 1000, t as f64));
+        }
+        store.record(SeriesKey::new("HPC", "old", "x"), sample(0, 1.0));
+
+        store.prune(3000);
+        assert_eq!(store.list(None).len(), 1);
+        let samples = store.range(&key, 0, 5000, None).unwrap();
+        assert_eq!(
+            samples,
+            vec![sample(3000, 3.0), sample(4000, 4.0), sample(5000, 5.0)]
+        );
+
+        // averages per step of 2s starting at `from`
+        let averaged = store.range(&key, 3000, 9000, Some(2000)).unwrap();
+        assert_eq!(
+            averaged,
+            vec![
+                sample(3000, 3.5),
+                sample(5000, 5.5),
+                sample(7000, 7.5),
+                sample(9000, 9.0)
+            ]
+        );
+        assert!(store
+            .range(&SeriesKey::new("HPC", "core", "missing"), 0, 1, None)
+            .is_none());
+    }
+
+    #[test]
+    fn test_ring_buffer_is_bounded() {
+        let mut store = TimeSeriesStore::new();
+        let key = SeriesKey::new("HPC", "", "cpu_usage");
+        for t in 0..(MAX_SAMPLES_PER_SERIES as i64 + 5) {
+            store.record(key.clone(), sample(t, 0.0));
+        }
+        let info = &store.list(Some("HPC"))[0];
+        assert_eq!(info.samples, MAX_SAMPLES_PER_SERIES);
+        assert_eq!(store.range(&key, 0, 4, None).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_record_container_list_and_node_info() {
+        let mut store = TimeSeriesStore::new();
+        let container = ContainerInfo {
+            id: "abc123".to_string(),
+            names: vec!["/helloworld-core".to_string()],
+            stats: HashMap::from([
+                ("MemoryUsage".to_string(), "2048".to_string()),
+                ("Status".to_string(), "StatsUnavailable".to_string()),
+            ]),
+            usage: Some(ContainerUsage {
+                cpu_percent: Some(UsageAggregate {
+                    min: 5.0,
+                    avg: 7.5,
+                    max: 10.0,
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        store.record_container_list(
+            &ContainerList {
+                node_name: "HPC".to_string(),
+                containers: vec![container],
+            },
+            1000,
+        );
+        store.record_node_info(
+            &NodeInfo {
+                node_name: "HPC".to_string(),
+                cpu_usage: 12.5,
+                ..Default::default()
+            },
+            1000,
+        );
+
+        let key = SeriesKey::new("HPC", "helloworld-core", "MemoryUsage");
+        assert_eq!(
+            store.range(&key, 0, 2000, None),
+            Some(vec![sample(1000, 2048.0)])
+        );
+        let container_cpu = SeriesKey::new("HPC", "helloworld-core", "cpu_percent");
+        assert_eq!(
+            store.range(&container_cpu, 0, 2000, None),
+            Some(vec![sample(1000, 7.5)])
+        );
+        let cpu = SeriesKey::new("HPC", "", "cpu_usage");
+        assert_eq!(
+            store.range(&cpu, 0, 2000, None),
+            Some(vec![sample(1000, 12.5)])
+        );
+        assert!(store.list(Some("ZONE")).is_empty());
+        // the non-numeric Status stat is not a series
+        assert_eq!(store.list(Some("HPC")).len(), 9);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir()
+            .join(format!("pullpiri-timeseries-{}", std::process::id()))
+            .join("timeseries.json");
+        let path = path.to_str().unwrap();
+        let mut store = TimeSeriesStore::new();
+        let key = SeriesKey::new("HPC", "core", "MemoryUsage");
+        store.record(key.clone(), sample(1000, 1.0));
+
+        store.save(path).unwrap();
+        let loaded = TimeSeriesStore::load(path).unwrap();
+        assert_eq!(
+            loaded.range(&key, 0, 2000, None),
+            Some(vec![sample(1000, 1.0)])
+        );
+
+        std::fs::remove_dir_all(std::path::Path::new(path).parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_range_endpoint() {
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let now = now_ms();
+        store().record(
+            SeriesKey::new("endpoint-node", "", "cpu_usage"),
+            sample(now, 42.0),
+        );
+
+        let response = router()
+            .oneshot(
+                Request::get("/api/timeseries/range?node=endpoint-node&metric=cpu_usage")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["samples"][0]["value"], 42.0);
+
+        let response = router()
+            .oneshot(
+                Request::get("/api/timeseries/range?node=endpoint-node&metric=unknown")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = router()
+            .oneshot(
+                Request::get(
+                    "/api/timeseries/range?node=endpoint-node&metric=cpu_usage&from=2&to=1",
+                )
+                .body(Body::empty())
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
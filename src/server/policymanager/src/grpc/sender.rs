@@ -5,6 +5,7 @@
 
 //! gRPC sender for PolicyManager to communicate with StateManager
 
+use common::rpc::RpcClient;
 use common::statemanager::state_manager_connection_client::StateManagerConnectionClient;
 use common::statemanager::{connect_server, OffloadingRequest, OffloadingResponse};
 use tonic::{Request, Response, Status};
@@ -13,20 +14,21 @@ use tonic::{Request, Response, Status};
 pub async fn trigger_offloading(
     request: OffloadingRequest,
 ) -> Result<Response<OffloadingResponse>, Status> {
-    let addr = connect_server();
-
-    let client = StateManagerConnectionClient::connect(addr).await;
-
-    match client {
-        Ok(mut client) => client.trigger_offloading(Request::new(request)).await,
-        Err(e) => {
-            eprintln!("[PolicyManager] Failed to connect to StateManager: {}", e);
-            Err(Status::unavailable(format!(
-                "Failed to connect to StateManager: {}",
-                e
-            )))
-        }
+    let request = &request;
+    let result = RpcClient::new("StateManager", connect_server())
+        .call(|channel| async move {
+            StateManagerConnectionClient::new(channel)
+                .trigger_offloading(Request::new(request.clone()))
+                .await
+        })
+        .await;
+    if let Err(e) = &result {
+        eprintln!(
+            "[PolicyManager] Offloading request to StateManager failed: {}",
+            e.message()
+        );
     }
+    result
 }
 
 #[cfg(test)]
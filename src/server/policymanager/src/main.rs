@@ -11,6 +11,9 @@ use tonic::transport::Server;
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🚀 PolicyManager starting...");
+    if let Err(e) = common::startup::wait_for_dependencies("policymanager").await {
+        println!("⚠️ PolicyManager starts without its dependencies, {}", e);
+    }
 
     let addr = common::policymanager::open_server().parse()?;
     let server = PolicyManagerGrpcServer::new();
@@ -18,7 +21,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("📡 PolicyManager gRPC server listening on {}", addr);
 
     Server::builder()
+        .layer(common::trace::GrpcTraceLayer)
         .add_service(PolicyManagerConnectionServer::new(server))
+        .add_service(common::reflection::service())
         .serve(addr)
         .await?;
 
@@ -377,6 +377,7 @@ async fn main() -> anyhow::Result<()> {
     // Start the gRPC server
     Server::builder()
         .add_service(RocksDbServiceServer::new(rocksdb_service))
+        .add_service(common::reflection::service())
         .serve(bind_addr)
         .await?;
 
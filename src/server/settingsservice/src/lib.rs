@@ -11,6 +11,7 @@ pub mod monitoring_types;
 pub mod settings_api;
 pub mod settings_config;
 pub mod settings_core;
+pub mod settings_events;
 pub mod settings_history;
 pub mod settings_monitoring;
 pub mod settings_storage;
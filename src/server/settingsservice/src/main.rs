@@ -15,13 +15,14 @@
 use anyhow::Result;
 use clap::Parser;
 use std::path::PathBuf;
-use tracing::info;
+use tracing::{info, warn};
 
 pub mod monitoring_etcd;
 pub mod monitoring_types;
 mod settings_api;
 mod settings_config;
 mod settings_core;
+mod settings_events;
 mod settings_history;
 mod settings_monitoring;
 mod settings_storage;
@@ -65,6 +66,9 @@ async fn main() -> Result<()> {
     info!("Starting Pullpiri Settings Service");
     info!("Config file: {:?}", args.config);
     info!("ETCD endpoints: {}", args.etcd_endpoints);
+    if let Err(e) = common::startup::wait_for_dependencies("settingsservice").await {
+        warn!("Settings Service starts without its dependencies, {}", e);
+    }
 
     // Run in server mode only
     run_server_mode(args).await
@@ -495,6 +495,7 @@ mod tests {
             config,
             annotation,
             stats,
+            usage: None,
         }
     }
 
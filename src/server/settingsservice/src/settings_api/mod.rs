@@ -5,28 +5,38 @@
 use crate::monitoring_etcd;
 use crate::monitoring_types::{BoardInfo, NodeInfo, SocInfo}; //, StressMetrics};
 use crate::settings_config::{Config, ConfigManager, ConfigSummary, ValidationResult};
+use crate::settings_events::{self, EventHub, EventKind};
 use crate::settings_history::{HistoryEntry, HistoryManager};
 use crate::settings_monitoring::{
     BoardListResponse, FilterSummary, Metric, MetricsFilter, MonitoringManager, NodeListResponse,
     SocListResponse,
 };
-use crate::settings_utils::error::SettingsError;
+use crate::settings_utils::error::{SettingsError, StorageError};
+use crate::settings_utils::logging::{self, LogLevels};
 use axum::{
-    extract::{Path, Query, State},
+    extract::{DefaultBodyLimit, Path, Query, State},
     http::StatusCode,
+    middleware::{from_fn, from_fn_with_state},
+    response::sse::{KeepAlive, Sse},
     response::Json,
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
     Router,
 };
 use chrono::Utc;
-use common::monitoringserver::ContainerInfo;
+use common::alert::AlertRule;
+use common::auth::{require_role, Role};
+use common::error::ErrorCode;
+use common::limit::{enforce_limits, RateLimiter};
+use common::listing::{self, ListMeta, ListQuery};
+use common::monitoringserver::{Alert, ContainerInfo};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tower_http::cors::CorsLayer;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 /// API server state
 #[derive(Clone)]
@@ -34,6 +44,7 @@ pub struct ApiState {
     pub config_manager: Arc<RwLock<ConfigManager>>,
     pub history_manager: Arc<RwLock<HistoryManager>>,
     pub monitoring_manager: Arc<RwLock<MonitoringManager>>,
+    pub event_hub: EventHub,
 }
 
 /// Query parameters for metrics API
@@ -76,6 +87,33 @@ pub struct ResourceQuery {
     pub filter: Option<String>,
 }
 
+/// Field selectors of the node listing
+///
+/// Pagination, `label` and `sort` come from [`ListQuery`]. Status, role
+/// and labels are read from the ApiServer node registry.
+#[derive(Debug, Default, Deserialize)]
+pub struct NodeListQuery {
+    /// Part of the node name
+    pub filter: Option<String>,
+    /// Node status, e.g. `Ready` or `NotReady`
+    pub status: Option<String>,
+    /// Node role, e.g. `master` or `nodeagent`
+    pub role: Option<String>,
+}
+
+/// Fields of `/api/v1/nodes` that can be sorted by
+const NODE_SORT_FIELDS: &[&str] = &["name", "status", "role", "cpu_usage", "mem_usage"];
+/// etcd prefix of the ApiServer node registry
+const NODE_REGISTRY_PREFIX: &str = "cluster/nodes/";
+
+/// Status, role and labels of a node registered with the ApiServer
+#[derive(Debug, Clone, Default, PartialEq)]
+struct NodeRegistration {
+    status: String,
+    role: String,
+    labels: HashMap<String, String>,
+}
+
 /// Request body for config creation/updates
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ConfigRequest {
@@ -91,12 +129,8 @@ pub struct SuccessResponse {
     pub message: String,
 }
 
-/// Error response
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-    pub details: Option<Value>,
-}
+/// Error response, with the code, message and correlation id shared with ApiServer
+pub type ErrorResponse = common::error::ApiError;
 
 /// Request body for container creation
 #[derive(Debug, Deserialize)]
@@ -131,6 +165,13 @@ pub struct PodInfo {
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Query parameters for events API
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+    /// Comma separated event kinds, all kinds if not given
+    pub kinds: Option<String>,
+}
+
 /// API server
 pub struct ApiServer {
     bind_address: String,
@@ -151,6 +192,7 @@ impl ApiServer {
             config_manager,
             history_manager,
             monitoring_manager,
+            event_hub: EventHub::new(),
         };
 
         Ok(Self {
@@ -163,6 +205,9 @@ impl ApiServer {
     /// Start the API server
     pub async fn start(self) -> Result<(), SettingsError> {
         let app = self.create_router();
+        self.state
+            .event_hub
+            .spawn_watcher(settings_events::DEFAULT_POLL_INTERVAL);
 
         let addr = format!("{}:{}", self.bind_address, self.bind_port);
         info!("Starting API server on {}", addr);
@@ -171,16 +216,26 @@ impl ApiServer {
             .await
             .map_err(|e| SettingsError::Api(format!("Failed to bind to {}: {}", addr, e)))?;
 
-        axum::serve(listener, app)
-            .await
-            .map_err(|e| SettingsError::Api(format!("Server error: {}", e)))?;
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .map_err(|e| SettingsError::Api(format!("Server error: {}", e)))?;
 
         Ok(())
     }
 
     /// Create the router with all endpoints
+    ///
+    /// Reads need the viewer role, configuration changes the operator role
+    /// and artifact apply and withdraw the admin role. Request rate and body
+    /// size are limited by the `limits` section of settings.yaml. The
+    /// `/healthz` and `/readyz` probes need neither a role nor count against
+    /// the limits. Failed requests return an [`ErrorResponse`] whose
+    /// correlation id is the trace ID of the request.
     fn create_router(&self) -> Router {
-        Router::new()
+        let read = Router::new()
             // Metrics endpoints
             .route("/api/v1/metrics", get(get_metrics))
             .route("/api/v1/metrics/:id", get(get_metric_by_id))
@@ -193,27 +248,26 @@ impl ApiServer {
                 get(get_metrics_by_type),
             )
             .route("/api/v1/metrics/filters", get(get_filters))
-            .route("/api/v1/metrics/filters", post(create_filter))
             .route("/api/v1/metrics/filters/:id", get(get_filter))
-            .route("/api/v1/metrics/filters/:id", delete(delete_filter))
+            // Alert endpoints
+            .route("/api/v1/alerts", get(list_alerts))
+            .route("/api/v1/alerts/rules", get(list_alert_rules))
+            .route("/api/v1/alerts/rules/:id", get(get_alert_rule))
             // Configuration endpoints
             .route("/api/v1/settings", get(list_configs))
             .route("/api/v1/settings/:path", get(get_config))
-            .route("/api/v1/settings/:path", post(create_config))
-            .route("/api/v1/settings/:path", delete(delete_config))
             .route("/api/v1/settings/validate", post(validate_config))
             .route("/api/v1/settings/schemas/:schema_type", get(get_schema))
             // History endpoints
             .route("/api/v1/history/:path", get(get_history))
             .route("/api/v1/history/:path/version/:version", get(get_version))
-            .route(
-                "/api/v1/history/:path/rollback/:version",
-                post(rollback_to_version),
-            )
             .route("/api/v1/history/:path/diff", get(diff_versions))
             // System endpoints
             .route("/api/v1/system/status", get(get_system_status))
             .route("/api/v1/system/health", get(health_check))
+            .route("/api/v1/logging/levels", get(get_log_levels))
+            // Live events for the Web GUI
+            .route("/api/v1/events", get(stream_events))
             // Node Management APIs - READ ONLY
             .route("/api/v1/nodes", get(list_nodes))
             .route("/api/v1/nodes/:name", get(get_node))
@@ -225,17 +279,12 @@ impl ApiServer {
                 "/api/v1/nodes/:name/containers",
                 get(get_containers_by_node),
             )
-            // YAML Management APIs - NEW (replacing container create/delete)
-            .route("/api/v1/yaml", post(apply_yaml_artifact))
-            .route("/api/v1/yaml", delete(withdraw_yaml_artifact))
             // SoC Management APIs - READ ONLY
             .route("/api/v1/socs", get(list_socs))
             .route("/api/v1/socs/:name", get(get_soc))
             // Board Management APIs - READ ONLY
             .route("/api/v1/boards", get(list_boards))
             .route("/api/v1/boards/:name", get(get_board))
-            // Integration with monitoring server
-            .route("/api/v1/monitoring/sync", post(sync_with_monitoring_server))
             // Additional metrics routes
             .route("/api/v1/metrics/nodes", get(get_all_node_metrics))
             .route("/api/v1/metrics/containers", get(get_all_container_metrics))
@@ -252,7 +301,43 @@ impl ApiServer {
                 "/api/v1/metrics/containers/:id",
                 get(get_container_metric_by_id),
             )
+            .route_layer(from_fn_with_state(Role::Viewer, require_role));
+
+        let operate = Router::new()
+            .route("/api/v1/metrics/filters", post(create_filter))
+            .route("/api/v1/metrics/filters/:id", delete(delete_filter))
+            .route("/api/v1/alerts/rules", post(create_alert_rule))
+            .route("/api/v1/alerts/rules/:id", put(update_alert_rule))
+            .route("/api/v1/alerts/rules/:id", delete(delete_alert_rule))
+            .route("/api/v1/settings/:path", post(create_config))
+            .route("/api/v1/settings/:path", delete(delete_config))
+            .route(
+                "/api/v1/history/:path/rollback/:version",
+                post(rollback_to_version),
+            )
+            // Integration with monitoring server
+            .route("/api/v1/monitoring/sync", post(sync_with_monitoring_server))
+            .route("/api/v1/logging/levels", put(update_log_levels))
+            .route_layer(from_fn_with_state(Role::Operator, require_role));
+
+        let admin = Router::new()
+            // YAML Management APIs - NEW (replacing container create/delete)
+            .route("/api/v1/yaml", post(apply_yaml_artifact))
+            .route("/api/v1/yaml", delete(withdraw_yaml_artifact))
+            .route_layer(from_fn_with_state(Role::Admin, require_role));
+
+        Router::new()
+            .merge(read)
+            .merge(operate)
+            .merge(admin)
             .with_state(self.state.clone())
+            .layer(from_fn_with_state(
+                Arc::new(RateLimiter::default()),
+                enforce_limits,
+            ))
+            .layer(DefaultBodyLimit::disable())
+            .layer(from_fn(common::trace::trace_requests))
+            .merge(common::health::router())
             .layer(CorsLayer::permissive())
     }
 }
@@ -293,7 +378,7 @@ async fn get_metrics(
 
     match monitoring_manager.get_metrics(filter.as_ref()).await {
         Ok(metrics) => Ok(Json(metrics)),
-        Err(e) => Err(internal_error(&format!("Failed to get metrics: {}", e))),
+        Err(e) => Err(failed_error("Failed to get metrics", &e)),
     }
 }
 
@@ -308,7 +393,7 @@ async fn get_metric_by_id(
     match monitoring_manager.get_metric_by_id(&id).await {
         Ok(Some(metric)) => Ok(Json(metric)),
         Ok(None) => Err(not_found_error("Metric not found")),
-        Err(e) => Err(internal_error(&format!("Failed to get metric: {}", e))),
+        Err(e) => Err(failed_error("Failed to get metric", &e)),
     }
 }
 
@@ -325,7 +410,7 @@ async fn get_metrics_by_component(
         .await
     {
         Ok(metrics) => Ok(Json(metrics)),
-        Err(e) => Err(internal_error(&format!("Failed to get metrics: {}", e))),
+        Err(e) => Err(failed_error("Failed to get metrics", &e)),
     }
 }
 
@@ -339,7 +424,7 @@ async fn get_metrics_by_type(
 
     match monitoring_manager.get_metrics_by_type(&metric_type).await {
         Ok(metrics) => Ok(Json(metrics)),
-        Err(e) => Err(internal_error(&format!("Failed to get metrics: {}", e))),
+        Err(e) => Err(failed_error("Failed to get metrics", &e)),
     }
 }
 
@@ -352,7 +437,7 @@ async fn get_filters(
 
     match monitoring_manager.list_filters().await {
         Ok(filters) => Ok(Json(filters)),
-        Err(e) => Err(internal_error(&format!("Failed to list filters: {}", e))),
+        Err(e) => Err(failed_error("Failed to list filters", &e)),
     }
 }
 
@@ -369,7 +454,7 @@ async fn create_filter(
             "id": filter_id,
             "message": "Filter created successfully"
         }))),
-        Err(e) => Err(internal_error(&format!("Failed to create filter: {}", e))),
+        Err(e) => Err(failed_error("Failed to create filter", &e)),
     }
 }
 
@@ -397,7 +482,99 @@ async fn delete_filter(
 
     match monitoring_manager.delete_filter(&id).await {
         Ok(_) => Ok(StatusCode::NO_CONTENT),
-        Err(e) => Err(internal_error(&format!("Failed to delete filter: {}", e))),
+        Err(e) => Err(failed_error("Failed to delete filter", &e)),
+    }
+}
+
+// Alert API handlers
+
+async fn list_alerts(
+    State(state): State<ApiState>,
+) -> Result<Json<Vec<Alert>>, (StatusCode, Json<ErrorResponse>)> {
+    debug!("GET /api/v1/alerts");
+
+    let mut monitoring_manager = state.monitoring_manager.write().await;
+
+    match monitoring_manager.list_alerts().await {
+        Ok(alerts) => Ok(Json(alerts)),
+        Err(e) => Err(failed_error("Failed to list alerts", &e)),
+    }
+}
+
+async fn list_alert_rules(
+    State(state): State<ApiState>,
+) -> Result<Json<Vec<AlertRule>>, (StatusCode, Json<ErrorResponse>)> {
+    debug!("GET /api/v1/alerts/rules");
+
+    let mut monitoring_manager = state.monitoring_manager.write().await;
+
+    match monitoring_manager.list_alert_rules().await {
+        Ok(rules) => Ok(Json(rules)),
+        Err(e) => Err(failed_error("Failed to list alert rules", &e)),
+    }
+}
+
+async fn create_alert_rule(
+    State(state): State<ApiState>,
+    Json(rule): Json<AlertRule>,
+) -> Result<(StatusCode, Json<AlertRule>), (StatusCode, Json<ErrorResponse>)> {
+    debug!("POST /api/v1/alerts/rules");
+
+    let mut monitoring_manager = state.monitoring_manager.write().await;
+
+    match monitoring_manager.create_alert_rule(&rule).await {
+        Ok(()) => Ok((StatusCode::CREATED, Json(rule))),
+        Err(SettingsError::Validation(e)) => Err(bad_request_error(&e)),
+        Err(e) => Err(failed_error("Failed to create alert rule", &e)),
+    }
+}
+
+async fn get_alert_rule(
+    Path(id): Path<String>,
+    State(state): State<ApiState>,
+) -> Result<Json<AlertRule>, (StatusCode, Json<ErrorResponse>)> {
+    debug!("GET /api/v1/alerts/rules/{}", id);
+
+    let mut monitoring_manager = state.monitoring_manager.write().await;
+
+    match monitoring_manager.get_alert_rule(&id).await {
+        Ok(Some(rule)) => Ok(Json(rule)),
+        Ok(None) => Err(not_found_error("Alert rule not found")),
+        Err(e) => Err(failed_error("Failed to get alert rule", &e)),
+    }
+}
+
+async fn update_alert_rule(
+    Path(id): Path<String>,
+    State(state): State<ApiState>,
+    Json(rule): Json<AlertRule>,
+) -> Result<Json<AlertRule>, (StatusCode, Json<ErrorResponse>)> {
+    debug!("PUT /api/v1/alerts/rules/{}", id);
+
+    let mut monitoring_manager = state.monitoring_manager.write().await;
+
+    if let Ok(None) = monitoring_manager.get_alert_rule(&id).await {
+        return Err(not_found_error("Alert rule not found"));
+    }
+    match monitoring_manager.update_alert_rule(&id, &rule).await {
+        Ok(()) => Ok(Json(AlertRule { id, ..rule })),
+        Err(SettingsError::Validation(e)) => Err(bad_request_error(&e)),
+        Err(e) => Err(failed_error("Failed to update alert rule", &e)),
+    }
+}
+
+async fn delete_alert_rule(
+    Path(id): Path<String>,
+    State(state): State<ApiState>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    debug!("DELETE /api/v1/alerts/rules/{}", id);
+
+    let mut monitoring_manager = state.monitoring_manager.write().await;
+
+    match monitoring_manager.delete_alert_rule(&id).await {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err(not_found_error("Alert rule not found")),
+        Err(e) => Err(failed_error("Failed to delete alert rule", &e)),
     }
 }
 
@@ -413,7 +590,7 @@ async fn list_configs(
 
     match config_manager.list_configs(query.prefix.as_deref()).await {
         Ok(configs) => Ok(Json(configs)),
-        Err(e) => Err(internal_error(&format!("Failed to list configs: {}", e))),
+        Err(e) => Err(failed_error("Failed to list configs", &e)),
     }
 }
 
@@ -453,10 +630,7 @@ async fn create_config(
         .await
     {
         Ok(config) => Ok(Json(config)),
-        Err(e) => Err(bad_request_error(&format!(
-            "Failed to create config: {}",
-            e
-        ))),
+        Err(e) => Err(rejected_error("Failed to create config", &e)),
     }
 }
 
@@ -474,7 +648,7 @@ async fn delete_config(
         .await
     {
         Ok(_) => Ok(StatusCode::NO_CONTENT),
-        Err(e) => Err(internal_error(&format!("Failed to delete config: {}", e))),
+        Err(e) => Err(failed_error("Failed to delete config", &e)),
     }
 }
 
@@ -488,7 +662,7 @@ async fn validate_config(
 
     match config_manager.validate_config(&config).await {
         Ok(result) => Ok(Json(result)),
-        Err(e) => Err(bad_request_error(&format!("Validation failed: {}", e))),
+        Err(e) => Err(rejected_error("Validation failed", &e)),
     }
 }
 
@@ -519,7 +693,7 @@ async fn get_history(
 
     match history_manager.list_history(&path, query.limit).await {
         Ok(history) => Ok(Json(history)),
-        Err(e) => Err(internal_error(&format!("Failed to get history: {}", e))),
+        Err(e) => Err(failed_error("Failed to get history", &e)),
     }
 }
 
@@ -558,7 +732,7 @@ async fn rollback_to_version(
         .await
     {
         Ok(config) => Ok(Json(config)),
-        Err(e) => Err(bad_request_error(&format!("Rollback failed: {}", e))),
+        Err(e) => Err(rejected_error("Rollback failed", &e)),
     }
 }
 
@@ -617,32 +791,135 @@ async fn health_check() -> StatusCode {
     StatusCode::OK
 }
 
+async fn get_log_levels() -> Result<Json<LogLevels>, (StatusCode, Json<ErrorResponse>)> {
+    debug!("GET /api/v1/logging/levels");
+
+    logging::log_levels()
+        .map(Json)
+        .ok_or_else(|| internal_error("Logging is not initialized"))
+}
+
+async fn update_log_levels(
+    Json(levels): Json<LogLevels>,
+) -> Result<Json<LogLevels>, (StatusCode, Json<ErrorResponse>)> {
+    debug!("PUT /api/v1/logging/levels");
+
+    if let Err(e) = levels.directives() {
+        return Err(bad_request_error(&e));
+    }
+    match logging::set_log_levels(levels.clone()) {
+        Ok(()) => {
+            info!(
+                "Log level set to {} with {} module level(s)",
+                levels.level,
+                levels.modules.len()
+            );
+            Ok(Json(levels))
+        }
+        Err(e) => Err(internal_error(&format!("Failed to set log levels: {}", e))),
+    }
+}
+
+// Event API handlers
+
+async fn stream_events(
+    Query(query): Query<EventsQuery>,
+    State(state): State<ApiState>,
+) -> Result<
+    Sse<impl futures::Stream<Item = Result<axum::response::sse::Event, axum::Error>>>,
+    (StatusCode, Json<ErrorResponse>),
+> {
+    debug!("GET /api/v1/events with query: {:?}", query);
+
+    let kinds = query
+        .kinds
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|kind| !kind.is_empty())
+        .map(|kind| kind.parse::<EventKind>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| bad_request_error(&e))?;
+
+    let stream = settings_events::event_stream(state.event_hub.subscribe(), kinds);
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
 // Node API handlers
 async fn list_nodes(
-    Query(query): Query<ResourceQuery>,
+    Query(query): Query<NodeListQuery>,
+    Query(list): Query<ListQuery>,
     State(_state): State<ApiState>,
 ) -> Result<Json<NodeListResponse>, (StatusCode, Json<ErrorResponse>)> {
-    debug!("GET /api/v1/nodes with query: {:?}", query);
+    debug!("GET /api/v1/nodes with query: {:?} {:?}", query, list);
 
     // Fetch from monitoring server data via etcd or direct integration
-    match fetch_all_nodes_from_monitoring_server().await {
-        Ok(nodes) => {
-            let filtered_nodes = if let Some(filter) = query.filter {
-                nodes
-                    .into_iter()
-                    .filter(|node| node.node_name.contains(&filter))
-                    .collect()
-            } else {
-                nodes
-            };
+    let nodes = fetch_all_nodes_from_monitoring_server()
+        .await
+        .map_err(|e| internal_error(&format!("Failed to fetch nodes: {}", e)))?;
+    let registrations = fetch_node_registrations().await;
+
+    let (nodes, meta) =
+        select_nodes(nodes, &registrations, &query, &list).map_err(|e| bad_request_error(&e))?;
+    Ok(Json(NodeListResponse {
+        nodes,
+        total: meta.total,
+        offset: meta.offset,
+        limit: meta.limit,
+        next_offset: meta.next_offset,
+    }))
+}
+
+/// Filter, sort and paginate the nodes reported by the monitoring server
+///
+/// Nodes missing from the registry have no status, role or labels and never
+/// match selectors on them.
+fn select_nodes(
+    nodes: Vec<NodeInfo>,
+    registrations: &HashMap<String, NodeRegistration>,
+    query: &NodeListQuery,
+    list: &ListQuery,
+) -> Result<(Vec<NodeInfo>, ListMeta), String> {
+    let selector = list.label_selector()?;
+    let sort = list.sort_field(NODE_SORT_FIELDS, "name")?;
+    let unregistered = NodeRegistration::default();
+    let registration =
+        |node: &NodeInfo| registrations.get(&node.node_name).unwrap_or(&unregistered);
+
+    let mut nodes: Vec<NodeInfo> = nodes
+        .into_iter()
+        .filter(|node| {
+            let reg = registration(node);
+            query
+                .filter
+                .as_ref()
+                .is_none_or(|filter| node.node_name.contains(filter))
+                && query
+                    .status
+                    .as_ref()
+                    .is_none_or(|status| reg.status.eq_ignore_ascii_case(status))
+                && query
+                    .role
+                    .as_ref()
+                    .is_none_or(|role| reg.role.eq_ignore_ascii_case(role))
+                && listing::matches_labels(&selector, &reg.labels)
+        })
+        .collect();
+
+    nodes.sort_by(|a, b| {
+        let ordering = match sort {
+            "status" => registration(a).status.cmp(&registration(b).status),
+            "role" => registration(a).role.cmp(&registration(b).role),
+            "cpu_usage" => a.cpu_usage.total_cmp(&b.cpu_usage),
+            "mem_usage" => a.mem_usage.total_cmp(&b.mem_usage),
+            _ => std::cmp::Ordering::Equal,
+        };
+        list.order
+            .apply(ordering.then_with(|| a.node_name.cmp(&b.node_name)))
+    });
 
-            Ok(Json(NodeListResponse {
-                total: filtered_nodes.len(),
-                nodes: filtered_nodes,
-            }))
-        }
-        Err(e) => Err(internal_error(&format!("Failed to fetch nodes: {}", e))),
-    }
+    Ok(list.paginate(nodes))
 }
 
 async fn get_node(
@@ -861,7 +1138,7 @@ async fn get_pod_metrics(
         }
         Err(e) => {
             error!("Failed to get pod metrics for node {}: {}", node_name, e);
-            Err(internal_error(&format!("Failed to get pod metrics: {}", e)))
+            Err(failed_error("Failed to get pod metrics", &e))
         }
     }
 }
@@ -894,6 +1171,39 @@ async fn fetch_all_nodes_from_monitoring_server() -> Result<Vec<NodeInfo>, Strin
         .map_err(|e| format!("ETCD error: {}", e))
 }
 
+/// Status, role and labels of the registered nodes by hostname
+///
+/// An unreadable registry leaves every node unregistered.
+async fn fetch_node_registrations() -> HashMap<String, NodeRegistration> {
+    let entries = match common::etcd::get_all_with_prefix(NODE_REGISTRY_PREFIX).await {
+        Ok(entries) => entries,
+        Err(e) => {
+            debug!("Failed to read node registry: {}", e);
+            return HashMap::new();
+        }
+    };
+    entries
+        .into_iter()
+        .filter_map(|(_, value)| node_registration(&value))
+        .collect()
+}
+
+fn node_registration(value: &str) -> Option<(String, NodeRegistration)> {
+    use common::nodeagent::fromapiserver::{NodeRole, NodeStatus};
+
+    let node: common::apiserver::NodeInfo = serde_json::from_str(value).ok()?;
+    let status = NodeStatus::try_from(node.status).unwrap_or(NodeStatus::Unspecified);
+    let role = NodeRole::try_from(node.node_role).unwrap_or(NodeRole::Unspecified);
+    Some((
+        node.hostname,
+        NodeRegistration {
+            status: format!("{:?}", status),
+            role: format!("{:?}", role).to_lowercase(),
+            labels: node.metadata,
+        },
+    ))
+}
+
 async fn fetch_node_from_monitoring_server(name: &str) -> Result<Option<NodeInfo>, String> {
     match crate::monitoring_etcd::get_node_info(name).await {
         Ok(node) => Ok(Some(node)),
@@ -930,39 +1240,72 @@ async fn fetch_container_from_monitoring_server(id: &str) -> Result<Option<Conta
     }
 }
 
+fn error_response(error: ErrorResponse) -> (StatusCode, Json<ErrorResponse>) {
+    let status =
+        StatusCode::from_u16(error.code.http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    (status, Json(error))
+}
+
 fn not_found_error(message: &str) -> (StatusCode, Json<ErrorResponse>) {
     debug!("Not found error: {}", message);
-    (
-        StatusCode::NOT_FOUND,
-        Json(ErrorResponse {
-            error: message.to_string(),
-            details: None,
-        }),
-    )
+    error_response(ErrorResponse::not_found(message))
 }
 
 fn bad_request_error(message: &str) -> (StatusCode, Json<ErrorResponse>) {
     debug!("Bad request error: {}", message);
-    (
-        StatusCode::BAD_REQUEST,
-        Json(ErrorResponse {
-            error: message.to_string(),
-            details: None,
-        }),
-    )
+    error_response(ErrorResponse::validation(message))
 }
 
 fn internal_error(message: &str) -> (StatusCode, Json<ErrorResponse>) {
     error!("Internal server error: {}", message);
-    (
-        StatusCode::INTERNAL_SERVER_ERROR,
-        Json(ErrorResponse {
-            error: "Internal server error".to_string(),
-            details: Some(serde_json::json!({ "details": message })),
-        }),
+    error_response(
+        ErrorResponse::internal("Internal server error")
+            .with_details(serde_json::json!({ "details": message })),
     )
 }
 
+/// Code chosen by the variant of an error, `None` if it does not choose one
+fn settings_error_code(error: &SettingsError) -> Option<ErrorCode> {
+    match error {
+        SettingsError::Validation(_) => Some(ErrorCode::Validation),
+        SettingsError::Conflict(_) => Some(ErrorCode::Conflict),
+        SettingsError::Storage(StorageError::ConnectionFailed(_)) => {
+            Some(ErrorCode::DependencyUnavailable)
+        }
+        SettingsError::Storage(StorageError::KeyNotFound(_) | StorageError::NotFound(_)) => {
+            Some(ErrorCode::NotFound)
+        }
+        _ => None,
+    }
+}
+
+/// Rejected request, unless the variant of `error` chooses another code
+fn rejected_error(context: &str, error: &SettingsError) -> (StatusCode, Json<ErrorResponse>) {
+    let message = format!("{}: {}", context, error);
+    match settings_error_code(error) {
+        Some(code) => typed_error(code, &message),
+        None => bad_request_error(&message),
+    }
+}
+
+/// Failed request, unless the variant of `error` chooses another code
+fn failed_error(context: &str, error: &SettingsError) -> (StatusCode, Json<ErrorResponse>) {
+    let message = format!("{}: {}", context, error);
+    match settings_error_code(error) {
+        Some(code) => typed_error(code, &message),
+        None => internal_error(&message),
+    }
+}
+
+fn typed_error(code: ErrorCode, message: &str) -> (StatusCode, Json<ErrorResponse>) {
+    if code == ErrorCode::DependencyUnavailable {
+        warn!("Dependency unavailable: {}", message);
+    } else {
+        debug!("Request error: {}", message);
+    }
+    error_response(ErrorResponse::new(code, message))
+}
+
 async fn get_all_node_metrics(
     State(state): State<ApiState>,
 ) -> Result<Json<Vec<NodeInfo>>, (StatusCode, Json<ErrorResponse>)> {
@@ -977,10 +1320,7 @@ async fn get_all_node_metrics(
         }
         Err(e) => {
             error!("Failed to get node metrics: {}", e);
-            Err(internal_error(&format!(
-                "Failed to get node metrics: {}",
-                e
-            )))
+            Err(failed_error("Failed to get node metrics", &e))
         }
     }
 }
@@ -1010,10 +1350,7 @@ async fn list_containers(
 
             Ok(Json(filtered_containers))
         }
-        Err(e) => Err(internal_error(&format!(
-            "Failed to fetch containers: {}",
-            e
-        ))),
+        Err(e) => Err(failed_error("Failed to fetch containers", &e)),
     }
 }
 
@@ -1041,7 +1378,7 @@ async fn get_container(
             Ok(Json(container))
         }
         Ok(None) => Err(not_found_error("Container not found")),
-        Err(e) => Err(internal_error(&format!("Failed to get container: {}", e))),
+        Err(e) => Err(failed_error("Failed to get container", &e)),
     }
 }
 
@@ -1076,10 +1413,7 @@ async fn get_containers_by_node(
             );
             Ok(Json(containers))
         }
-        Err(e) => Err(internal_error(&format!(
-            "Failed to get containers by node: {}",
-            e
-        ))),
+        Err(e) => Err(failed_error("Failed to get containers by node", &e)),
     }
 }
 #[allow(dead_code)]
@@ -1198,10 +1532,7 @@ async fn get_all_container_metrics(
         }
         Err(e) => {
             error!("Failed to get container metrics: {}", e);
-            Err(internal_error(&format!(
-                "Failed to get container metrics: {}",
-                e
-            )))
+            Err(failed_error("Failed to get container metrics", &e))
         }
     }
 }
@@ -1220,7 +1551,7 @@ async fn get_all_soc_metrics(
         }
         Err(e) => {
             error!("Failed to get SoC metrics: {}", e);
-            Err(internal_error(&format!("Failed to get SoC metrics: {}", e)))
+            Err(failed_error("Failed to get SoC metrics", &e))
         }
     }
 }
@@ -1239,10 +1570,7 @@ async fn get_all_board_metrics(
         }
         Err(e) => {
             error!("Failed to get board metrics: {}", e);
-            Err(internal_error(&format!(
-                "Failed to get board metrics: {}",
-                e
-            )))
+            Err(failed_error("Failed to get board metrics", &e))
         }
     }
 }
@@ -1345,7 +1673,7 @@ async fn get_node_metric_by_name(
     match monitoring_manager.get_node_metric_by_name(&name).await {
         Ok(Some(node)) => Ok(Json(node)),
         Ok(None) => Err(not_found_error("Node metric not found")),
-        Err(e) => Err(internal_error(&format!("Failed to get node metric: {}", e))),
+        Err(e) => Err(failed_error("Failed to get node metric", &e)),
     }
 }
 
@@ -1360,10 +1688,7 @@ async fn get_container_metric_by_id(
     match monitoring_manager.get_container_metric_by_id(&id).await {
         Ok(Some(container)) => Ok(Json(container)),
         Ok(None) => Err(not_found_error("Container metric not found")),
-        Err(e) => Err(internal_error(&format!(
-            "Failed to get container metric: {}",
-            e
-        ))),
+        Err(e) => Err(failed_error("Failed to get container metric", &e)),
     }
 }
 
@@ -1483,6 +1808,7 @@ mod tests {
             config_manager,
             history_manager,
             monitoring_manager,
+            event_hub: EventHub::new(),
         }
     }
 
@@ -1579,6 +1905,40 @@ mod tests {
         assert_eq!(response.status_code(), StatusCode::OK);
     }
 
+    #[tokio::test]
+    async fn test_update_log_levels_rejects_invalid_level() {
+        let server = create_test_server().await;
+        let response = server
+            .put("/api/v1/logging/levels")
+            .json(&json!({
+                "level": "info",
+                "modules": {"settingsservice::settings_api": "verbose"}
+            }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_apply_yaml_rejects_oversized_body() {
+        let server = create_test_server().await;
+        let max_body_kb = common::setting::get_config().limits.max_body_kb;
+        let yaml = "a".repeat((max_body_kb * 1024 + 1) as usize);
+        let response = server.post("/api/v1/yaml").text(yaml).await;
+
+        assert_eq!(response.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["code"], "PAYLOAD_TOO_LARGE");
+        assert!(body["message"].is_string());
+        assert!(body["correlation_id"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_stream_events_rejects_unknown_kind() {
+        let server = create_test_server().await;
+        let response = server.get("/api/v1/events?kinds=node,pod").await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    }
+
     #[tokio::test]
     async fn test_system_status() {
         let server = create_test_server().await;
@@ -1595,16 +1955,49 @@ mod tests {
     async fn test_error_helpers() {
         let not_found = not_found_error("Test not found");
         assert_eq!(not_found.0, StatusCode::NOT_FOUND);
-        assert_eq!(not_found.1.error, "Test not found");
+        assert_eq!(not_found.1.message, "Test not found");
 
         let bad_request = bad_request_error("Bad request test");
         assert_eq!(bad_request.0, StatusCode::BAD_REQUEST);
-        assert_eq!(bad_request.1.error, "Bad request test");
+        assert_eq!(bad_request.1.message, "Bad request test");
 
         let internal = internal_error("Internal error test");
         assert_eq!(internal.0, StatusCode::INTERNAL_SERVER_ERROR);
-        assert_eq!(internal.1.error, "Internal server error");
+        assert_eq!(internal.1.message, "Internal server error");
         assert!(internal.1.details.is_some());
+
+        // the message does not choose the code, the variant of the error does
+        let bad_request = bad_request_error("Configuration already exists: a/b");
+        assert_eq!(bad_request.1.code, ErrorCode::Validation);
+
+        let conflict = rejected_error(
+            "Failed to create config",
+            &SettingsError::Conflict("Configuration already exists: a/b".to_string()),
+        );
+        assert_eq!(conflict.0, StatusCode::CONFLICT);
+        assert_eq!(
+            conflict.1.message,
+            "Failed to create config: Conflict: Configuration already exists: a/b"
+        );
+
+        let rejected = rejected_error(
+            "Failed to create config",
+            &SettingsError::Config("invalid content".to_string()),
+        );
+        assert_eq!(rejected.0, StatusCode::BAD_REQUEST);
+
+        let unavailable = failed_error(
+            "Failed to list configs",
+            &SettingsError::Storage(StorageError::ConnectionFailed("refused".to_string())),
+        );
+        assert_eq!(unavailable.0, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(unavailable.1.code, ErrorCode::DependencyUnavailable);
+
+        let failed = failed_error(
+            "Failed to list configs",
+            &SettingsError::Storage(StorageError::OperationFailed("Put failed".to_string())),
+        );
+        assert_eq!(failed.0, StatusCode::INTERNAL_SERVER_ERROR);
     }
 
     #[tokio::test]
@@ -1724,16 +2117,15 @@ mod tests {
 
     #[tokio::test]
     async fn test_error_response_serialization() {
-        let response = ErrorResponse {
-            error: "Something went wrong".to_string(),
-            details: Some(json!({"code": 500, "description": "Internal error"})),
-        };
+        let response = ErrorResponse::internal("Something went wrong")
+            .with_details(json!({"code": 500, "description": "Internal error"}));
 
         let json = serde_json::to_string(&response);
         assert!(json.is_ok());
 
         let json_value: serde_json::Value = serde_json::from_str(&json.unwrap()).unwrap();
-        assert_eq!(json_value["error"], "Something went wrong");
+        assert_eq!(json_value["code"], "INTERNAL");
+        assert_eq!(json_value["message"], "Something went wrong");
         assert!(json_value["details"].is_object());
         assert_eq!(json_value["details"]["code"], 500);
     }
@@ -1937,13 +2329,13 @@ mod tests {
         let special_message = "Error with special chars: {}[]()!@#$%^&*";
 
         let not_found = not_found_error(special_message);
-        assert_eq!(not_found.1.error, special_message);
+        assert_eq!(not_found.1.message, special_message);
 
         let bad_request = bad_request_error(special_message);
-        assert_eq!(bad_request.1.error, special_message);
+        assert_eq!(bad_request.1.message, special_message);
 
         let internal = internal_error(special_message);
-        assert_eq!(internal.1.error, "Internal server error");
+        assert_eq!(internal.1.message, "Internal server error");
         let details = internal.1.details.clone().unwrap();
         assert_eq!(details["details"], special_message);
     }
@@ -1951,13 +2343,13 @@ mod tests {
     #[tokio::test]
     async fn test_error_functions_with_empty_strings() {
         let not_found = not_found_error("");
-        assert_eq!(not_found.1.error, "");
+        assert_eq!(not_found.1.message, "");
 
         let bad_request = bad_request_error("");
-        assert_eq!(bad_request.1.error, "");
+        assert_eq!(bad_request.1.message, "");
 
         let internal = internal_error("");
-        assert_eq!(internal.1.error, "Internal server error");
+        assert_eq!(internal.1.message, "Internal server error");
         let details = internal.1.details.clone().unwrap();
         assert_eq!(details["details"], "");
     }
@@ -1978,28 +2370,28 @@ mod tests {
 
     #[test]
     fn test_error_response_structure() {
-        let response = ErrorResponse {
-            error: "Error occurred".to_string(),
-            details: Some(json!({"additional": "info"})),
-        };
+        let response =
+            ErrorResponse::validation("Error occurred").with_details(json!({"additional": "info"}));
 
         let serialized = serde_json::to_value(&response).unwrap();
         assert!(serialized.is_object());
-        assert_eq!(serialized.as_object().unwrap().len(), 2);
-        assert!(serialized.as_object().unwrap().contains_key("error"));
+        assert_eq!(serialized.as_object().unwrap().len(), 4);
+        assert!(serialized.as_object().unwrap().contains_key("code"));
+        assert!(serialized.as_object().unwrap().contains_key("message"));
         assert!(serialized.as_object().unwrap().contains_key("details"));
+        assert!(serialized
+            .as_object()
+            .unwrap()
+            .contains_key("correlation_id"));
     }
 
     #[test]
     fn test_error_response_without_details() {
-        let response = ErrorResponse {
-            error: "Simple error".to_string(),
-            details: None,
-        };
+        let response = ErrorResponse::validation("Simple error");
 
         let serialized = serde_json::to_value(&response).unwrap();
         assert!(serialized.is_object());
-        assert!(serialized.as_object().unwrap().contains_key("error"));
+        assert!(serialized.as_object().unwrap().contains_key("message"));
         assert!(serialized["details"].is_null());
     }
 
@@ -2119,12 +2511,9 @@ mod tests {
         let debug_str = format!("{:?}", success_response);
         assert!(debug_str.contains("SuccessResponse"));
 
-        let error_response = ErrorResponse {
-            error: "Error".to_string(),
-            details: None,
-        };
+        let error_response = ErrorResponse::validation("Error");
         let debug_str = format!("{:?}", error_response);
-        assert!(debug_str.contains("ErrorResponse"));
+        assert!(debug_str.contains("ApiError"));
     }
 
     // Test serialization edge cases
@@ -2447,6 +2836,52 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_alert_rule_handlers() {
+        let server = create_test_server().await;
+        let rule = json!({
+            "id": "web-memory",
+            "metric": "memory_percent",
+            "operator": "ge",
+            "threshold": 90.0,
+            "severity": "critical",
+            "target": {"kind": "container", "name": "web"}
+        });
+
+        let response = server.post("/api/v1/alerts/rules").json(&rule).await;
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+        let response = server.post("/api/v1/alerts/rules").json(&rule).await;
+        assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+        let mut invalid = rule.clone();
+        invalid["id"] = json!("node-memory");
+        invalid["target"]["kind"] = json!("node");
+        let response = server.post("/api/v1/alerts/rules").json(&invalid).await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+        let mut updated = rule.clone();
+        updated["threshold"] = json!(80.0);
+        let response = server
+            .put("/api/v1/alerts/rules/web-memory")
+            .json(&updated)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let response = server
+            .put("/api/v1/alerts/rules/missing")
+            .json(&updated)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+        let rules: Vec<AlertRule> = server.get("/api/v1/alerts/rules").await.json();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].threshold, 80.0);
+
+        let response = server.delete("/api/v1/alerts/rules/web-memory").await;
+        assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+        let response = server.get("/api/v1/alerts/rules/web-memory").await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    }
+
     #[tokio::test]
     async fn test_list_configs_handler() {
         let server = create_test_server().await;
@@ -2905,4 +3340,120 @@ mod tests {
         // Should return an error for non-existent config/version
         assert!(result.is_err());
     }
+
+    fn monitored_node(name: &str, cpu_usage: f64) -> NodeInfo {
+        NodeInfo {
+            node_name: name.to_string(),
+            cpu_usage,
+            cpu_count: 4,
+            gpu_count: 0,
+            used_memory: 0,
+            total_memory: 0,
+            mem_usage: 0.0,
+            rx_bytes: 0,
+            tx_bytes: 0,
+            read_bytes: 0,
+            write_bytes: 0,
+            os: "Linux".to_string(),
+            arch: "x86_64".to_string(),
+            ip: "127.0.0.1".to_string(),
+        }
+    }
+
+    fn list_query(params: &str) -> ListQuery {
+        serde_json::from_str(params).unwrap()
+    }
+
+    #[test]
+    fn test_select_nodes_filters_sorts_and_paginates() {
+        let nodes: Vec<NodeInfo> = (0..5)
+            .map(|i| monitored_node(&format!("node-{}", i), (5 - i) as f64 * 10.0))
+            .collect();
+        let registration = |status: &str, role: &str, zone: &str| NodeRegistration {
+            status: status.to_string(),
+            role: role.to_string(),
+            labels: HashMap::from([("zone".to_string(), zone.to_string())]),
+        };
+        let registrations = HashMap::from([
+            (
+                "node-0".to_string(),
+                registration("Ready", "master", "front"),
+            ),
+            (
+                "node-1".to_string(),
+                registration("Ready", "nodeagent", "front"),
+            ),
+            (
+                "node-2".to_string(),
+                registration("NotReady", "nodeagent", "rear"),
+            ),
+            (
+                "node-3".to_string(),
+                registration("Ready", "nodeagent", "rear"),
+            ),
+        ]);
+
+        let query = NodeListQuery {
+            status: Some("ready".to_string()),
+            role: Some("nodeagent".to_string()),
+            ..Default::default()
+        };
+        let (page, meta) = select_nodes(
+            nodes.clone(),
+            &registrations,
+            &query,
+            &list_query(r#"{"sort": "cpu_usage", "limit": 1}"#),
+        )
+        .unwrap();
+        assert_eq!(meta.total, 2);
+        assert_eq!(meta.next_offset, Some(1));
+        assert_eq!(page[0].node_name, "node-3");
+
+        let (page, meta) = select_nodes(
+            nodes.clone(),
+            &registrations,
+            &NodeListQuery::default(),
+            &list_query(r#"{"label": "zone=front", "order": "desc"}"#),
+        )
+        .unwrap();
+        assert_eq!(meta.total, 2);
+        assert_eq!(page[0].node_name, "node-1");
+        assert_eq!(page[1].node_name, "node-0");
+
+        // Unregistered nodes remain listed without selectors
+        let (page, _) = select_nodes(
+            nodes.clone(),
+            &registrations,
+            &NodeListQuery::default(),
+            &ListQuery::default(),
+        )
+        .unwrap();
+        assert_eq!(page.len(), 5);
+
+        assert!(select_nodes(
+            nodes,
+            &registrations,
+            &NodeListQuery::default(),
+            &list_query(r#"{"sort": "ip"}"#),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_node_registration_from_registry_entry() {
+        let entry = common::apiserver::NodeInfo {
+            hostname: "hpc".to_string(),
+            status: 3,
+            node_role: 2,
+            metadata: HashMap::from([("zone".to_string(), "front".to_string())]),
+            ..Default::default()
+        };
+        let (hostname, registration) =
+            node_registration(&serde_json::to_string(&entry).unwrap()).unwrap();
+        assert_eq!(hostname, "hpc");
+        assert_eq!(registration.status, "Ready");
+        assert_eq!(registration.role, "nodeagent");
+        assert_eq!(registration.labels.get("zone"), Some(&"front".to_string()));
+        assert!(node_registration("not json").is_none());
+    }
 }
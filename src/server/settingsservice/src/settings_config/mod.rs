@@ -197,7 +197,7 @@ impl ConfigManager {
         // Check if config already exists
         let key = config_key(path);
         if self.storage.get(&key).await?.is_some() {
-            return Err(SettingsError::Config(format!(
+            return Err(SettingsError::Conflict(format!(
                 "Configuration already exists: {}",
                 path
             )));
@@ -767,10 +767,10 @@ mod tests {
             .await;
 
         assert!(result.is_err());
-        if let Err(SettingsError::Config(msg)) = result {
+        if let Err(SettingsError::Conflict(msg)) = result {
             assert!(msg.contains("Configuration already exists"));
         } else {
-            panic!("Expected Config error");
+            panic!("Expected Conflict error");
         }
     }
 
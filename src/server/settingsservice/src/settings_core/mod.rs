@@ -14,6 +14,11 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{debug, error, info};
 
+/// Readiness check of the default configuration schemas
+const CHECK_SCHEMAS: &str = "schemas";
+/// Interval of the etcd readiness probe
+const ETCD_PROBE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
 /// System status information
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -95,9 +100,18 @@ impl CoreManager {
     /// Start all services
     pub async fn start_services(&mut self) -> Result<(), SettingsError> {
         info!("Starting Settings Service components");
+        common::health::init(
+            "settingsservice",
+            &[common::health::CHECK_ETCD, CHECK_SCHEMAS],
+        );
+        tokio::spawn(common::health::watch_etcd(ETCD_PROBE_INTERVAL));
 
         // Load default schemas
-        self.load_default_schemas().await?;
+        if let Err(e) = self.load_default_schemas().await {
+            common::health::set_not_ready(CHECK_SCHEMAS, e.to_string());
+            return Err(e);
+        }
+        common::health::set_ready(CHECK_SCHEMAS);
 
         // Start API server
         if let Some(api_server) = self.api_server.take() {
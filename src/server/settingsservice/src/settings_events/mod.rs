@@ -0,0 +1,355 @@
+// SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Live events for the Web GUI
+//!
+//! The key-value store has no watch API, so the watcher polls the keys the
+//! other components write and publishes the differences between snapshots:
+//! - `cluster/nodes/<hostname>` (ApiServer node registry): node status
+//! - `/pullpiri/metrics/containers/<id>` (MonitoringServer): container status
+//! - `/scenario/<name>/state` (StateManager): scenario state
+//!
+//! Polling only runs while at least one client is subscribed. A new client
+//! receives changes from then on and reads the current state over REST.
+use axum::response::sse::Event;
+use chrono::{DateTime, Utc};
+use common::monitoringserver::ContainerInfo;
+use common::nodeagent::fromapiserver::NodeStatus;
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{debug, warn};
+
+const NODE_PREFIX: &str = "cluster/nodes/";
+const CONTAINER_PREFIX: &str = "/pullpiri/metrics/containers/";
+const SCENARIO_PREFIX: &str = "/scenario/";
+const SCENARIO_SUFFIX: &str = "/state";
+
+/// Events buffered per client before the oldest are dropped
+const EVENT_CAPACITY: usize = 256;
+
+/// Default time between two snapshots
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Source of an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Node,
+    Container,
+    Scenario,
+}
+
+impl EventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::Node => "node",
+            EventKind::Container => "container",
+            EventKind::Scenario => "scenario",
+        }
+    }
+}
+
+impl std::str::FromStr for EventKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "node" => Ok(EventKind::Node),
+            "container" => Ok(EventKind::Container),
+            "scenario" => Ok(EventKind::Scenario),
+            _ => Err(format!("unknown event kind '{}'", s)),
+        }
+    }
+}
+
+/// Change of a node, container or scenario
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LiveEvent {
+    pub kind: EventKind,
+    /// Node hostname, container name or scenario name
+    pub name: String,
+    /// State before the change, `None` if it just appeared
+    pub previous: Option<String>,
+    /// State after the change, `None` if it was removed
+    pub current: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// State of every watched resource
+type Snapshot = BTreeMap<(EventKind, String), String>;
+
+/// Extracts name and state from a key-value pair of a watched prefix
+type StateParser = fn(&str, &str) -> Option<(String, String)>;
+
+/// Publishes live events to all subscribed clients
+#[derive(Clone)]
+pub struct EventHub {
+    sender: broadcast::Sender<LiveEvent>,
+}
+
+impl Default for EventHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventHub {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CAPACITY);
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LiveEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Send an event to the current subscribers
+    pub fn publish(&self, event: LiveEvent) {
+        // Fails only when nobody is subscribed
+        let _ = self.sender.send(event);
+    }
+
+    /// Poll the stores and publish changes until the task is aborted
+    pub fn spawn_watcher(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let hub = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let mut snapshot: Option<Snapshot> = None;
+            loop {
+                ticker.tick().await;
+                if hub.sender.receiver_count() == 0 {
+                    snapshot = None;
+                    continue;
+                }
+
+                let current = load_snapshot(snapshot.as_ref()).await;
+                if let Some(previous) = &snapshot {
+                    for event in diff_snapshots(previous, &current, Utc::now()) {
+                        debug!("Live event: {:?}", event);
+                        hub.publish(event);
+                    }
+                }
+                snapshot = Some(current);
+            }
+        })
+    }
+}
+
+/// Turn a subscription into a stream of server-sent events
+///
+/// Events of other kinds than `kinds` are skipped, all kinds pass if it is
+/// empty. A client that falls behind loses the oldest events.
+pub fn event_stream(
+    receiver: broadcast::Receiver<LiveEvent>,
+    kinds: Vec<EventKind>,
+) -> impl Stream<Item = Result<Event, axum::Error>> {
+    futures::stream::unfold(receiver, move |mut receiver| {
+        let kinds = kinds.clone();
+        async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) if kinds.is_empty() || kinds.contains(&event.kind) => {
+                        let sse = Event::default()
+                            .event(event.kind.as_str())
+                            .json_data(&event);
+                        return Some((sse, receiver));
+                    }
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Live event client lagged, {} events dropped", skipped);
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }
+    })
+}
+
+/// Events for every resource that appeared, changed state or disappeared
+pub fn diff_snapshots(
+    previous: &Snapshot,
+    current: &Snapshot,
+    timestamp: DateTime<Utc>,
+) -> Vec<LiveEvent> {
+    let event = |(kind, name): &(EventKind, String),
+                 previous: Option<&String>,
+                 current: Option<&String>| {
+        LiveEvent {
+            kind: *kind,
+            name: name.clone(),
+            previous: previous.cloned(),
+            current: current.cloned(),
+            timestamp,
+        }
+    };
+
+    let mut events: Vec<LiveEvent> = current
+        .iter()
+        .filter(|(key, state)| previous.get(*key) != Some(*state))
+        .map(|(key, state)| event(key, previous.get(key), Some(state)))
+        .collect();
+    events.extend(
+        previous
+            .iter()
+            .filter(|(key, _)| !current.contains_key(*key))
+            .map(|(key, state)| event(key, Some(state), None)),
+    );
+    events
+}
+
+/// Read the state of all watched resources
+///
+/// If a store cannot be read, its resources keep their previous state so
+/// an outage is not reported as everything being removed.
+async fn load_snapshot(previous: Option<&Snapshot>) -> Snapshot {
+    let mut snapshot = Snapshot::new();
+    let sources: [(EventKind, &str, StateParser); 3] = [
+        (EventKind::Node, NODE_PREFIX, node_state),
+        (EventKind::Container, CONTAINER_PREFIX, container_state),
+        (EventKind::Scenario, SCENARIO_PREFIX, scenario_state),
+    ];
+
+    for (kind, prefix, parse) in sources {
+        match common::etcd::get_all_with_prefix(prefix).await {
+            Ok(kvs) => {
+                for (key, value) in kvs {
+                    if let Some((name, state)) = parse(&key, &value) {
+                        snapshot.insert((kind, name), state);
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("Failed to read {} states: {}", kind.as_str(), e);
+                if let Some(previous) = previous {
+                    snapshot.extend(
+                        previous
+                            .iter()
+                            .filter(|((k, _), _)| *k == kind)
+                            .map(|(key, state)| (key.clone(), state.clone())),
+                    );
+                }
+            }
+        }
+    }
+    snapshot
+}
+
+/// Hostname and status of a node registry entry
+fn node_state(key: &str, value: &str) -> Option<(String, String)> {
+    let hostname = key.strip_prefix(NODE_PREFIX)?;
+    let node: serde_json::Value = serde_json::from_str(value).ok()?;
+    let status = node.get("status")?.as_i64()?;
+    let status = NodeStatus::try_from(status as i32).unwrap_or(NodeStatus::Unspecified);
+    Some((hostname.to_string(), format!("{:?}", status)))
+}
+
+/// Name and status of a container reported by a nodeagent
+fn container_state(_key: &str, value: &str) -> Option<(String, String)> {
+    let container: ContainerInfo = serde_json::from_str(value).ok()?;
+    let name = container
+        .names
+        .first()
+        .map(|n| n.trim_start_matches('/').to_string())
+        .unwrap_or(container.id);
+    let status = container.state.get("Status")?.clone();
+    Some((name, status))
+}
+
+/// Name and state of a scenario stored by the StateManager
+fn scenario_state(key: &str, value: &str) -> Option<(String, String)> {
+    let name = key
+        .strip_prefix(SCENARIO_PREFIX)?
+        .strip_suffix(SCENARIO_SUFFIX)?;
+    Some((name.to_string(), value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    fn snapshot(entries: &[(EventKind, &str, &str)]) -> Snapshot {
+        entries
+            .iter()
+            .map(|(kind, name, state)| ((*kind, name.to_string()), state.to_string()))
+            .collect()
+    }
+
+    fn event(
+        kind: EventKind,
+        name: &str,
+        previous: Option<&str>,
+        current: Option<&str>,
+    ) -> LiveEvent {
+        LiveEvent {
+            kind,
+            name: name.to_string(),
+            previous: previous.map(str::to_string),
+            current: current.map(str::to_string),
+            timestamp: DateTime::<Utc>::default(),
+        }
+    }
+
+    #[test]
+    fn test_diff_snapshots() {
+        let previous = snapshot(&[
+            (EventKind::Node, "hpc", "Ready"),
+            (EventKind::Container, "helloworld", "running"),
+            (EventKind::Scenario, "helloworld", "waiting"),
+        ]);
+        let current = snapshot(&[
+            (EventKind::Node, "hpc", "NotReady"),
+            (EventKind::Scenario, "helloworld", "waiting"),
+            (EventKind::Scenario, "nightly", "idle"),
+        ]);
+
+        let events = diff_snapshots(&previous, &current, DateTime::<Utc>::default());
+        assert_eq!(
+            events,
+            vec![
+                event(EventKind::Node, "hpc", Some("Ready"), Some("NotReady")),
+                event(EventKind::Scenario, "nightly", None, Some("idle")),
+                event(EventKind::Container, "helloworld", Some("running"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_states() {
+        assert_eq!(
+            node_state("cluster/nodes/hpc", r#"{"hostname":"hpc","status":3}"#),
+            Some(("hpc".to_string(), "Ready".to_string()))
+        );
+        assert_eq!(node_state("cluster/nodes/hpc", "not json"), None);
+
+        let container = r#"{"id":"abc","names":["/helloworld"],"image":"hello",
+            "state":{"Status":"exited"},"config":{},"annotation":{},"stats":{}}"#;
+        assert_eq!(
+            container_state("/pullpiri/metrics/containers/abc", container),
+            Some(("helloworld".to_string(), "exited".to_string()))
+        );
+
+        assert_eq!(
+            scenario_state("/scenario/helloworld/state", "satisfied"),
+            Some(("helloworld".to_string(), "satisfied".to_string()))
+        );
+        assert_eq!(scenario_state("/scenario/helloworld/other", "x"), None);
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_filters_kinds() {
+        let hub = EventHub::new();
+        let stream = event_stream(hub.subscribe(), vec![EventKind::Scenario]);
+        futures::pin_mut!(stream);
+
+        hub.publish(event(EventKind::Node, "hpc", Some("Ready"), None));
+        hub.publish(event(EventKind::Scenario, "helloworld", None, Some("idle")));
+
+        assert!(stream.next().await.unwrap().is_ok());
+        drop(hub);
+        assert!(stream.next().await.is_none());
+    }
+}
@@ -3,11 +3,12 @@
 
 //! Monitoring and metrics management module
 use crate::monitoring_types::{BoardInfo, NodeInfo, SocInfo, StressMetrics};
-use crate::settings_storage::filter_key;
 use crate::settings_storage::Storage;
+use crate::settings_storage::{alert_rule_key, filter_key};
 use crate::settings_utils::error::SettingsError;
 use chrono::{DateTime, Utc};
-use common::monitoringserver::ContainerInfo;
+use common::alert::AlertRule;
+use common::monitoringserver::{Alert, ContainerInfo};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::RwLock;
@@ -42,7 +43,12 @@ fn default_version() -> u64 {
 #[derive(Debug, Serialize)]
 pub struct NodeListResponse {
     pub nodes: Vec<NodeInfo>,
+    /// Number of nodes matching the selectors, over all pages
     pub total: usize,
+    pub offset: usize,
+    pub limit: Option<usize>,
+    /// Offset of the next page, `None` on the last page
+    pub next_offset: Option<usize>,
 }
 
 #[derive(Debug, Serialize)]
@@ -76,7 +82,7 @@ pub enum MetricValue {
 
     // Resource-based metric types (matching etcd data)
     NodeInfo { value: NodeInfo },
-    ContainerInfo { value: ContainerInfo },
+    ContainerInfo { value: Box<ContainerInfo> },
     SocInfo { value: SocInfo },
     BoardInfo { value: BoardInfo },
     StressMetrics { value: StressMetrics },
@@ -230,7 +236,7 @@ impl MonitoringManager {
                             labels
                         },
                         value: MetricValue::ContainerInfo {
-                            value: container_info,
+                            value: Box::new(container_info),
                         },
                         timestamp: Utc::now(),
                     };
@@ -684,7 +690,7 @@ impl MonitoringManager {
                     labels
                 },
                 value: MetricValue::ContainerInfo {
-                    value: container_info,
+                    value: Box::new(container_info),
                 },
                 timestamp: Utc::now(),
             };
@@ -788,6 +794,107 @@ impl MonitoringManager {
         Ok(summaries)
     }
 
+    /// List all alert rules
+    pub async fn list_alert_rules(&mut self) -> Result<Vec<AlertRule>, SettingsError> {
+        debug!("Listing all alert rules");
+
+        let entries = self.storage.list(common::alert::ALERT_RULE_PREFIX).await?;
+        let mut rules = Vec::new();
+        for (key, value) in entries {
+            match serde_json::from_str::<AlertRule>(&value) {
+                Ok(rule) => rules.push(rule),
+                Err(e) => warn!("Failed to parse alert rule from key {}: {}", key, e),
+            }
+        }
+        rules.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Ok(rules)
+    }
+
+    /// Get alert rule by ID
+    pub async fn get_alert_rule(&mut self, id: &str) -> Result<Option<AlertRule>, SettingsError> {
+        debug!("Getting alert rule: {}", id);
+
+        match self.storage.get_json(&alert_rule_key(id)).await? {
+            Some(value) => serde_json::from_value(value).map(Some).map_err(|e| {
+                SettingsError::Metrics(format!("Failed to deserialize alert rule: {}", e))
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Create a new alert rule
+    pub async fn create_alert_rule(&mut self, rule: &AlertRule) -> Result<(), SettingsError> {
+        rule.validate().map_err(SettingsError::Validation)?;
+        if self.get_alert_rule(&rule.id).await?.is_some() {
+            return Err(SettingsError::Conflict(format!(
+                "Alert rule already exists: {}",
+                rule.id
+            )));
+        }
+
+        info!("Creating alert rule: {}", rule.id);
+        self.put_alert_rule(rule).await
+    }
+
+    /// Update alert rule
+    pub async fn update_alert_rule(
+        &mut self,
+        id: &str,
+        rule: &AlertRule,
+    ) -> Result<(), SettingsError> {
+        if self.get_alert_rule(id).await?.is_none() {
+            return Err(SettingsError::Metrics(format!(
+                "Alert rule not found: {}",
+                id
+            )));
+        }
+        let mut updated_rule = rule.clone();
+        updated_rule.id = id.to_string();
+        updated_rule.validate().map_err(SettingsError::Validation)?;
+
+        info!("Updating alert rule: {}", id);
+        self.put_alert_rule(&updated_rule).await
+    }
+
+    /// Delete alert rule, returns `false` if it did not exist
+    ///
+    /// Alerts raised by the rule are resolved by MonitoringServer with the next node report.
+    pub async fn delete_alert_rule(&mut self, id: &str) -> Result<bool, SettingsError> {
+        info!("Deleting alert rule: {}", id);
+        Ok(self.storage.delete(&alert_rule_key(id)).await?)
+    }
+
+    async fn put_alert_rule(&mut self, rule: &AlertRule) -> Result<(), SettingsError> {
+        let value = serde_json::to_value(rule).map_err(|e| {
+            SettingsError::Metrics(format!("Failed to serialize alert rule: {}", e))
+        })?;
+        self.storage
+            .put_json(&alert_rule_key(&rule.id), &value)
+            .await?;
+        Ok(())
+    }
+
+    /// List the alerts currently firing
+    pub async fn list_alerts(&mut self) -> Result<Vec<Alert>, SettingsError> {
+        debug!("Listing firing alerts");
+
+        let entries = self.storage.list(common::alert::ALERT_PREFIX).await?;
+        let mut alerts: Vec<Alert> = entries
+            .into_iter()
+            .filter_map(|(key, value)| match serde_json::from_str::<Alert>(&value) {
+                Ok(alert) => Some(alert),
+                Err(e) => {
+                    warn!("Failed to parse alert from key {}: {}", key, e);
+                    None
+                }
+            })
+            .collect();
+        alerts.sort_by_key(|alert| alert.fired_at);
+
+        Ok(alerts)
+    }
+
     /// Cache management methods
     fn get_cached(&self, key: &str) -> Option<Vec<Metric>> {
         let cache = self.cache.read().ok()?;
@@ -1030,7 +1137,9 @@ impl MonitoringManager {
                 component: "pod".to_string(),
                 metric_type: "PodInfo".to_string(),
                 labels,
-                value: MetricValue::ContainerInfo { value: container },
+                value: MetricValue::ContainerInfo {
+                    value: Box::new(container),
+                },
                 timestamp: Utc::now(),
             };
 
@@ -1208,6 +1317,7 @@ mod tests {
             config,
             annotation,
             stats: HashMap::new(),
+            usage: None,
         }
     }
 
@@ -1287,7 +1397,7 @@ mod tests {
             value: create_test_node_info(),
         };
         let container_info = MetricValue::ContainerInfo {
-            value: create_test_container_info(),
+            value: Box::new(create_test_container_info()),
         };
 
         match counter {
@@ -1834,6 +1944,9 @@ mod tests {
         let node_response = NodeListResponse {
             nodes: nodes.clone(),
             total: nodes.len(),
+            offset: 0,
+            limit: None,
+            next_offset: None,
         };
 
         assert_eq!(node_response.nodes.len(), 1);
@@ -1893,6 +2006,43 @@ mod tests {
         assert!(deleted_retrieved.is_err());
     }
 
+    #[tokio::test]
+    async fn test_alert_rule_operations() {
+        let mut manager = create_test_monitoring_manager().await;
+        let mut rule: AlertRule = serde_json::from_str(
+            r#"{"id": "cpu", "metric": "cpu_usage", "operator": "gt", "threshold": 90,
+                "target": {"kind": "node"}}"#,
+        )
+        .unwrap();
+
+        manager.create_alert_rule(&rule).await.unwrap();
+        assert!(matches!(
+            manager.create_alert_rule(&rule).await,
+            Err(SettingsError::Conflict(_))
+        ));
+        assert_eq!(
+            manager.get_alert_rule("cpu").await.unwrap(),
+            Some(rule.clone())
+        );
+
+        rule.threshold = 95.0;
+        manager.update_alert_rule("cpu", &rule).await.unwrap();
+        let rules = manager.list_alert_rules().await.unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].threshold, 95.0);
+
+        rule.metric = "memory_percent".to_string();
+        assert!(matches!(
+            manager.update_alert_rule("cpu", &rule).await,
+            Err(SettingsError::Validation(_))
+        ));
+        assert!(manager.update_alert_rule("missing", &rule).await.is_err());
+
+        assert!(manager.delete_alert_rule("cpu").await.unwrap());
+        assert!(!manager.delete_alert_rule("cpu").await.unwrap());
+        assert!(manager.get_alert_rule("cpu").await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn test_list_filters() {
         let mut manager = create_test_monitoring_manager().await;
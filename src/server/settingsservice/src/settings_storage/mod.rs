@@ -5,6 +5,7 @@
 
 use crate::settings_utils::error::StorageError;
 use async_trait::async_trait;
+use common::etcd::StoreError;
 use serde_json::Value;
 use tracing::debug;
 
@@ -27,7 +28,8 @@ impl EtcdClient {
 
         match common::etcd::get(key).await {
             Ok(value) => Ok(Some(value)),
-            Err(_) => Ok(None), // Key not found
+            Err(StoreError::NotFound) => Ok(None),
+            Err(e) => Err(e.into()),
         }
     }
 
@@ -35,9 +37,7 @@ impl EtcdClient {
     pub async fn put(&mut self, key: &str, value: &str) -> Result<(), StorageError> {
         debug!("Putting key: {}, value length: {}", key, value.len());
 
-        common::etcd::put(key, value)
-            .await
-            .map_err(|e| StorageError::OperationFailed(format!("Put operation failed: {}", e)))?;
+        common::etcd::put(key, value).await?;
 
         Ok(())
     }
@@ -56,9 +56,7 @@ impl EtcdClient {
     pub async fn list(&mut self, prefix: &str) -> Result<Vec<(String, String)>, StorageError> {
         debug!("Listing keys with prefix: {}", prefix);
 
-        let kvs = common::etcd::get_all_with_prefix(prefix)
-            .await
-            .map_err(|e| StorageError::OperationFailed(format!("List operation failed: {}", e)))?;
+        let kvs = common::etcd::get_all_with_prefix(prefix).await?;
 
         let results: Vec<(String, String)> = kvs.into_iter().map(|kv| (kv.0, kv.1)).collect();
 
@@ -158,6 +156,10 @@ pub fn schema_key(schema_type: &str) -> String {
     format!("{}{}", KeyPrefixes::SCHEMAS, schema_type)
 }
 
+pub fn alert_rule_key(rule_id: &str) -> String {
+    format!("{}{}", common::alert::ALERT_RULE_PREFIX, rule_id)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -15,6 +15,9 @@ pub enum SettingsError {
     #[error("Validation error: {0}")]
     Validation(String),
 
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
     #[error("History error: {0}")]
     History(String),
 
@@ -57,6 +60,16 @@ pub enum StorageError {
     SerializationError(String),
 }
 
+impl From<common::etcd::StoreError> for StorageError {
+    fn from(error: common::etcd::StoreError) -> Self {
+        match error {
+            common::etcd::StoreError::NotFound => Self::KeyNotFound(error.to_string()),
+            common::etcd::StoreError::Unavailable(e) => Self::ConnectionFailed(e),
+            common::etcd::StoreError::Failed(e) => Self::OperationFailed(e),
+        }
+    }
+}
+
 /// API-specific errors
 #[derive(Error, Debug)]
 #[allow(dead_code)]
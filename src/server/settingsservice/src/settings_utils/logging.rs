@@ -2,19 +2,481 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! Logging utilities
+//!
+//! Logs always go to stdout. The `logging` section of settings.yaml adds a
+//! rotating file, the systemd journal and a TCP collector receiving JSON
+//! lines, and sets the level of single modules. The levels can be changed
+//! at runtime with [`set_log_levels`].
 
 use anyhow::Result;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use chrono::{DateTime, Local};
+use common::setting::{FileLogSettings, LogRotation};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::os::unix::net::UnixDatagram;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::{layer::SubscriberExt, reload, util::SubscriberInitExt};
+use tracing_subscriber::{EnvFilter, Registry};
+
+/// Socket of the native protocol of systemd-journald
+const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Time a log line may wait for the TCP collector
+const TCP_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Time before connecting again to an unreachable TCP collector
+const TCP_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+static CONTROL: OnceLock<Mutex<LogControl>> = OnceLock::new();
+
+/// Levels of the logs of the service
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogLevels {
+    /// Level of the modules without their own level
+    pub level: String,
+    /// Level of each module, by module path
+    #[serde(default)]
+    pub modules: BTreeMap<String, String>,
+}
+
+impl LogLevels {
+    /// Filter directives of the levels
+    ///
+    /// ### Returns
+    /// * `Err(String)` - a level or module path is invalid
+    pub fn directives(&self) -> std::result::Result<String, String> {
+        let mut directives = format!("settingsservice={},warn", parse_level(&self.level)?);
+        for (module, level) in &self.modules {
+            if module.is_empty() || module.contains([',', '=', ' ']) {
+                return Err(format!("Invalid module path '{}'", module));
+            }
+            directives.push_str(&format!(",{}={}", module, parse_level(level)?));
+        }
+        Ok(directives)
+    }
+}
+
+fn parse_level(level: &str) -> std::result::Result<LevelFilter, String> {
+    LevelFilter::from_str(level).map_err(|_| format!("Invalid log level '{}'", level))
+}
+
+/// Levels in use and the filter they are applied to
+struct LogControl {
+    levels: LogLevels,
+    handle: reload::Handle<EnvFilter, Registry>,
+}
 
 /// Initialize logging with the specified level
+///
+/// `RUST_LOG`, when set, takes the place of `level` and the module levels
+/// of settings.yaml until the levels are changed with [`set_log_levels`].
+/// A sink of settings.yaml that cannot be opened is left out with a warning.
 pub fn init_logging(level: &str) -> Result<()> {
-    let filter = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new(format!("settingsservice={},warn", level)));
+    let settings = common::setting::get_config().logging.clone();
+    let levels = LogLevels {
+        level: level.to_string(),
+        modules: settings.modules.clone(),
+    };
+    let directives = levels.directives().map_err(anyhow::Error::msg)?;
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(directives));
+    let (filter, handle) = reload::Layer::new(filter);
+
+    let mut warnings = Vec::new();
+    let file = settings.file.as_ref().and_then(|file| {
+        RotatingFile::open(file)
+            .map_err(|e| warnings.push(format!("Log file '{}' not used: {}", file.path, e)))
+            .ok()
+    });
+    let journald = settings
+        .journald
+        .then(|| {
+            JournaldLayer::connect("settingsservice")
+                .map_err(|e| warnings.push(format!("Journald not used: {}", e)))
+                .ok()
+        })
+        .flatten();
+    let tcp = settings.tcp.as_ref().map(|tcp| TcpSink::new(&tcp.address));
 
     tracing_subscriber::registry()
         .with(filter)
         .with(tracing_subscriber::fmt::layer().with_target(false))
-        .init();
+        .with(file.map(|file| {
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(Mutex::new(file))
+        }))
+        .with(journald)
+        .with(tcp.map(|tcp| {
+            tracing_subscriber::fmt::layer()
+                .json()
+                .with_writer(Mutex::new(tcp))
+        }))
+        .try_init()?;
+
+    let _ = CONTROL.set(Mutex::new(LogControl { levels, handle }));
+    for warning in warnings {
+        tracing::warn!("{}", warning);
+    }
+    Ok(())
+}
 
+/// Levels in use, none before [`init_logging`]
+pub fn log_levels() -> Option<LogLevels> {
+    let control = CONTROL.get()?;
+    let control = control.lock().unwrap_or_else(|e| e.into_inner());
+    Some(control.levels.clone())
+}
+
+/// Replace the levels of the logs
+///
+/// ### Returns
+/// * `Err(String)` - the levels are invalid or logging is not initialized
+pub fn set_log_levels(levels: LogLevels) -> std::result::Result<(), String> {
+    let filter = EnvFilter::try_new(levels.directives()?).map_err(|e| e.to_string())?;
+    let control = CONTROL.get().ok_or("Logging is not initialized")?;
+    let mut control = control.lock().unwrap_or_else(|e| e.into_inner());
+    control.handle.reload(filter).map_err(|e| e.to_string())?;
+    control.levels = levels;
     Ok(())
 }
+
+/// Log file rotated by size and time
+pub struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    rotation: LogRotation,
+    max_files: usize,
+    file: File,
+    size: u64,
+    /// Period the current file was started in
+    period: String,
+}
+
+impl RotatingFile {
+    /// Open the log file for appending, creating it and its directory
+    pub fn open(settings: &FileLogSettings) -> io::Result<Self> {
+        if settings.path.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "path is empty"));
+        }
+        let path = PathBuf::from(&settings.path);
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let metadata = file.metadata()?;
+        let modified = metadata.modified().map(DateTime::<Local>::from);
+        Ok(Self {
+            max_size: settings.max_size_mb * 1024 * 1024,
+            rotation: settings.rotation,
+            max_files: settings.max_files,
+            size: metadata.len(),
+            period: period_of(settings.rotation, modified.unwrap_or_else(|_| Local::now())),
+            file,
+            path,
+        })
+    }
+
+    /// Whether `len` more bytes written at `now` go to a new file
+    fn needs_rotation(&self, len: u64, now: DateTime<Local>) -> bool {
+        self.size > 0
+            && ((self.max_size > 0 && self.size + len > self.max_size)
+                || period_of(self.rotation, now) != self.period)
+    }
+
+    /// Shift the rotated files up and start a new current file
+    fn rotate(&mut self, now: DateTime<Local>) -> io::Result<()> {
+        let rotated = |n: usize| PathBuf::from(format!("{}.{}", self.path.display(), n));
+        if self.max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            for n in (1..self.max_files).rev() {
+                if rotated(n).exists() {
+                    fs::rename(rotated(n), rotated(n + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated(1))?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+        self.period = period_of(self.rotation, now);
+        Ok(())
+    }
+
+    fn write_at(&mut self, buf: &[u8], now: DateTime<Local>) -> io::Result<usize> {
+        if self.needs_rotation(buf.len() as u64, now) {
+            self.rotate(now)?;
+        } else if self.size == 0 {
+            self.period = period_of(self.rotation, now);
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_at(buf, Local::now())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Key of the rotation period `time` falls in
+fn period_of(rotation: LogRotation, time: DateTime<Local>) -> String {
+    match rotation {
+        LogRotation::Never => String::new(),
+        LogRotation::Hourly => time.format("%Y%m%d%H").to_string(),
+        LogRotation::Daily => time.format("%Y%m%d").to_string(),
+    }
+}
+
+/// Layer sending every event to the systemd journal
+pub struct JournaldLayer {
+    socket: UnixDatagram,
+    identifier: String,
+}
+
+impl JournaldLayer {
+    /// Connect to the journal of the local systemd
+    pub fn connect(identifier: &str) -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(JOURNALD_SOCKET)?;
+        Ok(Self {
+            socket,
+            identifier: identifier.to_string(),
+        })
+    }
+}
+
+impl<S: Subscriber> Layer<S> for JournaldLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut message = MessageVisitor::default();
+        event.record(&mut message);
+        let metadata = event.metadata();
+        let entry = journal_entry(
+            &self.identifier,
+            *metadata.level(),
+            metadata.target(),
+            &message.0,
+        );
+        // The journal may be restarting, the event is still on stdout
+        let _ = self.socket.send(&entry);
+    }
+}
+
+/// Message of an event followed by its other fields
+#[derive(Default)]
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.0.insert_str(0, &format!("{:?}", value));
+        } else {
+            self.0.push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+}
+
+/// Entry in the native journal protocol
+fn journal_entry(identifier: &str, level: Level, target: &str, message: &str) -> Vec<u8> {
+    let priority = match level {
+        Level::ERROR => "3",
+        Level::WARN => "4",
+        Level::INFO => "6",
+        Level::DEBUG | Level::TRACE => "7",
+    };
+    let mut entry = Vec::new();
+    journal_field(&mut entry, "PRIORITY", priority);
+    journal_field(&mut entry, "SYSLOG_IDENTIFIER", identifier);
+    journal_field(&mut entry, "TARGET", target);
+    journal_field(&mut entry, "MESSAGE", message);
+    entry
+}
+
+/// Append a field, in the binary form if the value spans several lines
+fn journal_field(entry: &mut Vec<u8>, name: &str, value: &str) {
+    entry.extend_from_slice(name.as_bytes());
+    if value.contains('\n') {
+        entry.push(b'\n');
+        entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        entry.push(b'=');
+    }
+    entry.extend_from_slice(value.as_bytes());
+    entry.push(b'\n');
+}
+
+/// Connection to a log collector, dropping lines while it is unreachable
+///
+/// Logging never fails or waits long because of the collector: a line is
+/// dropped when the collector cannot be reached, and it is tried again
+/// after [`TCP_RETRY_INTERVAL`].
+pub struct TcpSink {
+    address: String,
+    stream: Option<TcpStream>,
+    retry_at: Option<Instant>,
+}
+
+impl TcpSink {
+    pub fn new(address: &str) -> Self {
+        Self {
+            address: address.to_string(),
+            stream: None,
+            retry_at: None,
+        }
+    }
+
+    fn connect(&self) -> io::Result<TcpStream> {
+        let addr = self
+            .address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "address not resolved"))?;
+        let stream = TcpStream::connect_timeout(&addr, TCP_TIMEOUT)?;
+        stream.set_write_timeout(Some(TCP_TIMEOUT))?;
+        Ok(stream)
+    }
+}
+
+impl Write for TcpSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.stream.is_none() {
+            if self.retry_at.is_some_and(|at| Instant::now() < at) {
+                return Ok(buf.len());
+            }
+            match self.connect() {
+                Ok(stream) => self.stream = Some(stream),
+                Err(_) => {
+                    self.retry_at = Some(Instant::now() + TCP_RETRY_INTERVAL);
+                    return Ok(buf.len());
+                }
+            }
+        }
+        if let Some(stream) = self.stream.as_mut() {
+            if stream.write_all(buf).is_err() {
+                self.stream = None;
+                self.retry_at = Some(Instant::now() + TCP_RETRY_INTERVAL);
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    fn levels(level: &str, modules: &[(&str, &str)]) -> LogLevels {
+        LogLevels {
+            level: level.to_string(),
+            modules: modules
+                .iter()
+                .map(|(module, level)| (module.to_string(), level.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_log_levels_directives() {
+        assert_eq!(
+            levels("info", &[("settingsservice::settings_api", "DEBUG")])
+                .directives()
+                .unwrap(),
+            "settingsservice=info,warn,settingsservice::settings_api=debug"
+        );
+        assert!(levels("loud", &[]).directives().is_err());
+        assert!(levels("info", &[("a=b", "debug")]).directives().is_err());
+        assert!(levels("info", &[("settingsservice", "verbose")])
+            .directives()
+            .is_err());
+    }
+
+    #[test]
+    fn test_rotating_file_rotates_by_size_and_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs/service.log");
+        let mut file = RotatingFile::open(&FileLogSettings {
+            path: path.display().to_string(),
+            max_size_mb: 1,
+            rotation: LogRotation::Daily,
+            max_files: 2,
+        })
+        .unwrap();
+        let day1 = Local.with_ymd_and_hms(2026, 3, 1, 10, 0, 0).unwrap();
+        let day2 = Local.with_ymd_and_hms(2026, 3, 2, 10, 0, 0).unwrap();
+        file.period = period_of(LogRotation::Daily, day1);
+
+        let line = vec![b'a'; 600 * 1024];
+        file.write_at(&line, day1).unwrap();
+        file.write_at(&line, day1).unwrap();
+        file.write_at(b"next day\n", day2).unwrap();
+        file.write_at(b"same day\n", day2).unwrap();
+        let rotated = |n: usize| PathBuf::from(format!("{}.{}", path.display(), n));
+        assert_eq!(fs::read(&path).unwrap(), b"next day\nsame day\n");
+        assert_eq!(fs::read(rotated(1)).unwrap().len(), line.len());
+        assert_eq!(fs::read(rotated(2)).unwrap().len(), line.len());
+
+        let large = vec![b'b'; 1024 * 1024];
+        file.write_at(&large, day2).unwrap();
+        assert_eq!(fs::read(&path).unwrap().len(), large.len());
+        assert_eq!(fs::read(rotated(1)).unwrap(), b"next day\nsame day\n");
+        assert_eq!(fs::read(rotated(2)).unwrap().len(), line.len());
+        assert!(!rotated(3).exists());
+
+        assert!(RotatingFile::open(&FileLogSettings::default()).is_err());
+    }
+
+    #[test]
+    fn test_journal_entry() {
+        let entry = journal_entry("settingsservice", Level::WARN, "settings_api", "disk full");
+        assert_eq!(
+            entry,
+            b"PRIORITY=4\nSYSLOG_IDENTIFIER=settingsservice\nTARGET=settings_api\nMESSAGE=disk full\n"
+        );
+
+        let mut field = Vec::new();
+        journal_field(&mut field, "MESSAGE", "a\nb");
+        assert_eq!(field, b"MESSAGE\n\x03\0\0\0\0\0\0\0a\nb\n");
+    }
+
+    #[test]
+    fn test_tcp_sink_forwards_and_drops_when_unreachable() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut sink = TcpSink::new(&listener.local_addr().unwrap().to_string());
+        assert_eq!(sink.write(b"{\"a\":1}\n").unwrap(), 8);
+        let (mut conn, _) = listener.accept().unwrap();
+        let mut received = [0u8; 8];
+        conn.read_exact(&mut received).unwrap();
+        assert_eq!(&received, b"{\"a\":1}\n");
+
+        drop(listener);
+        let mut unreachable = TcpSink::new("127.0.0.1:1");
+        assert_eq!(unreachable.write(b"lost\n").unwrap(), 5);
+        assert!(unreachable.stream.is_none());
+        assert!(unreachable.retry_at.is_some());
+    }
+}
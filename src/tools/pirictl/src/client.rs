@@ -5,6 +5,7 @@
 //! REST API client for SettingsService
 
 use crate::error::{CliError, Result};
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
 use reqwest::Client;
 use serde_json::Value;
 use std::time::Duration;
@@ -22,8 +23,24 @@ impl SettingsClient {
     /// * `base_url` - Base URL of the SettingsService (e.g., "http://localhost:47098")
     /// * `timeout` - Request timeout in seconds
     pub fn new(base_url: &str, timeout: u64) -> Result<Self> {
+        Self::with_token(base_url, timeout, None)
+    }
+
+    /// Create a client sending `Authorization: Bearer <token>` with every request
+    ///
+    /// # Arguments
+    /// * `token` - API token, requests are sent without one if `None`
+    pub fn with_token(base_url: &str, timeout: u64, token: Option<&str>) -> Result<Self> {
+        let mut headers = HeaderMap::new();
+        if let Some(token) = token {
+            let mut value = HeaderValue::from_str(&format!("Bearer {}", token))
+                .map_err(|_| CliError::Custom("token contains invalid characters".to_string()))?;
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
         let client = Client::builder()
             .timeout(Duration::from_secs(timeout))
+            .default_headers(headers)
             .build()
             .map_err(CliError::Http)?;
 
@@ -57,6 +74,29 @@ impl SettingsClient {
         Ok(json)
     }
 
+    /// Make a GET request to an endpoint returning plain text or YAML
+    ///
+    /// # Arguments
+    /// * `endpoint` - API endpoint (e.g., "/api/artifact/Model/helloworld")
+    pub async fn get_text(&self, endpoint: &str) -> Result<String> {
+        let url = format!("{}{}", self.base_url, endpoint);
+        let response = self.client.get(&url).send().await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(CliError::Custom(format!(
+                "Request failed with status: {} - {}",
+                status, error_text
+            )));
+        }
+
+        Ok(response.text().await?)
+    }
+
     /// Make a POST request to the specified endpoint
     ///
     /// # Arguments
@@ -136,10 +176,27 @@ impl SettingsClient {
     /// * `endpoint` - API endpoint (e.g., "/api/artifact")
     /// * `yaml_content` - YAML content as string
     pub async fn post_yaml(&self, endpoint: &str, yaml_content: &str) -> Result<Value> {
+        self.post_yaml_with_values(endpoint, yaml_content, &[])
+            .await
+    }
+
+    /// Apply YAML artifact template with the values of its parameters
+    ///
+    /// # Arguments
+    /// * `endpoint` - API endpoint (e.g., "/api/artifact")
+    /// * `yaml_content` - YAML content as string
+    /// * `values` - name and value of the template parameters, sent as query
+    pub async fn post_yaml_with_values(
+        &self,
+        endpoint: &str,
+        yaml_content: &str,
+        values: &[(String, String)],
+    ) -> Result<Value> {
         let url = format!("{}{}", self.base_url, endpoint);
         let response = self
             .client
             .post(&url)
+            .query(values)
             .header("Content-Type", "text/plain")
             .body(yaml_content.to_owned())
             .send()
@@ -226,6 +283,25 @@ mod tests {
         assert!(client.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_with_token_sends_bearer_header() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/test"))
+            .and(header("authorization", "Bearer secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let client = SettingsClient::with_token(&server.uri(), 5, Some("secret")).unwrap();
+        assert!(client.get("/api/v1/test").await.is_ok());
+    }
+
+    #[test]
+    fn test_with_token_rejects_invalid_token() {
+        assert!(SettingsClient::with_token("http://localhost:47098", 5, Some("a\nb")).is_err());
+    }
+
     // ── GET ───────────────────────────────────────────────────────────────────
 
     #[tokio::test]
@@ -283,6 +359,36 @@ mod tests {
         assert!(client.get("/api/v1/broken").await.is_err());
     }
 
+    #[tokio::test]
+    async fn test_get_text_success() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/artifact/Model/helloworld"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("kind: Model\n"))
+            .mount(&server)
+            .await;
+        let client = SettingsClient::new(&server.uri(), 5).unwrap();
+        let text = client.get_text("/api/artifact/Model/helloworld").await;
+        assert_eq!(text.unwrap(), "kind: Model\n");
+    }
+
+    #[tokio::test]
+    async fn test_get_text_error_includes_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/artifact/Model/missing"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("not found"))
+            .mount(&server)
+            .await;
+        let client = SettingsClient::new(&server.uri(), 5).unwrap();
+        let err = client
+            .get_text("/api/artifact/Model/missing")
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("404") && err.contains("not found"));
+    }
+
     // ── POST ──────────────────────────────────────────────────────────────────
 
     #[tokio::test]
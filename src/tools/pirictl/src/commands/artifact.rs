@@ -0,0 +1,285 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Artifact command implementation
+//!
+//! Scenarios, packages and models are read from the API Server.
+
+use crate::commands::{format::format_timestamp, OutputFormat};
+use crate::commands::{print_error, print_info, print_json, print_success, print_table_header};
+use crate::{Result, SettingsClient};
+use colored::Colorize;
+use serde_json::{json, Value};
+
+/// Number of state transitions shown by `describe`
+const DESCRIBE_HISTORY_LIMIT: u32 = 10;
+
+pub enum ArtifactAction {
+    /// List the stored artifacts of a kind (`Scenario`, `Package`, `Model`)
+    Get { kind: &'static str },
+    /// Show the spec, state and recent transitions of a model
+    DescribeModel { name: String },
+}
+
+/// Handle artifact commands
+pub async fn handle(
+    client: &SettingsClient,
+    action: ArtifactAction,
+    output: OutputFormat,
+) -> Result<()> {
+    match action {
+        ArtifactAction::Get { kind } => get_artifacts(client, kind, output).await,
+        ArtifactAction::DescribeModel { name } => describe_model(client, &name, output).await,
+    }
+}
+
+/// List the stored artifacts of a kind
+async fn get_artifacts(client: &SettingsClient, kind: &str, output: OutputFormat) -> Result<()> {
+    if output == OutputFormat::Table {
+        print_info(&format!("Fetching {} list...", kind.to_lowercase()));
+    }
+
+    let list = match client.get(&format!("/api/artifact/{}", kind)).await {
+        Ok(list) => list,
+        Err(e) => {
+            print_error(&format!("Failed to fetch {} list: {}", kind, e));
+            return Err(e);
+        }
+    };
+    if output == OutputFormat::Json {
+        return print_json(&list);
+    }
+
+    print_table_header(kind, &[("NAME", 32), ("LABELS", 40)]);
+    let artifacts = list
+        .get("artifacts")
+        .and_then(|a| a.as_array())
+        .cloned()
+        .unwrap_or_default();
+    if artifacts.is_empty() {
+        println!("No {} found.", kind.to_lowercase());
+    }
+    for artifact in &artifacts {
+        let name = artifact
+            .get("name")
+            .and_then(|n| n.as_str())
+            .unwrap_or("Unknown");
+        println!("{:<32} {:<40}", name, format_labels(artifact.get("labels")));
+    }
+
+    println!();
+    print_success(&format!("{} list retrieved successfully", kind));
+    Ok(())
+}
+
+/// Show the spec, current state and recent transitions of a model
+async fn describe_model(client: &SettingsClient, name: &str, output: OutputFormat) -> Result<()> {
+    if output == OutputFormat::Table {
+        print_info(&format!("Fetching model information for: {}", name));
+    }
+
+    let spec = match client
+        .get_text(&format!("/api/artifact/Model/{}", name))
+        .await
+    {
+        Ok(spec) => spec,
+        Err(e) => {
+            print_error(&format!("Failed to fetch model {}: {}", name, e));
+            return Err(e);
+        }
+    };
+    // The state is informative only, a model is still described without it
+    let endpoint = format!(
+        "/api/state/model/{}/history?limit={}",
+        name, DESCRIBE_HISTORY_LIMIT
+    );
+    let history = client.get(&endpoint).await.unwrap_or(Value::Null);
+    let transitions = history.as_array().cloned().unwrap_or_default();
+    let state = current_state(&transitions);
+
+    if output == OutputFormat::Json {
+        return print_json(&json!({
+            "name": name,
+            "state": state,
+            "spec": spec,
+            "history": transitions,
+        }));
+    }
+
+    println!("\n{:<24}{}", format!("{}:", "Name".bold()), name);
+    println!(
+        "{:<24}{}",
+        format!("{}:", "State".bold()),
+        state.unwrap_or("Unknown")
+    );
+    println!("{}", "Spec:".bold());
+    for line in spec.lines() {
+        println!("  {}", line);
+    }
+
+    println!("{}", "Transitions:".bold());
+    if transitions.is_empty() {
+        println!("  <none>");
+    }
+    for transition in &transitions {
+        let field = |key: &str| {
+            transition
+                .get(key)
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string()
+        };
+        let time = transition
+            .get("timestamp_ns")
+            .and_then(|t| t.as_i64())
+            .map(format_timestamp_ns)
+            .unwrap_or_default();
+        let result = match transition.get("success").and_then(|s| s.as_bool()) {
+            Some(true) => "ok",
+            _ => "failed",
+        };
+        println!(
+            "  {:<32} {:<12} {:<12} {:<24} {}",
+            time,
+            field("from_state"),
+            field("to_state"),
+            field("event"),
+            result
+        );
+    }
+
+    print_success("Model information retrieved successfully");
+    Ok(())
+}
+
+/// State reached by the newest successful transition
+fn current_state(transitions: &[Value]) -> Option<&str> {
+    transitions
+        .iter()
+        .rev()
+        .find(|t| t.get("success").and_then(|s| s.as_bool()) == Some(true))
+        .and_then(|t| t.get("to_state"))
+        .and_then(|s| s.as_str())
+}
+
+/// Labels as `key=value` pairs sorted by key, `<none>` without labels
+fn format_labels(labels: Option<&Value>) -> String {
+    let mut pairs: Vec<String> = labels
+        .and_then(|l| l.as_object())
+        .map(|l| {
+            l.iter()
+                .map(|(k, v)| format!("{}={}", k, v.as_str().unwrap_or_default()))
+                .collect()
+        })
+        .unwrap_or_default();
+    if pairs.is_empty() {
+        return "<none>".to_string();
+    }
+    pairs.sort();
+    pairs.join(",")
+}
+
+/// Local time of a Unix timestamp in nanoseconds
+fn format_timestamp_ns(timestamp_ns: i64) -> String {
+    let time = chrono::DateTime::from_timestamp_nanos(timestamp_ns).to_rfc3339();
+    format_timestamp(&time).unwrap_or(time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn make_client(base_url: &str) -> SettingsClient {
+        SettingsClient::new(base_url, 5).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_artifacts_table_and_json() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/artifact/Scenario"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "artifacts": [{"name": "antipinch", "labels": {"zone": "front"}}],
+                "total": 1,
+                "offset": 0
+            })))
+            .mount(&server)
+            .await;
+        let client = make_client(&server.uri()).await;
+        for output in [OutputFormat::Table, OutputFormat::Json] {
+            let action = ArtifactAction::Get { kind: "Scenario" };
+            assert!(handle(&client, action, output).await.is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_artifacts_server_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/artifact/Package"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+        let client = make_client(&server.uri()).await;
+        let action = ArtifactAction::Get { kind: "Package" };
+        assert!(handle(&client, action, OutputFormat::Table).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_describe_model_without_history() {
+        // The model is described even if the StateManager is unreachable
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/artifact/Model/helloworld"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("kind: Model\n"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/state/model/helloworld/history"))
+            .respond_with(ResponseTemplate::new(502))
+            .mount(&server)
+            .await;
+        let client = make_client(&server.uri()).await;
+        let action = ArtifactAction::DescribeModel {
+            name: "helloworld".to_string(),
+        };
+        assert!(handle(&client, action, OutputFormat::Table).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_describe_model_not_found() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/artifact/Model/missing"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        let client = make_client(&server.uri()).await;
+        let action = ArtifactAction::DescribeModel {
+            name: "missing".to_string(),
+        };
+        assert!(handle(&client, action, OutputFormat::Json).await.is_err());
+    }
+
+    #[test]
+    fn test_current_state_skips_failed_transitions() {
+        let transitions = vec![
+            json!({"to_state": "Created", "success": true}),
+            json!({"to_state": "Running", "success": true}),
+            json!({"to_state": "Dead", "success": false}),
+        ];
+        assert_eq!(current_state(&transitions), Some("Running"));
+        assert_eq!(current_state(&[]), None);
+    }
+
+    #[test]
+    fn test_format_labels() {
+        let labels = json!({"zone": "front", "app": "wiper"});
+        assert_eq!(format_labels(Some(&labels)), "app=wiper,zone=front");
+        assert_eq!(format_labels(Some(&json!({}))), "<none>");
+        assert_eq!(format_labels(None), "<none>");
+    }
+}
@@ -0,0 +1,147 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Logs command implementation
+//!
+//! The API Server serves the logs collected by the MonitoringServer and
+//! falls back to the NodeAgent of the node running the container. Without
+//! `--node`, the node is taken from the `Hostname` the container
+//! reports to the SettingsService.
+
+use crate::commands::{print_error, print_json, OutputFormat};
+use crate::{CliError, Result, SettingsClient};
+use serde_json::json;
+
+/// Print the logs of a container
+///
+/// # Arguments
+/// * `settings_client` - client used to find the node of the container
+/// * `api_client` - client of the API Server serving the logs
+/// * `container` - id or name of the container
+/// * `node` - hostname of the node running the container, if known
+/// * `tail` - number of last lines, all lines if 0
+pub async fn handle(
+    settings_client: &SettingsClient,
+    api_client: &SettingsClient,
+    container: &str,
+    node: Option<String>,
+    tail: u32,
+    output: OutputFormat,
+) -> Result<()> {
+    let node = match node {
+        Some(node) => node,
+        None => find_node(settings_client, container).await?,
+    };
+
+    let endpoint = format!(
+        "/api/v1/containers/{}/logs?node={}&tail={}",
+        container, node, tail
+    );
+    let logs = match api_client.get_text(&endpoint).await {
+        Ok(logs) => logs,
+        Err(e) => {
+            print_error(&format!("Failed to fetch logs of {}: {}", container, e));
+            return Err(e);
+        }
+    };
+
+    match output {
+        OutputFormat::Json => print_json(&json!({
+            "container": container,
+            "node": node,
+            "lines": logs.lines().collect::<Vec<_>>(),
+        })),
+        OutputFormat::Table => {
+            print!("{}", logs);
+            Ok(())
+        }
+    }
+}
+
+/// Hostname of the node running a container
+async fn find_node(client: &SettingsClient, container: &str) -> Result<String> {
+    let info = client
+        .get(&format!("/api/v1/containers/{}", container))
+        .await
+        .map_err(|e| {
+            CliError::Custom(format!(
+                "cannot find the node of container {}, use --node: {}",
+                container, e
+            ))
+        })?;
+
+    info.get("config")
+        .and_then(|c| c.get("Hostname"))
+        .and_then(|h| h.as_str())
+        .filter(|h| !h.is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| {
+            CliError::Custom(format!(
+                "container {} does not report its node, use --node",
+                container
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_logs_finds_node_of_container() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/containers/nginx"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"id": "nginx", "config": {"Hostname": "hpc"}})),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/containers/nginx/logs"))
+            .and(query_param("node", "hpc"))
+            .and(query_param("tail", "20"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("started\n"))
+            .expect(2)
+            .mount(&server)
+            .await;
+        let client = SettingsClient::new(&server.uri(), 5).unwrap();
+        for output in [OutputFormat::Table, OutputFormat::Json] {
+            assert!(handle(&client, &client, "nginx", None, 20, output)
+                .await
+                .is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_logs_without_known_node() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/containers/nginx"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": "nginx"})))
+            .mount(&server)
+            .await;
+        let client = SettingsClient::new(&server.uri(), 5).unwrap();
+        let result = handle(&client, &client, "nginx", None, 0, OutputFormat::Table).await;
+        assert!(result.unwrap_err().to_string().contains("--node"));
+    }
+
+    #[tokio::test]
+    async fn test_logs_with_node_skips_lookup() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/containers/nginx/logs"))
+            .and(query_param("node", "zone"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("not found"))
+            .mount(&server)
+            .await;
+        let client = SettingsClient::new(&server.uri(), 5).unwrap();
+        let node = Some("zone".to_string());
+        let result = handle(&client, &client, "nginx", node, 0, OutputFormat::Table).await;
+        assert!(result.is_err());
+    }
+}
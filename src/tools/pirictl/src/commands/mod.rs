@@ -4,11 +4,14 @@
 */
 //! Command implementations for pirictl
 
+pub mod artifact;
 pub mod board;
 pub mod container;
 pub mod format;
+pub mod logs;
 pub mod metrics;
 pub mod node;
+pub mod scenario;
 pub mod soc;
 pub mod top;
 pub mod yaml;
@@ -17,6 +20,16 @@ use crate::Result;
 use colored::Colorize;
 use serde_json::Value;
 
+/// Output format of commands supporting `--output`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable table
+    #[default]
+    Table,
+    /// Pretty printed JSON, for scripts
+    Json,
+}
+
 /// Helper function to pretty print JSON output
 pub fn print_json(value: &Value) -> Result<()> {
     let pretty = serde_json::to_string_pretty(value)?;
@@ -0,0 +1,254 @@
+/*
+* SPDX-FileCopyrightText: Copyright 2024 LG Electronics Inc.
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Scenario command implementation
+
+use crate::commands::{
+    print_error, print_info, print_json, print_success, print_table_header, OutputFormat,
+};
+use crate::{Result, SettingsClient};
+use colored::Colorize;
+use serde_json::{json, Value};
+
+pub enum ScenarioAction {
+    /// Run the action of a scenario without waiting for its condition
+    ///
+    /// With `dry_run`, only the execution plan of the action is shown.
+    Trigger { name: String, dry_run: bool },
+}
+
+/// Handle scenario commands
+pub async fn handle(
+    client: &SettingsClient,
+    action: ScenarioAction,
+    output: OutputFormat,
+) -> Result<()> {
+    match action {
+        ScenarioAction::Trigger {
+            name,
+            dry_run: false,
+        } => trigger_scenario(client, &name, output).await,
+        ScenarioAction::Trigger {
+            name,
+            dry_run: true,
+        } => plan_scenario(client, &name, output).await,
+    }
+}
+
+/// Ask the API Server to run the action of a scenario now
+async fn trigger_scenario(client: &SettingsClient, name: &str, output: OutputFormat) -> Result<()> {
+    if output == OutputFormat::Table {
+        print_info(&format!("Triggering scenario: {}", name));
+    }
+
+    let endpoint = format!("/api/v1/scenarios/{}/trigger", name);
+    match client.post(&endpoint, &Value::Null).await {
+        Ok(result) => {
+            if output == OutputFormat::Json {
+                return print_json(&result);
+            }
+            if let Some(id) = result["transition_id"].as_str().filter(|id| !id.is_empty()) {
+                println!("{:<16}{}", format!("{}:", "Transition".bold()), id);
+            }
+            print_success(&format!("Scenario {} triggered successfully", name));
+        }
+        Err(e) => {
+            print_error(&format!("Failed to trigger scenario {}: {}", name, e));
+            return Err(e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Show what triggering a scenario would run, without running it
+async fn plan_scenario(client: &SettingsClient, name: &str, output: OutputFormat) -> Result<()> {
+    if output == OutputFormat::Table {
+        print_info(&format!("Planning scenario: {}", name));
+    }
+
+    let endpoint = format!("/api/v1/scenarios/{}/trigger?dry_run=true", name);
+    let plan = match client.post(&endpoint, &Value::Null).await {
+        Ok(plan) => plan,
+        Err(e) => {
+            print_error(&format!("Failed to plan scenario {}: {}", name, e));
+            return Err(e);
+        }
+    };
+    if output == OutputFormat::Json {
+        return print_json(&plan);
+    }
+
+    let field = |value: &Value, key: &str| {
+        value
+            .get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    println!(
+        "\n{:<12}{}  {:<12}{}",
+        format!("{}:", "Package".bold()),
+        field(&plan, "package_name"),
+        format!("{}:", "Action".bold()),
+        field(&plan, "action")
+    );
+
+    print_table_header(
+        "Plan",
+        &[
+            ("ORDER", 6),
+            ("BATCH", 6),
+            ("NODE", 16),
+            ("MODEL", 32),
+            ("OPERATION", 10),
+            ("DETAIL", 30),
+        ],
+    );
+    let steps = plan_steps(&plan);
+    if steps.is_empty() {
+        println!("No operations planned.");
+    }
+    for (node, step) in &steps {
+        println!(
+            "{:<6} {:<6} {:<16} {:<32} {:<10} {}",
+            step.get("order").and_then(|o| o.as_u64()).unwrap_or(0),
+            step.get("batch").and_then(|b| b.as_u64()).unwrap_or(0),
+            node,
+            field(step, "model_name"),
+            field(step, "operation"),
+            field(step, "detail")
+        );
+    }
+
+    let warnings = plan
+        .get("warnings")
+        .and_then(|w| w.as_array())
+        .cloned()
+        .unwrap_or_default();
+    for warning in &warnings {
+        println!("{} {}", "⚠".yellow(), warning.as_str().unwrap_or_default());
+    }
+
+    println!();
+    print_success(&format!(
+        "Dry run of scenario {}, nothing was executed",
+        name
+    ));
+    Ok(())
+}
+
+/// Steps of all nodes of a plan with their node, in execution order
+fn plan_steps(plan: &Value) -> Vec<(String, Value)> {
+    let mut steps: Vec<(String, Value)> = plan
+        .get("nodes")
+        .and_then(|n| n.as_array())
+        .into_iter()
+        .flatten()
+        .flat_map(|node| {
+            let name = node
+                .get("node")
+                .and_then(|n| n.as_str())
+                .unwrap_or_default()
+                .to_string();
+            node.get("steps")
+                .and_then(|s| s.as_array())
+                .cloned()
+                .unwrap_or_default()
+                .into_iter()
+                .map(move |step| (name.clone(), step))
+        })
+        .collect();
+    steps.sort_by_key(|(_, step)| step.get("order").and_then(|o| o.as_u64()).unwrap_or(0));
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_trigger_scenario_success() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/scenarios/antipinch/trigger"))
+            .respond_with(ResponseTemplate::new(202).set_body_json(json!({
+                "transition_id": "4bf92f3577b34da6a3ce929d0e0e4736",
+                "scenario": "antipinch",
+                "status": "accepted"
+            })))
+            .mount(&server)
+            .await;
+        let client = SettingsClient::new(&server.uri(), 5).unwrap();
+        for output in [OutputFormat::Table, OutputFormat::Json] {
+            let action = ScenarioAction::Trigger {
+                name: "antipinch".to_string(),
+                dry_run: false,
+            };
+            assert!(handle(&client, action, output).await.is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trigger_scenario_server_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/scenarios/antipinch/trigger"))
+            .respond_with(ResponseTemplate::new(502))
+            .mount(&server)
+            .await;
+        let client = SettingsClient::new(&server.uri(), 5).unwrap();
+        let action = ScenarioAction::Trigger {
+            name: "antipinch".to_string(),
+            dry_run: false,
+        };
+        assert!(handle(&client, action, OutputFormat::Table).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_trigger_scenario_dry_run() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/scenarios/antipinch/trigger"))
+            .and(query_param("dry_run", "true"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "scenario_name": "antipinch",
+                "package_name": "antipinch",
+                "action": "launch",
+                "nodes": [{
+                    "node": "HPC",
+                    "node_type": "nodeagent",
+                    "steps": [{"order": 1, "batch": 1, "model_name": "core",
+                               "operation": "start", "detail": ""}]
+                }],
+                "warnings": ["Node 'ZONE' has no known role"]
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+        let client = SettingsClient::new(&server.uri(), 5).unwrap();
+        for output in [OutputFormat::Table, OutputFormat::Json] {
+            let action = ScenarioAction::Trigger {
+                name: "antipinch".to_string(),
+                dry_run: true,
+            };
+            assert!(handle(&client, action, output).await.is_ok());
+        }
+    }
+
+    #[test]
+    fn test_plan_steps_in_execution_order() {
+        let plan = json!({
+            "nodes": [
+                {"node": "HPC", "steps": [{"order": 1}, {"order": 3}]},
+                {"node": "ZONE", "steps": [{"order": 2}]}
+            ]
+        });
+        let nodes: Vec<String> = plan_steps(&plan).into_iter().map(|(n, _)| n).collect();
+        assert_eq!(nodes, vec!["HPC", "ZONE", "HPC"]);
+        assert!(plan_steps(&json!({})).is_empty());
+    }
+}
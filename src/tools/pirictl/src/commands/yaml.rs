@@ -15,6 +15,9 @@ pub enum YamlAction {
     Apply {
         /// Path to YAML file or '-' for stdin
         file: String,
+        /// Value of a template parameter as NAME=VALUE, may be repeated
+        #[arg(long = "set", value_name = "NAME=VALUE")]
+        set: Vec<String>,
     },
     /// Withdraw (delete) YAML artifact from the system
     Withdraw {
@@ -25,21 +28,25 @@ pub enum YamlAction {
 
 pub async fn handle(client: &SettingsClient, action: YamlAction) -> Result<()> {
     match action {
-        YamlAction::Apply { file } => apply_yaml(client, &file).await,
+        YamlAction::Apply { file, set } => apply_yaml(client, &file, &set).await,
         YamlAction::Withdraw { file } => withdraw_yaml(client, &file).await,
     }
 }
 
 /// Apply YAML artifact
-async fn apply_yaml(client: &SettingsClient, file_path: &str) -> Result<()> {
+async fn apply_yaml(client: &SettingsClient, file_path: &str, set: &[String]) -> Result<()> {
     print_info(&format!("Applying YAML artifact from: {}", file_path));
 
     let yaml_content = read_yaml_content(file_path)?;
+    let values = parse_values(set)?;
 
     // Validate that it's a multi-document YAML with required kinds
     validate_yaml_artifact(&yaml_content)?;
 
-    match client.post_yaml("/api/artifact", &yaml_content).await {
+    match client
+        .post_yaml_with_values("/api/artifact", &yaml_content, &values)
+        .await
+    {
         Ok(response) => {
             if let Some(message) = response.get("message") {
                 println!("{}", message.as_str().unwrap_or("Applied successfully"));
@@ -118,6 +125,19 @@ async fn withdraw_yaml(client: &SettingsClient, file_path: &str) -> Result<()> {
     Ok(())
 }
 
+/// Split the `--set NAME=VALUE` arguments of a template
+fn parse_values(set: &[String]) -> Result<Vec<(String, String)>> {
+    set.iter()
+        .map(|arg| match arg.split_once('=') {
+            Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+            _ => Err(crate::CliError::Custom(format!(
+                "Invalid --set '{}', expected NAME=VALUE",
+                arg
+            ))),
+        })
+        .collect()
+}
+
 /// Read YAML content from file or stdin
 fn read_yaml_content(file_path: &str) -> Result<String> {
     if file_path == "-" {
@@ -191,7 +211,7 @@ mod tests {
     use super::*;
     use serde_json::json;
     use std::io::Write;
-    use wiremock::matchers::{method, path};
+    use wiremock::matchers::{method, path, query_param};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
     async fn make_client(base_url: &str) -> SettingsClient {
@@ -221,6 +241,7 @@ mod tests {
         let client = make_client(&server.uri()).await;
         let action = YamlAction::Apply {
             file: tmp.path().to_str().unwrap().to_string(),
+            set: vec![],
         };
         assert!(handle(&client, action).await.is_ok());
     }
@@ -265,6 +286,7 @@ mod tests {
         let client = make_client(&server.uri()).await;
         let action = YamlAction::Apply {
             file: tmp.path().to_str().unwrap().to_string(),
+            set: vec![],
         };
         assert!(handle(&client, action).await.is_ok());
     }
@@ -282,6 +304,7 @@ mod tests {
         let client = make_client(&server.uri()).await;
         let action = YamlAction::Apply {
             file: tmp.path().to_str().unwrap().to_string(),
+            set: vec![],
         };
         assert!(handle(&client, action).await.is_ok());
     }
@@ -299,6 +322,7 @@ mod tests {
         let client = make_client(&server.uri()).await;
         let action = YamlAction::Apply {
             file: tmp.path().to_str().unwrap().to_string(),
+            set: vec![],
         };
         assert!(handle(&client, action).await.is_err());
     }
@@ -309,6 +333,32 @@ mod tests {
         let client = make_client(&server.uri()).await;
         let action = YamlAction::Apply {
             file: "/nonexistent/missing.yaml".to_string(),
+            set: vec![],
+        };
+        assert!(handle(&client, action).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_apply_yaml_sends_template_values() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/artifact"))
+            .and(query_param("image_tag", "1.2"))
+            .and(query_param("node", "HPC"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .mount(&server)
+            .await;
+        let tmp = write_temp_yaml("---\nkind: Scenario\n");
+        let client = make_client(&server.uri()).await;
+        let action = YamlAction::Apply {
+            file: tmp.path().to_str().unwrap().to_string(),
+            set: vec!["image_tag=1.2".to_string(), "node=HPC".to_string()],
+        };
+        assert!(handle(&client, action).await.is_ok());
+
+        let action = YamlAction::Apply {
+            file: tmp.path().to_str().unwrap().to_string(),
+            set: vec!["image_tag".to_string()],
         };
         assert!(handle(&client, action).await.is_err());
     }
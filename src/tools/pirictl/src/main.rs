@@ -9,7 +9,9 @@
 
 use clap::{Parser, Subcommand};
 use colored::Colorize;
-use pirictl::commands::{board, container, metrics, node, soc, top, yaml};
+use pirictl::commands::{
+    artifact, board, container, logs, metrics, node, scenario, soc, top, yaml, OutputFormat,
+};
 use pirictl::{Result, SettingsClient};
 use url::Url;
 
@@ -35,10 +37,18 @@ struct Cli {
     #[arg(short, long, default_value = "30")]
     timeout: u64,
 
+    /// API token sent as bearer token, if the servers require one
+    #[arg(long, env = "PULLPIRI_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+
     /// Enable verbose output
     #[arg(short, long)]
     verbose: bool,
 
+    /// Output format
+    #[arg(short, long, global = true, value_enum, default_value = "table")]
+    output: OutputFormat,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -70,6 +80,9 @@ enum Commands {
         /// Path to YAML file
         #[arg(short = 'f', long = "file")]
         file: String,
+        /// Value of a template parameter as NAME=VALUE, may be repeated
+        #[arg(long = "set", value_name = "NAME=VALUE")]
+        set: Vec<String>,
     },
     /// Delete YAML artifact from the system
     Delete {
@@ -77,6 +90,22 @@ enum Commands {
         #[arg(short = 'f', long = "file")]
         file: String,
     },
+    /// Print the logs of a container
+    Logs {
+        /// Container ID or name
+        container: String,
+        /// Node running the container, looked up from the container if omitted
+        #[arg(long)]
+        node: Option<String>,
+        /// Number of last lines to print, all lines if 0
+        #[arg(long, default_value = "0")]
+        tail: u32,
+    },
+    /// Trigger a resource
+    Trigger {
+        #[command(subcommand)]
+        resource: ResourceTypeTrigger,
+    },
     /// Test connection to SettingsService
     Health,
 }
@@ -91,6 +120,12 @@ enum ResourceType {
     Socs,
     /// Get all containers
     Containers,
+    /// Get all scenarios
+    Scenarios,
+    /// Get all packages
+    Packages,
+    /// Get all models
+    Models,
 }
 
 #[derive(Subcommand)]
@@ -115,6 +150,23 @@ enum ResourceTypeWithId {
         /// Container ID
         id: String,
     },
+    /// Describe specific model by name
+    Model {
+        /// Model name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ResourceTypeTrigger {
+    /// Run the action of a scenario without waiting for its condition
+    Scenario {
+        /// Scenario name
+        name: String,
+        /// Only show the execution plan, nothing is run
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -164,7 +216,8 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     }
 
     // Create two clients: one for SettingsService, one for API Server
-    let settings_client = match SettingsClient::new(&settings_url, cli.timeout) {
+    let token = cli.token.as_deref();
+    let settings_client = match SettingsClient::with_token(&settings_url, cli.timeout, token) {
         Ok(client) => client,
         Err(e) => {
             eprintln!(
@@ -176,7 +229,7 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
         }
     };
 
-    let api_client = match SettingsClient::new(&api_url, cli.timeout) {
+    let api_client = match SettingsClient::with_token(&api_url, cli.timeout, token) {
         Ok(client) => client,
         Err(e) => {
             eprintln!("{} Failed to create API client: {}", "✗".red().bold(), e);
@@ -185,14 +238,31 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     };
 
     // Execute command - YAML commands go directly to API Server; others go to SettingsService
+    let json = cli.output == OutputFormat::Json;
     let result = match cli.command {
+        // JSON output of SettingsService resources is their raw data
         Commands::Get { resource } => match resource {
+            ResourceType::Boards if json => {
+                board::handle(&settings_client, board::BoardAction::Raw { id: None }).await
+            }
+            ResourceType::Nodes if json => {
+                node::handle(&settings_client, node::NodeAction::Raw { id: None }).await
+            }
+            ResourceType::Socs if json => {
+                soc::handle(&settings_client, soc::SocAction::Raw { id: None }).await
+            }
+            ResourceType::Containers if json => {
+                container::handle(&settings_client, container::ContainerAction::Raw).await
+            }
             ResourceType::Boards => board::handle(&settings_client, board::BoardAction::Get).await,
             ResourceType::Nodes => node::handle(&settings_client, node::NodeAction::Get).await,
             ResourceType::Socs => soc::handle(&settings_client, soc::SocAction::Get).await,
             ResourceType::Containers => {
                 container::handle(&settings_client, container::ContainerAction::Get).await
             }
+            ResourceType::Scenarios => get_artifacts(&api_client, "Scenario", cli.output).await,
+            ResourceType::Packages => get_artifacts(&api_client, "Package", cli.output).await,
+            ResourceType::Models => get_artifacts(&api_client, "Model", cli.output).await,
         },
         Commands::Describe { resource } => match resource {
             ResourceTypeWithId::Board { id } => {
@@ -211,6 +281,10 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
                 )
                 .await
             }
+            ResourceTypeWithId::Model { name } => {
+                let action = artifact::ArtifactAction::DescribeModel { name };
+                artifact::handle(&api_client, action, cli.output).await
+            }
         },
         Commands::Raw { resource } => match resource {
             ResourceTypeRaw::Board { id } => {
@@ -230,12 +304,33 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
             }
         },
         Commands::Top { resource } => top::handle(&settings_client, resource).await,
-        Commands::Apply { file } => {
-            yaml::handle(&api_client, yaml::YamlAction::Apply { file }).await
+        Commands::Apply { file, set } => {
+            yaml::handle(&api_client, yaml::YamlAction::Apply { file, set }).await
         }
         Commands::Delete { file } => {
             yaml::handle(&api_client, yaml::YamlAction::Withdraw { file }).await
         }
+        Commands::Logs {
+            container,
+            node,
+            tail,
+        } => {
+            logs::handle(
+                &settings_client,
+                &api_client,
+                &container,
+                node,
+                tail,
+                cli.output,
+            )
+            .await
+        }
+        Commands::Trigger { resource } => match resource {
+            ResourceTypeTrigger::Scenario { name, dry_run } => {
+                let action = scenario::ScenarioAction::Trigger { name, dry_run };
+                scenario::handle(&api_client, action, cli.output).await
+            }
+        },
         Commands::Health => health_check(&settings_client).await,
     };
 
@@ -266,6 +361,15 @@ fn build_url_with_port(base_url: &str, port: u16) -> std::result::Result<String,
     Ok(parsed.as_str().trim_end_matches('/').to_string())
 }
 
+/// List the artifacts of a kind stored in the API Server
+async fn get_artifacts(
+    client: &SettingsClient,
+    kind: &'static str,
+    output: OutputFormat,
+) -> Result<()> {
+    artifact::handle(client, artifact::ArtifactAction::Get { kind }, output).await
+}
+
 /// Perform a health check on the SettingsService
 async fn health_check(client: &SettingsClient) -> Result<()> {
     println!("{} Checking SettingsService health...", "ℹ".blue().bold());